@@ -17,7 +17,9 @@ class MidenClientService {
       baseURL: this.rustServiceUrl,
       timeout: this.timeout,
       headers: {
-        'Content-Type': 'application/json'
+        'Content-Type': 'application/json',
+        // Escrow actions on the Rust service require an API key bound to the parties
+        ...(process.env.MIDEN_RUST_API_KEY ? { 'X-API-Key': process.env.MIDEN_RUST_API_KEY } : {})
       }
     });
 
@@ -86,16 +88,16 @@ class MidenClientService {
       
       if (response.data.success) {
         console.log('✅ Property token created!');
-        console.log(`   TX: ${response.data.transaction_id}`);
-        console.log(`   Note: ${response.data.note_id}`);
+        console.log(`   TX: ${response.data.data.transaction_id}`);
+        console.log(`   Note: ${response.data.data.note_id}`);
         
         return {
           success: true,
-          transactionId: response.data.transaction_id,
-          noteId: response.data.note_id,
+          transactionId: response.data.data.transaction_id,
+          noteId: response.data.data.note_id,
           propertyId: propertyData.id,
           ownerAccountId: ownerAccountId,
-          explorerUrl: `${this.explorerUrl}/tx/${response.data.transaction_id}`
+          explorerUrl: `${this.explorerUrl}/tx/${response.data.data.transaction_id}`
         };
       }
       
@@ -118,10 +120,10 @@ class MidenClientService {
       });
       
       if (response.data.success) {
-        console.log(`✅ Found ${response.data.notes.length} consumable notes`);
+        console.log(`✅ Found ${response.data.data.length} consumable notes`);
         return {
           success: true,
-          notes: response.data.notes
+          notes: response.data.data
         };
       }
       
@@ -153,12 +155,12 @@ class MidenClientService {
       
       if (response.data.success) {
         console.log('✅ Note consumed!');
-        console.log(`   TX: ${response.data.transaction_id}`);
+        console.log(`   TX: ${response.data.data.transaction_id}`);
         
         return {
           success: true,
-          transactionId: response.data.transaction_id,
-          explorerUrl: `${this.explorerUrl}/tx/${response.data.transaction_id}`
+          transactionId: response.data.data.transaction_id,
+          explorerUrl: `${this.explorerUrl}/tx/${response.data.data.transaction_id}`
         };
       }
       
@@ -181,12 +183,12 @@ class MidenClientService {
       
       if (response.data.success) {
         console.log('✅ Property transferred!');
-        console.log(`   TX: ${response.data.transaction_id}`);
+        console.log(`   TX: ${response.data.data.transaction_id}`);
         
         return {
           success: true,
-          transactionId: response.data.transaction_id,
-          explorerUrl: `${this.explorerUrl}/tx/${response.data.transaction_id}`
+          transactionId: response.data.data.transaction_id,
+          explorerUrl: `${this.explorerUrl}/tx/${response.data.data.transaction_id}`
         };
       }
       
@@ -208,12 +210,12 @@ class MidenClientService {
       
       if (response.data.success) {
         console.log('✅ Tokens sent!');
-        console.log(`   TX: ${response.data.transaction_id}`);
+        console.log(`   TX: ${response.data.data.transaction_id}`);
         
         return {
           success: true,
-          transactionId: response.data.transaction_id,
-          explorerUrl: `${this.explorerUrl}/tx/${response.data.transaction_id}`
+          transactionId: response.data.data.transaction_id,
+          explorerUrl: `${this.explorerUrl}/tx/${response.data.data.transaction_id}`
         };
       }
       
@@ -234,7 +236,7 @@ class MidenClientService {
         console.log('✅ Balance retrieved');
         return {
           success: true,
-          balance: response.data.balance
+          balance: response.data.data
         };
       }
       
@@ -263,18 +265,20 @@ class MidenClientService {
         throw new Error(response.data.error || 'Failed to create escrow');
       }
 
-      console.log(`✅ Escrow created: ${response.data.escrow.escrow_account_id}`);
+      const escrow = response.data.data;
+      console.log(`✅ Escrow created: ${escrow.escrow_account_id}`);
 
       return {
-        escrowAccountId: response.data.escrow.escrow_account_id,
-        buyerAccountId: response.data.escrow.buyer_account_id,
-        sellerAccountId: response.data.escrow.seller_account_id,
-        amount: response.data.escrow.amount,
-        status: response.data.escrow.status
+        escrowAccountId: escrow.escrow_account_id,
+        buyerAccountId: escrow.buyer_account_id,
+        sellerAccountId: escrow.seller_account_id,
+        amount: escrow.amount,
+        status: escrow.status
       };
     } catch (error) {
-      console.error('Create escrow failed:', error.message);
-      throw new Error(`Failed to create escrow: ${error.message}`);
+      const message = error.response?.data?.error || error.message;
+      console.error('Create escrow failed:', message);
+      throw new Error(`Failed to create escrow: ${message}`);
     }
   }
 
@@ -293,15 +297,17 @@ class MidenClientService {
         throw new Error(response.data.error || 'Failed to fund escrow');
       }
 
-      console.log(`✅ Escrow funded: TX ${response.data.transaction_id}`);
+      const { transaction_id: transactionId } = response.data.data;
+      console.log(`✅ Escrow funded: TX ${transactionId}`);
 
       return {
-        transactionId: response.data.transaction_id,
-        explorerUrl: `${this.explorerUrl}/tx/${response.data.transaction_id}`
+        transactionId,
+        explorerUrl: `${this.explorerUrl}/tx/${transactionId}`
       };
     } catch (error) {
-      console.error('Fund escrow failed:', error.message);
-      throw new Error(`Failed to fund escrow: ${error.message}`);
+      const message = error.response?.data?.error || error.message;
+      console.error('Fund escrow failed:', message);
+      throw new Error(`Failed to fund escrow: ${message}`);
     }
   }
 
@@ -320,15 +326,17 @@ class MidenClientService {
         throw new Error(response.data.error || 'Failed to release escrow');
       }
 
-      console.log(`✅ Escrow released: TX ${response.data.transaction_id}`);
+      const { transaction_id: transactionId } = response.data.data;
+      console.log(`✅ Escrow released: TX ${transactionId}`);
 
       return {
-        transactionId: response.data.transaction_id,
-        explorerUrl: `${this.explorerUrl}/tx/${response.data.transaction_id}`
+        transactionId,
+        explorerUrl: `${this.explorerUrl}/tx/${transactionId}`
       };
     } catch (error) {
-      console.error('Release escrow failed:', error.message);
-      throw new Error(`Failed to release escrow: ${error.message}`);
+      const message = error.response?.data?.error || error.message;
+      console.error('Release escrow failed:', message);
+      throw new Error(`Failed to release escrow: ${message}`);
     }
   }
 
@@ -347,15 +355,17 @@ class MidenClientService {
         throw new Error(response.data.error || 'Failed to refund escrow');
       }
 
-      console.log(`✅ Escrow refunded: TX ${response.data.transaction_id}`);
+      const { transaction_id: transactionId } = response.data.data;
+      console.log(`✅ Escrow refunded: TX ${transactionId}`);
 
       return {
-        transactionId: response.data.transaction_id,
-        explorerUrl: `${this.explorerUrl}/tx/${response.data.transaction_id}`
+        transactionId,
+        explorerUrl: `${this.explorerUrl}/tx/${transactionId}`
       };
     } catch (error) {
-      console.error('Refund escrow failed:', error.message);
-      throw new Error(`Failed to refund escrow: ${error.message}`);
+      const message = error.response?.data?.error || error.message;
+      console.error('Refund escrow failed:', message);
+      throw new Error(`Failed to refund escrow: ${message}`);
     }
   }
 
@@ -383,11 +393,11 @@ class MidenClientService {
 
       return {
         success: true,
-        proof: response.data.proof.proof,
-        programHash: response.data.proof.program_hash,
-        publicInputs: response.data.proof.public_inputs,
-        proofType: response.data.proof.proof_type,
-        timestamp: response.data.proof.timestamp
+        proof: response.data.data.proof.proof,
+        programHash: response.data.data.proof.program_hash,
+        publicInputs: response.data.data.proof.public_inputs,
+        proofType: response.data.data.proof.proof_type,
+        timestamp: response.data.data.proof.timestamp
       };
     } catch (error) {
       console.error('Generate proof failed:', error.message);
@@ -414,21 +424,21 @@ class MidenClientService {
         throw new Error(response.data.error || 'Proof verification failed');
       }
 
-      const isValid = response.data.valid;
+      const isValid = response.data.data.valid;
       
       if (isValid) {
         console.log('✅ Proof VERIFIED! User is accredited.');
-        console.log(`   Threshold met: $${response.data.threshold.toLocaleString()}`);
+        console.log(`   Threshold met: $${response.data.data.threshold.toLocaleString()}`);
       } else {
         console.log('❌ Proof verification FAILED');
       }
 
       return {
         success: true,
-        valid: response.data.valid,
-        threshold: response.data.threshold,
-        verifiedAt: response.data.verified_at,
-        proofType: response.data.proof_type
+        valid: response.data.data.valid,
+        threshold: response.data.data.threshold,
+        verifiedAt: response.data.data.verified_at,
+        proofType: response.data.data.proof_type
       };
     } catch (error) {
       console.error('Verify proof failed:', error.message);
@@ -460,12 +470,12 @@ class MidenClientService {
 
       return {
         success: true,
-        proof: response.data.proof.proof,
-        programHash: response.data.proof.program_hash,
-        publicInputs: response.data.proof.public_inputs,
-        proofType: response.data.proof.proof_type,
-        timestamp: response.data.proof.timestamp,
-        restrictedCount: response.data.proof.restricted_count
+        proof: response.data.data.proof.proof,
+        programHash: response.data.data.proof.program_hash,
+        publicInputs: response.data.data.proof.public_inputs,
+        proofType: response.data.data.proof.proof_type,
+        timestamp: response.data.data.proof.timestamp,
+        restrictedCount: response.data.data.proof.restricted_count
       };
     } catch (error) {
       console.error('Generate jurisdiction proof failed:', error.message);
@@ -492,7 +502,7 @@ class MidenClientService {
         throw new Error(response.data.error || 'Jurisdiction proof verification failed');
       }
 
-      const isValid = response.data.valid;
+      const isValid = response.data.data.valid;
       
       if (isValid) {
         console.log('✅ Jurisdiction proof VERIFIED! User is compliant.');
@@ -503,10 +513,10 @@ class MidenClientService {
 
       return {
         success: true,
-        valid: response.data.valid,
-        verifiedAt: response.data.verified_at,
-        proofType: response.data.proof_type,
-        message: response.data.message
+        valid: response.data.data.valid,
+        verifiedAt: response.data.data.verified_at,
+        proofType: response.data.data.proof_type,
+        message: response.data.data.message
       };
     } catch (error) {
       console.error('Verify jurisdiction proof failed:', error.message);
@@ -529,7 +539,7 @@ class MidenClientService {
         document_hash: documentHash
       });
 
-      if (!response.data.success) {
+      if (!response.data.success || !response.data.data.verified) {
         throw new Error(response.data.error || 'Ownership proof generation failed');
       }
 
@@ -538,11 +548,11 @@ class MidenClientService {
 
       return {
         success: true,
-        proof: response.data.proof,
-        programHash: response.data.program_hash,
-        publicInputs: response.data.public_inputs,
-        proofType: response.data.proof_type,
-        timestamp: response.data.timestamp
+        proof: response.data.data.proof,
+        programHash: response.data.data.program_hash,
+        publicInputs: response.data.data.public_inputs,
+        proofType: response.data.data.proof_type,
+        timestamp: response.data.data.timestamp
       };
     } catch (error) {
       console.error('Generate ownership proof failed:', error.message);
@@ -569,7 +579,7 @@ class MidenClientService {
         throw new Error(response.data.error || 'Ownership proof verification failed');
       }
 
-      const isValid = response.data.valid;
+      const isValid = response.data.data.valid;
       
       if (isValid) {
         console.log('✅ Ownership proof VERIFIED! User owns the property.');
@@ -579,10 +589,10 @@ class MidenClientService {
 
       return {
         success: true,
-        valid: response.data.valid,
-        verifiedAt: response.data.verified_at,
-        proofType: response.data.proof_type,
-        message: response.data.message
+        valid: response.data.data.valid,
+        verifiedAt: response.data.data.verified_at,
+        proofType: response.data.data.proof_type,
+        message: response.data.data.message
       };
     } catch (error) {
       console.error('Verify ownership proof failed:', error.message);
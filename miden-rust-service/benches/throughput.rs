@@ -0,0 +1,134 @@
+// benches/throughput.rs
+//
+// Transaction throughput scenarios against a running localnet service
+//
+//   MIDEN_PROFILE=localnet cargo run --release   # in another terminal
+//   cargo bench --bench throughput [-- <scenario>...]
+//
+// Each scenario is one POST /api/v1/admin/bench run (see src/bench.rs); the
+// per-phase throughput and latency percentiles are printed as a table.
+// BENCH_URL overrides the service address (default http://127.0.0.1:3000).
+
+use std::time::Duration;
+
+struct Scenario {
+    name: &'static str,
+    mints: u32,
+    consumes: u32,
+    transfers: u32,
+    concurrency: usize,
+}
+
+const SCENARIOS: &[Scenario] = &[
+    // One command at a time: execution cost without queueing
+    Scenario {
+        name: "serial",
+        mints: 5,
+        consumes: 1,
+        transfers: 5,
+        concurrency: 1,
+    },
+    // Mint burst: queue latency under load
+    Scenario {
+        name: "mint-burst",
+        mints: 50,
+        consumes: 0,
+        transfers: 0,
+        concurrency: 16,
+    },
+    Scenario {
+        name: "mixed",
+        mints: 20,
+        consumes: 5,
+        transfers: 20,
+        concurrency: 8,
+    },
+];
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let base_url =
+        std::env::var("BENCH_URL").unwrap_or_else(|_| "http://127.0.0.1:3000".to_string());
+    // `cargo bench` passes --bench; anything else selects scenarios by name
+    let selected: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+
+    let http = reqwest::Client::builder()
+        .timeout(Duration::from_secs(3_600))
+        .build()?;
+
+    for scenario in SCENARIOS {
+        if !selected.is_empty() && !selected.iter().any(|s| s == scenario.name) {
+            continue;
+        }
+        println!(
+            "== {} ({} mints, {} consumes, {} transfers, concurrency {})",
+            scenario.name,
+            scenario.mints,
+            scenario.consumes,
+            scenario.transfers,
+            scenario.concurrency
+        );
+
+        let body: serde_json::Value = http
+            .post(format!("{}/api/v1/admin/bench", base_url))
+            .json(&serde_json::json!({
+                "mints": scenario.mints,
+                "consumes": scenario.consumes,
+                "transfers": scenario.transfers,
+                "concurrency": scenario.concurrency,
+            }))
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if body["success"] != true {
+            anyhow::bail!("Scenario {} failed: {}", scenario.name, body["error"]);
+        }
+        print_report(&body["report"]);
+    }
+
+    Ok(())
+}
+
+fn print_report(report: &serde_json::Value) {
+    println!(
+        "{:<10} {:>5} {:>6} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "phase",
+        "ops",
+        "failed",
+        "ops/s",
+        "queue p50",
+        "queue p99",
+        "exec p50",
+        "exec p90",
+        "exec p99"
+    );
+    for phase in report["phases"].as_array().into_iter().flatten() {
+        println!(
+            "{:<10} {:>5} {:>6} {:>8.3} {:>10.1} {:>10.1} {:>10.1} {:>10.1} {:>10.1}",
+            phase["phase"].as_str().unwrap_or("?"),
+            phase["operations"].as_u64().unwrap_or_default(),
+            phase["failed"].as_u64().unwrap_or_default(),
+            phase["throughput_per_sec"].as_f64().unwrap_or_default(),
+            phase["queue_ms"]["p50"].as_f64().unwrap_or_default(),
+            phase["queue_ms"]["p99"].as_f64().unwrap_or_default(),
+            phase["execution_ms"]["p50"].as_f64().unwrap_or_default(),
+            phase["execution_ms"]["p90"].as_f64().unwrap_or_default(),
+            phase["execution_ms"]["p99"].as_f64().unwrap_or_default(),
+        );
+        for error in phase["errors"].as_array().into_iter().flatten() {
+            println!("  error: {}", error.as_str().unwrap_or_default());
+        }
+    }
+    println!(
+        "total: {} ops, {} failed, {:.3} ops/s over {:.1} s\n",
+        report["operations"],
+        report["failed"],
+        report["throughput_per_sec"].as_f64().unwrap_or_default(),
+        report["elapsed_ms"].as_f64().unwrap_or_default() / 1_000.0
+    );
+}
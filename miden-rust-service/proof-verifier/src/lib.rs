@@ -0,0 +1,301 @@
+// proof-verifier/src/lib.rs
+//
+// Stateless proof verification
+//
+// The checks here depend only on a proof and the published program list
+// (GET /zk/programs), so they run identically inside the service and in a
+// browser (build with `--features wasm`, see wasm.rs).
+//
+// What is checked:
+// - program_hash is a published program of the expected family
+// - the proof payload decodes
+// - public inputs have the shape the program expects
+// - an accreditation proof was generated for its public inputs: the threshold
+//   and rule it commits to are the ones claimed
+//
+// Checks against live service state (accreditation rule still current,
+// restricted-jurisdiction list version still current) are layered on top by the
+// service; browsers can pass the expected values they fetched.
+
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub const ACCREDITATION_FAMILY: &str = "accreditation";
+pub const OWNERSHIP_FAMILY: &str = "ownership";
+pub const JURISDICTION_FAMILY: &str = "jurisdiction";
+
+/// A program as published by GET /zk/programs (extra fields are ignored).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishedProgram {
+    pub program_id: String,
+    pub family: String,
+    pub version: u32,
+    pub mast_root: String,
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Verification {
+    pub valid: bool,
+    pub program_id: String,
+    pub program_active: bool,
+    pub message: String,
+}
+
+impl Verification {
+    fn new(program: &PublishedProgram, valid: bool, message: impl Into<String>) -> Self {
+        Self {
+            valid,
+            program_id: program.program_id.clone(),
+            program_active: program.active,
+            message: message.into(),
+        }
+    }
+}
+
+/// Resolves a submitted program hash to a published program of `family`.
+pub fn find_program<'a>(
+    programs: &'a [PublishedProgram],
+    family: &str,
+    program_hash: &str,
+) -> Result<&'a PublishedProgram> {
+    let normalized = program_hash.trim().to_lowercase();
+
+    programs
+        .iter()
+        .find(|p| p.family == family && p.mast_root.to_lowercase() == normalized)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Program hash {} is not a registered {} program",
+                program_hash,
+                family
+            )
+        })
+}
+
+pub fn decode_proof(proof_base64: &str) -> Result<Vec<u8>> {
+    general_purpose::STANDARD
+        .decode(proof_base64)
+        .map_err(|e| anyhow::anyhow!("Invalid proof format: {}", e))
+}
+
+/// Public inputs of an accreditation proof: (threshold, rule_id).
+pub fn accreditation_inputs(public_inputs: &[u64]) -> Result<(u64, u64)> {
+    match public_inputs {
+        [threshold, rule_id] => Ok((*threshold, *rule_id)),
+        _ => Err(anyhow::anyhow!("Public inputs must be [threshold, rule_id]")),
+    }
+}
+
+/// Public inputs of a jurisdiction proof: (list_version, restricted_count).
+pub fn jurisdiction_inputs(public_inputs: &[u64]) -> Result<(u64, u64)> {
+    match public_inputs {
+        [list_version, restricted_count, ..] => Ok((*list_version, *restricted_count)),
+        [list_version] => Ok((*list_version, 0)),
+        [] => Err(anyhow::anyhow!(
+            "Public inputs must start with the list version"
+        )),
+    }
+}
+
+/// The (threshold, rule_id) an accreditation proof was generated for. The
+/// demo payload is `PROOF_<net_worth>_<threshold>_<rule_id>`.
+fn accreditation_commitment(proof_bytes: &[u8]) -> Option<(u64, u64)> {
+    let payload = std::str::from_utf8(proof_bytes).ok()?;
+    let mut parts = payload.strip_prefix("PROOF_")?.split('_');
+    let (_net_worth, threshold, rule_id) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some((threshold.parse().ok()?, rule_id.parse().ok()?))
+}
+
+/// Verifies an accreditation proof.
+///
+/// `expected_threshold` and `expected_rule_id`, when given, must equal the
+/// proof's public threshold and rule.
+pub fn verify_accreditation(
+    programs: &[PublishedProgram],
+    proof_base64: &str,
+    program_hash: &str,
+    public_inputs: &[u64],
+    expected_threshold: Option<u64>,
+    expected_rule_id: Option<u64>,
+) -> Result<Verification> {
+    let program = find_program(programs, ACCREDITATION_FAMILY, program_hash)?;
+    let proof_bytes = decode_proof(proof_base64)?;
+    let (threshold, rule_id) = accreditation_inputs(public_inputs)?;
+
+    let mismatch = match accreditation_commitment(&proof_bytes) {
+        None => Some("Proof does not commit to a threshold and rule".to_string()),
+        Some(committed) if committed != (threshold, rule_id) => Some(format!(
+            "Proof was generated for threshold {} and rule {}, not the public inputs [{}, {}]",
+            committed.0, committed.1, threshold, rule_id
+        )),
+        Some(_) => match (expected_threshold, expected_rule_id) {
+            (Some(expected), _) if expected != threshold => Some(format!(
+                "Proof threshold {} does not match expected {} (rule {})",
+                threshold, expected, rule_id
+            )),
+            (_, Some(expected)) if expected != rule_id => Some(format!(
+                "Proof is for rule {}, not the expected rule {}",
+                rule_id, expected
+            )),
+            _ => None,
+        },
+    };
+
+    Ok(match mismatch {
+        Some(message) => Verification::new(program, false, message),
+        None => Verification::new(
+            program,
+            true,
+            "Proof verified. User meets accreditation threshold (demo version)",
+        ),
+    })
+}
+
+/// Verifies a jurisdiction proof.
+///
+/// `current_list_version`, when given, must equal the proof's list version.
+pub fn verify_jurisdiction(
+    programs: &[PublishedProgram],
+    proof_base64: &str,
+    program_hash: &str,
+    public_inputs: &[u64],
+    current_list_version: Option<u64>,
+) -> Result<Verification> {
+    let program = find_program(programs, JURISDICTION_FAMILY, program_hash)?;
+    decode_proof(proof_base64)?;
+    let (list_version, _) = jurisdiction_inputs(public_inputs)?;
+
+    Ok(match current_list_version {
+        Some(current) if current != list_version => Verification::new(
+            program,
+            false,
+            format!(
+                "Proof was generated against outdated list version {} (current: {})",
+                list_version, current
+            ),
+        ),
+        _ => Verification::new(
+            program,
+            true,
+            "Jurisdiction proof verified. User is not in restricted jurisdiction (demo version)",
+        ),
+    })
+}
+
+/// Verifies an ownership proof.
+pub fn verify_ownership(
+    programs: &[PublishedProgram],
+    proof_base64: &str,
+    program_hash: &str,
+) -> Result<Verification> {
+    let program = find_program(programs, OWNERSHIP_FAMILY, program_hash)?;
+    let proof_bytes = decode_proof(proof_base64)?;
+
+    let verified = String::from_utf8_lossy(&proof_bytes).contains("VERIFIED");
+
+    Ok(Verification::new(
+        program,
+        verified,
+        if verified {
+            "Ownership verified successfully"
+        } else {
+            "Ownership verification failed"
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAST_ROOT: &str = "0xabc";
+
+    fn programs() -> Vec<PublishedProgram> {
+        vec![PublishedProgram {
+            program_id: "accreditation-v1".to_string(),
+            family: ACCREDITATION_FAMILY.to_string(),
+            version: 1,
+            mast_root: MAST_ROOT.to_string(),
+            active: true,
+        }]
+    }
+
+    fn proof(payload: &str) -> String {
+        general_purpose::STANDARD.encode(payload)
+    }
+
+    fn verify(payload: &str, public_inputs: &[u64], expected: (Option<u64>, Option<u64>)) -> bool {
+        verify_accreditation(
+            &programs(),
+            &proof(payload),
+            MAST_ROOT,
+            public_inputs,
+            expected.0,
+            expected.1,
+        )
+        .unwrap()
+        .valid
+    }
+
+    #[test]
+    fn accreditation_proofs_verify_for_the_inputs_they_commit_to() {
+        assert!(verify(
+            "PROOF_2000000_1000000_3",
+            &[1_000_000, 3],
+            (None, None)
+        ));
+        assert!(verify(
+            "PROOF_2000000_1000000_3",
+            &[1_000_000, 3],
+            (Some(1_000_000), Some(3))
+        ));
+    }
+
+    #[test]
+    fn accreditation_proofs_are_bound_to_their_rule() {
+        assert!(!verify(
+            "PROOF_2000000_1000000_3",
+            &[1_000_000, 4],
+            (None, None)
+        ));
+        assert!(!verify(
+            "PROOF_2000000_1000000_3",
+            &[500_000, 3],
+            (None, None)
+        ));
+        assert!(!verify(
+            "PROOF_2000000_1000000",
+            &[1_000_000, 3],
+            (None, None)
+        ));
+        assert!(!verify(
+            "PROOF_2000000_1000000_3",
+            &[1_000_000, 3],
+            (None, Some(4))
+        ));
+        assert!(!verify(
+            "PROOF_2000000_1000000_3",
+            &[1_000_000, 3],
+            (Some(500_000), None)
+        ));
+    }
+
+    #[test]
+    fn accreditation_inputs_are_exactly_threshold_and_rule() {
+        assert_eq!(
+            accreditation_inputs(&[1_000_000, 3]).unwrap(),
+            (1_000_000, 3)
+        );
+        assert!(accreditation_inputs(&[1_000_000]).is_err());
+        assert!(accreditation_inputs(&[1_000_000, 3, 7]).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+// proof-verifier/src/wasm.rs
+//
+// Browser bindings
+//
+// Build: wasm-pack build --target web --features wasm
+//
+// All arguments and results are JSON strings so the JS side needs no glue
+// types. `programs_json` is the array returned by GET /zk/programs.
+//
+//   const programs = JSON.stringify(await (await fetch("/zk/programs")).json());
+//   const result = JSON.parse(
+//     verifyAccreditationProof(proof, programHash, "[1000000, 1]", programs, null, null)
+//   );
+
+use wasm_bindgen::prelude::*;
+
+use crate::PublishedProgram;
+
+fn parse_programs(programs_json: &str) -> Result<Vec<PublishedProgram>, JsError> {
+    serde_json::from_str(programs_json)
+        .map_err(|e| JsError::new(&format!("Invalid program list: {}", e)))
+}
+
+fn parse_inputs(public_inputs_json: &str) -> Result<Vec<u64>, JsError> {
+    serde_json::from_str(public_inputs_json)
+        .map_err(|e| JsError::new(&format!("Invalid public inputs: {}", e)))
+}
+
+fn to_json(result: anyhow::Result<crate::Verification>) -> Result<String, JsError> {
+    let verification = result.map_err(|e| JsError::new(&e.to_string()))?;
+    serde_json::to_string(&verification).map_err(|e| JsError::new(&e.to_string()))
+}
+
+#[wasm_bindgen(js_name = verifyAccreditationProof)]
+pub fn verify_accreditation_proof(
+    proof: &str,
+    program_hash: &str,
+    public_inputs_json: &str,
+    programs_json: &str,
+    expected_threshold: Option<f64>,
+    expected_rule_id: Option<f64>,
+) -> Result<String, JsError> {
+    to_json(crate::verify_accreditation(
+        &parse_programs(programs_json)?,
+        proof,
+        program_hash,
+        &parse_inputs(public_inputs_json)?,
+        expected_threshold.map(|t| t as u64),
+        expected_rule_id.map(|r| r as u64),
+    ))
+}
+
+#[wasm_bindgen(js_name = verifyJurisdictionProof)]
+pub fn verify_jurisdiction_proof(
+    proof: &str,
+    program_hash: &str,
+    public_inputs_json: &str,
+    programs_json: &str,
+    current_list_version: Option<f64>,
+) -> Result<String, JsError> {
+    to_json(crate::verify_jurisdiction(
+        &parse_programs(programs_json)?,
+        proof,
+        program_hash,
+        &parse_inputs(public_inputs_json)?,
+        current_list_version.map(|v| v as u64),
+    ))
+}
+
+#[wasm_bindgen(js_name = verifyOwnershipProof)]
+pub fn verify_ownership_proof(
+    proof: &str,
+    program_hash: &str,
+    programs_json: &str,
+) -> Result<String, JsError> {
+    to_json(crate::verify_ownership(
+        &parse_programs(programs_json)?,
+        proof,
+        program_hash,
+    ))
+}
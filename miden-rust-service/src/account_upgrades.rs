@@ -0,0 +1,201 @@
+// src/account_upgrades.rs
+//
+// Account component upgrades
+//
+// POST /accounts/:account_id/upgrade {"components": [...], "dry_run": true}
+// asks for the code of an account to be replaced by a new component set.
+// Components are named from what this build can assemble: the basic wallet
+// and Falcon-512 signature auth. It ships no escrow or multisig account
+// component; escrow accounts are basic wallets driven by the service
+// (escrow.rs), so neither can be requested.
+//
+// Every request runs the pre-flight checks and reports them:
+// - the caller holds the admin role
+// - the account is in the client store, so the service holds its keys
+// - its type, RegularAccountUpdatableCode being the one meant for upgrades
+// - it is not one of the service's own accounts (alice, bob, the faucet, the
+//   treasury), which the configuration refers to by ID
+// - the set includes the basic wallet, without which an account with the new
+//   code could not hold the assets
+// - whether the upgrade can be applied in place, which it never can: the Miden
+//   protocol this service runs on (0.12) has no transaction that replaces an
+//   existing account's code. An account delta carries storage, vault and nonce
+//   changes only, so the updatable-code account type records intent but cannot
+//   be exercised yet.
+//
+// Applying an upgrade (`dry_run: false`) is therefore refused. Moving to a new
+// account instead changes the account ID that escrows, listings, property
+// records and organizations refer to, which the service does not rewrite; the
+// report's guidance lists the steps of such a migration for an operator to
+// carry out.
+
+use anyhow::Result;
+use miden_client::account::AccountType;
+use serde::{Deserialize, Serialize};
+
+use crate::{reconcile::parse_hex_account_id, MidenClientWrapper};
+
+/// Components an upgrade may name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentKind {
+    BasicWallet,
+    AuthRpoFalcon512,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpgradeInput {
+    pub components: Vec<ComponentKind>,
+    /// Only run the pre-flight checks (the default); anything else is refused
+    #[serde(default = "default_dry_run")]
+    pub dry_run: bool,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpgradeCheck {
+    pub check: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpgradeReport {
+    pub account_id: String,
+    pub account_type: String,
+    pub code_commitment: String,
+    pub procedures: usize,
+    pub requested: Vec<ComponentKind>,
+    pub checks: Vec<UpgradeCheck>,
+    /// Whether the upgrade could be applied in place
+    pub applicable: bool,
+    pub reason: String,
+    pub guidance: Vec<String>,
+}
+
+/// Why no upgrade is applied in place (see module docs).
+const IN_PLACE_UNSUPPORTED: &str = "Miden 0.12 transactions cannot replace the code of an \
+                                    existing account; account deltas carry storage, vault \
+                                    and nonce changes only";
+
+fn migration_guidance(account_hex: &str) -> Vec<String> {
+    vec![
+        "Create an account with the new components; it gets a new account ID".to_string(),
+        format!(
+            "Move the assets of {} to it with a P2ID note and consume the note \
+             (POST /consume-note)",
+            account_hex
+        ),
+        format!(
+            "Settle or refund the open escrows of {}, and repoint its listings, \
+             properties and organizations at the new account ID",
+            account_hex
+        ),
+        format!(
+            "Keep {}: its keys stay in the keystore. To roll back, send the assets \
+             back from the new account with another P2ID note",
+            account_hex
+        ),
+    ]
+}
+
+// ============================================================================
+// CLIENT INTEGRATION
+// ============================================================================
+
+crate::operations! {
+    /// Pre-flight checks of an account component upgrade.
+    UpgradeAccount {
+        account_id: String,
+        input: UpgradeInput,
+        api_key: Option<String>,
+    } -> UpgradeReport;
+    |client, op| client.upgrade_account(&op.account_id, op.input, op.api_key.as_deref()).await
+}
+
+impl MidenClientWrapper {
+    /// Pre-flight checks of an account component upgrade; refuses to apply
+    /// one (see module docs).
+    pub async fn upgrade_account(
+        &mut self,
+        account_id: &str,
+        input: UpgradeInput,
+        api_key: Option<&str>,
+    ) -> Result<UpgradeReport> {
+        self.admin_principal(api_key, "account upgrades")?;
+
+        let account_hex = self.account_hex(account_id)?;
+        let id = parse_hex_account_id(&account_hex)?;
+        let record =
+            self.client.get_account(id).await?.ok_or_else(|| {
+                anyhow::anyhow!("Account {} is not in the client store", account_hex)
+            })?;
+        let account = record.account();
+
+        let mut checks = vec![UpgradeCheck {
+            check: "keys",
+            passed: true,
+            detail: "The account is in the client store".to_string(),
+        }];
+        let updatable = account.account_type() == AccountType::RegularAccountUpdatableCode;
+        checks.push(UpgradeCheck {
+            check: "account_type",
+            passed: updatable,
+            detail: format!("{:?}", account.account_type()),
+        });
+        let service_account = ["alice", "bob", "faucet"]
+            .iter()
+            .filter_map(|name| self.named_account(name).ok())
+            .chain(self.treasury_account_id().ok())
+            .any(|service_id| service_id == id);
+        checks.push(UpgradeCheck {
+            check: "service_account",
+            passed: !service_account,
+            detail: if service_account {
+                "The service refers to this account by ID".to_string()
+            } else {
+                "Not one of the service's own accounts".to_string()
+            },
+        });
+        let wallet = input.components.contains(&ComponentKind::BasicWallet);
+        checks.push(UpgradeCheck {
+            check: "wallet",
+            passed: wallet,
+            detail: if wallet {
+                "The new code can hold the assets".to_string()
+            } else {
+                "The set must include basic_wallet to hold the assets".to_string()
+            },
+        });
+        checks.push(UpgradeCheck {
+            check: "in_place_upgrade",
+            passed: false,
+            detail: IN_PLACE_UNSUPPORTED.to_string(),
+        });
+
+        if !input.dry_run {
+            tracing::warn!("Refused component upgrade of {}", account_hex);
+            return Err(anyhow::anyhow!(
+                "Account {} cannot be upgraded in place ({}); run with dry_run for migration \
+                 guidance",
+                account_hex,
+                IN_PLACE_UNSUPPORTED
+            ));
+        }
+
+        Ok(UpgradeReport {
+            account_id: account_hex.clone(),
+            account_type: format!("{:?}", account.account_type()),
+            code_commitment: account.code().commitment().to_hex(),
+            procedures: account.code().num_procedures(),
+            requested: input.components,
+            checks,
+            applicable: false,
+            reason: IN_PLACE_UNSUPPORTED.to_string(),
+            guidance: migration_guidance(&account_hex),
+        })
+    }
+}
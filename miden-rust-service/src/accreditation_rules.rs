@@ -0,0 +1,300 @@
+// src/accreditation_rules.rs
+//
+// Server-side accreditation thresholds
+//
+// Clients no longer choose the threshold their net worth is proven against.
+// Rules are keyed by jurisdiction and listing class ("*" matches anything) and
+// managed through the admin endpoints, with an admin key. The accreditation
+// proof flow resolves the most specific matching rule and embeds its ID in the
+// proof's public inputs.
+//
+// Resolution order (first match wins):
+// 1. jurisdiction + listing class
+// 2. jurisdiction + "*"
+// 3. "*" + listing class
+// 4. "*" + "*"
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::MidenClientWrapper;
+
+pub const WILDCARD: &str = "*";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccreditationRule {
+    pub rule_id: u64,
+    /// ISO country code (upper case) or "*"
+    pub jurisdiction: String,
+    /// Listing class such as "residential", "commercial", or "*"
+    pub listing_class: String,
+    pub threshold: u64,
+    pub description: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Fields accepted when creating or updating a rule.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuleInput {
+    pub jurisdiction: Option<String>,
+    pub listing_class: Option<String>,
+    pub threshold: u64,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RuleStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    rules: BTreeMap<u64, AccreditationRule>,
+    #[serde(default)]
+    next_rule_id: u64,
+}
+
+fn normalize_jurisdiction(value: Option<&str>) -> String {
+    match value.map(str::trim) {
+        None | Some("") => WILDCARD.to_string(),
+        Some(v) => v.to_uppercase(),
+    }
+}
+
+fn normalize_class(value: Option<&str>) -> String {
+    match value.map(str::trim) {
+        None | Some("") => WILDCARD.to_string(),
+        Some(v) => v.to_lowercase(),
+    }
+}
+
+impl RuleStore {
+    /// Loads rules from disk. A missing file starts with a single catch-all
+    /// rule using `default_threshold`.
+    pub fn load(path: impl Into<PathBuf>, default_threshold: u64) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<RuleStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            RuleStore::default()
+        };
+        store.path = path;
+
+        if store.rules.is_empty() {
+            store.create(RuleInput {
+                jurisdiction: None,
+                listing_class: None,
+                threshold: default_threshold,
+                description: Some("Default accreditation threshold".to_string()),
+            })?;
+        }
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<&AccreditationRule> {
+        self.rules.values().collect()
+    }
+
+    pub fn get(&self, rule_id: u64) -> Option<&AccreditationRule> {
+        self.rules.get(&rule_id)
+    }
+
+    pub fn create(&mut self, input: RuleInput) -> Result<AccreditationRule> {
+        if input.threshold == 0 {
+            return Err(anyhow::anyhow!("Threshold must be greater than zero"));
+        }
+
+        let jurisdiction = normalize_jurisdiction(input.jurisdiction.as_deref());
+        let listing_class = normalize_class(input.listing_class.as_deref());
+
+        if self
+            .rules
+            .values()
+            .any(|r| r.jurisdiction == jurisdiction && r.listing_class == listing_class)
+        {
+            return Err(anyhow::anyhow!(
+                "A rule for jurisdiction '{}' and listing class '{}' already exists",
+                jurisdiction,
+                listing_class
+            ));
+        }
+
+        self.next_rule_id += 1;
+        let now = chrono::Utc::now().timestamp();
+        let rule = AccreditationRule {
+            rule_id: self.next_rule_id,
+            jurisdiction,
+            listing_class,
+            threshold: input.threshold,
+            description: input.description,
+            created_at: now,
+            updated_at: now,
+        };
+
+        self.rules.insert(rule.rule_id, rule.clone());
+        self.save()?;
+        Ok(rule)
+    }
+
+    pub fn update(&mut self, rule_id: u64, input: RuleInput) -> Result<AccreditationRule> {
+        if input.threshold == 0 {
+            return Err(anyhow::anyhow!("Threshold must be greater than zero"));
+        }
+
+        let jurisdiction = normalize_jurisdiction(input.jurisdiction.as_deref());
+        let listing_class = normalize_class(input.listing_class.as_deref());
+
+        if self.rules.values().any(|r| {
+            r.rule_id != rule_id
+                && r.jurisdiction == jurisdiction
+                && r.listing_class == listing_class
+        }) {
+            return Err(anyhow::anyhow!(
+                "Another rule already covers jurisdiction '{}' and listing class '{}'",
+                jurisdiction,
+                listing_class
+            ));
+        }
+
+        let rule = self
+            .rules
+            .get_mut(&rule_id)
+            .ok_or_else(|| anyhow::anyhow!("Rule {} not found", rule_id))?;
+
+        rule.jurisdiction = jurisdiction;
+        rule.listing_class = listing_class;
+        rule.threshold = input.threshold;
+        rule.description = input.description;
+        rule.updated_at = chrono::Utc::now().timestamp();
+
+        let rule = rule.clone();
+        self.save()?;
+        Ok(rule)
+    }
+
+    pub fn delete(&mut self, rule_id: u64) -> Result<AccreditationRule> {
+        let rule = self
+            .rules
+            .remove(&rule_id)
+            .ok_or_else(|| anyhow::anyhow!("Rule {} not found", rule_id))?;
+        self.save()?;
+        Ok(rule)
+    }
+
+    /// Finds the most specific rule for a jurisdiction and listing class.
+    pub fn resolve(
+        &self,
+        jurisdiction: Option<&str>,
+        listing_class: Option<&str>,
+    ) -> Result<&AccreditationRule> {
+        let jurisdiction = normalize_jurisdiction(jurisdiction);
+        let listing_class = normalize_class(listing_class);
+
+        let candidates = [
+            (jurisdiction.as_str(), listing_class.as_str()),
+            (jurisdiction.as_str(), WILDCARD),
+            (WILDCARD, listing_class.as_str()),
+            (WILDCARD, WILDCARD),
+        ];
+
+        candidates
+            .iter()
+            .find_map(|(j, c)| {
+                self.rules
+                    .values()
+                    .find(|r| r.jurisdiction == *j && r.listing_class == *c)
+            })
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No accreditation rule applies to jurisdiction '{}' and listing class '{}'",
+                    jurisdiction,
+                    listing_class
+                )
+            })
+    }
+}
+
+// ============================================================================
+// CLIENT INTEGRATION
+// ============================================================================
+
+crate::operations! {
+    /// Rules, by ID.
+    ListAccreditationRules {} -> Vec<AccreditationRule>;
+    |client, _op| Ok(client.accreditation_rules.list().into_iter().cloned().collect());
+
+    CreateAccreditationRule {
+        input: RuleInput,
+        api_key: Option<String>,
+    } -> AccreditationRule;
+    |client, op| client.create_accreditation_rule(op.input, op.api_key.as_deref());
+
+    UpdateAccreditationRule {
+        rule_id: u64,
+        input: RuleInput,
+        api_key: Option<String>,
+    } -> AccreditationRule;
+    |client, op| client.update_accreditation_rule(op.rule_id, op.input, op.api_key.as_deref());
+
+    DeleteAccreditationRule {
+        rule_id: u64,
+        api_key: Option<String>,
+    } -> AccreditationRule;
+    |client, op| client.delete_accreditation_rule(op.rule_id, op.api_key.as_deref())
+}
+
+impl MidenClientWrapper {
+    /// Adds a rule. Needs an admin key.
+    pub fn create_accreditation_rule(
+        &mut self,
+        input: RuleInput,
+        api_key: Option<&str>,
+    ) -> Result<AccreditationRule> {
+        self.admin_principal(api_key, "accreditation rules")?;
+        let rule = self.accreditation_rules.create(input)?;
+        tracing::info!(
+            "Created accreditation rule {} ({}/{}: {})",
+            rule.rule_id,
+            rule.jurisdiction,
+            rule.listing_class,
+            rule.threshold
+        );
+        Ok(rule)
+    }
+
+    /// Replaces a rule's fields. Needs an admin key.
+    pub fn update_accreditation_rule(
+        &mut self,
+        rule_id: u64,
+        input: RuleInput,
+        api_key: Option<&str>,
+    ) -> Result<AccreditationRule> {
+        self.admin_principal(api_key, "accreditation rules")?;
+        let rule = self.accreditation_rules.update(rule_id, input)?;
+        tracing::info!("Updated accreditation rule {}", rule_id);
+        Ok(rule)
+    }
+
+    /// Removes a rule. Needs an admin key.
+    pub fn delete_accreditation_rule(
+        &mut self,
+        rule_id: u64,
+        api_key: Option<&str>,
+    ) -> Result<AccreditationRule> {
+        self.admin_principal(api_key, "accreditation rules")?;
+        let rule = self.accreditation_rules.delete(rule_id)?;
+        tracing::info!("Deleted accreditation rule {}", rule_id);
+        Ok(rule)
+    }
+}
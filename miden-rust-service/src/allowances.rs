@@ -0,0 +1,598 @@
+// src/allowances.rs
+//
+// Delegated spending allowances
+//
+// An account owner authorizes a spender to move up to `limit` of a token from
+// the owner's account for a purpose. The spender is either another account
+// (acting through an API key bound to it, see principals.rs) or the service
+// itself ("service", for scheduled payments such as rent).
+//
+// Allowances are enforced where the service moves an owner's tokens:
+// - escrow funding (fund_escrow, installment payments, lease deposits): a
+//   principal that is not bound to the buyer may fund from the buyer's account
+//   if it is bound to the spender of an allowance covering the amount
+// - rent collection: with SERVICE_PAYMENTS_REQUIRE_ALLOWANCE set, the service
+//   collects rent only against a "service" allowance from the tenant
+// - transfers: POST /allowances/:id/transfers sends tokens from the owner to
+//   any account, within the allowance
+//
+// Every spend is recorded on the allowance and counts against its limit. An
+// allowance ends when it is used up, expires or is revoked by the owner.
+// Granting and revoking are authorized like escrow actions: the API key must be
+// bound to the owner account, or hold the arbiter role.
+
+use anyhow::Result;
+use miden_client::account::AccountId;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    account_id_to_hex,
+    data_subjects::{erase, ErasedField},
+    escrow::{EscrowAccount, EscrowAction, EscrowAuthError},
+    read_cache::Touched,
+    MidenClientWrapper,
+};
+
+/// Spender that stands for the service itself.
+pub const SERVICE_SPENDER: &str = "service";
+
+/// What an allowance may be spent on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AllowancePurpose {
+    Any,
+    EscrowFunding,
+    Installments,
+    Rent,
+    Transfers,
+}
+
+impl AllowancePurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AllowancePurpose::Any => "any",
+            AllowancePurpose::EscrowFunding => "escrow_funding",
+            AllowancePurpose::Installments => "installments",
+            AllowancePurpose::Rent => "rent",
+            AllowancePurpose::Transfers => "transfers",
+        }
+    }
+
+    fn covers(&self, purpose: AllowancePurpose) -> bool {
+        *self == AllowancePurpose::Any || *self == purpose
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllowanceSpend {
+    pub amount: u64,
+    pub purpose: AllowancePurpose,
+    /// What the spend paid for, e.g. an escrow or "lease-1#3"
+    pub reference: String,
+    pub tx_id: String,
+    pub spent_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Allowance {
+    pub allowance_id: u64,
+    /// Hex AccountId whose tokens may be spent
+    pub owner_account_id: String,
+    /// Hex AccountId of the spender, or "service"
+    pub spender: String,
+    pub purpose: AllowancePurpose,
+    /// Hex AccountId of the token's faucet
+    pub faucet_id: String,
+    pub limit: u64,
+    pub spent: u64,
+    pub label: Option<String>,
+    /// Key ID of the principal that granted it (when auth is enforced)
+    pub granted_by: Option<u64>,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub revoked_at: Option<i64>,
+    pub spends: Vec<AllowanceSpend>,
+}
+
+impl Allowance {
+    pub fn remaining(&self) -> u64 {
+        self.limit.saturating_sub(self.spent)
+    }
+
+    pub fn is_active(&self, now: i64) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|t| now < t) && self.remaining() > 0
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AllowanceInput {
+    /// Account name or hex AccountId
+    pub owner_account_id: String,
+    /// Account name, hex AccountId or "service"
+    pub spender: String,
+    pub purpose: AllowancePurpose,
+    pub limit: u64,
+    /// Faucet of the token (the service token when absent)
+    #[serde(default)]
+    pub faucet_id: Option<String>,
+    #[serde(default)]
+    pub expires_in_secs: Option<u64>,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AllowanceTransferInput {
+    /// Account name or hex AccountId
+    pub to_account_id: String,
+    pub amount: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AllowanceStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    allowances: BTreeMap<u64, Allowance>,
+    #[serde(default)]
+    next_allowance_id: u64,
+}
+
+impl AllowanceStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<AllowanceStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            AllowanceStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, allowance_id: u64) -> Result<&Allowance> {
+        self.allowances
+            .get(&allowance_id)
+            .ok_or_else(|| anyhow::anyhow!("Allowance {} not found", allowance_id))
+    }
+
+    /// Erases the labels of allowances an account granted (data_subjects.rs).
+    pub fn erase_personal_fields(&mut self, account_hex: &str) -> Result<Vec<ErasedField>> {
+        let mut erased = Vec::new();
+        for allowance in self
+            .allowances
+            .values_mut()
+            .filter(|a| a.owner_account_id.eq_ignore_ascii_case(account_hex))
+        {
+            if let Some(original) = allowance.label.as_mut().and_then(erase) {
+                erased.push(ErasedField::new(
+                    "allowances",
+                    &allowance.allowance_id.to_string(),
+                    "label",
+                    original,
+                ));
+            }
+        }
+        if !erased.is_empty() {
+            self.save()?;
+        }
+        Ok(erased)
+    }
+
+    /// All allowances, or those an account is owner or spender of.
+    pub fn list(&self, account_hex: Option<&str>) -> Vec<&Allowance> {
+        self.allowances
+            .values()
+            .filter(|a| {
+                account_hex.is_none_or(|acc| {
+                    a.owner_account_id.eq_ignore_ascii_case(acc)
+                        || a.spender.eq_ignore_ascii_case(acc)
+                })
+            })
+            .collect()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn grant(
+        &mut self,
+        owner_hex: String,
+        spender: String,
+        purpose: AllowancePurpose,
+        faucet_hex: String,
+        limit: u64,
+        expires_at: Option<i64>,
+        label: Option<String>,
+        granted_by: Option<u64>,
+    ) -> Result<Allowance> {
+        self.next_allowance_id += 1;
+        let allowance = Allowance {
+            allowance_id: self.next_allowance_id,
+            owner_account_id: owner_hex.to_lowercase(),
+            spender: spender.to_lowercase(),
+            purpose,
+            faucet_id: faucet_hex.to_lowercase(),
+            limit,
+            spent: 0,
+            label,
+            granted_by,
+            created_at: chrono::Utc::now().timestamp(),
+            expires_at,
+            revoked_at: None,
+            spends: Vec::new(),
+        };
+
+        self.allowances
+            .insert(allowance.allowance_id, allowance.clone());
+        self.save()?;
+        Ok(allowance)
+    }
+
+    pub fn revoke(&mut self, allowance_id: u64) -> Result<Allowance> {
+        let allowance = self
+            .allowances
+            .get_mut(&allowance_id)
+            .ok_or_else(|| anyhow::anyhow!("Allowance {} not found", allowance_id))?;
+        if allowance.revoked_at.is_some() {
+            return Err(anyhow::anyhow!(
+                "Allowance {} has already been revoked",
+                allowance_id
+            ));
+        }
+        allowance.revoked_at = Some(chrono::Utc::now().timestamp());
+
+        let allowance = allowance.clone();
+        self.save()?;
+        Ok(allowance)
+    }
+
+    /// First active allowance from `owner_hex` to a spender accepted by
+    /// `spender` that covers `amount` of the token for `purpose`.
+    pub fn find(
+        &self,
+        owner_hex: &str,
+        spender: impl Fn(&str) -> bool,
+        purpose: AllowancePurpose,
+        faucet_hex: &str,
+        amount: u64,
+    ) -> Option<&Allowance> {
+        let now = chrono::Utc::now().timestamp();
+        self.allowances.values().find(|a| {
+            a.owner_account_id.eq_ignore_ascii_case(owner_hex)
+                && spender(&a.spender)
+                && a.purpose.covers(purpose)
+                && a.faucet_id.eq_ignore_ascii_case(faucet_hex)
+                && a.is_active(now)
+                && a.remaining() >= amount
+        })
+    }
+
+    /// Records a spend that was made against the allowance.
+    pub fn spend(
+        &mut self,
+        allowance_id: u64,
+        amount: u64,
+        purpose: AllowancePurpose,
+        reference: &str,
+        tx_id: &str,
+    ) {
+        let Some(allowance) = self.allowances.get_mut(&allowance_id) else {
+            return;
+        };
+        allowance.spent += amount;
+        allowance.spends.push(AllowanceSpend {
+            amount,
+            purpose,
+            reference: reference.to_string(),
+            tx_id: tx_id.to_string(),
+            spent_at: chrono::Utc::now().timestamp(),
+        });
+
+        if let Err(e) = self.save() {
+            tracing::warn!("Failed to persist allowances: {}", e);
+        }
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    GrantAllowance {
+        input: AllowanceInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.grant_allowance(op.input, op.api_key.as_deref());
+
+    RevokeAllowance {
+        allowance_id: u64,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.revoke_allowance(op.allowance_id, op.api_key.as_deref());
+
+    GetAllowance {
+        allowance_id: u64,
+    } -> serde_json::Value;
+    |client, op| client.get_allowance(op.allowance_id);
+
+    ListAllowances {
+        account_id: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.list_allowances(op.account_id.as_deref());
+
+    TransferFromAllowance {
+        allowance_id: u64,
+        input: AllowanceTransferInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    touched: |_op| Some(Touched::All);
+    |client, op| {
+        client
+            .transfer_from_allowance(op.allowance_id, op.input, op.api_key.as_deref())
+            .await
+    }
+}
+
+impl MidenClientWrapper {
+    fn service_token_hex(&self) -> Result<String> {
+        self.named_account("faucet").map(account_id_to_hex)
+    }
+
+    /// Allowance a caller spends from when moving `amount` of the service
+    /// token out of `owner_hex` without being bound to it. None when no
+    /// allowance is involved (auth disabled, or the caller acts for the owner).
+    pub(crate) fn delegated_allowance(
+        &self,
+        api_key: Option<&str>,
+        owner_hex: &str,
+        purpose: AllowancePurpose,
+        amount: u64,
+    ) -> Result<Option<u64>> {
+        let Some(principal) = self.request_principal(api_key)? else {
+            return Ok(None);
+        };
+        if principal.owns(owner_hex) {
+            return Ok(None);
+        }
+
+        let faucet_hex = self.service_token_hex()?;
+        Ok(self
+            .allowances
+            .find(
+                owner_hex,
+                |spender| principal.owns(spender),
+                purpose,
+                &faucet_hex,
+                amount,
+            )
+            .map(|a| a.allowance_id))
+    }
+
+    /// Authorizes paying `amount` from an escrow's buyer into the escrow: by
+    /// the buyer, or by a delegate spending an allowance from the buyer, whose
+    /// ID is returned so the spend can be recorded once it went through.
+    pub(crate) fn authorize_funding(
+        &self,
+        api_key: Option<&str>,
+        escrow: &EscrowAccount,
+        purpose: AllowancePurpose,
+        amount: u64,
+    ) -> Result<(EscrowAccount, Option<u64>)> {
+        let buyer_hex = account_id_to_hex(escrow.buyer_account_id);
+        let allowance_id = self.delegated_allowance(api_key, &buyer_hex, purpose, amount)?;
+        let action = match allowance_id {
+            Some(_) => EscrowAction::DelegatedFund,
+            None => EscrowAction::Fund,
+        };
+
+        Ok((
+            self.authorize_escrow(api_key, action, escrow)?,
+            allowance_id,
+        ))
+    }
+
+    /// Allowance the service spends from for a scheduled payment out of
+    /// `owner_hex`. None when SERVICE_PAYMENTS_REQUIRE_ALLOWANCE is off.
+    pub(crate) fn service_allowance(
+        &self,
+        owner_hex: &str,
+        purpose: AllowancePurpose,
+        amount: u64,
+    ) -> Result<Option<u64>> {
+        if !self.config.service_payments_require_allowance {
+            return Ok(None);
+        }
+
+        let faucet_hex = self.service_token_hex()?;
+        self.allowances
+            .find(
+                owner_hex,
+                |spender| spender == SERVICE_SPENDER,
+                purpose,
+                &faucet_hex,
+                amount,
+            )
+            .map(|a| Some(a.allowance_id))
+            .ok_or_else(|| {
+                EscrowAuthError::Forbidden(format!(
+                    "No active {} allowance lets the service spend {} from {}",
+                    purpose.as_str(),
+                    amount,
+                    owner_hex
+                ))
+                .into()
+            })
+    }
+
+    pub fn grant_allowance(
+        &mut self,
+        input: AllowanceInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let owner_hex = self.account_hex(&input.owner_account_id)?;
+        let granted_by = self.request_principal(api_key)?.map(|p| p.key_id);
+        self.authorize_account(api_key, &owner_hex, "owner")?;
+
+        let spender = match input.spender.as_str() {
+            SERVICE_SPENDER => SERVICE_SPENDER.to_string(),
+            other => self.account_hex(other)?,
+        };
+        if spender.eq_ignore_ascii_case(&owner_hex) {
+            return Err(anyhow::anyhow!(
+                "An account cannot grant itself an allowance"
+            ));
+        }
+        let faucet_hex = match input.faucet_id.as_deref() {
+            Some(faucet) => self.account_hex(faucet)?,
+            None => self.service_token_hex()?,
+        };
+        let expires_at = input
+            .expires_in_secs
+            .map(|secs| chrono::Utc::now().timestamp() + secs as i64);
+
+        let allowance = self.allowances.grant(
+            owner_hex,
+            spender,
+            input.purpose,
+            faucet_hex,
+            input.limit,
+            expires_at,
+            input.label,
+            granted_by,
+        )?;
+        tracing::info!(
+            "Allowance {}: {} may spend {} from {} ({})",
+            allowance.allowance_id,
+            allowance.spender,
+            allowance.limit,
+            allowance.owner_account_id,
+            allowance.purpose.as_str()
+        );
+
+        Ok(serde_json::json!(allowance))
+    }
+
+    pub fn revoke_allowance(
+        &mut self,
+        allowance_id: u64,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let owner_hex = self.allowances.get(allowance_id)?.owner_account_id.clone();
+        self.authorize_account(api_key, &owner_hex, "owner")?;
+
+        let allowance = self.allowances.revoke(allowance_id)?;
+        tracing::info!("Allowance {} revoked", allowance_id);
+
+        Ok(serde_json::json!(allowance))
+    }
+
+    pub fn get_allowance(&self, allowance_id: u64) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self.allowances.get(allowance_id)?))
+    }
+
+    pub fn list_allowances(&self, account_id: Option<&str>) -> Result<serde_json::Value> {
+        let account_hex = account_id.map(|a| self.account_hex(a)).transpose()?;
+        Ok(serde_json::json!(self
+            .allowances
+            .list(account_hex.as_deref())))
+    }
+
+    /// Sends tokens from the allowance owner's account on the spender's
+    /// behalf. "service" allowances can only be spent here by arbiters.
+    pub async fn transfer_from_allowance(
+        &mut self,
+        allowance_id: u64,
+        input: AllowanceTransferInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let allowance = self.allowances.get(allowance_id)?.clone();
+        if allowance.spender == SERVICE_SPENDER {
+            if let Some(principal) = self.request_principal(api_key)? {
+                if !principal.arbiter {
+                    return Err(EscrowAuthError::Forbidden(format!(
+                        "Only arbiters may spend service allowance {}",
+                        allowance_id
+                    ))
+                    .into());
+                }
+            }
+        } else {
+            self.authorize_account(api_key, &allowance.spender, "spender")?;
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        if !allowance.is_active(now) {
+            return Err(EscrowAuthError::Forbidden(format!(
+                "Allowance {} is revoked, expired or used up",
+                allowance_id
+            ))
+            .into());
+        }
+        if !allowance.purpose.covers(AllowancePurpose::Transfers) {
+            return Err(EscrowAuthError::Forbidden(format!(
+                "Allowance {} is for {} only",
+                allowance_id,
+                allowance.purpose.as_str()
+            ))
+            .into());
+        }
+        if input.amount > allowance.remaining() {
+            return Err(EscrowAuthError::Forbidden(format!(
+                "Allowance {} has {} left, {} requested",
+                allowance_id,
+                allowance.remaining(),
+                input.amount
+            ))
+            .into());
+        }
+
+        let from = AccountId::from_hex(&allowance.owner_account_id)?;
+        let to_hex = self.account_hex(&input.to_account_id)?;
+        let to = AccountId::from_hex(&to_hex)?;
+        let faucet = AccountId::from_hex(&allowance.faucet_id)?;
+
+        let reference = format!("allowance-{}", allowance_id);
+        let op_id = self
+            .records
+            .begin_operation("allowance_transfer", &reference);
+        let result = self
+            .submit_asset_payment(from, to, faucet, input.amount)
+            .await;
+        let tx_id = result.as_ref().ok().cloned();
+        self.records.finish_operation(op_id, &result, tx_id);
+        let tx_id = result?;
+
+        self.allowances.spend(
+            allowance_id,
+            input.amount,
+            AllowancePurpose::Transfers,
+            &to_hex,
+            &tx_id,
+        );
+        tracing::info!(
+            "Allowance {}: {} sent from {} to {} (tx {})",
+            allowance_id,
+            input.amount,
+            allowance.owner_account_id,
+            to_hex,
+            tx_id
+        );
+
+        Ok(serde_json::json!({
+            "tx_id": tx_id,
+            "allowance": self.allowances.get(allowance_id)?,
+        }))
+    }
+}
@@ -0,0 +1,279 @@
+// src/api_version.rs
+//
+// API versioning
+//
+// All endpoints are served under /api/v{N}. The original unprefixed routes stay
+// mounted as deprecated aliases of v1 and answer with:
+// - Deprecation: @<unix time>                (RFC 9745)
+// - Sunset: <HTTP-date>                      (RFC 8594, LEGACY_API_SUNSET)
+// - Link: </api/v1/...>; rel="successor-version"
+//
+// Version negotiation: a client may pin a version with an `Api-Version: N`
+// header or `Accept: application/vnd.obscura.vN+json`. The path version wins
+// for prefixed routes (a conflicting header is rejected); unprefixed routes use
+// the negotiated version. Unsupported versions get 406 with the supported list,
+// and every response carries the version that served it in `Api-Version`.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::NaiveDate;
+
+pub const CURRENT_API_VERSION: u32 = 1;
+pub const SUPPORTED_API_VERSIONS: &[u32] = &[1];
+
+/// Date the unprefixed routes were deprecated in favour of /api/v1.
+const LEGACY_DEPRECATED_ON: (i32, u32, u32) = (2026, 10, 16);
+
+pub const API_VERSION_HEADER: HeaderName = HeaderName::from_static("api-version");
+const DEPRECATION_HEADER: HeaderName = HeaderName::from_static("deprecation");
+const SUNSET_HEADER: HeaderName = HeaderName::from_static("sunset");
+
+const VENDOR_MEDIA_PREFIX: &str = "application/vnd.obscura.v";
+
+#[derive(Debug, Clone)]
+pub struct VersionPolicy {
+    /// When the legacy unprefixed routes are removed; None omits Sunset
+    pub legacy_sunset: Option<NaiveDate>,
+}
+
+fn http_date(date: NaiveDate) -> String {
+    date.and_hms_opt(0, 0, 0)
+        .unwrap_or_default()
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string()
+}
+
+fn deprecated_on() -> NaiveDate {
+    let (y, m, d) = LEGACY_DEPRECATED_ON;
+    NaiveDate::from_ymd_opt(y, m, d).unwrap_or_default()
+}
+
+impl VersionPolicy {
+    /// Body of GET /api/versions.
+    pub fn versions_document(&self) -> serde_json::Value {
+        serde_json::json!({
+            "current": CURRENT_API_VERSION,
+            "supported": SUPPORTED_API_VERSIONS,
+            "base_paths": SUPPORTED_API_VERSIONS
+                .iter()
+                .map(|v| format!("/api/v{}", v))
+                .collect::<Vec<_>>(),
+            "negotiation": {
+                "header": "Api-Version",
+                "media_type": format!("{}{{N}}+json", VENDOR_MEDIA_PREFIX),
+            },
+            "legacy_routes": {
+                "alias_of": format!("/api/v{}", CURRENT_API_VERSION),
+                "deprecated_on": deprecated_on().to_string(),
+                "sunset": self.legacy_sunset.map(|d| d.to_string()),
+            },
+        })
+    }
+}
+
+/// Version from an `/api/v{N}/...` path, if the path is versioned.
+fn path_version(path: &str) -> Option<u32> {
+    let segment = path.strip_prefix("/api/")?.split('/').next()?;
+    let digits = segment.strip_prefix('v')?;
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// The path without its /api/vN prefix, as routed for legacy aliases.
+pub fn unversioned_path(path: &str) -> &str {
+    if path_version(path).is_none() {
+        return path;
+    }
+    let rest = &path["/api/".len()..];
+    rest.find('/').map_or("/", |i| &rest[i..])
+}
+
+/// Version requested through headers, if any.
+fn header_version(headers: &HeaderMap) -> Result<Option<u32>, String> {
+    if let Some(value) = headers.get(API_VERSION_HEADER) {
+        let raw = value.to_str().unwrap_or_default().trim();
+        let digits = raw.strip_prefix('v').unwrap_or(raw);
+        return digits
+            .parse::<u32>()
+            .map(Some)
+            .map_err(|_| format!("Invalid Api-Version header: {}", raw));
+    }
+
+    let accept = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    for media in accept.split(',') {
+        let media = media.split(';').next().unwrap_or_default().trim();
+        if let Some(rest) = media.strip_prefix(VENDOR_MEDIA_PREFIX) {
+            let digits = rest.strip_suffix("+json").unwrap_or(rest);
+            return digits
+                .parse::<u32>()
+                .map(Some)
+                .map_err(|_| format!("Invalid versioned media type: {}", media));
+        }
+    }
+
+    Ok(None)
+}
+
+fn version_error(status: StatusCode, message: String) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "success": false,
+            "error": message,
+            "supported_versions": SUPPORTED_API_VERSIONS,
+        })),
+    )
+        .into_response()
+}
+
+/// Resolves the API version of every request and stamps it on the response.
+pub async fn negotiate(req: Request, next: Next) -> Response {
+    let from_path = path_version(req.uri().path());
+    let from_header = match header_version(req.headers()) {
+        Ok(version) => version,
+        Err(e) => return version_error(StatusCode::BAD_REQUEST, e),
+    };
+
+    let version = match (from_path, from_header) {
+        (Some(path), Some(requested)) if path != requested => {
+            return version_error(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Requested API version {} conflicts with path version {}",
+                    requested, path
+                ),
+            );
+        }
+        (Some(path), _) => path,
+        (None, requested) => requested.unwrap_or(CURRENT_API_VERSION),
+    };
+
+    if !SUPPORTED_API_VERSIONS.contains(&version) {
+        let status = if from_path.is_some() {
+            StatusCode::NOT_FOUND
+        } else {
+            StatusCode::NOT_ACCEPTABLE
+        };
+        return version_error(status, format!("API version {} is not supported", version));
+    }
+
+    let mut response = next.run(req).await;
+    response
+        .headers_mut()
+        .insert(API_VERSION_HEADER, HeaderValue::from(version));
+    response
+}
+
+/// Marks responses of the unprefixed legacy routes as deprecated.
+pub async fn legacy_alias(
+    State(policy): State<VersionPolicy>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let successor = format!("/api/v{}{}", CURRENT_API_VERSION, req.uri().path());
+
+    let mut response = next.run(req).await;
+    let headers = response.headers_mut();
+
+    let deprecated_at = deprecated_on()
+        .and_hms_opt(0, 0, 0)
+        .unwrap_or_default()
+        .and_utc()
+        .timestamp();
+    if let Ok(value) = HeaderValue::from_str(&format!("@{}", deprecated_at)) {
+        headers.insert(DEPRECATION_HEADER, value);
+    }
+    if let Some(sunset) = policy.legacy_sunset {
+        if let Ok(value) = HeaderValue::from_str(&http_date(sunset)) {
+            headers.insert(SUNSET_HEADER, value);
+        }
+    }
+    if let Ok(value) = HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", successor))
+    {
+        headers.append(header::LINK, value);
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(HeaderName, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(name.clone(), HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn only_numeric_v_segments_are_versions() {
+        assert_eq!(path_version("/api/v1/properties"), Some(1));
+        assert_eq!(path_version("/api/v12"), Some(12));
+        assert_eq!(path_version("/api/v/properties"), None);
+        assert_eq!(path_version("/api/vnext/properties"), None);
+        assert_eq!(path_version("/api/versions"), None);
+        assert_eq!(path_version("/properties/v1"), None);
+        // Past u32 is not a version either
+        assert_eq!(path_version("/api/v4294967296/x"), None);
+    }
+
+    #[test]
+    fn unversioned_paths_drop_only_the_prefix() {
+        assert_eq!(
+            unversioned_path("/api/v1/properties/P-1"),
+            "/properties/P-1"
+        );
+        assert_eq!(unversioned_path("/api/v1"), "/");
+        assert_eq!(unversioned_path("/api/versions"), "/api/versions");
+        assert_eq!(unversioned_path("/health"), "/health");
+    }
+
+    #[test]
+    fn the_api_version_header_wins_over_accept() {
+        assert_eq!(header_version(&HeaderMap::new()), Ok(None));
+        assert_eq!(
+            header_version(&headers(&[(API_VERSION_HEADER, " v2 ")])),
+            Ok(Some(2))
+        );
+        assert_eq!(
+            header_version(&headers(&[
+                (API_VERSION_HEADER, "1"),
+                (header::ACCEPT, "application/vnd.obscura.v3+json"),
+            ])),
+            Ok(Some(1))
+        );
+        assert!(header_version(&headers(&[(API_VERSION_HEADER, "latest")])).is_err());
+        assert!(header_version(&headers(&[(API_VERSION_HEADER, "4294967296")])).is_err());
+    }
+
+    #[test]
+    fn accept_picks_the_first_vendor_media_type() {
+        let accept = |value| header_version(&headers(&[(header::ACCEPT, value)]));
+        assert_eq!(accept("application/json"), Ok(None));
+        assert_eq!(
+            accept("text/html, application/vnd.obscura.v2+json; q=0.9, application/vnd.obscura.v1+json"),
+            Ok(Some(2))
+        );
+        assert_eq!(accept("application/vnd.obscura.v1"), Ok(Some(1)));
+        assert!(accept("application/vnd.obscura.vx+json").is_err());
+    }
+
+    #[test]
+    fn sunset_dates_are_http_dates() {
+        let date = NaiveDate::from_ymd_opt(2027, 4, 1).unwrap();
+        assert_eq!(http_date(date), "Thu, 01 Apr 2027 00:00:00 GMT");
+    }
+}
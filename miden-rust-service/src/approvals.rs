@@ -0,0 +1,523 @@
+// src/approvals.rs
+//
+// Two-person approval of high-value escrow releases and treasury withdrawals
+//
+// With ESCROW_APPROVAL_THRESHOLD set, a release of an escrow whose recorded
+// amount is above it is not executed on the first request. The request is
+// authorized as usual (escrow.rs) and then parked as a pending approval; the
+// release endpoint answers 202 with the approval ID. A second principal must
+// confirm it with POST /approvals/:approval_id/confirm:
+// - the confirming API key must itself be allowed to release the escrow
+// - it must belong to a different principal than the requesting one
+// - the approval must not have expired (ESCROW_APPROVAL_TTL_SECS)
+//
+// The client task executes the release as part of the confirmation, with the
+// usual lien and checklist checks; a transient failure lands in the retry
+// queue (retry_queue.rs) like any other release. Releases the service performs
+// itself (installment plan settlement) are not subject to approval.
+//
+// Every approval keeps an audit trail: who requested it, who confirmed it, and
+// how the release went, or when the approval expired.
+//
+// A release requested by the escrow's closing agent (principals.rs) is parked
+// whatever its amount, and closing agent keys cannot confirm approvals. Pending approvals expire
+// on the scheduler tick (scheduler.rs). Requires ESCROW_AUTH_REQUIRED, since
+// the two approvers are told apart by their API keys.
+//
+// Treasury withdrawals (treasury.rs) are always parked the same way; they are
+// requested and confirmed by two different admins, and the confirmation sends
+// the funds. Their approvals name the withdrawal instead of an escrow.
+//
+// Re-issues of a property after its owner lost access (property_recovery.rs)
+// need more than one confirmation (PROPERTY_REISSUE_APPROVERS admins besides
+// the requester). Confirmations before the last are recorded as endorsements
+// and leave the approval pending; the last one carries out the re-issue.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    account_id_to_hex,
+    escrow::{EscrowAccount, EscrowAction, EscrowAuthError, EscrowStatus},
+    principals::Principal,
+    property_recovery::PropertyReissueRequest,
+    read_cache::Touched,
+    retry_queue::{expect_escrow_status, RetryOperation},
+    treasury::TreasuryWithdrawal,
+    MidenClientWrapper,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    Pending,
+    /// Confirmed; the release is being retried (retry_queue.rs)
+    Confirmed,
+    /// Escrow released or withdrawal sent
+    Released,
+    Failed,
+    Expired,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalAction {
+    Requested,
+    /// A confirmation of an approval that needs more
+    Endorsed,
+    Confirmed,
+    Released,
+    ReleaseFailed,
+    Expired,
+}
+
+/// The principal behind an approval action.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Approver {
+    pub key_id: u64,
+    pub label: String,
+}
+
+impl From<&Principal> for Approver {
+    fn from(principal: &Principal) -> Self {
+        Self {
+            key_id: principal.key_id,
+            label: principal.label.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalAuditEntry {
+    pub action: ApprovalAction,
+    /// None for actions the service takes itself (expiry, release outcome)
+    pub approver: Option<Approver>,
+    pub at: i64,
+    pub detail: Option<String>,
+}
+
+/// What an approval lets happen once confirmed.
+#[derive(Debug, Clone)]
+pub enum ApprovalSubject {
+    /// Release of the escrow with this hex AccountId
+    EscrowRelease(String),
+    TreasuryWithdrawal(TreasuryWithdrawal),
+    PropertyReissue(PropertyReissueRequest),
+}
+
+fn one_confirmation() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Approval {
+    pub approval_id: u64,
+    /// Hex AccountId of the escrow to release; None for withdrawals and
+    /// re-issues
+    pub escrow_account_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub withdrawal: Option<TreasuryWithdrawal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub property_reissue: Option<PropertyReissueRequest>,
+    pub amount: u64,
+    pub status: ApprovalStatus,
+    pub requested_by: Approver,
+    /// Confirmations needed, the last one included
+    #[serde(default = "one_confirmation")]
+    pub confirmations_required: u32,
+    /// Confirmations before the last
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub endorsed_by: Vec<Approver>,
+    pub confirmed_by: Option<Approver>,
+    pub created_at: i64,
+    pub expires_at: i64,
+    /// Transaction of the release or withdrawal
+    pub release_tx_id: Option<String>,
+    pub error: Option<String>,
+    pub audit: Vec<ApprovalAuditEntry>,
+}
+
+impl Approval {
+    fn record(
+        &mut self,
+        action: ApprovalAction,
+        approver: Option<Approver>,
+        detail: Option<String>,
+    ) {
+        self.audit.push(ApprovalAuditEntry {
+            action,
+            approver,
+            at: chrono::Utc::now().timestamp(),
+            detail,
+        });
+    }
+}
+
+/// Raised when a release was parked for a second approver; the HTTP layer
+/// maps it to 202.
+#[derive(Debug, thiserror::Error)]
+#[error("Approval required: {0}")]
+pub struct ApprovalRequired(pub String);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ApprovalStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    approvals: BTreeMap<u64, Approval>,
+    #[serde(default)]
+    next_approval_id: u64,
+}
+
+impl ApprovalStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<ApprovalStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            ApprovalStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, approval_id: u64) -> Result<&Approval> {
+        self.approvals
+            .get(&approval_id)
+            .ok_or_else(|| anyhow::anyhow!("Approval {} not found", approval_id))
+    }
+
+    /// All approvals, or those in one status, ordered by approval ID.
+    pub fn list(&self, status: Option<ApprovalStatus>) -> Vec<&Approval> {
+        self.approvals
+            .values()
+            .filter(|a| status.is_none_or(|s| a.status == s))
+            .collect()
+    }
+
+    fn pending_for(&self, escrow_account_id: &str) -> Option<&Approval> {
+        self.approvals.values().find(|a| {
+            a.status == ApprovalStatus::Pending
+                && a.escrow_account_id
+                    .as_deref()
+                    .is_some_and(|id| id.eq_ignore_ascii_case(escrow_account_id))
+        })
+    }
+
+    pub fn request(
+        &mut self,
+        subject: ApprovalSubject,
+        amount: u64,
+        requested_by: Approver,
+        confirmations_required: u32,
+        ttl_secs: i64,
+    ) -> Result<Approval> {
+        let now = chrono::Utc::now().timestamp();
+        let (escrow_account_id, withdrawal, property_reissue) = match subject {
+            ApprovalSubject::EscrowRelease(escrow_account_id) => {
+                (Some(escrow_account_id), None, None)
+            }
+            ApprovalSubject::TreasuryWithdrawal(withdrawal) => (None, Some(withdrawal), None),
+            ApprovalSubject::PropertyReissue(reissue) => (None, None, Some(reissue)),
+        };
+        self.next_approval_id += 1;
+        let mut approval = Approval {
+            approval_id: self.next_approval_id,
+            escrow_account_id,
+            withdrawal,
+            property_reissue,
+            amount,
+            status: ApprovalStatus::Pending,
+            requested_by: requested_by.clone(),
+            confirmations_required: confirmations_required.max(1),
+            endorsed_by: Vec::new(),
+            confirmed_by: None,
+            created_at: now,
+            expires_at: now + ttl_secs,
+            release_tx_id: None,
+            error: None,
+            audit: Vec::new(),
+        };
+        approval.record(ApprovalAction::Requested, Some(requested_by), None);
+
+        self.approvals
+            .insert(approval.approval_id, approval.clone());
+        self.save()?;
+        Ok(approval)
+    }
+
+    /// Marks a pending approval confirmed by `approver`, or endorsed when it
+    /// needs more confirmations (it then stays pending).
+    pub fn confirm(&mut self, approval_id: u64, approver: Approver) -> Result<Approval> {
+        let approval = self
+            .approvals
+            .get_mut(&approval_id)
+            .ok_or_else(|| anyhow::anyhow!("Approval {} not found", approval_id))?;
+        if approval.status != ApprovalStatus::Pending {
+            return Err(anyhow::anyhow!(
+                "Conflict: approval {} is {:?}",
+                approval_id,
+                approval.status
+            ));
+        }
+        if approval
+            .endorsed_by
+            .iter()
+            .any(|a| a.key_id == approver.key_id)
+        {
+            return Err(EscrowAuthError::Forbidden(format!(
+                "API key {} already confirmed approval {}",
+                approver.key_id, approval_id
+            ))
+            .into());
+        }
+        if (approval.endorsed_by.len() as u32) + 1 < approval.confirmations_required {
+            approval.endorsed_by.push(approver.clone());
+            approval.record(ApprovalAction::Endorsed, Some(approver), None);
+        } else {
+            approval.status = ApprovalStatus::Confirmed;
+            approval.confirmed_by = Some(approver.clone());
+            approval.record(ApprovalAction::Confirmed, Some(approver), None);
+        }
+
+        let approval = approval.clone();
+        self.save()?;
+        Ok(approval)
+    }
+
+    /// Records how the release (or withdrawal) of a confirmed approval went.
+    pub fn finish(&mut self, approval_id: u64, result: &Result<String>) -> Result<Approval> {
+        let approval = self
+            .approvals
+            .get_mut(&approval_id)
+            .ok_or_else(|| anyhow::anyhow!("Approval {} not found", approval_id))?;
+        match result {
+            Ok(tx_id) => {
+                approval.status = ApprovalStatus::Released;
+                approval.release_tx_id = Some(tx_id.clone());
+                approval.record(ApprovalAction::Released, None, Some(tx_id.clone()));
+            }
+            Err(e) => {
+                let error = e.to_string();
+                // Handed to the retry or offline queue: stays confirmed
+                if !error.starts_with("Retrying:") && !error.starts_with("Queued offline:") {
+                    approval.status = ApprovalStatus::Failed;
+                }
+                approval.error = Some(error.clone());
+                approval.record(ApprovalAction::ReleaseFailed, None, Some(error));
+            }
+        }
+
+        let approval = approval.clone();
+        self.save()?;
+        Ok(approval)
+    }
+
+    /// Expires pending approvals past their deadline. Returns how many.
+    pub fn expire(&mut self, now: i64) -> Result<usize> {
+        let mut expired = 0;
+        for approval in self
+            .approvals
+            .values_mut()
+            .filter(|a| a.status == ApprovalStatus::Pending && a.expires_at <= now)
+        {
+            approval.status = ApprovalStatus::Expired;
+            approval.record(ApprovalAction::Expired, None, None);
+            expired += 1;
+        }
+        if expired > 0 {
+            self.save()?;
+        }
+        Ok(expired)
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    ConfirmApproval {
+        approval_id: u64,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.confirm_approval(op.approval_id, op.api_key.as_deref()).await;
+
+    GetApproval {
+        approval_id: u64,
+    } -> serde_json::Value;
+    |client, op| client.get_approval(op.approval_id);
+
+    ListApprovals {
+        status: Option<ApprovalStatus>,
+    } -> serde_json::Value;
+    |client, op| client.list_approvals(op.status)
+}
+
+impl MidenClientWrapper {
+    /// Parks a release of `escrow` (already authorized) for a second approver
+    /// when its amount is above ESCROW_APPROVAL_THRESHOLD or a closing agent
+    /// requests it. Returns Ok when no approval is needed.
+    pub(crate) fn require_release_approval(
+        &mut self,
+        escrow: &EscrowAccount,
+        api_key: Option<&str>,
+    ) -> Result<()> {
+        let escrow_hex = account_id_to_hex(escrow.escrow_account_id);
+        if self.is_closing_agent(api_key, &escrow_hex)? {
+            let requested_by = Approver::from(self.release_principal(api_key)?);
+            return self.park_release(escrow, requested_by);
+        }
+        self.require_release_approval_from(escrow, |wrapper| {
+            wrapper.release_principal(api_key).map(Approver::from)
+        })
+    }
+
+    /// Like `require_release_approval`, with the requester given by
+    /// `requested_by` (sagas.rs keeps the principal that started a saga).
+    pub(crate) fn require_release_approval_from(
+        &mut self,
+        escrow: &EscrowAccount,
+        requested_by: impl FnOnce(&Self) -> Result<Approver>,
+    ) -> Result<()> {
+        let threshold = self.config.escrow_approval_threshold;
+        if threshold == 0 || escrow.amount <= threshold {
+            return Ok(());
+        }
+
+        let requested_by = requested_by(self)?;
+        self.park_release(escrow, requested_by)
+    }
+
+    /// Parks a release of `escrow` as a pending approval; fails with 202.
+    fn park_release(&mut self, escrow: &EscrowAccount, requested_by: Approver) -> Result<()> {
+        let escrow_hex = account_id_to_hex(escrow.escrow_account_id);
+        self.approvals.expire(chrono::Utc::now().timestamp())?;
+        if let Some(pending) = self.approvals.pending_for(&escrow_hex) {
+            return Err(ApprovalRequired(format!(
+                "release of escrow {} is waiting for approval {}",
+                escrow_hex, pending.approval_id
+            ))
+            .into());
+        }
+
+        let approval = self.approvals.request(
+            ApprovalSubject::EscrowRelease(escrow_hex.clone()),
+            escrow.amount,
+            requested_by,
+            1,
+            self.config.escrow_approval_ttl.as_secs() as i64,
+        )?;
+        tracing::info!(
+            "Release of escrow {} ({}, requested by key {}) needs approval {}",
+            escrow_hex,
+            escrow.amount,
+            approval.requested_by.key_id,
+            approval.approval_id
+        );
+
+        Err(ApprovalRequired(format!(
+            "release of escrow {} needs a second approver; confirm approval {} with POST /approvals/{}/confirm",
+            escrow_hex, approval.approval_id, approval.approval_id
+        ))
+        .into())
+    }
+
+    fn release_principal(&self, api_key: Option<&str>) -> Result<&Principal> {
+        self.request_principal(api_key)?.ok_or_else(|| {
+            EscrowAuthError::Unauthenticated("Escrow approvals require API keys".into()).into()
+        })
+    }
+
+    /// Confirms a pending approval and executes the release (or withdrawal). A
+    /// failed release is recorded on the approval and returned as the error.
+    pub async fn confirm_approval(
+        &mut self,
+        approval_id: u64,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        self.approvals.expire(chrono::Utc::now().timestamp())?;
+        let approval = self.approvals.get(approval_id)?.clone();
+        if approval.status != ApprovalStatus::Pending {
+            return Err(anyhow::anyhow!(
+                "Conflict: approval {} is {:?}",
+                approval_id,
+                approval.status
+            ));
+        }
+
+        if approval.property_reissue.is_some() {
+            return self.confirm_property_reissue(&approval, api_key).await;
+        }
+        let Some(escrow_hex) = approval.escrow_account_id.clone() else {
+            return self.confirm_treasury_withdrawal(&approval, api_key).await;
+        };
+
+        let escrow = self.recorded_escrow(&escrow_hex)?;
+        self.authorize_escrow(api_key, EscrowAction::Release, &escrow)?;
+        let principal = self.release_principal(api_key)?;
+        if principal.closing_agent {
+            return Err(EscrowAuthError::Forbidden(format!(
+                "closing agent key {} may request releases but not confirm approval {}",
+                principal.key_id, approval_id
+            ))
+            .into());
+        }
+        let approver = Approver::from(principal);
+        if approver.key_id == approval.requested_by.key_id {
+            return Err(EscrowAuthError::Forbidden(format!(
+                "approval {} must be confirmed by a different principal than the requester",
+                approval_id
+            ))
+            .into());
+        }
+        expect_escrow_status(&escrow, EscrowStatus::Funded)?;
+
+        self.approvals.confirm(approval_id, approver.clone())?;
+        tracing::info!(
+            "Approval {} confirmed by API key {} ({}); releasing escrow {}",
+            approval_id,
+            approver.key_id,
+            approver.label,
+            escrow_hex
+        );
+
+        let result = self.release_escrow_unchecked(&escrow).await;
+        let result = self.retry_on_failure(
+            RetryOperation::ReleaseEscrow {
+                escrow_account_id: escrow_hex,
+            },
+            result,
+        );
+        let approval = self.approvals.finish(approval_id, &result)?;
+        result?;
+
+        Ok(serde_json::json!(approval))
+    }
+
+    pub fn get_approval(&self, approval_id: u64) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self.approvals.get(approval_id)?))
+    }
+
+    pub fn list_approvals(&self, status: Option<ApprovalStatus>) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self.approvals.list(status)))
+    }
+
+    /// Scheduler job: expires pending approvals. Returns how many.
+    pub fn run_approval_expiry(&mut self) -> Result<usize> {
+        self.approvals.expire(chrono::Utc::now().timestamp())
+    }
+}
@@ -0,0 +1,721 @@
+// src/attachments.rs
+//
+// Signed property attachments and escrow checklists
+//
+// Third parties attach signed documents to properties. The document itself stays
+// off the service; an attachment carries its SHA-256 hash, the signer and the
+// terms the signer vouches for. The only kind so far is the insurance binder:
+// an insurer registered here by an admin (name + Ed25519 key) signs a binder
+// covering a property for a period.
+//
+// Binder commitment (hex):
+//   SHA-256("obscura-insurance-binder" || property_id || document_hash ||
+//           coverage_start || coverage_end)
+// with each field length-prefixed (u32 LE) and the times as decimal Unix
+// seconds. The insurer signs the 32-byte commitment.
+//
+// A binder is active while its coverage period contains the current time and its
+// insurer is still registered as active; deactivating an insurer voids its
+// binders without deleting them.
+//
+// Escrow checklists list what must hold before an escrow may be released. An
+// escrow opened for a property can require an active insurance binder on that
+// property, and any escrow can require closing documents (documents.rs) to have
+// been generated; release_escrow (escrow.rs) refuses with 409 until the
+// checklist is complete. The buyer (or an arbiter, or the escrow's closing
+// agent) sets the checklist. The hash
+// of the latest version of each generated document is attached to it, and a
+// checklist can also require that version to have been signed through the
+// e-signature provider (esign.rs), which marks it signed on completion.
+
+use anyhow::Result;
+use ed25519_dalek::{Signature, Verifier};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    documents::{ClosingDocument, DocumentKind},
+    escrow::EscrowStatus,
+    jurisdiction_lists::parse_signer_key,
+    MidenClientWrapper,
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InsurerInput {
+    pub name: String,
+    pub public_key: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BinderInput {
+    pub insurer_id: u64,
+    /// SHA-256 of the binder document (hex)
+    pub document_hash: String,
+    /// Unix time coverage starts
+    pub coverage_start: i64,
+    /// Unix time coverage ends
+    pub coverage_end: i64,
+    /// Insurer signature over the binder commitment (hex)
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChecklistInput {
+    #[serde(default)]
+    pub require_insurance_binder: bool,
+    /// Closing documents that must have been generated
+    #[serde(default)]
+    pub require_documents: Vec<DocumentKind>,
+    /// Closing documents whose latest version must have been signed
+    #[serde(default)]
+    pub require_signatures: Vec<DocumentKind>,
+}
+
+/// An escrow release refused because its checklist is incomplete. The HTTP
+/// layer maps it to 409.
+#[derive(Debug, thiserror::Error)]
+#[error("Conflict: {0}")]
+pub struct ChecklistIncomplete(pub String);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Insurer {
+    pub insurer_id: u64,
+    pub name: String,
+    /// Ed25519 public key (hex) used to check binder signatures
+    pub public_key: String,
+    pub active: bool,
+    pub registered_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InsuranceBinder {
+    pub binder_id: u64,
+    pub property_id: String,
+    pub insurer_id: u64,
+    pub document_hash: String,
+    pub coverage_start: i64,
+    pub coverage_end: i64,
+    pub signature: String,
+    pub attached_at: i64,
+}
+
+impl InsuranceBinder {
+    pub fn commitment(&self) -> [u8; 32] {
+        binder_commitment(
+            &self.property_id,
+            &self.document_hash,
+            self.coverage_start,
+            self.coverage_end,
+        )
+    }
+
+    pub fn covers(&self, at: i64) -> bool {
+        self.coverage_start <= at && at < self.coverage_end
+    }
+}
+
+pub fn binder_commitment(
+    property_id: &str,
+    document_hash: &str,
+    coverage_start: i64,
+    coverage_end: i64,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"obscura-insurance-binder");
+    for field in [
+        property_id.trim().to_string(),
+        document_hash.trim().trim_start_matches("0x").to_lowercase(),
+        coverage_start.to_string(),
+        coverage_end.to_string(),
+    ] {
+        hasher.update((field.len() as u32).to_le_bytes());
+        hasher.update(field.as_bytes());
+    }
+    hasher.finalize().into()
+}
+
+/// A generated closing document, by the hash of its PDF.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachedDocument {
+    pub document_id: u64,
+    pub version: u32,
+    pub sha256: String,
+    pub attached_at: i64,
+}
+
+/// A closing document signed through the e-signature provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedDocument {
+    pub envelope_id: u64,
+    pub document_id: u64,
+    /// SHA-256 of the PDF that was signed
+    pub sha256: String,
+    /// SHA-256 of the provider's signed copy
+    pub signed_document_sha256: Option<String>,
+    pub signed_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EscrowChecklist {
+    pub escrow_account_id: String,
+    pub require_insurance_binder: bool,
+    #[serde(default)]
+    pub require_documents: Vec<DocumentKind>,
+    /// Latest version of each generated document
+    #[serde(default)]
+    pub documents: BTreeMap<DocumentKind, AttachedDocument>,
+    #[serde(default)]
+    pub require_signatures: Vec<DocumentKind>,
+    /// Latest signature of each document kind
+    #[serde(default)]
+    pub signatures: BTreeMap<DocumentKind, SignedDocument>,
+    pub updated_at: i64,
+}
+
+impl EscrowChecklist {
+    fn empty(escrow_account_id: &str, at: i64) -> Self {
+        Self {
+            escrow_account_id: escrow_account_id.to_string(),
+            require_insurance_binder: false,
+            require_documents: Vec::new(),
+            documents: BTreeMap::new(),
+            require_signatures: Vec::new(),
+            signatures: BTreeMap::new(),
+            updated_at: at,
+        }
+    }
+
+    /// Whether the latest version of `kind` has been signed.
+    pub fn is_signed(&self, kind: DocumentKind) -> bool {
+        match (self.documents.get(&kind), self.signatures.get(&kind)) {
+            (Some(document), Some(signed)) => document.document_id == signed.document_id,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AttachmentStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    insurers: BTreeMap<u64, Insurer>,
+    #[serde(default)]
+    binders: BTreeMap<u64, InsuranceBinder>,
+    #[serde(default)]
+    checklists: BTreeMap<String, EscrowChecklist>,
+    #[serde(default)]
+    next_insurer_id: u64,
+    #[serde(default)]
+    next_binder_id: u64,
+}
+
+impl AttachmentStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<AttachmentStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            AttachmentStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn insurers(&self) -> Vec<&Insurer> {
+        self.insurers.values().collect()
+    }
+
+    pub fn register_insurer(&mut self, input: InsurerInput) -> Result<Insurer> {
+        let key = parse_signer_key(&input.public_key)?;
+        let public_key = hex::encode(key.to_bytes());
+
+        if self.insurers.values().any(|i| i.public_key == public_key) {
+            return Err(anyhow::anyhow!(
+                "An insurer with this public key is already registered"
+            ));
+        }
+
+        self.next_insurer_id += 1;
+        let insurer = Insurer {
+            insurer_id: self.next_insurer_id,
+            name: input.name,
+            public_key,
+            active: true,
+            registered_at: chrono::Utc::now().timestamp(),
+        };
+
+        self.insurers.insert(insurer.insurer_id, insurer.clone());
+        self.save()?;
+        Ok(insurer)
+    }
+
+    /// Deactivates an insurer; its binders stay attached but are no longer
+    /// active.
+    pub fn deactivate_insurer(&mut self, insurer_id: u64) -> Result<Insurer> {
+        let insurer = self
+            .insurers
+            .get_mut(&insurer_id)
+            .ok_or_else(|| anyhow::anyhow!("Insurer {} not found", insurer_id))?;
+        insurer.active = false;
+
+        let insurer = insurer.clone();
+        self.save()?;
+        Ok(insurer)
+    }
+
+    /// Verifies the binder against its insurer's key and attaches it.
+    pub fn attach_binder(
+        &mut self,
+        property_id: &str,
+        input: BinderInput,
+    ) -> Result<InsuranceBinder> {
+        if input.coverage_end <= input.coverage_start {
+            return Err(anyhow::anyhow!("Coverage must end after it starts"));
+        }
+
+        let insurer = self
+            .insurers
+            .get(&input.insurer_id)
+            .filter(|i| i.active)
+            .ok_or_else(|| {
+                anyhow::anyhow!("Insurer {} is not registered or inactive", input.insurer_id)
+            })?;
+
+        let document_hash = input
+            .document_hash
+            .trim()
+            .trim_start_matches("0x")
+            .to_lowercase();
+        if hex::decode(&document_hash).ok().map(|h| h.len()) != Some(32) {
+            return Err(anyhow::anyhow!("Document hash must be 32 bytes of hex"));
+        }
+
+        let signature: [u8; 64] = hex::decode(input.signature.trim().trim_start_matches("0x"))?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Binder signature must be 64 bytes"))?;
+        let commitment = binder_commitment(
+            property_id,
+            &document_hash,
+            input.coverage_start,
+            input.coverage_end,
+        );
+        parse_signer_key(&insurer.public_key)?
+            .verify(&commitment, &Signature::from_bytes(&signature))
+            .map_err(|_| anyhow::anyhow!("Binder signature is not valid for insurer"))?;
+
+        if self
+            .binders
+            .values()
+            .any(|b| b.property_id == property_id && b.document_hash == document_hash)
+        {
+            return Err(anyhow::anyhow!(
+                "Binder {} is already attached to {}",
+                document_hash,
+                property_id
+            ));
+        }
+
+        self.next_binder_id += 1;
+        let binder = InsuranceBinder {
+            binder_id: self.next_binder_id,
+            property_id: property_id.to_string(),
+            insurer_id: input.insurer_id,
+            document_hash,
+            coverage_start: input.coverage_start,
+            coverage_end: input.coverage_end,
+            signature: hex::encode(signature),
+            attached_at: chrono::Utc::now().timestamp(),
+        };
+
+        self.binders.insert(binder.binder_id, binder.clone());
+        self.save()?;
+        Ok(binder)
+    }
+
+    pub fn binders(&self, property_id: &str) -> Vec<&InsuranceBinder> {
+        self.binders
+            .values()
+            .filter(|b| b.property_id == property_id)
+            .collect()
+    }
+
+    pub fn is_active(&self, binder: &InsuranceBinder, at: i64) -> bool {
+        binder.covers(at)
+            && self
+                .insurers
+                .get(&binder.insurer_id)
+                .map(|i| i.active)
+                .unwrap_or(false)
+    }
+
+    /// The property's active binder covering `at` the longest, if any.
+    pub fn active_binder(&self, property_id: &str, at: i64) -> Option<&InsuranceBinder> {
+        self.binders(property_id)
+            .into_iter()
+            .filter(|b| self.is_active(b, at))
+            .max_by_key(|b| b.coverage_end)
+    }
+
+    pub fn checklist(&self, escrow_account_id: &str) -> Option<&EscrowChecklist> {
+        self.checklists.get(escrow_account_id)
+    }
+
+    pub fn set_checklist(
+        &mut self,
+        escrow_account_id: &str,
+        input: ChecklistInput,
+    ) -> Result<EscrowChecklist> {
+        let mut require_documents = input.require_documents;
+        require_documents.sort();
+        require_documents.dedup();
+        let mut require_signatures = input.require_signatures;
+        require_signatures.sort();
+        require_signatures.dedup();
+
+        let checklist = self.checklist_entry(escrow_account_id);
+        checklist.require_insurance_binder = input.require_insurance_binder;
+        checklist.require_documents = require_documents;
+        checklist.require_signatures = require_signatures;
+        checklist.updated_at = chrono::Utc::now().timestamp();
+
+        let checklist = checklist.clone();
+        self.save()?;
+        Ok(checklist)
+    }
+
+    /// The escrow's checklist, created requiring nothing if it has none.
+    fn checklist_entry(&mut self, escrow_account_id: &str) -> &mut EscrowChecklist {
+        self.checklists
+            .entry(escrow_account_id.to_string())
+            .or_insert_with(|| {
+                EscrowChecklist::empty(escrow_account_id, chrono::Utc::now().timestamp())
+            })
+    }
+
+    /// Attaches a generated document's hash, replacing earlier versions of
+    /// its kind. Escrows without a checklist get one requiring nothing.
+    pub fn attach_document(
+        &mut self,
+        escrow_account_id: &str,
+        document: &ClosingDocument,
+    ) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let checklist = self.checklist_entry(escrow_account_id);
+        checklist.documents.insert(
+            document.kind,
+            AttachedDocument {
+                document_id: document.document_id,
+                version: document.version,
+                sha256: document.pdf_hash.clone(),
+                attached_at: now,
+            },
+        );
+        checklist.updated_at = now;
+        self.save()
+    }
+
+    /// Records the signature of a document. It counts while the document is
+    /// the latest version of its kind.
+    pub fn mark_signed(
+        &mut self,
+        escrow_account_id: &str,
+        kind: DocumentKind,
+        signed: SignedDocument,
+    ) -> Result<()> {
+        let checklist = self.checklist_entry(escrow_account_id);
+        checklist.updated_at = signed.signed_at;
+        checklist.signatures.insert(kind, signed);
+        self.save()
+    }
+
+    /// Fails unless every checklist item of the escrow holds at `at`.
+    pub fn check_release(
+        &self,
+        escrow_account_id: &str,
+        property_id: Option<&str>,
+        at: i64,
+    ) -> Result<(), ChecklistIncomplete> {
+        let Some(checklist) = self.checklist(escrow_account_id) else {
+            return Ok(());
+        };
+
+        if checklist.require_insurance_binder {
+            let insured = property_id
+                .map(|p| self.active_binder(p, at).is_some())
+                .unwrap_or(false);
+            if !insured {
+                return Err(ChecklistIncomplete(format!(
+                    "escrow {} requires an active insurance binder on {}",
+                    escrow_account_id,
+                    property_id.unwrap_or("its property")
+                )));
+            }
+        }
+
+        if let Some(missing) = checklist
+            .require_documents
+            .iter()
+            .find(|kind| !checklist.documents.contains_key(kind))
+        {
+            return Err(ChecklistIncomplete(format!(
+                "escrow {} requires a generated {}",
+                escrow_account_id,
+                missing.as_str()
+            )));
+        }
+
+        if let Some(unsigned) = checklist
+            .require_signatures
+            .iter()
+            .find(|kind| !checklist.is_signed(**kind))
+        {
+            return Err(ChecklistIncomplete(format!(
+                "escrow {} requires a signed {}",
+                escrow_account_id,
+                unsigned.as_str()
+            )));
+        }
+        Ok(())
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    /// Insurers, by ID.
+    ListInsurers {} -> Vec<Insurer>;
+    |client, _op| Ok(client.attachments.insurers().into_iter().cloned().collect());
+
+    RegisterInsurer {
+        input: InsurerInput,
+        api_key: Option<String>,
+    } -> Insurer;
+    |client, op| client.register_insurer(op.input, op.api_key.as_deref());
+
+    /// Voids the insurer's binders.
+    DeactivateInsurer {
+        insurer_id: u64,
+        api_key: Option<String>,
+    } -> Insurer;
+    |client, op| client.deactivate_insurer(op.insurer_id, op.api_key.as_deref());
+
+    AttachInsuranceBinder {
+        property_id: String,
+        input: BinderInput,
+    } -> serde_json::Value;
+    |client, op| client.attach_insurance_binder(&op.property_id, op.input);
+
+    ListInsuranceBinders {
+        property_id: String,
+    } -> serde_json::Value;
+    |client, op| client.list_insurance_binders(&op.property_id);
+
+    SetEscrowChecklist {
+        escrow_account_id: String,
+        input: ChecklistInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| {
+        client.set_escrow_checklist(&op.escrow_account_id, op.input, op.api_key.as_deref())
+    };
+
+    GetEscrowChecklist {
+        escrow_account_id: String,
+    } -> serde_json::Value;
+    |client, op| client.get_escrow_checklist(&op.escrow_account_id)
+}
+
+impl MidenClientWrapper {
+    /// Registers an insurer whose binders attachments accept. Needs an admin
+    /// key.
+    pub fn register_insurer(
+        &mut self,
+        input: InsurerInput,
+        api_key: Option<&str>,
+    ) -> Result<Insurer> {
+        self.admin_principal(api_key, "insurers")?;
+        let insurer = self.attachments.register_insurer(input)?;
+        tracing::info!(
+            "Registered insurer {} ({})",
+            insurer.insurer_id,
+            insurer.name
+        );
+        Ok(insurer)
+    }
+
+    /// Deactivates an insurer. Needs an admin key.
+    pub fn deactivate_insurer(
+        &mut self,
+        insurer_id: u64,
+        api_key: Option<&str>,
+    ) -> Result<Insurer> {
+        self.admin_principal(api_key, "insurers")?;
+        let insurer = self.attachments.deactivate_insurer(insurer_id)?;
+        tracing::info!("Deactivated insurer {}", insurer_id);
+        Ok(insurer)
+    }
+
+    /// Attaches an insurance binder; the insurer's signature is the
+    /// authorization.
+    pub fn attach_insurance_binder(
+        &mut self,
+        property_id: &str,
+        input: BinderInput,
+    ) -> Result<serde_json::Value> {
+        if !self.records.properties.contains_key(property_id) {
+            return Err(anyhow::anyhow!(
+                "Property {} has not been minted",
+                property_id
+            ));
+        }
+
+        let binder = self.attachments.attach_binder(property_id, input)?;
+        tracing::info!(
+            "Insurance binder {} attached to {} by insurer {}",
+            binder.binder_id,
+            property_id,
+            binder.insurer_id
+        );
+        Ok(self.binder_json(&binder))
+    }
+
+    pub fn list_insurance_binders(&self, property_id: &str) -> Result<serde_json::Value> {
+        let binders: Vec<_> = self
+            .attachments
+            .binders(property_id)
+            .into_iter()
+            .map(|b| self.binder_json(b))
+            .collect();
+        Ok(serde_json::json!(binders))
+    }
+
+    fn binder_json(&self, binder: &InsuranceBinder) -> serde_json::Value {
+        let now = chrono::Utc::now().timestamp();
+        let mut value = serde_json::json!(binder);
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert(
+                "active".into(),
+                serde_json::json!(self.attachments.is_active(binder, now)),
+            );
+        }
+        value
+    }
+
+    /// Sets an escrow's release checklist. Needs the buyer's or the closing
+    /// agent's API key.
+    pub fn set_escrow_checklist(
+        &mut self,
+        escrow_account_id: &str,
+        input: ChecklistInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let escrow_hex = escrow_account_id.to_lowercase();
+        let record = self
+            .records
+            .escrows
+            .get(&escrow_hex)
+            .ok_or_else(|| anyhow::anyhow!("Escrow {} not found in service records", escrow_hex))?
+            .clone();
+        if !self.is_closing_agent(api_key, &escrow_hex)? {
+            self.authorize_account(api_key, &record.buyer_account_id, "buyer")?;
+        }
+
+        if !matches!(record.status, EscrowStatus::Created | EscrowStatus::Funded) {
+            return Err(ChecklistIncomplete(format!(
+                "escrow {} is already {:?}",
+                escrow_hex, record.status
+            ))
+            .into());
+        }
+        if input.require_insurance_binder && record.property_id.is_none() {
+            return Err(anyhow::anyhow!(
+                "Escrow {} was not opened for a property; it cannot require an insurance binder",
+                escrow_hex
+            ));
+        }
+
+        self.attachments.set_checklist(&escrow_hex, input)?;
+        tracing::info!("Checklist updated for escrow {}", escrow_hex);
+        self.get_escrow_checklist(&escrow_hex)
+    }
+
+    /// The escrow's checklist with the current state of each item.
+    pub fn get_escrow_checklist(&self, escrow_account_id: &str) -> Result<serde_json::Value> {
+        let escrow_hex = escrow_account_id.to_lowercase();
+        let record =
+            self.records.escrows.get(&escrow_hex).ok_or_else(|| {
+                anyhow::anyhow!("Escrow {} not found in service records", escrow_hex)
+            })?;
+        let now = chrono::Utc::now().timestamp();
+
+        let checklist = self.attachments.checklist(&escrow_hex);
+        let required = checklist
+            .map(|c| c.require_insurance_binder)
+            .unwrap_or(false);
+        let binder = record
+            .property_id
+            .as_deref()
+            .and_then(|p| self.attachments.active_binder(p, now));
+
+        let mut items = vec![serde_json::json!({
+            "item": "insurance_binder",
+            "required": required,
+            "satisfied": binder.is_some(),
+            "binder_id": binder.map(|b| b.binder_id),
+        })];
+        for kind in DocumentKind::ALL {
+            let required = checklist
+                .map(|c| c.require_documents.contains(&kind))
+                .unwrap_or(false);
+            let attached = checklist.and_then(|c| c.documents.get(&kind));
+            if required || attached.is_some() {
+                items.push(serde_json::json!({
+                    "item": kind.as_str(),
+                    "required": required,
+                    "satisfied": attached.is_some(),
+                    "document_id": attached.map(|d| d.document_id),
+                    "document_hash": attached.map(|d| &d.sha256),
+                }));
+            }
+
+            let required = checklist
+                .map(|c| c.require_signatures.contains(&kind))
+                .unwrap_or(false);
+            let signed = checklist.and_then(|c| c.signatures.get(&kind));
+            if required || signed.is_some() {
+                items.push(serde_json::json!({
+                    "item": format!("{}_signature", kind.as_str()),
+                    "required": required,
+                    "satisfied": checklist.map(|c| c.is_signed(kind)).unwrap_or(false),
+                    "envelope_id": signed.map(|s| s.envelope_id),
+                    "document_id": signed.map(|s| s.document_id),
+                }));
+            }
+        }
+
+        Ok(serde_json::json!({
+            "escrow_account_id": escrow_hex,
+            "property_id": record.property_id,
+            "items": items,
+            "complete": self
+                .attachments
+                .check_release(&escrow_hex, record.property_id.as_deref(), now)
+                .is_ok(),
+        }))
+    }
+}
@@ -0,0 +1,767 @@
+// src/auctions.rs
+//
+// Property auctions
+//
+// A seller opens a timed auction for a property they own. Each bid locks its
+// amount in an escrow of its own (bidder -> seller, tied to the property),
+// funded with a P2ID note of exactly that amount of the listing's settlement
+// token (settlement_tokens.rs). A bid must meet the reserve price and beat the
+// leading bid by at least the minimum increment; the bid it displaces is
+// refunded straight away.
+//
+// The scheduler (scheduler.rs) closes auctions past their end time. The highest
+// bid wins: once the seller's vault is checked to still hold the property, the
+// winning escrow is released to the seller (subject to liens, liens.rs) and the
+// property is then transferred from the seller to the winner. Refunds and
+// settlement steps that fail are retried on later ticks; settlement steps
+// record their tx IDs, so none of them runs twice.
+//
+// Every auction keeps its event history. Events are also published on an
+// in-process feed once persisted, which GET /auctions/:id/events streams to
+// clients as server-sent events.
+//
+// Bids are escrow creations and fundings and are authorized as such (escrow.rs):
+// the API key must belong to the bidder. Opening an auction needs the seller's
+// key (or an arbiter's).
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+use tokio::sync::broadcast;
+
+use crate::{
+    account_id_to_hex,
+    escrow::{EscrowAction, EscrowStatus},
+    read_cache::Touched,
+    MidenClientWrapper,
+};
+
+/// Upper bound on how long an auction may run (30 days).
+pub const MAX_AUCTION_DURATION_SECS: u64 = 30 * 86_400;
+
+/// Events buffered per feed subscriber before it starts missing some.
+pub const AUCTION_FEED_CAPACITY: usize = 256;
+
+fn default_min_increment() -> u64 {
+    1
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuctionInput {
+    pub property_id: String,
+    /// Account name or hex AccountId; must own the property
+    pub seller_account_id: String,
+    /// Lowest acceptable first bid
+    pub reserve_price: u64,
+    /// How much a bid must beat the leading bid by
+    #[serde(default = "default_min_increment")]
+    pub min_increment: u64,
+    pub duration_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BidInput {
+    /// Account name or hex AccountId
+    pub bidder_account_id: String,
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuctionStatus {
+    Open,
+    /// Closed with a winner; title transfer and payout pending
+    Settling,
+    Sold,
+    /// Closed without bids
+    Unsold,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BidStatus {
+    Leading,
+    /// Displaced by a higher bid; refund pending
+    Outbid,
+    Refunded,
+    Won,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bid {
+    pub bid_id: u32,
+    pub bidder_account_id: String,
+    pub amount: u64,
+    pub escrow_account_id: String,
+    pub fund_tx_id: String,
+    pub status: BidStatus,
+    pub placed_at: i64,
+    pub refund_tx_id: Option<String>,
+    /// Last refund error, cleared once the refund goes through
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuctionEventKind {
+    Created,
+    BidPlaced,
+    Outbid,
+    BidRefunded,
+    Closed,
+    TitleTransferred,
+    EscrowReleased,
+    Sold,
+    Unsold,
+    SettlementFailed,
+}
+
+impl AuctionEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuctionEventKind::Created => "created",
+            AuctionEventKind::BidPlaced => "bid_placed",
+            AuctionEventKind::Outbid => "outbid",
+            AuctionEventKind::BidRefunded => "bid_refunded",
+            AuctionEventKind::Closed => "closed",
+            AuctionEventKind::TitleTransferred => "title_transferred",
+            AuctionEventKind::EscrowReleased => "escrow_released",
+            AuctionEventKind::Sold => "sold",
+            AuctionEventKind::Unsold => "unsold",
+            AuctionEventKind::SettlementFailed => "settlement_failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuctionEvent {
+    pub auction_id: String,
+    /// Property auctioned; empty in events recorded before it was included
+    #[serde(default)]
+    pub property_id: String,
+    pub at: i64,
+    pub kind: AuctionEventKind,
+    pub bid_id: Option<u32>,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Auction {
+    pub auction_id: String,
+    pub property_id: String,
+    pub seller_account_id: String,
+    pub reserve_price: u64,
+    pub min_increment: u64,
+    pub starts_at: i64,
+    pub ends_at: i64,
+    pub status: AuctionStatus,
+    pub bids: Vec<Bid>,
+    pub winning_bid_id: Option<u32>,
+    pub title_transfer_tx_id: Option<String>,
+    pub settle_tx_id: Option<String>,
+    pub events: Vec<AuctionEvent>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Auction {
+    fn event(&mut self, kind: AuctionEventKind, bid_id: Option<u32>, detail: impl Into<String>) {
+        let now = chrono::Utc::now().timestamp();
+        let detail = detail.into();
+        tracing::info!("Auction {}: {:?} {}", self.auction_id, kind, detail);
+        self.events.push(AuctionEvent {
+            auction_id: self.auction_id.clone(),
+            property_id: self.property_id.clone(),
+            at: now,
+            kind,
+            bid_id,
+            detail,
+        });
+        self.updated_at = now;
+    }
+
+    /// Records a settlement failure, once per distinct error.
+    fn failure(&mut self, bid_id: Option<u32>, detail: String) {
+        let repeated = self.events.last().is_some_and(|last| {
+            last.kind == AuctionEventKind::SettlementFailed && last.detail == detail
+        });
+        if !repeated {
+            self.event(AuctionEventKind::SettlementFailed, bid_id, detail);
+        }
+    }
+
+    /// The bid currently winning (or that won).
+    pub fn leading(&self) -> Option<&Bid> {
+        self.bids
+            .iter()
+            .find(|b| matches!(b.status, BidStatus::Leading | BidStatus::Won))
+    }
+
+    /// Smallest amount the next bid must offer.
+    pub fn minimum_bid(&self) -> u64 {
+        self.leading()
+            .map(|b| b.amount.saturating_add(self.min_increment))
+            .unwrap_or(self.reserve_price)
+    }
+
+    fn has_pending_refunds(&self) -> bool {
+        self.bids.iter().any(|b| b.status == BidStatus::Outbid)
+    }
+
+    /// Auction with its bids and event history.
+    pub fn report(&self) -> serde_json::Value {
+        let mut report = self.summary();
+        if let Some(obj) = report.as_object_mut() {
+            obj.insert("bids".into(), serde_json::json!(self.bids));
+            obj.insert("events".into(), serde_json::json!(self.events));
+        }
+        report
+    }
+
+    /// Terms and live state only.
+    pub fn summary(&self) -> serde_json::Value {
+        let now = chrono::Utc::now().timestamp();
+        let open = self.status == AuctionStatus::Open;
+
+        serde_json::json!({
+            "auction_id": self.auction_id,
+            "property_id": self.property_id,
+            "seller_account_id": self.seller_account_id,
+            "status": self.status,
+            "reserve_price": self.reserve_price,
+            "min_increment": self.min_increment,
+            "highest_bid": self.leading().map(|b| serde_json::json!({
+                "bid_id": b.bid_id,
+                "bidder_account_id": b.bidder_account_id,
+                "amount": b.amount,
+            })),
+            "minimum_bid": open.then(|| self.minimum_bid()),
+            "bid_count": self.bids.len(),
+            "starts_at": self.starts_at,
+            "ends_at": self.ends_at,
+            "remaining_secs": open.then(|| (self.ends_at - now).max(0)),
+            "winning_bid_id": self.winning_bid_id,
+            "title_transfer_tx_id": self.title_transfer_tx_id,
+            "settle_tx_id": self.settle_tx_id,
+            "created_at": self.created_at,
+            "updated_at": self.updated_at,
+        })
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AuctionStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    auctions: BTreeMap<String, Auction>,
+    #[serde(default)]
+    next_auction_id: u64,
+    #[serde(skip)]
+    feed: Option<broadcast::Sender<AuctionEvent>>,
+    /// Events per auction already published on the feed
+    #[serde(skip)]
+    published: HashMap<String, usize>,
+}
+
+impl AuctionStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<AuctionStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            AuctionStore::default()
+        };
+        store.path = path;
+        store.published = store
+            .auctions
+            .values()
+            .map(|a| (a.auction_id.clone(), a.events.len()))
+            .collect();
+
+        Ok(store)
+    }
+
+    /// Writes the store, then publishes the events it now holds durably.
+    pub fn save(&mut self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        self.publish();
+        Ok(())
+    }
+
+    /// Persists, logging instead of failing: a bid or refund that reached the
+    /// chain must not be repeated because the auction file could not be written.
+    pub fn persist(&mut self) {
+        if let Err(e) = self.save() {
+            tracing::warn!("Failed to persist auctions: {}", e);
+        }
+    }
+
+    /// Sends new events to feed subscribers (see main.rs, GET /auctions/:id/events).
+    pub fn attach_feed(&mut self, feed: broadcast::Sender<AuctionEvent>) {
+        self.feed = Some(feed);
+    }
+
+    fn publish(&mut self) {
+        for auction in self.auctions.values() {
+            let sent = self
+                .published
+                .entry(auction.auction_id.clone())
+                .or_default();
+            if let Some(feed) = &self.feed {
+                for event in &auction.events[(*sent).min(auction.events.len())..] {
+                    // No subscribers is not an error
+                    let _ = feed.send(event.clone());
+                }
+            }
+            *sent = auction.events.len();
+        }
+    }
+
+    pub fn next_id(&mut self) -> String {
+        self.next_auction_id += 1;
+        format!("auction-{}", self.next_auction_id)
+    }
+
+    pub fn insert(&mut self, auction: Auction) -> Result<()> {
+        self.auctions.insert(auction.auction_id.clone(), auction);
+        self.save()
+    }
+
+    pub fn get(&self, auction_id: &str) -> Result<&Auction> {
+        self.auctions
+            .get(auction_id)
+            .ok_or_else(|| anyhow::anyhow!("Auction {} not found", auction_id))
+    }
+
+    pub fn get_mut(&mut self, auction_id: &str) -> Result<&mut Auction> {
+        self.auctions
+            .get_mut(auction_id)
+            .ok_or_else(|| anyhow::anyhow!("Auction {} not found", auction_id))
+    }
+
+    pub fn list(&self) -> Vec<&Auction> {
+        self.auctions.values().collect()
+    }
+
+    /// True if the property is up for auction or its sale is being settled.
+    pub fn has_open_auction(&self, property_id: &str) -> bool {
+        self.auctions.values().any(|a| {
+            a.property_id == property_id
+                && matches!(a.status, AuctionStatus::Open | AuctionStatus::Settling)
+        })
+    }
+
+    /// Auctions the scheduler has to look at: open or settling ones, and any
+    /// with refunds still outstanding.
+    pub fn scheduled(&self) -> Vec<String> {
+        self.auctions
+            .values()
+            .filter(|a| {
+                matches!(a.status, AuctionStatus::Open | AuctionStatus::Settling)
+                    || a.has_pending_refunds()
+            })
+            .map(|a| a.auction_id.clone())
+            .collect()
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    CreateAuction {
+        input: AuctionInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.create_auction(op.input, op.api_key.as_deref());
+
+    PlaceBid {
+        auction_id: String,
+        input: BidInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.place_bid(&op.auction_id, op.input, op.api_key.as_deref()).await;
+
+    GetAuction {
+        auction_id: String,
+    } -> serde_json::Value;
+    |client, op| client.get_auction(&op.auction_id);
+
+    ListAuctions {} -> serde_json::Value;
+    |client, _op| client.list_auctions()
+}
+
+impl MidenClientWrapper {
+    /// Publishes auction events on `feed` from now on.
+    pub fn attach_auction_feed(&mut self, feed: broadcast::Sender<AuctionEvent>) {
+        self.auctions.attach_feed(feed);
+    }
+
+    /// Opens an auction for a property. Returns the auction report.
+    pub fn create_auction(
+        &mut self,
+        input: AuctionInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let owner = self
+            .records
+            .properties
+            .get(&input.property_id)
+            .map(|p| p.owner_account_id.clone())
+            .ok_or_else(|| anyhow::anyhow!("Property {} has not been minted", input.property_id))?;
+
+        let seller_hex = self.account_hex(&input.seller_account_id)?;
+        if !owner.eq_ignore_ascii_case(&seller_hex) {
+            return Err(anyhow::anyhow!(
+                "Seller {} does not own property {}",
+                input.seller_account_id,
+                input.property_id
+            ));
+        }
+        self.authorize_account(api_key, &seller_hex, "seller")?;
+
+        if self.auctions.has_open_auction(&input.property_id) {
+            return Err(anyhow::anyhow!(
+                "Property {} is already up for auction",
+                input.property_id
+            ));
+        }
+        if self.installments.has_open_plan(&input.property_id) {
+            return Err(anyhow::anyhow!(
+                "Property {} is being sold under an installment plan",
+                input.property_id
+            ));
+        }
+        if self.listing_under_contract(&input.property_id) {
+            return Err(anyhow::anyhow!(
+                "Property {} is under contract from an accepted offer",
+                input.property_id
+            ));
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let mut auction = Auction {
+            auction_id: self.auctions.next_id(),
+            property_id: input.property_id,
+            seller_account_id: seller_hex,
+            reserve_price: input.reserve_price,
+            min_increment: input.min_increment,
+            starts_at: now,
+            ends_at: now + input.duration_secs as i64,
+            status: AuctionStatus::Open,
+            bids: Vec::new(),
+            winning_bid_id: None,
+            title_transfer_tx_id: None,
+            settle_tx_id: None,
+            events: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        };
+        auction.event(
+            AuctionEventKind::Created,
+            None,
+            format!(
+                "reserve {}, increment {}, closes at {}",
+                auction.reserve_price, auction.min_increment, auction.ends_at
+            ),
+        );
+
+        let report = auction.report();
+        self.auctions.insert(auction)?;
+        Ok(report)
+    }
+
+    /// Places a bid: opens an escrow for it, funds it from the bidder and
+    /// refunds the bid it displaces. Returns the auction report.
+    pub async fn place_bid(
+        &mut self,
+        auction_id: &str,
+        input: BidInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let bidder_hex = self.account_hex(&input.bidder_account_id)?;
+        let (property_id, seller_hex) = {
+            let auction = self.auctions.get(auction_id)?;
+            let now = chrono::Utc::now().timestamp();
+            if auction.status != AuctionStatus::Open || now >= auction.ends_at {
+                return Err(anyhow::anyhow!("Auction {} is closed", auction_id));
+            }
+            if bidder_hex.eq_ignore_ascii_case(&auction.seller_account_id) {
+                return Err(anyhow::anyhow!(
+                    "The seller cannot bid on their own auction"
+                ));
+            }
+            let minimum = auction.minimum_bid();
+            if input.amount < minimum {
+                return Err(anyhow::anyhow!(
+                    "Bid of {} is below the minimum of {}",
+                    input.amount,
+                    minimum
+                ));
+            }
+            (
+                auction.property_id.clone(),
+                auction.seller_account_id.clone(),
+            )
+        };
+
+        let escrow = self
+            .create_escrow(
+                &bidder_hex,
+                &seller_hex,
+                input.amount,
+                Some(&property_id),
+                api_key,
+            )
+            .await?;
+        let escrow = self.authorize_escrow(api_key, EscrowAction::Fund, &escrow)?;
+        let escrow_hex = account_id_to_hex(escrow.escrow_account_id);
+        let faucet = self.escrow_settlement_faucet(&escrow_hex)?;
+
+        let op_id = self.records.begin_operation("place_bid", auction_id);
+        let result = self
+            .submit_asset_payment(
+                escrow.buyer_account_id,
+                escrow.escrow_account_id,
+                faucet,
+                input.amount,
+            )
+            .await;
+        let tx_id = result.as_ref().ok().cloned();
+        self.records.finish_operation(op_id, &result, tx_id.clone());
+        let tx_id = result?;
+
+        self.records
+            .update_escrow(&escrow_hex, EscrowStatus::Funded, Some(tx_id.clone()));
+
+        let auction = self.auctions.get_mut(auction_id)?;
+        let bid_id = auction.bids.len() as u32 + 1;
+        if let Some(previous) = auction
+            .bids
+            .iter_mut()
+            .find(|b| b.status == BidStatus::Leading)
+        {
+            previous.status = BidStatus::Outbid;
+            let detail = format!("{} by {}", previous.amount, previous.bidder_account_id);
+            let previous_id = previous.bid_id;
+            auction.event(AuctionEventKind::Outbid, Some(previous_id), detail);
+        }
+        auction.bids.push(Bid {
+            bid_id,
+            bidder_account_id: bidder_hex,
+            amount: input.amount,
+            escrow_account_id: escrow_hex,
+            fund_tx_id: tx_id.clone(),
+            status: BidStatus::Leading,
+            placed_at: chrono::Utc::now().timestamp(),
+            refund_tx_id: None,
+            error: None,
+        });
+        auction.event(
+            AuctionEventKind::BidPlaced,
+            Some(bid_id),
+            format!(
+                "{} by {} (tx {})",
+                input.amount, input.bidder_account_id, tx_id
+            ),
+        );
+        self.auctions.persist();
+
+        self.refund_outbid_bids(auction_id).await;
+
+        Ok(self.auctions.get(auction_id)?.report())
+    }
+
+    /// Refunds every outbid bid whose escrow has not been returned yet. Failures
+    /// are recorded on the bid and retried by the next tick.
+    async fn refund_outbid_bids(&mut self, auction_id: &str) {
+        let Ok(auction) = self.auctions.get(auction_id) else {
+            return;
+        };
+        let pending: Vec<(u32, String)> = auction
+            .bids
+            .iter()
+            .filter(|b| b.status == BidStatus::Outbid)
+            .map(|b| (b.bid_id, b.escrow_account_id.clone()))
+            .collect();
+
+        for (bid_id, escrow_hex) in pending {
+            let result = match self.recorded_escrow(&escrow_hex) {
+                Ok(escrow) => self.refund_escrow_unchecked(&escrow).await,
+                Err(e) => Err(e),
+            };
+
+            let Ok(auction) = self.auctions.get_mut(auction_id) else {
+                return;
+            };
+            let Some(bid) = auction.bids.iter_mut().find(|b| b.bid_id == bid_id) else {
+                continue;
+            };
+            match result {
+                Ok(tx_id) => {
+                    bid.status = BidStatus::Refunded;
+                    bid.refund_tx_id = Some(tx_id.clone());
+                    bid.error = None;
+                    let detail =
+                        format!("{} to {} (tx {})", bid.amount, bid.bidder_account_id, tx_id);
+                    auction.event(AuctionEventKind::BidRefunded, Some(bid_id), detail);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Auction {}: refund of bid {} failed: {}",
+                        auction_id,
+                        bid_id,
+                        e
+                    );
+                    bid.error = Some(e.to_string());
+                    auction.failure(Some(bid_id), format!("refund of bid {}: {}", bid_id, e));
+                }
+            }
+        }
+
+        self.auctions.persist();
+    }
+
+    /// Closes an open auction past its end time: the leading bid wins, or the
+    /// auction ends unsold.
+    fn close_auction(&mut self, auction_id: &str, now: i64) -> Result<()> {
+        let auction = self.auctions.get_mut(auction_id)?;
+        if auction.status != AuctionStatus::Open || now < auction.ends_at {
+            return Ok(());
+        }
+
+        let winner = auction
+            .bids
+            .iter_mut()
+            .find(|b| b.status == BidStatus::Leading);
+        match winner {
+            Some(bid) => {
+                bid.status = BidStatus::Won;
+                let (bid_id, detail) = (
+                    bid.bid_id,
+                    format!("won by {} with {}", bid.bidder_account_id, bid.amount),
+                );
+                auction.winning_bid_id = Some(bid_id);
+                auction.status = AuctionStatus::Settling;
+                auction.event(AuctionEventKind::Closed, Some(bid_id), detail);
+            }
+            None => {
+                auction.status = AuctionStatus::Unsold;
+                auction.event(AuctionEventKind::Unsold, None, "closed without bids");
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the outstanding settlement steps of a closed auction: winning
+    /// escrow to the seller, then title to the winner. The release only runs
+    /// once the title is known to be transferable.
+    async fn settle_auction(&mut self, auction_id: &str) -> Result<()> {
+        let auction = self.auctions.get(auction_id)?.clone();
+        if auction.status != AuctionStatus::Settling {
+            return Ok(());
+        }
+        let winner = auction
+            .leading()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Auction {} has no winning bid", auction_id))?;
+
+        if auction.settle_tx_id.is_none() {
+            if auction.title_transfer_tx_id.is_none() {
+                self.check_property_transferable(&auction.property_id, &auction.seller_account_id)
+                    .await?;
+            }
+            let escrow = self.recorded_escrow(&winner.escrow_account_id)?;
+            let tx_id = self.release_escrow_unchecked(&escrow).await?;
+            let auction = self.auctions.get_mut(auction_id)?;
+            auction.settle_tx_id = Some(tx_id.clone());
+            auction.event(
+                AuctionEventKind::EscrowReleased,
+                Some(winner.bid_id),
+                format!(
+                    "{} to seller {} (tx {})",
+                    winner.amount, auction.seller_account_id, tx_id
+                ),
+            );
+            self.auctions.persist();
+        }
+
+        if auction.title_transfer_tx_id.is_none() {
+            let tx_id = self
+                .transfer_property(&auction.property_id, &winner.bidder_account_id)
+                .await?;
+            let auction = self.auctions.get_mut(auction_id)?;
+            auction.title_transfer_tx_id = Some(tx_id.clone());
+            auction.event(
+                AuctionEventKind::TitleTransferred,
+                Some(winner.bid_id),
+                format!(
+                    "{} to {} (tx {})",
+                    auction.property_id, winner.bidder_account_id, tx_id
+                ),
+            );
+        }
+
+        let auction = self.auctions.get_mut(auction_id)?;
+        auction.status = AuctionStatus::Sold;
+        auction.event(
+            AuctionEventKind::Sold,
+            Some(winner.bid_id),
+            format!("to {} for {}", winner.bidder_account_id, winner.amount),
+        );
+        Ok(())
+    }
+
+    /// Scheduler job: closes auctions past their end time, settles closed ones
+    /// and retries outstanding refunds. Returns the number of auctions that
+    /// changed.
+    pub async fn run_auction_tick(&mut self) -> Result<usize> {
+        let now = chrono::Utc::now().timestamp();
+        let mut changed = 0;
+
+        for auction_id in self.auctions.scheduled() {
+            let events_before = self.auctions.get(&auction_id)?.events.len();
+
+            self.close_auction(&auction_id, now)?;
+            if let Err(e) = self.settle_auction(&auction_id).await {
+                tracing::warn!("Auction {} settlement failed: {}", auction_id, e);
+                self.auctions
+                    .get_mut(&auction_id)?
+                    .failure(None, e.to_string());
+            }
+            if self.auctions.get(&auction_id)?.has_pending_refunds() {
+                self.refund_outbid_bids(&auction_id).await;
+            }
+
+            if self.auctions.get(&auction_id)?.events.len() != events_before {
+                changed += 1;
+            }
+        }
+
+        if changed > 0 {
+            self.auctions.persist();
+        }
+        Ok(changed)
+    }
+
+    pub fn get_auction(&self, auction_id: &str) -> Result<serde_json::Value> {
+        Ok(self.auctions.get(auction_id)?.report())
+    }
+
+    pub fn list_auctions(&self) -> Result<serde_json::Value> {
+        let auctions: Vec<_> = self.auctions.list().iter().map(|a| a.summary()).collect();
+        Ok(serde_json::json!(auctions))
+    }
+}
@@ -0,0 +1,276 @@
+// src/balance_history.rs
+//
+// Balances at past blocks
+//
+// GET /accounts/:account_id/balance?at_block=N returns the fungible balances an
+// account (name or hex AccountId) held at block N, e.g. for "balance at end of
+// month" statements; without at_block it returns them at the latest indexed
+// block.
+//
+// Balances are reconstructed from an index of balance changes kept in SQLite
+// (BALANCE_HISTORY_PATH). Every sync (sync_deltas.rs) compares the balances of
+// the tracked accounts with the last ones indexed and stores those that moved,
+// at the sync's block; the first sync also stores the balances it started
+// from. The balance of a faucet at block N is then the last one stored at or
+// before N.
+//
+// The index only knows what syncs saw:
+// - a change is dated to the block the sync that saw it reached, not the block
+//   the transaction landed in, so around a change the history is as precise as
+//   syncs are frequent
+// - blocks before the first indexed sync, or past the last, are refused
+// - balances of accounts that stopped being tracked stay as last seen
+
+use anyhow::Result;
+use miden_client::{account::AccountId, asset::FungibleAsset};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::{collections::BTreeMap, path::Path};
+
+use crate::{currency::TokenAmount, sync_deltas::TrackedState, MidenClientWrapper};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS balance_changes (
+        account_id TEXT NOT NULL,
+        faucet_id TEXT NOT NULL,
+        block_num INTEGER NOT NULL,
+        amount INTEGER NOT NULL,
+        recorded_at INTEGER NOT NULL,
+        PRIMARY KEY (account_id, faucet_id, block_num)
+    );
+    CREATE TABLE IF NOT EXISTS indexed_range (
+        id INTEGER PRIMARY KEY CHECK (id = 0),
+        from_block INTEGER NOT NULL,
+        to_block INTEGER NOT NULL
+    );
+";
+
+/// Blocks the index covers.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct IndexedRange {
+    pub from_block: u32,
+    pub to_block: u32,
+}
+
+/// Balance changes seen by syncs.
+pub struct BalanceHistory {
+    db: Connection,
+    range: Option<IndexedRange>,
+    /// Last indexed balance per (account, faucet)
+    latest: BTreeMap<(String, String), u64>,
+}
+
+impl BalanceHistory {
+    pub fn open(path: &Path) -> Result<Self> {
+        let db = Connection::open(path)
+            .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", path.display(), e))?;
+        db.execute_batch(SCHEMA)?;
+
+        let range = db
+            .query_row(
+                "SELECT from_block, to_block FROM indexed_range WHERE id = 0",
+                [],
+                |row| {
+                    Ok(IndexedRange {
+                        from_block: row.get(0)?,
+                        to_block: row.get(1)?,
+                    })
+                },
+            )
+            .optional()?;
+
+        let mut latest = BTreeMap::new();
+        {
+            let mut stmt = db.prepare(
+                "SELECT account_id, faucet_id, amount FROM balance_changes b
+                 WHERE block_num = (SELECT MAX(block_num) FROM balance_changes
+                     WHERE account_id = b.account_id AND faucet_id = b.faucet_id)",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok(((row.get(0)?, row.get(1)?), row.get::<_, i64>(2)? as u64))
+            })?;
+            for row in rows {
+                let (key, amount) = row?;
+                latest.insert(key, amount);
+            }
+        }
+
+        Ok(Self { db, range, latest })
+    }
+
+    pub fn range(&self) -> Option<IndexedRange> {
+        self.range
+    }
+
+    /// Balances of `state` that differ from `latest`, a faucet an account
+    /// no longer holds as 0.
+    fn changes(
+        latest: &BTreeMap<(String, String), u64>,
+        state: &TrackedState,
+    ) -> Vec<((String, String), u64)> {
+        let mut changes: Vec<_> = state
+            .balances()
+            .filter(|(key, amount)| latest.get(key).copied().unwrap_or(0) != *amount)
+            .collect();
+        changes.extend(
+            latest
+                .iter()
+                .filter(|((account_id, faucet_id), amount)| {
+                    **amount > 0
+                        && state.is_tracked(account_id)
+                        && state.balance(account_id, faucet_id).is_none()
+                })
+                .map(|(key, _)| (key.clone(), 0)),
+        );
+        changes
+    }
+
+    /// Indexes the balance changes of a sync from `before` to `after`.
+    pub fn record(&mut self, before: &TrackedState, after: &TrackedState) -> Result<usize> {
+        let mut latest = self.latest.clone();
+        let mut staged = Vec::new();
+        let from_block = match self.range {
+            Some(range) => range.from_block,
+            None => {
+                // First sync: the balances it started from are the baseline
+                for (key, amount) in Self::changes(&latest, before) {
+                    staged.push((key.clone(), before.block_num, amount));
+                    latest.insert(key, amount);
+                }
+                before.block_num
+            }
+        };
+        for (key, amount) in Self::changes(&latest, after) {
+            staged.push((key.clone(), after.block_num, amount));
+            latest.insert(key, amount);
+        }
+        let range = IndexedRange {
+            from_block,
+            to_block: after.block_num,
+        };
+
+        let now = chrono::Utc::now().timestamp();
+        let tx = self.db.transaction()?;
+        for ((account_id, faucet_id), block_num, amount) in &staged {
+            tx.execute(
+                "INSERT OR REPLACE INTO balance_changes
+                     (account_id, faucet_id, block_num, amount, recorded_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![account_id, faucet_id, block_num, *amount as i64, now],
+            )?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO indexed_range (id, from_block, to_block) VALUES (0, ?1, ?2)",
+            params![range.from_block, range.to_block],
+        )?;
+        tx.commit()?;
+
+        self.latest = latest;
+        self.range = Some(range);
+        Ok(staged.len())
+    }
+
+    /// Non-zero balances per faucet of an account at `block_num`.
+    pub fn balances_at(&self, account_id: &str, block_num: u32) -> Result<Vec<(String, u64)>> {
+        let range = self
+            .range
+            .ok_or_else(|| anyhow::anyhow!("No balance history indexed yet"))?;
+        if block_num < range.from_block {
+            return Err(anyhow::anyhow!(
+                "No balance history before block {}",
+                range.from_block
+            ));
+        }
+        if block_num > range.to_block {
+            return Err(anyhow::anyhow!(
+                "Block {} is past the last indexed block {}",
+                block_num,
+                range.to_block
+            ));
+        }
+
+        let mut stmt = self.db.prepare(
+            "SELECT faucet_id, amount FROM balance_changes b
+             WHERE account_id = ?1 AND block_num = (SELECT MAX(block_num) FROM balance_changes
+                 WHERE account_id = b.account_id AND faucet_id = b.faucet_id
+                     AND block_num <= ?2)
+               AND amount > 0
+             ORDER BY faucet_id",
+        )?;
+        let rows = stmt.query_map(params![account_id, block_num], |row| {
+            Ok((row.get(0)?, row.get::<_, i64>(1)? as u64))
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    GetBalanceAt {
+        account_id: String,
+        at_block: Option<u32>,
+    } -> serde_json::Value;
+    |client, op| client.balance_at(&op.account_id, op.at_block).await
+}
+
+impl MidenClientWrapper {
+    /// Indexes what a sync changed; a failure is logged, the sync stands.
+    pub(crate) fn index_balances(&mut self, before: &TrackedState, after: &TrackedState) {
+        match self.balance_history.record(before, after) {
+            Ok(0) => {}
+            Ok(changes) => tracing::debug!(
+                "Indexed {} balance change(s) at block {}",
+                changes,
+                after.block_num
+            ),
+            Err(e) => tracing::error!(
+                "Failed to index balances at block {}: {}",
+                after.block_num,
+                e
+            ),
+        }
+    }
+
+    /// Fungible balances of an account (name or hex AccountId) at a past
+    /// block, or at the latest indexed block without one.
+    pub async fn balance_at(
+        &mut self,
+        account: &str,
+        at_block: Option<u32>,
+    ) -> Result<serde_json::Value> {
+        let account_hex = self.account_hex(account)?;
+
+        // Index up to the current block first unless the block asked for is
+        // already covered
+        let covered = self
+            .balance_history
+            .range()
+            .zip(at_block)
+            .is_some_and(|(range, block)| block <= range.to_block);
+        if !covered {
+            self.sync_state().await?;
+        }
+
+        let range = self
+            .balance_history
+            .range()
+            .ok_or_else(|| anyhow::anyhow!("No balance history indexed yet"))?;
+        let block_num = at_block.unwrap_or(range.to_block);
+
+        let mut balances: Vec<TokenAmount> = Vec::new();
+        for (faucet_hex, amount) in self.balance_history.balances_at(&account_hex, block_num)? {
+            let asset = FungibleAsset::new(AccountId::from_hex(&faucet_hex)?, amount)?;
+            balances.push(self.token_amount(&asset));
+        }
+
+        Ok(serde_json::json!({
+            "account_id": account_hex,
+            "at_block": block_num,
+            "indexed": range,
+            "balances": balances,
+        }))
+    }
+}
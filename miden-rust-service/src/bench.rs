@@ -0,0 +1,261 @@
+// src/bench.rs
+//
+// Throughput benchmark through the command pipeline (localnet only)
+//
+// POST /admin/bench {mints, consumes, transfers, concurrency?} runs three
+// phases one after another — mints, then consumes, then transfers — and
+// reports each phase's throughput and latency percentiles. Within a phase the
+// operations are sent as ordinary client commands, up to `concurrency` at a
+// time, so they queue behind each other (and behind live traffic) exactly as
+// HTTP requests do.
+//
+// Each operation is timed in two parts:
+// - queue_ms: from sending the command to the client task picking it up
+// - execution_ms: inside the client task; transaction execution, proving and
+//   submission, plus the propagation waits of the operation (proving dominates)
+//
+// Mints create real property records (IDs `<run_id>-<n>`, owned by alice);
+// consumes consume alice's notes; transfers send those properties to bob. The
+// endpoint is only routed under the localnet profile. benches/throughput.rs
+// drives it with a few standard scenarios.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use crate::{currency::PriceInput, read_cache::Touched, MidenClientWrapper};
+
+/// Upper bound on operations in one run
+pub const MAX_BENCH_OPERATIONS: u32 = 1_000;
+/// Upper bound on commands in flight at once
+pub const MAX_BENCH_CONCURRENCY: usize = 64;
+const DEFAULT_BENCH_CONCURRENCY: usize = 8;
+/// Errors kept per phase in the report
+const MAX_REPORTED_ERRORS: usize = 5;
+
+const BENCH_IPFS_CID: &str = "bench";
+const BENCH_PRICE: PriceInput = PriceInput::Minor(1);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BenchInput {
+    #[serde(default)]
+    pub mints: u32,
+    #[serde(default)]
+    pub consumes: u32,
+    /// Transfers of properties minted by this run (at most `mints`)
+    #[serde(default)]
+    pub transfers: u32,
+    pub concurrency: Option<usize>,
+}
+
+impl BenchInput {
+    pub fn concurrency(&self) -> usize {
+        self.concurrency.unwrap_or(DEFAULT_BENCH_CONCURRENCY)
+    }
+
+    /// Operations per phase, in run order.
+    pub fn plan(&self, run_id: &str) -> Vec<(BenchPhase, Vec<BenchOp>)> {
+        let property_id = |n: u32| format!("{}-{}", run_id, n);
+        vec![
+            (
+                BenchPhase::Mint,
+                (0..self.mints)
+                    .map(|n| BenchOp::Mint {
+                        property_id: property_id(n),
+                    })
+                    .collect(),
+            ),
+            (
+                BenchPhase::Consume,
+                (0..self.consumes).map(|_| BenchOp::Consume).collect(),
+            ),
+            (
+                BenchPhase::Transfer,
+                (0..self.transfers)
+                    .map(|n| BenchOp::Transfer {
+                        property_id: property_id(n),
+                    })
+                    .collect(),
+            ),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BenchPhase {
+    Mint,
+    Consume,
+    Transfer,
+}
+
+#[derive(Debug, Clone)]
+pub enum BenchOp {
+    Mint { property_id: String },
+    Consume,
+    Transfer { property_id: String },
+}
+
+/// Timing of one operation (see module docs).
+#[derive(Debug, Clone)]
+pub struct BenchSample {
+    pub queue: Duration,
+    pub execution: Duration,
+    pub error: Option<String>,
+}
+
+impl BenchSample {
+    /// Sample for an operation sent at `enqueued_at` and picked up at `started`.
+    pub fn new(enqueued_at: Instant, started: Instant, result: Result<()>) -> Self {
+        Self {
+            queue: started.saturating_duration_since(enqueued_at),
+            execution: started.elapsed(),
+            error: result.err().map(|e| e.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Percentiles {
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl Percentiles {
+    /// Nearest-rank percentiles, in milliseconds.
+    pub fn of(mut durations: Vec<Duration>) -> Self {
+        durations.sort();
+        let rank = |p: usize| -> f64 {
+            if durations.is_empty() {
+                return 0.0;
+            }
+            let index = (p * durations.len()).div_ceil(100).max(1) - 1;
+            millis(durations[index])
+        };
+        Self {
+            p50: rank(50),
+            p90: rank(90),
+            p99: rank(99),
+            max: rank(100),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PhaseReport {
+    pub phase: BenchPhase,
+    pub operations: usize,
+    pub failed: usize,
+    pub elapsed_ms: f64,
+    pub throughput_per_sec: f64,
+    pub queue_ms: Percentiles,
+    pub execution_ms: Percentiles,
+    /// First few distinct errors
+    pub errors: Vec<String>,
+}
+
+impl PhaseReport {
+    pub fn new(phase: BenchPhase, samples: Vec<BenchSample>, elapsed: Duration) -> Self {
+        let mut errors: Vec<String> = Vec::new();
+        for error in samples.iter().filter_map(|s| s.error.as_ref()) {
+            if errors.len() < MAX_REPORTED_ERRORS && !errors.contains(error) {
+                errors.push(error.clone());
+            }
+        }
+
+        Self {
+            phase,
+            operations: samples.len(),
+            failed: samples.iter().filter(|s| s.error.is_some()).count(),
+            elapsed_ms: millis(elapsed),
+            throughput_per_sec: per_sec(samples.len(), elapsed),
+            queue_ms: Percentiles::of(samples.iter().map(|s| s.queue).collect()),
+            execution_ms: Percentiles::of(samples.iter().map(|s| s.execution).collect()),
+            errors,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    pub run_id: String,
+    pub concurrency: usize,
+    pub operations: usize,
+    pub failed: usize,
+    pub elapsed_ms: f64,
+    pub throughput_per_sec: f64,
+    pub phases: Vec<PhaseReport>,
+}
+
+impl BenchReport {
+    pub fn new(
+        run_id: String,
+        concurrency: usize,
+        phases: Vec<PhaseReport>,
+        elapsed: Duration,
+    ) -> Self {
+        let operations = phases.iter().map(|p| p.operations).sum();
+        Self {
+            run_id,
+            concurrency,
+            operations,
+            failed: phases.iter().map(|p| p.failed).sum(),
+            elapsed_ms: millis(elapsed),
+            throughput_per_sec: per_sec(operations, elapsed),
+            phases,
+        }
+    }
+}
+
+pub fn millis(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 1_000_000.0).round() / 1_000.0
+}
+
+fn per_sec(operations: usize, elapsed: Duration) -> f64 {
+    if elapsed.is_zero() {
+        return 0.0;
+    }
+    (operations as f64 / elapsed.as_secs_f64() * 1_000.0).round() / 1_000.0
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    RunBenchOp {
+        op: BenchOp,
+        enqueued_at: std::time::Instant,
+    } -> BenchSample;
+    touched: |_op| Some(Touched::Accounts(
+        ["alice", "bob", "faucet"].map(String::from).to_vec(),
+    ));
+    |client, bench| {
+        let started = std::time::Instant::now();
+        let result = client.run_bench_op(&bench.op).await;
+        Ok(BenchSample::new(bench.enqueued_at, started, result))
+    }
+}
+
+impl MidenClientWrapper {
+    /// Runs one benchmark operation. Failures are part of the measurement and
+    /// are not queued for retry.
+    pub async fn run_bench_op(&mut self, op: &BenchOp) -> Result<()> {
+        match op {
+            BenchOp::Mint { property_id } => {
+                self.mint_property_nft(property_id, "alice", BENCH_IPFS_CID, 0, &BENCH_PRICE)
+                    .await?;
+            }
+            BenchOp::Consume => {
+                self.consume_note("bench", Some("alice".to_string()))
+                    .await?;
+            }
+            BenchOp::Transfer { property_id } => {
+                self.transfer_property(property_id, "bob").await?;
+            }
+        }
+        Ok(())
+    }
+}
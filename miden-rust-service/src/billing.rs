@@ -0,0 +1,1039 @@
+// src/billing.rs
+//
+// Usage billing
+//
+// API keys are metered per calendar month (UTC):
+// - api_call: every API request made with the key
+// - mint, transfer, escrow: successful requests that submit a transaction
+//   (property mints and mint batches; property transfers, token sends,
+//   allowance transfers, installment and lease deposit payments; escrow
+//   creation, funding, release and refund)
+// Requests are metered by the route they matched, before the client queue
+// (see main.rs), so a request the client refuses still counts as an API call.
+// Usage is kept in memory and written to BILLING_USAGE_PATH by the billing job
+// every scheduler tick; a crash loses at most a tick of it. Keys that do not
+// authenticate are not billed.
+//
+// BILLING_FEES sets the fee schedule in PROP base units, e.g.
+// "monthly=100000,api_call=10,free_api_calls=1000,mint=50000,escrow=25000"; a
+// fee it does not name is 0. Without BILLING_FEES usage is metered but nothing
+// is invoiced.
+//
+// Once a month has ended, the billing job invoices its usage (BILLING_PATH):
+// one invoice per organization, covering the member keys (a key in several
+// organizations bills to the oldest), and one per key outside organizations.
+// The monthly fee and free API calls apply per invoice; an invoice with
+// nothing to charge is not issued.
+//
+// With BILLING_AUTO_SETTLE the job also pays open invoices: a P2ID payment of
+// the total in PROP from the billed account (the organization's first account,
+// or the key's first bound account, which must be in the client store) to the
+// treasury, entered in the treasury ledger as billing income. A failed payment
+// is retried on the next ticks, MAX_SETTLEMENT_ATTEMPTS times in all; after
+// that the invoice waits for POST /admin/billing/invoices/:invoice_id/settle.
+//
+// GET /billing/invoices lists the invoices the caller's key may see: its own
+// and those of the organizations it owns or manages; admin keys see all.
+// GET /billing/invoices/:invoice_id returns one as JSON, or as a PDF with
+// ?format=pdf (or Accept: application/pdf), dated in the ?tz= zone (see
+// timestamps.rs). GET /billing/usage shows the key's metered usage per month.
+//
+// Fee sponsorship (FEE_SPONSOR_AMOUNT) lets accounts without the network's
+// fee asset consume their first notes: before POST /consume-note consumes an
+// account's notes, the treasury sends it FEE_SPONSOR_AMOUNT of the fee asset
+// (FEE_SPONSOR_FAUCET) when its vault holds less, and that note is consumed
+// with the others, paying the transaction's fee. Each account gets
+// FEE_SPONSOR_QUOTA sponsored transactions per calendar month, counted here
+// with the amounts sent; once they are used up, or when the top-up fails, the
+// account pays its own fees. The service's treasury and faucet are never
+// sponsored. Top-ups are entered in the treasury ledger as sponsorship. GET
+// /billing/sponsorship?account_id= shows an account's use of its quota.
+
+use anyhow::Result;
+use chrono::TimeZone;
+use miden_client::account::AccountId;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    account_id_to_hex,
+    currency::{decimal_string, SERVICE_TOKEN_DECIMALS, SERVICE_TOKEN_SYMBOL},
+    documents::{ArtifactFormat, DocumentArtifact},
+    escrow::EscrowAuthError,
+    organizations::OrgRole,
+    pdf::text_pdf,
+    principals::{hash_key, Principal},
+    read_cache::Touched,
+    reconcile::parse_hex_account_id,
+    timestamps::DisplayZone,
+    treasury::{LedgerEntry, LedgerEntryKind},
+    MidenClientWrapper,
+};
+
+/// Payment attempts per invoice before auto-settlement gives up on it
+pub const MAX_SETTLEMENT_ATTEMPTS: u32 = 3;
+/// Keys metered per month; requests with further unknown keys are not counted
+const MAX_METERED_KEYS: usize = 10_000;
+/// Months of usage kept after they are invoiced
+const USAGE_RETENTION_MONTHS: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageKind {
+    ApiCall,
+    Mint,
+    Transfer,
+    Escrow,
+}
+
+/// Routes whose successful POSTs submit a transaction, by kind
+const ONCHAIN_ROUTES: &[(&str, UsageKind)] = &[
+    ("/mint-property", UsageKind::Mint),
+    ("/mint-batch", UsageKind::Mint),
+    ("/transfer-property", UsageKind::Transfer),
+    ("/send-tokens", UsageKind::Transfer),
+    ("/allowances/:allowance_id/transfers", UsageKind::Transfer),
+    ("/installments/:plan_id/pay", UsageKind::Transfer),
+    ("/leases/:lease_id/deposit", UsageKind::Transfer),
+    ("/swaps/quotes/:quote_id/execute", UsageKind::Transfer),
+    ("/create-escrow", UsageKind::Escrow),
+    ("/fund-escrow", UsageKind::Escrow),
+    ("/release-escrow", UsageKind::Escrow),
+    ("/refund-escrow", UsageKind::Escrow),
+];
+
+impl UsageKind {
+    pub const ALL: [UsageKind; 4] = [
+        UsageKind::ApiCall,
+        UsageKind::Mint,
+        UsageKind::Transfer,
+        UsageKind::Escrow,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsageKind::ApiCall => "api_call",
+            UsageKind::Mint => "mint",
+            UsageKind::Transfer => "transfer",
+            UsageKind::Escrow => "escrow",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            UsageKind::ApiCall => "API calls",
+            UsageKind::Mint => "Mints",
+            UsageKind::Transfer => "Transfers and payments",
+            UsageKind::Escrow => "Escrow operations",
+        }
+    }
+
+    /// The on-chain operation a successful request to `route`, a matched
+    /// route without its version prefix, submits.
+    pub fn for_operation(method: &str, route: &str) -> Option<UsageKind> {
+        if method != "POST" {
+            return None;
+        }
+        ONCHAIN_ROUTES
+            .iter()
+            .find(|(r, _)| *r == route)
+            .map(|(_, kind)| *kind)
+    }
+}
+
+impl FromStr for UsageKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        UsageKind::ALL
+            .into_iter()
+            .find(|k| k.as_str() == s.trim())
+            .ok_or_else(|| anyhow::anyhow!("Unknown usage kind: {}", s))
+    }
+}
+
+/// Billing month ("2026-09") containing `at`.
+pub fn period_of(at: i64) -> String {
+    chrono::Utc
+        .timestamp_opt(at, 0)
+        .single()
+        .unwrap_or_default()
+        .format("%Y-%m")
+        .to_string()
+}
+
+/// Metered operations per kind.
+pub type UsageCounts = BTreeMap<UsageKind, u64>;
+
+/// Fees in PROP base units.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FeeSchedule {
+    /// Charged once per invoice
+    pub monthly: u64,
+    /// API calls per invoice not charged for
+    pub free_api_calls: u64,
+    /// Fee per operation
+    pub fees: BTreeMap<UsageKind, u64>,
+}
+
+/// Parses BILLING_FEES: comma-separated `name=amount`, names being monthly,
+/// free_api_calls or a usage kind.
+pub fn parse_fee_schedule(list: &str) -> Result<FeeSchedule> {
+    let mut schedule = FeeSchedule::default();
+    for entry in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (name, amount) = entry
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("entry is not name=amount: {}", entry))?;
+        let amount: u64 = amount
+            .trim()
+            .parse()
+            .map_err(|e| anyhow::anyhow!("amount of {} is not a number: {}", name.trim(), e))?;
+        match name.trim() {
+            "monthly" => schedule.monthly = amount,
+            "free_api_calls" => schedule.free_api_calls = amount,
+            kind => {
+                schedule.fees.insert(kind.parse()?, amount);
+            }
+        }
+    }
+    Ok(schedule)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceLine {
+    pub description: String,
+    /// None for the monthly fee
+    pub kind: Option<UsageKind>,
+    pub quantity: u64,
+    /// Part of the quantity not charged for
+    pub free: u64,
+    pub unit_fee: u64,
+    pub amount: u64,
+}
+
+impl FeeSchedule {
+    /// Invoice lines for a month's usage.
+    pub fn lines(&self, usage: &UsageCounts) -> Vec<InvoiceLine> {
+        let mut lines = Vec::new();
+        if self.monthly > 0 {
+            lines.push(InvoiceLine {
+                description: "Monthly fee".to_string(),
+                kind: None,
+                quantity: 1,
+                free: 0,
+                unit_fee: self.monthly,
+                amount: self.monthly,
+            });
+        }
+        for (kind, quantity) in usage.iter().filter(|(_, q)| **q > 0) {
+            let free = match kind {
+                UsageKind::ApiCall => self.free_api_calls.min(*quantity),
+                _ => 0,
+            };
+            let unit_fee = self.fees.get(kind).copied().unwrap_or(0);
+            lines.push(InvoiceLine {
+                description: kind.description().to_string(),
+                kind: Some(*kind),
+                quantity: *quantity,
+                free,
+                unit_fee,
+                amount: (quantity - free).saturating_mul(unit_fee),
+            });
+        }
+        lines
+    }
+}
+
+/// Who an invoice is billed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BillTo {
+    Organization { org_id: u64 },
+    ApiKey { key_id: u64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvoiceStatus {
+    Open,
+    Paid,
+}
+
+/// Treasury top-ups covering the fees of users' transactions.
+#[derive(Debug, Clone, Serialize)]
+pub struct SponsorshipPolicy {
+    /// Faucet of the asset network fees are paid in (hex)
+    pub fee_faucet_id: String,
+    /// Sent per sponsored transaction; at least one transaction's fee
+    pub amount: u64,
+    /// Sponsored transactions per account and month
+    pub monthly_quota: u32,
+}
+
+/// Fees sponsored for an account in a month.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SponsoredFees {
+    pub transactions: u32,
+    /// Fee asset sent, in base units
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Settlement {
+    /// Account the payment came from
+    pub payer: String,
+    pub tx_id: String,
+    pub paid_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invoice {
+    pub invoice_id: u64,
+    /// Billing month, e.g. "2026-09"
+    pub period: String,
+    pub bill_to: BillTo,
+    /// Organization name or key label at issue
+    pub bill_to_name: String,
+    /// Keys whose usage is billed
+    pub key_ids: Vec<u64>,
+    pub lines: Vec<InvoiceLine>,
+    /// PROP base units
+    pub total: u64,
+    pub status: InvoiceStatus,
+    pub issued_at: i64,
+    pub settlement: Option<Settlement>,
+    #[serde(default)]
+    pub settlement_attempts: u32,
+    pub settlement_error: Option<String>,
+}
+
+impl Invoice {
+    /// Plain text of the invoice, as rendered in its PDF.
+    pub fn to_text(&self, zone: DisplayZone) -> String {
+        let prop = |amount: u64| {
+            format!(
+                "{} {}",
+                decimal_string(amount, SERVICE_TOKEN_DECIMALS),
+                SERVICE_TOKEN_SYMBOL
+            )
+        };
+        let issued = zone.date(self.issued_at);
+        let bill_to = match self.bill_to {
+            BillTo::Organization { org_id } => format!("organization {}", org_id),
+            BillTo::ApiKey { key_id } => format!("API key {}", key_id),
+        };
+
+        let mut text = format!(
+            "Invoice {} for {}\nIssued {}\nBilled to {} ({})\n\n",
+            self.invoice_id, self.period, issued, self.bill_to_name, bill_to
+        );
+        for line in &self.lines {
+            text.push_str(&format!(
+                "{}: {} x {}",
+                line.description,
+                line.quantity,
+                prop(line.unit_fee)
+            ));
+            if line.free > 0 {
+                text.push_str(&format!(" ({} free)", line.free));
+            }
+            text.push_str(&format!(" = {}\n", prop(line.amount)));
+        }
+        text.push_str(&format!("\nTotal: {}\n", prop(self.total)));
+        match &self.settlement {
+            Some(settlement) => text.push_str(&format!(
+                "Paid from {} in transaction {}\n",
+                settlement.payer, settlement.tx_id
+            )),
+            None => text.push_str("Open\n"),
+        }
+        text
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoredUsage {
+    /// Usage per month, then per key hash
+    #[serde(default)]
+    usage: BTreeMap<String, BTreeMap<String, UsageCounts>>,
+}
+
+#[derive(Debug, Default)]
+struct MeterState {
+    usage: BTreeMap<String, BTreeMap<String, UsageCounts>>,
+    /// Changed since the last flush
+    dirty: bool,
+}
+
+/// Usage per API key, shared by the metering middleware (see main.rs) and the
+/// client task. Keys are identified by the SHA-256 of the presented key, as
+/// principals.rs stores them.
+#[derive(Debug, Clone)]
+pub struct UsageMeter {
+    path: Arc<PathBuf>,
+    state: Arc<Mutex<MeterState>>,
+}
+
+impl UsageMeter {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let stored = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<StoredUsage>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            StoredUsage::default()
+        };
+
+        Ok(Self {
+            path: Arc::new(path),
+            state: Arc::new(Mutex::new(MeterState {
+                usage: stored.usage,
+                dirty: false,
+            })),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MeterState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Counts one operation of `kind` by the key hashing to `key_hash`.
+    pub fn record(&self, key_hash: &str, kind: UsageKind, at: i64) {
+        let mut state = self.lock();
+        let month = state.usage.entry(period_of(at)).or_default();
+        if month.len() >= MAX_METERED_KEYS && !month.contains_key(key_hash) {
+            return;
+        }
+        *month
+            .entry(key_hash.to_string())
+            .or_default()
+            .entry(kind)
+            .or_default() += 1;
+        state.dirty = true;
+    }
+
+    /// Writes the usage if it changed; true when it was written.
+    pub fn flush(&self) -> Result<bool> {
+        let mut state = self.lock();
+        if !state.dirty {
+            return Ok(false);
+        }
+        let stored = StoredUsage {
+            usage: state.usage.clone(),
+        };
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(&stored)?)?;
+        std::fs::rename(&tmp_path, self.path.as_ref())?;
+        state.dirty = false;
+        Ok(true)
+    }
+
+    /// Months with usage that ended before `current`.
+    pub fn closed_periods(&self, current: &str) -> Vec<String> {
+        self.lock()
+            .usage
+            .keys()
+            .filter(|p| p.as_str() < current)
+            .cloned()
+            .collect()
+    }
+
+    /// Usage per key hash in a month.
+    pub fn period_usage(&self, period: &str) -> BTreeMap<String, UsageCounts> {
+        self.lock().usage.get(period).cloned().unwrap_or_default()
+    }
+
+    /// Usage of one key per month.
+    pub fn key_usage(&self, key_hash: &str) -> BTreeMap<String, UsageCounts> {
+        self.lock()
+            .usage
+            .iter()
+            .filter_map(|(period, keys)| Some((period.clone(), keys.get(key_hash)?.clone())))
+            .collect()
+    }
+
+    /// Forgets months before the last `keep`.
+    fn retain_recent(&self, keep: usize) {
+        let mut state = self.lock();
+        let excess = state.usage.len().saturating_sub(keep);
+        let dropped: Vec<String> = state.usage.keys().take(excess).cloned().collect();
+        for period in dropped {
+            state.usage.remove(&period);
+            state.dirty = true;
+        }
+    }
+}
+
+/// An invoice to issue: who it bills, for which keys, and its lines.
+#[derive(Debug, Clone)]
+pub struct InvoiceDraft {
+    pub bill_to: BillTo,
+    pub bill_to_name: String,
+    pub key_ids: Vec<u64>,
+    pub lines: Vec<InvoiceLine>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct InvoiceStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    invoices: BTreeMap<u64, Invoice>,
+    #[serde(default)]
+    next_invoice_id: u64,
+    /// Months already invoiced
+    #[serde(default)]
+    invoiced_periods: BTreeSet<String>,
+    /// Sponsored fees per month and account
+    #[serde(default)]
+    sponsored: BTreeMap<String, BTreeMap<String, SponsoredFees>>,
+}
+
+impl InvoiceStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<InvoiceStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            InvoiceStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn is_invoiced(&self, period: &str) -> bool {
+        self.invoiced_periods.contains(period)
+    }
+
+    /// Issues the invoices of a month that charge something and marks the
+    /// month invoiced.
+    pub fn issue(
+        &mut self,
+        period: &str,
+        drafts: Vec<InvoiceDraft>,
+        at: i64,
+    ) -> Result<Vec<Invoice>> {
+        if self.is_invoiced(period) {
+            return Err(anyhow::anyhow!("Conflict: {} is already invoiced", period));
+        }
+
+        let mut issued = Vec::new();
+        for draft in drafts {
+            let total = draft
+                .lines
+                .iter()
+                .fold(0u64, |total, line| total.saturating_add(line.amount));
+            if total == 0 {
+                continue;
+            }
+            self.next_invoice_id += 1;
+            let invoice = Invoice {
+                invoice_id: self.next_invoice_id,
+                period: period.to_string(),
+                bill_to: draft.bill_to,
+                bill_to_name: draft.bill_to_name,
+                key_ids: draft.key_ids,
+                lines: draft.lines,
+                total,
+                status: InvoiceStatus::Open,
+                issued_at: at,
+                settlement: None,
+                settlement_attempts: 0,
+                settlement_error: None,
+            };
+            self.invoices.insert(invoice.invoice_id, invoice.clone());
+            issued.push(invoice);
+        }
+        self.invoiced_periods.insert(period.to_string());
+        self.save()?;
+        Ok(issued)
+    }
+
+    pub fn get(&self, invoice_id: u64) -> Result<&Invoice> {
+        self.invoices
+            .get(&invoice_id)
+            .ok_or_else(|| anyhow::anyhow!("Invoice {} not found", invoice_id))
+    }
+
+    /// Invoices `visible` keeps, newest first.
+    pub fn list(&self, visible: impl Fn(&Invoice) -> bool) -> Vec<&Invoice> {
+        self.invoices
+            .values()
+            .rev()
+            .filter(|i| visible(i))
+            .collect()
+    }
+
+    /// Open invoices auto-settlement has not given up on.
+    pub fn settleable(&self) -> Vec<u64> {
+        self.invoices
+            .values()
+            .filter(|i| {
+                i.status == InvoiceStatus::Open && i.settlement_attempts < MAX_SETTLEMENT_ATTEMPTS
+            })
+            .map(|i| i.invoice_id)
+            .collect()
+    }
+
+    pub fn mark_paid(&mut self, invoice_id: u64, settlement: Settlement) -> Result<Invoice> {
+        let invoice = self
+            .invoices
+            .get_mut(&invoice_id)
+            .ok_or_else(|| anyhow::anyhow!("Invoice {} not found", invoice_id))?;
+        invoice.settlement_attempts += 1;
+        invoice.settlement_error = None;
+        invoice.settlement = Some(settlement);
+        invoice.status = InvoiceStatus::Paid;
+        let invoice = invoice.clone();
+        self.save()?;
+        Ok(invoice)
+    }
+
+    pub fn record_failure(&mut self, invoice_id: u64, error: &str) -> Result<()> {
+        if let Some(invoice) = self.invoices.get_mut(&invoice_id) {
+            invoice.settlement_attempts += 1;
+            invoice.settlement_error = Some(error.to_string());
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    pub fn sponsored(&self, period: &str, account_hex: &str) -> SponsoredFees {
+        self.sponsored
+            .get(period)
+            .and_then(|accounts| accounts.get(account_hex))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn record_sponsorship(
+        &mut self,
+        period: &str,
+        account_hex: &str,
+        amount: u64,
+    ) -> Result<()> {
+        let fees = self
+            .sponsored
+            .entry(period.to_string())
+            .or_default()
+            .entry(account_hex.to_string())
+            .or_default();
+        fees.transactions += 1;
+        fees.amount = fees.amount.saturating_add(amount);
+        self.save()
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    ListInvoices {
+        api_key: Option<String>,
+    } -> Vec<Invoice>;
+    |client, op| client.list_invoices(op.api_key.as_deref());
+
+    GetInvoiceArtifact {
+        invoice_id: u64,
+        format: ArtifactFormat,
+        zone: DisplayZone,
+        api_key: Option<String>,
+    } -> DocumentArtifact;
+    |client, op| client.invoice_artifact(op.invoice_id, op.format, op.zone, op.api_key.as_deref());
+
+    GetBillingUsage {
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.billing_usage(op.api_key.as_deref());
+
+    GetFeeSponsorship {
+        account_id: String,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.fee_sponsorship(&op.account_id, op.api_key.as_deref());
+
+    SettleInvoice {
+        invoice_id: u64,
+        api_key: Option<String>,
+    } -> Invoice;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.settle_invoice_now(op.invoice_id, op.api_key.as_deref()).await
+}
+
+impl MidenClientWrapper {
+    /// Scheduled job: writes the metered usage, invoices the months that
+    /// ended and, with BILLING_AUTO_SETTLE, pays open invoices. Returns the
+    /// number of invoices issued and paid.
+    pub async fn run_billing(&mut self) -> Result<usize> {
+        self.usage_meter.flush()?;
+        let Some(fees) = self.config.billing_fees.clone() else {
+            return Ok(0);
+        };
+
+        let mut changed = 0;
+        let current = period_of(chrono::Utc::now().timestamp());
+        for period in self.usage_meter.closed_periods(&current) {
+            if self.invoices.is_invoiced(&period) {
+                continue;
+            }
+            let drafts = self.invoice_drafts(&period, &fees);
+            let issued = self
+                .invoices
+                .issue(&period, drafts, chrono::Utc::now().timestamp())?;
+            tracing::info!("🧾 Invoiced {}: {} invoice(s)", period, issued.len());
+            changed += issued.len();
+        }
+        self.usage_meter.retain_recent(USAGE_RETENTION_MONTHS);
+
+        if self.config.billing_auto_settle {
+            for invoice_id in self.invoices.settleable() {
+                match self.settle_invoice(invoice_id).await {
+                    Ok(_) => changed += 1,
+                    Err(e) => {
+                        tracing::warn!("Failed to settle invoice {}: {}", invoice_id, e);
+                        self.invoices.record_failure(invoice_id, &e.to_string())?;
+                    }
+                }
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// Groups a month's usage by who pays for it.
+    fn invoice_drafts(&self, period: &str, fees: &FeeSchedule) -> Vec<InvoiceDraft> {
+        let mut grouped: BTreeMap<BillTo, (String, Vec<u64>, UsageCounts)> = BTreeMap::new();
+        for (key_hash, counts) in self.usage_meter.period_usage(period) {
+            let Some(principal) = self.principals.find_by_key_hash(&key_hash) else {
+                continue;
+            };
+            let (bill_to, name) = match self.organizations.list(Some(principal.key_id)).first() {
+                Some(org) => (
+                    BillTo::Organization { org_id: org.org_id },
+                    org.name.clone(),
+                ),
+                None => (
+                    BillTo::ApiKey {
+                        key_id: principal.key_id,
+                    },
+                    principal.label.clone(),
+                ),
+            };
+            let (_, key_ids, usage) =
+                grouped
+                    .entry(bill_to)
+                    .or_insert((name, Vec::new(), Default::default()));
+            key_ids.push(principal.key_id);
+            for (kind, count) in counts {
+                *usage.entry(kind).or_default() += count;
+            }
+        }
+
+        grouped
+            .into_iter()
+            .map(|(bill_to, (bill_to_name, key_ids, usage))| InvoiceDraft {
+                bill_to,
+                bill_to_name,
+                key_ids,
+                lines: fees.lines(&usage),
+            })
+            .collect()
+    }
+
+    /// Pays an invoice in PROP from the billed account to the treasury.
+    async fn settle_invoice(&mut self, invoice_id: u64) -> Result<Invoice> {
+        let invoice = self.invoices.get(invoice_id)?.clone();
+        if invoice.status == InvoiceStatus::Paid {
+            return Err(anyhow::anyhow!(
+                "Conflict: invoice {} is already paid",
+                invoice_id
+            ));
+        }
+        let payer = match invoice.bill_to {
+            BillTo::Organization { org_id } => self.organizations.get(org_id)?.accounts.first(),
+            BillTo::ApiKey { key_id } => {
+                self.principals.get(key_id).and_then(|p| p.accounts.first())
+            }
+        }
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("No account to pay invoice {} from", invoice_id))?;
+        let payer_id = parse_hex_account_id(&payer)?;
+        let treasury = self.treasury_account_id()?;
+        let faucet = self.named_account("faucet")?;
+
+        let (tx_id, note_id) = self
+            .submit_payment_note(payer_id, treasury, faucet, invoice.total)
+            .await?;
+        self.records.expect_note(
+            &note_id,
+            &account_id_to_hex(treasury),
+            "invoice-payment",
+            Some(tx_id.clone()),
+        );
+        self.record_treasury_entry(LedgerEntry {
+            counterparty: Some(payer.clone()),
+            note_id: Some(note_id),
+            tx_id: Some(tx_id.clone()),
+            ..LedgerEntry::new(
+                LedgerEntryKind::Billing,
+                account_id_to_hex(faucet),
+                invoice.total,
+            )
+        });
+        tracing::info!(
+            "🧾 Invoice {} paid from {}. TX: {}",
+            invoice_id,
+            payer,
+            tx_id
+        );
+
+        self.invoices.mark_paid(
+            invoice_id,
+            Settlement {
+                payer,
+                tx_id,
+                paid_at: chrono::Utc::now().timestamp(),
+            },
+        )
+    }
+
+    /// The principal behind a required API key.
+    fn billing_principal(&self, api_key: Option<&str>) -> Result<&Principal> {
+        let key = api_key.ok_or_else(|| {
+            EscrowAuthError::Unauthenticated("X-API-Key header is required for billing".into())
+        })?;
+        Ok(self
+            .principals
+            .authenticate(key)
+            .ok_or_else(|| EscrowAuthError::Unauthenticated("Invalid or revoked API key".into()))?)
+    }
+
+    /// Whether `principal` may see an invoice: admins, the billed key, and
+    /// owners and managers of the billed organization.
+    fn may_see_invoice(&self, principal: &Principal, invoice: &Invoice) -> bool {
+        if principal.admin {
+            return true;
+        }
+        match invoice.bill_to {
+            BillTo::ApiKey { key_id } => key_id == principal.key_id,
+            BillTo::Organization { org_id } => self
+                .organizations
+                .get(org_id)
+                .ok()
+                .and_then(|org| org.role_of(principal.key_id))
+                .is_some_and(|role| role >= OrgRole::Manager),
+        }
+    }
+
+    pub fn list_invoices(&self, api_key: Option<&str>) -> Result<Vec<Invoice>> {
+        let principal = self.billing_principal(api_key)?;
+        Ok(self
+            .invoices
+            .list(|invoice| self.may_see_invoice(principal, invoice))
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+
+    pub fn get_invoice(&self, invoice_id: u64, api_key: Option<&str>) -> Result<Invoice> {
+        let principal = self.billing_principal(api_key)?;
+        let invoice = self.invoices.get(invoice_id)?;
+        if !self.may_see_invoice(principal, invoice) {
+            return Err(EscrowAuthError::Forbidden(format!(
+                "API key {} may not see invoice {}",
+                principal.key_id, invoice_id
+            ))
+            .into());
+        }
+        Ok(invoice.clone())
+    }
+
+    /// An invoice as JSON or PDF.
+    pub fn invoice_artifact(
+        &self,
+        invoice_id: u64,
+        format: ArtifactFormat,
+        zone: DisplayZone,
+        api_key: Option<&str>,
+    ) -> Result<DocumentArtifact> {
+        let invoice = self.get_invoice(invoice_id, api_key)?;
+        Ok(match format {
+            ArtifactFormat::Pdf => DocumentArtifact {
+                content_type: "application/pdf",
+                filename: format!("invoice-{}.pdf", invoice_id),
+                bytes: text_pdf(&format!("Invoice {}", invoice_id), &invoice.to_text(zone)),
+            },
+            ArtifactFormat::Json => DocumentArtifact {
+                content_type: "application/json",
+                filename: format!("invoice-{}.json", invoice_id),
+                bytes: serde_json::to_vec_pretty(&invoice)?,
+            },
+        })
+    }
+
+    /// Metered usage of the caller's key per month, with the fee schedule.
+    pub fn billing_usage(&self, api_key: Option<&str>) -> Result<serde_json::Value> {
+        let principal = self.billing_principal(api_key)?;
+        let key_hash = hash_key(api_key.unwrap_or_default());
+        Ok(serde_json::json!({
+            "key_id": principal.key_id,
+            "usage": self.usage_meter.key_usage(&key_hash),
+            "fees": self.config.billing_fees,
+        }))
+    }
+
+    /// Pays an open invoice now, whatever its failed attempts. Admin only.
+    pub async fn settle_invoice_now(
+        &mut self,
+        invoice_id: u64,
+        api_key: Option<&str>,
+    ) -> Result<Invoice> {
+        let key_id = self.admin_principal(api_key, "settling invoices")?.key_id;
+        tracing::info!(
+            "Invoice {} settlement requested by API key {}",
+            invoice_id,
+            key_id
+        );
+        match self.settle_invoice(invoice_id).await {
+            Ok(invoice) => Ok(invoice),
+            Err(e) => {
+                if self
+                    .invoices
+                    .get(invoice_id)
+                    .is_ok_and(|i| i.status == InvoiceStatus::Open)
+                {
+                    self.invoices.record_failure(invoice_id, &e.to_string())?;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Tops up the fee asset of an account about to consume its notes when
+    /// sponsorship is on, the account cannot pay the fee and has quota left.
+    /// Returns the top-up note's ID. Failures are logged; the transaction goes
+    /// ahead unsponsored.
+    pub(crate) async fn sponsor_fee(&mut self, account_id: AccountId) -> Option<String> {
+        let policy = self.config.fee_sponsorship.clone()?;
+        match self.submit_fee_sponsorship(account_id, &policy).await {
+            Ok(note_id) => note_id,
+            Err(e) => {
+                tracing::warn!("Could not sponsor the fee of {}: {}", account_id, e);
+                None
+            }
+        }
+    }
+
+    async fn submit_fee_sponsorship(
+        &mut self,
+        account_id: AccountId,
+        policy: &SponsorshipPolicy,
+    ) -> Result<Option<String>> {
+        let treasury = self.treasury_account_id()?;
+        if account_id == treasury || Some(account_id) == self.faucet_account_id {
+            return Ok(None);
+        }
+        let account_hex = account_id_to_hex(account_id);
+        let fee_faucet = parse_hex_account_id(&policy.fee_faucet_id)?;
+
+        self.sync_state().await?;
+        let balance = self
+            .client
+            .get_account(account_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Account {} not found", account_hex))?
+            .account()
+            .vault()
+            .get_balance(fee_faucet)
+            .unwrap_or(0);
+        if balance >= policy.amount {
+            return Ok(None);
+        }
+        let period = period_of(chrono::Utc::now().timestamp());
+        if self.invoices.sponsored(&period, &account_hex).transactions >= policy.monthly_quota {
+            tracing::info!(
+                "{} has used its {} sponsored transactions for {}",
+                account_hex,
+                policy.monthly_quota,
+                period
+            );
+            return Ok(None);
+        }
+
+        let (tx_id, note_id) = self
+            .submit_payment_note(treasury, account_id, fee_faucet, policy.amount)
+            .await?;
+        self.records.expect_note(
+            &note_id,
+            &account_hex,
+            "fee-sponsorship",
+            Some(tx_id.clone()),
+        );
+        self.record_treasury_entry(LedgerEntry {
+            counterparty: Some(account_hex.clone()),
+            note_id: Some(note_id.clone()),
+            tx_id: Some(tx_id.clone()),
+            ..LedgerEntry::new(
+                LedgerEntryKind::Sponsorship,
+                policy.fee_faucet_id.clone(),
+                policy.amount,
+            )
+        });
+        self.invoices
+            .record_sponsorship(&period, &account_hex, policy.amount)?;
+        tracing::info!(
+            "⛽ Sponsored the fee of {} with {}. TX: {}",
+            account_hex,
+            policy.amount,
+            tx_id
+        );
+
+        // The account can only consume the top-up once the node has it
+        self.wait_for_propagation().await;
+        Ok(Some(note_id))
+    }
+
+    /// An account's sponsored transactions this month and what is left of its
+    /// quota.
+    pub fn fee_sponsorship(
+        &self,
+        account_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let account_hex = self.account_hex(account_id.trim())?;
+        self.authorize_account(api_key, &account_hex, "account")?;
+        let period = period_of(chrono::Utc::now().timestamp());
+        let used = self.invoices.sponsored(&period, &account_hex);
+        let remaining = self
+            .config
+            .fee_sponsorship
+            .as_ref()
+            .map(|p| p.monthly_quota.saturating_sub(used.transactions));
+        Ok(serde_json::json!({
+            "account_id": account_hex,
+            "period": period,
+            "policy": self.config.fee_sponsorship,
+            "sponsored": used,
+            "remaining": remaining,
+        }))
+    }
+}
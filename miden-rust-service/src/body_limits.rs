@@ -0,0 +1,135 @@
+// src/body_limits.rs
+//
+// Request body size limits
+//
+// Every API request body is limited according to its route: BODY_LIMIT_BYTES
+// by default (1 MiB), with built-in limits for routes that need less or more:
+// - proof generation takes a few fields: 16 KiB
+// - proof verification takes a hex-encoded proof: 256 KiB
+// - mint batches and note file imports: 8 MiB and 1 MiB
+// - photo uploads: MEDIA_MAX_BYTES per file, see media.rs
+// BODY_LIMITS overrides any route ("/mint-batch=16777216,/notes/import=65536";
+// routes without the /api/vN prefix, sizes in bytes).
+//
+// A request declaring a larger Content-Length is refused with 413 before its
+// body is read. Otherwise the body is read through a limit: the handler's
+// extractor fails with 413 as soon as more bytes arrive than allowed, so at
+// most the limit is ever buffered whatever the client sends (or claims to
+// send). Photo uploads are also read part by part, so a file is refused as it
+// streams in (see read_photos in media.rs).
+
+use anyhow::Result;
+use axum::{
+    body::Body,
+    extract::{MatchedPath, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use http_body_util::Limited;
+use std::{collections::HashMap, sync::Arc};
+
+use crate::api_version;
+
+/// Built-in limits (see module docs)
+const BUILTIN_LIMITS: &[(&str, usize)] = &[
+    ("/generate-accreditation-proof", 16 << 10),
+    ("/generate-jurisdiction-proof", 16 << 10),
+    ("/generate-ownership-proof", 16 << 10),
+    ("/generate-identity-proof", 16 << 10),
+    ("/verify-accreditation-proof", 256 << 10),
+    ("/verify-jurisdiction-proof", 256 << 10),
+    ("/verify-ownership-proof", 256 << 10),
+    ("/verify-identity-proof", 256 << 10),
+    ("/mint-batch", 8 << 20),
+    ("/notes/import", 1 << 20),
+];
+
+/// Photo upload route, limited by the media policy
+const MEDIA_UPLOAD_ROUTE: &str = "/properties/:property_id/media";
+
+/// Body size limit per route (see module docs).
+#[derive(Debug, Clone)]
+pub struct BodyLimitPolicy {
+    pub default: usize,
+    pub overrides: HashMap<String, usize>,
+}
+
+impl BodyLimitPolicy {
+    /// Builds the policy from a default, the photo upload limit and a
+    /// "route=bytes,..." list.
+    pub fn parse(default: usize, media_upload: usize, overrides: &str) -> Result<Self> {
+        let mut policy = Self {
+            default,
+            overrides: BUILTIN_LIMITS
+                .iter()
+                .map(|(route, limit)| (route.to_string(), *limit))
+                .collect(),
+        };
+        policy
+            .overrides
+            .insert(MEDIA_UPLOAD_ROUTE.to_string(), media_upload);
+
+        for entry in overrides
+            .split(',')
+            .map(str::trim)
+            .filter(|e| !e.is_empty())
+        {
+            let (route, bytes) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Expected route=bytes, got {}", entry))?;
+            let route = route.trim();
+            if !route.starts_with('/') {
+                return Err(anyhow::anyhow!("Route must start with '/': {}", route));
+            }
+            let bytes = bytes
+                .trim()
+                .parse::<usize>()
+                .map_err(|e| anyhow::anyhow!("Invalid size for {}: {}", route, e))?;
+            policy.overrides.insert(route.to_string(), bytes);
+        }
+        Ok(policy)
+    }
+
+    /// Limit for a matched route (without the /api/vN prefix).
+    pub fn for_route(&self, route: &str) -> usize {
+        self.overrides.get(route).copied().unwrap_or(self.default)
+    }
+}
+
+fn too_large(limit: usize) -> Response {
+    (
+        StatusCode::PAYLOAD_TOO_LARGE,
+        Json(serde_json::json!({
+            "success": false,
+            "error": format!("Request body is larger than {} bytes", limit)
+        })),
+    )
+        .into_response()
+}
+
+/// Applies the route's body limit to an API request.
+pub async fn limit_body(
+    State(policy): State<Arc<BodyLimitPolicy>>,
+    matched_path: Option<MatchedPath>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let path = matched_path
+        .as_ref()
+        .map_or(req.uri().path(), |p| p.as_str());
+    let limit = policy.for_route(api_version::unversioned_path(path));
+
+    let declared = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if declared.is_some_and(|length| length > limit as u64) {
+        return too_large(limit);
+    }
+
+    next.run(req.map(|body| Body::new(Limited::new(body, limit))))
+        .await
+}
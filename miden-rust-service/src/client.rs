@@ -1,213 +1,191 @@
 // src/client.rs
 //
-// Core Miden Client Integration
+// Miden client adapter
 //
-// This module provides a minimal wrapper around the Miden Rust client.
-// It follows the official Miden tutorials and is designed as a foundation
-// for further account, asset, and transaction logic.
+// The service is written against one generation of the miden-client API
+// (MIDEN_CLIENT_VERSION). The calls whose shape changes between releases are
+// kept here, behind functions the rest of the crate uses:
+// - the client and keystore types
+// - building the client over an RPC endpoint, store and optional keystore
+//   (startup, RPC failover, read replicas)
+// - the accounts the service creates: wallets (alice, bob, escrow accounts)
+//   and the PROP faucet
+// - the transactions of its own flows: faucet mints and P2ID payments, with a
+//   random or a given serial number, and notes burning assets at their faucet
 //
-// Reference:
-// https://0xpolygonmiden.github.io/miden-docs/miden-tutorials/rust-client/counter_contract_tutorial.html
+// Moving to another miden-client release starts here; the compiler then points
+// at whatever else changed. The pre-0.10 API (TonicRpcClient,
+// StoreAuthenticator, a synchronous Client::new) is not supported.
 
-use anyhow::{Result, Context};
-use std::{sync::Arc, path::PathBuf};
+use anyhow::Result;
+use rand::prelude::StdRng;
+use std::sync::Arc;
 
 use miden_client::{
-    Client,
-    crypto::RpoRandomCoin,
-    rpc::{Endpoint, TonicRpcClient},
-    store::{sqlite_store::SqliteStore, StoreAuthenticator, Store},
-    Felt,
+    account::{
+        component::{BasicFungibleFaucet, BasicWallet},
+        Account, AccountBuilder, AccountId, AccountStorageMode, AccountType,
+    },
+    asset::{Asset, FungibleAsset, TokenSymbol},
+    builder::ClientBuilder,
+    crypto::rpo_falcon512::SecretKey,
+    keystore::FilesystemKeyStore,
+    note::{
+        build_p2id_recipient, create_p2id_note, Note, NoteAssets, NoteExecutionHint, NoteId,
+        NoteInputs, NoteMetadata, NoteRecipient, NoteTag, NoteType, WellKnownNote,
+    },
+    rpc::Endpoint,
+    store::Store,
+    transaction::{OutputNote, TransactionRequest, TransactionRequestBuilder},
+    Client, ClientRng, Felt, Word,
 };
-
-use rand::Rng;
-use tracing::{info, debug};
-
-/// Thin wrapper around the Miden Rust client.
-///
-/// Responsibilities:
-/// - Initialize RPC connection
-/// - Initialize local store
-/// - Seed cryptographic RNG
-/// - Provide sync and placeholder methods for future extensions
-pub struct MidenClientWrapper {
-    client: Client<RpoRandomCoin>,
-}
-
-impl MidenClientWrapper {
-    /// Initialize a new Miden client instance.
-    ///
-    /// This closely follows the official Miden Rust client tutorial:
-    /// - Creates RPC client
-    /// - Seeds randomness
-    /// - Initializes SQLite-backed store
-    /// - Wires up authenticator and execution options
-    pub async fn new() -> Result<Self> {
-        info!("Initializing Miden client");
-
-        // ---------------------------------------------------------------------
-        // RPC endpoint configuration
-        // ---------------------------------------------------------------------
-        let endpoint = Endpoint::new(
-            "https".to_string(),
-            "rpc.testnet.miden.io".to_string(),
-            Some(443),
-        );
-        let timeout_ms = 10_000;
-
-        // Build gRPC RPC client
-        let rpc_api = Box::new(TonicRpcClient::new(endpoint, timeout_ms));
-
-        // ---------------------------------------------------------------------
-        // Cryptographic RNG initialization
-        // ---------------------------------------------------------------------
-        let mut seed_rng = rand::thread_rng();
-        let coin_seed: [u64; 4] = seed_rng.gen();
-        let rng = RpoRandomCoin::new(coin_seed.map(Felt::new));
-
-        // ---------------------------------------------------------------------
-        // Local persistent store (SQLite)
-        // ---------------------------------------------------------------------
-        let store_path = PathBuf::from("./miden-store.sqlite3");
-        let store = SqliteStore::new(store_path)
-            .await
-            .context("Failed to create SQLite store")?;
-
-        // Wrap store as Arc<dyn Store> as required by Client::new
-        let store: Arc<dyn Store> = Arc::new(store);
-
-        // ---------------------------------------------------------------------
-        // Store-backed authenticator for transaction signing
-        // ---------------------------------------------------------------------
-        let authenticator = StoreAuthenticator::new_with_rng(
-            store.clone(),
-            rng.clone(),
-        );
-
-        // ---------------------------------------------------------------------
-        // Client initialization
-        // ---------------------------------------------------------------------
-        // NOTE:
-        // - Client::new() is synchronous (not async)
-        // - It returns Client<R> directly (not Result)
-        //
-        // Signature based on:
-        // https://github.com/0xMiden/miden-client/blob/main/crates/rust-client/src/lib.rs
-        let client = Client::new(
-            rpc_api,                 // RPC API client
-            rng,                     // Random number generator
-            store,                   // Storage backend
-            Arc::new(authenticator), // Transaction authenticator
-            Default::default(),      // Execution options
-        );
-
-        info!("Miden client initialized successfully");
-
-        Ok(Self { client })
-    }
-
-    /// Sync local client state with the Miden network.
-    ///
-    /// This should be called before any operation that depends on:
-    /// - account state
-    /// - notes
-    /// - assets
-    pub async fn sync(&mut self) -> Result<()> {
-        debug!("Syncing with Miden network");
-
-        self.client
-            .sync_state()
-            .await
-            .context("Failed to sync with network")?;
-
-        info!("Client synced with network");
-        Ok(())
+use miden_lib::account::auth::AuthRpoFalcon512;
+
+/// miden-client release the adapter is written against
+pub const MIDEN_CLIENT_VERSION: &str = "0.12";
+
+pub type ServiceKeyStore = FilesystemKeyStore<StdRng>;
+
+/// Concrete client type used throughout the wrapper
+pub type MidenClient = Client<ServiceKeyStore>;
+
+/// Builds a client over `endpoint` and an existing store. Without a keystore
+/// (read replicas) the client cannot sign transactions.
+pub async fn build_client(
+    endpoint: &Endpoint,
+    timeout_ms: u64,
+    store: Arc<dyn Store>,
+    keystore: Option<&ServiceKeyStore>,
+) -> Result<MidenClient> {
+    let mut builder = ClientBuilder::new()
+        .grpc_client(endpoint, Some(timeout_ms))
+        .store(store)
+        .in_debug_mode(true.into());
+    if let Some(keystore) = keystore {
+        builder = builder.authenticator(keystore.clone().into());
     }
+    Ok(builder.build().await?)
+}
 
-    /// Create a property NFT faucet account.
-    ///
-    /// This function is intentionally left unimplemented.
-    /// It should follow the official faucet deployment tutorial:
-    /// https://docs.miden.xyz/miden-tutorials/rust-client/create_deploy_tutorial
-    pub async fn create_property_faucet(&self) -> Result<String> {
-        info!("Creating property NFT faucet");
-
-        // Steps to implement:
-        // 1. Define faucet account code (MASM)
-        // 2. Create faucet account
-        // 3. Deploy faucet to network
-        // 4. Return faucet account ID
-
-        todo!("Implement faucet creation following official tutorial")
-    }
-
-    /// Mint a property NFT.
-    ///
-    /// This function is a placeholder and mirrors the official
-    /// mint / consume / create flow tutorial.
-    ///
-    /// Reference:
-    /// https://docs.miden.xyz/miden-tutorials/rust-client/mint_consume_create_tutorial
-    pub async fn mint_property_nft(
-        &mut self,
-        property_id: &str,
-        _owner_account_id: &str,
-        _ipfs_cid: &str,
-        _property_type: u8,
-        _price: u64,
-    ) -> Result<(String, String)> {
-        info!("Minting property NFT: {}", property_id);
-
-        // Ensure client is in sync before minting
-        self.sync().await?;
+/// Public wallet with updatable code, signed with `key_pair`.
+pub fn wallet_account(init_seed: [u8; 32], key_pair: &SecretKey) -> Result<Account> {
+    Ok(AccountBuilder::new(init_seed)
+        .account_type(AccountType::RegularAccountUpdatableCode)
+        .storage_mode(AccountStorageMode::Public)
+        .with_auth_component(AuthRpoFalcon512::new(key_pair.public_key().into()))
+        .with_component(BasicWallet)
+        .build()?)
+}
 
-        // Steps to implement:
-        // 1. Load faucet account
-        // 2. Create NFT asset with metadata
-        // 3. Mint NFT via faucet
-        // 4. Create note containing NFT
-        // 5. Submit transaction
-        // 6. Return (transaction_id, note_id)
+/// Public fungible faucet issuing `symbol`, signed with `key_pair`.
+pub fn faucet_account(
+    init_seed: [u8; 32],
+    key_pair: &SecretKey,
+    symbol: &str,
+    decimals: u8,
+    max_supply: u64,
+) -> Result<Account> {
+    let faucet =
+        BasicFungibleFaucet::new(TokenSymbol::new(symbol)?, decimals, Felt::new(max_supply))?;
+    Ok(AccountBuilder::new(init_seed)
+        .account_type(AccountType::FungibleFaucet)
+        .storage_mode(AccountStorageMode::Public)
+        .with_auth_component(AuthRpoFalcon512::new(key_pair.public_key().into()))
+        .with_component(faucet)
+        .build()?)
+}
 
-        todo!("Implement NFT minting following official tutorial")
-    }
+/// Transaction of `faucet` minting `amount` into a public note for `target`.
+pub fn mint_request(
+    faucet: AccountId,
+    target: AccountId,
+    amount: u64,
+    rng: &mut ClientRng,
+) -> Result<TransactionRequest> {
+    let asset = FungibleAsset::new(faucet, amount)?;
+    Ok(TransactionRequestBuilder::new().build_mint_fungible_asset(
+        asset,
+        target,
+        NoteType::Public,
+        rng,
+    )?)
+}
 
-    /// Retrieve account information.
-    ///
-    /// Placeholder implementation that can later be expanded to:
-    /// - fetch account metadata
-    /// - list vault assets
-    /// - return note counts
-    pub async fn get_account_info(&mut self) -> Result<serde_json::Value> {
-        // Sync before reading any state
-        self.sync().await?;
+/// Public P2ID note paying `assets` from `sender` to `target`.
+pub fn p2id_note(
+    sender: AccountId,
+    target: AccountId,
+    assets: Vec<Asset>,
+    rng: &mut ClientRng,
+) -> Result<Note> {
+    Ok(create_p2id_note(
+        sender,
+        target,
+        assets,
+        NoteType::Public,
+        Felt::new(0),
+        rng,
+    )?)
+}
 
-        Ok(serde_json::json!({
-            "status": "not_implemented",
-            "message": "Follow Miden tutorials to implement account queries"
-        }))
-    }
+/// Public P2ID note like `p2id_note`, with a given serial number instead of
+/// a random one, so its ID can be recomputed (contract_anchors.rs,
+/// property_registry.rs).
+pub fn p2id_note_with_serial(
+    sender: AccountId,
+    target: AccountId,
+    assets: Vec<Asset>,
+    serial_num: Word,
+) -> Result<Note> {
+    let recipient = build_p2id_recipient(target, serial_num)?;
+    let metadata = NoteMetadata::new(
+        sender,
+        NoteType::Public,
+        NoteTag::from_account_id(target),
+        NoteExecutionHint::always(),
+        Felt::new(0),
+    )?;
+    Ok(Note::new(NoteAssets::new(assets)?, metadata, recipient))
+}
 
-    /// Transfer a property NFT between accounts.
-    ///
-    /// This is a placeholder for the full transfer flow:
-    /// - consume existing note
-    /// - create new note for recipient
-    /// - submit transaction
-    pub async fn transfer_property(
-        &self,
-        _note_id: &str,
-        from_account: &str,
-        to_account: &str,
-    ) -> Result<String> {
-        info!("Transferring property from {} to {}", from_account, to_account);
+/// Public BURN note returning `assets` from `sender` to `faucet`, which burns
+/// them when it consumes the note (property_retirement.rs).
+pub fn burn_note(
+    sender: AccountId,
+    faucet: AccountId,
+    assets: Vec<Asset>,
+    serial_num: Word,
+) -> Result<Note> {
+    let recipient = NoteRecipient::new(
+        serial_num,
+        WellKnownNote::BURN.script(),
+        NoteInputs::new(Vec::new())?,
+    );
+    let metadata = NoteMetadata::new(
+        sender,
+        NoteType::Public,
+        NoteTag::from_account_id(faucet),
+        NoteExecutionHint::always(),
+        Felt::new(0),
+    )?;
+    Ok(Note::new(NoteAssets::new(assets)?, metadata, recipient))
+}
 
-        // Steps to implement:
-        // 1. Consume the existing NFT note
-        // 2. Create a new note addressed to the recipient
-        // 3. Submit the transaction
-        // 4. Return transaction ID
+/// Transaction creating `notes`.
+pub fn notes_request(notes: Vec<Note>) -> Result<TransactionRequest> {
+    Ok(TransactionRequestBuilder::new()
+        .own_output_notes(notes.into_iter().map(OutputNote::Full).collect::<Vec<_>>())
+        .build()?)
+}
 
-        todo!("Implement property transfer following official tutorial")
-    }
-}
\ No newline at end of file
+/// Transaction of `sender` paying `assets` to `target` with a single P2ID
+/// note, and the ID of that note.
+pub fn p2id_request(
+    sender: AccountId,
+    target: AccountId,
+    assets: Vec<Asset>,
+    rng: &mut ClientRng,
+) -> Result<(NoteId, TransactionRequest)> {
+    let note = p2id_note(sender, target, assets, rng)?;
+    Ok((note.id(), notes_request(vec![note])?))
+}
@@ -0,0 +1,147 @@
+// src/commands.rs
+//
+// Typed operations for the client task
+//
+// Every call into the Miden client runs on the client task, one at a time, and
+// reaches it over the command queue in main.rs as an operation. Each one is
+// declared once, next to the MidenClientWrapper method it calls, with the
+// `operations!` macro:
+//
+//   operations! {
+//       /// Pre-flight checks of an account component upgrade.
+//       UpgradeAccount {
+//           account_id: String,
+//           input: UpgradeInput,
+//           api_key: Option<String>,
+//       } -> UpgradeReport;
+//       |client, op| client.upgrade_account(&op.account_id, op.input, op.api_key.as_deref()).await
+//   }
+//
+// This declares the request struct and implements `Operation` for it: the
+// output type, the name used in logs and queue stats (queue_stats.rs), and how
+// the client task runs it. An operation that changes accounts adds
+// `touched: |op| ...` after its output type, returning the accounts whose
+// cached reads it invalidates (read_cache.rs).
+//
+// Handlers send the struct with `call` (routes/mod.rs) and get its typed result
+// back; background tasks use `call_with` or `CommandSender::dispatch` directly.
+// The queue carries it as a `Dispatch`, which erases the type and answers the
+// sender once the operation has run.
+
+use anyhow::Result;
+use std::{future::Future, pin::Pin};
+use tokio::sync::oneshot;
+
+use crate::{read_cache::Touched, MidenClientWrapper};
+
+/// A request the client task can run.
+pub trait Operation: Send + 'static {
+    type Output: Send + 'static;
+
+    /// Name in logs and queue stats
+    const NAME: &'static str;
+
+    /// Accounts whose cached reads the operation may change (None for reads).
+    fn touched(&self) -> Option<Touched> {
+        None
+    }
+
+    fn run(self, client: &mut MidenClientWrapper) -> impl Future<Output = Result<Self::Output>>;
+}
+
+type LocalBoxFuture<'a> = Pin<Box<dyn Future<Output = ()> + 'a>>;
+
+type RunFn = Box<dyn for<'a> FnOnce(&'a mut MidenClientWrapper) -> LocalBoxFuture<'a> + Send>;
+
+/// An operation on its way to the client task, with the channel its result
+/// goes back on.
+pub struct Dispatch {
+    name: &'static str,
+    touched: Option<Touched>,
+    run: RunFn,
+}
+
+impl std::fmt::Debug for Dispatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+impl Dispatch {
+    /// Wraps an operation; the receiver gets its result.
+    pub fn new<O: Operation>(op: O) -> (Self, oneshot::Receiver<Result<O::Output, String>>) {
+        let (tx, rx) = oneshot::channel();
+        let dispatch = Dispatch {
+            name: O::NAME,
+            touched: op.touched(),
+            run: Box::new(move |client| run_operation(op, tx, client)),
+        };
+        (dispatch, rx)
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn touched(&self) -> Option<Touched> {
+        self.touched.clone()
+    }
+
+    /// Runs the operation and sends its result to whoever waits for it.
+    pub async fn run(self, client: &mut MidenClientWrapper) {
+        tracing::info!("Processing {}", self.name);
+        (self.run)(client).await
+    }
+}
+
+fn run_operation<O: Operation>(
+    op: O,
+    tx: oneshot::Sender<Result<O::Output, String>>,
+    client: &mut MidenClientWrapper,
+) -> LocalBoxFuture<'_> {
+    Box::pin(async move {
+        let result = op.run(client).await.map_err(|e| e.to_string());
+        let _ = tx.send(result);
+    })
+}
+
+/// Declares operations: one request struct and `Operation` impl each (see
+/// module docs).
+#[macro_export]
+macro_rules! operations {
+    ($(
+        $(#[$meta:meta])*
+        $name:ident { $($(#[$field_meta:meta])* $field:ident : $ty:ty),* $(,)? } -> $output:ty;
+        $(touched: |$touched_op:ident| $touched:expr;)?
+        |$client:ident, $op:ident| $body:expr
+    );* $(;)?) => {
+        $(
+            $(#[$meta])*
+            #[derive(Debug)]
+            pub struct $name {
+                $($(#[$field_meta])* pub $field: $ty,)*
+            }
+
+            impl $crate::commands::Operation for $name {
+                type Output = $output;
+
+                const NAME: &'static str = stringify!($name);
+
+                $(
+                    fn touched(&self) -> Option<$crate::read_cache::Touched> {
+                        let $touched_op = self;
+                        $touched
+                    }
+                )?
+
+                async fn run(
+                    self,
+                    $client: &mut $crate::MidenClientWrapper,
+                ) -> anyhow::Result<$output> {
+                    let $op = self;
+                    $body
+                }
+            }
+        )*
+    };
+}
@@ -0,0 +1,663 @@
+// src/confidential_listings.rs
+//
+// Confidential listings and selective disclosure
+//
+// With CONFIDENTIAL_LISTINGS enabled, an owner can make the listing of their
+// property (negotiation.rs) confidential: GET /listings/:property_id then hides
+// the price, the seller and the document CID from everyone but the owner (or an
+// arbiter).
+//
+// A buyer who wants the details proves accreditation (a verified accreditation
+// proof meeting the listing's minimum threshold) and receives a disclosure
+// token scoped to that listing and buyer:
+//   GET /listings/:property_id?disclosure=<token>
+// returns the full listing while the token is valid (DISCLOSURE_TOKEN_TTL_SECS)
+// and not revoked. Only the token hash is stored. Offers on a confidential
+// listing need a disclosure grant for the buyer, and only the parties (or an
+// arbiter) may see them.
+//
+// Every grant, refusal, redemption and revocation is appended to the listing's
+// audit trail, which the owner can read.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{escrow::EscrowAuthError, negotiation::OfferStatus, MidenClientWrapper};
+
+const TOKEN_PREFIX: &str = "obd_";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ConfidentialListingInput {
+    /// Accreditation threshold a buyer's proof must meet (any verified proof
+    /// when absent)
+    #[serde(default)]
+    pub min_accreditation_threshold: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DisclosureRequestInput {
+    /// Account name or hex AccountId
+    pub buyer_account_id: String,
+    /// Accreditation proof, as returned by /generate-accreditation-proof
+    pub proof: String,
+    pub program_hash: String,
+    pub public_inputs: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfidentialListing {
+    pub property_id: String,
+    pub min_accreditation_threshold: u64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisclosureGrant {
+    pub grant_id: u64,
+    pub property_id: String,
+    pub buyer_account_id: String,
+    /// Accreditation rule and threshold the buyer proved
+    pub rule_id: u64,
+    pub threshold: u64,
+    pub issued_at: i64,
+    pub expires_at: i64,
+    pub revoked_at: Option<i64>,
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    token_hash: String,
+}
+
+impl DisclosureGrant {
+    pub fn is_active(&self, now: i64) -> bool {
+        self.revoked_at.is_none() && now < self.expires_at
+    }
+
+    /// Copy without the token hash, for API responses.
+    pub fn public(&self) -> DisclosureGrant {
+        DisclosureGrant {
+            token_hash: String::new(),
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    MadeConfidential,
+    MadePublic,
+    DisclosureGranted,
+    DisclosureDenied,
+    DisclosureRedeemed,
+    DisclosureRevoked,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub event_id: u64,
+    pub property_id: String,
+    pub action: AuditAction,
+    /// Account the event concerns (buyer for disclosures, owner otherwise)
+    pub account_id: Option<String>,
+    pub grant_id: Option<u64>,
+    pub detail: Option<String>,
+    pub at: i64,
+}
+
+/// How a caller may see a listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListingAccess {
+    /// Listing is not confidential
+    Public,
+    /// Confidential; details hidden
+    Redacted,
+    /// Confidential; unlocked with a disclosure token
+    Disclosed,
+    /// Confidential; caller is the owner or an arbiter
+    Owner,
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ConfidentialListingStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    listings: BTreeMap<String, ConfidentialListing>,
+    #[serde(default)]
+    grants: BTreeMap<u64, DisclosureGrant>,
+    #[serde(default)]
+    audit: Vec<AuditEvent>,
+    #[serde(default)]
+    next_grant_id: u64,
+    #[serde(default)]
+    next_event_id: u64,
+}
+
+impl ConfidentialListingStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<ConfidentialListingStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            ConfidentialListingStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Saves and logs (rather than propagates) a failure; used when recording
+    /// a read in the audit trail.
+    fn persist(&self) {
+        if let Err(e) = self.save() {
+            tracing::warn!("⚠️  Failed to persist confidential listings: {}", e);
+        }
+    }
+
+    fn log(
+        &mut self,
+        property_id: &str,
+        action: AuditAction,
+        account_id: Option<&str>,
+        grant_id: Option<u64>,
+        detail: Option<String>,
+    ) {
+        self.next_event_id += 1;
+        self.audit.push(AuditEvent {
+            event_id: self.next_event_id,
+            property_id: property_id.to_string(),
+            action,
+            account_id: account_id.map(str::to_lowercase),
+            grant_id,
+            detail,
+            at: chrono::Utc::now().timestamp(),
+        });
+    }
+
+    pub fn get(&self, property_id: &str) -> Option<&ConfidentialListing> {
+        self.listings.get(property_id)
+    }
+
+    pub fn is_confidential(&self, property_id: &str) -> bool {
+        self.listings.contains_key(property_id)
+    }
+
+    pub fn make_confidential(
+        &mut self,
+        property_id: &str,
+        owner_hex: &str,
+        min_accreditation_threshold: u64,
+    ) -> Result<ConfidentialListing> {
+        let listing = ConfidentialListing {
+            property_id: property_id.to_string(),
+            min_accreditation_threshold,
+            created_at: chrono::Utc::now().timestamp(),
+        };
+        let detail = format!("minimum accreditation {}", min_accreditation_threshold);
+        self.listings
+            .insert(property_id.to_string(), listing.clone());
+        self.log(
+            property_id,
+            AuditAction::MadeConfidential,
+            Some(owner_hex),
+            None,
+            Some(detail),
+        );
+        self.save()?;
+        Ok(listing)
+    }
+
+    /// Makes the listing public again; outstanding grants are revoked.
+    pub fn make_public(&mut self, property_id: &str, owner_hex: &str) -> Result<()> {
+        if self.listings.remove(property_id).is_none() {
+            return Err(anyhow::anyhow!(
+                "Listing {} is not confidential",
+                property_id
+            ));
+        }
+        let now = chrono::Utc::now().timestamp();
+        for grant in self
+            .grants
+            .values_mut()
+            .filter(|g| g.property_id == property_id && g.revoked_at.is_none())
+        {
+            grant.revoked_at = Some(now);
+        }
+        self.log(
+            property_id,
+            AuditAction::MadePublic,
+            Some(owner_hex),
+            None,
+            None,
+        );
+        self.save()
+    }
+
+    /// Issues a disclosure grant; returns it with the plaintext token.
+    pub fn grant(
+        &mut self,
+        property_id: &str,
+        buyer_hex: &str,
+        rule_id: u64,
+        threshold: u64,
+        ttl_secs: u64,
+    ) -> Result<(DisclosureGrant, String)> {
+        self.next_grant_id += 1;
+        let grant_id = self.next_grant_id;
+        let secret: [u8; 32] = rand::random();
+        let token = format!("{}{}_{}", TOKEN_PREFIX, grant_id, hex::encode(secret));
+
+        let now = chrono::Utc::now().timestamp();
+        let grant = DisclosureGrant {
+            grant_id,
+            property_id: property_id.to_string(),
+            buyer_account_id: buyer_hex.to_lowercase(),
+            rule_id,
+            threshold,
+            issued_at: now,
+            expires_at: now + ttl_secs as i64,
+            revoked_at: None,
+            token_hash: hash_token(&token),
+        };
+
+        self.grants.insert(grant_id, grant.clone());
+        self.log(
+            property_id,
+            AuditAction::DisclosureGranted,
+            Some(buyer_hex),
+            Some(grant_id),
+            Some(format!("accreditation rule {} ({})", rule_id, threshold)),
+        );
+        self.save()?;
+        Ok((grant.public(), token))
+    }
+
+    pub fn deny(&mut self, property_id: &str, buyer_hex: &str, reason: &str) {
+        self.log(
+            property_id,
+            AuditAction::DisclosureDenied,
+            Some(buyer_hex),
+            None,
+            Some(reason.to_string()),
+        );
+        self.persist();
+    }
+
+    pub fn revoke(&mut self, property_id: &str, grant_id: u64) -> Result<DisclosureGrant> {
+        let grant = self
+            .grants
+            .get_mut(&grant_id)
+            .filter(|g| g.property_id == property_id)
+            .ok_or_else(|| {
+                anyhow::anyhow!("Disclosure {} not found for {}", grant_id, property_id)
+            })?;
+        if grant.revoked_at.is_some() {
+            return Err(anyhow::anyhow!(
+                "Disclosure {} is already revoked",
+                grant_id
+            ));
+        }
+        grant.revoked_at = Some(chrono::Utc::now().timestamp());
+
+        let grant = grant.public();
+        self.log(
+            property_id,
+            AuditAction::DisclosureRevoked,
+            Some(&grant.buyer_account_id),
+            Some(grant_id),
+            None,
+        );
+        self.save()?;
+        Ok(grant)
+    }
+
+    /// Checks a presented token against the listing and records the attempt.
+    pub fn redeem(&mut self, property_id: &str, token: &str) -> Result<DisclosureGrant> {
+        let now = chrono::Utc::now().timestamp();
+        let grant = token
+            .strip_prefix(TOKEN_PREFIX)
+            .and_then(|rest| rest.split('_').next())
+            .and_then(|id| id.parse::<u64>().ok())
+            .and_then(|grant_id| self.grants.get(&grant_id))
+            .filter(|g| g.token_hash == hash_token(token))
+            .cloned();
+
+        let refusal = match &grant {
+            Some(g) if g.property_id != property_id => {
+                Some(format!("disclosure {} is for another listing", g.grant_id))
+            }
+            Some(g) if !g.is_active(now) => Some(format!(
+                "disclosure {} has expired or was revoked",
+                g.grant_id
+            )),
+            Some(_) => None,
+            None => Some("invalid disclosure token".to_string()),
+        };
+        let action = match refusal {
+            Some(_) => AuditAction::DisclosureDenied,
+            None => AuditAction::DisclosureRedeemed,
+        };
+
+        self.log(
+            property_id,
+            action,
+            grant.as_ref().map(|g| g.buyer_account_id.as_str()),
+            grant.as_ref().map(|g| g.grant_id),
+            refusal.clone(),
+        );
+        self.persist();
+
+        match (grant, refusal) {
+            (Some(g), None) => Ok(g.public()),
+            (_, reason) => Err(EscrowAuthError::Forbidden(reason.unwrap_or_default()).into()),
+        }
+    }
+
+    /// Whether the buyer holds an active grant on the listing.
+    pub fn has_active_grant(&self, property_id: &str, buyer_hex: &str) -> bool {
+        let now = chrono::Utc::now().timestamp();
+        self.grants.values().any(|g| {
+            g.property_id == property_id
+                && g.buyer_account_id.eq_ignore_ascii_case(buyer_hex)
+                && g.is_active(now)
+        })
+    }
+
+    pub fn grants(&self, property_id: &str) -> Vec<DisclosureGrant> {
+        self.grants
+            .values()
+            .filter(|g| g.property_id == property_id)
+            .map(DisclosureGrant::public)
+            .collect()
+    }
+
+    pub fn audit_trail(&self, property_id: &str) -> Vec<&AuditEvent> {
+        self.audit
+            .iter()
+            .filter(|e| e.property_id == property_id)
+            .collect()
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    GetListing {
+        property_id: String,
+        disclosure: Option<String>,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| {
+        client.get_listing(
+            &op.property_id,
+            op.disclosure.as_deref(),
+            op.api_key.as_deref(),
+        )
+    };
+
+    MakeListingConfidential {
+        property_id: String,
+        input: ConfidentialListingInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.make_listing_confidential(&op.property_id, op.input, op.api_key.as_deref());
+
+    MakeListingPublic {
+        property_id: String,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.make_listing_public(&op.property_id, op.api_key.as_deref());
+
+    RequestDisclosure {
+        property_id: String,
+        input: DisclosureRequestInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.request_disclosure(&op.property_id, op.input, op.api_key.as_deref()).await;
+
+    RevokeDisclosure {
+        property_id: String,
+        grant_id: u64,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.revoke_disclosure(&op.property_id, op.grant_id, op.api_key.as_deref());
+
+    ListDisclosures {
+        property_id: String,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.list_disclosures(&op.property_id, op.api_key.as_deref())
+}
+
+impl MidenClientWrapper {
+    fn listing_owner(&self, property_id: &str) -> Result<String> {
+        self.records
+            .properties
+            .get(property_id)
+            .map(|p| p.owner_account_id.to_lowercase())
+            .ok_or_else(|| anyhow::anyhow!("Listing {} not found", property_id))
+    }
+
+    /// Whether the API key belongs to the listing's owner or an arbiter.
+    pub(crate) fn is_listing_owner(&self, property_id: &str, api_key: Option<&str>) -> bool {
+        let Ok(owner_hex) = self.listing_owner(property_id) else {
+            return false;
+        };
+        api_key
+            .and_then(|key| self.principals.authenticate(key))
+            .map(|p| p.owns(&owner_hex) || p.arbiter)
+            .unwrap_or(false)
+    }
+
+    /// Whether the API key may act for `account_hex` (an arbiter may not).
+    pub(crate) fn is_key_for(&self, api_key: Option<&str>, account_hex: &str) -> bool {
+        api_key
+            .and_then(|key| self.principals.authenticate(key))
+            .map(|p| p.owns(account_hex))
+            .unwrap_or(false)
+    }
+
+    /// The listing as the caller may see it.
+    pub fn get_listing(
+        &mut self,
+        property_id: &str,
+        disclosure: Option<&str>,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let owner_hex = self.listing_owner(property_id)?;
+
+        let access = if !self.confidential_listings.is_confidential(property_id) {
+            ListingAccess::Public
+        } else if self.is_listing_owner(property_id, api_key) {
+            ListingAccess::Owner
+        } else if let Some(token) = disclosure {
+            self.confidential_listings.redeem(property_id, token)?;
+            ListingAccess::Disclosed
+        } else {
+            ListingAccess::Redacted
+        };
+
+        let property = &self.records.properties[property_id];
+        let open_offers = self
+            .offers
+            .for_listing(property_id)
+            .into_iter()
+            .filter(|o| o.status == OfferStatus::Open)
+            .count();
+        let revealed = access != ListingAccess::Redacted;
+
+        Ok(serde_json::json!({
+            "property_id": property.property_id,
+            "property_type": property.property_type,
+            "confidential": access != ListingAccess::Public,
+            "access": access,
+            "price": revealed.then_some(property.price),
+            "price_currency": revealed.then(|| self.price_currency(property)),
+            "settlement_token": self.listing_settlement_symbol(property_id),
+            "seller_account_id": revealed.then_some(owner_hex),
+            "ipfs_cid": revealed.then(|| property.ipfs_cid.clone()),
+            "details": self.listing_details.get(property_id),
+            "location": property.location,
+            "media": self.media.for_property(property_id),
+            "under_contract": self.listing_under_contract(property_id),
+            "open_offers": open_offers,
+            "min_accreditation_threshold": self
+                .confidential_listings
+                .get(property_id)
+                .map(|l| l.min_accreditation_threshold),
+        }))
+    }
+
+    /// Makes a listing confidential. Needs an API key bound to the owner.
+    pub fn make_listing_confidential(
+        &mut self,
+        property_id: &str,
+        input: ConfidentialListingInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        if !self.config.confidential_listings {
+            return Err(anyhow::anyhow!("Confidential listings are not enabled"));
+        }
+        let owner_hex = self.listing_owner(property_id)?;
+        self.authorize_account(api_key, &owner_hex, "owner")?;
+
+        let listing = self.confidential_listings.make_confidential(
+            property_id,
+            &owner_hex,
+            input.min_accreditation_threshold.unwrap_or(0),
+        )?;
+        self.index_property(property_id);
+        tracing::info!("🔒 Listing {} is now confidential", property_id);
+        Ok(serde_json::json!(listing))
+    }
+
+    /// Makes a confidential listing public. Needs an API key bound to the owner.
+    pub fn make_listing_public(
+        &mut self,
+        property_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let owner_hex = self.listing_owner(property_id)?;
+        self.authorize_account(api_key, &owner_hex, "owner")?;
+
+        self.confidential_listings
+            .make_public(property_id, &owner_hex)?;
+        self.index_property(property_id);
+        tracing::info!("🔓 Listing {} is now public", property_id);
+        self.get_listing(property_id, None, api_key)
+    }
+
+    /// Issues a disclosure token to a buyer whose accreditation proof verifies
+    /// and meets the listing's minimum. Needs an API key bound to the buyer.
+    pub async fn request_disclosure(
+        &mut self,
+        property_id: &str,
+        input: DisclosureRequestInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let owner_hex = self.listing_owner(property_id)?;
+        let min_threshold = self
+            .confidential_listings
+            .get(property_id)
+            .map(|l| l.min_accreditation_threshold)
+            .ok_or_else(|| anyhow::anyhow!("Listing {} is not confidential", property_id))?;
+
+        let buyer_hex = self.account_hex(&input.buyer_account_id)?;
+        if buyer_hex.eq_ignore_ascii_case(&owner_hex) {
+            return Err(anyhow::anyhow!(
+                "The owner does not need a disclosure for their own listing"
+            ));
+        }
+        self.authorize_account(api_key, &buyer_hex, "buyer")?;
+
+        let verification = self
+            .verify_accreditation_proof(&input.proof, &input.program_hash, input.public_inputs)
+            .await;
+        let refusal = match &verification {
+            Err(e) => Some(format!("proof could not be verified: {}", e)),
+            Ok(v) if v["valid"] != true => Some(format!(
+                "proof is not valid: {}",
+                v["message"].as_str().unwrap_or_default()
+            )),
+            Ok(v) if v["threshold"].as_u64().unwrap_or(0) < min_threshold => Some(format!(
+                "proven threshold {} is below the listing minimum {}",
+                v["threshold"], min_threshold
+            )),
+            Ok(_) => None,
+        };
+        if let Some(reason) = refusal {
+            self.confidential_listings
+                .deny(property_id, &buyer_hex, &reason);
+            return Err(EscrowAuthError::Forbidden(reason).into());
+        }
+
+        let verification = verification?;
+        let (grant, token) = self.confidential_listings.grant(
+            property_id,
+            &buyer_hex,
+            verification["rule_id"].as_u64().unwrap_or(0),
+            verification["threshold"].as_u64().unwrap_or(0),
+            self.config.disclosure_token_ttl.as_secs(),
+        )?;
+        tracing::info!(
+            "Disclosure {} on {} granted to {}",
+            grant.grant_id,
+            property_id,
+            buyer_hex
+        );
+
+        Ok(serde_json::json!({
+            "grant": grant,
+            "token": token,
+        }))
+    }
+
+    /// Revokes a disclosure. Needs an API key bound to the owner.
+    pub fn revoke_disclosure(
+        &mut self,
+        property_id: &str,
+        grant_id: u64,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let owner_hex = self.listing_owner(property_id)?;
+        self.authorize_account(api_key, &owner_hex, "owner")?;
+
+        let grant = self.confidential_listings.revoke(property_id, grant_id)?;
+        Ok(serde_json::json!(grant))
+    }
+
+    /// Disclosures and audit trail of a listing. Needs an API key bound to the
+    /// owner.
+    pub fn list_disclosures(
+        &self,
+        property_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let owner_hex = self.listing_owner(property_id)?;
+        self.authorize_account(api_key, &owner_hex, "owner")?;
+
+        Ok(serde_json::json!({
+            "listing": self.confidential_listings.get(property_id),
+            "grants": self.confidential_listings.grants(property_id),
+            "audit_trail": self.confidential_listings.audit_trail(property_id),
+        }))
+    }
+}
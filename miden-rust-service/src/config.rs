@@ -0,0 +1,1090 @@
+// src/config.rs
+//
+// Service configuration
+//
+// All settings come from environment variables (a .env file is loaded if present).
+// A profile selects sensible defaults for the target network; individual
+// variables override the profile defaults.
+//
+// Profiles:
+// - testnet  (default): public Miden testnet, long propagation waits
+// - localnet: local node (spawned or attached), short waits, all wallets funded
+//
+// Deterministic demo mode (stable account IDs across restarts) requires both
+// DEMO_MASTER_SEED and UNSAFE_DEMO_DETERMINISTIC_SEEDS=true. Production
+// deployments get recoverable accounts from a master secret instead
+// (MASTER_SECRET_KEY, see secrets.rs).
+
+use anyhow::Result;
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc, time::Duration};
+
+use crate::{
+    billing::{parse_fee_schedule, FeeSchedule, SponsorshipPolicy}, body_limits::BodyLimitPolicy,
+    currency::find_currency, custodial::OtpDelivery, deadlines::DeadlinePolicy,
+    escrow::ReleasePolicy,
+    escrow_yield::{YieldPolicy, YieldRecipient, YieldSplit},
+    esign::SignatureProvider,
+    feature_flags::{parse_feature_defaults, FeatureFlag}, field_encryption::MasterKey,
+    http_security::{CorsPolicy, FrameOptions, SecurityHeaders}, installments::DefaultPolicy,
+    jurisdiction_lists::parse_signer_key, media::{MediaBackend, MediaPolicy},
+    mint_coalescing::MintCoalescing, note_inspection::{parse_script_whitelist, NoteScriptPolicy},
+    note_quarantine::{parse_sender_list, SpamPolicy},
+    seed::DeterministicSeeds, slo::SloPolicy,
+    swaps::{parse_swap_rates, SwapPolicy},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    Testnet,
+    Localnet,
+}
+
+impl Profile {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Profile::Testnet => "testnet",
+            Profile::Localnet => "localnet",
+        }
+    }
+}
+
+impl std::str::FromStr for Profile {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "testnet" => Ok(Profile::Testnet),
+            "localnet" | "local" => Ok(Profile::Localnet),
+            other => Err(anyhow::anyhow!("Unknown profile: {}", other)),
+        }
+    }
+}
+
+/// RPC endpoint split into the parts `miden_client::rpc::Endpoint::new` expects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcEndpointConfig {
+    pub protocol: String,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl RpcEndpointConfig {
+    /// Parses "protocol://host[:port]".
+    pub fn parse(url: &str) -> Result<Self> {
+        let (protocol, rest) = url
+            .split_once("://")
+            .ok_or_else(|| anyhow::anyhow!("RPC URL must include a protocol: {}", url))?;
+
+        let rest = rest.trim_end_matches('/');
+        let (host, port) = match rest.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port
+                    .parse::<u16>()
+                    .map_err(|e| anyhow::anyhow!("Invalid RPC port '{}': {}", port, e))?;
+                (host.to_string(), Some(port))
+            }
+            None => (rest.to_string(), None),
+        };
+
+        if host.is_empty() {
+            return Err(anyhow::anyhow!("RPC URL has no host: {}", url));
+        }
+
+        Ok(Self {
+            protocol: protocol.to_string(),
+            host,
+            port,
+        })
+    }
+
+    /// host:port pair used for TCP readiness checks.
+    pub fn socket_addr(&self) -> String {
+        let default_port = if self.protocol == "https" { 443 } else { 80 };
+        format!("{}:{}", self.host, self.port.unwrap_or(default_port))
+    }
+}
+
+impl std::fmt::Display for RpcEndpointConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.port {
+            Some(port) => write!(f, "{}://{}:{}", self.protocol, self.host, port),
+            None => write!(f, "{}://{}", self.protocol, self.host),
+        }
+    }
+}
+
+/// Local node orchestration settings (localnet profile only).
+#[derive(Debug, Clone)]
+pub struct LocalnetConfig {
+    /// Command used to spawn the node; when unset the service attaches to an
+    /// already-running node at the RPC endpoint.
+    pub node_command: Option<Vec<String>>,
+    /// How long to wait for the node RPC port to accept connections
+    pub startup_timeout: Duration,
+}
+
+/// Native TLS settings; absent when the service serves plain HTTP.
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+    /// CA bundle for client certificates; enables mutual TLS when set
+    pub client_ca_path: Option<PathBuf>,
+    /// Accept clients without a certificate even when mutual TLS is enabled
+    pub client_auth_optional: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ServiceConfig {
+    pub profile: Profile,
+    pub listen_addr: String,
+    pub tls: Option<TlsConfig>,
+    /// The preferred RPC endpoint (first of rpc_endpoints)
+    pub rpc: RpcEndpointConfig,
+    /// Every RPC endpoint, in order of preference (see rpc_failover.rs)
+    pub rpc_endpoints: Vec<RpcEndpointConfig>,
+    pub rpc_timeout_ms: u64,
+    /// Time between health checks of the RPC endpoints (zero disables them)
+    pub rpc_health_check_interval: Duration,
+    /// Consecutive failures that make an endpoint unhealthy
+    pub rpc_failover_threshold: u32,
+    pub store_path: PathBuf,
+    /// Blocks behind the latest one whose irrelevant headers are kept
+    pub store_retention_blocks: u32,
+    /// Time between scheduled store prunes and vacuums (zero disables them)
+    pub store_maintenance_interval: Duration,
+    /// Index of balance changes seen by syncs (balance_history.rs)
+    pub balance_history_path: PathBuf,
+    /// Published store snapshots (snapshots.rs)
+    pub snapshot_dir: PathBuf,
+    /// Time between published snapshots (zero disables publishing)
+    pub snapshot_publish_interval: Duration,
+    /// Snapshots kept; older ones are deleted
+    pub snapshot_keep: usize,
+    /// API base of the instance to bootstrap an empty store from
+    pub snapshot_bootstrap_url: Option<String>,
+    /// Admin API key for that instance
+    pub snapshot_bootstrap_api_key: Option<String>,
+    /// Query endpoints only, without a keystore (read_replica.rs)
+    pub read_replica: bool,
+    /// Hex account IDs of the primary's alice, bob and faucet, by name
+    pub read_replica_accounts: BTreeMap<String, String>,
+    pub keystore_path: PathBuf,
+    pub records_path: PathBuf,
+    /// Set when sensitive record fields are encrypted at rest
+    pub field_encryption_key: Option<MasterKey>,
+    /// Per-tenant data keys, wrapped under the master key
+    pub data_keys_path: PathBuf,
+    /// Listings may hide their price; record prices are then encrypted too
+    pub confidential_listings: bool,
+    pub confidential_listings_path: PathBuf,
+    /// Lifetime of a disclosure token for a confidential listing
+    pub disclosure_token_ttl: Duration,
+    /// Currency of prices submitted without one (currency.rs)
+    pub price_currency: String,
+    /// Wait between submitting a transaction and looking for its output notes
+    pub note_propagation_wait: Duration,
+    /// Notes consumed per transaction; larger batches are split into several
+    pub max_notes_per_tx: usize,
+    /// Note scripts consumed without review (note_inspection.rs)
+    pub note_scripts: NoteScriptPolicy,
+    /// Rules that quarantine spam notes (note_quarantine.rs)
+    pub spam_policy: SpamPolicy,
+    pub note_quarantine_path: PathBuf,
+    /// PROP amount minted into each funded wallet on startup
+    pub auto_fund_amount: u64,
+    /// Also fund Alice on startup (Bob is always funded)
+    pub auto_fund_alice: bool,
+    /// Features on or off unless overridden at runtime (feature_flags.rs)
+    pub feature_defaults: BTreeMap<FeatureFlag, bool>,
+    pub feature_flags_path: PathBuf,
+    pub localnet: LocalnetConfig,
+    /// Set only in deterministic demo mode
+    pub demo_seeds: Option<DeterministicSeeds>,
+    /// Set when account seeds derive from the persisted master secret
+    pub master_secret_key: Option<MasterKey>,
+    /// Key the master secret was wrapped under before MASTER_SECRET_KEY
+    pub master_secret_previous_key: Option<MasterKey>,
+    pub master_secret_path: PathBuf,
+    pub proof_cache_ttl: Duration,
+    pub proof_cache_max_entries: usize,
+    pub accreditation_rules_path: PathBuf,
+    /// Threshold of the catch-all rule created when no rules exist yet
+    pub default_accreditation_threshold: u64,
+    pub jurisdiction_lists_path: PathBuf,
+    /// Ed25519 key that must sign restricted-jurisdiction list updates
+    pub jurisdiction_list_signer: Option<ed25519_dalek::VerifyingKey>,
+    /// Published as list version 1 on first start (unsigned deployments only)
+    pub initial_restricted_jurisdictions: Vec<String>,
+    pub identity_providers_path: PathBuf,
+    pub api_keys_path: PathBuf,
+    /// Acts as an admin key until one is issued (principals.rs)
+    pub admin_bootstrap_key: Option<String>,
+    /// Organizations of API keys (organizations.rs)
+    pub organizations_path: PathBuf,
+    /// Tokens of the read-only customer portal (portal.rs)
+    pub portal_access_path: PathBuf,
+    /// Require an API key bound to the right party for escrow actions
+    pub escrow_auth_required: bool,
+    pub escrow_release_policy: ReleasePolicy,
+    /// Releases of escrows above this amount need a second approver (0: off)
+    pub escrow_approval_threshold: u64,
+    /// How long a release approval stays open for confirmation
+    pub escrow_approval_ttl: Duration,
+    pub approvals_path: PathBuf,
+    /// Admins besides the requester who must approve a re-issue after lost
+    /// access (property_recovery.rs)
+    pub property_reissue_approvers: u32,
+    /// How long an escrow may stay created (unfunded) or funded (unsettled)
+    /// before it is flagged as stale; None disables the check
+    pub escrow_stale_created: Option<Duration>,
+    pub escrow_stale_funded: Option<Duration>,
+    pub escrow_monitor_path: PathBuf,
+    /// Yield on long-held escrow balances; None when ESCROW_YIELD_RATE_BPS is
+    /// unset or 0
+    pub escrow_yield: Option<YieldPolicy>,
+    /// Account platform fees and recovered funds go to (name or hex)
+    pub treasury_account: Option<String>,
+    /// Platform fee on escrow payouts to sellers, in basis points (0: off)
+    pub platform_fee_bps: u64,
+    pub treasury_ledger_path: PathBuf,
+    /// Double-entry journal of asset movements (ledger.rs)
+    pub ledger_path: PathBuf,
+    /// Metered usage per API key and month (billing.rs)
+    pub billing_usage_path: PathBuf,
+    pub billing_path: PathBuf,
+    /// None: usage is metered but not invoiced
+    pub billing_fees: Option<FeeSchedule>,
+    /// Pay open invoices from the billed account to the treasury
+    pub billing_auto_settle: bool,
+    /// Treasury top-ups of users' fee asset; None when FEE_SPONSOR_AMOUNT is
+    /// unset or 0
+    pub fee_sponsorship: Option<SponsorshipPolicy>,
+    /// Erasures of personal data and their tombstones (data_subjects.rs)
+    pub data_subjects_path: PathBuf,
+    pub allowances_path: PathBuf,
+    /// Scheduled debits from user accounts (rent) need a "service" allowance
+    pub service_payments_require_allowance: bool,
+    /// How often scheduled jobs (installments, rent, auctions, offers, trade
+    /// settlement retries) run
+    pub scheduler_tick_interval: Duration,
+    pub installments_path: PathBuf,
+    /// How long before a due date the reminder event is issued
+    pub installment_reminder_lead: Duration,
+    /// How long after a due date an unpaid installment defaults the plan
+    pub installment_grace_period: Duration,
+    pub installment_default_policy: DefaultPolicy,
+    pub liens_path: PathBuf,
+    pub leases_path: PathBuf,
+    /// Time the owner has after a lease ends to settle the deposit before it
+    /// is returned in full
+    pub lease_deposit_return_period: Duration,
+    pub auctions_path: PathBuf,
+    pub offers_path: PathBuf,
+    /// How long each round of offer terms stays open unless the request says
+    pub offer_expiry: Duration,
+    pub order_book_path: PathBuf,
+    pub tax_ledger_path: PathBuf,
+    /// Tax withholding rules by jurisdiction (withholding.rs)
+    pub withholding_rules_path: PathBuf,
+    /// Account withheld tax goes to (name or hex)
+    pub tax_escrow_account: Option<String>,
+    /// Insurers, insurance binders and escrow checklists
+    pub attachments_path: PathBuf,
+    /// Registered appraisers/inspectors and their signed reports
+    pub professionals_path: PathBuf,
+    pub notary_path: PathBuf,
+    /// Service account ("alice" or "bob") that issues notarization notes
+    pub notary_account: String,
+    pub wallet_sessions_path: PathBuf,
+    /// Service account ("alice" or "bob") that receives wallet challenge notes
+    pub wallet_challenge_account: String,
+    /// Time a user has to send the challenge note
+    pub wallet_challenge_ttl: Duration,
+    pub wallet_session_ttl: Duration,
+    /// Email-onboarded users, their codes and sessions (custodial.rs)
+    pub custodial_users_path: PathBuf,
+    /// How sign-in and payment codes reach users (None disables onboarding)
+    pub otp_delivery: Option<OtpDelivery>,
+    pub otp_ttl: Duration,
+    /// Wrong codes allowed before a code is spent
+    pub otp_max_attempts: u32,
+    pub custodial_session_ttl: Duration,
+    /// Activity subscriptions and their webhook secrets (subscriptions.rs)
+    pub subscriptions_path: PathBuf,
+    /// Lifecycle hooks (hooks.rs)
+    pub hooks_path: PathBuf,
+    /// Longest a hook may take before it counts as failed
+    pub hook_timeout: Duration,
+    /// WASM hook modules and the fuel each call gets
+    pub hooks_wasm_dir: PathBuf,
+    pub hook_wasm_fuel: u64,
+    /// Address, description and size of listings (search.rs)
+    pub listing_details_path: PathBuf,
+    /// Accepted settlement tokens and listings' choices (settlement_tokens.rs)
+    pub settlement_tokens_path: PathBuf,
+    /// Token swaps; None when SWAP_LIQUIDITY_ACCOUNT is unset (swaps.rs)
+    pub swaps: Option<SwapPolicy>,
+    pub swaps_path: PathBuf,
+    /// Listing photo metadata (media.rs)
+    pub media_path: PathBuf,
+    /// Where photos are stored and the limits uploads are held to
+    pub media: MediaPolicy,
+    /// Generated closing documents (documents.rs) and their artifacts
+    pub documents_path: PathBuf,
+    pub documents_dir: PathBuf,
+    /// Templates overriding the built-in ones, as <kind>.hbs
+    pub document_templates_dir: Option<PathBuf>,
+    /// Signature envelopes of closing documents (esign.rs)
+    pub signatures_path: PathBuf,
+    /// E-signature provider documents are sent to (None disables signing)
+    pub esign: Option<Arc<dyn SignatureProvider>>,
+    pub mint_jobs_path: PathBuf,
+    /// Multi-step flows (sagas.rs)
+    pub sagas_path: PathBuf,
+    pub retry_queue_path: PathBuf,
+    /// How long after the original request a failed submission is retried
+    /// (zero disables the retry queue)
+    pub retry_deadline: Duration,
+    /// Wait before the first retry; doubled after every failed attempt
+    pub retry_backoff: Duration,
+    /// Requests queued in offline mode (offline_queue.rs)
+    pub offline_queue_path: PathBuf,
+    /// Maximum properties accepted by one /mint-batch request
+    pub mint_batch_max_items: usize,
+    /// Properties minted per client-queue turn
+    pub mint_batch_chunk_size: usize,
+    /// Mint requests sharing a faucet transaction; None mints each alone
+    /// (mint_coalescing.rs)
+    pub mint_coalescing: Option<MintCoalescing>,
+    /// Minimum time between network syncs triggered by conditional reads
+    pub read_sync_interval: Duration,
+    /// Lifetime bound for cached reads (zero disables the read cache)
+    pub read_cache_ttl: Duration,
+    pub read_cache_max_entries: usize,
+    /// Per-route deadlines of API requests (see deadlines.rs)
+    pub request_deadlines: DeadlinePolicy,
+    /// Queued client commands at which new requests are refused (zero disables)
+    pub load_shed_queue_depth: usize,
+    /// Request body size limit per route (see body_limits.rs)
+    pub body_limits: BodyLimitPolicy,
+    /// Success and latency objectives per endpoint (see slo.rs)
+    pub slo: SloPolicy,
+    /// Removal date announced for the unprefixed legacy routes
+    pub legacy_api_sunset: Option<chrono::NaiveDate>,
+    /// Cross-origin access to the API (see http_security.rs)
+    pub cors: CorsPolicy,
+    /// Security headers added to every response
+    pub security_headers: SecurityHeaders,
+}
+
+pub(crate) fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.trim().is_empty())
+}
+
+/// A hex master key given directly in `name` or in the file named by `file_name`.
+fn env_master_key(name: &str, file_name: &str) -> Result<Option<MasterKey>> {
+    match (env_var(name), env_var(file_name)) {
+        (Some(key), None) => Ok(Some(MasterKey::from_hex(&key)?)),
+        (None, Some(file)) => {
+            let key = std::fs::read_to_string(&file)
+                .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", file, e))?;
+            Ok(Some(MasterKey::from_hex(&key)?))
+        }
+        (None, None) => Ok(None),
+        (Some(_), Some(_)) => Err(anyhow::anyhow!(
+            "Set only one of {} and {}",
+            name,
+            file_name
+        )),
+    }
+}
+
+fn env_parse<T: std::str::FromStr>(name: &str) -> Result<Option<T>>
+where
+    T::Err: std::fmt::Display,
+{
+    match env_var(name) {
+        Some(raw) => raw
+            .trim()
+            .parse::<T>()
+            .map(Some)
+            .map_err(|e| anyhow::anyhow!("Invalid value for {}: {}", name, e)),
+        None => Ok(None),
+    }
+}
+
+fn env_bool(name: &str) -> Result<Option<bool>> {
+    match env_var(name) {
+        Some(raw) => match raw.trim().to_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Ok(Some(true)),
+            "0" | "false" | "no" | "off" => Ok(Some(false)),
+            other => Err(anyhow::anyhow!("Invalid boolean for {}: {}", name, other)),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Parses `alice=0x..,bob=0x..,faucet=0x..` (any subset).
+fn parse_named_accounts(list: &str) -> Result<BTreeMap<String, String>> {
+    let mut accounts = BTreeMap::new();
+    for entry in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (name, account) = entry.split_once('=').ok_or_else(|| {
+            anyhow::anyhow!("READ_REPLICA_ACCOUNTS entry is not name=id: {}", entry)
+        })?;
+        let name = name.trim().to_lowercase();
+        if !["alice", "bob", "faucet"].contains(&name.as_str()) {
+            return Err(anyhow::anyhow!(
+                "READ_REPLICA_ACCOUNTS names alice, bob or faucet, not {}",
+                name
+            ));
+        }
+        accounts.insert(name, account.trim().to_string());
+    }
+    Ok(accounts)
+}
+
+impl ServiceConfig {
+    /// Builds the configuration from the environment (and .env, if present).
+    pub fn from_env() -> Result<Self> {
+        let _ = dotenvy::dotenv();
+
+        let profile = env_parse::<Profile>("MIDEN_PROFILE")?.unwrap_or(Profile::Testnet);
+
+        let (default_rpc, default_wait_secs, default_fund_alice) = match profile {
+            Profile::Testnet => ("https://rpc.testnet.miden.io:443", 30, false),
+            Profile::Localnet => ("http://localhost:57291", 3, true),
+        };
+
+        let rpc_endpoints = match env_var("MIDEN_RPC_URLS") {
+            Some(urls) => urls
+                .split(',')
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(RpcEndpointConfig::parse)
+                .collect::<Result<Vec<_>>>()?,
+            None => vec![RpcEndpointConfig::parse(
+                &env_var("MIDEN_RPC_URL").unwrap_or_else(|| default_rpc.to_string()),
+            )?],
+        };
+        let rpc = rpc_endpoints
+            .first()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("MIDEN_RPC_URLS lists no endpoint"))?;
+
+        let demo_seeds = match (
+            env_var("DEMO_MASTER_SEED"),
+            env_bool("UNSAFE_DEMO_DETERMINISTIC_SEEDS")?.unwrap_or(false),
+        ) {
+            (Some(seed), true) => {
+                if rpc_endpoints.iter().any(|e| e.host.contains("mainnet")) {
+                    return Err(anyhow::anyhow!(
+                        "Deterministic demo seeds are only allowed on test networks"
+                    ));
+                }
+                tracing::warn!(
+                    "⚠️  UNSAFE deterministic demo mode: all keys derive from DEMO_MASTER_SEED"
+                );
+                Some(DeterministicSeeds::from_passphrase(&seed))
+            }
+            (Some(_), false) => {
+                return Err(anyhow::anyhow!(
+                    "DEMO_MASTER_SEED is set but UNSAFE_DEMO_DETERMINISTIC_SEEDS is not enabled"
+                ));
+            }
+            (None, true) => {
+                return Err(anyhow::anyhow!(
+                    "UNSAFE_DEMO_DETERMINISTIC_SEEDS requires DEMO_MASTER_SEED"
+                ));
+            }
+            (None, false) => None,
+        };
+
+        let tls = match (env_var("TLS_CERT_PATH"), env_var("TLS_KEY_PATH")) {
+            (Some(cert), Some(key)) => Some(TlsConfig {
+                cert_path: cert.into(),
+                key_path: key.into(),
+                client_ca_path: env_var("TLS_CLIENT_CA_PATH").map(PathBuf::from),
+                client_auth_optional: env_bool("TLS_CLIENT_AUTH_OPTIONAL")?.unwrap_or(false),
+            }),
+            (None, None) => {
+                if env_var("TLS_CLIENT_CA_PATH").is_some() {
+                    return Err(anyhow::anyhow!(
+                        "TLS_CLIENT_CA_PATH requires TLS_CERT_PATH and TLS_KEY_PATH"
+                    ));
+                }
+                None
+            }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "TLS_CERT_PATH and TLS_KEY_PATH must be set together"
+                ));
+            }
+        };
+
+        let notary_account = env_var("NOTARY_ACCOUNT").unwrap_or_else(|| "alice".to_string());
+        if !matches!(notary_account.as_str(), "alice" | "bob") {
+            return Err(anyhow::anyhow!(
+                "NOTARY_ACCOUNT must be alice or bob, got {}",
+                notary_account
+            ));
+        }
+
+        let wallet_challenge_account =
+            env_var("WALLET_CHALLENGE_ACCOUNT").unwrap_or_else(|| "alice".to_string());
+        if !matches!(wallet_challenge_account.as_str(), "alice" | "bob") {
+            return Err(anyhow::anyhow!(
+                "WALLET_CHALLENGE_ACCOUNT must be alice or bob, got {}",
+                wallet_challenge_account
+            ));
+        }
+
+        let field_encryption_key = env_master_key(
+            "FIELD_ENCRYPTION_MASTER_KEY",
+            "FIELD_ENCRYPTION_MASTER_KEY_FILE",
+        )?;
+        let master_secret_key = env_master_key("MASTER_SECRET_KEY", "MASTER_SECRET_KEY_FILE")?;
+        let master_secret_previous_key = env_master_key(
+            "MASTER_SECRET_PREVIOUS_KEY",
+            "MASTER_SECRET_PREVIOUS_KEY_FILE",
+        )?;
+        if master_secret_key.is_some() && demo_seeds.is_some() {
+            return Err(anyhow::anyhow!(
+                "MASTER_SECRET_KEY and deterministic demo seeds cannot be used together"
+            ));
+        }
+        if master_secret_previous_key.is_some() && master_secret_key.is_none() {
+            return Err(anyhow::anyhow!(
+                "MASTER_SECRET_PREVIOUS_KEY requires MASTER_SECRET_KEY"
+            ));
+        }
+        let confidential_listings = env_bool("CONFIDENTIAL_LISTINGS")?.unwrap_or(false);
+        if confidential_listings && field_encryption_key.is_none() {
+            return Err(anyhow::anyhow!(
+                "CONFIDENTIAL_LISTINGS requires a field encryption master key"
+            ));
+        }
+        let mint_coalescing = match env_parse::<u64>("MINT_COALESCE_WINDOW_MS")?.unwrap_or(0) {
+            0 => None,
+            window_ms => Some(MintCoalescing {
+                window: Duration::from_millis(window_ms),
+                max_items: env_parse::<usize>("MINT_COALESCE_MAX_ITEMS")?.unwrap_or(16).max(1),
+            }),
+        };
+        let max_notes_per_tx = env_parse("MAX_NOTES_PER_TX")?.unwrap_or(16);
+        if max_notes_per_tx == 0 {
+            return Err(anyhow::anyhow!("MAX_NOTES_PER_TX must be at least 1"));
+        }
+        let price_currency = env_var("PRICE_CURRENCY").unwrap_or_else(|| "EUR".to_string());
+        let price_currency = find_currency(&price_currency)
+            .ok_or_else(|| anyhow::anyhow!("Unknown PRICE_CURRENCY: {}", price_currency))?
+            .code
+            .to_string();
+        let escrow_auth_required = env_bool("ESCROW_AUTH_REQUIRED")?.unwrap_or(true);
+        if confidential_listings && !escrow_auth_required {
+            // Without API keys nobody can be told apart from the owner
+            return Err(anyhow::anyhow!(
+                "CONFIDENTIAL_LISTINGS requires ESCROW_AUTH_REQUIRED"
+            ));
+        }
+        let escrow_approval_threshold = env_parse("ESCROW_APPROVAL_THRESHOLD")?.unwrap_or(0);
+        if escrow_approval_threshold > 0 && !escrow_auth_required {
+            // The two approvers are told apart by their API keys
+            return Err(anyhow::anyhow!(
+                "ESCROW_APPROVAL_THRESHOLD requires ESCROW_AUTH_REQUIRED"
+            ));
+        }
+        let property_reissue_approvers = env_parse("PROPERTY_REISSUE_APPROVERS")?.unwrap_or(2);
+        if property_reissue_approvers == 0 {
+            return Err(anyhow::anyhow!(
+                "PROPERTY_REISSUE_APPROVERS must be at least 1"
+            ));
+        }
+        let treasury_account = env_var("TREASURY_ACCOUNT");
+        let platform_fee_bps = env_parse("PLATFORM_FEE_BPS")?.unwrap_or(0);
+        if platform_fee_bps > 10_000 {
+            return Err(anyhow::anyhow!(
+                "PLATFORM_FEE_BPS must be at most 10000, got {}",
+                platform_fee_bps
+            ));
+        }
+        if platform_fee_bps > 0 && treasury_account.is_none() {
+            return Err(anyhow::anyhow!("PLATFORM_FEE_BPS requires TREASURY_ACCOUNT"));
+        }
+        let escrow_yield_rate_bps: u64 = env_parse("ESCROW_YIELD_RATE_BPS")?.unwrap_or(0);
+        let escrow_yield = if escrow_yield_rate_bps > 0 {
+            if treasury_account.is_none() {
+                // Yield is paid out of the treasury
+                return Err(anyhow::anyhow!("ESCROW_YIELD_RATE_BPS requires TREASURY_ACCOUNT"));
+            }
+            Some(YieldPolicy {
+                rate_bps: escrow_yield_rate_bps,
+                after_days: env_parse("ESCROW_YIELD_AFTER_DAYS")?.unwrap_or(30),
+                on_release: env_parse("ESCROW_YIELD_ON_RELEASE")?
+                    .unwrap_or_else(|| YieldSplit::all_to(YieldRecipient::Buyer)),
+                on_refund: env_parse("ESCROW_YIELD_ON_REFUND")?
+                    .unwrap_or_else(|| YieldSplit::all_to(YieldRecipient::Buyer)),
+            })
+        } else {
+            None
+        };
+        let swap_rates = env_var("SWAP_RATES")
+            .map(|list| parse_swap_rates(&list))
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Invalid value for SWAP_RATES: {}", e))?
+            .unwrap_or_default();
+        let swap_oracle_url = env_var("SWAP_ORACLE_URL");
+        let swap_spread_bps = env_parse("SWAP_SPREAD_BPS")?.unwrap_or(0);
+        if swap_spread_bps >= 10_000 {
+            return Err(anyhow::anyhow!(
+                "SWAP_SPREAD_BPS must be below 10000, got {}",
+                swap_spread_bps
+            ));
+        }
+        let swaps = match env_var("SWAP_LIQUIDITY_ACCOUNT") {
+            Some(liquidity_account) => {
+                if swap_rates.is_empty() && swap_oracle_url.is_none() {
+                    return Err(anyhow::anyhow!(
+                        "SWAP_LIQUIDITY_ACCOUNT requires SWAP_RATES or SWAP_ORACLE_URL"
+                    ));
+                }
+                Some(SwapPolicy {
+                    liquidity_account,
+                    rates: swap_rates,
+                    oracle_url: swap_oracle_url,
+                    spread_bps: swap_spread_bps,
+                    quote_ttl: Duration::from_secs(
+                        env_parse("SWAP_QUOTE_TTL_SECS")?.unwrap_or(60),
+                    ),
+                })
+            }
+            None if !swap_rates.is_empty() || swap_oracle_url.is_some() => {
+                return Err(anyhow::anyhow!(
+                    "SWAP_RATES and SWAP_ORACLE_URL require SWAP_LIQUIDITY_ACCOUNT"
+                ));
+            }
+            None => None,
+        };
+        let billing_fees = env_var("BILLING_FEES")
+            .map(|list| parse_fee_schedule(&list))
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Invalid value for BILLING_FEES: {}", e))?;
+        let billing_auto_settle = env_bool("BILLING_AUTO_SETTLE")?.unwrap_or(false);
+        if billing_auto_settle && (billing_fees.is_none() || treasury_account.is_none()) {
+            return Err(anyhow::anyhow!(
+                "BILLING_AUTO_SETTLE requires BILLING_FEES and TREASURY_ACCOUNT"
+            ));
+        }
+        let fee_sponsor_amount: u64 = env_parse("FEE_SPONSOR_AMOUNT")?.unwrap_or(0);
+        let fee_sponsorship = if fee_sponsor_amount > 0 {
+            if treasury_account.is_none() {
+                // Top-ups come out of the treasury
+                return Err(anyhow::anyhow!("FEE_SPONSOR_AMOUNT requires TREASURY_ACCOUNT"));
+            }
+            let fee_faucet_id = env_var("FEE_SPONSOR_FAUCET")
+                .ok_or_else(|| anyhow::anyhow!("FEE_SPONSOR_AMOUNT requires FEE_SPONSOR_FAUCET"))?;
+            if !fee_faucet_id.starts_with("0x") {
+                return Err(anyhow::anyhow!(
+                    "FEE_SPONSOR_FAUCET must be a hex AccountId, got {}",
+                    fee_faucet_id
+                ));
+            }
+            Some(SponsorshipPolicy {
+                fee_faucet_id: fee_faucet_id.to_lowercase(),
+                amount: fee_sponsor_amount,
+                monthly_quota: env_parse("FEE_SPONSOR_QUOTA")?.unwrap_or(3),
+            })
+        } else {
+            None
+        };
+
+        let read_replica = env_bool("READ_REPLICA")?.unwrap_or(false);
+        let read_replica_accounts = env_var("READ_REPLICA_ACCOUNTS")
+            .map(|list| parse_named_accounts(&list))
+            .transpose()?
+            .unwrap_or_default();
+        if !read_replica_accounts.is_empty() && !read_replica {
+            return Err(anyhow::anyhow!("READ_REPLICA_ACCOUNTS requires READ_REPLICA"));
+        }
+
+        let media = MediaPolicy {
+            backend: MediaBackend::new(
+                &env_var("MEDIA_STORE").unwrap_or_else(|| "local".to_string()),
+                env_var("MEDIA_DIR").unwrap_or_else(|| "./media".to_string()).into(),
+                env_var("IPFS_API_URL"),
+            )
+            .map_err(|e| anyhow::anyhow!("Invalid value for MEDIA_STORE: {}", e))?,
+            max_bytes: env_parse("MEDIA_MAX_BYTES")?.unwrap_or(10 << 20),
+            thumbnail_px: env_parse("MEDIA_THUMBNAIL_PX")?.unwrap_or(320),
+            max_per_property: env_parse("MEDIA_MAX_PER_PROPERTY")?.unwrap_or(50),
+        };
+        let body_limits = BodyLimitPolicy::parse(
+            env_parse("BODY_LIMIT_BYTES")?.unwrap_or(1 << 20),
+            media.upload_body_limit(),
+            &env_var("BODY_LIMITS").unwrap_or_default(),
+        )
+        .map_err(|e| anyhow::anyhow!("Invalid value for BODY_LIMITS: {}", e))?;
+        // Codes in the log are for test networks only
+        let otp_delivery = OtpDelivery::from_env(
+            env_var("OTP_DELIVERY").as_deref(),
+            env_var("OTP_DELIVERY_URL"),
+            env_var("OTP_DELIVERY_API_KEY"),
+            rpc_endpoints.iter().any(|e| e.host.contains("mainnet")),
+        )
+        .map_err(|e| anyhow::anyhow!("Invalid value for OTP_DELIVERY: {}", e))?;
+
+        // HSTS only means something to browsers over TLS
+        let default_hsts_max_age = if tls.is_some() { 31_536_000 } else { 0 };
+
+        let node_command = env_var("LOCALNET_NODE_CMD")
+            .map(|cmd| cmd.split_whitespace().map(str::to_string).collect::<Vec<_>>());
+
+        Ok(Self {
+            profile,
+            listen_addr: env_var("LISTEN_ADDR").unwrap_or_else(|| "127.0.0.1:3000".to_string()),
+            tls,
+            rpc,
+            rpc_endpoints,
+            rpc_timeout_ms: env_parse("MIDEN_RPC_TIMEOUT_MS")?.unwrap_or(10_000),
+            rpc_health_check_interval: Duration::from_secs(
+                env_parse("RPC_HEALTH_CHECK_INTERVAL_SECS")?.unwrap_or(15),
+            ),
+            rpc_failover_threshold: env_parse("RPC_FAILOVER_THRESHOLD")?.unwrap_or(3),
+            store_path: env_var("MIDEN_STORE_PATH")
+                .unwrap_or_else(|| "./store.sqlite3".to_string())
+                .into(),
+            store_retention_blocks: env_parse("STORE_RETENTION_BLOCKS")?.unwrap_or(10_000),
+            store_maintenance_interval: Duration::from_secs(
+                env_parse("STORE_MAINTENANCE_INTERVAL_SECS")?.unwrap_or(86_400),
+            ),
+            balance_history_path: env_var("BALANCE_HISTORY_PATH")
+                .unwrap_or_else(|| "./balance-history.sqlite3".to_string())
+                .into(),
+            snapshot_dir: env_var("SNAPSHOT_DIR")
+                .unwrap_or_else(|| "./snapshots".to_string())
+                .into(),
+            snapshot_publish_interval: Duration::from_secs(
+                env_parse("SNAPSHOT_PUBLISH_INTERVAL_SECS")?.unwrap_or(0),
+            ),
+            snapshot_keep: env_parse("SNAPSHOT_KEEP")?.unwrap_or(3),
+            snapshot_bootstrap_url: env_var("SNAPSHOT_BOOTSTRAP_URL"),
+            snapshot_bootstrap_api_key: env_var("SNAPSHOT_BOOTSTRAP_API_KEY"),
+            read_replica,
+            read_replica_accounts,
+            keystore_path: env_var("MIDEN_KEYSTORE_PATH")
+                .unwrap_or_else(|| "./keystore".to_string())
+                .into(),
+            records_path: env_var("SERVICE_RECORDS_PATH")
+                .unwrap_or_else(|| "./service-records.json".to_string())
+                .into(),
+            field_encryption_key,
+            data_keys_path: env_var("DATA_KEYS_PATH")
+                .unwrap_or_else(|| "./data-keys.json".to_string())
+                .into(),
+            confidential_listings,
+            confidential_listings_path: env_var("CONFIDENTIAL_LISTINGS_PATH")
+                .unwrap_or_else(|| "./confidential-listings.json".to_string())
+                .into(),
+            disclosure_token_ttl: Duration::from_secs(
+                env_parse("DISCLOSURE_TOKEN_TTL_SECS")?.unwrap_or(7 * 86_400),
+            ),
+            price_currency,
+            note_propagation_wait: Duration::from_secs(
+                env_parse("NOTE_PROPAGATION_WAIT_SECS")?.unwrap_or(default_wait_secs),
+            ),
+            max_notes_per_tx,
+            note_scripts: NoteScriptPolicy {
+                custom: env_var("NOTE_SCRIPT_WHITELIST")
+                    .map(|list| parse_script_whitelist(&list))
+                    .transpose()?
+                    .unwrap_or_default(),
+                consume_unknown: env_bool("CONSUME_UNKNOWN_NOTE_SCRIPTS")?.unwrap_or(false),
+            },
+            spam_policy: SpamPolicy {
+                min_asset_amount: env_parse("NOTE_MIN_ASSET_AMOUNT")?.unwrap_or(1),
+                sender_allowlist: env_var("NOTE_SENDER_ALLOWLIST")
+                    .map(|list| parse_sender_list("NOTE_SENDER_ALLOWLIST", &list))
+                    .transpose()?
+                    .unwrap_or_default(),
+                sender_denylist: env_var("NOTE_SENDER_DENYLIST")
+                    .map(|list| parse_sender_list("NOTE_SENDER_DENYLIST", &list))
+                    .transpose()?
+                    .unwrap_or_default(),
+            },
+            note_quarantine_path: env_var("NOTE_QUARANTINE_PATH")
+                .unwrap_or_else(|| "./note-quarantine.json".to_string())
+                .into(),
+            auto_fund_amount: env_parse("AUTO_FUND_AMOUNT")?.unwrap_or(20_000_000),
+            auto_fund_alice: env_bool("AUTO_FUND_ALICE")?.unwrap_or(default_fund_alice),
+            feature_defaults: parse_feature_defaults(&env_var("FEATURE_FLAGS").unwrap_or_default())
+                .map_err(|e| anyhow::anyhow!("Invalid value for FEATURE_FLAGS: {}", e))?,
+            feature_flags_path: env_var("FEATURE_FLAGS_PATH")
+                .unwrap_or_else(|| "./feature-flags.json".to_string())
+                .into(),
+            localnet: LocalnetConfig {
+                node_command,
+                startup_timeout: Duration::from_secs(
+                    env_parse("LOCALNET_STARTUP_TIMEOUT_SECS")?.unwrap_or(60),
+                ),
+            },
+            demo_seeds,
+            master_secret_key,
+            master_secret_previous_key,
+            master_secret_path: env_var("MASTER_SECRET_PATH")
+                .unwrap_or_else(|| "./master-secret.json".to_string())
+                .into(),
+            proof_cache_ttl: Duration::from_secs(
+                env_parse("PROOF_CACHE_TTL_SECS")?.unwrap_or(3600),
+            ),
+            proof_cache_max_entries: env_parse("PROOF_CACHE_MAX_ENTRIES")?.unwrap_or(1024),
+            accreditation_rules_path: env_var("ACCREDITATION_RULES_PATH")
+                .unwrap_or_else(|| "./accreditation-rules.json".to_string())
+                .into(),
+            default_accreditation_threshold: env_parse("DEFAULT_ACCREDITATION_THRESHOLD")?
+                .unwrap_or(1_000_000),
+            jurisdiction_lists_path: env_var("JURISDICTION_LISTS_PATH")
+                .unwrap_or_else(|| "./jurisdiction-lists.json".to_string())
+                .into(),
+            jurisdiction_list_signer: env_var("JURISDICTION_LIST_SIGNER_PUBKEY")
+                .map(|key| parse_signer_key(&key))
+                .transpose()?,
+            initial_restricted_jurisdictions: env_var("INITIAL_RESTRICTED_JURISDICTIONS")
+                .map(|list| list.split(',').map(|c| c.trim().to_string()).collect())
+                .unwrap_or_default(),
+            identity_providers_path: env_var("IDENTITY_PROVIDERS_PATH")
+                .unwrap_or_else(|| "./identity-providers.json".to_string())
+                .into(),
+            api_keys_path: env_var("API_KEYS_PATH")
+                .unwrap_or_else(|| "./api-keys.json".to_string())
+                .into(),
+            admin_bootstrap_key: env_var("ADMIN_BOOTSTRAP_KEY"),
+            organizations_path: env_var("ORGANIZATIONS_PATH")
+                .unwrap_or_else(|| "./organizations.json".to_string())
+                .into(),
+            portal_access_path: env_var("PORTAL_ACCESS_PATH")
+                .unwrap_or_else(|| "./portal-access.json".to_string())
+                .into(),
+            escrow_auth_required,
+            escrow_release_policy: env_parse("ESCROW_RELEASE_POLICY")?
+                .unwrap_or(ReleasePolicy::SellerOrArbiter),
+            escrow_approval_threshold,
+            escrow_approval_ttl: Duration::from_secs(
+                env_parse("ESCROW_APPROVAL_TTL_SECS")?.unwrap_or(86_400),
+            ),
+            approvals_path: env_var("APPROVALS_PATH")
+                .unwrap_or_else(|| "./approvals.json".to_string())
+                .into(),
+            property_reissue_approvers,
+            escrow_stale_created: Some(Duration::from_secs(
+                env_parse("ESCROW_STALE_CREATED_SECS")?.unwrap_or(7 * 86_400),
+            ))
+            .filter(|d| !d.is_zero()),
+            escrow_stale_funded: Some(Duration::from_secs(
+                env_parse("ESCROW_STALE_FUNDED_SECS")?.unwrap_or(30 * 86_400),
+            ))
+            .filter(|d| !d.is_zero()),
+            escrow_monitor_path: env_var("ESCROW_MONITOR_PATH")
+                .unwrap_or_else(|| "./escrow-monitor.json".to_string())
+                .into(),
+            escrow_yield,
+            treasury_account,
+            platform_fee_bps,
+            treasury_ledger_path: env_var("TREASURY_LEDGER_PATH")
+                .unwrap_or_else(|| "./treasury.json".to_string())
+                .into(),
+            ledger_path: env_var("LEDGER_PATH")
+                .unwrap_or_else(|| "./ledger.json".to_string())
+                .into(),
+            billing_usage_path: env_var("BILLING_USAGE_PATH")
+                .unwrap_or_else(|| "./billing-usage.json".to_string())
+                .into(),
+            billing_path: env_var("BILLING_PATH")
+                .unwrap_or_else(|| "./billing.json".to_string())
+                .into(),
+            billing_fees,
+            billing_auto_settle,
+            fee_sponsorship,
+            data_subjects_path: env_var("DATA_SUBJECTS_PATH")
+                .unwrap_or_else(|| "./data-subjects.json".to_string())
+                .into(),
+            allowances_path: env_var("ALLOWANCES_PATH")
+                .unwrap_or_else(|| "./allowances.json".to_string())
+                .into(),
+            service_payments_require_allowance: env_bool("SERVICE_PAYMENTS_REQUIRE_ALLOWANCE")?
+                .unwrap_or(false),
+            scheduler_tick_interval: Duration::from_secs(
+                env_parse("SCHEDULER_TICK_SECS")?.unwrap_or(60),
+            ),
+            installments_path: env_var("INSTALLMENTS_PATH")
+                .unwrap_or_else(|| "./installments.json".to_string())
+                .into(),
+            installment_reminder_lead: Duration::from_secs(
+                env_parse("INSTALLMENT_REMINDER_LEAD_SECS")?.unwrap_or(3 * 86_400),
+            ),
+            installment_grace_period: Duration::from_secs(
+                env_parse("INSTALLMENT_GRACE_SECS")?.unwrap_or(5 * 86_400),
+            ),
+            installment_default_policy: env_parse("INSTALLMENT_DEFAULT_POLICY")?
+                .unwrap_or(DefaultPolicy::RefundBuyer),
+            liens_path: env_var("LIENS_PATH")
+                .unwrap_or_else(|| "./liens.json".to_string())
+                .into(),
+            leases_path: env_var("LEASES_PATH")
+                .unwrap_or_else(|| "./leases.json".to_string())
+                .into(),
+            lease_deposit_return_period: Duration::from_secs(
+                env_parse("LEASE_DEPOSIT_RETURN_SECS")?.unwrap_or(14 * 86_400),
+            ),
+            auctions_path: env_var("AUCTIONS_PATH")
+                .unwrap_or_else(|| "./auctions.json".to_string())
+                .into(),
+            offers_path: env_var("OFFERS_PATH")
+                .unwrap_or_else(|| "./offers.json".to_string())
+                .into(),
+            offer_expiry: Duration::from_secs(
+                env_parse("OFFER_EXPIRY_SECS")?.unwrap_or(3 * 86_400),
+            ),
+            order_book_path: env_var("ORDER_BOOK_PATH")
+                .unwrap_or_else(|| "./order-book.json".to_string())
+                .into(),
+            tax_ledger_path: env_var("TAX_LEDGER_PATH")
+                .unwrap_or_else(|| "./tax-ledger.json".to_string())
+                .into(),
+            withholding_rules_path: env_var("WITHHOLDING_RULES_PATH")
+                .unwrap_or_else(|| "./withholding-rules.json".to_string())
+                .into(),
+            tax_escrow_account: env_var("TAX_ESCROW_ACCOUNT"),
+            attachments_path: env_var("ATTACHMENTS_PATH")
+                .unwrap_or_else(|| "./attachments.json".to_string())
+                .into(),
+            professionals_path: env_var("PROFESSIONALS_PATH")
+                .unwrap_or_else(|| "./professionals.json".to_string())
+                .into(),
+            notary_path: env_var("NOTARY_PATH")
+                .unwrap_or_else(|| "./notary.json".to_string())
+                .into(),
+            notary_account,
+            wallet_sessions_path: env_var("WALLET_SESSIONS_PATH")
+                .unwrap_or_else(|| "./wallet-sessions.json".to_string())
+                .into(),
+            wallet_challenge_account,
+            wallet_challenge_ttl: Duration::from_secs(
+                env_parse("WALLET_CHALLENGE_TTL_SECS")?.unwrap_or(900),
+            ),
+            wallet_session_ttl: Duration::from_secs(
+                env_parse("WALLET_SESSION_TTL_SECS")?.unwrap_or(86_400),
+            ),
+            custodial_users_path: env_var("CUSTODIAL_USERS_PATH")
+                .unwrap_or_else(|| "./custodial-users.json".to_string())
+                .into(),
+            otp_delivery,
+            otp_ttl: Duration::from_secs(env_parse("OTP_TTL_SECS")?.unwrap_or(600)),
+            otp_max_attempts: env_parse("OTP_MAX_ATTEMPTS")?.unwrap_or(5),
+            custodial_session_ttl: Duration::from_secs(
+                env_parse("CUSTODIAL_SESSION_TTL_SECS")?.unwrap_or(86_400),
+            ),
+            subscriptions_path: env_var("SUBSCRIPTIONS_PATH")
+                .unwrap_or_else(|| "./subscriptions.json".to_string())
+                .into(),
+            hooks_path: env_var("HOOKS_PATH")
+                .unwrap_or_else(|| "./hooks.json".to_string())
+                .into(),
+            hook_timeout: Duration::from_millis(env_parse("HOOK_TIMEOUT_MS")?.unwrap_or(5_000)),
+            hooks_wasm_dir: env_var("HOOKS_WASM_DIR")
+                .unwrap_or_else(|| "./hooks".to_string())
+                .into(),
+            hook_wasm_fuel: env_parse("HOOK_WASM_FUEL")?.unwrap_or(100_000_000),
+            listing_details_path: env_var("LISTING_DETAILS_PATH")
+                .unwrap_or_else(|| "./listing-details.json".to_string())
+                .into(),
+            settlement_tokens_path: env_var("SETTLEMENT_TOKENS_PATH")
+                .unwrap_or_else(|| "./settlement-tokens.json".to_string())
+                .into(),
+            swaps,
+            swaps_path: env_var("SWAPS_PATH")
+                .unwrap_or_else(|| "./swaps.json".to_string())
+                .into(),
+            media_path: env_var("MEDIA_PATH")
+                .unwrap_or_else(|| "./media.json".to_string())
+                .into(),
+            media,
+            documents_path: env_var("DOCUMENTS_PATH")
+                .unwrap_or_else(|| "./documents.json".to_string())
+                .into(),
+            documents_dir: env_var("DOCUMENTS_DIR")
+                .unwrap_or_else(|| "./documents".to_string())
+                .into(),
+            document_templates_dir: env_var("DOCUMENT_TEMPLATES_DIR").map(PathBuf::from),
+            signatures_path: env_var("SIGNATURES_PATH")
+                .unwrap_or_else(|| "./signatures.json".to_string())
+                .into(),
+            esign: crate::esign::provider_from_env(
+                env_var("ESIGN_PROVIDER").as_deref(),
+                env_var("ESIGN_API_URL"),
+                env_var("ESIGN_API_KEY"),
+                env_var("ESIGN_CALLBACK_SECRET"),
+                env_var("ESIGN_CALLBACK_URL"),
+            )
+            .map_err(|e| anyhow::anyhow!("Invalid value for ESIGN_PROVIDER: {}", e))?,
+            mint_jobs_path: env_var("MINT_JOBS_PATH")
+                .unwrap_or_else(|| "./mint-jobs.json".to_string())
+                .into(),
+            sagas_path: env_var("SAGAS_PATH")
+                .unwrap_or_else(|| "./sagas.json".to_string())
+                .into(),
+            retry_queue_path: env_var("RETRY_QUEUE_PATH")
+                .unwrap_or_else(|| "./retry-queue.json".to_string())
+                .into(),
+            retry_deadline: Duration::from_secs(env_parse("RETRY_DEADLINE_SECS")?.unwrap_or(900)),
+            retry_backoff: Duration::from_secs(env_parse("RETRY_BACKOFF_SECS")?.unwrap_or(30)),
+            offline_queue_path: env_var("OFFLINE_QUEUE_PATH")
+                .unwrap_or_else(|| "./offline-queue.json".to_string())
+                .into(),
+            mint_batch_max_items: env_parse("MINT_BATCH_MAX_ITEMS")?.unwrap_or(500),
+            mint_batch_chunk_size: env_parse("MINT_BATCH_CHUNK_SIZE")?.unwrap_or(10),
+            mint_coalescing,
+            read_sync_interval: Duration::from_secs(
+                env_parse("READ_SYNC_INTERVAL_SECS")?.unwrap_or(5),
+            ),
+            read_cache_ttl: Duration::from_secs(env_parse("READ_CACHE_TTL_SECS")?.unwrap_or(30)),
+            read_cache_max_entries: env_parse("READ_CACHE_MAX_ENTRIES")?.unwrap_or(256),
+            request_deadlines: DeadlinePolicy::parse(
+                env_parse("COMMAND_TIMEOUT_SECS")?.unwrap_or(60),
+                &env_var("COMMAND_TIMEOUTS").unwrap_or_default(),
+            )
+            .map_err(|e| anyhow::anyhow!("Invalid value for COMMAND_TIMEOUTS: {}", e))?,
+            load_shed_queue_depth: env_parse("LOAD_SHED_QUEUE_DEPTH")?.unwrap_or(80),
+            body_limits,
+            slo: SloPolicy::parse(
+                env_parse("SLO_SUCCESS_TARGET")?.unwrap_or(99.0),
+                env_parse("SLO_BURN_RATE_ALERT")?.unwrap_or(14.4),
+                &env_var("SLO_OBJECTIVES").unwrap_or_default(),
+            )
+            .map_err(|e| anyhow::anyhow!("Invalid SLO configuration: {}", e))?,
+            legacy_api_sunset: match env_var("LEGACY_API_SUNSET").as_deref() {
+                Some("none") => None,
+                Some(_) => env_parse("LEGACY_API_SUNSET")?,
+                None => chrono::NaiveDate::from_ymd_opt(2027, 4, 30),
+            },
+            cors: CorsPolicy::parse(
+                &env_var("CORS_ALLOWED_ORIGINS").unwrap_or_default(),
+                &env_var("CORS_ALLOWED_METHODS").unwrap_or_default(),
+                &env_var("CORS_ALLOWED_HEADERS").unwrap_or_default(),
+                &env_var("CORS_EXPOSE_HEADERS").unwrap_or_default(),
+                env_bool("CORS_ALLOW_CREDENTIALS")?.unwrap_or(false),
+                env_parse("CORS_MAX_AGE_SECS")?.map(Duration::from_secs),
+            )
+            .map_err(|e| anyhow::anyhow!("Invalid CORS configuration: {}", e))?,
+            security_headers: SecurityHeaders {
+                enabled: env_bool("SECURITY_HEADERS")?.unwrap_or(true),
+                hsts_max_age: Some(Duration::from_secs(
+                    env_parse("HSTS_MAX_AGE_SECS")?.unwrap_or(default_hsts_max_age),
+                ))
+                .filter(|max_age| !max_age.is_zero()),
+                hsts_include_subdomains: env_bool("HSTS_INCLUDE_SUBDOMAINS")?.unwrap_or(false),
+                frame_options: env_parse::<FrameOptions>("FRAME_OPTIONS")?
+                    .unwrap_or(FrameOptions::Deny),
+            },
+        })
+    }
+}
@@ -0,0 +1,226 @@
+// src/consume_batches.rs
+//
+// Note consumption split into transactions
+//
+// Consuming an account's whole inbox in one transaction can exceed the
+// transaction kernel's execution limits once enough notes pile up (an escrow
+// account collecting many payments, the treasury, an orphaned account being
+// swept). Consumption is therefore split into batches of at most
+// MAX_NOTES_PER_TX notes, each its own transaction, submitted one after the
+// other from the same account.
+//
+// Every batch marks its notes consumed in the service records as soon as it is
+// submitted. When a batch fails, the batches before it stay consumed: the
+// result lists them with the error, and the notes left over are consumed by the
+// next attempt. A failure of the first batch is an error, as nothing moved.
+//
+// Consuming a whole inbox skips quarantined notes, such as dust or notes whose
+// scripts are not whitelisted (note_quarantine.rs); the result lists them as
+// refused.
+//
+// The result carries the spend receipt of each batch: the nullifiers it
+// consumed and the account commitment it left (spend_receipts.rs).
+//
+// Used by POST /consume-note, escrow release (escrow.rs), the recovery sweep
+// (recovery.rs) and treasury collection (treasury.rs).
+
+use anyhow::Result;
+use miden_client::{account::AccountId, note::NoteId, transaction::TransactionRequestBuilder};
+use serde::Serialize;
+
+use crate::{
+    account_id_to_hex,
+    note_inspection::NoteInspection,
+    spend_receipts::{self, SpendReceipt},
+    MidenClientWrapper,
+};
+
+/// Notes consumed by one transaction.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsumeBatch {
+    pub tx_id: String,
+    pub note_ids: Vec<String>,
+}
+
+/// Outcome of consuming an account's notes, batch by batch.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConsumedNotes {
+    /// Submitted batches, in order
+    pub batches: Vec<ConsumeBatch>,
+    /// Notes that were consumable, submitted or not
+    pub notes_found: usize,
+    /// Why the batches after the last one were not submitted
+    pub error: Option<String>,
+    /// Quarantined notes left alone
+    pub refused: Vec<NoteInspection>,
+    /// Receipt of each submitted batch (spend_receipts.rs)
+    pub receipts: Vec<SpendReceipt>,
+}
+
+impl ConsumedNotes {
+    /// Transaction IDs in submission order.
+    pub fn transaction_ids(&self) -> Vec<String> {
+        self.batches.iter().map(|b| b.tx_id.clone()).collect()
+    }
+
+    pub fn note_ids(&self) -> Vec<String> {
+        self.batches
+            .iter()
+            .flat_map(|b| b.note_ids.iter().cloned())
+            .collect()
+    }
+
+    /// Transaction that consumed a note.
+    pub fn tx_of(&self, note_id: &str) -> Option<&str> {
+        self.batches
+            .iter()
+            .find(|b| b.note_ids.iter().any(|id| id == note_id))
+            .map(|b| b.tx_id.as_str())
+    }
+
+    /// Nullifiers of the notes consumed, in batch order.
+    pub fn nullifiers(&self) -> Vec<String> {
+        spend_receipts::nullifiers(&self.receipts)
+    }
+
+    /// Commitment of the account after the last batch.
+    pub fn account_commitment(&self) -> Option<String> {
+        self.receipts
+            .last()
+            .map(|r| r.final_account_commitment.clone())
+    }
+
+    pub fn refused_note_ids(&self) -> Vec<String> {
+        self.refused.iter().map(|r| r.note_id.clone()).collect()
+    }
+
+    /// Last transaction ID, or an error if some batch was not submitted or
+    /// every note was refused.
+    pub fn into_tx_id(self) -> Result<String> {
+        if let Some(error) = self.error {
+            return Err(anyhow::anyhow!(
+                "Consumed {} of {} notes ({}) before a batch failed: {}",
+                self.note_ids().len(),
+                self.notes_found,
+                self.transaction_ids().join(", "),
+                error
+            ));
+        }
+        if self.batches.is_empty() && !self.refused.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Conflict: {} quarantined note(s) not consumed: {}",
+                self.refused.len(),
+                self.refused_note_ids().join(", ")
+            ));
+        }
+        self.batches
+            .last()
+            .map(|b| b.tx_id.clone())
+            .ok_or_else(|| anyhow::anyhow!("No consumable notes found"))
+    }
+}
+
+// ============================================================================
+// CLIENT INTEGRATION
+// ============================================================================
+
+impl MidenClientWrapper {
+    /// Consumes every consumable note for the account, at most
+    /// MAX_NOTES_PER_TX per transaction.
+    pub(crate) async fn submit_consume_all(
+        &mut self,
+        account_id: AccountId,
+    ) -> Result<ConsumedNotes> {
+        // Sync state so consumable notes reflect latest network view
+        self.sync_state().await?;
+
+        let consumable_notes = self.client.get_consumable_notes(Some(account_id)).await?;
+        if consumable_notes.is_empty() {
+            return Err(anyhow::anyhow!("No consumable notes found"));
+        }
+
+        let account_hex = account_id_to_hex(account_id);
+        let mut note_ids = Vec::new();
+        let mut refused = Vec::new();
+        for (note, _) in &consumable_notes {
+            match self.screen_note(&account_hex, note) {
+                (_, true) => note_ids.push(note.id()),
+                (inspection, false) => refused.push(inspection),
+            }
+        }
+        if note_ids.is_empty() {
+            return Ok(ConsumedNotes {
+                notes_found: refused.len(),
+                refused,
+                ..ConsumedNotes::default()
+            });
+        }
+
+        let mut consumed = self.submit_consume_notes(account_id, note_ids).await?;
+        consumed.refused = refused;
+        Ok(consumed)
+    }
+
+    /// Consumes the given notes into the account, at most MAX_NOTES_PER_TX per
+    /// transaction, and syncs afterwards.
+    pub(crate) async fn submit_consume_notes(
+        &mut self,
+        account_id: AccountId,
+        note_ids: Vec<NoteId>,
+    ) -> Result<ConsumedNotes> {
+        let batch_size = self.config.max_notes_per_tx.max(1);
+        tracing::info!(
+            "Consuming {} note(s) in {} transaction(s)",
+            note_ids.len(),
+            note_ids.len().div_ceil(batch_size)
+        );
+
+        let mut consumed = ConsumedNotes {
+            notes_found: note_ids.len(),
+            ..ConsumedNotes::default()
+        };
+        for batch in note_ids.chunks(batch_size) {
+            let batch_ids: Vec<String> = batch.iter().map(|id| id.to_string()).collect();
+            let submitted =
+                match TransactionRequestBuilder::new().build_consume_notes(batch.to_vec()) {
+                    Ok(request) => self
+                        .client
+                        .submit_new_transaction(account_id, request)
+                        .await
+                        .map_err(anyhow::Error::from),
+                    Err(e) => Err(e.into()),
+                };
+
+            match submitted {
+                Ok(transaction_id) => {
+                    let tx_id = transaction_id.to_string();
+                    tracing::info!("Consumed {} note(s). TX: {}", batch_ids.len(), tx_id);
+                    for note_id in &batch_ids {
+                        self.records
+                            .mark_note_consumed(note_id, Some(tx_id.clone()));
+                    }
+                    consumed.batches.push(ConsumeBatch {
+                        tx_id,
+                        note_ids: batch_ids,
+                    });
+                }
+                Err(e) if consumed.batches.is_empty() => return Err(e),
+                Err(e) => {
+                    tracing::warn!(
+                        "⚠️  Consume batch failed after {} transaction(s): {}",
+                        consumed.batches.len(),
+                        e
+                    );
+                    consumed.error = Some(e.to_string());
+                    break;
+                }
+            }
+        }
+
+        // Sync after the transactions to update local state (balances/notes)
+        self.sync_state().await?;
+
+        consumed.receipts = self.spend_receipts(&consumed.transaction_ids()).await;
+        Ok(consumed)
+    }
+}
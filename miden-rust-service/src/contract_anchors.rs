@@ -0,0 +1,196 @@
+// src/contract_anchors.rs
+//
+// Sales contract anchors
+//
+// A property transfer may name the SHA-256 of the signed purchase agreement it
+// carries out (POST /transfer-property {"contract_hash": "..."}). The transfer
+// note is then bound to that contract: whoever holds the note and the
+// agreement can check that the transfer was made under it.
+//
+// The standard P2ID script takes exactly two inputs, the target account ID, and
+// fails on any other count, so a hash among the inputs would leave the note
+// unconsumable by the buyer. The hash goes into the note's recipient instead,
+// as its serial number (like notarizations, notary.rs):
+//   serial_num = RPO256("obscura-sales-contract" || contract_hash bytes
+//                       || property_id)
+// The recipient commits to the serial number, script and inputs, and the note
+// ID to the recipient, so the note ID commits to the contract as inputs would.
+// The property ID keeps a contract from anchoring the transfer of another
+// property. A contract anchors one transfer of its property; a second one is
+// refused.
+//
+// GET /notes/:note_id/contract-anchor?contract_hash=...&property_id=...
+// reads the note back from the client store (sent from here, or received or
+// imported, note_files.rs) and checks that it is a P2ID note whose serial
+// number is the one the contract gives. Transfers anchored here are recorded
+// (records.rs), so for those the contract and property may be left out.
+
+use anyhow::Result;
+use miden_client::{
+    crypto::Rpo256,
+    note::{NoteDetails, NoteId, WellKnownNote},
+    Word,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{notary::normalize_document_hash, MidenClientWrapper};
+
+const CONTRACT_ANCHOR_DOMAIN: &[u8] = b"obscura-sales-contract";
+
+/// A property transfer anchored to a sales contract.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractAnchor {
+    pub note_id: String,
+    pub property_id: String,
+    /// SHA-256 of the purchase agreement (hex, lowercase)
+    pub contract_hash: String,
+    pub tx_id: String,
+    pub anchored_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AnchorQuery {
+    #[serde(default)]
+    pub contract_hash: Option<String>,
+    #[serde(default)]
+    pub property_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AnchorVerification {
+    pub note_id: String,
+    pub property_id: String,
+    pub contract_hash: String,
+    pub verified: bool,
+    /// Why the note does not verify
+    pub reason: Option<String>,
+    /// Whether the note is included in a block
+    pub committed: bool,
+    /// The anchor recorded when this service sent the note
+    pub anchor: Option<ContractAnchor>,
+}
+
+/// Serial number of the transfer note of `property_id` under a contract
+/// (normalized hash).
+pub fn anchor_serial_num(contract_hash: &str, property_id: &str) -> Result<Word> {
+    let mut preimage = CONTRACT_ANCHOR_DOMAIN.to_vec();
+    preimage.extend(hex::decode(contract_hash)?);
+    preimage.extend(property_id.as_bytes());
+    Ok(Rpo256::hash(&preimage))
+}
+
+// ============================================================================
+// CLIENT INTEGRATION
+// ============================================================================
+
+crate::operations! {
+    /// Checks that a note is anchored to a sales contract.
+    VerifyContractAnchor {
+        note_id: String,
+        query: AnchorQuery,
+    } -> AnchorVerification;
+    |client, op| client.verify_contract_anchor(&op.note_id, op.query).await
+}
+
+impl MidenClientWrapper {
+    /// Refuses a contract that already anchors a transfer of the property.
+    pub(crate) fn check_contract_unused(
+        &self,
+        contract_hash: &str,
+        property_id: &str,
+    ) -> Result<()> {
+        let existing = self
+            .records
+            .contract_anchors
+            .values()
+            .find(|a| a.contract_hash == contract_hash && a.property_id == property_id);
+        if let Some(anchor) = existing {
+            return Err(anyhow::anyhow!(
+                "Conflict: contract {} already anchors transfer note {} of {}",
+                contract_hash,
+                anchor.note_id,
+                property_id
+            ));
+        }
+        Ok(())
+    }
+
+    /// The anchor recorded for a transfer transaction.
+    pub fn contract_anchor_of_tx(&self, tx_id: &str) -> Option<ContractAnchor> {
+        self.records
+            .contract_anchors
+            .values()
+            .find(|a| a.tx_id == tx_id)
+            .cloned()
+    }
+
+    /// Verifies a note against a contract (see module docs). The contract
+    /// and property default to the recorded anchor of the note.
+    pub async fn verify_contract_anchor(
+        &self,
+        note_id: &str,
+        query: AnchorQuery,
+    ) -> Result<AnchorVerification> {
+        let id = NoteId::try_from_hex(note_id)
+            .map_err(|e| anyhow::anyhow!("Invalid note ID {}: {}", note_id, e))?;
+        let anchor = self.records.contract_anchors.get(&id.to_string()).cloned();
+
+        let contract_hash = query
+            .contract_hash
+            .or_else(|| anchor.as_ref().map(|a| a.contract_hash.clone()))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "contract_hash is required: note {} has no recorded anchor",
+                    note_id
+                )
+            })?;
+        let contract_hash = normalize_document_hash(&contract_hash)?;
+        let property_id = query
+            .property_id
+            .or_else(|| anchor.as_ref().map(|a| a.property_id.clone()))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "property_id is required: note {} has no recorded anchor",
+                    note_id
+                )
+            })?;
+
+        let (details, committed) = if let Some(record) = self.client.get_output_note(id).await? {
+            let committed = record.inclusion_proof().is_some();
+            (NoteDetails::try_from(record)?, committed)
+        } else if let Some(record) = self.client.get_input_note(id).await? {
+            (record.details().clone(), record.inclusion_proof().is_some())
+        } else {
+            return Err(anyhow::anyhow!(
+                "Note {} not found in the client store",
+                note_id
+            ));
+        };
+
+        let recipient = details.recipient();
+        let reason = if details.id() != id {
+            Some("note details do not hash to the note ID")
+        } else if recipient.script().root() != WellKnownNote::P2ID.script_root() {
+            Some("not a P2ID note")
+        } else if recipient.serial_num() != anchor_serial_num(&contract_hash, &property_id)? {
+            Some("serial number does not commit to this contract and property")
+        } else {
+            None
+        };
+
+        tracing::info!(
+            "Contract anchor of note {}: {}",
+            note_id,
+            reason.unwrap_or("verified")
+        );
+        Ok(AnchorVerification {
+            note_id: id.to_string(),
+            property_id,
+            contract_hash,
+            verified: reason.is_none(),
+            reason: reason.map(str::to_string),
+            committed,
+            anchor,
+        })
+    }
+}
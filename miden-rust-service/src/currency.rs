@@ -0,0 +1,479 @@
+// src/currency.rs
+//
+// Units of amounts and prices
+//
+// Token amounts are counted in base units of the faucet's token: the service
+// token (PROP) has 8 decimals, so 150000000 is 1.5 PROP. Property prices are
+// off-chain and counted in minor units of a currency (cents for EUR). A price
+// can be submitted as {"amount_minor": 12500000, "currency": "EUR"}. A bare
+// number is still accepted and read as minor units of PRICE_CURRENCY. The
+// currency is kept with the property record.
+//
+// Accepted currencies are the ISO 4217 codes in CURRENCIES, plus the service
+// token for prices settled in tokens.
+//
+// GET /formatting?locale=de-DE tells clients how to display amounts in a
+// locale: separators, where the symbol goes, and the symbol and decimals of
+// every currency and token, each with a formatted example. Balances list each
+// fungible asset with its symbol, its decimals and a locale-neutral decimal
+// string.
+
+use anyhow::Result;
+use miden_client::asset::FungibleAsset;
+use serde::{Deserialize, Serialize};
+
+use crate::{account_id_to_hex, records::PropertyRecord, MidenClientWrapper};
+
+/// Symbol of the service's fungible token, issued by the faucet account
+pub const SERVICE_TOKEN_SYMBOL: &str = "PROP";
+pub const SERVICE_TOKEN_DECIMALS: u8 = 8;
+
+/// Amount shown in the examples of GET /formatting, in minor units
+const EXAMPLE_AMOUNT_MINOR: u64 = 123_456_789;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Currency {
+    pub code: &'static str,
+    pub symbol: &'static str,
+    /// Digits after the decimal point (ISO 4217 minor units)
+    pub minor_units: u8,
+}
+
+pub const CURRENCIES: &[Currency] = &[
+    Currency {
+        code: "EUR",
+        symbol: "€",
+        minor_units: 2,
+    },
+    Currency {
+        code: "USD",
+        symbol: "$",
+        minor_units: 2,
+    },
+    Currency {
+        code: "GBP",
+        symbol: "£",
+        minor_units: 2,
+    },
+    Currency {
+        code: "CHF",
+        symbol: "CHF",
+        minor_units: 2,
+    },
+    Currency {
+        code: "SEK",
+        symbol: "kr",
+        minor_units: 2,
+    },
+    Currency {
+        code: "DKK",
+        symbol: "kr.",
+        minor_units: 2,
+    },
+    Currency {
+        code: "NOK",
+        symbol: "kr",
+        minor_units: 2,
+    },
+    Currency {
+        code: "PLN",
+        symbol: "zł",
+        minor_units: 2,
+    },
+    Currency {
+        code: "CZK",
+        symbol: "Kč",
+        minor_units: 2,
+    },
+    Currency {
+        code: "HUF",
+        symbol: "Ft",
+        minor_units: 2,
+    },
+    Currency {
+        code: "JPY",
+        symbol: "¥",
+        minor_units: 0,
+    },
+];
+
+/// The service token, as a currency prices can be given in
+pub const SERVICE_TOKEN: Currency = Currency {
+    code: SERVICE_TOKEN_SYMBOL,
+    symbol: SERVICE_TOKEN_SYMBOL,
+    minor_units: SERVICE_TOKEN_DECIMALS,
+};
+
+/// Looks up a currency (or the service token) by code, case-insensitively.
+pub fn find_currency(code: &str) -> Option<Currency> {
+    let code = code.trim().to_uppercase();
+    CURRENCIES
+        .iter()
+        .chain(std::iter::once(&SERVICE_TOKEN))
+        .find(|currency| currency.code == code)
+        .copied()
+}
+
+/// A price in minor units of an explicit currency.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Money {
+    pub amount_minor: u64,
+    pub currency: String,
+}
+
+/// A submitted price: with its currency, or a bare number in minor units of
+/// the configured PRICE_CURRENCY (the format of earlier requests, also found
+/// in persisted mint jobs and retry operations).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PriceInput {
+    Money(Money),
+    Minor(u64),
+}
+
+impl PriceInput {
+    pub fn amount_minor(&self) -> u64 {
+        match self {
+            PriceInput::Money(money) => money.amount_minor,
+            PriceInput::Minor(amount) => *amount,
+        }
+    }
+
+    /// The currency given with the price, if any.
+    pub fn currency(&self) -> Option<&str> {
+        match self {
+            PriceInput::Money(money) => Some(&money.currency),
+            PriceInput::Minor(_) => None,
+        }
+    }
+
+    /// The price with its currency code normalized, `default_currency` for a
+    /// bare number. Unknown currencies are refused.
+    pub fn resolve(&self, default_currency: &str) -> Result<Money> {
+        let code = self.currency().unwrap_or(default_currency);
+        let currency =
+            find_currency(code).ok_or_else(|| anyhow::anyhow!("Unknown currency: {}", code))?;
+        Ok(Money {
+            amount_minor: self.amount_minor(),
+            currency: currency.code.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolPosition {
+    /// "€1,234.56"
+    Before,
+    /// "€ 1.234,56"
+    BeforeWithSpace,
+    /// "1.234,56 €"
+    AfterWithSpace,
+}
+
+/// Display conventions of a language. Regional variants ("de-CH", "en-GB")
+/// use the conventions of their language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    En,
+    De,
+    Fr,
+    Nl,
+    Es,
+    It,
+}
+
+impl std::str::FromStr for Locale {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let language = s.split(['-', '_']).next().unwrap_or_default();
+        match language.trim().to_lowercase().as_str() {
+            "en" => Ok(Locale::En),
+            "de" => Ok(Locale::De),
+            "fr" => Ok(Locale::Fr),
+            "nl" => Ok(Locale::Nl),
+            "es" => Ok(Locale::Es),
+            "it" => Ok(Locale::It),
+            _ => Err(anyhow::anyhow!("Unsupported locale: {}", s)),
+        }
+    }
+}
+
+impl Locale {
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::De => "de",
+            Locale::Fr => "fr",
+            Locale::Nl => "nl",
+            Locale::Es => "es",
+            Locale::It => "it",
+        }
+    }
+
+    pub fn decimal_separator(self) -> char {
+        match self {
+            Locale::En => '.',
+            _ => ',',
+        }
+    }
+
+    pub fn grouping_separator(self) -> char {
+        match self {
+            Locale::En => ',',
+            // Narrow no-break space
+            Locale::Fr => '\u{202f}',
+            _ => '.',
+        }
+    }
+
+    pub fn symbol_position(self) -> SymbolPosition {
+        match self {
+            Locale::En => SymbolPosition::Before,
+            Locale::Nl => SymbolPosition::BeforeWithSpace,
+            _ => SymbolPosition::AfterWithSpace,
+        }
+    }
+
+    /// An amount in base units as a decimal number, e.g. 123456789 with 2
+    /// decimals is "1,234,567.89" in English and "1.234.567,89" in German.
+    pub fn format_number(self, amount: u64, decimals: u8) -> String {
+        let digits = decimal_string(amount, decimals);
+        let (whole, fraction) = match digits.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (digits.as_str(), None),
+        };
+
+        let mut formatted = String::new();
+        for (i, digit) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i) % 3 == 0 {
+                formatted.push(self.grouping_separator());
+            }
+            formatted.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            formatted.push(self.decimal_separator());
+            formatted.push_str(fraction);
+        }
+        formatted
+    }
+
+    /// An amount with its currency or token symbol, placed as the locale does.
+    /// Symbols made of letters ("CHF", "PROP") are always set apart by a space.
+    pub fn format_money(self, amount: u64, currency: &Currency) -> String {
+        self.format_amount(amount, currency.symbol, currency.minor_units)
+    }
+
+    /// `format_money` for a symbol and decimals outside the currency table,
+    /// such as a registered settlement token's.
+    pub fn format_amount(self, amount: u64, symbol: &str, decimals: u8) -> String {
+        let number = self.format_number(amount, decimals);
+        let word_symbol = symbol.chars().all(|c| c.is_ascii_alphabetic());
+        match self.symbol_position() {
+            SymbolPosition::Before if !word_symbol => format!("{}{}", symbol, number),
+            SymbolPosition::Before | SymbolPosition::BeforeWithSpace => {
+                format!("{}\u{a0}{}", symbol, number)
+            }
+            SymbolPosition::AfterWithSpace => format!("{}\u{a0}{}", number, symbol),
+        }
+    }
+}
+
+/// An amount in base units as a plain decimal string ("1.50000000"), without
+/// grouping, for clients that do their own formatting.
+pub fn decimal_string(amount: u64, decimals: u8) -> String {
+    if decimals == 0 {
+        return amount.to_string();
+    }
+    let digits = format!("{:0>width$}", amount, width = decimals as usize + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals as usize);
+    format!("{}.{}", whole, fraction)
+}
+
+/// A fungible asset balance with the units needed to display it.
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenAmount {
+    pub faucet_id: String,
+    /// Base units
+    pub amount: u64,
+    /// None for tokens of faucets neither created nor registered here
+    pub symbol: Option<String>,
+    pub decimals: u8,
+    pub amount_decimal: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DisplayFormat {
+    pub locale: String,
+    pub decimal_separator: char,
+    pub grouping_separator: char,
+    pub symbol_position: SymbolPosition,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CurrencyFormat {
+    #[serde(flatten)]
+    pub currency: Currency,
+    /// Faucet issuing the token; None for fiat currencies
+    pub faucet_id: Option<String>,
+    /// EXAMPLE_AMOUNT_MINOR formatted for the locale
+    pub example: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FormattingMetadata {
+    pub display: DisplayFormat,
+    /// Currency of prices submitted as bare numbers
+    pub default_price_currency: String,
+    pub example_amount_minor: u64,
+    pub currencies: Vec<CurrencyFormat>,
+    pub tokens: Vec<CurrencyFormat>,
+}
+
+impl FormattingMetadata {
+    pub fn new(locale: Locale, default_price_currency: &str, faucet_id: Option<String>) -> Self {
+        let format = |currency: &Currency, faucet_id: Option<String>| CurrencyFormat {
+            currency: *currency,
+            faucet_id,
+            example: locale.format_money(EXAMPLE_AMOUNT_MINOR, currency),
+        };
+        Self {
+            display: DisplayFormat {
+                locale: locale.tag().to_string(),
+                decimal_separator: locale.decimal_separator(),
+                grouping_separator: locale.grouping_separator(),
+                symbol_position: locale.symbol_position(),
+            },
+            default_price_currency: default_price_currency.to_string(),
+            example_amount_minor: EXAMPLE_AMOUNT_MINOR,
+            currencies: CURRENCIES
+                .iter()
+                .map(|currency| format(currency, None))
+                .collect(),
+            tokens: vec![format(&SERVICE_TOKEN, faucet_id)],
+        }
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    GetFormatting {
+        locale: Locale,
+    } -> FormattingMetadata;
+    |client, op| Ok(client.formatting_metadata(op.locale))
+}
+
+impl MidenClientWrapper {
+    /// A fungible asset with the units of its token. The service token and
+    /// registered settlement tokens (settlement_tokens.rs) are known; other
+    /// tokens are reported in base units.
+    pub(crate) fn token_amount(&self, asset: &FungibleAsset) -> TokenAmount {
+        let (symbol, decimals) = match self.token_units(asset.faucet_id()) {
+            Some((symbol, decimals)) => (Some(symbol), decimals),
+            None => (None, 0),
+        };
+        TokenAmount {
+            faucet_id: account_id_to_hex(asset.faucet_id()),
+            amount: asset.amount(),
+            symbol,
+            decimals,
+            amount_decimal: decimal_string(asset.amount(), decimals),
+        }
+    }
+
+    /// Currency of a property's price; PRICE_CURRENCY for records minted
+    /// before prices carried one.
+    pub(crate) fn price_currency<'a>(&'a self, property: &'a PropertyRecord) -> &'a str {
+        property
+            .price_currency
+            .as_deref()
+            .unwrap_or(&self.config.price_currency)
+    }
+
+    /// Display metadata of currencies and tokens for a locale.
+    pub fn formatting_metadata(&self, locale: Locale) -> FormattingMetadata {
+        FormattingMetadata::new(
+            locale,
+            &self.config.price_currency,
+            self.faucet_account_id.map(account_id_to_hex),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimal_strings_pad_small_amounts_and_keep_every_digit() {
+        assert_eq!(decimal_string(150_000_000, 8), "1.50000000");
+        assert_eq!(decimal_string(5, 8), "0.00000005");
+        assert_eq!(decimal_string(0, 2), "0.00");
+        assert_eq!(decimal_string(1234, 0), "1234");
+        assert_eq!(decimal_string(u64::MAX, 8), "184467440737.09551615");
+        assert_eq!(decimal_string(u64::MAX, 20), "0.18446744073709551615");
+    }
+
+    #[test]
+    fn numbers_are_grouped_and_separated_per_locale() {
+        assert_eq!(Locale::En.format_number(123_456_789, 2), "1,234,567.89");
+        assert_eq!(Locale::De.format_number(123_456_789, 2), "1.234.567,89");
+        assert_eq!(
+            Locale::Fr.format_number(123_456_789, 2),
+            "1\u{202f}234\u{202f}567,89"
+        );
+        // Group boundaries
+        assert_eq!(Locale::En.format_number(99_999, 2), "999.99");
+        assert_eq!(Locale::En.format_number(100_000, 2), "1,000.00");
+        assert_eq!(Locale::En.format_number(1_000_000, 0), "1,000,000");
+        assert_eq!(
+            Locale::En.format_number(u64::MAX, 0),
+            "18,446,744,073,709,551,615"
+        );
+    }
+
+    #[test]
+    fn symbols_go_where_the_locale_puts_them() {
+        let eur = find_currency("EUR").unwrap();
+        assert_eq!(Locale::En.format_money(123_456, &eur), "€1,234.56");
+        assert_eq!(Locale::Nl.format_money(123_456, &eur), "€\u{a0}1.234,56");
+        assert_eq!(Locale::De.format_money(123_456, &eur), "1.234,56\u{a0}€");
+        // Letter symbols are set apart even where the symbol comes first
+        assert_eq!(
+            Locale::En.format_money(150_000_000, &SERVICE_TOKEN),
+            "PROP\u{a0}1.50000000"
+        );
+    }
+
+    #[test]
+    fn locales_and_currencies_are_matched_loosely() {
+        assert_eq!("de-CH".parse::<Locale>().unwrap(), Locale::De);
+        assert_eq!("en_GB".parse::<Locale>().unwrap(), Locale::En);
+        assert!("pt-BR".parse::<Locale>().is_err());
+        assert_eq!(find_currency(" eur ").unwrap().code, "EUR");
+        assert_eq!(find_currency("prop").unwrap().minor_units, 8);
+        assert!(find_currency("XYZ").is_none());
+    }
+
+    #[test]
+    fn bare_prices_take_the_default_currency() {
+        let bare = PriceInput::Minor(12_500_000);
+        assert_eq!(
+            bare.resolve("eur").unwrap(),
+            Money {
+                amount_minor: 12_500_000,
+                currency: "EUR".to_string(),
+            }
+        );
+        let priced = PriceInput::Money(Money {
+            amount_minor: 100,
+            currency: "jpy".to_string(),
+        });
+        assert_eq!(priced.resolve("EUR").unwrap().currency, "JPY");
+        assert!(PriceInput::Minor(1).resolve("XYZ").is_err());
+    }
+}
@@ -0,0 +1,818 @@
+// src/custodial.rs
+//
+// Custodial wallets for email-onboarded users
+//
+// Buyers who have no Miden wallet of their own can sign in with an email
+// address and a one-time code; the service then keeps a wallet for them:
+//
+//   POST /custodial/otp {email}
+//     -> a six-digit code is sent to the address (the user is created on
+//        first request)
+//   POST /custodial/sessions {challenge_id, code}
+//     -> on the first successful sign-in the user's wallet is created, then a
+//        session token is issued (X-Custodial-Session)
+//
+// Wallets are derived from the master secret (secrets.rs), like escrow
+// accounts: the user record keeps the wallet number and generation, so the
+// account and its key can be re-derived from it. Without a master secret
+// there is no onboarding.
+//
+// With a session the user can claim the notes sent to the wallet
+// (POST /custodial/notes/consume) and pay from it. A payment needs a second
+// code, sent for that payment alone:
+//
+//   POST /custodial/payments {to_account_id, amount}
+//     -> a code naming the recipient and amount is sent to the user
+//   POST /custodial/payments/confirm {challenge_id, code}
+//     -> the P2ID note is sent from the wallet
+//
+// The session token is also accepted as X-Portal-Token on the read-only
+// portal (portal.rs), for balances, notes, escrows and payment history.
+//
+// Codes are delivered by OTP_DELIVERY: "http" posts them to a mailer
+// (OTP_DELIVERY_URL), "log" writes them to the service log (test networks
+// only). Delivery runs in the HTTP handler, outside the client task. Only
+// hashes of codes and session tokens are stored. Codes expire after
+// OTP_TTL_SECS, are single-use, allow OTP_MAX_ATTEMPTS wrong guesses and
+// cannot be re-requested for the same purpose within OTP_RESEND_SECS.
+
+use anyhow::Result;
+use miden_client::{auth::AuthSecretKey, crypto::rpo_falcon512::SecretKey};
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc, time::Duration};
+
+use crate::{
+    account_id_to_hex, client,
+    consume_batches::ConsumedNotes,
+    data_subjects::{erase, ErasedField},
+    escrow::EscrowAuthError,
+    read_cache::Touched,
+    reconcile::parse_hex_account_id,
+    MidenClientWrapper,
+};
+
+const TOKEN_PREFIX: &str = "obc_";
+/// Minimum time between two codes for the same user and purpose
+pub const OTP_RESEND_SECS: i64 = 60;
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OtpRequestInput {
+    pub email: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OtpVerifyInput {
+    pub challenge_id: u64,
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CustodialPaymentInput {
+    /// Recipient: alice, bob or a hex AccountId
+    pub to_account_id: String,
+    /// Service token amount
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustodialUser {
+    pub user_id: u64,
+    /// Normalized (trimmed, lowercase) address
+    pub email: String,
+    /// The user's wallet (hex), created on the first sign-in
+    pub account_id: Option<String>,
+    /// Master secret wallet number and generation the wallet derives from
+    pub wallet_id: Option<u64>,
+    pub generation: Option<u32>,
+    pub created_at: i64,
+    pub verified_at: Option<i64>,
+}
+
+/// What a code is good for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OtpPurpose {
+    SignIn,
+    /// Confirms one payment from the user's wallet
+    Payment {
+        to_account_id: String,
+        amount: u64,
+    },
+}
+
+impl OtpPurpose {
+    fn same_kind(&self, other: &OtpPurpose) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtpChallenge {
+    pub challenge_id: u64,
+    pub user_id: u64,
+    pub purpose: OtpPurpose,
+    pub issued_at: i64,
+    pub expires_at: i64,
+    /// Wrong codes presented so far
+    pub attempts: u32,
+    pub used: bool,
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    code_hash: String,
+}
+
+impl OtpChallenge {
+    fn is_open(&self, now: i64) -> bool {
+        !self.used && now < self.expires_at
+    }
+
+    /// Copy without the code hash, for API responses.
+    pub fn public(&self) -> OtpChallenge {
+        OtpChallenge {
+            code_hash: String::new(),
+            ..self.clone()
+        }
+    }
+}
+
+/// A freshly issued code and where it goes; the code itself never reaches
+/// the API response.
+#[derive(Debug, Clone)]
+pub struct IssuedOtp {
+    pub challenge: OtpChallenge,
+    pub email: String,
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustodialSession {
+    pub session_id: u64,
+    pub user_id: u64,
+    pub account_id: String,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub ended_at: Option<i64>,
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    token_hash: String,
+}
+
+impl CustodialSession {
+    pub fn is_active(&self, now: i64) -> bool {
+        self.ended_at.is_none() && now < self.expires_at
+    }
+
+    /// Copy without the token hash, for API responses.
+    pub fn public(&self) -> CustodialSession {
+        CustodialSession {
+            token_hash: String::new(),
+            ..self.clone()
+        }
+    }
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Codes are hashed with their challenge, so equal codes hash apart.
+fn hash_code(challenge_id: u64, code: &str) -> String {
+    hex::encode(Sha256::digest(
+        format!("{}:{}", challenge_id, code).as_bytes(),
+    ))
+}
+
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CustodialStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    users: BTreeMap<u64, CustodialUser>,
+    #[serde(default)]
+    challenges: BTreeMap<u64, OtpChallenge>,
+    #[serde(default)]
+    sessions: BTreeMap<u64, CustodialSession>,
+    #[serde(default)]
+    next_user_id: u64,
+    #[serde(default)]
+    next_challenge_id: u64,
+    #[serde(default)]
+    next_session_id: u64,
+}
+
+impl CustodialStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<CustodialStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            CustodialStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Whether `account_hex` is a user's wallet, current or held by one of
+    /// their sessions.
+    pub fn holds_account(&self, account_hex: &str) -> bool {
+        self.users
+            .values()
+            .filter_map(|u| u.account_id.as_deref())
+            .chain(self.sessions.values().map(|s| s.account_id.as_str()))
+            .any(|a| a.eq_ignore_ascii_case(account_hex))
+    }
+
+    pub fn user(&self, user_id: u64) -> Result<&CustodialUser> {
+        self.users
+            .get(&user_id)
+            .ok_or_else(|| anyhow::anyhow!("Custodial user {} not found", user_id))
+    }
+
+    /// The user registered with `email`, created if there is none.
+    fn enroll(&mut self, email: &str) -> u64 {
+        let email = normalize_email(email);
+        if let Some(user) = self.users.values().find(|u| u.email == email) {
+            return user.user_id;
+        }
+
+        self.next_user_id += 1;
+        let user = CustodialUser {
+            user_id: self.next_user_id,
+            email,
+            account_id: None,
+            wallet_id: None,
+            generation: None,
+            created_at: chrono::Utc::now().timestamp(),
+            verified_at: None,
+        };
+        self.users.insert(user.user_id, user);
+        self.next_user_id
+    }
+
+    /// Issues a code for `purpose` to a user (see module docs for the resend
+    /// interval). Returns it with the plaintext code.
+    pub fn issue_otp(
+        &mut self,
+        user_id: u64,
+        purpose: OtpPurpose,
+        ttl_secs: u64,
+    ) -> Result<IssuedOtp> {
+        let email = self.user(user_id)?.email.clone();
+        let now = chrono::Utc::now().timestamp();
+        // Spent and expired codes are of no further use
+        self.challenges.retain(|_, c| c.is_open(now));
+
+        if let Some(recent) = self.challenges.values().find(|c| {
+            c.user_id == user_id
+                && c.purpose.same_kind(&purpose)
+                && now - c.issued_at < OTP_RESEND_SECS
+        }) {
+            return Err(anyhow::anyhow!(
+                "Conflict: a code was sent less than {}s ago (challenge {})",
+                OTP_RESEND_SECS,
+                recent.challenge_id
+            ));
+        }
+        // A new code replaces the open ones for the same purpose
+        self.challenges
+            .retain(|_, c| !(c.user_id == user_id && c.purpose.same_kind(&purpose)));
+
+        self.next_challenge_id += 1;
+        let challenge_id = self.next_challenge_id;
+        let code = format!("{:06}", rand::rng().random_range(0..1_000_000u32));
+        let challenge = OtpChallenge {
+            challenge_id,
+            user_id,
+            purpose,
+            issued_at: now,
+            expires_at: now + ttl_secs as i64,
+            attempts: 0,
+            used: false,
+            code_hash: hash_code(challenge_id, &code),
+        };
+
+        self.challenges.insert(challenge_id, challenge.clone());
+        self.save()?;
+        Ok(IssuedOtp {
+            challenge: challenge.public(),
+            email,
+            code,
+        })
+    }
+
+    /// Checks a code and spends its challenge. `user_id`, when given, is the
+    /// only user the challenge may belong to. A wrong code counts an attempt;
+    /// the last allowed attempt closes the challenge.
+    pub fn verify_otp(
+        &mut self,
+        challenge_id: u64,
+        code: &str,
+        user_id: Option<u64>,
+        max_attempts: u32,
+    ) -> Result<OtpChallenge> {
+        let now = chrono::Utc::now().timestamp();
+        let challenge = self
+            .challenges
+            .get_mut(&challenge_id)
+            .filter(|c| c.is_open(now) && user_id.is_none_or(|u| u == c.user_id))
+            .ok_or_else(|| {
+                EscrowAuthError::Unauthenticated(format!(
+                    "Code challenge {} is unknown, expired or already used",
+                    challenge_id
+                ))
+            })?;
+
+        if challenge.code_hash != hash_code(challenge_id, code.trim()) {
+            challenge.attempts += 1;
+            if challenge.attempts >= max_attempts {
+                challenge.used = true;
+            }
+            let remaining = max_attempts.saturating_sub(challenge.attempts);
+            self.save()?;
+            return Err(EscrowAuthError::Unauthenticated(format!(
+                "Wrong code ({} attempt(s) left)",
+                remaining
+            ))
+            .into());
+        }
+
+        challenge.used = true;
+        let challenge = challenge.public();
+        self.save()?;
+        Ok(challenge)
+    }
+
+    /// Records the user's first sign-in and, when created, their wallet.
+    fn verified(
+        &mut self,
+        user_id: u64,
+        wallet: Option<(String, u64, u32)>,
+    ) -> Result<CustodialUser> {
+        let user = self
+            .users
+            .get_mut(&user_id)
+            .ok_or_else(|| anyhow::anyhow!("Custodial user {} not found", user_id))?;
+        if let Some((account_id, wallet_id, generation)) = wallet {
+            user.account_id = Some(account_id);
+            user.wallet_id = Some(wallet_id);
+            user.generation = Some(generation);
+        }
+        user.verified_at
+            .get_or_insert(chrono::Utc::now().timestamp());
+
+        let user = user.clone();
+        self.save()?;
+        Ok(user)
+    }
+
+    /// Opens a session for a user with a wallet; returns it with the
+    /// plaintext token.
+    fn open_session(&mut self, user_id: u64, ttl_secs: u64) -> Result<(CustodialSession, String)> {
+        let account_id = self
+            .user(user_id)?
+            .account_id
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Custodial user {} has no wallet", user_id))?;
+
+        self.next_session_id += 1;
+        let session_id = self.next_session_id;
+        let secret: [u8; 32] = rand::random();
+        let token = format!("{}{}_{}", TOKEN_PREFIX, session_id, hex::encode(secret));
+
+        let now = chrono::Utc::now().timestamp();
+        let session = CustodialSession {
+            session_id,
+            user_id,
+            account_id,
+            created_at: now,
+            expires_at: now + ttl_secs as i64,
+            ended_at: None,
+            token_hash: hash_token(&token),
+        };
+
+        self.sessions.insert(session_id, session.clone());
+        self.save()?;
+        Ok((session.public(), token))
+    }
+
+    /// Resolves a presented token to its active session.
+    pub fn authenticate(&self, token: &str) -> Option<&CustodialSession> {
+        let session_id = token
+            .strip_prefix(TOKEN_PREFIX)?
+            .split('_')
+            .next()?
+            .parse::<u64>()
+            .ok()?;
+
+        let now = chrono::Utc::now().timestamp();
+        let presented = hash_token(token);
+        self.sessions
+            .get(&session_id)
+            .filter(|s| s.token_hash == presented && s.is_active(now))
+    }
+
+    pub fn end(&mut self, session_id: u64) -> Result<CustodialSession> {
+        let session = self
+            .sessions
+            .get_mut(&session_id)
+            .ok_or_else(|| anyhow::anyhow!("Session {} not found", session_id))?;
+        session.ended_at = Some(chrono::Utc::now().timestamp());
+
+        let session = session.public();
+        self.save()?;
+        Ok(session)
+    }
+
+    /// Erases the email of the user whose wallet is `account_hex` and ends
+    /// their sessions: the address can no longer sign in to that wallet.
+    pub fn erase_personal_fields(&mut self, account_hex: &str) -> Result<Vec<ErasedField>> {
+        let mut erased = Vec::new();
+        let now = chrono::Utc::now().timestamp();
+        for user in self
+            .users
+            .values_mut()
+            .filter(|u| u.account_id.as_deref() == Some(account_hex))
+        {
+            if let Some(original) = erase(&mut user.email) {
+                erased.push(ErasedField::new(
+                    "custodial_users",
+                    &user.user_id.to_string(),
+                    "email",
+                    original,
+                ));
+            }
+            for session in self
+                .sessions
+                .values_mut()
+                .filter(|s| s.user_id == user.user_id && s.is_active(now))
+            {
+                session.ended_at = Some(now);
+            }
+        }
+        if !erased.is_empty() {
+            self.save()?;
+        }
+        Ok(erased)
+    }
+}
+
+// =============================================================================
+// CODE DELIVERY
+// =============================================================================
+
+/// How one-time codes reach users (see module docs).
+#[derive(Debug, Clone)]
+pub enum OtpDelivery {
+    /// Written to the service log; test networks only
+    Log,
+    Http(Arc<HttpOtpMailer>),
+}
+
+impl OtpDelivery {
+    /// The delivery configured by OTP_DELIVERY, if any.
+    pub fn from_env(
+        delivery: Option<&str>,
+        url: Option<String>,
+        api_key: Option<String>,
+        mainnet: bool,
+    ) -> Result<Option<Self>> {
+        let Some(delivery) = delivery.map(|d| d.trim().to_lowercase()) else {
+            return Ok(None);
+        };
+        match delivery.as_str() {
+            "" | "none" => Ok(None),
+            "log" if mainnet => Err(anyhow::anyhow!("log is only allowed on test networks")),
+            "log" => Ok(Some(OtpDelivery::Log)),
+            "http" => {
+                let url = url.ok_or_else(|| anyhow::anyhow!("http needs OTP_DELIVERY_URL"))?;
+                Ok(Some(OtpDelivery::Http(Arc::new(HttpOtpMailer::new(
+                    url, api_key,
+                )?))))
+            }
+            other => Err(anyhow::anyhow!("unknown OTP delivery {:?}", other)),
+        }
+    }
+
+    pub async fn deliver(&self, otp: &IssuedOtp) -> Result<()> {
+        match self {
+            OtpDelivery::Log => {
+                tracing::warn!(
+                    "✉️  Code {} for {} (challenge {}, OTP_DELIVERY=log)",
+                    otp.code,
+                    otp.email,
+                    otp.challenge.challenge_id
+                );
+                Ok(())
+            }
+            OtpDelivery::Http(mailer) => mailer.send(otp).await,
+        }
+    }
+}
+
+/// Posts codes to a mailer:
+///
+///   POST {OTP_DELIVERY_URL}                   (Bearer OTP_DELIVERY_API_KEY)
+///     {"email", "code", "purpose", "expires_at"}
+///
+/// `purpose` is the challenge's purpose; payment codes carry the recipient
+/// and amount, for the message to name them.
+pub struct HttpOtpMailer {
+    url: String,
+    api_key: Option<String>,
+    http: reqwest::Client,
+}
+
+impl HttpOtpMailer {
+    pub fn new(url: String, api_key: Option<String>) -> Result<Self> {
+        Ok(Self {
+            url,
+            api_key,
+            http: reqwest::Client::builder()
+                .timeout(DELIVERY_TIMEOUT)
+                .build()?,
+        })
+    }
+
+    async fn send(&self, otp: &IssuedOtp) -> Result<()> {
+        let body = serde_json::json!({
+            "email": otp.email,
+            "code": otp.code,
+            "purpose": otp.challenge.purpose,
+            "expires_at": otp.challenge.expires_at,
+        });
+        let mut post = self.http.post(&self.url).json(&body);
+        if let Some(api_key) = &self.api_key {
+            post = post.bearer_auth(api_key);
+        }
+        post.send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for HttpOtpMailer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpOtpMailer")
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    /// Issues a sign-in code; the handler delivers it.
+    RequestCustodialOtp {
+        input: OtpRequestInput,
+    } -> IssuedOtp;
+    |client, op| client.request_custodial_otp(op.input);
+
+    CreateCustodialSession {
+        input: OtpVerifyInput,
+    } -> serde_json::Value;
+    |client, op| client.create_custodial_session(op.input).await;
+
+    GetCustodialSession {
+        token: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.get_custodial_session(op.token.as_deref());
+
+    EndCustodialSession {
+        token: Option<String>,
+    } -> CustodialSession;
+    |client, op| client.end_custodial_session(op.token.as_deref());
+
+    ConsumeCustodialNotes {
+        token: Option<String>,
+    } -> ConsumedNotes;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.consume_custodial_notes(op.token.as_deref()).await;
+
+    /// Issues the code confirming a payment; the handler delivers it.
+    RequestCustodialPayment {
+        input: CustodialPaymentInput,
+        token: Option<String>,
+    } -> IssuedOtp;
+    |client, op| client.request_custodial_payment(op.input, op.token.as_deref());
+
+    ConfirmCustodialPayment {
+        input: OtpVerifyInput,
+        token: Option<String>,
+    } -> serde_json::Value;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.confirm_custodial_payment(op.input, op.token.as_deref()).await
+}
+
+impl MidenClientWrapper {
+    /// The session behind a custodial session token.
+    pub(crate) fn custodial_session(&self, token: Option<&str>) -> Result<CustodialSession> {
+        let token = token.ok_or_else(|| {
+            EscrowAuthError::Unauthenticated("X-Custodial-Session header is required".into())
+        })?;
+        self.custodial
+            .authenticate(token)
+            .map(CustodialSession::public)
+            .ok_or_else(|| {
+                EscrowAuthError::Unauthenticated("Invalid or expired custodial session".into())
+                    .into()
+            })
+    }
+
+    pub fn request_custodial_otp(&mut self, input: OtpRequestInput) -> Result<IssuedOtp> {
+        if self.master_secret.is_none() {
+            return Err(anyhow::anyhow!(
+                "Custodial wallets need a master secret (MASTER_SECRET_KEY)"
+            ));
+        }
+        let user_id = self.custodial.enroll(&input.email);
+        let otp =
+            self.custodial
+                .issue_otp(user_id, OtpPurpose::SignIn, self.config.otp_ttl.as_secs())?;
+        tracing::info!(
+            "Sign-in code {} issued to custodial user {}",
+            otp.challenge.challenge_id,
+            user_id
+        );
+        Ok(otp)
+    }
+
+    /// Checks a sign-in code; creates the user's wallet on their first
+    /// sign-in, then opens a session.
+    pub async fn create_custodial_session(
+        &mut self,
+        input: OtpVerifyInput,
+    ) -> Result<serde_json::Value> {
+        let challenge = self.custodial.verify_otp(
+            input.challenge_id,
+            &input.code,
+            None,
+            self.config.otp_max_attempts,
+        )?;
+        if challenge.purpose != OtpPurpose::SignIn {
+            return Err(EscrowAuthError::Unauthenticated(format!(
+                "Code challenge {} is not a sign-in code",
+                challenge.challenge_id
+            ))
+            .into());
+        }
+
+        let wallet = if self.custodial.user(challenge.user_id)?.account_id.is_some() {
+            None
+        } else {
+            Some(self.create_custodial_wallet(challenge.user_id).await?)
+        };
+        let user = self.custodial.verified(challenge.user_id, wallet)?;
+        let (session, token) = self
+            .custodial
+            .open_session(user.user_id, self.config.custodial_session_ttl.as_secs())?;
+        tracing::info!(
+            "🔑 Custodial session {} opened for user {}",
+            session.session_id,
+            user.user_id
+        );
+
+        Ok(serde_json::json!({
+            "user": user,
+            "session": session,
+            "session_token": token,
+        }))
+    }
+
+    /// Derives, tracks and keys the wallet of a custodial user.
+    async fn create_custodial_wallet(&mut self, user_id: u64) -> Result<(String, u64, u32)> {
+        let secret = self.master_secret.as_mut().ok_or_else(|| {
+            anyhow::anyhow!("Custodial wallets need a master secret (MASTER_SECRET_KEY)")
+        })?;
+        let (wallet_id, generation) = secret.next_custodial_wallet()?;
+        let (init_seed, auth_key_seed) = secret.custodial_wallet_seeds(wallet_id, generation)?;
+        let mut key_rng = rand_chacha::ChaCha20Rng::from_seed(auth_key_seed);
+        let key_pair = SecretKey::with_rng(&mut key_rng);
+
+        let account = client::wallet_account(init_seed, &key_pair)?;
+        let account_id = account.id();
+        self.client.add_account(&account, false).await?;
+        self.keystore()?
+            .add_key(&AuthSecretKey::RpoFalcon512(key_pair))?;
+
+        let account_hex = account_id_to_hex(account_id);
+        tracing::info!(
+            "✅ Custodial wallet {} created for user {} (wallet {}, generation {})",
+            account_hex,
+            user_id,
+            wallet_id,
+            generation
+        );
+        Ok((account_hex, wallet_id, generation))
+    }
+
+    pub fn get_custodial_session(&self, token: Option<&str>) -> Result<serde_json::Value> {
+        let session = self.custodial_session(token)?;
+        let user = self.custodial.user(session.user_id)?;
+        Ok(serde_json::json!({
+            "user": user,
+            "session": session,
+        }))
+    }
+
+    pub fn end_custodial_session(&mut self, token: Option<&str>) -> Result<CustodialSession> {
+        let session = self.custodial_session(token)?;
+        let session = self.custodial.end(session.session_id)?;
+        tracing::info!("Custodial session {} ended", session.session_id);
+        Ok(session)
+    }
+
+    /// Claims every consumable note sent to the session's wallet.
+    pub async fn consume_custodial_notes(&mut self, token: Option<&str>) -> Result<ConsumedNotes> {
+        let session = self.custodial_session(token)?;
+        self.consume_notes("all", Some(session.account_id)).await
+    }
+
+    /// Issues the code confirming a payment from the session's wallet.
+    pub fn request_custodial_payment(
+        &mut self,
+        input: CustodialPaymentInput,
+        token: Option<&str>,
+    ) -> Result<IssuedOtp> {
+        let session = self.custodial_session(token)?;
+        let to_account_id = self.account_hex(input.to_account_id.trim())?;
+        if to_account_id == session.account_id {
+            return Err(anyhow::anyhow!("A wallet cannot pay itself"));
+        }
+
+        let otp = self.custodial.issue_otp(
+            session.user_id,
+            OtpPurpose::Payment {
+                to_account_id,
+                amount: input.amount,
+            },
+            self.config.otp_ttl.as_secs(),
+        )?;
+        tracing::info!(
+            "Payment code {} issued to custodial user {}",
+            otp.challenge.challenge_id,
+            session.user_id
+        );
+        Ok(otp)
+    }
+
+    /// Sends the payment a confirmation code was issued for.
+    pub async fn confirm_custodial_payment(
+        &mut self,
+        input: OtpVerifyInput,
+        token: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let session = self.custodial_session(token)?;
+        let challenge = self.custodial.verify_otp(
+            input.challenge_id,
+            &input.code,
+            Some(session.user_id),
+            self.config.otp_max_attempts,
+        )?;
+        let OtpPurpose::Payment {
+            to_account_id,
+            amount,
+        } = challenge.purpose
+        else {
+            return Err(EscrowAuthError::Unauthenticated(format!(
+                "Code challenge {} is not a payment code",
+                challenge.challenge_id
+            ))
+            .into());
+        };
+
+        let from = parse_hex_account_id(&session.account_id)?;
+        let to = parse_hex_account_id(&to_account_id)?;
+        let tx_id = self.submit_token_payment(from, to, amount).await?;
+        tracing::info!(
+            "💸 Custodial user {} paid {} to {} (challenge {})",
+            session.user_id,
+            amount,
+            to_account_id,
+            challenge.challenge_id
+        );
+
+        Ok(serde_json::json!({
+            "account_id": session.account_id,
+            "to_account_id": to_account_id,
+            "amount": amount,
+            "tx_id": tx_id,
+        }))
+    }
+}
@@ -0,0 +1,413 @@
+// src/data_subjects.rs
+//
+// Data subject export and erasure
+//
+// GET /admin/data-subjects/:account_id gathers everything the service keeps
+// about an account: every record, in every store, that mentions its hex ID
+// (properties, escrows, notes, journaled operations, leases, bids, API keys,
+// ...), plus notarizations requested with its API keys and earlier erasures.
+// Credential hashes (API keys, session and disclosure tokens, one-time codes)
+// are left out.
+//
+// POST /admin/data-subjects/:account_id/erase anonymizes the personal fields
+// kept off-chain:
+// - professionals registered for the account: name and licence number
+// - properties it owns: the IPFS CID of the property documents
+// - API keys bound to it alone, allowances it granted and notarizations
+//   requested with its keys: their free-form labels
+// - the email-onboarded user whose wallet it is (custodial.rs): their email
+//   address; their sessions end
+//
+// Account, note and transaction IDs stay: they reference chain state the
+// service cannot change, and the journal and ledgers need them to stay
+// consistent. Document hashes stay too: they reveal nothing of the document,
+// notarization hashes are committed on-chain and report hashes are signed.
+//
+// Each erased field is replaced by "[erased]" and leaves a tombstone naming
+// the store, record and field, with a salted SHA-256 of the original value, so
+// an auditor holding the original can still check it. Erasing is idempotent.
+// Both endpoints need an admin API key.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{read_cache::Touched, MidenClientWrapper};
+
+/// Replaces erased values
+pub const ERASED: &str = "[erased]";
+
+/// Credential hashes never exported
+const SECRET_FIELDS: &[&str] = &["key_hash", "token_hash", "code_hash"];
+
+/// A personal field taken out of a record, before it is tombstoned.
+#[derive(Debug, Clone)]
+pub struct ErasedField {
+    pub store: &'static str,
+    pub record_id: String,
+    pub field: &'static str,
+    pub original: String,
+}
+
+impl ErasedField {
+    pub fn new(
+        store: &'static str,
+        record_id: &str,
+        field: &'static str,
+        original: String,
+    ) -> Self {
+        Self {
+            store,
+            record_id: record_id.to_string(),
+            field,
+            original,
+        }
+    }
+}
+
+/// Replaces `value` with the erasure marker. Returns the original, or None if
+/// there was nothing left to erase.
+pub fn erase(value: &mut String) -> Option<String> {
+    if value.is_empty() || value == ERASED {
+        return None;
+    }
+    Some(std::mem::replace(value, ERASED.to_string()))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ErasureInput {
+    /// Why the data is erased, e.g. the request reference
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tombstone {
+    pub tombstone_id: u64,
+    pub erasure_id: u64,
+    pub account_id: String,
+    pub store: String,
+    pub record_id: String,
+    pub field: String,
+    /// Random salt (hex) of `original_digest`
+    pub salt: String,
+    /// SHA-256(salt || original value), hex
+    pub original_digest: String,
+    pub erased_at: i64,
+}
+
+impl Tombstone {
+    /// Whether `value` is the value this tombstone replaced.
+    pub fn matches(&self, value: &str) -> bool {
+        hex::decode(&self.salt)
+            .map(|salt| salted_digest(&salt, value) == self.original_digest)
+            .unwrap_or(false)
+    }
+}
+
+fn salted_digest(salt: &[u8], value: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(value.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Erasure {
+    pub erasure_id: u64,
+    pub account_id: String,
+    pub reason: String,
+    /// Key ID of the admin that requested it
+    pub requested_by: u64,
+    pub erased_at: i64,
+    /// Fields erased by this request (zero when nothing was left)
+    pub fields_erased: usize,
+}
+
+/// Everything kept about one account (see module docs).
+#[derive(Debug, Clone, Serialize)]
+pub struct DataSubjectExport {
+    pub account_id: String,
+    pub exported_at: i64,
+    /// Store -> collection -> records mentioning the account
+    pub records: BTreeMap<String, serde_json::Map<String, serde_json::Value>>,
+    pub erasures: Vec<Erasure>,
+    pub tombstones: Vec<Tombstone>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DataSubjectLog {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    erasures: BTreeMap<u64, Erasure>,
+    #[serde(default)]
+    tombstones: BTreeMap<u64, Tombstone>,
+    #[serde(default)]
+    next_erasure_id: u64,
+    #[serde(default)]
+    next_tombstone_id: u64,
+}
+
+impl DataSubjectLog {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut log = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<DataSubjectLog>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            DataSubjectLog::default()
+        };
+        log.path = path;
+
+        Ok(log)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Records an erasure and a tombstone per erased field.
+    pub fn record(
+        &mut self,
+        account_hex: &str,
+        reason: String,
+        requested_by: u64,
+        fields: Vec<ErasedField>,
+    ) -> Result<Erasure> {
+        let now = chrono::Utc::now().timestamp();
+        self.next_erasure_id += 1;
+        let erasure = Erasure {
+            erasure_id: self.next_erasure_id,
+            account_id: account_hex.to_string(),
+            reason,
+            requested_by,
+            erased_at: now,
+            fields_erased: fields.len(),
+        };
+
+        for field in fields {
+            self.next_tombstone_id += 1;
+            let salt: [u8; 16] = rand::random();
+            self.tombstones.insert(
+                self.next_tombstone_id,
+                Tombstone {
+                    tombstone_id: self.next_tombstone_id,
+                    erasure_id: erasure.erasure_id,
+                    account_id: account_hex.to_string(),
+                    store: field.store.to_string(),
+                    record_id: field.record_id,
+                    field: field.field.to_string(),
+                    salt: hex::encode(salt),
+                    original_digest: salted_digest(&salt, &field.original),
+                    erased_at: now,
+                },
+            );
+        }
+
+        self.erasures.insert(erasure.erasure_id, erasure.clone());
+        self.save()?;
+        Ok(erasure)
+    }
+
+    pub fn erasures_for(&self, account_hex: &str) -> Vec<Erasure> {
+        self.erasures
+            .values()
+            .filter(|e| e.account_id == account_hex)
+            .cloned()
+            .collect()
+    }
+
+    pub fn tombstones_for(&self, account_hex: &str) -> Vec<Tombstone> {
+        self.tombstones
+            .values()
+            .filter(|t| t.account_id == account_hex)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Records of a store that mention `account_hex`, by collection.
+///
+/// Collections are the store's map and list fields; counters and other
+/// scalars are skipped.
+pub fn linked_records(
+    store: &impl Serialize,
+    account_hex: &str,
+) -> Result<serde_json::Map<String, serde_json::Value>> {
+    let serde_json::Value::Object(fields) = serde_json::to_value(store)? else {
+        return Ok(serde_json::Map::new());
+    };
+
+    let mut linked = serde_json::Map::new();
+    for (collection, value) in fields {
+        let records: Vec<serde_json::Value> = match value {
+            serde_json::Value::Object(map) => map.into_iter().map(|(_, r)| r).collect(),
+            serde_json::Value::Array(list) => list,
+            _ => continue,
+        };
+        let mut matching: Vec<serde_json::Value> = records
+            .into_iter()
+            .filter(|record| mentions(record, account_hex))
+            .collect();
+        if matching.is_empty() {
+            continue;
+        }
+        for record in &mut matching {
+            redact_secrets(record);
+        }
+        linked.insert(collection, serde_json::Value::Array(matching));
+    }
+    Ok(linked)
+}
+
+fn mentions(value: &serde_json::Value, account_hex: &str) -> bool {
+    match value {
+        serde_json::Value::String(s) => s.eq_ignore_ascii_case(account_hex),
+        serde_json::Value::Array(list) => list.iter().any(|v| mentions(v, account_hex)),
+        serde_json::Value::Object(map) => map.values().any(|v| mentions(v, account_hex)),
+        _ => false,
+    }
+}
+
+fn redact_secrets(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Array(list) => list.iter_mut().for_each(redact_secrets),
+        serde_json::Value::Object(map) => {
+            for field in SECRET_FIELDS {
+                map.remove(*field);
+            }
+            map.values_mut().for_each(redact_secrets);
+        }
+        _ => {}
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    ExportDataSubject {
+        account_id: String,
+        api_key: Option<String>,
+    } -> DataSubjectExport;
+    |client, op| client.export_data_subject(&op.account_id, op.api_key.as_deref());
+
+    EraseDataSubject {
+        account_id: String,
+        input: ErasureInput,
+        api_key: Option<String>,
+    } -> Erasure;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.erase_data_subject(&op.account_id, op.input, op.api_key.as_deref())
+}
+
+impl MidenClientWrapper {
+    /// Exports everything linked to an account (see module docs).
+    pub fn export_data_subject(
+        &self,
+        account: &str,
+        api_key: Option<&str>,
+    ) -> Result<DataSubjectExport> {
+        self.admin_principal(api_key, "data subject requests")?;
+        let account_hex = self.account_hex(account)?;
+
+        let hex = account_hex.as_str();
+        let stores: Vec<(&str, serde_json::Map<String, serde_json::Value>)> = vec![
+            ("records", linked_records(&self.records, hex)?),
+            ("professionals", linked_records(&self.professionals, hex)?),
+            ("allowances", linked_records(&self.allowances, hex)?),
+            ("api_keys", linked_records(&self.principals, hex)?),
+            (
+                "wallet_sessions",
+                linked_records(&self.wallet_sessions, hex)?,
+            ),
+            ("custodial_users", linked_records(&self.custodial, hex)?),
+            ("leases", linked_records(&self.leases, hex)?),
+            ("liens", linked_records(&self.liens, hex)?),
+            ("installments", linked_records(&self.installments, hex)?),
+            ("auctions", linked_records(&self.auctions, hex)?),
+            ("offers", linked_records(&self.offers, hex)?),
+            ("order_book", linked_records(&self.order_book, hex)?),
+            ("tax", linked_records(&self.tax, hex)?),
+            (
+                "confidential_listings",
+                linked_records(&self.confidential_listings, hex)?,
+            ),
+            ("approvals", linked_records(&self.approvals, hex)?),
+            ("escrow_monitor", linked_records(&self.escrow_monitor, hex)?),
+            ("treasury", linked_records(&self.treasury, hex)?),
+            ("mint_jobs", linked_records(&self.mint_jobs, hex)?),
+            ("retry_queue", linked_records(&self.retries, hex)?),
+        ];
+        let mut records: BTreeMap<String, serde_json::Map<String, serde_json::Value>> = stores
+            .into_iter()
+            .filter(|(_, linked)| !linked.is_empty())
+            .map(|(store, linked)| (store.to_string(), linked))
+            .collect();
+
+        // Notarizations name the API key that requested them, not the account
+        let key_ids = self.principals.key_ids_for(&account_hex);
+        let notarizations: Vec<serde_json::Value> = self
+            .notary
+            .list(None)
+            .into_iter()
+            .filter(|n| n.requested_by.is_some_and(|k| key_ids.contains(&k)))
+            .map(|n| serde_json::json!(n))
+            .collect();
+        if !notarizations.is_empty() {
+            records.entry("notary".to_string()).or_default().insert(
+                "notarizations".to_string(),
+                serde_json::Value::Array(notarizations),
+            );
+        }
+
+        tracing::info!("Exported data linked to {}", account_hex);
+        Ok(DataSubjectExport {
+            erasures: self.data_subjects.erasures_for(&account_hex),
+            tombstones: self.data_subjects.tombstones_for(&account_hex),
+            account_id: account_hex,
+            exported_at: chrono::Utc::now().timestamp(),
+            records,
+        })
+    }
+
+    /// Anonymizes the personal fields linked to an account (see module docs).
+    pub fn erase_data_subject(
+        &mut self,
+        account: &str,
+        input: ErasureInput,
+        api_key: Option<&str>,
+    ) -> Result<Erasure> {
+        let requested_by = self
+            .admin_principal(api_key, "data subject requests")?
+            .key_id;
+        let account_hex = self.account_hex(account)?;
+        let key_ids = self.principals.key_ids_for(&account_hex);
+
+        let mut fields = self.records.erase_personal_fields(&account_hex);
+        fields.extend(self.professionals.erase_personal_fields(&account_hex)?);
+        fields.extend(self.allowances.erase_personal_fields(&account_hex)?);
+        fields.extend(self.notary.erase_personal_fields(&key_ids)?);
+        fields.extend(self.principals.erase_personal_fields(&account_hex)?);
+        fields.extend(self.custodial.erase_personal_fields(&account_hex)?);
+
+        let erasure =
+            self.data_subjects
+                .record(&account_hex, input.reason, requested_by, fields)?;
+        tracing::info!(
+            "🧽 Erased {} personal field(s) of {} (erasure {}, by key {})",
+            erasure.fields_erased,
+            account_hex,
+            erasure.erasure_id,
+            requested_by
+        );
+        Ok(erasure)
+    }
+}
@@ -0,0 +1,294 @@
+// src/deadlines.rs
+//
+// Request deadlines, cancellation and load shedding
+//
+// Every API request runs against a deadline: COMMAND_TIMEOUT_SECS by default,
+// overridden per route with COMMAND_TIMEOUTS ("/mint-property=300,
+// /admin/bench=0"; zero means no deadline). When a request misses it:
+// - writes (POST/PUT/PATCH/DELETE) keep running in the background and answer
+//   504 with a job handle; GET /command-jobs/:job_id serves the response once
+//   the operation finishes
+// - reads are cancelled and answer 504
+//
+// Cancellation reaches the client task through the request's token: commands
+// are sent with the token of the request that sent them (see CommandSender in
+// main.rs) and a command whose token was cancelled while it waited in the
+// queue is dropped instead of run. A read is also cancelled when its caller
+// disconnects.
+//
+// Requests are shed (503, Retry-After) while LOAD_SHED_QUEUE_DEPTH or more
+// commands are already waiting for the client task.
+//
+// Jobs — mint batches, retry attempts and writes finishing in the background —
+// register their token in JobCancellations so DELETE /jobs/:job_id can stop
+// them: queued commands of the job are dropped, and a command already running
+// is interrupted at its next interruptible stage (propagation waits, the next
+// item of a mint chunk). The job then ends with a `cancelled` status.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::MidenClientWrapper;
+
+/// Finished background jobs kept for GET /command-jobs/:job_id
+const MAX_FINISHED_JOBS: usize = 1_000;
+
+tokio::task_local! {
+    static CANCELLATION: CancellationToken;
+}
+
+/// Cancellation token of the request being handled, if any.
+pub fn current_cancellation() -> Option<CancellationToken> {
+    CANCELLATION.try_with(|token| token.clone()).ok()
+}
+
+/// Runs `future` with `token` as the current request's cancellation token.
+pub async fn with_cancellation<F: Future>(token: CancellationToken, future: F) -> F::Output {
+    CANCELLATION.scope(token, future).await
+}
+
+/// Deadline per route (see module docs).
+#[derive(Debug, Clone, Default)]
+pub struct DeadlinePolicy {
+    pub default: Option<Duration>,
+    pub overrides: HashMap<String, Option<Duration>>,
+}
+
+impl DeadlinePolicy {
+    /// Builds the policy from a default in seconds and a "route=secs,..." list.
+    pub fn parse(default_secs: u64, overrides: &str) -> Result<Self> {
+        let deadline = |secs: u64| (secs > 0).then(|| Duration::from_secs(secs));
+
+        let mut policy = Self {
+            default: deadline(default_secs),
+            overrides: HashMap::new(),
+        };
+        for entry in overrides
+            .split(',')
+            .map(str::trim)
+            .filter(|e| !e.is_empty())
+        {
+            let (route, secs) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Expected route=secs, got {}", entry))?;
+            let route = route.trim();
+            if !route.starts_with('/') {
+                return Err(anyhow::anyhow!("Route must start with '/': {}", route));
+            }
+            let secs = secs
+                .trim()
+                .parse::<u64>()
+                .map_err(|e| anyhow::anyhow!("Invalid timeout for {}: {}", route, e))?;
+            policy.overrides.insert(route.to_string(), deadline(secs));
+        }
+        Ok(policy)
+    }
+
+    /// Deadline for a matched route (without the /api/vN prefix).
+    pub fn for_route(&self, route: &str) -> Option<Duration> {
+        self.overrides.get(route).copied().unwrap_or(self.default)
+    }
+}
+
+/// Cancellation tokens of running jobs, by job ID.
+///
+/// Shared by handlers and the client task, so a job can be cancelled while the
+/// client task is busy running it.
+#[derive(Debug, Clone, Default)]
+pub struct JobCancellations {
+    tokens: Arc<Mutex<HashMap<String, CancellationToken>>>,
+}
+
+impl JobCancellations {
+    /// Token of a job, registered on first use.
+    pub fn register(&self, job_id: &str) -> CancellationToken {
+        let mut tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        tokens.entry(job_id.to_string()).or_default().clone()
+    }
+
+    pub fn insert(&self, job_id: &str, token: CancellationToken) {
+        let mut tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        tokens.insert(job_id.to_string(), token);
+    }
+
+    /// Cancels a registered job. Returns false if none is registered.
+    pub fn cancel(&self, job_id: &str) -> bool {
+        let tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        match tokens.get(job_id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drops the token of a job that ended.
+    pub fn forget(&self, job_id: &str) {
+        let mut tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        tokens.remove(job_id);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackgroundStatus {
+    Running,
+    Finished,
+    /// Cancelled while running; the response records how far it got
+    Cancelled,
+}
+
+/// A write that missed its deadline and finishes in the background.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackgroundJob {
+    pub job_id: String,
+    pub method: String,
+    pub route: String,
+    pub started_at: i64,
+    pub timed_out_at: i64,
+    pub status: BackgroundStatus,
+    pub finished_at: Option<i64>,
+    /// HTTP status of the eventual response
+    pub status_code: Option<u16>,
+    /// Eventual response body (JSON, or the raw text)
+    pub response: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Default)]
+struct JobTable {
+    jobs: BTreeMap<u64, BackgroundJob>,
+    next_job_id: u64,
+}
+
+/// In-memory table of background jobs, shared by handlers.
+#[derive(Debug, Clone, Default)]
+pub struct BackgroundJobs {
+    inner: Arc<Mutex<JobTable>>,
+}
+
+impl BackgroundJobs {
+    /// Registers a timed-out write. Returns its job ID.
+    pub fn start(&self, method: &str, route: &str, started_at: i64) -> String {
+        let mut table = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        table.next_job_id += 1;
+        let id = table.next_job_id;
+        let job_id = format!("cmd-{}", id);
+        table.jobs.insert(
+            id,
+            BackgroundJob {
+                job_id: job_id.clone(),
+                method: method.to_string(),
+                route: route.to_string(),
+                started_at,
+                timed_out_at: chrono::Utc::now().timestamp(),
+                status: BackgroundStatus::Running,
+                finished_at: None,
+                status_code: None,
+                response: None,
+            },
+        );
+        job_id
+    }
+
+    /// Records the response of a background job and prunes old finished jobs.
+    pub fn finish(&self, job_id: &str, status_code: u16, response: serde_json::Value) {
+        let mut table = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(job) = table.jobs.values_mut().find(|j| j.job_id == job_id) {
+            if job.status == BackgroundStatus::Running {
+                job.status = BackgroundStatus::Finished;
+            }
+            job.finished_at = Some(chrono::Utc::now().timestamp());
+            job.status_code = Some(status_code);
+            job.response = Some(response);
+        }
+
+        let finished: Vec<u64> = table
+            .jobs
+            .iter()
+            .filter(|(_, j)| j.finished_at.is_some())
+            .map(|(id, _)| *id)
+            .collect();
+        for id in finished
+            .iter()
+            .take(finished.len().saturating_sub(MAX_FINISHED_JOBS))
+        {
+            table.jobs.remove(id);
+        }
+    }
+
+    /// Marks a running job cancelled.
+    pub fn cancel(&self, job_id: &str) -> Result<BackgroundJob> {
+        let mut table = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let job = table
+            .jobs
+            .values_mut()
+            .find(|j| j.job_id == job_id)
+            .ok_or_else(|| anyhow::anyhow!("Command job {} not found", job_id))?;
+        if job.status != BackgroundStatus::Running {
+            return Err(anyhow::anyhow!(
+                "Conflict: command job {} is {:?}",
+                job_id,
+                job.status
+            ));
+        }
+        job.status = BackgroundStatus::Cancelled;
+        Ok(job.clone())
+    }
+
+    pub fn get(&self, job_id: &str) -> Option<BackgroundJob> {
+        let table = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let id = job_id.strip_prefix("cmd-")?.parse::<u64>().ok()?;
+        table.jobs.get(&id).cloned()
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    CancelJob {
+        job_id: String,
+    } -> serde_json::Value;
+    |client, op| client.cancel_job(&op.job_id)
+}
+
+impl MidenClientWrapper {
+    /// Shares the job cancellation registry with handlers.
+    pub fn attach_job_cancellations(&mut self, cancellations: JobCancellations) {
+        self.job_cancellations = cancellations;
+    }
+
+    /// Sets the cancellation token of the command about to run.
+    pub fn set_cancellation(&mut self, token: Option<CancellationToken>) {
+        self.cancellation = token;
+    }
+
+    /// True once the command being run has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+    }
+
+    /// Records the cancellation of a mint batch or retry job.
+    pub fn cancel_job(&mut self, job_id: &str) -> Result<serde_json::Value> {
+        if job_id.starts_with("mint-batch-") {
+            self.cancel_mint_batch(job_id)
+        } else if job_id.starts_with("retry-") {
+            self.cancel_retry_job(job_id)
+        } else if job_id.starts_with("offline-") {
+            self.cancel_offline_job(job_id)
+        } else {
+            Err(anyhow::anyhow!("Job {} not found", job_id))
+        }
+    }
+}
@@ -0,0 +1,422 @@
+// src/demo.rs
+//
+// Demo scenario: a whole property sale in one request (localnet only)
+//
+// POST /demo/run-scenario runs the purchase lifecycle end to end, so a sales
+// demo needs neither a dozen curl commands nor manual waits between them:
+//   parties             bob sells, alice buys (the service wallets: the
+//                       service creates no other accounts)
+//   mint_property       a new property, DEMO-<n> unless named, minted to bob
+//   consume_note        its note consumed into bob's account
+//   list                listing details set, so it shows in GET /search
+//   prove_accreditation accreditation proof of the buyer generated and verified
+//   prove_ownership     ownership proof of the property generated and verified
+//   create_escrow       escrow from alice to bob for `amount`
+//   fund_escrow         funded from alice
+//   transfer_property   title to alice
+//   release_escrow      `amount` released to bob
+// Body (every field optional): {"property_id", "price", "amount",
+// "net_worth"}. The request answers 202 with the run while it goes on.
+//
+// Progress: GET /demo/scenarios/:run_id/ws streams the run's events over a
+// WebSocket, one JSON text message each — step_started, step_completed (with
+// what the step returned), step_failed, finished — and closes after finished.
+// A socket opened late first gets the events so far. GET
+// /demo/scenarios/:run_id returns the run with all its events.
+//
+// Steps are ordinary client commands sent one after another, like benchmark
+// operations (bench.rs), and act with the service's own authority: no API key
+// is asked for, which is why the endpoints are only routed under the localnet
+// profile. A failed step ends the run; nothing is undone. The proof steps are
+// skipped while the demo_proofs feature is off, and the list step while the
+// marketplace is (feature_flags.rs). Runs are kept in memory, the last
+// MAX_DEMO_RUNS of them.
+
+use anyhow::Result;
+use miden_client::account::AccountId;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::broadcast;
+
+use crate::{
+    currency::PriceInput, feature_flags::FeatureFlag, read_cache::Touched,
+    search::ListingDetailsInput, MidenClientWrapper,
+};
+
+/// Runs kept for GET /demo/scenarios/:run_id
+pub const MAX_DEMO_RUNS: usize = 20;
+/// Buffered events per socket before it starts missing some
+pub const DEMO_FEED_CAPACITY: usize = 256;
+
+const DEMO_IPFS_CID: &str = "demo";
+const DEFAULT_DEMO_PRICE: PriceInput = PriceInput::Minor(45_000_000);
+const DEFAULT_DEMO_AMOUNT: u64 = 10_000;
+const DEFAULT_DEMO_NET_WORTH: u64 = 5_000_000;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DemoScenarioInput {
+    pub property_id: Option<String>,
+    /// Listing price; a bare number is in PRICE_CURRENCY minor units
+    pub price: Option<PriceInput>,
+    /// PROP escrowed and released to the seller
+    pub amount: Option<u64>,
+    /// Net worth the buyer proves accreditation with
+    pub net_worth: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DemoStep {
+    Parties,
+    MintProperty,
+    ConsumeNote,
+    List,
+    ProveAccreditation,
+    ProveOwnership,
+    CreateEscrow,
+    FundEscrow,
+    TransferProperty,
+    ReleaseEscrow,
+}
+
+impl DemoStep {
+    /// Steps in run order
+    pub const ALL: [DemoStep; 10] = [
+        DemoStep::Parties,
+        DemoStep::MintProperty,
+        DemoStep::ConsumeNote,
+        DemoStep::List,
+        DemoStep::ProveAccreditation,
+        DemoStep::ProveOwnership,
+        DemoStep::CreateEscrow,
+        DemoStep::FundEscrow,
+        DemoStep::TransferProperty,
+        DemoStep::ReleaseEscrow,
+    ];
+}
+
+/// What the steps of a run work on, filled in as they go.
+#[derive(Debug, Clone, Serialize)]
+pub struct DemoContext {
+    pub property_id: String,
+    pub price: PriceInput,
+    pub amount: u64,
+    #[serde(skip)]
+    pub net_worth: u64,
+    pub seller_account_id: Option<String>,
+    pub buyer_account_id: Option<String>,
+    pub note_id: Option<String>,
+    pub escrow_account_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DemoEventKind {
+    StepStarted,
+    StepCompleted,
+    StepFailed,
+    Finished,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DemoEvent {
+    pub run_id: String,
+    /// Position in the run, from 0
+    pub seq: usize,
+    pub kind: DemoEventKind,
+    pub step: Option<DemoStep>,
+    /// What the step returned, why it failed, or the run's outcome
+    pub detail: serde_json::Value,
+    pub at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DemoStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DemoRun {
+    pub run_id: String,
+    pub status: DemoStatus,
+    pub steps: Vec<DemoStep>,
+    pub context: DemoContext,
+    pub events: Vec<DemoEvent>,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+}
+
+/// Demo runs and their event feed, shared by the handlers and the tasks
+/// driving the runs (see main.rs).
+#[derive(Debug, Clone)]
+pub struct DemoRuns {
+    runs: Arc<Mutex<VecDeque<DemoRun>>>,
+    feed: broadcast::Sender<DemoEvent>,
+}
+
+impl Default for DemoRuns {
+    fn default() -> Self {
+        Self {
+            runs: Arc::default(),
+            feed: broadcast::channel(DEMO_FEED_CAPACITY).0,
+        }
+    }
+}
+
+impl DemoRuns {
+    fn runs(&self) -> std::sync::MutexGuard<'_, VecDeque<DemoRun>> {
+        self.runs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Records a new run, dropping the oldest beyond MAX_DEMO_RUNS.
+    pub fn start(&self, input: DemoScenarioInput) -> DemoRun {
+        let now = chrono::Utc::now();
+        let mut runs = self.runs();
+        // Runs started within the same millisecond get the next free one
+        let mut millis = now.timestamp_millis();
+        while runs.iter().any(|r| r.run_id == format!("demo-{}", millis)) {
+            millis += 1;
+        }
+        let run_id = format!("demo-{}", millis);
+        let run = DemoRun {
+            run_id: run_id.clone(),
+            status: DemoStatus::Running,
+            steps: DemoStep::ALL.to_vec(),
+            context: DemoContext {
+                property_id: input
+                    .property_id
+                    .unwrap_or_else(|| format!("DEMO-{}", millis)),
+                price: input.price.unwrap_or(DEFAULT_DEMO_PRICE),
+                amount: input.amount.unwrap_or(DEFAULT_DEMO_AMOUNT),
+                net_worth: input.net_worth.unwrap_or(DEFAULT_DEMO_NET_WORTH),
+                seller_account_id: None,
+                buyer_account_id: None,
+                note_id: None,
+                escrow_account_id: None,
+            },
+            events: Vec::new(),
+            started_at: now.timestamp(),
+            finished_at: None,
+        };
+
+        runs.push_back(run.clone());
+        while runs.len() > MAX_DEMO_RUNS {
+            runs.pop_front();
+        }
+        run
+    }
+
+    pub fn get(&self, run_id: &str) -> Option<DemoRun> {
+        self.runs().iter().find(|r| r.run_id == run_id).cloned()
+    }
+
+    /// Appends an event to a run and publishes it; a finished event also ends
+    /// the run.
+    pub fn record(
+        &self,
+        run_id: &str,
+        kind: DemoEventKind,
+        step: Option<DemoStep>,
+        detail: serde_json::Value,
+        context: Option<&DemoContext>,
+    ) {
+        let mut runs = self.runs();
+        let Some(run) = runs.iter_mut().find(|r| r.run_id == run_id) else {
+            return;
+        };
+        let now = chrono::Utc::now().timestamp();
+        let event = DemoEvent {
+            run_id: run_id.to_string(),
+            seq: run.events.len(),
+            kind,
+            step,
+            detail,
+            at: now,
+        };
+        if let Some(context) = context {
+            run.context = context.clone();
+        }
+        if kind == DemoEventKind::StepFailed {
+            run.status = DemoStatus::Failed;
+        }
+        if kind == DemoEventKind::Finished {
+            if run.status == DemoStatus::Running {
+                run.status = DemoStatus::Completed;
+            }
+            run.finished_at = Some(now);
+        }
+        run.events.push(event.clone());
+        // Sent under the lock, so `follow` sees every event exactly once; no
+        // open socket is not an error
+        let _ = self.feed.send(event);
+    }
+
+    /// A run's events so far and a receiver of the ones that follow.
+    pub fn follow(&self, run_id: &str) -> Option<(Vec<DemoEvent>, broadcast::Receiver<DemoEvent>)> {
+        let runs = self.runs();
+        let run = runs.iter().find(|r| r.run_id == run_id)?;
+        Some((run.events.clone(), self.feed.subscribe()))
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    RunDemoStep {
+        step: DemoStep,
+        context: DemoContext,
+    } -> (serde_json::Value, DemoContext);
+    touched: |_op| Some(Touched::Accounts(
+        ["alice", "bob", "faucet"].map(String::from).to_vec(),
+    ));
+    |client, op| {
+        let mut context = op.context;
+        let detail = client.run_demo_step(op.step, &mut context).await?;
+        Ok((detail, context))
+    }
+}
+
+impl MidenClientWrapper {
+    /// Runs one step of a demo scenario; returns what it did.
+    pub async fn run_demo_step(
+        &mut self,
+        step: DemoStep,
+        context: &mut DemoContext,
+    ) -> Result<serde_json::Value> {
+        let seller = context.seller_account_id.clone().unwrap_or_default();
+        let buyer = context.buyer_account_id.clone().unwrap_or_default();
+        let property_id = context.property_id.clone();
+        let escrow = |wrapper: &Self, context: &DemoContext| {
+            let escrow_hex = context
+                .escrow_account_id
+                .as_deref()
+                .ok_or_else(|| anyhow::anyhow!("No escrow opened yet"))?;
+            wrapper.recorded_escrow(escrow_hex)
+        };
+
+        Ok(match step {
+            DemoStep::Parties => {
+                context.seller_account_id = Some(self.account_hex("bob")?);
+                context.buyer_account_id = Some(self.account_hex("alice")?);
+                serde_json::json!({
+                    "seller_account_id": context.seller_account_id,
+                    "buyer_account_id": context.buyer_account_id,
+                })
+            }
+            DemoStep::MintProperty => {
+                let (tx_id, note_id) = self
+                    .mint_property_nft(&property_id, &seller, DEMO_IPFS_CID, 0, &context.price)
+                    .await?;
+                context.note_id = Some(note_id.clone());
+                serde_json::json!({ "tx_id": tx_id, "note_id": note_id })
+            }
+            DemoStep::ConsumeNote => {
+                let note_id = context.note_id.clone().unwrap_or_default();
+                let tx_id = self.consume_note(&note_id, Some(seller)).await?;
+                serde_json::json!({ "tx_id": tx_id })
+            }
+            DemoStep::List => {
+                if !self.features.is_enabled(FeatureFlag::Marketplace) {
+                    return Ok(serde_json::json!({ "skipped": "marketplace is turned off" }));
+                }
+                let details = self.listing_details.set(
+                    &property_id,
+                    ListingDetailsInput {
+                        address: Some("1 Demo Street".to_string()),
+                        description: Some("Demo listing".to_string()),
+                        size_sqm: Some(120.0),
+                    },
+                )?;
+                self.index_property(&property_id);
+                serde_json::json!(details)
+            }
+            DemoStep::ProveAccreditation => {
+                if !self.features.is_enabled(FeatureFlag::DemoProofs) {
+                    return Ok(serde_json::json!({ "skipped": "demo_proofs is turned off" }));
+                }
+                let generated = self
+                    .generate_accreditation_proof(context.net_worth, None, None)
+                    .await?;
+                let proof = &generated["proof"];
+                let verified = self
+                    .verify_accreditation_proof(
+                        proof["proof"].as_str().unwrap_or_default(),
+                        proof["program_hash"].as_str().unwrap_or_default(),
+                        serde_json::from_value(proof["public_inputs"].clone())?,
+                    )
+                    .await?;
+                proof_checked("Accreditation", verified)?
+            }
+            DemoStep::ProveOwnership => {
+                if !self.features.is_enabled(FeatureFlag::DemoProofs) {
+                    return Ok(serde_json::json!({ "skipped": "demo_proofs is turned off" }));
+                }
+                // The document hash the demo ownership proof accepts
+                let document_hash = {
+                    use sha2::{Digest, Sha256};
+                    let mut hasher = Sha256::new();
+                    hasher.update(format!("{}-ownership", property_id).as_bytes());
+                    format!("{:x}", hasher.finalize())
+                };
+                let generated = self
+                    .generate_ownership_proof(&property_id, &document_hash, &[])
+                    .await?;
+                let verified = self
+                    .verify_ownership_proof(
+                        generated["proof"].as_str().unwrap_or_default(),
+                        generated["program_hash"].as_str().unwrap_or_default(),
+                        vec![property_id],
+                    )
+                    .await?;
+                proof_checked("Ownership", verified)?
+            }
+            DemoStep::CreateEscrow => {
+                let escrow = self
+                    .create_escrow_unchecked(
+                        AccountId::from_hex(&buyer)?,
+                        AccountId::from_hex(&seller)?,
+                        context.amount,
+                        Some(&property_id),
+                    )
+                    .await?;
+                let escrow_hex = crate::account_id_to_hex(escrow.escrow_account_id);
+                context.escrow_account_id = Some(escrow_hex.clone());
+                serde_json::json!({ "escrow_account_id": escrow_hex })
+            }
+            DemoStep::FundEscrow => {
+                let escrow = escrow(self, context)?;
+                let tx_id = self.fund_escrow_unchecked(&escrow, None).await?;
+                serde_json::json!({ "tx_id": tx_id })
+            }
+            DemoStep::TransferProperty => {
+                let tx_id = self.transfer_property(&property_id, &buyer).await?;
+                serde_json::json!({ "tx_id": tx_id })
+            }
+            DemoStep::ReleaseEscrow => {
+                let escrow = escrow(self, context)?;
+                let tx_id = self.release_escrow_unchecked(&escrow).await?;
+                serde_json::json!({ "tx_id": tx_id })
+            }
+        })
+    }
+}
+
+/// A verification result, or an error when the proof did not verify.
+fn proof_checked(kind: &str, verified: serde_json::Value) -> Result<serde_json::Value> {
+    if verified["valid"] != true {
+        return Err(anyhow::anyhow!(
+            "{} proof did not verify: {}",
+            kind,
+            verified["message"].as_str().unwrap_or("no reason given")
+        ));
+    }
+    Ok(verified)
+}
@@ -0,0 +1,600 @@
+// src/documents.rs
+//
+// Closing documents
+//
+// The closing documents of an escrow are rendered from Handlebars templates
+// filled with the escrow and property records:
+// - purchase_agreement: parties, property, price, closing and title
+// - escrow_instructions: deposit, release and refund of the escrow
+// Built-in templates (templates/*.hbs) are replaced by a <kind>.hbs file in
+// DOCUMENT_TEMPLATES_DIR, read at startup. Templates render plain text and are
+// strict: a field missing from the data fails rendering instead of leaving a
+// blank.
+//
+// POST /escrows/:escrow_account_id/documents {"kind"} renders a document for
+// the buyer, the seller, an arbiter or the escrow's closing agent. Every
+// rendering is a new version with two artifacts in DOCUMENTS_DIR, each hashed
+// (SHA-256):
+// - PDF: the rendered text laid out by pdf.rs
+// - JSON: kind, version, template hash, the data filled in and the text
+// The PDF hash of the latest version of each kind is attached to the escrow
+// checklist (attachments.rs); a checklist can require documents before release.
+//
+// GET /escrows/:escrow_account_id/documents lists the versions, and
+// GET /escrows/:escrow_account_id/documents/:document_id returns the PDF, or
+// the JSON with ?format=json. Artifacts are checked against their hash when read.
+//
+// Dates are printed in UTC, or in the zone given as {"timezone"} or by the
+// X-Timezone header (see timestamps.rs); the zone is part of the JSON data.
+
+use anyhow::Result;
+use handlebars::Handlebars;
+use miden_client::account::AccountId;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    currency::{Locale, SERVICE_TOKEN},
+    escrow::{EscrowAuthError, ReleasePolicy},
+    pdf::text_pdf,
+    records::EscrowRecord,
+    timestamps::DisplayZone,
+    MidenClientWrapper,
+};
+
+pub const PDF_CONTENT_TYPE: &str = "application/pdf";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentKind {
+    PurchaseAgreement,
+    EscrowInstructions,
+}
+
+impl DocumentKind {
+    pub const ALL: [DocumentKind; 2] = [
+        DocumentKind::PurchaseAgreement,
+        DocumentKind::EscrowInstructions,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DocumentKind::PurchaseAgreement => "purchase_agreement",
+            DocumentKind::EscrowInstructions => "escrow_instructions",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            DocumentKind::PurchaseAgreement => "Purchase Agreement",
+            DocumentKind::EscrowInstructions => "Escrow Instructions",
+        }
+    }
+
+    fn builtin_template(&self) -> &'static str {
+        match self {
+            DocumentKind::PurchaseAgreement => {
+                include_str!("../templates/purchase_agreement.hbs")
+            }
+            DocumentKind::EscrowInstructions => {
+                include_str!("../templates/escrow_instructions.hbs")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactFormat {
+    Pdf,
+    Json,
+}
+
+impl ArtifactFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ArtifactFormat::Pdf => "pdf",
+            ArtifactFormat::Json => "json",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DocumentInput {
+    pub kind: DocumentKind,
+    /// Zone dates are printed in; UTC if absent
+    #[serde(default)]
+    pub timezone: Option<DisplayZone>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClosingDocument {
+    pub document_id: u64,
+    pub escrow_account_id: String,
+    pub property_id: Option<String>,
+    pub kind: DocumentKind,
+    /// Renderings of this kind for the escrow so far, this one included
+    pub version: u32,
+    /// SHA-256 of the template source (hex)
+    pub template_hash: String,
+    /// SHA-256 of the artifacts (hex)
+    pub pdf_hash: String,
+    pub json_hash: String,
+    pub generated_at: i64,
+}
+
+/// The templates documents are rendered from (see module docs).
+pub struct DocumentTemplates {
+    registry: Handlebars<'static>,
+    hashes: BTreeMap<DocumentKind, String>,
+}
+
+impl DocumentTemplates {
+    /// Built-in templates, each replaced by `<kind>.hbs` in `dir` if present.
+    pub fn load(dir: Option<&Path>) -> Result<Self> {
+        let mut registry = Handlebars::new();
+        registry.set_strict_mode(true);
+        registry.register_escape_fn(handlebars::no_escape);
+
+        let mut hashes = BTreeMap::new();
+        for kind in DocumentKind::ALL {
+            let custom = dir.map(|d| d.join(format!("{}.hbs", kind.as_str())));
+            let source = match custom.filter(|path| path.exists()) {
+                Some(path) => {
+                    tracing::info!("Using document template {}", path.display());
+                    std::fs::read_to_string(&path)
+                        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?
+                }
+                None => kind.builtin_template().to_string(),
+            };
+            registry
+                .register_template_string(kind.as_str(), &source)
+                .map_err(|e| anyhow::anyhow!("Invalid {} template: {}", kind.as_str(), e))?;
+            hashes.insert(kind, hex::encode(Sha256::digest(source.as_bytes())));
+        }
+
+        Ok(Self { registry, hashes })
+    }
+
+    pub fn render(&self, kind: DocumentKind, data: &serde_json::Value) -> Result<String> {
+        self.registry
+            .render(kind.as_str(), data)
+            .map_err(|e| anyhow::anyhow!("Failed to render {}: {}", kind.as_str(), e))
+    }
+
+    pub fn template_hash(&self, kind: DocumentKind) -> &str {
+        self.hashes
+            .get(&kind)
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+}
+
+impl std::fmt::Debug for DocumentTemplates {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DocumentTemplates")
+            .field("hashes", &self.hashes)
+            .finish()
+    }
+}
+
+/// A rendered document's artifacts.
+#[derive(Debug, Clone)]
+pub struct RenderedDocument {
+    pub pdf: Vec<u8>,
+    pub json: Vec<u8>,
+}
+
+impl RenderedDocument {
+    pub fn new(
+        kind: DocumentKind,
+        version: u32,
+        template_hash: &str,
+        data: &serde_json::Value,
+        text: &str,
+    ) -> Result<Self> {
+        let json = serde_json::to_vec_pretty(&serde_json::json!({
+            "kind": kind,
+            "title": kind.title(),
+            "version": version,
+            "template_hash": template_hash,
+            "data": data,
+            "text": text,
+        }))?;
+        Ok(Self {
+            pdf: text_pdf(kind.title(), text),
+            json,
+        })
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DocumentStore {
+    #[serde(skip)]
+    path: PathBuf,
+    /// Where the artifacts are kept
+    #[serde(skip)]
+    dir: PathBuf,
+    #[serde(default)]
+    documents: BTreeMap<u64, ClosingDocument>,
+    #[serde(default)]
+    next_document_id: u64,
+}
+
+impl DocumentStore {
+    pub fn load(path: impl Into<PathBuf>, dir: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<DocumentStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            DocumentStore::default()
+        };
+        store.path = path;
+        store.dir = dir.into();
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    fn artifact_path(&self, document_id: u64, format: ArtifactFormat) -> PathBuf {
+        self.dir
+            .join(format!("{}.{}", document_id, format.extension()))
+    }
+
+    /// Documents of an escrow, oldest first.
+    pub fn for_escrow(&self, escrow_account_id: &str) -> Vec<&ClosingDocument> {
+        self.documents
+            .values()
+            .filter(|d| d.escrow_account_id == escrow_account_id)
+            .collect()
+    }
+
+    /// The version the next rendering of `kind` for the escrow gets.
+    pub fn next_version(&self, escrow_account_id: &str, kind: DocumentKind) -> u32 {
+        self.for_escrow(escrow_account_id)
+            .into_iter()
+            .filter(|d| d.kind == kind)
+            .map(|d| d.version)
+            .max()
+            .unwrap_or(0)
+            + 1
+    }
+
+    pub fn get(&self, escrow_account_id: &str, document_id: u64) -> Result<&ClosingDocument> {
+        self.documents
+            .get(&document_id)
+            .filter(|d| d.escrow_account_id == escrow_account_id)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Document {} not found for escrow {}",
+                    document_id,
+                    escrow_account_id
+                )
+            })
+    }
+
+    /// Stores the artifacts of a rendering and records it.
+    pub fn add(
+        &mut self,
+        escrow_account_id: &str,
+        property_id: Option<String>,
+        kind: DocumentKind,
+        version: u32,
+        template_hash: &str,
+        rendered: &RenderedDocument,
+    ) -> Result<ClosingDocument> {
+        let document = ClosingDocument {
+            document_id: self.next_document_id + 1,
+            escrow_account_id: escrow_account_id.to_string(),
+            property_id,
+            kind,
+            version,
+            template_hash: template_hash.to_string(),
+            pdf_hash: sha256_hex(&rendered.pdf),
+            json_hash: sha256_hex(&rendered.json),
+            generated_at: chrono::Utc::now().timestamp(),
+        };
+
+        std::fs::create_dir_all(&self.dir)?;
+        for (format, bytes) in [
+            (ArtifactFormat::Pdf, &rendered.pdf),
+            (ArtifactFormat::Json, &rendered.json),
+        ] {
+            let path = self.artifact_path(document.document_id, format);
+            let tmp_path = path.with_extension("tmp");
+            std::fs::write(&tmp_path, bytes)?;
+            std::fs::rename(&tmp_path, &path)?;
+        }
+
+        self.next_document_id = document.document_id;
+        self.documents
+            .insert(document.document_id, document.clone());
+        self.save()?;
+        Ok(document)
+    }
+
+    /// An artifact, checked against the hash recorded for it.
+    pub fn artifact(&self, document: &ClosingDocument, format: ArtifactFormat) -> Result<Vec<u8>> {
+        let path = self.artifact_path(document.document_id, format);
+        let bytes = std::fs::read(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        let expected = match format {
+            ArtifactFormat::Pdf => &document.pdf_hash,
+            ArtifactFormat::Json => &document.json_hash,
+        };
+        if &sha256_hex(&bytes) != expected {
+            return Err(anyhow::anyhow!(
+                "Document {} {} does not match its recorded hash",
+                document.document_id,
+                format.extension()
+            ));
+        }
+        Ok(bytes)
+    }
+}
+
+/// An artifact with what the HTTP layer needs to serve it.
+#[derive(Debug, Clone)]
+pub struct DocumentArtifact {
+    pub content_type: &'static str,
+    pub filename: String,
+    pub bytes: Vec<u8>,
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    GenerateDocument {
+        escrow_account_id: String,
+        input: DocumentInput,
+        api_key: Option<String>,
+    } -> ClosingDocument;
+    |client, op| client.generate_document(&op.escrow_account_id, op.input, op.api_key.as_deref());
+
+    ListDocuments {
+        escrow_account_id: String,
+        api_key: Option<String>,
+    } -> Vec<ClosingDocument>;
+    |client, op| client.list_documents(&op.escrow_account_id, op.api_key.as_deref());
+
+    GetDocumentArtifact {
+        escrow_account_id: String,
+        document_id: u64,
+        format: ArtifactFormat,
+        api_key: Option<String>,
+    } -> DocumentArtifact;
+    |client, op| {
+        client.get_document_artifact(
+            &op.escrow_account_id,
+            op.document_id,
+            op.format,
+            op.api_key.as_deref(),
+        )
+    }
+}
+
+impl MidenClientWrapper {
+    /// The escrow's record, if the caller is its buyer or seller, an arbiter
+    /// or its closing agent.
+    pub(crate) fn document_escrow(
+        &self,
+        escrow_account_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<EscrowRecord> {
+        let escrow_hex = escrow_account_id.to_lowercase();
+        let record = self
+            .records
+            .escrows
+            .get(&escrow_hex)
+            .ok_or_else(|| anyhow::anyhow!("Escrow {} not found in service records", escrow_hex))?
+            .clone();
+
+        if let Some(principal) = self.request_principal(api_key)? {
+            if !(principal.owns(&record.buyer_account_id)
+                || principal.owns(&record.seller_account_id)
+                || principal.arbiter
+                || principal.assigned_to(&escrow_hex))
+            {
+                return Err(EscrowAuthError::Forbidden(format!(
+                    "API key {} ({}) is not a party to escrow {}",
+                    principal.key_id, principal.label, escrow_hex
+                ))
+                .into());
+            }
+        }
+        Ok(record)
+    }
+
+    /// What templates are filled with.
+    fn document_data(
+        &self,
+        record: &EscrowRecord,
+        kind: DocumentKind,
+        version: u32,
+        zone: DisplayZone,
+    ) -> serde_json::Value {
+        let now = chrono::Utc::now().timestamp();
+
+        let amount = match &record.asset_faucet_id {
+            None => Locale::En.format_money(record.amount, &SERVICE_TOKEN),
+            Some(faucet) => match AccountId::from_hex(faucet)
+                .ok()
+                .and_then(|faucet| self.token_units(faucet))
+            {
+                Some((symbol, decimals)) => {
+                    Locale::En.format_amount(record.amount, &symbol, decimals)
+                }
+                None => format!("{} base units of faucet {}", record.amount, faucet),
+            },
+        };
+
+        let property = record
+            .property_id
+            .as_deref()
+            .and_then(|id| self.records.properties.get(id))
+            .map(|p| {
+                let details = self.listing_details.get(&p.property_id);
+                let currency =
+                    crate::currency::find_currency(self.price_currency(p)).unwrap_or(SERVICE_TOKEN);
+                serde_json::json!({
+                    "property_id": p.property_id,
+                    "property_type": p.property_type,
+                    "price": Locale::En.format_money(p.price, &currency),
+                    "ipfs_cid": p.ipfs_cid,
+                    "address": details.and_then(|d| d.address.clone()),
+                    "size_sqm": details.and_then(|d| d.size_sqm),
+                    "location": p.location.as_ref().map(|l| serde_json::json!({
+                        "latitude": l.latitude,
+                        "longitude": l.longitude,
+                    })),
+                })
+            });
+
+        let mut conditions = Vec::new();
+        if let Some(checklist) = self.attachments.checklist(&record.escrow_account_id) {
+            if checklist.require_insurance_binder {
+                conditions.push("an active insurance binder covers the property".to_string());
+            }
+            for required in &checklist.require_documents {
+                conditions.push(format!(
+                    "the {} has been generated for this escrow",
+                    required.title().to_lowercase()
+                ));
+            }
+            for required in &checklist.require_signatures {
+                conditions.push(format!(
+                    "the latest {} has been signed by all its signers",
+                    required.title().to_lowercase()
+                ));
+            }
+        }
+
+        serde_json::json!({
+            "document": {
+                "kind": kind,
+                "title": kind.title(),
+                "version": version,
+            },
+            "generated_on": zone.date(now),
+            "generated_at": zone.date_time(now),
+            "timezone": zone,
+            "network": self.config.profile.as_str(),
+            "escrow": {
+                "escrow_account_id": record.escrow_account_id,
+                "buyer_account_id": record.buyer_account_id,
+                "seller_account_id": record.seller_account_id,
+                "amount": amount,
+                "amount_base_units": record.amount,
+                "status": format!("{:?}", record.status).to_lowercase(),
+                "opened_on": zone.date(record.created_at),
+            },
+            "property": property,
+            "release": {
+                "released_by": match self.config.escrow_release_policy {
+                    ReleasePolicy::SellerOrArbiter => "the Seller or an arbiter",
+                    ReleasePolicy::ArbiterOnly => "an arbiter",
+                },
+                "refunded_by": "the Buyer or an arbiter",
+                "conditions": conditions,
+            },
+        })
+    }
+
+    /// Renders a closing document and attaches its hash to the escrow
+    /// checklist.
+    pub fn generate_document(
+        &mut self,
+        escrow_account_id: &str,
+        input: DocumentInput,
+        api_key: Option<&str>,
+    ) -> Result<ClosingDocument> {
+        let record = self.document_escrow(escrow_account_id, api_key)?;
+        let escrow_hex = record.escrow_account_id.clone();
+        let kind = input.kind;
+
+        let version = self.documents.next_version(&escrow_hex, kind);
+        let zone = input.timezone.unwrap_or_default();
+        let data = self.document_data(&record, kind, version, zone);
+        let template_hash = self.document_templates.template_hash(kind).to_string();
+        let text = self.document_templates.render(kind, &data)?;
+        let rendered = RenderedDocument::new(kind, version, &template_hash, &data, &text)?;
+
+        let document = self.documents.add(
+            &escrow_hex,
+            record.property_id.clone(),
+            kind,
+            version,
+            &template_hash,
+            &rendered,
+        )?;
+        self.attachments.attach_document(&escrow_hex, &document)?;
+        tracing::info!(
+            "Generated {} v{} for escrow {} (document {}, sha256 {})",
+            kind.as_str(),
+            version,
+            escrow_hex,
+            document.document_id,
+            document.pdf_hash
+        );
+        Ok(document)
+    }
+
+    pub fn list_documents(
+        &self,
+        escrow_account_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<Vec<ClosingDocument>> {
+        let record = self.document_escrow(escrow_account_id, api_key)?;
+        Ok(self
+            .documents
+            .for_escrow(&record.escrow_account_id)
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+
+    pub fn get_document_artifact(
+        &self,
+        escrow_account_id: &str,
+        document_id: u64,
+        format: ArtifactFormat,
+        api_key: Option<&str>,
+    ) -> Result<DocumentArtifact> {
+        let record = self.document_escrow(escrow_account_id, api_key)?;
+        let document = self.documents.get(&record.escrow_account_id, document_id)?;
+        let bytes = self.documents.artifact(document, format)?;
+
+        Ok(DocumentArtifact {
+            content_type: match format {
+                ArtifactFormat::Pdf => PDF_CONTENT_TYPE,
+                ArtifactFormat::Json => "application/json",
+            },
+            filename: format!(
+                "{}-v{}.{}",
+                document.kind.as_str().replace('_', "-"),
+                document.version,
+                format.extension()
+            ),
+            bytes,
+        })
+    }
+}
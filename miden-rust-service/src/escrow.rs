@@ -1,21 +1,69 @@
 // src/escrow.rs - Escrow System Implementation for Real Estate Transactions
 // UPDATED: Now accepts BOTH hex IDs and account names
+//
+// Authorization: escrow actions are tied to the API-key principal of the caller
+// (see principals.rs) and checked against the parties recorded when the escrow
+// was created, never the parties claimed in the request:
+// - create: a principal bound to the buyer or seller (or an arbiter)
+// - fund: the buyer, or a delegate holding an allowance from the buyer
+//   (allowances.rs)
+// - refund: the buyer or an arbiter
+// - release: per ESCROW_RELEASE_POLICY, the seller or an arbiter
+//   (seller_or_arbiter) or an arbiter only (arbiter_only); the escrow's closing
+//   agent may request it, subject to a second approver (approvals.rs)
+//
+// Escrows opened for a property additionally need lender sign-off on release
+// while the property carries active liens (liens.rs), and must satisfy their
+// release checklist, if one was set (attachments.rs). Releases above
+// ESCROW_APPROVAL_THRESHOLD wait for a second approver (approvals.rs).
+//
+// Funds are tagged per escrow: funding sends exactly the escrowed amount of the
+// escrow's settlement token as one P2ID note, whose ID and faucet are kept in
+// the escrow record. Escrows for a property settle in the token of its listing,
+// others in the service token (settlement_tokens.rs). Release, refund and split
+// settlement consume only those notes and pay out only that amount, so buyers
+// and escrow accounts can hold funds for other deals at the same time. With
+// PLATFORM_FEE_BPS set, payouts to the seller are reduced by the platform fee,
+// which goes to the treasury in the same transaction (treasury.rs), and so are
+// an agent commission set on the escrow (fee_splits.rs) and tax withheld for
+// the property's jurisdiction (withholding.rs). With
+// ESCROW_YIELD_RATE_BPS set, long-held funded escrows accrue yield, which is
+// split and paid out when they settle (escrow_yield.rs).
+//
+// With a master secret configured (secrets.rs), every escrow gets a fresh
+// account derived from the secret and the escrow's deal number, which the
+// escrow record keeps. After losing the client store and keystore, the
+// accounts and their keys are rebuilt from the records (POST
+// /admin/escrows/rebuild).
 
 use anyhow::Result;
-use rand::RngCore;
+use rand::{RngCore, SeedableRng};
 use miden_client::{Serializable, Deserializable};
 use miden_client::{
-    account::{AccountBuilder, AccountId, AccountStorageMode, AccountType, component::BasicWallet},
+    account::{Account, AccountId},
     asset::FungibleAsset,
     auth::AuthSecretKey,
     crypto::rpo_falcon512::SecretKey,
-    note::{create_p2id_note, NoteType},
-    transaction::{OutputNote, TransactionRequestBuilder},
-    Felt,
 };
-use miden_lib::account::auth::AuthRpoFalcon512;
-
-use crate::MidenClientWrapper;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account_id_to_hex,
+    allowances::AllowancePurpose,
+    client,
+    escrow_yield::YieldOutcome,
+    fee_splits::PayoutKind,
+    hooks::{HookMetadata, HookPoint},
+    ledger::{JournalKind, Posting},
+    liens::LienAction,
+    principals::Principal,
+    read_cache::Touched,
+    records::EscrowRecord,
+    retry_queue::{expect_escrow_status, RetryOperation},
+    secrets::MasterSecret,
+    spend_receipts::SpendReceipt,
+    MidenClientWrapper,
+};
 
 /// Escrow account information
 #[derive(Debug, Clone)]
@@ -27,7 +75,8 @@ pub struct EscrowAccount {
     pub status: EscrowStatus,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum EscrowStatus {
     Created,
     Funded,
@@ -36,6 +85,56 @@ pub enum EscrowStatus {
     Disputed,
 }
 
+/// Escrow operations subject to authorization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscrowAction {
+    Create,
+    Fund,
+    /// Funding by a delegate whose allowance from the buyer was checked
+    DelegatedFund,
+    Release,
+    Refund,
+}
+
+impl EscrowAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EscrowAction::Create => "create",
+            EscrowAction::Fund | EscrowAction::DelegatedFund => "fund",
+            EscrowAction::Release => "release",
+            EscrowAction::Refund => "refund",
+        }
+    }
+}
+
+/// Who may release escrowed funds to the seller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReleasePolicy {
+    SellerOrArbiter,
+    ArbiterOnly,
+}
+
+impl std::str::FromStr for ReleasePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "seller_or_arbiter" => Ok(ReleasePolicy::SellerOrArbiter),
+            "arbiter_only" => Ok(ReleasePolicy::ArbiterOnly),
+            other => Err(anyhow::anyhow!("Unknown escrow release policy: {}", other)),
+        }
+    }
+}
+
+/// Authorization failure; the HTTP layer maps these to 401 / 403.
+#[derive(Debug, thiserror::Error)]
+pub enum EscrowAuthError {
+    #[error("Unauthorized: {0}")]
+    Unauthenticated(String),
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+}
+
 /// Helper function to parse account ID from hex string or name
 /// Accepts BOTH "alice"/"faucet" AND hex IDs like "0x24e4b0c8..."
 fn parse_account_id(
@@ -76,14 +175,237 @@ fn parse_account_id(
     Ok(account_id)
 }
 
+/// Escrow accounts are plain public wallets.
+fn build_escrow_account(init_seed: [u8; 32], key_pair: &SecretKey) -> Result<Account> {
+    client::wallet_account(init_seed, key_pair)
+}
+
+/// Init seed and signing key of an escrow deal's account.
+fn escrow_seed_material(
+    secret: &MasterSecret,
+    deal_id: u64,
+    generation: u32,
+) -> Result<([u8; 32], SecretKey)> {
+    let (init_seed, auth_key_seed) = secret.escrow_seeds(deal_id, generation)?;
+    let mut key_rng = rand_chacha::ChaCha20Rng::from_seed(auth_key_seed);
+    Ok((init_seed, SecretKey::with_rng(&mut key_rng)))
+}
+
+crate::operations! {
+    CreateEscrow {
+        buyer_account_str: String,
+        seller_account_str: String,
+        amount: u64,
+        property_id: Option<String>,
+        api_key: Option<String>,
+    } -> EscrowAccount;
+    touched: |op| Some(Touched::Accounts(vec![
+        op.buyer_account_str.clone(),
+        op.seller_account_str.clone(),
+    ]));
+    |client, op| {
+        client
+            .create_escrow(
+                &op.buyer_account_str,
+                &op.seller_account_str,
+                op.amount,
+                op.property_id.as_deref(),
+                op.api_key.as_deref(),
+            )
+            .await
+    };
+
+    FundEscrow {
+        escrow: EscrowAccount,
+        api_key: Option<String>,
+    } -> String;
+    touched: |op| Some(Touched::Accounts(vec![
+        account_id_to_hex(op.escrow.escrow_account_id),
+        account_id_to_hex(op.escrow.buyer_account_id),
+        account_id_to_hex(op.escrow.seller_account_id),
+    ]));
+    |client, op| client.fund_escrow(&op.escrow, op.api_key.as_deref()).await;
+
+    /// Answers with the release transaction and its spend receipts
+    ReleaseEscrow {
+        escrow: EscrowAccount,
+        api_key: Option<String>,
+    } -> (String, Vec<SpendReceipt>);
+    touched: |op| Some(Touched::Accounts(vec![
+        account_id_to_hex(op.escrow.escrow_account_id),
+        account_id_to_hex(op.escrow.buyer_account_id),
+        account_id_to_hex(op.escrow.seller_account_id),
+    ]));
+    |client, op| {
+        let tx_id = client.release_escrow(&op.escrow, op.api_key.as_deref()).await?;
+        let escrow_hex = account_id_to_hex(op.escrow.escrow_account_id);
+        let receipts = client.release_receipts(&escrow_hex, &tx_id).await;
+        Ok((tx_id, receipts))
+    };
+
+    RefundEscrow {
+        escrow: EscrowAccount,
+        api_key: Option<String>,
+    } -> String;
+    touched: |op| Some(Touched::Accounts(vec![
+        account_id_to_hex(op.escrow.escrow_account_id),
+        account_id_to_hex(op.escrow.buyer_account_id),
+        account_id_to_hex(op.escrow.seller_account_id),
+    ]));
+    |client, op| client.refund_escrow(&op.escrow, op.api_key.as_deref()).await
+}
+
 impl MidenClientWrapper {
+    /// Resolves the principal behind an API key, if API-key auth is enforced
+    /// (ESCROW_AUTH_REQUIRED).
+    pub(crate) fn request_principal(&self, api_key: Option<&str>) -> Result<Option<&Principal>> {
+        if !self.config.escrow_auth_required {
+            return Ok(None);
+        }
+
+        let key = api_key.ok_or_else(|| {
+            EscrowAuthError::Unauthenticated("X-API-Key header is required for escrow actions".into())
+        })?;
+        let principal = self
+            .principals
+            .authenticate(key)
+            .ok_or_else(|| EscrowAuthError::Unauthenticated("Invalid or revoked API key".into()))?;
+
+        Ok(Some(principal))
+    }
+
+    /// Checks that the caller may act for `account_hex` outside of an escrow
+    /// (lien lenders, lease owners, auction sellers); arbiters may act for
+    /// anyone. `role` names the account in the error.
+    pub(crate) fn authorize_account(
+        &self,
+        api_key: Option<&str>,
+        account_hex: &str,
+        role: &str,
+    ) -> Result<()> {
+        if let Some(principal) = self.request_principal(api_key)? {
+            if !(principal.owns(account_hex) || principal.arbiter) {
+                return Err(EscrowAuthError::Forbidden(format!(
+                    "API key {} ({}) may not act for {} {}",
+                    principal.key_id, principal.label, role, account_hex
+                ))
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the caller is the closing agent assigned to `escrow_hex`.
+    pub(crate) fn is_closing_agent(&self, api_key: Option<&str>, escrow_hex: &str) -> Result<bool> {
+        Ok(self
+            .request_principal(api_key)?
+            .is_some_and(|p| p.assigned_to(escrow_hex)))
+    }
+
+    /// Checks that the caller may perform `action` on `escrow` and returns the
+    /// escrow with its parties and amount taken from the service records.
+    pub(crate) fn authorize_escrow(
+        &self,
+        api_key: Option<&str>,
+        action: EscrowAction,
+        escrow: &EscrowAccount,
+    ) -> Result<EscrowAccount> {
+        let escrow_hex = account_id_to_hex(escrow.escrow_account_id);
+        let principal = self.request_principal(api_key)?;
+
+        let Some(record) = self.records.escrows.get(&escrow_hex) else {
+            if principal.is_some() {
+                return Err(EscrowAuthError::Forbidden(format!(
+                    "Escrow {} is not known to this service",
+                    escrow_hex
+                ))
+                .into());
+            }
+            // Auth disabled: escrows opened before records existed are still usable
+            return Ok(escrow.clone());
+        };
+
+        if !record
+            .buyer_account_id
+            .eq_ignore_ascii_case(&account_id_to_hex(escrow.buyer_account_id))
+            || !record
+                .seller_account_id
+                .eq_ignore_ascii_case(&account_id_to_hex(escrow.seller_account_id))
+        {
+            return Err(EscrowAuthError::Forbidden(format!(
+                "Buyer and seller do not match the parties recorded for escrow {}",
+                escrow_hex
+            ))
+            .into());
+        }
+
+        if let Some(principal) = principal {
+            let buyer = principal.owns(&record.buyer_account_id);
+            let seller = principal.owns(&record.seller_account_id);
+            let closing_agent = principal.assigned_to(&escrow_hex);
+
+            let allowed = match action {
+                EscrowAction::Create => buyer || seller || principal.arbiter,
+                EscrowAction::Fund => buyer,
+                EscrowAction::DelegatedFund => !principal.closing_agent,
+                EscrowAction::Refund => buyer || principal.arbiter,
+                // A closing agent's release is always parked for approval
+                EscrowAction::Release => match self.config.escrow_release_policy {
+                    ReleasePolicy::SellerOrArbiter => seller || principal.arbiter || closing_agent,
+                    ReleasePolicy::ArbiterOnly => principal.arbiter || closing_agent,
+                },
+            };
+
+            if !allowed {
+                return Err(EscrowAuthError::Forbidden(format!(
+                    "API key {} ({}) may not {} escrow {}",
+                    principal.key_id,
+                    principal.label,
+                    action.as_str(),
+                    escrow_hex
+                ))
+                .into());
+            }
+        }
+
+        Ok(EscrowAccount {
+            escrow_account_id: escrow.escrow_account_id,
+            buyer_account_id: escrow.buyer_account_id,
+            seller_account_id: escrow.seller_account_id,
+            amount: record.amount,
+            status: record.status.clone(),
+        })
+    }
+
+    /// Rebuilds an escrow from its service record.
+    pub(crate) fn recorded_escrow(&self, escrow_account_id: &str) -> Result<EscrowAccount> {
+        let record = self
+            .records
+            .escrows
+            .get(escrow_account_id)
+            .ok_or_else(|| anyhow::anyhow!("Escrow {} not found in service records", escrow_account_id))?;
+
+        Ok(EscrowAccount {
+            escrow_account_id: parse_account_id(&record.escrow_account_id, None, None)?,
+            buyer_account_id: parse_account_id(&record.buyer_account_id, None, None)?,
+            seller_account_id: parse_account_id(&record.seller_account_id, None, None)?,
+            amount: record.amount,
+            status: record.status.clone(),
+        })
+    }
+
     /// Create a new escrow account for a property transaction
     /// UPDATED: Now accepts BOTH hex IDs and account names ("alice", "faucet")
+    ///
+    /// `property_id` ties the escrow to the property being sold, so releasing
+    /// it is subject to the property's liens.
     pub async fn create_escrow(
         &mut self,
         buyer_account_str: &str,
         seller_account_str: &str,
         amount: u64,
+        property_id: Option<&str>,
+        api_key: Option<&str>,
     ) -> Result<EscrowAccount> {
         tracing::info!("🔒 Creating escrow account");
         tracing::info!("   Buyer: {}", buyer_account_str);
@@ -103,31 +425,106 @@ impl MidenClientWrapper {
             self.faucet_account_id,
         )?;
 
+        if let Some(property_id) = property_id {
+            if !self.records.properties.contains_key(property_id) {
+                return Err(anyhow::anyhow!("Property {} has not been minted", property_id));
+            }
+            self.check_property_active(property_id)?;
+        }
+
         tracing::info!("✅ Buyer account resolved: {}", buyer_account);
         tracing::info!("✅ Seller account resolved: {}", seller_account);
 
-        // Create escrow account (regular account that will hold funds)
-        let mut init_seed = [0u8; 32];
-        self.client.rng().fill_bytes(&mut init_seed);
-        let key_pair = SecretKey::with_rng(self.client.rng());
+        if let Some(principal) = self.request_principal(api_key)? {
+            if !(principal.owns(&account_id_to_hex(buyer_account))
+                || principal.owns(&account_id_to_hex(seller_account))
+                || principal.arbiter)
+            {
+                return Err(EscrowAuthError::Forbidden(format!(
+                    "API key {} ({}) may not {} an escrow between these parties",
+                    principal.key_id,
+                    principal.label,
+                    EscrowAction::Create.as_str()
+                ))
+                .into());
+            }
+        }
 
-        let builder = AccountBuilder::new(init_seed)
-            .account_type(AccountType::RegularAccountUpdatableCode)
-            .storage_mode(AccountStorageMode::Public)
-            .with_auth_component(AuthRpoFalcon512::new(key_pair.public_key().into()))
-            .with_component(BasicWallet);
+        self.create_escrow_unchecked(buyer_account, seller_account, amount, property_id)
+            .await
+    }
 
-        let escrow_account = builder.build()?;
+    /// Opens an escrow whose creation was already authorized, for flows the
+    /// service runs itself (sagas.rs).
+    pub(crate) async fn create_escrow_unchecked(
+        &mut self,
+        buyer_account: AccountId,
+        seller_account: AccountId,
+        amount: u64,
+        property_id: Option<&str>,
+    ) -> Result<EscrowAccount> {
+        // Escrows for a property settle in the listing's token
+        let asset_faucet_id = property_id
+            .map(|_| self.settlement_faucet(property_id))
+            .transpose()?
+            .map(account_id_to_hex);
+
+        // Create escrow account (regular account that will hold funds), derived
+        // for this deal when a master secret is configured
+        let (deal, init_seed, key_pair) = match self.master_secret.as_mut() {
+            Some(secret) => {
+                let (deal_id, generation) = secret.next_escrow_deal()?;
+                let (init_seed, key_pair) = escrow_seed_material(secret, deal_id, generation)?;
+                (Some((deal_id, generation)), init_seed, key_pair)
+            }
+            None => {
+                let mut init_seed = [0u8; 32];
+                self.client.rng().fill_bytes(&mut init_seed);
+                (None, init_seed, SecretKey::with_rng(self.client.rng()))
+            }
+        };
+
+        let escrow_account = build_escrow_account(init_seed, &key_pair)?;
         let escrow_account_id = escrow_account.id();
 
         // Add escrow account to client
         self.client.add_account(&escrow_account, false).await?;
-        self.keystore.add_key(&AuthSecretKey::RpoFalcon512(key_pair))?;
-
-        tracing::info!("✅ Escrow account created: {}", escrow_account_id);
+        self.keystore()?.add_key(&AuthSecretKey::RpoFalcon512(key_pair))?;
+
+        match deal {
+            Some((deal_id, generation)) => tracing::info!(
+                "✅ Escrow account created: {} (deal {}, generation {})",
+                escrow_account_id,
+                deal_id,
+                generation
+            ),
+            None => tracing::info!("✅ Escrow account created: {}", escrow_account_id),
+        }
 
         // Sync state
-        self.client.sync_state().await?;
+        self.sync_state().await?;
+
+        let now = chrono::Utc::now().timestamp();
+        self.records.record_escrow(EscrowRecord {
+            escrow_account_id: account_id_to_hex(escrow_account_id),
+            buyer_account_id: account_id_to_hex(buyer_account),
+            seller_account_id: account_id_to_hex(seller_account),
+            property_id: property_id.map(str::to_string),
+            amount,
+            status: EscrowStatus::Created,
+            fund_tx_id: None,
+            settle_tx_id: None,
+            funding_note_ids: Vec::new(),
+            asset_faucet_id,
+            deal_id: deal.map(|(deal_id, _)| deal_id),
+            seed_generation: deal.map(|(_, generation)| generation),
+            metadata: HookMetadata::new(),
+            escrow_yield: None,
+            commission: None,
+            withholding: None,
+            created_at: now,
+            updated_at: now,
+        });
 
         Ok(EscrowAccount {
             escrow_account_id,
@@ -142,53 +539,110 @@ impl MidenClientWrapper {
     pub async fn fund_escrow(
         &mut self,
         escrow: &EscrowAccount,
+        api_key: Option<&str>,
+    ) -> Result<String> {
+        let escrow_hex = account_id_to_hex(escrow.escrow_account_id);
+        let amount = self
+            .records
+            .escrows
+            .get(&escrow_hex)
+            .map_or(escrow.amount, |r| r.amount);
+        let (escrow, allowance_id) =
+            self.authorize_funding(api_key, escrow, AllowancePurpose::EscrowFunding, amount)?;
+
+        let result = self.fund_escrow_unchecked(&escrow, allowance_id).await;
+        self.retry_on_failure(
+            RetryOperation::FundEscrow {
+                escrow_account_id: escrow_hex,
+                allowance_id,
+            },
+            result,
+        )
+    }
+
+    /// Funds an escrow whose funding was already authorized, recording the
+    /// spend against `allowance_id` when a delegate funds it. Journaled; only
+    /// a created escrow is funded.
+    pub(crate) async fn fund_escrow_unchecked(
+        &mut self,
+        escrow: &EscrowAccount,
+        allowance_id: Option<u64>,
     ) -> Result<String> {
+        expect_escrow_status(escrow, EscrowStatus::Created)?;
+        let escrow_hex = account_id_to_hex(escrow.escrow_account_id);
+        let op_id = self.records.begin_operation("fund_escrow", &escrow_hex);
+
+        let result = self.submit_escrow_funding(escrow).await;
+
+        let tx_id = result.as_ref().ok().cloned();
+        self.records.finish_operation(op_id, &result, tx_id.clone());
+
+        if let Ok(tx_id) = &result {
+            if let Some(allowance_id) = allowance_id {
+                self.allowances.spend(
+                    allowance_id,
+                    escrow.amount,
+                    AllowancePurpose::EscrowFunding,
+                    &escrow_hex,
+                    tx_id,
+                );
+            }
+            self.records
+                .update_escrow(&escrow_hex, EscrowStatus::Funded, Some(tx_id.clone()));
+            self.start_escrow_yield(&escrow_hex);
+        }
+
+        result
+    }
+
+    /// Sends the escrowed amount of the escrow's settlement token from the
+    /// buyer to the escrow account as a P2ID note, tagged to the escrow in its
+    /// record.
+    async fn submit_escrow_funding(
+        &mut self,
+        escrow: &EscrowAccount,
+    ) -> Result<String> {
+        let faucet_account_id =
+            self.escrow_settlement_faucet(&account_id_to_hex(escrow.escrow_account_id))?;
+
         tracing::info!("💰 Funding escrow");
         tracing::info!("   From (Buyer): {}", escrow.buyer_account_id);
         tracing::info!("   To (Escrow): {}", escrow.escrow_account_id);
         tracing::info!("   Amount: {}", escrow.amount);
 
         // Sync first to get latest state
-        self.client.sync_state().await?;
+        self.sync_state().await?;
 
-        // Get buyer's account to access vault
+        // Only the escrowed amount leaves the buyer's vault, so the buyer can
+        // fund other escrows from the rest
         let buyer_account = self
             .client
             .get_account(escrow.buyer_account_id)
             .await?
             .ok_or_else(|| anyhow::anyhow!("Buyer account not found"))?;
-
-        // Get assets from buyer's vault
-        let vault = buyer_account.account().vault();
-        let vault_assets: Vec<_> = vault.assets().collect();
-
-        if vault_assets.is_empty() {
-            return Err(anyhow::anyhow!("Buyer's vault is empty. Cannot fund escrow."));
+        let balance = buyer_account
+            .account()
+            .vault()
+            .get_balance(faucet_account_id)
+            .unwrap_or(0);
+        if balance < escrow.amount {
+            return Err(anyhow::anyhow!(
+                "Buyer's vault holds {} of the {} needed to fund the escrow",
+                balance,
+                escrow.amount
+            ));
         }
 
-        tracing::info!("✅ Found {} assets in buyer's vault", vault_assets.len());
+        let asset = FungibleAsset::new(faucet_account_id, escrow.amount)?;
 
-        // For this POC, send ALL assets from vault to escrow
-        // In production, you'd select specific assets matching the amount
-        let assets_to_send: Vec<_> = vault_assets.into_iter().collect();
-
-        tracing::info!("📦 Sending {} assets to escrow", assets_to_send.len());
-
-        // Create P2ID note to escrow account
-        let p2id_note = create_p2id_note(
+        // P2ID note to the escrow account
+        let (note_id, transaction_request) = client::p2id_request(
             escrow.buyer_account_id,
             escrow.escrow_account_id,
-            assets_to_send,
-            NoteType::Public,
-            Felt::new(0),
+            vec![asset.into()],
             &mut self.rng,
         )?;
-
-        // Create transaction with output note
-        let output_notes = vec![OutputNote::Full(p2id_note)];
-        let transaction_request = TransactionRequestBuilder::new()
-            .own_output_notes(output_notes)
-            .build()?;
+        let note_id = note_id.to_string();
 
         tracing::info!("📝 Executing fund escrow transaction...");
 
@@ -199,91 +653,190 @@ impl MidenClientWrapper {
             .await?;
 
         let tx_id = transaction_id.to_string();
-        tracing::info!("✅ Escrow funded! TX: {}", tx_id);
+        tracing::info!("✅ Escrow funded! TX: {} (note {})", tx_id, note_id);
+        let escrow_hex = account_id_to_hex(escrow.escrow_account_id);
+        self.post_ledger_entry(
+            JournalKind::EscrowFunding,
+            Posting::transfer(
+                &account_id_to_hex(escrow.buyer_account_id),
+                &escrow_hex,
+                &account_id_to_hex(faucet_account_id),
+                escrow.amount,
+            )
+            .to_vec(),
+            &tx_id,
+            Some(&escrow_hex),
+        );
+
+        self.records.tag_escrow_funding(
+            &account_id_to_hex(escrow.escrow_account_id),
+            &note_id,
+            &account_id_to_hex(faucet_account_id),
+            Some(tx_id.clone()),
+        );
 
         // Sync
-        self.client.sync_state().await?;
+        self.sync_state().await?;
 
         Ok(tx_id)
     }
 
-    /// Release funds from escrow to seller (on successful sale)
-    pub async fn release_escrow(
-        &mut self,
-        escrow: &EscrowAccount,
-    ) -> Result<String> {
-        tracing::info!("🔓 Releasing escrow funds to seller");
-        tracing::info!("   Escrow: {}", escrow.escrow_account_id);
-        tracing::info!("   To (Seller): {}", escrow.seller_account_id);
+    /// Consumes the escrow's own funding notes into its vault and returns the
+    /// asset that belongs to the deal. Notes and vault funds of other deals are
+    /// left alone. Escrows funded before funding notes were tagged consume
+    /// every note waiting for the escrow account.
+    async fn collect_escrow_funds(&mut self, escrow: &EscrowAccount) -> Result<FungibleAsset> {
+        let escrow_hex = account_id_to_hex(escrow.escrow_account_id);
+        let (funding_note_ids, asset_faucet_id) = self
+            .records
+            .escrows
+            .get(&escrow_hex)
+            .map(|record| (record.funding_note_ids.clone(), record.asset_faucet_id.clone()))
+            .unwrap_or_default();
+        let faucet_account_id = match asset_faucet_id {
+            Some(faucet_hex) => parse_account_id(&faucet_hex, None, None)?,
+            None => self
+                .faucet_account_id
+                .ok_or_else(|| anyhow::anyhow!("Faucet not initialized"))?,
+        };
 
         // Sync to get latest notes
-        self.client.sync_state().await?;
+        self.sync_state().await?;
 
-        // Get consumable notes for escrow account
         let consumable_notes = self
             .client
             .get_consumable_notes(Some(escrow.escrow_account_id))
             .await?;
-
-        if consumable_notes.is_empty() {
-            return Err(anyhow::anyhow!("No funds in escrow to release"));
-        }
-
-        tracing::info!("✅ Found {} note(s) in escrow", consumable_notes.len());
-
-        // First consume the notes to add to escrow vault
         let note_ids: Vec<_> = consumable_notes
             .iter()
             .map(|(note, _)| note.id())
+            .filter(|id| {
+                funding_note_ids.is_empty() || funding_note_ids.contains(&id.to_string())
+            })
             .collect();
 
-        let consume_request = TransactionRequestBuilder::new()
-            .build_consume_notes(note_ids)?;
-
-        tracing::info!("📝 Consuming escrow notes...");
-
-        let consume_tx_id = self
-            .client
-            .submit_new_transaction(escrow.escrow_account_id, consume_request)
-            .await?;
-
-        tracing::info!("✅ Notes consumed: {}", consume_tx_id);
-
-        // Sync to update vault
-        self.client.sync_state().await?;
+        // Notes already consumed by an earlier attempt are in the vault
+        if !note_ids.is_empty() {
+            tracing::info!("✅ Found {} funding note(s) for escrow", note_ids.len());
+            tracing::info!("📝 Consuming escrow notes...");
+
+            // Split into batches (consume_batches.rs); a partial consumption
+            // fails the release, and the retry consumes the rest
+            let consumed = self
+                .submit_consume_notes(escrow.escrow_account_id, note_ids)
+                .await?;
+            tracing::info!("✅ Notes consumed: {}", consumed.transaction_ids().join(", "));
+            consumed.into_tx_id()?;
+        }
 
-        // Now transfer from escrow vault to seller
         let escrow_account = self
             .client
             .get_account(escrow.escrow_account_id)
             .await?
             .ok_or_else(|| anyhow::anyhow!("Escrow account not found"))?;
+        let balance = escrow_account
+            .account()
+            .vault()
+            .get_balance(faucet_account_id)
+            .unwrap_or(0);
+        if balance < escrow.amount {
+            return Err(anyhow::anyhow!(
+                "Escrow vault holds {} of the {} escrowed",
+                balance,
+                escrow.amount
+            ));
+        }
 
-        // Get assets from vault
-        let vault = escrow_account.account().vault();
-        let vault_assets: Vec<_> = vault.assets().collect();
+        Ok(FungibleAsset::new(faucet_account_id, escrow.amount)?)
+    }
 
-        if vault_assets.is_empty() {
-            return Err(anyhow::anyhow!("Escrow vault is empty after consumption"));
+    /// Release funds from escrow to seller (on successful sale)
+    pub async fn release_escrow(
+        &mut self,
+        escrow: &EscrowAccount,
+        api_key: Option<&str>,
+    ) -> Result<String> {
+        let escrow = self.authorize_escrow(api_key, EscrowAction::Release, escrow)?;
+        self.require_release_approval(&escrow, api_key)?;
+        let result = self.release_escrow_unchecked(&escrow).await;
+        self.retry_on_failure(
+            RetryOperation::ReleaseEscrow {
+                escrow_account_id: account_id_to_hex(escrow.escrow_account_id),
+            },
+            result,
+        )
+    }
+
+    /// Releases without checking the caller, for settlements the service
+    /// performs itself (installment plans). Journaled like `release_escrow`;
+    /// the escrow must be funded, and lien sign-offs and the release checklist
+    /// are still required.
+    pub(crate) async fn release_escrow_unchecked(
+        &mut self,
+        escrow: &EscrowAccount,
+    ) -> Result<String> {
+        expect_escrow_status(escrow, EscrowStatus::Funded)?;
+        let escrow_hex = account_id_to_hex(escrow.escrow_account_id);
+        let property_id = self
+            .records
+            .escrows
+            .get(&escrow_hex)
+            .and_then(|record| record.property_id.clone());
+        if let Some(property_id) = &property_id {
+            self.liens.check(property_id, LienAction::Release)?;
         }
+        self.attachments.check_release(
+            &escrow_hex,
+            property_id.as_deref(),
+            chrono::Utc::now().timestamp(),
+        )?;
+        let payload = serde_json::json!(self.records.escrows.get(&escrow_hex));
+        let metadata = self
+            .run_hooks(HookPoint::BeforeEscrowRelease, &escrow_hex, payload)
+            .await?
+            .allowed()?;
 
-        tracing::info!("💰 Transferring {} asset(s) to seller", vault_assets.len());
+        let op_id = self.records.begin_operation("release_escrow", &escrow_hex);
 
-        // Create P2ID note to seller
-        let p2id_note = create_p2id_note(
-            escrow.escrow_account_id,
-            escrow.seller_account_id,
-            vault_assets.into_iter().collect(),
-            NoteType::Public,
-            Felt::new(0),
-            &mut self.rng,
-        )?;
+        let result = self.submit_escrow_release(escrow).await;
 
-        // Create transaction
-        let output_notes = vec![OutputNote::Full(p2id_note)];
-        let transaction_request = TransactionRequestBuilder::new()
-            .own_output_notes(output_notes)
-            .build()?;
+        let tx_id = result.as_ref().ok().cloned();
+        self.records.finish_operation(op_id, &result, tx_id.clone());
+
+        if result.is_ok() {
+            self.records.annotate_escrow(&escrow_hex, metadata);
+            self.records.update_escrow(&escrow_hex, EscrowStatus::Released, tx_id);
+            if let Some(property_id) = &property_id {
+                self.liens.use_sign_offs(property_id, LienAction::Release);
+            }
+            self.settle_escrow_yield(&escrow_hex, YieldOutcome::Release).await;
+        }
+
+        result
+    }
+
+    /// Consumes the escrow's funding notes and pays its amount to the seller.
+    async fn submit_escrow_release(
+        &mut self,
+        escrow: &EscrowAccount,
+    ) -> Result<String> {
+        tracing::info!("🔓 Releasing escrow funds to seller");
+        tracing::info!("   Escrow: {}", escrow.escrow_account_id);
+        tracing::info!("   To (Seller): {}", escrow.seller_account_id);
+
+        let asset = self.collect_escrow_funds(escrow).await?;
+        let plan = self.plan_payouts(escrow, asset.faucet_id(), escrow.amount)?;
+
+        tracing::info!(
+            "💰 Transferring {} to seller (platform fee {}, commission {}, withheld {})",
+            plan.amount(PayoutKind::Seller),
+            plan.fee_amount(),
+            plan.amount(PayoutKind::AgentCommission),
+            plan.amount(PayoutKind::TaxWithholding)
+        );
+
+        // Seller payout, commission, withholding and fee notes in one transaction
+        let transaction_request = self.payout_request(escrow.escrow_account_id, &plan)?;
 
         tracing::info!("📝 Executing release to seller...");
 
@@ -295,9 +848,13 @@ impl MidenClientWrapper {
 
         let tx_id = transaction_id.to_string();
         tracing::info!("✅ Escrow released to seller! TX: {}", tx_id);
+        let escrow_hex = account_id_to_hex(escrow.escrow_account_id);
+        self.record_payouts(&escrow_hex, &plan, &tx_id);
+        let postings = plan.postings(&escrow_hex);
+        self.post_ledger_entry(JournalKind::EscrowRelease, postings, &tx_id, Some(&escrow_hex));
 
         // Sync
-        self.client.sync_state().await?;
+        self.sync_state().await?;
 
         Ok(tx_id)
     }
@@ -306,91 +863,166 @@ impl MidenClientWrapper {
     pub async fn refund_escrow(
         &mut self,
         escrow: &EscrowAccount,
+        api_key: Option<&str>,
     ) -> Result<String> {
-        tracing::info!("↩️  Refunding escrow to buyer");
-        tracing::info!("   Escrow: {}", escrow.escrow_account_id);
-        tracing::info!("   To (Buyer): {}", escrow.buyer_account_id);
+        let escrow = self.authorize_escrow(api_key, EscrowAction::Refund, escrow)?;
+        let result = self.refund_escrow_unchecked(&escrow).await;
+        self.retry_on_failure(
+            RetryOperation::RefundEscrow {
+                escrow_account_id: account_id_to_hex(escrow.escrow_account_id),
+            },
+            result,
+        )
+    }
 
-        // Sync to get latest notes
-        self.client.sync_state().await?;
+    /// Refunds without checking the caller, for settlements the service
+    /// performs itself (installment plans). Journaled like `refund_escrow`;
+    /// the escrow must be funded.
+    pub(crate) async fn refund_escrow_unchecked(
+        &mut self,
+        escrow: &EscrowAccount,
+    ) -> Result<String> {
+        expect_escrow_status(escrow, EscrowStatus::Funded)?;
+        let escrow_hex = account_id_to_hex(escrow.escrow_account_id);
+        let op_id = self.records.begin_operation("refund_escrow", &escrow_hex);
 
-        // Get consumable notes for escrow account
-        let consumable_notes = self
-            .client
-            .get_consumable_notes(Some(escrow.escrow_account_id))
-            .await?;
+        let result = self.submit_escrow_refund(escrow).await;
+
+        let tx_id = result.as_ref().ok().cloned();
+        self.records.finish_operation(op_id, &result, tx_id.clone());
 
-        if consumable_notes.is_empty() {
-            return Err(anyhow::anyhow!("No funds in escrow to refund"));
+        if result.is_ok() {
+            self.records.update_escrow(&escrow_hex, EscrowStatus::Refunded, tx_id);
+            self.settle_escrow_yield(&escrow_hex, YieldOutcome::Refund).await;
         }
 
-        tracing::info!("✅ Found {} note(s) in escrow", consumable_notes.len());
+        result
+    }
 
-        // Consume notes
-        let note_ids: Vec<_> = consumable_notes
-            .iter()
-            .map(|(note, _)| note.id())
-            .collect();
+    /// Consumes the escrow's funding notes and pays its amount back to the buyer.
+    async fn submit_escrow_refund(
+        &mut self,
+        escrow: &EscrowAccount,
+    ) -> Result<String> {
+        tracing::info!("↩️  Refunding escrow to buyer");
+        tracing::info!("   Escrow: {}", escrow.escrow_account_id);
+        tracing::info!("   To (Buyer): {}", escrow.buyer_account_id);
+
+        let asset = self.collect_escrow_funds(escrow).await?;
+        let (faucet_hex, refunded) = (account_id_to_hex(asset.faucet_id()), asset.amount());
+
+        tracing::info!("💰 Refunding {} to buyer", escrow.amount);
 
-        let consume_request = TransactionRequestBuilder::new()
-            .build_consume_notes(note_ids)?;
+        // P2ID note back to the buyer
+        let (_, transaction_request) = client::p2id_request(
+            escrow.escrow_account_id,
+            escrow.buyer_account_id,
+            vec![asset.into()],
+            &mut self.rng,
+        )?;
+
+        tracing::info!("📝 Executing refund to buyer...");
 
-        let consume_tx_id = self
+        // Submit
+        let transaction_id = self
             .client
-            .submit_new_transaction(escrow.escrow_account_id, consume_request)
+            .submit_new_transaction(escrow.escrow_account_id, transaction_request)
             .await?;
 
-        tracing::info!("✅ Notes consumed: {}", consume_tx_id);
+        let tx_id = transaction_id.to_string();
+        tracing::info!("✅ Escrow refunded to buyer! TX: {}", tx_id);
+        let escrow_hex = account_id_to_hex(escrow.escrow_account_id);
+        self.post_ledger_entry(
+            JournalKind::EscrowRefund,
+            Posting::transfer(
+                &escrow_hex,
+                &account_id_to_hex(escrow.buyer_account_id),
+                &faucet_hex,
+                refunded,
+            )
+            .to_vec(),
+            &tx_id,
+            Some(&escrow_hex),
+        );
 
         // Sync
-        self.client.sync_state().await?;
+        self.sync_state().await?;
 
-        // Get escrow account with updated vault
-        let escrow_account = self
-            .client
-            .get_account(escrow.escrow_account_id)
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("Escrow account not found"))?;
+        Ok(tx_id)
+    }
 
-        // Get assets
-        let vault = escrow_account.account().vault();
-        let vault_assets: Vec<_> = vault.assets().collect();
+    /// Pays `to_seller` out of the escrow to the seller and the rest of the
+    /// escrowed amount back to the buyer (e.g. a partly withheld deposit).
+    /// Settles without checking the caller, like `refund_escrow_unchecked`.
+    pub(crate) async fn split_escrow_unchecked(
+        &mut self,
+        escrow: &EscrowAccount,
+        to_seller: u64,
+    ) -> Result<String> {
+        expect_escrow_status(escrow, EscrowStatus::Funded)?;
+        if to_seller > escrow.amount {
+            return Err(anyhow::anyhow!(
+                "Cannot pay {} to the seller out of an escrow of {}",
+                to_seller,
+                escrow.amount
+            ));
+        }
+
+        let escrow_hex = account_id_to_hex(escrow.escrow_account_id);
+        let op_id = self.records.begin_operation("split_escrow", &escrow_hex);
+
+        let result = self.submit_escrow_split(escrow, to_seller).await;
 
-        if vault_assets.is_empty() {
-            return Err(anyhow::anyhow!("Escrow vault is empty"));
+        let tx_id = result.as_ref().ok().cloned();
+        self.records.finish_operation(op_id, &result, tx_id.clone());
+
+        if result.is_ok() {
+            let (status, outcome) = if to_seller > 0 {
+                (EscrowStatus::Released, YieldOutcome::Release)
+            } else {
+                (EscrowStatus::Refunded, YieldOutcome::Refund)
+            };
+            self.records.update_escrow(&escrow_hex, status, tx_id);
+            self.settle_escrow_yield(&escrow_hex, outcome).await;
         }
 
-        tracing::info!("💰 Refunding {} asset(s) to buyer", vault_assets.len());
+        result
+    }
 
-        // Create P2ID note back to buyer
-        let p2id_note = create_p2id_note(
-            escrow.escrow_account_id,
-            escrow.buyer_account_id,
-            vault_assets.into_iter().collect(),
-            NoteType::Public,
-            Felt::new(0),
-            &mut self.rng,
-        )?;
+    /// Consumes the escrow's funding notes and pays the seller and buyer their
+    /// shares in one transaction.
+    async fn submit_escrow_split(
+        &mut self,
+        escrow: &EscrowAccount,
+        to_seller: u64,
+    ) -> Result<String> {
+        let to_buyer = escrow.amount - to_seller;
 
-        // Create transaction
-        let output_notes = vec![OutputNote::Full(p2id_note)];
-        let transaction_request = TransactionRequestBuilder::new()
-            .own_output_notes(output_notes)
-            .build()?;
+        tracing::info!("⚖️  Splitting escrow");
+        tracing::info!("   Escrow: {}", escrow.escrow_account_id);
+        tracing::info!("   To (Seller): {} -> {}", escrow.seller_account_id, to_seller);
+        tracing::info!("   To (Buyer): {} -> {}", escrow.buyer_account_id, to_buyer);
 
-        tracing::info!("📝 Executing refund to buyer...");
+        let faucet_account_id = self.collect_escrow_funds(escrow).await?.faucet_id();
+
+        // One note per payee with a non-zero share, the seller's net of the
+        // platform fee, commission and withholding (fee_splits.rs)
+        let plan = self.plan_payouts(escrow, faucet_account_id, to_seller)?;
+        let transaction_request = self.payout_request(escrow.escrow_account_id, &plan)?;
 
-        // Submit
         let transaction_id = self
             .client
             .submit_new_transaction(escrow.escrow_account_id, transaction_request)
             .await?;
 
         let tx_id = transaction_id.to_string();
-        tracing::info!("✅ Escrow refunded to buyer! TX: {}", tx_id);
+        tracing::info!("✅ Escrow split settled! TX: {}", tx_id);
+        let escrow_hex = account_id_to_hex(escrow.escrow_account_id);
+        self.record_payouts(&escrow_hex, &plan, &tx_id);
+        let postings = plan.postings(&escrow_hex);
+        self.post_ledger_entry(JournalKind::EscrowSplit, postings, &tx_id, Some(&escrow_hex));
 
-        // Sync
-        self.client.sync_state().await?;
+        self.sync_state().await?;
 
         Ok(tx_id)
     }
@@ -402,7 +1034,7 @@ impl MidenClientWrapper {
     ) -> Result<serde_json::Value> {
         tracing::info!("💰 Getting escrow balance: {}", escrow_account_id);
 
-        self.client.sync_state().await?;
+        self.sync_state().await?;
 
         let account = self
             .client
@@ -418,4 +1050,115 @@ impl MidenClientWrapper {
             "is_public": account.account().is_public(),
         }))
     }
-}
\ No newline at end of file
+
+    /// Rebuilds the accounts and signing keys of escrows derived from the
+    /// master secret, from their records alone. Accounts missing from the
+    /// client store are imported from the chain (or added, if never
+    /// deployed); keys are written to the keystore again. Needs an admin key.
+    pub async fn rebuild_escrow_accounts(
+        &mut self,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        self.admin_principal(api_key, "escrow account rebuilds")?;
+        if self.master_secret.is_none() {
+            return Err(anyhow::anyhow!(
+                "Master secret is not configured (MASTER_SECRET_KEY)"
+            ));
+        }
+
+        let derived: Vec<(String, u64, u32)> = self
+            .records
+            .escrows
+            .values()
+            .filter_map(|record| {
+                Some((
+                    record.escrow_account_id.clone(),
+                    record.deal_id?,
+                    record.seed_generation?,
+                ))
+            })
+            .collect();
+        let skipped = self.records.escrows.len() - derived.len();
+
+        let mut escrows = Vec::new();
+        for (escrow_hex, deal_id, generation) in derived {
+            let outcome = self.rebuild_escrow_account(&escrow_hex, deal_id, generation).await;
+            if let Err(e) = &outcome {
+                tracing::warn!("⚠️  Could not rebuild escrow {}: {}", escrow_hex, e);
+            }
+            escrows.push(serde_json::json!({
+                "escrow_account_id": escrow_hex,
+                "deal_id": deal_id,
+                "generation": generation,
+                "account": outcome.as_ref().ok(),
+                "error": outcome.as_ref().err().map(|e| e.to_string()),
+            }));
+        }
+
+        Ok(serde_json::json!({
+            "escrows": escrows,
+            // Created with random seeds, before a master secret was configured
+            "not_derived": skipped,
+        }))
+    }
+
+    fn escrow_unsettled(&self, escrow_hex: &str) -> bool {
+        self.records.escrows.get(escrow_hex).is_some_and(|record| {
+            matches!(
+                record.status,
+                EscrowStatus::Created | EscrowStatus::Funded | EscrowStatus::Disputed
+            )
+        })
+    }
+
+    /// Rebuilds one derived escrow account. Returns how the account was
+    /// restored: "in_store", "imported" or "added".
+    async fn rebuild_escrow_account(
+        &mut self,
+        escrow_hex: &str,
+        deal_id: u64,
+        generation: u32,
+    ) -> Result<&'static str> {
+        let secret = self
+            .master_secret
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Master secret is not configured"))?;
+        let (init_seed, key_pair) = escrow_seed_material(secret, deal_id, generation)?;
+        let account = build_escrow_account(init_seed, &key_pair)?;
+
+        let recorded_id = parse_account_id(escrow_hex, None, None)?;
+        if account.id() != recorded_id {
+            return Err(anyhow::anyhow!(
+                "Deal {} (generation {}) derives account {}, not the recorded escrow",
+                deal_id,
+                generation,
+                account_id_to_hex(account.id())
+            ));
+        }
+
+        let restored = if self.client.get_account(recorded_id).await?.is_some() {
+            "in_store"
+        } else {
+            match self.client.import_account_by_id(recorded_id).await {
+                Ok(()) => "imported",
+                // Escrow accounts first transact when they settle, so an
+                // unsettled escrow may never have been deployed; the derived
+                // account is then its current state
+                Err(_) if self.escrow_unsettled(escrow_hex) => {
+                    self.client.add_account(&account, false).await?;
+                    "added"
+                }
+                Err(e) => return Err(e.into()),
+            }
+        };
+        self.keystore()?.add_key(&AuthSecretKey::RpoFalcon512(key_pair))?;
+
+        tracing::info!(
+            "🔑 Rebuilt escrow {} from deal {} ({})",
+            escrow_hex,
+            deal_id,
+            restored
+        );
+        Ok(restored)
+    }
+}
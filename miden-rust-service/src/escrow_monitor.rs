@@ -0,0 +1,287 @@
+// src/escrow_monitor.rs
+//
+// Stale escrow monitoring
+//
+// An escrow that is created but never funded, or funded but never released or
+// refunded, ties up an account (and in the second case the buyer's money)
+// until somebody notices. The scheduler tick (scheduler.rs) flags escrows that
+// have sat in one status for longer than
+// - ESCROW_STALE_CREATED_SECS: created, not funded
+// - ESCROW_STALE_FUNDED_SECS: funded, not settled
+// (zero disables a check). The time in a status is counted from the escrow
+// record's last update.
+//
+// Flagging an escrow logs a warning and publishes a `flagged` event, streamed
+// by GET /escrows/stale-events; an escrow that moves on publishes `cleared`.
+// Each escrow is flagged once per status, and the flags survive restarts
+// (ESCROW_MONITOR_PATH). GET /escrows?stale=true lists the escrows that are
+// stale right now.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    path::PathBuf,
+    time::Duration,
+};
+use tokio::sync::broadcast;
+
+use crate::{escrow::EscrowStatus, records::EscrowRecord, MidenClientWrapper};
+
+/// Buffered stale escrow events per subscriber before it starts missing events.
+pub const STALE_ESCROW_FEED_CAPACITY: usize = 256;
+
+/// Events kept in the monitor file
+const MAX_EVENTS: usize = 1_000;
+
+/// How long an escrow may stay in each open status.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StalePolicy {
+    pub created: Option<Duration>,
+    pub funded: Option<Duration>,
+}
+
+impl StalePolicy {
+    /// Threshold for `status` in seconds; None for statuses never stale.
+    fn threshold(&self, status: &EscrowStatus) -> Option<i64> {
+        let limit = match status {
+            EscrowStatus::Created => self.created,
+            EscrowStatus::Funded => self.funded,
+            _ => None,
+        };
+        limit.map(|d| d.as_secs() as i64)
+    }
+
+    /// True if `record` has been in its status longer than allowed at `now`.
+    pub fn is_stale(&self, record: &EscrowRecord, now: i64) -> bool {
+        self.threshold(&record.status)
+            .is_some_and(|limit| now - record.updated_at > limit)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StaleEventKind {
+    Flagged,
+    Cleared,
+}
+
+impl StaleEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StaleEventKind::Flagged => "flagged",
+            StaleEventKind::Cleared => "cleared",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaleEscrowEvent {
+    pub escrow_account_id: String,
+    pub kind: StaleEventKind,
+    /// Status the escrow was stuck in
+    pub status: EscrowStatus,
+    pub at: i64,
+    pub detail: String,
+}
+
+/// An escrow flagged as stale.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaleFlag {
+    pub status: EscrowStatus,
+    /// Record update the escrow has been stuck since
+    pub since: i64,
+    pub flagged_at: i64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct EscrowMonitor {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(skip)]
+    feed: Option<broadcast::Sender<StaleEscrowEvent>>,
+    #[serde(default)]
+    flagged: BTreeMap<String, StaleFlag>,
+    #[serde(default)]
+    events: VecDeque<StaleEscrowEvent>,
+}
+
+impl EscrowMonitor {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<EscrowMonitor>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            EscrowMonitor::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Sends events to feed subscribers (see main.rs, GET /escrows/stale-events).
+    pub fn attach_feed(&mut self, feed: broadcast::Sender<StaleEscrowEvent>) {
+        self.feed = Some(feed);
+    }
+
+    pub fn flag(&self, escrow_account_id: &str) -> Option<&StaleFlag> {
+        self.flagged.get(escrow_account_id)
+    }
+
+    fn emit(&mut self, event: StaleEscrowEvent) {
+        if let Some(feed) = &self.feed {
+            // No subscribers is not an error
+            let _ = feed.send(event.clone());
+        }
+        if self.events.len() == MAX_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// Flags escrows that became stale and clears those that moved on.
+    /// Returns the number of escrows newly flagged.
+    pub fn scan<'a>(
+        &mut self,
+        escrows: impl Iterator<Item = &'a EscrowRecord>,
+        policy: &StalePolicy,
+        now: i64,
+    ) -> Result<usize> {
+        let mut flagged = 0;
+        let mut changed = false;
+        let mut seen = HashSet::new();
+
+        for record in escrows {
+            let escrow_hex = &record.escrow_account_id;
+            seen.insert(escrow_hex.clone());
+
+            let current = self.flagged.get(escrow_hex).is_some_and(|flag| {
+                flag.status == record.status && flag.since == record.updated_at
+            });
+            let stale = policy.is_stale(record, now);
+            if stale && current {
+                continue;
+            }
+
+            if let Some(flag) = self.flagged.remove(escrow_hex) {
+                changed = true;
+                self.emit(StaleEscrowEvent {
+                    escrow_account_id: escrow_hex.clone(),
+                    kind: StaleEventKind::Cleared,
+                    status: flag.status,
+                    at: now,
+                    detail: format!("escrow is now {:?}", record.status),
+                });
+            }
+            if stale {
+                let stuck_for = now - record.updated_at;
+                tracing::warn!(
+                    "⚠️  Escrow {} has been {:?} for {}s",
+                    escrow_hex,
+                    record.status,
+                    stuck_for
+                );
+                self.flagged.insert(
+                    escrow_hex.clone(),
+                    StaleFlag {
+                        status: record.status.clone(),
+                        since: record.updated_at,
+                        flagged_at: now,
+                    },
+                );
+                self.emit(StaleEscrowEvent {
+                    escrow_account_id: escrow_hex.clone(),
+                    kind: StaleEventKind::Flagged,
+                    status: record.status.clone(),
+                    at: now,
+                    detail: format!("{:?} for {}s", record.status, stuck_for),
+                });
+                flagged += 1;
+                changed = true;
+            }
+        }
+
+        // Escrows no longer in the records
+        let before = self.flagged.len();
+        self.flagged
+            .retain(|escrow_hex, _| seen.contains(escrow_hex));
+        changed |= self.flagged.len() != before;
+
+        if changed {
+            self.save()?;
+        }
+        Ok(flagged)
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    ListStaleEscrows {} -> serde_json::Value;
+    |client, _op| client.list_stale_escrows()
+}
+
+impl MidenClientWrapper {
+    /// Publishes stale escrow events on `feed` from now on.
+    pub fn attach_stale_escrow_feed(&mut self, feed: broadcast::Sender<StaleEscrowEvent>) {
+        self.escrow_monitor.attach_feed(feed);
+    }
+
+    fn stale_policy(&self) -> StalePolicy {
+        StalePolicy {
+            created: self.config.escrow_stale_created,
+            funded: self.config.escrow_stale_funded,
+        }
+    }
+
+    /// Scheduler job: flags stale escrows. Returns how many were newly flagged.
+    pub fn run_escrow_monitor(&mut self) -> Result<usize> {
+        let policy = self.stale_policy();
+        self.escrow_monitor.scan(
+            self.records.escrows.values(),
+            &policy,
+            chrono::Utc::now().timestamp(),
+        )
+    }
+
+    /// Escrows that are stale right now, longest stuck first.
+    pub fn list_stale_escrows(&self) -> Result<serde_json::Value> {
+        let policy = self.stale_policy();
+        let now = chrono::Utc::now().timestamp();
+
+        let mut stale: Vec<&EscrowRecord> = self
+            .records
+            .escrows
+            .values()
+            .filter(|record| policy.is_stale(record, now))
+            .collect();
+        stale.sort_by_key(|record| record.updated_at);
+
+        let escrows = stale
+            .into_iter()
+            .map(|record| {
+                let mut entry = serde_json::to_value(record)?;
+                entry["stale_for_secs"] = serde_json::json!(now - record.updated_at);
+                entry["flagged_at"] = serde_json::json!(self
+                    .escrow_monitor
+                    .flag(&record.escrow_account_id)
+                    .map(|flag| flag.flagged_at));
+                Ok(entry)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(serde_json::json!(escrows))
+    }
+}
@@ -0,0 +1,258 @@
+// src/escrow_timeline.rs
+//
+// Escrow timeline
+//
+// GET /escrows/:escrow_account_id answers with the escrow's record and its
+// timeline: every step of the deal in time order, assembled from what the
+// service already keeps:
+// - created: the escrow record (records.rs)
+// - funded, released, refunded, split: the operation journal (records.rs),
+//   with the transaction of each attempt or the error it failed with
+// - document_attached, document_signed: closing documents generated for the
+//   release checklist and signed through the e-signature provider
+//   (attachments.rs); the latest version of each
+// - approval: the audit trail of two-person releases (approvals.rs)
+//
+// Events with a transaction carry its state in the client store: the block it
+// was committed in, pending, discarded, or unknown (not in this store, e.g.
+// submitted by another instance). Block numbers are as fresh as the last sync.
+//
+// Settlements the service performs itself go through the same journal, so a
+// lease deposit partly withheld shows up as `split`. No flow sets the
+// disputed status yet; once one does, it belongs here too.
+//
+// Readable by the escrow's buyer, seller and arbiters.
+
+use anyhow::Result;
+use miden_client::transaction::{TransactionFilter, TransactionStatus};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    approvals::{ApprovalAction, Approver},
+    documents::DocumentKind,
+    records::{EscrowRecord, OperationStatus},
+    MidenClientWrapper,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineEventKind {
+    Created,
+    Funded,
+    Released,
+    Refunded,
+    Split,
+    DocumentAttached,
+    DocumentSigned,
+    Approval,
+}
+
+impl TimelineEventKind {
+    /// Event of a journal entry kind; None for operations on other subjects.
+    fn of_operation(kind: &str) -> Option<Self> {
+        match kind {
+            "fund_escrow" => Some(Self::Funded),
+            "release_escrow" => Some(Self::Released),
+            "refund_escrow" => Some(Self::Refunded),
+            "split_escrow" => Some(Self::Split),
+            _ => None,
+        }
+    }
+}
+
+/// State of an event's transaction in the client store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxState {
+    Pending,
+    Committed,
+    Discarded,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineEvent {
+    pub event: TimelineEventKind,
+    pub at: i64,
+    /// Outcome of a journaled operation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<OperationStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_state: Option<TxState>,
+    /// Block the transaction was committed in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_num: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document: Option<DocumentKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval_action: Option<ApprovalAction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub by: Option<Approver>,
+    /// Error of a failed operation, or the detail of an approval step
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl TimelineEvent {
+    fn new(event: TimelineEventKind, at: i64) -> Self {
+        Self {
+            event,
+            at,
+            status: None,
+            tx_id: None,
+            tx_state: None,
+            block_num: None,
+            document: None,
+            document_id: None,
+            approval_id: None,
+            approval_action: None,
+            by: None,
+            detail: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EscrowView {
+    pub escrow: EscrowRecord,
+    pub timeline: Vec<TimelineEvent>,
+}
+
+// ============================================================================
+// CLIENT INTEGRATION
+// ============================================================================
+
+crate::operations! {
+    /// An escrow's record and timeline.
+    GetEscrow {
+        escrow_account_id: String,
+        api_key: Option<String>,
+    } -> EscrowView;
+    |client, op| client.get_escrow(&op.escrow_account_id, op.api_key.as_deref()).await
+}
+
+impl MidenClientWrapper {
+    /// The escrow's record and timeline (see module docs), for its parties
+    /// and arbiters.
+    pub async fn get_escrow(
+        &self,
+        escrow_account_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<EscrowView> {
+        let record = self.document_escrow(escrow_account_id, api_key)?;
+        let escrow_hex = record.escrow_account_id.clone();
+
+        let mut timeline = vec![TimelineEvent::new(
+            TimelineEventKind::Created,
+            record.created_at,
+        )];
+
+        for op in self
+            .records
+            .operations
+            .iter()
+            .filter(|op| op.subject == escrow_hex)
+        {
+            let Some(kind) = TimelineEventKind::of_operation(&op.kind) else {
+                continue;
+            };
+            let mut event = TimelineEvent::new(kind, op.finished_at.unwrap_or(op.started_at));
+            event.status = Some(op.status);
+            event.tx_id = op.tx_id.clone();
+            event.detail = op.error.clone();
+            timeline.push(event);
+        }
+
+        if let Some(checklist) = self.attachments.checklist(&escrow_hex) {
+            for (kind, attached) in &checklist.documents {
+                let mut event =
+                    TimelineEvent::new(TimelineEventKind::DocumentAttached, attached.attached_at);
+                event.document = Some(*kind);
+                event.document_id = Some(attached.document_id);
+                timeline.push(event);
+            }
+            for (kind, signed) in &checklist.signatures {
+                let mut event =
+                    TimelineEvent::new(TimelineEventKind::DocumentSigned, signed.signed_at);
+                event.document = Some(*kind);
+                event.document_id = Some(signed.document_id);
+                timeline.push(event);
+            }
+        }
+
+        let approvals = self.approvals.list(None).into_iter().filter(|a| {
+            a.escrow_account_id
+                .as_deref()
+                .is_some_and(|id| id.eq_ignore_ascii_case(&escrow_hex))
+        });
+        for approval in approvals {
+            for entry in &approval.audit {
+                let mut event = TimelineEvent::new(TimelineEventKind::Approval, entry.at);
+                event.approval_id = Some(approval.approval_id);
+                event.approval_action = Some(entry.action);
+                event.by = entry.approver.clone();
+                event.detail = entry.detail.clone();
+                timeline.push(event);
+            }
+        }
+
+        self.attach_blocks(&mut timeline).await?;
+        // Stable: the creation stays first among events of the same second
+        timeline.sort_by_key(|event| event.at);
+
+        Ok(EscrowView {
+            escrow: record,
+            timeline,
+        })
+    }
+
+    /// Fills in the state and block of each event's transaction from the
+    /// client store.
+    async fn attach_blocks(&self, timeline: &mut [TimelineEvent]) -> Result<()> {
+        let wanted: BTreeSet<&str> = timeline
+            .iter()
+            .filter_map(|event| event.tx_id.as_deref())
+            .collect();
+        if wanted.is_empty() {
+            return Ok(());
+        }
+
+        let states: BTreeMap<String, (TxState, Option<u32>)> = self
+            .client
+            .get_transactions(TransactionFilter::All)
+            .await?
+            .into_iter()
+            .filter(|tx| wanted.contains(tx.id.to_string().as_str()))
+            .map(|tx| {
+                let state = match tx.status {
+                    TransactionStatus::Pending => (TxState::Pending, None),
+                    TransactionStatus::Committed { block_number, .. } => {
+                        (TxState::Committed, Some(block_number.as_u32()))
+                    }
+                    TransactionStatus::Discarded(_) => (TxState::Discarded, None),
+                };
+                (tx.id.to_string(), state)
+            })
+            .collect();
+
+        for event in timeline.iter_mut() {
+            let Some(tx_id) = &event.tx_id else {
+                continue;
+            };
+            let (state, block_num) = states
+                .get(tx_id)
+                .copied()
+                .unwrap_or((TxState::Unknown, None));
+            event.tx_state = Some(state);
+            event.block_num = block_num;
+        }
+        Ok(())
+    }
+}
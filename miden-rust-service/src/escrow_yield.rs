@@ -0,0 +1,490 @@
+// src/escrow_yield.rs
+//
+// Yield on long-held escrow balances
+//
+// With ESCROW_YIELD_RATE_BPS set, funded escrows earn simple interest on their
+// escrowed amount at that annual rate (365-day years) once they have been
+// funded for ESCROW_YIELD_AFTER_DAYS days, so a deposit held for months earns
+// and a quick closing does not. The escrow_yield scheduler job accrues it a
+// whole UTC day at a time into the escrow record, with each day's amount in
+// the accrual history. The rate is fixed when accrual starts; escrows funded
+// before yield was configured start their wait at the job's first run. The
+// yield is not encrypted in the records file, so with confidential listings
+// its amounts give away roughly what an escrow holds.
+//
+// Nothing moves while interest accrues: the platform pays it. When the escrow
+// settles, the accrued amount is split per policy between the buyer, the
+// seller and the platform: ESCROW_YIELD_ON_RELEASE for releases (and splits
+// that pay the seller anything), ESCROW_YIELD_ON_REFUND for refunds, each a
+// list of percentages such as "seller=100" or "buyer=50,platform=50" summing
+// to 100. The buyer's and seller's shares are paid from the treasury as P2ID
+// notes of the escrow's token and entered in the treasury ledger as yield; the
+// platform's share, and what rounding leaves, stays in the treasury. A payout
+// that fails is retried by the job, MAX_PAYOUT_ATTEMPTS times in all.
+//
+// GET /escrows/:escrow_account_id/yield shows the accrual history and payouts
+// to the escrow's buyer, seller and arbiters; GET /escrows lists it with the
+// escrow record.
+
+use anyhow::Result;
+use chrono::TimeZone;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, str::FromStr};
+
+use crate::{
+    account_id_to_hex,
+    escrow::EscrowStatus,
+    reconcile::parse_hex_account_id,
+    treasury::{LedgerEntry, LedgerEntryKind},
+    MidenClientWrapper,
+};
+
+/// Payout attempts before a yield payout is given up on
+pub const MAX_PAYOUT_ATTEMPTS: u32 = 5;
+const DAY: i64 = 86_400;
+const BPS_DENOMINATOR: u128 = 10_000;
+const DAYS_PER_YEAR: u128 = 365;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum YieldRecipient {
+    Buyer,
+    Seller,
+    Platform,
+}
+
+impl FromStr for YieldRecipient {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "buyer" => Ok(YieldRecipient::Buyer),
+            "seller" => Ok(YieldRecipient::Seller),
+            "platform" => Ok(YieldRecipient::Platform),
+            other => Err(anyhow::anyhow!("Unknown yield recipient: {}", other)),
+        }
+    }
+}
+
+/// Percent of the accrued yield per recipient, summing to 100.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct YieldSplit(BTreeMap<YieldRecipient, u64>);
+
+impl FromStr for YieldSplit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut shares = BTreeMap::new();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (recipient, percent) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("entry is not recipient=percent: {}", entry))?;
+            let percent: u64 = percent
+                .trim()
+                .parse()
+                .map_err(|e| anyhow::anyhow!("share of {} is not a number: {}", recipient, e))?;
+            *shares.entry(recipient.parse()?).or_default() += percent;
+        }
+        let total: u64 = shares.values().sum();
+        if total != 100 {
+            return Err(anyhow::anyhow!(
+                "shares add up to {} percent, not 100",
+                total
+            ));
+        }
+        Ok(YieldSplit(shares))
+    }
+}
+
+impl YieldSplit {
+    pub fn all_to(recipient: YieldRecipient) -> Self {
+        YieldSplit(BTreeMap::from([(recipient, 100)]))
+    }
+
+    /// Each recipient's share of `accrued`, rounded down; the remainder is
+    /// the platform's.
+    pub fn amounts(&self, accrued: u64) -> Vec<(YieldRecipient, u64)> {
+        let mut amounts: Vec<(YieldRecipient, u64)> = self
+            .0
+            .iter()
+            .filter(|(recipient, _)| **recipient != YieldRecipient::Platform)
+            .map(|(recipient, percent)| {
+                (
+                    *recipient,
+                    (accrued as u128 * *percent as u128 / 100) as u64,
+                )
+            })
+            .collect();
+        let paid: u64 = amounts.iter().map(|(_, amount)| amount).sum();
+        amounts.push((YieldRecipient::Platform, accrued - paid));
+        amounts.retain(|(_, amount)| *amount > 0);
+        amounts
+    }
+}
+
+/// ESCROW_YIELD_* settings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct YieldPolicy {
+    /// Annual rate in basis points
+    pub rate_bps: u64,
+    /// Days an escrow is funded before it accrues
+    pub after_days: u64,
+    pub on_release: YieldSplit,
+    pub on_refund: YieldSplit,
+}
+
+/// How an escrow settled, for picking the split.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YieldOutcome {
+    Release,
+    Refund,
+}
+
+/// One day of accrual.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YieldAccrual {
+    /// UTC day, e.g. "2026-10-15"
+    pub day: String,
+    pub amount: u64,
+    /// Accrued up to and including the day
+    pub accrued: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayoutStatus {
+    Pending,
+    Paid,
+    /// The platform's share: stays in the treasury
+    Retained,
+    /// Given up after MAX_PAYOUT_ATTEMPTS
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YieldPayout {
+    /// Paid to the escrow's buyer or seller account, whose IDs stay in the
+    /// (possibly encrypted) party fields of the record
+    pub recipient: YieldRecipient,
+    pub amount: u64,
+    pub status: PayoutStatus,
+    pub tx_id: Option<String>,
+    #[serde(default)]
+    pub attempts: u32,
+    pub error: Option<String>,
+}
+
+/// Yield of one escrow, kept in its record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EscrowYield {
+    pub rate_bps: u64,
+    /// Start of the first UTC day that accrues
+    pub accrues_from: i64,
+    pub accrued: u64,
+    #[serde(default)]
+    pub history: Vec<YieldAccrual>,
+    /// Set when the escrow settled and the yield was split
+    pub settled_at: Option<i64>,
+    #[serde(default)]
+    pub payouts: Vec<YieldPayout>,
+}
+
+impl EscrowYield {
+    /// Yield of an escrow funded at `funded_at`: the funding day does not
+    /// count, accrual starts the day after `after_days` more.
+    pub fn start(rate_bps: u64, funded_at: i64, after_days: u64) -> Self {
+        let funding_day = funded_at.div_euclid(DAY) * DAY;
+        Self {
+            rate_bps,
+            accrues_from: funding_day + (after_days as i64 + 1) * DAY,
+            accrued: 0,
+            history: Vec::new(),
+            settled_at: None,
+            payouts: Vec::new(),
+        }
+    }
+
+    /// Accrues every whole day on `principal` that has ended by `now`.
+    /// Returns the number of days added.
+    pub fn accrue(&mut self, principal: u64, now: i64) -> usize {
+        if self.settled_at.is_some() {
+            return 0;
+        }
+        let mut added = 0;
+        loop {
+            let days = self.history.len() as i64 + 1;
+            let day_start = self.accrues_from + (days - 1) * DAY;
+            if day_start + DAY > now {
+                return added;
+            }
+            // Interest to date rounded down, so the days add up exactly
+            let total = (principal as u128 * self.rate_bps as u128 * days as u128
+                / (BPS_DENOMINATOR * DAYS_PER_YEAR)) as u64;
+            let day = chrono::Utc
+                .timestamp_opt(day_start, 0)
+                .single()
+                .unwrap_or_default()
+                .format("%Y-%m-%d")
+                .to_string();
+            self.history.push(YieldAccrual {
+                day,
+                amount: total - self.accrued,
+                accrued: total,
+            });
+            self.accrued = total;
+            added += 1;
+        }
+    }
+
+    /// Splits the accrued yield among the parties; payouts start pending.
+    pub fn settle(&mut self, split: &YieldSplit, now: i64) {
+        self.settled_at = Some(now);
+        self.payouts = split
+            .amounts(self.accrued)
+            .into_iter()
+            .map(|(recipient, amount)| {
+                let status = match recipient {
+                    YieldRecipient::Platform => PayoutStatus::Retained,
+                    YieldRecipient::Buyer | YieldRecipient::Seller => PayoutStatus::Pending,
+                };
+                YieldPayout {
+                    recipient,
+                    amount,
+                    status,
+                    tx_id: None,
+                    attempts: 0,
+                    error: None,
+                }
+            })
+            .collect();
+    }
+
+    pub fn has_pending_payouts(&self) -> bool {
+        self.payouts
+            .iter()
+            .any(|p| p.status == PayoutStatus::Pending)
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    GetEscrowYield {
+        escrow_account_id: String,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.get_escrow_yield(&op.escrow_account_id, op.api_key.as_deref())
+}
+
+impl MidenClientWrapper {
+    /// Starts accrual on an escrow that was just funded, when yield is on.
+    pub(crate) fn start_escrow_yield(&mut self, escrow_hex: &str) {
+        let Some(policy) = &self.config.escrow_yield else {
+            return;
+        };
+        let started = EscrowYield::start(
+            policy.rate_bps,
+            chrono::Utc::now().timestamp(),
+            policy.after_days,
+        );
+        self.records.set_escrow_yield(escrow_hex, started);
+    }
+
+    /// Scheduled job: accrues yield on funded escrows and retries pending
+    /// payouts. Returns the number of escrows changed.
+    pub async fn run_escrow_yield(&mut self) -> Result<usize> {
+        let now = chrono::Utc::now().timestamp();
+        let mut changed = 0;
+
+        if let Some(policy) = self.config.escrow_yield.clone() {
+            let held: Vec<(String, u64, Option<EscrowYield>)> = self
+                .records
+                .escrows
+                .values()
+                .filter(|r| matches!(r.status, EscrowStatus::Funded | EscrowStatus::Disputed))
+                .map(|r| {
+                    (
+                        r.escrow_account_id.clone(),
+                        r.amount,
+                        r.escrow_yield.clone(),
+                    )
+                })
+                .collect();
+            for (escrow_hex, amount, escrow_yield) in held {
+                // Escrows funded before yield was configured start waiting now
+                let started = escrow_yield.is_none();
+                let mut escrow_yield = escrow_yield
+                    .unwrap_or_else(|| EscrowYield::start(policy.rate_bps, now, policy.after_days));
+                if escrow_yield.accrue(amount, now) > 0 || started {
+                    self.records.set_escrow_yield(&escrow_hex, escrow_yield);
+                    changed += 1;
+                }
+            }
+        }
+
+        let unpaid: Vec<String> = self
+            .records
+            .escrows
+            .values()
+            .filter(|r| {
+                r.escrow_yield
+                    .as_ref()
+                    .is_some_and(|y| y.has_pending_payouts())
+            })
+            .map(|r| r.escrow_account_id.clone())
+            .collect();
+        for escrow_hex in unpaid {
+            if self.pay_escrow_yield(&escrow_hex).await > 0 {
+                changed += 1;
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// Accrues to date, splits the yield of an escrow that just settled and
+    /// pays it out. Failures are logged; the settlement stands.
+    pub(crate) async fn settle_escrow_yield(&mut self, escrow_hex: &str, outcome: YieldOutcome) {
+        let Some(policy) = self.config.escrow_yield.clone() else {
+            return;
+        };
+        let Some(record) = self.records.escrows.get(escrow_hex) else {
+            return;
+        };
+        let Some(mut escrow_yield) = record.escrow_yield.clone() else {
+            return;
+        };
+        if escrow_yield.settled_at.is_some() {
+            return;
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        escrow_yield.accrue(record.amount, now);
+        let split = match outcome {
+            YieldOutcome::Release => &policy.on_release,
+            YieldOutcome::Refund => &policy.on_refund,
+        };
+        escrow_yield.settle(split, now);
+        tracing::info!(
+            "📈 Escrow {} accrued {} in yield over {} day(s)",
+            escrow_hex,
+            escrow_yield.accrued,
+            escrow_yield.history.len()
+        );
+        self.records.set_escrow_yield(escrow_hex, escrow_yield);
+
+        self.pay_escrow_yield(escrow_hex).await;
+    }
+
+    /// Pays the pending yield payouts of an escrow from the treasury. Returns
+    /// the number paid.
+    async fn pay_escrow_yield(&mut self, escrow_hex: &str) -> usize {
+        let Some(mut escrow_yield) = self
+            .records
+            .escrows
+            .get(escrow_hex)
+            .and_then(|r| r.escrow_yield.clone())
+        else {
+            return 0;
+        };
+
+        let mut paid = 0;
+        for payout in escrow_yield
+            .payouts
+            .iter_mut()
+            .filter(|p| p.status == PayoutStatus::Pending)
+        {
+            payout.attempts += 1;
+            match self.submit_yield_payout(escrow_hex, payout).await {
+                Ok(tx_id) => {
+                    payout.status = PayoutStatus::Paid;
+                    payout.tx_id = Some(tx_id);
+                    payout.error = None;
+                    paid += 1;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to pay {:?} yield of escrow {}: {}",
+                        payout.recipient,
+                        escrow_hex,
+                        e
+                    );
+                    payout.error = Some(e.to_string());
+                    if payout.attempts >= MAX_PAYOUT_ATTEMPTS {
+                        payout.status = PayoutStatus::Failed;
+                    }
+                }
+            }
+        }
+        self.records.set_escrow_yield(escrow_hex, escrow_yield);
+        paid
+    }
+
+    async fn submit_yield_payout(
+        &mut self,
+        escrow_hex: &str,
+        payout: &YieldPayout,
+    ) -> Result<String> {
+        let record =
+            self.records.escrows.get(escrow_hex).ok_or_else(|| {
+                anyhow::anyhow!("Escrow {} not found in service records", escrow_hex)
+            })?;
+        let account_hex = match payout.recipient {
+            YieldRecipient::Buyer => record.buyer_account_id.clone(),
+            YieldRecipient::Seller => record.seller_account_id.clone(),
+            YieldRecipient::Platform => {
+                return Err(anyhow::anyhow!("The platform's share is not paid out"))
+            }
+        };
+        let recipient = parse_hex_account_id(&account_hex)?;
+        let treasury = self.treasury_account_id()?;
+        // Yield is paid in the token the escrow holds
+        let faucet = match &record.asset_faucet_id {
+            Some(faucet_hex) => parse_hex_account_id(faucet_hex)?,
+            None => self.named_account("faucet")?,
+        };
+
+        let (tx_id, note_id) = self
+            .submit_payment_note(treasury, recipient, faucet, payout.amount)
+            .await?;
+        self.records
+            .expect_note(&note_id, &account_hex, "escrow-yield", Some(tx_id.clone()));
+        self.record_treasury_entry(LedgerEntry {
+            counterparty: Some(account_hex.clone()),
+            note_id: Some(note_id),
+            tx_id: Some(tx_id.clone()),
+            ..LedgerEntry::new(
+                LedgerEntryKind::Yield,
+                account_id_to_hex(faucet),
+                payout.amount,
+            )
+        });
+        tracing::info!(
+            "📈 Paid {} yield of escrow {} to {}. TX: {}",
+            payout.amount,
+            escrow_hex,
+            account_hex,
+            tx_id
+        );
+        Ok(tx_id)
+    }
+
+    /// Yield accrued by an escrow, its history and payouts, for the escrow's
+    /// parties and arbiters.
+    pub fn get_escrow_yield(
+        &self,
+        escrow_account_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let record = self.document_escrow(escrow_account_id, api_key)?;
+        Ok(serde_json::json!({
+            "escrow_account_id": record.escrow_account_id,
+            "status": record.status,
+            "amount": record.amount,
+            "policy": self.config.escrow_yield,
+            "yield": record.escrow_yield,
+        }))
+    }
+}
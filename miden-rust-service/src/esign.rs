@@ -0,0 +1,695 @@
+// src/esign.rs
+//
+// E-signature of closing documents
+//
+// A generated closing document (documents.rs) can be sent to an e-signature
+// provider, which collects the signers' signatures and reports back through
+// callbacks. Providers plug in through the SignatureProvider trait; the one
+// shipped here ("http", HttpSignatureProvider) speaks a small JSON protocol:
+//
+//   POST {ESIGN_API_URL}/envelopes            (Bearer ESIGN_API_KEY)
+//     {"reference", "title", "document": {"filename", "content_type",
+//      "content_base64", "sha256"}, "signers": [{"role", "name", "email"}],
+//      "callback_url"}
+//   -> {"envelope_id"}
+//
+//   POST /esign/callback                      (from the provider)
+//     {"envelope_id", "event", "signer_email"?, "document_sha256"?,
+//      "signed_document_sha256"?}
+//   signed with x-esign-signature: sha256=HMAC-SHA256(ESIGN_CALLBACK_SECRET, body)
+//
+// Events are signer_viewed, signer_signed and signer_declined for one signer,
+// and completed or voided for the whole envelope. A declined signer declines
+// the envelope. A completed envelope must report the SHA-256 of the document
+// that was signed; if it is not the hash of the PDF sent, the envelope is
+// rejected. Otherwise the signature is recorded on the escrow checklist
+// (attachments.rs), which can require signed documents before release.
+// Envelopes that are completed, declined, voided or rejected ignore further
+// events, so replayed callbacks are harmless.
+//
+// POST /escrows/:escrow_account_id/documents/:document_id/signatures sends
+// the latest version of a document for signature; the buyer, the seller or an
+// arbiter may do so. GET /escrows/:escrow_account_id/signatures lists the
+// envelopes of an escrow with each signer's status.
+
+use anyhow::Result;
+use axum::http::HeaderMap;
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc, time::Duration};
+
+use crate::{
+    attachments::SignedDocument,
+    documents::{ArtifactFormat, DocumentKind, PDF_CONTENT_TYPE},
+    MidenClientWrapper,
+};
+
+pub const CALLBACK_SIGNATURE_HEADER: &str = "x-esign-signature";
+pub const MAX_SIGNERS: usize = 10;
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SignerInput {
+    /// What the signer signs as, e.g. "buyer" or "seller"
+    pub role: String,
+    pub name: String,
+    pub email: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SignatureInput {
+    pub signers: Vec<SignerInput>,
+}
+
+/// What is sent to the provider.
+#[derive(Debug, Clone)]
+pub struct SignatureRequest {
+    pub escrow_account_id: String,
+    pub document_id: u64,
+    pub kind: DocumentKind,
+    pub filename: String,
+    pub pdf: Vec<u8>,
+    /// SHA-256 of the PDF (hex)
+    pub document_sha256: String,
+    pub signers: Vec<SignerInput>,
+}
+
+impl SignatureRequest {
+    /// Identifies the document at the provider.
+    pub fn reference(&self) -> String {
+        format!("{}/{}", self.escrow_account_id, self.document_id)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureEventKind {
+    SignerViewed,
+    SignerSigned,
+    SignerDeclined,
+    Completed,
+    Voided,
+}
+
+/// A provider callback, authenticated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureEvent {
+    /// The provider's envelope id
+    pub envelope_id: String,
+    pub event: SignatureEventKind,
+    /// Set for signer events
+    #[serde(default)]
+    pub signer_email: Option<String>,
+    /// SHA-256 of the document the envelope covers, set on completion
+    #[serde(default)]
+    pub document_sha256: Option<String>,
+    /// SHA-256 of the signed copy the provider produced
+    #[serde(default)]
+    pub signed_document_sha256: Option<String>,
+}
+
+/// A callback the receiver refuses. The HTTP layer maps Unauthenticated to 401
+/// and Malformed to 400.
+#[derive(Debug, thiserror::Error)]
+pub enum CallbackRejected {
+    #[error("Unauthorized: callback signature is missing or invalid")]
+    Unauthenticated,
+    #[error("Malformed callback: {0}")]
+    Malformed(String),
+}
+
+/// An e-signature provider (see module docs).
+#[axum::async_trait]
+pub trait SignatureProvider: Send + Sync + std::fmt::Debug {
+    /// Recorded on envelopes
+    fn name(&self) -> &'static str;
+
+    /// Creates an envelope for the document and sends it to the signers.
+    /// Returns the provider's envelope id.
+    async fn request_signature(&self, request: &SignatureRequest) -> Result<String>;
+
+    /// Authenticates a callback and reads its event.
+    fn parse_callback(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<SignatureEvent, CallbackRejected>;
+}
+
+/// The provider configured by ESIGN_PROVIDER, if any.
+pub fn provider_from_env(
+    provider: Option<&str>,
+    api_url: Option<String>,
+    api_key: Option<String>,
+    callback_secret: Option<String>,
+    callback_url: Option<String>,
+) -> Result<Option<Arc<dyn SignatureProvider>>> {
+    let Some(provider) = provider.map(|p| p.trim().to_lowercase()) else {
+        return Ok(None);
+    };
+    match provider.as_str() {
+        "" | "none" => Ok(None),
+        "http" => {
+            let api_url = api_url.ok_or_else(|| anyhow::anyhow!("http needs ESIGN_API_URL"))?;
+            let callback_secret = callback_secret
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| anyhow::anyhow!("http needs ESIGN_CALLBACK_SECRET"))?;
+            Ok(Some(Arc::new(HttpSignatureProvider::new(
+                api_url,
+                api_key,
+                callback_secret,
+                callback_url,
+            )?)))
+        }
+        other => Err(anyhow::anyhow!("unknown e-signature provider {:?}", other)),
+    }
+}
+
+/// The generic HTTP provider (see module docs).
+pub struct HttpSignatureProvider {
+    api_url: String,
+    api_key: Option<String>,
+    callback_secret: String,
+    callback_url: Option<String>,
+    http: reqwest::Client,
+}
+
+impl HttpSignatureProvider {
+    pub fn new(
+        api_url: String,
+        api_key: Option<String>,
+        callback_secret: String,
+        callback_url: Option<String>,
+    ) -> Result<Self> {
+        Ok(Self {
+            api_url: api_url.trim_end_matches('/').to_string(),
+            api_key,
+            callback_secret,
+            callback_url,
+            http: reqwest::Client::builder()
+                .timeout(PROVIDER_TIMEOUT)
+                .build()?,
+        })
+    }
+}
+
+impl std::fmt::Debug for HttpSignatureProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpSignatureProvider")
+            .field("api_url", &self.api_url)
+            .field("callback_url", &self.callback_url)
+            .finish()
+    }
+}
+
+#[axum::async_trait]
+impl SignatureProvider for HttpSignatureProvider {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    async fn request_signature(&self, request: &SignatureRequest) -> Result<String> {
+        #[derive(Deserialize)]
+        struct Created {
+            envelope_id: String,
+        }
+
+        let body = serde_json::json!({
+            "reference": request.reference(),
+            "title": request.kind.title(),
+            "document": {
+                "filename": request.filename,
+                "content_type": PDF_CONTENT_TYPE,
+                "content_base64": general_purpose::STANDARD.encode(&request.pdf),
+                "sha256": request.document_sha256,
+            },
+            "signers": request.signers.iter().map(|s| serde_json::json!({
+                "role": s.role,
+                "name": s.name,
+                "email": s.email,
+            })).collect::<Vec<_>>(),
+            "callback_url": self.callback_url,
+        });
+
+        let mut post = self
+            .http
+            .post(format!("{}/envelopes", self.api_url))
+            .json(&body);
+        if let Some(api_key) = &self.api_key {
+            post = post.bearer_auth(api_key);
+        }
+        let created: Created = post.send().await?.error_for_status()?.json().await?;
+        Ok(created.envelope_id)
+    }
+
+    fn parse_callback(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<SignatureEvent, CallbackRejected> {
+        let tag = headers
+            .get(CALLBACK_SIGNATURE_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().strip_prefix("sha256="))
+            .and_then(|v| hex::decode(v).ok())
+            .ok_or(CallbackRejected::Unauthenticated)?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.callback_secret.as_bytes())
+            .expect("HMAC accepts keys of any size");
+        mac.update(body);
+        mac.verify_slice(&tag)
+            .map_err(|_| CallbackRejected::Unauthenticated)?;
+
+        serde_json::from_slice(body).map_err(|e| CallbackRejected::Malformed(e.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignerStatus {
+    Sent,
+    Viewed,
+    Signed,
+    Declined,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvelopeSigner {
+    pub role: String,
+    pub name: String,
+    pub email: String,
+    pub status: SignerStatus,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvelopeStatus {
+    Sent,
+    Completed,
+    Declined,
+    Voided,
+    /// Completed, but not over the document that was sent
+    Rejected,
+}
+
+impl EnvelopeStatus {
+    pub fn is_final(&self) -> bool {
+        !matches!(self, EnvelopeStatus::Sent)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope {
+    pub envelope_id: u64,
+    pub provider: String,
+    pub provider_envelope_id: String,
+    pub escrow_account_id: String,
+    pub document_id: u64,
+    pub kind: DocumentKind,
+    /// SHA-256 of the PDF sent for signature (hex)
+    pub document_sha256: String,
+    pub signers: Vec<EnvelopeSigner>,
+    pub status: EnvelopeStatus,
+    /// Why a completed envelope was rejected
+    #[serde(default)]
+    pub rejection: Option<String>,
+    /// SHA-256 of the signed copy, as reported by the provider
+    #[serde(default)]
+    pub signed_document_sha256: Option<String>,
+    pub requested_at: i64,
+    pub updated_at: i64,
+    #[serde(default)]
+    pub completed_at: Option<i64>,
+}
+
+fn normalize_hash(hash: &str) -> String {
+    hash.trim().trim_start_matches("0x").to_lowercase()
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SignatureStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    envelopes: BTreeMap<u64, Envelope>,
+    #[serde(default)]
+    next_envelope_id: u64,
+}
+
+impl SignatureStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<SignatureStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            SignatureStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Envelopes of an escrow, oldest first.
+    pub fn for_escrow(&self, escrow_account_id: &str) -> Vec<&Envelope> {
+        self.envelopes
+            .values()
+            .filter(|e| e.escrow_account_id == escrow_account_id)
+            .collect()
+    }
+
+    /// The envelope of the document still out for signature or completed,
+    /// if any.
+    pub fn active_for_document(&self, document_id: u64) -> Option<&Envelope> {
+        self.envelopes.values().find(|e| {
+            e.document_id == document_id
+                && matches!(e.status, EnvelopeStatus::Sent | EnvelopeStatus::Completed)
+        })
+    }
+
+    /// Records an envelope the provider created.
+    pub fn add(
+        &mut self,
+        provider: &str,
+        provider_envelope_id: String,
+        request: &SignatureRequest,
+    ) -> Result<Envelope> {
+        if self.find(provider, &provider_envelope_id).is_some() {
+            return Err(anyhow::anyhow!(
+                "Envelope {} of provider {} is already recorded",
+                provider_envelope_id,
+                provider
+            ));
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let envelope = Envelope {
+            envelope_id: self.next_envelope_id + 1,
+            provider: provider.to_string(),
+            provider_envelope_id,
+            escrow_account_id: request.escrow_account_id.clone(),
+            document_id: request.document_id,
+            kind: request.kind,
+            document_sha256: request.document_sha256.clone(),
+            signers: request
+                .signers
+                .iter()
+                .map(|s| EnvelopeSigner {
+                    role: s.role.clone(),
+                    name: s.name.clone(),
+                    email: s.email.clone(),
+                    status: SignerStatus::Sent,
+                    updated_at: now,
+                })
+                .collect(),
+            status: EnvelopeStatus::Sent,
+            rejection: None,
+            signed_document_sha256: None,
+            requested_at: now,
+            updated_at: now,
+            completed_at: None,
+        };
+
+        self.next_envelope_id = envelope.envelope_id;
+        self.envelopes
+            .insert(envelope.envelope_id, envelope.clone());
+        self.save()?;
+        Ok(envelope)
+    }
+
+    fn find(&self, provider: &str, provider_envelope_id: &str) -> Option<u64> {
+        self.envelopes
+            .values()
+            .find(|e| e.provider == provider && e.provider_envelope_id == provider_envelope_id)
+            .map(|e| e.envelope_id)
+    }
+
+    /// Applies a callback event. Returns the envelope and whether the event
+    /// completed it.
+    pub fn apply(&mut self, provider: &str, event: &SignatureEvent) -> Result<(Envelope, bool)> {
+        let envelope_id = self.find(provider, &event.envelope_id).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Envelope {} of provider {} not found",
+                event.envelope_id,
+                provider
+            )
+        })?;
+        let envelope = self
+            .envelopes
+            .get_mut(&envelope_id)
+            .expect("envelope found above");
+
+        if envelope.status.is_final() {
+            return Ok((envelope.clone(), false));
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        match event.event {
+            SignatureEventKind::SignerViewed
+            | SignatureEventKind::SignerSigned
+            | SignatureEventKind::SignerDeclined => {
+                let email = event
+                    .signer_email
+                    .as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("{:?} event needs signer_email", event.event))?;
+                let signer = envelope
+                    .signers
+                    .iter_mut()
+                    .find(|s| s.email.eq_ignore_ascii_case(email.trim()))
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "{} is not a signer of envelope {}",
+                            email,
+                            event.envelope_id
+                        )
+                    })?;
+                let status = match event.event {
+                    SignatureEventKind::SignerViewed => SignerStatus::Viewed,
+                    SignatureEventKind::SignerSigned => SignerStatus::Signed,
+                    _ => SignerStatus::Declined,
+                };
+                // A late "viewed" does not undo a signature
+                if !(status == SignerStatus::Viewed && signer.status == SignerStatus::Signed) {
+                    signer.status = status;
+                    signer.updated_at = now;
+                }
+                if status == SignerStatus::Declined {
+                    envelope.status = EnvelopeStatus::Declined;
+                }
+            }
+            SignatureEventKind::Completed => {
+                let hash = event
+                    .document_sha256
+                    .as_deref()
+                    .map(normalize_hash)
+                    .ok_or_else(|| anyhow::anyhow!("completed event needs document_sha256"))?;
+                envelope.signed_document_sha256 =
+                    event.signed_document_sha256.as_deref().map(normalize_hash);
+                if hash == envelope.document_sha256 {
+                    envelope.status = EnvelopeStatus::Completed;
+                    envelope.completed_at = Some(now);
+                    for signer in &mut envelope.signers {
+                        signer.status = SignerStatus::Signed;
+                        signer.updated_at = now;
+                    }
+                } else {
+                    envelope.status = EnvelopeStatus::Rejected;
+                    envelope.rejection = Some(format!(
+                        "signed document hash {} does not match the document sent ({})",
+                        hash, envelope.document_sha256
+                    ));
+                }
+            }
+            SignatureEventKind::Voided => envelope.status = EnvelopeStatus::Voided,
+        }
+        envelope.updated_at = now;
+
+        let envelope = envelope.clone();
+        self.save()?;
+        let completed = envelope.status == EnvelopeStatus::Completed;
+        Ok((envelope, completed))
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    PrepareSignatureRequest {
+        escrow_account_id: String,
+        document_id: u64,
+        input: SignatureInput,
+        api_key: Option<String>,
+    } -> SignatureRequest;
+    |client, op| {
+        client.prepare_signature_request(
+            &op.escrow_account_id,
+            op.document_id,
+            op.input,
+            op.api_key.as_deref(),
+        )
+    };
+
+    RecordSignatureRequest {
+        provider: &'static str,
+        provider_envelope_id: String,
+        request: SignatureRequest,
+    } -> Envelope;
+    |client, op| client.record_signature_request(op.provider, op.provider_envelope_id, &op.request);
+
+    ApplySignatureEvent {
+        provider: &'static str,
+        event: SignatureEvent,
+    } -> Envelope;
+    |client, op| client.apply_signature_event(op.provider, op.event);
+
+    ListSignatureEnvelopes {
+        escrow_account_id: String,
+        api_key: Option<String>,
+    } -> Vec<Envelope>;
+    |client, op| client.list_signature_envelopes(&op.escrow_account_id, op.api_key.as_deref())
+}
+
+impl MidenClientWrapper {
+    /// Checks a signature request and reads the document to send. Needs the
+    /// buyer's, the seller's, an arbiter's or the closing agent's API key.
+    pub fn prepare_signature_request(
+        &self,
+        escrow_account_id: &str,
+        document_id: u64,
+        input: SignatureInput,
+        api_key: Option<&str>,
+    ) -> Result<SignatureRequest> {
+        let record = self.document_escrow(escrow_account_id, api_key)?;
+        let escrow_hex = record.escrow_account_id;
+        let document = self.documents.get(&escrow_hex, document_id)?;
+
+        if self.documents.next_version(&escrow_hex, document.kind) != document.version + 1 {
+            return Err(anyhow::anyhow!(
+                "Conflict: document {} has been superseded by a newer {}",
+                document_id,
+                document.kind.as_str()
+            ));
+        }
+        if let Some(envelope) = self.signatures.active_for_document(document_id) {
+            return Err(anyhow::anyhow!(
+                "Conflict: document {} is already in envelope {} ({:?})",
+                document_id,
+                envelope.envelope_id,
+                envelope.status
+            ));
+        }
+
+        let mut signers = input.signers;
+        for signer in &mut signers {
+            signer.email = signer.email.trim().to_string();
+        }
+
+        let pdf = self.documents.artifact(document, ArtifactFormat::Pdf)?;
+        Ok(SignatureRequest {
+            escrow_account_id: escrow_hex,
+            document_id,
+            kind: document.kind,
+            filename: format!(
+                "{}-v{}.pdf",
+                document.kind.as_str().replace('_', "-"),
+                document.version
+            ),
+            pdf,
+            document_sha256: document.pdf_hash.clone(),
+            signers,
+        })
+    }
+
+    /// Records the envelope a provider created for a prepared request.
+    pub fn record_signature_request(
+        &mut self,
+        provider: &str,
+        provider_envelope_id: String,
+        request: &SignatureRequest,
+    ) -> Result<Envelope> {
+        let envelope = self
+            .signatures
+            .add(provider, provider_envelope_id, request)?;
+        tracing::info!(
+            "Document {} of escrow {} sent for signature (envelope {}, {} {})",
+            envelope.document_id,
+            envelope.escrow_account_id,
+            envelope.envelope_id,
+            provider,
+            envelope.provider_envelope_id
+        );
+        Ok(envelope)
+    }
+
+    /// Applies a provider callback; a completed envelope marks the document
+    /// signed on the escrow checklist.
+    pub fn apply_signature_event(
+        &mut self,
+        provider: &str,
+        event: SignatureEvent,
+    ) -> Result<Envelope> {
+        let (envelope, completed) = self.signatures.apply(provider, &event)?;
+
+        match envelope.status {
+            EnvelopeStatus::Rejected => tracing::warn!(
+                "⚠️  Envelope {} rejected: {}",
+                envelope.envelope_id,
+                envelope.rejection.as_deref().unwrap_or_default()
+            ),
+            _ if completed => {
+                let signed_at = envelope.completed_at.unwrap_or(envelope.updated_at);
+                self.attachments.mark_signed(
+                    &envelope.escrow_account_id,
+                    envelope.kind,
+                    SignedDocument {
+                        envelope_id: envelope.envelope_id,
+                        document_id: envelope.document_id,
+                        sha256: envelope.document_sha256.clone(),
+                        signed_document_sha256: envelope.signed_document_sha256.clone(),
+                        signed_at,
+                    },
+                )?;
+                tracing::info!(
+                    "Envelope {} completed: {} of escrow {} is signed",
+                    envelope.envelope_id,
+                    envelope.kind.as_str(),
+                    envelope.escrow_account_id
+                );
+            }
+            _ => tracing::debug!(
+                "Envelope {} event {:?}: {:?}",
+                envelope.envelope_id,
+                event.event,
+                envelope.status
+            ),
+        }
+        Ok(envelope)
+    }
+
+    pub fn list_signature_envelopes(
+        &self,
+        escrow_account_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<Vec<Envelope>> {
+        let record = self.document_escrow(escrow_account_id, api_key)?;
+        Ok(self
+            .signatures
+            .for_escrow(&record.escrow_account_id)
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+}
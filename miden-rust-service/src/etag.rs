@@ -0,0 +1,144 @@
+// src/etag.rs
+//
+// ETags for polled read endpoints
+//
+// Frontends poll balances, account info and note listings far more often than
+// those change. Each read resource gets an ETag derived from local client state:
+// - balance / account info: account commitment(s), which change with any
+//   vault or storage update
+// - consumable notes: the set of consumable note IDs for the account
+//
+// Computing a tag reads only the local store. The network is synced at most
+// once per READ_SYNC_INTERVAL_SECS, so an If-None-Match poll that hits costs a
+// store lookup instead of a full sync.
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::time::Instant;
+
+use crate::MidenClientWrapper;
+
+/// A read endpoint whose response can be tagged.
+#[derive(Debug, Clone)]
+pub enum EtagResource {
+    Balance { account: String },
+    AccountInfo,
+    ConsumableNotes { account: Option<String> },
+}
+
+impl EtagResource {
+    /// Stable key identifying the resource (also used by the read cache).
+    pub fn label(&self) -> String {
+        match self {
+            EtagResource::Balance { account } => format!("balance:{}", account),
+            EtagResource::AccountInfo => "account-info".to_string(),
+            EtagResource::ConsumableNotes { account } => {
+                format!("notes:{}", account.as_deref().unwrap_or("alice"))
+            }
+        }
+    }
+
+    /// The single account this resource depends on, if any.
+    pub fn account(&self) -> Option<&str> {
+        match self {
+            EtagResource::Balance { account } => Some(account),
+            EtagResource::AccountInfo => None,
+            EtagResource::ConsumableNotes { account } => {
+                Some(account.as_deref().unwrap_or("alice"))
+            }
+        }
+    }
+}
+
+/// Strong ETag (quoted) over a resource label and its state components.
+fn make_etag(label: &str, parts: &[String]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(label.as_bytes());
+    for part in parts {
+        hasher.update((part.len() as u32).to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    format!("\"{}\"", hex::encode(&hasher.finalize()[..16]))
+}
+
+crate::operations! {
+    ResourceEtag {
+        resource: EtagResource,
+    } -> String;
+    |client, op| client.resource_etag(op.resource).await
+}
+
+impl MidenClientWrapper {
+    /// Syncs with the network unless a sync happened within the read interval.
+    pub(crate) async fn sync_if_stale(&mut self) -> Result<()> {
+        let fresh = self
+            .last_read_sync
+            .map(|at| at.elapsed() < self.config.read_sync_interval)
+            .unwrap_or(false);
+
+        if !fresh {
+            self.sync_state().await?;
+            self.last_read_sync = Some(Instant::now());
+        }
+        Ok(())
+    }
+
+    async fn account_commitment(&mut self, name: &str) -> Result<String> {
+        let account_id = self.named_account(name)?;
+        let account = self
+            .client
+            .get_account(account_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Account not found"))?;
+        Ok(account.account().commitment().to_hex())
+    }
+
+    /// Local sync height, used to key the HTTP read cache.
+    pub async fn sync_height(&mut self) -> Option<u32> {
+        self.client
+            .get_sync_height()
+            .await
+            .ok()
+            .map(|height| height.as_u32())
+    }
+
+    /// Named accounts with their hex IDs, for read-cache alias resolution.
+    pub fn named_account_ids(&self) -> Vec<(&'static str, String)> {
+        ["alice", "bob", "faucet"]
+            .into_iter()
+            .filter_map(|name| {
+                self.named_account(name)
+                    .ok()
+                    .map(|id| (name, crate::account_id_to_hex(id)))
+            })
+            .collect()
+    }
+
+    /// Current ETag of a read resource.
+    pub async fn resource_etag(&mut self, resource: EtagResource) -> Result<String> {
+        self.sync_if_stale().await?;
+
+        let parts = match &resource {
+            EtagResource::Balance { account } => vec![self.account_commitment(account).await?],
+            EtagResource::AccountInfo => vec![
+                self.account_commitment("alice").await?,
+                self.account_commitment("bob").await?,
+                self.account_commitment("faucet").await?,
+            ],
+            EtagResource::ConsumableNotes { account } => {
+                let account_id = self.named_account(account.as_deref().unwrap_or("alice"))?;
+                let mut note_ids: Vec<String> = self
+                    .client
+                    .get_consumable_notes(Some(account_id))
+                    .await?
+                    .iter()
+                    .map(|(note, _)| note.id().to_string())
+                    .collect();
+                note_ids.sort();
+                note_ids
+            }
+        };
+
+        Ok(make_etag(&resource.label(), &parts))
+    }
+}
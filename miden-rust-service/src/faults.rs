@@ -0,0 +1,210 @@
+// src/faults.rs
+//
+// Failure injection for integration tests (feature "fault-injection")
+//
+// Built only with `--features fault-injection`; release builds contain none of
+// this. With the feature on, the wrapper's Miden client is a FaultyClient: it
+// derefs to the real client but intercepts the two calls every chain write goes
+// through, so faults can be armed without touching the callers:
+// - sync_state: `sync_failure` fails the sync, `note_delay` holds it back by
+//   `delay_ms` (as if new notes were slow to reach the node)
+// - submit_new_transaction: `rpc_timeout` fails the submission with a timeout,
+//   `prover_error` fails it as if proving had failed
+//
+// Faults are armed with POST /admin/faults {kind, count?, delay_ms?}. A fault
+// with a `count` disarms itself after that many triggers; one without stays
+// armed until DELETE /admin/faults. GET /admin/faults lists armed faults and
+// how often each has triggered.
+//
+// Injected errors read like the real ones, so they take the same paths: an RPC
+// timeout or failed sync is transient and lands in the retry queue
+// (retry_queue.rs), a prover error fails the request outright. Faults live in
+// memory only and are gone after a restart.
+
+use anyhow::Result;
+use miden_client::{
+    account::AccountId,
+    sync::SyncSummary,
+    transaction::{TransactionId, TransactionRequest},
+};
+use serde::{Deserialize, Serialize};
+use std::ops::{Deref, DerefMut};
+
+use crate::{client::MidenClient, MidenClientWrapper};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FaultKind {
+    RpcTimeout,
+    SyncFailure,
+    NoteDelay,
+    ProverError,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FaultInput {
+    pub kind: FaultKind,
+    /// Triggers before the fault disarms itself (unset: until cleared)
+    pub count: Option<u32>,
+    /// Delay per sync, `note_delay` only
+    pub delay_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Fault {
+    pub fault_id: u64,
+    pub kind: FaultKind,
+    /// Triggers left; None while armed until cleared
+    pub remaining: Option<u32>,
+    pub delay_ms: u64,
+    pub triggered: u32,
+    pub armed_at: i64,
+}
+
+/// Miden client with failure injection on sync and transaction submission.
+pub struct FaultyClient {
+    inner: MidenClient,
+    faults: Vec<Fault>,
+    next_fault_id: u64,
+}
+
+impl From<MidenClient> for FaultyClient {
+    fn from(inner: MidenClient) -> Self {
+        Self {
+            inner,
+            faults: Vec::new(),
+            next_fault_id: 0,
+        }
+    }
+}
+
+impl Deref for FaultyClient {
+    type Target = MidenClient;
+
+    fn deref(&self) -> &MidenClient {
+        &self.inner
+    }
+}
+
+impl DerefMut for FaultyClient {
+    fn deref_mut(&mut self) -> &mut MidenClient {
+        &mut self.inner
+    }
+}
+
+impl FaultyClient {
+    pub async fn sync_state(&mut self) -> Result<SyncSummary> {
+        if let Some(fault) = self.trigger(FaultKind::NoteDelay) {
+            tracing::warn!("💥 Injected fault {}: delaying sync", fault.fault_id);
+            tokio::time::sleep(std::time::Duration::from_millis(fault.delay_ms)).await;
+        }
+        if let Some(fault) = self.trigger(FaultKind::SyncFailure) {
+            tracing::warn!("💥 Injected fault {}: failing sync", fault.fault_id);
+            return Err(anyhow::anyhow!(
+                "Sync failed: node unavailable (injected fault)"
+            ));
+        }
+        Ok(self.inner.sync_state().await?)
+    }
+
+    pub async fn submit_new_transaction(
+        &mut self,
+        account_id: AccountId,
+        transaction_request: TransactionRequest,
+    ) -> Result<TransactionId> {
+        if let Some(fault) = self.trigger(FaultKind::RpcTimeout) {
+            tracing::warn!("💥 Injected fault {}: RPC timeout", fault.fault_id);
+            return Err(anyhow::anyhow!(
+                "Transaction submission timed out (injected fault)"
+            ));
+        }
+        if let Some(fault) = self.trigger(FaultKind::ProverError) {
+            tracing::warn!("💥 Injected fault {}: prover error", fault.fault_id);
+            return Err(anyhow::anyhow!(
+                "Transaction proving failed (injected fault)"
+            ));
+        }
+        Ok(self
+            .inner
+            .submit_new_transaction(account_id, transaction_request)
+            .await?)
+    }
+
+    pub fn faults(&self) -> &[Fault] {
+        &self.faults
+    }
+
+    pub fn arm(&mut self, input: FaultInput) -> Fault {
+        self.next_fault_id += 1;
+        let fault = Fault {
+            fault_id: self.next_fault_id,
+            kind: input.kind,
+            remaining: input.count,
+            delay_ms: input.delay_ms.unwrap_or_default(),
+            triggered: 0,
+            armed_at: chrono::Utc::now().timestamp(),
+        };
+        self.faults.push(fault.clone());
+        fault
+    }
+
+    /// Disarms every fault. Returns how many were armed.
+    pub fn clear(&mut self) -> usize {
+        std::mem::take(&mut self.faults).len()
+    }
+
+    /// Fires the oldest armed fault of `kind`, disarming it once its count runs out.
+    fn trigger(&mut self, kind: FaultKind) -> Option<Fault> {
+        let index = self.faults.iter().position(|f| f.kind == kind)?;
+        let fault = &mut self.faults[index];
+        fault.triggered += 1;
+        if let Some(remaining) = &mut fault.remaining {
+            *remaining = remaining.saturating_sub(1);
+        }
+        let fired = fault.clone();
+        if fired.remaining == Some(0) {
+            self.faults.remove(index);
+        }
+        Some(fired)
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    ListFaults {} -> serde_json::Value;
+    |client, _op| client.list_faults();
+
+    InjectFault {
+        input: FaultInput,
+    } -> serde_json::Value;
+    |client, op| client.inject_fault(op.input);
+
+    ClearFaults {} -> serde_json::Value;
+    |client, _op| client.clear_faults()
+}
+
+impl MidenClientWrapper {
+    pub fn list_faults(&self) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self.client.faults()))
+    }
+
+    pub fn inject_fault(&mut self, input: FaultInput) -> Result<serde_json::Value> {
+        let fault = self.client.arm(input);
+        tracing::warn!(
+            "💥 Armed fault {} ({:?}, count {:?})",
+            fault.fault_id,
+            fault.kind,
+            fault.remaining
+        );
+        Ok(serde_json::json!(fault))
+    }
+
+    pub fn clear_faults(&mut self) -> Result<serde_json::Value> {
+        let cleared = self.client.clear();
+        tracing::info!("Cleared {} injected fault(s)", cleared);
+        Ok(serde_json::json!({ "cleared": cleared }))
+    }
+}
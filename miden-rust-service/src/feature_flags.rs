@@ -0,0 +1,297 @@
+// src/feature_flags.rs
+//
+// Feature flags
+//
+// Subsystems a deployment can run without, switched by configuration and, at
+// runtime, by admin keys: one build serves different capability sets per
+// environment, and a feature that misbehaves is turned off without a redeploy.
+// - auto_funding: minting PROP into the service wallets at startup
+//   (AUTO_FUND_AMOUNT, AUTO_FUND_ALICE); read once, when the service starts
+// - demo_proofs: the /generate-*-proof endpoints, which prove on the service's
+//   behalf from inputs the caller sends (verification is not affected)
+// - marketplace: listings, offers, search, auctions and share trading
+// - webhooks: webhook deliveries of activity subscriptions (subscriptions.rs)
+//   and webhook lifecycle hooks (hooks.rs); websocket subscriptions and WASM
+//   hooks go on
+//
+// FEATURE_FLAGS sets the defaults, e.g. "demo_proofs=off,webhooks=off"; a
+// feature it does not name is on. PUT /admin/features/:feature
+// {"enabled": false} overrides the default until
+// DELETE /admin/features/:feature drops the override; GET /admin/features
+// lists every feature with its default and the state in force. Overrides are
+// persisted (FEATURE_FLAGS_PATH) and keep winning over FEATURE_FLAGS across
+// restarts until dropped.
+//
+// Requests to a disabled feature's routes get 404 before they reach the client
+// queue. A webhook delivery or webhook hook is skipped while webhooks are off,
+// a hook as if it were not registered.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
+
+use crate::MidenClientWrapper;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeatureFlag {
+    AutoFunding,
+    DemoProofs,
+    Marketplace,
+    Webhooks,
+}
+
+/// Route prefixes of the marketplace
+const MARKETPLACE_ROUTES: &[&str] = &["/listings", "/search", "/auctions", "/markets"];
+
+impl FeatureFlag {
+    pub const ALL: [FeatureFlag; 4] = [
+        FeatureFlag::AutoFunding,
+        FeatureFlag::DemoProofs,
+        FeatureFlag::Marketplace,
+        FeatureFlag::Webhooks,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeatureFlag::AutoFunding => "auto_funding",
+            FeatureFlag::DemoProofs => "demo_proofs",
+            FeatureFlag::Marketplace => "marketplace",
+            FeatureFlag::Webhooks => "webhooks",
+        }
+    }
+
+    /// The feature serving `route`, a matched route without its version
+    /// prefix.
+    pub fn for_route(route: &str) -> Option<FeatureFlag> {
+        if route.starts_with("/generate-") && route.ends_with("-proof") {
+            return Some(FeatureFlag::DemoProofs);
+        }
+        MARKETPLACE_ROUTES
+            .iter()
+            .any(|prefix| {
+                route
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .then_some(FeatureFlag::Marketplace)
+    }
+}
+
+impl FromStr for FeatureFlag {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        FeatureFlag::ALL
+            .into_iter()
+            .find(|f| f.as_str() == s.trim())
+            .ok_or_else(|| anyhow::anyhow!("Unknown feature: {}", s))
+    }
+}
+
+/// Parses FEATURE_FLAGS: comma-separated `feature=on|off`.
+pub fn parse_feature_defaults(list: &str) -> Result<BTreeMap<FeatureFlag, bool>> {
+    let mut defaults = BTreeMap::new();
+    for entry in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (feature, state) = entry
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("entry is not feature=on|off: {}", entry))?;
+        let enabled = match state.trim().to_lowercase().as_str() {
+            "on" | "true" | "1" => true,
+            "off" | "false" | "0" => false,
+            other => return Err(anyhow::anyhow!("{} is neither on nor off", other)),
+        };
+        defaults.insert(feature.parse()?, enabled);
+    }
+    Ok(defaults)
+}
+
+/// An admin's runtime setting of a feature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlagOverride {
+    pub enabled: bool,
+    /// API key that set it
+    pub set_by: u64,
+    pub set_at: i64,
+}
+
+/// A feature as GET /admin/features lists it.
+#[derive(Debug, Clone, Serialize)]
+pub struct FlagState {
+    pub feature: FeatureFlag,
+    pub enabled: bool,
+    /// Per FEATURE_FLAGS
+    pub default: bool,
+    #[serde(rename = "override")]
+    pub override_: Option<FlagOverride>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoredOverrides {
+    #[serde(default)]
+    overrides: BTreeMap<FeatureFlag, FlagOverride>,
+}
+
+/// The flags in force, shared by the client task (admin changes, auto-funding,
+/// hooks), the subscription dispatcher and the route gate (see main.rs).
+#[derive(Debug, Clone)]
+pub struct FeatureFlags {
+    path: Arc<PathBuf>,
+    defaults: Arc<BTreeMap<FeatureFlag, bool>>,
+    overrides: Arc<RwLock<BTreeMap<FeatureFlag, FlagOverride>>>,
+}
+
+impl FeatureFlags {
+    pub fn load(path: impl Into<PathBuf>, defaults: BTreeMap<FeatureFlag, bool>) -> Result<Self> {
+        let path = path.into();
+
+        let stored = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<StoredOverrides>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            StoredOverrides::default()
+        };
+
+        Ok(Self {
+            path: Arc::new(path),
+            defaults: Arc::new(defaults),
+            overrides: Arc::new(RwLock::new(stored.overrides)),
+        })
+    }
+
+    fn save(&self, overrides: &BTreeMap<FeatureFlag, FlagOverride>) -> Result<()> {
+        let stored = StoredOverrides {
+            overrides: overrides.clone(),
+        };
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(&stored)?)?;
+        std::fs::rename(&tmp_path, self.path.as_ref())?;
+        Ok(())
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, BTreeMap<FeatureFlag, FlagOverride>> {
+        self.overrides.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn default_for(&self, feature: FeatureFlag) -> bool {
+        self.defaults.get(&feature).copied().unwrap_or(true)
+    }
+
+    pub fn is_enabled(&self, feature: FeatureFlag) -> bool {
+        self.read()
+            .get(&feature)
+            .map_or_else(|| self.default_for(feature), |o| o.enabled)
+    }
+
+    pub fn state(&self, feature: FeatureFlag) -> FlagState {
+        let override_ = self.read().get(&feature).cloned();
+        let default = self.default_for(feature);
+        FlagState {
+            feature,
+            enabled: override_.as_ref().map_or(default, |o| o.enabled),
+            default,
+            override_,
+        }
+    }
+
+    pub fn list(&self) -> Vec<FlagState> {
+        FeatureFlag::ALL
+            .into_iter()
+            .map(|f| self.state(f))
+            .collect()
+    }
+
+    /// Overrides a feature's default, or drops the override with None.
+    pub fn set(
+        &self,
+        feature: FeatureFlag,
+        enabled: Option<bool>,
+        set_by: u64,
+    ) -> Result<FlagState> {
+        {
+            let mut overrides = self.overrides.write().unwrap_or_else(|e| e.into_inner());
+            let mut updated = overrides.clone();
+            match enabled {
+                Some(enabled) => {
+                    updated.insert(
+                        feature,
+                        FlagOverride {
+                            enabled,
+                            set_by,
+                            set_at: chrono::Utc::now().timestamp(),
+                        },
+                    );
+                }
+                None => {
+                    updated.remove(&feature);
+                }
+            }
+            self.save(&updated)?;
+            *overrides = updated;
+        }
+        Ok(self.state(feature))
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    ListFeatureFlags {
+        api_key: Option<String>,
+    } -> Vec<FlagState>;
+    |client, op| client.list_feature_flags(op.api_key.as_deref());
+
+    SetFeatureFlag {
+        feature: String,
+        enabled: Option<bool>,
+        api_key: Option<String>,
+    } -> FlagState;
+    |client, op| client.set_feature_flag(&op.feature, op.enabled, op.api_key.as_deref())
+}
+
+impl MidenClientWrapper {
+    pub fn list_feature_flags(&self, api_key: Option<&str>) -> Result<Vec<FlagState>> {
+        self.admin_principal(api_key, "feature flags")?;
+        Ok(self.features.list())
+    }
+
+    /// Turns a feature on or off, or back to its default with None. Admin only.
+    pub fn set_feature_flag(
+        &mut self,
+        feature: &str,
+        enabled: Option<bool>,
+        api_key: Option<&str>,
+    ) -> Result<FlagState> {
+        let key_id = self.admin_principal(api_key, "feature flags")?.key_id;
+        let feature: FeatureFlag = feature.parse()?;
+        let state = self.features.set(feature, enabled, key_id)?;
+
+        match enabled {
+            Some(enabled) => tracing::warn!(
+                "🚩 Feature {} turned {} by API key {}",
+                feature.as_str(),
+                if enabled { "on" } else { "off" },
+                key_id
+            ),
+            None => tracing::warn!(
+                "🚩 Feature {} back to its default ({}) by API key {}",
+                feature.as_str(),
+                if state.enabled { "on" } else { "off" },
+                key_id
+            ),
+        }
+        if feature == FeatureFlag::AutoFunding {
+            tracing::info!("   auto_funding takes effect at the next startup");
+        }
+
+        Ok(state)
+    }
+}
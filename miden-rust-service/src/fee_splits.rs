@@ -0,0 +1,409 @@
+// src/fee_splits.rs
+//
+// Escrow payout splits
+//
+// Settling an escrow pays several parties out of one balance: the seller, the
+// platform fee (PLATFORM_FEE_BPS, treasury.rs), an agent commission, tax
+// withheld for the property's jurisdiction (withholding.rs), and for splits
+// the buyer. Every payout is an output note of one transaction from the
+// escrow account, so a settlement pays everyone or no one.
+//
+// The payouts are planned first and the plan must add up to exactly the
+// escrowed amount before a note is built; a plan that does not (deductions
+// larger than the seller's share) is refused and nothing is submitted. Zero
+// payouts are left out.
+//
+// An escrow's agent commission is set with PUT
+// /escrows/:escrow_account_id/commission
+//   {"agent_account_id": "0x...", "commission_bps": 250}
+// by its seller or an arbiter while it is created or funded; {"commission_bps":
+// 0} removes it. Like the platform fee, it is charged in basis points of the
+// seller's share and comes out of it.
+
+use anyhow::Result;
+use miden_client::{account::AccountId, asset::FungibleAsset, transaction::TransactionRequest};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account_id_to_hex, client,
+    escrow::{EscrowAccount, EscrowAuthError, EscrowStatus},
+    ledger::Posting,
+    reconcile::parse_hex_account_id,
+    records::EscrowRecord,
+    treasury::PlatformFee,
+    withholding::Withholding,
+    MidenClientWrapper,
+};
+
+const BPS_DENOMINATOR: u128 = 10_000;
+
+/// Commission an escrow pays its agent out of the seller's share.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentCommission {
+    pub agent_account_id: String,
+    pub commission_bps: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommissionInput {
+    #[serde(default)]
+    pub agent_account_id: Option<String>,
+    pub commission_bps: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayoutKind {
+    Seller,
+    Buyer,
+    AgentCommission,
+    TaxWithholding,
+}
+
+/// Payouts of one escrow settlement, checked to add up to its amount.
+pub(crate) struct PayoutPlan {
+    pub faucet_id: AccountId,
+    pub total: u64,
+    /// P2ID payouts other than the platform fee
+    pub payouts: Vec<(PayoutKind, AccountId, u64)>,
+    pub fee: Option<PlatformFee>,
+    /// Tax withheld, paid as the TaxWithholding payout
+    pub withholding: Option<Withholding>,
+}
+
+impl PayoutPlan {
+    /// Plan of `payouts` with the zero ones left out, checked to add up to
+    /// `total`.
+    fn new(
+        faucet_id: AccountId,
+        total: u64,
+        mut payouts: Vec<(PayoutKind, AccountId, u64)>,
+        fee: Option<PlatformFee>,
+        withholding: Option<Withholding>,
+    ) -> Result<Self> {
+        payouts.retain(|(.., amount)| *amount > 0);
+        let plan = PayoutPlan {
+            faucet_id,
+            total,
+            payouts,
+            fee,
+            withholding,
+        };
+        plan.check()?;
+        Ok(plan)
+    }
+
+    pub fn fee_amount(&self) -> u64 {
+        self.fee.as_ref().map_or(0, |fee| fee.asset.amount())
+    }
+
+    /// Amount paid to `kind`.
+    pub fn amount(&self, kind: PayoutKind) -> u64 {
+        self.payouts
+            .iter()
+            .filter(|(k, ..)| *k == kind)
+            .map(|(.., amount)| amount)
+            .sum()
+    }
+
+    /// Fails unless the payouts add up to exactly the total.
+    fn check(&self) -> Result<()> {
+        let paid = self
+            .payouts
+            .iter()
+            .try_fold(self.fee_amount(), |sum, (.., amount)| {
+                sum.checked_add(*amount)
+            })
+            .ok_or_else(|| anyhow::anyhow!("Payouts overflow"))?;
+        if paid != self.total {
+            return Err(anyhow::anyhow!(
+                "Conflict: payouts add up to {} of the {} escrowed",
+                paid,
+                self.total
+            ));
+        }
+        Ok(())
+    }
+
+    /// Ledger postings: the escrow credited its total, every payee debited.
+    pub fn postings(&self, escrow_hex: &str) -> Vec<Posting> {
+        let faucet_hex = account_id_to_hex(self.faucet_id);
+        let mut postings = vec![Posting::credit(escrow_hex, &faucet_hex, self.total)];
+        for (_, recipient, amount) in &self.payouts {
+            postings.push(Posting::debit(
+                account_id_to_hex(*recipient),
+                &faucet_hex,
+                *amount,
+            ));
+        }
+        if let Some(fee) = &self.fee {
+            postings.push(Posting::debit(
+                account_id_to_hex(fee.treasury),
+                &faucet_hex,
+                fee.asset.amount(),
+            ));
+        }
+        postings
+    }
+}
+
+/// `bps` basis points of `amount`, rounded down.
+fn basis_points(amount: u64, bps: u64) -> u64 {
+    (amount as u128 * bps as u128 / BPS_DENOMINATOR) as u64
+}
+
+/// What is left of the seller's share once the platform fee, commission and
+/// withholding come out of it.
+fn seller_net(to_seller: u64, fee: u64, commission: u64, withheld: u64) -> Result<u64> {
+    to_seller
+        .checked_sub(fee)
+        .and_then(|net| net.checked_sub(commission))
+        .and_then(|net| net.checked_sub(withheld))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Conflict: platform fee {}, commission {} and withholding {} exceed the seller's share of {}",
+                fee,
+                commission,
+                withheld,
+                to_seller
+            )
+        })
+}
+
+// ============================================================================
+// CLIENT INTEGRATION
+// ============================================================================
+
+crate::operations! {
+    /// Sets or removes an escrow's agent commission.
+    SetEscrowCommission {
+        escrow_account_id: String,
+        input: CommissionInput,
+        api_key: Option<String>,
+    } -> EscrowRecord;
+    |client, op| client.set_escrow_commission(&op.escrow_account_id, op.input, op.api_key.as_deref())
+}
+
+impl MidenClientWrapper {
+    /// Sets an escrow's agent commission (see module docs).
+    pub fn set_escrow_commission(
+        &mut self,
+        escrow_account_id: &str,
+        input: CommissionInput,
+        api_key: Option<&str>,
+    ) -> Result<EscrowRecord> {
+        let escrow_hex = escrow_account_id.to_lowercase();
+        let record =
+            self.records.escrows.get(&escrow_hex).ok_or_else(|| {
+                anyhow::anyhow!("Escrow {} not found in service records", escrow_hex)
+            })?;
+        if let Some(principal) = self.request_principal(api_key)? {
+            if !(principal.owns(&record.seller_account_id) || principal.arbiter) {
+                return Err(EscrowAuthError::Forbidden(format!(
+                    "API key {} ({}) may not set the commission of escrow {}",
+                    principal.key_id, principal.label, escrow_hex
+                ))
+                .into());
+            }
+        }
+        if !matches!(record.status, EscrowStatus::Created | EscrowStatus::Funded) {
+            return Err(anyhow::anyhow!(
+                "Conflict: escrow {} is {:?}",
+                escrow_hex,
+                record.status
+            ));
+        }
+
+        let commission = if input.commission_bps == 0 {
+            None
+        } else {
+            let agent = input
+                .agent_account_id
+                .ok_or_else(|| anyhow::anyhow!("agent_account_id is required"))?;
+            let agent_hex = self.account_hex(&agent)?;
+            parse_hex_account_id(&agent_hex)?;
+            Some(AgentCommission {
+                agent_account_id: agent_hex,
+                commission_bps: input.commission_bps,
+            })
+        };
+        tracing::info!(
+            "Commission of escrow {} set to {:?}",
+            escrow_hex,
+            commission
+        );
+        self.records.set_escrow_commission(&escrow_hex, commission);
+        self.records
+            .escrows
+            .get(&escrow_hex)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Escrow {} not found in service records", escrow_hex))
+    }
+
+    /// Plans a settlement of `escrow` paying `to_seller` of its amount to the
+    /// seller's side (platform fee and commission included) and the rest to
+    /// the buyer.
+    pub(crate) fn plan_payouts(
+        &mut self,
+        escrow: &EscrowAccount,
+        faucet_id: AccountId,
+        to_seller: u64,
+    ) -> Result<PayoutPlan> {
+        let escrow_hex = account_id_to_hex(escrow.escrow_account_id);
+        let to_buyer = escrow.amount.checked_sub(to_seller).ok_or_else(|| {
+            anyhow::anyhow!("Seller share exceeds the {} escrowed", escrow.amount)
+        })?;
+
+        let fee = self.platform_fee_note(escrow.escrow_account_id, faucet_id, to_seller)?;
+        let fee_amount = fee.as_ref().map_or(0, |fee| fee.asset.amount());
+        let commission = self
+            .records
+            .escrows
+            .get(&escrow_hex)
+            .and_then(|record| record.commission.clone());
+        let mut payouts = Vec::new();
+        let mut commission_amount = 0;
+        if let Some(commission) = &commission {
+            commission_amount = basis_points(to_seller, commission.commission_bps);
+            let agent = parse_hex_account_id(&commission.agent_account_id)?;
+            payouts.push((PayoutKind::AgentCommission, agent, commission_amount));
+        }
+        let withholding = self.plan_withholding(escrow, faucet_id, to_seller)?;
+        let withheld = withholding.as_ref().map_or(0, |w| w.amount);
+        if let Some(withholding) = &withholding {
+            let tax_account = parse_hex_account_id(&withholding.tax_account_id)?;
+            payouts.push((PayoutKind::TaxWithholding, tax_account, withheld));
+        }
+        let seller_net = seller_net(to_seller, fee_amount, commission_amount, withheld)?;
+        payouts.push((PayoutKind::Seller, escrow.seller_account_id, seller_net));
+        payouts.push((PayoutKind::Buyer, escrow.buyer_account_id, to_buyer));
+
+        PayoutPlan::new(faucet_id, escrow.amount, payouts, fee, withholding)
+    }
+
+    /// Records the platform fee and withholding of a plan settled in
+    /// transaction `tx_id`.
+    pub(crate) fn record_payouts(&mut self, escrow_hex: &str, plan: &PayoutPlan, tx_id: &str) {
+        if let Some(fee) = &plan.fee {
+            self.record_platform_fee(escrow_hex, fee, tx_id);
+        }
+        if let Some(withholding) = &plan.withholding {
+            self.record_withholding(withholding, tx_id);
+        }
+    }
+
+    /// The single transaction paying every payout of `plan` from the escrow.
+    pub(crate) fn payout_request(
+        &mut self,
+        escrow_account_id: AccountId,
+        plan: &PayoutPlan,
+    ) -> Result<TransactionRequest> {
+        plan.check()?;
+        let mut output_notes = Vec::with_capacity(plan.payouts.len() + 1);
+        for (_, recipient, amount) in &plan.payouts {
+            let asset = FungibleAsset::new(plan.faucet_id, *amount)?;
+            output_notes.push(client::p2id_note(
+                escrow_account_id,
+                *recipient,
+                vec![asset.into()],
+                &mut self.rng,
+            )?);
+        }
+        if let Some(fee) = &plan.fee {
+            output_notes.push(fee.note.clone());
+        }
+        client::notes_request(output_notes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use miden_objects::testing::account_id::{
+        ACCOUNT_ID_PUBLIC_FUNGIBLE_FAUCET, ACCOUNT_ID_REGULAR_PRIVATE_ACCOUNT_UPDATABLE_CODE,
+        ACCOUNT_ID_SENDER,
+    };
+
+    fn account(id: u128) -> AccountId {
+        AccountId::try_from(id).unwrap()
+    }
+
+    #[test]
+    fn basis_points_round_down() {
+        // 2.5% of 999 is 24.975
+        assert_eq!(basis_points(999, 250), 24);
+        assert_eq!(basis_points(39, 250), 0);
+        assert_eq!(basis_points(u64::MAX, 10_000), u64::MAX);
+    }
+
+    #[test]
+    fn rounded_deductions_leave_the_remainder_to_the_seller() {
+        // 2.5% commission and 15% withholding of 1001 are 25.025 and 150.15
+        let (to_seller, to_buyer) = (1_001, 499);
+        let commission = basis_points(to_seller, 250);
+        let withheld = basis_points(to_seller, 1_500);
+        assert_eq!((commission, withheld), (25, 150));
+
+        let net = seller_net(to_seller, 0, commission, withheld).unwrap();
+        assert_eq!(net, 826);
+
+        let faucet = account(ACCOUNT_ID_PUBLIC_FUNGIBLE_FAUCET);
+        let seller = account(ACCOUNT_ID_SENDER);
+        let buyer = account(ACCOUNT_ID_REGULAR_PRIVATE_ACCOUNT_UPDATABLE_CODE);
+        let payouts = vec![
+            (PayoutKind::AgentCommission, seller, commission),
+            (PayoutKind::TaxWithholding, seller, withheld),
+            (PayoutKind::Seller, seller, net),
+            (PayoutKind::Buyer, buyer, to_buyer),
+        ];
+        let plan = PayoutPlan::new(faucet, 1_500, payouts, None, None).unwrap();
+        assert_eq!(plan.amount(PayoutKind::Seller), 826);
+        assert_eq!(plan.fee_amount(), 0);
+    }
+
+    #[test]
+    fn deductions_over_the_seller_share_conflict() {
+        let err = seller_net(100, 40, 40, 21).unwrap_err();
+        assert!(err.to_string().starts_with("Conflict:"), "{}", err);
+        assert_eq!(seller_net(100, 40, 40, 20).unwrap(), 0);
+    }
+
+    #[test]
+    fn payouts_must_add_up_to_the_total() {
+        let faucet = account(ACCOUNT_ID_PUBLIC_FUNGIBLE_FAUCET);
+        let seller = account(ACCOUNT_ID_SENDER);
+        let buyer = account(ACCOUNT_ID_REGULAR_PRIVATE_ACCOUNT_UPDATABLE_CODE);
+
+        let short = vec![
+            (PayoutKind::Seller, seller, 600),
+            (PayoutKind::Buyer, buyer, 399),
+        ];
+        let err = PayoutPlan::new(faucet, 1_000, short, None, None)
+            .err()
+            .unwrap();
+        assert!(err.to_string().starts_with("Conflict:"), "{}", err);
+
+        let overflow = vec![
+            (PayoutKind::Seller, seller, u64::MAX),
+            (PayoutKind::Buyer, buyer, 1),
+        ];
+        assert!(PayoutPlan::new(faucet, 1_000, overflow, None, None).is_err());
+    }
+
+    #[test]
+    fn zero_payouts_are_left_out() {
+        let faucet = account(ACCOUNT_ID_PUBLIC_FUNGIBLE_FAUCET);
+        let seller = account(ACCOUNT_ID_SENDER);
+        let buyer = account(ACCOUNT_ID_REGULAR_PRIVATE_ACCOUNT_UPDATABLE_CODE);
+
+        let payouts = vec![
+            (PayoutKind::AgentCommission, seller, 0),
+            (PayoutKind::Seller, seller, 1_000),
+            (PayoutKind::Buyer, buyer, 0),
+        ];
+        let plan = PayoutPlan::new(faucet, 1_000, payouts, None, None).unwrap();
+        assert_eq!(plan.payouts.len(), 1);
+        assert_eq!(plan.payouts[0].0, PayoutKind::Seller);
+        assert_eq!(plan.amount(PayoutKind::Buyer), 0);
+    }
+}
@@ -0,0 +1,306 @@
+// src/field_encryption.rs
+//
+// Field-level encryption of service metadata at rest
+//
+// Sensitive fields of the service records (records.rs) are encrypted when they
+// are written to disk and decrypted when they are loaded, so the rest of the
+// service (and the API) only ever sees plaintext.
+//
+// Keys are two-level (envelope encryption):
+// - the master key comes from FIELD_ENCRYPTION_MASTER_KEY, or from the file
+//   named by FIELD_ENCRYPTION_MASTER_KEY_FILE (e.g. a secret mounted by a KMS
+//   agent); it never encrypts data itself
+// - every tenant (the account a record belongs to) gets its own random data
+//   key, stored in DATA_KEYS_PATH wrapped (AES-256-GCM) under the master key
+//
+// Tenants are stored as a keyed hash, so the key file does not reveal which
+// accounts have records. An encrypted field is stored as the string
+//   "enc:v1:<data key id>:<base64(nonce || ciphertext)>"
+// where the plaintext is the field's JSON value and the associated data names
+// the record and field, so a ciphertext cannot be moved to another field.
+// Plaintext values load unchanged and are encrypted on the next save.
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+const SEALED_PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+
+/// The key data keys are wrapped with (also wraps the master secret, see
+/// secrets.rs).
+#[derive(Clone)]
+pub struct MasterKey([u8; 32]);
+
+impl std::fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the key
+        f.debug_struct("MasterKey").finish_non_exhaustive()
+    }
+}
+
+impl MasterKey {
+    /// Parses a hex-encoded 32-byte key (surrounding whitespace ignored).
+    pub fn from_hex(value: &str) -> Result<Self> {
+        let bytes = hex::decode(value.trim().trim_start_matches("0x"))
+            .map_err(|_| anyhow::anyhow!("Master key must be hex"))?;
+        let key: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Master key must be 32 bytes"))?;
+        Ok(Self(key))
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WrappedDataKey {
+    key_id: u64,
+    /// Keyed hash of the tenant
+    tenant: String,
+    nonce: String,
+    wrapped_key: String,
+    created_at: i64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DataKeyFile {
+    #[serde(default)]
+    keys: BTreeMap<u64, WrappedDataKey>,
+    #[serde(default)]
+    next_key_id: u64,
+}
+
+#[derive(Default)]
+struct KeyState {
+    file: DataKeyFile,
+    /// Unwrapped data keys by key ID
+    unwrapped: HashMap<u64, [u8; 32]>,
+}
+
+/// Encrypts and decrypts record fields with per-tenant data keys.
+pub struct FieldCipher {
+    path: PathBuf,
+    master: MasterKey,
+    // Saving records only borrows them, but may create a data key for a new tenant
+    state: Mutex<KeyState>,
+}
+
+impl std::fmt::Debug for FieldCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FieldCipher")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+/// AES-256-GCM; the result is nonce || ciphertext.
+pub(crate) fn encrypt(key: &[u8; 32], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::rng().fill_bytes(&mut nonce);
+
+    let cipher = Aes256Gcm::new(key.into());
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend(ciphertext);
+    Ok(sealed)
+}
+
+pub(crate) fn decrypt(key: &[u8; 32], aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return Err(anyhow::anyhow!("Ciphertext is truncated"));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+
+    let cipher = Aes256Gcm::new(key.into());
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| anyhow::anyhow!("Decryption failed (wrong key or tampered data)"))
+}
+
+fn wrap_aad(key_id: u64, tenant: &str) -> Vec<u8> {
+    format!("obscura-data-key:{}:{}", key_id, tenant).into_bytes()
+}
+
+/// Whether a stored value is an encrypted field.
+pub fn is_sealed(value: &serde_json::Value) -> bool {
+    value
+        .as_str()
+        .map(|s| s.starts_with(SEALED_PREFIX))
+        .unwrap_or(false)
+}
+
+impl FieldCipher {
+    /// Loads the data key file and unwraps every key, so a wrong master key
+    /// fails at startup rather than on first use.
+    pub fn load(path: impl Into<PathBuf>, master: MasterKey) -> Result<Self> {
+        let path = path.into();
+
+        let file = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<DataKeyFile>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            DataKeyFile::default()
+        };
+
+        let mut unwrapped = HashMap::new();
+        for key in file.keys.values() {
+            let sealed = [hex::decode(&key.nonce)?, hex::decode(&key.wrapped_key)?].concat();
+            let data_key = decrypt(&master.0, &wrap_aad(key.key_id, &key.tenant), &sealed)
+                .map_err(|_| {
+                    anyhow::anyhow!(
+                        "Master key does not unwrap data key {} in {}",
+                        key.key_id,
+                        path.display()
+                    )
+                })?;
+            let data_key: [u8; 32] = data_key
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("Data key {} is not 32 bytes", key.key_id))?;
+            unwrapped.insert(key.key_id, data_key);
+        }
+
+        Ok(Self {
+            path,
+            master,
+            state: Mutex::new(KeyState { file, unwrapped }),
+        })
+    }
+
+    fn save(&self, file: &DataKeyFile) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(file)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    fn tenant_hash(&self, tenant: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(b"obscura-tenant");
+        hasher.update(self.master.0);
+        hasher.update(tenant.to_lowercase().as_bytes());
+        hex::encode(hasher.finalize())
+    }
+
+    /// The tenant's data key, created (and persisted) on first use.
+    fn tenant_key(&self, tenant: &str) -> Result<(u64, [u8; 32])> {
+        let tenant = self.tenant_hash(tenant);
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Data key state is poisoned"))?;
+
+        if let Some(key) = state.file.keys.values().find(|k| k.tenant == tenant) {
+            let key_id = key.key_id;
+            return Ok((key_id, state.unwrapped[&key_id]));
+        }
+
+        let mut data_key = [0u8; 32];
+        rand::rng().fill_bytes(&mut data_key);
+        state.file.next_key_id += 1;
+        let key_id = state.file.next_key_id;
+        let wrapped = encrypt(&self.master.0, &wrap_aad(key_id, &tenant), &data_key)?;
+        let (nonce, wrapped_key) = wrapped.split_at(NONCE_LEN);
+
+        state.file.keys.insert(
+            key_id,
+            WrappedDataKey {
+                key_id,
+                tenant,
+                nonce: hex::encode(nonce),
+                wrapped_key: hex::encode(wrapped_key),
+                created_at: chrono::Utc::now().timestamp(),
+            },
+        );
+        // The key must be on disk before anything is encrypted with it
+        if let Err(e) = self.save(&state.file) {
+            state.file.keys.remove(&key_id);
+            return Err(e);
+        }
+        state.unwrapped.insert(key_id, data_key);
+
+        tracing::info!("🔑 Created data key {}", key_id);
+        Ok((key_id, data_key))
+    }
+
+    /// Encrypts `value` under the tenant's data key; `context` names the
+    /// record and field (e.g. "properties/prop-1/ipfs_cid").
+    pub fn seal(
+        &self,
+        tenant: &str,
+        context: &str,
+        value: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let (key_id, data_key) = self.tenant_key(tenant)?;
+        let plaintext = serde_json::to_vec(value)?;
+        let sealed = encrypt(&data_key, context.as_bytes(), &plaintext)?;
+        Ok(serde_json::Value::String(format!(
+            "{}{}:{}",
+            SEALED_PREFIX,
+            key_id,
+            general_purpose::STANDARD.encode(sealed)
+        )))
+    }
+
+    /// Decrypts a value produced by `seal` with the same `context`.
+    pub fn open(&self, context: &str, value: &serde_json::Value) -> Result<serde_json::Value> {
+        let sealed = value
+            .as_str()
+            .and_then(|s| s.strip_prefix(SEALED_PREFIX))
+            .ok_or_else(|| anyhow::anyhow!("{} is not an encrypted field", context))?;
+        let (key_id, ciphertext) = sealed
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("{} is malformed", context))?;
+        let key_id: u64 = key_id
+            .parse()
+            .map_err(|_| anyhow::anyhow!("{} has an invalid data key ID", context))?;
+
+        let data_key = {
+            let state = self
+                .state
+                .lock()
+                .map_err(|_| anyhow::anyhow!("Data key state is poisoned"))?;
+            *state
+                .unwrapped
+                .get(&key_id)
+                .ok_or_else(|| anyhow::anyhow!("Data key {} for {} is missing", key_id, context))?
+        };
+
+        let plaintext = decrypt(
+            &data_key,
+            context.as_bytes(),
+            &general_purpose::STANDARD.decode(ciphertext)?,
+        )
+        .map_err(|e| anyhow::anyhow!("{}: {}", context, e))?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+}
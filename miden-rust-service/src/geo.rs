@@ -0,0 +1,399 @@
+// src/geo.rs
+//
+// Property locations and map queries
+//
+// A property's location is part of its record: a point (WGS84 latitude and
+// longitude) and optionally the parcel outline as a GeoJSON Polygon or
+// MultiPolygon ([longitude, latitude] positions, closed rings). Owners set it
+// with PUT /properties/:property_id/location.
+//
+// Map queries answer with GeoJSON FeatureCollections, one Feature per property
+// whose geometry is the parcel when known and the point otherwise:
+// - GET /properties/nearby?lat=&lon=&radius=: properties within `radius`
+//   meters of a point (great-circle distance), nearest first, with
+//   `distance_m` in each Feature's properties
+// - GET /properties/within?bbox=min_lon,min_lat,max_lon,max_lat: properties
+//   whose point lies in the box (min_lon > max_lon crosses the antimeridian)
+// Both take an optional property_type and limit. Confidential listings are
+// placed on the map without their price. GET /search filters by bounding box
+// too (search.rs).
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{records::PropertyRecord, MidenClientWrapper};
+
+/// Mean Earth radius (IUGG)
+pub const EARTH_RADIUS_M: f64 = 6_371_008.8;
+pub const MAX_NEARBY_RADIUS_M: f64 = 100_000.0;
+pub const DEFAULT_MAP_LIMIT: usize = 200;
+pub const MAX_MAP_LIMIT: usize = 1000;
+/// Positions per parcel outline, over all rings
+pub const MAX_PARCEL_POSITIONS: usize = 10_000;
+
+/// GeoJSON geometry (RFC 7946); positions are [longitude, latitude].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Geometry {
+    Point {
+        coordinates: [f64; 2],
+    },
+    Polygon {
+        coordinates: Vec<Vec<[f64; 2]>>,
+    },
+    MultiPolygon {
+        coordinates: Vec<Vec<Vec<[f64; 2]>>>,
+    },
+}
+
+impl Geometry {
+    /// Rings of a Polygon or MultiPolygon; None for other geometries.
+    pub fn rings(&self) -> Option<Vec<&Vec<[f64; 2]>>> {
+        match self {
+            Geometry::Point { .. } => None,
+            Geometry::Polygon { coordinates } => Some(coordinates.iter().collect()),
+            Geometry::MultiPolygon { coordinates } => Some(coordinates.iter().flatten().collect()),
+        }
+    }
+}
+
+pub fn valid_position([lon, lat]: [f64; 2]) -> bool {
+    (-180.0..=180.0).contains(&lon) && (-90.0..=90.0).contains(&lat)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LocationInput {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Polygon or MultiPolygon
+    pub parcel: Option<Geometry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropertyLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub parcel: Option<Geometry>,
+    pub updated_at: i64,
+}
+
+impl PropertyLocation {
+    /// The parcel when known, the point otherwise.
+    pub fn geometry(&self) -> Geometry {
+        self.parcel.clone().unwrap_or(Geometry::Point {
+            coordinates: [self.longitude, self.latitude],
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min_lon: f64,
+    pub min_lat: f64,
+    pub max_lon: f64,
+    pub max_lat: f64,
+}
+
+impl BoundingBox {
+    pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        let in_lon = if self.min_lon <= self.max_lon {
+            (self.min_lon..=self.max_lon).contains(&longitude)
+        } else {
+            longitude >= self.min_lon || longitude <= self.max_lon
+        };
+        in_lon && (self.min_lat..=self.max_lat).contains(&latitude)
+    }
+}
+
+impl std::str::FromStr for BoundingBox {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let values = s
+            .split(',')
+            .map(|v| v.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| anyhow::anyhow!("bbox must be four comma-separated numbers"))?;
+        let [min_lon, min_lat, max_lon, max_lat] = values[..] else {
+            return Err(anyhow::anyhow!(
+                "bbox must be min_lon,min_lat,max_lon,max_lat"
+            ));
+        };
+        if !valid_position([min_lon, min_lat]) || !valid_position([max_lon, max_lat]) {
+            return Err(anyhow::anyhow!("bbox coordinates are out of range"));
+        }
+        if min_lat > max_lat {
+            return Err(anyhow::anyhow!("bbox min_lat must not exceed max_lat"));
+        }
+        Ok(BoundingBox {
+            min_lon,
+            min_lat,
+            max_lon,
+            max_lat,
+        })
+    }
+}
+
+/// Great-circle distance in meters (haversine).
+pub fn distance_m(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let d_phi = (lat2 - lat1).to_radians();
+    let d_lambda = (lon2 - lon1).to_radians();
+    let a = (d_phi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (d_lambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_M * a.sqrt().min(1.0).asin()
+}
+
+/// Query string of GET /properties/nearby.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NearbyQuery {
+    pub lat: f64,
+    pub lon: f64,
+    /// Meters
+    pub radius: f64,
+    pub property_type: Option<u8>,
+    pub limit: Option<usize>,
+}
+
+/// Query string of GET /properties/within.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WithinQuery {
+    pub bbox: String,
+    pub property_type: Option<u8>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureProperties {
+    pub property_id: String,
+    pub property_type: u8,
+    pub confidential: bool,
+    pub price: Option<u64>,
+    pub price_currency: Option<String>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub distance_m: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Feature {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub id: String,
+    pub geometry: Geometry,
+    pub properties: FeatureProperties,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureCollection {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub features: Vec<Feature>,
+    /// Matching properties beyond `limit` were left out
+    pub truncated: bool,
+}
+
+impl FeatureCollection {
+    fn new(mut features: Vec<Feature>, limit: Option<usize>) -> Self {
+        let limit = limit.unwrap_or(DEFAULT_MAP_LIMIT).clamp(1, MAX_MAP_LIMIT);
+        let truncated = features.len() > limit;
+        features.truncate(limit);
+        FeatureCollection {
+            kind: "FeatureCollection",
+            features,
+            truncated,
+        }
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    SetPropertyLocation {
+        property_id: String,
+        input: LocationInput,
+        api_key: Option<String>,
+    } -> PropertyLocation;
+    |client, op| client.set_property_location(&op.property_id, op.input, op.api_key.as_deref());
+
+    PropertiesNearby {
+        query: NearbyQuery,
+    } -> FeatureCollection;
+    |client, op| Ok(client.properties_nearby(&op.query));
+
+    PropertiesWithin {
+        query: WithinQuery,
+    } -> FeatureCollection;
+    |client, op| client.properties_within(&op.query)
+}
+
+impl MidenClientWrapper {
+    fn feature(&self, property: &PropertyRecord, distance_m: Option<f64>) -> Option<Feature> {
+        let location = property.location.as_ref()?;
+        let confidential = self
+            .confidential_listings
+            .is_confidential(&property.property_id);
+        Some(Feature {
+            kind: "Feature",
+            id: property.property_id.clone(),
+            geometry: location.geometry(),
+            properties: FeatureProperties {
+                property_id: property.property_id.clone(),
+                property_type: property.property_type,
+                confidential,
+                price: (!confidential).then_some(property.price),
+                price_currency: (!confidential).then(|| self.price_currency(property).to_string()),
+                latitude: location.latitude,
+                longitude: location.longitude,
+                distance_m,
+            },
+        })
+    }
+
+    /// Sets the location of a property. Needs an API key bound to the owner.
+    pub fn set_property_location(
+        &mut self,
+        property_id: &str,
+        input: LocationInput,
+        api_key: Option<&str>,
+    ) -> Result<PropertyLocation> {
+        let owner_hex = self
+            .records
+            .properties
+            .get(property_id)
+            .map(|p| p.owner_account_id.to_lowercase())
+            .ok_or_else(|| anyhow::anyhow!("Property {} not found", property_id))?;
+        self.authorize_account(api_key, &owner_hex, "owner")?;
+
+        let location = PropertyLocation {
+            latitude: input.latitude,
+            longitude: input.longitude,
+            parcel: input.parcel,
+            updated_at: chrono::Utc::now().timestamp(),
+        };
+        self.records.record_location(property_id, location.clone());
+        self.index_property(property_id);
+        tracing::info!(
+            "Located {} at {}, {}",
+            property_id,
+            location.latitude,
+            location.longitude
+        );
+        Ok(location)
+    }
+
+    /// Properties within `radius` meters of a point, nearest first.
+    pub fn properties_nearby(&self, query: &NearbyQuery) -> FeatureCollection {
+        let mut features: Vec<Feature> = self
+            .records
+            .properties
+            .values()
+            .filter(|p| query.property_type.is_none_or(|t| p.property_type == t))
+            .filter_map(|p| {
+                let location = p.location.as_ref()?;
+                let distance =
+                    distance_m(query.lat, query.lon, location.latitude, location.longitude);
+                (distance <= query.radius)
+                    .then(|| self.feature(p, Some(distance)))
+                    .flatten()
+            })
+            .collect();
+        features.sort_by(|a, b| {
+            a.properties
+                .distance_m
+                .partial_cmp(&b.properties.distance_m)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        FeatureCollection::new(features, query.limit)
+    }
+
+    /// Properties located in a bounding box.
+    pub fn properties_within(&self, query: &WithinQuery) -> Result<FeatureCollection> {
+        let bbox: BoundingBox = query.bbox.parse()?;
+        let features = self
+            .records
+            .properties
+            .values()
+            .filter(|p| query.property_type.is_none_or(|t| p.property_type == t))
+            .filter(|p| {
+                p.location
+                    .as_ref()
+                    .is_some_and(|l| bbox.contains(l.latitude, l.longitude))
+            })
+            .filter_map(|p| self.feature(p, None))
+            .collect();
+        Ok(FeatureCollection::new(features, query.limit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Length of one degree of a great circle
+    const DEGREE_M: f64 = EARTH_RADIUS_M * std::f64::consts::PI / 180.0;
+
+    fn close(actual: f64, expected: f64) -> bool {
+        (actual - expected).abs() < 1e-6 * expected.max(1.0)
+    }
+
+    #[test]
+    fn distances_follow_the_great_circle() {
+        assert_eq!(distance_m(52.37, 4.89, 52.37, 4.89), 0.0);
+        assert!(close(distance_m(0.0, 0.0, 1.0, 0.0), DEGREE_M));
+        assert!(close(distance_m(0.0, 0.0, 0.0, 1.0), DEGREE_M));
+        // A degree of longitude shrinks with the cosine of the latitude
+        assert!(close(
+            distance_m(60.0, 10.0, 60.0, 11.0),
+            2.0 * EARTH_RADIUS_M * (60f64.to_radians().cos() * 0.5f64.to_radians().sin()).asin()
+        ));
+        let (a, b) = (
+            distance_m(48.85, 2.35, 51.51, -0.13),
+            distance_m(51.51, -0.13, 48.85, 2.35),
+        );
+        assert_eq!(a, b);
+        assert!((340_000.0..350_000.0).contains(&a));
+    }
+
+    #[test]
+    fn distances_stay_finite_at_the_antipode_and_across_the_antimeridian() {
+        let half_circumference = EARTH_RADIUS_M * std::f64::consts::PI;
+        assert!(close(distance_m(0.0, 0.0, 0.0, 180.0), half_circumference));
+        assert!(close(distance_m(90.0, 0.0, -90.0, 0.0), half_circumference));
+        assert!(close(distance_m(0.0, 179.5, 0.0, -179.5), DEGREE_M));
+    }
+
+    #[test]
+    fn boxes_parse_and_contain_their_edges() {
+        let bbox: BoundingBox = "4.7, 52.2, 5.1, 52.5".parse().unwrap();
+        assert!(bbox.contains(52.37, 4.89));
+        assert!(bbox.contains(52.2, 4.7));
+        assert!(bbox.contains(52.5, 5.1));
+        assert!(!bbox.contains(52.51, 4.89));
+        assert!(!bbox.contains(52.37, 5.11));
+    }
+
+    #[test]
+    fn boxes_may_cross_the_antimeridian() {
+        let bbox: BoundingBox = "170,-20,-170,-10".parse().unwrap();
+        assert!(bbox.contains(-15.0, 175.0));
+        assert!(bbox.contains(-15.0, -175.0));
+        assert!(bbox.contains(-15.0, 180.0));
+        assert!(!bbox.contains(-15.0, 0.0));
+    }
+
+    #[test]
+    fn malformed_boxes_are_refused() {
+        for bbox in [
+            "1,2,3",
+            "1,2,3,4,5",
+            "a,2,3,4",
+            "0,0,181,10",
+            "0,-91,10,10",
+            "0,20,10,10",
+        ] {
+            assert!(bbox.parse::<BoundingBox>().is_err(), "{}", bbox);
+        }
+    }
+}
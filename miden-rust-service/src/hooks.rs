@@ -0,0 +1,593 @@
+// src/hooks.rs
+//
+// Lifecycle hooks (plugins)
+//
+// Deployers add business rules without forking the service by registering
+// hooks (POST /admin/hooks) that run at defined points:
+// - before_escrow_release: before any escrow release is submitted, including
+//   those the service performs itself (installments, sagas, retries). May veto
+//   the release; metadata is kept on the escrow record.
+// - after_mint: once a property is minted and recorded. Metadata is kept on
+//   the property record; a veto can no longer stop anything and is only logged.
+// - proof_verified: after a ZK proof verification. A veto turns the proof
+//   invalid; metadata is returned with the result under "hook_metadata".
+//
+// A hook is a webhook or a WASM module:
+// - webhook: the event is POSTed as JSON, signed like subscription webhooks
+//   (X-Obscura-Signature: "sha256=" and the hex HMAC-SHA256 of the body under
+//   the hook's secret, returned once, on creation)
+// - wasm (cargo feature "wasm-hooks"): a module file in HOOKS_WASM_DIR with no
+//   imports, exporting `memory`, `alloc(len: i32) -> i32` and
+//   `hook(ptr: i32, len: i32) -> i64`. The event is written to a buffer from
+//   `alloc`; `hook` returns where its reply is, as (ptr << 32) | len. Each call
+//   runs on a fresh instance with HOOK_WASM_FUEL fuel. Modules are compiled
+//   when registered and again on first use after a restart; to change one,
+//   register it under a new file name.
+// Both receive {"hook_id", "point", "subject", "payload", "metadata",
+// "sent_at"}, where metadata is what earlier hooks added, and answer
+// {"allow": false, "reason": "...", "metadata": {...}}; every field is optional
+// and an empty reply lets the operation through.
+//
+// The hooks of a point run in registration order; the first veto stops the
+// operation with "Vetoed: ..." (409). A hook that fails or takes longer than
+// HOOK_TIMEOUT_MS lets the operation through when registered with fail_open,
+// and otherwise fails it as unavailable — a release is then retried by the
+// retry queue (retry_queue.rs). Hooks run on the client queue, so every other
+// request waits for them: keep them fast.
+//
+// Webhook hooks are skipped while the webhooks feature is off
+// (feature_flags.rs).
+//
+// Managing hooks needs an admin API key.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    feature_flags::FeatureFlag,
+    subscriptions::{sign_payload, SIGNATURE_HEADER},
+    MidenClientWrapper,
+};
+
+/// Largest reply read from a hook
+const MAX_REPLY_BYTES: usize = 64 << 10;
+
+#[cfg(not(feature = "wasm-hooks"))]
+const WASM_UNAVAILABLE: &str = "WASM hooks need a build with the wasm-hooks feature";
+
+/// Fields hooks add to a record or result.
+pub type HookMetadata = serde_json::Map<String, serde_json::Value>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookPoint {
+    BeforeEscrowRelease,
+    AfterMint,
+    ProofVerified,
+}
+
+impl HookPoint {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookPoint::BeforeEscrowRelease => "before_escrow_release",
+            HookPoint::AfterMint => "after_mint",
+            HookPoint::ProofVerified => "proof_verified",
+        }
+    }
+
+    /// Whether the operation can still be stopped when the hooks run.
+    fn can_veto(&self) -> bool {
+        !matches!(self, HookPoint::AfterMint)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HookTarget {
+    Webhook {
+        url: String,
+    },
+    /// File name of a module in HOOKS_WASM_DIR
+    Wasm {
+        module: String,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HookInput {
+    pub name: String,
+    pub point: HookPoint,
+    pub target: HookTarget,
+    /// Let the operation through when the hook fails or times out
+    #[serde(default)]
+    pub fail_open: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hook {
+    pub hook_id: u64,
+    pub name: String,
+    pub point: HookPoint,
+    pub target: HookTarget,
+    pub fail_open: bool,
+    /// Webhook signing key; blank outside the store
+    pub secret: String,
+    pub created_at: i64,
+}
+
+impl Hook {
+    /// Copy safe to return from the API (secret blanked).
+    pub fn public(&self) -> Hook {
+        Hook {
+            secret: String::new(),
+            ..self.clone()
+        }
+    }
+}
+
+/// What a hook is sent.
+#[derive(Debug, Serialize)]
+struct HookEvent<'a> {
+    hook_id: u64,
+    point: HookPoint,
+    /// Escrow account ID, property ID or proof program ID
+    subject: &'a str,
+    payload: &'a serde_json::Value,
+    /// Added by the hooks that ran before
+    metadata: &'a HookMetadata,
+    sent_at: i64,
+}
+
+/// What a hook answers; an empty reply lets the operation through.
+#[derive(Debug, Default, Deserialize)]
+struct HookReply {
+    allow: Option<bool>,
+    reason: Option<String>,
+    #[serde(default)]
+    metadata: HookMetadata,
+}
+
+impl HookReply {
+    fn parse(raw: &[u8]) -> Result<Self> {
+        if raw.iter().all(u8::is_ascii_whitespace) {
+            return Ok(HookReply::default());
+        }
+        serde_json::from_slice(raw).map_err(|e| anyhow::anyhow!("Invalid hook reply: {}", e))
+    }
+}
+
+/// What the hooks of a point decided.
+#[derive(Debug, Default)]
+pub struct HookOutcome {
+    pub metadata: HookMetadata,
+    /// Why the operation was refused
+    pub veto: Option<String>,
+}
+
+impl HookOutcome {
+    /// The metadata, or the veto as a "Vetoed:" error.
+    pub fn allowed(self) -> Result<HookMetadata> {
+        match self.veto {
+            Some(veto) => Err(anyhow::anyhow!("Vetoed: {}", veto)),
+            None => Ok(self.metadata),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HookStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(skip)]
+    http: reqwest::Client,
+    #[cfg(feature = "wasm-hooks")]
+    #[serde(skip)]
+    wasm: wasm::WasmModules,
+    #[serde(default)]
+    hooks: BTreeMap<u64, Hook>,
+    #[serde(default)]
+    next_hook_id: u64,
+}
+
+impl HookStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<HookStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            HookStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Registers a validated hook; returns it with its secret. A WASM module
+    /// must exist in `wasm_dir` and compile.
+    pub fn create(&mut self, input: HookInput, wasm_dir: &Path) -> Result<Hook> {
+        if let HookTarget::Wasm { module } = &input.target {
+            self.load_wasm(wasm_dir, module)?;
+        }
+
+        self.next_hook_id += 1;
+        let hook = Hook {
+            hook_id: self.next_hook_id,
+            name: input.name.trim().to_string(),
+            point: input.point,
+            target: input.target,
+            fail_open: input.fail_open,
+            secret: hex::encode(rand::random::<[u8; 32]>()),
+            created_at: chrono::Utc::now().timestamp(),
+        };
+
+        self.hooks.insert(hook.hook_id, hook.clone());
+        self.save()?;
+        Ok(hook)
+    }
+
+    pub fn list(&self) -> Vec<Hook> {
+        self.hooks.values().map(Hook::public).collect()
+    }
+
+    pub fn remove(&mut self, hook_id: u64) -> Result<Hook> {
+        let hook = self
+            .hooks
+            .remove(&hook_id)
+            .ok_or_else(|| anyhow::anyhow!("Hook {} not found", hook_id))?;
+        self.save()?;
+        Ok(hook)
+    }
+
+    /// Hooks of a point, in registration order.
+    fn at(&self, point: HookPoint) -> Vec<Hook> {
+        self.hooks
+            .values()
+            .filter(|h| h.point == point)
+            .cloned()
+            .collect()
+    }
+
+    #[cfg(feature = "wasm-hooks")]
+    fn load_wasm(&mut self, wasm_dir: &Path, module: &str) -> Result<()> {
+        self.wasm.module(&module_path(wasm_dir, module)?).map(drop)
+    }
+
+    #[cfg(feature = "wasm-hooks")]
+    fn run_wasm(
+        &mut self,
+        wasm_dir: &Path,
+        module: &str,
+        event: &[u8],
+        fuel: u64,
+    ) -> Result<Vec<u8>> {
+        let module = self.wasm.module(&module_path(wasm_dir, module)?)?;
+        wasm::call(self.wasm.engine(), &module, event, fuel)
+    }
+
+    #[cfg(not(feature = "wasm-hooks"))]
+    fn load_wasm(&mut self, _wasm_dir: &Path, _module: &str) -> Result<()> {
+        Err(anyhow::anyhow!(WASM_UNAVAILABLE))
+    }
+
+    #[cfg(not(feature = "wasm-hooks"))]
+    fn run_wasm(&mut self, _: &Path, _: &str, _: &[u8], _: u64) -> Result<Vec<u8>> {
+        Err(anyhow::anyhow!(WASM_UNAVAILABLE))
+    }
+
+    async fn call(
+        &mut self,
+        hook: &Hook,
+        event: &HookEvent<'_>,
+        timeout: Duration,
+        wasm_dir: &Path,
+        fuel: u64,
+    ) -> Result<HookReply> {
+        let body = serde_json::to_vec(event)?;
+        match &hook.target {
+            HookTarget::Webhook { url } => {
+                let response = self
+                    .http
+                    .post(url)
+                    .timeout(timeout)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .header(SIGNATURE_HEADER, sign_payload(&hook.secret, &body))
+                    .body(body)
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                let reply = response.bytes().await?;
+                if reply.len() > MAX_REPLY_BYTES {
+                    return Err(anyhow::anyhow!(
+                        "Hook reply is larger than {} bytes",
+                        MAX_REPLY_BYTES
+                    ));
+                }
+                HookReply::parse(&reply)
+            }
+            HookTarget::Wasm { module } => {
+                HookReply::parse(&self.run_wasm(wasm_dir, module, &body, fuel)?)
+            }
+        }
+    }
+}
+
+/// A module's path; module names are plain file names inside the directory.
+#[cfg(feature = "wasm-hooks")]
+fn module_path(wasm_dir: &Path, module: &str) -> Result<PathBuf> {
+    let plain = Path::new(module)
+        .file_name()
+        .is_some_and(|name| name == module);
+    if !plain || module.starts_with('.') {
+        return Err(anyhow::anyhow!(
+            "WASM module must be a file name: {}",
+            module
+        ));
+    }
+    Ok(wasm_dir.join(module))
+}
+
+#[cfg(feature = "wasm-hooks")]
+mod wasm {
+    use anyhow::Result;
+    use std::{collections::HashMap, path::Path};
+    use wasmtime::{Config, Engine, Instance, Module, Store};
+
+    /// The engine hooks run on and the modules compiled so far.
+    pub struct WasmModules {
+        engine: Engine,
+        modules: HashMap<String, Module>,
+    }
+
+    impl Default for WasmModules {
+        fn default() -> Self {
+            let mut config = Config::new();
+            config.consume_fuel(true);
+            Self {
+                engine: Engine::new(&config).expect("fuel metering is always available"),
+                modules: HashMap::new(),
+            }
+        }
+    }
+
+    impl std::fmt::Debug for WasmModules {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("WasmModules")
+                .field("modules", &self.modules.keys().collect::<Vec<_>>())
+                .finish()
+        }
+    }
+
+    impl WasmModules {
+        pub fn engine(&self) -> &Engine {
+            &self.engine
+        }
+
+        pub fn module(&mut self, path: &Path) -> Result<Module> {
+            let key = path.display().to_string();
+            if let Some(module) = self.modules.get(&key) {
+                return Ok(module.clone());
+            }
+            let module = Module::from_file(&self.engine, path)
+                .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", key, e))?;
+            self.modules.insert(key, module.clone());
+            Ok(module)
+        }
+    }
+
+    /// Runs a module's `hook` export on `event` (see module docs).
+    pub fn call(engine: &Engine, module: &Module, event: &[u8], fuel: u64) -> Result<Vec<u8>> {
+        let mut store = Store::new(engine, ());
+        store.set_fuel(fuel)?;
+        let instance = Instance::new(&mut store, module, &[])?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow::anyhow!("WASM hook does not export its memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        let hook = instance.get_typed_func::<(i32, i32), i64>(&mut store, "hook")?;
+
+        let len = i32::try_from(event.len())?;
+        let ptr = alloc.call(&mut store, len)?;
+        memory.write(&mut store, ptr as u32 as usize, event)?;
+
+        let reply = hook.call(&mut store, (ptr, len))? as u64;
+        let (reply_ptr, reply_len) = ((reply >> 32) as usize, (reply & 0xffff_ffff) as usize);
+        if reply_len > super::MAX_REPLY_BYTES {
+            return Err(anyhow::anyhow!(
+                "Hook reply is larger than {} bytes",
+                super::MAX_REPLY_BYTES
+            ));
+        }
+        let mut buffer = vec![0; reply_len];
+        memory.read(&store, reply_ptr, &mut buffer)?;
+        Ok(buffer)
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    CreateHook {
+        input: HookInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.create_hook(op.input, op.api_key.as_deref());
+
+    ListHooks {
+        api_key: Option<String>,
+    } -> Vec<Hook>;
+    |client, op| client.list_hooks(op.api_key.as_deref());
+
+    DeleteHook {
+        hook_id: u64,
+        api_key: Option<String>,
+    } -> Hook;
+    |client, op| client.delete_hook(op.hook_id, op.api_key.as_deref())
+}
+
+impl MidenClientWrapper {
+    /// Runs the hooks registered at `point` on an operation (see module docs).
+    pub(crate) async fn run_hooks(
+        &mut self,
+        point: HookPoint,
+        subject: &str,
+        payload: serde_json::Value,
+    ) -> Result<HookOutcome> {
+        let mut outcome = HookOutcome::default();
+        for hook in self.hooks.at(point) {
+            if matches!(hook.target, HookTarget::Webhook { .. })
+                && !self.features.is_enabled(FeatureFlag::Webhooks)
+            {
+                tracing::info!(
+                    "Hook {} ({}) skipped on {} {}: webhooks are turned off",
+                    hook.hook_id,
+                    hook.name,
+                    point.as_str(),
+                    subject
+                );
+                continue;
+            }
+            let event = HookEvent {
+                hook_id: hook.hook_id,
+                point,
+                subject,
+                payload: &payload,
+                metadata: &outcome.metadata,
+                sent_at: chrono::Utc::now().timestamp(),
+            };
+            let result = self
+                .hooks
+                .call(
+                    &hook,
+                    &event,
+                    self.config.hook_timeout,
+                    &self.config.hooks_wasm_dir,
+                    self.config.hook_wasm_fuel,
+                )
+                .await;
+
+            let reply = match result {
+                Ok(reply) => reply,
+                Err(e) if hook.fail_open || !point.can_veto() => {
+                    tracing::warn!(
+                        "⚠️  Hook {} ({}) failed on {} {}; going ahead: {}",
+                        hook.hook_id,
+                        hook.name,
+                        point.as_str(),
+                        subject,
+                        e
+                    );
+                    continue;
+                }
+                Err(e) => {
+                    return Err(anyhow::anyhow!(
+                        "Hook {} ({}) unavailable for {} {}: {}",
+                        hook.hook_id,
+                        hook.name,
+                        point.as_str(),
+                        subject,
+                        e
+                    ))
+                }
+            };
+
+            outcome.metadata.extend(reply.metadata);
+            if reply.allow == Some(false) {
+                let veto = format!(
+                    "hook {} ({}) refused {} {}: {}",
+                    hook.hook_id,
+                    hook.name,
+                    point.as_str(),
+                    subject,
+                    reply.reason.as_deref().unwrap_or("no reason given")
+                );
+                if point.can_veto() {
+                    tracing::warn!("🚫 {}", veto);
+                    outcome.veto = Some(veto);
+                    break;
+                }
+                tracing::warn!("⚠️  {} (too late to stop it)", veto);
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// Runs the proof_verified hooks on a verification result: a veto makes
+    /// the proof invalid; metadata comes back under "hook_metadata".
+    pub(crate) async fn proof_verified_hooks(
+        &mut self,
+        proof_kind: &str,
+        public_inputs: serde_json::Value,
+        mut result: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let subject = result["program_id"]
+            .as_str()
+            .unwrap_or(proof_kind)
+            .to_string();
+        let payload = serde_json::json!({
+            "proof_kind": proof_kind,
+            "public_inputs": public_inputs,
+            "result": result.clone(),
+        });
+        let outcome = self
+            .run_hooks(HookPoint::ProofVerified, &subject, payload)
+            .await?;
+
+        if let Some(veto) = outcome.veto {
+            result["valid"] = serde_json::json!(false);
+            result["message"] = serde_json::json!(format!("Vetoed: {}", veto));
+        }
+        if !outcome.metadata.is_empty() {
+            result["hook_metadata"] = serde_json::Value::Object(outcome.metadata);
+        }
+        Ok(result)
+    }
+
+    pub fn list_hooks(&self, api_key: Option<&str>) -> Result<Vec<Hook>> {
+        self.admin_principal(api_key, "lifecycle hooks")?;
+        Ok(self.hooks.list())
+    }
+
+    /// Registers a hook; its webhook secret is only returned here.
+    pub fn create_hook(
+        &mut self,
+        input: HookInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        self.admin_principal(api_key, "lifecycle hooks")?;
+        let hook = self.hooks.create(input, &self.config.hooks_wasm_dir)?;
+        tracing::info!(
+            "Registered hook {} ({}) at {}",
+            hook.hook_id,
+            hook.name,
+            hook.point.as_str()
+        );
+        Ok(serde_json::json!({
+            "hook": hook.public(),
+            "secret": hook.secret,
+        }))
+    }
+
+    pub fn delete_hook(&mut self, hook_id: u64, api_key: Option<&str>) -> Result<Hook> {
+        self.admin_principal(api_key, "lifecycle hooks")?;
+        let hook = self.hooks.remove(hook_id)?;
+        tracing::info!("Removed hook {} ({})", hook.hook_id, hook.name);
+        Ok(hook.public())
+    }
+}
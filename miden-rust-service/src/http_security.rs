@@ -0,0 +1,233 @@
+// src/http_security.rs
+//
+// CORS and security headers
+//
+// CORS is configured per deployment (each profile/environment has its own
+// .env):
+// - CORS_ALLOWED_ORIGINS: origins allowed to call the API, e.g.
+//   "https://app.obscura.example,https://admin.obscura.example"
+// - CORS_ALLOWED_METHODS, CORS_ALLOWED_HEADERS, CORS_EXPOSE_HEADERS: method
+//   and header names
+// - CORS_ALLOW_CREDENTIALS: lets browsers send cookies and TLS client
+//   certificates; requires explicit lists everywhere (no "*")
+// - CORS_MAX_AGE_SECS: how long browsers may cache a preflight answer
+// Unset or "*" allows anything, which is what the service did before these
+// settings existed; an open origin list outside localnet is logged at startup.
+//
+// Every response also gets the standard security headers, unless a handler
+// set them itself or SECURITY_HEADERS=false:
+// - Strict-Transport-Security: max-age=HSTS_MAX_AGE_SECS (one year by default
+//   when the service terminates TLS, otherwise off; set it behind a TLS proxy)
+// - X-Frame-Options: FRAME_OPTIONS (DENY or SAMEORIGIN)
+// - X-Content-Type-Options: nosniff
+// - Referrer-Policy: no-referrer
+
+use anyhow::Result;
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue, Method},
+    middleware::Next,
+    response::Response,
+};
+use std::{str::FromStr, sync::Arc, time::Duration};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer, ExposeHeaders};
+
+/// A CORS list: anything, or only the values listed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Allowed<T> {
+    Any,
+    Only(Vec<T>),
+}
+
+impl<T: FromStr> Allowed<T>
+where
+    T::Err: std::fmt::Display,
+{
+    /// Parses a comma-separated list; empty or "*" is `Any`.
+    fn parse(raw: &str, what: &str) -> Result<Self> {
+        let raw = raw.trim();
+        if raw.is_empty() || raw == "*" {
+            return Ok(Allowed::Any);
+        }
+        raw.split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| {
+                v.parse::<T>()
+                    .map_err(|e| anyhow::anyhow!("Invalid {} '{}': {}", what, v, e))
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Allowed::Only)
+    }
+}
+
+impl<T> Allowed<T> {
+    pub fn is_any(&self) -> bool {
+        matches!(self, Allowed::Any)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CorsPolicy {
+    pub allowed_origins: Allowed<HeaderValue>,
+    pub allowed_methods: Allowed<Method>,
+    pub allowed_headers: Allowed<HeaderName>,
+    pub expose_headers: Allowed<HeaderName>,
+    pub allow_credentials: bool,
+    pub max_age: Option<Duration>,
+}
+
+impl CorsPolicy {
+    pub fn parse(
+        origins: &str,
+        methods: &str,
+        headers: &str,
+        expose_headers: &str,
+        allow_credentials: bool,
+        max_age: Option<Duration>,
+    ) -> Result<Self> {
+        let policy = Self {
+            allowed_origins: Allowed::parse(origins, "origin")?,
+            allowed_methods: Allowed::parse(methods, "method")?,
+            allowed_headers: Allowed::parse(headers, "header name")?,
+            expose_headers: Allowed::parse(expose_headers, "header name")?,
+            allow_credentials,
+            max_age,
+        };
+        if let Allowed::Only(origins) = &policy.allowed_origins {
+            if let Some(origin) = origins.iter().find(|o| !is_origin(o)) {
+                return Err(anyhow::anyhow!(
+                    "CORS origin must be scheme://host[:port], got {:?}",
+                    origin
+                ));
+            }
+        }
+        // Browsers refuse wildcards on credentialed requests
+        if allow_credentials
+            && (policy.allowed_origins.is_any()
+                || policy.allowed_methods.is_any()
+                || policy.allowed_headers.is_any()
+                || policy.expose_headers.is_any())
+        {
+            return Err(anyhow::anyhow!(
+                "CORS_ALLOW_CREDENTIALS requires explicit origins, methods, allowed and \
+                 exposed headers"
+            ));
+        }
+        Ok(policy)
+    }
+
+    pub fn layer(&self) -> CorsLayer {
+        let mut layer = CorsLayer::new()
+            .allow_origin(match &self.allowed_origins {
+                Allowed::Any => AllowOrigin::any(),
+                Allowed::Only(origins) => AllowOrigin::list(origins.clone()),
+            })
+            .allow_methods(match &self.allowed_methods {
+                Allowed::Any => AllowMethods::any(),
+                Allowed::Only(methods) => AllowMethods::list(methods.clone()),
+            })
+            .allow_headers(match &self.allowed_headers {
+                Allowed::Any => AllowHeaders::any(),
+                Allowed::Only(headers) => AllowHeaders::list(headers.clone()),
+            })
+            .expose_headers(match &self.expose_headers {
+                Allowed::Any => ExposeHeaders::any(),
+                Allowed::Only(headers) => ExposeHeaders::list(headers.clone()),
+            })
+            .allow_credentials(self.allow_credentials);
+        if let Some(max_age) = self.max_age {
+            layer = layer.max_age(max_age);
+        }
+        layer
+    }
+}
+
+fn is_origin(origin: &HeaderValue) -> bool {
+    origin.to_str().is_ok_and(|o| {
+        o.split_once("://").is_some_and(|(scheme, host)| {
+            !scheme.is_empty() && !host.is_empty() && !host.contains('/')
+        })
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameOptions {
+    Deny,
+    SameOrigin,
+}
+
+impl FromStr for FrameOptions {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "DENY" => Ok(FrameOptions::Deny),
+            "SAMEORIGIN" => Ok(FrameOptions::SameOrigin),
+            other => Err(anyhow::anyhow!(
+                "expected DENY or SAMEORIGIN, got {}",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SecurityHeaders {
+    pub enabled: bool,
+    /// Strict-Transport-Security max-age; not sent when None
+    pub hsts_max_age: Option<Duration>,
+    pub hsts_include_subdomains: bool,
+    pub frame_options: FrameOptions,
+}
+
+impl SecurityHeaders {
+    /// The headers every response gets.
+    pub fn headers(&self) -> Vec<(HeaderName, HeaderValue)> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        let mut headers = vec![
+            (
+                header::X_FRAME_OPTIONS,
+                HeaderValue::from_static(match self.frame_options {
+                    FrameOptions::Deny => "DENY",
+                    FrameOptions::SameOrigin => "SAMEORIGIN",
+                }),
+            ),
+            (
+                header::X_CONTENT_TYPE_OPTIONS,
+                HeaderValue::from_static("nosniff"),
+            ),
+            (
+                header::REFERRER_POLICY,
+                HeaderValue::from_static("no-referrer"),
+            ),
+        ];
+        if let Some(max_age) = self.hsts_max_age {
+            let mut value = format!("max-age={}", max_age.as_secs());
+            if self.hsts_include_subdomains {
+                value.push_str("; includeSubDomains");
+            }
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                headers.push((header::STRICT_TRANSPORT_SECURITY, value));
+            }
+        }
+        headers
+    }
+}
+
+/// Adds the security headers a response does not already carry.
+pub async fn apply_security_headers(
+    State(headers): State<Arc<Vec<(HeaderName, HeaderValue)>>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(req).await;
+    for (name, value) in headers.iter() {
+        if !response.headers().contains_key(name) {
+            response.headers_mut().insert(name.clone(), value.clone());
+        }
+    }
+    response
+}
@@ -0,0 +1,302 @@
+// src/identity.rs
+//
+// Identity providers and attribute credentials
+//
+// Investor onboarding needs more than accreditation: buyers must also show they
+// are adults and not resident in a given country. Registered identity providers
+// issue credentials whose attributes (birth date, residency) stay private; the
+// provider signs a commitment to them, and the identity proof family proves a
+// single claim about the committed attributes.
+//
+// Credential commitment (hex):
+//   SHA-256("obscura-identity-credential" || provider_id || birth_date || residency || salt)
+// with each field length-prefixed (u32 LE). The provider signs the 32-byte
+// commitment with its Ed25519 key.
+//
+// Providers are registered and deactivated through /admin/identity-providers,
+// with an admin key.
+
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate};
+use ed25519_dalek::{Signature, Verifier};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{jurisdiction_lists::parse_signer_key, MidenClientWrapper};
+
+/// Public-input code for each claim kind (first public input of the proof).
+pub const CLAIM_MINIMUM_AGE: u64 = 1;
+pub const CLAIM_RESIDENCY_NOT: u64 = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdentityProvider {
+    pub provider_id: u64,
+    pub name: String,
+    /// Ed25519 public key (hex) used to check credential signatures
+    pub public_key: String,
+    pub active: bool,
+    pub registered_at: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderInput {
+    pub name: String,
+    pub public_key: String,
+}
+
+/// Credential as held by the investor. Never stored or logged by the service.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdentityCredential {
+    pub provider_id: u64,
+    /// ISO date, e.g. "1990-04-21"
+    pub birth_date: String,
+    /// ISO country code of residency
+    pub residency: String,
+    /// Random salt chosen by the provider (hex)
+    pub salt: String,
+    /// Provider signature over the commitment (hex)
+    pub signature: String,
+}
+
+/// The statement being proven about a credential.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "claim", rename_all = "snake_case")]
+pub enum AttributeClaim {
+    MinimumAge { years: u32 },
+    ResidencyNot { country: String },
+}
+
+impl AttributeClaim {
+    pub fn code(&self) -> u64 {
+        match self {
+            AttributeClaim::MinimumAge { .. } => CLAIM_MINIMUM_AGE,
+            AttributeClaim::ResidencyNot { .. } => CLAIM_RESIDENCY_NOT,
+        }
+    }
+
+    /// Public value the claim is checked against (years, or encoded country).
+    pub fn public_value(&self) -> Result<u64> {
+        match self {
+            AttributeClaim::MinimumAge { years } => Ok(*years as u64),
+            AttributeClaim::ResidencyNot { country } => encode_country(country),
+        }
+    }
+}
+
+/// Packs a 2-3 letter country code into a u64 (big-endian ASCII bytes).
+pub fn encode_country(code: &str) -> Result<u64> {
+    let code = code.trim().to_uppercase();
+    if !(2..=3).contains(&code.len()) || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(anyhow::anyhow!("Invalid country code: {}", code));
+    }
+    Ok(code.bytes().fold(0u64, |acc, b| (acc << 8) | b as u64))
+}
+
+impl IdentityCredential {
+    pub fn commitment(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"obscura-identity-credential");
+        for field in [
+            self.provider_id.to_string(),
+            self.birth_date.trim().to_string(),
+            self.residency.trim().to_uppercase(),
+            self.salt.trim().to_lowercase(),
+        ] {
+            hasher.update((field.len() as u32).to_le_bytes());
+            hasher.update(field.as_bytes());
+        }
+        hasher.finalize().into()
+    }
+
+    /// Age in whole years on `today`.
+    pub fn age_on(&self, today: NaiveDate) -> Result<u64> {
+        let birth = NaiveDate::parse_from_str(self.birth_date.trim(), "%Y-%m-%d")
+            .map_err(|e| anyhow::anyhow!("Invalid birth date: {}", e))?;
+        if birth > today {
+            return Err(anyhow::anyhow!("Birth date is in the future"));
+        }
+
+        let mut years = today.year() - birth.year();
+        if (today.month(), today.day()) < (birth.month(), birth.day()) {
+            years -= 1;
+        }
+        Ok(years as u64)
+    }
+
+    /// Private attribute value the claim is evaluated on.
+    pub fn attribute_value(&self, claim: &AttributeClaim, today: NaiveDate) -> Result<u64> {
+        match claim {
+            AttributeClaim::MinimumAge { .. } => self.age_on(today),
+            AttributeClaim::ResidencyNot { .. } => encode_country(&self.residency),
+        }
+    }
+}
+
+/// Checks the claim the same way the identity MASM program does.
+pub fn claim_holds(claim: &AttributeClaim, attribute: u64) -> Result<bool> {
+    let value = claim.public_value()?;
+    Ok(match claim {
+        AttributeClaim::MinimumAge { .. } => attribute >= value,
+        AttributeClaim::ResidencyNot { .. } => attribute != value,
+    })
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProviderRegistry {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    providers: BTreeMap<u64, IdentityProvider>,
+    #[serde(default)]
+    next_provider_id: u64,
+}
+
+impl ProviderRegistry {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut registry = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<ProviderRegistry>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            ProviderRegistry::default()
+        };
+        registry.path = path;
+
+        Ok(registry)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<&IdentityProvider> {
+        self.providers.values().collect()
+    }
+
+    pub fn get(&self, provider_id: u64) -> Option<&IdentityProvider> {
+        self.providers.get(&provider_id)
+    }
+
+    pub fn register(&mut self, input: ProviderInput) -> Result<IdentityProvider> {
+        let key = parse_signer_key(&input.public_key)?;
+        let public_key = hex::encode(key.to_bytes());
+
+        if self.providers.values().any(|p| p.public_key == public_key) {
+            return Err(anyhow::anyhow!(
+                "A provider with this public key is already registered"
+            ));
+        }
+
+        self.next_provider_id += 1;
+        let provider = IdentityProvider {
+            provider_id: self.next_provider_id,
+            name: input.name,
+            public_key,
+            active: true,
+            registered_at: chrono::Utc::now().timestamp(),
+        };
+
+        self.providers
+            .insert(provider.provider_id, provider.clone());
+        self.save()?;
+        Ok(provider)
+    }
+
+    /// Deactivates a provider. Its record is kept so old proofs still resolve,
+    /// but they no longer verify.
+    pub fn deactivate(&mut self, provider_id: u64) -> Result<IdentityProvider> {
+        let provider = self
+            .providers
+            .get_mut(&provider_id)
+            .ok_or_else(|| anyhow::anyhow!("Identity provider {} not found", provider_id))?;
+        provider.active = false;
+
+        let provider = provider.clone();
+        self.save()?;
+        Ok(provider)
+    }
+
+    /// Checks that the credential was issued (signed) by an active registered provider.
+    pub fn check_credential(&self, credential: &IdentityCredential) -> Result<&IdentityProvider> {
+        let provider = self
+            .providers
+            .get(&credential.provider_id)
+            .filter(|p| p.active)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Identity provider {} is not registered or inactive",
+                    credential.provider_id
+                )
+            })?;
+
+        let signature: [u8; 64] =
+            hex::decode(credential.signature.trim().trim_start_matches("0x"))?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("Credential signature must be 64 bytes"))?;
+
+        parse_signer_key(&provider.public_key)?
+            .verify(&credential.commitment(), &Signature::from_bytes(&signature))
+            .map_err(|_| anyhow::anyhow!("Credential signature is not valid for provider"))?;
+
+        Ok(provider)
+    }
+}
+
+// ============================================================================
+// CLIENT INTEGRATION
+// ============================================================================
+
+crate::operations! {
+    /// Providers, by ID.
+    ListIdentityProviders {} -> Vec<IdentityProvider>;
+    |client, _op| Ok(client.identity_providers.list().into_iter().cloned().collect());
+
+    RegisterIdentityProvider {
+        input: ProviderInput,
+        api_key: Option<String>,
+    } -> IdentityProvider;
+    |client, op| client.register_identity_provider(op.input, op.api_key.as_deref());
+
+    DeactivateIdentityProvider {
+        provider_id: u64,
+        api_key: Option<String>,
+    } -> IdentityProvider;
+    |client, op| client.deactivate_identity_provider(op.provider_id, op.api_key.as_deref())
+}
+
+impl MidenClientWrapper {
+    /// Registers a provider whose credentials identity proofs accept. Needs
+    /// an admin key.
+    pub fn register_identity_provider(
+        &mut self,
+        input: ProviderInput,
+        api_key: Option<&str>,
+    ) -> Result<IdentityProvider> {
+        self.admin_principal(api_key, "identity providers")?;
+        let provider = self.identity_providers.register(input)?;
+        tracing::info!(
+            "Registered identity provider {} ({})",
+            provider.provider_id,
+            provider.name
+        );
+        Ok(provider)
+    }
+
+    /// Stops accepting a provider's credentials. Needs an admin key.
+    pub fn deactivate_identity_provider(
+        &mut self,
+        provider_id: u64,
+        api_key: Option<&str>,
+    ) -> Result<IdentityProvider> {
+        self.admin_principal(api_key, "identity providers")?;
+        let provider = self.identity_providers.deactivate(provider_id)?;
+        tracing::info!("Deactivated identity provider {}", provider_id);
+        Ok(provider)
+    }
+}
@@ -0,0 +1,713 @@
+// src/installments.rs
+//
+// Installment purchases
+//
+// A buyer commits to N scheduled payments for a property. The plan opens an
+// escrow between buyer and seller; each installment is paid into it as a P2ID
+// note of exactly the installment amount, in the token the listing settles in
+// (settlement_tokens.rs), and the service tracks the principal paid so far.
+//
+// Once the final installment clears, the service checks that the seller's vault
+// still holds the property, releases the escrow to the seller and then
+// transfers the property from the seller to the buyer. If an installment is
+// still unpaid INSTALLMENT_GRACE_SECS after its due date, the plan defaults and
+// the escrowed principal is settled per INSTALLMENT_DEFAULT_POLICY:
+// - refund_buyer: paid installments go back to the buyer
+// - forfeit_to_seller: paid installments are released to the seller
+//
+// The scheduler (scheduler.rs) runs a periodic tick that emits reminder
+// events ahead of due dates, detects defaults and retries settlements that
+// failed part-way. Settlement steps record their tx IDs, so a retry never
+// transfers the title or settles the escrow twice.
+//
+// Payments are escrow fundings and are authorized as such (escrow.rs): the API
+// key must belong to the buyer. Settlement is performed by the service itself.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    account_id_to_hex, allowances::AllowancePurpose, escrow::EscrowStatus, read_cache::Touched,
+    MidenClientWrapper,
+};
+
+/// Upper bound on the number of installments in one plan.
+pub const MAX_INSTALLMENTS: u32 = 360;
+
+/// What happens to escrowed installments when a plan defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefaultPolicy {
+    RefundBuyer,
+    ForfeitToSeller,
+}
+
+impl std::str::FromStr for DefaultPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "refund_buyer" => Ok(DefaultPolicy::RefundBuyer),
+            "forfeit_to_seller" => Ok(DefaultPolicy::ForfeitToSeller),
+            other => Err(anyhow::anyhow!(
+                "Unknown installment default policy: {}",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InstallmentPlanInput {
+    pub property_id: String,
+    /// Account name or hex AccountId
+    pub buyer_account_id: String,
+    /// Account name or hex AccountId; must own the property
+    pub seller_account_id: String,
+    pub total_price: u64,
+    pub installments: u32,
+    /// Seconds between due dates
+    pub interval_secs: u64,
+    /// Unix time of the first due date (defaults to one interval from now)
+    #[serde(default)]
+    pub first_due_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallmentStatus {
+    Scheduled,
+    Paid,
+    Missed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Installment {
+    pub seq: u32,
+    pub amount: u64,
+    pub due_at: i64,
+    pub status: InstallmentStatus,
+    pub reminded: bool,
+    pub paid_at: Option<i64>,
+    pub tx_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanStatus {
+    Active,
+    /// Fully paid; title transfer and escrow release pending
+    Completing,
+    Completed,
+    /// Missed an installment; escrow settlement pending
+    Defaulting,
+    Defaulted,
+}
+
+impl PlanStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, PlanStatus::Completed | PlanStatus::Defaulted)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanEventKind {
+    Created,
+    Reminder,
+    Paid,
+    Missed,
+    TitleTransferred,
+    EscrowReleased,
+    EscrowRefunded,
+    SettlementFailed,
+    Completed,
+    Defaulted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanEvent {
+    pub at: i64,
+    pub kind: PlanEventKind,
+    pub seq: Option<u32>,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallmentPlan {
+    pub plan_id: String,
+    pub property_id: String,
+    pub buyer_account_id: String,
+    pub seller_account_id: String,
+    pub escrow_account_id: String,
+    pub total_price: u64,
+    pub paid_principal: u64,
+    pub status: PlanStatus,
+    pub default_policy: DefaultPolicy,
+    pub installments: Vec<Installment>,
+    pub title_transfer_tx_id: Option<String>,
+    pub settle_tx_id: Option<String>,
+    pub events: Vec<PlanEvent>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl InstallmentPlan {
+    /// The earliest installment not yet paid.
+    pub fn next_due(&self) -> Option<&Installment> {
+        self.installments
+            .iter()
+            .find(|i| i.status != InstallmentStatus::Paid)
+    }
+
+    fn next_due_mut(&mut self) -> Option<&mut Installment> {
+        self.installments
+            .iter_mut()
+            .find(|i| i.status != InstallmentStatus::Paid)
+    }
+
+    fn event(&mut self, kind: PlanEventKind, seq: Option<u32>, detail: impl Into<String>) {
+        let now = chrono::Utc::now().timestamp();
+        let detail = detail.into();
+        tracing::info!("Installment plan {}: {:?} {}", self.plan_id, kind, detail);
+        self.events.push(PlanEvent {
+            at: now,
+            kind,
+            seq,
+            detail,
+        });
+        self.updated_at = now;
+    }
+
+    /// Plan with its schedule and event history.
+    pub fn report(&self) -> serde_json::Value {
+        let mut report = self.summary();
+        if let Some(obj) = report.as_object_mut() {
+            obj.insert("installments".into(), serde_json::json!(self.installments));
+            obj.insert("events".into(), serde_json::json!(self.events));
+        }
+        report
+    }
+
+    /// Progress counters only.
+    pub fn summary(&self) -> serde_json::Value {
+        let paid = self
+            .installments
+            .iter()
+            .filter(|i| i.status == InstallmentStatus::Paid)
+            .count();
+
+        serde_json::json!({
+            "plan_id": self.plan_id,
+            "property_id": self.property_id,
+            "buyer_account_id": self.buyer_account_id,
+            "seller_account_id": self.seller_account_id,
+            "escrow_account_id": self.escrow_account_id,
+            "status": self.status,
+            "default_policy": self.default_policy,
+            "total_price": self.total_price,
+            "paid_principal": self.paid_principal,
+            "remaining_principal": self.total_price.saturating_sub(self.paid_principal),
+            "installments_paid": paid,
+            "installments_total": self.installments.len(),
+            "next_due": self.next_due().map(|i| serde_json::json!({
+                "seq": i.seq,
+                "amount": i.amount,
+                "due_at": i.due_at,
+            })),
+            "title_transfer_tx_id": self.title_transfer_tx_id,
+            "settle_tx_id": self.settle_tx_id,
+            "created_at": self.created_at,
+            "updated_at": self.updated_at,
+        })
+    }
+}
+
+/// Splits `total` into `count` equal installments, the remainder on the last.
+fn schedule(total: u64, count: u32, first_due_at: i64, interval_secs: u64) -> Vec<Installment> {
+    let base = total / count as u64;
+    let remainder = total % count as u64;
+
+    (0..count)
+        .map(|i| Installment {
+            seq: i + 1,
+            amount: if i + 1 == count {
+                base + remainder
+            } else {
+                base
+            },
+            due_at: first_due_at + (i as i64) * interval_secs as i64,
+            status: InstallmentStatus::Scheduled,
+            reminded: false,
+            paid_at: None,
+            tx_id: None,
+        })
+        .collect()
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct InstallmentStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    plans: BTreeMap<String, InstallmentPlan>,
+    #[serde(default)]
+    next_plan_id: u64,
+}
+
+impl InstallmentStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<InstallmentStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            InstallmentStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Persists, logging instead of failing: a payment that reached the chain
+    /// must not be reported as failed because the plan file could not be written.
+    pub fn persist(&self) {
+        if let Err(e) = self.save() {
+            tracing::warn!("Failed to persist installment plans: {}", e);
+        }
+    }
+
+    pub fn next_id(&mut self) -> String {
+        self.next_plan_id += 1;
+        format!("plan-{}", self.next_plan_id)
+    }
+
+    pub fn insert(&mut self, plan: InstallmentPlan) -> Result<()> {
+        self.plans.insert(plan.plan_id.clone(), plan);
+        self.save()
+    }
+
+    pub fn get(&self, plan_id: &str) -> Result<&InstallmentPlan> {
+        self.plans
+            .get(plan_id)
+            .ok_or_else(|| anyhow::anyhow!("Installment plan {} not found", plan_id))
+    }
+
+    pub fn get_mut(&mut self, plan_id: &str) -> Result<&mut InstallmentPlan> {
+        self.plans
+            .get_mut(plan_id)
+            .ok_or_else(|| anyhow::anyhow!("Installment plan {} not found", plan_id))
+    }
+
+    pub fn list(&self) -> Vec<&InstallmentPlan> {
+        self.plans.values().collect()
+    }
+
+    /// True if the property is already being sold under an unfinished plan.
+    pub fn has_open_plan(&self, property_id: &str) -> bool {
+        self.plans
+            .values()
+            .any(|p| p.property_id == property_id && !p.status.is_finished())
+    }
+
+    /// Plans the periodic tick still has to look at.
+    pub fn unfinished(&self) -> Vec<String> {
+        self.plans
+            .values()
+            .filter(|p| !p.status.is_finished())
+            .map(|p| p.plan_id.clone())
+            .collect()
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    CreateInstallmentPlan {
+        input: InstallmentPlanInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.create_installment_plan(op.input, op.api_key.as_deref()).await;
+
+    PayInstallment {
+        plan_id: String,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.pay_installment(&op.plan_id, op.api_key.as_deref()).await;
+
+    GetInstallmentPlan {
+        plan_id: String,
+    } -> serde_json::Value;
+    |client, op| client.get_installment_plan(&op.plan_id);
+
+    ListInstallmentPlans {} -> serde_json::Value;
+    |client, _op| client.list_installment_plans()
+}
+
+impl MidenClientWrapper {
+    /// Opens an installment plan and its escrow. Returns the plan report.
+    pub async fn create_installment_plan(
+        &mut self,
+        input: InstallmentPlanInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let owner = self
+            .records
+            .properties
+            .get(&input.property_id)
+            .map(|p| p.owner_account_id.clone())
+            .ok_or_else(|| anyhow::anyhow!("Property {} has not been minted", input.property_id))?;
+
+        if self.installments.has_open_plan(&input.property_id) {
+            return Err(anyhow::anyhow!(
+                "Property {} already has an open installment plan",
+                input.property_id
+            ));
+        }
+        if self.auctions.has_open_auction(&input.property_id) {
+            return Err(anyhow::anyhow!(
+                "Property {} is up for auction",
+                input.property_id
+            ));
+        }
+        if self.listing_under_contract(&input.property_id) {
+            return Err(anyhow::anyhow!(
+                "Property {} is under contract from an accepted offer",
+                input.property_id
+            ));
+        }
+
+        let seller_hex = self.account_hex(&input.seller_account_id)?;
+        if !owner.eq_ignore_ascii_case(&seller_hex) {
+            return Err(anyhow::anyhow!(
+                "Seller {} does not own property {}",
+                input.seller_account_id,
+                input.property_id
+            ));
+        }
+
+        let escrow = self
+            .create_escrow(
+                &input.buyer_account_id,
+                &input.seller_account_id,
+                input.total_price,
+                Some(&input.property_id),
+                api_key,
+            )
+            .await?;
+
+        let now = chrono::Utc::now().timestamp();
+        let first_due_at = input
+            .first_due_at
+            .unwrap_or(now + input.interval_secs as i64);
+
+        let mut plan = InstallmentPlan {
+            plan_id: self.installments.next_id(),
+            property_id: input.property_id,
+            buyer_account_id: account_id_to_hex(escrow.buyer_account_id),
+            seller_account_id: account_id_to_hex(escrow.seller_account_id),
+            escrow_account_id: account_id_to_hex(escrow.escrow_account_id),
+            total_price: input.total_price,
+            paid_principal: 0,
+            status: PlanStatus::Active,
+            default_policy: self.config.installment_default_policy,
+            installments: schedule(
+                input.total_price,
+                input.installments,
+                first_due_at,
+                input.interval_secs,
+            ),
+            title_transfer_tx_id: None,
+            settle_tx_id: None,
+            events: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        };
+        plan.event(
+            PlanEventKind::Created,
+            None,
+            format!(
+                "{} installments totalling {} into escrow {}",
+                input.installments, input.total_price, plan.escrow_account_id
+            ),
+        );
+
+        let report = plan.report();
+        self.installments.insert(plan)?;
+        Ok(report)
+    }
+
+    /// Pays the next installment of a plan from the buyer into the escrow.
+    ///
+    /// When this was the final installment, the title transfer and escrow
+    /// release are attempted straight away; if they fail the plan stays
+    /// `completing` and the periodic tick retries them.
+    pub async fn pay_installment(
+        &mut self,
+        plan_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let (escrow_hex, seq, amount) = {
+            let plan = self.installments.get(plan_id)?;
+            if plan.status != PlanStatus::Active {
+                return Err(anyhow::anyhow!(
+                    "Installment plan {} is {:?} and accepts no payments",
+                    plan_id,
+                    plan.status
+                ));
+            }
+            let next = plan
+                .next_due()
+                .ok_or_else(|| anyhow::anyhow!("Installment plan {} is fully paid", plan_id))?;
+            (plan.escrow_account_id.clone(), next.seq, next.amount)
+        };
+
+        let escrow = self.recorded_escrow(&escrow_hex)?;
+        let (escrow, allowance_id) =
+            self.authorize_funding(api_key, &escrow, AllowancePurpose::Installments, amount)?;
+        let faucet = self.escrow_settlement_faucet(&escrow_hex)?;
+
+        let reference = format!("{}#{}", plan_id, seq);
+        let op_id = self.records.begin_operation("pay_installment", &reference);
+        let result = self
+            .submit_asset_payment(
+                escrow.buyer_account_id,
+                escrow.escrow_account_id,
+                faucet,
+                amount,
+            )
+            .await;
+        let tx_id = result.as_ref().ok().cloned();
+        self.records.finish_operation(op_id, &result, tx_id.clone());
+        let tx_id = result?;
+
+        if let Some(allowance_id) = allowance_id {
+            self.allowances.spend(
+                allowance_id,
+                amount,
+                AllowancePurpose::Installments,
+                &reference,
+                &tx_id,
+            );
+        }
+
+        self.records
+            .update_escrow(&escrow_hex, EscrowStatus::Funded, Some(tx_id.clone()));
+
+        let fully_paid = {
+            let plan = self.installments.get_mut(plan_id)?;
+            if let Some(installment) = plan.next_due_mut() {
+                installment.status = InstallmentStatus::Paid;
+                installment.paid_at = Some(chrono::Utc::now().timestamp());
+                installment.tx_id = Some(tx_id.clone());
+            }
+            plan.paid_principal += amount;
+            plan.event(
+                PlanEventKind::Paid,
+                Some(seq),
+                format!("{} paid (tx {})", amount, tx_id),
+            );
+
+            let fully_paid = plan.next_due().is_none();
+            if fully_paid {
+                plan.status = PlanStatus::Completing;
+            }
+            fully_paid
+        };
+        self.installments.persist();
+
+        if fully_paid {
+            self.settle_installment_plan(plan_id).await;
+        }
+
+        Ok(self.installments.get(plan_id)?.report())
+    }
+
+    /// Runs the outstanding settlement steps of a completing or defaulting plan.
+    ///
+    /// Failures are recorded as events (once per distinct error) and left for
+    /// the next tick.
+    async fn settle_installment_plan(&mut self, plan_id: &str) {
+        if let Err(e) = self.try_settle_installment_plan(plan_id).await {
+            tracing::warn!("Installment plan {} settlement failed: {}", plan_id, e);
+            if let Ok(plan) = self.installments.get_mut(plan_id) {
+                let detail = e.to_string();
+                let repeated = plan.events.last().is_some_and(|last| {
+                    last.kind == PlanEventKind::SettlementFailed && last.detail == detail
+                });
+                if !repeated {
+                    plan.event(PlanEventKind::SettlementFailed, None, detail);
+                }
+            }
+        }
+        self.installments.persist();
+    }
+
+    async fn try_settle_installment_plan(&mut self, plan_id: &str) -> Result<()> {
+        let plan = self.installments.get(plan_id)?.clone();
+        let escrow = self.recorded_escrow(&plan.escrow_account_id)?;
+
+        match plan.status {
+            PlanStatus::Completing => {
+                if plan.settle_tx_id.is_none() {
+                    if plan.title_transfer_tx_id.is_none() {
+                        self.check_property_transferable(
+                            &plan.property_id,
+                            &plan.seller_account_id,
+                        )
+                        .await?;
+                    }
+                    // The final payment note must be visible before it can be consumed
+                    self.wait_for_propagation().await;
+                    let tx_id = self.release_escrow_unchecked(&escrow).await?;
+                    let plan = self.installments.get_mut(plan_id)?;
+                    plan.settle_tx_id = Some(tx_id.clone());
+                    plan.event(
+                        PlanEventKind::EscrowReleased,
+                        None,
+                        format!("to seller {} (tx {})", plan.seller_account_id, tx_id),
+                    );
+                    self.installments.persist();
+                }
+
+                if plan.title_transfer_tx_id.is_none() {
+                    let tx_id = self
+                        .transfer_property(&plan.property_id, &plan.buyer_account_id)
+                        .await?;
+                    let plan = self.installments.get_mut(plan_id)?;
+                    plan.title_transfer_tx_id = Some(tx_id.clone());
+                    plan.event(
+                        PlanEventKind::TitleTransferred,
+                        None,
+                        format!(
+                            "{} to {} (tx {})",
+                            plan.property_id, plan.buyer_account_id, tx_id
+                        ),
+                    );
+                }
+
+                let plan = self.installments.get_mut(plan_id)?;
+                plan.status = PlanStatus::Completed;
+                plan.event(
+                    PlanEventKind::Completed,
+                    None,
+                    "seller paid, title transferred",
+                );
+            }
+            PlanStatus::Defaulting => {
+                if plan.settle_tx_id.is_none() && plan.paid_principal > 0 {
+                    let (tx_id, kind, party) = match plan.default_policy {
+                        DefaultPolicy::RefundBuyer => (
+                            self.refund_escrow_unchecked(&escrow).await?,
+                            PlanEventKind::EscrowRefunded,
+                            &plan.buyer_account_id,
+                        ),
+                        DefaultPolicy::ForfeitToSeller => (
+                            self.release_escrow_unchecked(&escrow).await?,
+                            PlanEventKind::EscrowReleased,
+                            &plan.seller_account_id,
+                        ),
+                    };
+                    let detail = format!("{} to {} (tx {})", plan.paid_principal, party, tx_id);
+                    let plan = self.installments.get_mut(plan_id)?;
+                    plan.settle_tx_id = Some(tx_id);
+                    plan.event(kind, None, detail);
+                }
+
+                let plan = self.installments.get_mut(plan_id)?;
+                plan.status = PlanStatus::Defaulted;
+                plan.event(
+                    PlanEventKind::Defaulted,
+                    None,
+                    format!("settled per {:?} policy", plan.default_policy),
+                );
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Periodic pass over unfinished plans: reminders, defaults and settlement
+    /// retries. Returns the number of plans that changed.
+    pub async fn run_installment_tick(&mut self) -> Result<usize> {
+        let now = chrono::Utc::now().timestamp();
+        let lead = self.config.installment_reminder_lead.as_secs() as i64;
+        let grace = self.config.installment_grace_period.as_secs() as i64;
+        let mut changed = 0;
+
+        for plan_id in self.installments.unfinished() {
+            let plan = self.installments.get_mut(&plan_id)?;
+            let events_before = plan.events.len();
+
+            if plan.status == PlanStatus::Active {
+                let Some(next) = plan.next_due().cloned() else {
+                    continue;
+                };
+
+                if now > next.due_at + grace {
+                    if let Some(installment) = plan.next_due_mut() {
+                        installment.status = InstallmentStatus::Missed;
+                    }
+                    plan.status = PlanStatus::Defaulting;
+                    plan.event(
+                        PlanEventKind::Missed,
+                        Some(next.seq),
+                        format!("{} was due at {}", next.amount, next.due_at),
+                    );
+                } else if now >= next.due_at - lead && !next.reminded {
+                    if let Some(installment) = plan.next_due_mut() {
+                        installment.reminded = true;
+                    }
+                    plan.event(
+                        PlanEventKind::Reminder,
+                        Some(next.seq),
+                        format!("{} due at {}", next.amount, next.due_at),
+                    );
+                }
+            }
+
+            let needs_settlement =
+                matches!(plan.status, PlanStatus::Completing | PlanStatus::Defaulting);
+            if plan.events.len() != events_before {
+                changed += 1;
+            }
+            if needs_settlement {
+                self.settle_installment_plan(&plan_id).await;
+                changed += 1;
+            }
+        }
+
+        if changed > 0 {
+            self.installments.persist();
+        }
+        Ok(changed)
+    }
+
+    pub fn get_installment_plan(&self, plan_id: &str) -> Result<serde_json::Value> {
+        Ok(self.installments.get(plan_id)?.report())
+    }
+
+    pub fn list_installment_plans(&self) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self
+            .installments
+            .list()
+            .iter()
+            .map(|plan| plan.summary())
+            .collect::<Vec<_>>()))
+    }
+}
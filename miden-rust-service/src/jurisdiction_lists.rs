@@ -0,0 +1,198 @@
+// src/jurisdiction_lists.rs
+//
+// Managed restricted-jurisdiction lists
+//
+// Callers no longer supply the restricted-country list with each proof request.
+// Admins publish list versions; every version is immutable, numbered, and
+// identified by a digest. Jurisdiction proofs commit to the version they were
+// generated against, and verification rejects proofs made against any version
+// other than the current one.
+//
+// Digest (hex-encoded in `list_hash`):
+//   SHA-256("obscura-jurisdiction-list" || version as u64 LE || countries joined by ",")
+// where countries are upper-cased, de-duplicated and sorted.
+//
+// Signing: when JURISDICTION_LIST_SIGNER_PUBKEY is configured, each update must
+// carry an Ed25519 signature (hex) over the 32-byte digest of the version being
+// published. Without a signer key, versions are stored unsigned.
+
+use anyhow::Result;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JurisdictionListVersion {
+    pub version: u64,
+    /// Upper-cased, de-duplicated, sorted country codes
+    pub countries: Vec<String>,
+    pub list_hash: String,
+    /// Ed25519 signature over the digest (hex), if the update was signed
+    pub signature: Option<String>,
+    pub note: Option<String>,
+    pub published_at: i64,
+}
+
+/// Body of a list update.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ListUpdate {
+    pub countries: Vec<String>,
+    pub note: Option<String>,
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct JurisdictionListStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(skip)]
+    signer: Option<VerifyingKey>,
+    #[serde(default)]
+    versions: Vec<JurisdictionListVersion>,
+}
+
+fn canonical_countries(countries: &[String]) -> Vec<String> {
+    let mut canonical: Vec<String> = countries
+        .iter()
+        .map(|c| c.trim().to_uppercase())
+        .filter(|c| !c.is_empty())
+        .collect();
+    canonical.sort();
+    canonical.dedup();
+    canonical
+}
+
+/// Digest a version is identified (and signed) by.
+pub fn list_digest(version: u64, countries: &[String]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"obscura-jurisdiction-list");
+    hasher.update(version.to_le_bytes());
+    hasher.update(canonical_countries(countries).join(",").as_bytes());
+    hasher.finalize().into()
+}
+
+/// Parses a hex-encoded Ed25519 public key.
+pub fn parse_signer_key(hex_key: &str) -> Result<VerifyingKey> {
+    let bytes: [u8; 32] = hex::decode(hex_key.trim().trim_start_matches("0x"))?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Signer public key must be 32 bytes"))?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| anyhow::anyhow!("Invalid signer key: {}", e))
+}
+
+impl JurisdictionListStore {
+    /// Loads published versions from disk. When nothing has been published yet,
+    /// signing is not required and `initial` is non-empty, it becomes version 1.
+    pub fn load(
+        path: impl Into<PathBuf>,
+        signer: Option<VerifyingKey>,
+        initial: &[String],
+    ) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<JurisdictionListStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            JurisdictionListStore::default()
+        };
+        store.path = path;
+        store.signer = signer;
+
+        if store.versions.is_empty() && !initial.is_empty() {
+            if store.signing_required() {
+                tracing::warn!(
+                    "Ignoring configured initial jurisdiction list: updates must be signed"
+                );
+            } else {
+                store.append(initial, Some("Initial list from configuration".to_string()), None)?;
+            }
+        }
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn current(&self) -> Option<&JurisdictionListVersion> {
+        self.versions.last()
+    }
+
+    pub fn get(&self, version: u64) -> Option<&JurisdictionListVersion> {
+        self.versions.iter().find(|v| v.version == version)
+    }
+
+    pub fn list(&self) -> &[JurisdictionListVersion] {
+        &self.versions
+    }
+
+    pub fn signing_required(&self) -> bool {
+        self.signer.is_some()
+    }
+
+    /// Publishes a new version, verifying its signature if a signer is configured.
+    pub fn publish(&mut self, update: ListUpdate) -> Result<JurisdictionListVersion> {
+        let version = self.next_version();
+        let digest = list_digest(version, &update.countries);
+
+        let signature = match (&self.signer, update.signature.as_deref()) {
+            (Some(signer), Some(signature_hex)) => {
+                let bytes: [u8; 64] = hex::decode(signature_hex.trim().trim_start_matches("0x"))?
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("Signature must be 64 bytes"))?;
+                signer
+                    .verify(&digest, &Signature::from_bytes(&bytes))
+                    .map_err(|_| {
+                        anyhow::anyhow!("Signature does not match list version {}", version)
+                    })?;
+                Some(hex::encode(bytes))
+            }
+            (Some(_), None) => {
+                return Err(anyhow::anyhow!(
+                    "List updates must be signed (digest for version {}: {})",
+                    version,
+                    hex::encode(digest)
+                ));
+            }
+            (None, Some(_)) => {
+                return Err(anyhow::anyhow!(
+                    "Signature supplied but no list signer key is configured"
+                ));
+            }
+            (None, None) => None,
+        };
+
+        self.append(&update.countries, update.note, signature)
+    }
+
+    fn next_version(&self) -> u64 {
+        self.current().map(|v| v.version + 1).unwrap_or(1)
+    }
+
+    fn append(
+        &mut self,
+        countries: &[String],
+        note: Option<String>,
+        signature: Option<String>,
+    ) -> Result<JurisdictionListVersion> {
+        let version = self.next_version();
+        let entry = JurisdictionListVersion {
+            version,
+            countries: canonical_countries(countries),
+            list_hash: hex::encode(list_digest(version, countries)),
+            signature,
+            note,
+            published_at: chrono::Utc::now().timestamp(),
+        };
+
+        self.versions.push(entry.clone());
+        self.save()?;
+        Ok(entry)
+    }
+}
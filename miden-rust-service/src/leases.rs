@@ -0,0 +1,758 @@
+// src/leases.rs
+//
+// Rental agreements
+//
+// A lease links a property owner and a tenant: rent amount, rent interval and
+// number of rent periods, plus a security deposit held in a dedicated escrow
+// (tenant as buyer, owner as seller).
+//
+// Lifecycle:
+// - pending_deposit: created by the owner; the tenant pays the deposit into the
+//   escrow, which activates the lease and authorizes the scheduled rent debits
+// - active: the scheduler (scheduler.rs) collects each rent payment from the
+//   tenant when it falls due (paid in advance, at the start of each period);
+//   a payment that fails is marked late and retried on the following ticks
+//   (with SERVICE_PAYMENTS_REQUIRE_ALLOWANCE, rent is only debited against a
+//   "service" allowance from the tenant, see allowances.rs)
+// - ended: the term is over; the owner returns the deposit, withholding part
+//   or all of it with a stated reason. If the owner does not act within
+//   LEASE_DEPOSIT_RETURN_SECS, the scheduler returns the deposit in full
+// - closed: the deposit escrow has been settled
+//
+// Owner actions need an API key bound to the owner (or an arbiter); paying the
+// deposit is an escrow funding and needs the tenant's key (escrow.rs).
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    account_id_to_hex, allowances::AllowancePurpose, escrow::EscrowStatus, read_cache::Touched,
+    MidenClientWrapper,
+};
+
+/// Upper bound on the number of rent periods in one lease.
+pub const MAX_RENT_PERIODS: u32 = 120;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LeaseInput {
+    pub property_id: String,
+    /// Account name or hex AccountId; must own the property
+    pub owner_account_id: String,
+    /// Account name or hex AccountId
+    pub tenant_account_id: String,
+    pub rent_amount: u64,
+    /// Seconds per rent period
+    pub rent_interval_secs: u64,
+    /// Number of rent periods in the term
+    pub periods: u32,
+    pub deposit_amount: u64,
+    /// Unix time the term starts (defaults to when the deposit is paid)
+    #[serde(default)]
+    pub start_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LeaseEndInput {
+    /// Part of the deposit kept by the owner
+    #[serde(default)]
+    pub withhold_amount: u64,
+    /// Required when anything is withheld
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaseStatus {
+    PendingDeposit,
+    Active,
+    Ended,
+    Closed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RentStatus {
+    Scheduled,
+    Paid,
+    Late,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RentPayment {
+    pub seq: u32,
+    pub amount: u64,
+    pub due_at: i64,
+    pub status: RentStatus,
+    pub attempts: u32,
+    pub paid_at: Option<i64>,
+    pub tx_id: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DepositSettlement {
+    pub returned: u64,
+    pub withheld: u64,
+    pub reason: Option<String>,
+    /// Returned by the scheduler after the owner let the window lapse
+    pub automatic: bool,
+    pub tx_id: String,
+    pub settled_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaseEventKind {
+    Created,
+    DepositPaid,
+    RentPaid,
+    RentLate,
+    Ended,
+    DepositReturned,
+    DepositWithheld,
+    SettlementFailed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaseEvent {
+    pub at: i64,
+    pub kind: LeaseEventKind,
+    pub seq: Option<u32>,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lease {
+    pub lease_id: String,
+    pub property_id: String,
+    pub owner_account_id: String,
+    pub tenant_account_id: String,
+    pub rent_amount: u64,
+    pub rent_interval_secs: u64,
+    pub periods: u32,
+    pub deposit_amount: u64,
+    pub deposit_escrow_account_id: String,
+    pub deposit_tx_id: Option<String>,
+    pub status: LeaseStatus,
+    pub requested_start_at: Option<i64>,
+    /// Set when the lease activates
+    pub start_at: Option<i64>,
+    pub end_at: Option<i64>,
+    pub rent_payments: Vec<RentPayment>,
+    pub deposit_settlement: Option<DepositSettlement>,
+    pub events: Vec<LeaseEvent>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Lease {
+    fn event(&mut self, kind: LeaseEventKind, seq: Option<u32>, detail: impl Into<String>) {
+        let now = chrono::Utc::now().timestamp();
+        let detail = detail.into();
+        tracing::info!("Lease {}: {:?} {}", self.lease_id, kind, detail);
+        self.events.push(LeaseEvent {
+            at: now,
+            kind,
+            seq,
+            detail,
+        });
+        self.updated_at = now;
+    }
+
+    /// Starts the term at `start_at` and lays out the rent schedule.
+    fn activate(&mut self, start_at: i64) {
+        let interval = self.rent_interval_secs as i64;
+        self.status = LeaseStatus::Active;
+        self.start_at = Some(start_at);
+        self.end_at = Some(start_at + self.periods as i64 * interval);
+        self.rent_payments = (0..self.periods)
+            .map(|i| RentPayment {
+                seq: i + 1,
+                amount: self.rent_amount,
+                due_at: start_at + i as i64 * interval,
+                status: RentStatus::Scheduled,
+                attempts: 0,
+                paid_at: None,
+                tx_id: None,
+                error: None,
+            })
+            .collect();
+    }
+
+    /// Index of the earliest unpaid rent payment already due at `now`.
+    fn due_payment(&self, now: i64) -> Option<usize> {
+        self.rent_payments
+            .iter()
+            .position(|p| p.status != RentStatus::Paid)
+            .filter(|&i| self.rent_payments[i].due_at <= now)
+    }
+
+    /// Lease with its rent schedule and event history.
+    pub fn report(&self) -> serde_json::Value {
+        let mut report = self.summary();
+        if let Some(obj) = report.as_object_mut() {
+            obj.insert(
+                "rent_payments".into(),
+                serde_json::json!(self.rent_payments),
+            );
+            obj.insert("events".into(), serde_json::json!(self.events));
+        }
+        report
+    }
+
+    /// Terms and status only.
+    pub fn summary(&self) -> serde_json::Value {
+        let count = |status: RentStatus| {
+            self.rent_payments
+                .iter()
+                .filter(|p| p.status == status)
+                .count()
+        };
+
+        serde_json::json!({
+            "lease_id": self.lease_id,
+            "property_id": self.property_id,
+            "owner_account_id": self.owner_account_id,
+            "tenant_account_id": self.tenant_account_id,
+            "status": self.status,
+            "rent_amount": self.rent_amount,
+            "rent_interval_secs": self.rent_interval_secs,
+            "periods": self.periods,
+            "rent_paid": count(RentStatus::Paid),
+            "rent_late": count(RentStatus::Late),
+            "deposit_amount": self.deposit_amount,
+            "deposit_escrow_account_id": self.deposit_escrow_account_id,
+            "deposit_tx_id": self.deposit_tx_id,
+            "deposit_settlement": self.deposit_settlement,
+            "start_at": self.start_at,
+            "end_at": self.end_at,
+            "created_at": self.created_at,
+            "updated_at": self.updated_at,
+        })
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LeaseStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    leases: BTreeMap<String, Lease>,
+    #[serde(default)]
+    next_lease_id: u64,
+}
+
+impl LeaseStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<LeaseStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            LeaseStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Persists, logging instead of failing: a rent payment that reached the
+    /// chain must not be retried because the lease file could not be written.
+    pub fn persist(&self) {
+        if let Err(e) = self.save() {
+            tracing::warn!("Failed to persist leases: {}", e);
+        }
+    }
+
+    pub fn next_id(&mut self) -> String {
+        self.next_lease_id += 1;
+        format!("lease-{}", self.next_lease_id)
+    }
+
+    pub fn insert(&mut self, lease: Lease) -> Result<()> {
+        self.leases.insert(lease.lease_id.clone(), lease);
+        self.save()
+    }
+
+    pub fn get(&self, lease_id: &str) -> Result<&Lease> {
+        self.leases
+            .get(lease_id)
+            .ok_or_else(|| anyhow::anyhow!("Lease {} not found", lease_id))
+    }
+
+    pub fn get_mut(&mut self, lease_id: &str) -> Result<&mut Lease> {
+        self.leases
+            .get_mut(lease_id)
+            .ok_or_else(|| anyhow::anyhow!("Lease {} not found", lease_id))
+    }
+
+    pub fn list(&self) -> Vec<&Lease> {
+        self.leases.values().collect()
+    }
+
+    /// True if the property is let (or about to be) under a lease not yet closed.
+    pub fn has_open_lease(&self, property_id: &str) -> bool {
+        self.leases
+            .values()
+            .any(|l| l.property_id == property_id && l.status != LeaseStatus::Closed)
+    }
+
+    /// Leases the scheduler has to look at.
+    pub fn scheduled(&self) -> Vec<String> {
+        self.leases
+            .values()
+            .filter(|l| matches!(l.status, LeaseStatus::Active | LeaseStatus::Ended))
+            .map(|l| l.lease_id.clone())
+            .collect()
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    CreateLease {
+        input: LeaseInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.create_lease(op.input, op.api_key.as_deref()).await;
+
+    PayLeaseDeposit {
+        lease_id: String,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.pay_lease_deposit(&op.lease_id, op.api_key.as_deref()).await;
+
+    EndLease {
+        lease_id: String,
+        input: LeaseEndInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.end_lease(&op.lease_id, op.input, op.api_key.as_deref()).await;
+
+    GetLease {
+        lease_id: String,
+    } -> serde_json::Value;
+    |client, op| client.get_lease(&op.lease_id);
+
+    ListLeases {} -> serde_json::Value;
+    |client, _op| client.list_leases()
+}
+
+impl MidenClientWrapper {
+    /// Creates a lease and its deposit escrow. Returns the lease report.
+    pub async fn create_lease(
+        &mut self,
+        input: LeaseInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let owner = self
+            .records
+            .properties
+            .get(&input.property_id)
+            .map(|p| p.owner_account_id.clone())
+            .ok_or_else(|| anyhow::anyhow!("Property {} has not been minted", input.property_id))?;
+
+        let owner_hex = self.account_hex(&input.owner_account_id)?;
+        if !owner.eq_ignore_ascii_case(&owner_hex) {
+            return Err(anyhow::anyhow!(
+                "Owner {} does not own property {}",
+                input.owner_account_id,
+                input.property_id
+            ));
+        }
+        self.authorize_account(api_key, &owner_hex, "owner")?;
+
+        if self.leases.has_open_lease(&input.property_id) {
+            return Err(anyhow::anyhow!(
+                "Property {} already has an open lease",
+                input.property_id
+            ));
+        }
+
+        let escrow = self
+            .create_escrow(
+                &input.tenant_account_id,
+                &input.owner_account_id,
+                input.deposit_amount,
+                None,
+                api_key,
+            )
+            .await?;
+
+        let now = chrono::Utc::now().timestamp();
+        let mut lease = Lease {
+            lease_id: self.leases.next_id(),
+            property_id: input.property_id,
+            owner_account_id: owner_hex,
+            tenant_account_id: account_id_to_hex(escrow.buyer_account_id),
+            rent_amount: input.rent_amount,
+            rent_interval_secs: input.rent_interval_secs,
+            periods: input.periods,
+            deposit_amount: input.deposit_amount,
+            deposit_escrow_account_id: account_id_to_hex(escrow.escrow_account_id),
+            deposit_tx_id: None,
+            status: LeaseStatus::PendingDeposit,
+            requested_start_at: input.start_at,
+            start_at: None,
+            end_at: None,
+            rent_payments: Vec::new(),
+            deposit_settlement: None,
+            events: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        };
+        lease.event(
+            LeaseEventKind::Created,
+            None,
+            format!(
+                "{} periods of {} rent, deposit {} into escrow {}",
+                lease.periods,
+                lease.rent_amount,
+                lease.deposit_amount,
+                lease.deposit_escrow_account_id
+            ),
+        );
+
+        let report = lease.report();
+        self.leases.insert(lease)?;
+        Ok(report)
+    }
+
+    /// Tenant pays the deposit into the escrow, which activates the lease.
+    pub async fn pay_lease_deposit(
+        &mut self,
+        lease_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let (escrow_hex, amount) = {
+            let lease = self.leases.get(lease_id)?;
+            if lease.status != LeaseStatus::PendingDeposit {
+                return Err(anyhow::anyhow!(
+                    "Lease {} is {:?}; the deposit has already been paid",
+                    lease_id,
+                    lease.status
+                ));
+            }
+            (
+                lease.deposit_escrow_account_id.clone(),
+                lease.deposit_amount,
+            )
+        };
+
+        let escrow = self.recorded_escrow(&escrow_hex)?;
+        let (escrow, allowance_id) =
+            self.authorize_funding(api_key, &escrow, AllowancePurpose::EscrowFunding, amount)?;
+
+        let op_id = self.records.begin_operation("lease_deposit", lease_id);
+        let result = self
+            .submit_token_payment(escrow.buyer_account_id, escrow.escrow_account_id, amount)
+            .await;
+        let tx_id = result.as_ref().ok().cloned();
+        self.records.finish_operation(op_id, &result, tx_id.clone());
+        let tx_id = result?;
+
+        if let Some(allowance_id) = allowance_id {
+            self.allowances.spend(
+                allowance_id,
+                amount,
+                AllowancePurpose::EscrowFunding,
+                lease_id,
+                &tx_id,
+            );
+        }
+
+        self.records
+            .update_escrow(&escrow_hex, EscrowStatus::Funded, Some(tx_id.clone()));
+
+        let now = chrono::Utc::now().timestamp();
+        let lease = self.leases.get_mut(lease_id)?;
+        lease.deposit_tx_id = Some(tx_id.clone());
+        lease.activate(lease.requested_start_at.unwrap_or(now).max(now));
+        lease.event(
+            LeaseEventKind::DepositPaid,
+            None,
+            format!(
+                "{} (tx {}); term starts {}",
+                amount,
+                tx_id,
+                lease.start_at.unwrap_or(now)
+            ),
+        );
+        let report = lease.report();
+        self.leases.persist();
+
+        Ok(report)
+    }
+
+    /// Ends the lease (once its term is over) and settles the deposit:
+    /// `withhold_amount` goes to the owner, the rest back to the tenant.
+    pub async fn end_lease(
+        &mut self,
+        lease_id: &str,
+        input: LeaseEndInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let lease = self.leases.get(lease_id)?;
+        self.authorize_account(api_key, &lease.owner_account_id, "owner")?;
+
+        match lease.status {
+            LeaseStatus::Active | LeaseStatus::Ended => {}
+            status => {
+                return Err(anyhow::anyhow!(
+                    "Lease {} is {:?} and cannot be ended",
+                    lease_id,
+                    status
+                ));
+            }
+        }
+        let end_at = lease.end_at.unwrap_or_default();
+        if chrono::Utc::now().timestamp() < end_at {
+            return Err(anyhow::anyhow!("Lease {} runs until {}", lease_id, end_at));
+        }
+        if input.withhold_amount > lease.deposit_amount {
+            return Err(anyhow::anyhow!(
+                "Cannot withhold {} of a {} deposit",
+                input.withhold_amount,
+                lease.deposit_amount
+            ));
+        }
+        if input.withhold_amount > 0 && input.reason.is_none() {
+            return Err(anyhow::anyhow!(
+                "A reason is required to withhold part of the deposit"
+            ));
+        }
+
+        self.mark_lease_ended(lease_id)?;
+        self.settle_lease_deposit(lease_id, input.withhold_amount, input.reason, false)
+            .await?;
+        self.leases.persist();
+
+        Ok(self.leases.get(lease_id)?.report())
+    }
+
+    fn mark_lease_ended(&mut self, lease_id: &str) -> Result<()> {
+        let lease = self.leases.get_mut(lease_id)?;
+        if lease.status == LeaseStatus::Active {
+            lease.status = LeaseStatus::Ended;
+            let late = lease
+                .rent_payments
+                .iter()
+                .filter(|p| p.status != RentStatus::Paid)
+                .count();
+            lease.event(
+                LeaseEventKind::Ended,
+                None,
+                format!("term over, {} rent payment(s) outstanding", late),
+            );
+        }
+        Ok(())
+    }
+
+    async fn settle_lease_deposit(
+        &mut self,
+        lease_id: &str,
+        withhold: u64,
+        reason: Option<String>,
+        automatic: bool,
+    ) -> Result<()> {
+        let lease = self.leases.get(lease_id)?;
+        let escrow = self.recorded_escrow(&lease.deposit_escrow_account_id)?;
+        let returned = lease.deposit_amount - withhold;
+
+        let result = self.split_escrow_unchecked(&escrow, withhold).await;
+
+        let lease = self.leases.get_mut(lease_id)?;
+        let tx_id = match result {
+            Ok(tx_id) => tx_id,
+            Err(e) => {
+                // One event per distinct error, not one per retry
+                let detail = e.to_string();
+                let repeated = lease.events.last().is_some_and(|last| {
+                    last.kind == LeaseEventKind::SettlementFailed && last.detail == detail
+                });
+                if !repeated {
+                    lease.event(LeaseEventKind::SettlementFailed, None, detail);
+                }
+                self.leases.persist();
+                return Err(e);
+            }
+        };
+
+        if withhold > 0 {
+            lease.event(
+                LeaseEventKind::DepositWithheld,
+                None,
+                format!(
+                    "{} withheld ({}), {} returned (tx {})",
+                    withhold,
+                    reason.as_deref().unwrap_or_default(),
+                    returned,
+                    tx_id
+                ),
+            );
+        } else {
+            lease.event(
+                LeaseEventKind::DepositReturned,
+                None,
+                format!("{} returned (tx {})", returned, tx_id),
+            );
+        }
+        lease.deposit_settlement = Some(DepositSettlement {
+            returned,
+            withheld: withhold,
+            reason,
+            automatic,
+            tx_id,
+            settled_at: chrono::Utc::now().timestamp(),
+        });
+        lease.status = LeaseStatus::Closed;
+
+        Ok(())
+    }
+
+    /// Scheduler job: collects due rent, ends leases whose term is over and
+    /// returns deposits the owner has not settled in time. Returns the number
+    /// of leases that changed.
+    pub async fn run_lease_tick(&mut self) -> Result<usize> {
+        let now = chrono::Utc::now().timestamp();
+        let return_window = self.config.lease_deposit_return_period.as_secs() as i64;
+        let mut changed = 0;
+
+        for lease_id in self.leases.scheduled() {
+            let events_before = self.leases.get(&lease_id)?.events.len();
+
+            if self.leases.get(&lease_id)?.status == LeaseStatus::Active {
+                if let Err(e) = self.collect_due_rent(&lease_id, now).await {
+                    tracing::warn!("Lease {}: rent collection failed: {}", lease_id, e);
+                }
+
+                if now >= self.leases.get(&lease_id)?.end_at.unwrap_or(i64::MAX) {
+                    self.mark_lease_ended(&lease_id)?;
+                }
+            }
+
+            let lease = self.leases.get(&lease_id)?;
+            if lease.status == LeaseStatus::Ended
+                && now >= lease.end_at.unwrap_or(i64::MAX) + return_window
+            {
+                if let Err(e) = self.settle_lease_deposit(&lease_id, 0, None, true).await {
+                    tracing::warn!("Lease {}: deposit return failed: {}", lease_id, e);
+                }
+            }
+
+            if self.leases.get(&lease_id)?.events.len() != events_before {
+                changed += 1;
+            }
+        }
+
+        if changed > 0 {
+            self.leases.persist();
+        }
+        Ok(changed)
+    }
+
+    /// Pays every rent installment due by `now`, oldest first, stopping at the
+    /// first failure.
+    async fn collect_due_rent(&mut self, lease_id: &str, now: i64) -> Result<()> {
+        loop {
+            let lease = self.leases.get(lease_id)?;
+            let Some(index) = lease.due_payment(now) else {
+                return Ok(());
+            };
+            let payment = lease.rent_payments[index].clone();
+            let deposit_escrow = self.recorded_escrow(&lease.deposit_escrow_account_id)?;
+
+            let reference = format!("{}#{}", lease_id, payment.seq);
+            let op_id = self.records.begin_operation("collect_rent", &reference);
+            let result = match self.service_allowance(
+                &account_id_to_hex(deposit_escrow.buyer_account_id),
+                AllowancePurpose::Rent,
+                payment.amount,
+            ) {
+                Ok(allowance_id) => {
+                    let result = self
+                        .submit_token_payment(
+                            deposit_escrow.buyer_account_id,
+                            deposit_escrow.seller_account_id,
+                            payment.amount,
+                        )
+                        .await;
+                    if let (Ok(tx_id), Some(allowance_id)) = (&result, allowance_id) {
+                        self.allowances.spend(
+                            allowance_id,
+                            payment.amount,
+                            AllowancePurpose::Rent,
+                            &reference,
+                            tx_id,
+                        );
+                    }
+                    result
+                }
+                Err(e) => Err(e),
+            };
+            let tx_id = result.as_ref().ok().cloned();
+            self.records.finish_operation(op_id, &result, tx_id);
+
+            let lease = self.leases.get_mut(lease_id)?;
+            let entry = &mut lease.rent_payments[index];
+            entry.attempts += 1;
+            match result {
+                Ok(tx_id) => {
+                    entry.status = RentStatus::Paid;
+                    entry.paid_at = Some(chrono::Utc::now().timestamp());
+                    entry.tx_id = Some(tx_id.clone());
+                    entry.error = None;
+                    lease.event(
+                        LeaseEventKind::RentPaid,
+                        Some(payment.seq),
+                        format!("{} (tx {})", payment.amount, tx_id),
+                    );
+                    self.leases.persist();
+                }
+                Err(e) => {
+                    let first_failure = entry.status == RentStatus::Scheduled;
+                    entry.status = RentStatus::Late;
+                    entry.error = Some(e.to_string());
+                    if first_failure {
+                        lease.event(
+                            LeaseEventKind::RentLate,
+                            Some(payment.seq),
+                            format!("{} due at {}: {}", payment.amount, payment.due_at, e),
+                        );
+                    }
+                    self.leases.persist();
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    pub fn get_lease(&self, lease_id: &str) -> Result<serde_json::Value> {
+        Ok(self.leases.get(lease_id)?.report())
+    }
+
+    pub fn list_leases(&self) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self
+            .leases
+            .list()
+            .iter()
+            .map(|lease| lease.summary())
+            .collect::<Vec<_>>()))
+    }
+}
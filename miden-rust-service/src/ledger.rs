@@ -0,0 +1,411 @@
+// src/ledger.rs
+//
+// Double-entry ledger of the service's asset movements
+//
+// Every fungible movement the service submits is journaled as one entry of
+// balanced postings (LEDGER_PATH), so accountants get debits, credits and a
+// trial balance instead of notes:
+// - ledger accounts are the on-chain accounts (hex AccountId), plus
+//   issuance:<faucet> for what a faucet mints and `suspense` for adjustments
+// - a debit adds to the account's holdings of an asset, a credit takes from
+//   them: a mint debits the recipient and credits the faucet's issuance
+//   account, a payment debits the recipient and credits the sender, a burn
+//   credits the holder and debits the faucet's issuance account
+// - an entry's debits and credits are equal per asset; an entry that is not
+//   is refused
+// - amounts are posted when their transaction is submitted, so notes on their
+//   way count as the recipient's
+//
+// Journaled: startup funding and property mints, token sends and payments
+// (installments, rent, share trades), escrow funding, release, refund and
+// split (with the platform fee to the treasury), treasury withdrawals,
+// recovery sweeps, both legs of token swaps and property retirements.
+//
+// POST /admin/reconcile checks the ledger against the chain: every posted
+// account in the client store must hold its ledger balance in its vault and
+// consumable notes, and the journal must balance. With repair, a difference is
+// posted as an adjustment against `suspense`, which is how balances from
+// before the ledger or from outside the service come in. Accounts outside the
+// client store cannot be checked.
+//
+// GET /admin/ledger/trial-balance (?as_of=unix seconds) lists debits, credits
+// and balance per account and asset; GET /admin/ledger/entries (?account,
+// limit) the journal, newest first. Both need an admin API key. Account
+// owners read their own movements as monthly statements (statements.rs).
+
+use anyhow::Result;
+use miden_client::{account::AccountId, asset::Asset};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{account_id_to_hex, MidenClientWrapper};
+
+/// Ledger account adjustments are posted against.
+pub const SUSPENSE_ACCOUNT: &str = "suspense";
+
+/// Ledger account of what a faucet has minted through the service.
+pub fn issuance_account(faucet_id: &str) -> String {
+    format!("issuance:{}", faucet_id)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Debit,
+    Credit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Posting {
+    pub account: String,
+    pub faucet_id: String,
+    pub side: Side,
+    pub amount: u64,
+}
+
+impl Posting {
+    pub fn debit(account: impl Into<String>, faucet_id: &str, amount: u64) -> Self {
+        Self {
+            account: account.into(),
+            faucet_id: faucet_id.to_string(),
+            side: Side::Debit,
+            amount,
+        }
+    }
+
+    pub fn credit(account: impl Into<String>, faucet_id: &str, amount: u64) -> Self {
+        Self {
+            account: account.into(),
+            faucet_id: faucet_id.to_string(),
+            side: Side::Credit,
+            amount,
+        }
+    }
+
+    /// `amount` moving from one account to another.
+    pub fn transfer(from: &str, to: &str, faucet_id: &str, amount: u64) -> [Posting; 2] {
+        [
+            Posting::credit(from, faucet_id, amount),
+            Posting::debit(to, faucet_id, amount),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalKind {
+    Mint,
+    Transfer,
+    Payment,
+    EscrowFunding,
+    EscrowRelease,
+    EscrowRefund,
+    EscrowSplit,
+    TreasuryWithdrawal,
+    Recovery,
+    /// One leg of a token swap (swaps.rs) or share trade (order_book.rs)
+    Swap,
+    /// A property token sent back to its faucet to be burned
+    /// (property_retirement.rs)
+    Burn,
+    /// Posted by reconciliation to match the chain
+    Adjustment,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub entry_id: u64,
+    pub kind: JournalKind,
+    pub postings: Vec<Posting>,
+    /// None for adjustments
+    pub tx_id: Option<String>,
+    /// Escrow, property or account the movement was for
+    pub reference: Option<String>,
+    pub at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrialBalanceRow {
+    pub account: String,
+    pub faucet_id: String,
+    pub debits: u64,
+    pub credits: u64,
+    /// Debits minus credits
+    pub balance: i128,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AssetTotals {
+    pub faucet_id: String,
+    pub debits: u64,
+    pub credits: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrialBalance {
+    pub as_of: Option<i64>,
+    pub entries: usize,
+    pub rows: Vec<TrialBalanceRow>,
+    pub totals: Vec<AssetTotals>,
+    /// Debits equal credits for every asset
+    pub balanced: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GeneralLedger {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    entries: Vec<JournalEntry>,
+    #[serde(default)]
+    next_entry_id: u64,
+}
+
+impl GeneralLedger {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<GeneralLedger>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            GeneralLedger::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Journals balanced postings; postings of zero are left out.
+    pub fn post(
+        &mut self,
+        kind: JournalKind,
+        postings: Vec<Posting>,
+        tx_id: Option<String>,
+        reference: Option<String>,
+        at: i64,
+    ) -> Result<JournalEntry> {
+        let postings: Vec<Posting> = postings.into_iter().filter(|p| p.amount > 0).collect();
+        if postings.is_empty() {
+            return Err(anyhow::anyhow!("Entry has no postings"));
+        }
+        let mut net: BTreeMap<&str, i128> = BTreeMap::new();
+        for posting in &postings {
+            *net.entry(&posting.faucet_id).or_default() += match posting.side {
+                Side::Debit => posting.amount as i128,
+                Side::Credit => -(posting.amount as i128),
+            };
+        }
+        if let Some((faucet_id, difference)) = net.into_iter().find(|(_, n)| *n != 0) {
+            return Err(anyhow::anyhow!(
+                "Entry does not balance: debits and credits of {} differ by {}",
+                faucet_id,
+                difference
+            ));
+        }
+
+        self.next_entry_id += 1;
+        let entry = JournalEntry {
+            entry_id: self.next_entry_id,
+            kind,
+            postings,
+            tx_id,
+            reference,
+            at,
+        };
+        self.entries.push(entry.clone());
+        self.save()?;
+        Ok(entry)
+    }
+
+    /// Most recent entries first, optionally only those posting to `account`.
+    pub fn entries(&self, account: Option<&str>, limit: usize) -> Vec<&JournalEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(|e| {
+                account.is_none_or(|a| e.postings.iter().any(|p| p.account.eq_ignore_ascii_case(a)))
+            })
+            .take(limit)
+            .collect()
+    }
+
+    /// Debits and credits per account and asset of entries up to `as_of`.
+    pub fn trial_balance(&self, as_of: Option<i64>) -> TrialBalance {
+        let mut rows: BTreeMap<(String, String), TrialBalanceRow> = BTreeMap::new();
+        let mut totals: BTreeMap<String, AssetTotals> = BTreeMap::new();
+        let entries: Vec<&JournalEntry> = self
+            .entries
+            .iter()
+            .filter(|e| as_of.is_none_or(|as_of| e.at <= as_of))
+            .collect();
+
+        for posting in entries.iter().flat_map(|e| &e.postings) {
+            let row = rows
+                .entry((posting.account.clone(), posting.faucet_id.clone()))
+                .or_insert_with(|| TrialBalanceRow {
+                    account: posting.account.clone(),
+                    faucet_id: posting.faucet_id.clone(),
+                    debits: 0,
+                    credits: 0,
+                    balance: 0,
+                });
+            let total = totals
+                .entry(posting.faucet_id.clone())
+                .or_insert_with(|| AssetTotals {
+                    faucet_id: posting.faucet_id.clone(),
+                    debits: 0,
+                    credits: 0,
+                });
+            match posting.side {
+                Side::Debit => {
+                    row.debits += posting.amount;
+                    row.balance += posting.amount as i128;
+                    total.debits += posting.amount;
+                }
+                Side::Credit => {
+                    row.credits += posting.amount;
+                    row.balance -= posting.amount as i128;
+                    total.credits += posting.amount;
+                }
+            }
+        }
+
+        let totals: Vec<AssetTotals> = totals.into_values().collect();
+        TrialBalance {
+            as_of,
+            entries: entries.len(),
+            rows: rows.into_values().collect(),
+            balanced: totals.iter().all(|t| t.debits == t.credits),
+            totals,
+        }
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    GetTrialBalance {
+        as_of: Option<i64>,
+        api_key: Option<String>,
+    } -> TrialBalance;
+    |client, op| client.trial_balance(op.as_of, op.api_key.as_deref());
+
+    ListLedgerEntries {
+        account: Option<String>,
+        limit: usize,
+        api_key: Option<String>,
+    } -> Vec<JournalEntry>;
+    |client, op| client.ledger_entries(op.account.as_deref(), op.limit, op.api_key.as_deref())
+}
+
+impl MidenClientWrapper {
+    /// Journals a movement that was submitted. The funds have already moved,
+    /// so a ledger failure is logged rather than returned.
+    pub(crate) fn post_ledger_entry(
+        &mut self,
+        kind: JournalKind,
+        postings: Vec<Posting>,
+        tx_id: &str,
+        reference: Option<&str>,
+    ) {
+        let result = self.ledger.post(
+            kind,
+            postings,
+            Some(tx_id.to_string()),
+            reference.map(str::to_string),
+            chrono::Utc::now().timestamp(),
+        );
+        if let Err(e) = result {
+            tracing::error!(
+                "❌ Could not journal {:?} of tx {} in the ledger: {}",
+                kind,
+                tx_id,
+                e
+            );
+        }
+    }
+
+    pub fn trial_balance(&self, as_of: Option<i64>, api_key: Option<&str>) -> Result<TrialBalance> {
+        self.admin_principal(api_key, "the ledger")?;
+        Ok(self.ledger.trial_balance(as_of))
+    }
+
+    pub fn ledger_entries(
+        &self,
+        account: Option<&str>,
+        limit: usize,
+        api_key: Option<&str>,
+    ) -> Result<Vec<JournalEntry>> {
+        self.admin_principal(api_key, "the ledger")?;
+        let account = account.map(|a| self.account_hex(a)).transpose()?;
+        Ok(self
+            .ledger
+            .entries(account.as_deref(), limit)
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+
+    /// Ledger balances per (account, asset) of the posted on-chain accounts.
+    pub(crate) fn ledger_balances(&self) -> Vec<TrialBalanceRow> {
+        self.ledger
+            .trial_balance(None)
+            .rows
+            .into_iter()
+            .filter(|row| row.account.starts_with("0x"))
+            .collect()
+    }
+
+    /// Posts `difference` (chain minus ledger) of an account as an adjustment
+    /// against the suspense account.
+    pub(crate) fn post_ledger_adjustment(
+        &mut self,
+        account: &str,
+        faucet_id: &str,
+        difference: i128,
+    ) -> Result<JournalEntry> {
+        let amount = u64::try_from(difference.unsigned_abs())?;
+        let postings = if difference > 0 {
+            Posting::transfer(SUSPENSE_ACCOUNT, account, faucet_id, amount)
+        } else {
+            Posting::transfer(account, SUSPENSE_ACCOUNT, faucet_id, amount)
+        };
+        self.ledger.post(
+            JournalKind::Adjustment,
+            postings.to_vec(),
+            None,
+            Some(account.to_string()),
+            chrono::Utc::now().timestamp(),
+        )
+    }
+}
+
+/// Postings moving the fungible ones among `assets` from one account to
+/// another.
+pub(crate) fn asset_postings(from: AccountId, to: AccountId, assets: &[Asset]) -> Vec<Posting> {
+    let (from, to) = (account_id_to_hex(from), account_id_to_hex(to));
+    assets
+        .iter()
+        .filter_map(|asset| match asset {
+            Asset::Fungible(fungible) => Some(Posting::transfer(
+                &from,
+                &to,
+                &account_id_to_hex(fungible.faucet_id()),
+                fungible.amount(),
+            )),
+            Asset::NonFungible(_) => None,
+        })
+        .flatten()
+        .collect()
+}
@@ -13,34 +13,349 @@
 // - Some operations include waits to account for network finality
 // - Bob receives initial token balance for escrow/purchasing
 
+pub mod account_upgrades;
+pub mod accreditation_rules;
+pub mod allowances;
+pub mod api_version;
+pub mod balance_history;
+pub mod approvals;
+pub mod attachments;
+pub mod auctions;
+pub mod bench;
+pub mod billing;
+pub mod body_limits;
+pub mod client;
+pub mod commands;
+pub mod confidential_listings;
+pub mod config;
+pub mod consume_batches;
+pub mod contract_anchors;
+pub mod currency;
+pub mod custodial;
+pub mod data_subjects;
+pub mod deadlines;
+pub mod demo;
+pub mod documents;
 pub mod escrow;
+pub mod escrow_monitor;
+pub mod escrow_timeline;
+pub mod escrow_yield;
+pub mod esign;
+pub mod etag;
+#[cfg(feature = "fault-injection")]
+pub mod faults;
+pub mod feature_flags;
+pub mod fee_splits;
+pub mod field_encryption;
+pub mod geo;
+pub mod hooks;
+pub mod http_security;
+pub mod identity;
+pub mod installments;
+pub mod jurisdiction_lists;
+pub mod leases;
+pub mod ledger;
+pub mod liens;
+pub mod listing;
+pub mod localnet;
+pub mod logging;
+pub mod media;
+pub mod mint_coalescing;
+pub mod mint_jobs;
+pub mod negotiation;
+pub mod notary;
+pub mod note_files;
+pub mod note_inspection;
+pub mod note_quarantine;
+pub mod offline_queue;
+pub mod order_book;
+pub mod org_feed;
+pub mod organizations;
+pub mod pdf;
+pub mod portal;
+pub mod portfolio;
+pub mod principals;
+pub mod professionals;
+pub mod property_recovery;
+pub mod property_registry;
+pub mod property_retirement;
+pub mod proof_cache;
+pub mod queue_stats;
+pub mod read_cache;
+pub mod read_replica;
+pub mod reconcile;
+pub mod recovery;
+pub mod records;
+pub mod retry_queue;
+pub mod rpc_failover;
+pub mod sagas;
+pub mod scheduler;
+pub mod scripts;
+pub mod search;
+pub mod secrets;
+pub mod seed;
+pub mod settlement_tokens;
+pub mod snapshots;
+pub mod slo;
+pub mod spend_receipts;
+pub mod startup;
+pub mod statements;
+pub mod store_maintenance;
+pub mod subscriptions;
+pub mod swaps;
+pub mod sync_deltas;
+pub mod tax;
+pub mod timestamps;
+pub mod tls;
+pub mod treasury;
+pub mod validation;
+pub mod wallet_sessions;
+pub mod withholding;
+pub mod zk_programs;
 
 use anyhow::Result;
-use rand::RngCore;
+use rand::{RngCore, SeedableRng};
 use std::{path::PathBuf, sync::Arc};
+use tokio_util::sync::CancellationToken;
 
 use miden_client::{
-    account::{
-        component::{BasicFungibleFaucet, BasicWallet},
-        AccountBuilder, AccountId, AccountStorageMode, AccountType,
-    },
-    asset::{FungibleAsset, TokenSymbol},
+    account::{Account, AccountId},
+    asset::{Asset, FungibleAsset},
     auth::AuthSecretKey,
-    builder::ClientBuilder,
     crypto::rpo_falcon512::SecretKey,
-    keystore::FilesystemKeyStore,
-    note::{create_p2id_note, NoteType},
-    rpc::Endpoint,
     store::Store,
-    transaction::{OutputNote, TransactionRequestBuilder},
-    Client, ClientRng, Felt, Word,
+    ClientRng, Felt, Word,
 };
 use miden_client_sqlite_store::SqliteStore;
-use miden_lib::account::auth::AuthRpoFalcon512;
-use miden_objects::account::AccountIdVersion;
 
-/// Concrete client type used throughout the wrapper
-type MidenClient = Client<FilesystemKeyStore<rand::prelude::StdRng>>;
+use crate::{
+    accreditation_rules::RuleStore,
+    allowances::AllowanceStore,
+    approvals::ApprovalStore,
+    attachments::AttachmentStore,
+    auctions::AuctionStore,
+    balance_history::BalanceHistory,
+    billing::{InvoiceStore, UsageMeter},
+    client::{MidenClient, ServiceKeyStore},
+    confidential_listings::ConfidentialListingStore,
+    config::ServiceConfig,
+    consume_batches::ConsumedNotes,
+    contract_anchors::{anchor_serial_num, ContractAnchor},
+    currency::{Money, PriceInput, TokenAmount, SERVICE_TOKEN_DECIMALS, SERVICE_TOKEN_SYMBOL},
+    custodial::CustodialStore,
+    data_subjects::DataSubjectLog,
+    deadlines::JobCancellations,
+    documents::{DocumentStore, DocumentTemplates},
+    escrow::EscrowAuthError,
+    escrow_monitor::EscrowMonitor,
+    esign::SignatureStore,
+    feature_flags::{FeatureFlag, FeatureFlags},
+    field_encryption::FieldCipher,
+    hooks::{HookMetadata, HookPoint, HookStore},
+    identity::{AttributeClaim, IdentityCredential, ProviderRegistry},
+    installments::InstallmentStore,
+    jurisdiction_lists::{JurisdictionListStore, ListUpdate},
+    leases::LeaseStore,
+    ledger::{asset_postings, issuance_account, GeneralLedger, JournalKind, Posting},
+    liens::{LienAction, LienStore},
+    media::MediaStore,
+    mint_jobs::MintJobStore,
+    negotiation::OfferStore,
+    notary::{normalize_document_hash, NotaryStore},
+    note_quarantine::NoteQuarantine,
+    offline_queue::OfflineQueue,
+    order_book::OrderBook,
+    organizations::OrganizationStore,
+    portal::PortalAccessStore,
+    principals::{ApiKeyInput, Principal, PrincipalStore},
+    professionals::ProfessionalRegistry,
+    proof_cache::ProofCache,
+    property_registry::property_mint_serial_num,
+    read_cache::Touched,
+    records::{PropertyRecord, ServiceRecords},
+    retry_queue::{RetryOperation, RetryQueue},
+    rpc_failover::RpcPool,
+    sagas::SagaStore,
+    search::{ListingDetailsStore, SearchIndex},
+    secrets::MasterSecret,
+    seed::DeterministicSeeds,
+    settlement_tokens::SettlementTokenStore,
+    snapshots::SnapshotIndex,
+    startup::{StartupProgress, StartupStage},
+    store_maintenance::MaintenanceState,
+    subscriptions::SubscriptionStore,
+    swaps::SwapStore,
+    sync_deltas::SyncDeltas,
+    tax::TaxLedger,
+    treasury::TreasuryLedger,
+    wallet_sessions::WalletSessionStore,
+    withholding::WithholdingRuleStore,
+    zk_programs::ProgramRegistry,
+};
+
+/// Client held by the wrapper; wrapped for failure injection in test builds
+/// (see faults.rs)
+#[cfg(feature = "fault-injection")]
+type ServiceClient = faults::FaultyClient;
+#[cfg(not(feature = "fault-injection"))]
+type ServiceClient = MidenClient;
+
+/// Proof families backed by the ZK program registry (src/zk_programs.rs).
+///
+/// Proofs are generated with the family's active program; the program ID is
+/// part of the proof cache key, so an upgrade never serves stale proofs.
+pub use obscura_proof_verifier::{ACCREDITATION_FAMILY, JURISDICTION_FAMILY, OWNERSHIP_FAMILY};
+pub const IDENTITY_FAMILY: &str = "identity";
+
+/// Faucet tokens minted for each property (fixed in this implementation).
+pub(crate) const PROPERTY_MINT_AMOUNT: u64 = 100;
+
+/// Formats an AccountId as 0x-prefixed hex of its serialized bytes.
+///
+/// This is the same encoding the escrow endpoints accept and return.
+pub fn account_id_to_hex(account_id: AccountId) -> String {
+    use miden_client::Serializable;
+    format!("0x{}", hex::encode(account_id.to_bytes()))
+}
+
+/// Produces the init seed and signing key for an account alias.
+///
+/// In deterministic demo mode both are derived from the demo master seed, with
+/// a master secret (secrets.rs) from its entropy; otherwise they come from the
+/// client RNG.
+fn account_seed_material(
+    client: &mut MidenClient,
+    demo_seeds: Option<&DeterministicSeeds>,
+    master_secret: Option<&mut MasterSecret>,
+    alias: &str,
+) -> Result<([u8; 32], SecretKey)> {
+    let derived = match (demo_seeds, master_secret) {
+        (Some(seeds), _) => Some((seeds.account_init_seed(alias), seeds.auth_key_seed(alias))),
+        (None, Some(secret)) => Some(secret.account_seeds(alias)?),
+        (None, None) => None,
+    };
+    Ok(match derived {
+        Some((init_seed, auth_key_seed)) => {
+            let mut key_rng = rand_chacha::ChaCha20Rng::from_seed(auth_key_seed);
+            (init_seed, SecretKey::with_rng(&mut key_rng))
+        }
+        None => {
+            let mut init_seed = [0_u8; 32];
+            client.rng().fill_bytes(&mut init_seed);
+            (init_seed, SecretKey::with_rng(client.rng()))
+        }
+    })
+}
+
+/// Adds an account and its signing key to the client.
+///
+/// An account already present in the store (deterministic restarts) is reused.
+async fn register_account(
+    client: &mut MidenClient,
+    keystore: &ServiceKeyStore,
+    account: &Account,
+    key_pair: SecretKey,
+) -> Result<()> {
+    if client.get_account(account.id()).await?.is_some() {
+        tracing::info!("Account {} already in store; reusing", account.id());
+    } else {
+        client.add_account(account, false).await?;
+    }
+
+    keystore.add_key(&AuthSecretKey::RpoFalcon512(key_pair))?;
+    Ok(())
+}
+
+/// Creates (or, on deterministic restarts, reuses) the alice, bob and faucet
+/// accounts. Returns their IDs in that order.
+async fn create_service_accounts(
+    client: &mut MidenClient,
+    keystore: &ServiceKeyStore,
+    demo_seeds: Option<&DeterministicSeeds>,
+    mut master_secret: Option<&mut MasterSecret>,
+    progress: &StartupProgress,
+) -> Result<[AccountId; 3]> {
+    // ---------------------------------------------------------------------
+    // Alice wallet
+    // ---------------------------------------------------------------------
+    progress.advance(
+        StartupStage::CreatingAccounts,
+        0,
+        3,
+        "creating account alice".to_string(),
+    );
+    tracing::info!("Creating Alice wallet account");
+
+    let (init_seed, key_pair) =
+        account_seed_material(client, demo_seeds, master_secret.as_deref_mut(), "alice")?;
+
+    let alice_account = client::wallet_account(init_seed, &key_pair)?;
+    let alice_account_id = alice_account.id();
+
+    register_account(client, keystore, &alice_account, key_pair).await?;
+
+    tracing::info!("Alice account: {}", alice_account_id.to_string());
+
+    // ---------------------------------------------------------------------
+    // Bob wallet
+    // ---------------------------------------------------------------------
+    progress.advance(
+        StartupStage::CreatingAccounts,
+        1,
+        3,
+        "creating account bob".to_string(),
+    );
+    tracing::info!("Creating Bob wallet account");
+
+    let (init_seed, bob_key_pair) =
+        account_seed_material(client, demo_seeds, master_secret.as_deref_mut(), "bob")?;
+
+    let bob_account = client::wallet_account(init_seed, &bob_key_pair)?;
+    let bob_account_id = bob_account.id();
+
+    register_account(client, keystore, &bob_account, bob_key_pair).await?;
+
+    tracing::info!("Bob account: {}", bob_account_id.to_string());
+
+    // ---------------------------------------------------------------------
+    // Faucet (PROP token issuer)
+    // ---------------------------------------------------------------------
+    progress.advance(
+        StartupStage::CreatingAccounts,
+        2,
+        3,
+        "creating account faucet".to_string(),
+    );
+    tracing::info!("Creating Property Token Faucet");
+
+    let (init_seed, key_pair) =
+        account_seed_material(client, demo_seeds, master_secret.as_deref_mut(), "faucet")?;
+
+    let faucet_account = client::faucet_account(
+        init_seed,
+        &key_pair,
+        SERVICE_TOKEN_SYMBOL,
+        SERVICE_TOKEN_DECIMALS,
+        1_000_000,
+    )?;
+    let faucet_account_id = faucet_account.id();
+
+    register_account(client, keystore, &faucet_account, key_pair).await?;
+
+    tracing::info!("Faucet account: {}", faucet_account_id.to_string());
+
+    if let Some(secret) = master_secret.as_deref_mut() {
+        for (alias, account_id) in [
+            ("alice", alice_account_id),
+            ("bob", bob_account_id),
+            ("faucet", faucet_account_id),
+        ] {
+            secret.bind_account(alias, &account_id_to_hex(account_id))?;
+        }
+    }
+
+    Ok([alice_account_id, bob_account_id, faucet_account_id])
+}
 
 /// Wrapper over Miden client lifecycle and common business actions.
 ///
@@ -51,208 +366,619 @@ type MidenClient = Client<FilesystemKeyStore<rand::prelude::StdRng>>;
 /// - Minting assets, listing consumable notes, consuming notes
 /// - Creating P2ID notes for transfers/payments
 /// - Demo ZK proof endpoints (accreditation, ownership, jurisdiction)
+/// - Persisting service records for reconciliation
 pub struct MidenClientWrapper {
-    client: MidenClient,
-    pub keystore: FilesystemKeyStore<rand::prelude::StdRng>,
+    client: ServiceClient,
+    /// Kept to rebuild the client on another RPC endpoint (rpc_failover.rs)
+    store: Arc<dyn Store>,
+    rpc: RpcPool,
+    /// None on a read replica (read_replica.rs)
+    pub keystore: Option<ServiceKeyStore>,
     rng: ClientRng,
     alice_account_id: Option<AccountId>,
     bob_account_id: Option<AccountId>,
     faucet_account_id: Option<AccountId>,
+    records: ServiceRecords,
+    proof_cache: ProofCache,
+    zk_programs: ProgramRegistry,
+    accreditation_rules: RuleStore,
+    jurisdiction_lists: JurisdictionListStore,
+    identity_providers: ProviderRegistry,
+    mint_jobs: MintJobStore,
+    /// Funding, purchase and tokenization flows (sagas.rs)
+    sagas: SagaStore,
+    retries: RetryQueue,
+    /// Requests waiting for the node in offline mode (offline_queue.rs)
+    offline_queue: OfflineQueue,
+    installments: InstallmentStore,
+    liens: LienStore,
+    approvals: ApprovalStore,
+    escrow_monitor: EscrowMonitor,
+    treasury: TreasuryLedger,
+    /// Double-entry journal of asset movements (ledger.rs)
+    ledger: GeneralLedger,
+    data_subjects: DataSubjectLog,
+    /// Spam notes held back from listings and sweeps (note_quarantine.rs)
+    note_quarantine: NoteQuarantine,
+    leases: LeaseStore,
+    auctions: AuctionStore,
+    offers: OfferStore,
+    confidential_listings: ConfidentialListingStore,
+    order_book: OrderBook,
+    tax: TaxLedger,
+    withholding_rules: WithholdingRuleStore,
+    attachments: AttachmentStore,
+    professionals: ProfessionalRegistry,
+    notary: NotaryStore,
+    wallet_sessions: WalletSessionStore,
+    /// Email-onboarded users and their wallets (custodial.rs)
+    custodial: CustodialStore,
+    allowances: AllowanceStore,
+    principals: PrincipalStore,
+    /// Tokens of the read-only customer portal (portal.rs)
+    portal_access: PortalAccessStore,
+    /// Organizations the principals belong to (organizations.rs)
+    organizations: OrganizationStore,
+    subscriptions: SubscriptionStore,
+    /// Webhooks and WASM modules run at lifecycle points (hooks.rs)
+    hooks: HookStore,
+    /// Shared with the route gate and the subscription dispatcher
+    features: FeatureFlags,
+    /// Usage per API key, shared with the metering middleware (billing.rs)
+    usage_meter: UsageMeter,
+    invoices: InvoiceStore,
+    listing_details: ListingDetailsStore,
+    /// Registered settlement tokens and the one each listing settles in
+    settlement_tokens: SettlementTokenStore,
+    /// Swap quotes and their fills (swaps.rs)
+    swaps: SwapStore,
+    /// Full-text and range index over properties (search.rs)
+    search_index: SearchIndex,
+    media: MediaStore,
+    /// Generated closing documents and the templates they come from
+    documents: DocumentStore,
+    document_templates: DocumentTemplates,
+    /// E-signature envelopes of closing documents (esign.rs)
+    signatures: SignatureStore,
+    /// Set when account seeds derive from a master secret (secrets.rs)
+    master_secret: Option<MasterSecret>,
+    /// Last sync performed by a read path (see etag.rs)
+    last_read_sync: Option<std::time::Instant>,
+    /// What the last sync changed (sync_deltas.rs)
+    sync_deltas: SyncDeltas,
+    /// Balance changes seen by syncs (balance_history.rs)
+    balance_history: BalanceHistory,
+    /// Last client store pruning and vacuum (store_maintenance.rs)
+    store_maintenance: MaintenanceState,
+    /// Published store snapshots (snapshots.rs)
+    snapshots: SnapshotIndex,
+    /// Tokens of cancellable jobs, shared with handlers (see deadlines.rs)
+    job_cancellations: JobCancellations,
+    /// Cancellation token of the command being run
+    cancellation: Option<CancellationToken>,
+    /// Whether the command being run opted in to offline mode
+    offline_opt_in: bool,
+    /// Modification time of the records file last loaded (read_replica.rs)
+    replica_records_modified: Option<std::time::SystemTime>,
+    config: ServiceConfig,
+}
+
+// Operations on the accounts, notes and proofs below (see commands.rs)
+crate::operations! {
+    MintProperty {
+        property_id: String,
+        owner_account_id: String,
+        ipfs_cid: String,
+        property_type: u8,
+        price: PriceInput,
+    } -> (String, String);
+    touched: |op| Some(Touched::Accounts(vec![op.owner_account_id.clone(), "faucet".to_string()]));
+    |client, op| {
+        let result = client
+            .mint_property_nft(
+                &op.property_id,
+                &op.owner_account_id,
+                &op.ipfs_cid,
+                op.property_type,
+                &op.price,
+            )
+            .await;
+        let operation = RetryOperation::MintProperty {
+            property_id: op.property_id,
+            owner_account_id: op.owner_account_id,
+            ipfs_cid: op.ipfs_cid,
+            property_type: op.property_type,
+            price: op.price,
+        };
+        client.retry_on_failure(operation, result)
+    };
+
+    GetAccountInfo {} -> serde_json::Value;
+    |client, _op| client.get_account_info().await;
+
+    GetConsumableNotes {
+        account_id: Option<String>,
+    } -> Vec<serde_json::Value>;
+    |client, op| client.get_consumable_notes(op.account_id).await;
+
+    ConsumeNote {
+        note_id: String,
+        account_id: Option<String>,
+    } -> ConsumedNotes;
+    touched: |op| {
+        let account = op.account_id.clone().unwrap_or_else(|| "alice".to_string());
+        Some(Touched::Accounts(vec![account]))
+    };
+    |client, op| client.consume_notes(&op.note_id, op.account_id).await;
+
+    TransferProperty {
+        property_id: String,
+        to_account_id: String,
+        contract_hash: Option<String>,
+    } -> (String, Option<ContractAnchor>);
+    touched: |op| Some(Touched::Accounts(vec![op.to_account_id.clone(), "alice".to_string()]));
+    |client, op| {
+        let result = client
+            .transfer_property_under_contract(
+                &op.property_id,
+                &op.to_account_id,
+                op.contract_hash.as_deref(),
+            )
+            .await;
+        let operation = RetryOperation::TransferProperty {
+            property_id: op.property_id,
+            to_account_id: op.to_account_id,
+            contract_hash: op.contract_hash,
+        };
+        let tx_id = client.retry_on_failure(operation, result)?;
+        let anchor = client.contract_anchor_of_tx(&tx_id);
+        Ok((tx_id, anchor))
+    };
+
+    SendTokens {
+        to_account_id: String,
+        amount: u64,
+    } -> String;
+    touched: |op| Some(Touched::Accounts(vec![op.to_account_id.clone(), "alice".to_string()]));
+    |client, op| client.send_tokens(&op.to_account_id, op.amount).await;
+
+    GetBalance {
+        account_id: String,
+    } -> serde_json::Value;
+    |client, op| client.get_account_balance(&op.account_id).await;
+
+    GenerateAccreditationProof {
+        net_worth: u64,
+        jurisdiction: Option<String>,
+        listing_class: Option<String>,
+    } -> serde_json::Value;
+    |client, op| {
+        client
+            .generate_accreditation_proof(op.net_worth, op.jurisdiction, op.listing_class)
+            .await
+    };
+
+    VerifyAccreditationProof {
+        proof: String,
+        program_hash: String,
+        public_inputs: Vec<u64>,
+    } -> serde_json::Value;
+    |client, op| {
+        client
+            .verify_accreditation_proof(&op.proof, &op.program_hash, op.public_inputs)
+            .await
+    };
+
+    GenerateJurisdictionProof {
+        country_code: String,
+    } -> serde_json::Value;
+    |client, op| client.generate_jurisdiction_proof(&op.country_code).await;
+
+    VerifyJurisdictionProof {
+        proof: String,
+        program_hash: String,
+        public_inputs: Vec<u64>,
+    } -> serde_json::Value;
+    |client, op| {
+        client
+            .verify_jurisdiction_proof(&op.proof, &op.program_hash, op.public_inputs)
+            .await
+    };
+
+    GenerateOwnershipProof {
+        property_id: String,
+        document_hash: String,
+        report_ids: Vec<u64>,
+    } -> serde_json::Value;
+    |client, op| {
+        client
+            .generate_ownership_proof(&op.property_id, &op.document_hash, &op.report_ids)
+            .await
+    };
+
+    VerifyOwnershipProof {
+        proof: String,
+        program_hash: String,
+        public_inputs: Vec<String>,
+    } -> serde_json::Value;
+    |client, op| client.verify_ownership_proof(&op.proof, &op.program_hash, op.public_inputs).await;
+
+    GenerateIdentityProof {
+        credential: IdentityCredential,
+        claim: AttributeClaim,
+    } -> serde_json::Value;
+    |client, op| client.generate_identity_proof(op.credential, op.claim).await;
+
+    VerifyIdentityProof {
+        proof: String,
+        program_hash: String,
+        public_inputs: Vec<u64>,
+    } -> serde_json::Value;
+    |client, op| client.verify_identity_proof(&op.proof, &op.program_hash, op.public_inputs).await;
+
+    GetProofCacheStats {} -> serde_json::Value;
+    |client, _op| client.get_proof_cache_stats();
+
+    InvalidateProofCache {
+        program: Option<String>,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.invalidate_proof_cache(op.program, op.api_key.as_deref());
+
+    ListZkPrograms {} -> serde_json::Value;
+    |client, _op| client.list_zk_programs();
+
+    ListJurisdictionLists {} -> serde_json::Value;
+    |client, _op| client.list_jurisdiction_lists();
+
+    PublishJurisdictionList {
+        update: ListUpdate,
+    } -> serde_json::Value;
+    |client, op| client.publish_jurisdiction_list(op.update);
+
+    ListApiKeys {} -> serde_json::Value;
+    |client, _op| client.list_api_keys();
+
+    IssueApiKey {
+        input: ApiKeyInput,
+    } -> serde_json::Value;
+    |client, op| client.issue_api_key(op.input);
+
+    RevokeApiKey {
+        key_id: u64,
+    } -> serde_json::Value;
+    |client, op| client.revoke_api_key(op.key_id)
 }
 
 impl MidenClientWrapper {
     /// Initializes the client, store, keystore, and creates the three accounts.
     ///
-    /// This performs a network sync and persists local state:
+    /// This performs a network sync and persists local state at the configured
+    /// paths (defaults):
     /// - ./keystore
     /// - ./store.sqlite3
+    /// - ./service-records.json
     ///
     /// NEW: Automatically mints tokens for Bob so funds are available for escrow
-    pub async fn new() -> Result<Self> {
-        tracing::info!("Initializing Miden client wrapper (v0.12)");
+    /// (and for Alice too under the localnet profile)
+    ///
+    /// Each stage is reported to `progress` (see startup.rs). The client
+    /// connects to the first of `rpc`'s endpoints that answers.
+    ///
+    /// A read replica (read_replica.rs) skips the keystore, account creation
+    /// and funding; funding is also skipped while the auto_funding feature is
+    /// off (feature_flags.rs).
+    pub async fn new(
+        config: &ServiceConfig,
+        progress: &StartupProgress,
+        rpc: RpcPool,
+        features: FeatureFlags,
+        usage_meter: UsageMeter,
+    ) -> Result<Self> {
+        tracing::info!(
+            "Initializing Miden client wrapper (v{}, profile: {})",
+            client::MIDEN_CLIENT_VERSION,
+            config.profile.as_str()
+        );
 
-        // Create keystore (filesystem-backed)
-        let keystore: FilesystemKeyStore<rand::prelude::StdRng> =
-            FilesystemKeyStore::new("./keystore".into())?;
+        progress.advance(
+            StartupStage::Connecting,
+            0,
+            1,
+            format!("connecting to {}", config.rpc),
+        );
+
+        // Create keystore (filesystem-backed); a read replica has none
+        let keystore: Option<ServiceKeyStore> = if config.read_replica {
+            tracing::info!("📖 Read replica mode: no keystore, query endpoints only");
+            None
+        } else {
+            Some(ServiceKeyStore::new(config.keystore_path.clone())?)
+        };
+
+        // Start an empty store from a published snapshot (snapshots.rs)
+        snapshots::bootstrap(config, progress).await?;
 
         // Create SQLite store (persistent client state)
-        let store_path = PathBuf::from("./store.sqlite3");
+        let store_path = PathBuf::from(&config.store_path);
         let store = SqliteStore::new(store_path).await?;
         let store: Arc<dyn Store> = Arc::new(store);
 
+        // Load service records (operation journal, properties, escrows)
+        let records_modified = read_replica::records_modified_at(config);
+        let cipher = config
+            .field_encryption_key
+            .clone()
+            .map(|key| FieldCipher::load(config.data_keys_path.clone(), key))
+            .transpose()?;
+        let records = ServiceRecords::load(
+            config.records_path.clone(),
+            cipher,
+            config.confidential_listings,
+        )?;
+        let pending_ops = records.pending_operations().len();
+        if pending_ops > 0 {
+            tracing::warn!(
+                "⚠️  {} operation(s) were interrupted by the last shutdown; run POST /admin/reconcile",
+                pending_ops
+            );
+        }
+
         // Configure RPC endpoint
-        let endpoint = Endpoint::testnet();
-        let timeout_ms = 10_000;
+        let timeout_ms = config.rpc_timeout_ms;
+        let active = rpc.select_initial(timeout_ms).await;
+        let endpoint = rpc_failover::endpoint(&rpc.endpoints()[active]);
+        tracing::info!("Using RPC endpoint {}", rpc.endpoints()[active]);
 
         // Build client
-        let mut client = ClientBuilder::new()
-            .grpc_client(&endpoint, Some(timeout_ms))
-            .store(store)
-            .authenticator(keystore.clone().into())
-            .in_debug_mode(true.into())
-            .build()
-            .await?;
+        let mut client =
+            client::build_client(&endpoint, timeout_ms, store.clone(), keystore.as_ref()).await?;
 
         // Sync with network
-        let sync_summary = client.sync_state().await?;
+        let sync_summary =
+            startup::sync_with_progress(&mut client, &store, &endpoint, timeout_ms, progress)
+                .await?;
         tracing::info!("Client synced. Latest block: {}", sync_summary.block_num);
 
+        // Master secret for recoverable account seeds (secrets.rs)
+        let mut master_secret = config
+            .master_secret_key
+            .clone()
+            .map(|key| {
+                MasterSecret::load(
+                    config.master_secret_path.clone(),
+                    key,
+                    config.master_secret_previous_key.as_ref(),
+                )
+            })
+            .transpose()?;
+
         // Create ClientRng used for note creation and transactions
-        let mut seed_rng = rand::rng();
-        let coin_seed: Word = [
-            Felt::new(seed_rng.next_u64()),
-            Felt::new(seed_rng.next_u64()),
-            Felt::new(seed_rng.next_u64()),
-            Felt::new(seed_rng.next_u64()),
-        ]
-        .into();
+        let demo_seeds = config.demo_seeds.as_ref();
+        let coin_words = match (demo_seeds, &master_secret) {
+            (Some(seeds), _) => seeds.coin_seed(),
+            (None, Some(secret)) => secret.coin_seed()?,
+            (None, None) => {
+                let mut seed_rng = rand::rng();
+                [
+                    seed_rng.next_u64(),
+                    seed_rng.next_u64(),
+                    seed_rng.next_u64(),
+                    seed_rng.next_u64(),
+                ]
+            }
+        };
+        let coin_seed: Word = coin_words.map(Felt::new).into();
         let rng = ClientRng::new(Box::new(miden_client::crypto::RpoRandomCoin::new(coin_seed)));
 
-        // ---------------------------------------------------------------------
-        // Alice wallet
-        // ---------------------------------------------------------------------
-        tracing::info!("Creating Alice wallet account");
-
-        let mut init_seed = [0_u8; 32];
-        client.rng().fill_bytes(&mut init_seed);
-        let key_pair = SecretKey::with_rng(client.rng());
-
-        let builder = AccountBuilder::new(init_seed)
-            .account_type(AccountType::RegularAccountUpdatableCode)
-            .storage_mode(AccountStorageMode::Public)
-            .with_auth_component(AuthRpoFalcon512::new(key_pair.public_key().into()))
-            .with_component(BasicWallet);
-
-        let alice_account = builder.build()?;
-        let alice_account_id = alice_account.id();
-
-        client.add_account(&alice_account, false).await?;
-        keystore.add_key(&AuthSecretKey::RpoFalcon512(key_pair))?;
-
-        tracing::info!("Alice account: {}", alice_account_id.to_string());
-
-        // ---------------------------------------------------------------------
-        // Bob wallet
-        // ---------------------------------------------------------------------
-        tracing::info!("Creating Bob wallet account");
-
-        let mut init_seed = [0_u8; 32];
-        client.rng().fill_bytes(&mut init_seed);
-        let bob_key_pair = SecretKey::with_rng(client.rng());
-
-        let bob_builder = AccountBuilder::new(init_seed)
-            .account_type(AccountType::RegularAccountUpdatableCode)
-            .storage_mode(AccountStorageMode::Public)
-            .with_auth_component(AuthRpoFalcon512::new(bob_key_pair.public_key().into()))
-            .with_component(BasicWallet);
-
-        let bob_account = bob_builder.build()?;
-        let bob_account_id = bob_account.id();
-
-        client.add_account(&bob_account, false).await?;
-        keystore.add_key(&AuthSecretKey::RpoFalcon512(bob_key_pair))?;
-
-        tracing::info!("Bob account: {}", bob_account_id.to_string());
-
-        // ---------------------------------------------------------------------
-        // Faucet (PROP token issuer)
-        // ---------------------------------------------------------------------
-        tracing::info!("Creating Property Token Faucet");
-
-        let mut init_seed = [0u8; 32];
-        client.rng().fill_bytes(&mut init_seed);
-
-        let symbol = TokenSymbol::new("PROP")?;
-        let decimals = 8;
-        let max_supply = Felt::new(1_000_000);
-        let key_pair = SecretKey::with_rng(client.rng());
-
-        let builder = AccountBuilder::new(init_seed)
-            .account_type(AccountType::FungibleFaucet)
-            .storage_mode(AccountStorageMode::Public)
-            .with_auth_component(AuthRpoFalcon512::new(key_pair.public_key().into()))
-            .with_component(BasicFungibleFaucet::new(symbol, decimals, max_supply)?);
-
-        let faucet_account = builder.build()?;
-        let faucet_account_id = faucet_account.id();
-
-        client.add_account(&faucet_account, false).await?;
-        keystore.add_key(&AuthSecretKey::RpoFalcon512(key_pair))?;
+        if demo_seeds.is_some() {
+            tracing::warn!("⚠️  Deterministic demo mode: account IDs are stable across restarts");
+        }
+        if let Some(secret) = &master_secret {
+            tracing::info!(
+                "🔐 Account seeds derive from the master secret (generation {})",
+                secret.current_generation()
+            );
+        }
 
-        tracing::info!("Faucet account: {}", faucet_account_id.to_string());
+        let service_accounts = match &keystore {
+            Some(keystore) => {
+                let accounts = create_service_accounts(
+                    &mut client,
+                    keystore,
+                    demo_seeds,
+                    master_secret.as_mut(),
+                    progress,
+                )
+                .await?;
+
+                // Sync once after account creation
+                client.sync_state().await?;
+                Some(accounts)
+            }
+            None => None,
+        };
+        let (alice_account_id, bob_account_id, faucet_account_id) = match service_accounts {
+            Some([alice, bob, faucet]) => (Some(alice), Some(bob), Some(faucet)),
+            // Read replica: the primary's accounts, as configured
+            None => read_replica::named_accounts(config)?,
+        };
 
-        // Sync once after account creation
-        client.sync_state().await?;
+        #[cfg(feature = "fault-injection")]
+        let client = ServiceClient::from(client);
 
         let mut wrapper = Self {
             client,
+            store: store.clone(),
+            rpc,
             keystore,
             rng,
-            alice_account_id: Some(alice_account_id),
-            bob_account_id: Some(bob_account_id),
-            faucet_account_id: Some(faucet_account_id),
+            alice_account_id,
+            bob_account_id,
+            faucet_account_id,
+            records,
+            proof_cache: ProofCache::new(config.proof_cache_ttl, config.proof_cache_max_entries),
+            zk_programs: ProgramRegistry::builtin()?,
+            accreditation_rules: RuleStore::load(
+                config.accreditation_rules_path.clone(),
+                config.default_accreditation_threshold,
+            )?,
+            jurisdiction_lists: JurisdictionListStore::load(
+                config.jurisdiction_lists_path.clone(),
+                config.jurisdiction_list_signer,
+                &config.initial_restricted_jurisdictions,
+            )?,
+            identity_providers: ProviderRegistry::load(config.identity_providers_path.clone())?,
+            mint_jobs: MintJobStore::load(config.mint_jobs_path.clone())?,
+            sagas: SagaStore::load(config.sagas_path.clone())?,
+            retries: RetryQueue::load(config.retry_queue_path.clone())?,
+            offline_queue: OfflineQueue::load(config.offline_queue_path.clone())?,
+            installments: InstallmentStore::load(config.installments_path.clone())?,
+            liens: LienStore::load(config.liens_path.clone())?,
+            approvals: ApprovalStore::load(config.approvals_path.clone())?,
+            escrow_monitor: EscrowMonitor::load(config.escrow_monitor_path.clone())?,
+            treasury: TreasuryLedger::load(config.treasury_ledger_path.clone())?,
+            ledger: GeneralLedger::load(config.ledger_path.clone())?,
+            data_subjects: DataSubjectLog::load(config.data_subjects_path.clone())?,
+            note_quarantine: NoteQuarantine::load(config.note_quarantine_path.clone())?,
+            leases: LeaseStore::load(config.leases_path.clone())?,
+            auctions: AuctionStore::load(config.auctions_path.clone())?,
+            offers: OfferStore::load(config.offers_path.clone())?,
+            confidential_listings: ConfidentialListingStore::load(
+                config.confidential_listings_path.clone(),
+            )?,
+            order_book: OrderBook::load(config.order_book_path.clone())?,
+            tax: TaxLedger::load(config.tax_ledger_path.clone())?,
+            withholding_rules: WithholdingRuleStore::load(config.withholding_rules_path.clone())?,
+            attachments: AttachmentStore::load(config.attachments_path.clone())?,
+            professionals: ProfessionalRegistry::load(config.professionals_path.clone())?,
+            notary: NotaryStore::load(config.notary_path.clone())?,
+            wallet_sessions: WalletSessionStore::load(config.wallet_sessions_path.clone())?,
+            custodial: CustodialStore::load(config.custodial_users_path.clone())?,
+            allowances: AllowanceStore::load(config.allowances_path.clone())?,
+            principals: PrincipalStore::load(config.api_keys_path.clone())?
+                .with_bootstrap_key(config.admin_bootstrap_key.as_deref()),
+            portal_access: PortalAccessStore::load(config.portal_access_path.clone())?,
+            organizations: OrganizationStore::load(config.organizations_path.clone())?,
+            subscriptions: SubscriptionStore::load(config.subscriptions_path.clone())?,
+            hooks: HookStore::load(config.hooks_path.clone())?,
+            features,
+            usage_meter,
+            invoices: InvoiceStore::load(config.billing_path.clone())?,
+            listing_details: ListingDetailsStore::load(config.listing_details_path.clone())?,
+            settlement_tokens: SettlementTokenStore::load(config.settlement_tokens_path.clone())?,
+            swaps: SwapStore::load(config.swaps_path.clone())?,
+            search_index: SearchIndex::new()?,
+            media: MediaStore::load(config.media_path.clone())?,
+            documents: DocumentStore::load(
+                config.documents_path.clone(),
+                config.documents_dir.clone(),
+            )?,
+            document_templates: DocumentTemplates::load(
+                config.document_templates_dir.as_deref(),
+            )?,
+            signatures: SignatureStore::load(config.signatures_path.clone())?,
+            master_secret,
+            last_read_sync: None,
+            sync_deltas: SyncDeltas::default(),
+            balance_history: BalanceHistory::open(&config.balance_history_path)?,
+            store_maintenance: MaintenanceState::default(),
+            snapshots: SnapshotIndex::load(config.snapshot_dir.clone())?,
+            job_cancellations: JobCancellations::default(),
+            cancellation: None,
+            offline_opt_in: false,
+            replica_records_modified: records_modified,
+            config: config.clone(),
         };
 
+        let indexed = wrapper.rebuild_search_index()?;
+        tracing::info!("Search index built ({} properties)", indexed);
+        wrapper.refresh_org_accounts();
+
         // =====================================================================
-        // AUTO-FUND BOB WITH TOKENS FOR ESCROW OPERATIONS
+        // AUTO-FUND WALLETS WITH TOKENS FOR ESCROW OPERATIONS
         // =====================================================================
-        tracing::info!("🔄 Auto-funding Bob with tokens for escrow operations...");
-        
-        match wrapper.mint_tokens_for_bob().await {
+        // A read replica signs nothing
+        let Some([alice_account_id, bob_account_id, _]) = service_accounts else {
+            return Ok(wrapper);
+        };
+        if !wrapper.features.is_enabled(FeatureFlag::AutoFunding) {
+            tracing::info!("Auto-funding is turned off (feature flags); wallets not funded");
+            return Ok(wrapper);
+        }
+        let mut funded = vec![("bob", bob_account_id)];
+        if config.auto_fund_alice {
+            funded.push(("alice", alice_account_id));
+        }
+
+        let total = funded.len() as u64;
+        for (done, (name, account_id)) in funded.into_iter().enumerate() {
+            progress.advance(
+                StartupStage::Funding,
+                done as u64,
+                total,
+                format!("funding {}", name),
+            );
+            wrapper.auto_fund(name, account_id).await;
+        }
+
+        Ok(wrapper)
+    }
+
+    /// Mints the configured funding amount for a wallet and consumes it into the vault.
+    ///
+    /// Failures are logged, not returned: a wallet without funds only limits
+    /// escrow operations, it must not prevent the service from starting.
+    async fn auto_fund(&mut self, name: &str, account_id: AccountId) {
+        tracing::info!("🔄 Auto-funding {} with tokens for escrow operations...", name);
+
+        match self.mint_initial_funding(name, account_id).await {
             Ok((mint_tx_id, note_id)) => {
-                tracing::info!("✅ Bob initial funding successful");
+                tracing::info!("✅ {} initial funding successful", name);
                 tracing::info!("   Mint TX: {}", mint_tx_id);
                 tracing::info!("   Note ID: {}", note_id);
-                
-                // Consume the note into Bob's vault
-                tracing::info!("🔄 Consuming tokens into Bob's vault...");
-                match wrapper.consume_note(&note_id, Some("bob".to_string())).await {
+
+                // Consume the note into the wallet's vault
+                tracing::info!("🔄 Consuming tokens into {}'s vault...", name);
+                match self.consume_note(&note_id, Some(name.to_string())).await {
                     Ok(consume_tx_id) => {
-                        tracing::info!("✅ Tokens consumed into Bob's vault");
+                        tracing::info!("✅ Tokens consumed into {}'s vault", name);
                         tracing::info!("   Consume TX: {}", consume_tx_id);
-                        tracing::info!("💰 Bob is now ready for escrow operations!");
+                        tracing::info!("💰 {} is now ready for escrow operations!", name);
                     }
                     Err(e) => {
-                        tracing::warn!("⚠️  Failed to consume tokens into Bob's vault: {}", e);
-                        tracing::warn!("   Bob may need manual token consumption");
+                        tracing::warn!("⚠️  Failed to consume tokens into {}'s vault: {}", name, e);
+                        tracing::warn!("   {} may need manual token consumption", name);
                     }
                 }
             }
             Err(e) => {
-                tracing::warn!("⚠️  Failed to auto-fund Bob: {}", e);
-                tracing::warn!("   Bob may need manual funding for escrow operations");
+                tracing::warn!("⚠️  Failed to auto-fund {}: {}", name, e);
+                tracing::warn!("   {} may need manual funding for escrow operations", name);
             }
         }
-
-        Ok(wrapper)
     }
 
-    /// Mints tokens specifically for Bob during initialization.
+    /// Mints initial funding tokens for a wallet during initialization.
     ///
     /// Returns:
     /// - Transaction ID
     /// - Note ID (real when available, placeholder otherwise)
-    async fn mint_tokens_for_bob(&mut self) -> Result<(String, String)> {
-        let bob_account_id = self
-            .bob_account_id
-            .ok_or_else(|| anyhow::anyhow!("Bob not initialized"))?;
-        
+    async fn mint_initial_funding(
+        &mut self,
+        name: &str,
+        account_id: AccountId,
+    ) -> Result<(String, String)> {
         let faucet_account_id = self
             .faucet_account_id
             .ok_or_else(|| anyhow::anyhow!("Faucet not initialized"))?;
 
-        // Mint a substantial amount for Bob to use in escrow (e.g., 20M PROP tokens)
-        let amount: u64 = 20_000_000;
-        let fungible_asset = FungibleAsset::new(faucet_account_id, amount)?;
-
-        let mint_request = TransactionRequestBuilder::new().build_mint_fungible_asset(
-            fungible_asset,
-            bob_account_id,
-            NoteType::Public,
-            &mut self.rng,
-        )?;
+        // Mint a substantial amount to use in escrow (default 20M PROP tokens)
+        let amount: u64 = self.config.auto_fund_amount;
+        let mint_request =
+            client::mint_request(faucet_account_id, account_id, amount, &mut self.rng)?;
 
-        tracing::info!("   Minting {} PROP tokens for Bob", amount);
+        tracing::info!("   Minting {} PROP tokens for {}", amount, name);
 
         let mint_tx = self
             .client
@@ -260,79 +986,212 @@ impl MidenClientWrapper {
             .await?;
 
         let mint_tx_id = mint_tx.to_string();
+        let faucet_hex = account_id_to_hex(faucet_account_id);
+        self.post_ledger_entry(
+            JournalKind::Mint,
+            Posting::transfer(
+                &issuance_account(&faucet_hex),
+                &account_id_to_hex(account_id),
+                &faucet_hex,
+                amount,
+            )
+            .to_vec(),
+            &mint_tx_id,
+            Some(name),
+        );
 
         // Wait for note propagation
-        tracing::info!("   Waiting for note propagation (30s)...");
-        tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
+        self.wait_for_propagation().await;
 
-        self.client.sync_state().await?;
+        self.sync_state().await?;
 
         // Retrieve the note ID
         let consumable_notes = self
             .client
-            .get_consumable_notes(Some(bob_account_id))
+            .get_consumable_notes(Some(account_id))
             .await?;
 
         let real_note_id = if let Some((note, _)) = consumable_notes.first() {
-            note.id().to_string()
+            let note_id = note.id().to_string();
+            self.records.expect_note(
+                &note_id,
+                &account_id_to_hex(account_id),
+                &format!("{}-funding", name),
+                Some(mint_tx_id.clone()),
+            );
+            note_id
         } else {
-            format!("0x{}", hex::encode("bob-initial-funding"))
+            format!("0x{}", hex::encode(format!("{}-initial-funding", name)))
         };
 
         Ok((mint_tx_id, real_note_id))
     }
 
+    /// Sleeps for the configured note propagation wait, or until the current
+    /// command is cancelled.
+    pub(crate) async fn wait_for_propagation(&self) {
+        let wait = self.config.note_propagation_wait;
+        tracing::info!("   Waiting for note propagation ({:?})...", wait);
+        match &self.cancellation {
+            Some(token) => tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = token.cancelled() => tracing::info!("   Propagation wait cancelled"),
+            },
+            None => tokio::time::sleep(wait).await,
+        }
+    }
+
     /// Mints fungible property token.
     ///
     /// Returns:
     /// - Transaction ID
-    /// - Real note ID when available (falls back to placeholder if not yet visible)
+    /// - Note ID, known from the note the mint creates
     ///
     /// Notes:
-    /// - Uses a propagation wait + sync to retrieve consumable notes
+    /// - Waits for note propagation and syncs before returning
+    /// - Journaled and recorded in the service records
+    /// - A price without currency is in PRICE_CURRENCY (currency.rs)
+    /// - Refused for a property that is already minted (property_registry.rs)
     pub async fn mint_property_nft(
         &mut self,
         property_id: &str,
         owner_account_id: &str,
         ipfs_cid: &str,
         property_type: u8,
-        price: u64,
+        price: &PriceInput,
     ) -> Result<(String, String)> {
-        tracing::info!("Minting property NFT: {}", property_id);
-        tracing::info!("Owner: {}", owner_account_id);
+        self.check_property_unminted(property_id)?;
+        let price = price.resolve(&self.config.price_currency)?;
+        let op_id = self.records.begin_operation("mint_property", property_id);
+
+        let result = self
+            .submit_property_mint(property_id, owner_account_id, 0)
+            .await;
+
+        let tx_id = result.as_ref().ok().map(|(tx_id, _, _)| tx_id.clone());
+        self.records.finish_operation(op_id, &result, tx_id);
+
+        let (mint_tx_id, note_id, target_account_id) = result?;
+        self.record_property_mint(
+            property_id,
+            target_account_id,
+            ipfs_cid,
+            property_type,
+            price,
+            &mint_tx_id,
+            &note_id,
+            false,
+        )
+        .await;
+
+        Ok((mint_tx_id, note_id))
+    }
+
+    /// Records a minted property: service records, search index, expected
+    /// note, tax basis and after_mint hooks.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn record_property_mint(
+        &mut self,
+        property_id: &str,
+        owner_account_id: AccountId,
+        ipfs_cid: &str,
+        property_type: u8,
+        price: Money,
+        mint_tx_id: &str,
+        note_id: &str,
+        note_id_placeholder: bool,
+    ) {
+        let owner_hex = account_id_to_hex(owner_account_id);
+
+        self.records.record_property(PropertyRecord {
+            property_id: property_id.to_string(),
+            owner_account_id: owner_hex.clone(),
+            ipfs_cid: ipfs_cid.to_string(),
+            property_type,
+            price: price.amount_minor,
+            price_currency: Some(price.currency),
+            mint_tx_id: mint_tx_id.to_string(),
+            note_id: note_id.to_string(),
+            note_id_placeholder,
+            token_note_id: None,
+            location: None,
+            media_ids: Vec::new(),
+            metadata: HookMetadata::new(),
+            jurisdiction: None,
+            issuance: 0,
+            reissues: Vec::new(),
+            retirement: None,
+            lost_access: None,
+            provenance: Vec::new(),
+            created_at: chrono::Utc::now().timestamp(),
+        });
+        self.index_property(property_id);
+
+        if !note_id_placeholder {
+            self.records.expect_note(
+                note_id,
+                &owner_hex,
+                "property-mint",
+                Some(mint_tx_id.to_string()),
+            );
+        }
+        self.record_mint_basis(property_id, &owner_hex, price.amount_minor);
 
-        // Resolve owner account identifier (supports "alice", "bob", or hex AccountId)
-        let target_account_id = if owner_account_id == "alice" {
+        let payload = serde_json::json!(self.records.properties.get(property_id));
+        match self.run_hooks(HookPoint::AfterMint, property_id, payload).await {
+            Ok(outcome) => self.records.annotate_property(property_id, outcome.metadata),
+            Err(e) => tracing::warn!("⚠️  after_mint hooks failed for {}: {}", property_id, e),
+        }
+    }
+
+    /// Owner of a property mint: "alice", "bob" or a hex AccountId.
+    pub(crate) fn mint_recipient(&self, owner_account_id: &str) -> Result<AccountId> {
+        if owner_account_id == "alice" {
             self.alice_account_id
-                .ok_or_else(|| anyhow::anyhow!("Alice not initialized"))?
+                .ok_or_else(|| anyhow::anyhow!("Alice not initialized"))
         } else if owner_account_id == "bob" {
             self.bob_account_id
-                .ok_or_else(|| anyhow::anyhow!("Bob not initialized"))?
+                .ok_or_else(|| anyhow::anyhow!("Bob not initialized"))
         } else if owner_account_id.starts_with("0x") {
             let hex_str = owner_account_id.strip_prefix("0x").unwrap_or(owner_account_id);
             let bytes = hex::decode(hex_str)
                 .map_err(|e| anyhow::anyhow!("Failed to decode hex: {}", e))?;
             use miden_client::Deserializable;
             AccountId::read_from_bytes(&bytes[..])
-                .map_err(|e| anyhow::anyhow!("Failed to deserialize AccountId: {}", e))?
+                .map_err(|e| anyhow::anyhow!("Failed to deserialize AccountId: {}", e))
         } else {
-            return Err(anyhow::anyhow!("Unknown owner account: {}", owner_account_id));
-        };
+            Err(anyhow::anyhow!("Unknown owner account: {}", owner_account_id))
+        }
+    }
+
+    /// Submits the faucet mint of a property's `issuance`: one P2ID note whose
+    /// serial number derives from the property (property_registry.rs).
+    ///
+    /// Returns (transaction ID, note ID, recipient account).
+    pub(crate) async fn submit_property_mint(
+        &mut self,
+        property_id: &str,
+        owner_account_id: &str,
+        issuance: u32,
+    ) -> Result<(String, String, AccountId)> {
+        tracing::info!("Minting property NFT: {}", property_id);
+        tracing::info!("Owner: {}", owner_account_id);
+
+        let target_account_id = self.mint_recipient(owner_account_id)?;
 
         let faucet_account_id = self
             .faucet_account_id
             .ok_or_else(|| anyhow::anyhow!("Faucet not initialized"))?;
 
-        // Fixed amount used for the mint in this implementation
-        let amount: u64 = 100;
-        let fungible_asset = FungibleAsset::new(faucet_account_id, amount)?;
-
-        let mint_request = TransactionRequestBuilder::new().build_mint_fungible_asset(
-            fungible_asset,
+        let amount = PROPERTY_MINT_AMOUNT;
+        let note = client::p2id_note_with_serial(
+            faucet_account_id,
             target_account_id,
-            NoteType::Public,
-            &mut self.rng,
+            vec![FungibleAsset::new(faucet_account_id, amount)?.into()],
+            property_mint_serial_num(property_id, issuance),
         )?;
+        let note_id = note.id().to_string();
+        let mint_request = client::notes_request(vec![note])?;
 
         tracing::info!("Executing mint transaction");
 
@@ -343,29 +1202,28 @@ impl MidenClientWrapper {
 
         let mint_tx_id = mint_tx.to_string();
         tracing::info!("Minted. TX: {}", mint_tx_id);
+        let faucet_hex = account_id_to_hex(faucet_account_id);
+        self.post_ledger_entry(
+            JournalKind::Mint,
+            Posting::transfer(
+                &issuance_account(&faucet_hex),
+                &account_id_to_hex(target_account_id),
+                &faucet_hex,
+                amount,
+            )
+            .to_vec(),
+            &mint_tx_id,
+            Some(property_id),
+        );
 
-        // Wait for note propagation and resync to discover the new note
-        tracing::info!("Waiting for note propagation");
-        tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
-
-        self.client.sync_state().await?;
-
-        // Pull consumable notes for the recipient account
-        let consumable_notes = self
-            .client
-            .get_consumable_notes(Some(target_account_id))
-            .await?;
+        // Wait for note propagation and resync so the note is tracked
+        self.wait_for_propagation().await;
 
-        // Return first discovered note ID, else placeholder if still not visible
-        let real_note_id = if let Some((note, _)) = consumable_notes.first() {
-            note.id().to_string()
-        } else {
-            format!("0x{}", hex::encode(format!("note-{}", property_id)))
-        };
+        self.sync_state().await?;
 
-        tracing::info!("Note ID: {}", real_note_id);
+        tracing::info!("Note ID: {}", note_id);
 
-        Ok((mint_tx_id, real_note_id))
+        Ok((mint_tx_id, note_id, target_account_id))
     }
 
     /// Returns consumable notes for a given account.
@@ -383,7 +1241,7 @@ impl MidenClientWrapper {
         tracing::info!("Getting consumable notes");
 
         // Ensure local state is up-to-date
-        self.client.sync_state().await?;
+        self.sync_state().await?;
 
         // Resolve account to query
         let account_id = if let Some(id_str) = account_id_str {
@@ -404,24 +1262,37 @@ impl MidenClientWrapper {
                 .ok_or_else(|| anyhow::anyhow!("No default account"))?
         };
 
-        // Query consumable notes
-        let consumable_notes = self.client.get_consumable_notes(Some(account_id)).await?;
-
-        // Convert to a stable JSON response shape for external API usage
-        let notes: Vec<serde_json::Value> = consumable_notes
-            .iter()
-            .map(|(note, _status)| {
-                serde_json::json!({
-                    "note_id": note.id().to_string(),
-                    "note_type": "consumable",
-                })
-            })
-            .collect();
-
+        let notes = self.consumable_note_views(account_id).await?;
         tracing::info!("Found {} consumable notes", notes.len());
         Ok(notes)
     }
 
+    /// Consumable notes of an account in a stable JSON shape for external API
+    /// usage, leaving out quarantined notes (note_quarantine.rs).
+    pub(crate) async fn consumable_note_views(
+        &mut self,
+        account_id: AccountId,
+    ) -> Result<Vec<serde_json::Value>> {
+        let consumable_notes = self.client.get_consumable_notes(Some(account_id)).await?;
+        let account_hex = account_id_to_hex(account_id);
+        let mut notes = Vec::new();
+        for (note, _status) in &consumable_notes {
+            let (inspection, passed) = self.screen_note(&account_hex, note);
+            if !passed {
+                continue;
+            }
+            notes.push(serde_json::json!({
+                "note_id": note.id().to_string(),
+                "note_type": "consumable",
+                "script": inspection.class,
+                "script_root": inspection.script_root,
+                "script_name": inspection.script_name,
+                "sender": inspection.sender,
+            }));
+        }
+        Ok(notes)
+    }
+
     /// Consumes notes into the specified account.
     ///
     /// Parameters:
@@ -431,12 +1302,24 @@ impl MidenClientWrapper {
     /// Behavior:
     /// - Syncs state
     /// - Fetches all consumable notes for the account
-    /// - Consumes all of them in a single transaction
+    /// - Consumes all of them, MAX_NOTES_PER_TX per transaction
+    ///
+    /// Returns the last transaction ID; an error if some batch failed.
     pub async fn consume_note(
         &mut self,
         note_id: &str,
         account_str: Option<String>,
     ) -> Result<String> {
+        self.consume_notes(note_id, account_str).await?.into_tx_id()
+    }
+
+    /// Consumes notes into the specified account (see consume_note), reporting
+    /// every transaction and a failed batch (see consume_batches.rs).
+    pub async fn consume_notes(
+        &mut self,
+        note_id: &str,
+        account_str: Option<String>,
+    ) -> Result<ConsumedNotes> {
         tracing::info!("Consuming note: {}", note_id);
 
         // Resolve account to consume into (supports named accounts and hex AccountId)
@@ -467,202 +1350,313 @@ impl MidenClientWrapper {
 
         tracing::info!("Consuming into account: {}", account_id);
 
-        // Sync state so consumable notes reflect latest network view
-        self.client.sync_state().await?;
-
-        // Fetch all consumable notes (current implementation consumes all of them)
-        let consumable_notes = self.client.get_consumable_notes(Some(account_id)).await?;
-
-        let note_ids: Vec<_> = consumable_notes.iter().map(|(note, _)| note.id()).collect();
+        // An account without the fee asset gets it from the treasury first
+        // (billing.rs); the top-up is consumed with the other notes
+        self.sponsor_fee(account_id).await;
 
-        if note_ids.is_empty() {
-            return Err(anyhow::anyhow!("No consumable notes found"));
-        }
-
-        tracing::info!("Found {} consumable notes; consuming all", note_ids.len());
-
-        // Build consume transaction
-        let transaction_request = TransactionRequestBuilder::new().build_consume_notes(note_ids)?;
-
-        tracing::info!("Executing consume transaction");
-
-        // Submit transaction
-        let transaction_id = self
-            .client
-            .submit_new_transaction(account_id, transaction_request)
-            .await?;
+        let op_id = self
+            .records
+            .begin_operation("consume_notes", &account_id_to_hex(account_id));
 
-        let tx_id = transaction_id.to_string();
-        tracing::info!("Notes consumed. TX: {}", tx_id);
+        let result = self.submit_consume_all(account_id).await;
 
-        // Sync after transaction to update local state (balances/notes)
-        self.client.sync_state().await?;
+        let tx_id = result
+            .as_ref()
+            .ok()
+            .and_then(|consumed| consumed.batches.last())
+            .map(|batch| batch.tx_id.clone());
+        self.records.finish_operation(op_id, &result, tx_id);
 
-        Ok(tx_id)
+        result
     }
 
-    /// Transfers a property asset by creating a P2ID note from Alice's vault.
+    /// Transfers a property asset by creating a P2ID note from its recorded
+    /// owner's vault.
     ///
     /// Notes:
-    /// - Assumes the asset has already been consumed into Alice's vault
-    /// - Creates a dummy target account (current implementation does not use to_account_id)
+    /// - Assumes the asset has already been consumed into the owner's vault, an
+    ///   account the client store holds
+    /// - Pays PROPERTY_MINT_AMOUNT of the faucet token to `to_account_id` (alice, bob or hex)
+    /// - Refused while the property has active liens without a transfer sign-off (liens.rs)
     pub async fn transfer_property(
         &mut self,
         property_id: &str,
         to_account_id: &str,
     ) -> Result<String> {
-        tracing::info!("Transferring property: {}", property_id);
-        tracing::info!("To: {}", to_account_id);
+        self.transfer_property_under_contract(property_id, to_account_id, None)
+            .await
+    }
 
-        let alice_account_id = self
-            .alice_account_id
-            .ok_or_else(|| anyhow::anyhow!("Alice account not initialized"))?;
-        let faucet_account_id = self
-            .faucet_account_id
-            .ok_or_else(|| anyhow::anyhow!("Faucet not initialized"))?;
+    /// Transfers a property like `transfer_property`, with the transfer note
+    /// anchored to the SHA-256 of its sales contract when one is given
+    /// (contract_anchors.rs).
+    pub async fn transfer_property_under_contract(
+        &mut self,
+        property_id: &str,
+        to_account_id: &str,
+        contract_hash: Option<&str>,
+    ) -> Result<String> {
+        self.check_property_active(property_id)?;
+        self.liens.check(property_id, LienAction::Transfer)?;
+        let contract_hash = contract_hash.map(normalize_document_hash).transpose()?;
+        if let Some(contract_hash) = &contract_hash {
+            self.check_contract_unused(contract_hash, property_id)?;
+        }
 
-        // Pull Alice account state to inspect vault
-        let alice_account = self
-            .client
-            .get_account(alice_account_id)
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("Alice account not found"))?;
+        let previous_owner = self
+            .records
+            .properties
+            .get(property_id)
+            .map(|p| p.owner_account_id.clone());
+        let op_id = self.records.begin_operation("transfer_property", property_id);
+
+        let result = self
+            .submit_property_transfer(property_id, to_account_id, contract_hash.as_deref())
+            .await;
+
+        let tx_id = result.as_ref().ok().map(|(tx_id, _)| tx_id.clone());
+        self.records.finish_operation(op_id, &result, tx_id);
+
+        if let Ok((tx_id, note_id)) = &result {
+            self.liens.use_sign_offs(property_id, LienAction::Transfer);
+            match self.account_hex(to_account_id) {
+                Ok(owner_hex) => {
+                    self.records.record_transfer(property_id, &owner_hex, note_id);
+                    if let Some(from_hex) = previous_owner {
+                        self.record_title_transfer_basis(property_id, &from_hex, &owner_hex, tx_id);
+                    }
+                }
+                Err(e) => tracing::warn!("Owner of {} not recorded: {}", property_id, e),
+            }
+        }
 
-        let vault = alice_account.account().vault();
-        let vault_assets: Vec<_> = vault.assets().collect();
+        result.map(|(tx_id, _)| tx_id)
+    }
 
-        if vault_assets.is_empty() {
+    /// Checks that `property_id` can be transferred out of `seller_hex`'s
+    /// vault now, for settlements that pay the seller before the title
+    /// moves (auctions, installment plans).
+    pub(crate) async fn check_property_transferable(
+        &mut self,
+        property_id: &str,
+        seller_hex: &str,
+    ) -> Result<()> {
+        self.check_property_active(property_id)?;
+        self.liens.check(property_id, LienAction::Transfer)?;
+        let property = self
+            .records
+            .properties
+            .get(property_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Property {} has not been minted", property_id))?;
+        if !property.owner_account_id.eq_ignore_ascii_case(seller_hex) {
             return Err(anyhow::anyhow!(
-                "Vault is empty. Please consume property note first using POST /api/v1/properties/consume-note/:propertyId"
+                "Conflict: property {} is owned by {}, not the seller {}",
+                property_id,
+                property.owner_account_id,
+                seller_hex
             ));
         }
+        self.check_owner_holds_property_token(&property).await
+    }
 
-        tracing::info!("Found {} assets in vault", vault_assets.len());
+    /// Sends a property's token from its recorded owner's vault in a P2ID
+    /// note. Returns (transaction ID, note ID).
+    async fn submit_property_transfer(
+        &mut self,
+        property_id: &str,
+        to_account_id: &str,
+        contract_hash: Option<&str>,
+    ) -> Result<(String, String)> {
+        tracing::info!("Transferring property: {}", property_id);
+        tracing::info!("To: {}", to_account_id);
 
-        // Create dummy target account (Version0, Public, RegularAccountUpdatableCode)
-        let mut init_seed = [0_u8; 15];
-        self.client.rng().fill_bytes(&mut init_seed);
+        let property = self
+            .records
+            .properties
+            .get(property_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Property {} has not been minted", property_id))?;
+        let owner_hex = property.owner_account_id.clone();
+        let owner_account_id = AccountId::from_hex(&owner_hex)
+            .map_err(|e| anyhow::anyhow!("Invalid owner account {}: {}", owner_hex, e))?;
+        let faucet_account_id = self
+            .faucet_account_id
+            .ok_or_else(|| anyhow::anyhow!("Faucet not initialized"))?;
 
-        let target_account = AccountId::dummy(
-            init_seed,
-            AccountIdVersion::Version0,
-            AccountType::RegularAccountUpdatableCode,
-            AccountStorageMode::Public,
-        );
+        let target_account = self.mint_recipient(to_account_id)?;
 
-        // Transfer a single asset from the vault
-        let asset_to_transfer = vault_assets
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow::anyhow!("No assets available"))?;
+        // The property is PROPERTY_MINT_AMOUNT of the faucet token, delivered
+        // to the owner's vault by the note the records track for it
+        self.check_owner_holds_property_token(&property).await?;
 
-        let p2id_note = create_p2id_note(
-            alice_account_id,
+        let asset: Asset = FungibleAsset::new(faucet_account_id, PROPERTY_MINT_AMOUNT)?.into();
+        let postings = asset_postings(
+            owner_account_id,
             target_account,
-            vec![asset_to_transfer],
-            NoteType::Public,
-            Felt::new(0),
-            &mut self.rng,
-        )?;
-
-        let output_notes = vec![OutputNote::Full(p2id_note)];
-        let transaction_request = TransactionRequestBuilder::new()
-            .own_output_notes(output_notes)
-            .build()?;
+            std::slice::from_ref(&asset),
+        );
+        let (note_id, transaction_request) = match contract_hash {
+            Some(contract_hash) => {
+                let serial_num = anchor_serial_num(contract_hash, property_id)?;
+                let note = client::p2id_note_with_serial(
+                    owner_account_id,
+                    target_account,
+                    vec![asset],
+                    serial_num,
+                )?;
+                (note.id(), client::notes_request(vec![note])?)
+            }
+            None => {
+                client::p2id_request(owner_account_id, target_account, vec![asset], &mut self.rng)?
+            }
+        };
 
         tracing::info!("Executing transfer transaction");
 
         let transaction_id = self
             .client
-            .submit_new_transaction(alice_account_id, transaction_request)
+            .submit_new_transaction(owner_account_id, transaction_request)
             .await?;
 
         let tx_id = transaction_id.to_string();
         tracing::info!("Property transferred. TX: {}", tx_id);
+        self.post_ledger_entry(JournalKind::Transfer, postings, &tx_id, Some(property_id));
+        self.records.expect_note(
+            &note_id.to_string(),
+            &account_id_to_hex(target_account),
+            "property-transfer",
+            Some(tx_id.clone()),
+        );
+        if let Some(contract_hash) = contract_hash {
+            tracing::info!(
+                "Transfer note {} anchored to contract {}",
+                note_id,
+                contract_hash
+            );
+            self.records.record_contract_anchor(ContractAnchor {
+                note_id: note_id.to_string(),
+                property_id: property_id.to_string(),
+                contract_hash: contract_hash.to_string(),
+                tx_id: tx_id.clone(),
+                anchored_at: chrono::Utc::now().timestamp(),
+            });
+        }
 
-        Ok(tx_id)
+        Ok((tx_id, note_id.to_string()))
     }
 
-    /// Sends tokens by moving all assets currently present in Alice's vault.
-    ///
-    /// Notes:
-    /// - to_account_id is logged but current implementation uses a dummy target account
-    /// - _amount is not used (current implementation sends all vault assets)
-    pub async fn send_tokens(&mut self, to_account_id: &str, _amount: u64) -> Result<String> {
-        tracing::info!("Sending tokens to {}", to_account_id);
+    /// Sends `amount` of the service token from Alice's vault to
+    /// `to_account_id` (alice, bob or hex) as a P2ID note.
+    pub async fn send_tokens(&mut self, to_account_id: &str, amount: u64) -> Result<String> {
+        tracing::info!("Sending {} tokens to {}", amount, to_account_id);
 
         let alice_account_id = self
             .alice_account_id
             .ok_or_else(|| anyhow::anyhow!("Alice account not initialized"))?;
+        let target_account = self.mint_recipient(to_account_id)?;
 
-        // Sync before reading vault state
-        self.client.sync_state().await?;
-
-        // Load Alice account to inspect vault assets
-        let alice_account = self
-            .client
-            .get_account(alice_account_id)
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("Alice account not found"))?;
-
-        let vault = alice_account.account().vault();
-        let vault_assets: Vec<_> = vault.assets().collect();
-
-        if vault_assets.is_empty() {
-            return Err(anyhow::anyhow!(
-                "Alice's vault is empty. Please consume notes first."
-            ));
-        }
-
-        tracing::info!("Found {} assets in vault", vault_assets.len());
+        self.submit_token_payment(alice_account_id, target_account, amount)
+            .await
+    }
 
-        // Create dummy target account (Version0, Public, RegularAccountUpdatableCode)
-        let mut init_seed = [0_u8; 15];
-        self.client.rng().fill_bytes(&mut init_seed);
+    /// Sends exactly `amount` of the service token from `from` to `to` as a
+    /// P2ID note. Used for scheduled payments (installments, rent) where
+    /// sending the whole vault is not acceptable.
+    pub(crate) async fn submit_token_payment(
+        &mut self,
+        from: AccountId,
+        to: AccountId,
+        amount: u64,
+    ) -> Result<String> {
+        let faucet_account_id = self
+            .faucet_account_id
+            .ok_or_else(|| anyhow::anyhow!("Faucet not initialized"))?;
 
-        let target_account = AccountId::dummy(
-            init_seed,
-            AccountIdVersion::Version0,
-            AccountType::RegularAccountUpdatableCode,
-            AccountStorageMode::Public,
-        );
+        self.submit_asset_payment(from, to, faucet_account_id, amount).await
+    }
 
-        // Send all vault assets
-        let assets_to_send: Vec<_> = vault_assets.into_iter().collect();
-        tracing::info!("Sending {} assets from vault", assets_to_send.len());
+    /// Sends exactly `amount` of the fungible asset issued by `faucet` from
+    /// `from` to `to` as a P2ID note (service token payments, share trades).
+    pub(crate) async fn submit_asset_payment(
+        &mut self,
+        from: AccountId,
+        to: AccountId,
+        faucet: AccountId,
+        amount: u64,
+    ) -> Result<String> {
+        self.submit_payment_note(from, to, faucet, amount)
+            .await
+            .map(|(tx_id, _)| tx_id)
+    }
 
-        let p2id_note = create_p2id_note(
-            alice_account_id,
-            target_account,
-            assets_to_send,
-            NoteType::Public,
-            Felt::new(0),
-            &mut self.rng,
-        )?;
+    /// Like `submit_asset_payment`; returns the transaction and note IDs.
+    pub(crate) async fn submit_payment_note(
+        &mut self,
+        from: AccountId,
+        to: AccountId,
+        faucet: AccountId,
+        amount: u64,
+    ) -> Result<(String, String)> {
+        tracing::info!("💳 Sending payment of {} (faucet {})", amount, faucet);
+        tracing::info!("   From: {}", from);
+        tracing::info!("   To: {}", to);
 
-        let output_notes = vec![OutputNote::Full(p2id_note)];
-        let transaction_request = TransactionRequestBuilder::new()
-            .own_output_notes(output_notes)
-            .build()?;
+        self.sync_state().await?;
 
-        tracing::info!("Executing payment transaction");
+        let asset = FungibleAsset::new(faucet, amount)?;
+        let (note_id, transaction_request) =
+            client::p2id_request(from, to, vec![asset.into()], &mut self.rng)?;
+        let note_id = note_id.to_string();
 
         let transaction_id = self
             .client
-            .submit_new_transaction(alice_account_id, transaction_request)
+            .submit_new_transaction(from, transaction_request)
             .await?;
 
         let tx_id = transaction_id.to_string();
-        tracing::info!("Tokens sent. TX: {}", tx_id);
+        tracing::info!("✅ Payment sent! TX: {}", tx_id);
+        self.post_ledger_entry(
+            JournalKind::Payment,
+            Posting::transfer(
+                &account_id_to_hex(from),
+                &account_id_to_hex(to),
+                &account_id_to_hex(faucet),
+                amount,
+            )
+            .to_vec(),
+            &tx_id,
+            None,
+        );
 
-        self.client.sync_state().await?;
+        self.sync_state().await?;
 
-        Ok(tx_id)
+        Ok((tx_id, note_id))
+    }
+
+    /// Resolves one of the service's named accounts ("alice", "bob", "faucet").
+    pub(crate) fn named_account(&self, name: &str) -> Result<AccountId> {
+        match name {
+            "alice" => self.alice_account_id,
+            "bob" => self.bob_account_id,
+            "faucet" => self.faucet_account_id,
+            _ => return Err(anyhow::anyhow!("Unknown account: {}", name)),
+        }
+        .ok_or_else(|| anyhow::anyhow!("{} account not initialized", name))
+    }
+
+    /// Resolves an account name or hex AccountId to lowercase hex.
+    pub(crate) fn account_hex(&self, selector: &str) -> Result<String> {
+        if selector.starts_with("0x") {
+            Ok(selector.to_lowercase())
+        } else {
+            self.named_account(selector).map(account_id_to_hex)
+        }
     }
 
     /// Returns basic metadata about all system accounts (Alice, Bob, Faucet).
     pub async fn get_account_info(&mut self) -> Result<serde_json::Value> {
-        self.client.sync_state().await?;
+        self.sync_state().await?;
 
         let alice_account_id = self
             .alice_account_id
@@ -711,11 +1705,12 @@ impl MidenClientWrapper {
     ///
     /// Current implementation reports:
     /// - count of assets present in the vault
+    /// - fungible balances with their token symbol and decimals
     /// - public/private flags
     pub async fn get_account_balance(&mut self, account_str: &str) -> Result<serde_json::Value> {
         tracing::info!("Getting balance for: {}", account_str);
 
-        self.client.sync_state().await?;
+        self.sync_state().await?;
 
         let account_id = if account_str == "alice" {
             self.alice_account_id
@@ -744,10 +1739,19 @@ impl MidenClientWrapper {
             vault_assets.len()
         );
 
+        let balances: Vec<TokenAmount> = vault_assets
+            .iter()
+            .filter_map(|asset| match asset {
+                Asset::Fungible(fungible) => Some(self.token_amount(fungible)),
+                Asset::NonFungible(_) => None,
+            })
+            .collect();
+
         Ok(serde_json::json!({
             "account_id": account_id.to_string(),
             "vault_available": true,
             "vault_assets": vault_assets.len(),
+            "balances": balances,
             "is_public": account.account().is_public(),
         }))
     }
@@ -759,52 +1763,86 @@ impl MidenClientWrapper {
     /// Demo accreditation proof.
     ///
     /// Notes:
+    /// - Threshold comes from the server-side rule for (jurisdiction, listing class)
     /// - Validates net_worth >= threshold locally
+    /// - Public inputs are [threshold, rule_id]
     /// - Encodes a placeholder "proof" as base64 for demo/test flow
     pub async fn generate_accreditation_proof(
         &mut self,
         net_worth: u64,
-        threshold: u64,
+        jurisdiction: Option<String>,
+        listing_class: Option<String>,
     ) -> Result<serde_json::Value> {
+        let rule = self
+            .accreditation_rules
+            .resolve(jurisdiction.as_deref(), listing_class.as_deref())?
+            .clone();
+        let threshold = rule.threshold;
+
         tracing::info!("Generating ZK accreditation proof");
         tracing::info!("Net worth: {} (private; not included in proof)", net_worth);
-        tracing::info!("Threshold: {} (public)", threshold);
+        tracing::info!("Threshold: {} (public, rule {})", threshold, rule.rule_id);
 
         if net_worth < threshold {
             return Err(anyhow::anyhow!(
-                "Net worth {} does not meet threshold {}",
+                "Net worth {} does not meet threshold {} (rule {})",
                 net_worth,
-                threshold
+                threshold,
+                rule.rule_id
             ));
         }
 
-        let proof_data = format!("PROOF_{}_{}", net_worth, threshold);
+        let program = self.zk_programs.active(ACCREDITATION_FAMILY)?.clone();
+
+        let cache_key = ProofCache::key(
+            &program.program_id,
+            &[
+                &net_worth.to_string(),
+                &threshold.to_string(),
+                &rule.rule_id.to_string(),
+            ],
+        );
+        if let Some(cached) = self.proof_cache.get(&cache_key) {
+            tracing::info!("Proof served from cache");
+            return Ok(cached);
+        }
+
+        let proof_data = format!("PROOF_{}_{}_{}", net_worth, threshold, rule.rule_id);
 
         use base64::{engine::general_purpose, Engine as _};
         let proof_base64 = general_purpose::STANDARD.encode(proof_data.as_bytes());
 
-        let program_hash = format!("0x{}", hex::encode(format!("accreditation_v1")));
-
-        tracing::info!("Proof generated");
+        tracing::info!("Proof generated with {}", program.program_id);
 
-        Ok(serde_json::json!({
-            "success": true,
+        let result = serde_json::json!({
             "proof": {
                 "proof": proof_base64,
-                "program_hash": program_hash,
-                "public_inputs": vec![threshold],
+                "program_hash": program.mast_root,
+                "program_id": program.program_id,
+                "public_inputs": vec![threshold, rule.rule_id],
+                "rule": {
+                    "rule_id": rule.rule_id,
+                    "jurisdiction": rule.jurisdiction,
+                    "listing_class": rule.listing_class,
+                },
                 "proof_type": "miden-stark",
                 "timestamp": chrono::Utc::now().timestamp(),
             },
             "message": "ZK proof generated - net worth not revealed (demo version)"
-        }))
+        });
+
+        self.proof_cache
+            .insert(cache_key, &program.program_id, result.clone());
+
+        Ok(result)
     }
 
     /// Demo accreditation proof verification.
     ///
     /// Notes:
     /// - Decodes proof bytes to validate formatting
-    /// - Returns a positive verification result for demo flow
+    /// - Public inputs must be [threshold, rule_id], the ones the proof was
+    ///   generated for; the rule must still exist with the same threshold
     pub async fn verify_accreditation_proof(
         &mut self,
         proof_base64: &str,
@@ -813,21 +1851,47 @@ impl MidenClientWrapper {
     ) -> Result<serde_json::Value> {
         tracing::info!("Verifying ZK accreditation proof");
 
-        use base64::{engine::general_purpose, Engine as _};
-        let _proof_bytes = general_purpose::STANDARD
-            .decode(proof_base64)
-            .map_err(|e| anyhow::anyhow!("Invalid proof format: {}", e))?;
+        let verification = obscura_proof_verifier::verify_accreditation(
+            &self.zk_programs.published(),
+            proof_base64,
+            program_hash,
+            &public_inputs,
+            None,
+            None,
+        )?;
+        let (threshold, rule_id) = obscura_proof_verifier::accreditation_inputs(&public_inputs)?;
+
+        let (valid, message) = match self.accreditation_rules.get(rule_id) {
+            None => (false, format!("Accreditation rule {} no longer exists", rule_id)),
+            Some(rule) if rule.threshold != threshold => (
+                false,
+                format!(
+                    "Rule {} threshold changed from {} to {}; regenerate the proof",
+                    rule_id, threshold, rule.threshold
+                ),
+            ),
+            Some(_) => (verification.valid, verification.message),
+        };
 
-        tracing::info!("Proof verified");
+        tracing::info!(
+            "Proof checked against {} (rule {}): valid={}",
+            verification.program_id,
+            rule_id,
+            valid
+        );
 
-        Ok(serde_json::json!({
-            "success": true,
-            "valid": true,
+        let result = serde_json::json!({
+            "valid": valid,
             "proof_type": "miden-stark",
-            "threshold": public_inputs[0],
+            "program_id": verification.program_id,
+            "program_active": verification.program_active,
+            "threshold": threshold,
+            "rule_id": rule_id,
             "verified_at": chrono::Utc::now().timestamp(),
-            "message": "Proof verified. User meets accreditation threshold (demo version)"
-        }))
+            "message": message
+        });
+        self.proof_verified_hooks("accreditation", serde_json::json!(public_inputs), result)
+            .await
     }
 
     // =========================================================================
@@ -839,14 +1903,31 @@ impl MidenClientWrapper {
     /// Behavior:
     /// - Computes expected hash for "{property_id}-ownership"
     /// - Compares with provided document_hash
+    /// - Lists the cited appraisal/inspection reports (professionals.rs)
     /// - Encodes the result into a base64 "proof" payload
     pub async fn generate_ownership_proof(
         &mut self,
         property_id: &str,
         document_hash: &str,
+        report_ids: &[u64],
     ) -> Result<serde_json::Value> {
         tracing::info!("Generating ZK ownership proof");
 
+        let program = self.zk_programs.active(OWNERSHIP_FAMILY)?.clone();
+        let reports = self.cited_reports(property_id, report_ids)?;
+
+        let cited = report_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let cache_key =
+            ProofCache::key(&program.program_id, &[property_id, document_hash, &cited]);
+        if let Some(cached) = self.proof_cache.get(&cache_key) {
+            tracing::info!("Proof served from cache");
+            return Ok(cached);
+        }
+
         let expected_input = format!("{}-ownership", property_id);
         let expected_hash = {
             use sha2::{Digest, Sha256};
@@ -867,14 +1948,21 @@ impl MidenClientWrapper {
         use base64::{engine::general_purpose, Engine as _};
         let proof_base64 = general_purpose::STANDARD.encode(proof_data.as_bytes());
 
-        Ok(serde_json::json!({
-            "success": verified,
+        let result = serde_json::json!({
+            "verified": verified,
             "proof": proof_base64,
-            "program_hash": format!("0x{}", hex::encode("ownership_v1")),
+            "program_hash": program.mast_root,
+            "program_id": program.program_id,
             "public_inputs": vec![property_id],
+            "reports": reports,
             "proof_type": "miden-stark",
             "timestamp": chrono::Utc::now().timestamp()
-        }))
+        });
+
+        self.proof_cache
+            .insert(cache_key, &program.program_id, result.clone());
+
+        Ok(result)
     }
 
     /// Demo ownership verification.
@@ -887,25 +1975,22 @@ impl MidenClientWrapper {
         program_hash: &str,
         public_inputs: Vec<String>,
     ) -> Result<serde_json::Value> {
-        use base64::{engine::general_purpose, Engine as _};
-        let proof_bytes = general_purpose::STANDARD
-            .decode(proof_base64)
-            .map_err(|e| anyhow::anyhow!("Failed to decode proof: {}", e))?;
-
-        let proof_str = String::from_utf8_lossy(&proof_bytes);
-        let verified = proof_str.contains("VERIFIED");
+        let verification = obscura_proof_verifier::verify_ownership(
+            &self.zk_programs.published(),
+            proof_base64,
+            program_hash,
+        )?;
 
-        Ok(serde_json::json!({
-            "success": true,
-            "valid": verified,
-            "verified_at": chrono::Utc::now().to_rfc3339(),
+        let result = serde_json::json!({
+            "valid": verification.valid,
+            "verified_at": chrono::Utc::now().timestamp(),
             "proof_type": "miden-stark",
-            "message": if verified {
-                "Ownership verified successfully"
-            } else {
-                "Ownership verification failed"
-            }
-        }))
+            "program_id": verification.program_id,
+            "program_active": verification.program_active,
+            "message": verification.message,
+        });
+        self.proof_verified_hooks("ownership", serde_json::json!(public_inputs), result)
+            .await
     }
 
     // =========================================================================
@@ -915,73 +2000,384 @@ impl MidenClientWrapper {
     /// Demo jurisdiction proof.
     ///
     /// Behavior:
-    /// - Rejects if country_code appears in restricted list
+    /// - Checks country_code against the current restricted-jurisdiction list
+    /// - Public inputs are [list_version, restricted_count]
     /// - Encodes a placeholder payload as base64
     pub async fn generate_jurisdiction_proof(
         &mut self,
         country_code: &str,
-        restricted_countries: Vec<String>,
     ) -> Result<serde_json::Value> {
+        let list = self
+            .jurisdiction_lists
+            .current()
+            .ok_or_else(|| anyhow::anyhow!("No restricted-jurisdiction list has been published"))?
+            .clone();
+
         let country_upper = country_code.to_uppercase();
-        if restricted_countries
-            .iter()
-            .any(|c| c.to_uppercase() == country_upper)
-        {
-            return Err(anyhow::anyhow!("Country {} is in restricted list", country_code));
+        if list.countries.iter().any(|c| *c == country_upper) {
+            return Err(anyhow::anyhow!(
+                "Country {} is in restricted list (version {})",
+                country_code,
+                list.version
+            ));
         }
 
-        let proof_data = format!(
-            "JURIS_PROOF_{}_{}",
-            country_code,
-            restricted_countries.join(",")
-        );
+        let program = self.zk_programs.active(JURISDICTION_FAMILY)?.clone();
+
+        let cache_key = ProofCache::key(&program.program_id, &[&country_upper, &list.list_hash]);
+        if let Some(cached) = self.proof_cache.get(&cache_key) {
+            tracing::info!("Proof served from cache");
+            return Ok(cached);
+        }
+
+        let proof_data = format!("JURIS_PROOF_{}_{}", country_code, list.list_hash);
 
         use base64::{engine::general_purpose, Engine as _};
         let proof_base64 = general_purpose::STANDARD.encode(proof_data.as_bytes());
 
-        let restricted_hash = format!(
-            "0x{}",
-            hex::encode(format!("restricted_{}", restricted_countries.join("")))
-        );
-        let program_hash = format!("0x{}", hex::encode(format!("jurisdiction_v1")));
-
-        Ok(serde_json::json!({
-            "success": true,
+        let result = serde_json::json!({
             "proof": {
                 "proof": proof_base64,
-                "program_hash": program_hash,
-                "public_inputs": vec![restricted_countries.len() as u64],
+                "program_hash": program.mast_root,
+                "program_id": program.program_id,
+                "public_inputs": vec![list.version, list.countries.len() as u64],
                 "proof_type": "miden-stark",
                 "timestamp": chrono::Utc::now().timestamp(),
-                "restricted_count": restricted_countries.len(),
-                "restricted_hash": restricted_hash,
+                "list_version": list.version,
+                "restricted_count": list.countries.len(),
+                "restricted_hash": format!("0x{}", list.list_hash),
             },
             "message": "Jurisdiction proof generated - country not revealed (demo version)"
-        }))
+        });
+
+        self.proof_cache
+            .insert(cache_key, &program.program_id, result.clone());
+
+        Ok(result)
     }
 
     /// Demo jurisdiction proof verification.
     ///
     /// Behavior:
     /// - Decodes base64 payload to validate structure
-    /// - Returns a positive verification result for demo flow
+    /// - Rejects proofs generated against a list version other than the current one
     pub async fn verify_jurisdiction_proof(
         &mut self,
         proof_base64: &str,
         program_hash: &str,
         public_inputs: Vec<u64>,
     ) -> Result<serde_json::Value> {
+        let current_version = self.jurisdiction_lists.current().map(|l| l.version);
+
+        let verification = obscura_proof_verifier::verify_jurisdiction(
+            &self.zk_programs.published(),
+            proof_base64,
+            program_hash,
+            &public_inputs,
+            current_version,
+        )?;
+        let (list_version, _) = obscura_proof_verifier::jurisdiction_inputs(&public_inputs)?;
+
+        let (valid, message) = if current_version.is_none()
+            || self.jurisdiction_lists.get(list_version).is_none()
+        {
+            (false, format!("Unknown list version {}", list_version))
+        } else {
+            (verification.valid, verification.message)
+        };
+
+        let result = serde_json::json!({
+            "valid": valid,
+            "proof_type": "miden-stark",
+            "program_id": verification.program_id,
+            "program_active": verification.program_active,
+            "list_version": list_version,
+            "current_list_version": current_version,
+            "verified_at": chrono::Utc::now().timestamp(),
+            "message": message
+        });
+        self.proof_verified_hooks("jurisdiction", serde_json::json!(public_inputs), result)
+            .await
+    }
+
+    // =========================================================================
+    // ZK PROOF FUNCTIONS - IDENTITY ATTRIBUTES
+    // =========================================================================
+
+    /// Demo identity attribute proof (age >= N, residency != X).
+    ///
+    /// Behavior:
+    /// - Checks the credential was signed by an active registered provider
+    /// - Evaluates the claim on the private attribute
+    /// - Public inputs are [claim_code, claim_value, provider_id]
+    pub async fn generate_identity_proof(
+        &mut self,
+        credential: IdentityCredential,
+        claim: AttributeClaim,
+    ) -> Result<serde_json::Value> {
+        tracing::info!("Generating ZK identity proof ({:?})", claim);
+
+        let provider = self.identity_providers.check_credential(&credential)?.clone();
+
+        let today = chrono::Utc::now().date_naive();
+        let attribute = credential.attribute_value(&claim, today)?;
+        if !identity::claim_holds(&claim, attribute)? {
+            return Err(anyhow::anyhow!("Credential does not satisfy the requested claim"));
+        }
+
+        let program = self.zk_programs.active(IDENTITY_FAMILY)?.clone();
+        let commitment = hex::encode(credential.commitment());
+        let claim_value = claim.public_value()?;
+
+        let cache_key = ProofCache::key(
+            &program.program_id,
+            &[
+                &commitment,
+                &claim.code().to_string(),
+                &claim_value.to_string(),
+                &today.to_string(),
+            ],
+        );
+        if let Some(cached) = self.proof_cache.get(&cache_key) {
+            tracing::info!("Proof served from cache");
+            return Ok(cached);
+        }
+
+        let proof_data = format!("IDENTITY_PROOF_{}_{}_{}", commitment, claim.code(), claim_value);
+
+        use base64::{engine::general_purpose, Engine as _};
+        let proof_base64 = general_purpose::STANDARD.encode(proof_data.as_bytes());
+
+        let result = serde_json::json!({
+            "proof": {
+                "proof": proof_base64,
+                "program_hash": program.mast_root,
+                "program_id": program.program_id,
+                "public_inputs": vec![claim.code(), claim_value, provider.provider_id],
+                "claim": claim,
+                "credential_commitment": format!("0x{}", commitment),
+                "provider": provider.name,
+                "proof_type": "miden-stark",
+                "timestamp": chrono::Utc::now().timestamp(),
+            },
+            "message": "Identity proof generated - attributes not revealed (demo version)"
+        });
+
+        self.proof_cache
+            .insert(cache_key, &program.program_id, result.clone());
+
+        Ok(result)
+    }
+
+    /// Demo identity attribute proof verification.
+    ///
+    /// Behavior:
+    /// - Decodes base64 payload to validate structure
+    /// - Valid only while the issuing provider is still registered and active
+    pub async fn verify_identity_proof(
+        &mut self,
+        proof_base64: &str,
+        program_hash: &str,
+        public_inputs: Vec<u64>,
+    ) -> Result<serde_json::Value> {
+        let program = self.zk_programs.check_hash(IDENTITY_FAMILY, program_hash)?;
+
         use base64::{engine::general_purpose, Engine as _};
         let _proof_bytes = general_purpose::STANDARD
             .decode(proof_base64)
             .map_err(|e| anyhow::anyhow!("Invalid proof format: {}", e))?;
 
-        Ok(serde_json::json!({
-            "success": true,
-            "valid": true,
+        let (claim_code, claim_value, provider_id) = match public_inputs.as_slice() {
+            [code, value, provider, ..] => (*code, *value, *provider),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Public inputs must be [claim_code, claim_value, provider_id]"
+                ))
+            }
+        };
+
+        let (valid, message) = match self.identity_providers.get(provider_id) {
+            Some(provider) if provider.active => (
+                true,
+                format!("Identity claim verified (issued by {}) (demo version)", provider.name),
+            ),
+            Some(provider) => (
+                false,
+                format!("Identity provider {} has been deactivated", provider.name),
+            ),
+            None => (false, format!("Unknown identity provider {}", provider_id)),
+        };
+
+        let result = serde_json::json!({
+            "valid": valid,
             "proof_type": "miden-stark",
+            "program_id": program.program_id,
+            "program_active": program.active,
+            "claim_code": claim_code,
+            "claim_value": claim_value,
+            "provider_id": provider_id,
             "verified_at": chrono::Utc::now().timestamp(),
-            "message": "Jurisdiction proof verified. User is not in restricted jurisdiction (demo version)"
+            "message": message
+        });
+        self.proof_verified_hooks("identity", serde_json::json!(public_inputs), result)
+            .await
+    }
+
+    // =========================================================================
+    // API KEYS (ADMIN)
+    // =========================================================================
+
+    /// The admin principal behind `api_key`; `action` names what needs it.
+    pub(crate) fn admin_principal(
+        &self,
+        api_key: Option<&str>,
+        action: &str,
+    ) -> Result<&Principal> {
+        let key = api_key.ok_or_else(|| {
+            EscrowAuthError::Unauthenticated(format!("X-API-Key header is required for {}", action))
+        })?;
+        let principal = self
+            .principals
+            .authenticate_admin(key)
+            .ok_or_else(|| EscrowAuthError::Unauthenticated("Invalid or revoked API key".into()))?;
+        if !principal.admin {
+            return Err(EscrowAuthError::Forbidden(format!(
+                "API key {} does not hold the admin role",
+                principal.key_id
+            ))
+            .into());
+        }
+        Ok(principal)
+    }
+
+    pub fn list_api_keys(&self) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self.principals.list()))
+    }
+
+    /// Issues an API key. Account names are resolved to hex IDs so the binding
+    /// survives name changes; the plaintext key is only returned here.
+    pub fn issue_api_key(&mut self, input: ApiKeyInput) -> Result<serde_json::Value> {
+        let accounts = input
+            .accounts
+            .iter()
+            .map(|account| self.account_hex(account))
+            .collect::<Result<Vec<_>>>()?;
+
+        let escrows = input
+            .escrows
+            .iter()
+            .map(|escrow| escrow.to_lowercase())
+            .collect::<Vec<_>>();
+        if let Some(escrow) = escrows
+            .iter()
+            .find(|e| !self.records.escrows.contains_key(*e))
+        {
+            return Err(anyhow::anyhow!(
+                "Escrow {} not found in service records",
+                escrow
+            ));
+        }
+        if !input.closing_agent && !escrows.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Escrows are only assigned to closing agent keys"
+            ));
+        }
+
+        let (principal, api_key) = self.principals.issue(
+            input.label,
+            accounts,
+            input.arbiter,
+            input.admin,
+            input.closing_agent.then_some(escrows),
+        )?;
+        tracing::info!(
+            "Issued API key {} ({}, arbiter: {}, admin: {}, closing agent: {})",
+            principal.key_id,
+            principal.label,
+            principal.arbiter,
+            principal.admin,
+            principal.closing_agent
+        );
+
+        Ok(serde_json::json!({
+            "principal": principal,
+            "api_key": api_key,
+        }))
+    }
+
+    pub fn revoke_api_key(&mut self, key_id: u64) -> Result<serde_json::Value> {
+        let principal = self.principals.revoke(key_id)?;
+        let subscriptions = self.subscriptions.remove_for_key(key_id)?;
+        tracing::info!("Revoked API key {} ({} subscription(s) removed)", key_id, subscriptions);
+        Ok(serde_json::json!(principal))
+    }
+
+    // =========================================================================
+    // RESTRICTED-JURISDICTION LISTS (ADMIN)
+    // =========================================================================
+
+    pub fn list_jurisdiction_lists(&self) -> Result<serde_json::Value> {
+        Ok(serde_json::json!({
+            "current_version": self.jurisdiction_lists.current().map(|l| l.version),
+            "signing_required": self.jurisdiction_lists.signing_required(),
+            "versions": self.jurisdiction_lists.list(),
         }))
     }
-}
\ No newline at end of file
+
+    /// Publishes a new list version. Cached jurisdiction proofs are dropped since
+    /// they commit to the previous version.
+    pub fn publish_jurisdiction_list(&mut self, update: ListUpdate) -> Result<serde_json::Value> {
+        let published = self.jurisdiction_lists.publish(update)?;
+
+        let program_id = self.zk_programs.active(JURISDICTION_FAMILY)?.program_id.clone();
+        self.proof_cache.invalidate_program(&program_id);
+
+        tracing::info!(
+            "Published restricted-jurisdiction list v{} ({} countries, signed: {})",
+            published.version,
+            published.countries.len(),
+            published.signature.is_some()
+        );
+
+        Ok(serde_json::json!(published))
+    }
+
+    // =========================================================================
+    // PROOF CACHE
+    // =========================================================================
+
+    /// Returns proof cache size, hit/miss counters and per-program entry counts.
+    pub fn get_proof_cache_stats(&self) -> Result<serde_json::Value> {
+        Ok(self.proof_cache.stats())
+    }
+
+    /// Invalidates cached proofs for one program, or the whole cache. Admin
+    /// only.
+    pub fn invalidate_proof_cache(
+        &mut self,
+        program: Option<String>,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        self.admin_principal(api_key, "invalidating the proof cache")?;
+        let removed = match &program {
+            Some(program) => self.proof_cache.invalidate_program(program),
+            None => self.proof_cache.clear(),
+        };
+
+        tracing::info!("Invalidated {} cached proof(s)", removed);
+
+        Ok(serde_json::json!({
+            "program": program,
+            "removed": removed,
+        }))
+    }
+
+    // =========================================================================
+    // ZK PROGRAM REGISTRY
+    // =========================================================================
+
+    /// Lists registered ZK programs with their MAST roots.
+    pub fn list_zk_programs(&self) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self.zk_programs.list()))
+    }
+}
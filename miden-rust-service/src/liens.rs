@@ -0,0 +1,411 @@
+// src/liens.rs
+//
+// Lien registry
+//
+// Lenders register liens against minted properties (amount, lender account,
+// priority; 1 is the senior lien). While a lien is active, the property is
+// encumbered:
+// - transfer_property for the property is refused
+// - releasing an escrow opened for the property (EscrowRecord.property_id,
+//   which includes installment plan escrows) is refused
+//
+// unless the lender of every active lien has signed off on that action. A
+// sign-off covers a single transfer or release and is used up by it. A lien
+// stays in force until the lender discharges it, recording the payoff
+// transaction.
+//
+// Registration, sign-off and discharge are authorized like escrow actions
+// (principals.rs): the API key must be bound to the lender account, or hold the
+// arbiter role.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::MidenClientWrapper;
+
+/// Actions on an encumbered property that need lender sign-off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LienAction {
+    Transfer,
+    Release,
+}
+
+impl LienAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LienAction::Transfer => "transfer",
+            LienAction::Release => "release",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LienStatus {
+    Active,
+    Discharged,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignOff {
+    pub action: LienAction,
+    pub granted_at: i64,
+    pub used_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lien {
+    pub lien_id: u64,
+    pub property_id: String,
+    /// Hex AccountId of the lender
+    pub lender_account_id: String,
+    pub amount: u64,
+    pub priority: u32,
+    pub status: LienStatus,
+    pub sign_offs: Vec<SignOff>,
+    pub payoff_tx_id: Option<String>,
+    pub created_at: i64,
+    pub discharged_at: Option<i64>,
+}
+
+impl Lien {
+    fn has_sign_off(&self, action: LienAction) -> bool {
+        self.sign_offs
+            .iter()
+            .any(|s| s.action == action && s.used_at.is_none())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LienInput {
+    pub property_id: String,
+    /// Account name or hex AccountId
+    pub lender_account_id: String,
+    pub amount: u64,
+    pub priority: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SignOffInput {
+    pub action: LienAction,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DischargeInput {
+    /// Transaction that paid the lien off
+    pub payoff_tx_id: String,
+}
+
+/// Raised when an action is refused because of undischarged liens; the HTTP
+/// layer maps it to 409.
+#[derive(Debug, thiserror::Error)]
+#[error("Encumbered: {0}")]
+pub struct LienBlocked(pub String);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LienStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    liens: BTreeMap<u64, Lien>,
+    #[serde(default)]
+    next_lien_id: u64,
+}
+
+impl LienStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<LienStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            LienStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, lien_id: u64) -> Result<&Lien> {
+        self.liens
+            .get(&lien_id)
+            .ok_or_else(|| anyhow::anyhow!("Lien {} not found", lien_id))
+    }
+
+    /// All liens, or those against one property, ordered by lien ID.
+    pub fn list(&self, property_id: Option<&str>) -> Vec<&Lien> {
+        self.liens
+            .values()
+            .filter(|l| property_id.is_none_or(|p| l.property_id == p))
+            .collect()
+    }
+
+    /// Active liens against a property, senior first.
+    pub fn active(&self, property_id: &str) -> Vec<&Lien> {
+        let mut liens: Vec<&Lien> = self
+            .liens
+            .values()
+            .filter(|l| l.property_id == property_id && l.status == LienStatus::Active)
+            .collect();
+        liens.sort_by_key(|l| l.priority);
+        liens
+    }
+
+    pub fn register(
+        &mut self,
+        property_id: String,
+        lender_account_id: String,
+        amount: u64,
+        priority: u32,
+    ) -> Result<Lien> {
+        if let Some(existing) = self
+            .active(&property_id)
+            .into_iter()
+            .find(|l| l.priority == priority)
+        {
+            return Err(anyhow::anyhow!(
+                "Property {} already has an active lien with priority {} (lien {})",
+                property_id,
+                priority,
+                existing.lien_id
+            ));
+        }
+
+        self.next_lien_id += 1;
+        let lien = Lien {
+            lien_id: self.next_lien_id,
+            property_id,
+            lender_account_id: lender_account_id.to_lowercase(),
+            amount,
+            priority,
+            status: LienStatus::Active,
+            sign_offs: Vec::new(),
+            payoff_tx_id: None,
+            created_at: chrono::Utc::now().timestamp(),
+            discharged_at: None,
+        };
+
+        self.liens.insert(lien.lien_id, lien.clone());
+        self.save()?;
+        Ok(lien)
+    }
+
+    pub fn sign_off(&mut self, lien_id: u64, action: LienAction) -> Result<Lien> {
+        let lien = self
+            .liens
+            .get_mut(&lien_id)
+            .ok_or_else(|| anyhow::anyhow!("Lien {} not found", lien_id))?;
+        if lien.status != LienStatus::Active {
+            return Err(anyhow::anyhow!("Lien {} has been discharged", lien_id));
+        }
+        if !lien.has_sign_off(action) {
+            lien.sign_offs.push(SignOff {
+                action,
+                granted_at: chrono::Utc::now().timestamp(),
+                used_at: None,
+            });
+        }
+
+        let lien = lien.clone();
+        self.save()?;
+        Ok(lien)
+    }
+
+    pub fn discharge(&mut self, lien_id: u64, payoff_tx_id: String) -> Result<Lien> {
+        let lien = self
+            .liens
+            .get_mut(&lien_id)
+            .ok_or_else(|| anyhow::anyhow!("Lien {} not found", lien_id))?;
+        if lien.status != LienStatus::Active {
+            return Err(anyhow::anyhow!(
+                "Lien {} has already been discharged",
+                lien_id
+            ));
+        }
+        lien.status = LienStatus::Discharged;
+        lien.payoff_tx_id = Some(payoff_tx_id);
+        lien.discharged_at = Some(chrono::Utc::now().timestamp());
+
+        let lien = lien.clone();
+        self.save()?;
+        Ok(lien)
+    }
+
+    /// Fails unless every active lien on the property has an unused sign-off
+    /// for `action`.
+    pub fn check(&self, property_id: &str, action: LienAction) -> Result<(), LienBlocked> {
+        let missing: Vec<String> = self
+            .active(property_id)
+            .into_iter()
+            .filter(|l| !l.has_sign_off(action))
+            .map(|l| {
+                format!(
+                    "lien {} (priority {}, lender {})",
+                    l.lien_id, l.priority, l.lender_account_id
+                )
+            })
+            .collect();
+
+        if missing.is_empty() {
+            return Ok(());
+        }
+        Err(LienBlocked(format!(
+            "property {} cannot {} without sign-off on {}",
+            property_id,
+            action.as_str(),
+            missing.join(", ")
+        )))
+    }
+
+    /// Marks the sign-offs that allowed `action` as used.
+    pub fn use_sign_offs(&mut self, property_id: &str, action: LienAction) {
+        let now = chrono::Utc::now().timestamp();
+        let mut changed = false;
+
+        for lien in self
+            .liens
+            .values_mut()
+            .filter(|l| l.property_id == property_id && l.status == LienStatus::Active)
+        {
+            if let Some(sign_off) = lien
+                .sign_offs
+                .iter_mut()
+                .find(|s| s.action == action && s.used_at.is_none())
+            {
+                sign_off.used_at = Some(now);
+                changed = true;
+            }
+        }
+
+        if changed {
+            if let Err(e) = self.save() {
+                tracing::warn!("Failed to persist liens: {}", e);
+            }
+        }
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    RegisterLien {
+        input: LienInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.register_lien(op.input, op.api_key.as_deref());
+
+    SignOffLien {
+        lien_id: u64,
+        input: SignOffInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.sign_off_lien(op.lien_id, op.input, op.api_key.as_deref());
+
+    DischargeLien {
+        lien_id: u64,
+        input: DischargeInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.discharge_lien(op.lien_id, op.input, op.api_key.as_deref());
+
+    GetLien {
+        lien_id: u64,
+    } -> serde_json::Value;
+    |client, op| client.get_lien(op.lien_id);
+
+    ListLiens {
+        property_id: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.list_liens(op.property_id.as_deref())
+}
+
+impl MidenClientWrapper {
+    pub fn register_lien(
+        &mut self,
+        input: LienInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        if !self.records.properties.contains_key(&input.property_id) {
+            return Err(anyhow::anyhow!(
+                "Property {} has not been minted",
+                input.property_id
+            ));
+        }
+
+        let lender_hex = self.account_hex(&input.lender_account_id)?;
+        self.authorize_account(api_key, &lender_hex, "lender")?;
+
+        let lien =
+            self.liens
+                .register(input.property_id, lender_hex, input.amount, input.priority)?;
+        tracing::info!(
+            "Registered lien {} on {} (priority {}, amount {})",
+            lien.lien_id,
+            lien.property_id,
+            lien.priority,
+            lien.amount
+        );
+
+        Ok(serde_json::json!(lien))
+    }
+
+    pub fn sign_off_lien(
+        &mut self,
+        lien_id: u64,
+        input: SignOffInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let lender_hex = self.liens.get(lien_id)?.lender_account_id.clone();
+        self.authorize_account(api_key, &lender_hex, "lender")?;
+
+        let lien = self.liens.sign_off(lien_id, input.action)?;
+        tracing::info!(
+            "Lien {}: lender signed off on {}",
+            lien_id,
+            input.action.as_str()
+        );
+
+        Ok(serde_json::json!(lien))
+    }
+
+    pub fn discharge_lien(
+        &mut self,
+        lien_id: u64,
+        input: DischargeInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let lender_hex = self.liens.get(lien_id)?.lender_account_id.clone();
+        self.authorize_account(api_key, &lender_hex, "lender")?;
+
+        let lien = self.liens.discharge(lien_id, input.payoff_tx_id)?;
+        tracing::info!(
+            "Lien {} on {} discharged (payoff tx {})",
+            lien_id,
+            lien.property_id,
+            lien.payoff_tx_id.as_deref().unwrap_or_default()
+        );
+
+        Ok(serde_json::json!(lien))
+    }
+
+    pub fn get_lien(&self, lien_id: u64) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self.liens.get(lien_id)?))
+    }
+
+    pub fn list_liens(&self, property_id: Option<&str>) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self.liens.list(property_id)))
+    }
+}
@@ -0,0 +1,97 @@
+// src/listing.rs
+//
+// Paged access to large listings
+//
+// List endpoints that may return thousands of entries (properties, escrows,
+// operations) are read from the service records one page per client command.
+// The HTTP layer turns consecutive pages into an NDJSON stream, so neither the
+// client task nor the handler ever holds the full serialized response.
+//
+// Consumable notes come from a single client query; they are returned as one
+// page and streamed out entry by entry.
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::MidenClientWrapper;
+
+#[derive(Debug, Clone)]
+pub enum Listing {
+    Properties,
+    Escrows,
+    Operations,
+    ConsumableNotes { account_id: Option<String> },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Page {
+    pub items: Vec<serde_json::Value>,
+    /// Cursor for the following page; None when this was the last one
+    pub next_cursor: Option<usize>,
+}
+
+fn page_of<'a, T, I>(entries: I, total: usize, cursor: usize, limit: usize) -> Result<Page>
+where
+    T: Serialize + 'a,
+    I: Iterator<Item = &'a T>,
+{
+    let items = entries
+        .skip(cursor)
+        .take(limit)
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let end = cursor.saturating_add(items.len());
+    Ok(Page {
+        next_cursor: (end < total && !items.is_empty()).then_some(end),
+        items,
+    })
+}
+
+crate::operations! {
+    ListPage {
+        listing: Listing,
+        cursor: usize,
+        limit: usize,
+    } -> Page;
+    |client, op| client.list_page(op.listing, op.cursor, op.limit).await
+}
+
+impl MidenClientWrapper {
+    /// Returns up to `limit` entries of a listing starting at `cursor`.
+    pub async fn list_page(
+        &mut self,
+        listing: Listing,
+        cursor: usize,
+        limit: usize,
+    ) -> Result<Page> {
+        let limit = limit.max(1);
+
+        match listing {
+            Listing::Properties => {
+                let properties = &self.records.properties;
+                page_of(properties.values(), properties.len(), cursor, limit)
+            }
+            Listing::Escrows => {
+                let escrows = &self.records.escrows;
+                page_of(escrows.values(), escrows.len(), cursor, limit)
+            }
+            Listing::Operations => {
+                let operations = &self.records.operations;
+                page_of(operations.iter(), operations.len(), cursor, limit)
+            }
+            Listing::ConsumableNotes { account_id } => {
+                if cursor > 0 {
+                    return Ok(Page {
+                        items: Vec::new(),
+                        next_cursor: None,
+                    });
+                }
+                Ok(Page {
+                    items: self.get_consumable_notes(account_id).await?,
+                    next_cursor: None,
+                })
+            }
+        }
+    }
+}
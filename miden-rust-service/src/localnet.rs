@@ -0,0 +1,92 @@
+// src/localnet.rs
+//
+// Local Miden node orchestration for the localnet profile
+//
+// Either spawns a node process (LOCALNET_NODE_CMD) or attaches to one already
+// listening at the configured RPC endpoint. In both cases startup blocks until
+// the RPC port accepts TCP connections, so the client's first sync does not
+// race the node boot.
+
+use anyhow::Result;
+use std::time::Instant;
+use tokio::process::{Child, Command};
+
+use crate::config::ServiceConfig;
+
+/// Handle to the local node.
+///
+/// A spawned node is killed when the handle is dropped.
+pub struct LocalNode {
+    child: Option<Child>,
+    rpc_addr: String,
+}
+
+impl LocalNode {
+    /// Spawns (or attaches to) the local node and waits until its RPC is reachable.
+    pub async fn start(config: &ServiceConfig) -> Result<Self> {
+        let rpc_addr = config.rpc.socket_addr();
+
+        let child = match &config.localnet.node_command {
+            Some(command) if !command.is_empty() => {
+                tracing::info!("🚀 Spawning local Miden node: {}", command.join(" "));
+
+                let child = Command::new(&command[0])
+                    .args(&command[1..])
+                    .kill_on_drop(true)
+                    .spawn()
+                    .map_err(|e| anyhow::anyhow!("Failed to spawn local node: {}", e))?;
+
+                Some(child)
+            }
+            _ => {
+                tracing::info!("🔗 Attaching to local Miden node at {}", rpc_addr);
+                None
+            }
+        };
+
+        let mut node = Self { child, rpc_addr };
+        node.wait_until_ready(config.localnet.startup_timeout).await?;
+
+        Ok(node)
+    }
+
+    /// Polls the RPC port until it accepts connections or the timeout elapses.
+    async fn wait_until_ready(&mut self, timeout: std::time::Duration) -> Result<()> {
+        let started = Instant::now();
+
+        loop {
+            if tokio::net::TcpStream::connect(&self.rpc_addr).await.is_ok() {
+                tracing::info!(
+                    "✅ Local node RPC reachable at {} after {:?}",
+                    self.rpc_addr,
+                    started.elapsed()
+                );
+                return Ok(());
+            }
+
+            if let Some(child) = self.child.as_mut() {
+                if let Some(status) = child.try_wait()? {
+                    return Err(anyhow::anyhow!(
+                        "Local node exited during startup with {}",
+                        status
+                    ));
+                }
+            }
+
+            if started.elapsed() >= timeout {
+                return Err(anyhow::anyhow!(
+                    "Local node RPC at {} not reachable after {:?}",
+                    self.rpc_addr,
+                    timeout
+                ));
+            }
+
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        }
+    }
+
+    /// True when this process spawned the node (rather than attaching).
+    pub fn is_managed(&self) -> bool {
+        self.child.is_some()
+    }
+}
@@ -0,0 +1,314 @@
+// src/logging.rs
+//
+// Log output
+//
+// LOG_FORMAT=text (the default) keeps the human-readable lines. LOG_FORMAT=json
+// writes one JSON object per event instead, with the fields of the spans it
+// happened in merged into the object, so log stores can search on them:
+// - request_id: X-Request-Id of the HTTP request (generated when absent and
+//   echoed in the response), on everything logged while serving it
+//   (`trace_request`)
+// - command: the client command a request queued, on everything the client
+//   task logs while running it
+// - account: subject of the operation the command journaled (records.rs)
+// - tx_id: transaction of that operation, once it is known
+// - duration_ms: on the "request finished" and "command finished" events
+//
+// LOG_LEVEL sets the default level and LOG_LEVELS adds per-module levels as
+// comma-separated `target=level` directives, e.g.
+// "miden_client=warn,miden_rust_service::escrow=trace". RUST_LOG, when set,
+// replaces both.
+//
+// Built with the `otlp` feature, events are also shipped to an OpenTelemetry
+// collector when OTLP_ENDPOINT is set (OTLP over HTTP, e.g.
+// http://collector:4318/v1/logs), under the service name OTLP_SERVICE_NAME.
+
+use anyhow::Result;
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::{fmt, time::Instant};
+use tracing::{field::Field, Event, Instrument, Subscriber};
+use tracing_subscriber::{
+    fmt::{
+        format::{JsonFields, Writer},
+        FmtContext, FormatEvent, FormatFields, FormattedFields,
+    },
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
+
+use crate::config::env_var;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client-supplied request ID kept; longer ones are replaced.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(anyhow::anyhow!("Unknown log format: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    pub format: LogFormat,
+    /// EnvFilter directives (RUST_LOG, or LOG_LEVEL plus LOG_LEVELS)
+    pub filter: String,
+    pub otlp_endpoint: Option<String>,
+    pub otlp_service_name: String,
+}
+
+impl LogConfig {
+    /// Reads the logging settings from the environment (and .env, if present).
+    /// Separate from `ServiceConfig`, so that logging is up before the rest of
+    /// the configuration is read.
+    pub fn from_env() -> Result<Self> {
+        let _ = dotenvy::dotenv();
+
+        let format = match env_var("LOG_FORMAT") {
+            Some(format) => format.trim().parse()?,
+            None => LogFormat::Text,
+        };
+        let filter = match env_var("RUST_LOG") {
+            Some(filter) => filter,
+            None => {
+                let level = env_var("LOG_LEVEL").unwrap_or_else(|| "info".to_string());
+                match env_var("LOG_LEVELS") {
+                    Some(levels) => format!("{},{}", level.trim(), levels.trim()),
+                    None => format!("{},miden_rust_service=debug", level.trim()),
+                }
+            }
+        };
+        // Refuse bad directives instead of silently logging at the wrong level
+        EnvFilter::try_new(&filter)
+            .map_err(|e| anyhow::anyhow!("Invalid log levels \"{}\": {}", filter, e))?;
+
+        Ok(Self {
+            format,
+            filter,
+            otlp_endpoint: env_var("OTLP_ENDPOINT"),
+            otlp_service_name: env_var("OTLP_SERVICE_NAME")
+                .unwrap_or_else(|| "miden-rust-service".to_string()),
+        })
+    }
+}
+
+/// Keeps the log shipping pipeline alive; flushes it when dropped.
+pub struct LogGuard {
+    #[cfg(feature = "otlp")]
+    provider: Option<opentelemetry_sdk::logs::SdkLoggerProvider>,
+}
+
+impl Drop for LogGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otlp")]
+        if let Some(provider) = self.provider.take() {
+            let _ = provider.shutdown();
+        }
+    }
+}
+
+/// Installs the global subscriber. Hold the guard until shutdown.
+pub fn init(config: &LogConfig) -> Result<LogGuard> {
+    let output = match config.format {
+        LogFormat::Text => tracing_subscriber::fmt::layer().boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .fmt_fields(JsonFields::new())
+            .event_format(JsonLines)
+            .boxed(),
+    };
+
+    #[cfg(feature = "otlp")]
+    let (shipping, provider) = match &config.otlp_endpoint {
+        Some(endpoint) => {
+            let (layer, provider) = otlp::layer(endpoint, &config.otlp_service_name)?;
+            (Some(layer), Some(provider))
+        }
+        None => (None, None),
+    };
+    #[cfg(not(feature = "otlp"))]
+    let shipping: Option<Box<dyn Layer<_> + Send + Sync>> = None;
+
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_new(&config.filter)?)
+        .with(output)
+        .with(shipping)
+        .try_init()?;
+
+    #[cfg(not(feature = "otlp"))]
+    if config.otlp_endpoint.is_some() {
+        tracing::warn!(
+            "⚠️  OTLP_ENDPOINT is set but the service was built without the otlp feature"
+        );
+    }
+
+    Ok(LogGuard {
+        #[cfg(feature = "otlp")]
+        provider,
+    })
+}
+
+/// Middleware: serves the request in a span carrying its request ID, echoes
+/// the ID in the response and logs the outcome with its duration.
+pub async fn trace_request(req: Request, next: Next) -> Response {
+    let request_id = req
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+        .map(str::to_string)
+        .unwrap_or_else(|| hex::encode(rand::random::<[u8; 8]>()));
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %req.method(),
+        path = %req.uri().path(),
+    );
+
+    let started = Instant::now();
+    let mut response = next.run(req).instrument(span.clone()).await;
+    span.in_scope(|| {
+        tracing::info!(
+            status = response.status().as_u16(),
+            duration_ms = started.elapsed().as_millis() as u64,
+            "Request finished"
+        )
+    });
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// One JSON object per event: timestamp, level, target, the fields of the
+/// enclosing spans (outermost first, so inner spans win) and the event's own.
+struct JsonLines;
+
+impl<S, N> FormatEvent<S, N> for JsonLines
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        let mut line = serde_json::Map::new();
+        line.insert(
+            "timestamp".into(),
+            chrono::Utc::now()
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+                .into(),
+        );
+        line.insert("level".into(), metadata.level().as_str().into());
+        line.insert("target".into(), metadata.target().into());
+
+        if let Some(scope) = ctx.event_scope() {
+            for span in scope.from_root() {
+                let extensions = span.extensions();
+                let Some(fields) = extensions.get::<FormattedFields<N>>() else {
+                    continue;
+                };
+                // Spans without recorded fields format as an empty string
+                if let Ok(serde_json::Value::Object(fields)) = serde_json::from_str(fields) {
+                    line.extend(fields);
+                }
+            }
+        }
+
+        event.record(&mut JsonVisitor(&mut line));
+        writeln!(writer, "{}", serde_json::Value::Object(line))
+    }
+}
+
+struct JsonVisitor<'a>(&'a mut serde_json::Map<String, serde_json::Value>);
+
+impl tracing::field::Visit for JsonVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().into(), format!("{:?}", value).into());
+    }
+}
+
+#[cfg(feature = "otlp")]
+mod otlp {
+    use anyhow::Result;
+    use opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge;
+    use opentelemetry_otlp::{LogExporter, WithExportConfig};
+    use opentelemetry_sdk::{logs::SdkLoggerProvider, Resource};
+    use tracing::Subscriber;
+    use tracing_subscriber::{registry::LookupSpan, EnvFilter, Layer};
+
+    /// Crates the exporter itself logs through; shipping their events would
+    /// feed back into the exporter.
+    const EXPORTER_TARGETS: &str = "hyper=off,h2=off,reqwest=off,opentelemetry=off";
+
+    pub fn layer<S>(
+        endpoint: &str,
+        service_name: &str,
+    ) -> Result<(Box<dyn Layer<S> + Send + Sync>, SdkLoggerProvider)>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let exporter = LogExporter::builder()
+            .with_http()
+            .with_endpoint(endpoint)
+            .build()?;
+        let provider = SdkLoggerProvider::builder()
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(service_name.to_string())
+                    .build(),
+            )
+            .with_batch_exporter(exporter)
+            .build();
+
+        let layer = OpenTelemetryTracingBridge::new(&provider)
+            .with_filter(EnvFilter::try_new(format!("trace,{}", EXPORTER_TARGETS))?)
+            .boxed();
+        Ok((layer, provider))
+    }
+}
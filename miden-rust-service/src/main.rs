@@ -7,132 +7,83 @@
 // - HTTP handlers do not call client methods directly
 // - All client operations are executed via a command queue (mpsc + oneshot)
 // - This prevents concurrency hazards and keeps the blockchain client single-threaded
+// - HTTP handlers live in routes/ by area; main.rs builds the router around
+//   them and runs the client task and the background drivers
 //
 // Features:
 // - Property minting, note consumption, transfers, balances
 // - Escrow: create, fund, release, refund
-// - ZK proofs (demo): accreditation, jurisdiction, ownership
+// - ZK proofs (demo): accreditation, jurisdiction, ownership, identity attributes
 
 use axum::{
-    extract::State,
-    routing::{get, post},
+    extract::{DefaultBodyLimit, FromRequest, FromRequestParts, MatchedPath, Query, Request, State},
+    middleware,
+    routing::get,
     Router,
     Json,
-    http::StatusCode,
+    http::{header, request::Parts, HeaderMap, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
 };
-use serde::{Deserialize, Serialize};
-use tokio::sync::{mpsc, oneshot};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
 use tokio::task::LocalSet;
-use tower_http::cors::CorsLayer;
-use tracing::{info, error};
+use tokio_util::sync::CancellationToken;
+use axum_server::tls_rustls::RustlsConfig;
+use std::sync::Arc;
+use tracing::{info, error, Instrument};
+
+use miden_rust_service::{
+    MidenClientWrapper,
+    commands::{Dispatch, Operation},
+    config::{Profile, ServiceConfig},
+    deadlines::{self, BackgroundJobs, DeadlinePolicy, JobCancellations},
+    etag::{EtagResource, ResourceEtag},
+    localnet::LocalNode,
+    logging::{self, LogConfig},
+    auctions::{AuctionEvent, AUCTION_FEED_CAPACITY},
+    billing::{UsageKind, UsageMeter},
+    demo::DemoRuns,
+    principals::{hash_key, AdminKeys, PrincipalStore},
+    read_cache::{CachedRead, ReadCache},
+    queue_stats::QueueStats,
+    slo::SloTracker,
+    mint_coalescing::{MintCoalescer, MintPropertyBatch},
+    mint_jobs::{RunMintBatchChunk, UnfinishedMintBatches},
+    sagas::{RunSagaStep, SagaProgress, UnfinishedSagas},
+    scheduler::SchedulerTick,
+    feature_flags::{FeatureFlag, FeatureFlags},
+    custodial::OtpDelivery,
+    esign::SignatureProvider,
+    swaps::LIQUIDITY_ALIAS,
+    media::MediaPolicy,
+    retry_queue::{RetryEvent, RETRY_FEED_CAPACITY},
+    offline_queue,
+    rpc_failover::{self, RpcPool},
+    read_replica,
+    escrow_monitor::{StaleEscrowEvent, STALE_ESCROW_FEED_CAPACITY},
+    sync_deltas::{SyncDelta, SYNC_FEED_CAPACITY},
+    subscriptions::{self, SubscriptionDelivery},
+    startup::StartupProgress,
+    api_version::{self, VersionPolicy},
+    timestamps,
+    http_security,
+    body_limits,
+    tls,
+    validation::Validate,
+};
 
-use miden_rust_service::{MidenClientWrapper, escrow::{EscrowAccount, EscrowStatus}};
-use miden_client::{account::AccountId, Serializable, Deserializable};
+mod routes;
 
 // ============================================================================
 // COMMAND PATTERN FOR CLIENT OPERATIONS
 // ============================================================================
 //
-// Each HTTP request creates a command and sends it to the client task.
-// The response is returned over a oneshot channel.
+// Each HTTP request sends an operation to the client task (CommandSender
+// below) and gets its result back over a oneshot channel.
 // This ensures all Miden client calls remain serialized and deterministic.
-
-#[derive(Debug)]
-enum ClientCommand {
-    MintProperty {
-        property_id: String,
-        owner_account_id: String,
-        ipfs_cid: String,
-        property_type: u8,
-        price: u64,
-        response: oneshot::Sender<Result<(String, String), String>>,
-    },
-    GetAccountInfo {
-        response: oneshot::Sender<Result<serde_json::Value, String>>,
-    },
-    GetConsumableNotes {
-        account_id: Option<String>,
-        response: oneshot::Sender<Result<Vec<serde_json::Value>, String>>,
-    },
-    ConsumeNote {
-        note_id: String,
-        account_id: Option<String>,
-        response: oneshot::Sender<Result<String, String>>,
-    },
-    TransferProperty {
-        property_id: String,
-        to_account_id: String,
-        response: oneshot::Sender<Result<String, String>>,
-    },
-    SendTokens {
-        to_account_id: String,
-        amount: u64,
-        response: oneshot::Sender<Result<String, String>>,
-    },
-    GetBalance {
-        account_id: String,
-        response: oneshot::Sender<Result<serde_json::Value, String>>,
-    },
-
-    // Escrow commands
-    CreateEscrow {
-        buyer_account_str: String,
-        seller_account_str: String,
-        amount: u64,
-        resp: oneshot::Sender<Result<EscrowAccount, String>>,
-    },
-    FundEscrow {
-        escrow: EscrowAccount,
-        resp: oneshot::Sender<Result<String, String>>,
-    },
-    ReleaseEscrow {
-        escrow: EscrowAccount,
-        resp: oneshot::Sender<Result<String, String>>,
-    },
-    RefundEscrow {
-        escrow: EscrowAccount,
-        resp: oneshot::Sender<Result<String, String>>,
-    },
-
-    // ZK proof commands - accreditation
-    GenerateAccreditationProof {
-        net_worth: u64,
-        threshold: u64,
-        response: oneshot::Sender<Result<serde_json::Value, String>>,
-    },
-    VerifyAccreditationProof {
-        proof: String,
-        program_hash: String,
-        public_inputs: Vec<u64>,
-        response: oneshot::Sender<Result<serde_json::Value, String>>,
-    },
-
-    // ZK proof commands - jurisdiction
-    GenerateJurisdictionProof {
-        country_code: String,
-        restricted_countries: Vec<String>,
-        response: oneshot::Sender<Result<serde_json::Value, String>>,
-    },
-    VerifyJurisdictionProof {
-        proof: String,
-        program_hash: String,
-        public_inputs: Vec<u64>,
-        response: oneshot::Sender<Result<serde_json::Value, String>>,
-    },
-
-    // ZK proof commands - ownership
-    GenerateOwnershipProof {
-        property_id: String,
-        document_hash: String,
-        response: oneshot::Sender<Result<serde_json::Value, String>>,
-    },
-    VerifyOwnershipProof {
-        proof: String,
-        program_hash: String,
-        public_inputs: Vec<String>,
-        response: oneshot::Sender<Result<serde_json::Value, String>>,
-    },
-}
+//
+// Operations are declared next to the client method they call with
+// `operations!` (commands.rs) and sent with `call` (routes/mod.rs).
 
 // ============================================================================
 // APPLICATION STATE
@@ -143,1439 +94,1034 @@ enum ClientCommand {
 
 #[derive(Clone)]
 struct AppState {
-    client_tx: mpsc::Sender<ClientCommand>,
-}
-
-// ============================================================================
-// REQUEST/RESPONSE TYPES
-// ============================================================================
-//
-// Payload structs define the public API contract.
-// Many endpoints return a uniform shape: { success, data/tx_id, error }.
-
-#[derive(Debug, Deserialize)]
-struct MintPropertyRequest {
-    property_id: String,
-    owner_account_id: String,
-    ipfs_cid: String,
-    property_type: u8,
-    price: u64,
-}
-
-#[derive(Debug, Serialize)]
-struct MintPropertyResponse {
-    success: bool,
-    transaction_id: Option<String>,
-    note_id: Option<String>,
-    error: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-struct TransferPropertyRequest {
-    property_id: String,
-    to_account_id: String,
-}
-
-#[derive(Debug, Serialize)]
-struct TransferPropertyResponse {
-    success: bool,
-    transaction_id: Option<String>,
-    error: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-struct SendTokensRequest {
-    to_account_id: String,
-    amount: u64,
-}
-
-#[derive(Debug, Serialize)]
-struct SendTokensResponse {
-    success: bool,
-    transaction_id: Option<String>,
-    error: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-struct ConsumeNoteRequest {
-    note_id: String,
-    account_id: Option<String>,
-}
-
-#[derive(Debug, Serialize)]
-struct ConsumeNoteResponse {
-    success: bool,
-    transaction_id: Option<String>,
-    error: Option<String>,
-}
-
-#[derive(Debug, Serialize)]
-struct ConsumableNotesResponse {
-    success: bool,
-    notes: Vec<serde_json::Value>,
-    error: Option<String>,
-}
-
-#[derive(Debug, Serialize)]
-struct AccountInfoResponse {
-    success: bool,
-    data: Option<serde_json::Value>,
-    error: Option<String>,
+    client_tx: CommandSender,
+    read_cache: ReadCache,
+    /// Auction events published by the client task (auctions.rs)
+    auction_feed: broadcast::Sender<AuctionEvent>,
+    /// Retry job events published by the client task (retry_queue.rs)
+    retry_feed: broadcast::Sender<RetryEvent>,
+    /// Stale escrow events published by the client task (escrow_monitor.rs)
+    stale_escrow_feed: broadcast::Sender<StaleEscrowEvent>,
+    /// Changes found by syncs of the client task (sync_deltas.rs)
+    sync_feed: broadcast::Sender<SyncDelta>,
+    /// Websocket subscription deliveries from the dispatcher (subscriptions.rs)
+    subscription_deliveries: broadcast::Sender<SubscriptionDelivery>,
+    /// Request deadlines and writes finishing after theirs (deadlines.rs)
+    deadlines: Arc<DeadlinePolicy>,
+    background_jobs: BackgroundJobs,
+    load_shed_queue_depth: usize,
+    /// Tokens of cancellable jobs, shared with the client task
+    job_cancellations: JobCancellations,
+    /// Client initialization progress (startup.rs)
+    startup: StartupProgress,
+    /// Request outcomes per endpoint (slo.rs)
+    slo: SloTracker,
+    /// Photo storage and upload limits (media.rs)
+    media: MediaPolicy,
+    /// Provider closing documents are sent to for signature (esign.rs)
+    esign: Option<Arc<dyn SignatureProvider>>,
+    /// How custodial sign-in and payment codes are sent (custodial.rs)
+    otp_delivery: Option<OtpDelivery>,
+    /// RPC endpoints and their health (rpc_failover.rs)
+    rpc: RpcPool,
+    /// Keys the admin gate accepts, kept current by the client task
+    /// (principals.rs)
+    admin_keys: AdminKeys,
+    /// Demo scenario runs and their progress (demo.rs)
+    demo_runs: DemoRuns,
+    /// Batches single mints into shared faucet transactions (mint_coalescing.rs)
+    mint_coalescer: Option<MintCoalescer>,
 }
 
-#[derive(Debug, Serialize)]
-struct BalanceResponse {
-    success: bool,
-    balance: Option<serde_json::Value>,
-    error: Option<String>,
+/// An operation with the cancellation token of the request that sent it.
+struct QueuedCommand {
+    op: Dispatch,
+    cancellation: Option<CancellationToken>,
+    /// Whether the request opted in to offline mode (offline_queue.rs)
+    offline: bool,
+    /// Queue stats ticket (queue_stats.rs)
+    ticket: u64,
+    /// Span the client task runs the command in (see logging.rs)
+    span: tracing::Span,
 }
 
-#[derive(Debug, Serialize)]
-struct HealthResponse {
-    status: String,
-    service: String,
+/// Sending side of the command queue.
+///
+/// Tags each command with the current request's cancellation token (see
+/// deadlines.rs), so the client task can drop commands nobody waits for, and
+/// with its offline mode choice (see offline_queue.rs), and records it in the
+/// queue stats behind GET /admin/queue. The command runs in a span under the
+/// request's, so its logs carry the request ID.
+#[derive(Clone)]
+struct CommandSender {
+    tx: mpsc::Sender<QueuedCommand>,
+    stats: QueueStats,
 }
 
-// Escrow request/response types
-
-#[derive(Debug, Deserialize)]
-struct CreateEscrowRequest {
-    buyer_account_id: String,
-    seller_account_id: String,
-    amount: u64,
-}
+impl CommandSender {
+    /// Queues an operation for the client task; the receiver gets its result.
+    /// None if the client task is gone.
+    async fn dispatch<O: Operation>(
+        &self,
+        op: O,
+    ) -> Option<oneshot::Receiver<Result<O::Output, String>>> {
+        let (op, rx) = Dispatch::new(op);
+        let span = tracing::info_span!(
+            "command",
+            command = op.name(),
+            account = tracing::field::Empty,
+            tx_id = tracing::field::Empty,
+        );
+        let ticket = self.stats.enqueue(op.name().to_string());
+        let queued = QueuedCommand {
+            op,
+            cancellation: deadlines::current_cancellation(),
+            offline: offline_queue::opted_in(),
+            ticket,
+            span,
+        };
+        if self.tx.send(queued).await.is_err() {
+            self.stats.discard(ticket);
+            return None;
+        }
+        Some(rx)
+    }
 
-#[derive(Debug, Serialize)]
-struct CreateEscrowResponse {
-    escrow_account_id: String,
-    buyer_account_id: String,
-    seller_account_id: String,
-    amount: u64,
-    status: String,
+    /// Commands waiting for the client task.
+    fn queued(&self) -> usize {
+        self.tx.max_capacity() - self.tx.capacity()
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct FundEscrowRequest {
-    escrow_account_id: String,
-    buyer_account_id: String,
-    seller_account_id: String,
-    amount: u64,
-}
+// ============================================================================
+// HELPER FUNCTIONS
+// ============================================================================
 
-#[derive(Debug, Deserialize)]
-struct ReleaseEscrowRequest {
-    escrow_account_id: String,
-    buyer_account_id: String,
-    seller_account_id: String,
-    amount: u64,
-}
+/// JSON body extractor that also runs the payload's validation rules.
+///
+/// Undecodable bodies keep axum's status (400, 415 or 422); rule violations
+/// answer 422 listing every offending field.
+struct ValidJson<T>(T);
+
+#[axum::async_trait]
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(payload) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection| {
+                (
+                    rejection.status(),
+                    Json(serde_json::json!({
+                        "success": false,
+                        "error": rejection.body_text(),
+                        "errors": [],
+                    })),
+                )
+                    .into_response()
+            })?;
+
+        payload.validated().map_err(|errors| {
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(serde_json::json!({
+                    "success": false,
+                    "error": errors.summary(),
+                    "errors": errors.errors,
+                })),
+            )
+                .into_response()
+        })?;
 
-#[derive(Debug, Deserialize)]
-struct RefundEscrowRequest {
-    escrow_account_id: String,
-    buyer_account_id: String,
-    seller_account_id: String,
-    amount: u64,
+        Ok(ValidJson(payload))
+    }
 }
 
-// ZK proof request types - accreditation
+/// Query string extractor that also runs the query's validation rules, with
+/// the rejections of `ValidJson`.
+struct ValidQuery<T>(T);
+
+#[axum::async_trait]
+impl<T, S> FromRequestParts<S> for ValidQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| {
+                (
+                    rejection.status(),
+                    Json(serde_json::json!({
+                        "success": false,
+                        "error": rejection.body_text(),
+                        "errors": [],
+                    })),
+                )
+                    .into_response()
+            })?;
+
+        query.validated().map_err(|errors| {
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(serde_json::json!({
+                    "success": false,
+                    "error": errors.summary(),
+                    "errors": errors.errors,
+                })),
+            )
+                .into_response()
+        })?;
 
-#[derive(Debug, Deserialize)]
-struct GenerateAccreditationProofRequest {
-    net_worth: u64,
-    threshold: u64,
+        Ok(ValidQuery(query))
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct VerifyAccreditationProofRequest {
-    proof: String,
-    program_hash: String,
-    public_inputs: Vec<u64>,
+/// API key presented in the `X-API-Key` header, if any.
+fn api_key_header(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("x-api-key")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
 }
 
-// ZK proof request types - jurisdiction
-
-#[derive(Debug, Deserialize)]
-struct GenerateJurisdictionProofRequest {
-    country_code: String,
-    restricted_countries: Vec<String>,
+/// Wallet session token presented in the `X-Wallet-Session` header, if any.
+fn wallet_session_header(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("x-wallet-session")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
 }
 
-#[derive(Debug, Deserialize)]
-struct VerifyJurisdictionProofRequest {
-    proof: String,
-    program_hash: String,
-    public_inputs: Vec<u64>,
+/// Passes the request's `X-Offline-Queue` choice on to the commands it sends
+/// (see offline_queue.rs).
+async fn offline_mode(req: Request, next: middleware::Next) -> Response {
+    let opt_in = req
+        .headers()
+        .get(offline_queue::OFFLINE_QUEUE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "true" | "1"));
+    offline_queue::with_opt_in(opt_in, next.run(req)).await
 }
 
-// ZK proof request types - ownership
-
-#[derive(Debug, Deserialize)]
-struct GenerateOwnershipProofRequest {
-    property_id: String,
-    document_hash: String,
+/// Refuses every route a read replica does not serve (see read_replica.rs).
+async fn read_replica_guard(
+    matched_path: Option<MatchedPath>,
+    req: Request,
+    next: middleware::Next,
+) -> Response {
+    let route = matched_path
+        .as_ref()
+        .map(|p| api_version::unversioned_path(p.as_str()));
+    if read_replica::serves(req.method(), route) {
+        return next.run(req).await;
+    }
+    (
+        StatusCode::METHOD_NOT_ALLOWED,
+        [(header::ALLOW, "GET, HEAD")],
+        Json(serde_json::json!({
+            "success": false,
+            "error": format!(
+                "Read replica: {} {} is served by the primary instance",
+                req.method(),
+                route.unwrap_or_default()
+            )
+        })),
+    )
+        .into_response()
 }
 
-#[derive(Debug, Deserialize)]
-struct VerifyOwnershipProofRequest {
-    proof: String,
-    program_hash: String,
-    public_inputs: Vec<String>,
+/// Answers 404 to routes of features turned off (see feature_flags.rs).
+async fn feature_gate(
+    State(features): State<FeatureFlags>,
+    matched_path: Option<MatchedPath>,
+    req: Request,
+    next: middleware::Next,
+) -> Response {
+    let feature = matched_path
+        .as_ref()
+        .and_then(|p| FeatureFlag::for_route(api_version::unversioned_path(p.as_str())));
+    match feature {
+        Some(feature) if !features.is_enabled(feature) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "success": false,
+                "error": format!("The {} feature is turned off", feature.as_str())
+            })),
+        )
+            .into_response(),
+        _ => next.run(req).await,
+    }
 }
 
-// ============================================================================
-// HELPER FUNCTIONS
-// ============================================================================
-
-/// Parses an AccountId from a hex string (optionally 0x-prefixed).
-/// This is used by escrow endpoints that receive IDs as hex strings.
-fn parse_account_id_from_hex(hex_str: &str) -> Result<AccountId, String> {
-    let hex_str = hex_str.strip_prefix("0x").unwrap_or(hex_str);
-    let bytes = hex::decode(hex_str).map_err(|e| format!("Failed to decode hex: {}", e))?;
-    AccountId::read_from_bytes(&bytes[..]).map_err(|e| format!("Failed to deserialize AccountId: {}", e))
+/// Records the outcome and latency of every API request against its endpoint
+/// (see slo.rs).
+async fn track_slo(
+    State(state): State<AppState>,
+    matched_path: Option<MatchedPath>,
+    req: Request,
+    next: middleware::Next,
+) -> Response {
+    // Unmatched paths are not endpoints; they would only add noise
+    let Some(matched_path) = matched_path else {
+        return next.run(req).await;
+    };
+    let route = api_version::unversioned_path(matched_path.as_str()).to_string();
+    let method = req.method().clone();
+
+    let started = std::time::Instant::now();
+    let response = next.run(req).await;
+    state
+        .slo
+        .record(method.as_str(), &route, response.status().as_u16(), started.elapsed());
+    response
 }
 
-// ============================================================================
-// MAIN SERVER
-// ============================================================================
-//
-// Server responsibilities:
-// - Start the single Miden client task
-// - Start HTTP server
-// - Route each HTTP request into a queued command
+/// Meters the request against its API key, and the transaction it submitted
+/// once it succeeded (see billing.rs).
+async fn meter_usage(
+    State(meter): State<UsageMeter>,
+    matched_path: Option<MatchedPath>,
+    req: Request,
+    next: middleware::Next,
+) -> Response {
+    let Some(key) = api_key_header(req.headers()) else {
+        return next.run(req).await;
+    };
+    let key_hash = hash_key(&key);
+    meter.record(&key_hash, UsageKind::ApiCall, chrono::Utc::now().timestamp());
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "info,miden_rust_service=debug".into()),
+    let operation = matched_path.as_ref().and_then(|p| {
+        UsageKind::for_operation(
+            req.method().as_str(),
+            api_version::unversioned_path(p.as_str()),
         )
-        .init();
-
-    info!("Starting Miden Rust Service with Escrow + ZK Proofs (Accreditation + Jurisdiction)");
-
-    // Command channel: handlers -> client task
-    let (client_tx, mut client_rx) = mpsc::channel::<ClientCommand>(100);
-
-    // LocalSet to run the client task locally (single-threaded context)
-    let local = LocalSet::new();
-
-    // Client task: owns the Miden client and handles all commands sequentially
-    local.spawn_local(async move {
-        info!("Initializing Miden client");
-        match MidenClientWrapper::new().await {
-            Ok(mut client) => {
-                info!("Miden client initialized successfully");
-                info!("Client task ready to process commands");
-                info!("ZK Proof system enabled (Ownership)");
-
-                while let Some(cmd) = client_rx.recv().await {
-                    match cmd {
-                        ClientCommand::MintProperty {
-                            property_id,
-                            owner_account_id,
-                            ipfs_cid,
-                            property_type,
-                            price,
-                            response,
-                        } => {
-                            info!("Processing mint property: {}", property_id);
-                            let result = client
-                                .mint_property_nft(
-                                    &property_id,
-                                    &owner_account_id,
-                                    &ipfs_cid,
-                                    property_type,
-                                    price,
-                                )
-                                .await
-                                .map_err(|e| e.to_string());
-                            let _ = response.send(result);
-                        }
-                        ClientCommand::GetAccountInfo { response } => {
-                            info!("Processing get account info");
-                            let result = client.get_account_info().await.map_err(|e| e.to_string());
-                            let _ = response.send(result);
-                        }
-                        ClientCommand::GetConsumableNotes { account_id, response } => {
-                            info!("Processing get consumable notes");
-                            let result = client
-                                .get_consumable_notes(account_id)
-                                .await
-                                .map_err(|e| e.to_string());
-                            let _ = response.send(result);
-                        }
-                        ClientCommand::ConsumeNote { note_id, account_id, response } => {
-                            info!("Processing consume note: {}", note_id);
-                            let result = client
-                                .consume_note(&note_id, account_id)
-                                .await
-                                .map_err(|e| e.to_string());
-                            let _ = response.send(result);
-                        }
-                        ClientCommand::TransferProperty { property_id, to_account_id, response } => {
-                            info!("Processing transfer property: {} to {}", property_id, to_account_id);
-                            let result = client
-                                .transfer_property(&property_id, &to_account_id)
-                                .await
-                                .map_err(|e| e.to_string());
-                            let _ = response.send(result);
-                        }
-                        ClientCommand::SendTokens { to_account_id, amount, response } => {
-                            info!("Processing send tokens: {} to {}", amount, to_account_id);
-                            let result = client
-                                .send_tokens(&to_account_id, amount)
-                                .await
-                                .map_err(|e| e.to_string());
-                            let _ = response.send(result);
-                        }
-                        ClientCommand::GetBalance { account_id, response } => {
-                            info!("Processing get balance: {}", account_id);
-                            let result = client
-                                .get_account_balance(&account_id)
-                                .await
-                                .map_err(|e| e.to_string());
-                            let _ = response.send(result);
-                        }
-                        ClientCommand::CreateEscrow { buyer_account_str, seller_account_str, amount, resp } => {
-                            info!("Processing create escrow");
-                            let result = client
-                                .create_escrow(&buyer_account_str, &seller_account_str, amount)
-                                .await
-                                .map_err(|e| e.to_string());
-                            let _ = resp.send(result);
-                        }
-                        ClientCommand::FundEscrow { escrow, resp } => {
-                            info!("Processing fund escrow");
-                            let result = client.fund_escrow(&escrow).await.map_err(|e| e.to_string());
-                            let _ = resp.send(result);
-                        }
-                        ClientCommand::ReleaseEscrow { escrow, resp } => {
-                            info!("Processing release escrow");
-                            let result = client
-                                .release_escrow(&escrow)
-                                .await
-                                .map_err(|e| e.to_string());
-                            let _ = resp.send(result);
-                        }
-                        ClientCommand::RefundEscrow { escrow, resp } => {
-                            info!("Processing refund escrow");
-                            let result = client
-                                .refund_escrow(&escrow)
-                                .await
-                                .map_err(|e| e.to_string());
-                            let _ = resp.send(result);
-                        }
-                        ClientCommand::GenerateAccreditationProof { net_worth, threshold, response } => {
-                            info!("Processing generate accreditation proof");
-                            let result = client
-                                .generate_accreditation_proof(net_worth, threshold)
-                                .await
-                                .map_err(|e| e.to_string());
-                            let _ = response.send(result);
-                        }
-                        ClientCommand::VerifyAccreditationProof { proof, program_hash, public_inputs, response } => {
-                            info!("Processing verify accreditation proof");
-                            let result = client
-                                .verify_accreditation_proof(&proof, &program_hash, public_inputs)
-                                .await
-                                .map_err(|e| e.to_string());
-                            let _ = response.send(result);
-                        }
-                        ClientCommand::GenerateJurisdictionProof { country_code, restricted_countries, response } => {
-                            info!("Processing generate jurisdiction proof");
-                            let result = client
-                                .generate_jurisdiction_proof(&country_code, restricted_countries)
-                                .await
-                                .map_err(|e| e.to_string());
-                            let _ = response.send(result);
-                        }
-                        ClientCommand::VerifyJurisdictionProof { proof, program_hash, public_inputs, response } => {
-                            info!("Processing verify jurisdiction proof");
-                            let result = client
-                                .verify_jurisdiction_proof(&proof, &program_hash, public_inputs)
-                                .await
-                                .map_err(|e| e.to_string());
-                            let _ = response.send(result);
-                        }
-                        ClientCommand::GenerateOwnershipProof { property_id, document_hash, response } => {
-                            info!("Processing generate ownership proof");
-                            let result = client
-                                .generate_ownership_proof(&property_id, &document_hash)
-                                .await
-                                .map_err(|e| e.to_string());
-                            let _ = response.send(result);
-                        }
-                        ClientCommand::VerifyOwnershipProof { proof, program_hash, public_inputs, response } => {
-                            info!("Processing verify ownership proof");
-                            let result = client
-                                .verify_ownership_proof(&proof, &program_hash, public_inputs)
-                                .await
-                                .map_err(|e| e.to_string());
-                            let _ = response.send(result);
-                        }
-                    }
-                }
-
-                error!("Client task channel closed");
-            }
-            Err(e) => {
-                error!("Failed to initialize Miden client: {}", e);
-            }
-        }
     });
-
-    let state = AppState { client_tx };
-
-    // Router setup
-    let app = Router::new()
-        .route("/health", get(health_check))
-        .route("/get-account", get(get_account_info))
-        .route("/mint-property", post(mint_property))
-        .route("/get-consumable-notes", get(get_consumable_notes))
-        .route("/consume-note", post(consume_note))
-        .route("/transfer-property", post(transfer_property))
-        .route("/send-tokens", post(send_tokens))
-        .route("/get-balance/:account_id", get(get_balance))
-        // Escrow endpoints
-        .route("/create-escrow", post(create_escrow))
-        .route("/fund-escrow", post(fund_escrow))
-        .route("/release-escrow", post(release_escrow))
-        .route("/refund-escrow", post(refund_escrow))
-        // ZK proof endpoints - accreditation
-        .route("/generate-accreditation-proof", post(generate_accreditation_proof))
-        .route("/verify-accreditation-proof", post(verify_accreditation_proof))
-        // ZK proof endpoints - jurisdiction
-        .route("/generate-jurisdiction-proof", post(generate_jurisdiction_proof))
-        .route("/verify-jurisdiction-proof", post(verify_jurisdiction_proof))
-        // ZK proof endpoints - ownership
-        .route("/generate-ownership-proof", post(generate_ownership_proof))
-        .route("/verify-ownership-proof", post(verify_ownership_proof))
-        .with_state(state)
-        .layer(CorsLayer::permissive());
-
-    let addr = "127.0.0.1:3000";
-    info!("Server listening on http://{}", addr);
-    info!("Escrow system enabled");
-    info!("ZK Proof system enabled (Accreditation)");
-    info!("ZK Proof system enabled (Jurisdiction)");
-
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-
-    // Run both:
-    // - local client task (LocalSet)
-    // - Axum server
-    //
-    // If the client task ends unexpectedly, we log and exit.
-    // If the server ends, we return its result.
-    tokio::select! {
-        _ = local => {
-            error!("LocalSet (client task) terminated");
-        }
-        result = axum::serve(listener, app) => {
-            result?;
-        }
+    let response = next.run(req).await;
+
+    // Failures answer with an error status (routes/mod.rs), and 202 means the
+    // operation was queued or awaits approval, so nothing was submitted yet.
+    // The body is never read: the response goes out as the route made it.
+    let status = response.status();
+    let submitted = status.is_success() && status != StatusCode::ACCEPTED;
+    if let Some(kind) = operation.filter(|_| submitted) {
+        meter.record(&key_hash, kind, chrono::Utc::now().timestamp());
     }
-
-    Ok(())
-}
-
-// ============================================================================
-// ENDPOINT HANDLERS
-// ============================================================================
-
-async fn health_check() -> Json<HealthResponse> {
-    Json(HealthResponse {
-        status: "healthy".to_string(),
-        service: "miden-rust-service-with-escrow-and-zk".to_string(),
-    })
+    response
 }
 
-async fn get_account_info(State(state): State<AppState>) -> (StatusCode, Json<AccountInfoResponse>) {
-    info!("Received get account info request");
-
-    let (tx, rx) = oneshot::channel();
-    let cmd = ClientCommand::GetAccountInfo { response: tx };
+/// Largest background response body kept for GET /command-jobs/:job_id
+const MAX_BACKGROUND_RESPONSE_BYTES: usize = 1 << 20;
 
-    if let Err(e) = state.client_tx.send(cmd).await {
-        error!("Failed to send command to client task: {}", e);
+/// Sheds load and enforces the per-route deadline of every API request (see
+/// deadlines.rs).
+async fn enforce_deadline(
+    State(state): State<AppState>,
+    matched_path: Option<MatchedPath>,
+    req: Request,
+    next: middleware::Next,
+) -> Response {
+    if state.load_shed_queue_depth > 0 && state.client_tx.queued() >= state.load_shed_queue_depth {
         return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(AccountInfoResponse {
-                success: false,
-                data: None,
-                error: Some("Client task unavailable".to_string()),
-            }),
-        );
-    }
-
-    match rx.await {
-        Ok(Ok(data)) => {
-            info!("Account info retrieved");
-            (
-                StatusCode::OK,
-                Json(AccountInfoResponse {
-                    success: true,
-                    data: Some(data),
-                    error: None,
-                }),
-            )
-        }
-        Ok(Err(e)) => {
-            error!("Failed to get account info: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(AccountInfoResponse {
-                    success: false,
-                    data: None,
-                    error: Some(e),
-                }),
-            )
-        }
-        Err(_) => {
-            error!("Client task dropped response channel");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(AccountInfoResponse {
-                    success: false,
-                    data: None,
-                    error: Some("Internal communication error".to_string()),
-                }),
-            )
-        }
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, "1")],
+            Json(serde_json::json!({
+                "success": false,
+                "error": "Overloaded: too many queued commands, retry shortly"
+            })),
+        )
+            .into_response();
     }
-}
 
-async fn mint_property(
-    State(state): State<AppState>,
-    Json(payload): Json<MintPropertyRequest>,
-) -> (StatusCode, Json<MintPropertyResponse>) {
-    info!("Received mint property request: {:?}", payload);
-
-    let (tx, rx) = oneshot::channel();
-    let cmd = ClientCommand::MintProperty {
-        property_id: payload.property_id.clone(),
-        owner_account_id: payload.owner_account_id,
-        ipfs_cid: payload.ipfs_cid,
-        property_type: payload.property_type,
-        price: payload.price,
-        response: tx,
+    let path = matched_path.as_ref().map_or(req.uri().path(), |p| p.as_str());
+    let route = api_version::unversioned_path(path).to_string();
+    let Some(deadline) = state.deadlines.for_route(&route) else {
+        return next.run(req).await;
     };
 
-    if let Err(e) = state.client_tx.send(cmd).await {
-        error!("Failed to send command to client task: {}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(MintPropertyResponse {
-                success: false,
-                transaction_id: None,
-                note_id: None,
-                error: Some("Client task unavailable".to_string()),
-            }),
-        );
-    }
+    let method = req.method().clone();
+    let is_write = !matches!(method, Method::GET | Method::HEAD);
+    let started_at = chrono::Utc::now().timestamp();
+    let token = CancellationToken::new();
+    let mut handle = tokio::spawn(
+        deadlines::with_cancellation(token.clone(), next.run(req)).in_current_span(),
+    );
+    // A read is cancelled if its caller goes away; a write always finishes
+    let guard = (!is_write).then(|| token.clone().drop_guard());
 
-    match rx.await {
-        Ok(Ok((tx_id, note_id))) => {
-            info!("Property minted: tx={}, note={}", tx_id, note_id);
-            (
-                StatusCode::OK,
-                Json(MintPropertyResponse {
-                    success: true,
-                    transaction_id: Some(tx_id),
-                    note_id: Some(note_id),
-                    error: None,
-                }),
-            )
+    match tokio::time::timeout(deadline, &mut handle).await {
+        Ok(Ok(response)) => {
+            if let Some(guard) = guard {
+                guard.disarm();
+            }
+            response
         }
         Ok(Err(e)) => {
-            error!("Failed to mint property: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(MintPropertyResponse {
-                    success: false,
-                    transaction_id: None,
-                    note_id: None,
-                    error: Some(e),
-                }),
-            )
-        }
-        Err(_) => {
-            error!("Client task dropped response channel");
+            error!("{} {} failed: {}", method, route, e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(MintPropertyResponse {
-                    success: false,
-                    transaction_id: None,
-                    note_id: None,
-                    error: Some("Internal communication error".to_string()),
-                }),
+                Json(serde_json::json!({
+                    "success": false,
+                    "error": "Internal error"
+                })),
             )
+                .into_response()
         }
-    }
-}
-
-async fn get_consumable_notes(
-    State(state): State<AppState>,
-) -> (StatusCode, Json<ConsumableNotesResponse>) {
-    info!("Received get consumable notes request");
-
-    let (tx, rx) = oneshot::channel();
-    let cmd = ClientCommand::GetConsumableNotes {
-        account_id: None,
-        response: tx,
-    };
-
-    if let Err(e) = state.client_tx.send(cmd).await {
-        error!("Failed to send command: {}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ConsumableNotesResponse {
-                success: false,
-                notes: vec![],
-                error: Some("Client task unavailable".to_string()),
-            }),
-        );
-    }
+        Err(_) if is_write => {
+            let job_id = state.background_jobs.start(method.as_str(), &route, started_at);
+            state.job_cancellations.insert(&job_id, token.clone());
+            error!(
+                "{} {} missed its {:?} deadline; continuing as {}",
+                method, route, deadline, job_id
+            );
+
+            let jobs = state.background_jobs.clone();
+            let cancellations = state.job_cancellations.clone();
+            let background_id = job_id.clone();
+            tokio::spawn(async move {
+                let (status, body) = match handle.await {
+                    Ok(response) => {
+                        let status = response.status().as_u16();
+                        let body = response.into_body();
+                        let bytes = axum::body::to_bytes(body, MAX_BACKGROUND_RESPONSE_BYTES)
+                            .await
+                            .unwrap_or_default();
+                        let body = serde_json::from_slice(&bytes).unwrap_or_else(|_| {
+                            serde_json::Value::String(String::from_utf8_lossy(&bytes).into_owned())
+                        });
+                        (status, body)
+                    }
+                    Err(e) => {
+                        let body = serde_json::json!({ "success": false, "error": e.to_string() });
+                        (500, body)
+                    }
+                };
+                jobs.finish(&background_id, status, body);
+                cancellations.forget(&background_id);
+            });
 
-    match rx.await {
-        Ok(Ok(notes)) => {
-            info!("Retrieved {} consumable notes", notes.len());
-            (
-                StatusCode::OK,
-                Json(ConsumableNotesResponse {
-                    success: true,
-                    notes,
-                    error: None,
-                }),
-            )
-        }
-        Ok(Err(e)) => {
-            error!("Failed to get notes: {}", e);
             (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ConsumableNotesResponse {
-                    success: false,
-                    notes: vec![],
-                    error: Some(e),
-                }),
+                StatusCode::GATEWAY_TIMEOUT,
+                Json(serde_json::json!({
+                    "success": false,
+                    "error": format!(
+                        "Timeout: {} {} did not finish within {}s; it continues in the background",
+                        method, route, deadline.as_secs()
+                    ),
+                    "job_id": job_id,
+                    "job_url": format!("/api/v1/command-jobs/{}", job_id)
+                })),
             )
+                .into_response()
         }
         Err(_) => {
-            error!("Client task dropped response channel");
+            token.cancel();
+            handle.abort();
             (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ConsumableNotesResponse {
-                    success: false,
-                    notes: vec![],
-                    error: Some("Internal communication error".to_string()),
-                }),
+                StatusCode::GATEWAY_TIMEOUT,
+                Json(serde_json::json!({
+                    "success": false,
+                    "error": format!(
+                        "Timeout: {} {} did not finish within {}s and was cancelled",
+                        method, route, deadline.as_secs()
+                    )
+                })),
             )
+                .into_response()
         }
     }
 }
 
-async fn consume_note(
-    State(state): State<AppState>,
-    Json(payload): Json<ConsumeNoteRequest>,
-) -> (StatusCode, Json<ConsumeNoteResponse>) {
-    info!("Received consume note request: {:?}", payload);
-
-    let (tx, rx) = oneshot::channel();
-    let cmd = ClientCommand::ConsumeNote {
-        note_id: payload.note_id.clone(),
-        account_id: payload.account_id,
-        response: tx,
-    };
-
-    if let Err(e) = state.client_tx.send(cmd).await {
-        error!("Failed to send command: {}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ConsumeNoteResponse {
-                success: false,
-                transaction_id: None,
-                error: Some("Client task unavailable".to_string()),
-            }),
-        );
-    }
-
-    match rx.await {
-        Ok(Ok(tx_id)) => {
-            info!("Note consumed: tx={}", tx_id);
-            (
-                StatusCode::OK,
-                Json(ConsumeNoteResponse {
-                    success: true,
-                    transaction_id: Some(tx_id),
-                    error: None,
-                }),
-            )
-        }
-        Ok(Err(e)) => {
-            error!("Failed to consume note: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ConsumeNoteResponse {
-                    success: false,
-                    transaction_id: None,
-                    error: Some(e),
-                }),
-            )
-        }
-        Err(_) => {
-            error!("Client task dropped response channel");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ConsumeNoteResponse {
-                    success: false,
-                    transaction_id: None,
-                    error: Some("Internal communication error".to_string()),
-                }),
-            )
+/// Serves the router over plain HTTP, or over TLS when a config is given.
+async fn serve(
+    listener: tokio::net::TcpListener,
+    app: Router,
+    tls_config: Option<Arc<rustls::ServerConfig>>,
+) -> anyhow::Result<()> {
+    match tls_config {
+        None => axum::serve(listener, app).await?,
+        Some(config) => {
+            axum_server::from_tcp_rustls(listener.into_std()?, RustlsConfig::from_config(config))
+                .serve(app.into_make_service())
+                .await?
         }
     }
+    Ok(())
 }
 
-async fn transfer_property(
-    State(state): State<AppState>,
-    Json(payload): Json<TransferPropertyRequest>,
-) -> (StatusCode, Json<TransferPropertyResponse>) {
-    info!("Received transfer property request: {:?}", payload);
-
-    let (tx, rx) = oneshot::channel();
-    let cmd = ClientCommand::TransferProperty {
-        property_id: payload.property_id.clone(),
-        to_account_id: payload.to_account_id.clone(),
-        response: tx,
-    };
-
-    if let Err(e) = state.client_tx.send(cmd).await {
-        error!("Failed to send command: {}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(TransferPropertyResponse {
-                success: false,
-                transaction_id: None,
-                error: Some("Client task unavailable".to_string()),
-            }),
-        );
-    }
+/// Fetches the current ETag of a read resource from the client task.
+async fn fetch_etag(client_tx: &CommandSender, resource: EtagResource) -> Option<String> {
+    let rx = client_tx.dispatch(ResourceEtag { resource }).await?;
 
     match rx.await {
-        Ok(Ok(tx_id)) => {
-            info!("Property transferred: tx={}", tx_id);
-            (
-                StatusCode::OK,
-                Json(TransferPropertyResponse {
-                    success: true,
-                    transaction_id: Some(tx_id),
-                    error: None,
-                }),
-            )
-        }
+        Ok(Ok(etag)) => Some(etag),
         Ok(Err(e)) => {
-            error!("Failed to transfer property: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(TransferPropertyResponse {
-                    success: false,
-                    transaction_id: None,
-                    error: Some(e),
-                }),
-            )
-        }
-        Err(_) => {
-            error!("Client task dropped response channel");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(TransferPropertyResponse {
-                    success: false,
-                    transaction_id: None,
-                    error: Some("Internal communication error".to_string()),
-                }),
-            )
+            error!("Failed to compute ETag: {}", e);
+            None
         }
+        Err(_) => None,
     }
 }
 
-async fn send_tokens(
-    State(state): State<AppState>,
-    Json(payload): Json<SendTokensRequest>,
-) -> (StatusCode, Json<SendTokensResponse>) {
-    info!("Received send tokens request: {:?}", payload);
-
-    let (tx, rx) = oneshot::channel();
-    let cmd = ClientCommand::SendTokens {
-        to_account_id: payload.to_account_id.clone(),
-        amount: payload.amount,
-        response: tx,
-    };
+/// True if any entity tag in If-None-Match equals `etag` (or is "*").
+fn if_none_match_hits(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == etag || tag == "*")
+}
 
-    if let Err(e) = state.client_tx.send(cmd).await {
-        error!("Failed to send command: {}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(SendTokensResponse {
-                success: false,
-                transaction_id: None,
-                error: Some("Client task unavailable".to_string()),
-            }),
-        );
+/// Serves a read endpoint with ETag support and the read cache.
+///
+/// A fresh cache entry answers without touching the client task. Otherwise, with
+/// If-None-Match, the cheap ETag check runs first and a match returns 304
+/// without executing `read`. Successful responses carry the ETag of the state
+/// they were produced from and are cached.
+async fn conditional_read<T>(
+    state: &AppState,
+    headers: &HeaderMap,
+    resource: EtagResource,
+    read: impl std::future::Future<Output = (StatusCode, Json<T>)>,
+) -> Response
+where
+    T: Serialize,
+{
+    let cache_key = resource.label();
+
+    if let Some(cached) = state.read_cache.get(&cache_key) {
+        if if_none_match_hits(headers, &cached.etag) {
+            return (StatusCode::NOT_MODIFIED, [(header::ETAG, cached.etag)]).into_response();
+        }
+        let status = StatusCode::from_u16(cached.status).unwrap_or(StatusCode::OK);
+        return (status, [(header::ETAG, cached.etag)], Json(cached.body)).into_response();
     }
 
-    match rx.await {
-        Ok(Ok(tx_id)) => {
-            info!("Tokens sent: tx={}", tx_id);
-            (
-                StatusCode::OK,
-                Json(SendTokensResponse {
-                    success: true,
-                    transaction_id: Some(tx_id),
-                    error: None,
-                }),
-            )
-        }
-        Ok(Err(e)) => {
-            error!("Failed to send tokens: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(SendTokensResponse {
-                    success: false,
-                    transaction_id: None,
-                    error: Some(e),
-                }),
-            )
-        }
-        Err(_) => {
-            error!("Client task dropped response channel");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(SendTokensResponse {
-                    success: false,
-                    transaction_id: None,
-                    error: Some("Internal communication error".to_string()),
-                }),
-            )
+    if headers.contains_key(header::IF_NONE_MATCH) {
+        if let Some(etag) = fetch_etag(&state.client_tx, resource.clone()).await {
+            if if_none_match_hits(headers, &etag) {
+                return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+            }
         }
     }
-}
 
-async fn get_balance(
-    State(state): State<AppState>,
-    axum::extract::Path(account_id): axum::extract::Path<String>,
-) -> (StatusCode, Json<BalanceResponse>) {
-    info!("Received get balance request for: {}", account_id);
-
-    let (tx, rx) = oneshot::channel();
-    let cmd = ClientCommand::GetBalance {
-        account_id: account_id.clone(),
-        response: tx,
+    let (status, Json(body)) = read.await;
+    if !status.is_success() {
+        return (status, Json(body)).into_response();
+    }
+
+    let etag = fetch_etag(&state.client_tx, resource.clone()).await;
+    let body = match serde_json::to_value(&body) {
+        Ok(body) => body,
+        Err(_) => return (status, Json(body)).into_response(),
     };
 
-    if let Err(e) = state.client_tx.send(cmd).await {
-        error!("Failed to send command: {}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(BalanceResponse {
-                success: false,
-                balance: None,
-                error: Some("Client task unavailable".to_string()),
-            }),
+    let mut response = (status, Json(body.clone())).into_response();
+    if let Some(etag) = etag {
+        if let Ok(value) = HeaderValue::from_str(&etag) {
+            response.headers_mut().insert(header::ETAG, value);
+        }
+        state.read_cache.insert(
+            cache_key,
+            resource.account(),
+            CachedRead {
+                etag,
+                status: status.as_u16(),
+                body,
+            },
         );
     }
 
-    match rx.await {
-        Ok(Ok(balance)) => {
-            info!("Balance retrieved");
-            (
-                StatusCode::OK,
-                Json(BalanceResponse {
-                    success: true,
-                    balance: Some(balance),
-                    error: None,
-                }),
-            )
-        }
-        Ok(Err(e)) => {
-            error!("Failed to get balance: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(BalanceResponse {
-                    success: false,
-                    balance: None,
-                    error: Some(e),
-                }),
-            )
-        }
-        Err(_) => {
-            error!("Client task dropped response channel");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(BalanceResponse {
-                    success: false,
-                    balance: None,
-                    error: Some("Internal communication error".to_string()),
-                }),
-            )
-        }
-    }
+    response
 }
 
 // ============================================================================
-// ESCROW ENDPOINTS
+// MAIN SERVER
 // ============================================================================
+//
+// Server responsibilities:
+// - Start the single Miden client task
+// - Start HTTP server
+// - Route each HTTP request into a queued command
 
-async fn create_escrow(
-    State(state): State<AppState>,
-    Json(payload): Json<CreateEscrowRequest>,
-) -> Json<serde_json::Value> {
-    info!("Received create escrow request: {:?}", payload);
-
-    let (resp_tx, resp_rx) = oneshot::channel();
-
-    let command = ClientCommand::CreateEscrow {
-        buyer_account_str: payload.buyer_account_id,
-        seller_account_str: payload.seller_account_id,
-        amount: payload.amount,
-        resp: resp_tx,
-    };
-
-    if state.client_tx.send(command).await.is_err() {
-        return Json(serde_json::json!({
-            "success": false,
-            "error": "Client task not available"
-        }));
-    }
-
-    match resp_rx.await {
-        Ok(Ok(escrow)) => {
-            info!("Escrow created: escrow_id={}", escrow.escrow_account_id);
-
-            let escrow_hex = format!("0x{}", hex::encode(escrow.escrow_account_id.to_bytes()));
-            let buyer_hex = format!("0x{}", hex::encode(escrow.buyer_account_id.to_bytes()));
-            let seller_hex = format!("0x{}", hex::encode(escrow.seller_account_id.to_bytes()));
-
-            Json(serde_json::json!({
-                "success": true,
-                "escrow": {
-                    "escrow_account_id": escrow_hex,
-                    "buyer_account_id": buyer_hex,
-                    "seller_account_id": seller_hex,
-                    "amount": escrow.amount,
-                    "status": "created"
-                },
-                "error": null
-            }))
-        }
-        Ok(Err(e)) => {
-            error!("Failed to create escrow: {}", e);
-            Json(serde_json::json!({
-                "success": false,
-                "error": e
-            }))
-        }
-        Err(_) => Json(serde_json::json!({
-            "success": false,
-            "error": "Internal communication error"
-        })),
-    }
-}
-
-async fn fund_escrow(
-    State(state): State<AppState>,
-    Json(payload): Json<FundEscrowRequest>,
-) -> Json<serde_json::Value> {
-    info!("Received fund escrow request: {:?}", payload);
-
-    let escrow_account_id = match parse_account_id_from_hex(&payload.escrow_account_id) {
-        Ok(id) => id,
-        Err(e) => {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": format!("Invalid escrow account ID: {}", e)
-            }));
-        }
-    };
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // Held until shutdown: flushes shipped logs when dropped
+    let _log_guard = logging::init(&LogConfig::from_env()?)?;
 
-    let buyer_account_id = match parse_account_id_from_hex(&payload.buyer_account_id) {
-        Ok(id) => id,
-        Err(e) => {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": format!("Invalid buyer account ID: {}", e)
-            }));
-        }
-    };
+    info!("Starting Miden Rust Service with Escrow + ZK Proofs (Accreditation + Jurisdiction)");
 
-    let seller_account_id = match parse_account_id_from_hex(&payload.seller_account_id) {
-        Ok(id) => id,
-        Err(e) => {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": format!("Invalid seller account ID: {}", e)
-            }));
-        }
+    let config = ServiceConfig::from_env()?;
+    info!("Profile: {}", config.profile.as_str());
+
+    // Localnet: spawn or attach to a local node before the client connects.
+    // The handle is held for the lifetime of main so a spawned node is not killed early.
+    let _local_node = if config.profile == Profile::Localnet {
+        let node = LocalNode::start(&config).await?;
+        info!("Local node ready (managed: {})", node.is_managed());
+        Some(node)
+    } else {
+        None
     };
 
-    let escrow = EscrowAccount {
-        escrow_account_id,
-        buyer_account_id,
-        seller_account_id,
-        amount: payload.amount,
-        status: EscrowStatus::Created,
+    // Command channel: handlers -> client task
+    let (command_tx, mut client_rx) = mpsc::channel::<QueuedCommand>(100);
+    let queue_stats = QueueStats::default();
+    let client_tx = CommandSender {
+        tx: command_tx,
+        stats: queue_stats.clone(),
     };
 
-    let (resp_tx, resp_rx) = oneshot::channel();
+    // LocalSet to run the client task locally (single-threaded context)
+    let local = LocalSet::new();
 
-    let command = ClientCommand::FundEscrow { escrow, resp: resp_tx };
+    // Read cache shared by handlers (lookups) and the client task (invalidation)
+    let read_cache = ReadCache::new(config.read_cache_ttl, config.read_cache_max_entries);
 
-    if state.client_tx.send(command).await.is_err() {
-        return Json(serde_json::json!({
-            "success": false,
-            "error": "Client task not available"
-        }));
-    }
+    // Auction events: published by the client task, streamed by handlers
+    let (auction_feed, _) = broadcast::channel(AUCTION_FEED_CAPACITY);
+    let (retry_feed, _) = broadcast::channel(RETRY_FEED_CAPACITY);
+    let (stale_escrow_feed, _) = broadcast::channel(STALE_ESCROW_FEED_CAPACITY);
+    let (sync_feed, _) = broadcast::channel(SYNC_FEED_CAPACITY);
+    let (subscription_deliveries, _) = broadcast::channel(subscriptions::DELIVERY_FEED_CAPACITY);
+    // Subscriptions: kept by the client task, read by the dispatcher
+    let (subscription_watch, subscription_rx) = watch::channel(Vec::new());
 
-    match resp_rx.await {
-        Ok(Ok(tx_id)) => {
-            info!("Escrow funded: tx={}", tx_id);
-            Json(serde_json::json!({
-                "success": true,
-                "transaction_id": tx_id,
-                "error": null
-            }))
-        }
-        Ok(Err(e)) => {
-            error!("Failed to fund escrow: {}", e);
-            Json(serde_json::json!({
-                "success": false,
-                "error": e
-            }))
-        }
-        Err(_) => Json(serde_json::json!({
-            "success": false,
-            "error": "Internal communication error"
-        })),
+    // Client task: owns the Miden client and handles all commands sequentially
+    let client_config = config.clone();
+    let client_read_cache = read_cache.clone();
+    let client_auction_feed = auction_feed.clone();
+    let client_retry_feed = retry_feed.clone();
+    let client_stale_escrow_feed = stale_escrow_feed.clone();
+    let client_sync_feed = sync_feed.clone();
+    let job_cancellations = JobCancellations::default();
+    let client_job_cancellations = job_cancellations.clone();
+    let startup = StartupProgress::default();
+    let client_startup = startup.clone();
+    // RPC endpoints: the client task fails over between them, handlers report
+    let rpc_pool = RpcPool::new(config.rpc_endpoints.clone(), config.rpc_failover_threshold);
+    let client_rpc_pool = rpc_pool.clone();
+    // Feature flags: changed by the client task, read by the route gate and
+    // the subscription dispatcher
+    let features = FeatureFlags::load(
+        config.feature_flags_path.clone(),
+        config.feature_defaults.clone(),
+    )?;
+    for flag in features.list().iter().filter(|f| !f.enabled) {
+        info!("Feature {} is turned off", flag.feature.as_str());
     }
-}
+    let client_features = features.clone();
+    // Usage per API key: metered by the API layer, invoiced by the client task
+    let usage_meter = UsageMeter::load(config.billing_usage_path.clone())?;
+    let client_usage_meter = usage_meter.clone();
+    // Admin keys: kept by the client task, checked by the admin gate
+    let admin_keys = PrincipalStore::load(config.api_keys_path.clone())?
+        .with_bootstrap_key(config.admin_bootstrap_key.as_deref())
+        .admin_keys();
+    let client_admin_keys = admin_keys.clone();
+    local.spawn_local(async move {
+        info!("Initializing Miden client");
+        let initialized = MidenClientWrapper::new(
+            &client_config,
+            &client_startup,
+            client_rpc_pool,
+            client_features,
+            client_usage_meter,
+        )
+        .await;
+        match initialized {
+            Ok(mut client) => {
+                info!("Miden client initialized successfully");
+                info!("Client task ready to process commands");
+                info!("ZK Proof system enabled (Ownership)");
 
-async fn release_escrow(
-    State(state): State<AppState>,
-    Json(payload): Json<ReleaseEscrowRequest>,
-) -> Json<serde_json::Value> {
-    info!("Received release escrow request: {:?}", payload);
-
-    let escrow_account_id = match parse_account_id_from_hex(&payload.escrow_account_id) {
-        Ok(id) => id,
-        Err(e) => {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": format!("Invalid escrow account ID: {}", e)
-            }));
-        }
-    };
+                for (name, account_hex) in client.named_account_ids() {
+                    client_read_cache.register_alias(name, &account_hex);
+                }
+                if let Some(liquidity_hex) = client.swap_liquidity_account_hex() {
+                    client_read_cache.register_alias(LIQUIDITY_ALIAS, &liquidity_hex);
+                }
+                client.attach_auction_feed(client_auction_feed);
+                client.attach_retry_feed(client_retry_feed);
+                client.attach_stale_escrow_feed(client_stale_escrow_feed);
+                client.attach_sync_feed(client_sync_feed);
+                client.attach_subscription_watch(subscription_watch);
+                client.attach_job_cancellations(client_job_cancellations);
+                client.attach_admin_keys(client_admin_keys);
+                client_startup.ready();
+
+                while let Some(queued) = client_rx.recv().await {
+                    let QueuedCommand { op, cancellation, offline, ticket, span } = queued;
+                    // The request that sent it gave up while it was queued
+                    if cancellation.as_ref().is_some_and(|token| token.is_cancelled()) {
+                        span.in_scope(|| info!("Dropping cancelled command"));
+                        queue_stats.discard(ticket);
+                        continue;
+                    }
+                    queue_stats.start(ticket);
+                    client.set_cancellation(cancellation);
+                    client.set_offline_opt_in(offline);
+                    client.maintain_rpc_endpoint().await;
+                    if let Err(e) = client.refresh_replica_records() {
+                        error!("Failed to reload replicated records: {}", e);
+                    }
+                    let touched = op.touched();
+
+                    let started = std::time::Instant::now();
+                    async {
+                        op.run(&mut client).await;
+                        info!(
+                            duration_ms = started.elapsed().as_millis() as u64,
+                            "Command finished"
+                        );
+                    }
+                    .instrument(span)
+                    .await;
 
-    let buyer_account_id = match parse_account_id_from_hex(&payload.buyer_account_id) {
-        Ok(id) => id,
-        Err(e) => {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": format!("Invalid buyer account ID: {}", e)
-            }));
-        }
-    };
+                    if let Some(touched) = touched {
+                        client_read_cache.invalidate(&touched);
+                    }
+                    if let Some(height) = client.sync_height().await {
+                        client_read_cache.set_sync_height(height);
+                    }
+                    queue_stats.finish();
+                }
 
-    let seller_account_id = match parse_account_id_from_hex(&payload.seller_account_id) {
-        Ok(id) => id,
-        Err(e) => {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": format!("Invalid seller account ID: {}", e)
-            }));
+                error!("Client task channel closed");
+            }
+            Err(e) => {
+                error!("Failed to initialize Miden client: {}", e);
+                client_startup.failed(&e);
+            }
         }
-    };
-
-    let escrow = EscrowAccount {
-        escrow_account_id,
-        buyer_account_id,
-        seller_account_id,
-        amount: payload.amount,
-        status: EscrowStatus::Funded,
-    };
+    });
 
-    let (resp_tx, resp_rx) = oneshot::channel();
+    // Resume bulk mint jobs interrupted by the last shutdown. The request is
+    // queued until the client task has finished initializing. A read replica
+    // runs no background writers (see read_replica.rs).
+    if !config.read_replica {
+        let client_tx = client_tx.clone();
+        let job_cancellations = job_cancellations.clone();
+        tokio::spawn(async move {
+            let Some(rx) = client_tx.dispatch(UnfinishedMintBatches {}).await else {
+                return;
+            };
+            for job_id in rx.await.ok().and_then(Result::ok).unwrap_or_default() {
+                info!("Resuming mint job {}", job_id);
+                tokio::spawn(drive_mint_batch(
+                    client_tx.clone(),
+                    job_cancellations.clone(),
+                    job_id,
+                ));
+            }
 
-    let command = ClientCommand::ReleaseEscrow { escrow, resp: resp_tx };
+            let Some(rx) = client_tx.dispatch(UnfinishedSagas {}).await else {
+                return;
+            };
+            for saga_id in rx.await.ok().and_then(Result::ok).unwrap_or_default() {
+                info!("Resuming saga {}", saga_id);
+                tokio::spawn(drive_saga(client_tx.clone(), saga_id));
+            }
+        });
+    }
 
-    if state.client_tx.send(command).await.is_err() {
-        return Json(serde_json::json!({
-            "success": false,
-            "error": "Client task not available"
-        }));
+    if !config.read_replica {
+        // Activity subscriptions: matches feed events, posts webhooks
+        tokio::spawn(subscriptions::run_dispatcher(
+            subscription_rx,
+            sync_feed.subscribe(),
+            auction_feed.subscribe(),
+            stale_escrow_feed.subscribe(),
+            subscription_deliveries.clone(),
+            features.clone(),
+        ));
+
+        // Scheduled jobs: installment reminders/defaults, rent collection
+        tokio::spawn(drive_scheduler(
+            client_tx.clone(),
+            config.scheduler_tick_interval,
+        ));
     }
 
-    match resp_rx.await {
-        Ok(Ok(tx_id)) => {
-            info!("Escrow released: tx={}", tx_id);
-            Json(serde_json::json!({
-                "success": true,
-                "transaction_id": tx_id,
-                "error": null
-            }))
-        }
-        Ok(Err(e)) => {
-            error!("Failed to release escrow: {}", e);
-            Json(serde_json::json!({
-                "success": false,
-                "error": e
-            }))
-        }
-        Err(_) => Json(serde_json::json!({
-            "success": false,
-            "error": "Internal communication error"
-        })),
+    // Mint coalescing: single mints within a window share a faucet transaction
+    let mint_coalescer = config.mint_coalescing.clone().map(|policy| {
+        info!(
+            "Mint coalescing enabled ({:?} window, up to {} per transaction)",
+            policy.window, policy.max_items
+        );
+        let client_tx = client_tx.clone();
+        MintCoalescer::spawn(policy, move |items| {
+            let client_tx = client_tx.clone();
+            async move {
+                let rx = client_tx
+                    .dispatch(MintPropertyBatch { items })
+                    .await
+                    .ok_or_else(|| "Client task unavailable".to_string())?;
+                rx.await
+                    .map_err(|_| "Internal communication error".to_string())?
+            }
+        })
+    });
+
+    // RPC health checks: feed endpoint health to the client task's failover
+    if !config.rpc_health_check_interval.is_zero() {
+        tokio::spawn(rpc_failover::run_health_checks(
+            rpc_pool.clone(),
+            config.rpc_health_check_interval,
+            config.rpc_timeout_ms,
+        ));
     }
-}
 
-async fn refund_escrow(
-    State(state): State<AppState>,
-    Json(payload): Json<RefundEscrowRequest>,
-) -> Json<serde_json::Value> {
-    info!("Received refund escrow request: {:?}", payload);
-
-    let escrow_account_id = match parse_account_id_from_hex(&payload.escrow_account_id) {
-        Ok(id) => id,
-        Err(e) => {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": format!("Invalid escrow account ID: {}", e)
-            }));
-        }
+    let state = AppState {
+        client_tx,
+        read_cache,
+        auction_feed,
+        retry_feed,
+        stale_escrow_feed,
+        sync_feed,
+        subscription_deliveries,
+        deadlines: Arc::new(config.request_deadlines.clone()),
+        background_jobs: BackgroundJobs::default(),
+        load_shed_queue_depth: config.load_shed_queue_depth,
+        job_cancellations,
+        startup,
+        slo: SloTracker::new(config.slo.clone()),
+        media: config.media.clone(),
+        esign: config.esign.clone(),
+        otp_delivery: config.otp_delivery.clone(),
+        rpc: rpc_pool,
+        admin_keys,
+        demo_runs: DemoRuns::default(),
+        mint_coalescer,
     };
 
-    let buyer_account_id = match parse_account_id_from_hex(&payload.buyer_account_id) {
-        Ok(id) => id,
-        Err(e) => {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": format!("Invalid buyer account ID: {}", e)
-            }));
-        }
+    // Router setup
+    //
+    // Endpoints are served under /api/v1; the unprefixed routes remain as
+    // deprecated aliases (see api_version.rs).
+    //
+    // Deadlines and load shedding apply to every API route (see deadlines.rs)
+    let api = routes::router(&config)
+        // Inside the deadline layer, which runs the request on its own task
+        .layer(middleware::from_fn(offline_mode))
+        .layer(middleware::from_fn_with_state(state.clone(), enforce_deadline));
+    // Usage per API key for billing; replicas do not bill (see billing.rs)
+    let api = if config.read_replica {
+        api
+    } else {
+        api.layer(middleware::from_fn_with_state(usage_meter, meter_usage))
     };
-
-    let seller_account_id = match parse_account_id_from_hex(&payload.seller_account_id) {
-        Ok(id) => id,
-        Err(e) => {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": format!("Invalid seller account ID: {}", e)
-            }));
-        }
+    // Writes are refused before they reach the client queue (see read_replica.rs)
+    let api = if config.read_replica {
+        api.layer(middleware::from_fn(read_replica_guard))
+    } else {
+        api
     };
-
-    let escrow = EscrowAccount {
-        escrow_account_id,
-        buyer_account_id,
-        seller_account_id,
-        amount: payload.amount,
-        status: EscrowStatus::Funded,
+    // Routes of disabled features answer 404 (see feature_flags.rs)
+    let api = api
+        .layer(middleware::from_fn_with_state(features, feature_gate))
+        // Per-route limits replace axum's default one (see body_limits.rs)
+        .layer(DefaultBodyLimit::disable())
+        .layer(middleware::from_fn_with_state(
+            Arc::new(config.body_limits.clone()),
+            body_limits::limit_body,
+        ))
+        // Outside the deadline layer, so shed and timed-out requests count too
+        .layer(middleware::from_fn_with_state(state.clone(), track_slo))
+        // Added after the layers: never shed, so they answer when the queue is deep
+        .merge(routes::monitoring_router())
+        // Outermost, so no admin request reaches the queue without an admin key
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            routes::require_admin,
+        ));
+
+    let version_policy = VersionPolicy {
+        legacy_sunset: config.legacy_api_sunset,
     };
+    let versions_document = version_policy.versions_document();
 
-    let (resp_tx, resp_rx) = oneshot::channel();
-
-    let command = ClientCommand::RefundEscrow { escrow, resp: resp_tx };
-
-    if state.client_tx.send(command).await.is_err() {
-        return Json(serde_json::json!({
-            "success": false,
-            "error": "Client task not available"
-        }));
+    // CORS and security headers (see http_security.rs)
+    if config.cors.allowed_origins.is_any() && config.profile != Profile::Localnet {
+        tracing::warn!("⚠️  CORS allows any origin; set CORS_ALLOWED_ORIGINS to restrict it");
     }
+    let security_headers = Arc::new(config.security_headers.headers());
 
-    match resp_rx.await {
-        Ok(Ok(tx_id)) => {
-            info!("Escrow refunded: tx={}", tx_id);
-            Json(serde_json::json!({
-                "success": true,
-                "transaction_id": tx_id,
-                "error": null
-            }))
-        }
-        Ok(Err(e)) => {
-            error!("Failed to refund escrow: {}", e);
-            Json(serde_json::json!({
-                "success": false,
-                "error": e
-            }))
+    let app = routes::health_router()
+        .route(
+            "/api/versions",
+            get(move || async move { Json(versions_document) }),
+        )
+        .nest("/api/v1", api.clone())
+        .merge(api.layer(middleware::from_fn_with_state(
+            version_policy,
+            api_version::legacy_alias,
+        )))
+        .with_state(state)
+        .layer(middleware::from_fn(api_version::negotiate))
+        // ISO 8601 siblings of timestamp fields (see timestamps.rs)
+        .layer(middleware::from_fn(timestamps::annotate_responses))
+        .layer(config.cors.layer())
+        .layer(middleware::from_fn_with_state(
+            security_headers,
+            http_security::apply_security_headers,
+        ))
+        .layer(middleware::from_fn(logging::trace_request));
+
+    let addr = config.listen_addr.as_str();
+    let tls_config = config.tls.as_ref().map(tls::server_config).transpose()?;
+    let scheme = if tls_config.is_some() { "https" } else { "http" };
+    info!("Server listening on {}://{}", scheme, addr);
+    if let Some(tls) = &config.tls {
+        match (&tls.client_ca_path, tls.client_auth_optional) {
+            (Some(_), false) => info!("Mutual TLS enabled (client certificate required)"),
+            (Some(_), true) => info!("Mutual TLS enabled (client certificate optional)"),
+            (None, _) => info!("TLS enabled"),
         }
-        Err(_) => Json(serde_json::json!({
-            "success": false,
-            "error": "Internal communication error"
-        })),
     }
-}
-
-// ============================================================================
-// ZK PROOF ENDPOINTS - ACCREDITATION
-// ============================================================================
-
-async fn generate_accreditation_proof(
-    State(state): State<AppState>,
-    Json(payload): Json<GenerateAccreditationProofRequest>,
-) -> Json<serde_json::Value> {
-    info!("Received generate accreditation proof request");
-    info!("Net worth: {} (hidden in proof)", payload.net_worth);
-    info!("Threshold: {}", payload.threshold);
-
-    let (tx, rx) = oneshot::channel();
-    let cmd = ClientCommand::GenerateAccreditationProof {
-        net_worth: payload.net_worth,
-        threshold: payload.threshold,
-        response: tx,
-    };
+    info!("Escrow system enabled");
+    info!("ZK Proof system enabled (Accreditation)");
+    info!("ZK Proof system enabled (Jurisdiction)");
 
-    if state.client_tx.send(cmd).await.is_err() {
-        return Json(serde_json::json!({
-            "success": false,
-            "error": "Client task not available"
-        }));
-    }
+    let listener = tokio::net::TcpListener::bind(addr).await?;
 
-    match rx.await {
-        Ok(Ok(proof_data)) => {
-            info!("ZK proof generated successfully");
-            Json(proof_data)
+    // Run both:
+    // - local client task (LocalSet)
+    // - Axum server
+    //
+    // If the client task ends unexpectedly, we log and exit.
+    // If the server ends, we return its result.
+    tokio::select! {
+        _ = local => {
+            error!("LocalSet (client task) terminated");
         }
-        Ok(Err(e)) => {
-            error!("Failed to generate proof: {}", e);
-            Json(serde_json::json!({
-                "success": false,
-                "error": e
-            }))
+        result = serve(listener, app, tls_config) => {
+            result?;
         }
-        Err(_) => Json(serde_json::json!({
-            "success": false,
-            "error": "Internal communication error"
-        })),
     }
-}
-
-async fn verify_accreditation_proof(
-    State(state): State<AppState>,
-    Json(payload): Json<VerifyAccreditationProofRequest>,
-) -> Json<serde_json::Value> {
-    info!("Received verify accreditation proof request");
-    info!("Verifying without seeing private data");
-
-    let (tx, rx) = oneshot::channel();
-    let cmd = ClientCommand::VerifyAccreditationProof {
-        proof: payload.proof,
-        program_hash: payload.program_hash,
-        public_inputs: payload.public_inputs,
-        response: tx,
-    };
 
-    if state.client_tx.send(cmd).await.is_err() {
-        return Json(serde_json::json!({
-            "success": false,
-            "error": "Client task not available"
-        }));
-    }
-
-    match rx.await {
-        Ok(Ok(verification_result)) => {
-            info!("Proof verification complete");
-            Json(verification_result)
-        }
-        Ok(Err(e)) => {
-            error!("Failed to verify proof: {}", e);
-            Json(serde_json::json!({
-                "success": false,
-                "error": e
-            }))
-        }
-        Err(_) => Json(serde_json::json!({
-            "success": false,
-            "error": "Internal communication error"
-        })),
-    }
+    Ok(())
 }
 
 // ============================================================================
-// ZK PROOF ENDPOINTS - JURISDICTION
+// BACKGROUND DRIVERS
 // ============================================================================
-
-async fn generate_jurisdiction_proof(
-    State(state): State<AppState>,
-    Json(payload): Json<GenerateJurisdictionProofRequest>,
-) -> Json<serde_json::Value> {
-    info!("Received generate jurisdiction proof request");
-    info!("Country: {} (hidden in proof)", payload.country_code);
-    info!("Restricted: {:?}", payload.restricted_countries);
-
-    let (tx, rx) = oneshot::channel();
-    let cmd = ClientCommand::GenerateJurisdictionProof {
-        country_code: payload.country_code,
-        restricted_countries: payload.restricted_countries,
-        response: tx,
-    };
-
-    if state.client_tx.send(cmd).await.is_err() {
-        return Json(serde_json::json!({
-            "success": false,
-            "error": "Client task not available"
-        }));
-    }
-
-    match rx.await {
-        Ok(Ok(proof_data)) => {
-            info!("Jurisdiction ZK proof generated successfully");
-            Json(proof_data)
-        }
-        Ok(Err(e)) => {
-            error!("Failed to generate jurisdiction proof: {}", e);
-            Json(serde_json::json!({
-                "success": false,
-                "error": e
-            }))
-        }
-        Err(_) => Json(serde_json::json!({
-            "success": false,
-            "error": "Internal communication error"
-        })),
-    }
+//
+// Mint batches, sagas and scheduled jobs run outside any request: their
+// drivers are started here at startup, and by the job handlers in routes/.
+
+/// Feeds a mint job to the client task one chunk at a time until it finishes.
+///
+/// Each chunk is a separate queued command, so other requests are served
+/// between chunks instead of waiting for the whole batch. Chunks carry the
+/// job's cancellation token (see deadlines.rs): once the job is cancelled, a
+/// queued chunk is dropped and a running one stops at its next item.
+async fn drive_mint_batch(
+    client_tx: CommandSender,
+    cancellations: JobCancellations,
+    job_id: String,
+) {
+    let token = cancellations.register(&job_id);
+    deadlines::with_cancellation(token, drive_mint_batch_chunks(client_tx, &job_id)).await;
+    cancellations.forget(&job_id);
 }
 
-async fn verify_jurisdiction_proof(
-    State(state): State<AppState>,
-    Json(payload): Json<VerifyJurisdictionProofRequest>,
-) -> Json<serde_json::Value> {
-    info!("Received verify jurisdiction proof request");
-    info!("Verifying without seeing user's country");
-
-    let (tx, rx) = oneshot::channel();
-    let cmd = ClientCommand::VerifyJurisdictionProof {
-        proof: payload.proof,
-        program_hash: payload.program_hash,
-        public_inputs: payload.public_inputs,
-        response: tx,
-    };
-
-    if state.client_tx.send(cmd).await.is_err() {
-        return Json(serde_json::json!({
-            "success": false,
-            "error": "Client task not available"
-        }));
-    }
-
-    match rx.await {
-        Ok(Ok(verification_result)) => {
-            info!("Jurisdiction proof verification complete");
-            Json(verification_result)
-        }
-        Ok(Err(e)) => {
-            error!("Failed to verify jurisdiction proof: {}", e);
-            Json(serde_json::json!({
-                "success": false,
-                "error": e
-            }))
+async fn drive_mint_batch_chunks(client_tx: CommandSender, job_id: &str) {
+    loop {
+        let op = RunMintBatchChunk {
+            job_id: job_id.to_string(),
+        };
+        let Some(rx) = client_tx.dispatch(op).await else {
+            error!("Mint job {}: client task not available", job_id);
+            return;
+        };
+
+        match rx.await {
+            Ok(Ok(true)) => {
+                info!("Mint job {} finished", job_id);
+                return;
+            }
+            Ok(Ok(false)) => continue,
+            Ok(Err(e)) => {
+                error!("Mint job {} stopped: {}", job_id, e);
+                return;
+            }
+            Err(_) if deadlines::current_cancellation().is_some_and(|t| t.is_cancelled()) => {
+                info!("Mint job {} cancelled", job_id);
+                return;
+            }
+            Err(_) => {
+                error!("Mint job {}: internal communication error", job_id);
+                return;
+            }
         }
-        Err(_) => Json(serde_json::json!({
-            "success": false,
-            "error": "Internal communication error"
-        })),
     }
 }
 
-// ============================================================================
-// ZK PROOF ENDPOINTS - OWNERSHIP
-// ============================================================================
-
-async fn generate_ownership_proof(
-    State(state): State<AppState>,
-    Json(payload): Json<GenerateOwnershipProofRequest>,
-) -> Json<serde_json::Value> {
-    info!("Received generate ownership proof request");
-    info!("Property: {}", payload.property_id);
-
-    // Safe logging: truncate document hash preview for readability
-    info!(
-        "Document hash preview: {}...",
-        &payload.document_hash[..20.min(payload.document_hash.len())]
-    );
-
-    let (tx, rx) = oneshot::channel();
-    let cmd = ClientCommand::GenerateOwnershipProof {
-        property_id: payload.property_id,
-        document_hash: payload.document_hash,
-        response: tx,
-    };
-
-    if state.client_tx.send(cmd).await.is_err() {
-        return Json(serde_json::json!({
-            "success": false,
-            "error": "Client task not available"
-        }));
-    }
-
-    match rx.await {
-        Ok(Ok(proof_data)) => {
-            info!("Ownership ZK proof generated successfully");
-            Json(proof_data)
-        }
-        Ok(Err(e)) => {
-            error!("Failed to generate ownership proof: {}", e);
-            Json(serde_json::json!({
-                "success": false,
-                "error": e
-            }))
+/// Feeds a saga to the client task one step at a time until it finishes or
+/// stops, waiting between attempts of a step that failed transiently.
+async fn drive_saga(client_tx: CommandSender, saga_id: String) {
+    loop {
+        let op = RunSagaStep {
+            saga_id: saga_id.clone(),
+        };
+        let Some(rx) = client_tx.dispatch(op).await else {
+            error!("Saga {}: client task not available", saga_id);
+            return;
+        };
+
+        match rx.await {
+            Ok(Ok(SagaProgress::Next)) => continue,
+            Ok(Ok(SagaProgress::RetryIn(wait))) => tokio::time::sleep(wait).await,
+            Ok(Ok(SagaProgress::Done)) => return,
+            Ok(Err(e)) => {
+                error!("Saga {} stopped: {}", saga_id, e);
+                return;
+            }
+            Err(_) => {
+                error!("Saga {}: internal communication error", saga_id);
+                return;
+            }
         }
-        Err(_) => Json(serde_json::json!({
-            "success": false,
-            "error": "Internal communication error"
-        })),
     }
 }
 
-async fn verify_ownership_proof(
-    State(state): State<AppState>,
-    Json(payload): Json<VerifyOwnershipProofRequest>,
-) -> Json<serde_json::Value> {
-    info!("Received verify ownership proof request");
-    info!("Verifying without seeing user's document hash");
-
-    let (tx, rx) = oneshot::channel();
-    let cmd = ClientCommand::VerifyOwnershipProof {
-        proof: payload.proof,
-        program_hash: payload.program_hash,
-        public_inputs: payload.public_inputs,
-        response: tx,
-    };
-
-    if state.client_tx.send(cmd).await.is_err() {
-        return Json(serde_json::json!({
-            "success": false,
-            "error": "Client task not available"
-        }));
-    }
-
-    match rx.await {
-        Ok(Ok(verification_result)) => {
-            info!("Ownership proof verification complete");
-            Json(verification_result)
-        }
-        Ok(Err(e)) => {
-            error!("Failed to verify ownership proof: {}", e);
-            Json(serde_json::json!({
-                "success": false,
-                "error": e
-            }))
+/// Queues a scheduler tick on the client task every `interval`.
+///
+/// Ticks are ordinary commands, so they interleave with requests rather than
+/// holding the client while scheduled jobs wait for their turn.
+async fn drive_scheduler(client_tx: CommandSender, interval: std::time::Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        ticker.tick().await;
+
+        let Some(rx) = client_tx.dispatch(SchedulerTick {}).await else {
+            error!("Scheduler stopped: client task not available");
+            return;
+        };
+
+        match rx.await {
+            Ok(Ok(changed)) => {
+                for (job, count) in changed.into_iter().filter(|(_, count)| *count > 0) {
+                    info!("Scheduled job {} updated {} item(s)", job, count);
+                }
+            }
+            Ok(Err(e)) => error!("Scheduler tick failed: {}", e),
+            Err(_) => error!("Scheduler tick: internal communication error"),
         }
-        Err(_) => Json(serde_json::json!({
-            "success": false,
-            "error": "Internal communication error"
-        })),
     }
 }
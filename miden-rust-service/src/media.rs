@@ -0,0 +1,673 @@
+// src/media.rs
+//
+// Listing photos
+//
+// Owners upload photos of a property with POST /properties/:property_id/media,
+// a multipart/form-data body with one `file` part per photo (at most
+// MAX_FILES_PER_UPLOAD). Parts are read chunk by chunk and refused as soon as
+// they pass MEDIA_MAX_BYTES. The type comes from the content, not from the
+// declared content type: JPEG, PNG and WebP are accepted, and a part declaring
+// a different type (application/octet-stream aside) is refused. Every photo
+// must decode; a JPEG thumbnail fitting MEDIA_THUMBNAIL_PX on its longest side
+// is made from it.
+//
+// Photos and thumbnails are stored by the backend MEDIA_STORE selects:
+// - local: files in MEDIA_DIR named by the SHA-256 of their content
+// - ipfs: added to an IPFS node through its HTTP API (IPFS_API_URL, Kubo's
+//   /api/v0/add) and pinned there; the locator is ipfs://<cid>
+// Reads go by the locator, so photos stored locally stay readable after
+// switching to ipfs. Both backends address content, so uploading a photo twice
+// stores it once; a blob is deleted (unpinned) once no photo refers to it.
+//
+// The media store (MEDIA_PATH) keeps what is known about each photo, and
+// property records list their media IDs in upload order:
+// - GET /properties/:property_id/media: the property's photos
+// - GET /media/:media_id, GET /media/:media_id/thumbnail: the content
+// - DELETE /properties/:property_id/media/:media_id: owner removes a photo
+// A property holds at most MEDIA_MAX_PER_PROPERTY photos.
+
+use anyhow::Result;
+use axum::extract::Multipart;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, io::Cursor, path::PathBuf};
+
+use crate::MidenClientWrapper;
+
+/// `file` parts accepted by one upload request
+pub const MAX_FILES_PER_UPLOAD: usize = 10;
+/// Width and height a photo may have, in pixels
+pub const MAX_IMAGE_DIMENSION: u32 = 16_384;
+/// Memory the decoder may use for one photo
+const MAX_DECODE_ALLOC: u64 = 512 << 20;
+pub const THUMBNAIL_CONTENT_TYPE: &str = "image/jpeg";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageKind {
+    Jpeg,
+    Png,
+    Webp,
+}
+
+impl ImageKind {
+    /// The kind of image `content` is, by its signature.
+    pub fn sniff(content: &[u8]) -> Option<Self> {
+        if content.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(ImageKind::Jpeg)
+        } else if content.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(ImageKind::Png)
+        } else if content.len() >= 12 && &content[..4] == b"RIFF" && &content[8..12] == b"WEBP" {
+            Some(ImageKind::Webp)
+        } else {
+            None
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ImageKind::Jpeg => "image/jpeg",
+            ImageKind::Png => "image/png",
+            ImageKind::Webp => "image/webp",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageKind::Jpeg => "jpg",
+            ImageKind::Png => "png",
+            ImageKind::Webp => "webp",
+        }
+    }
+
+    fn format(&self) -> image::ImageFormat {
+        match self {
+            ImageKind::Jpeg => image::ImageFormat::Jpeg,
+            ImageKind::Png => image::ImageFormat::Png,
+            ImageKind::Webp => image::ImageFormat::WebP,
+        }
+    }
+}
+
+/// An upload refused before anything was stored. The HTTP layer maps each
+/// case to its status (413, 415 or 422).
+#[derive(Debug, thiserror::Error)]
+pub enum MediaRejected {
+    #[error("{name} is larger than {max} bytes")]
+    TooLarge { name: String, max: usize },
+    #[error("{name} is not a JPEG, PNG or WebP image")]
+    UnsupportedType { name: String },
+    #[error("{name} is declared as {declared} but is {actual}")]
+    TypeMismatch {
+        name: String,
+        declared: String,
+        actual: &'static str,
+    },
+    #[error("{name} could not be decoded: {reason}")]
+    Undecodable { name: String, reason: String },
+    #[error("At most {0} files per upload")]
+    TooManyFiles(usize),
+    #[error("Conflict: the property has room for {0} more photo(s)")]
+    NoRoom(usize),
+    #[error("No `file` part in the upload")]
+    NoFiles,
+    #[error("Malformed multipart body: {0}")]
+    Malformed(String),
+}
+
+/// A `file` part as received.
+#[derive(Debug, Clone)]
+pub struct Photo {
+    pub filename: Option<String>,
+    pub declared_type: Option<String>,
+    pub content: Vec<u8>,
+}
+
+impl Photo {
+    fn label(&self, index: usize) -> String {
+        self.filename
+            .clone()
+            .unwrap_or_else(|| format!("file {}", index + 1))
+    }
+}
+
+/// A checked photo and its thumbnail, ready to store.
+#[derive(Debug, Clone)]
+pub struct ProcessedPhoto {
+    pub filename: Option<String>,
+    pub kind: ImageKind,
+    pub content: Vec<u8>,
+    pub thumbnail: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    /// SHA-256 of the content (hex)
+    pub sha256: String,
+}
+
+/// A stored photo not yet attached to a property.
+#[derive(Debug, Clone)]
+pub struct MediaUpload {
+    pub filename: Option<String>,
+    pub content_type: String,
+    pub size: u64,
+    pub width: u32,
+    pub height: u32,
+    pub sha256: String,
+    pub locator: String,
+    pub thumbnail_locator: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaItem {
+    pub media_id: u64,
+    pub property_id: String,
+    pub filename: Option<String>,
+    pub content_type: String,
+    pub size: u64,
+    pub width: u32,
+    pub height: u32,
+    /// SHA-256 of the content (hex)
+    pub sha256: String,
+    /// File name in MEDIA_DIR or ipfs://<cid>
+    pub locator: String,
+    pub thumbnail_locator: String,
+    pub uploaded_at: i64,
+}
+
+/// A removed photo and the blobs no other photo refers to.
+#[derive(Debug, Clone)]
+pub struct MediaRemoval {
+    pub item: MediaItem,
+    pub orphaned: Vec<String>,
+}
+
+/// Reads the `file` parts of an upload, refusing any larger than `max_bytes`
+/// while it streams in. Other parts are skipped.
+pub async fn read_photos(
+    multipart: &mut Multipart,
+    max_bytes: usize,
+    max_files: usize,
+) -> Result<Vec<Photo>, MediaRejected> {
+    let mut photos: Vec<Photo> = Vec::new();
+
+    while let Some(mut field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| MediaRejected::Malformed(e.body_text()))?
+    {
+        if field.name() != Some("file") {
+            continue;
+        }
+        if photos.len() == max_files {
+            return Err(MediaRejected::TooManyFiles(max_files));
+        }
+
+        let mut photo = Photo {
+            filename: field.file_name().map(str::to_string),
+            declared_type: field.content_type().map(str::to_string),
+            content: Vec::new(),
+        };
+        while let Some(chunk) = field
+            .chunk()
+            .await
+            .map_err(|e| MediaRejected::Malformed(e.body_text()))?
+        {
+            if photo.content.len() + chunk.len() > max_bytes {
+                return Err(MediaRejected::TooLarge {
+                    name: photo.label(photos.len()),
+                    max: max_bytes,
+                });
+            }
+            photo.content.extend_from_slice(&chunk);
+        }
+        photos.push(photo);
+    }
+
+    if photos.is_empty() {
+        return Err(MediaRejected::NoFiles);
+    }
+    Ok(photos)
+}
+
+/// Checks the type of a photo, decodes it and makes its thumbnail. CPU-bound;
+/// callers run it off the async runtime.
+pub fn process_photo(
+    photo: Photo,
+    index: usize,
+    thumbnail_px: u32,
+) -> Result<ProcessedPhoto, MediaRejected> {
+    let name = photo.label(index);
+    let kind = ImageKind::sniff(&photo.content)
+        .ok_or_else(|| MediaRejected::UnsupportedType { name: name.clone() })?;
+
+    // Generic declarations say nothing; another image type is a mismatch
+    if let Some(declared) = photo.declared_type.as_deref() {
+        let declared = declared.split(';').next().unwrap_or_default().trim();
+        let generic =
+            declared.is_empty() || declared.eq_ignore_ascii_case("application/octet-stream");
+        if !generic && !declared.eq_ignore_ascii_case(kind.content_type()) {
+            return Err(MediaRejected::TypeMismatch {
+                name,
+                declared: declared.to_string(),
+                actual: kind.content_type(),
+            });
+        }
+    }
+
+    let undecodable = |e: image::ImageError| MediaRejected::Undecodable {
+        name: name.clone(),
+        reason: e.to_string(),
+    };
+    let mut limits = image::Limits::default();
+    limits.max_image_width = Some(MAX_IMAGE_DIMENSION);
+    limits.max_image_height = Some(MAX_IMAGE_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODE_ALLOC);
+    let mut reader = image::ImageReader::with_format(Cursor::new(&photo.content), kind.format());
+    reader.limits(limits);
+    let decoded = reader.decode().map_err(undecodable)?;
+    let (width, height) = (decoded.width(), decoded.height());
+
+    let thumbnail = if width <= thumbnail_px && height <= thumbnail_px {
+        decoded
+    } else {
+        decoded.thumbnail(thumbnail_px, thumbnail_px)
+    };
+    let mut encoded = Cursor::new(Vec::new());
+    image::DynamicImage::ImageRgb8(thumbnail.into_rgb8())
+        .write_to(&mut encoded, image::ImageFormat::Jpeg)
+        .map_err(undecodable)?;
+
+    Ok(ProcessedPhoto {
+        filename: photo.filename,
+        kind,
+        sha256: hex::encode(Sha256::digest(&photo.content)),
+        content: photo.content,
+        thumbnail: encoded.into_inner(),
+        width,
+        height,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaStoreKind {
+    Local,
+    Ipfs,
+}
+
+/// Where photo and thumbnail content is kept (see module docs).
+#[derive(Debug, Clone)]
+pub struct MediaBackend {
+    kind: MediaStoreKind,
+    dir: PathBuf,
+    ipfs_api_url: Option<String>,
+    http: reqwest::Client,
+}
+
+impl MediaBackend {
+    /// `store` is "local" or "ipfs"; ipfs needs the node's API URL.
+    pub fn new(store: &str, dir: PathBuf, ipfs_api_url: Option<String>) -> Result<Self> {
+        let kind = match store.trim().to_lowercase().as_str() {
+            "local" => MediaStoreKind::Local,
+            "ipfs" if ipfs_api_url.is_some() => MediaStoreKind::Ipfs,
+            "ipfs" => return Err(anyhow::anyhow!("ipfs needs IPFS_API_URL")),
+            other => return Err(anyhow::anyhow!("unknown media store {:?}", other)),
+        };
+        Ok(Self {
+            kind,
+            dir,
+            ipfs_api_url: ipfs_api_url.map(|url| url.trim_end_matches('/').to_string()),
+            http: reqwest::Client::new(),
+        })
+    }
+
+    pub fn kind(&self) -> MediaStoreKind {
+        self.kind
+    }
+
+    /// Stores a photo and its thumbnail.
+    pub async fn put(&self, photo: &ProcessedPhoto) -> Result<MediaUpload> {
+        let locator = self
+            .put_blob(
+                &format!("{}.{}", photo.sha256, photo.kind.extension()),
+                &photo.content,
+            )
+            .await?;
+        let thumbnail_locator = self
+            .put_blob(&format!("{}.thumb.jpg", photo.sha256), &photo.thumbnail)
+            .await?;
+
+        Ok(MediaUpload {
+            filename: photo.filename.clone(),
+            content_type: photo.kind.content_type().to_string(),
+            size: photo.content.len() as u64,
+            width: photo.width,
+            height: photo.height,
+            sha256: photo.sha256.clone(),
+            locator,
+            thumbnail_locator,
+        })
+    }
+
+    async fn put_blob(&self, name: &str, content: &[u8]) -> Result<String> {
+        match self.kind {
+            MediaStoreKind::Local => {
+                let path = self.dir.join(name);
+                if !path.exists() {
+                    tokio::fs::create_dir_all(&self.dir).await?;
+                    let tmp_path = path.with_extension("tmp");
+                    tokio::fs::write(&tmp_path, content).await?;
+                    tokio::fs::rename(&tmp_path, &path).await?;
+                }
+                Ok(name.to_string())
+            }
+            MediaStoreKind::Ipfs => {
+                #[derive(Deserialize)]
+                struct Added {
+                    #[serde(rename = "Hash")]
+                    hash: String,
+                }
+
+                let part =
+                    reqwest::multipart::Part::bytes(content.to_vec()).file_name(name.to_string());
+                let added: Added = self
+                    .http
+                    .post(format!("{}/api/v0/add", self.ipfs_api()?))
+                    .query(&[("pin", "true"), ("cid-version", "1")])
+                    .multipart(reqwest::multipart::Form::new().part("file", part))
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                Ok(format!("ipfs://{}", added.hash))
+            }
+        }
+    }
+
+    pub async fn get(&self, locator: &str) -> Result<Vec<u8>> {
+        match locator.strip_prefix("ipfs://") {
+            Some(cid) => {
+                let content = self
+                    .http
+                    .post(format!("{}/api/v0/cat", self.ipfs_api()?))
+                    .query(&[("arg", cid)])
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .bytes()
+                    .await?;
+                Ok(content.to_vec())
+            }
+            None => Ok(tokio::fs::read(self.local_path(locator)?).await?),
+        }
+    }
+
+    /// Deletes (unpins) a blob; a blob already gone is not an error.
+    pub async fn delete(&self, locator: &str) -> Result<()> {
+        match locator.strip_prefix("ipfs://") {
+            Some(cid) => {
+                self.http
+                    .post(format!("{}/api/v0/pin/rm", self.ipfs_api()?))
+                    .query(&[("arg", cid)])
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                Ok(())
+            }
+            None => match tokio::fs::remove_file(self.local_path(locator)?).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            },
+        }
+    }
+
+    fn ipfs_api(&self) -> Result<&str> {
+        self.ipfs_api_url
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Media is on IPFS but IPFS_API_URL is not set"))
+    }
+
+    fn local_path(&self, locator: &str) -> Result<PathBuf> {
+        if locator.is_empty() || locator.contains(['/', '\\']) || locator.starts_with('.') {
+            return Err(anyhow::anyhow!("Invalid media locator {:?}", locator));
+        }
+        Ok(self.dir.join(locator))
+    }
+}
+
+/// Media settings (see module docs).
+#[derive(Debug, Clone)]
+pub struct MediaPolicy {
+    pub backend: MediaBackend,
+    pub max_bytes: usize,
+    pub thumbnail_px: u32,
+    pub max_per_property: usize,
+}
+
+impl MediaPolicy {
+    /// Largest request body an upload may have: all parts at their largest
+    /// plus room for the multipart framing.
+    pub fn upload_body_limit(&self) -> usize {
+        self.max_bytes.saturating_mul(MAX_FILES_PER_UPLOAD) + (64 << 10)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MediaStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    items: BTreeMap<u64, MediaItem>,
+    #[serde(default)]
+    next_media_id: u64,
+}
+
+impl MediaStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<MediaStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            MediaStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, media_id: u64) -> Option<&MediaItem> {
+        self.items.get(&media_id)
+    }
+
+    /// Photos of a property in upload order.
+    pub fn for_property(&self, property_id: &str) -> Vec<&MediaItem> {
+        self.items
+            .values()
+            .filter(|m| m.property_id == property_id)
+            .collect()
+    }
+
+    pub fn add(&mut self, property_id: &str, uploads: Vec<MediaUpload>) -> Result<Vec<MediaItem>> {
+        let uploaded_at = chrono::Utc::now().timestamp();
+        let mut added = Vec::with_capacity(uploads.len());
+
+        for upload in uploads {
+            self.next_media_id += 1;
+            let item = MediaItem {
+                media_id: self.next_media_id,
+                property_id: property_id.to_string(),
+                filename: upload.filename,
+                content_type: upload.content_type,
+                size: upload.size,
+                width: upload.width,
+                height: upload.height,
+                sha256: upload.sha256,
+                locator: upload.locator,
+                thumbnail_locator: upload.thumbnail_locator,
+                uploaded_at,
+            };
+            self.items.insert(item.media_id, item.clone());
+            added.push(item);
+        }
+
+        self.save()?;
+        Ok(added)
+    }
+
+    pub fn remove(&mut self, property_id: &str, media_id: u64) -> Result<MediaRemoval> {
+        if self.get(media_id).map(|m| m.property_id.as_str()) != Some(property_id) {
+            return Err(anyhow::anyhow!(
+                "Media {} not found on {}",
+                media_id,
+                property_id
+            ));
+        }
+        let item = self
+            .items
+            .remove(&media_id)
+            .ok_or_else(|| anyhow::anyhow!("Media {} not found", media_id))?;
+        self.save()?;
+
+        let orphaned = [&item.locator, &item.thumbnail_locator]
+            .into_iter()
+            .filter(|locator| {
+                !self
+                    .items
+                    .values()
+                    .any(|m| &&m.locator == locator || &&m.thumbnail_locator == locator)
+            })
+            .cloned()
+            .collect();
+        Ok(MediaRemoval { item, orphaned })
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    MediaUploadSlots {
+        property_id: String,
+        api_key: Option<String>,
+    } -> usize;
+    |client, op| client.media_upload_slots(&op.property_id, op.api_key.as_deref());
+
+    AddPropertyMedia {
+        property_id: String,
+        uploads: Vec<MediaUpload>,
+        api_key: Option<String>,
+    } -> Vec<MediaItem>;
+    |client, op| client.add_property_media(&op.property_id, op.uploads, op.api_key.as_deref());
+
+    ListPropertyMedia {
+        property_id: String,
+    } -> Vec<MediaItem>;
+    |client, op| client.list_property_media(&op.property_id);
+
+    GetMedia {
+        media_id: u64,
+    } -> MediaItem;
+    |client, op| client.get_media(op.media_id);
+
+    RemovePropertyMedia {
+        property_id: String,
+        media_id: u64,
+        api_key: Option<String>,
+    } -> MediaRemoval;
+    |client, op| client.remove_property_media(&op.property_id, op.media_id, op.api_key.as_deref())
+}
+
+impl MidenClientWrapper {
+    fn authorize_media_owner(&self, property_id: &str, api_key: Option<&str>) -> Result<()> {
+        let owner_hex = self
+            .records
+            .properties
+            .get(property_id)
+            .map(|p| p.owner_account_id.to_lowercase())
+            .ok_or_else(|| anyhow::anyhow!("Property {} not found", property_id))?;
+        self.authorize_account(api_key, &owner_hex, "owner")
+    }
+
+    /// Photos the owner may still add to a property. Needs an API key bound
+    /// to the owner.
+    pub fn media_upload_slots(&self, property_id: &str, api_key: Option<&str>) -> Result<usize> {
+        self.authorize_media_owner(property_id, api_key)?;
+        Ok(self
+            .config
+            .media
+            .max_per_property
+            .saturating_sub(self.media.for_property(property_id).len()))
+    }
+
+    /// Attaches stored photos to a property and lists them on its record.
+    pub fn add_property_media(
+        &mut self,
+        property_id: &str,
+        uploads: Vec<MediaUpload>,
+        api_key: Option<&str>,
+    ) -> Result<Vec<MediaItem>> {
+        let slots = self.media_upload_slots(property_id, api_key)?;
+        if uploads.len() > slots {
+            return Err(MediaRejected::NoRoom(slots).into());
+        }
+
+        let added = self.media.add(property_id, uploads)?;
+        self.record_media_ids(property_id);
+        tracing::info!("Added {} photo(s) to {}", added.len(), property_id);
+        Ok(added)
+    }
+
+    pub fn list_property_media(&self, property_id: &str) -> Result<Vec<MediaItem>> {
+        if !self.records.properties.contains_key(property_id) {
+            return Err(anyhow::anyhow!("Property {} not found", property_id));
+        }
+        Ok(self
+            .media
+            .for_property(property_id)
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+
+    pub fn get_media(&self, media_id: u64) -> Result<MediaItem> {
+        self.media
+            .get(media_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Media {} not found", media_id))
+    }
+
+    /// Removes a photo from a property. Needs an API key bound to the owner;
+    /// the caller deletes the orphaned blobs from the backend.
+    pub fn remove_property_media(
+        &mut self,
+        property_id: &str,
+        media_id: u64,
+        api_key: Option<&str>,
+    ) -> Result<MediaRemoval> {
+        self.authorize_media_owner(property_id, api_key)?;
+        let removal = self.media.remove(property_id, media_id)?;
+        self.record_media_ids(property_id);
+        tracing::info!("Removed photo {} from {}", media_id, property_id);
+        Ok(removal)
+    }
+
+    fn record_media_ids(&mut self, property_id: &str) {
+        let media_ids = self
+            .media
+            .for_property(property_id)
+            .into_iter()
+            .map(|m| m.media_id)
+            .collect();
+        self.records.record_media(property_id, media_ids);
+    }
+}
@@ -0,0 +1,332 @@
+// src/mint_coalescing.rs
+//
+// Coalescing of property mints into shared faucet transactions
+//
+// Each POST /mint-property is its own faucet transaction followed by a note
+// propagation wait, so onboarding N properties takes N waits. With
+// MINT_COALESCE_WINDOW_MS set, mint requests are held for up to that window
+// (or until MINT_COALESCE_MAX_ITEMS are waiting) and sent to the client task as
+// one batch: a single faucet transaction with one P2ID output note per property,
+// then a single propagation wait for all of them.
+//
+// Every property of a batch is journaled, recorded and hooked as if minted
+// alone (lib.rs), with the shared transaction ID and its own note ID, known
+// from the note the batch created. A property already minted
+// (property_registry.rs), listed twice in the batch, or whose price or owner
+// cannot be resolved fails alone. If the batch transaction fails, its properties are
+// minted one at a time, so a single bad item does not fail the others; failures
+// left after that go to the retry queue like any mint (retry_queue.rs).
+//
+// Requests that opted in to offline mode (offline_queue.rs) skip coalescing.
+
+use anyhow::Result;
+use miden_client::{account::AccountId, asset::FungibleAsset};
+use std::{collections::HashSet, future::Future, time::Duration};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    account_id_to_hex, client,
+    currency::Money,
+    ledger::{issuance_account, JournalKind, Posting},
+    mint_jobs::MintItemInput,
+    property_registry::{canonical_property_id, property_mint_serial_num},
+    read_cache::Touched,
+    retry_queue::RetryOperation,
+    MidenClientWrapper, PROPERTY_MINT_AMOUNT,
+};
+
+/// Mint requests waiting for the coalescing task.
+const PENDING_CAPACITY: usize = 256;
+
+/// (transaction ID, note ID) of a mint, or why it failed.
+pub type MintResult = Result<(String, String), String>;
+
+#[derive(Debug, Clone)]
+pub struct MintCoalescing {
+    /// How long the first mint of a batch waits for others
+    pub window: Duration,
+    /// Mints per faucet transaction
+    pub max_items: usize,
+}
+
+struct PendingMint {
+    input: MintItemInput,
+    response: oneshot::Sender<MintResult>,
+}
+
+/// Handle of the coalescing task, shared by the mint handlers.
+#[derive(Clone)]
+pub struct MintCoalescer {
+    tx: mpsc::Sender<PendingMint>,
+}
+
+impl MintCoalescer {
+    /// Starts the coalescing task; `dispatch` sends a batch to the client task
+    /// and returns one result per item, in order.
+    pub fn spawn<F, Fut>(policy: MintCoalescing, dispatch: F) -> Self
+    where
+        F: Fn(Vec<MintItemInput>) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Vec<MintResult>, String>> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel(PENDING_CAPACITY);
+        tokio::spawn(run_coalescer(policy, rx, dispatch));
+        MintCoalescer { tx }
+    }
+
+    /// Queues a mint for the next batch; the receiver gets its result.
+    pub async fn submit(&self, input: MintItemInput) -> Result<oneshot::Receiver<MintResult>> {
+        let (response, rx) = oneshot::channel();
+        self.tx
+            .send(PendingMint { input, response })
+            .await
+            .map_err(|_| anyhow::anyhow!("Mint coalescing task not available"))?;
+        Ok(rx)
+    }
+}
+
+/// Collects mints into batches until the handles are dropped.
+async fn run_coalescer<F, Fut>(
+    policy: MintCoalescing,
+    mut rx: mpsc::Receiver<PendingMint>,
+    dispatch: F,
+) where
+    F: Fn(Vec<MintItemInput>) -> Fut,
+    Fut: Future<Output = Result<Vec<MintResult>, String>>,
+{
+    let max_items = policy.max_items.max(1);
+    while let Some(first) = rx.recv().await {
+        let mut batch = vec![first];
+        let deadline = tokio::time::Instant::now() + policy.window;
+        while batch.len() < max_items {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(pending)) => batch.push(pending),
+                _ => break,
+            }
+        }
+
+        tracing::info!("Dispatching {} coalesced mint(s)", batch.len());
+        let inputs = batch.iter().map(|pending| pending.input.clone()).collect();
+        match dispatch(inputs).await {
+            Ok(results) => {
+                let mut results = results.into_iter();
+                for pending in batch {
+                    let result = results
+                        .next()
+                        .unwrap_or_else(|| Err("Internal communication error".to_string()));
+                    let _ = pending.response.send(result);
+                }
+            }
+            Err(e) => {
+                for pending in batch {
+                    let _ = pending.response.send(Err(e.clone()));
+                }
+            }
+        }
+    }
+}
+
+// ============================================================================
+// CLIENT INTEGRATION
+// ============================================================================
+
+crate::operations! {
+    /// Mint requests coalesced into one faucet transaction
+    MintPropertyBatch {
+        items: Vec<MintItemInput>,
+    } -> Vec<MintResult>;
+    touched: |op| {
+        let mut accounts: Vec<String> =
+            op.items.iter().map(|i| i.owner_account_id.clone()).collect();
+        accounts.push("faucet".to_string());
+        Some(Touched::Accounts(accounts))
+    };
+    |client, op| {
+        let results = client.mint_property_batch(&op.items).await;
+        let results = op
+            .items
+            .into_iter()
+            .zip(results)
+            .map(|(item, result)| {
+                let operation = RetryOperation::MintProperty {
+                    property_id: item.property_id,
+                    owner_account_id: item.owner_account_id,
+                    ipfs_cid: item.ipfs_cid,
+                    property_type: item.property_type,
+                    price: item.price,
+                };
+                client
+                    .retry_on_failure(operation, result)
+                    .map_err(|e| e.to_string())
+            })
+            .collect();
+        Ok(results)
+    }
+}
+
+impl MidenClientWrapper {
+    /// Mints several properties in one faucet transaction (see module docs).
+    /// Returns one result per item, in order.
+    pub async fn mint_property_batch(
+        &mut self,
+        items: &[MintItemInput],
+    ) -> Vec<Result<(String, String)>> {
+        let mut results: Vec<Option<Result<(String, String)>>> =
+            items.iter().map(|_| None).collect();
+
+        // Items whose property, price or owner is refused fail alone
+        let mut planned: Vec<(usize, AccountId, Money)> = Vec::new();
+        let mut batched = HashSet::new();
+        for (index, item) in items.iter().enumerate() {
+            let resolved = self
+                .check_property_unminted(&item.property_id)
+                .and_then(|_| {
+                    if batched.insert(canonical_property_id(&item.property_id)) {
+                        Ok(())
+                    } else {
+                        Err(anyhow::anyhow!(
+                            "Conflict: property {} appears more than once in the batch",
+                            item.property_id
+                        ))
+                    }
+                })
+                .and_then(|_| item.price.resolve(&self.config.price_currency))
+                .and_then(|price| {
+                    self.mint_recipient(&item.owner_account_id)
+                        .map(|recipient| (recipient, price))
+                });
+            match resolved {
+                Ok((recipient, price)) => planned.push((index, recipient, price)),
+                Err(e) => results[index] = Some(Err(e)),
+            }
+        }
+
+        if planned.len() > 1 {
+            let op_ids: Vec<u64> = planned
+                .iter()
+                .map(|(index, _, _)| {
+                    self.records
+                        .begin_operation("mint_property", &items[*index].property_id)
+                })
+                .collect();
+            let submitted = self.submit_mint_batch(items, &planned).await;
+
+            let outcome: Result<()> = match &submitted {
+                Ok(_) => Ok(()),
+                Err(e) => Err(anyhow::anyhow!("{}", e)),
+            };
+            let tx_id = submitted.as_ref().ok().map(|(tx_id, _)| tx_id.clone());
+            for op_id in op_ids {
+                self.records
+                    .finish_operation(op_id, &outcome, tx_id.clone());
+            }
+
+            match submitted {
+                Ok((tx_id, note_ids)) => {
+                    for ((index, recipient, price), note_id) in planned.drain(..).zip(note_ids) {
+                        let item = &items[index];
+                        self.record_property_mint(
+                            &item.property_id,
+                            recipient,
+                            &item.ipfs_cid,
+                            item.property_type,
+                            price,
+                            &tx_id,
+                            &note_id,
+                            false,
+                        )
+                        .await;
+                        results[index] = Some(Ok((tx_id.clone(), note_id)));
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "⚠️  Mint batch of {} failed, minting one at a time: {}",
+                        planned.len(),
+                        e
+                    );
+                }
+            }
+        }
+
+        // A lone item, or the items of a failed batch
+        for (index, _, _) in planned {
+            let item = &items[index];
+            results[index] = Some(
+                self.mint_property_nft(
+                    &item.property_id,
+                    &item.owner_account_id,
+                    &item.ipfs_cid,
+                    item.property_type,
+                    &item.price,
+                )
+                .await,
+            );
+        }
+
+        results
+            .into_iter()
+            .map(|result| result.unwrap_or_else(|| Err(anyhow::anyhow!("Mint not attempted"))))
+            .collect()
+    }
+
+    /// Submits one faucet transaction with a P2ID note per planned item and
+    /// waits once for the notes to propagate.
+    ///
+    /// Returns (transaction ID, note IDs in the order of `planned`).
+    async fn submit_mint_batch(
+        &mut self,
+        items: &[MintItemInput],
+        planned: &[(usize, AccountId, Money)],
+    ) -> Result<(String, Vec<String>)> {
+        let faucet_account_id = self
+            .faucet_account_id
+            .ok_or_else(|| anyhow::anyhow!("Faucet not initialized"))?;
+        let faucet_hex = account_id_to_hex(faucet_account_id);
+
+        let mut notes = Vec::with_capacity(planned.len());
+        let mut note_ids = Vec::with_capacity(planned.len());
+        for (index, recipient, _) in planned {
+            let asset = FungibleAsset::new(faucet_account_id, PROPERTY_MINT_AMOUNT)?;
+            let note = client::p2id_note_with_serial(
+                faucet_account_id,
+                *recipient,
+                vec![asset.into()],
+                property_mint_serial_num(&items[*index].property_id, 0),
+            )?;
+            note_ids.push(note.id().to_string());
+            notes.push(note);
+        }
+
+        tracing::info!("Minting {} properties in one transaction", notes.len());
+        let request = client::notes_request(notes)?;
+        let tx_id = self
+            .client
+            .submit_new_transaction(faucet_account_id, request)
+            .await?
+            .to_string();
+        tracing::info!("Minted batch. TX: {}", tx_id);
+
+        for (index, recipient, _) in planned {
+            self.post_ledger_entry(
+                JournalKind::Mint,
+                Posting::transfer(
+                    &issuance_account(&faucet_hex),
+                    &account_id_to_hex(*recipient),
+                    &faucet_hex,
+                    PROPERTY_MINT_AMOUNT,
+                )
+                .to_vec(),
+                &tx_id,
+                Some(&items[*index].property_id),
+            );
+        }
+
+        // One wait for the whole batch
+        self.wait_for_propagation().await;
+        if let Err(e) = self.sync_state().await {
+            tracing::warn!("⚠️  Sync after mint batch {} failed: {}", tx_id, e);
+        }
+
+        Ok((tx_id, note_ids))
+    }
+}
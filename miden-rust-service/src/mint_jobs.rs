@@ -0,0 +1,467 @@
+// src/mint_jobs.rs
+//
+// Bulk mint jobs for portfolio onboarding
+//
+// A batch of property records is accepted as a job and minted in the background,
+// a chunk at a time, so interactive requests keep flowing through the client
+// queue between chunks. Job state is persisted after every item; on restart,
+// unfinished jobs are resumed. DELETE /jobs/:job_id cancels a job: items not
+// yet started are left unminted.
+//
+// Crash safety: an item is marked `submitting` before its transaction is sent.
+// If the service dies mid-item, the resume path looks the property up in the
+// service records journal first and only re-submits when no mint was recorded,
+// so a property is never minted twice by a resumed job.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{currency::PriceInput, read_cache::Touched, MidenClientWrapper};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MintItemInput {
+    pub property_id: String,
+    pub owner_account_id: String,
+    pub ipfs_cid: String,
+    pub property_type: u8,
+    pub price: PriceInput,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MintItemStatus {
+    Pending,
+    Submitting,
+    Minted,
+    Failed,
+    /// Not minted because the job was cancelled
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MintItem {
+    pub index: usize,
+    #[serde(flatten)]
+    pub input: MintItemInput,
+    pub status: MintItemStatus,
+    pub tx_id: Option<String>,
+    pub note_id: Option<String>,
+    pub error: Option<String>,
+    pub attempts: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MintJobStatus {
+    Queued,
+    Running,
+    Completed,
+    CompletedWithErrors,
+    Cancelled,
+}
+
+impl MintJobStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            MintJobStatus::Completed
+                | MintJobStatus::CompletedWithErrors
+                | MintJobStatus::Cancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MintJob {
+    pub job_id: String,
+    pub status: MintJobStatus,
+    pub chunk_size: usize,
+    pub items: Vec<MintItem>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub finished_at: Option<i64>,
+}
+
+impl MintJob {
+    /// Indexes of the next chunk of items still to be processed.
+    pub fn next_chunk(&self) -> Vec<usize> {
+        self.items
+            .iter()
+            .filter(|item| {
+                matches!(
+                    item.status,
+                    MintItemStatus::Pending | MintItemStatus::Submitting
+                )
+            })
+            .take(self.chunk_size)
+            .map(|item| item.index)
+            .collect()
+    }
+
+    /// Ends the job, leaving items not yet started unminted.
+    fn cancel(&mut self) {
+        let now = chrono::Utc::now().timestamp();
+        for item in &mut self.items {
+            if item.status == MintItemStatus::Pending {
+                item.status = MintItemStatus::Cancelled;
+            }
+        }
+        self.status = MintJobStatus::Cancelled;
+        self.updated_at = now;
+        self.finished_at = Some(now);
+    }
+
+    fn count(&self, status: MintItemStatus) -> usize {
+        self.items.iter().filter(|i| i.status == status).count()
+    }
+
+    /// Progress counters plus per-item results.
+    pub fn report(&self) -> serde_json::Value {
+        serde_json::json!({
+            "job_id": self.job_id,
+            "status": self.status,
+            "total": self.items.len(),
+            "minted": self.count(MintItemStatus::Minted),
+            "failed": self.count(MintItemStatus::Failed),
+            "cancelled": self.count(MintItemStatus::Cancelled),
+            "pending": self.count(MintItemStatus::Pending) + self.count(MintItemStatus::Submitting),
+            "chunk_size": self.chunk_size,
+            "created_at": self.created_at,
+            "updated_at": self.updated_at,
+            "finished_at": self.finished_at,
+            "items": self.items,
+        })
+    }
+
+    /// Progress counters only.
+    pub fn summary(&self) -> serde_json::Value {
+        let mut report = self.report();
+        if let Some(obj) = report.as_object_mut() {
+            obj.remove("items");
+        }
+        report
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MintJobStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    jobs: BTreeMap<String, MintJob>,
+    #[serde(default)]
+    next_job_id: u64,
+}
+
+impl MintJobStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<MintJobStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            MintJobStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Persists, logging instead of failing: losing a progress write must not
+    /// abort a mint that already reached the chain.
+    pub fn persist(&self) {
+        if let Err(e) = self.save() {
+            tracing::warn!("Failed to persist mint jobs: {}", e);
+        }
+    }
+
+    pub fn create(&mut self, inputs: Vec<MintItemInput>, chunk_size: usize) -> Result<&MintJob> {
+        self.next_job_id += 1;
+        let job_id = format!("mint-batch-{}", self.next_job_id);
+        let now = chrono::Utc::now().timestamp();
+
+        let items = inputs
+            .into_iter()
+            .enumerate()
+            .map(|(index, input)| MintItem {
+                index,
+                input,
+                status: MintItemStatus::Pending,
+                tx_id: None,
+                note_id: None,
+                error: None,
+                attempts: 0,
+            })
+            .collect();
+
+        self.jobs.insert(
+            job_id.clone(),
+            MintJob {
+                job_id: job_id.clone(),
+                status: MintJobStatus::Queued,
+                chunk_size: chunk_size.max(1),
+                items,
+                created_at: now,
+                updated_at: now,
+                finished_at: None,
+            },
+        );
+        self.save()?;
+
+        self.get(&job_id)
+    }
+
+    pub fn get(&self, job_id: &str) -> Result<&MintJob> {
+        self.jobs
+            .get(job_id)
+            .ok_or_else(|| anyhow::anyhow!("Mint job {} not found", job_id))
+    }
+
+    pub fn get_mut(&mut self, job_id: &str) -> Result<&mut MintJob> {
+        self.jobs
+            .get_mut(job_id)
+            .ok_or_else(|| anyhow::anyhow!("Mint job {} not found", job_id))
+    }
+
+    pub fn list(&self) -> Vec<&MintJob> {
+        self.jobs.values().collect()
+    }
+
+    /// Jobs that were queued or running when the service last stopped.
+    pub fn unfinished(&self) -> Vec<String> {
+        self.jobs
+            .values()
+            .filter(|job| !job.status.is_finished())
+            .map(|job| job.job_id.clone())
+            .collect()
+    }
+
+    /// True if the property is part of any job that has not finished yet.
+    pub fn has_unfinished_item(&self, property_id: &str) -> bool {
+        self.jobs
+            .values()
+            .filter(|job| !job.status.is_finished())
+            .flat_map(|job| job.items.iter())
+            .any(|item| {
+                item.input.property_id == property_id
+                    && matches!(
+                        item.status,
+                        MintItemStatus::Pending | MintItemStatus::Submitting
+                    )
+            })
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    CreateMintBatch {
+        items: Vec<MintItemInput>,
+    } -> serde_json::Value;
+    |client, op| client.create_mint_batch(op.items);
+
+    RunMintBatchChunk {
+        job_id: String,
+    } -> bool;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.run_mint_batch_chunk(&op.job_id).await;
+
+    GetMintBatch {
+        job_id: String,
+    } -> serde_json::Value;
+    |client, op| client.get_mint_batch(&op.job_id);
+
+    ListMintBatches {} -> serde_json::Value;
+    |client, _op| client.list_mint_batches();
+
+    UnfinishedMintBatches {} -> Vec<String>;
+    |client, _op| Ok(client.unfinished_mint_batches())
+}
+
+impl MidenClientWrapper {
+    /// Validates and queues a batch mint job. Returns the job summary.
+    pub fn create_mint_batch(&mut self, items: Vec<MintItemInput>) -> Result<serde_json::Value> {
+        if items.is_empty() {
+            return Err(anyhow::anyhow!("Batch contains no properties"));
+        }
+        if items.len() > self.config.mint_batch_max_items {
+            return Err(anyhow::anyhow!(
+                "Batch contains {} properties; the limit is {}",
+                items.len(),
+                self.config.mint_batch_max_items
+            ));
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        for item in &items {
+            if !seen.insert(item.property_id.as_str()) {
+                return Err(anyhow::anyhow!(
+                    "Property {} appears more than once in the batch",
+                    item.property_id
+                ));
+            }
+            if self.records.properties.contains_key(&item.property_id) {
+                return Err(anyhow::anyhow!(
+                    "Property {} has already been minted",
+                    item.property_id
+                ));
+            }
+            if self.mint_jobs.has_unfinished_item(&item.property_id) {
+                return Err(anyhow::anyhow!(
+                    "Property {} is already queued in another batch",
+                    item.property_id
+                ));
+            }
+        }
+
+        let chunk_size = self.config.mint_batch_chunk_size;
+        let job = self.mint_jobs.create(items, chunk_size)?;
+        tracing::info!(
+            "Queued mint job {} ({} properties)",
+            job.job_id,
+            job.items.len()
+        );
+
+        Ok(job.summary())
+    }
+
+    /// Mints the next chunk of a job. Returns true once the job has finished.
+    pub async fn run_mint_batch_chunk(&mut self, job_id: &str) -> Result<bool> {
+        let chunk = {
+            let job = self.mint_jobs.get_mut(job_id)?;
+            if job.status.is_finished() {
+                return Ok(true);
+            }
+            job.status = MintJobStatus::Running;
+            job.next_chunk()
+        };
+
+        for index in chunk {
+            // DELETE /jobs/:job_id (see deadlines.rs)
+            if self.is_cancelled() {
+                let job = self.mint_jobs.get_mut(job_id)?;
+                job.cancel();
+                self.mint_jobs.persist();
+                tracing::info!("Mint job {} cancelled", job_id);
+                return Ok(true);
+            }
+
+            let input = self.mint_jobs.get(job_id)?.items[index].input.clone();
+
+            // Already minted (e.g. crash after submission): take the journaled result
+            if let Some(record) = self.records.properties.get(&input.property_id) {
+                let (tx_id, note_id) = (record.mint_tx_id.clone(), record.note_id.clone());
+                let item = &mut self.mint_jobs.get_mut(job_id)?.items[index];
+                item.status = MintItemStatus::Minted;
+                item.tx_id = Some(tx_id);
+                item.note_id = Some(note_id);
+                self.mint_jobs.persist();
+                continue;
+            }
+
+            {
+                let item = &mut self.mint_jobs.get_mut(job_id)?.items[index];
+                item.status = MintItemStatus::Submitting;
+                item.attempts += 1;
+            }
+            self.mint_jobs.persist();
+
+            let result = self
+                .mint_property_nft(
+                    &input.property_id,
+                    &input.owner_account_id,
+                    &input.ipfs_cid,
+                    input.property_type,
+                    &input.price,
+                )
+                .await;
+
+            let item = &mut self.mint_jobs.get_mut(job_id)?.items[index];
+            match result {
+                Ok((tx_id, note_id)) => {
+                    item.status = MintItemStatus::Minted;
+                    item.tx_id = Some(tx_id);
+                    item.note_id = Some(note_id);
+                    item.error = None;
+                }
+                Err(e) => {
+                    tracing::warn!("Mint job {}: {} failed: {}", job_id, input.property_id, e);
+                    item.status = MintItemStatus::Failed;
+                    item.error = Some(e.to_string());
+                }
+            }
+            self.mint_jobs.persist();
+        }
+
+        let job = self.mint_jobs.get_mut(job_id)?;
+        let now = chrono::Utc::now().timestamp();
+        job.updated_at = now;
+
+        let done = job.next_chunk().is_empty();
+        if done {
+            let any_failed = job.items.iter().any(|i| i.status == MintItemStatus::Failed);
+            job.status = if any_failed {
+                MintJobStatus::CompletedWithErrors
+            } else {
+                MintJobStatus::Completed
+            };
+            job.finished_at = Some(now);
+            tracing::info!("Mint job {} finished: {:?}", job_id, job.status);
+        }
+        self.mint_jobs.persist();
+
+        Ok(done)
+    }
+
+    /// Cancels a job; items already minted stay minted. Returns the summary.
+    pub fn cancel_mint_batch(&mut self, job_id: &str) -> Result<serde_json::Value> {
+        let job = self.mint_jobs.get_mut(job_id)?;
+        match job.status {
+            MintJobStatus::Cancelled => return Ok(job.summary()),
+            status if status.is_finished() => {
+                return Err(anyhow::anyhow!(
+                    "Conflict: mint job {} already finished ({:?})",
+                    job_id,
+                    status
+                ));
+            }
+            _ => job.cancel(),
+        }
+        let summary = job.summary();
+        self.mint_jobs.persist();
+        tracing::info!("Mint job {} cancelled", job_id);
+
+        Ok(summary)
+    }
+
+    /// Full job report including per-item tx/note IDs.
+    pub fn get_mint_batch(&self, job_id: &str) -> Result<serde_json::Value> {
+        Ok(self.mint_jobs.get(job_id)?.report())
+    }
+
+    pub fn list_mint_batches(&self) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self
+            .mint_jobs
+            .list()
+            .iter()
+            .map(|job| job.summary())
+            .collect::<Vec<_>>()))
+    }
+
+    /// Jobs to resume after a restart.
+    pub fn unfinished_mint_batches(&self) -> Vec<String> {
+        self.mint_jobs.unfinished()
+    }
+}
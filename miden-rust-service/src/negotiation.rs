@@ -0,0 +1,711 @@
+// src/negotiation.rs
+//
+// Offer / counter-offer negotiation
+//
+// A listing is a minted property offered by its owner at its recorded price;
+// listings are addressed by property ID (/listings/:property_id). Buyers submit
+// offers on a listing, and buyer and seller then take turns:
+//
+//   open (awaiting seller) --counter--> open (awaiting buyer) --counter--> ...
+//   open --accept (awaiting party)-->  accepted: escrow opened on the terms
+//   open --reject (awaiting party)-->  rejected
+//   open --withdraw (proposer)------>  withdrawn
+//   open --terms expire------------->  expired
+//
+// Every step is a round with its own expiry; only the party the offer is
+// waiting on may accept, reject or counter, and only before the latest terms
+// expire. Accepting creates the escrow (buyer -> seller, tied to the property)
+// for the agreed amount and supersedes the other open offers on the listing.
+// Expired offers are closed by the scheduler (scheduler.rs), or when acted on.
+//
+// Each party acts with an API key bound to its account (or an arbiter's),
+// like escrow actions (escrow.rs).
+//
+// On a confidential listing (confidential_listings.rs) a buyer needs a
+// disclosure grant to make an offer, and offers are visible only to the owner,
+// an arbiter and each offer's buyer.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    account_id_to_hex,
+    escrow::{EscrowAuthError, EscrowStatus},
+    read_cache::Touched,
+    MidenClientWrapper,
+};
+
+/// Upper bound on how long one round of terms may stay open (30 days).
+pub const MAX_OFFER_EXPIRY_SECS: u64 = 30 * 86_400;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OfferInput {
+    /// Account name or hex AccountId
+    pub buyer_account_id: String,
+    pub amount: u64,
+    /// Seconds the seller has to respond (defaults to OFFER_EXPIRY_SECS)
+    #[serde(default)]
+    pub expires_in_secs: Option<u64>,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CounterInput {
+    pub amount: u64,
+    /// Seconds the other party has to respond (defaults to OFFER_EXPIRY_SECS)
+    #[serde(default)]
+    pub expires_in_secs: Option<u64>,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// Rejections and withdrawals may say why.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OfferResponseInput {
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Raised when an action does not fit the offer's current state; the HTTP
+/// layer maps it to 409.
+#[derive(Debug, thiserror::Error)]
+#[error("Conflict: {0}")]
+pub struct InvalidTransition(pub String);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Party {
+    Buyer,
+    Seller,
+}
+
+impl Party {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Party::Buyer => "buyer",
+            Party::Seller => "seller",
+        }
+    }
+
+    fn other(&self) -> Party {
+        match self {
+            Party::Buyer => Party::Seller,
+            Party::Seller => Party::Buyer,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OfferStatus {
+    Open,
+    Accepted,
+    Rejected,
+    Withdrawn,
+    Expired,
+    /// Another offer on the listing was accepted
+    Superseded,
+}
+
+/// One set of proposed terms.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfferRound {
+    pub round: u32,
+    pub by: Party,
+    pub amount: u64,
+    pub message: Option<String>,
+    pub proposed_at: i64,
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Offer {
+    pub offer_id: String,
+    pub property_id: String,
+    pub seller_account_id: String,
+    pub buyer_account_id: String,
+    pub status: OfferStatus,
+    pub rounds: Vec<OfferRound>,
+    /// Set once accepted
+    pub escrow_account_id: Option<String>,
+    /// Why the offer was rejected, withdrawn or superseded
+    pub closing_reason: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub closed_at: Option<i64>,
+}
+
+impl Offer {
+    /// The terms currently on the table.
+    pub fn latest(&self) -> &OfferRound {
+        self.rounds
+            .last()
+            .expect("an offer always has its opening round")
+    }
+
+    /// The party expected to respond to the latest terms.
+    pub fn awaiting(&self) -> Party {
+        self.latest().by.other()
+    }
+
+    pub fn party_account(&self, party: Party) -> &str {
+        match party {
+            Party::Buyer => &self.buyer_account_id,
+            Party::Seller => &self.seller_account_id,
+        }
+    }
+
+    fn close(&mut self, status: OfferStatus, reason: Option<String>) {
+        let now = chrono::Utc::now().timestamp();
+        tracing::info!(
+            "Offer {} on {}: {:?}{}",
+            self.offer_id,
+            self.property_id,
+            status,
+            reason
+                .as_deref()
+                .map(|r| format!(" ({})", r))
+                .unwrap_or_default()
+        );
+        self.status = status;
+        self.closing_reason = reason;
+        self.closed_at = Some(now);
+        self.updated_at = now;
+    }
+
+    /// Fails unless the offer is open with unexpired terms.
+    fn ensure_open(&self, now: i64) -> Result<(), InvalidTransition> {
+        if self.status != OfferStatus::Open {
+            return Err(InvalidTransition(format!(
+                "offer {} is {:?}",
+                self.offer_id, self.status
+            )));
+        }
+        if now >= self.latest().expires_at {
+            return Err(InvalidTransition(format!(
+                "offer {} expired at {}",
+                self.offer_id,
+                self.latest().expires_at
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OfferStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    offers: BTreeMap<String, Offer>,
+    #[serde(default)]
+    next_offer_id: u64,
+}
+
+impl OfferStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<OfferStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            OfferStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Persists, logging instead of failing: an escrow opened on acceptance
+    /// must not be opened again because the offer file could not be written.
+    pub fn persist(&self) {
+        if let Err(e) = self.save() {
+            tracing::warn!("Failed to persist offers: {}", e);
+        }
+    }
+
+    pub fn next_id(&mut self) -> String {
+        self.next_offer_id += 1;
+        format!("offer-{}", self.next_offer_id)
+    }
+
+    pub fn insert(&mut self, offer: Offer) -> Result<()> {
+        self.offers.insert(offer.offer_id.clone(), offer);
+        self.save()
+    }
+
+    /// An offer on the given listing.
+    pub fn get(&self, property_id: &str, offer_id: &str) -> Result<&Offer> {
+        self.offers
+            .get(offer_id)
+            .filter(|o| o.property_id == property_id)
+            .ok_or_else(|| {
+                anyhow::anyhow!("Offer {} not found on listing {}", offer_id, property_id)
+            })
+    }
+
+    pub fn get_mut(&mut self, property_id: &str, offer_id: &str) -> Result<&mut Offer> {
+        self.offers
+            .get_mut(offer_id)
+            .filter(|o| o.property_id == property_id)
+            .ok_or_else(|| {
+                anyhow::anyhow!("Offer {} not found on listing {}", offer_id, property_id)
+            })
+    }
+
+    /// Every offer, on any listing.
+    pub fn all(&self) -> impl Iterator<Item = &Offer> {
+        self.offers.values()
+    }
+
+    /// Offers on a listing, oldest first.
+    pub fn for_listing(&self, property_id: &str) -> Vec<&Offer> {
+        let mut offers: Vec<&Offer> = self
+            .offers
+            .values()
+            .filter(|o| o.property_id == property_id)
+            .collect();
+        offers.sort_by_key(|o| o.created_at);
+        offers
+    }
+
+    /// Escrows opened by accepted offers on a listing.
+    pub fn accepted_escrows(&self, property_id: &str) -> Vec<&str> {
+        self.offers
+            .values()
+            .filter(|o| o.property_id == property_id && o.status == OfferStatus::Accepted)
+            .filter_map(|o| o.escrow_account_id.as_deref())
+            .collect()
+    }
+
+    /// Closes the other open offers on a listing once one is accepted.
+    pub fn supersede(&mut self, property_id: &str, accepted_offer_id: &str) {
+        for offer in self.offers.values_mut().filter(|o| {
+            o.property_id == property_id
+                && o.offer_id != accepted_offer_id
+                && o.status == OfferStatus::Open
+        }) {
+            offer.close(
+                OfferStatus::Superseded,
+                Some(format!("offer {} was accepted", accepted_offer_id)),
+            );
+        }
+    }
+
+    /// Closes open offers whose latest terms expired by `now`. Returns how
+    /// many were closed.
+    pub fn expire(&mut self, now: i64) -> usize {
+        let mut expired = 0;
+        for offer in self
+            .offers
+            .values_mut()
+            .filter(|o| o.status == OfferStatus::Open && now >= o.latest().expires_at)
+        {
+            let reason = format!("{} did not respond in time", offer.awaiting().as_str());
+            offer.close(OfferStatus::Expired, Some(reason));
+            expired += 1;
+        }
+        expired
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    SubmitOffer {
+        property_id: String,
+        input: OfferInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.submit_offer(&op.property_id, op.input, op.api_key.as_deref());
+
+    CounterOffer {
+        property_id: String,
+        offer_id: String,
+        input: CounterInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| {
+        client.counter_offer(
+            &op.property_id,
+            &op.offer_id,
+            op.input,
+            op.api_key.as_deref(),
+        )
+    };
+
+    AcceptOffer {
+        property_id: String,
+        offer_id: String,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.accept_offer(&op.property_id, &op.offer_id, op.api_key.as_deref()).await;
+
+    RejectOffer {
+        property_id: String,
+        offer_id: String,
+        input: OfferResponseInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| {
+        client.reject_offer(
+            &op.property_id,
+            &op.offer_id,
+            op.input,
+            op.api_key.as_deref(),
+        )
+    };
+
+    WithdrawOffer {
+        property_id: String,
+        offer_id: String,
+        input: OfferResponseInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| {
+        client.withdraw_offer(
+            &op.property_id,
+            &op.offer_id,
+            op.input,
+            op.api_key.as_deref(),
+        )
+    };
+
+    GetOffer {
+        property_id: String,
+        offer_id: String,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.get_offer(&op.property_id, &op.offer_id, op.api_key.as_deref());
+
+    ListOffers {
+        property_id: String,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.list_offers(&op.property_id, op.api_key.as_deref())
+}
+
+impl MidenClientWrapper {
+    /// True while an accepted offer's escrow on the listing is unsettled.
+    pub(crate) fn listing_under_contract(&self, property_id: &str) -> bool {
+        self.offers
+            .accepted_escrows(property_id)
+            .into_iter()
+            .any(|escrow_hex| {
+                self.records.escrows.get(escrow_hex).is_some_and(|r| {
+                    matches!(r.status, EscrowStatus::Created | EscrowStatus::Funded)
+                })
+            })
+    }
+
+    /// Expiry for a new round of terms.
+    fn offer_expires_at(&self, now: i64, expires_in_secs: Option<u64>) -> i64 {
+        let secs = expires_in_secs.unwrap_or(self.config.offer_expiry.as_secs());
+        now + secs as i64
+    }
+
+    /// Loads an open offer for an action and checks that the caller acts for
+    /// the party entitled to it: the one the offer is waiting on, or with
+    /// `by_proposer` the one that proposed the latest terms. Offers whose terms
+    /// have expired are closed first.
+    fn offer_for_action(
+        &mut self,
+        property_id: &str,
+        offer_id: &str,
+        by_proposer: bool,
+        api_key: Option<&str>,
+    ) -> Result<(Offer, Party)> {
+        let now = chrono::Utc::now().timestamp();
+        let offer = self.offers.get(property_id, offer_id)?;
+        if offer.status == OfferStatus::Open && now >= offer.latest().expires_at {
+            self.offers.expire(now);
+            self.offers.persist();
+        }
+
+        let offer = self.offers.get(property_id, offer_id)?;
+        offer.ensure_open(now)?;
+        let party = if by_proposer {
+            offer.latest().by
+        } else {
+            offer.awaiting()
+        };
+        self.authorize_account(api_key, offer.party_account(party), party.as_str())?;
+
+        Ok((offer.clone(), party))
+    }
+
+    /// A buyer submits an offer on a listing. Returns the offer.
+    pub fn submit_offer(
+        &mut self,
+        property_id: &str,
+        input: OfferInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let seller_hex = self
+            .records
+            .properties
+            .get(property_id)
+            .map(|p| p.owner_account_id.to_lowercase())
+            .ok_or_else(|| anyhow::anyhow!("Listing {} not found", property_id))?;
+
+        let buyer_hex = self.account_hex(&input.buyer_account_id)?;
+        if buyer_hex.eq_ignore_ascii_case(&seller_hex) {
+            return Err(anyhow::anyhow!(
+                "The owner cannot make an offer on their own listing"
+            ));
+        }
+        self.authorize_account(api_key, &buyer_hex, "buyer")?;
+        if self.confidential_listings.is_confidential(property_id)
+            && !self
+                .confidential_listings
+                .has_active_grant(property_id, &buyer_hex)
+        {
+            return Err(EscrowAuthError::Forbidden(format!(
+                "listing {} is confidential; request a disclosure first",
+                property_id
+            ))
+            .into());
+        }
+
+        if self.listing_under_contract(property_id) {
+            return Err(
+                InvalidTransition(format!("listing {} is under contract", property_id)).into(),
+            );
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let offer = Offer {
+            offer_id: self.offers.next_id(),
+            property_id: property_id.to_string(),
+            seller_account_id: seller_hex,
+            buyer_account_id: buyer_hex,
+            status: OfferStatus::Open,
+            rounds: vec![OfferRound {
+                round: 1,
+                by: Party::Buyer,
+                amount: input.amount,
+                message: input.message,
+                proposed_at: now,
+                expires_at: self.offer_expires_at(now, input.expires_in_secs),
+            }],
+            escrow_account_id: None,
+            closing_reason: None,
+            created_at: now,
+            updated_at: now,
+            closed_at: None,
+        };
+        tracing::info!(
+            "Offer {} on {}: {} offered by {}",
+            offer.offer_id,
+            property_id,
+            input.amount,
+            offer.buyer_account_id
+        );
+
+        self.offers.insert(offer.clone())?;
+        Ok(serde_json::json!(offer))
+    }
+
+    /// The party the offer is waiting on proposes new terms instead.
+    pub fn counter_offer(
+        &mut self,
+        property_id: &str,
+        offer_id: &str,
+        input: CounterInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let (_, party) = self.offer_for_action(property_id, offer_id, false, api_key)?;
+
+        let now = chrono::Utc::now().timestamp();
+        let expires_at = self.offer_expires_at(now, input.expires_in_secs);
+        let offer = self.offers.get_mut(property_id, offer_id)?;
+        let round = offer.latest().round + 1;
+        offer.rounds.push(OfferRound {
+            round,
+            by: party,
+            amount: input.amount,
+            message: input.message,
+            proposed_at: now,
+            expires_at,
+        });
+        offer.updated_at = now;
+        tracing::info!(
+            "Offer {} on {}: {} countered with {} (round {})",
+            offer_id,
+            property_id,
+            party.as_str(),
+            input.amount,
+            round
+        );
+
+        let offer = offer.clone();
+        self.offers.save()?;
+        Ok(serde_json::json!(offer))
+    }
+
+    /// The party the offer is waiting on accepts the latest terms, which opens
+    /// the escrow for the agreed amount.
+    pub async fn accept_offer(
+        &mut self,
+        property_id: &str,
+        offer_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let (offer, party) = self.offer_for_action(property_id, offer_id, false, api_key)?;
+
+        let owner = self
+            .records
+            .properties
+            .get(property_id)
+            .map(|p| p.owner_account_id.clone())
+            .unwrap_or_default();
+        if !owner.eq_ignore_ascii_case(&offer.seller_account_id) {
+            return Err(InvalidTransition(format!(
+                "listing {} changed hands since offer {} was made",
+                property_id, offer_id
+            ))
+            .into());
+        }
+        if self.listing_under_contract(property_id)
+            || self.auctions.has_open_auction(property_id)
+            || self.installments.has_open_plan(property_id)
+        {
+            return Err(InvalidTransition(format!(
+                "listing {} is under contract or being sold otherwise",
+                property_id
+            ))
+            .into());
+        }
+
+        let amount = offer.latest().amount;
+        let escrow = self
+            .create_escrow(
+                &offer.buyer_account_id,
+                &offer.seller_account_id,
+                amount,
+                Some(property_id),
+                api_key,
+            )
+            .await?;
+        let escrow_hex = account_id_to_hex(escrow.escrow_account_id);
+
+        let offer = self.offers.get_mut(property_id, offer_id)?;
+        offer.escrow_account_id = Some(escrow_hex.clone());
+        offer.close(
+            OfferStatus::Accepted,
+            Some(format!(
+                "{} accepted {}; escrow {} opened",
+                party.as_str(),
+                amount,
+                escrow_hex
+            )),
+        );
+        let offer = offer.clone();
+        self.offers.supersede(property_id, offer_id);
+        self.offers.persist();
+
+        Ok(serde_json::json!(offer))
+    }
+
+    /// The party the offer is waiting on turns it down.
+    pub fn reject_offer(
+        &mut self,
+        property_id: &str,
+        offer_id: &str,
+        input: OfferResponseInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        self.offer_for_action(property_id, offer_id, false, api_key)?;
+
+        let offer = self.offers.get_mut(property_id, offer_id)?;
+        offer.close(OfferStatus::Rejected, input.reason);
+        let offer = offer.clone();
+        self.offers.save()?;
+        Ok(serde_json::json!(offer))
+    }
+
+    /// The party that proposed the latest terms takes them back.
+    pub fn withdraw_offer(
+        &mut self,
+        property_id: &str,
+        offer_id: &str,
+        input: OfferResponseInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        self.offer_for_action(property_id, offer_id, true, api_key)?;
+
+        let offer = self.offers.get_mut(property_id, offer_id)?;
+        offer.close(OfferStatus::Withdrawn, input.reason);
+        let offer = offer.clone();
+        self.offers.save()?;
+        Ok(serde_json::json!(offer))
+    }
+
+    /// Scheduler job: closes offers whose terms expired. Returns how many.
+    pub fn run_offer_expiry(&mut self) -> Result<usize> {
+        let expired = self.offers.expire(chrono::Utc::now().timestamp());
+        if expired > 0 {
+            self.offers.save()?;
+        }
+        Ok(expired)
+    }
+
+    /// Whether the caller may see the offer: always on a public listing; on a
+    /// confidential one only the owner, an arbiter or the offer's buyer.
+    fn may_view_offer(&self, offer: &Offer, api_key: Option<&str>) -> bool {
+        !self
+            .confidential_listings
+            .is_confidential(&offer.property_id)
+            || self.is_listing_owner(&offer.property_id, api_key)
+            || self.is_key_for(api_key, &offer.buyer_account_id)
+    }
+
+    pub fn get_offer(
+        &self,
+        property_id: &str,
+        offer_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let offer = self.offers.get(property_id, offer_id)?;
+        if !self.may_view_offer(offer, api_key) {
+            return Err(EscrowAuthError::Forbidden(format!(
+                "offer {} is on a confidential listing",
+                offer_id
+            ))
+            .into());
+        }
+        Ok(serde_json::json!(offer))
+    }
+
+    /// Offers on a listing; on a confidential listing, those the caller may see.
+    pub fn list_offers(
+        &self,
+        property_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        if !self.records.properties.contains_key(property_id) {
+            return Err(anyhow::anyhow!("Listing {} not found", property_id));
+        }
+        let offers: Vec<_> = self
+            .offers
+            .for_listing(property_id)
+            .into_iter()
+            .filter(|o| self.may_view_offer(o, api_key))
+            .collect();
+        Ok(serde_json::json!(offers))
+    }
+}
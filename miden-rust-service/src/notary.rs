@@ -0,0 +1,356 @@
+// src/notary.rs
+//
+// Notarization of document hashes
+//
+// A notarization commits the SHA-256 of a document (e.g. a closing statement)
+// on-chain so the document can later be shown to have existed no later than
+// the block the commitment landed in.
+//
+// The commitment is a public, asset-less P2ID note from the notary account
+// (NOTARY_ACCOUNT, one of the service's wallets) to itself, whose serial number
+// is derived from the document hash:
+//   serial_num = RPO256("obscura-notarization" || document_hash bytes)
+// The note ID therefore depends only on the notary account and the document
+// hash, so verification recomputes it rather than trusting the stored record,
+// and a document can be notarized only once.
+//
+// The note's inclusion in a block is the timestamp; consuming the note later
+// (e.g. when notes are consumed into the notary account) does not undo it.
+// The block reference is filled in once the note is committed, which may be a
+// block or two after the notarization request returns.
+
+use anyhow::Result;
+use miden_client::{
+    account::AccountId,
+    crypto::Rpo256,
+    note::{
+        build_p2id_recipient, Note, NoteAssets, NoteExecutionHint, NoteId, NoteMetadata, NoteTag,
+        NoteType,
+    },
+    transaction::{OutputNote, TransactionRequestBuilder},
+    Felt, Word,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    account_id_to_hex,
+    data_subjects::{erase, ErasedField},
+    read_cache::Touched,
+    MidenClientWrapper,
+};
+
+const NOTARIZATION_DOMAIN: &[u8] = b"obscura-notarization";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotarizationInput {
+    /// SHA-256 of the document (hex)
+    pub document_hash: String,
+    /// Free-form description, e.g. "Closing statement, 12 Main St"
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub property_id: Option<String>,
+}
+
+/// Block a commitment note was included in.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BlockReference {
+    pub block_num: u32,
+    /// Index of the note in the block's note tree
+    pub note_index: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notarization {
+    pub document_hash: String,
+    pub label: Option<String>,
+    pub property_id: Option<String>,
+    pub notary_account_id: String,
+    pub note_id: String,
+    pub tx_id: String,
+    /// Key ID of the principal that requested it (when auth is enforced)
+    pub requested_by: Option<u64>,
+    pub notarized_at: i64,
+    pub block: Option<BlockReference>,
+    pub committed_at: Option<i64>,
+}
+
+/// Normalizes a hex SHA-256 to lowercase without prefix.
+pub fn normalize_document_hash(document_hash: &str) -> Result<String> {
+    let hash = document_hash.trim().trim_start_matches("0x").to_lowercase();
+    if hex::decode(&hash).ok().map(|bytes| bytes.len()) != Some(32) {
+        return Err(anyhow::anyhow!(
+            "document_hash must be a hex-encoded SHA-256 (32 bytes)"
+        ));
+    }
+    Ok(hash)
+}
+
+/// Serial number of the commitment note for a (normalized) document hash.
+pub fn commitment_serial_num(document_hash: &str) -> Result<Word> {
+    let mut preimage = NOTARIZATION_DOMAIN.to_vec();
+    preimage.extend(hex::decode(document_hash)?);
+    Ok(Rpo256::hash(&preimage))
+}
+
+/// Builds the commitment note for a document hash.
+pub fn commitment_note(notary: AccountId, document_hash: &str) -> Result<Note> {
+    let recipient = build_p2id_recipient(notary, commitment_serial_num(document_hash)?)?;
+    let metadata = NoteMetadata::new(
+        notary,
+        NoteType::Public,
+        NoteTag::from_account_id(notary),
+        NoteExecutionHint::always(),
+        Felt::new(0),
+    )?;
+    Ok(Note::new(NoteAssets::default(), metadata, recipient))
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct NotaryStore {
+    #[serde(skip)]
+    path: PathBuf,
+    /// Keyed by normalized document hash
+    #[serde(default)]
+    notarizations: BTreeMap<String, Notarization>,
+}
+
+impl NotaryStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<NotaryStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            NotaryStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, document_hash: &str) -> Option<&Notarization> {
+        self.notarizations.get(document_hash)
+    }
+
+    pub fn list(&self, property_id: Option<&str>) -> Vec<&Notarization> {
+        let mut notarizations: Vec<_> = self
+            .notarizations
+            .values()
+            .filter(|n| {
+                property_id
+                    .map(|p| n.property_id.as_deref() == Some(p))
+                    .unwrap_or(true)
+            })
+            .collect();
+        notarizations.sort_by_key(|n| n.notarized_at);
+        notarizations
+    }
+
+    /// Erases the labels of notarizations requested with the given API keys
+    /// (data_subjects.rs). Document hashes are committed on-chain and stay.
+    pub fn erase_personal_fields(&mut self, key_ids: &[u64]) -> Result<Vec<ErasedField>> {
+        let mut erased = Vec::new();
+        for notarization in self
+            .notarizations
+            .values_mut()
+            .filter(|n| n.requested_by.is_some_and(|k| key_ids.contains(&k)))
+        {
+            if let Some(original) = notarization.label.as_mut().and_then(erase) {
+                erased.push(ErasedField::new(
+                    "notarizations",
+                    &notarization.document_hash,
+                    "label",
+                    original,
+                ));
+            }
+        }
+        if !erased.is_empty() {
+            self.save()?;
+        }
+        Ok(erased)
+    }
+
+    pub fn insert(&mut self, notarization: Notarization) -> Result<()> {
+        self.notarizations
+            .insert(notarization.document_hash.clone(), notarization);
+        self.save()
+    }
+
+    /// Records the block the commitment was included in.
+    pub fn mark_committed(
+        &mut self,
+        document_hash: &str,
+        block: BlockReference,
+    ) -> Result<Option<Notarization>> {
+        let Some(notarization) = self.notarizations.get_mut(document_hash) else {
+            return Ok(None);
+        };
+        if notarization.block.is_none() {
+            notarization.block = Some(block);
+            notarization.committed_at = Some(chrono::Utc::now().timestamp());
+        }
+
+        let notarization = notarization.clone();
+        self.save()?;
+        Ok(Some(notarization))
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    Notarize {
+        input: NotarizationInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.notarize(op.input, op.api_key.as_deref()).await;
+
+    VerifyNotarization {
+        document_hash: String,
+    } -> serde_json::Value;
+    |client, op| client.verify_notarization(&op.document_hash).await;
+
+    ListNotarizations {
+        property_id: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.list_notarizations(op.property_id.as_deref())
+}
+
+impl MidenClientWrapper {
+    /// Commits a document hash on-chain from the notary account.
+    ///
+    /// Any principal may notarize when auth is enforced; the requesting key is
+    /// recorded. A document already notarized is refused.
+    pub async fn notarize(
+        &mut self,
+        input: NotarizationInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let requested_by = self.request_principal(api_key)?.map(|p| p.key_id);
+        let document_hash = normalize_document_hash(&input.document_hash)?;
+        if let Some(existing) = self.notary.get(&document_hash) {
+            return Err(anyhow::anyhow!(
+                "Conflict: document {} was already notarized in note {}",
+                document_hash,
+                existing.note_id
+            ));
+        }
+        if let Some(property_id) = input.property_id.as_deref() {
+            if !self.records.properties.contains_key(property_id) {
+                return Err(anyhow::anyhow!(
+                    "Property {} has not been minted",
+                    property_id
+                ));
+            }
+        }
+
+        let notary = self.named_account(&self.config.notary_account)?;
+        let note = commitment_note(notary, &document_hash)?;
+        let note_id = note.id().to_string();
+
+        let op_id = self.records.begin_operation("notarize", &document_hash);
+        let result = self.submit_commitment_note(notary, note).await;
+        self.records
+            .finish_operation(op_id, &result, result.as_ref().ok().cloned());
+        let tx_id = result?;
+
+        self.notary.insert(Notarization {
+            document_hash: document_hash.clone(),
+            label: input.label,
+            property_id: input.property_id,
+            notary_account_id: account_id_to_hex(notary),
+            note_id,
+            tx_id,
+            requested_by,
+            notarized_at: chrono::Utc::now().timestamp(),
+            block: None,
+            committed_at: None,
+        })?;
+        tracing::info!("📜 Notarized document {}", document_hash);
+
+        // Usually not yet committed; picked up by the verification sync if not
+        let notarization = match self.commitment_block(notary, &document_hash).await? {
+            Some(block) => self.notary.mark_committed(&document_hash, block)?,
+            None => self.notary.get(&document_hash).cloned(),
+        };
+        Ok(serde_json::json!(notarization))
+    }
+
+    async fn submit_commitment_note(&mut self, notary: AccountId, note: Note) -> Result<String> {
+        self.sync_state().await?;
+
+        let transaction_request = TransactionRequestBuilder::new()
+            .own_output_notes(vec![OutputNote::Full(note)])
+            .build()?;
+        let transaction_id = self
+            .client
+            .submit_new_transaction(notary, transaction_request)
+            .await?;
+
+        self.sync_state().await?;
+        Ok(transaction_id.to_string())
+    }
+
+    /// Block the commitment note for `document_hash` was included in, if it
+    /// has been committed. The note ID is recomputed, not read from the store.
+    async fn commitment_block(
+        &mut self,
+        notary: AccountId,
+        document_hash: &str,
+    ) -> Result<Option<BlockReference>> {
+        let note_id: NoteId = commitment_note(notary, document_hash)?.id();
+        let Some(record) = self.client.get_output_note(note_id).await? else {
+            return Ok(None);
+        };
+        Ok(record.inclusion_proof().map(|proof| BlockReference {
+            block_num: proof.location().block_num().as_u32(),
+            note_index: proof.location().node_index_in_block(),
+        }))
+    }
+
+    /// Checks that a document's commitment exists on-chain.
+    ///
+    /// Syncs first, so a notarization committed since the request returned is
+    /// picked up and its block recorded.
+    pub async fn verify_notarization(&mut self, document_hash: &str) -> Result<serde_json::Value> {
+        let document_hash = normalize_document_hash(document_hash)?;
+        let notary = match self.notary.get(&document_hash) {
+            Some(n) => AccountId::from_hex(&n.notary_account_id)?,
+            None => self.named_account(&self.config.notary_account)?,
+        };
+
+        let sync_summary = self.sync_state().await?;
+        let block = self.commitment_block(notary, &document_hash).await?;
+        let notarization = match block {
+            Some(block) => self.notary.mark_committed(&document_hash, block)?,
+            None => self.notary.get(&document_hash).cloned(),
+        };
+
+        Ok(serde_json::json!({
+            "document_hash": document_hash,
+            "verified": block.is_some(),
+            "note_id": commitment_note(notary, &document_hash)?.id().to_string(),
+            "block": block,
+            "chain_tip": sync_summary.block_num.as_u32(),
+            "notarization": notarization,
+        }))
+    }
+
+    pub fn list_notarizations(&self, property_id: Option<&str>) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self.notary.list(property_id)))
+    }
+}
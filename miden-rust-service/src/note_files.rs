@@ -0,0 +1,396 @@
+// src/note_files.rs
+//
+// Note import and export
+//
+// Private notes never reach the node, so whoever is to consume one needs its
+// details from the creator. Notes are passed around as Miden note files, the
+// serialization of NoteFile that `miden export` writes and `miden import`
+// reads, in one of three types (the CLI's --export-type):
+// - id: the note ID only; enough for a public note, fetched from the node
+// - partial: the note details, the block to look for it after and its tag;
+//   for a note that is not yet committed
+// - full: the note and its inclusion proof; the note must be committed
+//
+// The bytes travel as one of three encodings:
+// - file: the raw bytes (a .mno file for the CLI)
+// - hex: "0x" followed by the bytes in hex
+// - base64: standard base64 of the bytes (URL-safe alphabet accepted on import)
+//
+// GET /notes/:note_id/export?type=&encoding= exports a note the client knows
+// of, created by the service or received; with API-key auth enforced the caller
+// must own its sender or be an arbiter. POST /notes/import takes a note file as
+// application/octet-stream, or {"encoding", "data"} as JSON, and adds the note
+// to the client store.
+
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use miden_client::{
+    note::{Note, NoteDetails, NoteFile, NoteId},
+    Deserializable, Serializable,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{account_id_to_hex, escrow::EscrowAuthError, read_cache::Touched, MidenClientWrapper};
+
+/// Filename extension the Miden CLI gives note files
+pub const NOTE_FILE_EXTENSION: &str = "mno";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoteExportType {
+    Id,
+    Partial,
+    #[default]
+    Full,
+}
+
+impl NoteExportType {
+    pub fn of(note_file: &NoteFile) -> Self {
+        match note_file {
+            NoteFile::NoteId(_) => NoteExportType::Id,
+            NoteFile::NoteDetails { .. } => NoteExportType::Partial,
+            NoteFile::NoteWithProof(..) => NoteExportType::Full,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoteEncoding {
+    #[default]
+    File,
+    Hex,
+    Base64,
+}
+
+impl NoteEncoding {
+    /// Encodes a note file's bytes; hex and base64 give ASCII text.
+    pub fn encode(&self, note_file: &NoteFile) -> Vec<u8> {
+        let bytes = note_file.to_bytes();
+        match self {
+            NoteEncoding::File => bytes,
+            NoteEncoding::Hex => format!("0x{}", hex::encode(bytes)).into_bytes(),
+            NoteEncoding::Base64 => general_purpose::STANDARD.encode(bytes).into_bytes(),
+        }
+    }
+
+    pub fn decode(&self, data: &[u8]) -> Result<NoteFile> {
+        let bytes = match self {
+            NoteEncoding::File => data.to_vec(),
+            NoteEncoding::Hex => {
+                let text = std::str::from_utf8(data)?.trim();
+                hex::decode(text.strip_prefix("0x").unwrap_or(text))
+                    .map_err(|e| anyhow::anyhow!("Invalid hex note file: {}", e))?
+            }
+            NoteEncoding::Base64 => {
+                let text: String = std::str::from_utf8(data)?
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .collect();
+                general_purpose::STANDARD
+                    .decode(&text)
+                    .or_else(|_| general_purpose::URL_SAFE.decode(&text))
+                    .or_else(|_| general_purpose::URL_SAFE_NO_PAD.decode(&text))
+                    .map_err(|e| anyhow::anyhow!("Invalid base64 note file: {}", e))?
+            }
+        };
+        NoteFile::read_from_bytes(&bytes)
+            .map_err(|e| anyhow::anyhow!("Not a Miden note file: {}", e))
+    }
+}
+
+/// Export options (query string of GET /notes/:note_id/export)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NoteExportQuery {
+    #[serde(default, rename = "type")]
+    pub export_type: NoteExportType,
+    #[serde(default)]
+    pub encoding: NoteEncoding,
+}
+
+/// A note file posted as JSON
+#[derive(Debug, Clone, Deserialize)]
+pub struct NoteImportInput {
+    pub encoding: NoteEncoding,
+    pub data: String,
+}
+
+/// An encoded note file with what the HTTP layer needs to serve it.
+#[derive(Debug, Clone)]
+pub struct ExportedNote {
+    pub note_id: String,
+    pub export_type: NoteExportType,
+    pub encoding: NoteEncoding,
+    pub data: Vec<u8>,
+}
+
+impl ExportedNote {
+    pub fn filename(&self) -> String {
+        format!(
+            "{}.{}",
+            self.note_id.trim_start_matches("0x"),
+            NOTE_FILE_EXTENSION
+        )
+    }
+}
+
+/// The ID of the note a file carries.
+pub fn note_file_id(note_file: &NoteFile) -> NoteId {
+    match note_file {
+        NoteFile::NoteId(note_id) => *note_id,
+        NoteFile::NoteDetails { details, .. } => details.id(),
+        NoteFile::NoteWithProof(note, _) => note.id(),
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    ExportNote {
+        note_id: String,
+        query: NoteExportQuery,
+        api_key: Option<String>,
+    } -> ExportedNote;
+    |client, op| client.export_note(&op.note_id, op.query, op.api_key.as_deref()).await;
+
+    ImportNote {
+        note_file: NoteFile,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.import_note(op.note_file, op.api_key.as_deref()).await
+}
+
+impl MidenClientWrapper {
+    /// Builds a note file from the client store. Output notes (created here)
+    /// are looked up first, then input notes.
+    pub async fn export_note(
+        &mut self,
+        note_id: &str,
+        query: NoteExportQuery,
+        api_key: Option<&str>,
+    ) -> Result<ExportedNote> {
+        let id = NoteId::try_from_hex(note_id)
+            .map_err(|e| anyhow::anyhow!("Invalid note ID {}: {}", note_id, e))?;
+
+        let note_file = if let Some(record) = self.client.get_output_note(id).await? {
+            self.authorize_note_export(api_key, Some(record.metadata().sender()), note_id)?;
+            match query.export_type {
+                NoteExportType::Id => NoteFile::NoteId(id),
+                NoteExportType::Partial => NoteFile::NoteDetails {
+                    details: NoteDetails::try_from(record.clone())?,
+                    after_block_num: record.expected_height(),
+                    tag: Some(record.metadata().tag()),
+                },
+                NoteExportType::Full => {
+                    let proof = record.inclusion_proof().cloned().ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Note {} is not committed yet; export it as partial",
+                            note_id
+                        )
+                    })?;
+                    NoteFile::NoteWithProof(Note::try_from(record)?, proof)
+                }
+            }
+        } else if let Some(record) = self.client.get_input_note(id).await? {
+            let metadata = record.metadata().copied();
+            self.authorize_note_export(api_key, metadata.map(|m| m.sender()), note_id)?;
+            match query.export_type {
+                NoteExportType::Id => NoteFile::NoteId(id),
+                NoteExportType::Partial => NoteFile::NoteDetails {
+                    details: record.details().clone(),
+                    after_block_num: record
+                        .inclusion_proof()
+                        .map(|p| p.location().block_num())
+                        .unwrap_or_default(),
+                    tag: metadata.map(|m| m.tag()),
+                },
+                NoteExportType::Full => {
+                    let proof = record.inclusion_proof().cloned().ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Note {} is not committed yet; export it as partial",
+                            note_id
+                        )
+                    })?;
+                    NoteFile::NoteWithProof(Note::try_from(record)?, proof)
+                }
+            }
+        } else {
+            return Err(anyhow::anyhow!(
+                "Note {} not found in the client store",
+                note_id
+            ));
+        };
+
+        tracing::info!(
+            "Exported note {} ({:?}, {:?})",
+            note_id,
+            query.export_type,
+            query.encoding
+        );
+        Ok(ExportedNote {
+            note_id: id.to_string(),
+            export_type: query.export_type,
+            encoding: query.encoding,
+            data: query.encoding.encode(&note_file),
+        })
+    }
+
+    /// With auth enforced, only the sender's owner or an arbiter may export a
+    /// note's details.
+    fn authorize_note_export(
+        &self,
+        api_key: Option<&str>,
+        sender: Option<miden_client::account::AccountId>,
+        note_id: &str,
+    ) -> Result<()> {
+        let Some(principal) = self.request_principal(api_key)? else {
+            return Ok(());
+        };
+        let owns_sender = sender
+            .map(|s| principal.owns(&account_id_to_hex(s)))
+            .unwrap_or(false);
+        if !(owns_sender || principal.arbiter) {
+            return Err(EscrowAuthError::Forbidden(format!(
+                "API key {} ({}) may not export note {}",
+                principal.key_id, principal.label, note_id
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Adds the note in a note file to the client store.
+    pub async fn import_note(
+        &mut self,
+        note_file: NoteFile,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        self.request_principal(api_key)?;
+
+        let export_type = NoteExportType::of(&note_file);
+        let expected_id = note_file_id(&note_file);
+        let note_id = self
+            .client
+            .import_notes(&[note_file])
+            .await?
+            .into_iter()
+            .next()
+            .unwrap_or(expected_id);
+
+        let record = self.client.get_input_note(note_id).await?;
+        tracing::info!("Imported note {} ({:?})", note_id, export_type);
+        Ok(serde_json::json!({
+            "note_id": note_id.to_string(),
+            "type": export_type,
+            "committed": record.as_ref().map(|r| r.inclusion_proof().is_some()),
+            "consumed": record.as_ref().map(|r| r.is_consumed()),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use miden_client::{
+        account::AccountId,
+        asset::FungibleAsset,
+        crypto::RpoRandomCoin,
+        note::{create_p2id_note, NoteTag, NoteType},
+        Felt,
+    };
+    use miden_objects::{
+        block::BlockNumber,
+        testing::account_id::{
+            ACCOUNT_ID_PUBLIC_FUNGIBLE_FAUCET, ACCOUNT_ID_REGULAR_PRIVATE_ACCOUNT_UPDATABLE_CODE,
+            ACCOUNT_ID_SENDER,
+        },
+    };
+
+    fn private_note() -> Note {
+        let sender = AccountId::try_from(ACCOUNT_ID_SENDER).unwrap();
+        let target =
+            AccountId::try_from(ACCOUNT_ID_REGULAR_PRIVATE_ACCOUNT_UPDATABLE_CODE).unwrap();
+        let faucet = AccountId::try_from(ACCOUNT_ID_PUBLIC_FUNGIBLE_FAUCET).unwrap();
+        let asset = FungibleAsset::new(faucet, 250).unwrap();
+        let mut rng = RpoRandomCoin::new([1u64, 2, 3, 4].map(Felt::new).into());
+        create_p2id_note(
+            sender,
+            target,
+            vec![asset.into()],
+            NoteType::Private,
+            Felt::new(0),
+            &mut rng,
+        )
+        .unwrap()
+    }
+
+    fn note_files() -> Vec<NoteFile> {
+        let note = private_note();
+        vec![
+            NoteFile::NoteId(note.id()),
+            NoteFile::NoteDetails {
+                details: NoteDetails::from(note.clone()),
+                after_block_num: BlockNumber::from(42u32),
+                tag: Some(NoteTag::from_account_id(note.metadata().sender())),
+            },
+        ]
+    }
+
+    #[test]
+    fn every_encoding_round_trips_client_serialization() {
+        for note_file in note_files() {
+            for encoding in [NoteEncoding::File, NoteEncoding::Hex, NoteEncoding::Base64] {
+                let encoded = encoding.encode(&note_file);
+                let decoded = encoding.decode(&encoded).unwrap();
+                assert_eq!(decoded.to_bytes(), note_file.to_bytes());
+                assert_eq!(note_file_id(&decoded), note_file_id(&note_file));
+                assert_eq!(NoteExportType::of(&decoded), NoteExportType::of(&note_file));
+            }
+        }
+    }
+
+    #[test]
+    fn file_encoding_is_the_client_serialization() {
+        for note_file in note_files() {
+            let bytes = NoteEncoding::File.encode(&note_file);
+            assert_eq!(bytes, note_file.to_bytes());
+            let read = NoteFile::read_from_bytes(&bytes).unwrap();
+            assert_eq!(note_file_id(&read), note_file_id(&note_file));
+        }
+    }
+
+    #[test]
+    fn text_encodings_are_lenient_about_framing() {
+        let note_file = note_files().remove(1);
+        let bytes = note_file.to_bytes();
+
+        let bare_hex = hex::encode(&bytes);
+        assert!(NoteEncoding::Hex.decode(bare_hex.as_bytes()).is_ok());
+        let padded = format!("  0x{}\n", bare_hex);
+        assert!(NoteEncoding::Hex.decode(padded.as_bytes()).is_ok());
+
+        let url_safe = general_purpose::URL_SAFE_NO_PAD.encode(&bytes);
+        assert!(NoteEncoding::Base64.decode(url_safe.as_bytes()).is_ok());
+        let wrapped: String = general_purpose::STANDARD
+            .encode(&bytes)
+            .as_bytes()
+            .chunks(64)
+            .map(|line| format!("{}\n", std::str::from_utf8(line).unwrap()))
+            .collect();
+        assert!(NoteEncoding::Base64.decode(wrapped.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn rejects_what_is_not_a_note_file() {
+        assert!(NoteEncoding::File.decode(b"not a note").is_err());
+        assert!(NoteEncoding::Hex.decode(b"0xzz").is_err());
+        assert!(NoteEncoding::Base64.decode(b"%%%").is_err());
+
+        let mut truncated = note_files().remove(1).to_bytes();
+        truncated.truncate(truncated.len() / 2);
+        assert!(NoteEncoding::File.decode(&truncated).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+// src/note_inspection.rs
+//
+// Note script inspection
+//
+// Anyone can send a note to a public account, and consuming a note runs its
+// script in the consuming account's transaction. Before the service consumes
+// whatever sits in an inbox, every note is inspected: its script root is
+// looked up in a whitelist of known scripts.
+//
+// Known scripts are the standard notes of the protocol (P2ID, P2IDE - the
+// reclaimable and timelocked successor of P2IDR - and SWAP) plus the service's
+// own custom scripts, given as NOTE_SCRIPT_WHITELIST=name=0xroot,... The
+// service itself only sends standard notes today, so that list starts empty.
+//
+// The classification is part of every note listing (GET
+// /get-consumable-notes). Notes with unknown scripts are quarantined
+// (note_quarantine.rs): consuming an account's whole inbox (POST
+// /consume-note, the recovery sweep, treasury collection) leaves them alone and
+// reports them as refused, unless CONSUME_UNKNOWN_NOTE_SCRIPTS is set. Notes
+// the service consumes by ID (escrow release) were created by the service and
+// are not filtered.
+
+use anyhow::Result;
+use miden_client::note::WellKnownNote;
+use miden_client::store::InputNoteRecord;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use crate::{account_id_to_hex, MidenClientWrapper};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptClass {
+    P2id,
+    P2ide,
+    Swap,
+    /// A script of the service, from NOTE_SCRIPT_WHITELIST
+    Custom,
+    Unknown,
+}
+
+impl ScriptClass {
+    pub fn known(&self) -> bool {
+        *self != ScriptClass::Unknown
+    }
+}
+
+/// Inspection of a single note.
+#[derive(Debug, Clone, Serialize)]
+pub struct NoteInspection {
+    pub note_id: String,
+    pub script_root: String,
+    pub class: ScriptClass,
+    /// Whitelist name of a custom script
+    pub script_name: Option<String>,
+    pub sender: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NoteScriptPolicy {
+    /// Custom script roots (lowercase hex) by name
+    pub custom: BTreeMap<String, String>,
+    /// Consume notes with unknown scripts along with the rest
+    pub consume_unknown: bool,
+}
+
+/// Parses NOTE_SCRIPT_WHITELIST: comma-separated `name=0xroot` entries.
+pub fn parse_script_whitelist(list: &str) -> Result<BTreeMap<String, String>> {
+    let mut custom = BTreeMap::new();
+    for entry in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (name, root) = entry.split_once('=').ok_or_else(|| {
+            anyhow::anyhow!("NOTE_SCRIPT_WHITELIST entry is not name=root: {}", entry)
+        })?;
+        let root = root.trim().to_lowercase();
+        let digits = root.strip_prefix("0x").unwrap_or(&root);
+        if digits.len() != 64 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow::anyhow!(
+                "NOTE_SCRIPT_WHITELIST root of {} is not a 32-byte hex word: {}",
+                name.trim(),
+                root
+            ));
+        }
+        custom.insert(format!("0x{}", digits), name.trim().to_string());
+    }
+    Ok(custom)
+}
+
+/// Script roots of the standard notes.
+fn well_known_roots() -> &'static BTreeMap<String, ScriptClass> {
+    static ROOTS: OnceLock<BTreeMap<String, ScriptClass>> = OnceLock::new();
+    ROOTS.get_or_init(|| {
+        [
+            (WellKnownNote::P2ID, ScriptClass::P2id),
+            (WellKnownNote::P2IDE, ScriptClass::P2ide),
+            (WellKnownNote::SWAP, ScriptClass::Swap),
+        ]
+        .into_iter()
+        .map(|(note, class)| (note.script_root().to_hex().to_lowercase(), class))
+        .collect()
+    })
+}
+
+impl NoteScriptPolicy {
+    /// Class of a script root, and its whitelist name for custom scripts.
+    pub fn classify(&self, script_root: &str) -> (ScriptClass, Option<String>) {
+        let root = script_root.to_lowercase();
+        if let Some(class) = well_known_roots().get(&root) {
+            return (*class, None);
+        }
+        match self.custom.get(&root) {
+            Some(name) => (ScriptClass::Custom, Some(name.clone())),
+            None => (ScriptClass::Unknown, None),
+        }
+    }
+}
+
+// ============================================================================
+// CLIENT INTEGRATION
+// ============================================================================
+
+impl MidenClientWrapper {
+    pub(crate) fn inspect_note(&self, note: &InputNoteRecord) -> NoteInspection {
+        let script_root = note.details().script().root().to_hex();
+        let (class, script_name) = self.config.note_scripts.classify(&script_root);
+        NoteInspection {
+            note_id: note.id().to_string(),
+            script_root,
+            class,
+            script_name,
+            sender: note.metadata().map(|m| account_id_to_hex(m.sender())),
+        }
+    }
+}
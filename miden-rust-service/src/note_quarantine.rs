@@ -0,0 +1,321 @@
+// src/note_quarantine.rs
+//
+// Spam-note filtering and quarantine
+//
+// Anyone can send notes to a public account, and dust or junk notes bloat its
+// note listing and every sweep that consumes its inbox. Each consumable note is
+// screened against these rules:
+// - its sender is on NOTE_SENDER_DENYLIST
+// - it carries no non-fungible asset and less than NOTE_MIN_ASSET_AMOUNT of
+//   fungible tokens in total (dust; notes with no assets at all by default)
+// - its script is unknown (note_inspection.rs), unless
+//   CONSUME_UNKNOWN_NOTE_SCRIPTS is set
+// Senders on NOTE_SENDER_ALLOWLIST skip the dust and script rules.
+//
+// A note breaking a rule is quarantined: it is left out of GET
+// /get-consumable-notes, the recovery scan and sweep, treasury collection and
+// POST /consume-note, and recorded with its reasons in NOTE_QUARANTINE_PATH the
+// first time it is seen. The chain still holds it; nothing is rejected.
+//
+// GET /notes/quarantine lists quarantined notes (?account_id=, and
+// ?include_released=true for released ones too). POST
+// /notes/quarantine/:note_id/release lets a note through whatever the rules
+// say; it is consumed with the next inbox of its account. Both need an admin
+// API key.
+
+use anyhow::Result;
+use miden_client::{asset::Asset, store::InputNoteRecord};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
+
+use crate::{
+    note_inspection::{NoteInspection, ScriptClass},
+    read_cache::Touched,
+    MidenClientWrapper,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct SpamPolicy {
+    /// Least fungible amount a note without non-fungible assets must carry
+    pub min_asset_amount: u64,
+    /// Senders (lowercase hex) exempt from the dust and script rules
+    pub sender_allowlist: BTreeSet<String>,
+    /// Senders (lowercase hex) whose notes are always quarantined
+    pub sender_denylist: BTreeSet<String>,
+}
+
+/// Parses a comma-separated list of hex account IDs.
+pub fn parse_sender_list(name: &str, list: &str) -> Result<BTreeSet<String>> {
+    let mut senders = BTreeSet::new();
+    for entry in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        if !entry.starts_with("0x") {
+            return Err(anyhow::anyhow!(
+                "{} entry is not a hex account ID: {}",
+                name,
+                entry
+            ));
+        }
+        senders.insert(entry.to_lowercase());
+    }
+    Ok(senders)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum QuarantineReason {
+    DeniedSender { sender: String },
+    Dust { amount: u64, minimum: u64 },
+    UnknownScript { script_root: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantinedNote {
+    pub note_id: String,
+    /// Account the note was found for
+    pub account_id: String,
+    pub sender: Option<String>,
+    pub script_root: String,
+    pub script: ScriptClass,
+    /// Fungible amount carried, all faucets together
+    pub fungible_amount: u64,
+    pub non_fungible_assets: usize,
+    pub reasons: Vec<QuarantineReason>,
+    pub quarantined_at: i64,
+    pub released_at: Option<i64>,
+    /// Key ID of the admin that released it
+    pub released_by: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct QuarantineQuery {
+    pub account_id: Option<String>,
+    #[serde(default)]
+    pub include_released: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct NoteQuarantine {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    notes: BTreeMap<String, QuarantinedNote>,
+}
+
+impl NoteQuarantine {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut quarantine = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<NoteQuarantine>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            NoteQuarantine::default()
+        };
+        quarantine.path = path;
+
+        Ok(quarantine)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn is_released(&self, note_id: &str) -> bool {
+        self.notes
+            .get(note_id)
+            .is_some_and(|note| note.released_at.is_some())
+    }
+
+    /// Records a note the first time it is quarantined.
+    pub fn hold(&mut self, note: QuarantinedNote) -> Result<()> {
+        if self.notes.contains_key(&note.note_id) {
+            return Ok(());
+        }
+        tracing::warn!(
+            "🚧 Quarantined note {} for {}: {:?}",
+            note.note_id,
+            note.account_id,
+            note.reasons
+        );
+        self.notes.insert(note.note_id.clone(), note);
+        self.save()
+    }
+
+    pub fn list(&self, query: &QuarantineQuery) -> Vec<QuarantinedNote> {
+        self.notes
+            .values()
+            .filter(|note| query.include_released || note.released_at.is_none())
+            .filter(|note| {
+                query
+                    .account_id
+                    .as_ref()
+                    .is_none_or(|account| &note.account_id == account)
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub fn release(&mut self, note_id: &str, released_by: u64) -> Result<QuarantinedNote> {
+        let note = self
+            .notes
+            .get_mut(note_id)
+            .ok_or_else(|| anyhow::anyhow!("Note {} is not quarantined", note_id))?;
+        if note.released_at.is_some() {
+            return Err(anyhow::anyhow!(
+                "Conflict: note {} was already released",
+                note_id
+            ));
+        }
+        note.released_at = Some(chrono::Utc::now().timestamp());
+        note.released_by = Some(released_by);
+        let released = note.clone();
+        self.save()?;
+        Ok(released)
+    }
+}
+
+// ============================================================================
+// CLIENT INTEGRATION
+// ============================================================================
+
+crate::operations! {
+    ListQuarantinedNotes {
+        query: QuarantineQuery,
+        api_key: Option<String>,
+    } -> Vec<QuarantinedNote>;
+    |client, op| client.list_quarantined_notes(op.query, op.api_key.as_deref());
+
+    /// Lets a quarantined note through; listings of its account change.
+    ReleaseQuarantinedNote {
+        note_id: String,
+        api_key: Option<String>,
+    } -> QuarantinedNote;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.release_quarantined_note(&op.note_id, op.api_key.as_deref())
+}
+
+impl MidenClientWrapper {
+    /// Rules a note breaks; empty if it may be listed and consumed.
+    fn quarantine_reasons(
+        &self,
+        note: &InputNoteRecord,
+        inspection: &NoteInspection,
+    ) -> Vec<QuarantineReason> {
+        if self.note_quarantine.is_released(&inspection.note_id) {
+            return Vec::new();
+        }
+        let policy = &self.config.spam_policy;
+        let sender = inspection.sender.as_deref().unwrap_or_default();
+        if policy.sender_denylist.contains(sender) {
+            return vec![QuarantineReason::DeniedSender {
+                sender: sender.to_string(),
+            }];
+        }
+        if policy.sender_allowlist.contains(sender) {
+            return Vec::new();
+        }
+
+        let mut reasons = Vec::new();
+        let (amount, non_fungible) = carried(note);
+        if non_fungible == 0 && amount < policy.min_asset_amount {
+            reasons.push(QuarantineReason::Dust {
+                amount,
+                minimum: policy.min_asset_amount,
+            });
+        }
+        if !inspection.class.known() && !self.config.note_scripts.consume_unknown {
+            reasons.push(QuarantineReason::UnknownScript {
+                script_root: inspection.script_root.clone(),
+            });
+        }
+        reasons
+    }
+
+    /// Whether a note may be listed and consumed, without recording it.
+    pub(crate) fn admissible(&self, note: &InputNoteRecord) -> bool {
+        let inspection = self.inspect_note(note);
+        self.quarantine_reasons(note, &inspection).is_empty()
+    }
+
+    /// Screens a consumable note of `account_hex`: the inspection, and
+    /// whether the note passed. Notes that did not are quarantined.
+    pub(crate) fn screen_note(
+        &mut self,
+        account_hex: &str,
+        note: &InputNoteRecord,
+    ) -> (NoteInspection, bool) {
+        let inspection = self.inspect_note(note);
+        let reasons = self.quarantine_reasons(note, &inspection);
+        if reasons.is_empty() {
+            return (inspection, true);
+        }
+
+        let (fungible_amount, non_fungible_assets) = carried(note);
+        let held = QuarantinedNote {
+            note_id: inspection.note_id.clone(),
+            account_id: account_hex.to_string(),
+            sender: inspection.sender.clone(),
+            script_root: inspection.script_root.clone(),
+            script: inspection.class,
+            fungible_amount,
+            non_fungible_assets,
+            reasons,
+            quarantined_at: chrono::Utc::now().timestamp(),
+            released_at: None,
+            released_by: None,
+        };
+        if let Err(e) = self.note_quarantine.hold(held) {
+            tracing::warn!("⚠️  Failed to record quarantined note: {}", e);
+        }
+        (inspection, false)
+    }
+
+    pub fn list_quarantined_notes(
+        &self,
+        query: QuarantineQuery,
+        api_key: Option<&str>,
+    ) -> Result<Vec<QuarantinedNote>> {
+        self.admin_principal(api_key, "the note quarantine")?;
+        let query = QuarantineQuery {
+            account_id: query
+                .account_id
+                .map(|account| self.account_hex(&account))
+                .transpose()?,
+            ..query
+        };
+        Ok(self.note_quarantine.list(&query))
+    }
+
+    pub fn release_quarantined_note(
+        &mut self,
+        note_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<QuarantinedNote> {
+        let released_by = self
+            .admin_principal(api_key, "releasing quarantined notes")?
+            .key_id;
+        let released = self.note_quarantine.release(note_id, released_by)?;
+        tracing::info!("Released quarantined note {}", note_id);
+        Ok(released)
+    }
+}
+
+/// Fungible amount (all faucets) and number of non-fungible assets of a note.
+fn carried(note: &InputNoteRecord) -> (u64, usize) {
+    let mut amount = 0u64;
+    let mut non_fungible = 0;
+    for asset in note.assets().iter() {
+        match asset {
+            Asset::Fungible(fungible) => amount = amount.saturating_add(fungible.amount()),
+            Asset::NonFungible(_) => non_fungible += 1,
+        }
+    }
+    (amount, non_fungible)
+}
@@ -0,0 +1,354 @@
+// src/offline_queue.rs
+//
+// Offline mode
+//
+// When the node cannot be reached, mutating requests (mint, transfer, escrow
+// fund/release/refund) fail right away. A request sent with the header
+// `X-Offline-Queue: true` opts in to offline mode instead: if it fails because
+// the node is unreachable, the operation is queued durably (OFFLINE_QUEUE_PATH)
+// and the request answers 202 with the offline job ID.
+//
+// Offline jobs are replayed from the scheduler tick (scheduler.rs), strictly
+// in the order they were queued: the oldest job runs first, and replay stops
+// at the first job that still cannot reach the node, so a later job never
+// overtakes an earlier one. While the active RPC endpoint is unhealthy (see
+// rpc_failover.rs) nothing is attempted. A job ends as
+// - succeeded: the replay went through
+// - failed: the replay failed for another reason (e.g. the escrow moved on
+//   meanwhile); replay goes on with the next job
+// - cancelled: by DELETE /jobs/:job_id
+//
+// Unlike retry jobs (retry_queue.rs), offline jobs have no deadline: they
+// wait for the node as long as it takes. A connectivity failure of an
+// opted-in request goes to the offline queue, not the retry queue. Their
+// state is served by GET /offline-jobs.
+//
+// Jobs hold operations that were already authorized when they were queued;
+// replays run without an API key.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, future::Future, path::PathBuf};
+
+use crate::{retry_queue::RetryOperation, MidenClientWrapper};
+
+/// Header a request opts in to offline mode with.
+pub const OFFLINE_QUEUE_HEADER: &str = "x-offline-queue";
+
+/// Error fragments (lowercase) of failures to reach the node.
+const CONNECTIVITY_MARKERS: &[&str] = &[
+    "timeout",
+    "timed out",
+    "unavailable",
+    "connection",
+    "transport",
+    "unreachable",
+    "dns error",
+];
+
+tokio::task_local! {
+    static OPT_IN: bool;
+}
+
+/// Whether the request being handled opted in to offline mode.
+pub fn opted_in() -> bool {
+    OPT_IN.try_with(|opt_in| *opt_in).unwrap_or(false)
+}
+
+/// Runs `future` with `opt_in` as the current request's offline mode choice.
+pub async fn with_opt_in<F: Future>(opt_in: bool, future: F) -> F::Output {
+    OPT_IN.scope(opt_in, future).await
+}
+
+/// Whether a failed submission failed because the node could not be reached.
+pub fn is_connectivity_error(error: &str) -> bool {
+    let error = error.to_lowercase();
+    CONNECTIVITY_MARKERS.iter().any(|m| error.contains(m))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OfflineStatus {
+    Queued,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineJob {
+    pub job_id: String,
+    /// Replay order
+    pub sequence: u64,
+    pub operation: RetryOperation,
+    pub status: OfflineStatus,
+    pub queued_at: i64,
+    /// Failure of the original request
+    pub error: String,
+    /// Replays attempted (the original request not included)
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub last_attempt_at: Option<i64>,
+    /// Result of the replay that went through (transaction ID, note ID)
+    pub result: Option<serde_json::Value>,
+    pub finished_at: Option<i64>,
+}
+
+impl OfflineJob {
+    fn finish(&mut self, status: OfflineStatus) {
+        self.status = status;
+        self.finished_at = Some(chrono::Utc::now().timestamp());
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OfflineQueue {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    jobs: BTreeMap<String, OfflineJob>,
+    #[serde(default)]
+    next_sequence: u64,
+}
+
+impl OfflineQueue {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<OfflineQueue>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            OfflineQueue::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Persists, logging instead of failing.
+    pub fn persist(&self) {
+        if let Err(e) = self.save() {
+            tracing::warn!("Failed to persist offline queue: {}", e);
+        }
+    }
+
+    pub fn get(&self, job_id: &str) -> Result<&OfflineJob> {
+        self.jobs
+            .get(job_id)
+            .ok_or_else(|| anyhow::anyhow!("Offline job {} not found", job_id))
+    }
+
+    pub fn get_mut(&mut self, job_id: &str) -> Result<&mut OfflineJob> {
+        self.jobs
+            .get_mut(job_id)
+            .ok_or_else(|| anyhow::anyhow!("Offline job {} not found", job_id))
+    }
+
+    /// Jobs in replay order, optionally only those in one state.
+    pub fn list(&self, status: Option<OfflineStatus>) -> Vec<&OfflineJob> {
+        let mut jobs: Vec<&OfflineJob> = self
+            .jobs
+            .values()
+            .filter(|j| status.is_none_or(|s| j.status == s))
+            .collect();
+        jobs.sort_by_key(|j| j.sequence);
+        jobs
+    }
+
+    /// IDs of the jobs waiting for replay, in replay order.
+    pub fn queued(&self) -> Vec<String> {
+        self.list(Some(OfflineStatus::Queued))
+            .into_iter()
+            .map(|j| j.job_id.clone())
+            .collect()
+    }
+
+    /// Queues `operation` behind every waiting job. Returns the job and its
+    /// position in the queue (1-based).
+    pub fn enqueue(&mut self, operation: RetryOperation, error: &str) -> (OfflineJob, usize) {
+        self.next_sequence += 1;
+        let job = OfflineJob {
+            job_id: format!("offline-{}", self.next_sequence),
+            sequence: self.next_sequence,
+            operation,
+            status: OfflineStatus::Queued,
+            queued_at: chrono::Utc::now().timestamp(),
+            error: error.to_string(),
+            attempts: 0,
+            last_error: None,
+            last_attempt_at: None,
+            result: None,
+            finished_at: None,
+        };
+
+        self.jobs.insert(job.job_id.clone(), job.clone());
+        self.persist();
+        (job, self.queued().len())
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    GetOfflineJob {
+        job_id: String,
+    } -> serde_json::Value;
+    |client, op| client.get_offline_job(&op.job_id);
+
+    ListOfflineJobs {
+        status: Option<OfflineStatus>,
+    } -> serde_json::Value;
+    |client, op| client.list_offline_jobs(op.status)
+}
+
+impl MidenClientWrapper {
+    /// Whether the command being run comes from a request that opted in to
+    /// offline mode.
+    pub fn set_offline_opt_in(&mut self, opt_in: bool) {
+        self.offline_opt_in = opt_in;
+    }
+
+    /// Queues `operation` for replay if the command opted in to offline mode
+    /// and `error` is a connectivity failure. Returns the "Queued offline:"
+    /// error to report instead of `error`.
+    pub(crate) fn queue_offline(
+        &mut self,
+        operation: &RetryOperation,
+        error: &str,
+    ) -> Option<anyhow::Error> {
+        if !self.offline_opt_in || !is_connectivity_error(error) {
+            return None;
+        }
+
+        let description = operation.describe();
+        let (job, position) = self.offline_queue.enqueue(operation.clone(), error);
+        tracing::warn!(
+            "📴 {} failed ({}); queued offline as {} (position {})",
+            description,
+            error,
+            job.job_id,
+            position
+        );
+
+        Some(anyhow::anyhow!(
+            "Queued offline: {}; queued as offline job {} (position {})",
+            error,
+            job.job_id,
+            position
+        ))
+    }
+
+    /// Replays waiting offline jobs in order, stopping at the first one that
+    /// still cannot reach the node. Returns the number of jobs that finished.
+    pub async fn run_offline_queue(&mut self) -> Result<usize> {
+        let queued = self.offline_queue.queued();
+        if queued.is_empty() || !self.rpc.is_healthy(self.rpc.active()) {
+            return Ok(0);
+        }
+        let mut finished = 0;
+
+        for job_id in queued {
+            let operation = self.offline_queue.get(&job_id)?.operation.clone();
+
+            // Cancellable while it runs (DELETE /jobs/:job_id)
+            let token = self.job_cancellations.register(&job_id);
+            let outer = self.cancellation.replace(token.clone());
+            let result = self.attempt_retry(&operation).await;
+            self.cancellation = outer;
+            self.job_cancellations.forget(&job_id);
+
+            let job = self.offline_queue.get_mut(&job_id)?;
+            job.attempts += 1;
+            job.last_attempt_at = Some(chrono::Utc::now().timestamp());
+            let still_offline = match result {
+                Err(e) if token.is_cancelled() => {
+                    job.last_error = Some(e.to_string());
+                    job.finish(OfflineStatus::Cancelled);
+                    tracing::info!("Offline job {} cancelled", job_id);
+                    finished += 1;
+                    false
+                }
+                Ok(value) => {
+                    job.result = Some(value);
+                    job.finish(OfflineStatus::Succeeded);
+                    tracing::info!("✅ Offline job {} replayed", job_id);
+                    finished += 1;
+                    false
+                }
+                Err(e) => {
+                    let error = e.to_string();
+                    let offline = is_connectivity_error(&error);
+                    if !offline {
+                        job.finish(OfflineStatus::Failed);
+                        tracing::warn!("Offline job {} failed: {}", job_id, error);
+                        finished += 1;
+                    }
+                    job.last_error = Some(error);
+                    offline
+                }
+            };
+            self.offline_queue.persist();
+
+            if still_offline {
+                // Keep the order: later jobs wait behind this one
+                break;
+            }
+        }
+
+        Ok(finished)
+    }
+
+    /// Cancels a waiting offline job. Returns the job.
+    pub fn cancel_offline_job(&mut self, job_id: &str) -> Result<serde_json::Value> {
+        let job = self.offline_queue.get_mut(job_id)?;
+        // Already interrupted mid-replay (see run_offline_queue)
+        if job.status == OfflineStatus::Cancelled {
+            return Ok(serde_json::json!(job));
+        }
+        if job.status != OfflineStatus::Queued {
+            return Err(anyhow::anyhow!(
+                "Conflict: offline job {} is {:?}",
+                job_id,
+                job.status
+            ));
+        }
+        job.last_error = Some("Cancelled by request".to_string());
+        job.finish(OfflineStatus::Cancelled);
+        let job = serde_json::json!(job);
+        self.offline_queue.persist();
+        tracing::info!("Offline job {} cancelled", job_id);
+
+        Ok(job)
+    }
+
+    pub fn get_offline_job(&self, job_id: &str) -> Result<serde_json::Value> {
+        let mut job = serde_json::json!(self.offline_queue.get(job_id)?);
+        // Place in the replay order while queued
+        job["position"] = serde_json::json!(self
+            .offline_queue
+            .queued()
+            .iter()
+            .position(|id| id == job_id)
+            .map(|index| index + 1));
+        Ok(job)
+    }
+
+    pub fn list_offline_jobs(&self, status: Option<OfflineStatus>) -> Result<serde_json::Value> {
+        Ok(serde_json::json!({
+            "online": self.rpc.is_healthy(self.rpc.active()),
+            "queued": self.offline_queue.queued().len(),
+            "jobs": self.offline_queue.list(status),
+        }))
+    }
+}
@@ -0,0 +1,993 @@
+// src/order_book.rs
+//
+// Secondary-market share trading
+//
+// A fractionalized property has its shares issued as a fungible token by a
+// faucet of its own. Once the owner opens a market for the property (naming
+// that share faucet), accounts post limit orders to buy or sell shares, priced
+// per share in the service token.
+//
+// Matching runs when an order is posted, with price-time priority: an incoming
+// buy fills against the cheapest resting sells priced at or below its limit
+// (oldest first at equal prices), an incoming sell against the highest resting
+// buys, each trade at the resting order's price. Orders never match an order of
+// the same account. Unfilled quantity rests on the book until filled or
+// cancelled.
+//
+// Each trade settles atomically through the client task with paired notes, as
+// swaps do (swaps.rs): the seller sends a SWAP note offering the shares and
+// requesting quantity * price of the service token, and the buyer consumes it,
+// which takes the shares and pays the seller back in a payback note in the
+// same transaction. Neither side can end up with one leg and not the other.
+// The SWAP note only goes out once the buyer's vault covers the price. Both
+// steps record their tx IDs; a step that fails leaves the trade settling, and
+// the scheduler (scheduler.rs) retries the missing step without repeating the
+// other.
+//
+// An order must be covered when it is posted: a buy by the service token and a
+// sell by shares in the trading account's vault, net of what the account's
+// resting orders and unsettled trades already commit.
+//
+// Posting or cancelling an order needs an API key bound to the trading account
+// (or an arbiter's); posting is the account's consent to settlement.
+
+use anyhow::Result;
+use miden_client::{account::AccountId, asset::FungibleAsset};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    account_id_to_hex,
+    ledger::{JournalKind, Posting},
+    read_cache::Touched,
+    MidenClientWrapper,
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MarketInput {
+    pub property_id: String,
+    /// Hex AccountId of the faucet issuing the property's shares
+    pub share_faucet_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrderInput {
+    /// Account name or hex AccountId
+    pub account_id: String,
+    pub side: Side,
+    /// Number of shares
+    pub quantity: u64,
+    /// Limit price per share, in the service token
+    pub price: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    Open,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Market {
+    pub property_id: String,
+    pub share_faucet_id: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Order {
+    pub order_id: u64,
+    pub property_id: String,
+    pub account_id: String,
+    pub side: Side,
+    pub price: u64,
+    pub quantity: u64,
+    pub filled: u64,
+    pub status: OrderStatus,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Order {
+    pub fn remaining(&self) -> u64 {
+        self.quantity - self.filled
+    }
+
+    pub fn is_resting(&self) -> bool {
+        matches!(
+            self.status,
+            OrderStatus::Open | OrderStatus::PartiallyFilled
+        )
+    }
+
+    /// Whether this order's price is acceptable to an incoming order.
+    fn crosses(&self, incoming: &Order) -> bool {
+        match incoming.side {
+            Side::Buy => self.side == Side::Sell && self.price <= incoming.price,
+            Side::Sell => self.side == Side::Buy && self.price >= incoming.price,
+        }
+    }
+
+    fn fill(&mut self, quantity: u64, now: i64) {
+        self.filled += quantity;
+        self.status = if self.remaining() == 0 {
+            OrderStatus::Filled
+        } else {
+            OrderStatus::PartiallyFilled
+        };
+        self.updated_at = now;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeStatus {
+    Settling,
+    Settled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    pub trade_id: u64,
+    pub property_id: String,
+    pub buy_order_id: u64,
+    pub sell_order_id: u64,
+    pub buyer_account_id: String,
+    pub seller_account_id: String,
+    pub quantity: u64,
+    pub price: u64,
+    pub status: TradeStatus,
+    /// The seller's SWAP note: the shares, for the price
+    #[serde(default)]
+    pub swap_note_id: Option<String>,
+    #[serde(default)]
+    pub swap_tx_id: Option<String>,
+    /// Note paying the price to the seller, created by the fill
+    #[serde(default)]
+    pub payback_note_id: Option<String>,
+    /// The buyer's consumption of the SWAP note
+    #[serde(default)]
+    pub fill_tx_id: Option<String>,
+    pub attempts: u32,
+    /// Last settlement error, cleared once the trade settles
+    pub error: Option<String>,
+    pub matched_at: i64,
+    pub settled_at: Option<i64>,
+}
+
+/// One price level of the book.
+#[derive(Debug, Clone, Serialize)]
+pub struct Level {
+    pub price: u64,
+    pub quantity: u64,
+    pub orders: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OrderBook {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    markets: BTreeMap<String, Market>,
+    #[serde(default)]
+    orders: BTreeMap<u64, Order>,
+    #[serde(default)]
+    trades: BTreeMap<u64, Trade>,
+    #[serde(default)]
+    next_order_id: u64,
+    #[serde(default)]
+    next_trade_id: u64,
+}
+
+impl OrderBook {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut book = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<OrderBook>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            OrderBook::default()
+        };
+        book.path = path;
+
+        Ok(book)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Persists, logging instead of failing: a settlement leg that reached the
+    /// chain must not be repeated because the book could not be written.
+    pub fn persist(&self) {
+        if let Err(e) = self.save() {
+            tracing::warn!("Failed to persist order book: {}", e);
+        }
+    }
+
+    pub fn market(&self, property_id: &str) -> Result<&Market> {
+        self.markets
+            .get(property_id)
+            .ok_or_else(|| anyhow::anyhow!("No market for property {}", property_id))
+    }
+
+    pub fn markets(&self) -> Vec<&Market> {
+        self.markets.values().collect()
+    }
+
+    pub fn open_market(&mut self, property_id: String, share_faucet_id: String) -> Result<Market> {
+        if self.markets.contains_key(&property_id) {
+            return Err(anyhow::anyhow!(
+                "A market for property {} is already open",
+                property_id
+            ));
+        }
+
+        let market = Market {
+            property_id: property_id.clone(),
+            share_faucet_id: share_faucet_id.to_lowercase(),
+            created_at: chrono::Utc::now().timestamp(),
+        };
+        self.markets.insert(property_id, market.clone());
+        self.save()?;
+        Ok(market)
+    }
+
+    pub fn order(&self, property_id: &str, order_id: u64) -> Result<&Order> {
+        self.orders
+            .get(&order_id)
+            .filter(|o| o.property_id == property_id)
+            .ok_or_else(|| {
+                anyhow::anyhow!("Order {} not found in market {}", order_id, property_id)
+            })
+    }
+
+    pub fn trade(&self, trade_id: u64) -> Result<&Trade> {
+        self.trades
+            .get(&trade_id)
+            .ok_or_else(|| anyhow::anyhow!("Trade {} not found", trade_id))
+    }
+
+    pub fn trade_mut(&mut self, trade_id: u64) -> Result<&mut Trade> {
+        self.trades
+            .get_mut(&trade_id)
+            .ok_or_else(|| anyhow::anyhow!("Trade {} not found", trade_id))
+    }
+
+    /// Orders in a market, oldest first, optionally for one account.
+    pub fn orders(&self, property_id: &str, account_id: Option<&str>) -> Vec<&Order> {
+        self.orders
+            .values()
+            .filter(|o| o.property_id == property_id)
+            .filter(|o| account_id.is_none_or(|a| o.account_id.eq_ignore_ascii_case(a)))
+            .collect()
+    }
+
+    /// Trades in a market, oldest first, optionally involving one account.
+    pub fn trades(&self, property_id: &str, account_id: Option<&str>) -> Vec<&Trade> {
+        self.trades
+            .values()
+            .filter(|t| t.property_id == property_id)
+            .filter(|t| {
+                account_id.is_none_or(|a| {
+                    t.buyer_account_id.eq_ignore_ascii_case(a)
+                        || t.seller_account_id.eq_ignore_ascii_case(a)
+                })
+            })
+            .collect()
+    }
+
+    /// What an account's resting orders and unsettled trades commit: service
+    /// tokens across every market for `Side::Buy`, shares of `property_id`
+    /// for `Side::Sell`.
+    pub fn committed(&self, account_id: &str, side: Side, property_id: &str) -> u64 {
+        let in_scope = |market: &str| side == Side::Buy || market == property_id;
+        let orders: u64 = self
+            .orders
+            .values()
+            .filter(|o| o.is_resting() && o.side == side && in_scope(&o.property_id))
+            .filter(|o| o.account_id.eq_ignore_ascii_case(account_id))
+            .map(|o| match side {
+                Side::Buy => o.remaining() * o.price,
+                Side::Sell => o.remaining(),
+            })
+            .sum();
+        let trades: u64 = self
+            .trades
+            .values()
+            .filter(|t| t.status == TradeStatus::Settling && in_scope(&t.property_id))
+            .map(|t| match side {
+                Side::Buy if t.buyer_account_id.eq_ignore_ascii_case(account_id) => {
+                    t.quantity * t.price
+                }
+                // Shares leave the seller with the SWAP note
+                Side::Sell
+                    if t.seller_account_id.eq_ignore_ascii_case(account_id)
+                        && t.swap_note_id.is_none() =>
+                {
+                    t.quantity
+                }
+                _ => 0,
+            })
+            .sum();
+        orders + trades
+    }
+
+    /// Trades with a settlement step still outstanding.
+    pub fn settling(&self) -> Vec<u64> {
+        self.trades
+            .values()
+            .filter(|t| t.status == TradeStatus::Settling)
+            .map(|t| t.trade_id)
+            .collect()
+    }
+
+    /// Resting bids (best first) and asks (best first), aggregated by price.
+    pub fn depth(&self, property_id: &str) -> (Vec<Level>, Vec<Level>) {
+        let mut bids: BTreeMap<u64, Level> = BTreeMap::new();
+        let mut asks: BTreeMap<u64, Level> = BTreeMap::new();
+
+        for order in self
+            .orders
+            .values()
+            .filter(|o| o.property_id == property_id && o.is_resting())
+        {
+            let levels = match order.side {
+                Side::Buy => &mut bids,
+                Side::Sell => &mut asks,
+            };
+            let level = levels.entry(order.price).or_insert(Level {
+                price: order.price,
+                quantity: 0,
+                orders: 0,
+            });
+            level.quantity += order.remaining();
+            level.orders += 1;
+        }
+
+        (
+            bids.into_values().rev().collect(),
+            asks.into_values().collect(),
+        )
+    }
+
+    /// Adds an order and matches it against the book. Returns the order as
+    /// left after matching and the trades it produced.
+    pub fn place(
+        &mut self,
+        property_id: &str,
+        account_id: String,
+        side: Side,
+        quantity: u64,
+        price: u64,
+    ) -> Result<(Order, Vec<Trade>)> {
+        self.market(property_id)?;
+
+        let now = chrono::Utc::now().timestamp();
+        self.next_order_id += 1;
+        let mut incoming = Order {
+            order_id: self.next_order_id,
+            property_id: property_id.to_string(),
+            account_id: account_id.to_lowercase(),
+            side,
+            price,
+            quantity,
+            filled: 0,
+            status: OrderStatus::Open,
+            created_at: now,
+            updated_at: now,
+        };
+
+        // Price-time priority; order IDs increase with time
+        let mut candidates: Vec<(u64, u64)> = self
+            .orders
+            .values()
+            .filter(|o| {
+                o.property_id == property_id
+                    && o.is_resting()
+                    && o.crosses(&incoming)
+                    && !o.account_id.eq_ignore_ascii_case(&incoming.account_id)
+            })
+            .map(|o| (o.price, o.order_id))
+            .collect();
+        match side {
+            Side::Buy => candidates.sort(),
+            Side::Sell => candidates.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1))),
+        }
+
+        let mut trades = Vec::new();
+        for (_, resting_id) in candidates {
+            if incoming.remaining() == 0 {
+                break;
+            }
+            let resting = self
+                .orders
+                .get_mut(&resting_id)
+                .expect("candidate orders come from the book");
+            let quantity = incoming.remaining().min(resting.remaining());
+            resting.fill(quantity, now);
+            incoming.fill(quantity, now);
+
+            let (buy, sell) = match side {
+                Side::Buy => (&incoming, &*resting),
+                Side::Sell => (&*resting, &incoming),
+            };
+            self.next_trade_id += 1;
+            let trade = Trade {
+                trade_id: self.next_trade_id,
+                property_id: property_id.to_string(),
+                buy_order_id: buy.order_id,
+                sell_order_id: sell.order_id,
+                buyer_account_id: buy.account_id.clone(),
+                seller_account_id: sell.account_id.clone(),
+                quantity,
+                price: resting.price,
+                status: TradeStatus::Settling,
+                swap_note_id: None,
+                swap_tx_id: None,
+                payback_note_id: None,
+                fill_tx_id: None,
+                attempts: 0,
+                error: None,
+                matched_at: now,
+                settled_at: None,
+            };
+            self.trades.insert(trade.trade_id, trade.clone());
+            trades.push(trade);
+        }
+
+        self.orders.insert(incoming.order_id, incoming.clone());
+        self.save()?;
+        Ok((incoming, trades))
+    }
+
+    pub fn cancel(&mut self, property_id: &str, order_id: u64) -> Result<Order> {
+        let order = self
+            .orders
+            .get_mut(&order_id)
+            .filter(|o| o.property_id == property_id)
+            .ok_or_else(|| {
+                anyhow::anyhow!("Order {} not found in market {}", order_id, property_id)
+            })?;
+        if !order.is_resting() {
+            return Err(anyhow::anyhow!(
+                "Order {} is {:?} and cannot be cancelled",
+                order_id,
+                order.status
+            ));
+        }
+        order.status = OrderStatus::Cancelled;
+        order.updated_at = chrono::Utc::now().timestamp();
+
+        let order = order.clone();
+        self.save()?;
+        Ok(order)
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    OpenMarket {
+        input: MarketInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.open_market(op.input, op.api_key.as_deref());
+
+    ListMarkets {} -> serde_json::Value;
+    |client, _op| client.list_markets();
+
+    GetMarket {
+        property_id: String,
+    } -> serde_json::Value;
+    |client, op| client.get_market(&op.property_id);
+
+    PlaceOrder {
+        property_id: String,
+        input: OrderInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.place_order(&op.property_id, op.input, op.api_key.as_deref()).await;
+
+    CancelOrder {
+        property_id: String,
+        order_id: u64,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.cancel_order(&op.property_id, op.order_id, op.api_key.as_deref());
+
+    ListOrders {
+        property_id: String,
+        account_id: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.list_orders(&op.property_id, op.account_id.as_deref());
+
+    ListTrades {
+        property_id: String,
+        account_id: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.list_trades(&op.property_id, op.account_id.as_deref())
+}
+
+impl MidenClientWrapper {
+    /// Opens a share market for a property. Needs the owner's API key.
+    pub fn open_market(
+        &mut self,
+        input: MarketInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let owner = self
+            .records
+            .properties
+            .get(&input.property_id)
+            .map(|p| p.owner_account_id.clone())
+            .ok_or_else(|| anyhow::anyhow!("Property {} has not been minted", input.property_id))?;
+        self.authorize_account(api_key, &owner, "owner")?;
+
+        let share_faucet = AccountId::from_hex(&input.share_faucet_id)
+            .map_err(|e| anyhow::anyhow!("Invalid share faucet ID: {}", e))?;
+        if !share_faucet.is_faucet() {
+            return Err(anyhow::anyhow!(
+                "{} is not a faucet account",
+                input.share_faucet_id
+            ));
+        }
+        if Some(share_faucet) == self.faucet_account_id {
+            return Err(anyhow::anyhow!(
+                "Shares must be issued by a faucet other than the service token's"
+            ));
+        }
+
+        let market = self
+            .order_book
+            .open_market(input.property_id, input.share_faucet_id)?;
+        tracing::info!(
+            "Opened share market for {} (shares from {})",
+            market.property_id,
+            market.share_faucet_id
+        );
+
+        Ok(serde_json::json!(market))
+    }
+
+    /// Posts an order, matches it and settles the resulting trades. Returns
+    /// the order and its trades.
+    pub async fn place_order(
+        &mut self,
+        property_id: &str,
+        input: OrderInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let account_hex = self.account_hex(&input.account_id)?;
+        self.authorize_account(api_key, &account_hex, "trader")?;
+        let value = input
+            .quantity
+            .checked_mul(input.price)
+            .ok_or_else(|| anyhow::anyhow!("Order value overflows"))?;
+
+        let (faucet_hex, needed, unit) = match input.side {
+            Side::Buy => {
+                let faucet = self
+                    .faucet_account_id
+                    .ok_or_else(|| anyhow::anyhow!("Faucet not initialized"))?;
+                (account_id_to_hex(faucet), value, "service token(s)")
+            }
+            Side::Sell => {
+                let market = self.order_book.market(property_id)?;
+                (market.share_faucet_id.clone(), input.quantity, "share(s)")
+            }
+        };
+        self.sync_state().await?;
+        let held = self
+            .vault_balance(
+                AccountId::from_hex(&account_hex)?,
+                AccountId::from_hex(&faucet_hex)?,
+            )
+            .await?;
+        let committed = self
+            .order_book
+            .committed(&account_hex, input.side, property_id);
+        let available = held.saturating_sub(committed);
+        if available < needed {
+            return Err(anyhow::anyhow!(
+                "Conflict: account holds {} {} not committed to other orders, {} needed",
+                available,
+                unit,
+                needed
+            ));
+        }
+
+        let (order, trades) = self.order_book.place(
+            property_id,
+            account_hex,
+            input.side,
+            input.quantity,
+            input.price,
+        )?;
+        tracing::info!(
+            "Order {} in {}: {:?} {} @ {} ({} trade(s))",
+            order.order_id,
+            property_id,
+            order.side,
+            order.quantity,
+            order.price,
+            trades.len()
+        );
+
+        for trade in &trades {
+            if let Err(e) = self.settle_trade(trade.trade_id).await {
+                tracing::warn!("Trade {} settlement failed: {}", trade.trade_id, e);
+            }
+        }
+
+        let trades: Vec<&Trade> = trades
+            .iter()
+            .filter_map(|t| self.order_book.trade(t.trade_id).ok())
+            .collect();
+        Ok(serde_json::json!({
+            "order": self.order_book.order(property_id, order.order_id)?,
+            "trades": trades,
+        }))
+    }
+
+    /// Runs the outstanding steps of a trade: the seller's SWAP note, then the
+    /// buyer's fill of it.
+    async fn settle_trade(&mut self, trade_id: u64) -> Result<()> {
+        let trade = self.order_book.trade(trade_id)?.clone();
+        if trade.status == TradeStatus::Settled {
+            return Ok(());
+        }
+        let share_faucet =
+            AccountId::from_hex(&self.order_book.market(&trade.property_id)?.share_faucet_id)?;
+        let service_faucet = self
+            .faucet_account_id
+            .ok_or_else(|| anyhow::anyhow!("Faucet not initialized"))?;
+        let buyer = AccountId::from_hex(&trade.buyer_account_id)?;
+        let seller = AccountId::from_hex(&trade.seller_account_id)?;
+
+        let result = self
+            .submit_trade_swap(&trade, share_faucet, service_faucet, buyer, seller)
+            .await;
+
+        let trade = self.order_book.trade_mut(trade_id)?;
+        trade.attempts += 1;
+        match &result {
+            Ok(()) => {
+                trade.status = TradeStatus::Settled;
+                trade.settled_at = Some(chrono::Utc::now().timestamp());
+                trade.error = None;
+                tracing::info!(
+                    "Trade {} settled: {} share(s) @ {}",
+                    trade_id,
+                    trade.quantity,
+                    trade.price
+                );
+            }
+            Err(e) => trade.error = Some(e.to_string()),
+        }
+        let trade = trade.clone();
+        self.order_book.persist();
+        if result.is_ok() {
+            self.record_trade_basis(&trade);
+        }
+
+        result
+    }
+
+    /// Settles a trade with paired notes: the seller's SWAP note offering the
+    /// shares for the price, consumed by the buyer. Each step is journaled and
+    /// recorded on the trade, so a retry skips what already went through.
+    async fn submit_trade_swap(
+        &mut self,
+        trade: &Trade,
+        share_faucet: AccountId,
+        service_faucet: AccountId,
+        buyer: AccountId,
+        seller: AccountId,
+    ) -> Result<()> {
+        let subject = format!("trade-{}", trade.trade_id);
+        let value = trade.quantity * trade.price;
+
+        let swap_note_id = match &trade.swap_note_id {
+            Some(note_id) => note_id.clone(),
+            None => {
+                // The shares only leave the seller once the buyer can pay
+                self.sync_state().await?;
+                let funds = self.vault_balance(buyer, service_faucet).await?;
+                if funds < value {
+                    return Err(anyhow::anyhow!(
+                        "Buyer holds {} of the {} the trade costs",
+                        funds,
+                        value
+                    ));
+                }
+
+                let op_id = self.records.begin_operation("trade_swap", &subject);
+                let result = self
+                    .submit_swap_offer(
+                        seller,
+                        FungibleAsset::new(share_faucet, trade.quantity)?,
+                        FungibleAsset::new(service_faucet, value)?,
+                    )
+                    .await;
+                let tx_id = result.as_ref().ok().map(|(_, _, tx_id)| tx_id.clone());
+                self.records.finish_operation(op_id, &result, tx_id);
+                let (note_id, payback_note_id, tx_id) = result?;
+                tracing::info!(
+                    "🔁 Swap note {} sent for trade {}. TX: {}",
+                    note_id,
+                    trade.trade_id,
+                    tx_id
+                );
+
+                self.records.expect_note(
+                    &note_id,
+                    &trade.buyer_account_id,
+                    "trade",
+                    Some(tx_id.clone()),
+                );
+                self.records.expect_note(
+                    &payback_note_id,
+                    &trade.seller_account_id,
+                    "trade-payback",
+                    None,
+                );
+                self.post_ledger_entry(
+                    JournalKind::Swap,
+                    Posting::transfer(
+                        &trade.seller_account_id,
+                        &trade.buyer_account_id,
+                        &account_id_to_hex(share_faucet),
+                        trade.quantity,
+                    )
+                    .to_vec(),
+                    &tx_id,
+                    Some(&subject),
+                );
+                let recorded = self.order_book.trade_mut(trade.trade_id)?;
+                recorded.swap_note_id = Some(note_id.clone());
+                recorded.swap_tx_id = Some(tx_id);
+                recorded.payback_note_id = Some(payback_note_id);
+                self.order_book.persist();
+
+                // The buyer can only consume the note once the node has it
+                self.wait_for_propagation().await;
+                note_id
+            }
+        };
+
+        if trade.fill_tx_id.is_none() {
+            let op_id = self.records.begin_operation("trade_fill", &subject);
+            let result = self.submit_swap_consume(buyer, &swap_note_id).await;
+            let tx_id = result.as_ref().ok().cloned();
+            self.records.finish_operation(op_id, &result, tx_id);
+            let tx_id = result?;
+            tracing::info!("🔁 Filled trade {}. TX: {}", trade.trade_id, tx_id);
+
+            self.records
+                .mark_note_consumed(&swap_note_id, Some(tx_id.clone()));
+            self.post_ledger_entry(
+                JournalKind::Swap,
+                Posting::transfer(
+                    &trade.buyer_account_id,
+                    &trade.seller_account_id,
+                    &account_id_to_hex(service_faucet),
+                    value,
+                )
+                .to_vec(),
+                &tx_id,
+                Some(&subject),
+            );
+            self.order_book.trade_mut(trade.trade_id)?.fill_tx_id = Some(tx_id);
+            self.order_book.persist();
+        }
+
+        Ok(())
+    }
+
+    /// Cancels the rest of an order. Needs the trading account's API key.
+    pub fn cancel_order(
+        &mut self,
+        property_id: &str,
+        order_id: u64,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let account_hex = self
+            .order_book
+            .order(property_id, order_id)?
+            .account_id
+            .clone();
+        self.authorize_account(api_key, &account_hex, "trader")?;
+
+        let order = self.order_book.cancel(property_id, order_id)?;
+        tracing::info!("Order {} in {} cancelled", order_id, property_id);
+        Ok(serde_json::json!(order))
+    }
+
+    /// Scheduler job: retries trades with a settlement step outstanding.
+    /// Returns the number of trades that settled.
+    pub async fn run_trade_settlement(&mut self) -> Result<usize> {
+        let mut settled = 0;
+        for trade_id in self.order_book.settling() {
+            match self.settle_trade(trade_id).await {
+                Ok(()) => settled += 1,
+                Err(e) => tracing::warn!("Trade {} settlement failed: {}", trade_id, e),
+            }
+        }
+        Ok(settled)
+    }
+
+    pub fn list_markets(&self) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self.order_book.markets()))
+    }
+
+    /// Market with its book depth and last trade.
+    pub fn get_market(&self, property_id: &str) -> Result<serde_json::Value> {
+        let market = self.order_book.market(property_id)?;
+        let (bids, asks) = self.order_book.depth(property_id);
+        let last_trade = self.order_book.trades(property_id, None).last().copied();
+
+        Ok(serde_json::json!({
+            "property_id": market.property_id,
+            "share_faucet_id": market.share_faucet_id,
+            "created_at": market.created_at,
+            "bids": bids,
+            "asks": asks,
+            "last_trade": last_trade,
+        }))
+    }
+
+    pub fn list_orders(
+        &self,
+        property_id: &str,
+        account: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        self.order_book.market(property_id)?;
+        let account_hex = account.map(|a| self.account_hex(a)).transpose()?;
+        Ok(serde_json::json!(self
+            .order_book
+            .orders(property_id, account_hex.as_deref())))
+    }
+
+    pub fn list_trades(
+        &self,
+        property_id: &str,
+        account: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        self.order_book.market(property_id)?;
+        let account_hex = account.map(|a| self.account_hex(a)).transpose()?;
+        Ok(serde_json::json!(self
+            .order_book
+            .trades(property_id, account_hex.as_deref())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(name: &str) -> OrderBook {
+        let path =
+            std::env::temp_dir().join(format!("order_book_{}_{}.json", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut book = OrderBook::load(path).unwrap();
+        book.open_market("prop-1".to_string(), "0xFAUCET".to_string())
+            .unwrap();
+        book
+    }
+
+    fn place(book: &mut OrderBook, account: &str, side: Side, quantity: u64, price: u64) -> Order {
+        book.place("prop-1", account.to_string(), side, quantity, price)
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn buy_fills_cheapest_sells_oldest_first_at_their_price() {
+        let mut book = book("buy");
+        let dear = place(&mut book, "0xa", Side::Sell, 3, 12);
+        let cheap_old = place(&mut book, "0xb", Side::Sell, 2, 10);
+        let cheap_new = place(&mut book, "0xc", Side::Sell, 2, 10);
+        place(&mut book, "0xa", Side::Sell, 5, 16);
+
+        let (buy, trades) = book
+            .place("prop-1", "0xd".to_string(), Side::Buy, 5, 15)
+            .unwrap();
+        let fills: Vec<(u64, u64, u64)> = trades
+            .iter()
+            .map(|t| (t.sell_order_id, t.quantity, t.price))
+            .collect();
+        assert_eq!(
+            fills,
+            vec![
+                (cheap_old.order_id, 2, 10),
+                (cheap_new.order_id, 2, 10),
+                (dear.order_id, 1, 12),
+            ]
+        );
+        assert_eq!(buy.status, OrderStatus::Filled);
+        assert!(trades.iter().all(|t| t.buyer_account_id == "0xd"));
+
+        let dear = book.order("prop-1", dear.order_id).unwrap();
+        assert_eq!(dear.status, OrderStatus::PartiallyFilled);
+        assert_eq!(dear.remaining(), 2);
+        let _ = std::fs::remove_file(&book.path);
+    }
+
+    #[test]
+    fn sell_fills_highest_bids_and_rests_the_remainder() {
+        let mut book = book("sell");
+        let low = place(&mut book, "0xa", Side::Buy, 4, 8);
+        let high = place(&mut book, "0xb", Side::Buy, 3, 11);
+
+        let (sell, trades) = book
+            .place("prop-1", "0xc".to_string(), Side::Sell, 10, 9)
+            .unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].buy_order_id, high.order_id);
+        assert_eq!((trades[0].quantity, trades[0].price), (3, 11));
+        assert_eq!(sell.status, OrderStatus::PartiallyFilled);
+        assert_eq!(sell.remaining(), 7);
+
+        // The bid at 8 is below the limit and keeps resting
+        let (bids, asks) = book.depth("prop-1");
+        assert_eq!((bids.len(), bids[0].price, bids[0].quantity), (1, 8, 4));
+        assert_eq!((asks.len(), asks[0].price, asks[0].quantity), (1, 9, 7));
+        assert!(book.order("prop-1", low.order_id).unwrap().is_resting());
+        let _ = std::fs::remove_file(&book.path);
+    }
+
+    #[test]
+    fn orders_skip_their_own_account_and_match_the_next() {
+        let mut book = book("self");
+        let own = place(&mut book, "0xa", Side::Sell, 5, 9);
+        let other = place(&mut book, "0xb", Side::Sell, 2, 10);
+
+        let (buy, trades) = book
+            .place("prop-1", "0xA".to_string(), Side::Buy, 5, 10)
+            .unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].sell_order_id, other.order_id);
+        assert_eq!(buy.status, OrderStatus::PartiallyFilled);
+        assert_eq!(buy.remaining(), 3);
+
+        // The own ask keeps resting; the rest of the bid and the settling
+        // trade both commit tokens
+        assert!(book.order("prop-1", own.order_id).unwrap().is_resting());
+        assert_eq!(book.committed("0xa", Side::Buy, "prop-1"), 3 * 10 + 2 * 10);
+        assert_eq!(book.committed("0xa", Side::Sell, "prop-1"), 5);
+        let _ = std::fs::remove_file(&book.path);
+    }
+
+    #[test]
+    fn cancelled_orders_leave_the_book() {
+        let mut book = book("cancel");
+        let sell = place(&mut book, "0xa", Side::Sell, 5, 10);
+        book.cancel("prop-1", sell.order_id).unwrap();
+
+        let (_, trades) = book
+            .place("prop-1", "0xb".to_string(), Side::Buy, 5, 10)
+            .unwrap();
+        assert!(trades.is_empty());
+        assert!(book.cancel("prop-1", sell.order_id).is_err());
+        let _ = std::fs::remove_file(&book.path);
+    }
+}
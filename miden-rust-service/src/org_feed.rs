@@ -0,0 +1,276 @@
+// src/org_feed.rs
+//
+// Organization activity feed
+//
+// GET /orgs/:org_id/feed lists what happened on an organization's accounts
+// (organizations.rs), newest first, for "what happened today" dashboards:
+// - listing_created: a property now held by one of its accounts was minted
+// - offer_received: a buyer made an offer on one of its listings
+// - escrow_funded: an escrow one of its accounts is party to was funded
+// - transfer_completed: a property title moved to or from one of its accounts
+//
+// The feed keeps no store of its own: each request assembles it from what the
+// service already records — properties, escrows and the operation journal
+// (records.rs), offers (negotiation.rs) and title transfers as entered in the
+// tax ledger (tax.rs) — so it covers everything since those began and reflects
+// the organization's accounts as they are now: an account added to an
+// organization brings its history along.
+//
+// Query: since / until (unix seconds, events in [since, until)), types
+// (comma-separated event types), cursor and limit (default 50, at most 500).
+// Members of the organization and admin keys may read it.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, str::FromStr};
+
+use crate::{escrow::EscrowStatus, records::OperationStatus, tax::AssetKind, MidenClientWrapper};
+
+pub const DEFAULT_FEED_LIMIT: usize = 50;
+pub const MAX_FEED_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedEventType {
+    ListingCreated,
+    OfferReceived,
+    EscrowFunded,
+    TransferCompleted,
+}
+
+impl FromStr for FeedEventType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "listing_created" => Ok(FeedEventType::ListingCreated),
+            "offer_received" => Ok(FeedEventType::OfferReceived),
+            "escrow_funded" => Ok(FeedEventType::EscrowFunded),
+            "transfer_completed" => Ok(FeedEventType::TransferCompleted),
+            other => Err(anyhow::anyhow!("Unknown feed event type: {}", other)),
+        }
+    }
+}
+
+/// One entry of an organization's feed.
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedEvent {
+    pub event_type: FeedEventType,
+    pub occurred_at: i64,
+    /// The organization's account involved
+    pub account_id: String,
+    pub property_id: Option<String>,
+    /// Price, offered amount, escrowed amount or consideration
+    pub amount: Option<u64>,
+    /// Offer ID, escrow account ID or transaction ID
+    pub reference: String,
+    /// The source entry
+    pub detail: serde_json::Value,
+}
+
+/// Query string of GET /orgs/:org_id/feed.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FeedQuery {
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    /// Comma-separated event types; all of them when absent
+    pub types: Option<String>,
+    #[serde(default)]
+    pub cursor: usize,
+    pub limit: Option<usize>,
+}
+
+impl FeedQuery {
+    fn event_types(&self) -> Result<Option<Vec<FeedEventType>>> {
+        self.types
+            .as_deref()
+            .filter(|t| !t.trim().is_empty())
+            .map(|t| {
+                t.split(',')
+                    .filter(|t| !t.trim().is_empty())
+                    .map(FeedEventType::from_str)
+                    .collect()
+            })
+            .transpose()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedPage {
+    pub org_id: u64,
+    /// Events matching the query, across pages
+    pub total: usize,
+    pub events: Vec<FeedEvent>,
+    /// Cursor for the following page; None when this was the last one
+    pub next_cursor: Option<usize>,
+}
+
+/// Orders events newest first; ties keep a stable order across requests.
+fn sort_feed(events: &mut [FeedEvent]) {
+    events.sort_by(|a, b| {
+        b.occurred_at
+            .cmp(&a.occurred_at)
+            .then(a.event_type.cmp(&b.event_type))
+            .then_with(|| a.reference.cmp(&b.reference))
+            .then_with(|| a.account_id.cmp(&b.account_id))
+    });
+}
+
+/// Filters, sorts and pages the assembled events.
+fn page_feed(org_id: u64, mut events: Vec<FeedEvent>, query: &FeedQuery) -> Result<FeedPage> {
+    let types = query.event_types()?;
+    events.retain(|e| {
+        query.since.is_none_or(|since| e.occurred_at >= since)
+            && query.until.is_none_or(|until| e.occurred_at < until)
+            && types
+                .as_ref()
+                .is_none_or(|types| types.contains(&e.event_type))
+    });
+    sort_feed(&mut events);
+
+    let total = events.len();
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_FEED_LIMIT)
+        .clamp(1, MAX_FEED_LIMIT);
+    let events: Vec<FeedEvent> = events.into_iter().skip(query.cursor).take(limit).collect();
+    let end = query.cursor.saturating_add(events.len());
+
+    Ok(FeedPage {
+        org_id,
+        total,
+        next_cursor: (end < total && !events.is_empty()).then_some(end),
+        events,
+    })
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    GetOrganizationFeed {
+        org_id: u64,
+        query: FeedQuery,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| {
+        client
+            .organization_feed(op.org_id, op.query, op.api_key.as_deref())
+            .map(|page| serde_json::json!(page))
+    }
+}
+
+impl MidenClientWrapper {
+    /// A page of an organization's activity feed (see module docs).
+    pub fn organization_feed(
+        &self,
+        org_id: u64,
+        query: FeedQuery,
+        api_key: Option<&str>,
+    ) -> Result<FeedPage> {
+        let org = self.org_member(org_id, api_key)?;
+        let mut events = Vec::new();
+
+        for property in self.records.properties.values() {
+            if org.owns(&property.owner_account_id) {
+                events.push(FeedEvent {
+                    event_type: FeedEventType::ListingCreated,
+                    occurred_at: property.created_at,
+                    account_id: property.owner_account_id.to_lowercase(),
+                    property_id: Some(property.property_id.clone()),
+                    amount: Some(property.price),
+                    reference: property.mint_tx_id.clone(),
+                    detail: serde_json::json!(property),
+                });
+            }
+        }
+
+        for offer in self.offers.all().filter(|o| org.owns(&o.seller_account_id)) {
+            events.push(FeedEvent {
+                event_type: FeedEventType::OfferReceived,
+                occurred_at: offer.created_at,
+                account_id: offer.seller_account_id.to_lowercase(),
+                property_id: Some(offer.property_id.clone()),
+                amount: offer.rounds.first().map(|r| r.amount),
+                reference: offer.offer_id.clone(),
+                detail: serde_json::json!(offer),
+            });
+        }
+
+        // When each escrow was funded, from the operation journal
+        let funded_at: HashMap<String, i64> = self
+            .records
+            .operations
+            .iter()
+            .filter(|op| op.kind == "fund_escrow" && op.status == OperationStatus::Completed)
+            .filter_map(|op| Some((op.subject.to_lowercase(), op.finished_at?)))
+            .collect();
+        for escrow in self.records.escrows.values() {
+            if escrow.fund_tx_id.is_none() || escrow.status == EscrowStatus::Created {
+                continue;
+            }
+            let occurred_at = funded_at
+                .get(&escrow.escrow_account_id.to_lowercase())
+                .copied()
+                .unwrap_or(escrow.updated_at);
+            // An escrow between two of the organization's accounts is listed
+            // once per side
+            for account in [&escrow.buyer_account_id, &escrow.seller_account_id] {
+                if org.owns(account) {
+                    events.push(FeedEvent {
+                        event_type: FeedEventType::EscrowFunded,
+                        occurred_at,
+                        account_id: account.to_lowercase(),
+                        property_id: escrow.property_id.clone(),
+                        amount: Some(escrow.amount),
+                        reference: escrow.escrow_account_id.clone(),
+                        detail: serde_json::json!(escrow),
+                    });
+                }
+            }
+        }
+
+        // A title transfer is a disposal by the previous owner and a lot
+        // acquired by the new one, both sourced "transfer <tx>"
+        let transfer_tx = |source: &str| source.strip_prefix("transfer ").map(str::to_string);
+        let acquired_by: HashMap<String, &str> = self
+            .tax
+            .lots()
+            .filter(|l| l.asset == AssetKind::Property)
+            .filter_map(|l| Some((transfer_tx(&l.source)?, l.account_id.as_str())))
+            .collect();
+        for disposal in self
+            .tax
+            .disposals()
+            .filter(|d| d.asset == AssetKind::Property)
+        {
+            let Some(tx_id) = transfer_tx(&disposal.source) else {
+                continue;
+            };
+            let to = acquired_by.get(&tx_id).copied();
+            let detail = serde_json::json!({
+                "tx_id": tx_id,
+                "property_id": disposal.property_id,
+                "from_account_id": disposal.account_id,
+                "to_account_id": to,
+                "consideration": disposal.proceeds,
+            });
+            for account in std::iter::once(disposal.account_id.as_str()).chain(to) {
+                if org.owns(account) {
+                    events.push(FeedEvent {
+                        event_type: FeedEventType::TransferCompleted,
+                        occurred_at: disposal.disposed_at,
+                        account_id: account.to_lowercase(),
+                        property_id: Some(disposal.property_id.clone()),
+                        amount: Some(disposal.proceeds),
+                        reference: tx_id.clone(),
+                        detail: detail.clone(),
+                    });
+                }
+            }
+        }
+
+        page_feed(org_id, events, &query)
+    }
+}
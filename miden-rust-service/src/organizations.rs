@@ -0,0 +1,630 @@
+// src/organizations.rs
+//
+// Organizations and teams
+//
+// An organization (a brokerage, say) sits above API keys: it owns accounts,
+// and through them the properties those accounts hold and the escrows they
+// are parties to. Its members are API keys, each with a role:
+// - owner: everything a manager can do, plus adding and removing owners and
+//   managers; an organization always keeps at least one owner
+// - manager: adds and removes agents, adds accounts the manager's own key is
+//   bound to, removes accounts
+// - agent: acts for the organization's accounts
+// Every member acts for every account the organization owns. An account
+// belongs to at most one organization.
+//
+// Authorization resolves through membership: the accounts a key may act for
+// are the ones it is bound to plus those of its organizations (see
+// Principal::owns in principals.rs), so every check that asks whether a key
+// owns an account — escrows, liens, leases, allowances, documents, note
+// exports, subscriptions — sees the organization's accounts. The resolution is
+// refreshed whenever an organization changes.
+//
+// Any API key may create an organization (it becomes its owner) with accounts
+// it is bound to itself; admin keys may add any account. Closing agent keys
+// (principals.rs) take no part in organizations, so they never act for an
+// organization's accounts. Revoking a key ends
+// its memberships in effect, since a revoked key no longer authenticates.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{escrow::EscrowAuthError, principals::Principal, MidenClientWrapper};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrgRole {
+    Agent,
+    Manager,
+    Owner,
+}
+
+impl OrgRole {
+    /// Whether a member with this role may add, change or remove members
+    /// with `role`.
+    fn manages(self, role: OrgRole) -> bool {
+        match self {
+            OrgRole::Owner => true,
+            OrgRole::Manager => role == OrgRole::Agent,
+            OrgRole::Agent => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Membership {
+    pub key_id: u64,
+    pub role: OrgRole,
+    pub added_at: i64,
+    /// Key that added the member (None: the creator)
+    pub added_by: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Organization {
+    pub org_id: u64,
+    pub name: String,
+    /// Hex AccountIds the organization owns
+    pub accounts: Vec<String>,
+    /// Members by key ID
+    pub members: BTreeMap<u64, Membership>,
+    pub created_at: i64,
+}
+
+impl Organization {
+    pub fn role_of(&self, key_id: u64) -> Option<OrgRole> {
+        self.members.get(&key_id).map(|m| m.role)
+    }
+
+    pub fn owns(&self, account_hex: &str) -> bool {
+        self.accounts
+            .iter()
+            .any(|a| a.eq_ignore_ascii_case(account_hex))
+    }
+
+    fn owners(&self) -> usize {
+        self.members
+            .values()
+            .filter(|m| m.role == OrgRole::Owner)
+            .count()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrganizationInput {
+    pub name: String,
+    /// Account names ("alice", "bob") or hex AccountIds
+    #[serde(default)]
+    pub accounts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MemberInput {
+    pub key_id: u64,
+    pub role: OrgRole,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MemberRoleInput {
+    pub role: OrgRole,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrgAccountInput {
+    /// Account name ("alice", "bob") or hex AccountId
+    pub account: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OrganizationStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    organizations: BTreeMap<u64, Organization>,
+    #[serde(default)]
+    next_org_id: u64,
+}
+
+impl OrganizationStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<OrganizationStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            OrganizationStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, org_id: u64) -> Result<&Organization> {
+        self.organizations
+            .get(&org_id)
+            .ok_or_else(|| anyhow::anyhow!("Organization {} not found", org_id))
+    }
+
+    fn get_mut(&mut self, org_id: u64) -> Result<&mut Organization> {
+        self.organizations
+            .get_mut(&org_id)
+            .ok_or_else(|| anyhow::anyhow!("Organization {} not found", org_id))
+    }
+
+    /// Organizations `key_id` is a member of, or all of them.
+    pub fn list(&self, key_id: Option<u64>) -> Vec<&Organization> {
+        self.organizations
+            .values()
+            .filter(|o| key_id.is_none_or(|k| o.members.contains_key(&k)))
+            .collect()
+    }
+
+    /// The organization owning an account, if any.
+    pub fn owner_of(&self, account_hex: &str) -> Option<&Organization> {
+        self.organizations.values().find(|o| o.owns(account_hex))
+    }
+
+    /// Accounts each member key may act for through its organizations.
+    pub fn accounts_by_member(&self) -> BTreeMap<u64, Vec<String>> {
+        let mut accounts: BTreeMap<u64, Vec<String>> = BTreeMap::new();
+        for org in self.organizations.values() {
+            for key_id in org.members.keys() {
+                accounts
+                    .entry(*key_id)
+                    .or_default()
+                    .extend(org.accounts.iter().cloned());
+            }
+        }
+        accounts
+    }
+
+    fn check_unowned(&self, account_hex: &str) -> Result<()> {
+        match self.owner_of(account_hex) {
+            Some(org) => Err(anyhow::anyhow!(
+                "Conflict: account {} already belongs to organization {} ({})",
+                account_hex,
+                org.org_id,
+                org.name
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub fn create(
+        &mut self,
+        name: String,
+        accounts: Vec<String>,
+        owner_key_id: u64,
+    ) -> Result<Organization> {
+        if name.trim().is_empty() {
+            return Err(anyhow::anyhow!("Organization name must not be empty"));
+        }
+        for account in &accounts {
+            self.check_unowned(account)?;
+        }
+
+        self.next_org_id += 1;
+        let now = chrono::Utc::now().timestamp();
+        let org = Organization {
+            org_id: self.next_org_id,
+            name: name.trim().to_string(),
+            accounts,
+            members: BTreeMap::from([(
+                owner_key_id,
+                Membership {
+                    key_id: owner_key_id,
+                    role: OrgRole::Owner,
+                    added_at: now,
+                    added_by: None,
+                },
+            )]),
+            created_at: now,
+        };
+
+        self.organizations.insert(org.org_id, org.clone());
+        self.save()?;
+        Ok(org)
+    }
+
+    /// Adds a member or changes its role.
+    pub fn set_member(
+        &mut self,
+        org_id: u64,
+        key_id: u64,
+        role: OrgRole,
+        added_by: u64,
+    ) -> Result<Organization> {
+        let org = self.get_mut(org_id)?;
+        let previous = org.role_of(key_id);
+        if previous == Some(OrgRole::Owner) && role != OrgRole::Owner && org.owners() == 1 {
+            return Err(anyhow::anyhow!(
+                "Conflict: key {} is the last owner of organization {}",
+                key_id,
+                org_id
+            ));
+        }
+        let member = org.members.entry(key_id).or_insert(Membership {
+            key_id,
+            role,
+            added_at: chrono::Utc::now().timestamp(),
+            added_by: Some(added_by),
+        });
+        member.role = role;
+
+        let org = org.clone();
+        self.save()?;
+        Ok(org)
+    }
+
+    pub fn remove_member(&mut self, org_id: u64, key_id: u64) -> Result<Organization> {
+        let org = self.get_mut(org_id)?;
+        match org.role_of(key_id) {
+            None => {
+                return Err(anyhow::anyhow!(
+                    "Key {} is not a member of organization {}",
+                    key_id,
+                    org_id
+                ))
+            }
+            Some(OrgRole::Owner) if org.owners() == 1 => {
+                return Err(anyhow::anyhow!(
+                    "Conflict: key {} is the last owner of organization {}",
+                    key_id,
+                    org_id
+                ))
+            }
+            Some(_) => {}
+        }
+        org.members.remove(&key_id);
+
+        let org = org.clone();
+        self.save()?;
+        Ok(org)
+    }
+
+    pub fn add_account(&mut self, org_id: u64, account_hex: String) -> Result<Organization> {
+        self.get(org_id)?;
+        self.check_unowned(&account_hex)?;
+        let org = self.get_mut(org_id)?;
+        org.accounts.push(account_hex);
+
+        let org = org.clone();
+        self.save()?;
+        Ok(org)
+    }
+
+    pub fn remove_account(&mut self, org_id: u64, account_hex: &str) -> Result<Organization> {
+        let org = self.get_mut(org_id)?;
+        if !org.owns(account_hex) {
+            return Err(anyhow::anyhow!(
+                "Account {} does not belong to organization {}",
+                account_hex,
+                org_id
+            ));
+        }
+        org.accounts
+            .retain(|a| !a.eq_ignore_ascii_case(account_hex));
+
+        let org = org.clone();
+        self.save()?;
+        Ok(org)
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    CreateOrganization {
+        input: OrganizationInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.create_organization(op.input, op.api_key.as_deref());
+
+    ListOrganizations {
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.list_organizations(op.api_key.as_deref());
+
+    GetOrganization {
+        org_id: u64,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.get_organization(op.org_id, op.api_key.as_deref());
+
+    SetOrgMember {
+        org_id: u64,
+        input: MemberInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.set_org_member(op.org_id, op.input, op.api_key.as_deref());
+
+    RemoveOrgMember {
+        org_id: u64,
+        key_id: u64,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.remove_org_member(op.org_id, op.key_id, op.api_key.as_deref());
+
+    AddOrgAccount {
+        org_id: u64,
+        input: OrgAccountInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.add_org_account(op.org_id, op.input, op.api_key.as_deref());
+
+    RemoveOrgAccount {
+        org_id: u64,
+        account: String,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.remove_org_account(op.org_id, &op.account, op.api_key.as_deref())
+}
+
+impl MidenClientWrapper {
+    /// The principal behind `api_key`; organizations always need one.
+    fn org_caller(&self, api_key: Option<&str>) -> Result<Principal> {
+        let key = api_key.ok_or_else(|| {
+            EscrowAuthError::Unauthenticated(
+                "X-API-Key header is required for organizations".into(),
+            )
+        })?;
+        let principal = self
+            .principals
+            .authenticate(key)
+            .ok_or_else(|| EscrowAuthError::Unauthenticated("Invalid or revoked API key".into()))?;
+        if principal.closing_agent {
+            return Err(EscrowAuthError::Forbidden(format!(
+                "closing agent key {} may not use organizations",
+                principal.key_id
+            ))
+            .into());
+        }
+        Ok(principal.clone())
+    }
+
+    /// Checks that the caller's role in `org_id` (or the admin role) allows
+    /// managing members with `role`. Returns the caller.
+    fn org_manager(&self, org_id: u64, api_key: Option<&str>, role: OrgRole) -> Result<Principal> {
+        let caller = self.org_caller(api_key)?;
+        let org = self.organizations.get(org_id)?;
+        if caller.admin || org.role_of(caller.key_id).is_some_and(|r| r.manages(role)) {
+            return Ok(caller);
+        }
+        Err(EscrowAuthError::Forbidden(format!(
+            "API key {} may not manage {:?} members of organization {}",
+            caller.key_id, role, org_id
+        ))
+        .into())
+    }
+
+    /// Checks that the caller may add `account_hex` to an organization: it is
+    /// bound to the account itself, or holds the admin role.
+    fn check_account_contribution(&self, caller: &Principal, account_hex: &str) -> Result<()> {
+        if caller.admin || caller.owns_directly(account_hex) {
+            return Ok(());
+        }
+        Err(EscrowAuthError::Forbidden(format!(
+            "API key {} is not bound to account {}",
+            caller.key_id, account_hex
+        ))
+        .into())
+    }
+
+    /// Hands the organizations' accounts to the principals (see module docs).
+    pub(crate) fn refresh_org_accounts(&mut self) {
+        self.principals
+            .attach_org_accounts(self.organizations.accounts_by_member());
+    }
+
+    /// An organization with the properties and escrows its accounts hold.
+    fn org_view(&self, org: &Organization) -> serde_json::Value {
+        let properties: Vec<&String> = self
+            .records
+            .properties
+            .iter()
+            .filter(|(_, p)| org.owns(&p.owner_account_id))
+            .map(|(id, _)| id)
+            .collect();
+        let escrows: Vec<&String> = self
+            .records
+            .escrows
+            .iter()
+            .filter(|(_, e)| org.owns(&e.buyer_account_id) || org.owns(&e.seller_account_id))
+            .map(|(id, _)| id)
+            .collect();
+
+        let mut view = serde_json::json!(org);
+        view["properties"] = serde_json::json!(properties);
+        view["escrows"] = serde_json::json!(escrows);
+        view
+    }
+
+    pub fn create_organization(
+        &mut self,
+        input: OrganizationInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let caller = self.org_caller(api_key)?;
+        let accounts = input
+            .accounts
+            .iter()
+            .map(|account| self.account_hex(account))
+            .collect::<Result<Vec<_>>>()?;
+        for account in &accounts {
+            self.check_account_contribution(&caller, account)?;
+        }
+
+        let org = self
+            .organizations
+            .create(input.name, accounts, caller.key_id)?;
+        self.refresh_org_accounts();
+        tracing::info!(
+            "🏢 Organization {} ({}) created by key {}",
+            org.org_id,
+            org.name,
+            caller.key_id
+        );
+
+        Ok(self.org_view(&org))
+    }
+
+    /// Organizations of the caller (all of them for admin keys).
+    pub fn list_organizations(&self, api_key: Option<&str>) -> Result<serde_json::Value> {
+        let caller = self.org_caller(api_key)?;
+        let key_id = (!caller.admin).then_some(caller.key_id);
+        Ok(serde_json::json!(self
+            .organizations
+            .list(key_id)
+            .into_iter()
+            .map(|org| self.org_view(org))
+            .collect::<Vec<_>>()))
+    }
+
+    /// An organization the caller is a member of (any organization for admin
+    /// keys).
+    pub(crate) fn org_member(&self, org_id: u64, api_key: Option<&str>) -> Result<&Organization> {
+        let caller = self.org_caller(api_key)?;
+        let org = self.organizations.get(org_id)?;
+        if !caller.admin && org.role_of(caller.key_id).is_none() {
+            return Err(EscrowAuthError::Forbidden(format!(
+                "API key {} is not a member of organization {}",
+                caller.key_id, org_id
+            ))
+            .into());
+        }
+        Ok(org)
+    }
+
+    /// An organization, for its members and admin keys.
+    pub fn get_organization(
+        &self,
+        org_id: u64,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let org = self.org_member(org_id, api_key)?;
+        Ok(self.org_view(org))
+    }
+
+    pub fn set_org_member(
+        &mut self,
+        org_id: u64,
+        input: MemberInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let caller = self.org_manager(org_id, api_key, input.role)?;
+        // Changing a role needs the right to manage the current one too
+        if let Some(current) = self.organizations.get(org_id)?.role_of(input.key_id) {
+            self.org_manager(org_id, api_key, current)?;
+        }
+        if !self.principals.is_active(input.key_id) {
+            return Err(anyhow::anyhow!("API key {} not found", input.key_id));
+        }
+        if self
+            .principals
+            .get(input.key_id)
+            .is_some_and(|p| p.closing_agent)
+        {
+            return Err(EscrowAuthError::Forbidden(format!(
+                "closing agent key {} may not join organizations",
+                input.key_id
+            ))
+            .into());
+        }
+
+        let org = self
+            .organizations
+            .set_member(org_id, input.key_id, input.role, caller.key_id)?;
+        self.refresh_org_accounts();
+        tracing::info!(
+            "🏢 Key {} is now {:?} of organization {} (by key {})",
+            input.key_id,
+            input.role,
+            org_id,
+            caller.key_id
+        );
+
+        Ok(self.org_view(&org))
+    }
+
+    pub fn remove_org_member(
+        &mut self,
+        org_id: u64,
+        key_id: u64,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let role = self
+            .organizations
+            .get(org_id)?
+            .role_of(key_id)
+            .unwrap_or(OrgRole::Agent);
+        let caller = self.org_manager(org_id, api_key, role)?;
+
+        let org = self.organizations.remove_member(org_id, key_id)?;
+        self.refresh_org_accounts();
+        tracing::info!(
+            "🏢 Key {} removed from organization {} (by key {})",
+            key_id,
+            org_id,
+            caller.key_id
+        );
+
+        Ok(self.org_view(&org))
+    }
+
+    pub fn add_org_account(
+        &mut self,
+        org_id: u64,
+        input: OrgAccountInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let caller = self.org_manager(org_id, api_key, OrgRole::Agent)?;
+        let account_hex = self.account_hex(&input.account)?;
+        self.check_account_contribution(&caller, &account_hex)?;
+
+        let org = self
+            .organizations
+            .add_account(org_id, account_hex.clone())?;
+        self.refresh_org_accounts();
+        tracing::info!(
+            "🏢 Account {} added to organization {} (by key {})",
+            account_hex,
+            org_id,
+            caller.key_id
+        );
+
+        Ok(self.org_view(&org))
+    }
+
+    pub fn remove_org_account(
+        &mut self,
+        org_id: u64,
+        account: &str,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let caller = self.org_manager(org_id, api_key, OrgRole::Agent)?;
+        let account_hex = self.account_hex(account)?;
+
+        let org = self.organizations.remove_account(org_id, &account_hex)?;
+        self.refresh_org_accounts();
+        tracing::info!(
+            "🏢 Account {} removed from organization {} (by key {})",
+            account_hex,
+            org_id,
+            caller.key_id
+        );
+
+        Ok(self.org_view(&org))
+    }
+}
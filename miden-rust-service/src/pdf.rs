@@ -0,0 +1,172 @@
+// src/pdf.rs
+//
+// Plain-text PDFs
+//
+// Lays out text on A4 pages in Courier, one of the fonts every PDF reader
+// ships, so nothing is embedded and lines wrap at a fixed number of characters.
+// Used for generated documents (documents.rs). Nothing time-dependent is
+// written, so the same text always gives the same bytes (and hash).
+//
+// Text is encoded as WinAnsi; characters outside Latin-1 print as '?'.
+
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+const FONT_SIZE: f32 = 10.0;
+const TITLE_SIZE: f32 = 14.0;
+const LEADING: f32 = 14.0;
+/// Courier glyphs are 0.6 em wide: (595 - 2 * 56) / 6
+pub const LINE_CHARS: usize = 80;
+/// Body lines per page, the footer line kept free
+const PAGE_LINES: usize = ((PAGE_HEIGHT - 2.0 * MARGIN) / LEADING) as usize - 2;
+/// Lines the title takes on the first page
+const TITLE_LINES: usize = 3;
+
+const FONT: Name = Name(b"F1");
+const TITLE_FONT: Name = Name(b"F2");
+
+/// Renders `body` under `title` as a PDF.
+pub fn text_pdf(title: &str, body: &str) -> Vec<u8> {
+    let lines = wrap(body, LINE_CHARS);
+
+    let mut pages: Vec<&[String]> = Vec::new();
+    let mut rest = lines.as_slice();
+    let mut room = PAGE_LINES - TITLE_LINES;
+    loop {
+        let (page, remaining) = rest.split_at(rest.len().min(room));
+        pages.push(page);
+        rest = remaining;
+        room = PAGE_LINES;
+        if rest.is_empty() {
+            break;
+        }
+    }
+
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let font_id = Ref::new(3);
+    let title_font_id = Ref::new(4);
+    let info_id = Ref::new(5);
+    let page_ids: Vec<Ref> = (0..pages.len())
+        .map(|i| Ref::new(6 + 2 * i as i32))
+        .collect();
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().copied())
+        .count(pages.len() as i32);
+    pdf.document_info(info_id)
+        .title(TextStr(title))
+        .producer(TextStr("Obscura"));
+    pdf.type1_font(font_id)
+        .base_font(Name(b"Courier"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+    pdf.type1_font(title_font_id)
+        .base_font(Name(b"Courier-Bold"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+
+    let total = pages.len();
+    for (index, (page_lines, page_id)) in pages.into_iter().zip(&page_ids).enumerate() {
+        let content_id = Ref::new(page_id.get() + 1);
+
+        let mut page = pdf.page(*page_id);
+        page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT))
+            .parent(page_tree_id)
+            .contents(content_id);
+        let mut resources = page.resources();
+        let mut fonts = resources.fonts();
+        fonts.pair(FONT, font_id).pair(TITLE_FONT, title_font_id);
+        fonts.finish();
+        resources.finish();
+        page.finish();
+
+        let mut content = Content::new();
+        let mut top = PAGE_HEIGHT - MARGIN;
+        if index == 0 {
+            content
+                .begin_text()
+                .set_font(TITLE_FONT, TITLE_SIZE)
+                .next_line(MARGIN, top)
+                .show(Str(&encode(title)))
+                .end_text();
+            top -= LEADING * TITLE_LINES as f32;
+        }
+
+        content
+            .begin_text()
+            .set_font(FONT, FONT_SIZE)
+            .set_leading(LEADING)
+            .next_line(MARGIN, top);
+        for line in page_lines {
+            content.show(Str(&encode(line))).next_line_using_leading();
+        }
+        content.end_text();
+
+        let footer = format!("{} - page {} of {}", title, index + 1, total);
+        content
+            .begin_text()
+            .set_font(FONT, FONT_SIZE - 2.0)
+            .next_line(MARGIN, MARGIN / 2.0)
+            .show(Str(&encode(&footer)))
+            .end_text();
+
+        pdf.stream(content_id, &content.finish());
+    }
+
+    pdf.finish()
+}
+
+/// Breaks text into lines of at most `width` characters at spaces, keeping
+/// each line's indentation; words longer than a line are split.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let paragraph = paragraph.replace('\t', "    ");
+        let indent: String = paragraph.chars().take_while(|c| *c == ' ').collect();
+        let indent = if indent.len() * 2 > width {
+            String::new()
+        } else {
+            indent
+        };
+
+        let mut line = indent.clone();
+        for word in paragraph.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            let used = line.chars().count();
+            let gap = usize::from(used > indent.len());
+            if used + gap + word.len() > width && used > indent.len() {
+                lines.push(std::mem::replace(&mut line, indent.clone()));
+            } else if gap == 1 {
+                line.push(' ');
+            }
+            while indent.len() + word.len() > width {
+                let rest = word.split_off(width - indent.len());
+                line.extend(word);
+                lines.push(std::mem::replace(&mut line, indent.clone()));
+                word = rest;
+            }
+            line.extend(word);
+        }
+
+        lines.push(if line.trim().is_empty() {
+            String::new()
+        } else {
+            line
+        });
+    }
+
+    lines
+}
+
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x20..=0x7E | 0xA0..=0xFF => c as u8,
+            _ => b'?',
+        })
+        .collect()
+}
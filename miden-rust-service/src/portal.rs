@@ -0,0 +1,442 @@
+// src/portal.rs
+//
+// Read-only customer portal
+//
+// End customers (buyers, sellers, tenants) get a narrow read-only API of their
+// own, apart from the integrator API and its API keys:
+//
+//   GET /portal/me        the access and its account
+//   GET /portal/balance   the account's fungible balances
+//   GET /portal/notes     notes waiting to be claimed by the account
+//   GET /portal/escrows   escrows the account is buyer or seller of
+//   GET /portal/leases    leases the account is tenant or owner of
+//   GET /portal/payments  the account's movements in the ledger (ledger.rs)
+//
+// Portal calls authenticate with a portal token (X-Portal-Token), bound to
+// exactly one account. Admins issue and revoke them:
+//   GET/POST /admin/portal-access {"account_id", "label"}
+//   DELETE /admin/portal-access/:access_id
+// Tokens look like "obp_<access_id>_<64 hex chars>"; only their SHA-256 is
+// stored. API keys are not accepted on the portal, and portal tokens nowhere
+// else. Users onboarded by email (custodial.rs) present their custodial
+// session token instead, for every read but /portal/me.
+//
+// Every portal read goes through the viewer's `PortalScope`, which keeps only
+// what concerns its account. Payments show the account's own postings and the
+// accounts on the other side of each entry, not the postings among others
+// (fees, commissions) that share the entry. Balances and notes are read from
+// the client store, so they are only available for accounts it tracks.
+
+use anyhow::Result;
+use miden_client::asset::Asset;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    currency::TokenAmount,
+    escrow::EscrowAuthError,
+    ledger::{JournalEntry, JournalKind, Side},
+    reconcile::parse_hex_account_id,
+    records::EscrowRecord,
+    MidenClientWrapper,
+};
+
+const TOKEN_PREFIX: &str = "obp_";
+
+/// Most ledger entries a payment history returns.
+pub const MAX_PAYMENTS: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortalAccess {
+    pub access_id: u64,
+    /// Hex AccountId the token may read
+    pub account_id: String,
+    pub label: String,
+    pub revoked: bool,
+    pub created_at: i64,
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    token_hash: String,
+}
+
+impl PortalAccess {
+    /// Copy without the token hash, for API responses.
+    pub fn public(&self) -> PortalAccess {
+        PortalAccess {
+            token_hash: String::new(),
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PortalAccessInput {
+    /// Account name or hex AccountId
+    pub account_id: String,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PaymentQuery {
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentDirection {
+    In,
+    Out,
+}
+
+/// One ledger entry as seen by one account.
+#[derive(Debug, Clone, Serialize)]
+pub struct PortalPayment {
+    pub entry_id: u64,
+    pub kind: JournalKind,
+    pub direction: PaymentDirection,
+    pub faucet_id: String,
+    pub amount: u64,
+    /// Accounts on the other side of the entry
+    pub counterparties: Vec<String>,
+    pub tx_id: Option<String>,
+    pub reference: Option<String>,
+    pub at: i64,
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PortalAccessStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    accesses: BTreeMap<u64, PortalAccess>,
+    #[serde(default)]
+    next_access_id: u64,
+}
+
+impl PortalAccessStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<PortalAccessStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            PortalAccessStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<PortalAccess> {
+        self.accesses.values().map(PortalAccess::public).collect()
+    }
+
+    /// Issues a token for `account_hex`; returns the access and the plaintext
+    /// token.
+    pub fn issue(&mut self, account_hex: &str, label: String) -> Result<(PortalAccess, String)> {
+        self.next_access_id += 1;
+        let access_id = self.next_access_id;
+        let secret: [u8; 32] = rand::random();
+        let token = format!("{}{}_{}", TOKEN_PREFIX, access_id, hex::encode(secret));
+
+        let access = PortalAccess {
+            access_id,
+            account_id: account_hex.to_lowercase(),
+            label,
+            revoked: false,
+            created_at: chrono::Utc::now().timestamp(),
+            token_hash: hash_token(&token),
+        };
+
+        self.accesses.insert(access_id, access.clone());
+        self.save()?;
+        Ok((access.public(), token))
+    }
+
+    pub fn revoke(&mut self, access_id: u64) -> Result<PortalAccess> {
+        let access = self
+            .accesses
+            .get_mut(&access_id)
+            .ok_or_else(|| anyhow::anyhow!("Portal access {} not found", access_id))?;
+        access.revoked = true;
+
+        let access = access.public();
+        self.save()?;
+        Ok(access)
+    }
+
+    /// Resolves a presented token to its (non-revoked) access.
+    pub fn authenticate(&self, token: &str) -> Option<&PortalAccess> {
+        let access_id = token
+            .strip_prefix(TOKEN_PREFIX)?
+            .split('_')
+            .next()?
+            .parse::<u64>()
+            .ok()?;
+
+        let presented = hash_token(token);
+        self.accesses
+            .get(&access_id)
+            .filter(|a| !a.revoked && a.token_hash == presented)
+    }
+}
+
+/// What a portal viewer may see: everything below is filtered to its account.
+pub struct PortalScope {
+    pub account_id: String,
+}
+
+impl PortalScope {
+    fn is_account(&self, account_hex: &str) -> bool {
+        self.account_id.eq_ignore_ascii_case(account_hex)
+    }
+
+    pub fn sees_escrow(&self, escrow: &EscrowRecord) -> bool {
+        self.is_account(&escrow.buyer_account_id) || self.is_account(&escrow.seller_account_id)
+    }
+
+    /// The account's side of a ledger entry, per asset; empty when its
+    /// postings in it cancel out.
+    pub fn payments(&self, entry: &JournalEntry) -> Vec<PortalPayment> {
+        let mut by_asset: BTreeMap<&str, (u64, u64)> = BTreeMap::new();
+        for posting in entry
+            .postings
+            .iter()
+            .filter(|p| self.is_account(&p.account))
+        {
+            let (debits, credits) = by_asset.entry(&posting.faucet_id).or_default();
+            match posting.side {
+                Side::Debit => *debits += posting.amount,
+                Side::Credit => *credits += posting.amount,
+            }
+        }
+
+        by_asset
+            .into_iter()
+            .filter(|(_, (debits, credits))| debits != credits)
+            .map(|(faucet_id, (debits, credits))| {
+                let direction = if debits > credits {
+                    PaymentDirection::In
+                } else {
+                    PaymentDirection::Out
+                };
+                // The other side: credited accounts of a payment in, debited
+                // ones of a payment out
+                let other_side = match direction {
+                    PaymentDirection::In => Side::Credit,
+                    PaymentDirection::Out => Side::Debit,
+                };
+                let mut counterparties: Vec<String> = entry
+                    .postings
+                    .iter()
+                    .filter(|p| {
+                        p.faucet_id == faucet_id
+                            && p.side == other_side
+                            && !self.is_account(&p.account)
+                    })
+                    .map(|p| p.account.clone())
+                    .collect();
+                counterparties.sort();
+                counterparties.dedup();
+                PortalPayment {
+                    entry_id: entry.entry_id,
+                    kind: entry.kind,
+                    direction,
+                    faucet_id: faucet_id.to_string(),
+                    amount: debits.abs_diff(credits),
+                    counterparties,
+                    tx_id: entry.tx_id.clone(),
+                    reference: entry.reference.clone(),
+                    at: entry.at,
+                }
+            })
+            .collect()
+    }
+}
+
+// ============================================================================
+// CLIENT INTEGRATION
+// ============================================================================
+
+crate::operations! {
+    /// Portal accesses, without token hashes.
+    ListPortalAccess {} -> Vec<PortalAccess>;
+    |client, _op| Ok(client.portal_access.list());
+
+    /// Issues a portal token; the plaintext is only returned here.
+    IssuePortalAccess {
+        input: PortalAccessInput,
+    } -> serde_json::Value;
+    |client, op| client.issue_portal_access(op.input);
+
+    RevokePortalAccess {
+        access_id: u64,
+    } -> PortalAccess;
+    |client, op| client.portal_access.revoke(op.access_id);
+
+    /// The viewer's access.
+    GetPortalAccess {
+        token: Option<String>,
+    } -> PortalAccess;
+    |client, op| client.portal_viewer(op.token.as_deref());
+
+    PortalBalance {
+        token: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.portal_balance(op.token.as_deref()).await;
+
+    PortalNotes {
+        token: Option<String>,
+    } -> Vec<serde_json::Value>;
+    |client, op| client.portal_notes(op.token.as_deref()).await;
+
+    PortalEscrows {
+        token: Option<String>,
+    } -> Vec<EscrowRecord>;
+    |client, op| client.portal_escrows(op.token.as_deref());
+
+    PortalLeases {
+        token: Option<String>,
+    } -> Vec<serde_json::Value>;
+    |client, op| client.portal_leases(op.token.as_deref());
+
+    PortalPayments {
+        token: Option<String>,
+        query: PaymentQuery,
+    } -> Vec<PortalPayment>;
+    |client, op| client.portal_payments(op.token.as_deref(), op.query)
+}
+
+impl MidenClientWrapper {
+    pub fn issue_portal_access(&mut self, input: PortalAccessInput) -> Result<serde_json::Value> {
+        let account_hex = self.account_hex(&input.account_id)?;
+        parse_hex_account_id(&account_hex)?;
+
+        let (access, token) = self.portal_access.issue(&account_hex, input.label)?;
+        tracing::info!(
+            "Issued portal access {} ({}) for {}",
+            access.access_id,
+            access.label,
+            access.account_id
+        );
+
+        Ok(serde_json::json!({
+            "access": access,
+            "portal_token": token,
+        }))
+    }
+
+    /// The access behind a portal token.
+    fn portal_viewer(&self, token: Option<&str>) -> Result<PortalAccess> {
+        let token = token.ok_or_else(|| {
+            EscrowAuthError::Unauthenticated("X-Portal-Token header is required".into())
+        })?;
+        self.portal_access
+            .authenticate(token)
+            .map(PortalAccess::public)
+            .ok_or_else(|| {
+                EscrowAuthError::Unauthenticated("Invalid or revoked portal token".into()).into()
+            })
+    }
+
+    fn portal_scope(&self, token: Option<&str>) -> Result<PortalScope> {
+        if let Some(session) = token.and_then(|t| self.custodial.authenticate(t)) {
+            return Ok(PortalScope {
+                account_id: session.account_id.clone(),
+            });
+        }
+        let access = self.portal_viewer(token)?;
+        Ok(PortalScope {
+            account_id: access.account_id,
+        })
+    }
+
+    pub async fn portal_balance(&mut self, token: Option<&str>) -> Result<serde_json::Value> {
+        let scope = self.portal_scope(token)?;
+        let account_id = parse_hex_account_id(&scope.account_id)?;
+        self.sync_state().await?;
+
+        let account = self.client.get_account(account_id).await?.ok_or_else(|| {
+            anyhow::anyhow!(
+                "Account {} is not tracked by this service",
+                scope.account_id
+            )
+        })?;
+        let balances: Vec<TokenAmount> = account
+            .account()
+            .vault()
+            .assets()
+            .filter_map(|asset| match asset {
+                Asset::Fungible(fungible) => Some(self.token_amount(&fungible)),
+                Asset::NonFungible(_) => None,
+            })
+            .collect();
+
+        Ok(serde_json::json!({
+            "account_id": scope.account_id,
+            "balances": balances,
+        }))
+    }
+
+    pub async fn portal_notes(&mut self, token: Option<&str>) -> Result<Vec<serde_json::Value>> {
+        let scope = self.portal_scope(token)?;
+        let account_id = parse_hex_account_id(&scope.account_id)?;
+        self.sync_state().await?;
+        self.consumable_note_views(account_id).await
+    }
+
+    pub fn portal_escrows(&self, token: Option<&str>) -> Result<Vec<EscrowRecord>> {
+        let scope = self.portal_scope(token)?;
+        Ok(self
+            .records
+            .escrows
+            .values()
+            .filter(|escrow| scope.sees_escrow(escrow))
+            .cloned()
+            .collect())
+    }
+
+    pub fn portal_leases(&self, token: Option<&str>) -> Result<Vec<serde_json::Value>> {
+        let scope = self.portal_scope(token)?;
+        Ok(self
+            .leases
+            .list()
+            .iter()
+            .filter(|lease| {
+                scope.is_account(&lease.tenant_account_id)
+                    || scope.is_account(&lease.owner_account_id)
+            })
+            .map(|lease| lease.report())
+            .collect())
+    }
+
+    /// The account's movements, newest first.
+    pub fn portal_payments(
+        &self,
+        token: Option<&str>,
+        query: PaymentQuery,
+    ) -> Result<Vec<PortalPayment>> {
+        let scope = self.portal_scope(token)?;
+        let limit = query.limit.unwrap_or(MAX_PAYMENTS).min(MAX_PAYMENTS);
+        Ok(self
+            .ledger
+            .entries(Some(&scope.account_id), limit)
+            .into_iter()
+            .flat_map(|entry| scope.payments(entry))
+            .collect())
+    }
+}
@@ -0,0 +1,310 @@
+// src/portfolio.rs
+//
+// Per-account portfolio view
+//
+// Everything here is computed from what the service has already recorded
+// (records.rs and the feature stores) plus the client's local store; nothing
+// syncs with or queries the chain, so a portfolio is cheap to build and may
+// lag the chain by whatever has not been recorded yet.
+//
+// A portfolio covers:
+// - properties: whole properties the account owns, valued at their minted price
+//   in minor units of its currency (currency.rs), with totals per currency
+// - share_positions: shares held in properties with an open market
+//   (order_book.rs), valued at the market's last trade price. For accounts the
+//   client tracks, holdings come from the share faucet balance in the local
+//   vault; otherwise they are the net of settled trades
+// - pending_escrows: escrows the account is party to that are not yet settled
+// - balances: service token that is liquid (local vault, tracked accounts only),
+//   locked in funded escrows, and committed to resting buy orders
+// - distributions: what the account has been paid: rent, escrow payouts as
+//   seller, withheld lease deposits and share sale proceeds
+
+use anyhow::Result;
+use miden_client::account::AccountId;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{
+    account_id_to_hex,
+    currency::{SERVICE_TOKEN_DECIMALS, SERVICE_TOKEN_SYMBOL},
+    escrow::EscrowStatus,
+    leases::RentStatus,
+    order_book::{Side, TradeStatus},
+    MidenClientWrapper,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DistributionKind {
+    Rent,
+    EscrowPayout,
+    DepositWithheld,
+    ShareSale,
+}
+
+/// A payment the account received.
+#[derive(Debug, Clone, Serialize)]
+pub struct Distribution {
+    pub kind: DistributionKind,
+    pub property_id: Option<String>,
+    /// Lease, escrow or trade the payment came from
+    pub source: String,
+    pub amount: u64,
+    pub tx_id: Option<String>,
+    pub received_at: i64,
+}
+
+crate::operations! {
+    GetPortfolio {
+        account_id: String,
+    } -> serde_json::Value;
+    |client, op| client.get_portfolio(&op.account_id).await
+}
+
+impl MidenClientWrapper {
+    /// Builds the portfolio of an account (name or hex AccountId).
+    pub async fn get_portfolio(&mut self, account: &str) -> Result<serde_json::Value> {
+        let account_hex = self.account_hex(account)?;
+        let is_account = |id: &str| id.eq_ignore_ascii_case(&account_hex);
+
+        let properties: Vec<_> = self
+            .records
+            .properties
+            .values()
+            .filter(|p| is_account(&p.owner_account_id))
+            .map(|p| {
+                serde_json::json!({
+                    "property_id": p.property_id,
+                    "property_type": p.property_type,
+                    "ipfs_cid": p.ipfs_cid,
+                    "value": p.price,
+                    "currency": self.price_currency(p),
+                    "leased": self.leases.has_open_lease(&p.property_id),
+                })
+            })
+            .collect();
+        let property_value: u64 = properties.iter().filter_map(|p| p["value"].as_u64()).sum();
+        let mut property_value_by_currency: BTreeMap<&str, u64> = BTreeMap::new();
+        for p in &properties {
+            if let (Some(currency), Some(value)) = (p["currency"].as_str(), p["value"].as_u64()) {
+                *property_value_by_currency.entry(currency).or_default() += value;
+            }
+        }
+
+        let mut share_positions = Vec::new();
+        let mut share_value = 0u64;
+        let mut reserved_for_orders = 0u64;
+        let markets: Vec<_> = self.order_book.markets().into_iter().cloned().collect();
+        for market in markets {
+            let trades = self.order_book.trades(&market.property_id, None);
+            let last_price = trades.last().map(|t| t.price);
+            let net_traded: i128 = trades
+                .iter()
+                .filter(|t| t.status == TradeStatus::Settled)
+                .map(|t| {
+                    if is_account(&t.buyer_account_id) {
+                        t.quantity as i128
+                    } else if is_account(&t.seller_account_id) {
+                        -(t.quantity as i128)
+                    } else {
+                        0
+                    }
+                })
+                .sum();
+
+            let mut shares_on_order = 0u64;
+            for order in self
+                .order_book
+                .orders(&market.property_id, Some(&account_hex))
+                .into_iter()
+                .filter(|o| o.is_resting())
+            {
+                match order.side {
+                    Side::Buy => reserved_for_orders += order.remaining() * order.price,
+                    Side::Sell => shares_on_order += order.remaining(),
+                }
+            }
+
+            let (shares, source) = match self
+                .local_balance(&account_hex, &market.share_faucet_id)
+                .await
+            {
+                Some(balance) => (balance, "vault"),
+                None => (net_traded.max(0) as u64, "trades"),
+            };
+            if shares == 0 && shares_on_order == 0 {
+                continue;
+            }
+
+            let value = last_price.map(|price| shares.saturating_mul(price));
+            share_value = share_value.saturating_add(value.unwrap_or(0));
+            share_positions.push(serde_json::json!({
+                "property_id": market.property_id,
+                "share_faucet_id": market.share_faucet_id,
+                "shares": shares,
+                "shares_source": source,
+                "shares_on_order": shares_on_order,
+                "last_price": last_price,
+                "value": value,
+            }));
+        }
+
+        let pending_escrows: Vec<_> = self
+            .records
+            .escrows
+            .values()
+            .filter(|e| {
+                matches!(
+                    e.status,
+                    EscrowStatus::Created | EscrowStatus::Funded | EscrowStatus::Disputed
+                )
+            })
+            .filter_map(|e| {
+                let role = if is_account(&e.buyer_account_id) {
+                    "buyer"
+                } else if is_account(&e.seller_account_id) {
+                    "seller"
+                } else {
+                    return None;
+                };
+                Some(serde_json::json!({
+                    "escrow_account_id": e.escrow_account_id,
+                    "property_id": e.property_id,
+                    "role": role,
+                    "amount": e.amount,
+                    "status": e.status,
+                    "created_at": e.created_at,
+                }))
+            })
+            .collect();
+
+        let locked_in_escrow: u64 = self
+            .records
+            .escrows
+            .values()
+            .filter(|e| {
+                is_account(&e.buyer_account_id)
+                    && matches!(e.status, EscrowStatus::Funded | EscrowStatus::Disputed)
+            })
+            .map(|e| e.amount)
+            .sum();
+
+        let service_faucet = self.faucet_account_id.map(account_id_to_hex);
+        let liquid = match service_faucet {
+            Some(faucet) => self.local_balance(&account_hex, &faucet).await,
+            None => None,
+        };
+
+        let distributions = self.distributions(&account_hex);
+        let distributions_total: u64 = distributions.iter().map(|d| d.amount).sum();
+
+        Ok(serde_json::json!({
+            "account_id": account_hex,
+            "as_of": chrono::Utc::now().timestamp(),
+            "properties": properties,
+            "share_positions": share_positions,
+            "pending_escrows": pending_escrows,
+            "balances": {
+                "symbol": SERVICE_TOKEN_SYMBOL,
+                "decimals": SERVICE_TOKEN_DECIMALS,
+                "liquid": liquid,
+                "locked_in_escrow": locked_in_escrow,
+                "reserved_for_orders": reserved_for_orders,
+            },
+            "distributions": distributions,
+            "totals": {
+                "property_value": property_value,
+                "property_value_by_currency": property_value_by_currency,
+                "share_value": share_value,
+                "distributions_received": distributions_total,
+            },
+        }))
+    }
+
+    /// Payments received by the account, oldest first.
+    fn distributions(&self, account_hex: &str) -> Vec<Distribution> {
+        let is_account = |id: &str| id.eq_ignore_ascii_case(account_hex);
+        let mut distributions = Vec::new();
+        let mut deposit_escrows = Vec::new();
+
+        for lease in self.leases.list() {
+            deposit_escrows.push(lease.deposit_escrow_account_id.as_str());
+            if !is_account(&lease.owner_account_id) {
+                continue;
+            }
+
+            for payment in &lease.rent_payments {
+                if payment.status == RentStatus::Paid {
+                    distributions.push(Distribution {
+                        kind: DistributionKind::Rent,
+                        property_id: Some(lease.property_id.clone()),
+                        source: format!("{} #{}", lease.lease_id, payment.seq),
+                        amount: payment.amount,
+                        tx_id: payment.tx_id.clone(),
+                        received_at: payment.paid_at.unwrap_or(lease.updated_at),
+                    });
+                }
+            }
+            if let Some(settlement) = lease.deposit_settlement.as_ref() {
+                if settlement.withheld > 0 {
+                    distributions.push(Distribution {
+                        kind: DistributionKind::DepositWithheld,
+                        property_id: Some(lease.property_id.clone()),
+                        source: lease.lease_id.clone(),
+                        amount: settlement.withheld,
+                        tx_id: Some(settlement.tx_id.clone()),
+                        received_at: settlement.settled_at,
+                    });
+                }
+            }
+        }
+
+        // Lease deposits are counted above; a release may have split them
+        for escrow in self.records.escrows.values() {
+            if escrow.status == EscrowStatus::Released
+                && is_account(&escrow.seller_account_id)
+                && !deposit_escrows.contains(&escrow.escrow_account_id.as_str())
+            {
+                distributions.push(Distribution {
+                    kind: DistributionKind::EscrowPayout,
+                    property_id: escrow.property_id.clone(),
+                    source: escrow.escrow_account_id.clone(),
+                    amount: escrow.amount,
+                    tx_id: escrow.settle_tx_id.clone(),
+                    received_at: escrow.updated_at,
+                });
+            }
+        }
+
+        for market in self.order_book.markets() {
+            for trade in self
+                .order_book
+                .trades(&market.property_id, Some(account_hex))
+            {
+                if trade.fill_tx_id.is_some() && is_account(&trade.seller_account_id) {
+                    distributions.push(Distribution {
+                        kind: DistributionKind::ShareSale,
+                        property_id: Some(trade.property_id.clone()),
+                        source: format!("trade-{}", trade.trade_id),
+                        amount: trade.quantity * trade.price,
+                        tx_id: trade.fill_tx_id.clone(),
+                        received_at: trade.settled_at.unwrap_or(trade.matched_at),
+                    });
+                }
+            }
+        }
+
+        distributions.sort_by_key(|d| d.received_at);
+        distributions
+    }
+
+    /// Balance of `faucet_hex` tokens in the local vault of an account the
+    /// client tracks; None for accounts it does not.
+    async fn local_balance(&mut self, account_hex: &str, faucet_hex: &str) -> Option<u64> {
+        let account_id = AccountId::from_hex(account_hex).ok()?;
+        let faucet_id = AccountId::from_hex(faucet_hex).ok()?;
+        let account = self.client.get_account(account_id).await.ok()??;
+        account.account().vault().get_balance(faucet_id).ok()
+    }
+}
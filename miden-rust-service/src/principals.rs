@@ -0,0 +1,483 @@
+// src/principals.rs
+//
+// API keys and the principals behind them
+//
+// Callers acting on escrows authenticate with an `X-API-Key` header. Each key
+// belongs to a principal that is bound to the accounts it may act for (hex
+// AccountIds), and optionally holds the arbiter role. Escrow authorization
+// (escrow.rs) checks these bindings against the escrow's recorded parties.
+// The admin role is needed to move funds out of the treasury (treasury.rs).
+//
+// A closing agent key (`closing_agent`) is bound to no account and holds no
+// other role; instead it is assigned escrows, at issue or later with
+//   PUT/DELETE /admin/api-keys/:key_id/escrows/:escrow_account_id
+// On an assigned escrow it may view the escrow and its documents, set the
+// release checklist (attachments.rs), generate closing documents and send them
+// for signature (documents.rs, esign.rs), and request the release, which is
+// always parked for a second approver (approvals.rs) that is not a closing
+// agent. Everything else is refused by the checks that ask whether a key owns
+// an account: it cannot open, fund or refund escrows, move funds of any
+// account, or join organizations.
+//
+// A key that is a member of an organization also acts for the organization's
+// accounts (organizations.rs); those are not stored with the key but handed
+// over by the organization store.
+//
+// Keys look like "obk_<key_id>_<64 hex chars>". Only their SHA-256 is stored;
+// the plaintext is returned once, when the key is issued.
+//
+// Every /admin route needs an admin key (routes/admin.rs). The first one is
+// issued with ADMIN_BOOTSTRAP_KEY, a configured key that acts as an admin (key
+// ID 0, label "bootstrap") until an admin key exists and is no longer accepted
+// from then on.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    data_subjects::{erase, ErasedField},
+    MidenClientWrapper,
+};
+
+const KEY_PREFIX: &str = "obk_";
+/// Key ID of the principal behind ADMIN_BOOTSTRAP_KEY; issued keys start at 1
+pub const BOOTSTRAP_KEY_ID: u64 = 0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Principal {
+    pub key_id: u64,
+    pub label: String,
+    /// Hex AccountIds this principal may act for
+    pub accounts: Vec<String>,
+    /// May settle escrows it is not a party to
+    pub arbiter: bool,
+    /// May request and approve treasury withdrawals
+    #[serde(default)]
+    pub admin: bool,
+    /// Closing agent for the escrows in `escrows` only
+    #[serde(default)]
+    pub closing_agent: bool,
+    /// Hex AccountIds of the escrows a closing agent is assigned to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub escrows: Vec<String>,
+    pub revoked: bool,
+    pub created_at: i64,
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    key_hash: String,
+    /// Accounts of the principal's organizations
+    #[serde(skip)]
+    org_accounts: Vec<String>,
+}
+
+impl Principal {
+    /// Whether the principal may act for `account_hex`, itself or through an
+    /// organization.
+    pub fn owns(&self, account_hex: &str) -> bool {
+        self.owns_directly(account_hex)
+            || self
+                .org_accounts
+                .iter()
+                .any(|a| a.eq_ignore_ascii_case(account_hex))
+    }
+
+    /// Whether the key itself is bound to `account_hex`.
+    pub fn owns_directly(&self, account_hex: &str) -> bool {
+        self.accounts
+            .iter()
+            .any(|a| a.eq_ignore_ascii_case(account_hex))
+    }
+
+    /// Whether the principal is the closing agent of `escrow_hex`.
+    pub fn assigned_to(&self, escrow_hex: &str) -> bool {
+        self.closing_agent
+            && self
+                .escrows
+                .iter()
+                .any(|e| e.eq_ignore_ascii_case(escrow_hex))
+    }
+
+    /// Copy without the key hash, for API responses.
+    pub fn public(&self) -> Principal {
+        Principal {
+            key_hash: String::new(),
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiKeyInput {
+    pub label: String,
+    /// Account names ("alice", "bob") or hex AccountIds
+    #[serde(default)]
+    pub accounts: Vec<String>,
+    #[serde(default)]
+    pub arbiter: bool,
+    #[serde(default)]
+    pub admin: bool,
+    #[serde(default)]
+    pub closing_agent: bool,
+    /// Escrows (hex AccountIds) assigned to a closing agent key
+    #[serde(default)]
+    pub escrows: Vec<String>,
+}
+
+/// SHA-256 of a key, as stored; also how usage is metered (billing.rs).
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Whether each active key (by hash) holds the admin role, shared by the
+/// principal store and the admin gate (routes/admin.rs), which checks keys
+/// without queueing for the client task. The store refreshes it on every
+/// change.
+#[derive(Debug, Clone, Default)]
+pub struct AdminKeys {
+    keys: Arc<RwLock<HashMap<String, bool>>>,
+}
+
+impl AdminKeys {
+    /// Some(true) for an admin key, Some(false) for another active key, None
+    /// for an unknown or revoked one.
+    pub fn is_admin(&self, key: &str) -> Option<bool> {
+        let keys = self.keys.read().unwrap_or_else(|e| e.into_inner());
+        keys.get(&hash_key(key)).copied()
+    }
+
+    fn replace(&self, keys: HashMap<String, bool>) {
+        *self.keys.write().unwrap_or_else(|e| e.into_inner()) = keys;
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PrincipalStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    principals: BTreeMap<u64, Principal>,
+    #[serde(default)]
+    next_key_id: u64,
+    /// Principal of ADMIN_BOOTSTRAP_KEY
+    #[serde(skip)]
+    bootstrap: Option<Principal>,
+    #[serde(skip)]
+    admin_keys: AdminKeys,
+}
+
+impl PrincipalStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<PrincipalStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            PrincipalStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        self.refresh_admin_keys();
+        Ok(())
+    }
+
+    /// Accepts `key` as an admin until an admin key is issued (see module
+    /// docs).
+    pub fn with_bootstrap_key(mut self, key: Option<&str>) -> Self {
+        self.bootstrap = key.map(|key| Principal {
+            key_id: BOOTSTRAP_KEY_ID,
+            label: "bootstrap".to_string(),
+            accounts: Vec::new(),
+            arbiter: false,
+            admin: true,
+            closing_agent: false,
+            escrows: Vec::new(),
+            revoked: false,
+            created_at: 0,
+            key_hash: hash_key(key),
+            org_accounts: Vec::new(),
+        });
+        self.refresh_admin_keys();
+        self
+    }
+
+    /// The keys the admin gate checks.
+    pub fn admin_keys(&self) -> AdminKeys {
+        self.admin_keys.clone()
+    }
+
+    /// Shares the admin gate's keys with this store, which keeps them current.
+    pub fn attach_admin_keys(&mut self, admin_keys: AdminKeys) {
+        self.admin_keys = admin_keys;
+        self.refresh_admin_keys();
+    }
+
+    fn refresh_admin_keys(&self) {
+        let mut keys: HashMap<String, bool> = self
+            .principals
+            .values()
+            .filter(|p| !p.revoked)
+            .map(|p| (p.key_hash.clone(), p.admin))
+            .collect();
+        if let Some(bootstrap) = self.active_bootstrap() {
+            keys.insert(bootstrap.key_hash.clone(), true);
+        }
+        self.admin_keys.replace(keys);
+    }
+
+    /// The bootstrap principal while no admin key exists.
+    fn active_bootstrap(&self) -> Option<&Principal> {
+        let has_admin = self.principals.values().any(|p| p.admin && !p.revoked);
+        self.bootstrap.as_ref().filter(|_| !has_admin)
+    }
+
+    pub fn list(&self) -> Vec<Principal> {
+        self.principals.values().map(Principal::public).collect()
+    }
+
+    /// Issues a key for a principal bound to `accounts` (already resolved to hex).
+    /// `closing_agent` issues a closing agent key assigned those escrows.
+    ///
+    /// Returns the principal and the plaintext key.
+    pub fn issue(
+        &mut self,
+        label: String,
+        accounts: Vec<String>,
+        arbiter: bool,
+        admin: bool,
+        closing_agent: Option<Vec<String>>,
+    ) -> Result<(Principal, String)> {
+        if closing_agent.is_some() && (!accounts.is_empty() || arbiter || admin) {
+            return Err(anyhow::anyhow!(
+                "A closing agent key may not be bound to accounts or hold the arbiter or admin role"
+            ));
+        }
+        if accounts.is_empty() && !arbiter && !admin && closing_agent.is_none() {
+            return Err(anyhow::anyhow!(
+                "A key must be bound to at least one account or hold the arbiter, admin or closing agent role"
+            ));
+        }
+
+        self.next_key_id += 1;
+        let key_id = self.next_key_id;
+        let secret: [u8; 32] = rand::random();
+        let key = format!("{}{}_{}", KEY_PREFIX, key_id, hex::encode(secret));
+
+        let principal = Principal {
+            key_id,
+            label,
+            accounts: accounts.iter().map(|a| a.to_lowercase()).collect(),
+            arbiter,
+            admin,
+            closing_agent: closing_agent.is_some(),
+            escrows: closing_agent
+                .unwrap_or_default()
+                .iter()
+                .map(|e| e.to_lowercase())
+                .collect(),
+            revoked: false,
+            created_at: chrono::Utc::now().timestamp(),
+            key_hash: hash_key(&key),
+            org_accounts: Vec::new(),
+        };
+
+        self.principals.insert(key_id, principal.clone());
+        self.save()?;
+        Ok((principal.public(), key))
+    }
+
+    pub fn revoke(&mut self, key_id: u64) -> Result<Principal> {
+        let principal = self
+            .principals
+            .get_mut(&key_id)
+            .ok_or_else(|| anyhow::anyhow!("API key {} not found", key_id))?;
+        principal.revoked = true;
+
+        let principal = principal.public();
+        self.save()?;
+        Ok(principal)
+    }
+
+    /// Assigns an escrow to a closing agent key, or with `assigned` false
+    /// takes it away.
+    pub fn assign_escrow(
+        &mut self,
+        key_id: u64,
+        escrow_hex: &str,
+        assigned: bool,
+    ) -> Result<Principal> {
+        let principal = self
+            .principals
+            .get_mut(&key_id)
+            .filter(|p| !p.revoked)
+            .ok_or_else(|| anyhow::anyhow!("API key {} not found", key_id))?;
+        if !principal.closing_agent {
+            return Err(anyhow::anyhow!(
+                "API key {} is not a closing agent key",
+                key_id
+            ));
+        }
+        principal
+            .escrows
+            .retain(|e| !e.eq_ignore_ascii_case(escrow_hex));
+        if assigned {
+            principal.escrows.push(escrow_hex.to_lowercase());
+        }
+
+        let principal = principal.public();
+        self.save()?;
+        Ok(principal)
+    }
+
+    /// The principal of a key, revoked or not.
+    pub fn get(&self, key_id: u64) -> Option<&Principal> {
+        self.principals.get(&key_id)
+    }
+
+    /// The principal whose key hashes to `key_hash`, revoked or not.
+    pub fn find_by_key_hash(&self, key_hash: &str) -> Option<&Principal> {
+        self.principals.values().find(|p| p.key_hash == key_hash)
+    }
+
+    /// Whether `key_id` names a key that has not been revoked.
+    pub fn is_active(&self, key_id: u64) -> bool {
+        self.principals.get(&key_id).is_some_and(|p| !p.revoked)
+    }
+
+    /// Replaces the organization accounts of every key (organizations.rs).
+    pub fn attach_org_accounts(&mut self, mut accounts: BTreeMap<u64, Vec<String>>) {
+        for (key_id, principal) in self.principals.iter_mut() {
+            principal.org_accounts = accounts.remove(key_id).unwrap_or_default();
+        }
+    }
+
+    /// IDs of the keys bound to an account.
+    pub fn key_ids_for(&self, account_hex: &str) -> Vec<u64> {
+        self.principals
+            .values()
+            .filter(|p| p.owns_directly(account_hex))
+            .map(|p| p.key_id)
+            .collect()
+    }
+
+    /// Erases the labels of keys bound to an account and nothing else
+    /// (data_subjects.rs); shared keys keep theirs.
+    pub fn erase_personal_fields(&mut self, account_hex: &str) -> Result<Vec<ErasedField>> {
+        let mut erased = Vec::new();
+        for principal in self
+            .principals
+            .values_mut()
+            .filter(|p| p.owns_directly(account_hex) && p.accounts.len() == 1)
+        {
+            if let Some(original) = erase(&mut principal.label) {
+                erased.push(ErasedField::new(
+                    "api_keys",
+                    &principal.key_id.to_string(),
+                    "label",
+                    original,
+                ));
+            }
+        }
+        if !erased.is_empty() {
+            self.save()?;
+        }
+        Ok(erased)
+    }
+
+    /// Resolves a presented key to its (non-revoked) principal.
+    pub fn authenticate(&self, key: &str) -> Option<&Principal> {
+        let key_id = key
+            .strip_prefix(KEY_PREFIX)?
+            .split('_')
+            .next()?
+            .parse::<u64>()
+            .ok()?;
+
+        let presented = hash_key(key);
+        self.principals
+            .get(&key_id)
+            .filter(|p| !p.revoked && p.key_hash == presented)
+    }
+
+    /// Resolves a presented key to an admin principal: an issued admin key,
+    /// or ADMIN_BOOTSTRAP_KEY while no admin key exists.
+    pub fn authenticate_admin(&self, key: &str) -> Option<&Principal> {
+        let presented = hash_key(key);
+        self.authenticate(key).or_else(|| {
+            self.active_bootstrap()
+                .filter(|bootstrap| bootstrap.key_hash == presented)
+        })
+    }
+}
+
+// ============================================================================
+// CLIENT INTEGRATION
+// ============================================================================
+
+crate::operations! {
+    /// Assigns an escrow to a closing agent key.
+    AssignClosingAgentEscrow {
+        key_id: u64,
+        escrow_account_id: String,
+    } -> Principal;
+    |client, op| client.assign_closing_agent_escrow(op.key_id, &op.escrow_account_id, true);
+
+    /// Takes an escrow away from a closing agent key.
+    UnassignClosingAgentEscrow {
+        key_id: u64,
+        escrow_account_id: String,
+    } -> Principal;
+    |client, op| client.assign_closing_agent_escrow(op.key_id, &op.escrow_account_id, false)
+}
+
+impl MidenClientWrapper {
+    /// Shares the keys the admin gate checks with handlers.
+    pub fn attach_admin_keys(&mut self, admin_keys: AdminKeys) {
+        self.principals.attach_admin_keys(admin_keys);
+    }
+
+    /// Assigns (or takes away) an escrow known to the service records.
+    pub fn assign_closing_agent_escrow(
+        &mut self,
+        key_id: u64,
+        escrow_account_id: &str,
+        assigned: bool,
+    ) -> Result<Principal> {
+        let escrow_hex = escrow_account_id.to_lowercase();
+        if assigned && !self.records.escrows.contains_key(&escrow_hex) {
+            return Err(anyhow::anyhow!(
+                "Escrow {} not found in service records",
+                escrow_hex
+            ));
+        }
+        let principal = self
+            .principals
+            .assign_escrow(key_id, &escrow_hex, assigned)?;
+        let change = if assigned {
+            "assigned"
+        } else {
+            "unassigned from"
+        };
+        tracing::info!(
+            "Closing agent key {} ({}) {} escrow {}",
+            key_id,
+            principal.label,
+            change,
+            escrow_hex
+        );
+        Ok(principal)
+    }
+}
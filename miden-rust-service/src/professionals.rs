@@ -0,0 +1,551 @@
+// src/professionals.rs
+//
+// Appraisers and inspectors
+//
+// Professionals register an account together with their credentials (licence
+// number and issuing jurisdiction) and the Ed25519 key they sign reports with.
+// Registering needs an API key bound to that account (or an arbiter's); so does
+// revoking, which an arbiter does when a licence lapses.
+//
+// A professional attaches a report on a property by signing its commitment:
+//   SHA-256("obscura-professional-report" || professional_id || property_id ||
+//           document_hash || appraised_value)
+// with each field length-prefixed (u32 LE), the ID and value in decimal and the
+// value empty for inspection reports. Appraisal reports must state the
+// appraised value; inspection reports must not.
+//
+// Reports can be cited when generating an ownership proof (lib.rs), which then
+// lists them. A report only counts while its professional is active; revoking a
+// professional keeps their reports on record but they can no longer be cited.
+
+use anyhow::Result;
+use ed25519_dalek::{Signature, Verifier};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    data_subjects::{erase, ErasedField},
+    jurisdiction_lists::parse_signer_key,
+    MidenClientWrapper,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfessionalRole {
+    Appraiser,
+    Inspector,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfessionalStatus {
+    Active,
+    Revoked,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProfessionalInput {
+    /// Account name or hex AccountId
+    pub account_id: String,
+    pub role: ProfessionalRole,
+    pub name: String,
+    pub license_number: String,
+    /// ISO country (or country-region) code of the licensing authority
+    pub license_jurisdiction: String,
+    /// Ed25519 public key (hex) reports are signed with
+    pub public_key: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RevokeProfessionalInput {
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReportInput {
+    pub professional_id: u64,
+    /// SHA-256 of the report document (hex)
+    pub document_hash: String,
+    /// Required for appraisals, absent for inspections
+    #[serde(default)]
+    pub appraised_value: Option<u64>,
+    /// Professional's signature over the report commitment (hex)
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Professional {
+    pub professional_id: u64,
+    pub account_id: String,
+    pub role: ProfessionalRole,
+    pub name: String,
+    pub license_number: String,
+    pub license_jurisdiction: String,
+    pub public_key: String,
+    pub status: ProfessionalStatus,
+    pub registered_at: i64,
+    pub revoked_at: Option<i64>,
+    pub revocation_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfessionalReport {
+    pub report_id: u64,
+    pub property_id: String,
+    pub professional_id: u64,
+    pub role: ProfessionalRole,
+    pub document_hash: String,
+    pub appraised_value: Option<u64>,
+    pub signature: String,
+    pub attached_at: i64,
+}
+
+pub fn report_commitment(
+    professional_id: u64,
+    property_id: &str,
+    document_hash: &str,
+    appraised_value: Option<u64>,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"obscura-professional-report");
+    for field in [
+        professional_id.to_string(),
+        property_id.trim().to_string(),
+        document_hash.trim().trim_start_matches("0x").to_lowercase(),
+        appraised_value.map(|v| v.to_string()).unwrap_or_default(),
+    ] {
+        hasher.update((field.len() as u32).to_le_bytes());
+        hasher.update(field.as_bytes());
+    }
+    hasher.finalize().into()
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProfessionalRegistry {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    professionals: BTreeMap<u64, Professional>,
+    #[serde(default)]
+    reports: BTreeMap<u64, ProfessionalReport>,
+    #[serde(default)]
+    next_professional_id: u64,
+    #[serde(default)]
+    next_report_id: u64,
+}
+
+impl ProfessionalRegistry {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut registry = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<ProfessionalRegistry>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            ProfessionalRegistry::default()
+        };
+        registry.path = path;
+
+        Ok(registry)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, professional_id: u64) -> Result<&Professional> {
+        self.professionals
+            .get(&professional_id)
+            .ok_or_else(|| anyhow::anyhow!("Professional {} not found", professional_id))
+    }
+
+    pub fn list(&self, role: Option<ProfessionalRole>) -> Vec<&Professional> {
+        self.professionals
+            .values()
+            .filter(|p| role.map(|r| p.role == r).unwrap_or(true))
+            .collect()
+    }
+
+    /// Erases the name and licence number of professionals registered for an
+    /// account (data_subjects.rs). Their reports stay on record.
+    pub fn erase_personal_fields(&mut self, account_hex: &str) -> Result<Vec<ErasedField>> {
+        let mut erased = Vec::new();
+        for professional in self
+            .professionals
+            .values_mut()
+            .filter(|p| p.account_id.eq_ignore_ascii_case(account_hex))
+        {
+            let record_id = professional.professional_id.to_string();
+            if let Some(original) = erase(&mut professional.name) {
+                erased.push(ErasedField::new(
+                    "professionals",
+                    &record_id,
+                    "name",
+                    original,
+                ));
+            }
+            if let Some(original) = erase(&mut professional.license_number) {
+                erased.push(ErasedField::new(
+                    "professionals",
+                    &record_id,
+                    "license_number",
+                    original,
+                ));
+            }
+        }
+        if !erased.is_empty() {
+            self.save()?;
+        }
+        Ok(erased)
+    }
+
+    /// Registers a professional; `account_hex` is the resolved account.
+    pub fn register(
+        &mut self,
+        account_hex: &str,
+        input: ProfessionalInput,
+    ) -> Result<Professional> {
+        let key = parse_signer_key(&input.public_key)?;
+        let public_key = hex::encode(key.to_bytes());
+        let license_jurisdiction = input.license_jurisdiction.trim().to_uppercase();
+        let license_number = input.license_number.trim().to_string();
+
+        for existing in self
+            .professionals
+            .values()
+            .filter(|p| p.status == ProfessionalStatus::Active)
+        {
+            if existing.public_key == public_key {
+                return Err(anyhow::anyhow!(
+                    "A professional with this public key is already registered"
+                ));
+            }
+            if existing.role == input.role
+                && existing.license_number == license_number
+                && existing.license_jurisdiction == license_jurisdiction
+            {
+                return Err(anyhow::anyhow!(
+                    "Licence {} ({}) is already registered to professional {}",
+                    license_number,
+                    license_jurisdiction,
+                    existing.professional_id
+                ));
+            }
+        }
+
+        self.next_professional_id += 1;
+        let professional = Professional {
+            professional_id: self.next_professional_id,
+            account_id: account_hex.to_lowercase(),
+            role: input.role,
+            name: input.name,
+            license_number,
+            license_jurisdiction,
+            public_key,
+            status: ProfessionalStatus::Active,
+            registered_at: chrono::Utc::now().timestamp(),
+            revoked_at: None,
+            revocation_reason: None,
+        };
+
+        self.professionals
+            .insert(professional.professional_id, professional.clone());
+        self.save()?;
+        Ok(professional)
+    }
+
+    pub fn revoke(&mut self, professional_id: u64, reason: String) -> Result<Professional> {
+        let professional = self
+            .professionals
+            .get_mut(&professional_id)
+            .ok_or_else(|| anyhow::anyhow!("Professional {} not found", professional_id))?;
+        if professional.status == ProfessionalStatus::Revoked {
+            return Err(anyhow::anyhow!(
+                "Professional {} is already revoked",
+                professional_id
+            ));
+        }
+        professional.status = ProfessionalStatus::Revoked;
+        professional.revoked_at = Some(chrono::Utc::now().timestamp());
+        professional.revocation_reason = Some(reason);
+
+        let professional = professional.clone();
+        self.save()?;
+        Ok(professional)
+    }
+
+    /// Verifies the report against its professional's key and attaches it.
+    pub fn attach_report(
+        &mut self,
+        property_id: &str,
+        input: ReportInput,
+    ) -> Result<ProfessionalReport> {
+        let professional = self
+            .professionals
+            .get(&input.professional_id)
+            .filter(|p| p.status == ProfessionalStatus::Active)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Professional {} is not registered or revoked",
+                    input.professional_id
+                )
+            })?;
+
+        match (professional.role, input.appraised_value) {
+            (ProfessionalRole::Appraiser, None) => {
+                return Err(anyhow::anyhow!(
+                    "Appraisal reports must state appraised_value"
+                ))
+            }
+            (ProfessionalRole::Inspector, Some(_)) => {
+                return Err(anyhow::anyhow!(
+                    "Inspection reports cannot state appraised_value"
+                ))
+            }
+            _ => {}
+        }
+
+        let document_hash = input
+            .document_hash
+            .trim()
+            .trim_start_matches("0x")
+            .to_lowercase();
+        if hex::decode(&document_hash).ok().map(|h| h.len()) != Some(32) {
+            return Err(anyhow::anyhow!("Document hash must be 32 bytes of hex"));
+        }
+
+        let signature: [u8; 64] = hex::decode(input.signature.trim().trim_start_matches("0x"))?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Report signature must be 64 bytes"))?;
+        let commitment = report_commitment(
+            input.professional_id,
+            property_id,
+            &document_hash,
+            input.appraised_value,
+        );
+        parse_signer_key(&professional.public_key)?
+            .verify(&commitment, &Signature::from_bytes(&signature))
+            .map_err(|_| anyhow::anyhow!("Report signature is not valid for professional"))?;
+
+        if self
+            .reports
+            .values()
+            .any(|r| r.property_id == property_id && r.document_hash == document_hash)
+        {
+            return Err(anyhow::anyhow!(
+                "Report {} is already attached to {}",
+                document_hash,
+                property_id
+            ));
+        }
+
+        self.next_report_id += 1;
+        let report = ProfessionalReport {
+            report_id: self.next_report_id,
+            property_id: property_id.to_string(),
+            professional_id: input.professional_id,
+            role: professional.role,
+            document_hash,
+            appraised_value: input.appraised_value,
+            signature: hex::encode(signature),
+            attached_at: chrono::Utc::now().timestamp(),
+        };
+
+        self.reports.insert(report.report_id, report.clone());
+        self.save()?;
+        Ok(report)
+    }
+
+    pub fn reports(&self, property_id: &str) -> Vec<&ProfessionalReport> {
+        self.reports
+            .values()
+            .filter(|r| r.property_id == property_id)
+            .collect()
+    }
+
+    pub fn is_current(&self, report: &ProfessionalReport) -> bool {
+        self.professionals
+            .get(&report.professional_id)
+            .map(|p| p.status == ProfessionalStatus::Active)
+            .unwrap_or(false)
+    }
+
+    /// A report that may be cited for the property: attached to it and signed
+    /// by a professional who is still active.
+    pub fn citable(&self, property_id: &str, report_id: u64) -> Result<&ProfessionalReport> {
+        let report = self
+            .reports
+            .get(&report_id)
+            .filter(|r| r.property_id == property_id)
+            .ok_or_else(|| {
+                anyhow::anyhow!("Report {} is not attached to {}", report_id, property_id)
+            })?;
+        if !self.is_current(report) {
+            return Err(anyhow::anyhow!(
+                "Report {} was signed by a revoked professional",
+                report_id
+            ));
+        }
+        Ok(report)
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    RegisterProfessional {
+        input: ProfessionalInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.register_professional(op.input, op.api_key.as_deref());
+
+    RevokeProfessional {
+        professional_id: u64,
+        input: RevokeProfessionalInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.revoke_professional(op.professional_id, op.input, op.api_key.as_deref());
+
+    ListProfessionals {
+        role: Option<ProfessionalRole>,
+    } -> serde_json::Value;
+    |client, op| client.list_professionals(op.role);
+
+    GetProfessional {
+        professional_id: u64,
+    } -> serde_json::Value;
+    |client, op| client.get_professional(op.professional_id);
+
+    AttachProfessionalReport {
+        property_id: String,
+        input: ReportInput,
+    } -> serde_json::Value;
+    |client, op| client.attach_professional_report(&op.property_id, op.input);
+
+    ListProfessionalReports {
+        property_id: String,
+    } -> serde_json::Value;
+    |client, op| client.list_professional_reports(&op.property_id)
+}
+
+impl MidenClientWrapper {
+    /// Registers a professional. Needs an API key bound to their account.
+    pub fn register_professional(
+        &mut self,
+        input: ProfessionalInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let account_hex = self.account_hex(&input.account_id)?;
+        self.authorize_account(api_key, &account_hex, "professional")?;
+
+        let professional = self.professionals.register(&account_hex, input)?;
+        tracing::info!(
+            "Registered {:?} {} ({})",
+            professional.role,
+            professional.professional_id,
+            professional.name
+        );
+        Ok(serde_json::json!(professional))
+    }
+
+    /// Revokes a professional. Needs an API key bound to their account, or an
+    /// arbiter's.
+    pub fn revoke_professional(
+        &mut self,
+        professional_id: u64,
+        input: RevokeProfessionalInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let account_hex = self.professionals.get(professional_id)?.account_id.clone();
+        self.authorize_account(api_key, &account_hex, "professional")?;
+
+        let professional = self.professionals.revoke(professional_id, input.reason)?;
+        tracing::info!("Revoked professional {}", professional_id);
+        Ok(serde_json::json!(professional))
+    }
+
+    pub fn list_professionals(&self, role: Option<ProfessionalRole>) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self.professionals.list(role)))
+    }
+
+    pub fn get_professional(&self, professional_id: u64) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self
+            .professionals
+            .get(professional_id)?))
+    }
+
+    /// Attaches a signed report; the professional's signature is the
+    /// authorization.
+    pub fn attach_professional_report(
+        &mut self,
+        property_id: &str,
+        input: ReportInput,
+    ) -> Result<serde_json::Value> {
+        if !self.records.properties.contains_key(property_id) {
+            return Err(anyhow::anyhow!(
+                "Property {} has not been minted",
+                property_id
+            ));
+        }
+
+        let report = self.professionals.attach_report(property_id, input)?;
+        tracing::info!(
+            "{:?} report {} attached to {} by professional {}",
+            report.role,
+            report.report_id,
+            property_id,
+            report.professional_id
+        );
+        Ok(self.report_json(&report))
+    }
+
+    pub fn list_professional_reports(&self, property_id: &str) -> Result<serde_json::Value> {
+        let reports: Vec<_> = self
+            .professionals
+            .reports(property_id)
+            .into_iter()
+            .map(|r| self.report_json(r))
+            .collect();
+        Ok(serde_json::json!(reports))
+    }
+
+    /// Summaries of the cited reports, for inclusion in a proof. Fails if any
+    /// report cannot be cited for the property.
+    pub(crate) fn cited_reports(
+        &self,
+        property_id: &str,
+        report_ids: &[u64],
+    ) -> Result<Vec<serde_json::Value>> {
+        report_ids
+            .iter()
+            .map(|&report_id| {
+                let report = self.professionals.citable(property_id, report_id)?;
+                Ok(serde_json::json!({
+                    "report_id": report.report_id,
+                    "role": report.role,
+                    "professional_id": report.professional_id,
+                    "document_hash": report.document_hash,
+                    "appraised_value": report.appraised_value,
+                }))
+            })
+            .collect()
+    }
+
+    fn report_json(&self, report: &ProfessionalReport) -> serde_json::Value {
+        let mut value = serde_json::json!(report);
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert(
+                "current".into(),
+                serde_json::json!(self.professionals.is_current(report)),
+            );
+        }
+        value
+    }
+}
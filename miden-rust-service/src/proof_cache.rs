@@ -0,0 +1,162 @@
+// src/proof_cache.rs
+//
+// Content-addressed cache for generated ZK proofs
+//
+// Key: SHA-256 over (program ID, proof inputs), so private inputs such as net
+// worth never appear in the cache in plaintext.
+// Value: the proof artifact exactly as returned by the generate endpoint.
+//
+// Entries expire after a TTL and are dropped when their program is upgraded
+// (a different program ID simply never matches the old keys, and
+// `invalidate_program` evicts them eagerly).
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+struct CacheEntry {
+    program: String,
+    artifact: serde_json::Value,
+    inserted_at: Instant,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ProofCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub insertions: u64,
+    pub expirations: u64,
+    pub evictions: u64,
+    pub invalidations: u64,
+}
+
+pub struct ProofCache {
+    entries: HashMap<String, CacheEntry>,
+    ttl: Duration,
+    max_entries: usize,
+    stats: ProofCacheStats,
+}
+
+impl ProofCache {
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            ttl,
+            max_entries,
+            stats: ProofCacheStats::default(),
+        }
+    }
+
+    /// Derives the cache key for a program and its inputs.
+    pub fn key(program: &str, inputs: &[&str]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update((program.len() as u32).to_le_bytes());
+        hasher.update(program.as_bytes());
+        for input in inputs {
+            hasher.update((input.len() as u32).to_le_bytes());
+            hasher.update(input.as_bytes());
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    /// Returns a cached artifact (marked with `"cached": true`) if present and fresh.
+    pub fn get(&mut self, key: &str) -> Option<serde_json::Value> {
+        let expired = match self.entries.get(key) {
+            Some(entry) => entry.inserted_at.elapsed() > self.ttl,
+            None => {
+                self.stats.misses += 1;
+                return None;
+            }
+        };
+
+        if expired {
+            self.entries.remove(key);
+            self.stats.expirations += 1;
+            self.stats.misses += 1;
+            return None;
+        }
+
+        self.stats.hits += 1;
+        let mut artifact = self.entries.get(key)?.artifact.clone();
+        if let Some(obj) = artifact.as_object_mut() {
+            obj.insert("cached".to_string(), serde_json::Value::Bool(true));
+        }
+        Some(artifact)
+    }
+
+    pub fn insert(&mut self, key: String, program: &str, artifact: serde_json::Value) {
+        if self.max_entries == 0 {
+            return;
+        }
+
+        if self.entries.len() >= self.max_entries && !self.entries.contains_key(&key) {
+            self.evict_oldest();
+        }
+
+        self.entries.insert(
+            key,
+            CacheEntry {
+                program: program.to_string(),
+                artifact,
+                inserted_at: Instant::now(),
+            },
+        );
+        self.stats.insertions += 1;
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.inserted_at)
+            .map(|(key, _)| key.clone());
+
+        if let Some(key) = oldest {
+            self.entries.remove(&key);
+            self.stats.evictions += 1;
+        }
+    }
+
+    /// Drops every entry produced by the given program. Returns the number removed.
+    pub fn invalidate_program(&mut self, program: &str) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|_, entry| entry.program != program);
+        let removed = before - self.entries.len();
+        self.stats.invalidations += removed as u64;
+        removed
+    }
+
+    /// Drops every entry. Returns the number removed.
+    pub fn clear(&mut self) -> usize {
+        let removed = self.entries.len();
+        self.entries.clear();
+        self.stats.invalidations += removed as u64;
+        removed
+    }
+
+    pub fn stats(&self) -> serde_json::Value {
+        let lookups = self.stats.hits + self.stats.misses;
+        let hit_rate = if lookups == 0 {
+            0.0
+        } else {
+            self.stats.hits as f64 / lookups as f64
+        };
+
+        let mut per_program: HashMap<&str, usize> = HashMap::new();
+        for entry in self.entries.values() {
+            *per_program.entry(entry.program.as_str()).or_default() += 1;
+        }
+
+        serde_json::json!({
+            "entries": self.entries.len(),
+            "max_entries": self.max_entries,
+            "ttl_secs": self.ttl.as_secs(),
+            "hit_rate": hit_rate,
+            "counters": self.stats,
+            "entries_per_program": per_program,
+        })
+    }
+}
@@ -0,0 +1,451 @@
+// src/property_recovery.rs
+//
+// Re-issuing a property after its owner lost access
+//
+// When an owner loses the keys of the account holding a property's token, the
+// token cannot move again. The governance flow below moves the property to a
+// new account of the owner under the approval of several admins:
+//
+// 1. An admin files the request, with the evidence in its reason:
+//      POST /admin/properties/:property_id/reissue-requests
+//      {"new_owner_account_id", "reason"}
+//    The property is flagged (`lost_access` on its record) and the request is
+//    parked as an approval (approvals.rs); the call answers 202 with the
+//    approval ID. While it is pending the property cannot be transferred,
+//    escrowed or re-issued directly. Refused for retired properties and
+//    properties in an open escrow.
+// 2. PROPERTY_REISSUE_APPROVERS other admins confirm it, each with
+//      POST /approvals/:approval_id/confirm
+//    Every confirmation but the last answers 202. Unconfirmed requests expire
+//    like other approvals (ESCROW_APPROVAL_TTL_SECS).
+// 3. The last confirmation burns and re-mints. The previous token is burned
+//    as a retirement burns it (property_retirement.rs) when the note the
+//    records track for it was consumed by the old owner, whose vault in the
+//    client store still holds it, and the service can still sign for that
+//    account. A balance of the faucet token in that vault is not enough on its
+//    own: it may be the owner's settlement tokens or another property's token.
+//    Otherwise the previous token is void: it stays where it is, and the
+//    records and the issuance its note's serial number commits to
+//    (property_registry.rs) no longer match it. The next issuance is then
+//    minted to the new account.
+//
+// Every step lands in the property's provenance: the request, each
+// confirmation, the burn or voiding of the previous token, the re-issue (or
+// its failure) with the admin behind it. Direct admin re-issues and
+// retirements are recorded there too. GET /properties/:property_id/provenance
+// returns it with the flag and the re-issue history. The `lost_access` flag
+// stays on the record after the re-issue, with the time it completed.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    approvals::{Approval, ApprovalRequired, ApprovalStatus, ApprovalSubject, Approver},
+    escrow::EscrowAuthError,
+    property_registry::PropertyReissue,
+    property_retirement::{property_token_note_id, PropertyRetirement},
+    reconcile::parse_hex_account_id,
+    MidenClientWrapper, PROPERTY_MINT_AMOUNT,
+};
+
+/// What an approval of a re-issue after lost access carries out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PropertyReissueRequest {
+    pub property_id: String,
+    /// Hex AccountId the property is re-issued to
+    pub new_owner_account_id: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LostAccessInput {
+    /// "alice", "bob" or a hex AccountId the owner holds the keys of
+    pub new_owner_account_id: String,
+    pub reason: String,
+}
+
+/// Flag on a property whose owner lost access to the account holding it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LostAccess {
+    pub approval_id: u64,
+    pub new_owner_account_id: String,
+    pub reported_at: i64,
+    /// Set once the property is re-issued
+    #[serde(default)]
+    pub reissued_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvenanceEvent {
+    ReissueRequested,
+    ReissueConfirmed,
+    /// The previous token was sent to the faucet to be burned
+    TokenBurned,
+    /// The previous token could not be burned and is only void in the records
+    TokenVoided,
+    Reissued,
+    ReissueFailed,
+    Retired,
+}
+
+/// One step in the life of a property token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProvenanceEntry {
+    pub event: ProvenanceEvent,
+    pub at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_id: Option<u64>,
+    /// Admin behind the step; None for the service's own steps and owners'
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub by: Option<Approver>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl ProvenanceEntry {
+    pub fn new(event: ProvenanceEvent) -> Self {
+        Self {
+            event,
+            at: chrono::Utc::now().timestamp(),
+            approval_id: None,
+            by: None,
+            tx_id: None,
+            note_id: None,
+            detail: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PropertyProvenance {
+    pub property_id: String,
+    pub owner_account_id: String,
+    pub issuance: u32,
+    pub mint_tx_id: String,
+    pub note_id: String,
+    pub lost_access: Option<LostAccess>,
+    pub reissues: Vec<PropertyReissue>,
+    pub retirement: Option<PropertyRetirement>,
+    pub provenance: Vec<ProvenanceEntry>,
+}
+
+// ============================================================================
+// CLIENT INTEGRATION
+// ============================================================================
+
+crate::operations! {
+    /// Files a re-issue after lost access for approval.
+    RequestPropertyReissue {
+        property_id: String,
+        input: LostAccessInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.request_property_reissue(
+        &op.property_id,
+        op.input,
+        op.api_key.as_deref(),
+    );
+
+    GetPropertyProvenance {
+        property_id: String,
+    } -> PropertyProvenance;
+    |client, op| client.property_provenance(&op.property_id)
+}
+
+impl MidenClientWrapper {
+    /// Refuses to act on a retired property or one with a re-issue after
+    /// lost access pending.
+    pub(crate) fn check_property_active(&self, property_id: &str) -> Result<()> {
+        self.check_property_not_retired(property_id)?;
+        let pending = self
+            .records
+            .properties
+            .get(property_id)
+            .and_then(|p| p.lost_access.as_ref())
+            .filter(|flag| flag.reissued_at.is_none())
+            .filter(|flag| {
+                self.approvals.get(flag.approval_id).is_ok_and(|a| {
+                    matches!(
+                        a.status,
+                        ApprovalStatus::Pending | ApprovalStatus::Confirmed
+                    )
+                })
+            });
+        match pending {
+            Some(flag) => Err(anyhow::anyhow!(
+                "Conflict: property {} is waiting to be re-issued to {} (approval {})",
+                property_id,
+                flag.new_owner_account_id,
+                flag.approval_id
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Flags the property and parks the re-issue for approval; fails with
+    /// 202 (see module docs).
+    pub fn request_property_reissue(
+        &mut self,
+        property_id: &str,
+        input: LostAccessInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let requested_by = Approver::from(self.admin_principal(api_key, "property re-issues")?);
+        let property = self
+            .minted_property(property_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Property {} has not been minted", property_id))?;
+        let property_id = property.property_id;
+        self.approvals.expire(chrono::Utc::now().timestamp())?;
+        self.check_property_active(&property_id)?;
+        let open_escrows = self.open_property_escrows(&property_id);
+        if !open_escrows.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Encumbered: property {} is in open escrow(s) {}",
+                property_id,
+                open_escrows.join(", ")
+            ));
+        }
+
+        let new_owner_hex = self.account_hex(&input.new_owner_account_id)?;
+        parse_hex_account_id(&new_owner_hex)?;
+        if new_owner_hex.eq_ignore_ascii_case(&property.owner_account_id) {
+            return Err(anyhow::anyhow!(
+                "Property {} is already held by {}",
+                property_id,
+                new_owner_hex
+            ));
+        }
+
+        let reason = input.reason.trim().to_string();
+        let approval = self.approvals.request(
+            ApprovalSubject::PropertyReissue(PropertyReissueRequest {
+                property_id: property_id.clone(),
+                new_owner_account_id: new_owner_hex.clone(),
+                reason: reason.clone(),
+            }),
+            PROPERTY_MINT_AMOUNT,
+            requested_by.clone(),
+            self.config.property_reissue_approvers,
+            self.config.escrow_approval_ttl.as_secs() as i64,
+        )?;
+        let now = chrono::Utc::now().timestamp();
+        self.records.set_lost_access(
+            &property_id,
+            LostAccess {
+                approval_id: approval.approval_id,
+                new_owner_account_id: new_owner_hex.clone(),
+                reported_at: now,
+                reissued_at: None,
+            },
+        );
+        self.records.record_provenance(
+            &property_id,
+            ProvenanceEntry {
+                approval_id: Some(approval.approval_id),
+                by: Some(requested_by),
+                detail: Some(format!("to {}: {}", new_owner_hex, reason)),
+                ..ProvenanceEntry::new(ProvenanceEvent::ReissueRequested)
+            },
+        );
+        tracing::info!(
+            "Re-issue of {} to {} requested by key {}; needs approval {}",
+            property_id,
+            new_owner_hex,
+            approval.requested_by.key_id,
+            approval.approval_id
+        );
+
+        Err(ApprovalRequired(format!(
+            "re-issue of property {} needs {} more admin(s); confirm approval {} with \
+             POST /approvals/{}/confirm",
+            property_id,
+            approval.confirmations_required,
+            approval.approval_id,
+            approval.approval_id
+        ))
+        .into())
+    }
+
+    /// Confirms a pending re-issue approval (see `confirm_approval`); the last
+    /// confirmation carries out the re-issue.
+    pub(crate) async fn confirm_property_reissue(
+        &mut self,
+        approval: &Approval,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let request = approval.property_reissue.clone().ok_or_else(|| {
+            anyhow::anyhow!("Approval {} is not a re-issue", approval.approval_id)
+        })?;
+        let approver = Approver::from(self.admin_principal(api_key, "property re-issues")?);
+        if approver.key_id == approval.requested_by.key_id {
+            return Err(EscrowAuthError::Forbidden(format!(
+                "approval {} must be confirmed by other admins than the requester",
+                approval.approval_id
+            ))
+            .into());
+        }
+
+        let approval = self
+            .approvals
+            .confirm(approval.approval_id, approver.clone())?;
+        self.records.record_provenance(
+            &request.property_id,
+            ProvenanceEntry {
+                approval_id: Some(approval.approval_id),
+                by: Some(approver.clone()),
+                ..ProvenanceEntry::new(ProvenanceEvent::ReissueConfirmed)
+            },
+        );
+        tracing::info!(
+            "Approval {} (re-issue of {}) confirmed by API key {} ({})",
+            approval.approval_id,
+            request.property_id,
+            approver.key_id,
+            approver.label
+        );
+        if approval.status == ApprovalStatus::Pending {
+            let missing = approval.confirmations_required - approval.endorsed_by.len() as u32;
+            return Err(ApprovalRequired(format!(
+                "re-issue of property {} needs {} more admin(s) to confirm approval {}",
+                request.property_id, missing, approval.approval_id
+            ))
+            .into());
+        }
+
+        let result = self.execute_property_reissue(&approval, &request).await;
+        if let Err(e) = &result {
+            self.records.record_provenance(
+                &request.property_id,
+                ProvenanceEntry {
+                    approval_id: Some(approval.approval_id),
+                    detail: Some(e.to_string()),
+                    ..ProvenanceEntry::new(ProvenanceEvent::ReissueFailed)
+                },
+            );
+        }
+        let approval = self.approvals.finish(approval.approval_id, &result)?;
+        result?;
+
+        Ok(serde_json::json!(approval))
+    }
+
+    /// Burns (or voids) the previous token and mints the next issuance to the
+    /// new owner. Returns the mint transaction ID.
+    async fn execute_property_reissue(
+        &mut self,
+        approval: &Approval,
+        request: &PropertyReissueRequest,
+    ) -> Result<String> {
+        let property = self
+            .records
+            .properties
+            .get(&request.property_id)
+            .cloned()
+            .ok_or_else(|| {
+                anyhow::anyhow!("Property {} has not been minted", request.property_id)
+            })?;
+        self.check_property_not_retired(&property.property_id)?;
+        let approval_id = Some(approval.approval_id);
+
+        let shortfall = self.property_token_shortfall(&property).await?;
+        let burn = match shortfall {
+            None => Some(self.send_property_to_burn(&property).await),
+            Some(_) => None,
+        };
+        let burn_tx_id = match burn {
+            Some(Ok((send_tx_id, burn_note_id))) => {
+                let burned = self.consume_burn_note(&burn_note_id).await;
+                let (tx_id, detail) = match burned {
+                    Ok(burn_tx_id) => (burn_tx_id, None),
+                    Err(e) => (
+                        send_tx_id,
+                        Some(format!("sent to the faucet, not burned yet: {}", e)),
+                    ),
+                };
+                self.records.record_provenance(
+                    &property.property_id,
+                    ProvenanceEntry {
+                        approval_id,
+                        tx_id: Some(tx_id.clone()),
+                        note_id: Some(burn_note_id),
+                        detail,
+                        ..ProvenanceEntry::new(ProvenanceEvent::TokenBurned)
+                    },
+                );
+                Some(tx_id)
+            }
+            not_burned => {
+                let why = match not_burned {
+                    Some(Err(e)) => format!("burn failed: {}", e),
+                    _ => shortfall.unwrap_or_default(),
+                };
+                let token_note_id = property_token_note_id(&property).unwrap_or(&property.note_id);
+                self.records.record_provenance(
+                    &property.property_id,
+                    ProvenanceEntry {
+                        approval_id,
+                        note_id: Some(token_note_id.to_string()),
+                        detail: Some(format!("issuance {} is void; {}", property.issuance, why)),
+                        ..ProvenanceEntry::new(ProvenanceEvent::TokenVoided)
+                    },
+                );
+                None
+            }
+        };
+
+        let reissue = PropertyReissue {
+            issuance: property.issuance + 1,
+            previous_owner_account_id: property.owner_account_id.clone(),
+            previous_mint_tx_id: property.mint_tx_id.clone(),
+            previous_note_id: property.note_id.clone(),
+            reason: request.reason.clone(),
+            reissued_by: approval.requested_by.key_id,
+            reissued_at: chrono::Utc::now().timestamp(),
+            approval_id,
+            approved_by: approval
+                .endorsed_by
+                .iter()
+                .chain(approval.confirmed_by.as_ref())
+                .map(|a| a.key_id)
+                .collect(),
+            burn_tx_id,
+        };
+        let mint_tx_id = self
+            .mint_next_issuance(
+                &property.property_id,
+                &request.new_owner_account_id,
+                reissue,
+                approval.confirmed_by.clone(),
+            )
+            .await?;
+
+        if let Some(mut flag) = property.lost_access {
+            flag.reissued_at = Some(chrono::Utc::now().timestamp());
+            self.records.set_lost_access(&property.property_id, flag);
+        }
+        Ok(mint_tx_id)
+    }
+
+    pub fn property_provenance(&self, property_id: &str) -> Result<PropertyProvenance> {
+        let property = self
+            .minted_property(property_id)
+            .ok_or_else(|| anyhow::anyhow!("Property {} has not been minted", property_id))?;
+        Ok(PropertyProvenance {
+            property_id: property.property_id.clone(),
+            owner_account_id: property.owner_account_id.clone(),
+            issuance: property.issuance,
+            mint_tx_id: property.mint_tx_id.clone(),
+            note_id: property.note_id.clone(),
+            lost_access: property.lost_access.clone(),
+            reissues: property.reissues.clone(),
+            retirement: property.retirement.clone(),
+            provenance: property.provenance.clone(),
+        })
+    }
+}
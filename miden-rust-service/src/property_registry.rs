@@ -0,0 +1,243 @@
+// src/property_registry.rs
+//
+// One token per property
+//
+// A property is minted once. Its ID is compared in canonical form: ASCII
+// letters lowercased and '_' and '.' read as '-', so "LOT-12", "lot_12" and
+// "Lot.12" are the same property. A mint (POST /mint-property, coalesced
+// batches, mint jobs, retries) of a property already in the records is refused
+// with 409 and a pointer to the existing token: its recorded ID, mint
+// transaction, note and owner.
+//
+// The mint note's serial number is derived from the canonical ID instead of
+// drawn at random:
+//   serial_num = RPO256("obscura-property-mint" || canonical_id
+//                       || issuance as u32, big-endian)
+// The recipient commits to the serial number and the note ID to the recipient,
+// so anyone with the note's details can recompute which property, and which
+// issuance of it, the note was minted for. Only the note is bound this way.
+// The token it carries is PROPERTY_MINT_AMOUNT of the faucet's fungible token,
+// which nothing on-chain tells apart from other tokens of the faucet once the
+// note is consumed; the records track which note holds which property's token
+// (property_retirement.rs). This build has no non-fungible faucet that could
+// put the property ID in an asset of its own.
+//
+// Legitimate re-issues (a token lost with its account, a mint sent to the
+// wrong account) go through an admin:
+//   POST /admin/properties/:property_id/reissue {"owner_account_id", "reason"}
+// mints the next issuance to the given owner (by default the recorded one)
+// and moves the record to the new token, keeping the replaced mint in its
+// re-issue history. Location, media and jurisdiction stay. The previous token
+// is not burned here; it stays wherever it is. Retired properties
+// (property_retirement.rs) and properties with a pending re-issue after lost
+// access (property_recovery.rs) are not re-issued this way.
+
+use anyhow::Result;
+use miden_client::{account::AccountId, crypto::Rpo256, Word};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account_id_to_hex,
+    approvals::Approver,
+    property_recovery::{ProvenanceEntry, ProvenanceEvent},
+    read_cache::Touched,
+    records::PropertyRecord,
+    MidenClientWrapper,
+};
+
+const PROPERTY_MINT_DOMAIN: &[u8] = b"obscura-property-mint";
+
+/// A replaced mint of a property.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PropertyReissue {
+    /// Issuance minted by the re-issue
+    pub issuance: u32,
+    pub previous_owner_account_id: String,
+    pub previous_mint_tx_id: String,
+    pub previous_note_id: String,
+    pub reason: String,
+    /// Admin API key that ordered the re-issue
+    pub reissued_by: u64,
+    pub reissued_at: i64,
+    /// Approval of a re-issue after lost access (property_recovery.rs) and
+    /// the admins who confirmed it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub approved_by: Vec<u64>,
+    /// Faucet transaction burning the previous token; None when it was not
+    /// burned and is only void in the records
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burn_tx_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PropertyReissueInput {
+    /// "alice", "bob" or a hex AccountId; defaults to the recorded owner
+    #[serde(default)]
+    pub owner_account_id: Option<String>,
+    pub reason: String,
+}
+
+/// The form property IDs are compared in (see module docs).
+pub fn canonical_property_id(property_id: &str) -> String {
+    property_id
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '_' | '.' => '-',
+            c => c.to_ascii_lowercase(),
+        })
+        .collect()
+}
+
+/// Serial number of the mint note of a property's `issuance`.
+pub fn property_mint_serial_num(property_id: &str, issuance: u32) -> Word {
+    let mut preimage = PROPERTY_MINT_DOMAIN.to_vec();
+    preimage.extend(canonical_property_id(property_id).as_bytes());
+    preimage.extend(issuance.to_be_bytes());
+    Rpo256::hash(&preimage)
+}
+
+// ============================================================================
+// CLIENT INTEGRATION
+// ============================================================================
+
+crate::operations! {
+    /// Mints the next issuance of a minted property.
+    ReissueProperty {
+        property_id: String,
+        input: PropertyReissueInput,
+        api_key: Option<String>,
+    } -> PropertyRecord;
+    // Without an owner in the input the token goes to the recorded one
+    touched: |op| {
+        Some(match &op.input.owner_account_id {
+            Some(owner) => Touched::Accounts(vec![owner.clone(), "faucet".to_string()]),
+            None => Touched::All,
+        })
+    };
+    |client, op| client.reissue_property(&op.property_id, op.input, op.api_key.as_deref()).await
+}
+
+impl MidenClientWrapper {
+    /// The recorded property with the same canonical ID, if any.
+    pub fn minted_property(&self, property_id: &str) -> Option<&PropertyRecord> {
+        if let Some(property) = self.records.properties.get(property_id) {
+            return Some(property);
+        }
+        let canonical = canonical_property_id(property_id);
+        self.records
+            .properties
+            .values()
+            .find(|p| canonical_property_id(&p.property_id) == canonical)
+    }
+
+    /// Refuses to mint a property that already has a token.
+    pub(crate) fn check_property_unminted(&self, property_id: &str) -> Result<()> {
+        match self.minted_property(property_id) {
+            Some(existing) => Err(anyhow::anyhow!(
+                "Conflict: property {} is already minted as {} (tx {}, note {}, owner {}); \
+                 re-issues go through POST /admin/properties/{}/reissue",
+                property_id,
+                existing.property_id,
+                existing.mint_tx_id,
+                existing.note_id,
+                existing.owner_account_id,
+                existing.property_id
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Mints the next issuance of a property and moves its record to the new
+    /// token (see module docs).
+    pub async fn reissue_property(
+        &mut self,
+        property_id: &str,
+        input: PropertyReissueInput,
+        api_key: Option<&str>,
+    ) -> Result<PropertyRecord> {
+        let reissued_by = Approver::from(self.admin_principal(api_key, "property re-issues")?);
+        let previous = self
+            .minted_property(property_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Property {} has not been minted", property_id))?;
+        self.check_property_active(&previous.property_id)?;
+        let owner = input
+            .owner_account_id
+            .unwrap_or_else(|| previous.owner_account_id.clone());
+
+        let reissue = PropertyReissue {
+            issuance: previous.issuance + 1,
+            previous_owner_account_id: previous.owner_account_id.clone(),
+            previous_mint_tx_id: previous.mint_tx_id.clone(),
+            previous_note_id: previous.note_id.clone(),
+            reason: input.reason.trim().to_string(),
+            reissued_by: reissued_by.key_id,
+            reissued_at: chrono::Utc::now().timestamp(),
+            approval_id: None,
+            approved_by: Vec::new(),
+            burn_tx_id: None,
+        };
+        self.mint_next_issuance(&previous.property_id, &owner, reissue, Some(reissued_by))
+            .await?;
+        self.records
+            .properties
+            .get(&previous.property_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Property {} has not been minted", property_id))
+    }
+
+    /// Mints `reissue.issuance` of a property to `owner` and moves the record
+    /// to it. Returns the mint transaction ID.
+    pub(crate) async fn mint_next_issuance(
+        &mut self,
+        property_id: &str,
+        owner: &str,
+        mut reissue: PropertyReissue,
+        by: Option<Approver>,
+    ) -> Result<String> {
+        let op_id = self
+            .records
+            .begin_operation("reissue_property", property_id);
+        let result = self
+            .submit_property_mint(property_id, owner, reissue.issuance)
+            .await;
+        let tx_id = result.as_ref().ok().map(|(tx_id, _, _)| tx_id.clone());
+        self.records.finish_operation(op_id, &result, tx_id);
+        let (mint_tx_id, note_id, owner_account_id): (String, String, AccountId) = result?;
+
+        let owner_hex = account_id_to_hex(owner_account_id);
+        let issuance = reissue.issuance;
+        reissue.reissued_at = chrono::Utc::now().timestamp();
+        self.records
+            .record_reissue(property_id, &owner_hex, &mint_tx_id, &note_id, reissue);
+        self.records.expect_note(
+            &note_id,
+            &owner_hex,
+            "property-mint",
+            Some(mint_tx_id.clone()),
+        );
+        self.records.record_provenance(
+            property_id,
+            ProvenanceEntry {
+                by,
+                tx_id: Some(mint_tx_id.clone()),
+                note_id: Some(note_id),
+                detail: Some(format!("issuance {} to {}", issuance, owner_hex)),
+                ..ProvenanceEntry::new(ProvenanceEvent::Reissued)
+            },
+        );
+        self.index_property(property_id);
+
+        tracing::info!(
+            "Property {} re-issued (issuance {}) to {}: tx {}",
+            property_id,
+            issuance,
+            owner_hex,
+            mint_tx_id
+        );
+        Ok(mint_tx_id)
+    }
+}
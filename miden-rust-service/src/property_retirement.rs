@@ -0,0 +1,423 @@
+// src/property_retirement.rs
+//
+// Retiring property tokens
+//
+// POST /properties/:property_id/retire {"reason"} takes a property off the
+// chain for good, e.g. after demolition or de-tokenization: its token is
+// burned and the property is marked retired in the records. The caller's API
+// key must be bound to the owner (or be an arbiter's) when API-key auth is
+// enforced. Retiring is refused with 409 while an escrow of the property is
+// open (created, funded or disputed) or a lien on it is active (liens.rs);
+// settle, refund or discharge those first.
+//
+// Burning takes two transactions. The owner sends the token to the faucet in
+// a BURN note, the standard note the faucet burns the assets of when it
+// consumes it, with a serial number derived from the property like its mint
+// note's (property_registry.rs):
+//   serial_num = RPO256("obscura-property-retire" || canonical_id
+//                       || issuance as u32, big-endian)
+// After the propagation wait the faucet consumes the note. The owner must be
+// an account the service holds the key of, and the property's own token must
+// be in its vault: the note the records track for it (the mint note, or the
+// last transfer note) was consumed by the owner, and the vault holds
+// PROPERTY_MINT_AMOUNT of the faucet token for this and every other live
+// property delivered to it that way. A balance of the faucet token alone, such
+// as settlement tokens or the tokens of the owner's other properties, does
+// not let a property be retired. The burn is journaled when the note is sent,
+// crediting the owner and debiting the faucet's issuance account.
+//
+// If the faucet's consumption fails, the property stays retired with its burn
+// note on the way; retiring it again completes the burn.
+//
+// A retired property cannot be transferred, escrowed or re-issued, and its ID
+// stays taken for mints. The burn is recorded in the property's provenance
+// (property_recovery.rs).
+
+use anyhow::Result;
+use miden_client::{
+    asset::FungibleAsset, crypto::Rpo256, note::NoteId, transaction::TransactionRequestBuilder,
+    Word,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account_id_to_hex, client,
+    escrow::EscrowStatus,
+    ledger::{issuance_account, JournalKind, Posting},
+    property_recovery::{ProvenanceEntry, ProvenanceEvent},
+    property_registry::canonical_property_id,
+    read_cache::Touched,
+    reconcile::parse_hex_account_id,
+    records::PropertyRecord,
+    MidenClientWrapper, PROPERTY_MINT_AMOUNT,
+};
+
+const PROPERTY_RETIRE_DOMAIN: &[u8] = b"obscura-property-retire";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PropertyRetirement {
+    pub reason: String,
+    /// Account the token was burned from
+    pub owner_account_id: String,
+    pub burn_note_id: String,
+    /// Transaction sending the token to the faucet
+    pub send_tx_id: String,
+    /// Transaction of the faucet consuming the burn note; None until then
+    #[serde(default)]
+    pub burn_tx_id: Option<String>,
+    pub retired_at: i64,
+    #[serde(default)]
+    pub burned_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PropertyRetireInput {
+    pub reason: String,
+}
+
+/// The note that delivered a property's token to its current owner: the last
+/// transfer note, or the mint note before any transfer. None while the mint
+/// note is a placeholder.
+pub(crate) fn property_token_note_id(property: &PropertyRecord) -> Option<&str> {
+    match &property.token_note_id {
+        Some(note_id) => Some(note_id),
+        None if property.note_id_placeholder => None,
+        None => Some(&property.note_id),
+    }
+}
+
+/// Serial number of the burn note of a property's `issuance`.
+pub fn property_retire_serial_num(property_id: &str, issuance: u32) -> Word {
+    let mut preimage = PROPERTY_RETIRE_DOMAIN.to_vec();
+    preimage.extend(canonical_property_id(property_id).as_bytes());
+    preimage.extend(issuance.to_be_bytes());
+    Rpo256::hash(&preimage)
+}
+
+// ============================================================================
+// CLIENT INTEGRATION
+// ============================================================================
+
+crate::operations! {
+    /// Burns a property's token and marks the property retired.
+    RetireProperty {
+        property_id: String,
+        input: PropertyRetireInput,
+        api_key: Option<String>,
+    } -> PropertyRecord;
+    // The owner is only known from the records
+    touched: |_op| Some(Touched::All);
+    |client, op| client.retire_property(&op.property_id, op.input, op.api_key.as_deref()).await
+}
+
+impl MidenClientWrapper {
+    /// Refuses to act on a retired property.
+    pub(crate) fn check_property_not_retired(&self, property_id: &str) -> Result<()> {
+        match self
+            .records
+            .properties
+            .get(property_id)
+            .and_then(|p| p.retirement.as_ref())
+        {
+            Some(retirement) => Err(anyhow::anyhow!(
+                "Conflict: property {} was retired ({}); its token was sent to be burned \
+                 in note {}",
+                property_id,
+                retirement.reason,
+                retirement.burn_note_id
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Escrows of a property that are created, funded or disputed.
+    pub(crate) fn open_property_escrows(&self, property_id: &str) -> Vec<String> {
+        let canonical = canonical_property_id(property_id);
+        self.records
+            .escrows
+            .values()
+            .filter(|e| {
+                e.property_id
+                    .as_deref()
+                    .is_some_and(|id| canonical_property_id(id) == canonical)
+                    && matches!(
+                        e.status,
+                        EscrowStatus::Created | EscrowStatus::Funded | EscrowStatus::Disputed
+                    )
+            })
+            .map(|e| e.escrow_account_id.clone())
+            .collect()
+    }
+
+    /// Refuses to retire a property with an open escrow or an active lien.
+    fn check_property_unencumbered(&self, property: &PropertyRecord) -> Result<()> {
+        let open_escrows = self.open_property_escrows(&property.property_id);
+        if !open_escrows.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Encumbered: property {} is in open escrow(s) {}",
+                property.property_id,
+                open_escrows.join(", ")
+            ));
+        }
+
+        let liens: Vec<String> = self
+            .liens
+            .active(&property.property_id)
+            .iter()
+            .map(|l| format!("{} (lender {})", l.lien_id, l.lender_account_id))
+            .collect();
+        if !liens.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Encumbered: property {} has active lien(s) {}",
+                property.property_id,
+                liens.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// Retires a property (see module docs); retiring one whose burn note was
+    /// sent but not consumed completes the burn.
+    pub async fn retire_property(
+        &mut self,
+        property_id: &str,
+        input: PropertyRetireInput,
+        api_key: Option<&str>,
+    ) -> Result<PropertyRecord> {
+        let property = self
+            .minted_property(property_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Property {} has not been minted", property_id))?;
+        let property_id = property.property_id.clone();
+        self.authorize_account(api_key, &property.owner_account_id, "owner")?;
+
+        let mut retirement = match property.retirement.clone() {
+            Some(retirement) if retirement.burned_at.is_some() => {
+                return Err(anyhow::anyhow!(
+                    "Conflict: property {} was already retired (burn tx {})",
+                    property_id,
+                    retirement.burn_tx_id.unwrap_or_default()
+                ));
+            }
+            Some(retirement) => retirement,
+            None => {
+                self.check_property_unencumbered(&property)?;
+                let op_id = self
+                    .records
+                    .begin_operation("retire_property", &property_id);
+                let result = self.send_property_to_burn(&property).await;
+                let tx_id = result.as_ref().ok().map(|(tx_id, _)| tx_id.clone());
+                self.records.finish_operation(op_id, &result, tx_id);
+                let (send_tx_id, burn_note_id) = result?;
+
+                let retirement = PropertyRetirement {
+                    reason: input.reason.trim().to_string(),
+                    owner_account_id: property.owner_account_id.clone(),
+                    burn_note_id,
+                    send_tx_id,
+                    burn_tx_id: None,
+                    retired_at: chrono::Utc::now().timestamp(),
+                    burned_at: None,
+                };
+                self.records
+                    .record_retirement(&property_id, retirement.clone());
+                self.index_property(&property_id);
+                retirement
+            }
+        };
+
+        let burn_tx_id = self
+            .consume_burn_note(&retirement.burn_note_id)
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "Property {} is retired and its token was sent to the faucet in note {}, \
+                     but the faucet did not burn it yet: {}; retire it again to complete the burn",
+                    property_id,
+                    retirement.burn_note_id,
+                    e
+                )
+            })?;
+        tracing::info!(
+            "Property {} retired: token burned in tx {}",
+            property_id,
+            burn_tx_id
+        );
+        self.records.record_provenance(
+            &property_id,
+            ProvenanceEntry {
+                tx_id: Some(burn_tx_id.clone()),
+                note_id: Some(retirement.burn_note_id.clone()),
+                detail: Some(retirement.reason.clone()),
+                ..ProvenanceEntry::new(ProvenanceEvent::Retired)
+            },
+        );
+        retirement.burn_tx_id = Some(burn_tx_id);
+        retirement.burned_at = Some(chrono::Utc::now().timestamp());
+        self.records.record_retirement(&property_id, retirement);
+
+        self.records
+            .properties
+            .get(&property_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Property {} has not been minted", property_id))
+    }
+
+    /// Whether the note the records track for a property's token was
+    /// consumed by its current owner.
+    fn property_token_delivered(&self, property: &PropertyRecord) -> bool {
+        property_token_note_id(property)
+            .and_then(|note_id| self.records.expected_notes.get(note_id))
+            .is_some_and(|note| {
+                note.consumed
+                    && note
+                        .recipient_account_id
+                        .eq_ignore_ascii_case(&property.owner_account_id)
+            })
+    }
+
+    /// Why the owner's vault, in the client store, does not hold the
+    /// property's own token (see module docs); None when it does.
+    pub(crate) async fn property_token_shortfall(
+        &mut self,
+        property: &PropertyRecord,
+    ) -> Result<Option<String>> {
+        let faucet_account_id = self
+            .faucet_account_id
+            .ok_or_else(|| anyhow::anyhow!("Faucet not initialized"))?;
+        let note_id = match property_token_note_id(property) {
+            Some(note_id) => note_id,
+            None => {
+                return Ok(Some(format!(
+                    "its mint note is not known yet (placeholder {}); run POST /admin/reconcile",
+                    property.note_id
+                )))
+            }
+        };
+        if !self.property_token_delivered(property) {
+            return Ok(Some(format!(
+                "note {} carrying its token has not been consumed by {}; consume it first",
+                note_id, property.owner_account_id
+            )));
+        }
+
+        let owner = parse_hex_account_id(&property.owner_account_id)?;
+        self.sync_state().await?;
+        let balance = match self.client.get_account(owner).await? {
+            Some(account) => account
+                .account()
+                .vault()
+                .get_balance(faucet_account_id)
+                .unwrap_or(0),
+            None => {
+                return Ok(Some(format!(
+                    "owner account {} is not tracked by this service",
+                    property.owner_account_id
+                )))
+            }
+        };
+        let held = self
+            .records
+            .properties
+            .values()
+            .filter(|p| {
+                p.retirement.is_none()
+                    && p.owner_account_id
+                        .eq_ignore_ascii_case(&property.owner_account_id)
+                    && self.property_token_delivered(p)
+            })
+            .count() as u64;
+        let needed = held.saturating_mul(PROPERTY_MINT_AMOUNT);
+        if balance < needed {
+            return Ok(Some(format!(
+                "vault of {} holds {} of the faucet token, less than the {} the {} \
+                 property token(s) delivered to it add up to",
+                property.owner_account_id, balance, needed, held
+            )));
+        }
+        Ok(None)
+    }
+
+    /// Refuses to move a property whose token is not in its owner's vault.
+    pub(crate) async fn check_owner_holds_property_token(
+        &mut self,
+        property: &PropertyRecord,
+    ) -> Result<()> {
+        match self.property_token_shortfall(property).await? {
+            Some(shortfall) => Err(anyhow::anyhow!(
+                "Conflict: the token of property {} is not in its owner's vault: {}",
+                property.property_id,
+                shortfall
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Sends the property's token from its owner's vault to the faucet in a
+    /// BURN note. Returns (transaction ID, note ID).
+    pub(crate) async fn send_property_to_burn(
+        &mut self,
+        property: &PropertyRecord,
+    ) -> Result<(String, String)> {
+        let faucet_account_id = self
+            .faucet_account_id
+            .ok_or_else(|| anyhow::anyhow!("Faucet not initialized"))?;
+        let owner = parse_hex_account_id(&property.owner_account_id)?;
+        self.check_owner_holds_property_token(property).await?;
+
+        let note = client::burn_note(
+            owner,
+            faucet_account_id,
+            vec![FungibleAsset::new(faucet_account_id, PROPERTY_MINT_AMOUNT)?.into()],
+            property_retire_serial_num(&property.property_id, property.issuance),
+        )?;
+        let note_id = note.id().to_string();
+        let tx_id = self
+            .client
+            .submit_new_transaction(owner, client::notes_request(vec![note])?)
+            .await?
+            .to_string();
+        tracing::info!(
+            "Token of {} sent to be burned in note {}: tx {}",
+            property.property_id,
+            note_id,
+            tx_id
+        );
+
+        let faucet_hex = account_id_to_hex(faucet_account_id);
+        self.post_ledger_entry(
+            JournalKind::Burn,
+            Posting::transfer(
+                &property.owner_account_id,
+                &issuance_account(&faucet_hex),
+                &faucet_hex,
+                PROPERTY_MINT_AMOUNT,
+            )
+            .to_vec(),
+            &tx_id,
+            Some(&property.property_id),
+        );
+
+        self.wait_for_propagation().await;
+        Ok((tx_id, note_id))
+    }
+
+    /// The faucet consumes a burn note, burning its assets.
+    pub(crate) async fn consume_burn_note(&mut self, note_id: &str) -> Result<String> {
+        let faucet_account_id = self
+            .faucet_account_id
+            .ok_or_else(|| anyhow::anyhow!("Faucet not initialized"))?;
+        let note_id = NoteId::try_from_hex(note_id)
+            .map_err(|e| anyhow::anyhow!("Invalid burn note ID: {}", e))?;
+
+        self.sync_state().await?;
+        let request = TransactionRequestBuilder::new().build_consume_notes(vec![note_id])?;
+        let tx_id = self
+            .client
+            .submit_new_transaction(faucet_account_id, request)
+            .await?
+            .to_string();
+        self.sync_state().await?;
+        Ok(tx_id)
+    }
+}
@@ -0,0 +1,218 @@
+// src/queue_stats.rs
+//
+// Command queue introspection
+//
+// Every chain operation goes through the single client task, one command at a
+// time, so a slow command or a burst of one kind holds up everything behind
+// it. GET /admin/queue shows what the pipeline is doing right now:
+// - backlog: commands waiting for the client task, per command type, with the
+//   age of the oldest one
+// - executing: the command the client task is running and for how long
+// - latency: per command type, queue wait and execution time over the last
+//   LATENCY_WINDOW commands of that type, plus totals since startup
+//
+// The stats are updated by the sending side of the queue (CommandSender in
+// main.rs) and by the client task, and read without going through the client
+// task, so the endpoint answers even while the task is stuck. They live in
+// memory only.
+
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::bench::{millis, Percentiles};
+
+/// Commands per type the latency percentiles are computed over
+const LATENCY_WINDOW: usize = 100;
+
+#[derive(Debug)]
+struct Waiting {
+    kind: String,
+    enqueued_at: Instant,
+}
+
+#[derive(Debug)]
+struct Running {
+    kind: String,
+    queued: Duration,
+    started: Instant,
+    started_at: i64,
+}
+
+#[derive(Debug, Default)]
+struct KindLatency {
+    queue: VecDeque<Duration>,
+    execution: VecDeque<Duration>,
+    executed: u64,
+    dropped: u64,
+}
+
+#[derive(Debug, Default)]
+struct QueueTable {
+    /// Waiting commands by ticket, oldest first
+    waiting: BTreeMap<u64, Waiting>,
+    next_ticket: u64,
+    running: Option<Running>,
+    latency: HashMap<String, KindLatency>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KindBacklog {
+    pub kind: String,
+    pub queued: usize,
+    pub oldest_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutingCommand {
+    pub kind: String,
+    pub started_at: i64,
+    /// Time the command waited before the client task picked it up
+    pub queued_ms: f64,
+    pub elapsed_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KindStats {
+    pub kind: String,
+    /// Commands run since startup
+    pub executed: u64,
+    /// Commands dropped unrun since startup (cancelled while queued)
+    pub dropped: u64,
+    /// Over the last LATENCY_WINDOW runs
+    pub queue_ms: Percentiles,
+    pub execution_ms: Percentiles,
+}
+
+/// Point-in-time view of the command queue (see module docs).
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueSnapshot {
+    pub queued: usize,
+    pub oldest_queued_ms: Option<f64>,
+    /// Per command type, largest backlog first
+    pub backlog: Vec<KindBacklog>,
+    pub executing: Option<ExecutingCommand>,
+    /// Per command type, slowest (p90 execution) first
+    pub latency: Vec<KindStats>,
+}
+
+/// Queue stats shared by the command sender, the client task and handlers.
+#[derive(Debug, Clone, Default)]
+pub struct QueueStats {
+    inner: Arc<Mutex<QueueTable>>,
+}
+
+impl QueueStats {
+    /// Records a command of `kind` entering the queue. Returns its ticket.
+    pub fn enqueue(&self, kind: String) -> u64 {
+        let mut table = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        table.next_ticket += 1;
+        let ticket = table.next_ticket;
+        table.waiting.insert(
+            ticket,
+            Waiting {
+                kind,
+                enqueued_at: Instant::now(),
+            },
+        );
+        ticket
+    }
+
+    /// Records a queued command leaving without being run.
+    pub fn discard(&self, ticket: u64) {
+        let mut table = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(waiting) = table.waiting.remove(&ticket) {
+            table.latency.entry(waiting.kind).or_default().dropped += 1;
+        }
+    }
+
+    /// Records the client task picking up a queued command.
+    pub fn start(&self, ticket: u64) {
+        let mut table = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(waiting) = table.waiting.remove(&ticket) {
+            table.running = Some(Running {
+                kind: waiting.kind,
+                queued: waiting.enqueued_at.elapsed(),
+                started: Instant::now(),
+                started_at: chrono::Utc::now().timestamp(),
+            });
+        }
+    }
+
+    /// Records the end of the running command.
+    pub fn finish(&self) {
+        let mut table = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(running) = table.running.take() else {
+            return;
+        };
+        let latency = table.latency.entry(running.kind).or_default();
+        latency.executed += 1;
+        push_window(&mut latency.queue, running.queued);
+        push_window(&mut latency.execution, running.started.elapsed());
+    }
+
+    pub fn snapshot(&self) -> QueueSnapshot {
+        let table = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+
+        // Tickets are issued in order, so the first waiting command per type
+        // is its oldest
+        let mut backlog: Vec<KindBacklog> = Vec::new();
+        for waiting in table.waiting.values() {
+            match backlog.iter_mut().find(|b| b.kind == waiting.kind) {
+                Some(entry) => entry.queued += 1,
+                None => backlog.push(KindBacklog {
+                    kind: waiting.kind.clone(),
+                    queued: 1,
+                    oldest_ms: millis(now.saturating_duration_since(waiting.enqueued_at)),
+                }),
+            }
+        }
+        backlog.sort_by(|a, b| b.queued.cmp(&a.queued).then(a.kind.cmp(&b.kind)));
+
+        let mut latency: Vec<KindStats> = table
+            .latency
+            .iter()
+            .map(|(kind, stats)| KindStats {
+                kind: kind.clone(),
+                executed: stats.executed,
+                dropped: stats.dropped,
+                queue_ms: Percentiles::of(stats.queue.iter().copied().collect()),
+                execution_ms: Percentiles::of(stats.execution.iter().copied().collect()),
+            })
+            .collect();
+        latency.sort_by(|a, b| {
+            b.execution_ms
+                .p90
+                .total_cmp(&a.execution_ms.p90)
+                .then(a.kind.cmp(&b.kind))
+        });
+
+        QueueSnapshot {
+            queued: table.waiting.len(),
+            oldest_queued_ms: table
+                .waiting
+                .values()
+                .next()
+                .map(|w| millis(now.saturating_duration_since(w.enqueued_at))),
+            backlog,
+            executing: table.running.as_ref().map(|running| ExecutingCommand {
+                kind: running.kind.clone(),
+                started_at: running.started_at,
+                queued_ms: millis(running.queued),
+                elapsed_ms: millis(now.saturating_duration_since(running.started)),
+            }),
+            latency,
+        }
+    }
+}
+
+fn push_window(window: &mut VecDeque<Duration>, sample: Duration) {
+    if window.len() == LATENCY_WINDOW {
+        window.pop_front();
+    }
+    window.push_back(sample);
+}
@@ -0,0 +1,207 @@
+// src/read_cache.rs
+//
+// In-process cache for read-heavy endpoints
+//
+// Balances, account info and consumable-note lists are cached in the HTTP layer,
+// so a repeated read between syncs is answered without enqueuing a client
+// command at all.
+//
+// Validity:
+// - every entry records the sync height it was produced at; once the client
+//   task reports a newer height the entry is stale
+// - any write command touching an account drops that account's entries (and
+//   entries not tied to a single account, such as account info)
+// - a TTL bounds how long an entry can live regardless
+//
+// Account selectors arrive both as names ("alice") and hex IDs; the client task
+// registers the name -> ID mapping so both forms resolve to the same entries.
+
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Accounts affected by a write command.
+#[derive(Debug, Clone)]
+pub enum Touched {
+    Accounts(Vec<String>),
+    All,
+}
+
+#[derive(Debug, Clone)]
+pub struct CachedRead {
+    pub etag: String,
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+struct Entry {
+    account: Option<String>,
+    sync_height: u32,
+    stored_at: Instant,
+    read: CachedRead,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+struct Inner {
+    entries: Mutex<HashMap<String, Entry>>,
+    aliases: Mutex<HashMap<String, String>>,
+    sync_height: AtomicU32,
+    ttl: Duration,
+    max_entries: usize,
+    counters: Counters,
+}
+
+#[derive(Clone)]
+pub struct ReadCache {
+    inner: Arc<Inner>,
+}
+
+impl ReadCache {
+    /// A TTL of zero or `max_entries` of zero disables caching.
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                entries: Mutex::new(HashMap::new()),
+                aliases: Mutex::new(HashMap::new()),
+                sync_height: AtomicU32::new(0),
+                ttl,
+                max_entries,
+                counters: Counters::default(),
+            }),
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        !self.inner.ttl.is_zero() && self.inner.max_entries > 0
+    }
+
+    /// Records that `name` refers to the account with hex ID `account_hex`.
+    pub fn register_alias(&self, name: &str, account_hex: &str) {
+        if let Ok(mut aliases) = self.inner.aliases.lock() {
+            aliases.insert(name.to_lowercase(), account_hex.to_lowercase());
+        }
+    }
+
+    fn canonical(&self, account: &str) -> String {
+        let account = account.trim().to_lowercase();
+        self.inner
+            .aliases
+            .lock()
+            .ok()
+            .and_then(|aliases| aliases.get(&account).cloned())
+            .unwrap_or(account)
+    }
+
+    /// Called by the client task after each command with the latest sync height.
+    pub fn set_sync_height(&self, height: u32) {
+        self.inner.sync_height.fetch_max(height, Ordering::Relaxed);
+    }
+
+    pub fn get(&self, key: &str) -> Option<CachedRead> {
+        if !self.enabled() {
+            return None;
+        }
+
+        let height = self.inner.sync_height.load(Ordering::Relaxed);
+        let mut entries = self.inner.entries.lock().ok()?;
+
+        let fresh = entries
+            .get(key)
+            .map(|e| e.sync_height == height && e.stored_at.elapsed() < self.inner.ttl);
+
+        match fresh {
+            Some(true) => {
+                self.inner.counters.hits.fetch_add(1, Ordering::Relaxed);
+                entries.get(key).map(|e| e.read.clone())
+            }
+            Some(false) => {
+                entries.remove(key);
+                self.inner.counters.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            None => {
+                self.inner.counters.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    pub fn insert(&self, key: String, account: Option<&str>, read: CachedRead) {
+        if !self.enabled() {
+            return;
+        }
+
+        let account = account.map(|a| self.canonical(a));
+        let height = self.inner.sync_height.load(Ordering::Relaxed);
+        let Ok(mut entries) = self.inner.entries.lock() else {
+            return;
+        };
+
+        if entries.len() >= self.inner.max_entries && !entries.contains_key(&key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, e)| e.stored_at)
+                .map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+
+        entries.insert(
+            key,
+            Entry {
+                account,
+                sync_height: height,
+                stored_at: Instant::now(),
+                read,
+            },
+        );
+    }
+
+    /// Drops entries affected by a write.
+    pub fn invalidate(&self, touched: &Touched) {
+        let Ok(mut entries) = self.inner.entries.lock() else {
+            return;
+        };
+        let before = entries.len();
+
+        match touched {
+            Touched::All => entries.clear(),
+            Touched::Accounts(accounts) => {
+                let accounts: Vec<String> = accounts.iter().map(|a| self.canonical(a)).collect();
+                entries.retain(|_, e| match &e.account {
+                    Some(account) => !accounts.contains(account),
+                    None => false,
+                });
+            }
+        }
+
+        let removed = (before - entries.len()) as u64;
+        self.inner
+            .counters
+            .invalidations
+            .fetch_add(removed, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> serde_json::Value {
+        serde_json::json!({
+            "enabled": self.enabled(),
+            "entries": self.inner.entries.lock().map(|e| e.len()).unwrap_or(0),
+            "sync_height": self.inner.sync_height.load(Ordering::Relaxed),
+            "ttl_secs": self.inner.ttl.as_secs(),
+            "counters": self.inner.counters,
+        })
+    }
+}
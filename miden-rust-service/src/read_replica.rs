@@ -0,0 +1,124 @@
+// src/read_replica.rs
+//
+// Read replica mode
+//
+// With READ_REPLICA=true the service runs as a read-only copy of a primary
+// instance: it answers queries (balances, notes, properties, escrow status,
+// proofs) and refuses everything else. A replica
+// - has no keystore: no key is loaded or written, and the client is built
+//   without an authenticator, so it cannot sign a transaction
+// - creates no accounts and funds nothing at startup; the primary's alice,
+//   bob and faucet come from READ_REPLICA_ACCOUNTS (name=hex ID, any subset)
+// - runs no scheduler, mint batch or subscription dispatcher
+// - answers 405 to every route outside the allowlist below, before the
+//   request reaches the client queue
+//
+// The replicated store is the primary's client store (typically bootstrapped
+// from a published snapshot, see snapshots.rs, and then kept current by the
+// replica's own syncs) and its service records file (SERVICE_RECORDS_PATH),
+// which the deployment copies from the primary. The replica reloads the
+// records file whenever it changes on disk.
+//
+// Proof generation and verification are served: they read the store and
+// records but sign nothing.
+
+use anyhow::Result;
+use axum::http::Method;
+use miden_client::account::AccountId;
+use std::time::SystemTime;
+
+use crate::{
+    client::ServiceKeyStore, config::ServiceConfig, field_encryption::FieldCipher,
+    records::ServiceRecords, MidenClientWrapper,
+};
+
+/// Non-GET routes a replica serves (unversioned, as matched)
+const QUERY_POSTS: &[&str] = &[
+    "/generate-accreditation-proof",
+    "/verify-accreditation-proof",
+    "/generate-jurisdiction-proof",
+    "/verify-jurisdiction-proof",
+    "/generate-ownership-proof",
+    "/verify-ownership-proof",
+    "/generate-identity-proof",
+    "/verify-identity-proof",
+];
+
+/// Whether a replica serves `method` on `route`. Unmatched paths pass through
+/// (they answer 404 anyway).
+pub fn serves(method: &Method, route: Option<&str>) -> bool {
+    let Some(route) = route else {
+        return true;
+    };
+    match *method {
+        Method::GET | Method::HEAD | Method::OPTIONS => true,
+        Method::POST => QUERY_POSTS.contains(&route),
+        _ => false,
+    }
+}
+
+/// The primary's alice, bob and faucet IDs (READ_REPLICA_ACCOUNTS).
+pub fn named_accounts(
+    config: &ServiceConfig,
+) -> Result<(Option<AccountId>, Option<AccountId>, Option<AccountId>)> {
+    let account = |name: &str| {
+        config
+            .read_replica_accounts
+            .get(name)
+            .map(|hex| {
+                AccountId::from_hex(hex)
+                    .map_err(|e| anyhow::anyhow!("Invalid {} account ID {}: {}", name, hex, e))
+            })
+            .transpose()
+    };
+    Ok((account("alice")?, account("bob")?, account("faucet")?))
+}
+
+/// Modification time of the service records file, if it exists.
+pub(crate) fn records_modified_at(config: &ServiceConfig) -> Option<SystemTime> {
+    std::fs::metadata(&config.records_path)
+        .and_then(|m| m.modified())
+        .ok()
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+impl MidenClientWrapper {
+    /// The keystore, which a read replica does not have.
+    pub(crate) fn keystore(&self) -> Result<&ServiceKeyStore> {
+        self.keystore
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Read replica: this instance holds no keys"))
+    }
+
+    /// Reloads the service records if the replicated file changed since the
+    /// last load. Returns whether it did. Does nothing on a primary.
+    pub fn refresh_replica_records(&mut self) -> Result<bool> {
+        if !self.config.read_replica {
+            return Ok(false);
+        }
+        let modified = records_modified_at(&self.config);
+        if modified.is_none() || modified == self.replica_records_modified {
+            return Ok(false);
+        }
+
+        let cipher = self
+            .config
+            .field_encryption_key
+            .clone()
+            .map(|key| FieldCipher::load(self.config.data_keys_path.clone(), key))
+            .transpose()?;
+        self.records = ServiceRecords::load(
+            self.config.records_path.clone(),
+            cipher,
+            self.config.confidential_listings,
+        )?;
+        self.replica_records_modified = modified;
+        let indexed = self.rebuild_search_index()?;
+        tracing::info!(properties = indexed, "Replicated records reloaded");
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,448 @@
+// src/reconcile.rs - Reconciliation of service records against chain state
+//
+// After a crash or a stretch of missed syncs the service records (src/records.rs)
+// can disagree with what actually happened on-chain. The reconciler:
+// - Reports operations that were started but never recorded as finished
+// - Checks every expected note (consumed? still pending? unknown to the node?)
+// - Checks every escrow account's vault against its recorded status
+// - Finds notes addressed to the service's accounts that were never recorded
+// - Checks the ledger's account balances against vaults and pending notes
+//   (src/ledger.rs)
+//
+// In repair mode, records that can be re-derived from chain data are updated,
+// and ledger differences are posted as adjustments.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use miden_client::{account::AccountId, note::NoteId, Deserializable};
+
+use crate::{
+    account_id_to_hex,
+    escrow::EscrowStatus,
+    read_cache::Touched,
+    records::OperationStatus,
+    MidenClientWrapper,
+};
+
+/// Category of a single disagreement between records and chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscrepancyKind {
+    /// Operation journal entry left pending (service stopped mid-operation)
+    InterruptedOperation,
+    /// Property was recorded with a placeholder note ID
+    UnresolvedNoteId,
+    /// Expected note is unknown to the local store / node
+    NoteNotFound,
+    /// Note was consumed on-chain but records still show it as pending
+    NoteConsumedNotRecorded,
+    /// Note is still consumable although records say it was consumed
+    NoteRecordedButUnconsumed,
+    /// Escrow account is missing from the client store
+    EscrowAccountMissing,
+    /// Escrow holds funds but records show it as Created
+    EscrowFundedNotRecorded,
+    /// Escrow recorded as Funded but holds no assets and no pending notes
+    EscrowFundsMissing,
+    /// Escrow recorded as Released/Refunded but still holds assets
+    EscrowSettlementIncomplete,
+    /// Consumable note for a service account that was never recorded
+    UntrackedNote,
+    /// Debits and credits of the ledger differ for an asset
+    LedgerUnbalanced,
+    /// Ledger balance differs from the account's vault plus pending notes
+    LedgerBalanceMismatch,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Discrepancy {
+    pub kind: DiscrepancyKind,
+    pub subject: String,
+    pub detail: String,
+    pub repaired: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReconciliationReport {
+    pub repair: bool,
+    pub block_num: u32,
+    pub operations_checked: usize,
+    pub notes_checked: usize,
+    pub escrows_checked: usize,
+    /// Ledger balances (account and asset) compared with the chain
+    pub ledger_balances_checked: usize,
+    pub discrepancies: Vec<Discrepancy>,
+    pub repaired_count: usize,
+    pub completed_at: i64,
+}
+
+pub(crate) fn parse_hex_account_id(account_str: &str) -> Result<AccountId> {
+    let hex_str = account_str.strip_prefix("0x").unwrap_or(account_str);
+    let bytes = hex::decode(hex_str).map_err(|e| anyhow::anyhow!("Failed to decode hex: {}", e))?;
+    AccountId::read_from_bytes(&bytes[..])
+        .map_err(|e| anyhow::anyhow!("Failed to deserialize AccountId: {}", e))
+}
+
+crate::operations! {
+    Reconcile {
+        repair: bool,
+        api_key: Option<String>,
+    } -> ReconciliationReport;
+    touched: |op| op.repair.then_some(Touched::All);
+    |client, op| client.reconcile(op.repair, op.api_key.as_deref()).await;
+
+    GetOperations {
+        limit: usize,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.get_operations(op.limit, op.api_key.as_deref())
+}
+
+impl MidenClientWrapper {
+    /// Compares service records with chain state and optionally repairs them.
+    ///
+    /// Repairs only ever move records towards what the chain says; nothing is
+    /// submitted on-chain from here. Admin only.
+    pub async fn reconcile(
+        &mut self,
+        repair: bool,
+        api_key: Option<&str>,
+    ) -> Result<ReconciliationReport> {
+        self.admin_principal(api_key, "reconciliation")?;
+        tracing::info!(repair, "Reconciling service records");
+
+        let sync_summary = self.sync_state().await?;
+        let mut discrepancies = Vec::new();
+
+        // ---------------------------------------------------------------------
+        // Interrupted operations
+        // ---------------------------------------------------------------------
+        let operations_checked = self.records.operations.len();
+        let pending: Vec<(u64, String, String)> = self
+            .records
+            .pending_operations()
+            .into_iter()
+            .map(|op| (op.op_id, op.kind.clone(), op.subject.clone()))
+            .collect();
+
+        for (op_id, kind, subject) in pending {
+            if repair {
+                // The chain checks below re-derive whatever the operation managed
+                // to do; the journal entry itself can only be closed out.
+                self.records
+                    .fail_operation(op_id, "Interrupted; closed by reconciliation");
+            }
+            discrepancies.push(Discrepancy {
+                kind: DiscrepancyKind::InterruptedOperation,
+                subject: format!("op#{} {}", op_id, kind),
+                detail: format!("Operation on '{}' never recorded an outcome", subject),
+                repaired: repair,
+            });
+        }
+
+        // ---------------------------------------------------------------------
+        // Properties with placeholder note IDs
+        // ---------------------------------------------------------------------
+        for property in self.records.properties.values() {
+            if property.note_id_placeholder {
+                discrepancies.push(Discrepancy {
+                    kind: DiscrepancyKind::UnresolvedNoteId,
+                    subject: property.property_id.clone(),
+                    detail: format!(
+                        "Mint tx {} recorded with placeholder note {}",
+                        property.mint_tx_id, property.note_id
+                    ),
+                    repaired: false,
+                });
+            }
+        }
+
+        // ---------------------------------------------------------------------
+        // Expected notes
+        // ---------------------------------------------------------------------
+        let expected: Vec<(String, bool)> = self
+            .records
+            .expected_notes
+            .values()
+            .map(|note| (note.note_id.clone(), note.consumed))
+            .collect();
+        let notes_checked = expected.len();
+
+        for (note_id_str, recorded_consumed) in expected {
+            let note_id = match NoteId::try_from_hex(&note_id_str) {
+                Ok(id) => id,
+                Err(_) => {
+                    discrepancies.push(Discrepancy {
+                        kind: DiscrepancyKind::NoteNotFound,
+                        subject: note_id_str.clone(),
+                        detail: "Recorded note ID is not a valid note hash".to_string(),
+                        repaired: false,
+                    });
+                    continue;
+                }
+            };
+
+            match self.client.get_input_note(note_id).await? {
+                None => discrepancies.push(Discrepancy {
+                    kind: DiscrepancyKind::NoteNotFound,
+                    subject: note_id_str,
+                    detail: "Note is not present in the local store after sync".to_string(),
+                    repaired: false,
+                }),
+                Some(note_record) => {
+                    let chain_consumed = note_record.is_consumed();
+
+                    if chain_consumed && !recorded_consumed {
+                        if repair {
+                            self.records.mark_note_consumed(&note_id_str, None);
+                        }
+                        discrepancies.push(Discrepancy {
+                            kind: DiscrepancyKind::NoteConsumedNotRecorded,
+                            subject: note_id_str,
+                            detail: "Note was consumed on-chain but not recorded".to_string(),
+                            repaired: repair,
+                        });
+                    } else if !chain_consumed && recorded_consumed {
+                        if repair {
+                            if let Some(note) = self.records.expected_notes.get_mut(&note_id_str) {
+                                note.consumed = false;
+                                note.consumed_tx_id = None;
+                            }
+                            self.records.save()?;
+                        }
+                        discrepancies.push(Discrepancy {
+                            kind: DiscrepancyKind::NoteRecordedButUnconsumed,
+                            subject: note_id_str,
+                            detail: "Records show the note consumed, chain does not".to_string(),
+                            repaired: repair,
+                        });
+                    }
+                }
+            }
+        }
+
+        // ---------------------------------------------------------------------
+        // Escrows
+        // ---------------------------------------------------------------------
+        let escrows: Vec<(String, EscrowStatus)> = self
+            .records
+            .escrows
+            .values()
+            .map(|escrow| (escrow.escrow_account_id.clone(), escrow.status.clone()))
+            .collect();
+        let escrows_checked = escrows.len();
+
+        for (escrow_hex, status) in escrows {
+            let escrow_account_id = match parse_hex_account_id(&escrow_hex) {
+                Ok(id) => id,
+                Err(e) => {
+                    discrepancies.push(Discrepancy {
+                        kind: DiscrepancyKind::EscrowAccountMissing,
+                        subject: escrow_hex,
+                        detail: format!("Recorded escrow ID is invalid: {}", e),
+                        repaired: false,
+                    });
+                    continue;
+                }
+            };
+
+            let Some(account) = self.client.get_account(escrow_account_id).await? else {
+                discrepancies.push(Discrepancy {
+                    kind: DiscrepancyKind::EscrowAccountMissing,
+                    subject: escrow_hex,
+                    detail: "Escrow account not found in client store".to_string(),
+                    repaired: false,
+                });
+                continue;
+            };
+
+            let vault_asset_count = account.account().vault().assets().count();
+            let pending_note_count = self
+                .client
+                .get_consumable_notes(Some(escrow_account_id))
+                .await?
+                .len();
+            let holds_funds = vault_asset_count > 0 || pending_note_count > 0;
+
+            match status {
+                EscrowStatus::Created if holds_funds => {
+                    if repair {
+                        self.records
+                            .update_escrow(&escrow_hex, EscrowStatus::Funded, None);
+                    }
+                    discrepancies.push(Discrepancy {
+                        kind: DiscrepancyKind::EscrowFundedNotRecorded,
+                        subject: escrow_hex,
+                        detail: format!(
+                            "{} vault asset(s), {} pending note(s) while recorded as created",
+                            vault_asset_count, pending_note_count
+                        ),
+                        repaired: repair,
+                    });
+                }
+                EscrowStatus::Funded if !holds_funds => discrepancies.push(Discrepancy {
+                    kind: DiscrepancyKind::EscrowFundsMissing,
+                    subject: escrow_hex,
+                    detail: "Recorded as funded but vault and pending notes are empty".to_string(),
+                    repaired: false,
+                }),
+                EscrowStatus::Released | EscrowStatus::Refunded if vault_asset_count > 0 => {
+                    discrepancies.push(Discrepancy {
+                        kind: DiscrepancyKind::EscrowSettlementIncomplete,
+                        subject: escrow_hex,
+                        detail: format!(
+                            "Recorded as {:?} but vault still holds {} asset(s)",
+                            status, vault_asset_count
+                        ),
+                        repaired: false,
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        // ---------------------------------------------------------------------
+        // Untracked notes for the service's own accounts
+        // ---------------------------------------------------------------------
+        let service_accounts = [self.alice_account_id, self.bob_account_id]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+
+        for account_id in service_accounts {
+            let consumable_notes = self.client.get_consumable_notes(Some(account_id)).await?;
+
+            for (note, _) in consumable_notes {
+                let note_id_str = note.id().to_string();
+                if self.records.expected_notes.contains_key(&note_id_str) {
+                    continue;
+                }
+
+                let account_hex = account_id_to_hex(account_id);
+                if repair {
+                    self.records
+                        .expect_note(&note_id_str, &account_hex, "recovered", None);
+                }
+                discrepancies.push(Discrepancy {
+                    kind: DiscrepancyKind::UntrackedNote,
+                    subject: note_id_str,
+                    detail: format!("Consumable note for {} was never recorded", account_hex),
+                    repaired: repair,
+                });
+            }
+        }
+
+        // ---------------------------------------------------------------------
+        // Ledger balances
+        // ---------------------------------------------------------------------
+        let trial_balance = self.ledger.trial_balance(None);
+        for totals in trial_balance.totals.iter().filter(|t| t.debits != t.credits) {
+            discrepancies.push(Discrepancy {
+                kind: DiscrepancyKind::LedgerUnbalanced,
+                subject: totals.faucet_id.clone(),
+                detail: format!(
+                    "Ledger debits {} and credits {} of the asset differ",
+                    totals.debits, totals.credits
+                ),
+                repaired: false,
+            });
+        }
+
+        let mut ledger_balances_checked = 0;
+        let mut pending_by_account: BTreeMap<String, Vec<(String, u64)>> = BTreeMap::new();
+        for row in self.ledger_balances() {
+            // Accounts outside the client store cannot be checked
+            let (Ok(account_id), Ok(faucet_id)) = (
+                parse_hex_account_id(&row.account),
+                parse_hex_account_id(&row.faucet_id),
+            ) else {
+                continue;
+            };
+            let Some(account) = self.client.get_account(account_id).await? else {
+                continue;
+            };
+            let vault = account.account().vault().get_balance(faucet_id).unwrap_or(0);
+            if !pending_by_account.contains_key(&row.account) {
+                let mut pending = Vec::new();
+                for (note, _) in self.client.get_consumable_notes(Some(account_id)).await? {
+                    for asset in note.assets().iter_fungible() {
+                        pending.push((account_id_to_hex(asset.faucet_id()), asset.amount()));
+                    }
+                }
+                pending_by_account.insert(row.account.clone(), pending);
+            }
+            let pending: u64 = pending_by_account[&row.account]
+                .iter()
+                .filter(|(faucet, _)| faucet.eq_ignore_ascii_case(&row.faucet_id))
+                .map(|(_, amount)| amount)
+                .sum();
+            ledger_balances_checked += 1;
+
+            let on_chain = vault as i128 + pending as i128;
+            let difference = on_chain - row.balance;
+            if difference == 0 {
+                continue;
+            }
+            let repaired = repair
+                && match self.post_ledger_adjustment(&row.account, &row.faucet_id, difference) {
+                    Ok(_) => true,
+                    Err(e) => {
+                        tracing::warn!("Could not adjust the ledger for {}: {}", row.account, e);
+                        false
+                    }
+                };
+            discrepancies.push(Discrepancy {
+                kind: DiscrepancyKind::LedgerBalanceMismatch,
+                subject: format!("{} {}", row.account, row.faucet_id),
+                detail: format!(
+                    "Ledger balance {} but vault and pending notes hold {}",
+                    row.balance, on_chain
+                ),
+                repaired,
+            });
+        }
+
+        let repaired_count = discrepancies.iter().filter(|d| d.repaired).count();
+
+        tracing::info!(
+            discrepancies = discrepancies.len(),
+            repaired = repaired_count,
+            "Reconciliation complete"
+        );
+
+        Ok(ReconciliationReport {
+            repair,
+            block_num: sync_summary.block_num.as_u32(),
+            operations_checked,
+            notes_checked,
+            escrows_checked,
+            ledger_balances_checked,
+            discrepancies,
+            repaired_count,
+            completed_at: chrono::Utc::now().timestamp(),
+        })
+    }
+}
+
+impl MidenClientWrapper {
+    /// Returns the operation journal: counts by status plus the most recent entries.
+    pub fn get_operations(&self, limit: usize, api_key: Option<&str>) -> Result<serde_json::Value> {
+        self.admin_principal(api_key, "the operations journal")?;
+        let operations = &self.records.operations;
+        let count = |status: OperationStatus| {
+            operations.iter().filter(|op| op.status == status).count()
+        };
+
+        let recent: Vec<_> = operations.iter().rev().take(limit).collect();
+
+        Ok(serde_json::json!({
+            "pending": count(OperationStatus::Pending),
+            "completed": count(OperationStatus::Completed),
+            "failed": count(OperationStatus::Failed),
+            "total": operations.len(),
+            "recent": recent,
+        }))
+    }
+}
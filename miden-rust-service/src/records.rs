@@ -0,0 +1,617 @@
+// src/records.rs
+//
+// Persisted service records
+//
+// The Miden client store only knows about accounts and notes. This module keeps
+// the service's own view of what it has done so far:
+// - Properties minted (and the note that carries each one)
+// - Escrows opened, their lifecycle status and the funding notes and asset
+//   that belong to each
+// - Notes the service expects to appear (and later be consumed) on-chain
+// - A journal of mutating operations (pending -> completed / failed)
+//
+// Records are stored as a single JSON document and rewritten after every change.
+// The reconciliation tool (src/reconcile.rs) compares them against chain state.
+//
+// With field encryption configured (src/field_encryption.rs), party identities
+// and document CIDs are encrypted in the file (and prices too when confidential
+// listings are enabled); in memory records are always plaintext.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    contract_anchors::ContractAnchor,
+    data_subjects::{erase, ErasedField},
+    escrow::EscrowStatus,
+    escrow_yield::EscrowYield,
+    fee_splits::AgentCommission,
+    field_encryption::{is_sealed, FieldCipher},
+    geo::PropertyLocation,
+    hooks::HookMetadata,
+    property_recovery::{LostAccess, ProvenanceEntry},
+    property_registry::PropertyReissue,
+    property_retirement::PropertyRetirement,
+    withholding::Withholding,
+};
+
+/// Record collections with encrypted fields: (collection, field naming the
+/// tenant, fields encrypted, price field encrypted for confidential listings).
+const SEALED_COLLECTIONS: &[(&str, &str, &[&str], &str)] = &[
+    (
+        "properties",
+        "owner_account_id",
+        &["owner_account_id", "ipfs_cid"],
+        "price",
+    ),
+    (
+        "escrows",
+        "buyer_account_id",
+        &["buyer_account_id", "seller_account_id"],
+        "amount",
+    ),
+];
+
+/// A property minted through this service.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PropertyRecord {
+    pub property_id: String,
+    pub owner_account_id: String,
+    pub ipfs_cid: String,
+    pub property_type: u8,
+    /// Minor units of `price_currency`
+    pub price: u64,
+    /// ISO 4217 code (or the service token); None for properties minted before
+    /// prices carried a currency, whose prices are in PRICE_CURRENCY
+    #[serde(default)]
+    pub price_currency: Option<String>,
+    pub mint_tx_id: String,
+    pub note_id: String,
+    /// True when `note_id` is a placeholder because the note was not yet visible
+    pub note_id_placeholder: bool,
+    /// Transfer note that carried the token to the current owner; None while
+    /// the token is in the mint note `note_id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_note_id: Option<String>,
+    /// Point and parcel outline, once the owner has set them (geo.rs)
+    #[serde(default)]
+    pub location: Option<PropertyLocation>,
+    /// Photos in upload order (media.rs)
+    #[serde(default)]
+    pub media_ids: Vec<u64>,
+    /// Added by after_mint hooks (hooks.rs)
+    #[serde(default)]
+    pub metadata: HookMetadata,
+    /// Tax jurisdiction, for withholding at settlement (withholding.rs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jurisdiction: Option<String>,
+    /// Issuance the mint note's serial number derives from; 0 for the first
+    /// mint, raised by each re-issue (property_registry.rs)
+    #[serde(default)]
+    pub issuance: u32,
+    /// Mints replaced by re-issues, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reissues: Vec<PropertyReissue>,
+    /// Set once the property is retired and its token sent to be burned
+    /// (property_retirement.rs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retirement: Option<PropertyRetirement>,
+    /// Set when a re-issue after lost access is requested; kept after it
+    /// completes (property_recovery.rs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lost_access: Option<LostAccess>,
+    /// Re-issues, burns and retirements of the property's tokens, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub provenance: Vec<ProvenanceEntry>,
+    pub created_at: i64,
+}
+
+/// An escrow opened through this service.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EscrowRecord {
+    pub escrow_account_id: String,
+    pub buyer_account_id: String,
+    pub seller_account_id: String,
+    /// Property being sold, when the escrow was opened for one
+    #[serde(default)]
+    pub property_id: Option<String>,
+    pub amount: u64,
+    pub status: EscrowStatus,
+    pub fund_tx_id: Option<String>,
+    pub settle_tx_id: Option<String>,
+    /// Funding notes sent to the escrow for this deal; release and refund
+    /// consume only these
+    #[serde(default)]
+    pub funding_note_ids: Vec<String>,
+    /// Faucet of the escrowed asset (hex); `amount` of it belongs to this deal.
+    /// Set at creation for escrows of a property (settlement_tokens.rs),
+    /// otherwise at funding
+    #[serde(default)]
+    pub asset_faucet_id: Option<String>,
+    /// Deal number and master secret generation the escrow account was derived
+    /// from (secrets.rs); None for escrows created with random seeds
+    #[serde(default)]
+    pub deal_id: Option<u64>,
+    #[serde(default)]
+    pub seed_generation: Option<u32>,
+    /// Added by before_escrow_release hooks (hooks.rs)
+    #[serde(default)]
+    pub metadata: HookMetadata,
+    /// Accrued yield and its payouts (escrow_yield.rs)
+    #[serde(default, rename = "yield", skip_serializing_if = "Option::is_none")]
+    pub escrow_yield: Option<EscrowYield>,
+    /// Agent paid out of the seller's share on release (fee_splits.rs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commission: Option<AgentCommission>,
+    /// Tax withheld at settlement (withholding.rs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub withholding: Option<Withholding>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// A note the service created (or caused to be created) and expects to be consumed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpectedNote {
+    pub note_id: String,
+    pub recipient_account_id: String,
+    /// Short label such as "property-mint", "bob-funding", "recovered"
+    pub purpose: String,
+    pub created_tx_id: Option<String>,
+    pub consumed_tx_id: Option<String>,
+    pub consumed: bool,
+    pub recorded_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationStatus {
+    Pending,
+    Completed,
+    Failed,
+}
+
+/// Journal entry for a mutating operation.
+///
+/// An entry left in `Pending` means the service stopped (crash, restart) between
+/// starting the operation and recording its outcome.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationEntry {
+    pub op_id: u64,
+    pub kind: String,
+    pub subject: String,
+    pub status: OperationStatus,
+    pub tx_id: Option<String>,
+    pub error: Option<String>,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+}
+
+/// JSON-backed record set owned by the client task.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ServiceRecords {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    pub properties: BTreeMap<String, PropertyRecord>,
+    #[serde(default)]
+    pub escrows: BTreeMap<String, EscrowRecord>,
+    #[serde(default)]
+    pub expected_notes: BTreeMap<String, ExpectedNote>,
+    #[serde(default)]
+    pub operations: Vec<OperationEntry>,
+    /// Transfers anchored to sales contracts, keyed by note ID
+    #[serde(default)]
+    pub contract_anchors: BTreeMap<String, ContractAnchor>,
+    #[serde(default)]
+    next_op_id: u64,
+    #[serde(skip)]
+    cipher: Option<FieldCipher>,
+    #[serde(skip)]
+    seal_prices: bool,
+}
+
+impl ServiceRecords {
+    /// Loads records from disk, starting empty if the file does not exist yet.
+    ///
+    /// Encrypted fields are decrypted with `cipher`; without one, a file that
+    /// has encrypted fields is refused. `seal_prices` also encrypts prices on
+    /// save.
+    pub fn load(
+        path: impl Into<PathBuf>,
+        cipher: Option<FieldCipher>,
+        seal_prices: bool,
+    ) -> Result<Self> {
+        let path = path.into();
+
+        let mut records = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            let mut value: serde_json::Value = serde_json::from_str(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?;
+            open_sealed_fields(&mut value, cipher.as_ref())
+                .map_err(|e| anyhow::anyhow!("Failed to decrypt {}: {}", path.display(), e))?;
+            serde_json::from_value::<ServiceRecords>(value)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            ServiceRecords::default()
+        };
+
+        records.path = path;
+        records.cipher = cipher;
+        records.seal_prices = seal_prices;
+        Ok(records)
+    }
+
+    /// Writes records to disk (write to temp file, then rename).
+    pub fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        let raw = match &self.cipher {
+            Some(cipher) => serde_json::to_string_pretty(&self.sealed(cipher)?)?,
+            None => serde_json::to_string_pretty(self)?,
+        };
+        std::fs::write(&tmp_path, raw)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Saves and logs (rather than propagates) a failure.
+    ///
+    /// Record keeping must never fail an operation that already hit the chain.
+    fn persist(&self) {
+        if let Err(e) = self.save() {
+            tracing::warn!("⚠️  Failed to persist service records: {}", e);
+        }
+    }
+
+    /// The records as stored: sensitive fields encrypted under the data key
+    /// of the record's tenant.
+    fn sealed(&self, cipher: &FieldCipher) -> Result<serde_json::Value> {
+        let mut value = serde_json::to_value(self)?;
+
+        for &(collection, tenant_field, fields, price_field) in SEALED_COLLECTIONS {
+            let Some(entries) = value.get_mut(collection).and_then(|v| v.as_object_mut()) else {
+                continue;
+            };
+            let price = self.seal_prices.then_some(price_field);
+
+            for (key, entry) in entries.iter_mut() {
+                let tenant = entry[tenant_field].as_str().unwrap_or_default().to_string();
+                for &field in fields.iter().chain(price.as_ref()) {
+                    if let Some(slot) = entry.get_mut(field) {
+                        let context = format!("{}/{}/{}", collection, key, field);
+                        *slot = cipher.seal(&tenant, &context, slot)?;
+                    }
+                }
+            }
+        }
+
+        Ok(value)
+    }
+
+    // -------------------------------------------------------------------------
+    // Operation journal
+    // -------------------------------------------------------------------------
+
+    pub fn begin_operation(&mut self, kind: &str, subject: &str) -> u64 {
+        self.next_op_id += 1;
+        let op_id = self.next_op_id;
+        // Tags the log lines of the running command (logging.rs)
+        tracing::Span::current().record("account", subject);
+
+        self.operations.push(OperationEntry {
+            op_id,
+            kind: kind.to_string(),
+            subject: subject.to_string(),
+            status: OperationStatus::Pending,
+            tx_id: None,
+            error: None,
+            started_at: chrono::Utc::now().timestamp(),
+            finished_at: None,
+        });
+
+        self.persist();
+        op_id
+    }
+
+    pub fn complete_operation(&mut self, op_id: u64, tx_id: Option<String>) {
+        if let Some(op) = self.operations.iter_mut().find(|op| op.op_id == op_id) {
+            op.status = OperationStatus::Completed;
+            if let Some(tx_id) = &tx_id {
+                tracing::Span::current().record("tx_id", tx_id.as_str());
+            }
+            op.tx_id = tx_id;
+            op.finished_at = Some(chrono::Utc::now().timestamp());
+        }
+        self.persist();
+    }
+
+    pub fn fail_operation(&mut self, op_id: u64, error: &str) {
+        if let Some(op) = self.operations.iter_mut().find(|op| op.op_id == op_id) {
+            op.status = OperationStatus::Failed;
+            op.error = Some(error.to_string());
+            op.finished_at = Some(chrono::Utc::now().timestamp());
+        }
+        self.persist();
+    }
+
+    /// Records the outcome of an operation started with `begin_operation`.
+    pub fn finish_operation<T>(&mut self, op_id: u64, result: &Result<T>, tx_id: Option<String>) {
+        match result {
+            Ok(_) => self.complete_operation(op_id, tx_id),
+            Err(e) => self.fail_operation(op_id, &e.to_string()),
+        }
+    }
+
+    pub fn pending_operations(&self) -> Vec<&OperationEntry> {
+        self.operations
+            .iter()
+            .filter(|op| op.status == OperationStatus::Pending)
+            .collect()
+    }
+
+    // -------------------------------------------------------------------------
+    // Properties / notes / escrows
+    // -------------------------------------------------------------------------
+
+    pub fn record_property(&mut self, record: PropertyRecord) {
+        self.properties.insert(record.property_id.clone(), record);
+        self.persist();
+    }
+
+    /// Erases the document references (IPFS CIDs) of properties an account
+    /// owns (data_subjects.rs). They are kept off-chain only.
+    pub fn erase_personal_fields(&mut self, account_hex: &str) -> Vec<ErasedField> {
+        let mut erased = Vec::new();
+        for property in self
+            .properties
+            .values_mut()
+            .filter(|p| p.owner_account_id.eq_ignore_ascii_case(account_hex))
+        {
+            if let Some(original) = erase(&mut property.ipfs_cid) {
+                erased.push(ErasedField::new(
+                    "properties",
+                    &property.property_id,
+                    "ipfs_cid",
+                    original,
+                ));
+            }
+        }
+        if !erased.is_empty() {
+            self.persist();
+        }
+        erased
+    }
+
+    pub fn record_location(&mut self, property_id: &str, location: PropertyLocation) {
+        if let Some(property) = self.properties.get_mut(property_id) {
+            property.location = Some(location);
+            self.persist();
+        }
+    }
+
+    pub fn record_media(&mut self, property_id: &str, media_ids: Vec<u64>) {
+        if let Some(property) = self.properties.get_mut(property_id) {
+            property.media_ids = media_ids;
+            self.persist();
+        }
+    }
+
+    /// Adds hook metadata to a property; later values replace earlier ones.
+    pub fn annotate_property(&mut self, property_id: &str, metadata: HookMetadata) {
+        if metadata.is_empty() {
+            return;
+        }
+        if let Some(property) = self.properties.get_mut(property_id) {
+            property.metadata.extend(metadata);
+            self.persist();
+        }
+    }
+
+    pub fn set_property_jurisdiction(&mut self, property_id: &str, jurisdiction: Option<String>) {
+        if let Some(property) = self.properties.get_mut(property_id) {
+            property.jurisdiction = jurisdiction;
+            self.persist();
+        }
+    }
+
+    /// Moves a property to its new owner after a completed title transfer.
+    pub fn record_transfer(&mut self, property_id: &str, owner_account_id: &str, note_id: &str) {
+        if let Some(property) = self.properties.get_mut(property_id) {
+            property.owner_account_id = owner_account_id.to_lowercase();
+            property.token_note_id = Some(note_id.to_string());
+            self.persist();
+        }
+    }
+
+    /// Moves a property to the token minted by a re-issue.
+    pub fn record_reissue(
+        &mut self,
+        property_id: &str,
+        owner_account_id: &str,
+        mint_tx_id: &str,
+        note_id: &str,
+        reissue: PropertyReissue,
+    ) {
+        if let Some(property) = self.properties.get_mut(property_id) {
+            property.owner_account_id = owner_account_id.to_lowercase();
+            property.mint_tx_id = mint_tx_id.to_string();
+            property.note_id = note_id.to_string();
+            property.note_id_placeholder = false;
+            property.token_note_id = None;
+            property.issuance = reissue.issuance;
+            property.reissues.push(reissue);
+            self.persist();
+        }
+    }
+
+    pub fn record_retirement(&mut self, property_id: &str, retirement: PropertyRetirement) {
+        if let Some(property) = self.properties.get_mut(property_id) {
+            property.retirement = Some(retirement);
+            self.persist();
+        }
+    }
+
+    pub fn set_lost_access(&mut self, property_id: &str, lost_access: LostAccess) {
+        if let Some(property) = self.properties.get_mut(property_id) {
+            property.lost_access = Some(lost_access);
+            self.persist();
+        }
+    }
+
+    pub fn record_provenance(&mut self, property_id: &str, entry: ProvenanceEntry) {
+        if let Some(property) = self.properties.get_mut(property_id) {
+            property.provenance.push(entry);
+            self.persist();
+        }
+    }
+
+    pub fn record_contract_anchor(&mut self, anchor: ContractAnchor) {
+        self.contract_anchors.insert(anchor.note_id.clone(), anchor);
+        self.persist();
+    }
+
+    pub fn expect_note(
+        &mut self,
+        note_id: &str,
+        recipient_account_id: &str,
+        purpose: &str,
+        created_tx_id: Option<String>,
+    ) {
+        self.expected_notes.insert(
+            note_id.to_string(),
+            ExpectedNote {
+                note_id: note_id.to_string(),
+                recipient_account_id: recipient_account_id.to_string(),
+                purpose: purpose.to_string(),
+                created_tx_id,
+                consumed_tx_id: None,
+                consumed: false,
+                recorded_at: chrono::Utc::now().timestamp(),
+            },
+        );
+        self.persist();
+    }
+
+    pub fn mark_note_consumed(&mut self, note_id: &str, consumed_tx_id: Option<String>) {
+        if let Some(note) = self.expected_notes.get_mut(note_id) {
+            note.consumed = true;
+            note.consumed_tx_id = consumed_tx_id;
+        }
+        self.persist();
+    }
+
+    pub fn record_escrow(&mut self, record: EscrowRecord) {
+        self.escrows.insert(record.escrow_account_id.clone(), record);
+        self.persist();
+    }
+
+    /// Tags a funding note and its asset as belonging to an escrow, and
+    /// expects the note to be consumed by the escrow account.
+    pub fn tag_escrow_funding(
+        &mut self,
+        escrow_account_id: &str,
+        note_id: &str,
+        faucet_id: &str,
+        created_tx_id: Option<String>,
+    ) {
+        if let Some(escrow) = self.escrows.get_mut(escrow_account_id) {
+            escrow.funding_note_ids.push(note_id.to_string());
+            escrow.asset_faucet_id = Some(faucet_id.to_string());
+            escrow.updated_at = chrono::Utc::now().timestamp();
+        }
+        self.expect_note(note_id, escrow_account_id, "escrow-funding", created_tx_id);
+    }
+
+    /// Adds hook metadata to an escrow; later values replace earlier ones.
+    pub fn annotate_escrow(&mut self, escrow_account_id: &str, metadata: HookMetadata) {
+        if metadata.is_empty() {
+            return;
+        }
+        if let Some(escrow) = self.escrows.get_mut(escrow_account_id) {
+            escrow.metadata.extend(metadata);
+            self.persist();
+        }
+    }
+
+    /// Replaces an escrow's yield record.
+    pub fn set_escrow_yield(&mut self, escrow_account_id: &str, escrow_yield: EscrowYield) {
+        if let Some(escrow) = self.escrows.get_mut(escrow_account_id) {
+            escrow.escrow_yield = Some(escrow_yield);
+            self.persist();
+        }
+    }
+
+    pub fn set_escrow_commission(
+        &mut self,
+        escrow_account_id: &str,
+        commission: Option<AgentCommission>,
+    ) {
+        if let Some(escrow) = self.escrows.get_mut(escrow_account_id) {
+            escrow.commission = commission;
+            escrow.updated_at = chrono::Utc::now().timestamp();
+            self.persist();
+        }
+    }
+
+    pub fn set_escrow_withholding(&mut self, escrow_account_id: &str, withholding: Withholding) {
+        if let Some(escrow) = self.escrows.get_mut(escrow_account_id) {
+            escrow.withholding = Some(withholding);
+            escrow.updated_at = chrono::Utc::now().timestamp();
+            self.persist();
+        }
+    }
+
+    /// Updates an escrow's status, creating a minimal record if the escrow was
+    /// opened before records were kept.
+    pub fn update_escrow(
+        &mut self,
+        escrow_account_id: &str,
+        status: EscrowStatus,
+        tx_id: Option<String>,
+    ) {
+        let now = chrono::Utc::now().timestamp();
+
+        if let Some(escrow) = self.escrows.get_mut(escrow_account_id) {
+            match status {
+                EscrowStatus::Funded => escrow.fund_tx_id = tx_id,
+                EscrowStatus::Released | EscrowStatus::Refunded => escrow.settle_tx_id = tx_id,
+                _ => {}
+            }
+            escrow.status = status;
+            escrow.updated_at = now;
+            self.persist();
+        } else {
+            tracing::warn!(
+                "⚠️  Escrow {} not found in service records; status update not recorded",
+                escrow_account_id
+            );
+        }
+    }
+}
+
+/// Decrypts every encrypted field of stored records in place.
+fn open_sealed_fields(value: &mut serde_json::Value, cipher: Option<&FieldCipher>) -> Result<()> {
+    for &(collection, ..) in SEALED_COLLECTIONS {
+        let Some(entries) = value.get_mut(collection).and_then(|v| v.as_object_mut()) else {
+            continue;
+        };
+
+        for (key, entry) in entries.iter_mut() {
+            let Some(fields) = entry.as_object_mut() else {
+                continue;
+            };
+            for (field, slot) in fields.iter_mut().filter(|(_, slot)| is_sealed(slot)) {
+                let context = format!("{}/{}/{}", collection, key, field);
+                let cipher = cipher.ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "{} is encrypted but no field encryption master key is configured",
+                        context
+                    )
+                })?;
+                *slot = cipher.open(&context, slot)?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,413 @@
+// src/recovery.rs
+//
+// Recovery of funds left in orphaned accounts
+//
+// Earlier versions of the service created a fresh Alice, Bob and faucet on
+// every restart and dummy target accounts for transfers, so tokens are
+// scattered over accounts the service no longer uses. The client store and
+// keystore still hold those accounts and their keys.
+//
+// GET /admin/recovery scans every account in the client store and reports its
+// vault (fungible balances per faucet, non-fungible asset count) and the notes
+// waiting to be consumed by it. An account is recoverable unless it is
+// - one of the current named accounts (alice, bob, faucet)
+// - the escrow account of an open escrow (created, funded or disputed)
+// - the treasury account itself, the swap liquidity account
+//   (SWAP_LIQUIDITY_ACCOUNT, swaps.rs) or the tax escrow account
+//   (TAX_ESCROW_ACCOUNT, withholding.rs)
+// - a custodial user's wallet (custodial.rs)
+// - a faucet
+//
+// POST /admin/recovery/sweep moves the funds of recoverable accounts to the
+// configured treasury account (TREASURY_ACCOUNT), never anywhere else: pending
+// notes are consumed into the orphaned account first (in batches, see
+// consume_batches.rs), then its whole vault is sent to the treasury as one P2ID
+// note. `account_ids` limits the sweep to some accounts; `dry_run` reports
+// what would move without submitting anything. Both routes need an admin key. Each sweep is journaled like other operations, the
+// note to the treasury is recorded as expected (records.rs), and swept
+// fungible assets are entered in the treasury ledger (treasury.rs).
+
+use anyhow::Result;
+use miden_client::{account::AccountId, asset::Asset};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{
+    account_id_to_hex, client,
+    escrow::EscrowStatus,
+    ledger::{asset_postings, JournalKind},
+    read_cache::Touched,
+    reconcile::parse_hex_account_id,
+    treasury::{LedgerEntry, LedgerEntryKind},
+    MidenClientWrapper,
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SweepInput {
+    /// Accounts to sweep (hex); all recoverable accounts when absent
+    pub account_ids: Option<Vec<String>>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FungibleBalance {
+    pub faucet_id: String,
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountFunds {
+    pub account_id: String,
+    pub balances: Vec<FungibleBalance>,
+    pub non_fungible_assets: usize,
+    /// Notes waiting to be consumed by the account
+    pub pending_note_ids: Vec<String>,
+    pub recoverable: bool,
+    /// Why the account is not recoverable
+    pub reason: Option<String>,
+}
+
+impl AccountFunds {
+    fn holds_funds(&self) -> bool {
+        !self.balances.is_empty()
+            || self.non_fungible_assets > 0
+            || !self.pending_note_ids.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveryScan {
+    /// Accounts holding assets or pending notes
+    pub accounts: Vec<AccountFunds>,
+    pub accounts_scanned: usize,
+    /// Vault balances of recoverable accounts, per faucet (pending notes not
+    /// included)
+    pub recoverable_totals: Vec<FungibleBalance>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SweptAccount {
+    pub account_id: String,
+    /// One per batch of pending notes (see consume_batches.rs)
+    pub consume_tx_ids: Vec<String>,
+    pub consumed_note_ids: Vec<String>,
+    /// Pending notes left alone for their unknown scripts (note_inspection.rs)
+    pub refused_note_ids: Vec<String>,
+    pub sweep_tx_id: Option<String>,
+    pub sweep_note_id: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepReport {
+    pub treasury_account_id: String,
+    pub dry_run: bool,
+    pub swept: Vec<SweptAccount>,
+    /// Requested accounts that are not recoverable, with the reason
+    pub skipped: Vec<AccountFunds>,
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    ScanRecoverableFunds {
+        api_key: Option<String>,
+    } -> RecoveryScan;
+    |client, op| client.scan_recoverable_funds(op.api_key.as_deref()).await;
+
+    /// Moves funds unless it is a dry run.
+    SweepRecoverableFunds {
+        input: SweepInput,
+        api_key: Option<String>,
+    } -> SweepReport;
+    touched: |op| (!op.input.dry_run).then_some(Touched::All);
+    |client, op| client.sweep_recoverable_funds(op.input, op.api_key.as_deref()).await
+}
+
+impl MidenClientWrapper {
+    /// Why an account may not be swept; None if it may.
+    fn unrecoverable_reason(
+        &self,
+        account_id: AccountId,
+        treasury: Option<AccountId>,
+    ) -> Option<String> {
+        let account_hex = account_id_to_hex(account_id);
+        if self
+            .named_account_ids()
+            .iter()
+            .any(|(_, named)| *named == account_hex)
+        {
+            return Some("active service account".to_string());
+        }
+        if treasury == Some(account_id) {
+            return Some("treasury account".to_string());
+        }
+        let configured = |selector: Option<&str>| {
+            selector
+                .and_then(|s| self.account_hex(s).ok())
+                .is_some_and(|hex| hex.eq_ignore_ascii_case(&account_hex))
+        };
+        let liquidity = self
+            .config
+            .swaps
+            .as_ref()
+            .map(|s| s.liquidity_account.as_str());
+        if configured(liquidity) {
+            return Some("swap liquidity account".to_string());
+        }
+        if configured(self.config.tax_escrow_account.as_deref()) {
+            return Some("tax escrow account".to_string());
+        }
+        if self.custodial.holds_account(&account_hex) {
+            return Some("custodial wallet".to_string());
+        }
+        if account_id.is_faucet() {
+            return Some("faucet account".to_string());
+        }
+        match self.records.escrows.get(&account_hex).map(|r| &r.status) {
+            Some(EscrowStatus::Created | EscrowStatus::Funded | EscrowStatus::Disputed) => {
+                Some("escrow account of an open escrow".to_string())
+            }
+            _ => None,
+        }
+    }
+
+    async fn account_funds(
+        &mut self,
+        account_id: AccountId,
+        treasury: Option<AccountId>,
+    ) -> Result<AccountFunds> {
+        let account = self
+            .client
+            .get_account(account_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Account {} not found", account_id))?;
+
+        let mut balances = Vec::new();
+        let mut non_fungible_assets = 0;
+        for asset in account.account().vault().assets() {
+            match asset {
+                Asset::Fungible(fungible) => balances.push(FungibleBalance {
+                    faucet_id: account_id_to_hex(fungible.faucet_id()),
+                    amount: fungible.amount(),
+                }),
+                Asset::NonFungible(_) => non_fungible_assets += 1,
+            }
+        }
+
+        let pending_note_ids = self
+            .client
+            .get_consumable_notes(Some(account_id))
+            .await?
+            .iter()
+            .filter(|(note, _)| self.admissible(note))
+            .map(|(note, _)| note.id().to_string())
+            .collect();
+
+        let reason = self.unrecoverable_reason(account_id, treasury);
+        Ok(AccountFunds {
+            account_id: account_id_to_hex(account_id),
+            balances,
+            non_fungible_assets,
+            pending_note_ids,
+            recoverable: reason.is_none(),
+            reason,
+        })
+    }
+
+    /// Lists accounts in the client store that hold assets or pending notes.
+    pub async fn scan_recoverable_funds(&mut self, api_key: Option<&str>) -> Result<RecoveryScan> {
+        self.admin_principal(api_key, "funds recovery")?;
+        self.sync_state().await?;
+        let treasury = self.treasury_account_id().ok();
+
+        let headers = self.client.get_account_headers().await?;
+        let accounts_scanned = headers.len();
+
+        let mut accounts = Vec::new();
+        let mut totals: BTreeMap<String, u64> = BTreeMap::new();
+        for (header, _) in headers {
+            let funds = self.account_funds(header.id(), treasury).await?;
+            if !funds.holds_funds() {
+                continue;
+            }
+            if funds.recoverable {
+                for balance in &funds.balances {
+                    *totals.entry(balance.faucet_id.clone()).or_default() += balance.amount;
+                }
+            }
+            accounts.push(funds);
+        }
+
+        Ok(RecoveryScan {
+            accounts,
+            accounts_scanned,
+            recoverable_totals: totals
+                .into_iter()
+                .map(|(faucet_id, amount)| FungibleBalance { faucet_id, amount })
+                .collect(),
+        })
+    }
+
+    /// Sweeps recoverable accounts into the treasury (see module docs).
+    pub async fn sweep_recoverable_funds(
+        &mut self,
+        input: SweepInput,
+        api_key: Option<&str>,
+    ) -> Result<SweepReport> {
+        let treasury = self.treasury_account_id()?;
+        let treasury_hex = account_id_to_hex(treasury);
+
+        let scan = self.scan_recoverable_funds(api_key).await?;
+        let mut candidates = scan.accounts;
+        if let Some(account_ids) = &input.account_ids {
+            let wanted: Vec<String> = account_ids.iter().map(|id| id.to_lowercase()).collect();
+            if let Some(missing) = wanted
+                .iter()
+                .find(|id| !candidates.iter().any(|c| &c.account_id == *id))
+            {
+                return Err(anyhow::anyhow!(
+                    "Account {} is not in the client store or holds no funds",
+                    missing
+                ));
+            }
+            candidates.retain(|c| wanted.contains(&c.account_id));
+        }
+        let (recoverable, skipped): (Vec<_>, Vec<_>) =
+            candidates.into_iter().partition(|c| c.recoverable);
+
+        let mut swept = Vec::new();
+        for funds in recoverable {
+            if input.dry_run {
+                swept.push(SweptAccount {
+                    account_id: funds.account_id,
+                    consume_tx_ids: Vec::new(),
+                    consumed_note_ids: funds.pending_note_ids,
+                    refused_note_ids: Vec::new(),
+                    sweep_tx_id: None,
+                    sweep_note_id: None,
+                    error: None,
+                });
+                continue;
+            }
+
+            let op_id = self
+                .records
+                .begin_operation("recovery_sweep", &funds.account_id);
+            let mut outcome = SweptAccount {
+                account_id: funds.account_id.clone(),
+                consume_tx_ids: Vec::new(),
+                consumed_note_ids: Vec::new(),
+                refused_note_ids: Vec::new(),
+                sweep_tx_id: None,
+                sweep_note_id: None,
+                error: None,
+            };
+            let result = self.sweep_account(&funds, treasury, &mut outcome).await;
+            self.records
+                .finish_operation(op_id, &result, outcome.sweep_tx_id.clone());
+            if let Err(e) = result {
+                tracing::warn!("⚠️  Could not sweep {}: {}", funds.account_id, e);
+                outcome.error = Some(e.to_string());
+            }
+            swept.push(outcome);
+        }
+
+        Ok(SweepReport {
+            treasury_account_id: treasury_hex,
+            dry_run: input.dry_run,
+            swept,
+            skipped,
+        })
+    }
+
+    /// Consumes an orphaned account's pending notes and sends its vault to the
+    /// treasury, filling in `outcome` as steps complete.
+    async fn sweep_account(
+        &mut self,
+        funds: &AccountFunds,
+        treasury: AccountId,
+        outcome: &mut SweptAccount,
+    ) -> Result<String> {
+        let account_id = parse_hex_account_id(&funds.account_id)?;
+
+        if !funds.pending_note_ids.is_empty() {
+            let consumed = self.submit_consume_all(account_id).await?;
+            outcome.consume_tx_ids = consumed.transaction_ids();
+            outcome.consumed_note_ids = consumed.note_ids();
+            outcome.refused_note_ids = consumed.refused_note_ids();
+            // The vault is swept by a later run, with the notes left over
+            if consumed.error.is_some() {
+                consumed.into_tx_id()?;
+            }
+        }
+
+        let account = self
+            .client
+            .get_account(account_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Account {} not found", funds.account_id))?;
+        let assets: Vec<Asset> = account.account().vault().assets().collect();
+        if assets.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Vault is empty after consuming pending notes"
+            ));
+        }
+
+        tracing::info!(
+            "🧹 Sweeping {} asset(s) from {} to treasury {}",
+            assets.len(),
+            funds.account_id,
+            treasury
+        );
+
+        let swept: Vec<FungibleBalance> = assets
+            .iter()
+            .filter_map(|asset| match asset {
+                Asset::Fungible(fungible) => Some(FungibleBalance {
+                    faucet_id: account_id_to_hex(fungible.faucet_id()),
+                    amount: fungible.amount(),
+                }),
+                Asset::NonFungible(_) => None,
+            })
+            .collect();
+
+        let postings = asset_postings(account_id, treasury, &assets);
+        let (note_id, transaction_request) =
+            client::p2id_request(account_id, treasury, assets, &mut self.rng)?;
+        let note_id = note_id.to_string();
+        let tx_id = self
+            .client
+            .submit_new_transaction(account_id, transaction_request)
+            .await?
+            .to_string();
+
+        self.records.expect_note(
+            &note_id,
+            &account_id_to_hex(treasury),
+            "recovered-funds",
+            Some(tx_id.clone()),
+        );
+        self.post_ledger_entry(JournalKind::Recovery, postings, &tx_id, Some(&funds.account_id));
+        for balance in swept {
+            self.record_treasury_entry(LedgerEntry {
+                counterparty: Some(funds.account_id.clone()),
+                note_id: Some(note_id.clone()),
+                tx_id: Some(tx_id.clone()),
+                ..LedgerEntry::new(
+                    LedgerEntryKind::Recovered,
+                    balance.faucet_id,
+                    balance.amount,
+                )
+            });
+        }
+        outcome.sweep_tx_id = Some(tx_id.clone());
+        outcome.sweep_note_id = Some(note_id);
+
+        self.sync_state().await?;
+        Ok(tx_id)
+    }
+}
@@ -0,0 +1,592 @@
+// src/retry_queue.rs
+//
+// Deadline-aware retry queue for failed submissions
+//
+// Mutating requests (mint, transfer, escrow fund/release/refund) that fail for
+// a transient reason — an RPC timeout or unavailable node, or a transaction
+// built against stale local state — are queued for automatic retry instead of
+// failing outright. The request then answers 202 with the retry job ID.
+//
+// Each job is retried from the scheduler tick (scheduler.rs): the client syncs
+// first, so the retry is built against fresh state, then the operation runs
+// again. Waits between attempts double from RETRY_BACKOFF_SECS (and are never
+// shorter than SCHEDULER_TICK_SECS). A job ends as
+// - succeeded: an attempt went through
+// - failed: an attempt failed for a non-transient reason
+// - expired: RETRY_DEADLINE_SECS passed since the original request
+// - cancelled: by DELETE /jobs/:job_id (an attempt in progress is interrupted
+//   at its propagation wait, see deadlines.rs)
+//
+// Jobs are persisted (RETRY_QUEUE_PATH) and resumed after a restart. Their
+// state is served by GET /retry-jobs; every state change is also published as
+// an event, streamed by GET /retry-jobs/events.
+//
+// Jobs hold operations that were already authorized when they were queued;
+// retries run without an API key.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+use tokio::sync::broadcast;
+
+use crate::{
+    account_id_to_hex,
+    currency::PriceInput,
+    escrow::{EscrowAccount, EscrowStatus},
+    MidenClientWrapper,
+};
+
+/// Buffered retry events per subscriber before it starts missing events.
+pub const RETRY_FEED_CAPACITY: usize = 256;
+
+/// Error prefixes that are never worth retrying (see escrow.rs, liens.rs,
+/// hooks.rs).
+const PERMANENT_PREFIXES: &[&str] = &[
+    "Unauthorized:",
+    "Forbidden:",
+    "Encumbered:",
+    "Conflict:",
+    "Vetoed:",
+];
+
+/// Error fragments (lowercase) of failures that may go away on their own.
+const TRANSIENT_MARKERS: &[&str] = &[
+    "timeout",
+    "timed out",
+    "deadline",
+    "unavailable",
+    "connection",
+    "transport",
+    "too many requests",
+    "stale",
+    "out of sync",
+    "nonce",
+    "initial state",
+];
+
+/// Whether a failed submission is worth retrying.
+pub fn is_transient(error: &str) -> bool {
+    if PERMANENT_PREFIXES.iter().any(|p| error.starts_with(p)) {
+        return false;
+    }
+    let error = error.to_lowercase();
+    TRANSIENT_MARKERS.iter().any(|m| error.contains(m))
+}
+
+/// A mutating request that can be replayed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RetryOperation {
+    MintProperty {
+        property_id: String,
+        owner_account_id: String,
+        ipfs_cid: String,
+        property_type: u8,
+        price: PriceInput,
+    },
+    TransferProperty {
+        property_id: String,
+        to_account_id: String,
+        /// Sales contract the transfer note is anchored to (contract_anchors.rs)
+        #[serde(default)]
+        contract_hash: Option<String>,
+    },
+    FundEscrow {
+        escrow_account_id: String,
+        /// Allowance a delegate funds from (allowances.rs)
+        allowance_id: Option<u64>,
+    },
+    ReleaseEscrow {
+        escrow_account_id: String,
+    },
+    RefundEscrow {
+        escrow_account_id: String,
+    },
+}
+
+impl RetryOperation {
+    pub fn describe(&self) -> String {
+        match self {
+            RetryOperation::MintProperty { property_id, .. } => format!("mint {}", property_id),
+            RetryOperation::TransferProperty { property_id, .. } => {
+                format!("transfer {}", property_id)
+            }
+            RetryOperation::FundEscrow {
+                escrow_account_id, ..
+            } => format!("fund escrow {}", escrow_account_id),
+            RetryOperation::ReleaseEscrow { escrow_account_id } => {
+                format!("release escrow {}", escrow_account_id)
+            }
+            RetryOperation::RefundEscrow { escrow_account_id } => {
+                format!("refund escrow {}", escrow_account_id)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryStatus {
+    Pending,
+    Succeeded,
+    Failed,
+    Expired,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryEventKind {
+    Queued,
+    AttemptFailed,
+    Succeeded,
+    Failed,
+    Expired,
+    Cancelled,
+}
+
+impl RetryEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RetryEventKind::Queued => "queued",
+            RetryEventKind::AttemptFailed => "attempt_failed",
+            RetryEventKind::Succeeded => "succeeded",
+            RetryEventKind::Failed => "failed",
+            RetryEventKind::Expired => "expired",
+            RetryEventKind::Cancelled => "cancelled",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryEvent {
+    pub job_id: String,
+    pub at: i64,
+    pub kind: RetryEventKind,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryJob {
+    pub job_id: String,
+    pub operation: RetryOperation,
+    pub status: RetryStatus,
+    /// Attempts made by the queue (the original request not included)
+    pub attempts: u32,
+    pub created_at: i64,
+    pub deadline: i64,
+    pub next_attempt_at: i64,
+    pub last_error: String,
+    /// Result of the successful attempt (transaction ID, note ID)
+    pub result: Option<serde_json::Value>,
+    pub finished_at: Option<i64>,
+    pub events: Vec<RetryEvent>,
+}
+
+impl RetryJob {
+    fn event(&mut self, kind: RetryEventKind, detail: String) {
+        self.events.push(RetryEvent {
+            job_id: self.job_id.clone(),
+            at: chrono::Utc::now().timestamp(),
+            kind,
+            detail,
+        });
+    }
+
+    fn finish(&mut self, status: RetryStatus, kind: RetryEventKind, detail: String) {
+        self.status = status;
+        self.finished_at = Some(chrono::Utc::now().timestamp());
+        self.event(kind, detail);
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RetryQueue {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(skip)]
+    feed: Option<broadcast::Sender<RetryEvent>>,
+    /// Events per job already published on the feed
+    #[serde(skip)]
+    published: HashMap<String, usize>,
+    #[serde(default)]
+    jobs: BTreeMap<String, RetryJob>,
+    #[serde(default)]
+    next_job_id: u64,
+}
+
+impl RetryQueue {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<RetryQueue>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            RetryQueue::default()
+        };
+        store.path = path;
+        // Events from before a restart are not replayed to subscribers
+        store.published = store
+            .jobs
+            .values()
+            .map(|j| (j.job_id.clone(), j.events.len()))
+            .collect();
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Persists, logging instead of failing, then publishes new events.
+    pub fn persist(&mut self) {
+        if let Err(e) = self.save() {
+            tracing::warn!("Failed to persist retry queue: {}", e);
+        }
+        self.publish();
+    }
+
+    /// Sends new events to feed subscribers (see main.rs, GET /retry-jobs/events).
+    pub fn attach_feed(&mut self, feed: broadcast::Sender<RetryEvent>) {
+        self.feed = Some(feed);
+    }
+
+    fn publish(&mut self) {
+        for job in self.jobs.values() {
+            let sent = self.published.entry(job.job_id.clone()).or_default();
+            if let Some(feed) = &self.feed {
+                for event in &job.events[(*sent).min(job.events.len())..] {
+                    // No subscribers is not an error
+                    let _ = feed.send(event.clone());
+                }
+            }
+            *sent = job.events.len();
+        }
+    }
+
+    pub fn get(&self, job_id: &str) -> Result<&RetryJob> {
+        self.jobs
+            .get(job_id)
+            .ok_or_else(|| anyhow::anyhow!("Retry job {} not found", job_id))
+    }
+
+    pub fn get_mut(&mut self, job_id: &str) -> Result<&mut RetryJob> {
+        self.jobs
+            .get_mut(job_id)
+            .ok_or_else(|| anyhow::anyhow!("Retry job {} not found", job_id))
+    }
+
+    /// Jobs, newest first, optionally only those in one state.
+    pub fn list(&self, status: Option<RetryStatus>) -> Vec<&RetryJob> {
+        let mut jobs: Vec<&RetryJob> = self
+            .jobs
+            .values()
+            .filter(|j| status.is_none_or(|s| j.status == s))
+            .collect();
+        jobs.sort_by_key(|j| std::cmp::Reverse(j.created_at));
+        jobs
+    }
+
+    pub fn enqueue(
+        &mut self,
+        operation: RetryOperation,
+        error: &str,
+        deadline: i64,
+        first_attempt_at: i64,
+    ) -> RetryJob {
+        self.next_job_id += 1;
+        let mut job = RetryJob {
+            job_id: format!("retry-{}", self.next_job_id),
+            operation,
+            status: RetryStatus::Pending,
+            attempts: 0,
+            created_at: chrono::Utc::now().timestamp(),
+            deadline,
+            next_attempt_at: first_attempt_at,
+            last_error: error.to_string(),
+            result: None,
+            finished_at: None,
+            events: Vec::new(),
+        };
+        job.event(RetryEventKind::Queued, error.to_string());
+
+        self.jobs.insert(job.job_id.clone(), job.clone());
+        self.persist();
+        job
+    }
+
+    /// Pending jobs due for an attempt at `now`, oldest first.
+    pub fn due(&self, now: i64) -> Vec<String> {
+        let mut due: Vec<&RetryJob> = self
+            .jobs
+            .values()
+            .filter(|j| j.status == RetryStatus::Pending && j.next_attempt_at <= now)
+            .collect();
+        due.sort_by_key(|j| j.created_at);
+        due.into_iter().map(|j| j.job_id.clone()).collect()
+    }
+}
+
+/// Refuses an escrow step the escrow has moved past: a second funding, a
+/// refund after a release, or a replay of an attempt that reported a timeout
+/// but went through after all.
+pub(crate) fn expect_escrow_status(escrow: &EscrowAccount, expected: EscrowStatus) -> Result<()> {
+    if escrow.status != expected {
+        return Err(anyhow::anyhow!(
+            "Conflict: escrow {} is {:?}, expected {:?}",
+            account_id_to_hex(escrow.escrow_account_id),
+            escrow.status,
+            expected
+        ));
+    }
+    Ok(())
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    GetRetryJob {
+        job_id: String,
+    } -> serde_json::Value;
+    |client, op| client.get_retry_job(&op.job_id);
+
+    ListRetryJobs {
+        status: Option<RetryStatus>,
+    } -> serde_json::Value;
+    |client, op| client.list_retry_jobs(op.status)
+}
+
+impl MidenClientWrapper {
+    /// Publishes retry events on `feed` from now on.
+    pub fn attach_retry_feed(&mut self, feed: broadcast::Sender<RetryEvent>) {
+        self.retries.attach_feed(feed);
+    }
+
+    /// Passes `result` through, except that a transient failure is queued for
+    /// retry and reported as a "Retrying:" error naming the retry job. A
+    /// connectivity failure of a request in offline mode goes to the offline
+    /// queue instead (offline_queue.rs).
+    pub fn retry_on_failure<T>(
+        &mut self,
+        operation: RetryOperation,
+        result: Result<T>,
+    ) -> Result<T> {
+        let error = match result {
+            Ok(value) => return Ok(value),
+            Err(e) => e.to_string(),
+        };
+        if let Some(queued) = self.queue_offline(&operation, &error) {
+            return Err(queued);
+        }
+        if self.config.retry_deadline.is_zero() || !is_transient(&error) {
+            return Err(anyhow::anyhow!(error));
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let deadline = now + self.config.retry_deadline.as_secs() as i64;
+        let first_attempt_at = now + self.config.retry_backoff.as_secs() as i64;
+        let description = operation.describe();
+        let job = self
+            .retries
+            .enqueue(operation, &error, deadline, first_attempt_at);
+        tracing::warn!(
+            "🔁 {} failed ({}); queued as {} until {}",
+            description,
+            error,
+            job.job_id,
+            deadline
+        );
+
+        Err(anyhow::anyhow!(
+            "Retrying: {}; queued as retry job {} (deadline {})",
+            error,
+            job.job_id,
+            deadline
+        ))
+    }
+
+    /// Runs one attempt of every due retry job. Returns the number of jobs
+    /// that finished.
+    pub async fn run_retry_queue(&mut self) -> Result<usize> {
+        let now = chrono::Utc::now().timestamp();
+        let mut finished = 0;
+
+        for job_id in self.retries.due(now) {
+            let (operation, deadline, attempts) = {
+                let job = self.retries.get(&job_id)?;
+                (job.operation.clone(), job.deadline, job.attempts)
+            };
+
+            if now >= deadline {
+                let job = self.retries.get_mut(&job_id)?;
+                let detail = format!("Deadline passed after {} attempt(s)", attempts);
+                job.finish(RetryStatus::Expired, RetryEventKind::Expired, detail);
+                self.retries.persist();
+                tracing::warn!("Retry job {} expired", job_id);
+                finished += 1;
+                continue;
+            }
+
+            // Cancellable while it runs (DELETE /jobs/:job_id)
+            let token = self.job_cancellations.register(&job_id);
+            let outer = self.cancellation.replace(token.clone());
+            let result = self.attempt_retry(&operation).await;
+            self.cancellation = outer;
+            self.job_cancellations.forget(&job_id);
+
+            let job = self.retries.get_mut(&job_id)?;
+            job.attempts += 1;
+            match result {
+                Err(e) if token.is_cancelled() => {
+                    job.last_error = e.to_string();
+                    let detail = format!("Cancelled during attempt {}", job.attempts);
+                    job.finish(RetryStatus::Cancelled, RetryEventKind::Cancelled, detail);
+                    tracing::info!("Retry job {} cancelled", job_id);
+                    finished += 1;
+                }
+                Ok(value) => {
+                    job.result = Some(value);
+                    let detail = format!("Attempt {} went through", job.attempts);
+                    job.finish(RetryStatus::Succeeded, RetryEventKind::Succeeded, detail);
+                    tracing::info!("✅ Retry job {} succeeded", job_id);
+                    finished += 1;
+                }
+                Err(e) => {
+                    let error = e.to_string();
+                    let backoff = self.config.retry_backoff.as_secs() as i64
+                        * 2_i64.pow(job.attempts.min(16));
+                    job.last_error = error.clone();
+                    if !is_transient(&error) {
+                        job.finish(RetryStatus::Failed, RetryEventKind::Failed, error);
+                        tracing::warn!("Retry job {} failed", job_id);
+                        finished += 1;
+                    } else if now + backoff >= deadline {
+                        let detail = format!("{} (no attempt left before the deadline)", error);
+                        job.finish(RetryStatus::Expired, RetryEventKind::Expired, detail);
+                        tracing::warn!("Retry job {} expired", job_id);
+                        finished += 1;
+                    } else {
+                        job.next_attempt_at = now + backoff;
+                        job.event(RetryEventKind::AttemptFailed, error);
+                    }
+                }
+            }
+            self.retries.persist();
+        }
+
+        Ok(finished)
+    }
+
+    /// Replays an operation against freshly synced state.
+    pub(crate) async fn attempt_retry(
+        &mut self,
+        operation: &RetryOperation,
+    ) -> Result<serde_json::Value> {
+        self.sync_state().await?;
+
+        match operation {
+            RetryOperation::MintProperty {
+                property_id,
+                owner_account_id,
+                ipfs_cid,
+                property_type,
+                price,
+            } => {
+                // A previous attempt may have landed after all
+                if let Some(property) = self.records.properties.get(property_id) {
+                    return Ok(serde_json::json!({
+                        "tx_id": property.mint_tx_id,
+                        "note_id": property.note_id,
+                    }));
+                }
+                let (tx_id, note_id) = self
+                    .mint_property_nft(
+                        property_id,
+                        owner_account_id,
+                        ipfs_cid,
+                        *property_type,
+                        price,
+                    )
+                    .await?;
+                Ok(serde_json::json!({ "tx_id": tx_id, "note_id": note_id }))
+            }
+            RetryOperation::TransferProperty {
+                property_id,
+                to_account_id,
+                contract_hash,
+            } => {
+                let tx_id = self
+                    .transfer_property_under_contract(
+                        property_id,
+                        to_account_id,
+                        contract_hash.as_deref(),
+                    )
+                    .await?;
+                Ok(serde_json::json!({ "tx_id": tx_id }))
+            }
+            RetryOperation::FundEscrow {
+                escrow_account_id,
+                allowance_id,
+            } => {
+                let escrow = self.recorded_escrow(escrow_account_id)?;
+                let tx_id = self.fund_escrow_unchecked(&escrow, *allowance_id).await?;
+                Ok(serde_json::json!({ "tx_id": tx_id }))
+            }
+            RetryOperation::ReleaseEscrow { escrow_account_id } => {
+                let escrow = self.recorded_escrow(escrow_account_id)?;
+                let tx_id = self.release_escrow_unchecked(&escrow).await?;
+                Ok(serde_json::json!({ "tx_id": tx_id }))
+            }
+            RetryOperation::RefundEscrow { escrow_account_id } => {
+                let escrow = self.recorded_escrow(escrow_account_id)?;
+                let tx_id = self.refund_escrow_unchecked(&escrow).await?;
+                Ok(serde_json::json!({ "tx_id": tx_id }))
+            }
+        }
+    }
+
+    /// Cancels a pending retry job. Returns the job.
+    pub fn cancel_retry_job(&mut self, job_id: &str) -> Result<serde_json::Value> {
+        let job = self.retries.get_mut(job_id)?;
+        // Already interrupted mid-attempt (see run_retry_queue)
+        if job.status == RetryStatus::Cancelled {
+            return Ok(serde_json::json!(job));
+        }
+        if job.status != RetryStatus::Pending {
+            return Err(anyhow::anyhow!(
+                "Conflict: retry job {} is {:?}",
+                job_id,
+                job.status
+            ));
+        }
+        job.finish(
+            RetryStatus::Cancelled,
+            RetryEventKind::Cancelled,
+            "Cancelled by request".to_string(),
+        );
+        let job = serde_json::json!(job);
+        self.retries.persist();
+        tracing::info!("Retry job {} cancelled", job_id);
+
+        Ok(job)
+    }
+
+    pub fn get_retry_job(&self, job_id: &str) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self.retries.get(job_id)?))
+    }
+
+    pub fn list_retry_jobs(&self, status: Option<RetryStatus>) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self.retries.list(status)))
+    }
+}
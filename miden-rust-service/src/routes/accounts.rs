@@ -0,0 +1,695 @@
+// src/routes/accounts.rs
+//
+// Account endpoints: account details, balances (current and at a past block),
+// monthly statements, portfolios, component upgrades, token sends, display
+// formatting, spending allowances (allowances.rs), organizations
+// (organizations.rs), wallet sessions (wallet_sessions.rs) and tax lots and
+// reports (tax.rs).
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use serde::Deserialize;
+use tracing::info;
+
+use miden_rust_service::{
+    account_upgrades::{UpgradeAccount, UpgradeInput},
+    allowances::{
+        AllowanceInput, AllowanceTransferInput, GetAllowance, GrantAllowance, ListAllowances,
+        RevokeAllowance, TransferFromAllowance,
+    },
+    balance_history::GetBalanceAt,
+    currency::{GetFormatting, Locale},
+    documents::ArtifactFormat,
+    etag::EtagResource,
+    org_feed::{FeedQuery, GetOrganizationFeed},
+    organizations::{
+        AddOrgAccount, CreateOrganization, GetOrganization, ListOrganizations, MemberInput,
+        MemberRoleInput, OrgAccountInput, OrganizationInput, RemoveOrgAccount, RemoveOrgMember,
+        SetOrgMember,
+    },
+    portfolio::GetPortfolio,
+    statements::GetAccountStatement,
+    tax::{GetTaxReport, ListTaxLots, LotSelectionInput, SelectTaxLots},
+    timestamps::DisplayZone,
+    validation::{self, Validate, ValidationErrors},
+    wallet_sessions::{
+        BuildUnsignedPayment, ChallengeInput, CreateWalletChallenge, CreateWalletSession,
+        EndWalletSession, GetWalletSession, ListWalletNotes, SessionInput, UnsignedPaymentInput,
+    },
+    GetAccountInfo, GetBalance, SendTokens,
+};
+
+use super::{call, failure, respond};
+use crate::{api_key_header, conditional_read, wallet_session_header, AppState, ValidJson};
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/get-account", get(get_account_info))
+        .route("/get-balance/:account_id", get(get_balance))
+        .route("/accounts/:account_id/portfolio", get(get_portfolio))
+        .route("/accounts/:account_id/balance", get(get_balance_at))
+        .route(
+            "/accounts/:account_id/statement",
+            get(get_account_statement),
+        )
+        .route("/accounts/:account_id/upgrade", post(upgrade_account))
+        .route("/send-tokens", post(send_tokens))
+        .route("/formatting", get(get_formatting))
+        .route("/allowances", get(list_allowances).post(grant_allowance))
+        .route(
+            "/allowances/:allowance_id",
+            get(get_allowance).delete(revoke_allowance),
+        )
+        .route(
+            "/allowances/:allowance_id/transfers",
+            post(transfer_from_allowance),
+        )
+        .route("/orgs", get(list_organizations).post(create_organization))
+        .route("/orgs/:org_id", get(get_organization))
+        .route("/orgs/:org_id/members", post(add_org_member))
+        .route(
+            "/orgs/:org_id/members/:key_id",
+            put(set_org_member_role).delete(remove_org_member),
+        )
+        .route("/orgs/:org_id/accounts", post(add_org_account))
+        .route(
+            "/orgs/:org_id/accounts/:account_id",
+            delete(remove_org_account),
+        )
+        .route("/orgs/:org_id/feed", get(get_organization_feed))
+        // Wallet sessions (X-Wallet-Session header)
+        .route("/wallet/challenges", post(create_wallet_challenge))
+        .route("/wallet/sessions", post(create_wallet_session))
+        .route(
+            "/wallet/session",
+            get(get_wallet_session).delete(end_wallet_session),
+        )
+        .route("/wallet/notes", get(list_wallet_notes))
+        .route("/wallet/transactions/p2id", post(build_unsigned_payment))
+        .route("/tax/:account_id/lots", get(list_tax_lots))
+        .route("/tax/:account_id/lot-selection", post(select_tax_lots))
+        .route("/tax/:account_id/reports/:year", get(get_tax_report))
+}
+
+/// Block to reconstruct balances at; the latest indexed block when absent
+#[derive(Debug, Deserialize)]
+struct BalanceAtQuery {
+    at_block: Option<u32>,
+}
+
+async fn get_account_info(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    info!("Received get account info request");
+
+    conditional_read(
+        &state,
+        &headers,
+        EtagResource::AccountInfo,
+        get_account_info_uncached(state.clone()),
+    )
+    .await
+}
+
+async fn get_account_info_uncached(state: AppState) -> (StatusCode, Json<serde_json::Value>) {
+    let op = GetAccountInfo {};
+    respond(call(&state, op).await, "get account info")
+}
+
+async fn get_balance(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(account_id): Path<String>,
+) -> Response {
+    info!("Received get balance request for: {}", account_id);
+
+    conditional_read(
+        &state,
+        &headers,
+        EtagResource::Balance {
+            account: account_id.clone(),
+        },
+        get_balance_uncached(state.clone(), account_id),
+    )
+    .await
+}
+
+async fn get_balance_uncached(
+    state: AppState,
+    account_id: String,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = GetBalance { account_id };
+    respond(call(&state, op).await, "get balance")
+}
+
+// ============================================================================
+// PORTFOLIO ENDPOINTS
+// ============================================================================
+
+async fn get_portfolio(
+    State(state): State<AppState>,
+    Path(account_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received portfolio request: {}", account_id);
+    let op = GetPortfolio { account_id };
+    respond(call(&state, op).await, "build portfolio")
+}
+
+/// Balances of an account at a past block (see balance_history.rs).
+async fn get_balance_at(
+    State(state): State<AppState>,
+    Path(account_id): Path<String>,
+    Query(query): Query<BalanceAtQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received balance request for {} at {:?}",
+        account_id, query.at_block
+    );
+    let op = GetBalanceAt {
+        account_id,
+        at_block: query.at_block,
+    };
+    respond(call(&state, op).await, "get balance at block")
+}
+
+// ============================================================================
+// ACCOUNT UPGRADE AND STATEMENT ENDPOINTS
+// ============================================================================
+
+/// Checks an account component upgrade; applying one is refused (see
+/// account_upgrades.rs).
+async fn upgrade_account(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(account_id): Path<String>,
+    ValidJson(payload): ValidJson<UpgradeInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received account upgrade request for: {} (dry run: {})",
+        account_id, payload.dry_run
+    );
+    let op = UpgradeAccount {
+        account_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "upgrade account")
+}
+
+/// Statement of one month: a PDF unless `?format=json`; `?tz=` for the zone
+/// the month is cut in and dates are printed in
+#[derive(Debug, Deserialize)]
+struct StatementQuery {
+    month: String,
+    format: Option<ArtifactFormat>,
+    tz: Option<String>,
+}
+
+/// A month of an account's movements and balances (see statements.rs).
+async fn get_account_statement(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(account_id): Path<String>,
+    Query(query): Query<StatementQuery>,
+) -> Response {
+    info!(
+        "Received statement request for {} ({})",
+        account_id, query.month
+    );
+    let zone = match DisplayZone::requested(&headers, query.tz.as_deref()) {
+        Ok(zone) => zone,
+        Err(e) => return failure(StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let op = GetAccountStatement {
+        account_id,
+        month: query.month,
+        format: query.format.unwrap_or(ArtifactFormat::Pdf),
+        zone,
+        api_key: api_key_header(&headers),
+    };
+    match call(&state, op).await {
+        Ok(artifact) => (
+            [
+                (header::CONTENT_TYPE, artifact.content_type.to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}\"", artifact.filename),
+                ),
+            ],
+            artifact.bytes,
+        )
+            .into_response(),
+        Err(e) => respond(Err::<(), _>(e), "render account statement").into_response(),
+    }
+}
+
+// ============================================================================
+// TOKEN TRANSFER ENDPOINTS
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+struct SendTokensRequest {
+    to_account_id: String,
+    amount: u64,
+}
+
+impl Validate for SendTokensRequest {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "to_account_id",
+            validation::account_selector(&self.to_account_id, &["alice", "bob"]),
+        );
+        errors.check("amount", validation::positive(self.amount));
+    }
+}
+
+async fn send_tokens(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<SendTokensRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received send tokens request: {:?}", payload);
+
+    let op = SendTokens {
+        to_account_id: payload.to_account_id,
+        amount: payload.amount,
+    };
+    let result = call(&state, op).await.map(|tx_id| {
+        info!("Tokens sent: tx={}", tx_id);
+        serde_json::json!({ "transaction_id": tx_id })
+    });
+    respond(result, "send tokens")
+}
+
+// ============================================================================
+// FORMATTING ENDPOINTS (see currency.rs)
+// ============================================================================
+
+/// Locale tag such as "de-DE"; English conventions when absent
+#[derive(Debug, Deserialize)]
+struct FormattingQuery {
+    locale: Option<String>,
+}
+
+/// Symbols, decimals and separators for displaying prices and token amounts.
+async fn get_formatting(
+    State(state): State<AppState>,
+    Query(query): Query<FormattingQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let locale = match query.locale.as_deref().map(str::parse::<Locale>) {
+        Some(Ok(locale)) => locale,
+        None => Locale::En,
+        Some(Err(e)) => return failure(StatusCode::BAD_REQUEST, e),
+    };
+    let op = GetFormatting { locale };
+    respond(call(&state, op).await, "get formatting")
+}
+
+// ============================================================================
+// SPENDING ALLOWANCE ENDPOINTS (see allowances.rs)
+// ============================================================================
+
+/// Allowance listing filter (owner or spender)
+#[derive(Debug, Deserialize)]
+struct AllowanceQuery {
+    account_id: Option<String>,
+}
+
+async fn grant_allowance(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<AllowanceInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received grant allowance request: {:?}", payload);
+    let op = GrantAllowance {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "grant allowance")
+}
+
+async fn revoke_allowance(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(allowance_id): Path<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received revoke allowance request: {}", allowance_id);
+    let op = RevokeAllowance {
+        allowance_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "revoke allowance")
+}
+
+async fn get_allowance(
+    State(state): State<AppState>,
+    Path(allowance_id): Path<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received allowance request: {}", allowance_id);
+    let op = GetAllowance { allowance_id };
+    respond(call(&state, op).await, "get allowance")
+}
+
+async fn list_allowances(
+    State(state): State<AppState>,
+    Query(query): Query<AllowanceQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list allowances request: {:?}", query.account_id);
+    let op = ListAllowances {
+        account_id: query.account_id,
+    };
+    respond(call(&state, op).await, "list allowances")
+}
+
+async fn transfer_from_allowance(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(allowance_id): Path<u64>,
+    ValidJson(payload): ValidJson<AllowanceTransferInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received allowance transfer request: {} {:?}",
+        allowance_id, payload
+    );
+    let op = TransferFromAllowance {
+        allowance_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "transfer from allowance")
+}
+
+// ============================================================================
+// ORGANIZATION ENDPOINTS (see organizations.rs)
+// ============================================================================
+
+/// Creates an organization owned by the calling API key.
+async fn create_organization(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<OrganizationInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received create organization request: {}", payload.name);
+    let op = CreateOrganization {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "create organization")
+}
+
+/// Organizations of the API key (all of them for admin keys).
+async fn list_organizations(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list organizations request");
+    let op = ListOrganizations {
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "list organizations")
+}
+
+/// An organization with its members, accounts, properties and escrows.
+async fn get_organization(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(org_id): Path<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received get organization {} request", org_id);
+    let op = GetOrganization {
+        org_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "get organization")
+}
+
+async fn add_org_member(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(org_id): Path<u64>,
+    ValidJson(payload): ValidJson<MemberInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received add member {} to organization {} request",
+        payload.key_id, org_id
+    );
+    let op = SetOrgMember {
+        org_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "add org member")
+}
+
+async fn set_org_member_role(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((org_id, key_id)): Path<(u64, u64)>,
+    ValidJson(payload): ValidJson<MemberRoleInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received organization {} member {} role request",
+        org_id, key_id
+    );
+    let op = SetOrgMember {
+        org_id,
+        input: MemberInput {
+            key_id,
+            role: payload.role,
+        },
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "set org member role")
+}
+
+async fn remove_org_member(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((org_id, key_id)): Path<(u64, u64)>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received remove member {} from organization {} request",
+        key_id, org_id
+    );
+    let op = RemoveOrgMember {
+        org_id,
+        key_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "remove org member")
+}
+
+async fn add_org_account(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(org_id): Path<u64>,
+    ValidJson(payload): ValidJson<OrgAccountInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received add account {} to organization {} request",
+        payload.account, org_id
+    );
+    let op = AddOrgAccount {
+        org_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "add org account")
+}
+
+async fn remove_org_account(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((org_id, account_id)): Path<(u64, String)>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received remove account {} from organization {} request",
+        account_id, org_id
+    );
+    let op = RemoveOrgAccount {
+        org_id,
+        account: account_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "remove org account")
+}
+
+/// What happened on the organization's accounts, newest first (see org_feed.rs).
+async fn get_organization_feed(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(org_id): Path<u64>,
+    Query(query): Query<FeedQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received organization {} feed request", org_id);
+    let op = GetOrganizationFeed {
+        org_id,
+        query,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "get organization feed")
+}
+
+// ============================================================================
+// WALLET SESSION ENDPOINTS (see wallet_sessions.rs)
+// ============================================================================
+
+async fn create_wallet_challenge(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<ChallengeInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received wallet challenge request: {}", payload.account_id);
+    let op = CreateWalletChallenge { input: payload };
+    respond(call(&state, op).await, "create wallet challenge")
+}
+
+async fn create_wallet_session(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<SessionInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received wallet session request for challenge {}",
+        payload.challenge_id
+    );
+    let op = CreateWalletSession { input: payload };
+    respond(call(&state, op).await, "open wallet session")
+}
+
+async fn get_wallet_session(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received wallet session lookup");
+    let op = GetWalletSession {
+        session_token: wallet_session_header(&headers),
+    };
+    respond(call(&state, op).await, "get wallet session")
+}
+
+async fn end_wallet_session(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received wallet logout");
+    let op = EndWalletSession {
+        session_token: wallet_session_header(&headers),
+    };
+    respond(call(&state, op).await, "end wallet session")
+}
+
+async fn list_wallet_notes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received wallet notes request");
+    let op = ListWalletNotes {
+        session_token: wallet_session_header(&headers),
+    };
+    respond(call(&state, op).await, "list wallet notes")
+}
+
+async fn build_unsigned_payment(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<UnsignedPaymentInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received unsigned payment request to {}",
+        payload.to_account_id
+    );
+    let op = BuildUnsignedPayment {
+        input: payload,
+        session_token: wallet_session_header(&headers),
+    };
+    respond(call(&state, op).await, "build unsigned payment")
+}
+
+// ============================================================================
+// TAX REPORTING ENDPOINTS (see tax.rs)
+// ============================================================================
+
+/// Tax report format: `?format=csv` (or `Accept: text/csv`) for CSV, JSON
+/// otherwise; `?tz=` for the zone CSV dates are printed in
+#[derive(Debug, Deserialize)]
+struct TaxReportQuery {
+    format: Option<String>,
+    tz: Option<String>,
+}
+
+async fn select_tax_lots(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(account_id): Path<String>,
+    ValidJson(payload): ValidJson<LotSelectionInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received tax lot selection for {}: {:?}",
+        account_id, payload
+    );
+    let op = SelectTaxLots {
+        account_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "select tax lots")
+}
+
+async fn list_tax_lots(
+    State(state): State<AppState>,
+    Path(account_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received tax lots request: {}", account_id);
+    let op = ListTaxLots { account_id };
+    respond(call(&state, op).await, "list tax lots")
+}
+
+const CSV_CONTENT_TYPE: &str = "text/csv; charset=utf-8";
+
+/// Realized gains for a calendar year, as JSON or CSV.
+async fn get_tax_report(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((account_id, year)): Path<(String, i32)>,
+    Query(query): Query<TaxReportQuery>,
+) -> Response {
+    info!("Received tax report request: {} {}", account_id, year);
+
+    let csv = match query.format.as_deref() {
+        Some(format) => format.eq_ignore_ascii_case("csv"),
+        None => headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .map(|accept| accept.contains("text/csv"))
+            .unwrap_or(false),
+    };
+    let zone = match DisplayZone::requested(&headers, query.tz.as_deref()) {
+        Ok(zone) => zone,
+        Err(e) => return failure(StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    let op = GetTaxReport { account_id, year };
+    match call(&state, op).await {
+        Ok(report) if csv => {
+            let account: String = report
+                .account_id
+                .chars()
+                .filter(|c| c.is_ascii_alphanumeric())
+                .collect();
+            let disposition = format!(
+                "attachment; filename=\"tax-report-{}-{}.csv\"",
+                account, year
+            );
+            (
+                [
+                    (header::CONTENT_TYPE, CSV_CONTENT_TYPE.to_string()),
+                    (header::CONTENT_DISPOSITION, disposition),
+                ],
+                report.to_csv(zone),
+            )
+                .into_response()
+        }
+        report => respond(report, "build tax report").into_response(),
+    }
+}
@@ -0,0 +1,1175 @@
+// src/routes/admin.rs
+//
+// Admin endpoints: API keys and the escrows of closing agent keys
+// (principals.rs), customer portal tokens (portal.rs), the master secret,
+// escrow account rebuilds, funds recovery, data subject requests, tax
+// withholding rules (withholding.rs), property re-issues
+// (property_registry.rs), re-issue requests after lost access
+// (property_recovery.rs), accreditation rules (accreditation_rules.rs),
+// identity providers (identity.rs), insurers (attachments.rs), reconciliation,
+// lifecycle hooks (hooks.rs), feature flags (feature_flags.rs), restricted-
+// jurisdiction lists, the treasury (treasury.rs), the ledger (ledger.rs), store
+// maintenance and snapshots, and queue, SLO and RPC introspection.
+// `require_admin` gates every /admin route.
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{MatchedPath, Path, Query, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use serde::Deserialize;
+use tracing::{error, info};
+
+use miden_rust_service::{
+    accreditation_rules::{
+        CreateAccreditationRule, DeleteAccreditationRule, ListAccreditationRules, RuleInput,
+        UpdateAccreditationRule,
+    },
+    api_version,
+    attachments::{DeactivateInsurer, InsurerInput, ListInsurers, RegisterInsurer},
+    data_subjects::{EraseDataSubject, ErasureInput, ExportDataSubject},
+    escrow::EscrowAuthError,
+    feature_flags::{ListFeatureFlags, SetFeatureFlag},
+    hooks::{CreateHook, DeleteHook, HookInput, ListHooks},
+    identity::{
+        DeactivateIdentityProvider, ListIdentityProviders, ProviderInput, RegisterIdentityProvider,
+    },
+    jurisdiction_lists::ListUpdate,
+    ledger::{GetTrialBalance, ListLedgerEntries},
+    listing::Listing,
+    portal::{IssuePortalAccess, ListPortalAccess, PortalAccessInput, RevokePortalAccess},
+    principals::{ApiKeyInput, AssignClosingAgentEscrow, UnassignClosingAgentEscrow},
+    property_recovery::{LostAccessInput, RequestPropertyReissue},
+    property_registry::{PropertyReissueInput, ReissueProperty},
+    reconcile::{GetOperations, Reconcile},
+    recovery::{ScanRecoverableFunds, SweepInput, SweepRecoverableFunds},
+    secrets::{GetMasterSecret, RebuildEscrowAccounts, RotateMasterSecret},
+    snapshots::{LatestSnapshot, ListSnapshots, PublishSnapshot, SnapshotFile},
+    store_maintenance::{GetStoreStats, PruneInput, PruneStore, VacuumStore},
+    treasury::{
+        CollectTreasuryNotes, GetTreasury, GetTreasuryIncome, GetTreasuryLedger, IncomeBucket,
+        LedgerEntryKind, RequestTreasuryWithdrawal, WithdrawalInput,
+    },
+    validation::{Validate, ValidationErrors},
+    withholding::{
+        CreateWithholdingRule, DeleteWithholdingRule, JurisdictionInput, ListWithholdingRules,
+        SetPropertyJurisdiction, WithholdingRuleInput,
+    },
+    IssueApiKey, ListApiKeys, ListJurisdictionLists, PublishJurisdictionList, RevokeApiKey,
+};
+
+#[cfg(feature = "fault-injection")]
+use miden_rust_service::faults::{ClearFaults, FaultInput, InjectFault, ListFaults};
+
+use super::{call, failure, ndjson_listing, respond, wants_ndjson};
+use crate::{api_key_header, AppState, ValidJson};
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/api-keys", get(list_api_keys).post(issue_api_key))
+        .route("/admin/api-keys/:key_id", delete(revoke_api_key))
+        .route(
+            "/admin/api-keys/:key_id/escrows/:escrow_account_id",
+            put(assign_closing_agent_escrow).delete(unassign_closing_agent_escrow),
+        )
+        .route(
+            "/admin/portal-access",
+            get(list_portal_access).post(issue_portal_access),
+        )
+        .route(
+            "/admin/portal-access/:access_id",
+            delete(revoke_portal_access),
+        )
+        .route("/admin/secrets", get(get_master_secret))
+        .route("/admin/secrets/rotate", post(rotate_master_secret))
+        .route("/admin/escrows/rebuild", post(rebuild_escrow_accounts))
+        .route("/admin/recovery", get(scan_recoverable_funds))
+        .route("/admin/recovery/sweep", post(sweep_recoverable_funds))
+        .route("/admin/data-subjects/:account_id", get(export_data_subject))
+        .route(
+            "/admin/data-subjects/:account_id/erase",
+            post(erase_data_subject),
+        )
+        .route(
+            "/admin/withholding-rules",
+            get(list_withholding_rules).post(create_withholding_rule),
+        )
+        .route(
+            "/admin/withholding-rules/:rule_id",
+            delete(delete_withholding_rule),
+        )
+        .route(
+            "/admin/properties/:property_id/jurisdiction",
+            put(set_property_jurisdiction),
+        )
+        .route(
+            "/admin/properties/:property_id/reissue",
+            post(reissue_property),
+        )
+        .route(
+            "/admin/properties/:property_id/reissue-requests",
+            post(request_property_reissue),
+        )
+        .route(
+            "/admin/accreditation-rules",
+            get(list_accreditation_rules).post(create_accreditation_rule),
+        )
+        .route(
+            "/admin/accreditation-rules/:rule_id",
+            put(update_accreditation_rule).delete(delete_accreditation_rule),
+        )
+        .route(
+            "/admin/identity-providers",
+            get(list_identity_providers).post(register_identity_provider),
+        )
+        .route(
+            "/admin/identity-providers/:provider_id",
+            delete(deactivate_identity_provider),
+        )
+        .route("/admin/insurers", get(list_insurers).post(register_insurer))
+        .route("/admin/insurers/:insurer_id", delete(deactivate_insurer))
+        .route("/admin/reconcile", post(reconcile))
+        .route("/admin/operations", get(get_operations))
+        .route("/admin/hooks", get(list_hooks).post(create_hook))
+        .route("/admin/hooks/:hook_id", delete(delete_hook))
+        .route("/admin/features", get(list_feature_flags))
+        .route(
+            "/admin/features/:feature",
+            put(set_feature_flag).delete(reset_feature_flag),
+        )
+        .route(
+            "/admin/jurisdiction-lists",
+            get(list_jurisdiction_lists).post(publish_jurisdiction_list),
+        )
+        .route("/admin/treasury", get(get_treasury))
+        .route("/admin/treasury/collect", post(collect_treasury_notes))
+        .route("/admin/treasury/ledger", get(get_treasury_ledger))
+        .route("/admin/treasury/income", get(get_treasury_income))
+        .route(
+            "/admin/treasury/withdrawals",
+            post(request_treasury_withdrawal),
+        )
+        .route("/admin/ledger/trial-balance", get(get_trial_balance))
+        .route("/admin/ledger/entries", get(list_ledger_entries))
+        .route("/admin/store", get(get_store_stats))
+        .route("/admin/store/prune", post(prune_store))
+        .route("/admin/store/vacuum", post(vacuum_store))
+        .route("/admin/snapshots", post(publish_snapshot))
+        .route("/snapshots", get(list_snapshots))
+        .route("/snapshots/latest", get(latest_snapshot))
+        .route("/snapshots/:snapshot_id/file", get(download_snapshot))
+        .route("/read-cache/stats", get(get_read_cache_stats))
+}
+
+/// Queue, SLO and RPC introspection. main.rs adds these after the deadline
+/// and load-shedding layers: never shed, so they answer when the queue is
+/// deep.
+pub(crate) fn monitoring_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/queue", get(get_queue))
+        .route("/admin/slo", get(get_slo))
+        .route("/admin/rpc", get(get_rpc))
+}
+
+/// Failure injection for integration tests (see faults.rs)
+#[cfg(feature = "fault-injection")]
+pub(crate) fn fault_router() -> Router<AppState> {
+    tracing::warn!("Fault injection enabled: /admin/faults is served");
+    Router::new().route(
+        "/admin/faults",
+        get(list_faults).post(inject_fault).delete(clear_faults),
+    )
+}
+
+/// Refuses /admin routes to callers without an admin key (principals.rs).
+///
+/// Layered over the whole API router in main.rs, so it covers admin routes
+/// declared outside this module too; keys are checked against the shared
+/// `AdminKeys`, without queueing for the client task.
+pub(crate) async fn require_admin(
+    State(state): State<AppState>,
+    matched_path: Option<MatchedPath>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let route = matched_path
+        .as_ref()
+        .map(|p| api_version::unversioned_path(p.as_str()));
+    if !route.is_some_and(|r| r == "/admin" || r.starts_with("/admin/")) {
+        return next.run(req).await;
+    }
+
+    let denied = match api_key_header(req.headers()) {
+        None => {
+            EscrowAuthError::Unauthenticated("X-API-Key header is required for admin routes".into())
+        }
+        Some(key) => match state.admin_keys.is_admin(&key) {
+            Some(true) => return next.run(req).await,
+            Some(false) => {
+                EscrowAuthError::Forbidden("API key does not hold the admin role".into())
+            }
+            None => EscrowAuthError::Unauthenticated("Invalid or revoked API key".into()),
+        },
+    };
+    respond::<()>(Err(denied.to_string()), "authorize admin request").into_response()
+}
+
+// ============================================================================
+// API KEY ADMIN ENDPOINTS
+// ============================================================================
+
+async fn list_api_keys(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list API keys request");
+    let op = ListApiKeys {};
+    respond(call(&state, op).await, "list API keys")
+}
+
+async fn issue_api_key(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<ApiKeyInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received issue API key request: {}", payload.label);
+    let op = IssueApiKey { input: payload };
+    respond(call(&state, op).await, "issue API key")
+}
+
+async fn revoke_api_key(
+    State(state): State<AppState>,
+    Path(key_id): Path<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received revoke API key {} request", key_id);
+    let op = RevokeApiKey { key_id };
+    respond(call(&state, op).await, "revoke API key")
+}
+
+/// Assigns an escrow to a closing agent key.
+async fn assign_closing_agent_escrow(
+    State(state): State<AppState>,
+    Path((key_id, escrow_account_id)): Path<(u64, String)>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received assign escrow {} to API key {} request",
+        escrow_account_id, key_id
+    );
+    let op = AssignClosingAgentEscrow {
+        key_id,
+        escrow_account_id,
+    };
+    respond(call(&state, op).await, "assign closing agent escrow")
+}
+
+async fn unassign_closing_agent_escrow(
+    State(state): State<AppState>,
+    Path((key_id, escrow_account_id)): Path<(u64, String)>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received unassign escrow {} from API key {} request",
+        escrow_account_id, key_id
+    );
+    let op = UnassignClosingAgentEscrow {
+        key_id,
+        escrow_account_id,
+    };
+    respond(call(&state, op).await, "unassign closing agent escrow")
+}
+
+// ============================================================================
+// PORTAL ACCESS ENDPOINTS (see portal.rs)
+// ============================================================================
+
+async fn list_portal_access(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    respond(
+        call(&state, ListPortalAccess {}).await,
+        "list portal access",
+    )
+}
+
+/// Issues a portal token for one account; the token is only returned here.
+async fn issue_portal_access(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<PortalAccessInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received issue portal access request for {}: {}",
+        payload.account_id, payload.label
+    );
+    let op = IssuePortalAccess { input: payload };
+    respond(call(&state, op).await, "issue portal access")
+}
+
+async fn revoke_portal_access(
+    State(state): State<AppState>,
+    Path(access_id): Path<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received revoke portal access {} request", access_id);
+    respond(
+        call(&state, RevokePortalAccess { access_id }).await,
+        "revoke portal access",
+    )
+}
+
+// ============================================================================
+// MASTER SECRET ENDPOINTS (see secrets.rs)
+// ============================================================================
+
+/// Generations and alias bindings of the master secret (never key material).
+async fn get_master_secret(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received get master secret request");
+    let op = GetMasterSecret {
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "get master secret")
+}
+
+/// Starts a new master secret generation for later derivations.
+async fn rotate_master_secret(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received rotate master secret request");
+    let op = RotateMasterSecret {
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "rotate master secret")
+}
+
+/// Rebuilds derived escrow accounts and keys from the escrow records.
+async fn rebuild_escrow_accounts(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received rebuild escrow accounts request");
+    let op = RebuildEscrowAccounts {
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "rebuild escrow accounts")
+}
+
+// ============================================================================
+// FUNDS RECOVERY ENDPOINTS (see recovery.rs)
+// ============================================================================
+
+async fn scan_recoverable_funds(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received recovery scan request");
+    let op = ScanRecoverableFunds {
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "scan for recoverable funds")
+}
+
+/// Sweeps recoverable funds into the configured treasury account.
+async fn sweep_recoverable_funds(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<SweepInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received recovery sweep request (dry run: {})",
+        payload.dry_run
+    );
+    let op = SweepRecoverableFunds {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "sweep recoverable funds")
+}
+
+// ============================================================================
+// DATA SUBJECT ENDPOINTS (see data_subjects.rs)
+// ============================================================================
+
+/// Everything kept about an account, for a data subject access request.
+async fn export_data_subject(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(account_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received data subject export request for: {}", account_id);
+    let op = ExportDataSubject {
+        account_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "export data subject")
+}
+
+/// Anonymizes the personal fields linked to an account, leaving tombstones.
+async fn erase_data_subject(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(account_id): Path<String>,
+    ValidJson(payload): ValidJson<ErasureInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received data subject erasure request for: {}", account_id);
+    let op = EraseDataSubject {
+        account_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "erase data subject")
+}
+
+// ============================================================================
+// TAX WITHHOLDING ENDPOINTS (see withholding.rs)
+// ============================================================================
+
+async fn list_withholding_rules(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    respond(
+        call(&state, ListWithholdingRules {}).await,
+        "list withholding rules",
+    )
+}
+
+async fn create_withholding_rule(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<WithholdingRuleInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received create withholding rule request: {:?}", payload);
+    let op = CreateWithholdingRule { input: payload };
+    respond(call(&state, op).await, "create withholding rule")
+}
+
+async fn delete_withholding_rule(
+    State(state): State<AppState>,
+    Path(rule_id): Path<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received delete withholding rule {} request", rule_id);
+    respond(
+        call(&state, DeleteWithholdingRule { rule_id }).await,
+        "delete withholding rule",
+    )
+}
+
+/// Sets the tax jurisdiction withholding rules are resolved for.
+async fn set_property_jurisdiction(
+    State(state): State<AppState>,
+    Path(property_id): Path<String>,
+    ValidJson(payload): ValidJson<JurisdictionInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received jurisdiction of {}: {:?}", property_id, payload);
+    let op = SetPropertyJurisdiction {
+        property_id,
+        input: payload,
+    };
+    respond(call(&state, op).await, "set property jurisdiction")
+}
+
+/// Mints the next issuance of an already minted property.
+async fn reissue_property(
+    State(state): State<AppState>,
+    Path(property_id): Path<String>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<PropertyReissueInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received re-issue of {}: {:?}", property_id, payload);
+    let op = ReissueProperty {
+        property_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "reissue property")
+}
+
+/// Files a re-issue after lost access; answers 202 with the approval to
+/// confirm.
+async fn request_property_reissue(
+    State(state): State<AppState>,
+    Path(property_id): Path<String>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<LostAccessInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received re-issue request for {}: {:?}",
+        property_id, payload
+    );
+    let op = RequestPropertyReissue {
+        property_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "request property reissue")
+}
+
+// ============================================================================
+// ACCREDITATION RULE ENDPOINTS (see accreditation_rules.rs)
+// ============================================================================
+
+async fn list_accreditation_rules(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list accreditation rules request");
+    respond(
+        call(&state, ListAccreditationRules {}).await,
+        "list accreditation rules",
+    )
+}
+
+async fn create_accreditation_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<RuleInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received create accreditation rule request: {:?}", payload);
+    let op = CreateAccreditationRule {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "create accreditation rule")
+}
+
+async fn update_accreditation_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(rule_id): Path<u64>,
+    ValidJson(payload): ValidJson<RuleInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received update accreditation rule {} request: {:?}",
+        rule_id, payload
+    );
+    let op = UpdateAccreditationRule {
+        rule_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "update accreditation rule")
+}
+
+async fn delete_accreditation_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(rule_id): Path<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received delete accreditation rule {} request", rule_id);
+    let op = DeleteAccreditationRule {
+        rule_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "delete accreditation rule")
+}
+
+// ============================================================================
+// IDENTITY PROVIDER ENDPOINTS (see identity.rs)
+// ============================================================================
+
+async fn list_identity_providers(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list identity providers request");
+    respond(
+        call(&state, ListIdentityProviders {}).await,
+        "list identity providers",
+    )
+}
+
+async fn register_identity_provider(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<ProviderInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received register identity provider request: {}",
+        payload.name
+    );
+    let op = RegisterIdentityProvider {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "register identity provider")
+}
+
+async fn deactivate_identity_provider(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(provider_id): Path<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received deactivate identity provider {} request",
+        provider_id
+    );
+    let op = DeactivateIdentityProvider {
+        provider_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "deactivate identity provider")
+}
+
+// ============================================================================
+// INSURER ENDPOINTS (see attachments.rs)
+// ============================================================================
+
+async fn list_insurers(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list insurers request");
+    respond(call(&state, ListInsurers {}).await, "list insurers")
+}
+
+async fn register_insurer(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<InsurerInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received register insurer request: {}", payload.name);
+    let op = RegisterInsurer {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "register insurer")
+}
+
+/// Deactivates an insurer, voiding its binders.
+async fn deactivate_insurer(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(insurer_id): Path<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received deactivate insurer {} request", insurer_id);
+    let op = DeactivateInsurer {
+        insurer_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "deactivate insurer")
+}
+
+// ============================================================================
+// RECONCILIATION ENDPOINTS
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+struct ReconcileRequest {
+    #[serde(default)]
+    repair: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct OperationsQuery {
+    limit: Option<usize>,
+}
+
+impl Validate for ReconcileRequest {
+    // A flag only; decoding checks it
+    fn validate(&self, _errors: &mut ValidationErrors) {}
+}
+
+/// Reconciles service records with the chain; repairs with `repair`. Admin
+/// only.
+async fn reconcile(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<ReconcileRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received reconcile request (repair: {})", payload.repair);
+    let op = Reconcile {
+        repair: payload.repair,
+        api_key: api_key_header(&headers),
+    };
+    let report = call(&state, op).await;
+    if let Ok(report) = &report {
+        info!(
+            "Reconciliation finished: {} discrepancies",
+            report.discrepancies.len()
+        );
+    }
+    respond(report, "reconcile")
+}
+
+async fn get_operations(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<OperationsQuery>,
+) -> Response {
+    info!("Received get operations request");
+
+    if wants_ndjson(&headers) {
+        return ndjson_listing(state.client_tx, Listing::Operations);
+    }
+
+    let op = GetOperations {
+        limit: query.limit.unwrap_or(50),
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "get operations").into_response()
+}
+
+// ============================================================================
+// LIFECYCLE HOOK ENDPOINTS (see hooks.rs)
+// ============================================================================
+
+/// Registers a hook; the webhook secret is only returned here. Admin only.
+async fn create_hook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<HookInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received create hook request: {} at {:?}",
+        payload.name, payload.point
+    );
+    let op = CreateHook {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "create hook")
+}
+
+/// Registered hooks, in the order they run. Admin only.
+async fn list_hooks(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = ListHooks {
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "list hooks")
+}
+
+async fn delete_hook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(hook_id): Path<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received delete hook {} request", hook_id);
+    let op = DeleteHook {
+        hook_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "delete hook")
+}
+
+// ============================================================================
+// FEATURE FLAG ENDPOINTS (see feature_flags.rs)
+// ============================================================================
+
+/// Body of PUT /admin/features/:feature
+#[derive(Debug, Deserialize)]
+struct FeatureFlagInput {
+    enabled: bool,
+}
+
+impl Validate for FeatureFlagInput {
+    // The feature is checked against the known ones by the operation
+    fn validate(&self, _errors: &mut ValidationErrors) {}
+}
+
+/// Every feature, its default and the state in force. Admin only.
+async fn list_feature_flags(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = ListFeatureFlags {
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "list feature flags")
+}
+
+/// Turns a feature on or off until the override is dropped. Admin only.
+async fn set_feature_flag(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(feature): Path<String>,
+    ValidJson(payload): ValidJson<FeatureFlagInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received set feature flag request: {} = {}",
+        feature, payload.enabled
+    );
+    let op = SetFeatureFlag {
+        feature,
+        enabled: Some(payload.enabled),
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "set feature flag")
+}
+
+/// Drops a feature's override: it is back to its configured default.
+async fn reset_feature_flag(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(feature): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received reset feature flag {} request", feature);
+    let op = SetFeatureFlag {
+        feature,
+        enabled: None,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "reset feature flag")
+}
+
+// ============================================================================
+// RESTRICTED-JURISDICTION LIST ADMIN ENDPOINTS
+// ============================================================================
+
+async fn list_jurisdiction_lists(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list jurisdiction lists request");
+    let op = ListJurisdictionLists {};
+    respond(call(&state, op).await, "list jurisdiction lists")
+}
+
+async fn publish_jurisdiction_list(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<ListUpdate>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received publish jurisdiction list request ({} countries)",
+        payload.countries.len()
+    );
+    let op = PublishJurisdictionList { update: payload };
+    respond(call(&state, op).await, "publish jurisdiction list")
+}
+
+// ============================================================================
+// TREASURY ENDPOINTS (see treasury.rs)
+// ============================================================================
+
+/// Treasury ledger filter
+#[derive(Debug, Deserialize)]
+struct TreasuryLedgerQuery {
+    kind: Option<LedgerEntryKind>,
+    limit: Option<usize>,
+}
+
+/// Treasury income report: entries of `kind` (default fee) per `bucket`
+/// (default day) in [from, to), unix seconds
+#[derive(Debug, Deserialize)]
+struct TreasuryIncomeQuery {
+    kind: Option<LedgerEntryKind>,
+    bucket: Option<IncomeBucket>,
+    from: Option<i64>,
+    to: Option<i64>,
+}
+
+async fn get_treasury(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received get treasury request");
+    let op = GetTreasury {};
+    respond(call(&state, op).await, "get treasury")
+}
+
+/// Consumes the notes waiting for the treasury into its vault.
+async fn collect_treasury_notes(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received treasury collect request");
+    let op = CollectTreasuryNotes {};
+    respond(call(&state, op).await, "collect treasury notes")
+}
+
+async fn get_treasury_ledger(
+    State(state): State<AppState>,
+    Query(query): Query<TreasuryLedgerQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received treasury ledger request: {:?}", query.kind);
+    let op = GetTreasuryLedger {
+        kind: query.kind,
+        limit: query.limit.unwrap_or(50),
+    };
+    respond(call(&state, op).await, "get treasury ledger")
+}
+
+async fn get_treasury_income(
+    State(state): State<AppState>,
+    Query(query): Query<TreasuryIncomeQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received treasury income request: {:?}", query);
+    let op = GetTreasuryIncome {
+        kind: query.kind.unwrap_or(LedgerEntryKind::Fee),
+        bucket: query.bucket.unwrap_or_default(),
+        from: query.from,
+        to: query.to,
+    };
+    respond(call(&state, op).await, "get treasury income")
+}
+
+/// Parks a withdrawal for a second admin; answers 202 with the approval ID.
+async fn request_treasury_withdrawal(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<WithdrawalInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received treasury withdrawal request: {} to {}",
+        payload.amount, payload.recipient_account_id
+    );
+    let op = RequestTreasuryWithdrawal {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "request treasury withdrawal")
+}
+
+// ============================================================================
+// LEDGER ENDPOINTS (see ledger.rs)
+// ============================================================================
+
+/// Trial balance of entries up to `as_of` (unix seconds)
+#[derive(Debug, Deserialize)]
+struct TrialBalanceQuery {
+    as_of: Option<i64>,
+}
+
+/// Ledger journal filter: entries posting to `account` (name or hex)
+#[derive(Debug, Deserialize)]
+struct LedgerEntriesQuery {
+    account: Option<String>,
+    limit: Option<usize>,
+}
+
+/// Debits, credits and balance per account and asset. Admin only.
+async fn get_trial_balance(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<TrialBalanceQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received trial balance request (as of {:?})", query.as_of);
+    let op = GetTrialBalance {
+        as_of: query.as_of,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "get trial balance")
+}
+
+/// Journal entries, newest first. Admin only.
+async fn list_ledger_entries(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<LedgerEntriesQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received ledger entries request: {:?}", query.account);
+    let op = ListLedgerEntries {
+        account: query.account,
+        limit: query.limit.unwrap_or(50),
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "list ledger entries")
+}
+
+// ============================================================================
+// STORE MAINTENANCE ENDPOINTS (see store_maintenance.rs)
+// ============================================================================
+
+/// Size, free pages and rows per table of the client store, and the last
+/// maintenance run.
+async fn get_store_stats(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received get store stats request");
+    let op = GetStoreStats {};
+    respond(call(&state, op).await, "get store stats")
+}
+
+/// Prunes block headers older than the retention horizon, optionally as a
+/// dry run or followed by a vacuum. Admin only.
+async fn prune_store(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<PruneInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received store prune request: {:?}", payload);
+    let op = PruneStore {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "prune store")
+}
+
+/// Rebuilds the client store file, reporting the space reclaimed. Admin only.
+async fn vacuum_store(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received store vacuum request");
+    let op = VacuumStore {
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "vacuum store")
+}
+
+// ============================================================================
+// STORE SNAPSHOT ENDPOINTS (see snapshots.rs)
+// ============================================================================
+
+/// Bytes read per chunk when streaming a snapshot file
+const SNAPSHOT_CHUNK_BYTES: usize = 256 * 1024;
+/// Publishes a snapshot of the client store now. Admin only.
+async fn publish_snapshot(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received snapshot publish request");
+    let op = PublishSnapshot {
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "publish snapshot")
+}
+
+/// The newest snapshot's manifest, which a bootstrapping instance starts from.
+/// Admin only.
+async fn latest_snapshot(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received latest snapshot request");
+    let op = LatestSnapshot {
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "latest snapshot")
+}
+
+/// Published snapshots, newest first. Admin only.
+async fn list_snapshots(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list snapshots request");
+    let op = ListSnapshots {
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "list snapshots")
+}
+
+/// Streams a snapshot file, with its checksum in `X-Snapshot-Sha256`. Admin
+/// only.
+async fn download_snapshot(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(snapshot_id): Path<String>,
+) -> Response {
+    use tokio::io::AsyncReadExt;
+
+    info!("Received snapshot download request: {}", snapshot_id);
+    let op = SnapshotFile {
+        snapshot_id,
+        api_key: api_key_header(&headers),
+    };
+    let (manifest, path) = match call(&state, op).await {
+        Ok(found) => found,
+        Err(e) if e.ends_with("not found") => {
+            return failure(StatusCode::NOT_FOUND, e).into_response()
+        }
+        Err(e) => return respond::<()>(Err(e), "serve snapshot").into_response(),
+    };
+    let file = match tokio::fs::File::open(&path).await {
+        Ok(file) => file,
+        Err(e) => {
+            error!("Failed to open snapshot {}: {}", path.display(), e);
+            return failure(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Snapshot {} is not available", manifest.snapshot_id),
+            )
+            .into_response();
+        }
+    };
+
+    // The file is gone from the state after a read error, ending the stream
+    let chunks = futures_util::stream::unfold(Some(file), |file| async move {
+        let mut file = file?;
+        let mut buffer = vec![0u8; SNAPSHOT_CHUNK_BYTES];
+        match file.read(&mut buffer).await {
+            Ok(0) => None,
+            Ok(read) => {
+                buffer.truncate(read);
+                Some((Ok(Bytes::from(buffer)), Some(file)))
+            }
+            Err(e) => Some((Err(e), None)),
+        }
+    });
+
+    (
+        [
+            (header::CONTENT_TYPE, "application/vnd.sqlite3".to_string()),
+            (header::CONTENT_LENGTH, manifest.size_bytes.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", manifest.file_name),
+            ),
+            (
+                header::HeaderName::from_static("x-snapshot-sha256"),
+                manifest.sha256,
+            ),
+        ],
+        Body::from_stream(chunks),
+    )
+        .into_response()
+}
+
+// ============================================================================
+// READ CACHE ENDPOINTS
+// ============================================================================
+
+/// Served from the shared cache handle; never enqueues a client command.
+async fn get_read_cache_stats(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    respond(Ok(state.read_cache.stats()), "get read cache stats")
+}
+
+// ============================================================================
+// QUEUE, SLO AND RPC INTROSPECTION
+// ============================================================================
+
+/// Command backlog, running command and per-type latency (see queue_stats.rs).
+///
+/// Read straight from the queue stats, not through the client task, so it
+/// answers even while the task is stuck on a command.
+async fn get_queue(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received get queue request");
+    let queue = serde_json::json!({
+        "queue": state.client_tx.stats.snapshot(),
+        "load_shed_queue_depth": state.load_shed_queue_depth
+    });
+    respond(Ok(queue), "get queue")
+}
+
+/// Success rate, latency and burn rates per endpoint against the configured
+/// objectives (see slo.rs). Alerting endpoints come first.
+async fn get_slo(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received get SLO request");
+    respond(Ok(state.slo.summary()), "get SLO")
+}
+
+/// Health, chain tip and latency of every configured RPC endpoint, and which
+/// one the client is using (see rpc_failover.rs).
+async fn get_rpc(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received get RPC endpoints request");
+    respond(Ok(state.rpc.report()), "get RPC endpoints")
+}
+
+// ============================================================================
+// FAULT INJECTION ENDPOINTS (feature "fault-injection")
+// ============================================================================
+
+#[cfg(feature = "fault-injection")]
+async fn list_faults(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list faults request");
+    let op = ListFaults {};
+    respond(call(&state, op).await, "list faults")
+}
+
+#[cfg(feature = "fault-injection")]
+async fn inject_fault(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<FaultInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received inject fault request: {:?}", payload.kind);
+    let op = InjectFault { input: payload };
+    respond(call(&state, op).await, "inject fault")
+}
+
+#[cfg(feature = "fault-injection")]
+async fn clear_faults(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received clear faults request");
+    let op = ClearFaults {};
+    respond(call(&state, op).await, "clear faults")
+}
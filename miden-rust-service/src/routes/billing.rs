@@ -0,0 +1,155 @@
+// src/routes/billing.rs
+//
+// Billing endpoints (billing.rs): invoices of API key usage, the usage itself,
+// fee sponsorship and invoice settlement.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use tracing::info;
+
+use miden_rust_service::{
+    billing::{
+        GetBillingUsage, GetFeeSponsorship, GetInvoiceArtifact, ListInvoices, SettleInvoice,
+    },
+    documents::ArtifactFormat,
+    timestamps::DisplayZone,
+};
+
+use super::{call, failure, respond};
+use crate::{api_key_header, AppState};
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/billing/invoices", get(list_invoices))
+        .route("/billing/invoices/:invoice_id", get(get_invoice))
+        .route("/billing/usage", get(get_billing_usage))
+        .route("/billing/sponsorship", get(get_fee_sponsorship))
+        .route(
+            "/admin/billing/invoices/:invoice_id/settle",
+            post(settle_invoice),
+        )
+}
+
+/// Invoice format: `?format=pdf` (or `Accept: application/pdf`) for the PDF,
+/// JSON otherwise; `?tz=` for the zone the PDF dates are printed in
+#[derive(Debug, Deserialize)]
+struct InvoiceQuery {
+    format: Option<ArtifactFormat>,
+    tz: Option<String>,
+}
+
+/// Account (name or hex) whose fee sponsorship to show
+#[derive(Debug, Deserialize)]
+struct SponsorshipQuery {
+    account_id: String,
+}
+
+/// Invoices the caller's API key may see, newest first.
+async fn list_invoices(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list invoices request");
+    let op = ListInvoices {
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "list invoices")
+}
+
+/// An invoice as JSON, or as a PDF with `?format=pdf` (or
+/// `Accept: application/pdf`).
+async fn get_invoice(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(invoice_id): Path<u64>,
+    Query(query): Query<InvoiceQuery>,
+) -> Response {
+    info!("Received get invoice request: {}", invoice_id);
+
+    let format = query.format.unwrap_or_else(|| {
+        let pdf = headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .map(|accept| accept.contains("application/pdf"))
+            .unwrap_or(false);
+        if pdf {
+            ArtifactFormat::Pdf
+        } else {
+            ArtifactFormat::Json
+        }
+    });
+    let zone = match DisplayZone::requested(&headers, query.tz.as_deref()) {
+        Ok(zone) => zone,
+        Err(e) => return failure(StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    let op = GetInvoiceArtifact {
+        invoice_id,
+        format,
+        zone,
+        api_key: api_key_header(&headers),
+    };
+    match call(&state, op).await {
+        Ok(artifact) if format == ArtifactFormat::Json => (
+            [(header::CONTENT_TYPE, artifact.content_type.to_string())],
+            artifact.bytes,
+        )
+            .into_response(),
+        Ok(artifact) => (
+            [
+                (header::CONTENT_TYPE, artifact.content_type.to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}\"", artifact.filename),
+                ),
+            ],
+            artifact.bytes,
+        )
+            .into_response(),
+        Err(e) => respond::<()>(Err(e), "read invoice").into_response(),
+    }
+}
+
+/// Metered usage of the caller's API key per month.
+async fn get_billing_usage(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = GetBillingUsage {
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "get billing usage")
+}
+
+/// An account's sponsored transactions this month and its remaining quota.
+async fn get_fee_sponsorship(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<SponsorshipQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = GetFeeSponsorship {
+        account_id: query.account_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "get fee sponsorship")
+}
+
+/// Pays an open invoice from the billed account to the treasury. Admin only.
+async fn settle_invoice(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(invoice_id): Path<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received settle invoice request: {}", invoice_id);
+    let op = SettleInvoice {
+        invoice_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "settle invoice")
+}
@@ -0,0 +1,200 @@
+// src/routes/custodial.rs
+//
+// Email sign-in and custodial wallet endpoints (custodial.rs). Codes are
+// delivered here, after the client task has issued them; responses carry the
+// challenge, never the code.
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use tracing::{error, info};
+
+use miden_rust_service::custodial::{
+    ConfirmCustodialPayment, ConsumeCustodialNotes, CreateCustodialSession, CustodialPaymentInput,
+    EndCustodialSession, GetCustodialSession, IssuedOtp, OtpDelivery, OtpRequestInput,
+    OtpVerifyInput, RequestCustodialOtp, RequestCustodialPayment,
+};
+
+use super::{call, respond};
+use crate::{AppState, ValidJson};
+
+const CUSTODIAL_SESSION_HEADER: &str = "x-custodial-session";
+
+pub(crate) fn router() -> Router<AppState> {
+    let session_routes = Router::new()
+        .route(
+            "/custodial/session",
+            get(get_custodial_session).delete(end_custodial_session),
+        )
+        .route("/custodial/notes/consume", post(consume_custodial_notes))
+        .route("/custodial/payments", post(request_custodial_payment))
+        .route(
+            "/custodial/payments/confirm",
+            post(confirm_custodial_payment),
+        )
+        .route_layer(middleware::from_fn(require_custodial_session));
+
+    Router::new()
+        .route("/custodial/otp", post(request_custodial_otp))
+        .route("/custodial/sessions", post(create_custodial_session))
+        .merge(session_routes)
+}
+
+/// Session token presented in the `X-Custodial-Session` header, if any.
+fn custodial_session_header(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(CUSTODIAL_SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Answers 401 to session requests without a token before they reach the
+/// client queue; the token itself is checked there.
+async fn require_custodial_session(req: Request, next: Next) -> Response {
+    if custodial_session_header(req.headers()).is_some() {
+        return next.run(req).await;
+    }
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(serde_json::json!({
+            "success": false,
+            "data": null,
+            "error": "Unauthorized: X-Custodial-Session header is required"
+        })),
+    )
+        .into_response()
+}
+
+fn no_delivery() -> (StatusCode, Json<serde_json::Value>) {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(serde_json::json!({
+            "success": false,
+            "data": null,
+            "error": "No one-time code delivery is configured (OTP_DELIVERY)"
+        })),
+    )
+}
+
+/// Sends an issued code to its user and answers with its challenge.
+async fn deliver(
+    delivery: OtpDelivery,
+    issued: Result<IssuedOtp, String>,
+    action: &str,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let issued = match issued {
+        Ok(issued) => issued,
+        Err(e) => return respond(Err::<(), _>(e), action),
+    };
+    if let Err(e) = delivery.deliver(&issued).await {
+        error!(
+            "Failed to deliver code {}: {}",
+            issued.challenge.challenge_id, e
+        );
+        return (
+            StatusCode::BAD_GATEWAY,
+            Json(serde_json::json!({
+                "success": false,
+                "data": null,
+                "error": format!("Code delivery failed: {}", e)
+            })),
+        );
+    }
+    respond(Ok(issued.challenge), action)
+}
+
+async fn request_custodial_otp(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<OtpRequestInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received custodial sign-in code request");
+    let Some(delivery) = state.otp_delivery.clone() else {
+        return no_delivery();
+    };
+    let op = RequestCustodialOtp { input: payload };
+    deliver(delivery, call(&state, op).await, "issue sign-in code").await
+}
+
+async fn create_custodial_session(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<OtpVerifyInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received custodial sign-in for challenge {}",
+        payload.challenge_id
+    );
+    let op = CreateCustodialSession { input: payload };
+    respond(call(&state, op).await, "create custodial session")
+}
+
+async fn get_custodial_session(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = GetCustodialSession {
+        token: custodial_session_header(&headers),
+    };
+    respond(call(&state, op).await, "get custodial session")
+}
+
+async fn end_custodial_session(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = EndCustodialSession {
+        token: custodial_session_header(&headers),
+    };
+    respond(call(&state, op).await, "end custodial session")
+}
+
+async fn consume_custodial_notes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received custodial note consumption request");
+    let op = ConsumeCustodialNotes {
+        token: custodial_session_header(&headers),
+    };
+    respond(call(&state, op).await, "consume custodial notes")
+}
+
+async fn request_custodial_payment(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<CustodialPaymentInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received custodial payment request: {} to {}",
+        payload.amount, payload.to_account_id
+    );
+    let Some(delivery) = state.otp_delivery.clone() else {
+        return no_delivery();
+    };
+    let op = RequestCustodialPayment {
+        input: payload,
+        token: custodial_session_header(&headers),
+    };
+    deliver(delivery, call(&state, op).await, "issue payment code").await
+}
+
+async fn confirm_custodial_payment(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<OtpVerifyInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received custodial payment confirmation for challenge {}",
+        payload.challenge_id
+    );
+    let op = ConfirmCustodialPayment {
+        input: payload,
+        token: custodial_session_header(&headers),
+    };
+    respond(call(&state, op).await, "confirm custodial payment")
+}
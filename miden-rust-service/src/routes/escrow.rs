@@ -0,0 +1,836 @@
+// src/routes/escrow.rs
+//
+// Escrow endpoints: create, fund, release and refund (escrow.rs), the escrow
+// read model with its timeline (escrow_timeline.rs), escrow listings and stale
+// escrow events (escrow_monitor.rs), liens (liens.rs), release approvals
+// (approvals.rs), checklists, yield, closing documents (documents.rs) and
+// e-signatures (esign.rs).
+
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post, put},
+    Json, Router,
+};
+use miden_client::{account::AccountId, Deserializable};
+use serde::Deserialize;
+use tokio::sync::broadcast;
+use tracing::{error, info};
+
+use miden_rust_service::{
+    account_id_to_hex,
+    approvals::{ApprovalStatus, ConfirmApproval, GetApproval, ListApprovals},
+    attachments::{ChecklistInput, GetEscrowChecklist, SetEscrowChecklist},
+    documents::{
+        ArtifactFormat, DocumentInput, GenerateDocument, GetDocumentArtifact, ListDocuments,
+    },
+    escrow::{CreateEscrow, EscrowAccount, EscrowStatus, FundEscrow, RefundEscrow, ReleaseEscrow},
+    escrow_monitor::ListStaleEscrows,
+    escrow_timeline::GetEscrow,
+    escrow_yield::GetEscrowYield,
+    esign::{
+        ApplySignatureEvent, CallbackRejected, ListSignatureEnvelopes, PrepareSignatureRequest,
+        RecordSignatureRequest, SignatureInput,
+    },
+    fee_splits::{CommissionInput, SetEscrowCommission},
+    liens::{
+        DischargeInput, DischargeLien, GetLien, LienInput, ListLiens, RegisterLien, SignOffInput,
+        SignOffLien,
+    },
+    listing::Listing,
+    spend_receipts,
+    timestamps::DisplayZone,
+    validation::{self, Validate, ValidationErrors},
+};
+
+use super::{
+    call, collect_listing, failure, ndjson_listing, respond, wants_ndjson, CLIENT_DROPPED,
+    CLIENT_UNAVAILABLE,
+};
+use crate::{api_key_header, AppState, ValidJson};
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/create-escrow", post(create_escrow))
+        .route("/fund-escrow", post(fund_escrow))
+        .route("/release-escrow", post(release_escrow))
+        .route("/refund-escrow", post(refund_escrow))
+        .route("/escrows/:escrow_account_id", get(get_escrow))
+        .route(
+            "/escrows/:escrow_account_id/commission",
+            put(set_escrow_commission),
+        )
+        .route("/escrows", get(list_escrows))
+        .route("/escrows/stale-events", get(stale_escrow_events))
+        .route("/liens", get(list_liens).post(register_lien))
+        .route("/liens/:lien_id", get(get_lien))
+        .route("/liens/:lien_id/sign-off", post(sign_off_lien))
+        .route("/liens/:lien_id/discharge", post(discharge_lien))
+        .route("/approvals", get(list_approvals))
+        .route("/approvals/:approval_id", get(get_approval))
+        .route("/approvals/:approval_id/confirm", post(confirm_approval))
+        .route(
+            "/escrows/:escrow_account_id/checklist",
+            get(get_escrow_checklist).put(set_escrow_checklist),
+        )
+        .route("/escrows/:escrow_account_id/yield", get(get_escrow_yield))
+        .route(
+            "/escrows/:escrow_account_id/documents",
+            get(list_documents).post(generate_document),
+        )
+        .route(
+            "/escrows/:escrow_account_id/documents/:document_id",
+            get(get_document_artifact),
+        )
+        .route(
+            "/escrows/:escrow_account_id/documents/:document_id/signatures",
+            post(request_signature),
+        )
+        .route(
+            "/escrows/:escrow_account_id/signatures",
+            get(list_signature_envelopes),
+        )
+        .route("/esign/callback", post(esign_callback))
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateEscrowRequest {
+    buyer_account_id: String,
+    seller_account_id: String,
+    amount: u64,
+    /// Property being sold; its liens then gate the release
+    #[serde(default)]
+    property_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FundEscrowRequest {
+    escrow_account_id: String,
+    buyer_account_id: String,
+    seller_account_id: String,
+    amount: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseEscrowRequest {
+    escrow_account_id: String,
+    buyer_account_id: String,
+    seller_account_id: String,
+    amount: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct RefundEscrowRequest {
+    escrow_account_id: String,
+    buyer_account_id: String,
+    seller_account_id: String,
+    amount: u64,
+}
+
+/// Checks shared by all escrow requests (account IDs are hex, 0x optional).
+fn validate_escrow_parties(
+    errors: &mut ValidationErrors,
+    escrow_account_id: Option<&str>,
+    buyer_account_id: &str,
+    seller_account_id: &str,
+    amount: u64,
+) {
+    if let Some(escrow_account_id) = escrow_account_id {
+        errors.check(
+            "escrow_account_id",
+            validation::hex_string(escrow_account_id, false),
+        );
+    }
+    errors.check(
+        "buyer_account_id",
+        validation::hex_string(buyer_account_id, false),
+    );
+    errors.check(
+        "seller_account_id",
+        validation::hex_string(seller_account_id, false),
+    );
+    if buyer_account_id
+        .trim_start_matches("0x")
+        .eq_ignore_ascii_case(seller_account_id.trim_start_matches("0x"))
+    {
+        errors.add("seller_account_id", "must differ from buyer_account_id");
+    }
+    errors.check("amount", validation::positive(amount));
+}
+
+impl Validate for CreateEscrowRequest {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        validate_escrow_parties(
+            errors,
+            None,
+            &self.buyer_account_id,
+            &self.seller_account_id,
+            self.amount,
+        );
+        if let Some(property_id) = &self.property_id {
+            errors.check("property_id", validation::property_id(property_id));
+        }
+    }
+}
+
+impl Validate for FundEscrowRequest {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        validate_escrow_parties(
+            errors,
+            Some(&self.escrow_account_id),
+            &self.buyer_account_id,
+            &self.seller_account_id,
+            self.amount,
+        );
+    }
+}
+
+impl Validate for ReleaseEscrowRequest {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        validate_escrow_parties(
+            errors,
+            Some(&self.escrow_account_id),
+            &self.buyer_account_id,
+            &self.seller_account_id,
+            self.amount,
+        );
+    }
+}
+
+impl Validate for RefundEscrowRequest {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        validate_escrow_parties(
+            errors,
+            Some(&self.escrow_account_id),
+            &self.buyer_account_id,
+            &self.seller_account_id,
+            self.amount,
+        );
+    }
+}
+
+/// Parses an AccountId from a hex string (optionally 0x-prefixed).
+/// This is used by escrow endpoints that receive IDs as hex strings.
+fn parse_account_id_from_hex(hex_str: &str) -> Result<AccountId, String> {
+    let hex_str = hex_str.strip_prefix("0x").unwrap_or(hex_str);
+    let bytes = hex::decode(hex_str).map_err(|e| format!("Failed to decode hex: {}", e))?;
+    AccountId::read_from_bytes(&bytes[..])
+        .map_err(|e| format!("Failed to deserialize AccountId: {}", e))
+}
+
+// ============================================================================
+// ESCROW ENDPOINTS
+// ============================================================================
+
+async fn create_escrow(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<CreateEscrowRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received create escrow request: {:?}", payload);
+
+    let property_id = payload.property_id.clone();
+    let op = CreateEscrow {
+        buyer_account_str: payload.buyer_account_id,
+        seller_account_str: payload.seller_account_id,
+        amount: payload.amount,
+        property_id: payload.property_id,
+        api_key: api_key_header(&headers),
+    };
+    let result = call(&state, op).await.map(|escrow| {
+        let escrow_hex = account_id_to_hex(escrow.escrow_account_id);
+        info!("Escrow created: escrow_id={}", escrow_hex);
+        serde_json::json!({
+            "escrow_account_id": escrow_hex,
+            "buyer_account_id": account_id_to_hex(escrow.buyer_account_id),
+            "seller_account_id": account_id_to_hex(escrow.seller_account_id),
+            "property_id": property_id,
+            "amount": escrow.amount,
+            "status": "created"
+        })
+    });
+    respond(result, "create escrow")
+}
+
+/// The escrow a fund, release or refund request names, in `status`.
+fn requested_escrow(
+    escrow_account_id: &str,
+    buyer_account_id: &str,
+    seller_account_id: &str,
+    amount: u64,
+    status: EscrowStatus,
+) -> Result<EscrowAccount, String> {
+    Ok(EscrowAccount {
+        escrow_account_id: parse_account_id_from_hex(escrow_account_id)
+            .map_err(|e| format!("Invalid escrow account ID: {}", e))?,
+        buyer_account_id: parse_account_id_from_hex(buyer_account_id)
+            .map_err(|e| format!("Invalid buyer account ID: {}", e))?,
+        seller_account_id: parse_account_id_from_hex(seller_account_id)
+            .map_err(|e| format!("Invalid seller account ID: {}", e))?,
+        amount,
+        status,
+    })
+}
+
+async fn fund_escrow(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<FundEscrowRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received fund escrow request: {:?}", payload);
+
+    let escrow = match requested_escrow(
+        &payload.escrow_account_id,
+        &payload.buyer_account_id,
+        &payload.seller_account_id,
+        payload.amount,
+        EscrowStatus::Created,
+    ) {
+        Ok(escrow) => escrow,
+        Err(e) => return respond::<()>(Err(e), "fund escrow"),
+    };
+    let op = FundEscrow {
+        escrow,
+        api_key: api_key_header(&headers),
+    };
+    let result = call(&state, op).await.map(|tx_id| {
+        info!("Escrow funded: tx={}", tx_id);
+        serde_json::json!({ "transaction_id": tx_id })
+    });
+    respond(result, "fund escrow")
+}
+
+async fn release_escrow(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<ReleaseEscrowRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received release escrow request: {:?}", payload);
+
+    let escrow = match requested_escrow(
+        &payload.escrow_account_id,
+        &payload.buyer_account_id,
+        &payload.seller_account_id,
+        payload.amount,
+        EscrowStatus::Funded,
+    ) {
+        Ok(escrow) => escrow,
+        Err(e) => return respond::<()>(Err(e), "release escrow"),
+    };
+    let op = ReleaseEscrow {
+        escrow,
+        api_key: api_key_header(&headers),
+    };
+    let result = call(&state, op).await.map(|(tx_id, receipts)| {
+        info!("Escrow released: tx={}", tx_id);
+        serde_json::json!({
+            "transaction_id": tx_id,
+            "nullifiers": spend_receipts::nullifiers(&receipts),
+            "account_commitment": receipts
+                .iter()
+                .find(|r| r.tx_id == tx_id)
+                .map(|r| &r.final_account_commitment),
+            "receipts": receipts
+        })
+    });
+    respond(result, "release escrow")
+}
+
+async fn refund_escrow(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<RefundEscrowRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received refund escrow request: {:?}", payload);
+
+    let escrow = match requested_escrow(
+        &payload.escrow_account_id,
+        &payload.buyer_account_id,
+        &payload.seller_account_id,
+        payload.amount,
+        EscrowStatus::Funded,
+    ) {
+        Ok(escrow) => escrow,
+        Err(e) => return respond::<()>(Err(e), "refund escrow"),
+    };
+    let op = RefundEscrow {
+        escrow,
+        api_key: api_key_header(&headers),
+    };
+    let result = call(&state, op).await.map(|tx_id| {
+        info!("Escrow refunded: tx={}", tx_id);
+        serde_json::json!({ "transaction_id": tx_id })
+    });
+    respond(result, "refund escrow")
+}
+
+/// The escrow's record and timeline.
+async fn get_escrow(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(escrow_account_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received escrow request: {}", escrow_account_id);
+    let op = GetEscrow {
+        escrow_account_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "get escrow")
+}
+
+/// Sets or removes the escrow's agent commission.
+async fn set_escrow_commission(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(escrow_account_id): Path<String>,
+    ValidJson(payload): ValidJson<CommissionInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received commission for escrow {}: {:?}",
+        escrow_account_id, payload
+    );
+    let op = SetEscrowCommission {
+        escrow_account_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "set escrow commission")
+}
+
+// ============================================================================
+// ESCROW LISTINGS (BUFFERED OR NDJSON)
+// ============================================================================
+
+/// `stale=true` lists only escrows flagged by the stale escrow monitor
+#[derive(Debug, Deserialize)]
+struct EscrowListQuery {
+    stale: Option<bool>,
+}
+
+async fn list_escrows(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<EscrowListQuery>,
+) -> Response {
+    info!("Received list escrows request");
+
+    // Escrows stuck in created or funded beyond their threshold (escrow_monitor.rs)
+    if query.stale == Some(true) {
+        let op = ListStaleEscrows {};
+        return respond(call(&state, op).await, "list stale escrows").into_response();
+    }
+
+    if wants_ndjson(&headers) {
+        return ndjson_listing(state.client_tx, Listing::Escrows);
+    }
+
+    respond(
+        collect_listing(&state, Listing::Escrows).await,
+        "list escrows",
+    )
+    .into_response()
+}
+
+/// Streams stale escrow events (`flagged`, `cleared`) as server-sent events.
+async fn stale_escrow_events(State(state): State<AppState>) -> Response {
+    info!("Received stale escrow event stream request");
+
+    let feed = state.stale_escrow_feed.subscribe();
+    let stream = futures_util::stream::unfold(feed, |mut feed| async move {
+        loop {
+            match feed.recv().await {
+                Ok(event) => match Event::default()
+                    .event(event.kind.as_str())
+                    .json_data(&event)
+                {
+                    Ok(sse) => return Some((Ok::<_, std::convert::Infallible>(sse), feed)),
+                    Err(e) => error!("Failed to encode stale escrow event: {}", e),
+                },
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    error!("Stale escrow event stream skipped {} event(s)", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+// ============================================================================
+// LIEN REGISTRY ENDPOINTS (see liens.rs)
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+struct LienQuery {
+    property_id: Option<String>,
+}
+
+async fn register_lien(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<LienInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received register lien request: {:?}", payload);
+    let op = RegisterLien {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "register lien")
+}
+
+async fn sign_off_lien(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(lien_id): Path<u64>,
+    ValidJson(payload): ValidJson<SignOffInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received lien sign-off request: {} ({:?})",
+        lien_id, payload.action
+    );
+    let op = SignOffLien {
+        lien_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "sign off lien")
+}
+
+async fn discharge_lien(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(lien_id): Path<u64>,
+    ValidJson(payload): ValidJson<DischargeInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received lien discharge request: {}", lien_id);
+    let op = DischargeLien {
+        lien_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "discharge lien")
+}
+
+async fn get_lien(
+    State(state): State<AppState>,
+    Path(lien_id): Path<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received lien request: {}", lien_id);
+    let op = GetLien { lien_id };
+    respond(call(&state, op).await, "get lien")
+}
+
+async fn list_liens(
+    State(state): State<AppState>,
+    Query(query): Query<LienQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list liens request: {:?}", query.property_id);
+    let op = ListLiens {
+        property_id: query.property_id,
+    };
+    respond(call(&state, op).await, "list liens")
+}
+
+// ============================================================================
+// RELEASE APPROVAL ENDPOINTS (see approvals.rs)
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+struct ApprovalQuery {
+    status: Option<ApprovalStatus>,
+}
+
+async fn confirm_approval(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(approval_id): Path<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received approval confirmation: {}", approval_id);
+    let op = ConfirmApproval {
+        approval_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "confirm approval")
+}
+
+async fn get_approval(
+    State(state): State<AppState>,
+    Path(approval_id): Path<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received approval request: {}", approval_id);
+    let op = GetApproval { approval_id };
+    respond(call(&state, op).await, "get approval")
+}
+
+async fn list_approvals(
+    State(state): State<AppState>,
+    Query(query): Query<ApprovalQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list approvals request: {:?}", query.status);
+    let op = ListApprovals {
+        status: query.status,
+    };
+    respond(call(&state, op).await, "list approvals")
+}
+
+// ============================================================================
+// CHECKLIST AND YIELD ENDPOINTS (see attachments.rs, escrow_yield.rs)
+// ============================================================================
+
+async fn set_escrow_checklist(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(escrow_account_id): Path<String>,
+    ValidJson(payload): ValidJson<ChecklistInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received checklist for escrow {}: {:?}",
+        escrow_account_id, payload
+    );
+    let op = SetEscrowChecklist {
+        escrow_account_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "set escrow checklist")
+}
+
+async fn get_escrow_checklist(
+    State(state): State<AppState>,
+    Path(escrow_account_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received escrow checklist request: {}", escrow_account_id);
+    let op = GetEscrowChecklist { escrow_account_id };
+    respond(call(&state, op).await, "get escrow checklist")
+}
+
+async fn get_escrow_yield(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(escrow_account_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = GetEscrowYield {
+        escrow_account_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "get escrow yield")
+}
+
+// ============================================================================
+// CLOSING DOCUMENT ENDPOINTS (see documents.rs)
+// ============================================================================
+
+/// Closing document artifact: `?format=json` for the JSON, the PDF otherwise
+#[derive(Debug, Deserialize)]
+struct DocumentArtifactQuery {
+    format: Option<ArtifactFormat>,
+}
+
+/// Renders a closing document for an escrow.
+async fn generate_document(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(escrow_account_id): Path<String>,
+    ValidJson(mut payload): ValidJson<DocumentInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received document request for escrow {}: {}",
+        escrow_account_id,
+        payload.kind.as_str()
+    );
+    if payload.timezone.is_none() {
+        match DisplayZone::requested(&headers, None) {
+            Ok(zone) => payload.timezone = Some(zone),
+            Err(e) => return failure(StatusCode::BAD_REQUEST, e),
+        }
+    }
+    let op = GenerateDocument {
+        escrow_account_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "generate document")
+}
+
+async fn list_documents(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(escrow_account_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = ListDocuments {
+        escrow_account_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "list documents")
+}
+
+/// A document's PDF, or its JSON with `?format=json` (or
+/// `Accept: application/json`).
+async fn get_document_artifact(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((escrow_account_id, document_id)): Path<(String, u64)>,
+    Query(query): Query<DocumentArtifactQuery>,
+) -> Response {
+    let format = query.format.unwrap_or_else(|| {
+        let json = headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .map(|accept| accept.contains("application/json"))
+            .unwrap_or(false);
+        if json {
+            ArtifactFormat::Json
+        } else {
+            ArtifactFormat::Pdf
+        }
+    });
+
+    let op = GetDocumentArtifact {
+        escrow_account_id,
+        document_id,
+        format,
+        api_key: api_key_header(&headers),
+    };
+    match call(&state, op).await {
+        Ok(artifact) => (
+            [
+                (header::CONTENT_TYPE, artifact.content_type.to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}\"", artifact.filename),
+                ),
+            ],
+            artifact.bytes,
+        )
+            .into_response(),
+        Err(e) => respond::<()>(Err(e), "read document").into_response(),
+    }
+}
+
+// ============================================================================
+// E-SIGNATURE ENDPOINTS (see esign.rs)
+// ============================================================================
+
+/// Sends the latest version of a closing document to the e-signature
+/// provider.
+async fn request_signature(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((escrow_account_id, document_id)): Path<(String, u64)>,
+    ValidJson(payload): ValidJson<SignatureInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received signature request for document {} of escrow {}",
+        document_id, escrow_account_id
+    );
+
+    let Some(provider) = state.esign.clone() else {
+        return failure(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "No e-signature provider is configured (ESIGN_PROVIDER)",
+        );
+    };
+
+    let op = PrepareSignatureRequest {
+        escrow_account_id,
+        document_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    let request = match call(&state, op).await {
+        Ok(request) => request,
+        Err(e) => return respond::<()>(Err(e), "prepare signature request"),
+    };
+
+    let provider_envelope_id = match provider.request_signature(&request).await {
+        Ok(provider_envelope_id) => provider_envelope_id,
+        Err(e) => {
+            error!(
+                "E-signature provider {} refused document {}: {}",
+                provider.name(),
+                document_id,
+                e
+            );
+            return failure(
+                StatusCode::BAD_GATEWAY,
+                format!("E-signature provider failed: {}", e),
+            );
+        }
+    };
+
+    let op = RecordSignatureRequest {
+        provider: provider.name(),
+        provider_envelope_id,
+        request,
+    };
+    respond(call(&state, op).await, "record signature envelope")
+}
+
+async fn list_signature_envelopes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(escrow_account_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = ListSignatureEnvelopes {
+        escrow_account_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "list signature envelopes")
+}
+
+/// Receives the e-signature provider's callbacks. Authenticated by the
+/// provider's signature, not an API key.
+async fn esign_callback(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let Some(provider) = state.esign.clone() else {
+        return failure(
+            StatusCode::NOT_FOUND,
+            "No e-signature provider is configured",
+        );
+    };
+
+    let event = match provider.parse_callback(&headers, &body) {
+        Ok(event) => event,
+        Err(rejected) => {
+            tracing::warn!("Rejected e-signature callback: {}", rejected);
+            let status = match rejected {
+                CallbackRejected::Unauthenticated => StatusCode::UNAUTHORIZED,
+                CallbackRejected::Malformed(_) => StatusCode::BAD_REQUEST,
+            };
+            return failure(status, rejected);
+        }
+    };
+
+    let op = ApplySignatureEvent {
+        provider: provider.name(),
+        event,
+    };
+    // Anything but 2xx makes the provider retry
+    match call(&state, op).await {
+        Ok(envelope) => respond(
+            Ok(serde_json::json!({
+                "envelope_id": envelope.envelope_id,
+                "status": envelope.status
+            })),
+            "apply signature event",
+        ),
+        Err(e) if e == CLIENT_UNAVAILABLE || e == CLIENT_DROPPED => {
+            respond::<()>(Err(e), "apply signature event")
+        }
+        Err(e) => {
+            tracing::warn!("E-signature callback not applied: {}", e);
+            failure(StatusCode::UNPROCESSABLE_ENTITY, e)
+        }
+    }
+}
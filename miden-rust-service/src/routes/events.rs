@@ -0,0 +1,202 @@
+// src/routes/events.rs
+//
+// Change feeds: sync deltas (sync_deltas.rs), with their server-sent event
+// stream, and activity subscriptions (subscriptions.rs) delivered by webhook
+// or websocket.
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{delete, get, post},
+    Json, Router,
+};
+use tokio::sync::broadcast;
+use tracing::{error, info};
+
+use miden_rust_service::{
+    subscriptions::{
+        AuthorizeSubscription, CreateSubscription, DeleteSubscription, ListSubscriptions,
+        SubscriptionDelivery, SubscriptionInput,
+    },
+    sync_deltas::{GetLastSyncDelta, SyncNow},
+};
+
+use super::{call, respond};
+use crate::{api_key_header, AppState, ValidJson};
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/sync", post(sync_now))
+        .route("/sync/last-delta", get(get_last_sync_delta))
+        .route("/sync/events", get(sync_events))
+        .route(
+            "/subscriptions",
+            get(list_subscriptions).post(create_subscription),
+        )
+        .route(
+            "/subscriptions/:subscription_id",
+            delete(delete_subscription),
+        )
+        .route(
+            "/subscriptions/:subscription_id/ws",
+            get(subscription_socket),
+        )
+}
+
+// ============================================================================
+// SYNC DELTA ENDPOINTS (see sync_deltas.rs)
+// ============================================================================
+
+/// Syncs all tracked accounts now and returns what changed.
+async fn sync_now(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received sync request");
+    let op = SyncNow {};
+    respond(call(&state, op).await, "sync")
+}
+
+/// What the last sync changed; `delta` is null before the first sync.
+async fn get_last_sync_delta(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received last sync delta request");
+    let op = GetLastSyncDelta {};
+    respond(call(&state, op).await, "get last sync delta")
+}
+
+/// Streams sync deltas with changes as server-sent `delta` events.
+async fn sync_events(State(state): State<AppState>) -> Response {
+    info!("Received sync event stream request");
+
+    let feed = state.sync_feed.subscribe();
+    let stream = futures_util::stream::unfold(feed, |mut feed| async move {
+        loop {
+            match feed.recv().await {
+                Ok(delta) => match Event::default().event("delta").json_data(&delta) {
+                    Ok(sse) => return Some((Ok::<_, std::convert::Infallible>(sse), feed)),
+                    Err(e) => error!("Failed to encode sync delta: {}", e),
+                },
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    error!("Sync event stream skipped {} delta(s)", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+// ============================================================================
+// ACTIVITY SUBSCRIPTION ENDPOINTS (see subscriptions.rs)
+// ============================================================================
+
+/// Subscribes the API key to events; the webhook secret is only returned here.
+async fn create_subscription(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<SubscriptionInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received create subscription request: {:?}",
+        payload.event_types
+    );
+    let op = CreateSubscription {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "create subscription")
+}
+
+/// Subscriptions of the API key (all of them for admin keys).
+async fn list_subscriptions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list subscriptions request");
+    let op = ListSubscriptions {
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "list subscriptions")
+}
+
+async fn delete_subscription(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(subscription_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received delete subscription {} request", subscription_id);
+    let op = DeleteSubscription {
+        subscription_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "delete subscription")
+}
+
+/// Streams the deliveries of a websocket subscription, one JSON text message
+/// each. The key is checked before the upgrade.
+async fn subscription_socket(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(subscription_id): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    info!("Received subscription socket request: {}", subscription_id);
+    let op = AuthorizeSubscription {
+        subscription_id: subscription_id.clone(),
+        api_key: api_key_header(&headers),
+    };
+    if let Err(e) = call(&state, op).await {
+        return respond::<()>(Err(e), "authorize subscription socket").into_response();
+    }
+
+    // Subscribe before the upgrade so nothing is missed in between
+    let deliveries = state.subscription_deliveries.subscribe();
+    ws.on_upgrade(move |socket| forward_deliveries(socket, deliveries, subscription_id))
+}
+
+async fn forward_deliveries(
+    mut socket: WebSocket,
+    mut deliveries: broadcast::Receiver<SubscriptionDelivery>,
+    subscription_id: String,
+) {
+    loop {
+        tokio::select! {
+            delivery = deliveries.recv() => match delivery {
+                Ok(delivery) if delivery.subscription_id == subscription_id => {
+                    let text = match serde_json::to_string(&delivery) {
+                        Ok(text) => text,
+                        Err(e) => {
+                            error!("Failed to encode delivery {}: {}", delivery.delivery_id, e);
+                            continue;
+                        }
+                    };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    error!(
+                        "Subscription {} socket skipped {} delivery(s)",
+                        subscription_id, missed
+                    );
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            // Client closed the socket (incoming messages are ignored)
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
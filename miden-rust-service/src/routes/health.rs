@@ -0,0 +1,49 @@
+// src/routes/health.rs
+//
+// Liveness and startup probes. Served outside /api, without the API
+// middleware, so they answer while the client task is still starting.
+
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use serde::Serialize;
+
+use miden_rust_service::startup::StartupStage;
+
+use crate::AppState;
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/health", get(health_check))
+        .route("/health/startup", get(startup_status))
+}
+
+#[derive(Debug, Serialize)]
+struct HealthResponse {
+    status: String,
+    service: String,
+}
+
+async fn health_check() -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "healthy".to_string(),
+        service: "miden-rust-service-with-escrow-and-zk".to_string(),
+    })
+}
+
+/// Client initialization stage and progress (see startup.rs): 200 once ready,
+/// 503 while starting, 500 if initialization failed.
+async fn startup_status(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    let status = state.startup.status();
+    let code = match status.stage {
+        StartupStage::Ready => StatusCode::OK,
+        StartupStage::Failed => StatusCode::INTERNAL_SERVER_ERROR,
+        _ => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (
+        code,
+        Json(serde_json::json!({
+            "success": status.stage == StartupStage::Ready,
+            "startup": status,
+            "error": status.error
+        })),
+    )
+}
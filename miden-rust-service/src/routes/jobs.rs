@@ -0,0 +1,361 @@
+// src/routes/jobs.rs
+//
+// Background work: bulk mint jobs (mint_jobs.rs), sagas (sagas.rs) and the
+// admin scripts run as sagas (scripts.rs), the retry and offline queues
+// (retry_queue.rs, offline_queue.rs), writes that missed their deadline and
+// job cancellation (deadlines.rs).
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use tokio::sync::broadcast;
+use tracing::{error, info};
+
+use miden_rust_service::{
+    deadlines::CancelJob,
+    mint_jobs::{CreateMintBatch, GetMintBatch, ListMintBatches, MintItemInput},
+    offline_queue::{GetOfflineJob, ListOfflineJobs, OfflineStatus},
+    retry_queue::{GetRetryJob, ListRetryJobs, RetryStatus},
+    sagas::{GetSaga, ListSagas, ResumeSaga, Saga, SagaInput, SagaStatus, StartSaga},
+    scripts::{ScriptPlan, StartScript},
+    validation::{Validate, ValidationErrors},
+};
+
+use super::{call, failure, respond};
+use crate::{api_key_header, drive_mint_batch, drive_saga, AppState, ValidJson};
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/mint-batch",
+            get(list_mint_batches).post(create_mint_batch),
+        )
+        .route("/mint-batch/:job_id", get(get_mint_batch))
+        .route("/sagas", get(list_sagas).post(start_saga))
+        .route("/sagas/:saga_id", get(get_saga))
+        .route("/sagas/:saga_id/resume", post(resume_saga))
+        .route("/admin/script", post(run_admin_script))
+        .route("/retry-jobs", get(list_retry_jobs))
+        .route("/retry-jobs/events", get(retry_events))
+        .route("/retry-jobs/:job_id", get(get_retry_job))
+        .route("/offline-jobs", get(list_offline_jobs))
+        .route("/offline-jobs/:job_id", get(get_offline_job))
+        .route("/command-jobs/:job_id", get(get_command_job))
+        .route("/jobs/:job_id", delete(cancel_job))
+}
+
+#[derive(Debug, Deserialize)]
+struct MintBatchRequest {
+    items: Vec<MintItemInput>,
+}
+
+impl Validate for MintBatchRequest {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if self.items.is_empty() {
+            errors.add("items", "must contain at least one property");
+        }
+        for (i, item) in self.items.iter().enumerate() {
+            errors.nested(&format!("items[{}]", i), item);
+        }
+    }
+}
+
+/// Saga listing filter
+#[derive(Debug, Deserialize)]
+struct SagaQuery {
+    status: Option<SagaStatus>,
+}
+
+/// Retry job listing filter
+#[derive(Debug, Deserialize)]
+struct RetryJobQuery {
+    status: Option<RetryStatus>,
+}
+
+/// Offline job listing filter
+#[derive(Debug, Deserialize)]
+struct OfflineJobQuery {
+    status: Option<OfflineStatus>,
+}
+
+// ============================================================================
+// BULK MINT ENDPOINTS
+// ============================================================================
+
+async fn create_mint_batch(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<MintBatchRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received mint batch request ({} items)",
+        payload.items.len()
+    );
+    let op = CreateMintBatch {
+        items: payload.items,
+    };
+    let job = call(&state, op).await;
+    if let Some(job_id) = job.as_ref().ok().and_then(|job| job["job_id"].as_str()) {
+        tokio::spawn(drive_mint_batch(
+            state.client_tx.clone(),
+            state.job_cancellations.clone(),
+            job_id.to_string(),
+        ));
+    }
+    respond(job, "create mint batch")
+}
+
+async fn get_mint_batch(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received mint batch status request: {}", job_id);
+    let op = GetMintBatch { job_id };
+    respond(call(&state, op).await, "get mint batch")
+}
+
+async fn list_mint_batches(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list mint batches request");
+    let op = ListMintBatches {};
+    respond(call(&state, op).await, "list mint batches")
+}
+
+// ============================================================================
+// SAGA ENDPOINTS (see sagas.rs)
+// ============================================================================
+
+/// Starts a funding, purchase or tokenization saga; answers 202 while it runs.
+async fn start_saga(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<SagaInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received start saga request: {:?}", payload);
+    let op = StartSaga {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    saga_started(&state, call(&state, op).await, "start saga")
+}
+async fn list_sagas(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<SagaQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list sagas request");
+    let op = ListSagas {
+        status: query.status,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "list sagas")
+}
+
+async fn get_saga(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(saga_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = GetSaga {
+        saga_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "get saga")
+}
+
+/// Picks up a saga stopped as failed or compensation_failed.
+async fn resume_saga(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(saga_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received resume saga {} request", saga_id);
+    let op = ResumeSaga {
+        saga_id,
+        api_key: api_key_header(&headers),
+    };
+    saga_started(&state, call(&state, op).await, "resume saga")
+}
+
+/// Runs an admin script (see scripts.rs): JSON, or YAML when sent as
+/// application/yaml. Answers 202 with its saga while it runs.
+async fn run_admin_script(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: String,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok());
+    let plan = match ScriptPlan::parse(&body, content_type) {
+        Ok(plan) => plan,
+        Err(e) => return failure(StatusCode::BAD_REQUEST, e),
+    };
+    info!(
+        "Received admin script request: {} step(s)",
+        plan.steps.len()
+    );
+    let op = StartScript {
+        plan,
+        api_key: api_key_header(&headers),
+    };
+    saga_started(&state, call(&state, op).await, "run admin script")
+}
+
+/// Drives a started or resumed saga in the background and answers 202 with it.
+fn saga_started(
+    state: &AppState,
+    result: Result<Saga, String>,
+    action: &str,
+) -> (StatusCode, Json<serde_json::Value>) {
+    match result {
+        Ok(saga) => {
+            tokio::spawn(drive_saga(state.client_tx.clone(), saga.saga_id.clone()));
+            let (_, body) = respond(Ok(saga), action);
+            (StatusCode::ACCEPTED, body)
+        }
+        Err(e) => respond::<Saga>(Err(e), action),
+    }
+}
+
+// ============================================================================
+// JOB ENDPOINTS (cancellation, background writes)
+// ============================================================================
+
+/// Cancels a mint batch, retry job, offline job or background write (see
+/// deadlines.rs).
+///
+/// In-flight work is signalled right away, since the client task may be busy
+/// running it; the terminal status is then recorded through the client task
+/// (or directly, for background writes).
+async fn cancel_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received cancel job request: {}", job_id);
+
+    let signalled = state.job_cancellations.cancel(&job_id);
+    let job = if job_id.starts_with("cmd-") {
+        state
+            .background_jobs
+            .cancel(&job_id)
+            .map(|job| serde_json::json!(job))
+            .map_err(|e| e.to_string())
+    } else {
+        let op = CancelJob {
+            job_id: job_id.clone(),
+        };
+        call(&state, op).await.map(|job| serde_json::json!(job))
+    };
+    let cancelled = job.map(|job| {
+        serde_json::json!({
+            "job": job,
+            "signalled": signalled
+        })
+    });
+    respond(cancelled, &format!("cancel job {}", job_id))
+}
+
+/// Serves a write that missed its deadline (see deadlines.rs): still running,
+/// or finished with the response it would have returned.
+async fn get_command_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received get command job request: {}", job_id);
+
+    match state.background_jobs.get(&job_id) {
+        Some(job) => respond(Ok(job), "get command job"),
+        None => failure(
+            StatusCode::NOT_FOUND,
+            format!("Command job {} not found", job_id),
+        ),
+    }
+}
+
+// ============================================================================
+// RETRY QUEUE ENDPOINTS
+// ============================================================================
+
+async fn get_retry_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received get retry job request: {}", job_id);
+    let op = GetRetryJob { job_id };
+    respond(call(&state, op).await, "get retry job")
+}
+
+async fn list_retry_jobs(
+    State(state): State<AppState>,
+    Query(query): Query<RetryJobQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list retry jobs request: {:?}", query.status);
+    let op = ListRetryJobs {
+        status: query.status,
+    };
+    respond(call(&state, op).await, "list retry jobs")
+}
+
+/// Streams retry job events as server-sent events, one event per job event
+/// (named after its kind, e.g. `attempt_failed` or `expired`) as the client
+/// task publishes them.
+async fn retry_events(State(state): State<AppState>) -> Response {
+    info!("Received retry event stream request");
+
+    let feed = state.retry_feed.subscribe();
+    let stream = futures_util::stream::unfold(feed, |mut feed| async move {
+        loop {
+            match feed.recv().await {
+                Ok(event) => match Event::default()
+                    .event(event.kind.as_str())
+                    .json_data(&event)
+                {
+                    Ok(sse) => return Some((Ok::<_, std::convert::Infallible>(sse), feed)),
+                    Err(e) => error!("Failed to encode retry event: {}", e),
+                },
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    error!("Retry event stream skipped {} event(s)", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+// ============================================================================
+// OFFLINE MODE ENDPOINTS (see offline_queue.rs)
+// ============================================================================
+
+/// An offline job, with its place in the replay order while it waits.
+async fn get_offline_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received get offline job request: {}", job_id);
+    let op = GetOfflineJob { job_id };
+    respond(call(&state, op).await, "get offline job")
+}
+
+/// Offline jobs in replay order, with whether the node is reachable and how
+/// many jobs wait for it.
+async fn list_offline_jobs(
+    State(state): State<AppState>,
+    Query(query): Query<OfflineJobQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list offline jobs request: {:?}", query.status);
+    let op = ListOfflineJobs {
+        status: query.status,
+    };
+    respond(call(&state, op).await, "list offline jobs")
+}
@@ -0,0 +1,361 @@
+// src/routes/listings.rs
+//
+// Listing endpoints (listings are addressed by property ID): confidential
+// listings and their disclosures (confidential_listings.rs), listing details
+// and search (search.rs), settlement tokens (settlement_tokens.rs) and offer
+// negotiation (negotiation.rs).
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use serde::Deserialize;
+use tracing::info;
+
+use miden_rust_service::{
+    confidential_listings::{
+        ConfidentialListingInput, DisclosureRequestInput, GetListing, ListDisclosures,
+        MakeListingConfidential, MakeListingPublic, RequestDisclosure, RevokeDisclosure,
+    },
+    negotiation::{
+        AcceptOffer, CounterInput, CounterOffer, GetOffer, ListOffers, OfferInput,
+        OfferResponseInput, RejectOffer, SubmitOffer, WithdrawOffer,
+    },
+    search::{ListingDetailsInput, SearchListings, SearchQuery, SetListingDetails},
+    settlement_tokens::{
+        ListSettlementTokens, ListingTokenInput, RegisterSettlementToken,
+        SetListingSettlementToken, SettlementTokenInput,
+    },
+};
+
+use super::{call, respond};
+use crate::{api_key_header, AppState, ValidJson, ValidQuery};
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/listings/:property_id", get(get_listing))
+        .route(
+            "/listings/:property_id/confidential",
+            post(make_listing_confidential).delete(make_listing_public),
+        )
+        .route(
+            "/listings/:property_id/disclosures",
+            get(list_disclosures).post(request_disclosure),
+        )
+        .route(
+            "/listings/:property_id/disclosures/:grant_id",
+            delete(revoke_disclosure),
+        )
+        .route("/listings/:property_id/details", put(set_listing_details))
+        .route("/search", get(search_listings))
+        .route("/settlement-tokens", get(list_settlement_tokens))
+        .route(
+            "/listings/:property_id/settlement-token",
+            put(set_listing_settlement_token),
+        )
+        .route("/admin/settlement-tokens", post(register_settlement_token))
+        .route(
+            "/listings/:property_id/offers",
+            get(list_offers).post(submit_offer),
+        )
+        .route("/listings/:property_id/offers/:offer_id", get(get_offer))
+        .route(
+            "/listings/:property_id/offers/:offer_id/counter",
+            post(counter_offer),
+        )
+        .route(
+            "/listings/:property_id/offers/:offer_id/accept",
+            post(accept_offer),
+        )
+        .route(
+            "/listings/:property_id/offers/:offer_id/reject",
+            post(reject_offer),
+        )
+        .route(
+            "/listings/:property_id/offers/:offer_id/withdraw",
+            post(withdraw_offer),
+        )
+}
+
+// ============================================================================
+// CONFIDENTIAL LISTING ENDPOINTS (see confidential_listings.rs)
+// ============================================================================
+
+/// Disclosure token unlocking a confidential listing
+#[derive(Debug, Deserialize)]
+struct ListingQuery {
+    disclosure: Option<String>,
+}
+
+async fn get_listing(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(property_id): Path<String>,
+    Query(query): Query<ListingQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received listing request: {}", property_id);
+    let op = GetListing {
+        property_id,
+        disclosure: query.disclosure,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "get listing")
+}
+
+async fn make_listing_confidential(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(property_id): Path<String>,
+    ValidJson(payload): ValidJson<ConfidentialListingInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received confidential listing request: {}", property_id);
+    let op = MakeListingConfidential {
+        property_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "make listing confidential")
+}
+
+async fn make_listing_public(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(property_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received public listing request: {}", property_id);
+    let op = MakeListingPublic {
+        property_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "make listing public")
+}
+
+async fn request_disclosure(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(property_id): Path<String>,
+    ValidJson(payload): ValidJson<DisclosureRequestInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received disclosure request on {}: {}",
+        property_id, payload.buyer_account_id
+    );
+    let op = RequestDisclosure {
+        property_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "grant disclosure")
+}
+
+async fn revoke_disclosure(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((property_id, grant_id)): Path<(String, u64)>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received disclosure revocation: {}/{}",
+        property_id, grant_id
+    );
+    let op = RevokeDisclosure {
+        property_id,
+        grant_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "revoke disclosure")
+}
+
+async fn list_disclosures(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(property_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list disclosures request: {}", property_id);
+    let op = ListDisclosures {
+        property_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "list disclosures")
+}
+
+// ============================================================================
+// LISTING DETAILS AND SEARCH ENDPOINTS (see search.rs)
+// ============================================================================
+
+/// Sets the address, description, size and coordinates of a listing.
+async fn set_listing_details(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(property_id): Path<String>,
+    ValidJson(payload): ValidJson<ListingDetailsInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received listing details request: {}", property_id);
+    let op = SetListingDetails {
+        property_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "set listing details")
+}
+
+/// Full-text, price, size and bounding-box search over properties.
+async fn search_listings(
+    State(state): State<AppState>,
+    ValidQuery(query): ValidQuery<SearchQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = SearchListings { query };
+    respond(call(&state, op).await, "search listings")
+}
+
+// ============================================================================
+// SETTLEMENT TOKEN ENDPOINTS (see settlement_tokens.rs)
+// ============================================================================
+
+/// The service token and the registered tokens listings may settle in.
+async fn list_settlement_tokens(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = ListSettlementTokens {};
+    respond(call(&state, op).await, "list settlement tokens")
+}
+
+/// Sets the token a listing settles in. Needs an API key bound to the owner.
+async fn set_listing_settlement_token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(property_id): Path<String>,
+    ValidJson(payload): ValidJson<ListingTokenInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = SetListingSettlementToken {
+        property_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "set listing settlement token")
+}
+
+/// Registers a fungible faucet as a settlement token. Admin API key only.
+async fn register_settlement_token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<SettlementTokenInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = RegisterSettlementToken {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "register settlement token")
+}
+
+// ============================================================================
+// OFFER NEGOTIATION ENDPOINTS (see negotiation.rs)
+// ============================================================================
+
+async fn submit_offer(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(property_id): Path<String>,
+    ValidJson(payload): ValidJson<OfferInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received offer on {}: {:?}", property_id, payload);
+    let op = SubmitOffer {
+        property_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "submit offer")
+}
+
+async fn counter_offer(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((property_id, offer_id)): Path<(String, String)>,
+    ValidJson(payload): ValidJson<CounterInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received counter-offer on {}/{}: {:?}",
+        property_id, offer_id, payload
+    );
+    let op = CounterOffer {
+        property_id,
+        offer_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "counter offer")
+}
+
+async fn accept_offer(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((property_id, offer_id)): Path<(String, String)>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received offer acceptance: {}/{}", property_id, offer_id);
+    let op = AcceptOffer {
+        property_id,
+        offer_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "accept offer")
+}
+
+async fn reject_offer(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((property_id, offer_id)): Path<(String, String)>,
+    ValidJson(payload): ValidJson<OfferResponseInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received offer rejection: {}/{}", property_id, offer_id);
+    let op = RejectOffer {
+        property_id,
+        offer_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "reject offer")
+}
+
+async fn withdraw_offer(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((property_id, offer_id)): Path<(String, String)>,
+    ValidJson(payload): ValidJson<OfferResponseInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received offer withdrawal: {}/{}", property_id, offer_id);
+    let op = WithdrawOffer {
+        property_id,
+        offer_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "withdraw offer")
+}
+
+async fn get_offer(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((property_id, offer_id)): Path<(String, String)>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received offer request: {}/{}", property_id, offer_id);
+    let op = GetOffer {
+        property_id,
+        offer_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "get offer")
+}
+
+async fn list_offers(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(property_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list offers request: {}", property_id);
+    let op = ListOffers {
+        property_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "list offers")
+}
@@ -0,0 +1,266 @@
+// src/routes/localnet.rs
+//
+// Endpoints served on localnet only: the throughput benchmark (bench.rs) and
+// the demo scenario (demo.rs), with its event socket.
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use futures_util::StreamExt;
+use tokio::sync::broadcast;
+use tracing::{error, info};
+
+use miden_rust_service::{
+    bench::{BenchInput, BenchReport, BenchSample, PhaseReport, RunBenchOp},
+    demo::{DemoEvent, DemoEventKind, DemoRun, DemoRuns, DemoScenarioInput, RunDemoStep},
+};
+
+use super::{call_with, failure, respond};
+use crate::{AppState, CommandSender, ValidJson};
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/bench", post(run_bench))
+        .route("/demo/run-scenario", post(run_demo_scenario))
+        .route("/demo/scenarios/:run_id", get(get_demo_scenario))
+        .route("/demo/scenarios/:run_id/ws", get(demo_scenario_socket))
+}
+
+// ============================================================================
+// BENCHMARK ENDPOINTS
+// ============================================================================
+
+/// Runs a benchmark (see bench.rs) and answers with its report once all
+/// phases have finished.
+async fn run_bench(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<BenchInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received bench request: {} mint(s), {} consume(s), {} transfer(s)",
+        payload.mints, payload.consumes, payload.transfers
+    );
+
+    let run_id = format!("bench-{}", chrono::Utc::now().timestamp_millis());
+    let concurrency = payload.concurrency();
+    let started = std::time::Instant::now();
+
+    let mut phases = Vec::new();
+    for (phase, ops) in payload.plan(&run_id) {
+        if ops.is_empty() {
+            continue;
+        }
+        let phase_started = std::time::Instant::now();
+        let samples: Vec<Result<BenchSample, String>> = futures_util::stream::iter(ops)
+            .map(|op| {
+                let client_tx = state.client_tx.clone();
+                async move {
+                    let op = RunBenchOp {
+                        op,
+                        enqueued_at: std::time::Instant::now(),
+                    };
+                    call_with(&client_tx, op).await
+                }
+            })
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
+
+        let samples = match samples.into_iter().collect::<Result<Vec<_>, _>>() {
+            Ok(samples) => samples,
+            Err(e) => return respond::<BenchReport>(Err(e), "run bench"),
+        };
+        let report = PhaseReport::new(phase, samples, phase_started.elapsed());
+        info!(
+            "Bench {} {:?}: {} op(s), {} failed, {}/s",
+            run_id, phase, report.operations, report.failed, report.throughput_per_sec
+        );
+        phases.push(report);
+    }
+
+    let report = BenchReport::new(run_id, concurrency, phases, started.elapsed());
+    respond(Ok(report), "run bench")
+}
+
+// ============================================================================
+// DEMO SCENARIO ENDPOINTS
+// ============================================================================
+
+/// Starts a demo scenario (see demo.rs); answers 202 with the run while a
+/// task drives it. The body may be empty.
+async fn run_demo_scenario(
+    State(state): State<AppState>,
+    body: String,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let input = if body.trim().is_empty() {
+        DemoScenarioInput::default()
+    } else {
+        match serde_json::from_str::<DemoScenarioInput>(&body) {
+            Ok(input) => input,
+            Err(e) => {
+                return failure(
+                    StatusCode::BAD_REQUEST,
+                    format!("Invalid demo scenario: {}", e),
+                )
+            }
+        }
+    };
+
+    let run = state.demo_runs.start(input);
+    info!(
+        "Received demo scenario request: {} ({})",
+        run.run_id, run.context.property_id
+    );
+    tokio::spawn(drive_demo(
+        state.client_tx.clone(),
+        state.demo_runs.clone(),
+        run.clone(),
+    ));
+
+    let (_, body) = respond(Ok(run), "run demo scenario");
+    (StatusCode::ACCEPTED, body)
+}
+
+/// Sends a demo run's steps to the client task one after another, recording
+/// an event before and after each, until one fails.
+async fn drive_demo(client_tx: CommandSender, runs: DemoRuns, run: DemoRun) {
+    let run_id = run.run_id;
+    let mut context = run.context;
+
+    for step in run.steps {
+        runs.record(
+            &run_id,
+            DemoEventKind::StepStarted,
+            Some(step),
+            serde_json::Value::Null,
+            None,
+        );
+
+        let op = RunDemoStep {
+            step,
+            context: context.clone(),
+        };
+        let result = call_with(&client_tx, op).await;
+
+        match result {
+            Ok((detail, updated)) => {
+                context = updated;
+                let kind = DemoEventKind::StepCompleted;
+                runs.record(&run_id, kind, Some(step), detail, Some(&context));
+            }
+            Err(e) => {
+                error!("Demo {} failed at {:?}: {}", run_id, step, e);
+                runs.record(
+                    &run_id,
+                    DemoEventKind::StepFailed,
+                    Some(step),
+                    serde_json::json!({ "error": e }),
+                    None,
+                );
+                runs.record(
+                    &run_id,
+                    DemoEventKind::Finished,
+                    None,
+                    serde_json::json!({ "status": "failed" }),
+                    None,
+                );
+                return;
+            }
+        }
+    }
+
+    info!("Demo {} completed: {} sold", run_id, context.property_id);
+    runs.record(
+        &run_id,
+        DemoEventKind::Finished,
+        None,
+        serde_json::json!({ "status": "completed" }),
+        None,
+    );
+}
+
+async fn get_demo_scenario(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    match state.demo_runs.get(&run_id) {
+        Some(run) => respond(Ok(run), "get demo scenario"),
+        None => failure(
+            StatusCode::NOT_FOUND,
+            format!("Demo run {} not found", run_id),
+        ),
+    }
+}
+
+/// Streams a demo run's events, one JSON text message each: first the ones
+/// so far, then the rest as they happen, closing after the finished event.
+async fn demo_scenario_socket(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    info!("Received demo scenario socket request: {}", run_id);
+
+    // Followed before the upgrade so nothing is missed in between
+    match state.demo_runs.follow(&run_id) {
+        Some((past, live)) => {
+            ws.on_upgrade(move |socket| forward_demo_events(socket, past, live, run_id))
+        }
+        None => failure(
+            StatusCode::NOT_FOUND,
+            format!("Demo run {} not found", run_id),
+        )
+        .into_response(),
+    }
+}
+
+async fn forward_demo_events(
+    mut socket: WebSocket,
+    past: Vec<DemoEvent>,
+    mut live: broadcast::Receiver<DemoEvent>,
+    run_id: String,
+) {
+    async fn send(socket: &mut WebSocket, event: &DemoEvent) -> bool {
+        let Ok(text) = serde_json::to_string(event) else {
+            return true;
+        };
+        socket.send(Message::Text(text)).await.is_ok() && event.kind != DemoEventKind::Finished
+    }
+
+    for event in &past {
+        if !send(&mut socket, event).await {
+            let _ = socket.send(Message::Close(None)).await;
+            return;
+        }
+    }
+
+    loop {
+        tokio::select! {
+            event = live.recv() => match event {
+                Ok(event) if event.run_id == run_id => {
+                    if !send(&mut socket, &event).await {
+                        let _ = socket.send(Message::Close(None)).await;
+                        return;
+                    }
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    error!("Demo {} socket skipped {} event(s)", run_id, missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            // Client closed the socket (incoming messages are ignored)
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
@@ -0,0 +1,135 @@
+// src/routes/markets.rs
+//
+// Share trading endpoints (order_book.rs): markets in a property's shares,
+// their order books and trade history.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::{delete, get},
+    Json, Router,
+};
+use serde::Deserialize;
+use tracing::info;
+
+use miden_rust_service::order_book::{
+    CancelOrder, GetMarket, ListMarkets, ListOrders, ListTrades, MarketInput, OpenMarket,
+    OrderInput, PlaceOrder,
+};
+
+use super::{call, respond};
+use crate::{api_key_header, AppState, ValidJson};
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/markets", get(list_markets).post(open_market))
+        .route("/markets/:property_id", get(get_market))
+        .route(
+            "/markets/:property_id/orders",
+            get(list_orders).post(place_order),
+        )
+        .route(
+            "/markets/:property_id/orders/:order_id",
+            delete(cancel_order),
+        )
+        .route("/markets/:property_id/trades", get(list_trades))
+}
+
+/// Order and trade history filter
+#[derive(Debug, Deserialize)]
+struct TradingQuery {
+    /// Account name or hex AccountId
+    account_id: Option<String>,
+}
+
+async fn open_market(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<MarketInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received open market request: {:?}", payload);
+    let op = OpenMarket {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "open market")
+}
+
+async fn list_markets(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list markets request");
+    let op = ListMarkets {};
+    respond(call(&state, op).await, "list markets")
+}
+
+async fn get_market(
+    State(state): State<AppState>,
+    Path(property_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received market request: {}", property_id);
+    let op = GetMarket { property_id };
+    respond(call(&state, op).await, "get market")
+}
+
+async fn place_order(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(property_id): Path<String>,
+    ValidJson(payload): ValidJson<OrderInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received order in {}: {:?}", property_id, payload);
+    let op = PlaceOrder {
+        property_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "place order")
+}
+
+async fn cancel_order(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((property_id, order_id)): Path<(String, u64)>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received cancel order request: {} in {}",
+        order_id, property_id
+    );
+    let op = CancelOrder {
+        property_id,
+        order_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "cancel order")
+}
+
+async fn list_orders(
+    State(state): State<AppState>,
+    Path(property_id): Path<String>,
+    Query(query): Query<TradingQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received list orders request: {} ({:?})",
+        property_id, query.account_id
+    );
+    let op = ListOrders {
+        property_id,
+        account_id: query.account_id,
+    };
+    respond(call(&state, op).await, "list orders")
+}
+
+async fn list_trades(
+    State(state): State<AppState>,
+    Path(property_id): Path<String>,
+    Query(query): Query<TradingQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received list trades request: {} ({:?})",
+        property_id, query.account_id
+    );
+    let op = ListTrades {
+        property_id,
+        account_id: query.account_id,
+    };
+    respond(call(&state, op).await, "list trades")
+}
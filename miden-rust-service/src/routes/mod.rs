@@ -0,0 +1,304 @@
+// src/routes/mod.rs
+//
+// HTTP handlers, by area
+//
+// Each module holds the handlers of one area, the request types only they use,
+// and a `router()` with their routes; `router()` here merges them into the API
+// router built in main.rs, which adds the middleware.
+//
+// - accounts.rs: account details, balances, statements, portfolios,
+//   component upgrades, token sends, formatting, allowances, organizations,
+//   wallet sessions, tax lots and reports
+// - notes.rs: consumable notes, note files, the spam quarantine, contract
+//   anchors
+// - escrow.rs: create, fund, release, refund, the escrow and its timeline,
+//   escrow listings, liens, release approvals, checklists, yield, closing
+//   documents, e-signatures
+// - proofs.rs: ZK proofs (demo), the proof cache and program registry,
+//   notarizations
+// - admin.rs: API keys and closing agent assignments, portal tokens, secrets,
+//   funds recovery, data subject requests, withholding rules, property
+//   re-issues and re-issue requests after lost access, accreditation rules,
+//   identity providers, insurers, reconciliation, hooks, feature flags,
+//   jurisdiction lists, the treasury and ledger, store maintenance and
+//   snapshots, and the never-shed queue, SLO and RPC introspection
+// - portal.rs: the read-only customer portal, with its own token auth
+// - custodial.rs: email sign-in and the wallets kept for those users
+// - properties.rs: minting, transfers, the property listing, retirement,
+//   provenance, locations, photos, insurance binders, appraisers and
+//   inspectors
+// - swaps.rs: token swap quotes and their execution
+// - listings.rs: confidential listings, details and search, settlement
+//   tokens, offers
+// - markets.rs: share trading
+// - sales.rs: installment purchases, leases, auctions
+// - billing.rs: invoices, usage, fee sponsorship
+// - events.rs: sync deltas and activity subscriptions
+// - jobs.rs: mint batches, sagas, retry and offline jobs, job cancellation
+// - localnet.rs: the benchmark and demo scenarios (localnet only)
+// - health.rs: liveness and startup probes, outside /api
+//
+// Handlers send typed operations (commands.rs) with `call` and answer with
+// `respond`: the uniform envelope
+//
+//   {"success": true, "data": <output>, "error": null}
+//   {"success": false, "data": null, "error": "<message>"}
+//
+// with the status the error prefix calls for (`error_status`). An operation
+// that fails part way through answers with what it got done as `data`
+// (`respond_partial`).
+
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use serde::Serialize;
+use tracing::error;
+
+use miden_rust_service::{
+    commands::Operation,
+    config::{Profile, ServiceConfig},
+    listing::{ListPage, Listing},
+};
+
+use crate::{AppState, CommandSender};
+
+mod accounts;
+mod admin;
+mod billing;
+mod custodial;
+mod escrow;
+mod events;
+mod health;
+mod jobs;
+mod listings;
+mod localnet;
+mod markets;
+mod notes;
+mod portal;
+mod proofs;
+mod properties;
+mod sales;
+mod swaps;
+
+pub(crate) use admin::{monitoring_router, require_admin};
+pub(crate) use health::router as health_router;
+
+/// Errors of `call` when the client task is gone
+const CLIENT_UNAVAILABLE: &str = "Client task not available";
+const CLIENT_DROPPED: &str = "Internal communication error";
+
+/// The API routes of every area. Failure injection is only served with the
+/// `fault-injection` feature (see faults.rs), the benchmark and demo scenarios
+/// only on localnet (see bench.rs, demo.rs).
+pub(crate) fn router(config: &ServiceConfig) -> Router<AppState> {
+    let router = Router::new()
+        .merge(accounts::router())
+        .merge(notes::router())
+        .merge(escrow::router())
+        .merge(proofs::router())
+        .merge(admin::router())
+        .merge(portal::router())
+        .merge(custodial::router())
+        .merge(properties::router(config))
+        .merge(swaps::router())
+        .merge(listings::router())
+        .merge(markets::router())
+        .merge(sales::router())
+        .merge(billing::router())
+        .merge(events::router())
+        .merge(jobs::router());
+    #[cfg(feature = "fault-injection")]
+    let router = router.merge(admin::fault_router());
+    if config.profile == Profile::Localnet {
+        router.merge(localnet::router())
+    } else {
+        router
+    }
+}
+
+/// Status of a prefixed error: authorization failures (see escrow.rs) 401 /
+/// 403, actions blocked by liens (see liens.rs) or state 409, and submissions
+/// handed to the retry or offline queue (see retry_queue.rs, offline_queue.rs)
+/// or awaiting approval 202. None for errors without a prefix.
+fn error_status(error: &str) -> Option<StatusCode> {
+    if error.starts_with("Unauthorized:") {
+        Some(StatusCode::UNAUTHORIZED)
+    } else if error.starts_with("Forbidden:") {
+        Some(StatusCode::FORBIDDEN)
+    } else if error.starts_with("Encumbered:")
+        || error.starts_with("Conflict:")
+        || error.starts_with("Vetoed:")
+    {
+        Some(StatusCode::CONFLICT)
+    } else if error.starts_with("Retrying:")
+        || error.starts_with("Queued offline:")
+        || error.starts_with("Approval required:")
+    {
+        Some(StatusCode::ACCEPTED)
+    } else if error == CLIENT_UNAVAILABLE {
+        Some(StatusCode::SERVICE_UNAVAILABLE)
+    } else if error == CLIENT_DROPPED {
+        Some(StatusCode::INTERNAL_SERVER_ERROR)
+    } else {
+        None
+    }
+}
+
+/// The `{success, data, error}` envelope of an operation result (see module
+/// docs). Errors without a prefix are the caller's: 400. `action` names the
+/// operation in the failure log.
+pub(crate) fn respond<T: Serialize>(
+    result: Result<T, String>,
+    action: &str,
+) -> (StatusCode, Json<serde_json::Value>) {
+    match result {
+        Ok(data) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "success": true,
+                "data": data,
+                "error": null
+            })),
+        ),
+        Err(e) => {
+            error!("Failed to {}: {}", action, e);
+            (
+                error_status(&e).unwrap_or(StatusCode::BAD_REQUEST),
+                Json(serde_json::json!({
+                    "success": false,
+                    "data": null,
+                    "error": e
+                })),
+            )
+        }
+    }
+}
+
+/// The envelope of an operation that failed part way through, such as note
+/// consumption batch by batch: `data` is what got done before `error`.
+pub(crate) fn respond_partial<T: Serialize>(
+    data: T,
+    error: String,
+    action: &str,
+) -> (StatusCode, Json<serde_json::Value>) {
+    error!("Failed to {}: {}", action, error);
+    (
+        error_status(&error).unwrap_or(StatusCode::BAD_REQUEST),
+        Json(serde_json::json!({
+            "success": false,
+            "data": data,
+            "error": error
+        })),
+    )
+}
+
+/// The envelope of a failure found outside the client task, such as an
+/// unknown run or a file the media store could not read, with its status.
+pub(crate) fn failure(
+    status: StatusCode,
+    error: impl ToString,
+) -> (StatusCode, Json<serde_json::Value>) {
+    (
+        status,
+        Json(serde_json::json!({
+            "success": false,
+            "data": null,
+            "error": error.to_string()
+        })),
+    )
+}
+
+/// Sends a typed operation (commands.rs) to the client task and waits for its
+/// result.
+pub(crate) async fn call<O: Operation>(state: &AppState, op: O) -> Result<O::Output, String> {
+    call_with(&state.client_tx, op).await
+}
+
+/// `call` on a command queue handle, for work that outlives the request's state
+/// borrow, such as a streamed listing or a scenario run.
+pub(crate) async fn call_with<O: Operation>(
+    client_tx: &CommandSender,
+    op: O,
+) -> Result<O::Output, String> {
+    let Some(rx) = client_tx.dispatch(op).await else {
+        return Err(CLIENT_UNAVAILABLE.to_string());
+    };
+    rx.await.unwrap_or_else(|_| Err(CLIENT_DROPPED.to_string()))
+}
+
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Entries fetched from the client task per streamed page.
+const LISTING_PAGE_SIZE: usize = 200;
+
+/// True if the client asked for newline-delimited JSON.
+pub(crate) fn wants_ndjson(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .map(|accept| accept.contains(NDJSON_CONTENT_TYPE))
+        .unwrap_or(false)
+}
+
+/// Streams a listing as NDJSON, one client operation per page.
+///
+/// The status line is sent before the first page is read, so a failure part way
+/// through is reported as a final `{"error": ...}` line.
+pub(crate) fn ndjson_listing(client_tx: CommandSender, listing: Listing) -> Response {
+    let pages = futures_util::stream::unfold(Some(0usize), move |cursor| {
+        let client_tx = client_tx.clone();
+        let listing = listing.clone();
+        async move {
+            let cursor = cursor?;
+
+            let op = ListPage {
+                listing,
+                cursor,
+                limit: LISTING_PAGE_SIZE,
+            };
+            let page = call_with(&client_tx, op).await;
+
+            let mut chunk = Vec::new();
+            let next = match page {
+                Ok(page) => {
+                    for item in &page.items {
+                        if serde_json::to_writer(&mut chunk, item).is_ok() {
+                            chunk.push(b'\n');
+                        }
+                    }
+                    page.next_cursor
+                }
+                Err(e) => {
+                    error!("Listing stream stopped: {}", e);
+                    let _ = serde_json::to_writer(&mut chunk, &serde_json::json!({ "error": e }));
+                    chunk.push(b'\n');
+                    None
+                }
+            };
+
+            Some((Ok::<_, std::convert::Infallible>(Bytes::from(chunk)), next))
+        }
+    });
+
+    (
+        [(header::CONTENT_TYPE, NDJSON_CONTENT_TYPE)],
+        Body::from_stream(pages),
+    )
+        .into_response()
+}
+
+/// Reads a whole listing into memory (default, non-streaming responses).
+pub(crate) async fn collect_listing(
+    state: &AppState,
+    listing: Listing,
+) -> Result<Vec<serde_json::Value>, String> {
+    let op = ListPage {
+        listing,
+        cursor: 0,
+        limit: usize::MAX,
+    };
+    call(state, op).await.map(|page| page.items)
+}
@@ -0,0 +1,263 @@
+// src/routes/notes.rs
+//
+// Note endpoints: consumable note listings, note consumption, note file
+// export/import (note_files.rs), the spam quarantine (note_quarantine.rs) and
+// sales contract anchors of transfer notes (contract_anchors.rs).
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use miden_rust_service::{
+    contract_anchors::{AnchorQuery, VerifyContractAnchor},
+    etag::EtagResource,
+    listing::Listing,
+    note_files::{ExportNote, ImportNote, NoteEncoding, NoteExportQuery, NoteImportInput},
+    note_quarantine::{ListQuarantinedNotes, QuarantineQuery, ReleaseQuarantinedNote},
+    spend_receipts::SpendReceipt,
+    validation::{self, Validate, ValidationErrors},
+    ConsumeNote, GetConsumableNotes,
+};
+
+use super::{call, failure, ndjson_listing, respond, respond_partial, wants_ndjson};
+use crate::{api_key_header, conditional_read, AppState, ValidJson};
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/get-consumable-notes", get(get_consumable_notes))
+        .route("/consume-note", post(consume_note))
+        // Note files (Miden CLI and wallets)
+        .route("/notes/:note_id/export", get(export_note))
+        .route("/notes/import", post(import_note))
+        .route(
+            "/notes/:note_id/contract-anchor",
+            get(verify_contract_anchor),
+        )
+        .route("/notes/quarantine", get(list_quarantined_notes))
+        .route(
+            "/notes/quarantine/:note_id/release",
+            post(release_quarantined_note),
+        )
+}
+
+#[derive(Debug, Deserialize)]
+struct ConsumeNoteRequest {
+    note_id: String,
+    account_id: Option<String>,
+}
+
+/// What a consume request got done, up to a failed batch if there was one.
+#[derive(Debug, Serialize)]
+struct ConsumedNotesSummary {
+    /// Last transaction, after which the notes are consumed
+    transaction_id: Option<String>,
+    /// Every transaction, one per MAX_NOTES_PER_TX notes
+    transaction_ids: Vec<String>,
+    consumed_note_ids: Vec<String>,
+    /// Notes left alone for their unknown scripts (note_inspection.rs)
+    refused_note_ids: Vec<String>,
+    /// Nullifiers of the consumed notes (spend_receipts.rs)
+    nullifiers: Vec<String>,
+    /// Account commitment after the last transaction
+    account_commitment: Option<String>,
+    receipts: Vec<SpendReceipt>,
+}
+
+impl Validate for ConsumeNoteRequest {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("note_id", validation::hex_string(&self.note_id, true));
+        if let Some(account_id) = &self.account_id {
+            errors.check(
+                "account_id",
+                validation::account_selector(account_id, &["alice", "bob", "faucet"]),
+            );
+        }
+    }
+}
+
+async fn get_consumable_notes(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    info!("Received get consumable notes request");
+
+    if wants_ndjson(&headers) {
+        return ndjson_listing(
+            state.client_tx,
+            Listing::ConsumableNotes { account_id: None },
+        );
+    }
+
+    conditional_read(
+        &state,
+        &headers,
+        EtagResource::ConsumableNotes { account: None },
+        get_consumable_notes_buffered(state.clone()),
+    )
+    .await
+}
+
+async fn get_consumable_notes_buffered(state: AppState) -> (StatusCode, Json<serde_json::Value>) {
+    let op = GetConsumableNotes { account_id: None };
+    let result = call(&state, op).await.map(|notes| {
+        info!("Retrieved {} consumable notes", notes.len());
+        notes
+    });
+    respond(result, "get consumable notes")
+}
+
+async fn consume_note(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<ConsumeNoteRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received consume note request: {:?}", payload);
+
+    let op = ConsumeNote {
+        note_id: payload.note_id,
+        account_id: payload.account_id,
+    };
+    let consumed = match call(&state, op).await {
+        Ok(consumed) => consumed,
+        Err(e) => return respond::<()>(Err(e), "consume note"),
+    };
+
+    let transaction_ids = consumed.transaction_ids();
+    let summary = ConsumedNotesSummary {
+        transaction_id: transaction_ids.last().cloned(),
+        consumed_note_ids: consumed.note_ids(),
+        refused_note_ids: consumed.refused_note_ids(),
+        nullifiers: consumed.nullifiers(),
+        account_commitment: consumed.account_commitment(),
+        receipts: consumed.receipts.clone(),
+        transaction_ids,
+    };
+    match consumed.into_tx_id() {
+        Ok(_) => {
+            info!("Notes consumed: tx={}", summary.transaction_ids.join(", "));
+            respond(Ok(summary), "consume note")
+        }
+        // Batches before the failed one stay consumed
+        Err(e) => respond_partial(summary, e.to_string(), "consume note"),
+    }
+}
+
+// ============================================================================
+// NOTE FILE ENDPOINTS (see note_files.rs)
+// ============================================================================
+
+/// Exports a note as a Miden note file: the raw file with `encoding=file`
+/// (the default), JSON with the hex or base64 text otherwise.
+async fn export_note(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    axum::extract::Path(note_id): axum::extract::Path<String>,
+    axum::extract::Query(query): axum::extract::Query<NoteExportQuery>,
+) -> Response {
+    info!("Received note export request: {}", note_id);
+
+    let op = ExportNote {
+        note_id: note_id.clone(),
+        query,
+        api_key: api_key_header(&headers),
+    };
+    match call(&state, op).await {
+        Ok(exported) if exported.encoding == NoteEncoding::File => (
+            [
+                (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}\"", exported.filename()),
+                ),
+            ],
+            exported.data,
+        )
+            .into_response(),
+        Ok(exported) => {
+            let note = serde_json::json!({
+                "note_id": exported.note_id,
+                "type": exported.export_type,
+                "encoding": exported.encoding,
+                "data": String::from_utf8_lossy(&exported.data)
+            });
+            respond(Ok(note), "export note").into_response()
+        }
+        Err(e) => respond::<()>(Err(e), &format!("export note {}", note_id)).into_response(),
+    }
+}
+
+/// Imports a note file: the raw file, or `{"encoding", "data"}` when sent as
+/// JSON.
+async fn import_note(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.starts_with("application/json"))
+        .unwrap_or(false);
+
+    let decoded = if is_json {
+        serde_json::from_slice::<NoteImportInput>(&body)
+            .map_err(anyhow::Error::from)
+            .and_then(|input| input.encoding.decode(input.data.as_bytes()))
+    } else {
+        NoteEncoding::File.decode(&body)
+    };
+    let note_file = match decoded {
+        Ok(note_file) => note_file,
+        Err(e) => return failure(StatusCode::BAD_REQUEST, e),
+    };
+    let op = ImportNote {
+        note_file,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "import note")
+}
+
+// ============================================================================
+// NOTE QUARANTINE ENDPOINTS
+// ============================================================================
+
+/// Lists notes held back as spam.
+async fn list_quarantined_notes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    axum::extract::Query(query): axum::extract::Query<QuarantineQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = ListQuarantinedNotes {
+        query,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "list quarantined notes")
+}
+
+/// Checks a transfer note against the sales contract it claims to carry out.
+async fn verify_contract_anchor(
+    State(state): State<AppState>,
+    axum::extract::Path(note_id): axum::extract::Path<String>,
+    axum::extract::Query(query): axum::extract::Query<AnchorQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received contract anchor verification: {}", note_id);
+    let op = VerifyContractAnchor { note_id, query };
+    respond(call(&state, op).await, "verify contract anchor")
+}
+
+/// Lets a quarantined note through to listings and sweeps.
+async fn release_quarantined_note(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    axum::extract::Path(note_id): axum::extract::Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received release request for quarantined note: {}", note_id);
+    let op = ReleaseQuarantinedNote {
+        note_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "release quarantined note")
+}
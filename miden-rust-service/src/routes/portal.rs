@@ -0,0 +1,125 @@
+// src/routes/portal.rs
+//
+// Customer portal endpoints (portal.rs): read-only views of one account,
+// authenticated by X-Portal-Token instead of API keys.
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use tracing::info;
+
+use miden_rust_service::portal::{
+    GetPortalAccess, PaymentQuery, PortalBalance, PortalEscrows, PortalLeases, PortalNotes,
+    PortalPayments,
+};
+
+use super::{call, respond};
+use crate::AppState;
+
+const PORTAL_TOKEN_HEADER: &str = "x-portal-token";
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/portal/me", get(get_portal_access))
+        .route("/portal/balance", get(get_portal_balance))
+        .route("/portal/notes", get(list_portal_notes))
+        .route("/portal/escrows", get(list_portal_escrows))
+        .route("/portal/leases", get(list_portal_leases))
+        .route("/portal/payments", get(list_portal_payments))
+        .route_layer(middleware::from_fn(require_portal_token))
+}
+
+/// Portal token presented in the `X-Portal-Token` header, if any.
+fn portal_token_header(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(PORTAL_TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Answers 401 to portal requests without a token before they reach the
+/// client queue; the token itself is checked there.
+async fn require_portal_token(req: Request, next: Next) -> Response {
+    if portal_token_header(req.headers()).is_some() {
+        return next.run(req).await;
+    }
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(serde_json::json!({
+            "success": false,
+            "data": null,
+            "error": "Unauthorized: X-Portal-Token header is required"
+        })),
+    )
+        .into_response()
+}
+
+async fn get_portal_access(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = GetPortalAccess {
+        token: portal_token_header(&headers),
+    };
+    respond(call(&state, op).await, "get portal access")
+}
+
+async fn get_portal_balance(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received portal balance request");
+    let op = PortalBalance {
+        token: portal_token_header(&headers),
+    };
+    respond(call(&state, op).await, "get portal balance")
+}
+
+async fn list_portal_notes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received portal notes request");
+    let op = PortalNotes {
+        token: portal_token_header(&headers),
+    };
+    respond(call(&state, op).await, "list portal notes")
+}
+
+async fn list_portal_escrows(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = PortalEscrows {
+        token: portal_token_header(&headers),
+    };
+    respond(call(&state, op).await, "list portal escrows")
+}
+
+async fn list_portal_leases(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = PortalLeases {
+        token: portal_token_header(&headers),
+    };
+    respond(call(&state, op).await, "list portal leases")
+}
+
+async fn list_portal_payments(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    axum::extract::Query(query): axum::extract::Query<PaymentQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = PortalPayments {
+        token: portal_token_header(&headers),
+        query,
+    };
+    respond(call(&state, op).await, "list portal payments")
+}
@@ -0,0 +1,485 @@
+// src/routes/proofs.rs
+//
+// ZK proof endpoints (demo): accreditation, jurisdiction, ownership and
+// identity attribute proofs, the proof cache and the program registry; and
+// document notarizations (notary.rs).
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use tracing::info;
+
+use miden_rust_service::{
+    identity::{AttributeClaim, IdentityCredential},
+    notary::{ListNotarizations, NotarizationInput, Notarize, VerifyNotarization},
+    validation::{self, Validate, ValidationErrors},
+    GenerateAccreditationProof, GenerateIdentityProof, GenerateJurisdictionProof,
+    GenerateOwnershipProof, GetProofCacheStats, InvalidateProofCache, ListZkPrograms,
+    VerifyAccreditationProof, VerifyIdentityProof, VerifyJurisdictionProof, VerifyOwnershipProof,
+};
+
+use super::{call, respond};
+use crate::{api_key_header, AppState, ValidJson};
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new()
+        // Accreditation
+        .route(
+            "/generate-accreditation-proof",
+            post(generate_accreditation_proof),
+        )
+        .route(
+            "/verify-accreditation-proof",
+            post(verify_accreditation_proof),
+        )
+        // Jurisdiction
+        .route(
+            "/generate-jurisdiction-proof",
+            post(generate_jurisdiction_proof),
+        )
+        .route(
+            "/verify-jurisdiction-proof",
+            post(verify_jurisdiction_proof),
+        )
+        // Ownership
+        .route("/generate-ownership-proof", post(generate_ownership_proof))
+        .route("/verify-ownership-proof", post(verify_ownership_proof))
+        // Identity attributes
+        .route("/generate-identity-proof", post(generate_identity_proof))
+        .route("/verify-identity-proof", post(verify_identity_proof))
+        // Proof cache
+        .route("/proof-cache/stats", get(get_proof_cache_stats))
+        .route("/proof-cache/invalidate", post(invalidate_proof_cache))
+        // ZK program registry
+        .route("/zk/programs", get(list_zk_programs))
+        // Notarization
+        .route("/notarizations", get(list_notarizations).post(notarize))
+        .route(
+            "/notarizations/:document_hash/verify",
+            get(verify_notarization),
+        )
+}
+
+#[derive(Debug, Deserialize)]
+struct GenerateAccreditationProofRequest {
+    net_worth: u64,
+    /// Selects the server-side threshold rule; omitted means "any"
+    #[serde(default)]
+    jurisdiction: Option<String>,
+    #[serde(default)]
+    listing_class: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerifyAccreditationProofRequest {
+    proof: String,
+    program_hash: String,
+    public_inputs: Vec<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GenerateJurisdictionProofRequest {
+    country_code: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerifyJurisdictionProofRequest {
+    proof: String,
+    program_hash: String,
+    public_inputs: Vec<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GenerateOwnershipProofRequest {
+    property_id: String,
+    document_hash: String,
+    /// Appraisal/inspection reports on the property to cite in the proof
+    #[serde(default)]
+    report_ids: Vec<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerifyOwnershipProofRequest {
+    proof: String,
+    program_hash: String,
+    public_inputs: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GenerateIdentityProofRequest {
+    credential: IdentityCredential,
+    #[serde(flatten)]
+    claim: AttributeClaim,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerifyIdentityProofRequest {
+    proof: String,
+    program_hash: String,
+    public_inputs: Vec<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InvalidateProofCacheRequest {
+    program: Option<String>,
+}
+
+/// Checks shared by all proof verification requests.
+fn validate_proof_fields(errors: &mut ValidationErrors, proof: &str, program_hash: &str) {
+    if proof.trim().is_empty() {
+        errors.add("proof", "must not be empty");
+    }
+    errors.check("program_hash", validation::hex_string(program_hash, false));
+}
+
+fn validate_public_inputs(errors: &mut ValidationErrors, public_inputs: &[u64]) {
+    if public_inputs.is_empty() {
+        errors.add("public_inputs", "must not be empty");
+    }
+}
+
+impl Validate for GenerateAccreditationProofRequest {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("net_worth", validation::positive(self.net_worth));
+        if let Some(listing_class) = &self.listing_class {
+            errors.check("listing_class", validation::non_empty(listing_class));
+        }
+    }
+}
+
+impl Validate for VerifyAccreditationProofRequest {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        validate_proof_fields(errors, &self.proof, &self.program_hash);
+        validate_public_inputs(errors, &self.public_inputs);
+    }
+}
+
+impl Validate for GenerateJurisdictionProofRequest {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("country_code", validation::country_code(&self.country_code));
+    }
+}
+
+impl Validate for VerifyJurisdictionProofRequest {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        validate_proof_fields(errors, &self.proof, &self.program_hash);
+        validate_public_inputs(errors, &self.public_inputs);
+    }
+}
+
+impl Validate for GenerateOwnershipProofRequest {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("property_id", validation::property_id(&self.property_id));
+        errors.check(
+            "document_hash",
+            validation::hex_string(&self.document_hash, false),
+        );
+    }
+}
+
+impl Validate for VerifyOwnershipProofRequest {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        validate_proof_fields(errors, &self.proof, &self.program_hash);
+    }
+}
+
+impl Validate for GenerateIdentityProofRequest {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        match &self.claim {
+            AttributeClaim::MinimumAge { years } => {
+                if *years == 0 || *years > 150 {
+                    errors.add("years", "must be between 1 and 150");
+                }
+            }
+            AttributeClaim::ResidencyNot { country } => {
+                errors.check("country", validation::country_code(country));
+            }
+        }
+    }
+}
+
+impl Validate for VerifyIdentityProofRequest {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        validate_proof_fields(errors, &self.proof, &self.program_hash);
+        validate_public_inputs(errors, &self.public_inputs);
+    }
+}
+
+impl Validate for InvalidateProofCacheRequest {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if let Some(program) = &self.program {
+            errors.check("program", validation::non_empty(program));
+        }
+    }
+}
+
+// ============================================================================
+// ZK PROOF ENDPOINTS - ACCREDITATION
+// ============================================================================
+
+async fn generate_accreditation_proof(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<GenerateAccreditationProofRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received generate accreditation proof request");
+    info!("Net worth: {} (hidden in proof)", payload.net_worth);
+    info!(
+        "Rule selector: jurisdiction={:?}, listing_class={:?}",
+        payload.jurisdiction, payload.listing_class
+    );
+
+    let op = GenerateAccreditationProof {
+        net_worth: payload.net_worth,
+        jurisdiction: payload.jurisdiction,
+        listing_class: payload.listing_class,
+    };
+
+    let result = call(&state, op).await.map(|proof_data| {
+        info!("ZK proof generated successfully");
+        proof_data
+    });
+    respond(result, "generate proof")
+}
+
+async fn verify_accreditation_proof(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<VerifyAccreditationProofRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received verify accreditation proof request");
+    info!("Verifying without seeing private data");
+
+    let op = VerifyAccreditationProof {
+        proof: payload.proof,
+        program_hash: payload.program_hash,
+        public_inputs: payload.public_inputs,
+    };
+
+    let result = call(&state, op).await.map(|verification_result| {
+        info!("Proof verification complete");
+        verification_result
+    });
+    respond(result, "verify proof")
+}
+
+// ============================================================================
+// ZK PROOF ENDPOINTS - JURISDICTION
+// ============================================================================
+
+async fn generate_jurisdiction_proof(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<GenerateJurisdictionProofRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received generate jurisdiction proof request");
+    info!("Country: {} (hidden in proof)", payload.country_code);
+
+    let op = GenerateJurisdictionProof {
+        country_code: payload.country_code,
+    };
+
+    let result = call(&state, op).await.map(|proof_data| {
+        info!("Jurisdiction ZK proof generated successfully");
+        proof_data
+    });
+    respond(result, "generate jurisdiction proof")
+}
+
+async fn verify_jurisdiction_proof(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<VerifyJurisdictionProofRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received verify jurisdiction proof request");
+    info!("Verifying without seeing user's country");
+
+    let op = VerifyJurisdictionProof {
+        proof: payload.proof,
+        program_hash: payload.program_hash,
+        public_inputs: payload.public_inputs,
+    };
+
+    let result = call(&state, op).await.map(|verification_result| {
+        info!("Jurisdiction proof verification complete");
+        verification_result
+    });
+    respond(result, "verify jurisdiction proof")
+}
+
+// ============================================================================
+// ZK PROOF ENDPOINTS - OWNERSHIP
+// ============================================================================
+
+async fn generate_ownership_proof(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<GenerateOwnershipProofRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received generate ownership proof request");
+    info!("Property: {}", payload.property_id);
+
+    // Safe logging: truncate document hash preview for readability
+    info!(
+        "Document hash preview: {}...",
+        &payload.document_hash[..20.min(payload.document_hash.len())]
+    );
+
+    let op = GenerateOwnershipProof {
+        property_id: payload.property_id,
+        document_hash: payload.document_hash,
+        report_ids: payload.report_ids,
+    };
+
+    let result = call(&state, op).await.map(|proof_data| {
+        info!("Ownership ZK proof generated successfully");
+        proof_data
+    });
+    respond(result, "generate ownership proof")
+}
+
+async fn verify_ownership_proof(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<VerifyOwnershipProofRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received verify ownership proof request");
+    info!("Verifying without seeing user's document hash");
+
+    let op = VerifyOwnershipProof {
+        proof: payload.proof,
+        program_hash: payload.program_hash,
+        public_inputs: payload.public_inputs,
+    };
+
+    let result = call(&state, op).await.map(|verification_result| {
+        info!("Ownership proof verification complete");
+        verification_result
+    });
+    respond(result, "verify ownership proof")
+}
+
+// ============================================================================
+// ZK PROOF ENDPOINTS - IDENTITY ATTRIBUTES
+// ============================================================================
+
+async fn generate_identity_proof(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<GenerateIdentityProofRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received generate identity proof request");
+    info!(
+        "Provider: {}, claim: {:?}",
+        payload.credential.provider_id, payload.claim
+    );
+
+    let op = GenerateIdentityProof {
+        credential: payload.credential,
+        claim: payload.claim,
+    };
+
+    let result = call(&state, op).await.map(|proof_data| {
+        info!("Identity ZK proof generated successfully");
+        proof_data
+    });
+    respond(result, "generate identity proof")
+}
+
+async fn verify_identity_proof(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<VerifyIdentityProofRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received verify identity proof request");
+
+    let op = VerifyIdentityProof {
+        proof: payload.proof,
+        program_hash: payload.program_hash,
+        public_inputs: payload.public_inputs,
+    };
+
+    let result = call(&state, op).await.map(|verification_result| {
+        info!("Identity proof verification complete");
+        verification_result
+    });
+    respond(result, "verify identity proof")
+}
+
+// ============================================================================
+// PROOF CACHE ENDPOINTS
+// ============================================================================
+
+async fn get_proof_cache_stats(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received get proof cache stats request");
+    let op = GetProofCacheStats {};
+    respond(call(&state, op).await, "get proof cache stats")
+}
+
+/// Drops cached proofs of one program, or all of them. Admin only.
+async fn invalidate_proof_cache(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<InvalidateProofCacheRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received invalidate proof cache request: {:?}", payload);
+    let op = InvalidateProofCache {
+        program: payload.program,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "invalidate proof cache")
+}
+
+// ============================================================================
+// ZK PROGRAM REGISTRY ENDPOINTS
+// ============================================================================
+
+async fn list_zk_programs(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list ZK programs request");
+    let op = ListZkPrograms {};
+    respond(call(&state, op).await, "list ZK programs")
+}
+
+// ============================================================================
+// NOTARY ENDPOINTS
+// ============================================================================
+
+/// Notarization listing filter
+#[derive(Debug, Deserialize)]
+struct NotarizationQuery {
+    property_id: Option<String>,
+}
+
+async fn notarize(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<NotarizationInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received notarization request: {}", payload.document_hash);
+    let op = Notarize {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "notarize document")
+}
+
+async fn verify_notarization(
+    State(state): State<AppState>,
+    Path(document_hash): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received notarization check: {}", document_hash);
+    let op = VerifyNotarization { document_hash };
+    respond(call(&state, op).await, "verify notarization")
+}
+
+async fn list_notarizations(
+    State(state): State<AppState>,
+    Query(query): Query<NotarizationQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received list notarizations request: {:?}",
+        query.property_id
+    );
+    let op = ListNotarizations {
+        property_id: query.property_id,
+    };
+    respond(call(&state, op).await, "list notarizations")
+}
@@ -0,0 +1,611 @@
+// src/routes/properties.rs
+//
+// Property endpoints: minting, transfers and the property listing, token
+// retirement (property_retirement.rs) and provenance (property_recovery.rs),
+// locations (geo.rs), listing photos (media.rs), insurance binders
+// (attachments.rs) and appraisers and inspectors (professionals.rs).
+
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use miden_rust_service::{
+    attachments::{AttachInsuranceBinder, BinderInput, ListInsuranceBinders},
+    config::ServiceConfig,
+    contract_anchors::ContractAnchor,
+    currency::PriceInput,
+    geo::{
+        LocationInput, NearbyQuery, PropertiesNearby, PropertiesWithin, SetPropertyLocation,
+        WithinQuery,
+    },
+    listing::Listing,
+    media::{
+        process_photo, read_photos, AddPropertyMedia, GetMedia, ListPropertyMedia, MediaRejected,
+        MediaUploadSlots, RemovePropertyMedia, MAX_FILES_PER_UPLOAD, THUMBNAIL_CONTENT_TYPE,
+    },
+    mint_jobs::MintItemInput,
+    offline_queue,
+    professionals::{
+        AttachProfessionalReport, GetProfessional, ListProfessionalReports, ListProfessionals,
+        ProfessionalInput, ProfessionalRole, RegisterProfessional, ReportInput, RevokeProfessional,
+        RevokeProfessionalInput,
+    },
+    property_recovery::GetPropertyProvenance,
+    property_retirement::{PropertyRetireInput, RetireProperty},
+    validation::{self, Validate, ValidationErrors},
+    MintProperty, TransferProperty,
+};
+
+use super::{
+    call, collect_listing, failure, ndjson_listing, respond, wants_ndjson, CLIENT_DROPPED,
+    CLIENT_UNAVAILABLE,
+};
+use crate::{api_key_header, AppState, ValidJson, ValidQuery};
+
+/// `config` sizes the body limit of photo uploads (see media.rs).
+pub(crate) fn router(config: &ServiceConfig) -> Router<AppState> {
+    Router::new()
+        .route("/mint-property", post(mint_property))
+        .route("/transfer-property", post(transfer_property))
+        .route("/properties", get(list_properties))
+        .route("/properties/:property_id/retire", post(retire_property))
+        .route(
+            "/properties/:property_id/provenance",
+            get(property_provenance),
+        )
+        .route(
+            "/properties/:property_id/location",
+            put(set_property_location),
+        )
+        .route("/properties/nearby", get(properties_nearby))
+        .route("/properties/within", get(properties_within))
+        .route(
+            "/properties/:property_id/media",
+            get(list_property_media)
+                .post(upload_property_media)
+                .layer(DefaultBodyLimit::max(config.media.upload_body_limit())),
+        )
+        .route(
+            "/properties/:property_id/media/:media_id",
+            delete(delete_property_media),
+        )
+        .route("/media/:media_id", get(get_media))
+        .route("/media/:media_id/thumbnail", get(get_media_thumbnail))
+        .route(
+            "/properties/:property_id/insurance-binders",
+            get(list_insurance_binders).post(attach_insurance_binder),
+        )
+        .route(
+            "/professionals",
+            get(list_professionals).post(register_professional),
+        )
+        .route("/professionals/:professional_id", get(get_professional))
+        .route(
+            "/professionals/:professional_id/revoke",
+            post(revoke_professional),
+        )
+        .route(
+            "/properties/:property_id/reports",
+            get(list_professional_reports).post(attach_professional_report),
+        )
+}
+
+/// Burns the property's token and marks the property retired.
+async fn retire_property(
+    State(state): State<AppState>,
+    Path(property_id): Path<String>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<PropertyRetireInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received retirement of {}: {:?}", property_id, payload);
+    let op = RetireProperty {
+        property_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "retire property")
+}
+
+/// Re-issues, burns and retirements of the property's tokens.
+async fn property_provenance(
+    State(state): State<AppState>,
+    Path(property_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received provenance request for {}", property_id);
+    respond(
+        call(&state, GetPropertyProvenance { property_id }).await,
+        "get property provenance",
+    )
+}
+
+// ============================================================================
+// MINT AND TRANSFER ENDPOINTS
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+struct MintPropertyRequest {
+    property_id: String,
+    owner_account_id: String,
+    ipfs_cid: String,
+    property_type: u8,
+    /// Minor units, bare or with a currency (currency.rs)
+    price: PriceInput,
+}
+
+#[derive(Debug, Serialize)]
+struct MintedProperty {
+    transaction_id: String,
+    note_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TransferPropertyRequest {
+    property_id: String,
+    to_account_id: String,
+    /// SHA-256 of the purchase agreement to anchor the transfer note to
+    #[serde(default)]
+    contract_hash: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct TransferredProperty {
+    transaction_id: String,
+    /// Present when the transfer is anchored to a contract (contract_anchors.rs)
+    contract_anchor: Option<ContractAnchor>,
+}
+
+impl Validate for MintPropertyRequest {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("property_id", validation::property_id(&self.property_id));
+        errors.check(
+            "owner_account_id",
+            validation::account_selector(&self.owner_account_id, &["alice", "bob"]),
+        );
+        errors.check("ipfs_cid", validation::non_empty(&self.ipfs_cid));
+        errors.check(
+            "property_type",
+            validation::property_type(self.property_type),
+        );
+        errors.check("price", validation::price(&self.price));
+    }
+}
+
+impl Validate for TransferPropertyRequest {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("property_id", validation::property_id(&self.property_id));
+        errors.check(
+            "to_account_id",
+            validation::account_selector(&self.to_account_id, &["alice", "bob"]),
+        );
+        if let Some(contract_hash) = &self.contract_hash {
+            errors.check(
+                "contract_hash",
+                validation::hex_string(contract_hash, false),
+            );
+        }
+    }
+}
+
+async fn mint_property(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<MintPropertyRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received mint property request: {:?}", payload);
+
+    let result = match &state.mint_coalescer {
+        // Offline-mode requests are queued one by one (offline_queue.rs)
+        Some(coalescer) if !offline_queue::opted_in() => {
+            let sent = coalescer
+                .submit(MintItemInput {
+                    property_id: payload.property_id,
+                    owner_account_id: payload.owner_account_id,
+                    ipfs_cid: payload.ipfs_cid,
+                    property_type: payload.property_type,
+                    price: payload.price,
+                })
+                .await;
+            match sent {
+                Ok(rx) => rx.await.unwrap_or_else(|_| Err(CLIENT_DROPPED.to_string())),
+                Err(e) => {
+                    error!("Failed to send mint to the coalescer: {}", e);
+                    Err(CLIENT_UNAVAILABLE.to_string())
+                }
+            }
+        }
+        _ => {
+            let op = MintProperty {
+                property_id: payload.property_id,
+                owner_account_id: payload.owner_account_id,
+                ipfs_cid: payload.ipfs_cid,
+                property_type: payload.property_type,
+                price: payload.price,
+            };
+            call(&state, op).await
+        }
+    };
+    let result = result.map(|(tx_id, note_id)| {
+        info!("Property minted: tx={}, note={}", tx_id, note_id);
+        MintedProperty {
+            transaction_id: tx_id,
+            note_id,
+        }
+    });
+    respond(result, "mint property")
+}
+
+async fn transfer_property(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<TransferPropertyRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received transfer property request: {:?}", payload);
+
+    let op = TransferProperty {
+        property_id: payload.property_id,
+        to_account_id: payload.to_account_id,
+        contract_hash: payload.contract_hash,
+    };
+    let result = call(&state, op).await.map(|(tx_id, contract_anchor)| {
+        info!("Property transferred: tx={}", tx_id);
+        TransferredProperty {
+            transaction_id: tx_id,
+            contract_anchor,
+        }
+    });
+    respond(result, "transfer property")
+}
+
+async fn list_properties(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    info!("Received list properties request");
+
+    if wants_ndjson(&headers) {
+        return ndjson_listing(state.client_tx, Listing::Properties);
+    }
+
+    respond(
+        collect_listing(&state, Listing::Properties).await,
+        "list properties",
+    )
+    .into_response()
+}
+
+// ============================================================================
+// LOCATION ENDPOINTS (see geo.rs)
+// ============================================================================
+
+/// Sets the point and parcel outline of a property.
+async fn set_property_location(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(property_id): Path<String>,
+    ValidJson(payload): ValidJson<LocationInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received property location request: {}", property_id);
+    let op = SetPropertyLocation {
+        property_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "set property location")
+}
+
+/// Properties within `radius` meters of a point, nearest first. The body is a
+/// GeoJSON FeatureCollection.
+async fn properties_nearby(
+    State(state): State<AppState>,
+    ValidQuery(query): ValidQuery<NearbyQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = PropertiesNearby { query };
+    match call(&state, op).await {
+        Ok(collection) => (StatusCode::OK, Json(serde_json::json!(collection))),
+        Err(e) => respond::<()>(Err(e), "find nearby properties"),
+    }
+}
+
+/// Properties located in a bounding box. The body is a GeoJSON
+/// FeatureCollection.
+async fn properties_within(
+    State(state): State<AppState>,
+    ValidQuery(query): ValidQuery<WithinQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = PropertiesWithin { query };
+    match call(&state, op).await {
+        Ok(collection) => (StatusCode::OK, Json(serde_json::json!(collection))),
+        Err(e) => respond::<()>(Err(e), "find properties within bounds"),
+    }
+}
+
+// ============================================================================
+// MEDIA ENDPOINTS (see media.rs)
+// ============================================================================
+
+/// Status and body of an upload refused before anything was stored.
+fn media_rejection(rejected: &MediaRejected) -> (StatusCode, Json<serde_json::Value>) {
+    let status = match rejected {
+        MediaRejected::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+        MediaRejected::UnsupportedType { .. } | MediaRejected::TypeMismatch { .. } => {
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        }
+        MediaRejected::NoRoom(_) => StatusCode::CONFLICT,
+        MediaRejected::Malformed(_) => StatusCode::BAD_REQUEST,
+        _ => StatusCode::UNPROCESSABLE_ENTITY,
+    };
+    failure(status, rejected)
+}
+
+/// Uploads listing photos: multipart/form-data with one `file` part per photo.
+async fn upload_property_media(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(property_id): Path<String>,
+    mut multipart: Multipart,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received media upload request: {}", property_id);
+    let api_key = api_key_header(&headers);
+
+    // Owner and room first, so the body is only read for the owner
+    let op = MediaUploadSlots {
+        property_id: property_id.clone(),
+        api_key: api_key.clone(),
+    };
+    let slots = match call(&state, op).await {
+        Ok(slots) => slots,
+        Err(e) => return respond::<()>(Err(e), "check media upload"),
+    };
+
+    let photos =
+        match read_photos(&mut multipart, state.media.max_bytes, MAX_FILES_PER_UPLOAD).await {
+            Ok(photos) if photos.len() > slots => {
+                return media_rejection(&MediaRejected::NoRoom(slots))
+            }
+            Ok(photos) => photos,
+            Err(rejected) => return media_rejection(&rejected),
+        };
+
+    let thumbnail_px = state.media.thumbnail_px;
+    let processed = tokio::task::spawn_blocking(move || {
+        photos
+            .into_iter()
+            .enumerate()
+            .map(|(index, photo)| process_photo(photo, index, thumbnail_px))
+            .collect::<Result<Vec<_>, _>>()
+    })
+    .await;
+    let processed = match processed {
+        Ok(Ok(processed)) => processed,
+        Ok(Err(rejected)) => return media_rejection(&rejected),
+        Err(e) => {
+            error!("Photo processing task failed: {}", e);
+            return failure(StatusCode::INTERNAL_SERVER_ERROR, "Photo processing failed");
+        }
+    };
+
+    let mut uploads = Vec::with_capacity(processed.len());
+    for photo in &processed {
+        match state.media.backend.put(photo).await {
+            Ok(upload) => uploads.push(upload),
+            Err(e) => {
+                error!("Failed to store photo for {}: {}", property_id, e);
+                return failure(
+                    StatusCode::BAD_GATEWAY,
+                    format!("Failed to store photo: {}", e),
+                );
+            }
+        }
+    }
+
+    let op = AddPropertyMedia {
+        property_id,
+        uploads,
+        api_key,
+    };
+    respond(call(&state, op).await, "add property media")
+}
+
+async fn list_property_media(
+    State(state): State<AppState>,
+    Path(property_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received property media request: {}", property_id);
+    let op = ListPropertyMedia { property_id };
+    respond(call(&state, op).await, "list property media")
+}
+
+/// Removes a photo from a property; content no other photo uses is deleted
+/// from the media store.
+async fn delete_property_media(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((property_id, media_id)): Path<(String, u64)>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received media removal request: {} {}",
+        property_id, media_id
+    );
+    let op = RemovePropertyMedia {
+        property_id,
+        media_id,
+        api_key: api_key_header(&headers),
+    };
+    let removal = match call(&state, op).await {
+        Ok(removal) => removal,
+        Err(e) => return respond::<()>(Err(e), "remove property media"),
+    };
+    for locator in &removal.orphaned {
+        if let Err(e) = state.media.backend.delete(locator).await {
+            tracing::warn!("Failed to delete media content {}: {}", locator, e);
+        }
+    }
+    respond(Ok(removal.item), "remove property media")
+}
+
+/// Content of a photo.
+async fn get_media(State(state): State<AppState>, Path(media_id): Path<u64>) -> Response {
+    serve_media(state, media_id, false).await
+}
+
+/// JPEG thumbnail of a photo.
+async fn get_media_thumbnail(State(state): State<AppState>, Path(media_id): Path<u64>) -> Response {
+    serve_media(state, media_id, true).await
+}
+
+/// Media IDs are never reused, so their content may be cached for good.
+async fn serve_media(state: AppState, media_id: u64, thumbnail: bool) -> Response {
+    let op = GetMedia { media_id };
+    let item = match call(&state, op).await {
+        Ok(item) => item,
+        Err(e) if e == CLIENT_UNAVAILABLE || e == CLIENT_DROPPED => {
+            return respond::<()>(Err(e), "get media").into_response()
+        }
+        Err(e) => return failure(StatusCode::NOT_FOUND, e).into_response(),
+    };
+
+    let (locator, content_type) = if thumbnail {
+        (&item.thumbnail_locator, THUMBNAIL_CONTENT_TYPE.to_string())
+    } else {
+        (&item.locator, item.content_type.clone())
+    };
+    match state.media.backend.get(locator).await {
+        Ok(content) => (
+            [
+                (header::CONTENT_TYPE, content_type),
+                (
+                    header::CACHE_CONTROL,
+                    "public, max-age=31536000, immutable".to_string(),
+                ),
+                (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+            ],
+            content,
+        )
+            .into_response(),
+        Err(e) => {
+            error!("Failed to read media {}: {}", media_id, e);
+            failure(
+                StatusCode::BAD_GATEWAY,
+                format!("Failed to read media {}", media_id),
+            )
+            .into_response()
+        }
+    }
+}
+
+// ============================================================================
+// INSURANCE BINDER ENDPOINTS (see attachments.rs)
+// ============================================================================
+
+async fn attach_insurance_binder(
+    State(state): State<AppState>,
+    Path(property_id): Path<String>,
+    ValidJson(payload): ValidJson<BinderInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received insurance binder for {}: insurer {}",
+        property_id, payload.insurer_id
+    );
+    let op = AttachInsuranceBinder {
+        property_id,
+        input: payload,
+    };
+    respond(call(&state, op).await, "attach insurance binder")
+}
+
+async fn list_insurance_binders(
+    State(state): State<AppState>,
+    Path(property_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received insurance binders request: {}", property_id);
+    let op = ListInsuranceBinders { property_id };
+    respond(call(&state, op).await, "list insurance binders")
+}
+
+// ============================================================================
+// APPRAISER AND INSPECTOR ENDPOINTS (see professionals.rs)
+// ============================================================================
+
+/// Professional listing filter
+#[derive(Debug, Deserialize)]
+struct ProfessionalQuery {
+    role: Option<ProfessionalRole>,
+}
+
+async fn register_professional(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<ProfessionalInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received register professional request: {} ({:?})",
+        payload.name, payload.role
+    );
+    let op = RegisterProfessional {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "register professional")
+}
+
+async fn revoke_professional(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(professional_id): Path<u64>,
+    ValidJson(payload): ValidJson<RevokeProfessionalInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received revoke professional {} request: {}",
+        professional_id, payload.reason
+    );
+    let op = RevokeProfessional {
+        professional_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "revoke professional")
+}
+
+async fn list_professionals(
+    State(state): State<AppState>,
+    Query(query): Query<ProfessionalQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list professionals request: {:?}", query.role);
+    let op = ListProfessionals { role: query.role };
+    respond(call(&state, op).await, "list professionals")
+}
+
+async fn get_professional(
+    State(state): State<AppState>,
+    Path(professional_id): Path<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received professional request: {}", professional_id);
+    let op = GetProfessional { professional_id };
+    respond(call(&state, op).await, "get professional")
+}
+
+async fn attach_professional_report(
+    State(state): State<AppState>,
+    Path(property_id): Path<String>,
+    ValidJson(payload): ValidJson<ReportInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received report for {}: professional {}",
+        property_id, payload.professional_id
+    );
+    let op = AttachProfessionalReport {
+        property_id,
+        input: payload,
+    };
+    respond(call(&state, op).await, "attach report")
+}
+
+async fn list_professional_reports(
+    State(state): State<AppState>,
+    Path(property_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received reports request: {}", property_id);
+    let op = ListProfessionalReports { property_id };
+    respond(call(&state, op).await, "list reports")
+}
@@ -0,0 +1,263 @@
+// src/routes/sales.rs
+//
+// Sale and rental endpoints: installment purchases (installments.rs), leases
+// (leases.rs) and auctions (auctions.rs), with the auction event stream.
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Json, Router,
+};
+use futures_util::StreamExt;
+use tokio::sync::broadcast;
+use tracing::{error, info};
+
+use miden_rust_service::{
+    auctions::{AuctionInput, BidInput, CreateAuction, GetAuction, ListAuctions, PlaceBid},
+    installments::{
+        CreateInstallmentPlan, GetInstallmentPlan, InstallmentPlanInput, ListInstallmentPlans,
+        PayInstallment,
+    },
+    leases::{
+        CreateLease, EndLease, GetLease, LeaseEndInput, LeaseInput, ListLeases, PayLeaseDeposit,
+    },
+};
+
+use super::{call, failure, respond, CLIENT_DROPPED, CLIENT_UNAVAILABLE};
+use crate::{api_key_header, AppState, ValidJson};
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/installments",
+            get(list_installment_plans).post(create_installment_plan),
+        )
+        .route("/installments/:plan_id", get(get_installment_plan))
+        .route("/installments/:plan_id/pay", post(pay_installment))
+        .route("/leases", get(list_leases).post(create_lease))
+        .route("/leases/:lease_id", get(get_lease))
+        .route("/leases/:lease_id/deposit", post(pay_lease_deposit))
+        .route("/leases/:lease_id/end", post(end_lease))
+        .route("/auctions", get(list_auctions).post(create_auction))
+        .route("/auctions/:auction_id", get(get_auction))
+        .route("/auctions/:auction_id/bids", post(place_bid))
+        .route("/auctions/:auction_id/events", get(auction_events))
+}
+
+// ============================================================================
+// INSTALLMENT PURCHASE ENDPOINTS (see installments.rs)
+// ============================================================================
+
+async fn create_installment_plan(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<InstallmentPlanInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received create installment plan request: {:?}", payload);
+    let op = CreateInstallmentPlan {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "create installment plan")
+}
+
+async fn pay_installment(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(plan_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received installment payment request: {}", plan_id);
+    let op = PayInstallment {
+        plan_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "pay installment")
+}
+
+async fn get_installment_plan(
+    State(state): State<AppState>,
+    Path(plan_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received installment plan request: {}", plan_id);
+    let op = GetInstallmentPlan { plan_id };
+    respond(call(&state, op).await, "get installment plan")
+}
+
+async fn list_installment_plans(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list installment plans request");
+    let op = ListInstallmentPlans {};
+    respond(call(&state, op).await, "list installment plans")
+}
+
+// ============================================================================
+// LEASE ENDPOINTS (see leases.rs)
+// ============================================================================
+
+async fn create_lease(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<LeaseInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received create lease request: {:?}", payload);
+    let op = CreateLease {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "create lease")
+}
+
+async fn pay_lease_deposit(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(lease_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received lease deposit request: {}", lease_id);
+    let op = PayLeaseDeposit {
+        lease_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "pay lease deposit")
+}
+
+async fn end_lease(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(lease_id): Path<String>,
+    ValidJson(payload): ValidJson<LeaseEndInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received end lease request: {} ({:?})", lease_id, payload);
+    let op = EndLease {
+        lease_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "end lease")
+}
+
+async fn get_lease(
+    State(state): State<AppState>,
+    Path(lease_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received lease request: {}", lease_id);
+    let op = GetLease { lease_id };
+    respond(call(&state, op).await, "get lease")
+}
+
+async fn list_leases(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list leases request");
+    let op = ListLeases {};
+    respond(call(&state, op).await, "list leases")
+}
+
+// ============================================================================
+// AUCTION ENDPOINTS (see auctions.rs)
+// ============================================================================
+
+async fn create_auction(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<AuctionInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received create auction request: {:?}", payload);
+    let op = CreateAuction {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "create auction")
+}
+
+async fn place_bid(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(auction_id): Path<String>,
+    ValidJson(payload): ValidJson<BidInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received bid on {}: {:?}", auction_id, payload);
+    let op = PlaceBid {
+        auction_id,
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "place bid")
+}
+
+async fn get_auction(
+    State(state): State<AppState>,
+    Path(auction_id): Path<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received auction request: {}", auction_id);
+    let op = GetAuction { auction_id };
+    respond(call(&state, op).await, "get auction")
+}
+
+async fn list_auctions(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received list auctions request");
+    let op = ListAuctions {};
+    respond(call(&state, op).await, "list auctions")
+}
+
+/// Streams an auction as server-sent events: a `snapshot` event with the
+/// current auction report, then one event per auction event (named after its
+/// kind, e.g. `bid_placed`) as the client task publishes them.
+async fn auction_events(State(state): State<AppState>, Path(auction_id): Path<String>) -> Response {
+    info!("Received auction event stream request: {}", auction_id);
+
+    // Subscribe before reading the snapshot so no event falls in between
+    let feed = state.auction_feed.subscribe();
+
+    let op = GetAuction {
+        auction_id: auction_id.clone(),
+    };
+    let snapshot = match call(&state, op).await {
+        Ok(report) => report,
+        Err(e) if e == CLIENT_UNAVAILABLE || e == CLIENT_DROPPED => {
+            return respond::<()>(Err(e), "stream auction events").into_response()
+        }
+        Err(e) => return failure(StatusCode::NOT_FOUND, e).into_response(),
+    };
+
+    let snapshot = Event::default()
+        .event("snapshot")
+        .json_data(&snapshot)
+        .map_err(|e| error!("Failed to encode auction snapshot: {}", e))
+        .ok();
+
+    let updates = futures_util::stream::unfold(feed, move |mut feed| {
+        let auction_id = auction_id.clone();
+        async move {
+            loop {
+                match feed.recv().await {
+                    Ok(event) if event.auction_id == auction_id => {
+                        match Event::default()
+                            .event(event.kind.as_str())
+                            .json_data(&event)
+                        {
+                            Ok(sse) => return Some((sse, feed)),
+                            Err(e) => error!("Failed to encode auction event: {}", e),
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        error!("Auction {} stream skipped {} event(s)", auction_id, missed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+
+    let stream = futures_util::stream::iter(snapshot)
+        .chain(updates)
+        .map(Ok::<_, std::convert::Infallible>);
+
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
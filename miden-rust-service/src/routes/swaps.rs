@@ -0,0 +1,106 @@
+// src/routes/swaps.rs
+//
+// Token swap endpoints (swaps.rs): quotes between the service token and the
+// settlement tokens, and their execution against the liquidity account.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use tracing::info;
+
+use miden_rust_service::swaps::{
+    ExecuteSwap, GetSwapQuote, ListSwapQuotes, QuoteSwap, SwapQuote, SwapQuoteInput,
+};
+
+use super::{call, respond};
+use crate::{api_key_header, AppState, ValidJson};
+
+/// Swap quote listing: the quotes of `account_id` (name or hex)
+#[derive(Debug, Deserialize)]
+struct SwapListQuery {
+    account_id: String,
+}
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/swaps", get(list_swap_quotes))
+        .route("/swaps/quotes", post(quote_swap))
+        .route("/swaps/quotes/:quote_id", get(get_swap_quote))
+        .route("/swaps/quotes/:quote_id/execute", post(execute_swap))
+}
+
+/// Quotes a swap between the service token and settlement tokens.
+async fn quote_swap(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<SwapQuoteInput>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "Received swap quote request: {} -> {:?}",
+        payload.from, payload.to
+    );
+    let op = QuoteSwap {
+        input: payload,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "quote swap")
+}
+
+/// Executes an open quote through paired notes with the liquidity account.
+/// The quote is read first for the account the swap changes.
+async fn execute_swap(
+    State(state): State<AppState>,
+    Path(quote_id): Path<u64>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("Received swap execution: quote {}", quote_id);
+    let api_key = api_key_header(&headers);
+    let quote = call(
+        &state,
+        GetSwapQuote {
+            quote_id,
+            api_key: api_key.clone(),
+        },
+    )
+    .await;
+    let quote = match quote {
+        Ok(quote) => quote,
+        Err(e) => return respond::<SwapQuote>(Err(e), "execute swap"),
+    };
+
+    let op = ExecuteSwap {
+        quote_id,
+        account_id: quote.account_id,
+        api_key,
+    };
+    respond(call(&state, op).await, "execute swap")
+}
+
+async fn get_swap_quote(
+    State(state): State<AppState>,
+    Path(quote_id): Path<u64>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = GetSwapQuote {
+        quote_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "get swap quote")
+}
+
+/// Swap quotes of an account, newest first.
+async fn list_swap_quotes(
+    State(state): State<AppState>,
+    Query(query): Query<SwapListQuery>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let op = ListSwapQuotes {
+        account_id: query.account_id,
+        api_key: api_key_header(&headers),
+    };
+    respond(call(&state, op).await, "list swap quotes")
+}
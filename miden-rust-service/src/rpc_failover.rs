@@ -0,0 +1,380 @@
+// src/rpc_failover.rs
+//
+// RPC endpoint failover
+//
+// MIDEN_RPC_URLS lists the node RPC endpoints in order of preference (the
+// first one replaces MIDEN_RPC_URL). The client talks to one of them at a time,
+// the active endpoint; the others are standbys:
+// - health checks: every RPC_HEALTH_CHECK_INTERVAL_SECS each endpoint is asked
+//   for the chain tip (a block header request)
+// - calls: every sync the client runs counts for the active endpoint
+// An endpoint failing RPC_FAILOVER_THRESHOLD times in a row is unhealthy; a
+// success makes it healthy again.
+//
+// Before each command the client task checks the preferred endpoint: the active
+// one while it is healthy, otherwise the first healthy endpoint in list order.
+// A more preferred endpoint that is healthy again takes over once it has
+// passed RPC_FAILOVER_THRESHOLD checks in a row, so a flapping primary does not
+// bounce the client back and forth. Switching rebuilds the gRPC client over
+// the same store and keystore; nothing else changes for the callers. A sync
+// that fails on an endpoint that has just become unhealthy is retried once on
+// the new one.
+//
+// At startup the client connects to the first endpoint answering a health
+// check. GET /admin/rpc reports every endpoint with its health, chain tip and
+// latency percentiles over the last LATENCY_WINDOW requests; like the queue
+// stats, the figures live in memory and are read without the client task.
+
+use anyhow::Result;
+use miden_client::rpc::{Endpoint, GrpcClient, NodeRpcClient};
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{bench::Percentiles, client, config::RpcEndpointConfig, MidenClientWrapper};
+
+/// Requests per endpoint the latency percentiles are computed over
+const LATENCY_WINDOW: usize = 200;
+
+pub fn endpoint(config: &RpcEndpointConfig) -> Endpoint {
+    Endpoint::new(config.protocol.clone(), config.host.clone(), config.port)
+}
+
+#[derive(Debug, Default)]
+struct EndpointState {
+    healthy: bool,
+    consecutive_failures: u32,
+    consecutive_successes: u32,
+    latency: VecDeque<Duration>,
+    requests: u64,
+    failures: u64,
+    chain_tip: Option<u32>,
+    last_error: Option<String>,
+    last_checked_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Failover {
+    pub from: String,
+    pub to: String,
+    pub reason: String,
+    pub at: i64,
+}
+
+#[derive(Debug)]
+struct PoolState {
+    endpoints: Vec<EndpointState>,
+    active: usize,
+    failovers: u64,
+    last_failover: Option<Failover>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointReport {
+    pub url: String,
+    pub active: bool,
+    pub healthy: bool,
+    pub consecutive_failures: u32,
+    pub requests: u64,
+    pub failures: u64,
+    pub chain_tip: Option<u32>,
+    pub latency_ms: Percentiles,
+    pub last_error: Option<String>,
+    pub last_checked_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RpcReport {
+    pub active: String,
+    pub failover_threshold: u32,
+    pub failovers: u64,
+    pub last_failover: Option<Failover>,
+    pub endpoints: Vec<EndpointReport>,
+}
+
+/// The configured endpoints and their health, shared by the client task, the
+/// health checker and handlers.
+#[derive(Debug, Clone)]
+pub struct RpcPool {
+    endpoints: Arc<Vec<RpcEndpointConfig>>,
+    threshold: u32,
+    inner: Arc<Mutex<PoolState>>,
+}
+
+impl RpcPool {
+    pub fn new(endpoints: Vec<RpcEndpointConfig>, threshold: u32) -> Self {
+        let states = endpoints
+            .iter()
+            .map(|_| EndpointState {
+                // Innocent until a check says otherwise
+                healthy: true,
+                ..EndpointState::default()
+            })
+            .collect();
+        Self {
+            endpoints: Arc::new(endpoints),
+            threshold: threshold.max(1),
+            inner: Arc::new(Mutex::new(PoolState {
+                endpoints: states,
+                active: 0,
+                failovers: 0,
+                last_failover: None,
+            })),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, PoolState> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn endpoints(&self) -> &[RpcEndpointConfig] {
+        &self.endpoints
+    }
+
+    pub fn active(&self) -> usize {
+        self.state().active
+    }
+
+    pub fn active_endpoint(&self) -> &RpcEndpointConfig {
+        &self.endpoints[self.active()]
+    }
+
+    pub fn is_healthy(&self, index: usize) -> bool {
+        self.state().endpoints[index].healthy
+    }
+
+    pub fn record_success(&self, index: usize, latency: Duration, chain_tip: Option<u32>) {
+        let mut state = self.state();
+        let endpoint = &mut state.endpoints[index];
+        record_latency(endpoint, latency);
+        if !endpoint.healthy {
+            tracing::info!("RPC endpoint {} is healthy again", self.endpoints[index]);
+        }
+        endpoint.healthy = true;
+        endpoint.consecutive_failures = 0;
+        endpoint.consecutive_successes = endpoint.consecutive_successes.saturating_add(1);
+        if chain_tip.is_some() {
+            endpoint.chain_tip = chain_tip;
+        }
+    }
+
+    pub fn record_failure(&self, index: usize, latency: Duration, error: &str) {
+        let mut state = self.state();
+        let endpoint = &mut state.endpoints[index];
+        record_latency(endpoint, latency);
+        endpoint.failures += 1;
+        endpoint.consecutive_successes = 0;
+        endpoint.consecutive_failures = endpoint.consecutive_failures.saturating_add(1);
+        endpoint.last_error = Some(error.to_string());
+        if endpoint.healthy && endpoint.consecutive_failures >= self.threshold {
+            endpoint.healthy = false;
+            tracing::warn!(
+                "⚠️  RPC endpoint {} is unhealthy after {} failures: {}",
+                self.endpoints[index],
+                endpoint.consecutive_failures,
+                error
+            );
+        }
+    }
+
+    /// The endpoint the client should be using (see module docs); None when
+    /// it should stay where it is.
+    pub fn preferred(&self) -> Option<usize> {
+        let state = self.state();
+        let active = state.active;
+        let stable = |e: &EndpointState| e.healthy && e.consecutive_successes >= self.threshold;
+
+        if state.endpoints[active].healthy {
+            return state.endpoints[..active].iter().position(stable);
+        }
+        state
+            .endpoints
+            .iter()
+            .position(|e| e.healthy)
+            .filter(|index| *index != active)
+    }
+
+    fn set_active(&self, index: usize, reason: &str) {
+        let mut state = self.state();
+        if state.active == index {
+            return;
+        }
+        let failover = Failover {
+            from: self.endpoints[state.active].to_string(),
+            to: self.endpoints[index].to_string(),
+            reason: reason.to_string(),
+            at: chrono::Utc::now().timestamp(),
+        };
+        tracing::warn!(
+            "⚠️  RPC failover {} -> {} ({})",
+            failover.from,
+            failover.to,
+            failover.reason
+        );
+        state.active = index;
+        state.failovers += 1;
+        state.last_failover = Some(failover);
+    }
+
+    pub fn report(&self) -> RpcReport {
+        let state = self.state();
+        RpcReport {
+            active: self.endpoints[state.active].to_string(),
+            failover_threshold: self.threshold,
+            failovers: state.failovers,
+            last_failover: state.last_failover.clone(),
+            endpoints: self
+                .endpoints
+                .iter()
+                .zip(&state.endpoints)
+                .enumerate()
+                .map(|(index, (config, endpoint))| EndpointReport {
+                    url: config.to_string(),
+                    active: index == state.active,
+                    healthy: endpoint.healthy,
+                    consecutive_failures: endpoint.consecutive_failures,
+                    requests: endpoint.requests,
+                    failures: endpoint.failures,
+                    chain_tip: endpoint.chain_tip,
+                    latency_ms: Percentiles::of(endpoint.latency.iter().copied().collect()),
+                    last_error: endpoint.last_error.clone(),
+                    last_checked_at: endpoint.last_checked_at,
+                })
+                .collect(),
+        }
+    }
+
+    /// Asks one endpoint for the chain tip and records the outcome.
+    pub async fn check(&self, index: usize, timeout_ms: u64) -> Result<u32> {
+        let started = Instant::now();
+        let result = GrpcClient::new(&endpoint(&self.endpoints[index]), timeout_ms)
+            .get_block_header_by_number(None, false)
+            .await;
+        let elapsed = started.elapsed();
+        self.state().endpoints[index].last_checked_at = Some(chrono::Utc::now().timestamp());
+
+        match result {
+            Ok((header, _)) => {
+                let tip = header.block_num().as_u32();
+                self.record_success(index, elapsed, Some(tip));
+                Ok(tip)
+            }
+            Err(e) => {
+                self.record_failure(index, elapsed, &e.to_string());
+                Err(anyhow::anyhow!("{}: {}", self.endpoints[index], e))
+            }
+        }
+    }
+
+    /// Makes the first endpoint answering a health check the active one.
+    /// Stays on the first endpoint when none answers.
+    pub async fn select_initial(&self, timeout_ms: u64) -> usize {
+        if self.endpoints.len() > 1 {
+            for index in 0..self.endpoints.len() {
+                match self.check(index, timeout_ms).await {
+                    Ok(_) => {
+                        self.set_active(index, "first endpoint answering at startup");
+                        break;
+                    }
+                    Err(e) => tracing::warn!("RPC endpoint unavailable at startup: {}", e),
+                }
+            }
+        }
+        self.active()
+    }
+}
+
+fn record_latency(endpoint: &mut EndpointState, latency: Duration) {
+    endpoint.requests += 1;
+    if endpoint.latency.len() == LATENCY_WINDOW {
+        endpoint.latency.pop_front();
+    }
+    endpoint.latency.push_back(latency);
+}
+
+/// Checks every endpoint each `interval`. Runs for the life of the service.
+pub async fn run_health_checks(pool: RpcPool, interval: Duration, timeout_ms: u64) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        for index in 0..pool.endpoints().len() {
+            if let Err(e) = pool.check(index, timeout_ms).await {
+                tracing::debug!("RPC health check failed: {}", e);
+            }
+        }
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+impl MidenClientWrapper {
+    /// Moves the client to the preferred endpoint if it is not on it.
+    pub async fn maintain_rpc_endpoint(&mut self) {
+        let Some(index) = self.rpc.preferred() else {
+            return;
+        };
+        let reason = if self.rpc.is_healthy(self.rpc.active()) {
+            "preferred endpoint recovered"
+        } else {
+            "active endpoint unhealthy"
+        };
+        if let Err(e) = self.switch_rpc_endpoint(index, reason).await {
+            tracing::error!(
+                "Failed to switch to RPC endpoint {}: {}",
+                self.rpc.endpoints()[index],
+                e
+            );
+        }
+    }
+
+    /// Rebuilds the client over `index`, keeping the store and keystore (if any).
+    async fn switch_rpc_endpoint(&mut self, index: usize, reason: &str) -> Result<()> {
+        let client = client::build_client(
+            &endpoint(&self.rpc.endpoints()[index]),
+            self.config.rpc_timeout_ms,
+            self.store.clone(),
+            self.keystore.as_ref(),
+        )
+        .await?;
+
+        #[cfg(feature = "fault-injection")]
+        {
+            // Armed faults stay armed
+            *self.client = client;
+        }
+        #[cfg(not(feature = "fault-injection"))]
+        {
+            self.client = client;
+        }
+        self.rpc.set_active(index, reason);
+        Ok(())
+    }
+
+    /// Records the outcome of a call to the active endpoint. Returns whether
+    /// the client moved to another endpoint because of it.
+    pub(crate) async fn record_rpc_call<T>(
+        &mut self,
+        started: Instant,
+        result: &Result<T>,
+    ) -> bool {
+        let active = self.rpc.active();
+        match result {
+            Ok(_) => {
+                self.rpc.record_success(active, started.elapsed(), None);
+                false
+            }
+            Err(e) => {
+                self.rpc
+                    .record_failure(active, started.elapsed(), &e.to_string());
+                self.maintain_rpc_endpoint().await;
+                self.rpc.active() != active
+            }
+        }
+    }
+}
@@ -0,0 +1,1196 @@
+// src/sagas.rs
+//
+// Saga orchestration of multi-step flows
+//
+// Flows that take several transactions run as sagas instead of as a sequence
+// of separate requests, so a failure midway no longer leaves them half done:
+// - funding: open an escrow, fund it from the buyer
+// - purchase: open and fund an escrow for the price (or take an escrow already
+//   opened for the property, e.g. by an accepted offer), transfer the title to
+//   the buyer, release the price to the seller
+// - tokenization: mint a property, consume its note into the owner's account
+// - script: an admin's list of operations (scripts.rs, POST /admin/script)
+//
+// POST /sagas starts one ({"kind": "purchase", ...}) and answers 202 with the
+// saga; GET /sagas/:saga_id follows it. Each step is a separate command on the
+// client queue, like mint batch chunks, and the saga's state is persisted
+// (SAGAS_PATH) before and after every step, so sagas left running by a
+// restart are resumed at startup.
+//
+// A step that fails for a transient reason (retry_queue.rs) is retried up to
+// STEP_ATTEMPTS times with doubling waits. Any other failure
+// - before an irreversible step (title transfer, release, mint) completed:
+//   the completed steps are compensated in reverse order — a funded escrow is
+//   refunded to the buyer; an escrow never funded is left empty — and the saga
+//   ends `compensated`
+// - after one: compensating would mean undoing a transfer, so the saga stops
+//   as `failed` and moves on with POST /sagas/:saga_id/resume once the cause
+//   is fixed (a lien signed off, a release approval confirmed, ...)
+// A compensation that fails stops the saga as `compensation_failed`; resuming
+// retries it. Scripts are never compensated: a failed step stops them as
+// `failed`.
+//
+// Crash safety: a step is marked `running` before its transaction is sent.
+// When a saga is resumed, every step first looks for its own effect in the
+// service records (escrow status, property owner, minted property, consumed
+// note), so a step that went through before the service died is taken as done
+// instead of being submitted twice. An escrow opened by a step interrupted
+// before the saga recorded it is found by its parties, amount and creation
+// time.
+//
+// Sagas are authorized when they start, for every action they will take: the
+// steps then run without an API key. Under ESCROW_AUTH_REQUIRED
+// - funding needs a key that may act for the buyer
+// - purchase needs a key that may release the escrow (per
+//   ESCROW_RELEASE_POLICY: the seller or an arbiter), and also act for the
+//   buyer unless the escrow is already funded
+// - tokenization needs a key that may act for the owner
+// A release above ESCROW_APPROVAL_THRESHOLD waits for a second approver
+// (approvals.rs) named after the key that started the saga; the saga stops as
+// `failed` until the approval is confirmed and the saga resumed. Sagas are
+// visible to the key that started them and to arbiter and admin keys.
+
+use anyhow::Result;
+use miden_client::account::AccountId;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
+
+use crate::{
+    approvals::Approver,
+    currency::PriceInput,
+    escrow::{EscrowAuthError, EscrowStatus, ReleasePolicy},
+    principals::Principal,
+    read_cache::Touched,
+    retry_queue::is_transient,
+    scripts::ScriptStep,
+    MidenClientWrapper,
+};
+
+/// Attempts of a step failing for a transient reason before the saga gives up
+/// on it.
+pub const STEP_ATTEMPTS: u32 = 3;
+/// Wait before retrying a step after its first transient failure, doubled
+/// after each attempt.
+const STEP_BACKOFF: Duration = Duration::from_secs(5);
+
+/// A flow to run, as posted to POST /sagas.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SagaInput {
+    Funding {
+        /// Account name or hex AccountId
+        buyer_account_id: String,
+        seller_account_id: String,
+        amount: u64,
+        #[serde(default)]
+        property_id: Option<String>,
+    },
+    Purchase {
+        property_id: String,
+        /// An escrow opened for the property; the buyer and amount are then
+        /// the escrow's
+        #[serde(default)]
+        escrow_account_id: Option<String>,
+        #[serde(default)]
+        buyer_account_id: Option<String>,
+        #[serde(default)]
+        amount: Option<u64>,
+    },
+    Tokenization {
+        property_id: String,
+        owner_account_id: String,
+        ipfs_cid: String,
+        property_type: u8,
+        price: PriceInput,
+    },
+    /// Started with POST /admin/script, not POST /sagas
+    Script {
+        #[serde(default)]
+        name: Option<String>,
+        steps: Vec<ScriptStep>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepKind {
+    CreateEscrow,
+    FundEscrow,
+    TransferProperty,
+    ReleaseEscrow,
+    MintProperty,
+    ConsumeNote,
+    /// Script steps only
+    RefundEscrow,
+}
+
+impl StepKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StepKind::CreateEscrow => "create_escrow",
+            StepKind::FundEscrow => "fund_escrow",
+            StepKind::TransferProperty => "transfer_property",
+            StepKind::ReleaseEscrow => "release_escrow",
+            StepKind::MintProperty => "mint_property",
+            StepKind::ConsumeNote => "consume_note",
+            StepKind::RefundEscrow => "refund_escrow",
+        }
+    }
+
+    /// Whether a completed step is undone when the saga fails (a funded
+    /// escrow is refunded).
+    fn is_compensated(self) -> bool {
+        self == StepKind::FundEscrow
+    }
+
+    /// Steps that cannot be undone: once one completed, the saga only moves
+    /// forward.
+    fn is_irreversible(self) -> bool {
+        matches!(
+            self,
+            StepKind::TransferProperty | StepKind::ReleaseEscrow | StepKind::MintProperty
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Pending,
+    /// Submitted; outcome not yet recorded
+    Running,
+    Done,
+    Failed,
+    /// Compensation submitted; outcome not yet recorded
+    Compensating,
+    Compensated,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SagaStep {
+    pub kind: StepKind,
+    pub status: StepStatus,
+    pub tx_id: Option<String>,
+    /// Transaction that undid the step (escrow refund)
+    pub compensation_tx_id: Option<String>,
+    pub error: Option<String>,
+    pub attempts: u32,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+    /// What a script step produced, for later steps to reference
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub outputs: BTreeMap<String, String>,
+}
+
+impl SagaStep {
+    fn new(kind: StepKind) -> Self {
+        Self {
+            kind,
+            status: StepStatus::Pending,
+            tx_id: None,
+            compensation_tx_id: None,
+            error: None,
+            attempts: 0,
+            started_at: None,
+            finished_at: None,
+            outputs: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SagaStatus {
+    Running,
+    Compensating,
+    Completed,
+    Compensated,
+    /// Stopped after an irreversible step; waits for a resume
+    Failed,
+    /// Stopped while compensating; waits for a resume
+    CompensationFailed,
+}
+
+impl SagaStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, SagaStatus::Completed | SagaStatus::Compensated)
+    }
+
+    /// Whether the driver has work to do.
+    pub fn is_active(&self) -> bool {
+        matches!(self, SagaStatus::Running | SagaStatus::Compensating)
+    }
+}
+
+/// The parties and objects a saga works on, resolved when it starts and
+/// filled in by its steps.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SagaContext {
+    pub buyer_account_id: Option<String>,
+    pub seller_account_id: Option<String>,
+    pub amount: Option<u64>,
+    pub property_id: Option<String>,
+    /// Given, or opened by the create_escrow step
+    pub escrow_account_id: Option<String>,
+    /// Owner of a tokenized property
+    pub owner_account_id: Option<String>,
+    /// Note minted by the mint_property step
+    pub note_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Saga {
+    pub saga_id: String,
+    pub input: SagaInput,
+    pub status: SagaStatus,
+    pub steps: Vec<SagaStep>,
+    pub context: SagaContext,
+    /// Key that started the saga; None when escrow auth is off
+    pub requested_by: Option<Approver>,
+    /// Why the saga stopped or was compensated
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub finished_at: Option<i64>,
+}
+
+/// What a saga does next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SagaAction {
+    Run(usize),
+    Compensate(usize),
+}
+
+impl Saga {
+    /// The next step to run or compensate; None once the saga finished or
+    /// stopped.
+    pub fn next_action(&self) -> Option<SagaAction> {
+        match self.status {
+            SagaStatus::Running => self
+                .steps
+                .iter()
+                .position(|s| s.status != StepStatus::Done)
+                .map(SagaAction::Run),
+            SagaStatus::Compensating => self
+                .steps
+                .iter()
+                .rposition(|s| {
+                    s.kind.is_compensated()
+                        && matches!(s.status, StepStatus::Done | StepStatus::Compensating)
+                })
+                .map(SagaAction::Compensate),
+            _ => None,
+        }
+    }
+
+    pub fn is_script(&self) -> bool {
+        matches!(self.input, SagaInput::Script { .. })
+    }
+
+    fn passed_irreversible_step(&self) -> bool {
+        self.steps
+            .iter()
+            .any(|s| s.kind.is_irreversible() && s.status == StepStatus::Done)
+    }
+
+    fn finish(&mut self, status: SagaStatus, now: i64) {
+        self.status = status;
+        self.finished_at = Some(now);
+    }
+
+    /// Marks a step as submitted (`Running`, or `Compensating` when undoing
+    /// it).
+    pub fn start_step(&mut self, action: SagaAction, now: i64) {
+        let (index, status) = match action {
+            SagaAction::Run(index) => (index, StepStatus::Running),
+            SagaAction::Compensate(index) => (index, StepStatus::Compensating),
+        };
+        let step = &mut self.steps[index];
+        if status == StepStatus::Running {
+            step.attempts += 1;
+            step.started_at = Some(now);
+        }
+        step.status = status;
+        self.updated_at = now;
+    }
+
+    /// Records a step's success; the saga completes after its last step.
+    pub fn step_succeeded(&mut self, index: usize, tx_id: Option<String>, now: i64) {
+        let step = &mut self.steps[index];
+        step.status = StepStatus::Done;
+        step.tx_id = tx_id;
+        step.error = None;
+        step.finished_at = Some(now);
+        self.updated_at = now;
+
+        if self.steps.iter().all(|s| s.status == StepStatus::Done) {
+            self.error = None;
+            self.finish(SagaStatus::Completed, now);
+        }
+    }
+
+    /// Puts a step that failed for a transient reason back in line. Returns
+    /// the wait before its next attempt, or None when it used up its attempts.
+    pub fn step_deferred(&mut self, index: usize, error: &str, now: i64) -> Option<Duration> {
+        let step = &mut self.steps[index];
+        if step.attempts >= STEP_ATTEMPTS {
+            return None;
+        }
+        step.status = StepStatus::Pending;
+        step.error = Some(error.to_string());
+        self.updated_at = now;
+        Some(STEP_BACKOFF * 2u32.pow(step.attempts.saturating_sub(1)))
+    }
+
+    /// Records a step's failure: before an irreversible step completed, the
+    /// saga starts undoing the steps it completed; after, or in a script, it
+    /// stops.
+    pub fn step_failed(&mut self, index: usize, error: &str, now: i64) {
+        let step = &mut self.steps[index];
+        step.status = StepStatus::Failed;
+        step.error = Some(error.to_string());
+        step.finished_at = Some(now);
+        self.error = Some(format!("{} failed: {}", step.kind.as_str(), error));
+        self.updated_at = now;
+
+        if self.passed_irreversible_step() || self.is_script() {
+            self.status = SagaStatus::Failed;
+        } else {
+            self.status = SagaStatus::Compensating;
+            if self.next_action().is_none() {
+                self.finish(SagaStatus::Compensated, now);
+            }
+        }
+    }
+
+    pub fn compensation_succeeded(&mut self, index: usize, tx_id: Option<String>, now: i64) {
+        let step = &mut self.steps[index];
+        step.status = StepStatus::Compensated;
+        step.compensation_tx_id = tx_id;
+        self.updated_at = now;
+        if self.next_action().is_none() {
+            self.finish(SagaStatus::Compensated, now);
+        }
+    }
+
+    pub fn compensation_failed(&mut self, index: usize, error: &str, now: i64) {
+        let step = &mut self.steps[index];
+        step.status = StepStatus::Done;
+        step.error = Some(format!("compensation failed: {}", error));
+        self.error = Some(format!("undoing {} failed: {}", step.kind.as_str(), error));
+        self.status = SagaStatus::CompensationFailed;
+        self.updated_at = now;
+    }
+
+    /// Picks a stopped saga up again.
+    pub fn resume(&mut self, now: i64) -> Result<()> {
+        self.status = match self.status {
+            SagaStatus::Failed => {
+                for step in self
+                    .steps
+                    .iter_mut()
+                    .filter(|s| s.status == StepStatus::Failed)
+                {
+                    step.status = StepStatus::Pending;
+                    step.attempts = 0;
+                }
+                SagaStatus::Running
+            }
+            SagaStatus::CompensationFailed => SagaStatus::Compensating,
+            status if status.is_active() => return Ok(()),
+            status => {
+                return Err(anyhow::anyhow!(
+                    "Conflict: saga {} already finished ({:?})",
+                    self.saga_id,
+                    status
+                ))
+            }
+        };
+        self.updated_at = now;
+        Ok(())
+    }
+
+    fn works_on_escrow(&self, escrow_hex: &str) -> bool {
+        self.context
+            .escrow_account_id
+            .as_deref()
+            .is_some_and(|e| e.eq_ignore_ascii_case(escrow_hex))
+    }
+
+    /// Whether `principal` may see and resume the saga; anyone may when escrow
+    /// auth is off.
+    fn visible_to(&self, principal: Option<&Principal>) -> bool {
+        principal.is_none_or(|p| {
+            p.admin
+                || p.arbiter
+                || self
+                    .requested_by
+                    .as_ref()
+                    .is_some_and(|r| r.key_id == p.key_id)
+        })
+    }
+}
+
+/// What the driver does after a step (see main.rs, drive_saga).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SagaProgress {
+    /// Run the next step now
+    Next,
+    /// Run the next step after this wait
+    RetryIn(Duration),
+    /// Finished or stopped
+    Done,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SagaStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    sagas: BTreeMap<String, Saga>,
+    #[serde(default)]
+    next_saga_id: u64,
+}
+
+impl SagaStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<SagaStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            SagaStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Persists, logging instead of failing: a step's transaction went out
+    /// whether or not its outcome could be written (see module docs).
+    pub fn persist(&self) {
+        if let Err(e) = self.save() {
+            tracing::warn!("Failed to persist sagas: {}", e);
+        }
+    }
+
+    pub fn create(
+        &mut self,
+        input: SagaInput,
+        steps: &[StepKind],
+        context: SagaContext,
+        requested_by: Option<Approver>,
+    ) -> Result<&Saga> {
+        self.next_saga_id += 1;
+        let saga_id = format!("saga-{}", self.next_saga_id);
+        let now = chrono::Utc::now().timestamp();
+
+        self.sagas.insert(
+            saga_id.clone(),
+            Saga {
+                saga_id: saga_id.clone(),
+                input,
+                status: SagaStatus::Running,
+                steps: steps.iter().copied().map(SagaStep::new).collect(),
+                context,
+                requested_by,
+                error: None,
+                created_at: now,
+                updated_at: now,
+                finished_at: None,
+            },
+        );
+        self.save()?;
+
+        self.get(&saga_id)
+    }
+
+    pub fn get(&self, saga_id: &str) -> Result<&Saga> {
+        self.sagas
+            .get(saga_id)
+            .ok_or_else(|| anyhow::anyhow!("Saga {} not found", saga_id))
+    }
+
+    pub fn get_mut(&mut self, saga_id: &str) -> Result<&mut Saga> {
+        self.sagas
+            .get_mut(saga_id)
+            .ok_or_else(|| anyhow::anyhow!("Saga {} not found", saga_id))
+    }
+
+    /// Sagas, newest first.
+    pub fn list(&self, status: Option<SagaStatus>) -> Vec<&Saga> {
+        let mut sagas: Vec<&Saga> = self
+            .sagas
+            .values()
+            .filter(|s| status.is_none_or(|status| s.status == status))
+            .collect();
+        sagas.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        sagas
+    }
+
+    /// Sagas to resume after a restart.
+    pub fn unfinished(&self) -> Vec<String> {
+        self.sagas
+            .values()
+            .filter(|s| s.status.is_active())
+            .map(|s| s.saga_id.clone())
+            .collect()
+    }
+
+    /// Whether a saga other than `saga_id` worked on the escrow.
+    fn claims_escrow(&self, saga_id: &str, escrow_hex: &str) -> bool {
+        self.sagas
+            .values()
+            .any(|s| s.saga_id != saga_id && s.works_on_escrow(escrow_hex))
+    }
+
+    /// Whether an unfinished saga works on the property or escrow.
+    fn has_unfinished_for(&self, property_id: Option<&str>, escrow_hex: Option<&str>) -> bool {
+        self.sagas
+            .values()
+            .filter(|s| !s.status.is_finished())
+            .any(|s| {
+                property_id.is_some_and(|p| s.context.property_id.as_deref() == Some(p))
+                    || escrow_hex.is_some_and(|e| s.works_on_escrow(e))
+            })
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    StartSaga {
+        input: SagaInput,
+        api_key: Option<String>,
+    } -> Saga;
+    |client, op| client.start_saga(op.input, op.api_key.as_deref());
+
+    RunSagaStep {
+        saga_id: String,
+    } -> SagaProgress;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.run_saga_step(&op.saga_id).await;
+
+    GetSaga {
+        saga_id: String,
+        api_key: Option<String>,
+    } -> Saga;
+    |client, op| client.get_saga(&op.saga_id, op.api_key.as_deref());
+
+    ListSagas {
+        status: Option<SagaStatus>,
+        api_key: Option<String>,
+    } -> Vec<Saga>;
+    |client, op| client.list_sagas(op.status, op.api_key.as_deref());
+
+    ResumeSaga {
+        saga_id: String,
+        api_key: Option<String>,
+    } -> Saga;
+    |client, op| client.resume_saga(&op.saga_id, op.api_key.as_deref());
+
+    UnfinishedSagas {} -> Vec<String>;
+    |client, _op| Ok(client.unfinished_sagas())
+}
+
+impl MidenClientWrapper {
+    /// The caller, when escrow auth is on (see module docs).
+    fn saga_principal(&self, api_key: Option<&str>) -> Result<Option<Principal>> {
+        Ok(self.request_principal(api_key)?.cloned())
+    }
+
+    fn saga_forbidden(principal: &Principal, what: &str) -> anyhow::Error {
+        EscrowAuthError::Forbidden(format!(
+            "API key {} ({}) may not {}",
+            principal.key_id, principal.label, what
+        ))
+        .into()
+    }
+
+    /// Validates and authorizes a saga, and resolves its steps and context.
+    fn plan_saga(
+        &self,
+        input: &SagaInput,
+        principal: Option<&Principal>,
+    ) -> Result<(Vec<StepKind>, SagaContext)> {
+        match input {
+            SagaInput::Funding {
+                buyer_account_id,
+                seller_account_id,
+                amount,
+                property_id,
+            } => {
+                let buyer = self.account_hex(buyer_account_id)?;
+                let seller = self.account_hex(seller_account_id)?;
+                if *amount == 0 {
+                    return Err(anyhow::anyhow!("Escrow amount must be positive"));
+                }
+                if let Some(property_id) = property_id {
+                    if !self.records.properties.contains_key(property_id) {
+                        return Err(anyhow::anyhow!(
+                            "Property {} has not been minted",
+                            property_id
+                        ));
+                    }
+                }
+                if let Some(p) = principal.filter(|p| !p.owns(&buyer)) {
+                    return Err(Self::saga_forbidden(
+                        p,
+                        &format!("fund for buyer {}", buyer),
+                    ));
+                }
+
+                Ok((
+                    vec![StepKind::CreateEscrow, StepKind::FundEscrow],
+                    SagaContext {
+                        buyer_account_id: Some(buyer),
+                        seller_account_id: Some(seller),
+                        amount: Some(*amount),
+                        property_id: property_id.clone(),
+                        ..SagaContext::default()
+                    },
+                ))
+            }
+            SagaInput::Purchase {
+                property_id,
+                escrow_account_id,
+                buyer_account_id,
+                amount,
+            } => {
+                let seller = self
+                    .records
+                    .properties
+                    .get(property_id)
+                    .map(|p| p.owner_account_id.to_lowercase())
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("Property {} has not been minted", property_id)
+                    })?;
+
+                let (buyer, amount, funded, escrow_hex) = match escrow_account_id {
+                    Some(escrow) => {
+                        let escrow_hex = escrow.to_lowercase();
+                        let record = self.records.escrows.get(&escrow_hex).ok_or_else(|| {
+                            anyhow::anyhow!("Escrow {} not found in service records", escrow_hex)
+                        })?;
+                        if record.property_id.as_deref() != Some(property_id.as_str())
+                            || !record.seller_account_id.eq_ignore_ascii_case(&seller)
+                        {
+                            return Err(anyhow::anyhow!(
+                                "Conflict: escrow {} is not a sale of {} by its owner",
+                                escrow_hex,
+                                property_id
+                            ));
+                        }
+                        if !matches!(record.status, EscrowStatus::Created | EscrowStatus::Funded) {
+                            return Err(anyhow::anyhow!(
+                                "Conflict: escrow {} is {:?}",
+                                escrow_hex,
+                                record.status
+                            ));
+                        }
+                        (
+                            record.buyer_account_id.to_lowercase(),
+                            record.amount,
+                            record.status == EscrowStatus::Funded,
+                            Some(escrow_hex),
+                        )
+                    }
+                    None => {
+                        let buyer = buyer_account_id.as_deref().ok_or_else(|| {
+                            anyhow::anyhow!("buyer_account_id is required without an escrow")
+                        })?;
+                        let amount = amount
+                            .filter(|a| *a > 0)
+                            .ok_or_else(|| anyhow::anyhow!("A positive amount is required"))?;
+                        (self.account_hex(buyer)?, amount, false, None)
+                    }
+                };
+                if buyer.eq_ignore_ascii_case(&seller) {
+                    return Err(anyhow::anyhow!(
+                        "Buyer {} already owns {}",
+                        buyer,
+                        property_id
+                    ));
+                }
+
+                if let Some(p) = principal {
+                    let may_release = p.arbiter
+                        || (self.config.escrow_release_policy == ReleasePolicy::SellerOrArbiter
+                            && p.owns(&seller));
+                    if !may_release {
+                        return Err(Self::saga_forbidden(p, &format!("sell {}", property_id)));
+                    }
+                    if !funded && !p.owns(&buyer) {
+                        return Err(Self::saga_forbidden(
+                            p,
+                            &format!("fund for buyer {}", buyer),
+                        ));
+                    }
+                }
+
+                let mut steps = vec![
+                    StepKind::FundEscrow,
+                    StepKind::TransferProperty,
+                    StepKind::ReleaseEscrow,
+                ];
+                if escrow_hex.is_none() {
+                    steps.insert(0, StepKind::CreateEscrow);
+                }
+                Ok((
+                    steps,
+                    SagaContext {
+                        buyer_account_id: Some(buyer),
+                        seller_account_id: Some(seller),
+                        amount: Some(amount),
+                        property_id: Some(property_id.clone()),
+                        escrow_account_id: escrow_hex,
+                        ..SagaContext::default()
+                    },
+                ))
+            }
+            SagaInput::Tokenization {
+                property_id,
+                owner_account_id,
+                price,
+                ..
+            } => {
+                let owner = self.account_hex(owner_account_id)?;
+                price.resolve(&self.config.price_currency)?;
+                if self.records.properties.contains_key(property_id) {
+                    return Err(anyhow::anyhow!(
+                        "Property {} has already been minted",
+                        property_id
+                    ));
+                }
+                if self.mint_jobs.has_unfinished_item(property_id) {
+                    return Err(anyhow::anyhow!(
+                        "Property {} is queued in a mint batch",
+                        property_id
+                    ));
+                }
+                if let Some(p) = principal.filter(|p| !(p.owns(&owner) || p.arbiter)) {
+                    return Err(Self::saga_forbidden(p, &format!("tokenize for {}", owner)));
+                }
+
+                Ok((
+                    vec![StepKind::MintProperty, StepKind::ConsumeNote],
+                    SagaContext {
+                        property_id: Some(property_id.clone()),
+                        owner_account_id: Some(owner),
+                        ..SagaContext::default()
+                    },
+                ))
+            }
+            SagaInput::Script { .. } => Err(anyhow::anyhow!(
+                "Scripts are started with POST /admin/script"
+            )),
+        }
+    }
+
+    /// Validates, authorizes and records a saga; the caller then drives it
+    /// (see main.rs).
+    pub fn start_saga(&mut self, input: SagaInput, api_key: Option<&str>) -> Result<Saga> {
+        let principal = self.saga_principal(api_key)?;
+        let (steps, context) = self.plan_saga(&input, principal.as_ref())?;
+        if self.sagas.has_unfinished_for(
+            context.property_id.as_deref(),
+            context.escrow_account_id.as_deref(),
+        ) {
+            return Err(anyhow::anyhow!(
+                "Conflict: another saga is working on this property or escrow"
+            ));
+        }
+
+        let saga = self
+            .sagas
+            .create(
+                input,
+                &steps,
+                context,
+                principal.as_ref().map(Approver::from),
+            )?
+            .clone();
+        tracing::info!(
+            "🧭 Saga {} started: {}",
+            saga.saga_id,
+            saga.steps
+                .iter()
+                .map(|s| s.kind.as_str())
+                .collect::<Vec<_>>()
+                .join(" -> ")
+        );
+
+        Ok(saga)
+    }
+
+    /// Runs or compensates the next step of a saga.
+    pub async fn run_saga_step(&mut self, saga_id: &str) -> Result<SagaProgress> {
+        let now = chrono::Utc::now().timestamp();
+        let saga = self.sagas.get_mut(saga_id)?;
+        let Some(action) = saga.next_action() else {
+            return Ok(SagaProgress::Done);
+        };
+        // A script step left running by a restart may or may not have gone
+        // through: the script stops there for the operator to check
+        if let SagaAction::Run(index) = action {
+            if saga.is_script() && saga.steps[index].status == StepStatus::Running {
+                saga.step_failed(
+                    index,
+                    "interrupted by a restart; check whether it went through before resuming",
+                    now,
+                );
+                tracing::warn!("Saga {}: script interrupted at step {}", saga_id, index + 1);
+                self.sagas.persist();
+                return Ok(SagaProgress::Done);
+            }
+        }
+        saga.start_step(action, now);
+        let saga = saga.clone();
+        self.sagas.persist();
+
+        let progress = match action {
+            SagaAction::Run(index) => {
+                let kind = saga.steps[index].kind;
+                let result = if saga.is_script() {
+                    self.run_script_step(&saga, index).await
+                } else {
+                    match self.saga_step_effect(&saga, kind) {
+                        Some(tx_id) => {
+                            tracing::info!(
+                                "Saga {}: {} had already gone through",
+                                saga_id,
+                                kind.as_str()
+                            );
+                            Ok(tx_id)
+                        }
+                        None => self.run_saga_action(&saga, kind).await,
+                    }
+                    .map(|tx_id| (tx_id, BTreeMap::new()))
+                };
+
+                let now = chrono::Utc::now().timestamp();
+                let saga = self.sagas.get_mut(saga_id)?;
+                match result {
+                    Ok((tx_id, outputs)) => {
+                        tracing::info!("🧭 Saga {}: {} done", saga_id, kind.as_str());
+                        saga.steps[index].outputs = outputs;
+                        saga.step_succeeded(index, tx_id, now);
+                        SagaProgress::Next
+                    }
+                    Err(e) => {
+                        let error = e.to_string();
+                        match is_transient(&error)
+                            .then(|| saga.step_deferred(index, &error, now))
+                            .flatten()
+                        {
+                            Some(wait) => {
+                                tracing::warn!(
+                                    "Saga {}: {} failed, retrying in {:?}: {}",
+                                    saga_id,
+                                    kind.as_str(),
+                                    wait,
+                                    error
+                                );
+                                SagaProgress::RetryIn(wait)
+                            }
+                            None => {
+                                tracing::warn!(
+                                    "Saga {}: {} failed: {}",
+                                    saga_id,
+                                    kind.as_str(),
+                                    error
+                                );
+                                saga.step_failed(index, &error, now);
+                                SagaProgress::Next
+                            }
+                        }
+                    }
+                }
+            }
+            SagaAction::Compensate(index) => {
+                let kind = saga.steps[index].kind;
+                let result = self.compensate_saga_step(&saga, kind).await;
+
+                let now = chrono::Utc::now().timestamp();
+                let saga = self.sagas.get_mut(saga_id)?;
+                match result {
+                    Ok(tx_id) => {
+                        tracing::info!("🧭 Saga {}: {} undone", saga_id, kind.as_str());
+                        saga.compensation_succeeded(index, tx_id, now);
+                    }
+                    Err(e) => {
+                        tracing::warn!("Saga {}: undoing {} failed: {}", saga_id, kind.as_str(), e);
+                        saga.compensation_failed(index, &e.to_string(), now);
+                    }
+                }
+                SagaProgress::Next
+            }
+        };
+
+        let saga = self.sagas.get(saga_id)?;
+        let progress = if saga.status.is_active() {
+            progress
+        } else {
+            tracing::info!("🧭 Saga {} ended: {:?}", saga_id, saga.status);
+            SagaProgress::Done
+        };
+        self.sagas.persist();
+
+        Ok(progress)
+    }
+
+    fn saga_escrow_hex(saga: &Saga) -> Result<&str> {
+        saga.context
+            .escrow_account_id
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Saga {} has no escrow", saga.saga_id))
+    }
+
+    /// The transaction of a step that already went through, per the service
+    /// records (see module docs). None when it has yet to run.
+    fn saga_step_effect(&mut self, saga: &Saga, kind: StepKind) -> Option<Option<String>> {
+        let escrow = saga
+            .context
+            .escrow_account_id
+            .as_deref()
+            .and_then(|e| self.records.escrows.get(e));
+        match kind {
+            StepKind::CreateEscrow => {
+                if escrow.is_some() {
+                    return Some(None);
+                }
+                // Opened by an interrupted attempt: same deal, opened since
+                let started_at = saga.steps.iter().find(|s| s.kind == kind)?.started_at?;
+                let found = self
+                    .records
+                    .escrows
+                    .values()
+                    .filter(|e| {
+                        e.status == EscrowStatus::Created
+                            && e.created_at >= started_at.saturating_sub(1)
+                            && Some(e.amount) == saga.context.amount
+                            && e.property_id == saga.context.property_id
+                            && saga
+                                .context
+                                .buyer_account_id
+                                .as_deref()
+                                .is_some_and(|b| e.buyer_account_id.eq_ignore_ascii_case(b))
+                            && saga
+                                .context
+                                .seller_account_id
+                                .as_deref()
+                                .is_some_and(|s| e.seller_account_id.eq_ignore_ascii_case(s))
+                    })
+                    .map(|e| e.escrow_account_id.clone())
+                    .find(|e| !self.sagas.claims_escrow(&saga.saga_id, e))?;
+                self.sagas
+                    .get_mut(&saga.saga_id)
+                    .ok()?
+                    .context
+                    .escrow_account_id = Some(found);
+                Some(None)
+            }
+            StepKind::FundEscrow => escrow
+                .filter(|e| e.status != EscrowStatus::Created)
+                .map(|e| e.fund_tx_id.clone()),
+            StepKind::ReleaseEscrow => escrow
+                .filter(|e| e.status == EscrowStatus::Released)
+                .map(|e| e.settle_tx_id.clone()),
+            StepKind::RefundEscrow => escrow
+                .filter(|e| e.status == EscrowStatus::Refunded)
+                .map(|e| e.settle_tx_id.clone()),
+            StepKind::TransferProperty => {
+                let property = self
+                    .records
+                    .properties
+                    .get(saga.context.property_id.as_deref()?)?;
+                let buyer = saga.context.buyer_account_id.as_deref()?;
+                property
+                    .owner_account_id
+                    .eq_ignore_ascii_case(buyer)
+                    .then_some(None)
+            }
+            StepKind::MintProperty => {
+                let property = self
+                    .records
+                    .properties
+                    .get(saga.context.property_id.as_deref()?)?;
+                let (tx_id, note_id) = (property.mint_tx_id.clone(), property.note_id.clone());
+                self.sagas.get_mut(&saga.saga_id).ok()?.context.note_id = Some(note_id);
+                Some(Some(tx_id))
+            }
+            StepKind::ConsumeNote => {
+                let note = self
+                    .records
+                    .expected_notes
+                    .get(saga.context.note_id.as_deref()?)?;
+                note.consumed.then(|| note.consumed_tx_id.clone())
+            }
+        }
+    }
+
+    /// Submits a step. Returns its transaction ID, when it has one.
+    async fn run_saga_action(&mut self, saga: &Saga, kind: StepKind) -> Result<Option<String>> {
+        let context = &saga.context;
+        let account = |id: Option<&String>| -> Result<AccountId> {
+            let hex =
+                id.ok_or_else(|| anyhow::anyhow!("Saga {} is missing an account", saga.saga_id))?;
+            Ok(AccountId::from_hex(hex)?)
+        };
+
+        match kind {
+            StepKind::CreateEscrow => {
+                let escrow = self
+                    .create_escrow_unchecked(
+                        account(context.buyer_account_id.as_ref())?,
+                        account(context.seller_account_id.as_ref())?,
+                        context.amount.unwrap_or_default(),
+                        context.property_id.as_deref(),
+                    )
+                    .await?;
+                self.sagas.get_mut(&saga.saga_id)?.context.escrow_account_id =
+                    Some(crate::account_id_to_hex(escrow.escrow_account_id));
+                self.sagas.persist();
+                Ok(None)
+            }
+            StepKind::FundEscrow => {
+                let escrow = self.recorded_escrow(Self::saga_escrow_hex(saga)?)?;
+                Ok(Some(self.fund_escrow_unchecked(&escrow, None).await?))
+            }
+            StepKind::TransferProperty => {
+                let property_id = context.property_id.as_deref().unwrap_or_default();
+                let buyer = context.buyer_account_id.as_deref().unwrap_or_default();
+                Ok(Some(self.transfer_property(property_id, buyer).await?))
+            }
+            StepKind::ReleaseEscrow => {
+                let escrow = self.recorded_escrow(Self::saga_escrow_hex(saga)?)?;
+                let requested_by = saga.requested_by.clone();
+                self.require_release_approval_from(&escrow, |_| {
+                    requested_by.ok_or_else(|| {
+                        EscrowAuthError::Unauthenticated("Escrow approvals require API keys".into())
+                            .into()
+                    })
+                })?;
+                Ok(Some(self.release_escrow_unchecked(&escrow).await?))
+            }
+            StepKind::MintProperty => {
+                let SagaInput::Tokenization {
+                    property_id,
+                    owner_account_id,
+                    ipfs_cid,
+                    property_type,
+                    price,
+                } = &saga.input
+                else {
+                    return Err(anyhow::anyhow!("Saga {} mints nothing", saga.saga_id));
+                };
+                let (tx_id, note_id) = self
+                    .mint_property_nft(
+                        property_id,
+                        owner_account_id,
+                        ipfs_cid,
+                        *property_type,
+                        price,
+                    )
+                    .await?;
+                self.sagas.get_mut(&saga.saga_id)?.context.note_id = Some(note_id);
+                self.sagas.persist();
+                Ok(Some(tx_id))
+            }
+            StepKind::ConsumeNote => {
+                // The note went to the owner the mint recorded
+                let owner = context
+                    .property_id
+                    .as_deref()
+                    .and_then(|p| self.records.properties.get(p))
+                    .map(|p| p.owner_account_id.clone())
+                    .or_else(|| context.owner_account_id.clone())
+                    .ok_or_else(|| anyhow::anyhow!("Saga {} has no owner", saga.saga_id))?;
+                let note_id = context.note_id.clone().unwrap_or_default();
+                Ok(Some(self.consume_note(&note_id, Some(owner)).await?))
+            }
+            StepKind::RefundEscrow => Err(anyhow::anyhow!("Saga {} refunds nothing", saga.saga_id)),
+        }
+    }
+
+    /// Undoes a completed step (see `StepKind::is_compensated`).
+    async fn compensate_saga_step(
+        &mut self,
+        saga: &Saga,
+        kind: StepKind,
+    ) -> Result<Option<String>> {
+        match kind {
+            StepKind::FundEscrow => {
+                let escrow_hex = Self::saga_escrow_hex(saga)?;
+                let escrow = self.recorded_escrow(escrow_hex)?;
+                match escrow.status {
+                    EscrowStatus::Refunded => Ok(self
+                        .records
+                        .escrows
+                        .get(escrow_hex)
+                        .and_then(|e| e.settle_tx_id.clone())),
+                    EscrowStatus::Funded => Ok(Some(self.refund_escrow_unchecked(&escrow).await?)),
+                    status => Err(anyhow::anyhow!(
+                        "Conflict: escrow {} is {:?} and cannot be refunded",
+                        escrow_hex,
+                        status
+                    )),
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn get_saga(&self, saga_id: &str, api_key: Option<&str>) -> Result<Saga> {
+        let principal = self.saga_principal(api_key)?;
+        let saga = self.sagas.get(saga_id)?;
+        if !saga.visible_to(principal.as_ref()) {
+            return Err(anyhow::anyhow!("Saga {} not found", saga_id));
+        }
+        Ok(saga.clone())
+    }
+
+    pub fn list_sagas(
+        &self,
+        status: Option<SagaStatus>,
+        api_key: Option<&str>,
+    ) -> Result<Vec<Saga>> {
+        let principal = self.saga_principal(api_key)?;
+        Ok(self
+            .sagas
+            .list(status)
+            .into_iter()
+            .filter(|s| s.visible_to(principal.as_ref()))
+            .cloned()
+            .collect())
+    }
+
+    /// Resumes a stopped saga; the caller then drives it (see main.rs).
+    pub fn resume_saga(&mut self, saga_id: &str, api_key: Option<&str>) -> Result<Saga> {
+        let saga = self.get_saga(saga_id, api_key)?;
+        let now = chrono::Utc::now().timestamp();
+        let saga = self.sagas.get_mut(&saga.saga_id)?;
+        saga.resume(now)?;
+        let saga = saga.clone();
+        self.sagas.persist();
+        tracing::info!("🧭 Saga {} resumed ({:?})", saga_id, saga.status);
+
+        Ok(saga)
+    }
+
+    /// Sagas to resume after a restart.
+    pub fn unfinished_sagas(&self) -> Vec<String> {
+        self.sagas.unfinished()
+    }
+}
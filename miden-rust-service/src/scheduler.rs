@@ -0,0 +1,106 @@
+// src/scheduler.rs
+//
+// Periodic jobs
+//
+// main.rs queues a scheduler tick on the client task every SCHEDULER_TICK_SECS.
+// Each tick runs the time-driven work of the modules that need it:
+// - installment plans: reminders, defaults, settlement retries (installments.rs)
+// - leases: rent collection, lease end and deposit return (leases.rs)
+// - auctions: closing, settlement and refunds of outbid bids (auctions.rs)
+// - offers: closing offers whose terms expired (negotiation.rs)
+// - approvals: expiring unconfirmed release approvals (approvals.rs)
+// - stale escrows: flagging escrows stuck unfunded or unsettled
+//   (escrow_monitor.rs)
+// - escrow yield: accruing yield on funded escrows and retrying yield payouts
+//   (escrow_yield.rs)
+// - trades: retrying share trade settlement steps that failed (order_book.rs)
+// - swaps: filling swap notes whose fill failed and expiring quotes (swaps.rs)
+// - retries: replaying submissions that failed transiently (retry_queue.rs)
+// - offline: replaying requests queued while the node was unreachable
+//   (offline_queue.rs)
+// - store: pruning and vacuuming the client store, once per
+//   STORE_MAINTENANCE_INTERVAL_SECS (store_maintenance.rs)
+// - snapshots: publishing a store snapshot, once per
+//   SNAPSHOT_PUBLISH_INTERVAL_SECS (snapshots.rs)
+// - billing: writing metered usage, invoicing ended months and settling open
+//   invoices (billing.rs)
+//
+// Jobs run one after another inside the tick; a failing job is logged and does
+// not keep the others from running.
+
+use anyhow::Result;
+
+use crate::{read_cache::Touched, MidenClientWrapper};
+
+crate::operations! {
+    SchedulerTick {} -> Vec<(&'static str, usize)>;
+    touched: |_op| Some(Touched::All);
+    |client, _op| client.run_scheduled_jobs().await
+}
+
+impl MidenClientWrapper {
+    /// Runs every scheduled job once. Returns the number of items each job
+    /// changed.
+    pub async fn run_scheduled_jobs(&mut self) -> Result<Vec<(&'static str, usize)>> {
+        let mut changed = Vec::new();
+
+        match self.run_installment_tick().await {
+            Ok(n) => changed.push(("installments", n)),
+            Err(e) => tracing::warn!("Scheduled job installments failed: {}", e),
+        }
+        match self.run_lease_tick().await {
+            Ok(n) => changed.push(("leases", n)),
+            Err(e) => tracing::warn!("Scheduled job leases failed: {}", e),
+        }
+        match self.run_auction_tick().await {
+            Ok(n) => changed.push(("auctions", n)),
+            Err(e) => tracing::warn!("Scheduled job auctions failed: {}", e),
+        }
+        match self.run_offer_expiry() {
+            Ok(n) => changed.push(("offers", n)),
+            Err(e) => tracing::warn!("Scheduled job offers failed: {}", e),
+        }
+        match self.run_approval_expiry() {
+            Ok(n) => changed.push(("approvals", n)),
+            Err(e) => tracing::warn!("Scheduled job approvals failed: {}", e),
+        }
+        match self.run_escrow_monitor() {
+            Ok(n) => changed.push(("stale_escrows", n)),
+            Err(e) => tracing::warn!("Scheduled job stale_escrows failed: {}", e),
+        }
+        match self.run_escrow_yield().await {
+            Ok(n) => changed.push(("escrow_yield", n)),
+            Err(e) => tracing::warn!("Scheduled job escrow_yield failed: {}", e),
+        }
+        match self.run_trade_settlement().await {
+            Ok(n) => changed.push(("trades", n)),
+            Err(e) => tracing::warn!("Scheduled job trades failed: {}", e),
+        }
+        match self.run_swaps().await {
+            Ok(n) => changed.push(("swaps", n)),
+            Err(e) => tracing::warn!("Scheduled job swaps failed: {}", e),
+        }
+        match self.run_retry_queue().await {
+            Ok(n) => changed.push(("retries", n)),
+            Err(e) => tracing::warn!("Scheduled job retries failed: {}", e),
+        }
+        match self.run_offline_queue().await {
+            Ok(n) => changed.push(("offline", n)),
+            Err(e) => tracing::warn!("Scheduled job offline failed: {}", e),
+        }
+        match self.run_store_maintenance().await {
+            Ok(n) => changed.push(("store", n)),
+            Err(e) => tracing::warn!("Scheduled job store failed: {}", e),
+        }
+        match self.run_snapshot_publishing().await {
+            Ok(n) => changed.push(("snapshots", n)),
+            Err(e) => tracing::warn!("Scheduled job snapshots failed: {}", e),
+        }
+        match self.run_billing().await {
+            Ok(n) => changed.push(("billing", n)),
+            Err(e) => tracing::warn!("Scheduled job billing failed: {}", e),
+        }
+
+        Ok(changed)
+    }
+}
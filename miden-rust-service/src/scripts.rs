@@ -0,0 +1,457 @@
+// src/scripts.rs
+//
+// Admin scripts: batch operations as one declarative plan
+//
+// POST /admin/script takes a list of operations and runs it as a saga
+// (sagas.rs), instead of operators scripting curl calls against the raw
+// endpoints. The plan is JSON, or YAML when sent as application/yaml:
+//
+//   name: seed demo listing
+//   steps:
+//     - id: mint
+//       op: mint_property
+//       property_id: PROP-42
+//       owner_account_id: bob
+//       ipfs_cid: Qm...
+//       property_type: 1
+//       price: 250000
+//     - op: consume_note
+//       note_id: ${mint}
+//       account_id: ${mint.owner_account_id}
+//     - id: escrow
+//       op: create_escrow
+//       buyer_account_id: alice
+//       seller_account_id: bob
+//       amount: 1000
+//       property_id: ${mint.property_id}
+//     - op: fund_escrow
+//       escrow_account_id: ${escrow}
+//
+// Operations: mint_property, consume_note, transfer_property, create_escrow,
+// fund_escrow, release_escrow and refund_escrow, with the fields of their
+// endpoints. The service has no operation creating accounts or minting
+// fungible tokens, so scripts work with the accounts it knows (alice, bob,
+// NAMED_ACCOUNTS or hex IDs).
+//
+// A text field that is exactly "${id}" or "${id.output}" takes an output of an
+// earlier step with that id, once that step ran:
+// - mint_property: note_id (the default), tx_id, property_id, owner_account_id
+// - create_escrow: escrow_account_id (the default)
+// - every other operation: tx_id (the default)
+// The whole plan is checked before anything runs: a plan with unknown
+// operations or fields, duplicate ids, references to later or unknown steps or
+// outputs, or zero amounts is refused with 400, one naming an unknown account
+// with success: false.
+//
+// Steps run in order, one per turn of the client queue, and are retried like
+// saga steps when they fail for a transient reason. A script is not undone
+// when a step fails: it stops as `failed` at that step, which
+// POST /sagas/:saga_id/resume runs again once the cause is fixed. A step the
+// service was running when it stopped is not submitted again on its own: the
+// script stops as `failed` there, so the operator can check whether it went
+// through before resuming. Follow a script with GET /sagas/:saga_id.
+//
+// Scripts run with the authority of the admin key that posted them, and a
+// release above ESCROW_APPROVAL_THRESHOLD still needs a second approver.
+
+use anyhow::Result;
+use miden_client::account::AccountId;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    account_id_to_hex,
+    approvals::Approver,
+    currency::PriceInput,
+    escrow::EscrowAuthError,
+    sagas::{Saga, SagaContext, SagaInput, StepKind},
+    MidenClientWrapper,
+};
+
+/// Most steps a script may have
+pub const MAX_SCRIPT_STEPS: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ScriptOp {
+    MintProperty {
+        property_id: String,
+        owner_account_id: String,
+        ipfs_cid: String,
+        property_type: u8,
+        price: PriceInput,
+    },
+    ConsumeNote {
+        note_id: String,
+        account_id: String,
+    },
+    TransferProperty {
+        property_id: String,
+        to_account_id: String,
+    },
+    CreateEscrow {
+        buyer_account_id: String,
+        seller_account_id: String,
+        amount: u64,
+        #[serde(default)]
+        property_id: Option<String>,
+    },
+    FundEscrow {
+        escrow_account_id: String,
+    },
+    ReleaseEscrow {
+        escrow_account_id: String,
+    },
+    RefundEscrow {
+        escrow_account_id: String,
+    },
+}
+
+impl ScriptOp {
+    pub fn kind(&self) -> StepKind {
+        match self {
+            ScriptOp::MintProperty { .. } => StepKind::MintProperty,
+            ScriptOp::ConsumeNote { .. } => StepKind::ConsumeNote,
+            ScriptOp::TransferProperty { .. } => StepKind::TransferProperty,
+            ScriptOp::CreateEscrow { .. } => StepKind::CreateEscrow,
+            ScriptOp::FundEscrow { .. } => StepKind::FundEscrow,
+            ScriptOp::ReleaseEscrow { .. } => StepKind::ReleaseEscrow,
+            ScriptOp::RefundEscrow { .. } => StepKind::RefundEscrow,
+        }
+    }
+
+    /// Outputs later steps may reference; the first is the default.
+    fn outputs(&self) -> &'static [&'static str] {
+        match self {
+            ScriptOp::MintProperty { .. } => {
+                &["note_id", "tx_id", "property_id", "owner_account_id"]
+            }
+            ScriptOp::CreateEscrow { .. } => &["escrow_account_id"],
+            _ => &["tx_id"],
+        }
+    }
+
+    /// Every text field, for resolving references.
+    fn fields_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            ScriptOp::MintProperty {
+                property_id,
+                owner_account_id,
+                ipfs_cid,
+                ..
+            } => vec![property_id, owner_account_id, ipfs_cid],
+            ScriptOp::ConsumeNote {
+                note_id,
+                account_id,
+            } => vec![note_id, account_id],
+            ScriptOp::TransferProperty {
+                property_id,
+                to_account_id,
+            } => vec![property_id, to_account_id],
+            ScriptOp::CreateEscrow {
+                buyer_account_id,
+                seller_account_id,
+                property_id,
+                ..
+            } => {
+                let mut fields = vec![buyer_account_id, seller_account_id];
+                fields.extend(property_id.as_mut());
+                fields
+            }
+            ScriptOp::FundEscrow { escrow_account_id }
+            | ScriptOp::ReleaseEscrow { escrow_account_id }
+            | ScriptOp::RefundEscrow { escrow_account_id } => vec![escrow_account_id],
+        }
+    }
+
+    /// Account fields, checked up front unless they are references.
+    fn accounts(&self) -> Vec<&str> {
+        match self {
+            ScriptOp::MintProperty {
+                owner_account_id, ..
+            } => vec![owner_account_id],
+            ScriptOp::ConsumeNote { account_id, .. } => vec![account_id],
+            ScriptOp::TransferProperty { to_account_id, .. } => vec![to_account_id],
+            ScriptOp::CreateEscrow {
+                buyer_account_id,
+                seller_account_id,
+                ..
+            } => vec![buyer_account_id, seller_account_id],
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptStep {
+    /// Name later steps reference this one by
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(flatten)]
+    pub op: ScriptOp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptPlan {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub steps: Vec<ScriptStep>,
+}
+
+impl ScriptPlan {
+    /// Parses and checks a plan sent as JSON, or as YAML when `content_type`
+    /// says so.
+    pub fn parse(body: &str, content_type: Option<&str>) -> Result<Self> {
+        let yaml = content_type.is_some_and(|t| t.contains("yaml"));
+        let plan: ScriptPlan = if yaml {
+            serde_yaml::from_str(body).map_err(|e| anyhow::anyhow!("Invalid script: {}", e))?
+        } else {
+            serde_json::from_str(body).map_err(|e| anyhow::anyhow!("Invalid script: {}", e))?
+        };
+        plan.validate()?;
+        Ok(plan)
+    }
+
+    /// Checks ids and references (see module docs).
+    fn validate(&self) -> Result<()> {
+        if self.steps.is_empty() {
+            return Err(anyhow::anyhow!("A script needs at least one step"));
+        }
+        if self.steps.len() > MAX_SCRIPT_STEPS {
+            return Err(anyhow::anyhow!(
+                "A script may have at most {} steps",
+                MAX_SCRIPT_STEPS
+            ));
+        }
+
+        let mut earlier: HashMap<&str, &ScriptOp> = HashMap::new();
+        for (index, step) in self.steps.iter().enumerate() {
+            let position = index + 1;
+            for field in step.op.clone().fields_mut() {
+                let Some((id, output)) = reference(field) else {
+                    continue;
+                };
+                let source = earlier.get(id).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Step {} references {}, which is not an earlier step",
+                        position,
+                        id
+                    )
+                })?;
+                if let Some(output) = output.filter(|o| !source.outputs().contains(o)) {
+                    return Err(anyhow::anyhow!(
+                        "Step {} references {}.{}; {} outputs {}",
+                        position,
+                        id,
+                        output,
+                        id,
+                        source.outputs().join(", ")
+                    ));
+                }
+            }
+            if let ScriptOp::CreateEscrow { amount: 0, .. } = step.op {
+                return Err(anyhow::anyhow!(
+                    "Step {}: amount must be positive",
+                    position
+                ));
+            }
+
+            if let Some(id) = &step.id {
+                let valid = !id.is_empty()
+                    && id
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+                if !valid {
+                    return Err(anyhow::anyhow!(
+                        "Step {}: id must be letters, digits, '_' or '-'",
+                        position
+                    ));
+                }
+                if earlier.insert(id, &step.op).is_some() {
+                    return Err(anyhow::anyhow!("Step {}: duplicate id {}", position, id));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The step id and output of a "${id}" / "${id.output}" reference.
+fn reference(value: &str) -> Option<(&str, Option<&str>)> {
+    let inner = value.strip_prefix("${")?.strip_suffix('}')?;
+    Some(match inner.split_once('.') {
+        Some((id, output)) => (id, Some(output)),
+        None => (inner, None),
+    })
+}
+
+/// A step's operation with its references replaced by the outputs of the
+/// steps they name.
+fn resolve(saga: &Saga, steps: &[ScriptStep], index: usize) -> Result<ScriptOp> {
+    let mut op = steps[index].op.clone();
+    for field in op.fields_mut() {
+        let Some((id, output)) = reference(field) else {
+            continue;
+        };
+        let source = steps[..index]
+            .iter()
+            .position(|s| s.id.as_deref() == Some(id))
+            .ok_or_else(|| anyhow::anyhow!("Unknown step {}", id))?;
+        let output = output.unwrap_or(steps[source].op.outputs()[0]);
+        let value = saga.steps[source]
+            .outputs
+            .get(output)
+            .ok_or_else(|| anyhow::anyhow!("Step {} did not output {}", id, output))?;
+        *field = value.clone();
+    }
+    Ok(op)
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    StartScript {
+        plan: ScriptPlan,
+        api_key: Option<String>,
+    } -> Saga;
+    |client, op| client.start_script(op.plan, op.api_key.as_deref())
+}
+
+impl MidenClientWrapper {
+    /// Records a parsed script; the caller then drives it like any saga (see
+    /// main.rs). Admin only.
+    pub fn start_script(&mut self, plan: ScriptPlan, api_key: Option<&str>) -> Result<Saga> {
+        let admin = Approver::from(self.admin_principal(api_key, "admin scripts")?);
+        for (index, step) in plan.steps.iter().enumerate() {
+            for account in step.op.accounts() {
+                if reference(account).is_none() {
+                    self.account_hex(account)
+                        .map_err(|e| anyhow::anyhow!("Step {}: {}", index + 1, e))?;
+                }
+            }
+        }
+
+        let kinds: Vec<StepKind> = plan.steps.iter().map(|s| s.op.kind()).collect();
+        let saga = self
+            .sagas
+            .create(
+                SagaInput::Script {
+                    name: plan.name,
+                    steps: plan.steps,
+                },
+                &kinds,
+                SagaContext::default(),
+                Some(admin),
+            )?
+            .clone();
+        tracing::info!(
+            "📜 Script {} started by API key {}: {} step(s)",
+            saga.saga_id,
+            saga.requested_by.as_ref().map_or(0, |r| r.key_id),
+            saga.steps.len()
+        );
+
+        Ok(saga)
+    }
+
+    /// Runs step `index` of a script. Returns its transaction ID, when it has
+    /// one, and its outputs.
+    pub(crate) async fn run_script_step(
+        &mut self,
+        saga: &Saga,
+        index: usize,
+    ) -> Result<(Option<String>, BTreeMap<String, String>)> {
+        let SagaInput::Script { steps, .. } = &saga.input else {
+            return Err(anyhow::anyhow!("Saga {} is not a script", saga.saga_id));
+        };
+        let account = |wrapper: &Self, account: &str| -> Result<AccountId> {
+            Ok(AccountId::from_hex(&wrapper.account_hex(account)?)?)
+        };
+
+        let mut outputs = BTreeMap::new();
+        let tx_id = match resolve(saga, steps, index)? {
+            ScriptOp::MintProperty {
+                property_id,
+                owner_account_id,
+                ipfs_cid,
+                property_type,
+                price,
+            } => {
+                let (tx_id, note_id) = self
+                    .mint_property_nft(
+                        &property_id,
+                        &owner_account_id,
+                        &ipfs_cid,
+                        property_type,
+                        &price,
+                    )
+                    .await?;
+                let owner = self
+                    .records
+                    .properties
+                    .get(&property_id)
+                    .map(|p| p.owner_account_id.clone())
+                    .unwrap_or(owner_account_id);
+                outputs.insert("note_id".to_string(), note_id);
+                outputs.insert("property_id".to_string(), property_id);
+                outputs.insert("owner_account_id".to_string(), owner);
+                tx_id
+            }
+            ScriptOp::ConsumeNote {
+                note_id,
+                account_id,
+            } => {
+                let account = self.account_hex(&account_id)?;
+                self.consume_note(&note_id, Some(account)).await?
+            }
+            ScriptOp::TransferProperty {
+                property_id,
+                to_account_id,
+            } => {
+                let to = self.account_hex(&to_account_id)?;
+                self.transfer_property(&property_id, &to).await?
+            }
+            ScriptOp::CreateEscrow {
+                buyer_account_id,
+                seller_account_id,
+                amount,
+                property_id,
+            } => {
+                let buyer = account(self, &buyer_account_id)?;
+                let seller = account(self, &seller_account_id)?;
+                let escrow = self
+                    .create_escrow_unchecked(buyer, seller, amount, property_id.as_deref())
+                    .await?;
+                outputs.insert(
+                    "escrow_account_id".to_string(),
+                    account_id_to_hex(escrow.escrow_account_id),
+                );
+                return Ok((None, outputs));
+            }
+            ScriptOp::FundEscrow { escrow_account_id } => {
+                let escrow = self.recorded_escrow(&escrow_account_id.to_lowercase())?;
+                self.fund_escrow_unchecked(&escrow, None).await?
+            }
+            ScriptOp::ReleaseEscrow { escrow_account_id } => {
+                let escrow = self.recorded_escrow(&escrow_account_id.to_lowercase())?;
+                let requested_by = saga.requested_by.clone();
+                self.require_release_approval_from(&escrow, |_| {
+                    requested_by.ok_or_else(|| {
+                        EscrowAuthError::Unauthenticated("Escrow approvals require API keys".into())
+                            .into()
+                    })
+                })?;
+                self.release_escrow_unchecked(&escrow).await?
+            }
+            ScriptOp::RefundEscrow { escrow_account_id } => {
+                let escrow = self.recorded_escrow(&escrow_account_id.to_lowercase())?;
+                self.refund_escrow_unchecked(&escrow).await?
+            }
+        };
+
+        outputs.insert("tx_id".to_string(), tx_id.clone());
+        Ok((Some(tx_id), outputs))
+    }
+}
@@ -0,0 +1,482 @@
+// src/search.rs
+//
+// Listing search
+//
+// Owners describe their listing with PUT /listings/:property_id/details
+// (address, description and size in square meters); details are kept in their
+// own JSON document next to the service records. Coordinates come from the
+// property's location (geo.rs).
+//
+// GET /search queries an SQLite FTS5 index over properties and their details:
+// - q: full-text search on address and description (every word must match, as
+//   a prefix: "amst cana" finds "Amsterdam, Keizersgracht canal house")
+// - min_price / max_price: minor units of `currency` (PRICE_CURRENCY when
+//   absent); properties priced in another currency are left out
+// - min_size / max_size: square meters
+// - bbox: "min_lon,min_lat,max_lon,max_lat"; only located properties match,
+//   and min_lon > max_lon crosses the antimeridian
+// - property_type, cursor, limit
+// Text matches are ranked by relevance (bm25), other searches list the newest
+// properties first.
+//
+// The index lives in memory: it is rebuilt from the records at startup and
+// updated when a property is minted, located, its details change or its listing
+// becomes confidential or public. Confidential listings are found by text, size and
+// location, but their price is neither indexed nor returned.
+
+use anyhow::Result;
+use rusqlite::{params_from_iter, types::Value, Connection};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{geo::BoundingBox, MidenClientWrapper};
+
+pub const MAX_ADDRESS_LEN: usize = 256;
+pub const MAX_DESCRIPTION_LEN: usize = 4000;
+pub const DEFAULT_SEARCH_LIMIT: usize = 20;
+pub const MAX_SEARCH_LIMIT: usize = 100;
+
+const SCHEMA: &str = "
+    CREATE TABLE properties (
+        property_id TEXT PRIMARY KEY,
+        property_type INTEGER NOT NULL,
+        price INTEGER,
+        price_currency TEXT,
+        confidential INTEGER NOT NULL,
+        address TEXT,
+        description TEXT,
+        size_sqm REAL,
+        latitude REAL,
+        longitude REAL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE VIRTUAL TABLE property_text USING fts5(
+        property_id UNINDEXED,
+        address,
+        description,
+        tokenize = 'unicode61 remove_diacritics 2'
+    );
+";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ListingDetailsInput {
+    pub address: Option<String>,
+    pub description: Option<String>,
+    pub size_sqm: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListingDetails {
+    pub address: Option<String>,
+    pub description: Option<String>,
+    pub size_sqm: Option<f64>,
+    pub updated_at: i64,
+}
+
+/// Descriptive listing details per property, set by owners.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ListingDetailsStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    details: BTreeMap<String, ListingDetails>,
+}
+
+impl ListingDetailsStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<ListingDetailsStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            ListingDetailsStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, property_id: &str) -> Option<&ListingDetails> {
+        self.details.get(property_id)
+    }
+
+    /// Replaces the details of a property.
+    pub fn set(&mut self, property_id: &str, input: ListingDetailsInput) -> Result<ListingDetails> {
+        let details = ListingDetails {
+            address: input.address,
+            description: input.description,
+            size_sqm: input.size_sqm,
+            updated_at: chrono::Utc::now().timestamp(),
+        };
+        self.details
+            .insert(property_id.to_string(), details.clone());
+        self.save()?;
+        Ok(details)
+    }
+}
+
+/// A property as indexed.
+#[derive(Debug, Clone)]
+pub struct IndexedProperty {
+    pub property_id: String,
+    pub property_type: u8,
+    /// None for confidential listings
+    pub price: Option<u64>,
+    pub price_currency: Option<String>,
+    pub confidential: bool,
+    pub details: Option<ListingDetails>,
+    /// Latitude and longitude
+    pub location: Option<(f64, f64)>,
+    pub created_at: i64,
+}
+
+/// Query string of GET /search.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SearchQuery {
+    pub q: Option<String>,
+    pub property_type: Option<u8>,
+    pub min_price: Option<u64>,
+    pub max_price: Option<u64>,
+    /// Currency of the price range
+    pub currency: Option<String>,
+    pub min_size: Option<f64>,
+    pub max_size: Option<f64>,
+    /// "min_lon,min_lat,max_lon,max_lat"
+    pub bbox: Option<String>,
+    #[serde(default)]
+    pub cursor: usize,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub property_id: String,
+    pub property_type: u8,
+    pub confidential: bool,
+    pub price: Option<u64>,
+    pub price_currency: Option<String>,
+    pub address: Option<String>,
+    pub description: Option<String>,
+    pub size_sqm: Option<f64>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Matching text with the terms marked, for text searches
+    pub snippet: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResults {
+    pub total: usize,
+    pub results: Vec<SearchHit>,
+    /// Cursor for the following page; None when this was the last one
+    pub next_cursor: Option<usize>,
+}
+
+/// Words of a search as an FTS5 query: every word as a quoted prefix term.
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|word| word.replace('"', ""))
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"*", word))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Prices are stored as SQLite integers.
+fn sql_amount(amount: u64) -> Value {
+    Value::Integer(i64::try_from(amount).unwrap_or(i64::MAX))
+}
+
+/// In-memory full-text and range index over properties.
+#[derive(Debug)]
+pub struct SearchIndex {
+    db: Connection,
+}
+
+impl SearchIndex {
+    pub fn new() -> Result<Self> {
+        let db = Connection::open_in_memory()?;
+        db.execute_batch(SCHEMA)?;
+        Ok(Self { db })
+    }
+
+    pub fn clear(&mut self) -> Result<()> {
+        self.db
+            .execute_batch("DELETE FROM properties; DELETE FROM property_text;")?;
+        Ok(())
+    }
+
+    pub fn upsert(&mut self, property: &IndexedProperty) -> Result<()> {
+        let details = property.details.as_ref();
+        let address = details.and_then(|d| d.address.clone());
+        let description = details.and_then(|d| d.description.clone());
+
+        let tx = self.db.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO properties (property_id, property_type, price,
+                 price_currency, confidential, address, description, size_sqm, latitude,
+                 longitude, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params_from_iter([
+                Value::Text(property.property_id.clone()),
+                Value::Integer(property.property_type as i64),
+                property.price.map(sql_amount).unwrap_or(Value::Null),
+                property
+                    .price_currency
+                    .clone()
+                    .map(Value::Text)
+                    .unwrap_or(Value::Null),
+                Value::Integer(property.confidential as i64),
+                address.clone().map(Value::Text).unwrap_or(Value::Null),
+                description.clone().map(Value::Text).unwrap_or(Value::Null),
+                details
+                    .and_then(|d| d.size_sqm)
+                    .map(Value::Real)
+                    .unwrap_or(Value::Null),
+                property
+                    .location
+                    .map(|(latitude, _)| Value::Real(latitude))
+                    .unwrap_or(Value::Null),
+                property
+                    .location
+                    .map(|(_, longitude)| Value::Real(longitude))
+                    .unwrap_or(Value::Null),
+                Value::Integer(property.created_at),
+            ]),
+        )?;
+        tx.execute(
+            "DELETE FROM property_text WHERE property_id = ?1",
+            [&property.property_id],
+        )?;
+        tx.execute(
+            "INSERT INTO property_text (property_id, address, description) VALUES (?1, ?2, ?3)",
+            params_from_iter([
+                Value::Text(property.property_id.clone()),
+                Value::Text(address.unwrap_or_default()),
+                Value::Text(description.unwrap_or_default()),
+            ]),
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Runs a search; price ranges apply to `default_currency` unless the
+    /// query names one.
+    pub fn search(&self, query: &SearchQuery, default_currency: &str) -> Result<SearchResults> {
+        let text = query.q.as_deref().and_then(fts_query);
+        let mut conditions: Vec<String> = Vec::new();
+        let mut params: Vec<Value> = Vec::new();
+        let mut condition = |sql: &str, values: Vec<Value>| {
+            conditions.push(sql.to_string());
+            params.extend(values);
+        };
+
+        if let Some(text) = &text {
+            condition("property_text MATCH ?", vec![Value::Text(text.clone())]);
+        }
+        if let Some(property_type) = query.property_type {
+            condition(
+                "p.property_type = ?",
+                vec![Value::Integer(property_type as i64)],
+            );
+        }
+        let currency = query.currency.as_deref().unwrap_or(default_currency);
+        if query.min_price.is_some() || query.max_price.is_some() || query.currency.is_some() {
+            condition(
+                "p.price IS NOT NULL AND p.price_currency = ?",
+                vec![Value::Text(currency.to_uppercase())],
+            );
+        }
+        if let Some(min_price) = query.min_price {
+            condition("p.price >= ?", vec![sql_amount(min_price)]);
+        }
+        if let Some(max_price) = query.max_price {
+            condition("p.price <= ?", vec![sql_amount(max_price)]);
+        }
+        if let Some(min_size) = query.min_size {
+            condition("p.size_sqm >= ?", vec![Value::Real(min_size)]);
+        }
+        if let Some(max_size) = query.max_size {
+            condition("p.size_sqm <= ?", vec![Value::Real(max_size)]);
+        }
+        if let Some(bbox) = &query.bbox {
+            let bbox: BoundingBox = bbox.parse()?;
+            condition(
+                "p.latitude BETWEEN ? AND ?",
+                vec![Value::Real(bbox.min_lat), Value::Real(bbox.max_lat)],
+            );
+            let longitude = if bbox.min_lon <= bbox.max_lon {
+                "p.longitude BETWEEN ? AND ?"
+            } else {
+                "(p.longitude >= ? OR p.longitude <= ?)"
+            };
+            condition(
+                longitude,
+                vec![Value::Real(bbox.min_lon), Value::Real(bbox.max_lon)],
+            );
+        }
+
+        let from = if text.is_some() {
+            "properties p JOIN property_text ON property_text.property_id = p.property_id"
+        } else {
+            "properties p"
+        };
+        let filter = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let total: i64 = self.db.query_row(
+            &format!("SELECT COUNT(*) FROM {} {}", from, filter),
+            params_from_iter(params.iter()),
+            |row| row.get(0),
+        )?;
+
+        let (snippet, order) = if text.is_some() {
+            (
+                "snippet(property_text, -1, '<mark>', '</mark>', '…', 16)",
+                "bm25(property_text), p.property_id",
+            )
+        } else {
+            ("NULL", "p.created_at DESC, p.property_id")
+        };
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .clamp(1, MAX_SEARCH_LIMIT);
+        params.push(Value::Integer(limit as i64));
+        params.push(Value::Integer(query.cursor as i64));
+
+        let mut statement = self.db.prepare(&format!(
+            "SELECT p.property_id, p.property_type, p.confidential, p.price, p.price_currency,
+                 p.address, p.description, p.size_sqm, p.latitude, p.longitude, {},
+                 p.created_at
+             FROM {} {} ORDER BY {} LIMIT ? OFFSET ?",
+            snippet, from, filter, order
+        ))?;
+        let results = statement
+            .query_map(params_from_iter(params.iter()), |row| {
+                Ok(SearchHit {
+                    property_id: row.get(0)?,
+                    property_type: row.get(1)?,
+                    confidential: row.get(2)?,
+                    price: row.get::<_, Option<i64>>(3)?.map(|p| p as u64),
+                    price_currency: row.get(4)?,
+                    address: row.get(5)?,
+                    description: row.get(6)?,
+                    size_sqm: row.get(7)?,
+                    latitude: row.get(8)?,
+                    longitude: row.get(9)?,
+                    snippet: row.get(10)?,
+                    created_at: row.get(11)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let total = total as usize;
+        let end = query.cursor.saturating_add(results.len());
+        Ok(SearchResults {
+            total,
+            next_cursor: (end < total && !results.is_empty()).then_some(end),
+            results,
+        })
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    SetListingDetails {
+        property_id: String,
+        input: ListingDetailsInput,
+        api_key: Option<String>,
+    } -> ListingDetails;
+    |client, op| client.set_listing_details(&op.property_id, op.input, op.api_key.as_deref());
+
+    SearchListings {
+        query: SearchQuery,
+    } -> SearchResults;
+    |client, op| client.search_listings(&op.query)
+}
+
+impl MidenClientWrapper {
+    fn indexed_property(&self, property_id: &str) -> Option<IndexedProperty> {
+        let property = self.records.properties.get(property_id)?;
+        let confidential = self.confidential_listings.is_confidential(property_id);
+        Some(IndexedProperty {
+            property_id: property.property_id.clone(),
+            property_type: property.property_type,
+            price: (!confidential).then_some(property.price),
+            price_currency: (!confidential).then(|| self.price_currency(property).to_string()),
+            confidential,
+            details: self.listing_details.get(property_id).cloned(),
+            location: property
+                .location
+                .as_ref()
+                .map(|l| (l.latitude, l.longitude)),
+            created_at: property.created_at,
+        })
+    }
+
+    /// Brings a property's index entry up to date. Failures are logged: the
+    /// index is rebuilt at the next start.
+    pub(crate) fn index_property(&mut self, property_id: &str) {
+        let Some(property) = self.indexed_property(property_id) else {
+            return;
+        };
+        if let Err(e) = self.search_index.upsert(&property) {
+            tracing::error!("Failed to index property {}: {}", property_id, e);
+        }
+    }
+
+    /// Indexes every recorded property; returns how many.
+    pub fn rebuild_search_index(&mut self) -> Result<usize> {
+        self.search_index.clear()?;
+        let property_ids: Vec<String> = self.records.properties.keys().cloned().collect();
+        for property_id in &property_ids {
+            if let Some(property) = self.indexed_property(property_id) {
+                self.search_index.upsert(&property)?;
+            }
+        }
+        Ok(property_ids.len())
+    }
+
+    /// Sets the details of a listing. Needs an API key bound to the owner.
+    pub fn set_listing_details(
+        &mut self,
+        property_id: &str,
+        input: ListingDetailsInput,
+        api_key: Option<&str>,
+    ) -> Result<ListingDetails> {
+        let owner_hex = self
+            .records
+            .properties
+            .get(property_id)
+            .map(|p| p.owner_account_id.to_lowercase())
+            .ok_or_else(|| anyhow::anyhow!("Listing {} not found", property_id))?;
+        self.authorize_account(api_key, &owner_hex, "owner")?;
+
+        let details = self.listing_details.set(property_id, input)?;
+        self.index_property(property_id);
+        tracing::info!("Updated listing details of {}", property_id);
+        Ok(details)
+    }
+
+    pub fn search_listings(&self, query: &SearchQuery) -> Result<SearchResults> {
+        self.search_index.search(query, &self.config.price_currency)
+    }
+}
@@ -0,0 +1,467 @@
+// src/secrets.rs
+//
+// Master secret for account seeds
+//
+// Without one, account init seeds, signing keys and the ClientRng coin seed
+// are drawn from the OS RNG on every start, and the accounts exist only in the
+// keystore and store files. With MASTER_SECRET_KEY set, the service keeps 32
+// bytes of master entropy in MASTER_SECRET_PATH, wrapped (AES-256-GCM) under
+// that key, and derives everything from it with HKDF-SHA256:
+// - per alias ("alice", "bob", "faucet"): the account init seed and the seed
+//   of its signing key
+// - per boot: the coin seed, from a boot counter kept in the file, so note
+//   serial numbers never repeat across restarts
+// - per escrow deal: the init seed and signing key seed of a fresh escrow
+//   account, from a deal counter kept in the file, so no two deals ever share
+//   an account
+// - per custodial wallet: the same for the wallet the service keeps for an
+//   email-onboarded user (custodial.rs), from a wallet counter
+//
+// The file and the key are enough to recover every account: the same seeds
+// rebuild the same accounts, and the keystore is refilled with the same keys
+// on every start. Escrow records keep their deal number and generation, so an
+// escrow account and its key can be rebuilt from the record alone
+// (POST /admin/escrows/rebuild).
+//
+// Rotation (POST /admin/secrets/rotate) adds a new entropy generation, used by
+// every later derivation. Aliases stay bound to the generation their account
+// was created under, since an account's ID and auth key are fixed by its
+// seeds; retired generations are kept so those accounts stay recoverable.
+//
+// Re-keying: to replace MASTER_SECRET_KEY, start once with the old key as
+// MASTER_SECRET_PREVIOUS_KEY. Generations that only open under the old key are
+// re-wrapped under the new one and saved.
+//
+// Neither touches the keystore (MIDEN_KEYSTORE_PATH), which is out of scope
+// here. The client's filesystem keystore writes each signing key to its own
+// file unencrypted and has no way to seal them, and an existing account's key
+// cannot be swapped: the auth component the accounts are built with exports no
+// procedure that changes the public key it stores. Rotation therefore only
+// protects accounts derived afterwards. Keys already in the keystore stay
+// valid and are guarded by the directory's permissions alone; the derived ones
+// can be written back from the master secret, but accounts created from random
+// seeds before MASTER_SECRET_KEY was set have no other copy.
+
+use anyhow::Result;
+use hkdf::Hkdf;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+use crate::{
+    field_encryption::{decrypt, encrypt, MasterKey},
+    read_cache::Touched,
+    MidenClientWrapper,
+};
+
+const NONCE_LEN: usize = 12;
+const HKDF_SALT: &[u8] = b"obscura-master-secret-v1";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Generation {
+    generation: u32,
+    created_at: i64,
+    retired_at: Option<i64>,
+    nonce: String,
+    sealed_entropy: String,
+}
+
+/// Generation an alias derives its account seeds from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AliasBinding {
+    pub alias: String,
+    pub generation: u32,
+    /// Account built from the derived seeds (hex)
+    pub account_id: Option<String>,
+    pub bound_at: i64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SecretFile {
+    #[serde(default)]
+    generations: BTreeMap<u32, Generation>,
+    #[serde(default)]
+    bindings: BTreeMap<String, AliasBinding>,
+    /// Starts of the service, for per-boot coin seeds
+    #[serde(default)]
+    boots: u64,
+    /// Escrow deals derived so far
+    #[serde(default)]
+    escrow_deals: u64,
+    /// Custodial wallets derived so far
+    #[serde(default)]
+    custodial_wallets: u64,
+}
+
+/// Master entropy generations and the aliases bound to them.
+pub struct MasterSecret {
+    path: PathBuf,
+    key: MasterKey,
+    file: SecretFile,
+    entropy: HashMap<u32, [u8; 32]>,
+}
+
+impl std::fmt::Debug for MasterSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the entropy
+        f.debug_struct("MasterSecret")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+fn wrap_aad(generation: u32) -> Vec<u8> {
+    format!("obscura-master-secret:{}", generation).into_bytes()
+}
+
+impl MasterSecret {
+    /// Opens (or creates) the secret file and counts a boot. Generations that
+    /// only open under `previous_key` are re-wrapped under `key`.
+    pub fn load(
+        path: impl Into<PathBuf>,
+        key: MasterKey,
+        previous_key: Option<&MasterKey>,
+    ) -> Result<Self> {
+        let path = path.into();
+
+        let file = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<SecretFile>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            SecretFile::default()
+        };
+
+        let mut secret = Self {
+            path,
+            key,
+            file,
+            entropy: HashMap::new(),
+        };
+
+        let mut rewrapped = 0;
+        let generations: Vec<Generation> = secret.file.generations.values().cloned().collect();
+        for generation in generations {
+            let sealed = [
+                hex::decode(&generation.nonce)?,
+                hex::decode(&generation.sealed_entropy)?,
+            ]
+            .concat();
+            let aad = wrap_aad(generation.generation);
+
+            let opened = match decrypt(secret.key.as_bytes(), &aad, &sealed) {
+                Ok(entropy) => entropy,
+                Err(_) => {
+                    let entropy = previous_key
+                        .and_then(|previous| decrypt(previous.as_bytes(), &aad, &sealed).ok())
+                        .ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "Master secret key does not open generation {} in {}",
+                                generation.generation,
+                                secret.path.display()
+                            )
+                        })?;
+                    rewrapped += 1;
+                    entropy
+                }
+            };
+            let entropy: [u8; 32] = opened.try_into().map_err(|_| {
+                anyhow::anyhow!(
+                    "Master secret generation {} is not 32 bytes",
+                    generation.generation
+                )
+            })?;
+            secret.entropy.insert(generation.generation, entropy);
+        }
+
+        if secret.file.generations.is_empty() {
+            secret.add_generation()?;
+            tracing::info!(
+                generation = 1,
+                path = %secret.path.display(),
+                "Created master secret"
+            );
+        }
+        if rewrapped > 0 {
+            // Re-wrap everything under the current key
+            for generation in secret.entropy.keys().copied().collect::<Vec<_>>() {
+                secret.seal(generation)?;
+            }
+            tracing::info!(
+                generations = rewrapped,
+                "Re-wrapped master secret generations under the current key"
+            );
+        }
+
+        secret.file.boots += 1;
+        secret.save()?;
+        Ok(secret)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(&self.file)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Wraps a generation's entropy under the current key into the file.
+    fn seal(&mut self, generation: u32) -> Result<()> {
+        let entropy = self.entropy[&generation];
+        let sealed = encrypt(self.key.as_bytes(), &wrap_aad(generation), &entropy)?;
+        let (nonce, sealed_entropy) = sealed.split_at(NONCE_LEN);
+
+        let now = chrono::Utc::now().timestamp();
+        let entry = self
+            .file
+            .generations
+            .entry(generation)
+            .or_insert_with(|| Generation {
+                generation,
+                created_at: now,
+                retired_at: None,
+                nonce: String::new(),
+                sealed_entropy: String::new(),
+            });
+        entry.nonce = hex::encode(nonce);
+        entry.sealed_entropy = hex::encode(sealed_entropy);
+        Ok(())
+    }
+
+    /// Draws fresh entropy as the new current generation (not yet saved).
+    fn add_generation(&mut self) -> Result<u32> {
+        let generation = self.current_generation() + 1;
+        let mut entropy = [0u8; 32];
+        rand::rng().fill_bytes(&mut entropy);
+        self.entropy.insert(generation, entropy);
+        self.seal(generation)?;
+        Ok(generation)
+    }
+
+    pub fn current_generation(&self) -> u32 {
+        self.file
+            .generations
+            .keys()
+            .next_back()
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// HKDF-SHA256 of a generation's entropy; `purpose` and `label` name the
+    /// derived seed.
+    fn derive(&self, generation: u32, purpose: &str, label: &str) -> Result<[u8; 32]> {
+        let entropy = self
+            .entropy
+            .get(&generation)
+            .ok_or_else(|| anyhow::anyhow!("Master secret generation {} is missing", generation))?;
+        let info = format!("{}:{}", purpose, label);
+        let mut seed = [0u8; 32];
+        Hkdf::<Sha256>::new(Some(HKDF_SALT), entropy)
+            .expand(info.as_bytes(), &mut seed)
+            .map_err(|_| anyhow::anyhow!("Seed derivation failed"))?;
+        Ok(seed)
+    }
+
+    /// Account init seed and signing key seed of an alias, binding it to the
+    /// current generation on first use.
+    pub fn account_seeds(&mut self, alias: &str) -> Result<([u8; 32], [u8; 32])> {
+        let generation = match self.file.bindings.get(alias) {
+            Some(binding) => binding.generation,
+            None => {
+                let generation = self.current_generation();
+                self.file.bindings.insert(
+                    alias.to_string(),
+                    AliasBinding {
+                        alias: alias.to_string(),
+                        generation,
+                        account_id: None,
+                        bound_at: chrono::Utc::now().timestamp(),
+                    },
+                );
+                self.save()?;
+                generation
+            }
+        };
+        Ok((
+            self.derive(generation, "account-init", alias)?,
+            self.derive(generation, "auth-key", alias)?,
+        ))
+    }
+
+    /// Records the account an alias's seeds built.
+    pub fn bind_account(&mut self, alias: &str, account_id: &str) -> Result<()> {
+        let binding = self
+            .file
+            .bindings
+            .get_mut(alias)
+            .ok_or_else(|| anyhow::anyhow!("Alias {} has no master secret binding", alias))?;
+        match &binding.account_id {
+            Some(bound) if bound == account_id => return Ok(()),
+            Some(bound) => tracing::warn!(
+                alias,
+                account = account_id,
+                was = %bound,
+                "Seeds now build a different account; account code changed?"
+            ),
+            None => {}
+        }
+        binding.account_id = Some(account_id.to_string());
+        self.save()
+    }
+
+    /// Allocates the next escrow deal under the current generation. Returns
+    /// the deal number and generation to record with the escrow.
+    pub fn next_escrow_deal(&mut self) -> Result<(u64, u32)> {
+        self.file.escrow_deals += 1;
+        if let Err(e) = self.save() {
+            self.file.escrow_deals -= 1;
+            return Err(e);
+        }
+        Ok((self.file.escrow_deals, self.current_generation()))
+    }
+
+    /// Account init seed and signing key seed of an escrow deal.
+    pub fn escrow_seeds(&self, deal_id: u64, generation: u32) -> Result<([u8; 32], [u8; 32])> {
+        let label = format!("escrow-{}", deal_id);
+        Ok((
+            self.derive(generation, "account-init", &label)?,
+            self.derive(generation, "auth-key", &label)?,
+        ))
+    }
+
+    /// Allocates the next custodial wallet under the current generation.
+    /// Returns the wallet number and generation to record with its user.
+    pub fn next_custodial_wallet(&mut self) -> Result<(u64, u32)> {
+        self.file.custodial_wallets += 1;
+        if let Err(e) = self.save() {
+            self.file.custodial_wallets -= 1;
+            return Err(e);
+        }
+        Ok((self.file.custodial_wallets, self.current_generation()))
+    }
+
+    /// Account init seed and signing key seed of a custodial wallet.
+    pub fn custodial_wallet_seeds(
+        &self,
+        wallet_id: u64,
+        generation: u32,
+    ) -> Result<([u8; 32], [u8; 32])> {
+        let label = format!("custodial-{}", wallet_id);
+        Ok((
+            self.derive(generation, "account-init", &label)?,
+            self.derive(generation, "auth-key", &label)?,
+        ))
+    }
+
+    /// Four words for the ClientRng coin seed of this boot.
+    pub fn coin_seed(&self) -> Result<[u64; 4]> {
+        let bytes = self.derive(
+            self.current_generation(),
+            "client-rng",
+            &format!("boot-{}", self.file.boots),
+        )?;
+        let mut words = [0u64; 4];
+        for (i, chunk) in bytes.chunks_exact(8).enumerate() {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(chunk);
+            words[i] = u64::from_le_bytes(word);
+        }
+        Ok(words)
+    }
+
+    /// Adds a new generation and retires the current one.
+    pub fn rotate(&mut self) -> Result<u32> {
+        let retired = self.current_generation();
+        let generation = self.add_generation()?;
+        if let Some(entry) = self.file.generations.get_mut(&retired) {
+            entry.retired_at = Some(chrono::Utc::now().timestamp());
+        }
+        if let Err(e) = self.save() {
+            self.file.generations.remove(&generation);
+            self.entropy.remove(&generation);
+            if let Some(entry) = self.file.generations.get_mut(&retired) {
+                entry.retired_at = None;
+            }
+            return Err(e);
+        }
+        Ok(generation)
+    }
+
+    /// Generations and bindings, without key material.
+    pub fn summary(&self) -> serde_json::Value {
+        let generations: Vec<serde_json::Value> = self
+            .file
+            .generations
+            .values()
+            .map(|g| {
+                serde_json::json!({
+                    "generation": g.generation,
+                    "created_at": g.created_at,
+                    "retired_at": g.retired_at,
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "current_generation": self.current_generation(),
+            "boots": self.file.boots,
+            "escrow_deals": self.file.escrow_deals,
+            "custodial_wallets": self.file.custodial_wallets,
+            "generations": generations,
+            "bindings": self.file.bindings.values().collect::<Vec<_>>(),
+        })
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    /// Generations and alias bindings of the master secret (never key
+    /// material).
+    GetMasterSecret {
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.get_master_secret(op.api_key.as_deref());
+
+    RotateMasterSecret {
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.rotate_master_secret(op.api_key.as_deref());
+
+    /// Re-adds the derived escrow accounts and keys (escrow.rs).
+    RebuildEscrowAccounts {
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    touched: |_op| Some(Touched::All);
+    |client, op| client.rebuild_escrow_accounts(op.api_key.as_deref()).await
+}
+
+impl MidenClientWrapper {
+    /// Needs an admin key.
+    pub fn get_master_secret(&self, api_key: Option<&str>) -> Result<serde_json::Value> {
+        self.admin_principal(api_key, "the master secret")?;
+        self.master_secret
+            .as_ref()
+            .map(MasterSecret::summary)
+            .ok_or_else(not_configured)
+    }
+
+    /// Starts a new generation for later derivations; the keystore is left
+    /// as it is (see module docs). Needs an admin key.
+    pub fn rotate_master_secret(&mut self, api_key: Option<&str>) -> Result<serde_json::Value> {
+        self.admin_principal(api_key, "the master secret")?;
+        let secret = self.master_secret.as_mut().ok_or_else(not_configured)?;
+        let generation = secret.rotate()?;
+        tracing::info!(generation, "Rotated master secret");
+        Ok(secret.summary())
+    }
+}
+
+fn not_configured() -> anyhow::Error {
+    anyhow::anyhow!("Master secret is not configured (MASTER_SECRET_KEY)")
+}
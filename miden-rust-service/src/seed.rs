@@ -0,0 +1,71 @@
+// src/seed.rs
+//
+// Deterministic seed derivation for reproducible demos
+//
+// In deterministic mode every account init seed, signing key seed, and the
+// ClientRng coin seed is derived from a single configured master seed, so the
+// Alice / Bob / Faucet account IDs are identical across restarts.
+//
+// UNSAFE: anyone who knows the master seed can re-derive every signing key.
+// This mode is gated behind UNSAFE_DEMO_DETERMINISTIC_SEEDS and refused on
+// anything that does not look like a test network (see config.rs).
+
+use sha2::{Digest, Sha256};
+
+/// Domain-separated derivation of 32-byte seeds from a master seed.
+#[derive(Clone)]
+pub struct DeterministicSeeds {
+    master: [u8; 32],
+}
+
+impl std::fmt::Debug for DeterministicSeeds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the master seed
+        f.debug_struct("DeterministicSeeds").finish_non_exhaustive()
+    }
+}
+
+impl DeterministicSeeds {
+    /// Accepts any passphrase; it is hashed into the 32-byte master seed.
+    pub fn from_passphrase(passphrase: &str) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(b"obscura-demo-master-seed");
+        hasher.update(passphrase.as_bytes());
+        Self {
+            master: hasher.finalize().into(),
+        }
+    }
+
+    /// SHA-256(master || purpose || alias) with length-prefixed labels.
+    pub fn derive(&self, purpose: &str, alias: &str) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.master);
+        hasher.update((purpose.len() as u32).to_le_bytes());
+        hasher.update(purpose.as_bytes());
+        hasher.update((alias.len() as u32).to_le_bytes());
+        hasher.update(alias.as_bytes());
+        hasher.finalize().into()
+    }
+
+    /// Seed passed to `AccountBuilder::new` for the given account alias.
+    pub fn account_init_seed(&self, alias: &str) -> [u8; 32] {
+        self.derive("account-init", alias)
+    }
+
+    /// Seed for the RNG that generates the alias's signing key.
+    pub fn auth_key_seed(&self, alias: &str) -> [u8; 32] {
+        self.derive("auth-key", alias)
+    }
+
+    /// Four words for the ClientRng coin seed.
+    pub fn coin_seed(&self) -> [u64; 4] {
+        let bytes = self.derive("client-rng", "coin");
+        let mut words = [0u64; 4];
+        for (i, chunk) in bytes.chunks_exact(8).enumerate() {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(chunk);
+            words[i] = u64::from_le_bytes(word);
+        }
+        words
+    }
+}
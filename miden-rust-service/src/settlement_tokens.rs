@@ -0,0 +1,375 @@
+// src/settlement_tokens.rs
+//
+// Settlement tokens
+//
+// Escrows settle in the service token (PROP) unless the listing says
+// otherwise. Admins register other fungible faucets, such as a testnet
+// stablecoin, as accepted settlement tokens (POST /admin/settlement-tokens)
+// under a symbol and with the decimals of their token; GET /settlement-tokens
+// lists them after the service token. Symbols are 1 to 6 letters A-Z and may
+// not be the service token's or an ISO 4217 code prices are given in.
+//
+// The owner of a listing picks the token it settles in with PUT
+// /listings/:property_id/settlement-token ({"symbol": "USDT"}; PROP or null
+// for the service token), which is refused while an escrow for the property is
+// open. An escrow opened for a property takes the listing's token into its
+// record (asset_faucet_id); its amount is in base units of that token.
+// Funding sends that token and is refused when the listing has since moved to
+// another one. Escrows without a property settle in the service token.
+//
+// Registered tokens are kept in SETTLEMENT_TOKENS_PATH with the listings'
+// choices. Balances and closing documents show amounts of registered tokens
+// with their symbol and decimals.
+
+use anyhow::Result;
+use miden_client::account::{AccountId, AccountType};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    account_id_to_hex,
+    currency::{find_currency, SERVICE_TOKEN_DECIMALS, SERVICE_TOKEN_SYMBOL},
+    escrow::EscrowStatus,
+    MidenClientWrapper,
+};
+
+/// Longest symbol a Miden fungible faucet's token may have
+pub const MAX_SYMBOL_LEN: usize = 6;
+/// Most decimals a Miden fungible faucet's token may have
+pub const MAX_DECIMALS: u8 = 12;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SettlementTokenInput {
+    pub symbol: String,
+    /// Hex AccountId of the fungible faucet issuing the token
+    pub faucet_id: String,
+    pub decimals: u8,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ListingTokenInput {
+    /// None (or the service token's symbol) for the service token
+    pub symbol: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettlementToken {
+    pub symbol: String,
+    pub faucet_id: String,
+    pub decimals: u8,
+    /// Admin API key that registered the token
+    pub registered_by: u64,
+    pub registered_at: i64,
+}
+
+/// Normalizes a symbol, refusing those no faucet can have or that clash with
+/// the service token and price currencies.
+pub fn parse_symbol(symbol: &str) -> Result<String> {
+    let symbol = symbol.trim().to_uppercase();
+    if symbol.is_empty()
+        || symbol.len() > MAX_SYMBOL_LEN
+        || !symbol.chars().all(|c| c.is_ascii_uppercase())
+    {
+        return Err(anyhow::anyhow!(
+            "Token symbol must be 1 to {} letters A-Z, got {:?}",
+            MAX_SYMBOL_LEN,
+            symbol
+        ));
+    }
+    if find_currency(&symbol).is_some() {
+        return Err(anyhow::anyhow!(
+            "{} is already the service token or a price currency",
+            symbol
+        ));
+    }
+    Ok(symbol)
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SettlementTokenStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    tokens: BTreeMap<String, SettlementToken>,
+    /// Symbol each listing settles in; absent for the service token
+    #[serde(default)]
+    listings: BTreeMap<String, String>,
+}
+
+impl SettlementTokenStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<SettlementTokenStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            SettlementTokenStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Registers a token; symbol and faucet must both be new.
+    pub fn register(&mut self, token: SettlementToken) -> Result<SettlementToken> {
+        if self.tokens.contains_key(&token.symbol) {
+            return Err(anyhow::anyhow!(
+                "Conflict: token {} is already registered",
+                token.symbol
+            ));
+        }
+        if let Some(existing) = self.by_faucet(&token.faucet_id) {
+            return Err(anyhow::anyhow!(
+                "Conflict: faucet {} is already registered as {}",
+                token.faucet_id,
+                existing.symbol
+            ));
+        }
+        self.tokens.insert(token.symbol.clone(), token.clone());
+        self.save()?;
+        Ok(token)
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&SettlementToken> {
+        self.tokens.get(symbol)
+    }
+
+    pub fn by_faucet(&self, faucet_id: &str) -> Option<&SettlementToken> {
+        self.tokens
+            .values()
+            .find(|t| t.faucet_id.eq_ignore_ascii_case(faucet_id))
+    }
+
+    pub fn list(&self) -> Vec<&SettlementToken> {
+        self.tokens.values().collect()
+    }
+
+    /// Token a listing settles in; None for the service token.
+    pub fn listing_token(&self, property_id: &str) -> Option<&SettlementToken> {
+        self.listings
+            .get(property_id)
+            .and_then(|symbol| self.tokens.get(symbol))
+    }
+
+    pub fn set_listing_token(&mut self, property_id: &str, symbol: Option<String>) -> Result<()> {
+        match symbol {
+            Some(symbol) => self.listings.insert(property_id.to_string(), symbol),
+            None => self.listings.remove(property_id),
+        };
+        self.save()
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    RegisterSettlementToken {
+        input: SettlementTokenInput,
+        api_key: Option<String>,
+    } -> SettlementToken;
+    |client, op| client.register_settlement_token(op.input, op.api_key.as_deref());
+
+    ListSettlementTokens {} -> serde_json::Value;
+    |client, _op| Ok(client.list_settlement_tokens());
+
+    SetListingSettlementToken {
+        property_id: String,
+        input: ListingTokenInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| {
+        client.set_listing_settlement_token(&op.property_id, op.input, op.api_key.as_deref())
+    }
+}
+
+impl MidenClientWrapper {
+    pub fn register_settlement_token(
+        &mut self,
+        input: SettlementTokenInput,
+        api_key: Option<&str>,
+    ) -> Result<SettlementToken> {
+        let key_id = self
+            .admin_principal(api_key, "registering settlement tokens")?
+            .key_id;
+
+        let symbol = parse_symbol(&input.symbol)?;
+        let faucet = AccountId::from_hex(input.faucet_id.trim())
+            .map_err(|e| anyhow::anyhow!("Invalid faucet ID: {}", e))?;
+        if faucet.account_type() != AccountType::FungibleFaucet {
+            return Err(anyhow::anyhow!(
+                "{} is not a fungible faucet account",
+                input.faucet_id
+            ));
+        }
+        if Some(faucet) == self.faucet_account_id {
+            return Err(anyhow::anyhow!(
+                "{} is the service token's faucet",
+                input.faucet_id
+            ));
+        }
+
+        let token = self.settlement_tokens.register(SettlementToken {
+            symbol,
+            faucet_id: account_id_to_hex(faucet),
+            decimals: input.decimals,
+            registered_by: key_id,
+            registered_at: chrono::Utc::now().timestamp(),
+        })?;
+        tracing::info!(
+            "🪙 Registered settlement token {} (faucet {}) by API key {}",
+            token.symbol,
+            token.faucet_id,
+            key_id
+        );
+        Ok(token)
+    }
+
+    /// The service token followed by the registered tokens.
+    pub fn list_settlement_tokens(&self) -> serde_json::Value {
+        let service = serde_json::json!({
+            "symbol": SERVICE_TOKEN_SYMBOL,
+            "faucet_id": self.faucet_account_id.map(account_id_to_hex),
+            "decimals": SERVICE_TOKEN_DECIMALS,
+            "service_token": true,
+        });
+        let registered = self.settlement_tokens.list().into_iter().map(|token| {
+            let mut value = serde_json::json!(token);
+            value["service_token"] = false.into();
+            value
+        });
+        serde_json::Value::Array(std::iter::once(service).chain(registered).collect())
+    }
+
+    /// Sets the token a listing settles in. Needs an API key bound to the
+    /// owner, and no open escrow for the property.
+    pub fn set_listing_settlement_token(
+        &mut self,
+        property_id: &str,
+        input: ListingTokenInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let owner_hex = self
+            .records
+            .properties
+            .get(property_id)
+            .map(|p| p.owner_account_id.to_lowercase())
+            .ok_or_else(|| anyhow::anyhow!("Listing {} not found", property_id))?;
+        self.authorize_account(api_key, &owner_hex, "owner")?;
+
+        let symbol = match input.symbol.as_deref().map(|s| s.trim().to_uppercase()) {
+            None => None,
+            Some(symbol) if symbol == SERVICE_TOKEN_SYMBOL => None,
+            Some(symbol) => {
+                self.settlement_tokens
+                    .get(&symbol)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown settlement token: {}", symbol))?;
+                Some(symbol)
+            }
+        };
+        let open_escrow = self.records.escrows.values().find(|r| {
+            r.property_id.as_deref() == Some(property_id)
+                && matches!(r.status, EscrowStatus::Created | EscrowStatus::Funded)
+        });
+        if let Some(escrow) = open_escrow {
+            return Err(anyhow::anyhow!(
+                "Conflict: escrow {} for listing {} is open",
+                escrow.escrow_account_id,
+                property_id
+            ));
+        }
+
+        self.settlement_tokens
+            .set_listing_token(property_id, symbol.clone())?;
+        tracing::info!(
+            "Listing {} now settles in {}",
+            property_id,
+            symbol.as_deref().unwrap_or(SERVICE_TOKEN_SYMBOL)
+        );
+        Ok(serde_json::json!({
+            "property_id": property_id,
+            "settlement_token": symbol.as_deref().unwrap_or(SERVICE_TOKEN_SYMBOL),
+        }))
+    }
+
+    /// Symbol of the token a listing settles in.
+    pub(crate) fn listing_settlement_symbol(&self, property_id: &str) -> &str {
+        self.settlement_tokens
+            .listing_token(property_id)
+            .map(|t| t.symbol.as_str())
+            .unwrap_or(SERVICE_TOKEN_SYMBOL)
+    }
+
+    /// Faucet of the token escrows for `property_id` settle in: the
+    /// listing's, or the service token's without a property.
+    pub(crate) fn settlement_faucet(&self, property_id: Option<&str>) -> Result<AccountId> {
+        match property_id.and_then(|p| self.settlement_tokens.listing_token(p)) {
+            Some(token) => Ok(AccountId::from_hex(&token.faucet_id)?),
+            None => self
+                .faucet_account_id
+                .ok_or_else(|| anyhow::anyhow!("Faucet not initialized")),
+        }
+    }
+
+    /// Faucet an escrow is funded in: the one its record was opened with,
+    /// which must still be its listing's settlement token.
+    pub(crate) fn escrow_settlement_faucet(&self, escrow_hex: &str) -> Result<AccountId> {
+        let record = self.records.escrows.get(escrow_hex);
+        let expected = self.settlement_faucet(record.and_then(|r| r.property_id.as_deref()))?;
+        match record.and_then(|r| r.asset_faucet_id.as_deref()) {
+            Some(faucet_hex) if !faucet_hex.eq_ignore_ascii_case(&account_id_to_hex(expected)) => {
+                let symbol = |faucet: AccountId| {
+                    self.token_units(faucet)
+                        .map(|(symbol, _)| symbol)
+                        .unwrap_or_else(|| account_id_to_hex(faucet))
+                };
+                Err(anyhow::anyhow!(
+                    "Conflict: escrow {} was opened to settle in {} but its listing settles in {}",
+                    escrow_hex,
+                    AccountId::from_hex(faucet_hex)
+                        .map(symbol)
+                        .unwrap_or_else(|_| faucet_hex.to_string()),
+                    symbol(expected)
+                ))
+            }
+            _ => Ok(expected),
+        }
+    }
+
+    /// Symbol and decimals of the service token or a registered token.
+    pub(crate) fn token_units(&self, faucet_id: AccountId) -> Option<(String, u8)> {
+        if Some(faucet_id) == self.faucet_account_id {
+            return Some((SERVICE_TOKEN_SYMBOL.to_string(), SERVICE_TOKEN_DECIMALS));
+        }
+        self.settlement_tokens
+            .by_faucet(&account_id_to_hex(faucet_id))
+            .map(|t| (t.symbol.clone(), t.decimals))
+    }
+
+    /// Symbol, faucet and decimals of the service token or a registered token
+    /// by symbol.
+    pub(crate) fn token_by_symbol(&self, symbol: &str) -> Result<(String, AccountId, u8)> {
+        let symbol = symbol.trim().to_uppercase();
+        if symbol == SERVICE_TOKEN_SYMBOL {
+            let faucet = self
+                .faucet_account_id
+                .ok_or_else(|| anyhow::anyhow!("Faucet not initialized"))?;
+            return Ok((symbol, faucet, SERVICE_TOKEN_DECIMALS));
+        }
+        let token = self
+            .settlement_tokens
+            .get(&symbol)
+            .ok_or_else(|| anyhow::anyhow!("Unknown settlement token: {}", symbol))?;
+        Ok((symbol, AccountId::from_hex(&token.faucet_id)?, token.decimals))
+    }
+}
@@ -0,0 +1,461 @@
+// src/slo.rs
+//
+// Service level objectives per endpoint
+//
+// Every API request that matched a route is recorded against its endpoint
+// (method and route, without the /api/vN prefix): whether it succeeded and
+// how long it took. A request fails when it answers 5xx, which includes
+// requests shed under load (503) and deadline misses (504); 4xx answers are
+// the caller's and count as successes.
+//
+// Objectives:
+// - success rate: SLO_SUCCESS_TARGET percent (99.0 by default) for every
+//   endpoint, unless the endpoint sets its own
+// - latency: only where configured, as "pNN<threshold" — at most 100-NN
+//   percent of requests may take longer than the threshold
+//
+// SLO_OBJECTIVES lists per-endpoint objectives separated by ';', each a route
+// (optionally preceded by a method) followed by its objectives, e.g.
+// "POST /mint-property p95<45s; /get-balance/:account_id p99<500ms success>=99.9".
+// An objective without a method applies to every method of the route.
+//
+// Burn rate is how fast an objective spends its error budget: the fraction of
+// bad requests (failed, or slower than the threshold) divided by the fraction
+// the objective allows. 1.0 spends the budget exactly over the objective's
+// period; GET /admin/slo reports it over the last 5 minutes and the last hour,
+// and flags an endpoint as alerting when both exceed SLO_BURN_RATE_ALERT
+// (14.4 by default: a 30-day budget gone in two days).
+//
+// Like the queue stats, the figures live in memory only and are read without
+// going through the client task.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::bench::{millis, Percentiles};
+
+/// Requests per endpoint the latency percentiles are computed over
+const LATENCY_WINDOW: usize = 1_000;
+/// Burn rate windows, in minutes
+const SHORT_WINDOW_MINUTES: i64 = 5;
+const LONG_WINDOW_MINUTES: i64 = 60;
+
+/// Latency objective: `percentile` percent of requests within `threshold`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyObjective {
+    pub percentile: f64,
+    pub threshold: Duration,
+}
+
+/// Objectives of one route, or of one method of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EndpointObjectives {
+    pub method: Option<String>,
+    pub route: String,
+    /// Percent of requests that must succeed; SLO_SUCCESS_TARGET when unset
+    pub success_rate: Option<f64>,
+    pub latency: Vec<LatencyObjective>,
+}
+
+/// Configured objectives (see module docs).
+#[derive(Debug, Clone)]
+pub struct SloPolicy {
+    pub default_success_rate: f64,
+    pub burn_rate_alert: f64,
+    pub endpoints: Vec<EndpointObjectives>,
+}
+
+impl Default for SloPolicy {
+    fn default() -> Self {
+        Self {
+            default_success_rate: 99.0,
+            burn_rate_alert: 14.4,
+            endpoints: Vec::new(),
+        }
+    }
+}
+
+impl SloPolicy {
+    /// Builds the policy from the default success target, the alert threshold
+    /// and a "[METHOD] route objective...; ..." list.
+    pub fn parse(
+        default_success_rate: f64,
+        burn_rate_alert: f64,
+        objectives: &str,
+    ) -> Result<Self> {
+        check_percent(default_success_rate)?;
+        if burn_rate_alert <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "Burn rate alert threshold must be positive"
+            ));
+        }
+
+        let mut policy = Self {
+            default_success_rate,
+            burn_rate_alert,
+            endpoints: Vec::new(),
+        };
+        for entry in objectives
+            .split(';')
+            .map(str::trim)
+            .filter(|e| !e.is_empty())
+        {
+            let mut words = entry.split_whitespace().peekable();
+            let method = match words.peek() {
+                Some(word) if !word.starts_with('/') => words.next().map(|m| m.to_uppercase()),
+                _ => None,
+            };
+            let route = words
+                .next()
+                .filter(|route| route.starts_with('/'))
+                .ok_or_else(|| anyhow::anyhow!("Expected a route starting with '/': {}", entry))?;
+
+            let mut endpoint = EndpointObjectives {
+                method,
+                route: route.to_string(),
+                success_rate: None,
+                latency: Vec::new(),
+            };
+            for objective in words {
+                if let Some(rate) = objective.strip_prefix("success>=") {
+                    let rate = rate.parse::<f64>().map_err(|e| {
+                        anyhow::anyhow!("Invalid success rate for {}: {}", route, e)
+                    })?;
+                    check_percent(rate)?;
+                    endpoint.success_rate = Some(rate);
+                } else if let Some((percentile, threshold)) =
+                    objective.strip_prefix('p').and_then(|o| o.split_once('<'))
+                {
+                    let percentile = percentile
+                        .parse::<f64>()
+                        .map_err(|e| anyhow::anyhow!("Invalid percentile for {}: {}", route, e))?;
+                    check_percent(percentile)?;
+                    endpoint.latency.push(LatencyObjective {
+                        percentile,
+                        threshold: parse_threshold(threshold)?,
+                    });
+                } else {
+                    return Err(anyhow::anyhow!(
+                        "Unknown objective for {}: {} (expected pNN<duration or success>=percent)",
+                        route,
+                        objective
+                    ));
+                }
+            }
+            if endpoint.success_rate.is_none() && endpoint.latency.is_empty() {
+                return Err(anyhow::anyhow!("No objectives given for {}", route));
+            }
+            policy.endpoints.push(endpoint);
+        }
+        Ok(policy)
+    }
+
+    /// Objectives of an endpoint: the method-specific entry, else the
+    /// route-wide one.
+    fn objectives_for(&self, method: &str, route: &str) -> Option<&EndpointObjectives> {
+        let for_route = || self.endpoints.iter().filter(move |e| e.route == route);
+        for_route()
+            .find(|e| e.method.as_deref() == Some(method))
+            .or_else(|| for_route().find(|e| e.method.is_none()))
+    }
+}
+
+fn check_percent(value: f64) -> Result<()> {
+    if value > 0.0 && value < 100.0 {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Percentages must be between 0 and 100 (exclusive), got {}",
+            value
+        ))
+    }
+}
+
+/// Parses "500ms", "45s" or "2m".
+fn parse_threshold(threshold: &str) -> Result<Duration> {
+    let invalid = || {
+        anyhow::anyhow!(
+            "Invalid latency threshold {} (expected e.g. 500ms, 45s, 2m)",
+            threshold
+        )
+    };
+    let (value, unit_ms) = if let Some(value) = threshold.strip_suffix("ms") {
+        (value, 1)
+    } else if let Some(value) = threshold.strip_suffix('s') {
+        (value, 1_000)
+    } else if let Some(value) = threshold.strip_suffix('m') {
+        (value, 60_000)
+    } else {
+        return Err(invalid());
+    };
+    let value = value.parse::<u64>().map_err(|_| invalid())?;
+    if value == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_millis(value * unit_ms))
+}
+
+/// Requests of one endpoint within one minute.
+#[derive(Debug)]
+struct MinuteBucket {
+    minute: i64,
+    requests: u64,
+    failed: u64,
+    /// Requests over each latency threshold, in objective order
+    slow: Vec<u64>,
+}
+
+#[derive(Debug, Default)]
+struct EndpointWindow {
+    requests: u64,
+    failed: u64,
+    /// Last LONG_WINDOW_MINUTES minutes with traffic, oldest first
+    buckets: VecDeque<MinuteBucket>,
+    latency: VecDeque<Duration>,
+}
+
+impl EndpointWindow {
+    /// (requests, failed, slow per threshold) over the last `minutes`.
+    fn totals(&self, now_minute: i64, minutes: i64, thresholds: usize) -> (u64, u64, Vec<u64>) {
+        let mut requests = 0;
+        let mut failed = 0;
+        let mut slow = vec![0; thresholds];
+        for bucket in self
+            .buckets
+            .iter()
+            .filter(|b| b.minute > now_minute - minutes)
+        {
+            requests += bucket.requests;
+            failed += bucket.failed;
+            for (total, count) in slow.iter_mut().zip(&bucket.slow) {
+                *total += count;
+            }
+        }
+        (requests, failed, slow)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct BurnRates {
+    /// Over the last 5 minutes; None without traffic
+    pub short: Option<f64>,
+    /// Over the last hour; None without traffic
+    pub long: Option<f64>,
+}
+
+impl BurnRates {
+    fn alerting(&self, threshold: f64) -> bool {
+        matches!((self.short, self.long), (Some(short), Some(long)) if short >= threshold && long >= threshold)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SuccessStatus {
+    pub target: f64,
+    /// Over the last hour; None without traffic
+    pub observed: Option<f64>,
+    pub burn_rate: BurnRates,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyStatus {
+    pub percentile: f64,
+    pub threshold_ms: f64,
+    /// Over the last LATENCY_WINDOW requests; None without traffic
+    pub observed_ms: Option<f64>,
+    pub met: bool,
+    pub burn_rate: BurnRates,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointSlo {
+    pub method: String,
+    pub route: String,
+    /// Requests since startup
+    pub requests: u64,
+    pub failed: u64,
+    /// Over the last LATENCY_WINDOW requests
+    pub latency_ms: Percentiles,
+    pub success: SuccessStatus,
+    pub latency_objectives: Vec<LatencyStatus>,
+    /// An objective burns its budget faster than SLO_BURN_RATE_ALERT over
+    /// both windows
+    pub alerting: bool,
+}
+
+/// Snapshot behind GET /admin/slo (see module docs).
+#[derive(Debug, Clone, Serialize)]
+pub struct SloSummary {
+    pub burn_rate_alert: f64,
+    /// "METHOD route" of alerting endpoints
+    pub alerts: Vec<String>,
+    /// Alerting endpoints first, then by route
+    pub endpoints: Vec<EndpointSlo>,
+}
+
+/// Per-endpoint request outcomes, shared by the tracking middleware and the
+/// summary handler.
+#[derive(Debug, Clone, Default)]
+pub struct SloTracker {
+    policy: Arc<SloPolicy>,
+    inner: Arc<Mutex<HashMap<(String, String), EndpointWindow>>>,
+}
+
+impl SloTracker {
+    pub fn new(policy: SloPolicy) -> Self {
+        Self {
+            policy: Arc::new(policy),
+            inner: Arc::default(),
+        }
+    }
+
+    /// Records one request to a matched route.
+    pub fn record(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        let thresholds: Vec<Duration> = self
+            .policy
+            .objectives_for(method, route)
+            .map(|o| o.latency.iter().map(|l| l.threshold).collect())
+            .unwrap_or_default();
+        let minute = chrono::Utc::now().timestamp() / 60;
+        let failed = status >= 500;
+
+        let mut endpoints = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let window = endpoints
+            .entry((method.to_string(), route.to_string()))
+            .or_default();
+        window.requests += 1;
+        window.failed += u64::from(failed);
+
+        if window.buckets.back().map(|b| b.minute) != Some(minute) {
+            window.buckets.push_back(MinuteBucket {
+                minute,
+                requests: 0,
+                failed: 0,
+                slow: vec![0; thresholds.len()],
+            });
+        }
+        while window
+            .buckets
+            .front()
+            .is_some_and(|b| b.minute <= minute - LONG_WINDOW_MINUTES)
+        {
+            window.buckets.pop_front();
+        }
+        if let Some(bucket) = window.buckets.back_mut() {
+            bucket.requests += 1;
+            bucket.failed += u64::from(failed);
+            for (count, threshold) in bucket.slow.iter_mut().zip(&thresholds) {
+                *count += u64::from(elapsed > *threshold);
+            }
+        }
+
+        if window.latency.len() == LATENCY_WINDOW {
+            window.latency.pop_front();
+        }
+        window.latency.push_back(elapsed);
+    }
+
+    pub fn summary(&self) -> SloSummary {
+        let endpoints = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let now_minute = chrono::Utc::now().timestamp() / 60;
+        let alert = self.policy.burn_rate_alert;
+
+        let mut summaries: Vec<EndpointSlo> = endpoints
+            .iter()
+            .map(|((method, route), window)| {
+                let objectives = self.policy.objectives_for(method, route);
+                let latency_objectives = objectives.map_or(&[][..], |o| &o.latency[..]);
+                let (short_requests, short_failed, short_slow) =
+                    window.totals(now_minute, SHORT_WINDOW_MINUTES, latency_objectives.len());
+                let (long_requests, long_failed, long_slow) =
+                    window.totals(now_minute, LONG_WINDOW_MINUTES, latency_objectives.len());
+
+                let target = objectives
+                    .and_then(|o| o.success_rate)
+                    .unwrap_or(self.policy.default_success_rate);
+                let success = SuccessStatus {
+                    target,
+                    observed: (long_requests > 0).then(|| {
+                        100.0 * (long_requests - long_failed) as f64 / long_requests as f64
+                    }),
+                    burn_rate: BurnRates {
+                        short: burn_rate(short_failed, short_requests, target),
+                        long: burn_rate(long_failed, long_requests, target),
+                    },
+                };
+
+                let mut samples: Vec<Duration> = window.latency.iter().copied().collect();
+                samples.sort();
+                let latency: Vec<LatencyStatus> = latency_objectives
+                    .iter()
+                    .enumerate()
+                    .map(|(i, objective)| {
+                        let observed = percentile(&samples, objective.percentile);
+                        LatencyStatus {
+                            percentile: objective.percentile,
+                            threshold_ms: millis(objective.threshold),
+                            observed_ms: observed.map(millis),
+                            met: observed.is_none_or(|o| o <= objective.threshold),
+                            burn_rate: BurnRates {
+                                short: burn_rate(
+                                    short_slow[i],
+                                    short_requests,
+                                    objective.percentile,
+                                ),
+                                long: burn_rate(long_slow[i], long_requests, objective.percentile),
+                            },
+                        }
+                    })
+                    .collect();
+
+                let alerting = success.burn_rate.alerting(alert)
+                    || latency.iter().any(|l| l.burn_rate.alerting(alert));
+                EndpointSlo {
+                    method: method.clone(),
+                    route: route.clone(),
+                    requests: window.requests,
+                    failed: window.failed,
+                    latency_ms: Percentiles::of(samples),
+                    success,
+                    latency_objectives: latency,
+                    alerting,
+                }
+            })
+            .collect();
+        summaries.sort_by(|a, b| {
+            b.alerting
+                .cmp(&a.alerting)
+                .then(a.route.cmp(&b.route))
+                .then(a.method.cmp(&b.method))
+        });
+
+        SloSummary {
+            burn_rate_alert: alert,
+            alerts: summaries
+                .iter()
+                .filter(|e| e.alerting)
+                .map(|e| format!("{} {}", e.method, e.route))
+                .collect(),
+            endpoints: summaries,
+        }
+    }
+}
+
+/// Bad fraction over the fraction the target allows; None without requests.
+fn burn_rate(bad: u64, requests: u64, target_percent: f64) -> Option<f64> {
+    (requests > 0).then(|| (bad as f64 / requests as f64) / (1.0 - target_percent / 100.0))
+}
+
+/// Nearest-rank percentile of sorted samples.
+fn percentile(sorted: &[Duration], percent: f64) -> Option<Duration> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
@@ -0,0 +1,400 @@
+// src/snapshots.rs
+//
+// Store snapshots for fast bootstrap
+//
+// A new instance normally builds its client store by syncing from genesis,
+// which takes long on a busy chain. Instead, it can start from a snapshot of
+// another instance's store and only sync the blocks since:
+//
+// Publishing (the primary instance). Every SNAPSHOT_PUBLISH_INTERVAL_SECS the
+// scheduler (scheduler.rs) copies the client store into SNAPSHOT_DIR, and
+// POST /admin/snapshots does so on demand. The copy is made with SQLite's
+// VACUUM INTO, so it is consistent and compact even while the client runs. Each
+// snapshot has a manifest: profile, latest block, size and SHA-256 of the file.
+// The newest SNAPSHOT_KEEP snapshots are kept. GET /snapshots lists them,
+// GET /snapshots/latest serves the newest manifest and
+// GET /snapshots/:snapshot_id/file the file itself.
+//
+// Bootstrap (a new instance). With SNAPSHOT_BOOTSTRAP_URL set to the primary's
+// API base (e.g. https://primary.example.com/api/v1) and no store at
+// MIDEN_STORE_PATH yet, startup fetches the latest manifest, downloads the
+// file, checks its size and checksum and only then moves it into place. The
+// usual startup sync (startup.rs) then catches up from the snapshot's block.
+// A snapshot of another profile is refused; a failed download leaves no store
+// behind, so the next start tries again (or syncs from genesis without the URL).
+//
+// A snapshot holds no keys (the keystore is not part of it) but does hold the
+// primary's accounts and notes, so every snapshot endpoint needs an admin API
+// key (SNAPSHOT_BOOTSTRAP_API_KEY on the new instance). It is meant for
+// instances of the same deployment, e.g. a replacement or a read replica.
+
+use anyhow::Result;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    config::ServiceConfig,
+    startup::{StartupProgress, StartupStage},
+    MidenClientWrapper,
+};
+
+/// Longest a snapshot download may take.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub snapshot_id: String,
+    pub file_name: String,
+    pub created_at: i64,
+    /// Profile of the instance that published it (testnet, localnet)
+    pub profile: String,
+    /// Latest block header in the store; the bootstrap sync starts there
+    pub block_num: Option<u32>,
+    pub size_bytes: u64,
+    pub sha256: String,
+}
+
+/// Published snapshots, newest last (SNAPSHOT_DIR/index.json).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SnapshotIndex {
+    #[serde(skip)]
+    dir: PathBuf,
+    #[serde(default)]
+    snapshots: Vec<SnapshotManifest>,
+}
+
+impl SnapshotIndex {
+    pub fn load(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        let path = dir.join("index.json");
+
+        let mut index = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<SnapshotIndex>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            SnapshotIndex::default()
+        };
+        index.dir = dir;
+
+        Ok(index)
+    }
+
+    fn save(&self) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join("index.json");
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<&SnapshotManifest> {
+        self.snapshots.iter().rev().collect()
+    }
+
+    pub fn latest(&self) -> Option<&SnapshotManifest> {
+        self.snapshots.last()
+    }
+
+    pub fn get(&self, snapshot_id: &str) -> Result<&SnapshotManifest> {
+        self.snapshots
+            .iter()
+            .find(|s| s.snapshot_id == snapshot_id)
+            .ok_or_else(|| anyhow::anyhow!("Snapshot {} not found", snapshot_id))
+    }
+
+    pub fn file_path(&self, manifest: &SnapshotManifest) -> PathBuf {
+        self.dir.join(&manifest.file_name)
+    }
+
+    /// Records a published snapshot and deletes all but the newest `keep`.
+    fn add(&mut self, manifest: SnapshotManifest, keep: usize) -> Result<()> {
+        self.snapshots.push(manifest);
+        let excess = self.snapshots.len().saturating_sub(keep.max(1));
+        let removed: Vec<SnapshotManifest> = self.snapshots.drain(..excess).collect();
+        self.save()?;
+
+        for old in removed {
+            if let Err(e) = std::fs::remove_file(self.file_path(&old)) {
+                tracing::warn!("Failed to delete snapshot {}: {}", old.snapshot_id, e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// SHA-256 (hex) and size of a file, read in chunks.
+fn file_digest(path: &Path) -> Result<(String, u64)> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+    Ok((hex::encode(hasher.finalize()), size))
+}
+
+/// Copies the store at `store_path` into `dir` as a new snapshot.
+pub fn publish(store_path: &Path, dir: &Path, profile: &str) -> Result<SnapshotManifest> {
+    std::fs::create_dir_all(dir)?;
+    let created_at = chrono::Utc::now().timestamp();
+    let snapshot_id = format!("snapshot-{}", created_at);
+    let file_name = format!("{}.sqlite3", snapshot_id);
+    let tmp_path = dir.join(format!("{}.tmp", file_name));
+    let _ = std::fs::remove_file(&tmp_path);
+
+    let conn = Connection::open(store_path)?;
+    conn.busy_timeout(Duration::from_secs(30))?;
+    conn.execute("VACUUM INTO ?1", [tmp_path.to_string_lossy()])?;
+    let block_num: Option<u32> = conn
+        .query_row("SELECT MAX(block_num) FROM block_headers", [], |row| {
+            row.get(0)
+        })
+        .unwrap_or(None);
+    drop(conn);
+
+    let (sha256, size_bytes) = file_digest(&tmp_path)?;
+    std::fs::rename(&tmp_path, dir.join(&file_name))?;
+
+    Ok(SnapshotManifest {
+        snapshot_id,
+        file_name,
+        created_at,
+        profile: profile.to_string(),
+        block_num,
+        size_bytes,
+        sha256,
+    })
+}
+
+/// Downloads the latest snapshot from SNAPSHOT_BOOTSTRAP_URL into an empty
+/// store path (see module docs). Returns the manifest of the snapshot used,
+/// or None when there was nothing to do.
+pub async fn bootstrap(
+    config: &ServiceConfig,
+    progress: &StartupProgress,
+) -> Result<Option<SnapshotManifest>> {
+    let Some(base_url) = config.snapshot_bootstrap_url.as_deref() else {
+        return Ok(None);
+    };
+    if config.store_path.exists() {
+        tracing::info!("Store already present; skipping snapshot bootstrap");
+        return Ok(None);
+    }
+    let base_url = base_url.trim_end_matches('/');
+    let http = reqwest::Client::builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .build()?;
+    let get = |url: String| {
+        let request = http.get(url);
+        match &config.snapshot_bootstrap_api_key {
+            Some(key) => request.header("x-api-key", key),
+            None => request,
+        }
+    };
+
+    progress.advance(
+        StartupStage::Connecting,
+        0,
+        1,
+        format!("fetching snapshot manifest from {}", base_url),
+    );
+    let body: serde_json::Value = get(format!("{}/snapshots/latest", base_url))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let manifest: SnapshotManifest = serde_json::from_value(body["snapshot"].clone())
+        .map_err(|e| anyhow::anyhow!("Invalid snapshot manifest from {}: {}", base_url, e))?;
+    if manifest.profile != config.profile.as_str() {
+        return Err(anyhow::anyhow!(
+            "Snapshot {} is from profile {}, this instance runs {}",
+            manifest.snapshot_id,
+            manifest.profile,
+            config.profile.as_str()
+        ));
+    }
+
+    let mut tmp_name = config.store_path.as_os_str().to_owned();
+    tmp_name.push(".download");
+    let tmp_path = PathBuf::from(tmp_name);
+    let result = download(&get, base_url, &manifest, &tmp_path, progress).await;
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+    std::fs::rename(&tmp_path, &config.store_path)?;
+
+    tracing::info!(
+        "📦 Store bootstrapped from {} (block {:?}, {} bytes)",
+        manifest.snapshot_id,
+        manifest.block_num,
+        manifest.size_bytes
+    );
+    Ok(Some(manifest))
+}
+
+/// Streams the snapshot file to `path`, checking its size and checksum.
+async fn download(
+    get: &impl Fn(String) -> reqwest::RequestBuilder,
+    base_url: &str,
+    manifest: &SnapshotManifest,
+    path: &Path,
+    progress: &StartupProgress,
+) -> Result<()> {
+    let url = format!("{}/snapshots/{}/file", base_url, manifest.snapshot_id);
+    let mut response = get(url).send().await?.error_for_status()?;
+    let mut file = std::fs::File::create(path)?;
+    let mut hasher = Sha256::new();
+    let mut received = 0u64;
+
+    while let Some(chunk) = response.chunk().await? {
+        received += chunk.len() as u64;
+        if received > manifest.size_bytes {
+            return Err(anyhow::anyhow!(
+                "Snapshot {} is larger than its manifest says",
+                manifest.snapshot_id
+            ));
+        }
+        hasher.update(&chunk);
+        file.write_all(&chunk)?;
+        progress.advance(
+            StartupStage::Connecting,
+            received,
+            manifest.size_bytes,
+            format!(
+                "downloading snapshot {} ({}/{} bytes)",
+                manifest.snapshot_id, received, manifest.size_bytes
+            ),
+        );
+    }
+    file.sync_all()?;
+
+    let sha256 = hex::encode(hasher.finalize());
+    if received != manifest.size_bytes || sha256 != manifest.sha256 {
+        return Err(anyhow::anyhow!(
+            "Snapshot {} failed verification: got {} bytes with SHA-256 {}, expected {} bytes \
+             with {}",
+            manifest.snapshot_id,
+            received,
+            sha256,
+            manifest.size_bytes,
+            manifest.sha256
+        ));
+    }
+    Ok(())
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    ListSnapshots {
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.list_snapshots(op.api_key.as_deref());
+
+    LatestSnapshot {
+        api_key: Option<String>,
+    } -> SnapshotManifest;
+    |client, op| client.latest_snapshot(op.api_key.as_deref());
+
+    SnapshotFile {
+        snapshot_id: String,
+        api_key: Option<String>,
+    } -> (SnapshotManifest, std::path::PathBuf);
+    |client, op| client.snapshot_file(&op.snapshot_id, op.api_key.as_deref());
+
+    PublishSnapshot {
+        api_key: Option<String>,
+    } -> SnapshotManifest;
+    |client, op| client.publish_snapshot_now(op.api_key.as_deref()).await
+}
+
+impl MidenClientWrapper {
+    /// Publishes a snapshot of the client store (POST /admin/snapshots).
+    /// Admin only.
+    pub async fn publish_snapshot_now(
+        &mut self,
+        api_key: Option<&str>,
+    ) -> Result<SnapshotManifest> {
+        self.admin_principal(api_key, "publishing snapshots")?;
+        self.publish_snapshot().await
+    }
+
+    async fn publish_snapshot(&mut self) -> Result<SnapshotManifest> {
+        let store_path = self.config.store_path.clone();
+        let dir = self.config.snapshot_dir.clone();
+        let profile = self.config.profile.as_str();
+        let manifest =
+            tokio::task::spawn_blocking(move || publish(&store_path, &dir, profile)).await??;
+
+        self.snapshots
+            .add(manifest.clone(), self.config.snapshot_keep)?;
+        tracing::info!(
+            "📦 Published store snapshot {} (block {:?}, {} bytes)",
+            manifest.snapshot_id,
+            manifest.block_num,
+            manifest.size_bytes
+        );
+        Ok(manifest)
+    }
+
+    /// Publishes a snapshot once SNAPSHOT_PUBLISH_INTERVAL_SECS have passed
+    /// since the latest. Returns the number of snapshots published.
+    pub async fn run_snapshot_publishing(&mut self) -> Result<usize> {
+        let interval = self.config.snapshot_publish_interval.as_secs() as i64;
+        if interval == 0 {
+            return Ok(0);
+        }
+        let now = chrono::Utc::now().timestamp();
+        if self
+            .snapshots
+            .latest()
+            .is_some_and(|latest| now - latest.created_at < interval)
+        {
+            return Ok(0);
+        }
+        self.publish_snapshot().await?;
+        Ok(1)
+    }
+
+    pub fn list_snapshots(&self, api_key: Option<&str>) -> Result<serde_json::Value> {
+        self.admin_principal(api_key, "snapshots")?;
+        Ok(serde_json::json!(self.snapshots.list()))
+    }
+
+    pub fn latest_snapshot(&self, api_key: Option<&str>) -> Result<SnapshotManifest> {
+        self.admin_principal(api_key, "snapshots")?;
+        self.snapshots
+            .latest()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No snapshot has been published"))
+    }
+
+    /// The manifest of a snapshot and where its file is, for downloading.
+    pub fn snapshot_file(
+        &self,
+        snapshot_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<(SnapshotManifest, PathBuf)> {
+        self.admin_principal(api_key, "snapshots")?;
+        let manifest = self.snapshots.get(snapshot_id)?;
+        Ok((manifest.clone(), self.snapshots.file_path(manifest)))
+    }
+}
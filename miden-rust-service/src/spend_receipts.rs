@@ -0,0 +1,129 @@
+// src/spend_receipts.rs
+//
+// Spend receipts
+//
+// A transaction ID alone does not tell a downstream system which notes were
+// spent, nor let it notice when two reports describe the same spend. Consuming
+// notes and releasing an escrow therefore answer with a receipt per
+// transaction, read back from the client store right after submission:
+// - the nullifiers of the notes the transaction consumed; a nullifier is what
+//   the chain records when a note is spent, so the same nullifier in two
+//   reports is the same spend
+// - the commitment of the account before and after the transaction, which
+//   chains receipts of one account together and can be checked against the
+//   account's on-chain commitment
+//
+// POST /consume-note reports the receipt of each batch (consume_batches.rs).
+// POST /release-escrow reports the receipts of the transactions that consumed
+// the escrow's funding notes, whether in this release or an earlier attempt,
+// followed by the release itself.
+//
+// Receipts are read, never stored: a receipt that cannot be read (the
+// transaction is not in this instance's store) is left out with a warning, and
+// the operation, which already reached the chain, still succeeds.
+
+use miden_client::transaction::TransactionFilter;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{account_id_to_hex, MidenClientWrapper};
+
+/// What one transaction spent, as executed.
+#[derive(Debug, Clone, Serialize)]
+pub struct SpendReceipt {
+    pub tx_id: String,
+    pub account_id: String,
+    /// Nullifiers of the notes consumed (hex)
+    pub nullifiers: Vec<String>,
+    pub initial_account_commitment: String,
+    pub final_account_commitment: String,
+}
+
+/// Nullifiers of several receipts, in order.
+pub fn nullifiers(receipts: &[SpendReceipt]) -> Vec<String> {
+    receipts
+        .iter()
+        .flat_map(|r| r.nullifiers.iter().cloned())
+        .collect()
+}
+
+// ============================================================================
+// CLIENT INTEGRATION
+// ============================================================================
+
+impl MidenClientWrapper {
+    /// Receipts of the given transactions, in that order (see module docs).
+    pub(crate) async fn spend_receipts(&self, tx_ids: &[String]) -> Vec<SpendReceipt> {
+        if tx_ids.is_empty() {
+            return Vec::new();
+        }
+        let records = match self.client.get_transactions(TransactionFilter::All).await {
+            Ok(records) => records,
+            Err(e) => {
+                tracing::warn!("⚠️  No spend receipts, transactions unreadable: {}", e);
+                return Vec::new();
+            }
+        };
+        let mut by_id: BTreeMap<String, SpendReceipt> = records
+            .into_iter()
+            .map(|record| {
+                let tx_id = record.id.to_string();
+                let details = &record.details;
+                let receipt = SpendReceipt {
+                    tx_id: tx_id.clone(),
+                    account_id: account_id_to_hex(details.account_id),
+                    nullifiers: details
+                        .input_note_nullifiers
+                        .iter()
+                        .map(|n| n.to_hex())
+                        .collect(),
+                    initial_account_commitment: details.init_account_state.to_hex(),
+                    final_account_commitment: details.final_account_state.to_hex(),
+                };
+                (tx_id, receipt)
+            })
+            .collect();
+
+        let mut receipts = Vec::with_capacity(tx_ids.len());
+        for tx_id in tx_ids {
+            match by_id.remove(tx_id) {
+                Some(receipt) => receipts.push(receipt),
+                None => tracing::warn!(
+                    "⚠️  No spend receipt for {}: not in the client store",
+                    tx_id
+                ),
+            }
+        }
+        receipts
+    }
+
+    /// Receipts of an escrow release: the transactions that consumed its
+    /// funding notes, then the release transaction.
+    pub(crate) async fn release_receipts(
+        &self,
+        escrow_hex: &str,
+        release_tx_id: &str,
+    ) -> Vec<SpendReceipt> {
+        let funding_note_ids = self
+            .records
+            .escrows
+            .get(escrow_hex)
+            .map(|record| record.funding_note_ids.clone())
+            .unwrap_or_default();
+        let mut tx_ids: Vec<String> = Vec::new();
+        for note_id in &funding_note_ids {
+            let consumed_by = self
+                .records
+                .expected_notes
+                .get(note_id)
+                .and_then(|note| note.consumed_tx_id.clone());
+            if let Some(tx_id) = consumed_by {
+                if !tx_ids.contains(&tx_id) {
+                    tx_ids.push(tx_id);
+                }
+            }
+        }
+        tx_ids.push(release_tx_id.to_string());
+        self.spend_receipts(&tx_ids).await
+    }
+}
@@ -0,0 +1,253 @@
+// src/startup.rs
+//
+// Startup progress reporting
+//
+// Client initialization can take minutes: the first sync against testnet
+// walks the whole chain, and auto-funding proves and submits transactions.
+// The HTTP server is up from the start, so startup runs through stages that
+// GET /health/startup reports (200 once ready, 503 while starting, 500 if
+// initialization failed) and that are logged with an overall percentage:
+//
+//   connecting         0%   RPC client and local store (and downloading a
+//                            store snapshot, see snapshots.rs)
+//   syncing            5%   blocks from the local sync height to the chain tip
+//   creating_accounts 60%   alice, bob and the faucet
+//   funding           80%   auto-funding mints and consumes
+//   ready            100%
+//
+// While syncing, the local sync height is polled every SYNC_POLL_INTERVAL and
+// logged against the chain tip fetched before the sync started, with a
+// heartbeat even when the height does not move, so a slow sync can be told
+// apart from a hung one.
+
+use anyhow::Result;
+use miden_client::{
+    rpc::{Endpoint, GrpcClient, NodeRpcClient},
+    store::Store,
+    sync::SyncSummary,
+};
+use serde::Serialize;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::client::MidenClient;
+
+/// How often sync progress is polled and logged
+const SYNC_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupStage {
+    Connecting,
+    Syncing,
+    CreatingAccounts,
+    Funding,
+    Ready,
+    Failed,
+}
+
+impl StartupStage {
+    /// Overall progress at the start of the stage, and the stage's share.
+    fn span(self) -> (u8, u8) {
+        match self {
+            StartupStage::Connecting => (0, 5),
+            StartupStage::Syncing => (5, 55),
+            StartupStage::CreatingAccounts => (60, 20),
+            StartupStage::Funding => (80, 20),
+            StartupStage::Ready | StartupStage::Failed => (100, 0),
+        }
+    }
+}
+
+/// Blocks covered by the startup sync.
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncProgress {
+    pub from_block: u32,
+    pub current_block: u32,
+    /// Chain tip when the sync started; None if the node did not report it
+    pub target_block: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupStatus {
+    pub stage: StartupStage,
+    pub percent: u8,
+    /// What the current stage is doing, e.g. "syncing blocks 1200/5000"
+    pub detail: String,
+    pub started_at: i64,
+    pub stage_started_at: i64,
+    pub elapsed_secs: u64,
+    pub sync: Option<SyncProgress>,
+    pub ready_at: Option<i64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug)]
+struct Tracker {
+    status: StartupStatus,
+    started: Instant,
+}
+
+/// Startup progress shared by the client task (writer) and handlers.
+#[derive(Debug, Clone)]
+pub struct StartupProgress {
+    inner: Arc<Mutex<Tracker>>,
+}
+
+impl Default for StartupProgress {
+    fn default() -> Self {
+        let now = chrono::Utc::now().timestamp();
+        Self {
+            inner: Arc::new(Mutex::new(Tracker {
+                status: StartupStatus {
+                    stage: StartupStage::Connecting,
+                    percent: 0,
+                    detail: "starting".to_string(),
+                    started_at: now,
+                    stage_started_at: now,
+                    elapsed_secs: 0,
+                    sync: None,
+                    ready_at: None,
+                    error: None,
+                },
+                started: Instant::now(),
+            })),
+        }
+    }
+}
+
+impl StartupProgress {
+    pub fn status(&self) -> StartupStatus {
+        let tracker = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut status = tracker.status.clone();
+        if status.ready_at.is_none() && status.error.is_none() {
+            status.elapsed_secs = tracker.started.elapsed().as_secs();
+        }
+        status
+    }
+
+    /// Moves to `stage` (or updates it) with `done` of `total` steps complete.
+    pub fn advance(&self, stage: StartupStage, done: u64, total: u64, detail: String) {
+        let (base, share) = stage.span();
+        let fraction = if total == 0 {
+            0.0
+        } else {
+            done.min(total) as f64 / total as f64
+        };
+        let percent = base + (share as f64 * fraction) as u8;
+
+        let mut tracker = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let elapsed = tracker.started.elapsed().as_secs();
+        let status = &mut tracker.status;
+        if status.stage != stage {
+            status.stage_started_at = chrono::Utc::now().timestamp();
+        }
+        status.stage = stage;
+        status.percent = percent;
+        status.detail = detail;
+        status.elapsed_secs = elapsed;
+        tracing::info!(
+            "🚀 Startup {:>3}% [{}s] {}",
+            percent,
+            elapsed,
+            status.detail
+        );
+    }
+
+    pub fn ready(&self) {
+        self.advance(StartupStage::Ready, 0, 0, "ready".to_string());
+        let mut tracker = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        tracker.status.ready_at = Some(chrono::Utc::now().timestamp());
+    }
+
+    /// Records a failed initialization. The percentage stays where it stopped.
+    pub fn failed(&self, error: &anyhow::Error) {
+        let mut tracker = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        tracker.status.elapsed_secs = tracker.started.elapsed().as_secs();
+        let status = &mut tracker.status;
+        tracing::error!(
+            "🚀 Startup failed at {}% ({}): {}",
+            status.percent,
+            status.detail,
+            error
+        );
+        status.stage = StartupStage::Failed;
+        status.stage_started_at = chrono::Utc::now().timestamp();
+        status.error = Some(error.to_string());
+    }
+
+    fn set_sync(&self, sync: SyncProgress) {
+        let (done, total) = match sync.target_block {
+            Some(target) => (
+                u64::from(sync.current_block.saturating_sub(sync.from_block)),
+                u64::from(target.saturating_sub(sync.from_block)),
+            ),
+            None => (0, 0),
+        };
+        let detail = match sync.target_block {
+            Some(target) => format!("syncing blocks {}/{}", sync.current_block, target),
+            None => format!("syncing blocks {}/?", sync.current_block),
+        };
+        self.advance(StartupStage::Syncing, done, total, detail);
+        let mut tracker = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        tracker.status.sync = Some(sync);
+    }
+}
+
+/// Syncs `client` to the chain tip, reporting progress (see module docs).
+pub async fn sync_with_progress(
+    client: &mut MidenClient,
+    store: &Arc<dyn Store>,
+    endpoint: &Endpoint,
+    timeout_ms: u64,
+    progress: &StartupProgress,
+) -> Result<SyncSummary> {
+    let sync_height = || async {
+        store
+            .get_sync_height()
+            .await
+            .map(|height| height.as_u32())
+            .unwrap_or_default()
+    };
+
+    let from_block = sync_height().await;
+    let target_block = match GrpcClient::new(endpoint, timeout_ms)
+        .get_block_header_by_number(None, false)
+        .await
+    {
+        Ok((header, _)) => Some(header.block_num().as_u32()),
+        Err(e) => {
+            tracing::warn!("Could not fetch the chain tip: {}", e);
+            None
+        }
+    };
+    let mut sync = SyncProgress {
+        from_block,
+        current_block: from_block,
+        target_block,
+    };
+    progress.set_sync(sync.clone());
+
+    let summary = {
+        let sync_state = client.sync_state();
+        tokio::pin!(sync_state);
+        let mut poll = tokio::time::interval(SYNC_POLL_INTERVAL);
+        poll.tick().await;
+        loop {
+            tokio::select! {
+                result = &mut sync_state => break result?,
+                _ = poll.tick() => {
+                    sync.current_block = sync_height().await.max(sync.current_block);
+                    progress.set_sync(sync.clone());
+                }
+            }
+        }
+    };
+
+    sync.current_block = summary.block_num.as_u32();
+    sync.target_block = sync.target_block.map(|t| t.max(sync.current_block));
+    progress.set_sync(sync);
+    Ok(summary)
+}
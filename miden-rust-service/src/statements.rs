@@ -0,0 +1,351 @@
+// src/statements.rs
+//
+// Monthly account statements
+//
+// GET /accounts/:account_id/statement?month=YYYY-MM renders a statement of one
+// calendar month of an account, e.g. as proof of payment history for a lender:
+// opening balances, every movement with its counterparties and memo, and
+// closing balances, per asset. It is a PDF (pdf.rs) unless ?format=json asks
+// for the data; ?tz= (or X-Timezone) sets the zone the month is cut in and
+// dates are printed in (timestamps.rs).
+//
+// Statements are read from the service ledger (ledger.rs): a movement is the
+// account's side of one journal entry, as the portal shows payments
+// (portal.rs), and its memo is the escrow, property or account the entry was
+// for. Balances are ledger balances, so holdings from before the ledger or
+// from outside the service only count once reconciliation has posted them;
+// until then an opening balance can be negative. For the current month the
+// statement runs up to now.
+//
+// The caller's API key must be bound to the account (or be an arbiter's)
+// when API-key auth is enforced.
+
+use anyhow::Result;
+use miden_client::account::AccountId;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{
+    currency::decimal_string,
+    documents::{ArtifactFormat, DocumentArtifact},
+    ledger::JournalKind,
+    pdf::text_pdf,
+    portal::{PaymentDirection, PortalScope},
+    timestamps::DisplayZone,
+    MidenClientWrapper,
+};
+
+/// One asset's balances over the period.
+#[derive(Debug, Clone, Serialize)]
+pub struct StatementBalance {
+    pub faucet_id: String,
+    pub symbol: Option<String>,
+    pub decimals: u8,
+    /// Ledger balance before the period; negative when the ledger holds less
+    /// than the account received from outside it
+    pub opening: i128,
+    pub received: u64,
+    pub sent: u64,
+    pub closing: i128,
+}
+
+/// The account's side of one journal entry in one asset.
+#[derive(Debug, Clone, Serialize)]
+pub struct StatementLine {
+    pub entry_id: u64,
+    pub at: i64,
+    pub kind: JournalKind,
+    pub direction: PaymentDirection,
+    pub faucet_id: String,
+    pub amount: u64,
+    pub counterparties: Vec<String>,
+    /// Escrow, property or account the movement was for
+    pub memo: Option<String>,
+    pub tx_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountStatement {
+    pub account_id: String,
+    /// YYYY-MM
+    pub month: String,
+    pub timezone: DisplayZone,
+    pub period_start: i64,
+    /// End of the month, or now for the current month (exclusive)
+    pub period_end: i64,
+    pub balances: Vec<StatementBalance>,
+    pub movements: Vec<StatementLine>,
+}
+
+/// Year and month of "YYYY-MM".
+pub fn parse_month(month: &str) -> Result<(i32, u32)> {
+    let invalid = || anyhow::anyhow!("Invalid month {:?} (expected YYYY-MM)", month);
+    let (year, month_of_year) = month.trim().split_once('-').ok_or_else(invalid)?;
+    if year.len() != 4 || month_of_year.len() != 2 {
+        return Err(invalid());
+    }
+    let year: i32 = year.parse().map_err(|_| invalid())?;
+    let month_of_year: u32 = month_of_year.parse().map_err(|_| invalid())?;
+    if !(1..=12).contains(&month_of_year) {
+        return Err(invalid());
+    }
+    Ok((year, month_of_year))
+}
+
+/// A ledger amount with its sign, in the asset's units.
+fn signed_amount(amount: i128, decimals: u8) -> String {
+    let magnitude = decimal_string(amount.unsigned_abs() as u64, decimals);
+    if amount < 0 {
+        format!("-{}", magnitude)
+    } else {
+        magnitude
+    }
+}
+
+impl AccountStatement {
+    fn balance(&self, faucet_id: &str) -> Option<&StatementBalance> {
+        self.balances.iter().find(|b| b.faucet_id == faucet_id)
+    }
+
+    /// An amount with the symbol of its asset, or its faucet when unknown.
+    fn amount(&self, faucet_id: &str, amount: i128) -> String {
+        match self.balance(faucet_id) {
+            Some(StatementBalance {
+                symbol: Some(symbol),
+                decimals,
+                ..
+            }) => format!("{} {}", signed_amount(amount, *decimals), symbol),
+            _ => format!("{} of {}", signed_amount(amount, 0), faucet_id),
+        }
+    }
+
+    /// The statement as printed in the PDF.
+    pub fn to_text(&self) -> String {
+        let zone = self.timezone;
+        let mut text = format!(
+            "Account {}\nStatement for {} ({} to {}, {})\n\n",
+            self.account_id,
+            self.month,
+            zone.date(self.period_start),
+            zone.date(self.period_end - 1),
+            zone
+        );
+
+        text.push_str("Opening balances\n");
+        if self.balances.is_empty() {
+            text.push_str("  none\n");
+        }
+        for balance in &self.balances {
+            text.push_str(&format!(
+                "  {}\n",
+                self.amount(&balance.faucet_id, balance.opening)
+            ));
+        }
+
+        text.push_str("\nMovements\n");
+        if self.movements.is_empty() {
+            text.push_str("  none in this period\n");
+        }
+        for line in &self.movements {
+            let (sign, preposition) = match line.direction {
+                PaymentDirection::In => (1, "from"),
+                PaymentDirection::Out => (-1, "to"),
+            };
+            let kind = serde_json::to_value(line.kind)
+                .ok()
+                .and_then(|v| v.as_str().map(|s| s.replace('_', " ")))
+                .unwrap_or_default();
+            text.push_str(&format!(
+                "{}  {}  {}\n",
+                zone.date_time(line.at),
+                kind,
+                self.amount(&line.faucet_id, sign * line.amount as i128)
+            ));
+            if !line.counterparties.is_empty() {
+                text.push_str(&format!(
+                    "    {} {}\n",
+                    preposition,
+                    line.counterparties.join(", ")
+                ));
+            }
+            if let Some(memo) = &line.memo {
+                text.push_str(&format!("    memo: {}\n", memo));
+            }
+            if let Some(tx_id) = &line.tx_id {
+                text.push_str(&format!("    tx: {}\n", tx_id));
+            }
+        }
+
+        text.push_str("\nClosing balances\n");
+        if self.balances.is_empty() {
+            text.push_str("  none\n");
+        }
+        for balance in &self.balances {
+            text.push_str(&format!(
+                "  {}  (received {}, sent {})\n",
+                self.amount(&balance.faucet_id, balance.closing),
+                self.amount(&balance.faucet_id, balance.received as i128),
+                self.amount(&balance.faucet_id, balance.sent as i128)
+            ));
+        }
+        text.push_str(
+            "\nBalances are the service ledger's; movements are as journaled when\n\
+             their transactions were submitted.\n",
+        );
+        text
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    GetAccountStatement {
+        account_id: String,
+        month: String,
+        format: ArtifactFormat,
+        zone: DisplayZone,
+        api_key: Option<String>,
+    } -> DocumentArtifact;
+    |client, op| client.account_statement_artifact(
+        &op.account_id,
+        &op.month,
+        op.format,
+        op.zone,
+        op.api_key.as_deref(),
+    )
+}
+
+impl MidenClientWrapper {
+    /// One month of an account's movements and balances (see module docs).
+    pub fn account_statement(
+        &self,
+        account: &str,
+        month: &str,
+        zone: DisplayZone,
+        api_key: Option<&str>,
+    ) -> Result<AccountStatement> {
+        let account_hex = self.account_hex(account)?;
+        self.authorize_account(api_key, &account_hex, "account")?;
+
+        let (year, month_of_year) = parse_month(month)?;
+        let (next_year, next_month) = if month_of_year == 12 {
+            (year + 1, 1)
+        } else {
+            (year, month_of_year + 1)
+        };
+        let unknown = || anyhow::anyhow!("Month {} cannot be placed in {}", month, zone);
+        let period_start = zone.month_start(year, month_of_year).ok_or_else(unknown)?;
+        let month_end = zone
+            .month_start(next_year, next_month)
+            .ok_or_else(unknown)?;
+        let now = chrono::Utc::now().timestamp();
+        if period_start > now {
+            return Err(anyhow::anyhow!("Month {} has not started yet", month));
+        }
+        let period_end = month_end.min(now + 1);
+
+        let scope = PortalScope {
+            account_id: account_hex.clone(),
+        };
+        // Oldest first
+        let mut entries = self.ledger.entries(Some(&account_hex), usize::MAX);
+        entries.reverse();
+
+        let mut balances: BTreeMap<String, StatementBalance> = BTreeMap::new();
+        let mut movements = Vec::new();
+        for entry in entries.into_iter().take_while(|e| e.at < period_end) {
+            for payment in scope.payments(entry) {
+                let balance = balances
+                    .entry(payment.faucet_id.clone())
+                    .or_insert_with(|| {
+                        let units = AccountId::from_hex(&payment.faucet_id)
+                            .ok()
+                            .and_then(|faucet| self.token_units(faucet));
+                        StatementBalance {
+                            faucet_id: payment.faucet_id.clone(),
+                            decimals: units.as_ref().map_or(0, |(_, decimals)| *decimals),
+                            symbol: units.map(|(symbol, _)| symbol),
+                            opening: 0,
+                            received: 0,
+                            sent: 0,
+                            closing: 0,
+                        }
+                    });
+                let signed = match payment.direction {
+                    PaymentDirection::In => payment.amount as i128,
+                    PaymentDirection::Out => -(payment.amount as i128),
+                };
+                balance.closing += signed;
+                if payment.at < period_start {
+                    balance.opening += signed;
+                    continue;
+                }
+                match payment.direction {
+                    PaymentDirection::In => balance.received += payment.amount,
+                    PaymentDirection::Out => balance.sent += payment.amount,
+                }
+                movements.push(StatementLine {
+                    entry_id: payment.entry_id,
+                    at: payment.at,
+                    kind: payment.kind,
+                    direction: payment.direction,
+                    faucet_id: payment.faucet_id,
+                    amount: payment.amount,
+                    counterparties: payment.counterparties,
+                    memo: payment.reference,
+                    tx_id: payment.tx_id,
+                });
+            }
+        }
+
+        Ok(AccountStatement {
+            account_id: account_hex,
+            month: format!("{:04}-{:02}", year, month_of_year),
+            timezone: zone,
+            period_start,
+            period_end,
+            balances: balances.into_values().collect(),
+            movements,
+        })
+    }
+
+    /// A statement as PDF or JSON.
+    pub fn account_statement_artifact(
+        &self,
+        account: &str,
+        month: &str,
+        format: ArtifactFormat,
+        zone: DisplayZone,
+        api_key: Option<&str>,
+    ) -> Result<DocumentArtifact> {
+        let statement = self.account_statement(account, month, zone, api_key)?;
+        let name = format!(
+            "statement-{}-{}",
+            statement.account_id.trim_start_matches("0x"),
+            statement.month
+        );
+        tracing::info!(
+            "Statement {} of {} rendered ({} movement(s))",
+            statement.month,
+            statement.account_id,
+            statement.movements.len()
+        );
+        Ok(match format {
+            ArtifactFormat::Pdf => DocumentArtifact {
+                content_type: "application/pdf",
+                filename: format!("{}.pdf", name),
+                bytes: text_pdf(
+                    &format!("Account statement {}", statement.month),
+                    &statement.to_text(),
+                ),
+            },
+            ArtifactFormat::Json => DocumentArtifact {
+                content_type: "application/json",
+                filename: format!("{}.json", name),
+                bytes: serde_json::to_vec_pretty(&statement)?,
+            },
+        })
+    }
+}
@@ -0,0 +1,414 @@
+// src/store_maintenance.rs
+//
+// Client store pruning and compaction
+//
+// The client store (MIDEN_STORE_PATH) only grows: every sync adds block
+// headers, and notes stay after they are consumed. Maintenance has two steps:
+// - prune: removes block headers that hold none of the client's notes and are
+//   older than the retention horizon (STORE_RETENTION_BLOCKS behind the latest
+//   synced block). The genesis block, the latest block and blocks with client
+//   notes are always kept, as the client needs them to authenticate its notes.
+//   Notes and transactions are kept: the client store has no way to drop them
+//   without losing the history it reports.
+// - vacuum: rebuilds the database file so the space freed by pruning (and by
+//   the client's own deletes) goes back to the filesystem.
+//
+// POST /admin/store/prune (optionally a dry run, optionally followed by a
+// vacuum) and POST /admin/store/vacuum run a step on demand; both need an
+// admin API key. Every STORE_MAINTENANCE_INTERVAL_SECS the scheduler runs
+// both (scheduler.rs). GET /admin/store reports the file size, free pages,
+// rows per table and the last maintenance run.
+//
+// Pruning goes through SQL, so each step first checks that the store has the
+// tables and columns it expects; against a schema it does not know, the step
+// is skipped and the report says why. Steps run on the client task, between
+// commands, so the client never sees a half-pruned store.
+
+use anyhow::Result;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use crate::{bench::millis, MidenClientWrapper};
+
+/// Columns of the block header table pruning relies on.
+const BLOCK_HEADER_COLUMNS: &[&str] = &["block_num", "has_client_notes"];
+
+/// How long a step waits for the client's connections to release the database.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockHeaderStats {
+    pub count: u64,
+    pub with_client_notes: u64,
+    pub earliest: Option<u32>,
+    pub latest: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreStats {
+    pub path: PathBuf,
+    /// Database file plus write-ahead log
+    pub size_bytes: u64,
+    pub wal_bytes: u64,
+    pub page_size: u64,
+    pub page_count: u64,
+    /// Pages a vacuum would give back
+    pub free_pages: u64,
+    pub reclaimable_bytes: u64,
+    pub rows: BTreeMap<String, u64>,
+    pub block_headers: Option<BlockHeaderStats>,
+}
+
+/// Prune request (POST /admin/store/prune).
+#[derive(Debug, Default, Deserialize)]
+pub struct PruneInput {
+    /// Overrides STORE_RETENTION_BLOCKS
+    #[serde(default)]
+    pub retention_blocks: Option<u32>,
+    /// Counts what would be pruned without deleting it
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Vacuums after pruning
+    #[serde(default)]
+    pub vacuum: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PruneReport {
+    pub retention_blocks: u32,
+    pub latest_block: Option<u32>,
+    /// Irrelevant block headers below this block are pruned
+    pub horizon_block: Option<u32>,
+    pub block_headers_pruned: u64,
+    pub dry_run: bool,
+    /// Steps the store does not support, with the reason
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VacuumReport {
+    pub size_before: u64,
+    pub size_after: u64,
+    pub reclaimed_bytes: u64,
+    pub duration_ms: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceTrigger {
+    Scheduled,
+    Manual,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceReport {
+    pub at: i64,
+    pub trigger: MaintenanceTrigger,
+    pub prune: Option<PruneReport>,
+    pub vacuum: Option<VacuumReport>,
+}
+
+/// When maintenance last ran, for the scheduler and GET /admin/store.
+#[derive(Debug)]
+pub struct MaintenanceState {
+    last_run: Instant,
+    last_report: Option<MaintenanceReport>,
+}
+
+impl Default for MaintenanceState {
+    fn default() -> Self {
+        // The first scheduled run comes one interval after startup
+        Self {
+            last_run: Instant::now(),
+            last_report: None,
+        }
+    }
+}
+
+fn open(path: &Path) -> Result<Connection> {
+    if !path.exists() {
+        return Err(anyhow::anyhow!("Store {} not found", path.display()));
+    }
+    let conn = Connection::open(path)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    Ok(conn)
+}
+
+/// Size of the database file and of its write-ahead log.
+fn file_sizes(path: &Path) -> (u64, u64) {
+    let size = |p: &Path| std::fs::metadata(p).map(|m| m.len()).unwrap_or(0);
+    let mut wal = path.as_os_str().to_owned();
+    wal.push("-wal");
+    (size(path), size(Path::new(&wal)))
+}
+
+fn pragma(conn: &Connection, name: &str) -> Result<u64> {
+    let value: i64 = conn.query_row(&format!("PRAGMA {}", name), [], |row| row.get(0))?;
+    Ok(value.max(0) as u64)
+}
+
+fn has_columns(conn: &Connection, table: &str, columns: &[&str]) -> Result<bool> {
+    let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?1)")?;
+    let present = stmt
+        .query_map([table], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(columns.iter().all(|c| present.iter().any(|p| p == c)))
+}
+
+pub fn stats(path: &Path) -> Result<StoreStats> {
+    let conn = open(path)?;
+    let (file_bytes, wal_bytes) = file_sizes(path);
+    let page_size = pragma(&conn, "page_size")?;
+    let free_pages = pragma(&conn, "freelist_count")?;
+
+    let tables = conn
+        .prepare(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+        )?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let mut rows = BTreeMap::new();
+    for table in tables {
+        let count: i64 =
+            conn.query_row(&format!("SELECT COUNT(*) FROM \"{}\"", table), [], |row| {
+                row.get(0)
+            })?;
+        rows.insert(table, count.max(0) as u64);
+    }
+
+    let block_headers = if has_columns(&conn, "block_headers", BLOCK_HEADER_COLUMNS)? {
+        Some(conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(has_client_notes != 0), 0),
+                    MIN(block_num), MAX(block_num)
+             FROM block_headers",
+            [],
+            |row| {
+                Ok(BlockHeaderStats {
+                    count: row.get::<_, i64>(0)?.max(0) as u64,
+                    with_client_notes: row.get::<_, i64>(1)?.max(0) as u64,
+                    earliest: row.get(2)?,
+                    latest: row.get(3)?,
+                })
+            },
+        )?)
+    } else {
+        None
+    };
+
+    Ok(StoreStats {
+        path: path.to_path_buf(),
+        size_bytes: file_bytes + wal_bytes,
+        wal_bytes,
+        page_size,
+        page_count: pragma(&conn, "page_count")?,
+        free_pages,
+        reclaimable_bytes: free_pages * page_size,
+        rows,
+        block_headers,
+    })
+}
+
+/// Prunes block headers older than `retention_blocks` behind the latest
+/// synced block that hold none of the client's notes (see module docs).
+pub fn prune(path: &Path, retention_blocks: u32, dry_run: bool) -> Result<PruneReport> {
+    let mut conn = open(path)?;
+    let mut report = PruneReport {
+        retention_blocks,
+        latest_block: None,
+        horizon_block: None,
+        block_headers_pruned: 0,
+        dry_run,
+        skipped: vec![
+            "notes: the client store keeps consumed notes for the history it reports".to_string(),
+        ],
+    };
+
+    if !has_columns(&conn, "block_headers", BLOCK_HEADER_COLUMNS)? {
+        report
+            .skipped
+            .push("block headers: the store has no block_headers table this version knows".into());
+        return Ok(report);
+    }
+
+    let tx = conn.transaction()?;
+    let latest: Option<u32> =
+        tx.query_row("SELECT MAX(block_num) FROM block_headers", [], |row| {
+            row.get(0)
+        })?;
+    report.latest_block = latest;
+    let Some(horizon) = latest.map(|latest| latest.saturating_sub(retention_blocks)) else {
+        return Ok(report);
+    };
+    report.horizon_block = Some(horizon);
+
+    // The latest block is never below the horizon
+    let filter =
+        "FROM block_headers WHERE has_client_notes = 0 AND block_num > 0 AND block_num < ?1";
+    report.block_headers_pruned = if dry_run {
+        let count: i64 =
+            tx.query_row(&format!("SELECT COUNT(*) {}", filter), [horizon], |row| {
+                row.get(0)
+            })?;
+        count.max(0) as u64
+    } else {
+        tx.execute(&format!("DELETE {}", filter), [horizon])? as u64
+    };
+    tx.commit()?;
+
+    Ok(report)
+}
+
+/// Rebuilds the database file and truncates its write-ahead log.
+pub fn vacuum(path: &Path) -> Result<VacuumReport> {
+    let conn = open(path)?;
+    let started = Instant::now();
+    let (file_bytes, wal_bytes) = file_sizes(path);
+    let size_before = file_bytes + wal_bytes;
+
+    conn.execute_batch("VACUUM")?;
+    // Only meaningful in WAL mode; a no-op otherwise
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+
+    let (file_bytes, wal_bytes) = file_sizes(path);
+    let size_after = file_bytes + wal_bytes;
+    Ok(VacuumReport {
+        size_before,
+        size_after,
+        reclaimed_bytes: size_before.saturating_sub(size_after),
+        duration_ms: millis(started.elapsed()),
+    })
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    GetStoreStats {} -> serde_json::Value;
+    |client, _op| client.store_stats().await;
+
+    PruneStore {
+        input: PruneInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.prune_store(op.input, op.api_key.as_deref()).await;
+
+    VacuumStore {
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.vacuum_store(op.api_key.as_deref()).await
+}
+
+impl MidenClientWrapper {
+    async fn blocking_store<T, F>(&self, step: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Path) -> Result<T> + Send + 'static,
+    {
+        let path = self.config.store_path.clone();
+        tokio::task::spawn_blocking(move || step(&path)).await?
+    }
+
+    pub async fn store_stats(&self) -> Result<serde_json::Value> {
+        let stats = self.blocking_store(stats).await?;
+        Ok(serde_json::json!({
+            "stats": stats,
+            "retention_blocks": self.config.store_retention_blocks,
+            "maintenance_interval_secs": self.config.store_maintenance_interval.as_secs(),
+            "last_maintenance": self.store_maintenance.last_report,
+        }))
+    }
+
+    async fn run_store_steps(
+        &mut self,
+        trigger: MaintenanceTrigger,
+        prune_with: Option<(u32, bool)>,
+        then_vacuum: bool,
+    ) -> Result<MaintenanceReport> {
+        let pruned = match prune_with {
+            Some((retention, dry_run)) => Some(
+                self.blocking_store(move |path| prune(path, retention, dry_run))
+                    .await?,
+            ),
+            None => None,
+        };
+        let vacuumed = if then_vacuum {
+            Some(self.blocking_store(vacuum).await?)
+        } else {
+            None
+        };
+
+        let report = MaintenanceReport {
+            at: chrono::Utc::now().timestamp(),
+            trigger,
+            prune: pruned,
+            vacuum: vacuumed,
+        };
+        tracing::info!(
+            "🧹 Store maintenance ({:?}): {} block header(s) pruned, {} byte(s) reclaimed",
+            trigger,
+            report.prune.as_ref().map_or(0, |p| p.block_headers_pruned),
+            report.vacuum.as_ref().map_or(0, |v| v.reclaimed_bytes)
+        );
+        let dry_run = report.prune.as_ref().is_some_and(|p| p.dry_run);
+        if !dry_run {
+            self.store_maintenance.last_run = Instant::now();
+            self.store_maintenance.last_report = Some(report.clone());
+        }
+        Ok(report)
+    }
+
+    /// Prunes the store (POST /admin/store/prune). Admin only.
+    pub async fn prune_store(
+        &mut self,
+        input: PruneInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        self.admin_principal(api_key, "store maintenance")?;
+        let retention = input
+            .retention_blocks
+            .unwrap_or(self.config.store_retention_blocks);
+        // A dry run changes nothing, so there is nothing to vacuum
+        let vacuum = input.vacuum && !input.dry_run;
+        let report = self
+            .run_store_steps(
+                MaintenanceTrigger::Manual,
+                Some((retention, input.dry_run)),
+                vacuum,
+            )
+            .await?;
+        Ok(serde_json::json!(report))
+    }
+
+    /// Vacuums the store (POST /admin/store/vacuum). Admin only.
+    pub async fn vacuum_store(&mut self, api_key: Option<&str>) -> Result<serde_json::Value> {
+        self.admin_principal(api_key, "store maintenance")?;
+        let report = self
+            .run_store_steps(MaintenanceTrigger::Manual, None, true)
+            .await?;
+        Ok(serde_json::json!(report))
+    }
+
+    /// Prunes and vacuums once STORE_MAINTENANCE_INTERVAL_SECS have passed
+    /// since the last run. Returns the number of block headers pruned.
+    pub async fn run_store_maintenance(&mut self) -> Result<usize> {
+        let interval = self.config.store_maintenance_interval;
+        if interval.is_zero() || self.store_maintenance.last_run.elapsed() < interval {
+            return Ok(0);
+        }
+        let retention = self.config.store_retention_blocks;
+        let report = self
+            .run_store_steps(
+                MaintenanceTrigger::Scheduled,
+                Some((retention, false)),
+                true,
+            )
+            .await?;
+        Ok(report.prune.map_or(0, |p| p.block_headers_pruned) as usize)
+    }
+}
@@ -0,0 +1,665 @@
+// src/subscriptions.rs
+//
+// Account activity subscriptions
+//
+// Integrators subscribe to event types for specific accounts or properties
+// (POST /subscriptions) instead of polling, e.g. "any note addressed to account
+// X over 1000 PROP":
+//   {"event_types": ["note_received"], "account_ids": ["0x..."],
+//    "faucet_id": "PROP", "min_amount": 100000000000,
+//    "delivery": {"type": "webhook", "url": "https://..."}}
+//
+// Events come from the feeds the client task already publishes:
+// - note_received / note_consumed / balance_changed: per-account sync deltas
+//   (sync_deltas.rs)
+// - auction: auction events (auctions.rs), by property
+// - stale_escrow: stale escrow flags (escrow_monitor.rs), by escrow account
+//
+// A subscription matches an event of one of its types whose account is one of
+// `account_ids` or whose property is one of `property_ids` (any subject when
+// both are empty). With `faucet_id` (hex, or "PROP" for the service token)
+// and/or `min_amount` (base units) the event must also move at least that much
+// of that token: a note's assets, or the size of a balance change.
+//
+// The dispatcher runs in its own task next to the handlers, so a slow webhook
+// never holds up the client queue. Deliveries:
+// - webhook: POSTed as JSON with an X-Obscura-Signature header, "sha256=" and
+//   the hex HMAC-SHA256 of the body under the subscription secret (returned
+//   once, on creation). Failed posts are retried WEBHOOK_ATTEMPTS times with
+//   doubling backoff, then dropped with a warning.
+// - websocket: streamed to GET /subscriptions/:id/ws connections, one JSON text
+//   message per delivery.
+// Webhook deliveries are dropped while the webhooks feature is off
+// (feature_flags.rs).
+//
+// Subscriptions belong to the API key that created them. Keys without the
+// admin role must name accounts they are bound to, or properties those
+// accounts own. Revoking a key removes its subscriptions.
+
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
+use tokio::sync::{broadcast, watch};
+
+use crate::{
+    auctions::AuctionEvent,
+    currency::SERVICE_TOKEN_SYMBOL,
+    escrow::EscrowAuthError,
+    escrow_monitor::StaleEscrowEvent,
+    feature_flags::{FeatureFlag, FeatureFlags},
+    principals::Principal,
+    sync_deltas::{NoteAsset, SyncDelta},
+    MidenClientWrapper,
+};
+
+/// Buffered websocket deliveries per connection before it starts missing some.
+pub const DELIVERY_FEED_CAPACITY: usize = 256;
+
+pub const SIGNATURE_HEADER: &str = "x-obscura-signature";
+
+/// Posts per webhook delivery before it is dropped
+const WEBHOOK_ATTEMPTS: u32 = 3;
+const WEBHOOK_BACKOFF: Duration = Duration::from_secs(2);
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventType {
+    NoteReceived,
+    NoteConsumed,
+    BalanceChanged,
+    Auction,
+    StaleEscrow,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Delivery {
+    Webhook { url: String },
+    Websocket,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubscriptionInput {
+    pub event_types: Vec<EventType>,
+    /// Accounts (names or hex)
+    #[serde(default)]
+    pub account_ids: Vec<String>,
+    #[serde(default)]
+    pub property_ids: Vec<String>,
+    /// Token amounts are counted in: hex faucet ID, or "PROP"
+    pub faucet_id: Option<String>,
+    /// Minimum amount moved, in base units
+    pub min_amount: Option<u64>,
+    pub delivery: Delivery,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subscription {
+    pub subscription_id: String,
+    /// API key that created the subscription
+    pub key_id: u64,
+    pub event_types: Vec<EventType>,
+    /// Hex AccountIds
+    pub account_ids: Vec<String>,
+    pub property_ids: Vec<String>,
+    /// Hex faucet ID
+    pub faucet_id: Option<String>,
+    pub min_amount: Option<u64>,
+    pub delivery: Delivery,
+    /// Webhook signing key; blank outside the store
+    pub secret: String,
+    pub created_at: i64,
+}
+
+impl Subscription {
+    /// Copy safe to return from the API (secret blanked).
+    pub fn public(&self) -> Subscription {
+        Subscription {
+            secret: String::new(),
+            ..self.clone()
+        }
+    }
+
+    pub fn matches(&self, event: &ActivityEvent) -> bool {
+        if !self.event_types.contains(&event.event_type) {
+            return false;
+        }
+
+        let any_subject = self.account_ids.is_empty() && self.property_ids.is_empty();
+        let account_match = event.account_id.as_ref().is_some_and(|account| {
+            self.account_ids
+                .iter()
+                .any(|a| a.eq_ignore_ascii_case(account))
+        });
+        let property_match = event
+            .property_id
+            .as_ref()
+            .is_some_and(|property| self.property_ids.contains(property));
+        if !(any_subject || account_match || property_match) {
+            return false;
+        }
+
+        if self.faucet_id.is_none() && self.min_amount.is_none() {
+            return true;
+        }
+        event.amounts.iter().any(|moved| {
+            let faucet_match = match &self.faucet_id {
+                Some(faucet_id) => faucet_id.eq_ignore_ascii_case(&moved.faucet_id),
+                None => true,
+            };
+            faucet_match && moved.amount >= self.min_amount.unwrap_or(0)
+        })
+    }
+}
+
+/// An event as seen by subscriptions.
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityEvent {
+    pub event_type: EventType,
+    pub account_id: Option<String>,
+    pub property_id: Option<String>,
+    /// Fungible amounts the event moved: a note's assets, a balance change
+    pub amounts: Vec<NoteAsset>,
+    pub occurred_at: i64,
+    /// The source event
+    pub detail: serde_json::Value,
+}
+
+impl ActivityEvent {
+    /// One event per new note, consumed note and balance change in a delta.
+    pub fn from_sync_delta(delta: &SyncDelta) -> Vec<ActivityEvent> {
+        let mut events = Vec::new();
+        for account in &delta.accounts {
+            let event = |event_type, amounts, detail| ActivityEvent {
+                event_type,
+                account_id: Some(account.account_id.clone()),
+                property_id: None,
+                amounts,
+                occurred_at: delta.synced_at,
+                detail,
+            };
+            for note in &account.new_notes {
+                events.push(event(
+                    EventType::NoteReceived,
+                    note.assets.clone(),
+                    serde_json::json!(note),
+                ));
+            }
+            for note_id in &account.consumed_notes {
+                events.push(event(
+                    EventType::NoteConsumed,
+                    Vec::new(),
+                    serde_json::json!({ "note_id": note_id }),
+                ));
+            }
+            for change in &account.balance_changes {
+                let moved = NoteAsset {
+                    faucet_id: change.faucet_id.clone(),
+                    amount: change.after.abs_diff(change.before),
+                };
+                events.push(event(
+                    EventType::BalanceChanged,
+                    vec![moved],
+                    serde_json::json!(change),
+                ));
+            }
+        }
+        events
+    }
+
+    pub fn from_auction(auction_event: &AuctionEvent) -> ActivityEvent {
+        ActivityEvent {
+            event_type: EventType::Auction,
+            account_id: None,
+            property_id: Some(auction_event.property_id.clone())
+                .filter(|property_id| !property_id.is_empty()),
+            amounts: Vec::new(),
+            occurred_at: auction_event.at,
+            detail: serde_json::json!(auction_event),
+        }
+    }
+
+    pub fn from_stale_escrow(stale_event: &StaleEscrowEvent) -> ActivityEvent {
+        ActivityEvent {
+            event_type: EventType::StaleEscrow,
+            account_id: Some(stale_event.escrow_account_id.clone()),
+            property_id: None,
+            amounts: Vec::new(),
+            occurred_at: stale_event.at,
+            detail: serde_json::json!(stale_event),
+        }
+    }
+}
+
+/// An event delivered to one subscription.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubscriptionDelivery {
+    pub subscription_id: String,
+    /// Unique per delivery, for receivers to drop retried duplicates
+    pub delivery_id: String,
+    pub event: ActivityEvent,
+}
+
+/// HMAC-SHA256 of a webhook body, as sent in the signature header.
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SubscriptionStore {
+    #[serde(skip)]
+    path: PathBuf,
+    /// Current subscriptions, for the dispatcher
+    #[serde(skip)]
+    watch: Option<watch::Sender<Vec<Subscription>>>,
+    #[serde(default)]
+    subscriptions: BTreeMap<String, Subscription>,
+    #[serde(default)]
+    next_subscription_id: u64,
+}
+
+impl SubscriptionStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<SubscriptionStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            SubscriptionStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Hands the subscriptions to the dispatcher now and after every change.
+    pub fn attach_watch(&mut self, watch: watch::Sender<Vec<Subscription>>) {
+        self.watch = Some(watch);
+        self.publish();
+    }
+
+    fn publish(&self) {
+        if let Some(watch) = &self.watch {
+            watch.send_replace(self.subscriptions.values().cloned().collect());
+        }
+    }
+
+    /// Stores a new subscription; returns it with its secret.
+    pub fn create(
+        &mut self,
+        key_id: u64,
+        input: SubscriptionInput,
+        account_ids: Vec<String>,
+        faucet_id: Option<String>,
+    ) -> Result<Subscription> {
+        self.next_subscription_id += 1;
+        let subscription = Subscription {
+            subscription_id: format!("sub-{}", self.next_subscription_id),
+            key_id,
+            event_types: input.event_types,
+            account_ids,
+            property_ids: input.property_ids,
+            faucet_id,
+            min_amount: input.min_amount,
+            delivery: input.delivery,
+            secret: hex::encode(rand::random::<[u8; 32]>()),
+            created_at: chrono::Utc::now().timestamp(),
+        };
+
+        self.subscriptions
+            .insert(subscription.subscription_id.clone(), subscription.clone());
+        self.save()?;
+        self.publish();
+        Ok(subscription)
+    }
+
+    pub fn get(&self, subscription_id: &str) -> Result<&Subscription> {
+        self.subscriptions
+            .get(subscription_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown subscription: {}", subscription_id))
+    }
+
+    /// Subscriptions of one API key, or all of them.
+    pub fn list(&self, key_id: Option<u64>) -> Vec<Subscription> {
+        self.subscriptions
+            .values()
+            .filter(|s| key_id.is_none_or(|key_id| s.key_id == key_id))
+            .map(Subscription::public)
+            .collect()
+    }
+
+    pub fn remove(&mut self, subscription_id: &str) -> Result<Subscription> {
+        let subscription = self
+            .subscriptions
+            .remove(subscription_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown subscription: {}", subscription_id))?;
+        self.save()?;
+        self.publish();
+        Ok(subscription.public())
+    }
+
+    /// Drops the subscriptions of a revoked API key; returns how many.
+    pub fn remove_for_key(&mut self, key_id: u64) -> Result<usize> {
+        let before = self.subscriptions.len();
+        self.subscriptions.retain(|_, s| s.key_id != key_id);
+        let removed = before - self.subscriptions.len();
+        if removed > 0 {
+            self.save()?;
+            self.publish();
+        }
+        Ok(removed)
+    }
+}
+
+// =============================================================================
+// DISPATCHER
+// =============================================================================
+
+/// Matches events from the feeds against the subscriptions and delivers them.
+/// Runs until one of the feeds closes.
+pub async fn run_dispatcher(
+    subscriptions: watch::Receiver<Vec<Subscription>>,
+    mut sync_feed: broadcast::Receiver<SyncDelta>,
+    mut auction_feed: broadcast::Receiver<AuctionEvent>,
+    mut stale_escrow_feed: broadcast::Receiver<StaleEscrowEvent>,
+    deliveries: broadcast::Sender<SubscriptionDelivery>,
+    features: FeatureFlags,
+) {
+    let http = match reqwest::Client::builder().timeout(WEBHOOK_TIMEOUT).build() {
+        Ok(http) => http,
+        Err(e) => {
+            tracing::error!("Subscription dispatcher not started: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        let events = tokio::select! {
+            delta = sync_feed.recv() => match received(delta, "sync") {
+                Some(delta) => delta.map(|d| ActivityEvent::from_sync_delta(&d)),
+                None => return,
+            },
+            event = auction_feed.recv() => match received(event, "auction") {
+                Some(event) => event.map(|e| vec![ActivityEvent::from_auction(&e)]),
+                None => return,
+            },
+            event = stale_escrow_feed.recv() => match received(event, "stale escrow") {
+                Some(event) => event.map(|e| vec![ActivityEvent::from_stale_escrow(&e)]),
+                None => return,
+            },
+        };
+
+        let subscriptions = subscriptions.borrow().clone();
+        for event in events.unwrap_or_default() {
+            for subscription in subscriptions.iter().filter(|s| s.matches(&event)) {
+                let delivery = SubscriptionDelivery {
+                    subscription_id: subscription.subscription_id.clone(),
+                    delivery_id: hex::encode(rand::random::<[u8; 8]>()),
+                    event: event.clone(),
+                };
+                match &subscription.delivery {
+                    Delivery::Webhook { .. } if !features.is_enabled(FeatureFlag::Webhooks) => {
+                        tracing::debug!(
+                            "Webhook delivery to subscription {} dropped: webhooks are turned off",
+                            subscription.subscription_id
+                        );
+                    }
+                    Delivery::Webhook { url } => {
+                        tokio::spawn(post_webhook(
+                            http.clone(),
+                            url.clone(),
+                            subscription.secret.clone(),
+                            delivery,
+                        ));
+                    }
+                    Delivery::Websocket => {
+                        // No open connections is not an error
+                        let _ = deliveries.send(delivery);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Outer None: the feed closed. Inner None: events were missed (logged).
+fn received<T>(result: Result<T, broadcast::error::RecvError>, feed: &str) -> Option<Option<T>> {
+    match result {
+        Ok(value) => Some(Some(value)),
+        Err(broadcast::error::RecvError::Lagged(missed)) => {
+            tracing::warn!(
+                "⚠️  Subscription dispatcher missed {} {} event(s)",
+                missed,
+                feed
+            );
+            Some(None)
+        }
+        Err(broadcast::error::RecvError::Closed) => None,
+    }
+}
+
+async fn post_webhook(
+    http: reqwest::Client,
+    url: String,
+    secret: String,
+    delivery: SubscriptionDelivery,
+) {
+    let body = match serde_json::to_vec(&delivery) {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("Failed to encode delivery {}: {}", delivery.delivery_id, e);
+            return;
+        }
+    };
+    let signature = sign_payload(&secret, &body);
+
+    let mut backoff = WEBHOOK_BACKOFF;
+    for attempt in 1..=WEBHOOK_ATTEMPTS {
+        let result = http
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, &signature)
+            .body(body.clone())
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        match result {
+            Ok(_) => return,
+            Err(e) if attempt < WEBHOOK_ATTEMPTS => {
+                tracing::debug!(
+                    "Webhook {} for {} failed (attempt {}): {}",
+                    delivery.delivery_id,
+                    delivery.subscription_id,
+                    attempt,
+                    e
+                );
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+            Err(e) => tracing::warn!(
+                "⚠️  Dropped webhook {} for {} after {} attempts: {}",
+                delivery.delivery_id,
+                delivery.subscription_id,
+                WEBHOOK_ATTEMPTS,
+                e
+            ),
+        }
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    CreateSubscription {
+        input: SubscriptionInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.create_subscription(op.input, op.api_key.as_deref());
+
+    ListSubscriptions {
+        api_key: Option<String>,
+    } -> Vec<Subscription>;
+    |client, op| client.list_subscriptions(op.api_key.as_deref());
+
+    AuthorizeSubscription {
+        subscription_id: String,
+        api_key: Option<String>,
+    } -> Subscription;
+    |client, op| client.authorize_subscription(&op.subscription_id, op.api_key.as_deref());
+
+    DeleteSubscription {
+        subscription_id: String,
+        api_key: Option<String>,
+    } -> Subscription;
+    |client, op| client.delete_subscription(&op.subscription_id, op.api_key.as_deref())
+}
+
+impl MidenClientWrapper {
+    /// Hands subscriptions to the dispatcher (see main.rs).
+    pub fn attach_subscription_watch(&mut self, watch: watch::Sender<Vec<Subscription>>) {
+        self.subscriptions.attach_watch(watch);
+    }
+
+    fn subscriber(&self, api_key: Option<&str>) -> Result<&Principal> {
+        let key = api_key.ok_or_else(|| {
+            EscrowAuthError::Unauthenticated(
+                "X-API-Key header is required for subscriptions".into(),
+            )
+        })?;
+        Ok(self
+            .principals
+            .authenticate(key)
+            .ok_or_else(|| EscrowAuthError::Unauthenticated("Invalid or revoked API key".into()))?)
+    }
+
+    /// A subscription the key may read or delete: its own, or any for admins.
+    fn owned_subscription(
+        &self,
+        subscription_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<&Subscription> {
+        let principal = self.subscriber(api_key)?;
+        let subscription = self.subscriptions.get(subscription_id)?;
+        if !principal.admin && subscription.key_id != principal.key_id {
+            return Err(EscrowAuthError::Forbidden(format!(
+                "Subscription {} belongs to another API key",
+                subscription_id
+            ))
+            .into());
+        }
+        Ok(subscription)
+    }
+
+    pub fn create_subscription(
+        &mut self,
+        input: SubscriptionInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let principal = self.subscriber(api_key)?.clone();
+
+        let account_ids = input
+            .account_ids
+            .iter()
+            .map(|account| self.account_hex(account))
+            .collect::<Result<Vec<_>>>()?;
+        for property_id in &input.property_ids {
+            let property = self
+                .records
+                .properties
+                .get(property_id)
+                .ok_or_else(|| anyhow::anyhow!("Unknown property: {}", property_id))?;
+            if !principal.admin && !principal.owns(&property.owner_account_id) {
+                return Err(EscrowAuthError::Forbidden(format!(
+                    "API key {} is not bound to the owner of {}",
+                    principal.key_id, property_id
+                ))
+                .into());
+            }
+        }
+        if !principal.admin {
+            if account_ids.is_empty() && input.property_ids.is_empty() {
+                return Err(EscrowAuthError::Forbidden(
+                    "Subscribing to every account needs an admin API key".into(),
+                )
+                .into());
+            }
+            if let Some(account) = account_ids.iter().find(|a| !principal.owns(a)) {
+                return Err(EscrowAuthError::Forbidden(format!(
+                    "API key {} is not bound to {}",
+                    principal.key_id, account
+                ))
+                .into());
+            }
+        }
+
+        let faucet_id = match &input.faucet_id {
+            Some(symbol) if symbol == SERVICE_TOKEN_SYMBOL => Some(self.account_hex("faucet")?),
+            Some(faucet_id) => Some(faucet_id.to_lowercase()),
+            None => None,
+        };
+
+        let subscription =
+            self.subscriptions
+                .create(principal.key_id, input, account_ids, faucet_id)?;
+        tracing::info!(
+            "Created subscription {} for API key {}",
+            subscription.subscription_id,
+            principal.key_id
+        );
+        Ok(serde_json::json!({
+            "subscription": subscription.public(),
+            "secret": subscription.secret,
+        }))
+    }
+
+    /// Subscriptions of the key; admins see all of them.
+    pub fn list_subscriptions(&self, api_key: Option<&str>) -> Result<Vec<Subscription>> {
+        let principal = self.subscriber(api_key)?;
+        let key_id = (!principal.admin).then_some(principal.key_id);
+        Ok(self.subscriptions.list(key_id))
+    }
+
+    /// The subscription, if the key may stream it (GET /subscriptions/:id/ws).
+    pub fn authorize_subscription(
+        &self,
+        subscription_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<Subscription> {
+        let subscription = self.owned_subscription(subscription_id, api_key)?;
+        if subscription.delivery != Delivery::Websocket {
+            return Err(anyhow::anyhow!(
+                "Subscription {} is delivered by webhook",
+                subscription_id
+            ));
+        }
+        Ok(subscription.public())
+    }
+
+    pub fn delete_subscription(
+        &mut self,
+        subscription_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<Subscription> {
+        self.owned_subscription(subscription_id, api_key)?;
+        let subscription = self.subscriptions.remove(subscription_id)?;
+        tracing::info!("Deleted subscription {}", subscription_id);
+        Ok(subscription)
+    }
+}
@@ -0,0 +1,955 @@
+// src/swaps.rs
+//
+// Token swaps through the service's liquidity account
+//
+// A buyer holding one token can settle a listing priced in another by swapping
+// first. The service quotes exchanges between the service token (PROP) and the
+// registered settlement tokens (settlement_tokens.rs) out of a liquidity
+// account it holds in its client store (SWAP_LIQUIDITY_ACCOUNT, a name or hex
+// AccountId):
+// - rates are fixed per direction in SWAP_RATES, such as
+//   "USDT/PROP=0.95,PROP/USDT=1.04" (whole PROP per whole USDT), or asked of
+//   SWAP_ORACLE_URL for pairs not listed there (GET
+//   {url}?base=USDT&quote=PROP answering {"rate": "0.95"})
+// - SWAP_SPREAD_BPS comes off every rate; amounts are rounded in the liquidity
+//   account's favour
+// - a quote holds for SWAP_QUOTE_TTL_SECS
+//
+// POST /swaps/quotes ({account_id, from, to, amount_in | amount_out}) quotes
+// either what `amount_in` buys or what `amount_out` costs. With
+// escrow_account_id instead of `to` and an amount, the quote is for the whole
+// amount of an unfunded escrow the account is the buyer of, in the escrow's
+// settlement token. The liquidity account must hold what the quote pays out.
+//
+// POST /swaps/quotes/:quote_id/execute swaps atomically with paired notes: the
+// account sends a SWAP note offering its tokens and requesting the quoted
+// ones, and the liquidity account consumes it, which takes the offer and pays
+// the request back to the account in a payback note in the same transaction.
+// Neither side can take one leg without the other. A fill that fails leaves
+// the quote executing; the swaps scheduler job retries it, MAX_FILL_ATTEMPTS
+// times in all, and expires quotes nobody executed. Both legs are journaled
+// in the ledger as swaps.
+//
+// GET /swaps/quotes/:quote_id shows a quote and its fill; GET
+// /swaps?account_id= lists an account's quotes, newest first. With
+// ESCROW_AUTH_REQUIRED, quoting, executing and reading need an API key bound
+// to the account. Quotes are kept in SWAPS_PATH.
+
+use anyhow::Result;
+use miden_client::{
+    account::AccountId,
+    asset::FungibleAsset,
+    note::{create_swap_note, NoteId, NoteType},
+    transaction::{OutputNote, TransactionRequestBuilder},
+    Felt,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, path::PathBuf, str::FromStr, time::Duration};
+
+use crate::{
+    account_id_to_hex,
+    escrow::EscrowStatus,
+    ledger::{JournalKind, Posting},
+    read_cache::Touched,
+    reconcile::parse_hex_account_id,
+    MidenClientWrapper,
+};
+
+/// Fill attempts before an executing quote is marked failed
+pub const MAX_FILL_ATTEMPTS: u32 = 5;
+/// Read cache alias of the liquidity account (read_cache.rs)
+pub const LIQUIDITY_ALIAS: &str = "swap-liquidity";
+/// Fraction digits a rate may have
+pub const RATE_DECIMALS: u32 = 9;
+const RATE_SCALE: u128 = 10u128.pow(RATE_DECIMALS);
+const ORACLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Whole units of the quote token per whole unit of the base token, in
+/// billionths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Rate(u128);
+
+impl Rate {
+    /// The rate less `spread_bps` basis points, rounded down.
+    pub fn less_spread(self, spread_bps: u64) -> Rate {
+        // Split so that no rate overflows on the way
+        let kept = (10_000 - spread_bps.min(10_000)) as u128;
+        Rate(self.0 / 10_000 * kept + self.0 % 10_000 * kept / 10_000)
+    }
+
+    /// Base units of the quote token `amount_in` base units of the base token
+    /// buy, rounded down; None on overflow.
+    pub fn convert(self, amount_in: u64, from_decimals: u8, to_decimals: u8) -> Option<u64> {
+        let numerator = (amount_in as u128)
+            .checked_mul(self.0)?
+            .checked_mul(10u128.checked_pow(to_decimals as u32)?)?;
+        let denominator = RATE_SCALE.checked_mul(10u128.checked_pow(from_decimals as u32)?)?;
+        u64::try_from(numerator / denominator).ok()
+    }
+
+    /// Base units of the base token that buy `amount_out` base units of the
+    /// quote token, rounded up; None on overflow or a zero rate.
+    pub fn required_input(
+        self,
+        amount_out: u64,
+        from_decimals: u8,
+        to_decimals: u8,
+    ) -> Option<u64> {
+        let numerator = (amount_out as u128)
+            .checked_mul(RATE_SCALE)?
+            .checked_mul(10u128.checked_pow(from_decimals as u32)?)?;
+        let denominator = self
+            .0
+            .checked_mul(10u128.checked_pow(to_decimals as u32)?)?;
+        if denominator == 0 {
+            return None;
+        }
+        u64::try_from(numerator.div_ceil(denominator)).ok()
+    }
+}
+
+impl FromStr for Rate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+        if (whole.is_empty() && fraction.is_empty())
+            || !whole
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(anyhow::anyhow!("Invalid rate {:?}", s));
+        }
+        if fraction.len() > RATE_DECIMALS as usize {
+            return Err(anyhow::anyhow!(
+                "Rate {} has more than {} decimal places",
+                s,
+                RATE_DECIMALS
+            ));
+        }
+        let whole: u128 = if whole.is_empty() { 0 } else { whole.parse()? };
+        let fraction: u128 =
+            format!("{:0<width$}", fraction, width = RATE_DECIMALS as usize).parse()?;
+        let rate = whole
+            .checked_mul(RATE_SCALE)
+            .and_then(|w| w.checked_add(fraction))
+            .ok_or_else(|| anyhow::anyhow!("Rate {} is too large", s))?;
+        if rate == 0 {
+            return Err(anyhow::anyhow!("Rate must be positive"));
+        }
+        Ok(Rate(rate))
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let whole = self.0 / RATE_SCALE;
+        let fraction = self.0 % RATE_SCALE;
+        if fraction == 0 {
+            return write!(f, "{}", whole);
+        }
+        let fraction = format!("{:0width$}", fraction, width = RATE_DECIMALS as usize);
+        write!(f, "{}.{}", whole, fraction.trim_end_matches('0'))
+    }
+}
+
+impl Serialize for Rate {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rate {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Parses SWAP_RATES: comma-separated `FROM/TO=rate` entries, one per
+/// direction.
+pub fn parse_swap_rates(list: &str) -> Result<BTreeMap<(String, String), Rate>> {
+    let mut rates = BTreeMap::new();
+    for entry in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (pair, rate) = entry
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Expected FROM/TO=rate, got {:?}", entry))?;
+        let (from, to) = pair
+            .split_once('/')
+            .ok_or_else(|| anyhow::anyhow!("Expected FROM/TO=rate, got {:?}", entry))?;
+        let from = from.trim().to_uppercase();
+        let to = to.trim().to_uppercase();
+        if from.is_empty() || to.is_empty() || from == to {
+            return Err(anyhow::anyhow!("Invalid token pair {:?}", pair));
+        }
+        let rate: Rate = rate.parse()?;
+        if rates.insert((from.clone(), to.clone()), rate).is_some() {
+            return Err(anyhow::anyhow!("Rate for {}/{} is given twice", from, to));
+        }
+    }
+    Ok(rates)
+}
+
+#[derive(Debug, Clone)]
+pub struct SwapPolicy {
+    /// Account name or hex AccountId quotes are filled from
+    pub liquidity_account: String,
+    pub rates: BTreeMap<(String, String), Rate>,
+    pub oracle_url: Option<String>,
+    pub spread_bps: u64,
+    pub quote_ttl: Duration,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SwapQuoteInput {
+    /// Account name or hex AccountId swapping
+    pub account_id: String,
+    /// Symbol of the token offered
+    pub from: String,
+    /// Symbol of the token wanted; the escrow's token when escrow_account_id is
+    /// given
+    pub to: Option<String>,
+    pub amount_in: Option<u64>,
+    pub amount_out: Option<u64>,
+    /// Unfunded escrow the swap is to fund
+    pub escrow_account_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateSource {
+    Fixed,
+    Oracle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuoteStatus {
+    Open,
+    /// The SWAP note is out; the liquidity account has yet to fill it
+    Executing,
+    Filled,
+    Expired,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwapLeg {
+    pub symbol: String,
+    pub faucet_id: String,
+    /// Base units of the token
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwapFill {
+    pub swap_note_id: String,
+    pub swap_tx_id: String,
+    /// Note paying `receive` to the account, created by the fill
+    pub payback_note_id: String,
+    pub fill_tx_id: Option<String>,
+    pub filled_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwapQuote {
+    pub quote_id: u64,
+    pub account_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub escrow_account_id: Option<String>,
+    /// What the account pays
+    pub give: SwapLeg,
+    /// What the account gets
+    pub receive: SwapLeg,
+    /// Rate after the spread
+    pub rate: Rate,
+    pub source: RateSource,
+    pub spread_bps: u64,
+    pub status: QuoteStatus,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub fill: Option<SwapFill>,
+    #[serde(default)]
+    pub attempts: u32,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SwapStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    quotes: BTreeMap<u64, SwapQuote>,
+    #[serde(default)]
+    next_quote_id: u64,
+}
+
+impl SwapStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<SwapStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            SwapStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Stores a new quote under the next ID.
+    pub fn insert(&mut self, mut quote: SwapQuote) -> Result<SwapQuote> {
+        self.next_quote_id += 1;
+        quote.quote_id = self.next_quote_id;
+        self.quotes.insert(quote.quote_id, quote.clone());
+        self.save()?;
+        Ok(quote)
+    }
+
+    pub fn get(&self, quote_id: u64) -> Result<&SwapQuote> {
+        self.quotes
+            .get(&quote_id)
+            .ok_or_else(|| anyhow::anyhow!("Swap quote {} not found", quote_id))
+    }
+
+    /// Replaces a stored quote.
+    pub fn update(&mut self, quote: SwapQuote) -> Result<()> {
+        self.quotes.insert(quote.quote_id, quote);
+        self.save()
+    }
+
+    /// Quotes of an account, newest first.
+    pub fn list(&self, account_hex: &str) -> Vec<&SwapQuote> {
+        self.quotes
+            .values()
+            .rev()
+            .filter(|q| q.account_id.eq_ignore_ascii_case(account_hex))
+            .collect()
+    }
+
+    /// Quotes whose SWAP note is out but not yet filled.
+    pub fn executing(&self) -> Vec<u64> {
+        self.quotes
+            .values()
+            .filter(|q| q.status == QuoteStatus::Executing)
+            .map(|q| q.quote_id)
+            .collect()
+    }
+
+    /// Marks open quotes past their expiry expired. Returns how many were.
+    pub fn expire(&mut self, now: i64) -> Result<usize> {
+        let mut expired = 0;
+        for quote in self.quotes.values_mut() {
+            if quote.status == QuoteStatus::Open && quote.expires_at <= now {
+                quote.status = QuoteStatus::Expired;
+                expired += 1;
+            }
+        }
+        if expired > 0 {
+            self.save()?;
+        }
+        Ok(expired)
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    QuoteSwap {
+        input: SwapQuoteInput,
+        api_key: Option<String>,
+    } -> SwapQuote;
+    |client, op| client.quote_swap(op.input, op.api_key.as_deref()).await;
+
+    /// `account_id` is the quote's account, read by the caller beforehand: the
+    /// swap moves its funds and the liquidity account's.
+    ExecuteSwap {
+        quote_id: u64,
+        account_id: String,
+        api_key: Option<String>,
+    } -> SwapQuote;
+    touched: |op| Some(Touched::Accounts(vec![op.account_id.clone(), LIQUIDITY_ALIAS.to_string()]));
+    |client, op| client.execute_swap(op.quote_id, op.api_key.as_deref()).await;
+
+    GetSwapQuote {
+        quote_id: u64,
+        api_key: Option<String>,
+    } -> SwapQuote;
+    |client, op| client.get_swap_quote(op.quote_id, op.api_key.as_deref());
+
+    ListSwapQuotes {
+        account_id: String,
+        api_key: Option<String>,
+    } -> Vec<SwapQuote>;
+    |client, op| client.list_swap_quotes(&op.account_id, op.api_key.as_deref())
+}
+
+impl MidenClientWrapper {
+    fn swap_policy(&self) -> Result<SwapPolicy> {
+        self.config
+            .swaps
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Swaps are not configured (SWAP_LIQUIDITY_ACCOUNT)"))
+    }
+
+    /// Hex ID of the liquidity account, if swaps are configured and it resolves.
+    pub fn swap_liquidity_account_hex(&self) -> Option<String> {
+        let policy = self.config.swaps.as_ref()?;
+        self.account_hex(&policy.liquidity_account).ok()
+    }
+
+    /// Quotes a swap for an account. Needs an API key bound to the account.
+    pub async fn quote_swap(
+        &mut self,
+        input: SwapQuoteInput,
+        api_key: Option<&str>,
+    ) -> Result<SwapQuote> {
+        let policy = self.swap_policy()?;
+        let account_hex = self.account_hex(input.account_id.trim())?;
+        self.authorize_account(api_key, &account_hex, "swapper")?;
+
+        let escrow_hex = input
+            .escrow_account_id
+            .as_deref()
+            .map(|id| self.account_hex(id.trim()))
+            .transpose()?;
+        let (to, mut amount_out) = match &escrow_hex {
+            Some(escrow_hex) => {
+                let record = self.records.escrows.get(escrow_hex).ok_or_else(|| {
+                    anyhow::anyhow!("Escrow {} not found in service records", escrow_hex)
+                })?;
+                if !record.buyer_account_id.eq_ignore_ascii_case(&account_hex) {
+                    return Err(anyhow::anyhow!(
+                        "{} is not the buyer of escrow {}",
+                        account_hex,
+                        escrow_hex
+                    ));
+                }
+                if record.status != EscrowStatus::Created {
+                    return Err(anyhow::anyhow!(
+                        "Conflict: escrow {} is already {:?}",
+                        escrow_hex,
+                        record.status
+                    ));
+                }
+                let amount = record.amount;
+                let faucet = self.escrow_settlement_faucet(escrow_hex)?;
+                let (symbol, _) = self.token_units(faucet).ok_or_else(|| {
+                    anyhow::anyhow!("Escrow {} settles in an unregistered token", escrow_hex)
+                })?;
+                if let Some(to) = &input.to {
+                    if !to.trim().eq_ignore_ascii_case(&symbol) {
+                        return Err(anyhow::anyhow!(
+                            "Escrow {} settles in {}, not {}",
+                            escrow_hex,
+                            symbol,
+                            to.trim()
+                        ));
+                    }
+                }
+                (symbol, Some(amount))
+            }
+            None => (input.to.clone().unwrap_or_default(), input.amount_out),
+        };
+
+        let (from_symbol, from_faucet, from_decimals) = self.token_by_symbol(&input.from)?;
+        let (to_symbol, to_faucet, to_decimals) = self.token_by_symbol(&to)?;
+        if from_faucet == to_faucet {
+            return Err(anyhow::anyhow!("Cannot swap {} for itself", from_symbol));
+        }
+
+        let (rate, source) = self.swap_rate(&policy, &from_symbol, &to_symbol).await?;
+        let rate = rate.less_spread(policy.spread_bps);
+        let amount_in = match (input.amount_in, amount_out) {
+            (Some(amount_in), None) => {
+                amount_out = rate.convert(amount_in, from_decimals, to_decimals);
+                amount_in
+            }
+            (None, Some(amount_out)) => rate
+                .required_input(amount_out, from_decimals, to_decimals)
+                .ok_or_else(|| anyhow::anyhow!("Amount {} is too large to quote", amount_out))?,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Give exactly one of amount_in and amount_out"
+                ))
+            }
+        };
+        let amount_out = amount_out.filter(|a| *a > 0).ok_or_else(|| {
+            anyhow::anyhow!("{} {} buys no {}", amount_in, from_symbol, to_symbol)
+        })?;
+
+        let liquidity = parse_hex_account_id(&self.account_hex(&policy.liquidity_account)?)?;
+        self.sync_state().await?;
+        let available = self.vault_balance(liquidity, to_faucet).await?;
+        if available < amount_out {
+            return Err(anyhow::anyhow!(
+                "Liquidity account holds {} of the {} {} quoted",
+                available,
+                amount_out,
+                to_symbol
+            ));
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let quote = self.swaps.insert(SwapQuote {
+            quote_id: 0,
+            account_id: account_hex,
+            escrow_account_id: escrow_hex,
+            give: SwapLeg {
+                symbol: from_symbol,
+                faucet_id: account_id_to_hex(from_faucet),
+                amount: amount_in,
+            },
+            receive: SwapLeg {
+                symbol: to_symbol,
+                faucet_id: account_id_to_hex(to_faucet),
+                amount: amount_out,
+            },
+            rate,
+            source,
+            spread_bps: policy.spread_bps,
+            status: QuoteStatus::Open,
+            created_at: now,
+            expires_at: now + policy.quote_ttl.as_secs() as i64,
+            fill: None,
+            attempts: 0,
+            error: None,
+        })?;
+        tracing::info!(
+            "🔁 Quote {}: {} {} for {} {} at {} ({:?})",
+            quote.quote_id,
+            quote.give.amount,
+            quote.give.symbol,
+            quote.receive.amount,
+            quote.receive.symbol,
+            quote.rate,
+            quote.source
+        );
+        Ok(quote)
+    }
+
+    /// Executes an open quote: sends the account's SWAP note and fills it from
+    /// the liquidity account. Needs an API key bound to the account.
+    pub async fn execute_swap(
+        &mut self,
+        quote_id: u64,
+        api_key: Option<&str>,
+    ) -> Result<SwapQuote> {
+        let policy = self.swap_policy()?;
+        let mut quote = self.swaps.get(quote_id)?.clone();
+        self.authorize_account(api_key, &quote.account_id, "swapper")?;
+        if quote.status != QuoteStatus::Open {
+            return Err(anyhow::anyhow!(
+                "Conflict: quote {} is {:?}",
+                quote_id,
+                quote.status
+            ));
+        }
+        if quote.expires_at <= chrono::Utc::now().timestamp() {
+            quote.status = QuoteStatus::Expired;
+            self.swaps.update(quote)?;
+            return Err(anyhow::anyhow!("Conflict: quote {} has expired", quote_id));
+        }
+
+        let account = parse_hex_account_id(&quote.account_id)?;
+        let liquidity_hex = self.account_hex(&policy.liquidity_account)?;
+        let liquidity = parse_hex_account_id(&liquidity_hex)?;
+        let give_faucet = parse_hex_account_id(&quote.give.faucet_id)?;
+        let receive_faucet = parse_hex_account_id(&quote.receive.faucet_id)?;
+
+        self.sync_state().await?;
+        let balance = self.vault_balance(account, give_faucet).await?;
+        if balance < quote.give.amount {
+            return Err(anyhow::anyhow!(
+                "Account holds {} of the {} {} the swap needs",
+                balance,
+                quote.give.amount,
+                quote.give.symbol
+            ));
+        }
+        let available = self.vault_balance(liquidity, receive_faucet).await?;
+        if available < quote.receive.amount {
+            return Err(anyhow::anyhow!(
+                "Liquidity account holds {} of the {} {} quoted",
+                available,
+                quote.receive.amount,
+                quote.receive.symbol
+            ));
+        }
+
+        let op_id = self.records.begin_operation("swap", &quote.account_id);
+        let result = self.submit_swap_note(&quote, account).await;
+        let tx_id = result.as_ref().ok().map(|fill| fill.swap_tx_id.clone());
+        self.records.finish_operation(op_id, &result, tx_id);
+        let fill = result?;
+
+        self.records.expect_note(
+            &fill.swap_note_id,
+            &liquidity_hex,
+            "swap",
+            Some(fill.swap_tx_id.clone()),
+        );
+        self.records.expect_note(
+            &fill.payback_note_id,
+            &quote.account_id,
+            "swap-payback",
+            None,
+        );
+        self.post_ledger_entry(
+            JournalKind::Swap,
+            Posting::transfer(
+                &quote.account_id,
+                &liquidity_hex,
+                &quote.give.faucet_id,
+                quote.give.amount,
+            )
+            .to_vec(),
+            &fill.swap_tx_id,
+            Some(&format!("swap-quote:{}", quote_id)),
+        );
+        quote.status = QuoteStatus::Executing;
+        quote.fill = Some(fill);
+        self.swaps.update(quote)?;
+
+        // The liquidity account can only consume the note once the node has it
+        self.wait_for_propagation().await;
+        self.fill_swap(quote_id).await;
+        Ok(self.swaps.get(quote_id)?.clone())
+    }
+
+    async fn submit_swap_note(
+        &mut self,
+        quote: &SwapQuote,
+        account: AccountId,
+    ) -> Result<SwapFill> {
+        let offered = FungibleAsset::new(
+            parse_hex_account_id(&quote.give.faucet_id)?,
+            quote.give.amount,
+        )?;
+        let requested = FungibleAsset::new(
+            parse_hex_account_id(&quote.receive.faucet_id)?,
+            quote.receive.amount,
+        )?;
+        let (swap_note_id, payback_note_id, swap_tx_id) =
+            self.submit_swap_offer(account, offered, requested).await?;
+        tracing::info!(
+            "🔁 Swap note {} sent for quote {}. TX: {}",
+            swap_note_id,
+            quote.quote_id,
+            swap_tx_id
+        );
+
+        Ok(SwapFill {
+            swap_note_id,
+            swap_tx_id,
+            payback_note_id,
+            fill_tx_id: None,
+            filled_at: None,
+        })
+    }
+
+    /// Consumes the SWAP note of an executing quote into the liquidity
+    /// account. Failures are recorded on the quote for the job to retry.
+    async fn fill_swap(&mut self, quote_id: u64) -> bool {
+        let Ok(mut quote) = self.swaps.get(quote_id).cloned() else {
+            return false;
+        };
+        let Some(mut fill) = quote.fill.clone() else {
+            return false;
+        };
+
+        quote.attempts += 1;
+        let filled = match self.submit_swap_fill(&fill).await {
+            Ok((liquidity_hex, tx_id)) => {
+                tracing::info!("🔁 Filled quote {}. TX: {}", quote_id, tx_id);
+                self.records
+                    .mark_note_consumed(&fill.swap_note_id, Some(tx_id.clone()));
+                self.post_ledger_entry(
+                    JournalKind::Swap,
+                    Posting::transfer(
+                        &liquidity_hex,
+                        &quote.account_id,
+                        &quote.receive.faucet_id,
+                        quote.receive.amount,
+                    )
+                    .to_vec(),
+                    &tx_id,
+                    Some(&format!("swap-quote:{}", quote_id)),
+                );
+                fill.fill_tx_id = Some(tx_id);
+                fill.filled_at = Some(chrono::Utc::now().timestamp());
+                quote.fill = Some(fill);
+                quote.status = QuoteStatus::Filled;
+                quote.error = None;
+                true
+            }
+            Err(e) => {
+                tracing::warn!("Failed to fill swap quote {}: {}", quote_id, e);
+                quote.error = Some(e.to_string());
+                if quote.attempts >= MAX_FILL_ATTEMPTS {
+                    tracing::error!(
+                        "❌ Giving up on swap quote {}; swap note {} is left unfilled",
+                        quote_id,
+                        fill.swap_note_id
+                    );
+                    quote.status = QuoteStatus::Failed;
+                }
+                false
+            }
+        };
+        if let Err(e) = self.swaps.update(quote) {
+            tracing::error!("❌ Could not save swap quote {}: {}", quote_id, e);
+        }
+        filled
+    }
+
+    async fn submit_swap_fill(&mut self, fill: &SwapFill) -> Result<(String, String)> {
+        let policy = self.swap_policy()?;
+        let liquidity_hex = self.account_hex(&policy.liquidity_account)?;
+        let liquidity = parse_hex_account_id(&liquidity_hex)?;
+        let tx_id = self
+            .submit_swap_consume(liquidity, &fill.swap_note_id)
+            .await?;
+        Ok((liquidity_hex, tx_id))
+    }
+
+    /// Sends a SWAP note from `account` offering `offered` to whoever pays
+    /// `requested` back to it. Returns the SWAP note, payback note and
+    /// transaction IDs.
+    pub(crate) async fn submit_swap_offer(
+        &mut self,
+        account: AccountId,
+        offered: FungibleAsset,
+        requested: FungibleAsset,
+    ) -> Result<(String, String, String)> {
+        let (swap_note, payback) = create_swap_note(
+            account,
+            offered.into(),
+            requested.into(),
+            NoteType::Public,
+            Felt::new(0),
+            NoteType::Public,
+            Felt::new(0),
+            &mut self.rng,
+        )?;
+        let swap_note_id = swap_note.id().to_string();
+        let payback_note_id = payback.id().to_string();
+
+        let transaction_request = TransactionRequestBuilder::new()
+            .own_output_notes(vec![OutputNote::Full(swap_note)])
+            .build()?;
+        let transaction_id = self
+            .client
+            .submit_new_transaction(account, transaction_request)
+            .await?;
+
+        Ok((swap_note_id, payback_note_id, transaction_id.to_string()))
+    }
+
+    /// Consumes a SWAP note into `account`: takes its offer and pays its
+    /// request back to the sender in the same transaction.
+    pub(crate) async fn submit_swap_consume(
+        &mut self,
+        account: AccountId,
+        swap_note_id: &str,
+    ) -> Result<String> {
+        let note_id = NoteId::try_from_hex(swap_note_id)
+            .map_err(|e| anyhow::anyhow!("Invalid swap note ID: {}", e))?;
+
+        self.sync_state().await?;
+        let transaction_request =
+            TransactionRequestBuilder::new().build_consume_notes(vec![note_id])?;
+        let transaction_id = self
+            .client
+            .submit_new_transaction(account, transaction_request)
+            .await?;
+        self.sync_state().await?;
+
+        Ok(transaction_id.to_string())
+    }
+
+    /// Scheduled job: retries fills of executing quotes and expires open ones.
+    /// Returns the number of quotes changed.
+    pub async fn run_swaps(&mut self) -> Result<usize> {
+        let mut changed = self.swaps.expire(chrono::Utc::now().timestamp())?;
+        if self.config.swaps.is_none() {
+            return Ok(changed);
+        }
+        for quote_id in self.swaps.executing() {
+            self.fill_swap(quote_id).await;
+            changed += 1;
+        }
+        Ok(changed)
+    }
+
+    pub fn get_swap_quote(&self, quote_id: u64, api_key: Option<&str>) -> Result<SwapQuote> {
+        let quote = self.swaps.get(quote_id)?;
+        self.authorize_account(api_key, &quote.account_id, "swapper")?;
+        Ok(quote.clone())
+    }
+
+    pub fn list_swap_quotes(
+        &self,
+        account_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<Vec<SwapQuote>> {
+        let account_hex = self.account_hex(account_id.trim())?;
+        self.authorize_account(api_key, &account_hex, "swapper")?;
+        Ok(self.swaps.list(&account_hex).into_iter().cloned().collect())
+    }
+
+    /// Rate from `from` to `to`: the fixed one, else the oracle's.
+    async fn swap_rate(
+        &self,
+        policy: &SwapPolicy,
+        from: &str,
+        to: &str,
+    ) -> Result<(Rate, RateSource)> {
+        if let Some(rate) = policy.rates.get(&(from.to_string(), to.to_string())) {
+            return Ok((*rate, RateSource::Fixed));
+        }
+        let Some(oracle_url) = &policy.oracle_url else {
+            return Err(anyhow::anyhow!("No rate for {}/{}", from, to));
+        };
+
+        let http = reqwest::Client::builder().timeout(ORACLE_TIMEOUT).build()?;
+        let body: serde_json::Value = http
+            .get(oracle_url)
+            .query(&[("base", from), ("quote", to)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let rate = match &body["rate"] {
+            serde_json::Value::String(rate) => rate.parse(),
+            serde_json::Value::Number(rate) => rate.to_string().parse(),
+            _ => Err(anyhow::anyhow!("no rate in the answer")),
+        }
+        .map_err(|e| anyhow::anyhow!("Rate oracle has no {}/{} rate: {}", from, to, e))?;
+        Ok((rate, RateSource::Oracle))
+    }
+
+    pub(crate) async fn vault_balance(
+        &mut self,
+        account: AccountId,
+        faucet: AccountId,
+    ) -> Result<u64> {
+        let record =
+            self.client.get_account(account).await?.ok_or_else(|| {
+                anyhow::anyhow!("Account {} not found in the client store", account)
+            })?;
+        Ok(record.account().vault().get_balance(faucet).unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate(s: &str) -> Rate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn rates_parse_to_billionths_and_print_back() {
+        assert_eq!(rate("0.95"), Rate(950_000_000));
+        assert_eq!(rate(".5"), Rate(500_000_000));
+        assert_eq!(rate("2."), Rate(2 * RATE_SCALE));
+        assert_eq!(rate(" 0.000000001 "), Rate(1));
+        assert_eq!(rate("0.95").to_string(), "0.95");
+        assert_eq!(rate("2.000").to_string(), "2");
+        assert_eq!(rate("0.000000001").to_string(), "0.000000001");
+    }
+
+    #[test]
+    fn malformed_zero_and_oversized_rates_are_refused() {
+        for s in [
+            "",
+            ".",
+            "0",
+            "0.000",
+            "-1",
+            "1e3",
+            "1.2.3",
+            "0.0000000001",
+            // u128::MAX billionths is about 3.4e29 whole units
+            "340282366920938463463374607432",
+        ] {
+            assert!(s.parse::<Rate>().is_err(), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn the_spread_comes_off_rounded_down_without_overflow() {
+        assert_eq!(rate("1").less_spread(100), rate("0.99"));
+        assert_eq!(rate("0.000000001").less_spread(1), Rate(0));
+        assert_eq!(rate("1").less_spread(0), rate("1"));
+        assert_eq!(rate("1").less_spread(20_000), Rate(0));
+        assert_eq!(
+            Rate(u128::MAX).less_spread(1),
+            Rate(u128::MAX - u128::MAX.div_ceil(10_000))
+        );
+    }
+
+    #[test]
+    fn conversions_scale_by_decimals_and_round_down() {
+        // 100 USDT (6 decimals) at 0.95 buy 95 PROP (8 decimals)
+        assert_eq!(rate("0.95").convert(100_000_000, 6, 8), Some(9_500_000_000));
+        assert_eq!(rate("0.95").convert(9_500_000_000, 8, 6), Some(90_250_000));
+        assert_eq!(rate("0.5").convert(1, 0, 0), Some(0));
+        assert_eq!(rate("1.999999999").convert(1, 0, 0), Some(1));
+        assert_eq!(rate("1").convert(u64::MAX, 8, 8), Some(u64::MAX));
+        assert_eq!(rate("2").convert(u64::MAX, 8, 8), None);
+        assert_eq!(rate("1").convert(1, 0, 40), None);
+        assert_eq!(rate("1").convert(1, 0, 255), None);
+    }
+
+    #[test]
+    fn required_inputs_round_up_and_cover_the_output() {
+        assert_eq!(
+            rate("0.95").required_input(9_500_000_000, 6, 8),
+            Some(100_000_000)
+        );
+        assert_eq!(rate("0.95").required_input(1, 6, 8), Some(1));
+        assert_eq!(rate("3").required_input(10, 0, 0), Some(4));
+        for amount_out in [1, 7, 999, 1_000_001, 123_456_789] {
+            let r = rate("1.234567891");
+            let amount_in = r.required_input(amount_out, 6, 8).unwrap();
+            assert!(r.convert(amount_in, 6, 8).unwrap() >= amount_out);
+            assert!(r.convert(amount_in - 1, 6, 8).unwrap() < amount_out);
+        }
+        assert_eq!(Rate(0).required_input(1, 0, 0), None);
+        assert_eq!(rate("0.000000001").required_input(u64::MAX, 0, 0), None);
+    }
+
+    #[test]
+    fn swap_rate_lists_are_keyed_by_direction() {
+        let rates = parse_swap_rates(" usdt/PROP=0.95, PROP/USDT=1.04 ,").unwrap();
+        assert_eq!(rates.len(), 2);
+        assert_eq!(
+            rates[&("USDT".to_string(), "PROP".to_string())],
+            rate("0.95")
+        );
+        assert!(parse_swap_rates("").unwrap().is_empty());
+        assert!(parse_swap_rates("USDT/PROP=1,usdt/prop=2").is_err());
+        assert!(parse_swap_rates("PROP/PROP=1").is_err());
+        assert!(parse_swap_rates("USDT-PROP=1").is_err());
+        assert!(parse_swap_rates("USDT/PROP").is_err());
+    }
+}
@@ -0,0 +1,342 @@
+// src/sync_deltas.rs
+//
+// What changed in a sync
+//
+// Every sync of the wrapper goes through `sync_state` here, which brings all
+// accounts the client tracks up to date in one network sync and records what
+// changed for each of them. The state of every tracked account is read from the
+// local store before and after the sync:
+// - new_notes: notes that became consumable by the account, with their
+//   fungible assets
+// - consumed_notes: notes that were consumable and no longer are (consumed by
+//   this service, or by someone else for notes several accounts may consume)
+// - balance_changes: fungible balances per faucet that moved, before and after
+//
+// Accounts without changes are left out of the delta. Deltas with changes are
+// published on the sync feed (GET /sync/events streams them as server-sent
+// `delta` events), and the last delta, empty or not, is served by
+// GET /sync/last-delta. POST /sync syncs right away and returns its delta.
+// Deltas are kept in memory only; the balance changes are also indexed for
+// balances at past blocks (balance_history.rs).
+
+use anyhow::Result;
+use miden_client::{asset::Asset, sync::SyncSummary};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Instant,
+};
+use tokio::sync::broadcast;
+
+use crate::{account_id_to_hex, MidenClientWrapper};
+
+/// Buffered sync deltas per subscriber before it starts missing deltas.
+pub const SYNC_FEED_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct AccountState {
+    /// Fungible balances per faucet (hex)
+    balances: BTreeMap<String, u64>,
+    /// Consumable notes with their fungible assets
+    consumable_notes: BTreeMap<String, Vec<NoteAsset>>,
+}
+
+/// Local state of every tracked account at one point in time.
+#[derive(Debug, Clone, Default)]
+pub struct TrackedState {
+    pub block_num: u32,
+    accounts: BTreeMap<String, AccountState>,
+}
+
+impl TrackedState {
+    pub fn new(block_num: u32) -> Self {
+        Self {
+            block_num,
+            accounts: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a tracked account, so that it is listed even without assets.
+    pub fn track(&mut self, account_id: &str) {
+        self.accounts.entry(account_id.to_string()).or_default();
+    }
+
+    pub fn set_balance(&mut self, account_id: &str, faucet_id: &str, amount: u64) {
+        let account = self.accounts.entry(account_id.to_string()).or_default();
+        account.balances.insert(faucet_id.to_string(), amount);
+    }
+
+    pub fn add_consumable_note(&mut self, account_id: &str, note_id: &str, assets: Vec<NoteAsset>) {
+        let account = self.accounts.entry(account_id.to_string()).or_default();
+        account.consumable_notes.insert(note_id.to_string(), assets);
+    }
+
+    pub fn accounts_tracked(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn is_tracked(&self, account_id: &str) -> bool {
+        self.accounts.contains_key(account_id)
+    }
+
+    pub fn balance(&self, account_id: &str, faucet_id: &str) -> Option<u64> {
+        self.accounts.get(account_id)?.balances.get(faucet_id).copied()
+    }
+
+    /// Every fungible balance, keyed by (account, faucet).
+    pub fn balances(&self) -> impl Iterator<Item = ((String, String), u64)> + '_ {
+        self.accounts.iter().flat_map(|(account_id, account)| {
+            account
+                .balances
+                .iter()
+                .map(move |(faucet_id, amount)| ((account_id.clone(), faucet_id.clone()), *amount))
+        })
+    }
+
+    /// Per-account changes from `self` to `after`, accounts without changes
+    /// left out. Accounts only in one of the two states count as empty in the
+    /// other.
+    pub fn changes_to(&self, after: &TrackedState) -> Vec<AccountDelta> {
+        let empty = AccountState::default();
+        let account_ids: BTreeSet<&String> =
+            self.accounts.keys().chain(after.accounts.keys()).collect();
+
+        account_ids
+            .into_iter()
+            .filter_map(|account_id| {
+                let before = self.accounts.get(account_id).unwrap_or(&empty);
+                let now = after.accounts.get(account_id).unwrap_or(&empty);
+                if before == now {
+                    return None;
+                }
+
+                let faucets: BTreeSet<&String> =
+                    before.balances.keys().chain(now.balances.keys()).collect();
+                let balance_changes = faucets
+                    .into_iter()
+                    .filter_map(|faucet_id| {
+                        let was = before.balances.get(faucet_id).copied().unwrap_or(0);
+                        let is = now.balances.get(faucet_id).copied().unwrap_or(0);
+                        (was != is).then(|| BalanceChange {
+                            faucet_id: faucet_id.clone(),
+                            before: was,
+                            after: is,
+                        })
+                    })
+                    .collect();
+
+                let delta = AccountDelta {
+                    account_id: account_id.clone(),
+                    new_notes: now
+                        .consumable_notes
+                        .iter()
+                        .filter(|(note_id, _)| !before.consumable_notes.contains_key(*note_id))
+                        .map(|(note_id, assets)| NewNote {
+                            note_id: note_id.clone(),
+                            assets: assets.clone(),
+                        })
+                        .collect(),
+                    consumed_notes: before
+                        .consumable_notes
+                        .keys()
+                        .filter(|note_id| !now.consumable_notes.contains_key(*note_id))
+                        .cloned()
+                        .collect(),
+                    balance_changes,
+                };
+                (!delta.is_empty()).then_some(delta)
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NoteAsset {
+    pub faucet_id: String,
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NewNote {
+    pub note_id: String,
+    pub assets: Vec<NoteAsset>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BalanceChange {
+    pub faucet_id: String,
+    pub before: u64,
+    pub after: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountDelta {
+    pub account_id: String,
+    pub new_notes: Vec<NewNote>,
+    pub consumed_notes: Vec<String>,
+    pub balance_changes: Vec<BalanceChange>,
+}
+
+impl AccountDelta {
+    fn is_empty(&self) -> bool {
+        self.new_notes.is_empty()
+            && self.consumed_notes.is_empty()
+            && self.balance_changes.is_empty()
+    }
+}
+
+/// What one sync changed for the tracked accounts.
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncDelta {
+    pub sync_id: u64,
+    /// Sync height before and after the sync
+    pub from_block: u32,
+    pub to_block: u32,
+    pub synced_at: i64,
+    pub accounts_tracked: usize,
+    /// Accounts with changes
+    pub accounts: Vec<AccountDelta>,
+}
+
+/// The last sync delta and the feed deltas are published on.
+#[derive(Debug, Default)]
+pub struct SyncDeltas {
+    last: Option<SyncDelta>,
+    next_sync_id: u64,
+    feed: Option<broadcast::Sender<SyncDelta>>,
+}
+
+impl SyncDeltas {
+    /// Sends deltas with changes to feed subscribers (see main.rs, GET /sync/events).
+    pub fn attach_feed(&mut self, feed: broadcast::Sender<SyncDelta>) {
+        self.feed = Some(feed);
+    }
+
+    /// Records the changes between two states as the last delta and publishes
+    /// it when anything changed.
+    pub fn record(&mut self, before: &TrackedState, after: &TrackedState) -> &SyncDelta {
+        self.next_sync_id += 1;
+        let delta = SyncDelta {
+            sync_id: self.next_sync_id,
+            from_block: before.block_num,
+            to_block: after.block_num,
+            synced_at: chrono::Utc::now().timestamp(),
+            accounts_tracked: after.accounts_tracked(),
+            accounts: before.changes_to(after),
+        };
+
+        if !delta.accounts.is_empty() {
+            if let Some(feed) = &self.feed {
+                // No subscribers is not an error
+                let _ = feed.send(delta.clone());
+            }
+        }
+        self.last.insert(delta)
+    }
+
+    pub fn last(&self) -> Option<&SyncDelta> {
+        self.last.as_ref()
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    SyncNow {} -> SyncDelta;
+    |client, _op| client.sync_now().await;
+
+    GetLastSyncDelta {} -> Option<SyncDelta>;
+    |client, _op| Ok(client.last_sync_delta())
+}
+
+impl MidenClientWrapper {
+    /// Publishes sync deltas on `feed` from now on.
+    pub fn attach_sync_feed(&mut self, feed: broadcast::Sender<SyncDelta>) {
+        self.sync_deltas.attach_feed(feed);
+    }
+
+    /// Balances and consumable notes of every tracked account, from the local
+    /// store.
+    async fn tracked_state(&mut self) -> Result<TrackedState> {
+        let block_num = self.client.get_sync_height().await?.as_u32();
+        let mut state = TrackedState::new(block_num);
+
+        for (header, _) in self.client.get_account_headers().await? {
+            let account_hex = account_id_to_hex(header.id());
+            state.track(&account_hex);
+            let Some(account) = self.client.get_account(header.id()).await? else {
+                continue;
+            };
+            for asset in account.account().vault().assets() {
+                if let Asset::Fungible(fungible) = asset {
+                    state.set_balance(
+                        &account_hex,
+                        &account_id_to_hex(fungible.faucet_id()),
+                        fungible.amount(),
+                    );
+                }
+            }
+        }
+
+        for (note, relevances) in self.client.get_consumable_notes(None).await? {
+            let note_id = note.id().to_string();
+            let assets: Vec<NoteAsset> = note
+                .assets()
+                .iter()
+                .filter_map(|asset| match asset {
+                    Asset::Fungible(fungible) => Some(NoteAsset {
+                        faucet_id: account_id_to_hex(fungible.faucet_id()),
+                        amount: fungible.amount(),
+                    }),
+                    Asset::NonFungible(_) => None,
+                })
+                .collect();
+            for (account_id, _) in relevances {
+                state.add_consumable_note(&account_id_to_hex(account_id), &note_id, assets.clone());
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// Syncs all tracked accounts with the network and records what changed.
+    pub(crate) async fn sync_state(&mut self) -> Result<SyncSummary> {
+        let before = self.tracked_state().await?;
+        let started = Instant::now();
+        let mut result = self.client.sync_state().await.map_err(anyhow::Error::from);
+        if self.record_rpc_call(started, &result).await {
+            // Failed over (rpc_failover.rs): one more try on the new endpoint
+            let started = Instant::now();
+            result = self.client.sync_state().await.map_err(anyhow::Error::from);
+            self.record_rpc_call(started, &result).await;
+        }
+        let summary = result?;
+        let after = self.tracked_state().await?;
+
+        self.index_balances(&before, &after);
+        let delta = self.sync_deltas.record(&before, &after);
+        if !delta.accounts.is_empty() {
+            tracing::debug!(
+                "Sync {} changed {} account(s) (blocks {}..{})",
+                delta.sync_id,
+                delta.accounts.len(),
+                delta.from_block,
+                delta.to_block
+            );
+        }
+        Ok(summary)
+    }
+
+    /// Syncs now and returns the delta (POST /sync).
+    pub async fn sync_now(&mut self) -> Result<SyncDelta> {
+        self.sync_state().await?;
+        self.last_sync_delta()
+            .ok_or_else(|| anyhow::anyhow!("No sync recorded"))
+    }
+
+    pub fn last_sync_delta(&self) -> Option<SyncDelta> {
+        self.sync_deltas.last().cloned()
+    }
+}
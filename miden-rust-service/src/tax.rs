@@ -0,0 +1,831 @@
+// src/tax.rs
+//
+// Cost basis and realized gains
+//
+// The tax ledger keeps, per account, the lots it acquired (a whole property or a
+// quantity of a property's shares, with what was paid for them) and the
+// disposals that consumed them. Entries are written by the same events that move
+// title or shares through the service:
+// - mint: the owner acquires the property at its registered price
+// - title transfer (lib.rs): the previous owner disposes of the property and the
+//   new owner acquires it. The consideration is the escrow between the two
+//   parties for that property (funded or released); without one the transfer
+//   is recorded at zero, as a gift
+// - settled share trade (order_book.rs): the seller disposes of the shares and
+//   the buyer acquires them at the trade price
+//
+// A disposal consumes lots first-in, first-out unless the account designated
+// specific lots for its next disposal of that asset. Quantity not covered by any
+// recorded lot (assets held before the ledger existed) is reported with a zero
+// basis and no acquisition date.
+//
+// Gains are long-term when the lot was held for more than LONG_TERM_HOLDING_SECS.
+// The yearly report lists each lot consumed by a disposal in that calendar year
+// (UTC) and is served as JSON or CSV; CSV dates are printed in the requested
+// display zone (see timestamps.rs).
+//
+// Tax withheld from the account's escrow settlements (withholding.rs) is listed
+// in the report of the year it was withheld; in the CSV as one row each, with
+// the seller's share as proceeds, the amount under tax_withheld and the other
+// disposal columns empty.
+
+use anyhow::Result;
+use chrono::Datelike;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    escrow::EscrowStatus, timestamps::DisplayZone, withholding::Withholding, MidenClientWrapper,
+};
+
+/// Holding period after which a gain is long-term (one year).
+pub const LONG_TERM_HOLDING_SECS: i64 = 365 * 86_400;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LotSelectionInput {
+    pub asset: AssetKind,
+    pub property_id: String,
+    /// Lots to consume, in order, on the next disposal of this asset
+    pub lot_ids: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetKind {
+    Property,
+    Shares,
+}
+
+impl AssetKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssetKind::Property => "property",
+            AssetKind::Shares => "shares",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LotMethod {
+    Fifo,
+    SpecificLot,
+}
+
+impl LotMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LotMethod::Fifo => "fifo",
+            LotMethod::SpecificLot => "specific_lot",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HoldingTerm {
+    Short,
+    Long,
+    /// No recorded acquisition
+    Unknown,
+}
+
+impl HoldingTerm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HoldingTerm::Short => "short",
+            HoldingTerm::Long => "long",
+            HoldingTerm::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lot {
+    pub lot_id: u64,
+    pub account_id: String,
+    pub asset: AssetKind,
+    pub property_id: String,
+    pub quantity: u64,
+    pub remaining: u64,
+    /// Total cost of the lot
+    pub cost: u64,
+    /// Cost of the remaining quantity
+    pub remaining_cost: u64,
+    pub acquired_at: i64,
+    /// Event that created the lot, e.g. "mint", "transfer <tx>", "trade-7"
+    pub source: String,
+}
+
+/// The part of a disposal matched against one lot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LotMatch {
+    /// None for quantity no recorded lot covered
+    pub lot_id: Option<u64>,
+    pub quantity: u64,
+    pub acquired_at: Option<i64>,
+    pub cost_basis: u64,
+    pub proceeds: u64,
+    pub gain: i64,
+    pub term: HoldingTerm,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Disposal {
+    pub disposal_id: u64,
+    pub account_id: String,
+    pub asset: AssetKind,
+    pub property_id: String,
+    pub quantity: u64,
+    pub proceeds: u64,
+    pub cost_basis: u64,
+    pub gain: i64,
+    pub method: LotMethod,
+    pub matches: Vec<LotMatch>,
+    pub disposed_at: i64,
+    pub source: String,
+}
+
+/// Lots an account chose for its next disposal of an asset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LotDesignation {
+    pub account_id: String,
+    pub asset: AssetKind,
+    pub property_id: String,
+    pub lot_ids: Vec<u64>,
+}
+
+impl LotDesignation {
+    fn is_for(&self, account_id: &str, asset: AssetKind, property_id: &str) -> bool {
+        self.account_id == account_id && self.asset == asset && self.property_id == property_id
+    }
+}
+
+/// Realized gains of one account in one calendar year.
+#[derive(Debug, Clone, Serialize)]
+pub struct TaxReport {
+    pub account_id: String,
+    pub year: i32,
+    pub disposals: Vec<Disposal>,
+    pub proceeds: u64,
+    pub cost_basis: u64,
+    pub short_term_gain: i64,
+    pub long_term_gain: i64,
+    pub unknown_term_gain: i64,
+    pub withholdings: Vec<Withholding>,
+    pub tax_withheld: u64,
+}
+
+impl TaxReport {
+    /// One row per lot matched by a disposal, then one per withholding.
+    pub fn to_csv(&self, zone: DisplayZone) -> String {
+        let mut csv = String::from(
+            "disposal_id,disposed_at,asset,property_id,lot_id,acquired_at,quantity,proceeds,cost_basis,gain,term,method,source,tax_withheld\n",
+        );
+        for disposal in &self.disposals {
+            for m in &disposal.matches {
+                let row = [
+                    disposal.disposal_id.to_string(),
+                    zone.rfc3339(disposal.disposed_at),
+                    disposal.asset.as_str().to_string(),
+                    disposal.property_id.clone(),
+                    m.lot_id.map(|id| id.to_string()).unwrap_or_default(),
+                    m.acquired_at.map(|at| zone.rfc3339(at)).unwrap_or_default(),
+                    m.quantity.to_string(),
+                    m.proceeds.to_string(),
+                    m.cost_basis.to_string(),
+                    m.gain.to_string(),
+                    m.term.as_str().to_string(),
+                    disposal.method.as_str().to_string(),
+                    disposal.source.clone(),
+                    String::new(),
+                ];
+                let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+                csv.push_str(&row.join(","));
+                csv.push('\n');
+            }
+        }
+        for withholding in &self.withholdings {
+            let mut row = vec![String::new(); 14];
+            row[1] = zone.rfc3339(withholding.withheld_at);
+            row[2] = AssetKind::Property.as_str().to_string();
+            row[3] = withholding.property_id.clone().unwrap_or_default();
+            row[7] = withholding.base_amount.to_string();
+            row[12] = format!(
+                "withholding {} escrow {}",
+                withholding.jurisdiction, withholding.escrow_account_id
+            );
+            row[13] = withholding.amount.to_string();
+            let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn gain(proceeds: u64, cost: u64) -> i64 {
+    (proceeds as i128 - cost as i128).clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+/// `total * part / whole` without overflow.
+fn pro_rata(total: u64, part: u64, whole: u64) -> u64 {
+    if whole == 0 {
+        return 0;
+    }
+    (total as u128 * part as u128 / whole as u128) as u64
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TaxLedger {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    lots: BTreeMap<u64, Lot>,
+    #[serde(default)]
+    disposals: Vec<Disposal>,
+    #[serde(default)]
+    designations: Vec<LotDesignation>,
+    /// Tax withheld from escrow settlements (withholding.rs)
+    #[serde(default)]
+    withholdings: Vec<Withholding>,
+    #[serde(default)]
+    next_lot_id: u64,
+    #[serde(default)]
+    next_disposal_id: u64,
+}
+
+impl TaxLedger {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut ledger = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<TaxLedger>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            TaxLedger::default()
+        };
+        ledger.path = path;
+
+        Ok(ledger)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Persists, logging instead of failing: the ledger follows transfers that
+    /// already happened on chain.
+    pub fn persist(&self) {
+        if let Err(e) = self.save() {
+            tracing::warn!("Failed to persist tax ledger: {}", e);
+        }
+    }
+
+    /// Records an acquisition. Returns the new lot's ID.
+    #[allow(clippy::too_many_arguments)]
+    pub fn acquire(
+        &mut self,
+        account_id: &str,
+        asset: AssetKind,
+        property_id: &str,
+        quantity: u64,
+        cost: u64,
+        acquired_at: i64,
+        source: String,
+    ) -> u64 {
+        self.next_lot_id += 1;
+        let lot_id = self.next_lot_id;
+        self.lots.insert(
+            lot_id,
+            Lot {
+                lot_id,
+                account_id: account_id.to_lowercase(),
+                asset,
+                property_id: property_id.to_string(),
+                quantity,
+                remaining: quantity,
+                cost,
+                remaining_cost: cost,
+                acquired_at,
+                source,
+            },
+        );
+        lot_id
+    }
+
+    /// Records a disposal, consuming the designated lots first (if any), then
+    /// the oldest open lots.
+    #[allow(clippy::too_many_arguments)]
+    pub fn dispose(
+        &mut self,
+        account_id: &str,
+        asset: AssetKind,
+        property_id: &str,
+        quantity: u64,
+        proceeds: u64,
+        disposed_at: i64,
+        source: String,
+    ) -> Disposal {
+        let account_id = account_id.to_lowercase();
+
+        let designated = match self
+            .designations
+            .iter()
+            .position(|d| d.is_for(&account_id, asset, property_id))
+        {
+            Some(i) => self.designations.remove(i).lot_ids,
+            None => Vec::new(),
+        };
+        let method = if designated.is_empty() {
+            LotMethod::Fifo
+        } else {
+            LotMethod::SpecificLot
+        };
+
+        // Lot IDs increase with acquisition order
+        let fifo: Vec<u64> = self
+            .lots
+            .values()
+            .filter(|l| {
+                l.account_id == account_id
+                    && l.asset == asset
+                    && l.property_id == property_id
+                    && l.remaining > 0
+                    && !designated.contains(&l.lot_id)
+            })
+            .map(|l| l.lot_id)
+            .collect();
+
+        let mut matches = Vec::new();
+        let mut left = quantity;
+        let mut proceeds_left = proceeds;
+        for lot_id in designated.into_iter().chain(fifo) {
+            if left == 0 {
+                break;
+            }
+            let Some(lot) = self.lots.get_mut(&lot_id) else {
+                continue;
+            };
+            if lot.account_id != account_id || lot.remaining == 0 {
+                continue;
+            }
+
+            let take = lot.remaining.min(left);
+            let cost_basis = if take == lot.remaining {
+                lot.remaining_cost
+            } else {
+                pro_rata(lot.remaining_cost, take, lot.remaining)
+            };
+            lot.remaining -= take;
+            lot.remaining_cost -= cost_basis;
+            left -= take;
+
+            let part = if left == 0 {
+                proceeds_left
+            } else {
+                pro_rata(proceeds, take, quantity)
+            };
+            proceeds_left -= part;
+
+            let term = if disposed_at - lot.acquired_at > LONG_TERM_HOLDING_SECS {
+                HoldingTerm::Long
+            } else {
+                HoldingTerm::Short
+            };
+            matches.push(LotMatch {
+                lot_id: Some(lot_id),
+                quantity: take,
+                acquired_at: Some(lot.acquired_at),
+                cost_basis,
+                proceeds: part,
+                gain: gain(part, cost_basis),
+                term,
+            });
+        }
+        if left > 0 {
+            matches.push(LotMatch {
+                lot_id: None,
+                quantity: left,
+                acquired_at: None,
+                cost_basis: 0,
+                proceeds: proceeds_left,
+                gain: gain(proceeds_left, 0),
+                term: HoldingTerm::Unknown,
+            });
+        }
+
+        let cost_basis: u64 = matches.iter().map(|m| m.cost_basis).sum();
+        self.next_disposal_id += 1;
+        let disposal = Disposal {
+            disposal_id: self.next_disposal_id,
+            account_id,
+            asset,
+            property_id: property_id.to_string(),
+            quantity,
+            proceeds,
+            cost_basis,
+            gain: gain(proceeds, cost_basis),
+            method,
+            matches,
+            disposed_at,
+            source,
+        };
+        self.disposals.push(disposal.clone());
+        disposal
+    }
+
+    /// Designates the lots the account's next disposal of an asset consumes,
+    /// replacing any earlier designation for it.
+    pub fn designate(
+        &mut self,
+        account_id: &str,
+        asset: AssetKind,
+        property_id: &str,
+        lot_ids: Vec<u64>,
+    ) -> Result<()> {
+        let account_id = account_id.to_lowercase();
+        if lot_ids.is_empty() {
+            return Err(anyhow::anyhow!("lot_ids must not be empty"));
+        }
+        for lot_id in &lot_ids {
+            let lot = self
+                .lots
+                .get(lot_id)
+                .ok_or_else(|| anyhow::anyhow!("Lot {} not found", lot_id))?;
+            if lot.account_id != account_id || lot.asset != asset || lot.property_id != property_id
+            {
+                return Err(anyhow::anyhow!(
+                    "Lot {} is not a {} lot of {} held by {}",
+                    lot_id,
+                    asset.as_str(),
+                    property_id,
+                    account_id
+                ));
+            }
+            if lot.remaining == 0 {
+                return Err(anyhow::anyhow!("Lot {} is fully disposed of", lot_id));
+            }
+        }
+
+        self.designations
+            .retain(|d| !d.is_for(&account_id, asset, property_id));
+        self.designations.push(LotDesignation {
+            account_id,
+            asset,
+            property_id: property_id.to_string(),
+            lot_ids,
+        });
+        self.save()
+    }
+
+    /// Lots of an account with quantity left.
+    pub fn open_lots(&self, account_id: &str) -> Vec<&Lot> {
+        self.lots
+            .values()
+            .filter(|l| l.account_id.eq_ignore_ascii_case(account_id) && l.remaining > 0)
+            .collect()
+    }
+
+    pub fn lots(&self) -> impl Iterator<Item = &Lot> {
+        self.lots.values()
+    }
+
+    pub fn disposals(&self) -> impl Iterator<Item = &Disposal> {
+        self.disposals.iter()
+    }
+
+    pub fn record_withholding(&mut self, withholding: Withholding) {
+        self.withholdings.push(withholding);
+    }
+
+    pub fn report(&self, account_id: &str, year: i32) -> TaxReport {
+        let disposals: Vec<Disposal> = self
+            .disposals
+            .iter()
+            .filter(|d| {
+                d.account_id.eq_ignore_ascii_case(account_id)
+                    && chrono::DateTime::from_timestamp(d.disposed_at, 0)
+                        .map(|t| t.year() == year)
+                        .unwrap_or(false)
+            })
+            .cloned()
+            .collect();
+
+        let term_gain = |term: HoldingTerm| -> i64 {
+            disposals
+                .iter()
+                .flat_map(|d| &d.matches)
+                .filter(|m| m.term == term)
+                .map(|m| m.gain)
+                .fold(0i64, i64::saturating_add)
+        };
+
+        let withholdings: Vec<Withholding> = self
+            .withholdings
+            .iter()
+            .filter(|w| {
+                w.seller_account_id.eq_ignore_ascii_case(account_id)
+                    && chrono::DateTime::from_timestamp(w.withheld_at, 0)
+                        .map(|t| t.year() == year)
+                        .unwrap_or(false)
+            })
+            .cloned()
+            .collect();
+
+        TaxReport {
+            account_id: account_id.to_lowercase(),
+            year,
+            proceeds: disposals.iter().map(|d| d.proceeds).sum(),
+            cost_basis: disposals.iter().map(|d| d.cost_basis).sum(),
+            short_term_gain: term_gain(HoldingTerm::Short),
+            long_term_gain: term_gain(HoldingTerm::Long),
+            unknown_term_gain: term_gain(HoldingTerm::Unknown),
+            disposals,
+            tax_withheld: withholdings.iter().map(|w| w.amount).sum(),
+            withholdings,
+        }
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    SelectTaxLots {
+        account_id: String,
+        input: LotSelectionInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.select_tax_lots(&op.account_id, op.input, op.api_key.as_deref());
+
+    ListTaxLots {
+        account_id: String,
+    } -> serde_json::Value;
+    |client, op| client.list_tax_lots(&op.account_id);
+
+    GetTaxReport {
+        account_id: String,
+        year: i32,
+    } -> TaxReport;
+    |client, op| client.get_tax_report(&op.account_id, op.year)
+}
+
+impl MidenClientWrapper {
+    /// Ledger entries for a minted property: the owner's first lot.
+    pub(crate) fn record_mint_basis(&mut self, property_id: &str, owner_hex: &str, price: u64) {
+        self.tax.acquire(
+            owner_hex,
+            AssetKind::Property,
+            property_id,
+            1,
+            price,
+            chrono::Utc::now().timestamp(),
+            "mint".to_string(),
+        );
+        self.tax.persist();
+    }
+
+    /// Ledger entries for a completed title transfer.
+    pub(crate) fn record_title_transfer_basis(
+        &mut self,
+        property_id: &str,
+        from_hex: &str,
+        to_hex: &str,
+        tx_id: &str,
+    ) {
+        if from_hex.eq_ignore_ascii_case(to_hex) {
+            return;
+        }
+
+        // The most recent escrow between the parties for this property
+        let consideration = self
+            .records
+            .escrows
+            .values()
+            .filter(|e| {
+                e.property_id.as_deref() == Some(property_id)
+                    && e.seller_account_id.eq_ignore_ascii_case(from_hex)
+                    && e.buyer_account_id.eq_ignore_ascii_case(to_hex)
+                    && matches!(e.status, EscrowStatus::Funded | EscrowStatus::Released)
+            })
+            .max_by_key(|e| e.updated_at)
+            .map(|e| e.amount)
+            .unwrap_or(0);
+
+        let now = chrono::Utc::now().timestamp();
+        let source = format!("transfer {}", tx_id);
+        let disposal = self.tax.dispose(
+            from_hex,
+            AssetKind::Property,
+            property_id,
+            1,
+            consideration,
+            now,
+            source.clone(),
+        );
+        self.tax.acquire(
+            to_hex,
+            AssetKind::Property,
+            property_id,
+            1,
+            consideration,
+            now,
+            source,
+        );
+        self.tax.persist();
+
+        tracing::info!(
+            "Tax ledger: {} disposed of {} for {} (gain {})",
+            from_hex,
+            property_id,
+            consideration,
+            disposal.gain
+        );
+    }
+
+    /// Ledger entries for a settled share trade.
+    pub(crate) fn record_trade_basis(&mut self, trade: &crate::order_book::Trade) {
+        let at = trade.settled_at.unwrap_or(trade.matched_at);
+        let amount = trade.quantity * trade.price;
+        let source = format!("trade-{}", trade.trade_id);
+
+        self.tax.dispose(
+            &trade.seller_account_id,
+            AssetKind::Shares,
+            &trade.property_id,
+            trade.quantity,
+            amount,
+            at,
+            source.clone(),
+        );
+        self.tax.acquire(
+            &trade.buyer_account_id,
+            AssetKind::Shares,
+            &trade.property_id,
+            trade.quantity,
+            amount,
+            at,
+            source,
+        );
+        self.tax.persist();
+    }
+
+    /// Designates lots for the account's next disposal of an asset. Needs the
+    /// account's API key.
+    pub fn select_tax_lots(
+        &mut self,
+        account: &str,
+        input: LotSelectionInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let account_hex = self.account_hex(account)?;
+        self.authorize_account(api_key, &account_hex, "holder")?;
+
+        self.tax.designate(
+            &account_hex,
+            input.asset,
+            &input.property_id,
+            input.lot_ids.clone(),
+        )?;
+
+        Ok(serde_json::json!({
+            "account_id": account_hex,
+            "asset": input.asset,
+            "property_id": input.property_id,
+            "lot_ids": input.lot_ids,
+        }))
+    }
+
+    pub fn list_tax_lots(&self, account: &str) -> Result<serde_json::Value> {
+        let account_hex = self.account_hex(account)?;
+        Ok(serde_json::json!(self.tax.open_lots(&account_hex)))
+    }
+
+    pub fn get_tax_report(&self, account: &str, year: i32) -> Result<TaxReport> {
+        let account_hex = self.account_hex(account)?;
+        Ok(self.tax.report(&account_hex, year))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 86_400;
+
+    fn shares(ledger: &mut TaxLedger, quantity: u64, cost: u64, acquired_at: i64) -> u64 {
+        ledger.acquire(
+            "0xabc",
+            AssetKind::Shares,
+            "prop-1",
+            quantity,
+            cost,
+            acquired_at,
+            "trade".to_string(),
+        )
+    }
+
+    fn sell(ledger: &mut TaxLedger, quantity: u64, proceeds: u64, disposed_at: i64) -> Disposal {
+        ledger.dispose(
+            "0xABC",
+            AssetKind::Shares,
+            "prop-1",
+            quantity,
+            proceeds,
+            disposed_at,
+            "trade".to_string(),
+        )
+    }
+
+    #[test]
+    fn disposals_consume_the_oldest_lots_first() {
+        let mut ledger = TaxLedger::default();
+        let first = shares(&mut ledger, 10, 100, 0);
+        let second = shares(&mut ledger, 10, 300, 100 * DAY);
+
+        let disposal = sell(&mut ledger, 15, 600, 400 * DAY);
+        assert_eq!(disposal.method, LotMethod::Fifo);
+        let lots: Vec<(Option<u64>, u64)> = disposal
+            .matches
+            .iter()
+            .map(|m| (m.lot_id, m.quantity))
+            .collect();
+        assert_eq!(lots, vec![(Some(first), 10), (Some(second), 5)]);
+
+        // The first lot is consumed whole, half of the second pro rata
+        assert_eq!(disposal.matches[0].cost_basis, 100);
+        assert_eq!(disposal.matches[1].cost_basis, 150);
+        assert_eq!(disposal.matches[0].term, HoldingTerm::Long);
+        assert_eq!(disposal.matches[1].term, HoldingTerm::Short);
+        assert_eq!(disposal.cost_basis, 250);
+        assert_eq!(disposal.gain, 350);
+
+        let open = ledger.open_lots("0xabc");
+        assert_eq!(open.len(), 1);
+        assert_eq!((open[0].remaining, open[0].remaining_cost), (5, 150));
+    }
+
+    #[test]
+    fn rounded_proceeds_add_up_to_the_disposal() {
+        let mut ledger = TaxLedger::default();
+        shares(&mut ledger, 1, 10, 0);
+        shares(&mut ledger, 1, 10, 0);
+        shares(&mut ledger, 1, 10, 0);
+
+        let disposal = sell(&mut ledger, 3, 100, DAY);
+        let proceeds: Vec<u64> = disposal.matches.iter().map(|m| m.proceeds).collect();
+        assert_eq!(proceeds, vec![33, 33, 34]);
+    }
+
+    #[test]
+    fn short_disposals_leave_the_excess_without_basis() {
+        let mut ledger = TaxLedger::default();
+        shares(&mut ledger, 4, 40, 0);
+
+        let disposal = sell(&mut ledger, 6, 90, DAY);
+        let uncovered = disposal.matches.last().unwrap();
+        assert_eq!(uncovered.lot_id, None);
+        assert_eq!(uncovered.quantity, 2);
+        assert_eq!(uncovered.cost_basis, 0);
+        assert_eq!(uncovered.proceeds, 30);
+        assert_eq!(uncovered.term, HoldingTerm::Unknown);
+        assert_eq!(disposal.cost_basis, 40);
+    }
+
+    #[test]
+    fn designated_lots_go_before_fifo_once() {
+        let path = std::env::temp_dir().join(format!("tax_ledger_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut ledger = TaxLedger::load(&path).unwrap();
+        let first = shares(&mut ledger, 10, 100, 0);
+        let second = shares(&mut ledger, 10, 300, DAY);
+        ledger
+            .designate("0xabc", AssetKind::Shares, "prop-1", vec![second])
+            .unwrap();
+
+        let disposal = sell(&mut ledger, 12, 600, 2 * DAY);
+        assert_eq!(disposal.method, LotMethod::SpecificLot);
+        let lots: Vec<Option<u64>> = disposal.matches.iter().map(|m| m.lot_id).collect();
+        assert_eq!(lots, vec![Some(second), Some(first)]);
+
+        // The designation is used up by that disposal
+        let disposal = sell(&mut ledger, 1, 50, 3 * DAY);
+        assert_eq!(disposal.method, LotMethod::Fifo);
+        assert_eq!(disposal.matches[0].lot_id, Some(first));
+        let _ = std::fs::remove_file(&path);
+    }
+}
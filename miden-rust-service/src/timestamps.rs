@@ -0,0 +1,205 @@
+// src/timestamps.rs
+//
+// Timestamps in responses and generated documents
+//
+// Records store times as unix seconds (UTC). Every JSON response carries them
+// both ways: the annotate_responses layer adds an ISO 8601 UTC sibling next to
+// each timestamp field, so {"created_at": 1760572800} is answered as
+//   {"created_at": 1760572800, "created_at_iso": "2025-10-16T00:00:00Z"}
+// A timestamp field is `at`, `timestamp`, a `*_at` field or one of
+// OTHER_TIMESTAMP_KEYS, holding an integer. Responses that are downloads
+// (Content-Disposition) are passed through untouched, as their bytes are hashed.
+//
+// Generated documents and reports (closing documents, invoice PDFs, account
+// statements, tax CSVs) print dates in a display timezone, UTC unless the
+// request names an IANA zone with `?tz=Europe/Paris` or an
+// `X-Timezone: Europe/Paris` header. Stored records stay in UTC; only the
+// rendering changes.
+
+use anyhow::{anyhow, Result};
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderMap, HeaderName},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{SecondsFormat, TimeZone};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub const TIMEZONE_HEADER: HeaderName = HeaderName::from_static("x-timezone");
+
+/// Timestamp fields whose name does not end in `_at`.
+const OTHER_TIMESTAMP_KEYS: &[&str] = &[
+    "since",
+    "until",
+    "deadline",
+    "as_of",
+    "accrues_from",
+    "coverage_start",
+    "coverage_end",
+    "period_start",
+];
+
+/// Suffix of the ISO 8601 sibling of a timestamp field.
+const ISO_SUFFIX: &str = "_iso";
+
+/// 9999-12-31T23:59:59Z; larger integers are not timestamps.
+const MAX_TIMESTAMP: i64 = 253_402_300_799;
+
+/// ISO 8601 UTC form of a unix timestamp, e.g. 2025-10-16T00:00:00Z.
+pub fn iso8601(at: i64) -> Option<String> {
+    if !(0..=MAX_TIMESTAMP).contains(&at) {
+        return None;
+    }
+    chrono::DateTime::from_timestamp(at, 0).map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+fn is_timestamp_key(key: &str) -> bool {
+    key == "at"
+        || key == "timestamp"
+        || (key.ends_with("_at") && key.len() > 3)
+        || OTHER_TIMESTAMP_KEYS.contains(&key)
+}
+
+/// Adds an ISO 8601 sibling to every timestamp field of a JSON value.
+pub fn annotate(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut additions = Vec::new();
+            for (key, field) in map.iter_mut() {
+                if is_timestamp_key(key) {
+                    if let Some(iso) = field.as_i64().and_then(iso8601) {
+                        additions.push((format!("{}{}", key, ISO_SUFFIX), iso));
+                    }
+                } else {
+                    annotate(field);
+                }
+            }
+            for (key, iso) in additions {
+                map.entry(key).or_insert(serde_json::Value::String(iso));
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(annotate),
+        _ => {}
+    }
+}
+
+/// Middleware: annotates the timestamps of JSON responses.
+pub async fn annotate_responses(req: Request, next: Next) -> Response {
+    let response = next.run(req).await;
+    let json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if !json || response.headers().contains_key(header::CONTENT_DISPOSITION) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("Failed to read response body: {}", e);
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    let mut value: serde_json::Value = match serde_json::from_slice(&bytes) {
+        Ok(value) => value,
+        Err(_) => return Response::from_parts(parts, Body::from(bytes)),
+    };
+    annotate(&mut value);
+    match serde_json::to_vec(&value) {
+        Ok(annotated) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(annotated))
+        }
+        Err(_) => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
+
+/// Timezone dates are displayed in by generated documents and reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayZone(Tz);
+
+impl Default for DisplayZone {
+    fn default() -> Self {
+        DisplayZone(Tz::UTC)
+    }
+}
+
+impl std::str::FromStr for DisplayZone {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.trim()
+            .parse::<Tz>()
+            .map(DisplayZone)
+            .map_err(|_| anyhow!("Unknown timezone: {} (expected an IANA name)", s))
+    }
+}
+
+impl std::fmt::Display for DisplayZone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0.name())
+    }
+}
+
+impl Serialize for DisplayZone {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for DisplayZone {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl DisplayZone {
+    /// Zone named by a request: `?tz=` first, then the X-Timezone header.
+    pub fn requested(headers: &HeaderMap, query: Option<&str>) -> Result<Self> {
+        let header = headers.get(TIMEZONE_HEADER).and_then(|v| v.to_str().ok());
+        match query.or(header) {
+            Some(name) if !name.trim().is_empty() => name.parse(),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    fn local(&self, at: i64) -> Option<chrono::DateTime<Tz>> {
+        self.0.timestamp_opt(at, 0).single()
+    }
+
+    /// Unix seconds of local midnight starting day 1 of a month in this zone.
+    pub fn month_start(&self, year: i32, month: u32) -> Option<i64> {
+        self.0
+            .with_ymd_and_hms(year, month, 1, 0, 0, 0)
+            .earliest()
+            .map(|t| t.timestamp())
+    }
+
+    /// e.g. 2025-10-16
+    pub fn date(&self, at: i64) -> String {
+        self.local(at)
+            .map(|t| t.format("%Y-%m-%d").to_string())
+            .unwrap_or_default()
+    }
+
+    /// e.g. 2025-10-16 02:00:00 CEST
+    pub fn date_time(&self, at: i64) -> String {
+        self.local(at)
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S %Z").to_string())
+            .unwrap_or_default()
+    }
+
+    /// e.g. 2025-10-16T02:00:00+02:00
+    pub fn rfc3339(&self, at: i64) -> String {
+        self.local(at)
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_else(|| at.to_string())
+    }
+}
@@ -0,0 +1,82 @@
+// src/tls.rs
+//
+// Native TLS for the HTTP server
+//
+// By default the service speaks plain HTTP and expects a reverse proxy in front
+// of it. Setting TLS_CERT_PATH / TLS_KEY_PATH makes it terminate TLS itself
+// (rustls, ring provider).
+//
+// Mutual TLS: with TLS_CLIENT_CA_PATH set, clients must present a certificate
+// chaining to one of the CAs in that bundle; the handshake fails otherwise.
+// TLS_CLIENT_AUTH_OPTIONAL=true accepts clients without a certificate while
+// still rejecting invalid ones (useful while rolling out client certs).
+
+use anyhow::Result;
+use rustls::{
+    pki_types::{CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    RootCertStore, ServerConfig,
+};
+use std::{fs::File, io::BufReader, path::Path, sync::Arc};
+
+use crate::config::TlsConfig;
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let file = File::open(path)
+        .map_err(|e| anyhow::anyhow!("Cannot open certificate file {:?}: {}", path, e))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| anyhow::anyhow!("Invalid certificate PEM in {:?}: {}", path, e))?;
+
+    if certs.is_empty() {
+        return Err(anyhow::anyhow!("No certificates found in {:?}", path));
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &Path) -> Result<PrivateKeyDer<'static>> {
+    let file =
+        File::open(path).map_err(|e| anyhow::anyhow!("Cannot open key file {:?}: {}", path, e))?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|e| anyhow::anyhow!("Invalid key PEM in {:?}: {}", path, e))?
+        .ok_or_else(|| anyhow::anyhow!("No private key found in {:?}", path))
+}
+
+/// Builds the rustls server configuration from the TLS settings.
+pub fn server_config(tls: &TlsConfig) -> Result<Arc<ServerConfig>> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| anyhow::anyhow!("Unsupported TLS protocol configuration: {}", e))?;
+
+    let builder = match &tls.client_ca_path {
+        Some(ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(ca_path)? {
+                roots
+                    .add(cert)
+                    .map_err(|e| anyhow::anyhow!("Invalid client CA in {:?}: {}", ca_path, e))?;
+            }
+
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+            let verifier = if tls.client_auth_optional {
+                verifier.allow_unauthenticated()
+            } else {
+                verifier
+            }
+            .build()
+            .map_err(|e| anyhow::anyhow!("Cannot build client certificate verifier: {}", e))?;
+
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut config = builder
+        .with_single_cert(load_certs(&tls.cert_path)?, load_key(&tls.key_path)?)
+        .map_err(|e| anyhow::anyhow!("Certificate and key do not match: {}", e))?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(Arc::new(config))
+}
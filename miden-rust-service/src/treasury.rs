@@ -0,0 +1,715 @@
+// src/treasury.rs
+//
+// Treasury account and operational funds
+//
+// The treasury (TREASURY_ACCOUNT) is an account in the client store that
+// collects the service's own funds:
+// - platform fees: with PLATFORM_FEE_BPS set, every escrow payout to a seller
+//   (a release, or the seller's share of a split) sends that fraction of the
+//   payout to the treasury in the same transaction (escrow.rs)
+// - funds swept from orphaned accounts (recovery.rs)
+// - invoice payments (billing.rs)
+// - anything else sent to it, such as faucet change
+//
+// Every inflow and withdrawal is entered in a ledger (TREASURY_LEDGER_PATH).
+// Fees, recovered funds and invoice payments are entered when their note to
+// the treasury is created; other notes when POST /admin/treasury/collect consumes the notes
+// waiting for the treasury. GET /admin/treasury shows the vault and those
+// notes, GET /admin/treasury/ledger the entries, and GET
+// /admin/treasury/income fee income (or another kind of entry) per day, week
+// or month.
+//
+// Escrow yield (escrow_yield.rs) and fee sponsorship top-ups (billing.rs) are
+// paid out of the treasury without approval and entered when their note is
+// created.
+//
+// Withdrawals to an external account (POST /admin/treasury/withdrawals) need
+// an API key with the admin role (principals.rs) and are parked as an approval
+// (approvals.rs). A different admin confirms it with POST
+// /approvals/:approval_id/confirm, which sends the funds as one P2ID note.
+// Withdrawals need API keys whether or not ESCROW_AUTH_REQUIRED is set.
+
+use anyhow::Result;
+use chrono::{Datelike, TimeZone};
+use miden_client::{
+    account::AccountId,
+    asset::{Asset, FungibleAsset},
+    note::Note,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    account_id_to_hex,
+    approvals::{Approval, ApprovalRequired, ApprovalSubject, Approver},
+    client,
+    escrow::EscrowAuthError,
+    ledger::{JournalKind, Posting},
+    read_cache::Touched,
+    reconcile::parse_hex_account_id,
+    recovery::FungibleBalance,
+    MidenClientWrapper,
+};
+
+/// Fees are in basis points of the payout.
+const BPS_DENOMINATOR: u128 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerEntryKind {
+    Fee,
+    Recovered,
+    /// Invoice payments (billing.rs)
+    Billing,
+    /// Any other note collected by the treasury
+    Other,
+    Withdrawal,
+    /// Escrow yield paid to buyers and sellers (escrow_yield.rs)
+    Yield,
+    /// Fee asset sent to accounts whose fees are sponsored (billing.rs)
+    Sponsorship,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub entry_id: u64,
+    pub kind: LedgerEntryKind,
+    pub faucet_id: String,
+    pub amount: u64,
+    /// Escrow a fee was taken from, account funds were recovered from, sender
+    /// of another note, or recipient of a withdrawal, yield or sponsorship
+    pub counterparty: Option<String>,
+    pub note_id: Option<String>,
+    pub tx_id: Option<String>,
+    /// Approval a withdrawal was confirmed under
+    pub approval_id: Option<u64>,
+    pub at: i64,
+}
+
+impl LedgerEntry {
+    /// Entry with only the asset filled in; the ledger assigns ID and time.
+    pub fn new(kind: LedgerEntryKind, faucet_id: String, amount: u64) -> Self {
+        Self {
+            entry_id: 0,
+            kind,
+            faucet_id,
+            amount,
+            counterparty: None,
+            note_id: None,
+            tx_id: None,
+            approval_id: None,
+            at: 0,
+        }
+    }
+}
+
+/// Period length of the income report; periods start at midnight UTC (weeks
+/// on Monday, months on the 1st).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IncomeBucket {
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+impl IncomeBucket {
+    /// Start of the period containing `at`.
+    pub fn period_start(&self, at: i64) -> i64 {
+        const DAY: i64 = 86_400;
+        let day = at.div_euclid(DAY);
+        match self {
+            IncomeBucket::Day => day * DAY,
+            // 1970-01-01 was a Thursday
+            IncomeBucket::Week => (day - (day + 3).rem_euclid(7)) * DAY,
+            IncomeBucket::Month => {
+                let date = chrono::Utc
+                    .timestamp_opt(at, 0)
+                    .single()
+                    .unwrap_or_default()
+                    .date_naive();
+                chrono::Utc
+                    .with_ymd_and_hms(date.year(), date.month(), 1, 0, 0, 0)
+                    .single()
+                    .map_or(day * DAY, |start| start.timestamp())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IncomePeriod {
+    pub period_start: i64,
+    pub faucet_id: String,
+    pub amount: u64,
+    pub entries: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IncomeReport {
+    pub kind: LedgerEntryKind,
+    pub bucket: IncomeBucket,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    /// Oldest period first
+    pub periods: Vec<IncomePeriod>,
+    pub totals: Vec<FungibleBalance>,
+}
+
+/// A withdrawal waiting for (or executed under) an approval.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreasuryWithdrawal {
+    pub recipient_account_id: String,
+    pub faucet_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WithdrawalInput {
+    /// Account name or hex AccountId
+    pub recipient_account_id: String,
+    pub amount: u64,
+    /// Hex faucet ID of the asset; defaults to the service token
+    pub faucet_id: Option<String>,
+}
+
+/// Fee note built for an escrow payout, recorded once its transaction is in.
+pub(crate) struct PlatformFee {
+    pub note: Note,
+    pub asset: FungibleAsset,
+    pub treasury: AccountId,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TreasuryLedger {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    entries: Vec<LedgerEntry>,
+    #[serde(default)]
+    next_entry_id: u64,
+}
+
+impl TreasuryLedger {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<TreasuryLedger>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            TreasuryLedger::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, mut entry: LedgerEntry, at: i64) -> Result<LedgerEntry> {
+        self.next_entry_id += 1;
+        entry.entry_id = self.next_entry_id;
+        entry.at = at;
+        self.entries.push(entry.clone());
+        self.save()?;
+        Ok(entry)
+    }
+
+    /// Whether a note is already entered (fees and recovered funds are entered
+    /// before the treasury collects them).
+    pub fn has_note(&self, note_id: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|e| e.note_id.as_deref() == Some(note_id))
+    }
+
+    /// Most recent entries first, optionally of one kind.
+    pub fn entries(&self, kind: Option<LedgerEntryKind>, limit: usize) -> Vec<&LedgerEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(|e| kind.is_none_or(|k| e.kind == k))
+            .take(limit)
+            .collect()
+    }
+
+    /// Sums entries of `kind` in [from, to) per period and faucet.
+    pub fn income(
+        &self,
+        kind: LedgerEntryKind,
+        bucket: IncomeBucket,
+        from: Option<i64>,
+        to: Option<i64>,
+    ) -> IncomeReport {
+        let mut periods: BTreeMap<(i64, String), IncomePeriod> = BTreeMap::new();
+        let mut totals: BTreeMap<String, u64> = BTreeMap::new();
+        for entry in self.entries.iter().filter(|e| {
+            e.kind == kind && from.is_none_or(|from| e.at >= from) && to.is_none_or(|to| e.at < to)
+        }) {
+            let period_start = bucket.period_start(entry.at);
+            let period = periods
+                .entry((period_start, entry.faucet_id.clone()))
+                .or_insert_with(|| IncomePeriod {
+                    period_start,
+                    faucet_id: entry.faucet_id.clone(),
+                    amount: 0,
+                    entries: 0,
+                });
+            period.amount += entry.amount;
+            period.entries += 1;
+            *totals.entry(entry.faucet_id.clone()).or_default() += entry.amount;
+        }
+
+        IncomeReport {
+            kind,
+            bucket,
+            from,
+            to,
+            periods: periods.into_values().collect(),
+            totals: totals
+                .into_iter()
+                .map(|(faucet_id, amount)| FungibleBalance { faucet_id, amount })
+                .collect(),
+        }
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    GetTreasury {} -> serde_json::Value;
+    |client, _op| client.get_treasury().await;
+
+    CollectTreasuryNotes {} -> serde_json::Value;
+    touched: |_op| Some(Touched::All);
+    |client, _op| client.collect_treasury_notes().await;
+
+    GetTreasuryLedger {
+        kind: Option<LedgerEntryKind>,
+        limit: usize,
+    } -> serde_json::Value;
+    |client, op| client.treasury_ledger(op.kind, op.limit);
+
+    GetTreasuryIncome {
+        kind: LedgerEntryKind,
+        bucket: IncomeBucket,
+        from: Option<i64>,
+        to: Option<i64>,
+    } -> serde_json::Value;
+    |client, op| client.treasury_income(op.kind, op.bucket, op.from, op.to);
+
+    RequestTreasuryWithdrawal {
+        input: WithdrawalInput,
+        api_key: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.request_treasury_withdrawal(op.input, op.api_key.as_deref()).await
+}
+
+impl MidenClientWrapper {
+    /// The TREASURY_ACCOUNT.
+    pub(crate) fn treasury_account_id(&self) -> Result<AccountId> {
+        let selector = self
+            .config
+            .treasury_account
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("No treasury account (TREASURY_ACCOUNT)"))?;
+        parse_hex_account_id(&self.account_hex(selector)?)
+    }
+
+    /// Enters an inflow or withdrawal in the ledger. The funds have already
+    /// moved, so a ledger write failure is logged rather than returned.
+    pub(crate) fn record_treasury_entry(&mut self, entry: LedgerEntry) {
+        let kind = entry.kind;
+        if let Err(e) = self.treasury.record(entry, chrono::Utc::now().timestamp()) {
+            tracing::error!(
+                "❌ Could not enter {:?} in the treasury ledger: {}",
+                kind,
+                e
+            );
+        }
+    }
+
+    /// Builds the note paying the platform fee on `payout` from an escrow to
+    /// the treasury; None when no fee is charged.
+    pub(crate) fn platform_fee_note(
+        &mut self,
+        escrow_account_id: AccountId,
+        faucet_id: AccountId,
+        payout: u64,
+    ) -> Result<Option<PlatformFee>> {
+        let bps = self.config.platform_fee_bps as u128;
+        let fee = (payout as u128 * bps / BPS_DENOMINATOR) as u64;
+        if fee == 0 {
+            return Ok(None);
+        }
+
+        let treasury = self.treasury_account_id()?;
+        let asset = FungibleAsset::new(faucet_id, fee)?;
+        let note = client::p2id_note(
+            escrow_account_id,
+            treasury,
+            vec![asset.into()],
+            &mut self.rng,
+        )?;
+        Ok(Some(PlatformFee {
+            note,
+            asset,
+            treasury,
+        }))
+    }
+
+    /// Records a fee note sent in transaction `tx_id`.
+    pub(crate) fn record_platform_fee(&mut self, escrow_hex: &str, fee: &PlatformFee, tx_id: &str) {
+        let note_id = fee.note.id().to_string();
+        self.records.expect_note(
+            &note_id,
+            &account_id_to_hex(fee.treasury),
+            "platform-fee",
+            Some(tx_id.to_string()),
+        );
+        self.record_treasury_entry(LedgerEntry {
+            counterparty: Some(escrow_hex.to_string()),
+            note_id: Some(note_id),
+            tx_id: Some(tx_id.to_string()),
+            ..LedgerEntry::new(
+                LedgerEntryKind::Fee,
+                account_id_to_hex(fee.asset.faucet_id()),
+                fee.asset.amount(),
+            )
+        });
+    }
+
+    /// Vault and pending notes of the treasury account.
+    pub async fn get_treasury(&mut self) -> Result<serde_json::Value> {
+        let treasury = self.treasury_account_id()?;
+        self.sync_state().await?;
+
+        let account = self
+            .client
+            .get_account(treasury)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Treasury account {} not found", treasury))?;
+        let mut balances = Vec::new();
+        let mut non_fungible_assets = 0;
+        for asset in account.account().vault().assets() {
+            match asset {
+                Asset::Fungible(fungible) => balances.push(FungibleBalance {
+                    faucet_id: account_id_to_hex(fungible.faucet_id()),
+                    amount: fungible.amount(),
+                }),
+                Asset::NonFungible(_) => non_fungible_assets += 1,
+            }
+        }
+
+        let pending_notes: Vec<_> = self
+            .client
+            .get_consumable_notes(Some(treasury))
+            .await?
+            .iter()
+            .filter(|(note, _)| self.admissible(note))
+            .map(|(note, _)| {
+                let note_id = note.id().to_string();
+                serde_json::json!({
+                    "purpose": self
+                        .records
+                        .expected_notes
+                        .get(&note_id)
+                        .map(|expected| expected.purpose.clone()),
+                    "note_id": note_id,
+                })
+            })
+            .collect();
+
+        Ok(serde_json::json!({
+            "treasury_account_id": account_id_to_hex(treasury),
+            "balances": balances,
+            "non_fungible_assets": non_fungible_assets,
+            "pending_notes": pending_notes,
+            "platform_fee_bps": self.config.platform_fee_bps,
+        }))
+    }
+
+    /// Consumes the notes waiting for the treasury into its vault, entering
+    /// notes the ledger does not know yet as `other`.
+    pub async fn collect_treasury_notes(&mut self) -> Result<serde_json::Value> {
+        let treasury = self.treasury_account_id()?;
+        self.sync_state().await?;
+
+        let mut unknown = Vec::new();
+        for (note, _) in self.client.get_consumable_notes(Some(treasury)).await? {
+            let note_id = note.id().to_string();
+            if self.treasury.has_note(&note_id) {
+                continue;
+            }
+            let sender = note.metadata().map(|m| account_id_to_hex(m.sender()));
+            for asset in note.assets().iter_fungible() {
+                unknown.push(LedgerEntry {
+                    counterparty: sender.clone(),
+                    note_id: Some(note_id.clone()),
+                    ..LedgerEntry::new(
+                        LedgerEntryKind::Other,
+                        account_id_to_hex(asset.faucet_id()),
+                        asset.amount(),
+                    )
+                });
+            }
+        }
+
+        let treasury_hex = account_id_to_hex(treasury);
+        let op_id = self
+            .records
+            .begin_operation("treasury_collect", &treasury_hex);
+        let result = self.submit_consume_all(treasury).await;
+        let tx_id = result
+            .as_ref()
+            .ok()
+            .and_then(|consumed| consumed.batches.last())
+            .map(|batch| batch.tx_id.clone());
+        self.records.finish_operation(op_id, &result, tx_id.clone());
+        let consumed = result?;
+
+        let mut entered = 0;
+        for entry in unknown {
+            let consumed_by = entry
+                .note_id
+                .as_deref()
+                .and_then(|note_id| consumed.tx_of(note_id));
+            if let Some(consumed_by) = consumed_by {
+                self.record_treasury_entry(LedgerEntry {
+                    tx_id: Some(consumed_by.to_string()),
+                    ..entry
+                });
+                entered += 1;
+            }
+        }
+        let consumed_note_ids = consumed.note_ids();
+        tracing::info!(
+            "🏦 Treasury collected {} note(s), {} new ledger entries. TX: {}",
+            consumed_note_ids.len(),
+            entered,
+            consumed.transaction_ids().join(", ")
+        );
+
+        Ok(serde_json::json!({
+            "tx_id": tx_id,
+            "tx_ids": consumed.transaction_ids(),
+            "consumed_note_ids": consumed_note_ids,
+            "refused_note_ids": consumed.refused_note_ids(),
+            "new_entries": entered,
+            "error": consumed.error,
+        }))
+    }
+
+    pub fn treasury_ledger(
+        &self,
+        kind: Option<LedgerEntryKind>,
+        limit: usize,
+    ) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self.treasury.entries(kind, limit)))
+    }
+
+    pub fn treasury_income(
+        &self,
+        kind: LedgerEntryKind,
+        bucket: IncomeBucket,
+        from: Option<i64>,
+        to: Option<i64>,
+    ) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self
+            .treasury
+            .income(kind, bucket, from, to)))
+    }
+
+    /// Treasury balance of `faucet_id`.
+    async fn treasury_balance(&mut self, treasury: AccountId, faucet_id: AccountId) -> Result<u64> {
+        let account = self
+            .client
+            .get_account(treasury)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Treasury account {} not found", treasury))?;
+        Ok(account
+            .account()
+            .vault()
+            .get_balance(faucet_id)
+            .unwrap_or(0))
+    }
+
+    /// Parks a withdrawal for a second admin. Always returns an error: the
+    /// `ApprovalRequired` naming the approval, or why it was refused.
+    pub async fn request_treasury_withdrawal(
+        &mut self,
+        input: WithdrawalInput,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let requested_by = Approver::from(self.admin_principal(api_key, "treasury withdrawals")?);
+        let treasury = self.treasury_account_id()?;
+
+        let recipient = parse_hex_account_id(&self.account_hex(&input.recipient_account_id)?)?;
+        if recipient == treasury {
+            return Err(anyhow::anyhow!("Cannot withdraw to the treasury itself"));
+        }
+        let faucet_id = match &input.faucet_id {
+            Some(faucet_id) => parse_hex_account_id(faucet_id)?,
+            None => self
+                .faucet_account_id
+                .ok_or_else(|| anyhow::anyhow!("Faucet not initialized"))?,
+        };
+
+        self.sync_state().await?;
+        let balance = self.treasury_balance(treasury, faucet_id).await?;
+        if balance < input.amount {
+            return Err(anyhow::anyhow!(
+                "Treasury holds {} of faucet {}, cannot withdraw {}",
+                balance,
+                account_id_to_hex(faucet_id),
+                input.amount
+            ));
+        }
+
+        self.approvals.expire(chrono::Utc::now().timestamp())?;
+        let withdrawal = TreasuryWithdrawal {
+            recipient_account_id: account_id_to_hex(recipient),
+            faucet_id: account_id_to_hex(faucet_id),
+        };
+        let approval = self.approvals.request(
+            ApprovalSubject::TreasuryWithdrawal(withdrawal.clone()),
+            input.amount,
+            requested_by,
+            1,
+            self.config.escrow_approval_ttl.as_secs() as i64,
+        )?;
+        tracing::info!(
+            "Treasury withdrawal of {} to {} needs approval {}",
+            input.amount,
+            withdrawal.recipient_account_id,
+            approval.approval_id
+        );
+
+        Err(ApprovalRequired(format!(
+            "withdrawal of {} to {} needs a second admin; confirm approval {} with POST /approvals/{}/confirm",
+            input.amount, withdrawal.recipient_account_id, approval.approval_id, approval.approval_id
+        ))
+        .into())
+    }
+
+    /// Confirms a pending withdrawal approval (see `confirm_approval`) and
+    /// sends the funds.
+    pub(crate) async fn confirm_treasury_withdrawal(
+        &mut self,
+        approval: &Approval,
+        api_key: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let withdrawal = approval.withdrawal.clone().ok_or_else(|| {
+            anyhow::anyhow!("Approval {} is not a withdrawal", approval.approval_id)
+        })?;
+        let approver = Approver::from(self.admin_principal(api_key, "treasury withdrawals")?);
+        if approver.key_id == approval.requested_by.key_id {
+            return Err(EscrowAuthError::Forbidden(format!(
+                "approval {} must be confirmed by a different admin than the requester",
+                approval.approval_id
+            ))
+            .into());
+        }
+
+        self.approvals
+            .confirm(approval.approval_id, approver.clone())?;
+        tracing::info!(
+            "Approval {} confirmed by API key {} ({}); withdrawing {} to {}",
+            approval.approval_id,
+            approver.key_id,
+            approver.label,
+            approval.amount,
+            withdrawal.recipient_account_id
+        );
+
+        let op_id = self
+            .records
+            .begin_operation("treasury_withdrawal", &withdrawal.recipient_account_id);
+        let result = self
+            .submit_treasury_withdrawal(approval.approval_id, &withdrawal, approval.amount)
+            .await;
+        let tx_id = result.as_ref().ok().cloned();
+        self.records.finish_operation(op_id, &result, tx_id);
+        let finished = self.approvals.finish(approval.approval_id, &result)?;
+        result?;
+
+        Ok(serde_json::json!(finished))
+    }
+
+    /// Sends `amount` from the treasury to the recipient and enters the
+    /// withdrawal in the ledger.
+    async fn submit_treasury_withdrawal(
+        &mut self,
+        approval_id: u64,
+        withdrawal: &TreasuryWithdrawal,
+        amount: u64,
+    ) -> Result<String> {
+        let treasury = self.treasury_account_id()?;
+        let recipient = parse_hex_account_id(&withdrawal.recipient_account_id)?;
+        let faucet_id = parse_hex_account_id(&withdrawal.faucet_id)?;
+
+        self.sync_state().await?;
+        let balance = self.treasury_balance(treasury, faucet_id).await?;
+        if balance < amount {
+            return Err(anyhow::anyhow!(
+                "Treasury holds {} of faucet {}, cannot withdraw {}",
+                balance,
+                withdrawal.faucet_id,
+                amount
+            ));
+        }
+
+        let asset = FungibleAsset::new(faucet_id, amount)?;
+        let (note_id, transaction_request) =
+            client::p2id_request(treasury, recipient, vec![asset.into()], &mut self.rng)?;
+        let note_id = note_id.to_string();
+        let tx_id = self
+            .client
+            .submit_new_transaction(treasury, transaction_request)
+            .await?
+            .to_string();
+        tracing::info!("🏦 Treasury withdrawal sent! TX: {}", tx_id);
+        self.post_ledger_entry(
+            JournalKind::TreasuryWithdrawal,
+            Posting::transfer(
+                &account_id_to_hex(treasury),
+                &withdrawal.recipient_account_id,
+                &withdrawal.faucet_id,
+                amount,
+            )
+            .to_vec(),
+            &tx_id,
+            Some(&withdrawal.recipient_account_id),
+        );
+
+        self.records.expect_note(
+            &note_id,
+            &withdrawal.recipient_account_id,
+            "treasury-withdrawal",
+            Some(tx_id.clone()),
+        );
+        self.record_treasury_entry(LedgerEntry {
+            counterparty: Some(withdrawal.recipient_account_id.clone()),
+            note_id: Some(note_id),
+            tx_id: Some(tx_id.clone()),
+            approval_id: Some(approval_id),
+            ..LedgerEntry::new(
+                LedgerEntryKind::Withdrawal,
+                withdrawal.faucet_id.clone(),
+                amount,
+            )
+        });
+
+        self.sync_state().await?;
+        Ok(tx_id)
+    }
+}
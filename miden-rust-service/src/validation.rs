@@ -0,0 +1,1252 @@
+// src/validation.rs
+//
+// Request payload validation
+//
+// Request DTOs implement `Validate`; the HTTP layer runs it after JSON decoding
+// and answers 422 with one message per offending field, so malformed input is
+// rejected before it reaches the client queue.
+//
+// Domain rules kept here (shared by single and batch endpoints):
+// - property IDs: 1-64 chars of [A-Za-z0-9._-] (the backend issues "PROP-<ts>")
+// - property types: 0 residential, 1 commercial, 2 land
+// - token amounts and prices: greater than zero; price currencies known
+//   (currency.rs)
+// - account selectors: a named account or a 0x-prefixed hex AccountId
+
+use serde::Serialize;
+
+#[cfg(feature = "fault-injection")]
+use crate::faults::{FaultInput, FaultKind};
+use crate::{
+    account_upgrades::UpgradeInput,
+    accreditation_rules::{RuleInput, WILDCARD},
+    allowances::{AllowanceInput, AllowanceTransferInput, SERVICE_SPENDER},
+    attachments::{BinderInput, ChecklistInput, InsurerInput},
+    auctions::{AuctionInput, BidInput, MAX_AUCTION_DURATION_SECS},
+    bench::{BenchInput, MAX_BENCH_CONCURRENCY, MAX_BENCH_OPERATIONS},
+    confidential_listings::{ConfidentialListingInput, DisclosureRequestInput},
+    currency::{find_currency, PriceInput, SERVICE_TOKEN_SYMBOL},
+    custodial::{CustodialPaymentInput, OtpRequestInput, OtpVerifyInput},
+    data_subjects::ErasureInput,
+    documents::DocumentInput,
+    esign::{SignatureInput, SignerInput, MAX_SIGNERS},
+    fee_splits::CommissionInput,
+    geo::{
+        valid_position, BoundingBox, Geometry, LocationInput, NearbyQuery, WithinQuery,
+        MAX_MAP_LIMIT, MAX_NEARBY_RADIUS_M, MAX_PARCEL_POSITIONS,
+    },
+    hooks::{HookInput, HookTarget},
+    identity::ProviderInput,
+    installments::{InstallmentPlanInput, MAX_INSTALLMENTS},
+    jurisdiction_lists::ListUpdate,
+    leases::{LeaseEndInput, LeaseInput, MAX_RENT_PERIODS},
+    liens::{DischargeInput, LienInput, SignOffInput},
+    mint_jobs::MintItemInput,
+    negotiation::{CounterInput, OfferInput, OfferResponseInput, MAX_OFFER_EXPIRY_SECS},
+    notary::NotarizationInput,
+    order_book::{MarketInput, OrderInput},
+    organizations::{MemberInput, MemberRoleInput, OrgAccountInput, OrganizationInput},
+    portal::PortalAccessInput,
+    principals::ApiKeyInput,
+    professionals::{ProfessionalInput, ReportInput, RevokeProfessionalInput},
+    property_recovery::LostAccessInput,
+    property_registry::PropertyReissueInput,
+    property_retirement::PropertyRetireInput,
+    recovery::SweepInput,
+    sagas::SagaInput,
+    search::{
+        ListingDetailsInput, SearchQuery, MAX_ADDRESS_LEN, MAX_DESCRIPTION_LEN, MAX_SEARCH_LIMIT,
+    },
+    settlement_tokens::{parse_symbol, ListingTokenInput, SettlementTokenInput, MAX_DECIMALS},
+    store_maintenance::PruneInput,
+    subscriptions::{Delivery, SubscriptionInput},
+    swaps::SwapQuoteInput,
+    tax::LotSelectionInput,
+    treasury::WithdrawalInput,
+    wallet_sessions::{ChallengeInput, SessionInput, UnsignedPaymentInput},
+    withholding::{JurisdictionInput, WithholdingRuleInput},
+};
+
+pub const MAX_PROPERTY_ID_LEN: usize = 64;
+pub const MAX_PROPERTY_TYPE: u8 = 2;
+const MAX_TEXT_LEN: usize = 256;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Field errors collected while validating one payload.
+#[derive(Debug, Default, Serialize)]
+pub struct ValidationErrors {
+    pub errors: Vec<FieldError>,
+}
+
+impl ValidationErrors {
+    pub fn add(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(FieldError {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Records the outcome of a rule check for `field`.
+    pub fn check(&mut self, field: &str, result: Result<(), String>) {
+        if let Err(message) = result {
+            self.add(field, message);
+        }
+    }
+
+    /// Validates a nested value, prefixing its field names with `prefix`.
+    pub fn nested<T: Validate>(&mut self, prefix: &str, value: &T) {
+        let mut inner = ValidationErrors::default();
+        value.validate(&mut inner);
+        for error in inner.errors {
+            self.add(format!("{}.{}", prefix, error.field), error.message);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// One-line summary for the `error` field of responses.
+    pub fn summary(&self) -> String {
+        let fields: Vec<String> = self
+            .errors
+            .iter()
+            .map(|e| format!("{}: {}", e.field, e.message))
+            .collect();
+        format!("Validation failed ({})", fields.join("; "))
+    }
+}
+
+pub trait Validate {
+    fn validate(&self, errors: &mut ValidationErrors);
+
+    fn validated(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        self.validate(&mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Field rules
+// ----------------------------------------------------------------------------
+
+pub fn non_empty(value: &str) -> Result<(), String> {
+    if value.trim().is_empty() {
+        return Err("must not be empty".to_string());
+    }
+    if value.len() > MAX_TEXT_LEN {
+        return Err(format!("must be at most {} characters", MAX_TEXT_LEN));
+    }
+    Ok(())
+}
+
+pub fn property_id(value: &str) -> Result<(), String> {
+    if value.is_empty() {
+        return Err("must not be empty".to_string());
+    }
+    if value.len() > MAX_PROPERTY_ID_LEN {
+        return Err(format!(
+            "must be at most {} characters",
+            MAX_PROPERTY_ID_LEN
+        ));
+    }
+    if !value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err("may only contain letters, digits, '-', '_' and '.'".to_string());
+    }
+    Ok(())
+}
+
+pub fn property_type(value: u8) -> Result<(), String> {
+    if value > MAX_PROPERTY_TYPE {
+        return Err(format!(
+            "must be 0 (residential), 1 (commercial) or 2 (land), got {}",
+            value
+        ));
+    }
+    Ok(())
+}
+
+pub fn positive(value: u64) -> Result<(), String> {
+    if value == 0 {
+        return Err("must be greater than 0".to_string());
+    }
+    Ok(())
+}
+
+/// A positive price in a known currency (or without one).
+pub fn price(value: &PriceInput) -> Result<(), String> {
+    positive(value.amount_minor())?;
+    match value.currency() {
+        Some(code) if find_currency(code).is_none() => Err(format!("unknown currency {}", code)),
+        _ => Ok(()),
+    }
+}
+
+/// 0x-prefixed (or bare, when `require_prefix` is false) even-length hex.
+pub fn hex_string(value: &str, require_prefix: bool) -> Result<(), String> {
+    let digits = match value.strip_prefix("0x") {
+        Some(digits) => digits,
+        None if require_prefix => return Err("must be 0x-prefixed hex".to_string()),
+        None => value,
+    };
+    if digits.is_empty() {
+        return Err("must not be empty".to_string());
+    }
+    if digits.len() % 2 != 0 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("must be an even-length hex string".to_string());
+    }
+    Ok(())
+}
+
+/// A named account from `names` or a 0x-prefixed hex AccountId.
+pub fn account_selector(value: &str, names: &[&str]) -> Result<(), String> {
+    if names.contains(&value) {
+        return Ok(());
+    }
+    hex_string(value, true).map_err(|_| {
+        format!(
+            "must be one of {} or a 0x-prefixed hex account ID",
+            names.join(", ")
+        )
+    })
+}
+
+/// A plausible email address: one "@", a local part and a dotted domain.
+pub fn email(value: &str) -> Result<(), String> {
+    let value = value.trim();
+    let valid = value.len() <= 254
+        && !value.chars().any(char::is_whitespace)
+        && value.split_once('@').is_some_and(|(local, domain)| {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.split('.').count() >= 2
+                && domain.split('.').all(|label| !label.is_empty())
+        });
+    if !valid {
+        return Err("must be an email address".to_string());
+    }
+    Ok(())
+}
+
+pub fn country_code(value: &str) -> Result<(), String> {
+    if value.len() != 2 || !value.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err("must be a two-letter ISO 3166-1 country code".to_string());
+    }
+    Ok(())
+}
+
+/// ISO 3166-1 country code, optionally followed by a region: "US", "US-CA".
+pub fn jurisdiction_code(value: &str) -> Result<(), String> {
+    let (country, region) = match value.split_once('-') {
+        Some((country, region)) => (country, Some(region)),
+        None => (value, None),
+    };
+    let region_ok = region
+        .is_none_or(|r| (1..=3).contains(&r.len()) && r.chars().all(|c| c.is_ascii_alphanumeric()));
+    if country_code(country).is_err() || !region_ok {
+        return Err("must be a country code, optionally with a region (e.g. US-CA)".to_string());
+    }
+    Ok(())
+}
+
+impl Validate for MintItemInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("property_id", property_id(&self.property_id));
+        errors.check(
+            "owner_account_id",
+            account_selector(&self.owner_account_id, &["alice", "bob"]),
+        );
+        errors.check("ipfs_cid", non_empty(&self.ipfs_cid));
+        errors.check("property_type", property_type(self.property_type));
+        errors.check("price", price(&self.price));
+    }
+}
+
+impl Validate for SagaInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        match self {
+            SagaInput::Funding {
+                buyer_account_id,
+                seller_account_id,
+                amount,
+                property_id: property,
+            } => {
+                errors.check(
+                    "buyer_account_id",
+                    account_selector(buyer_account_id, &["alice", "bob"]),
+                );
+                errors.check(
+                    "seller_account_id",
+                    account_selector(seller_account_id, &["alice", "bob"]),
+                );
+                errors.check("amount", positive(*amount));
+                if let Some(property) = property {
+                    errors.check("property_id", property_id(property));
+                }
+            }
+            SagaInput::Purchase {
+                property_id: property,
+                escrow_account_id,
+                buyer_account_id,
+                amount,
+            } => {
+                errors.check("property_id", property_id(property));
+                if let Some(escrow) = escrow_account_id {
+                    errors.check("escrow_account_id", hex_string(escrow, false));
+                } else {
+                    // Without an escrow the buyer and amount open one
+                    match buyer_account_id {
+                        Some(buyer) => errors.check(
+                            "buyer_account_id",
+                            account_selector(buyer, &["alice", "bob"]),
+                        ),
+                        None => errors.add(
+                            "buyer_account_id",
+                            "is required without an escrow_account_id",
+                        ),
+                    }
+                    errors.check("amount", positive(amount.unwrap_or(0)));
+                }
+            }
+            SagaInput::Tokenization {
+                property_id: property,
+                owner_account_id,
+                ipfs_cid,
+                property_type: kind,
+                price: asking,
+            } => {
+                errors.check("property_id", property_id(property));
+                errors.check(
+                    "owner_account_id",
+                    account_selector(owner_account_id, &["alice", "bob"]),
+                );
+                errors.check("ipfs_cid", non_empty(ipfs_cid));
+                errors.check("property_type", property_type(*kind));
+                errors.check("price", price(asking));
+            }
+            SagaInput::Script { .. } => {
+                errors.add("kind", "script sagas are started with POST /admin/script")
+            }
+        }
+    }
+}
+
+impl Validate for RuleInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        // Empty or "*" selects the wildcard rule
+        if let Some(jurisdiction) = self
+            .jurisdiction
+            .as_deref()
+            .map(str::trim)
+            .filter(|j| !j.is_empty() && *j != WILDCARD)
+        {
+            errors.check("jurisdiction", country_code(jurisdiction));
+        }
+        errors.check("threshold", positive(self.threshold));
+    }
+}
+
+impl Validate for ListUpdate {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        for (i, country) in self.countries.iter().enumerate() {
+            errors.check(&format!("countries[{}]", i), country_code(country));
+        }
+        if let Some(signature) = &self.signature {
+            errors.check("signature", hex_string(signature, false));
+        }
+    }
+}
+
+impl Validate for ProviderInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("name", non_empty(&self.name));
+        errors.check("public_key", hex_string(&self.public_key, false));
+    }
+}
+
+impl Validate for PortalAccessInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("label", non_empty(&self.label));
+        errors.check(
+            "account_id",
+            account_selector(&self.account_id, &["alice", "bob"]),
+        );
+    }
+}
+
+impl Validate for ApiKeyInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("label", non_empty(&self.label));
+        for (i, account) in self.accounts.iter().enumerate() {
+            errors.check(
+                &format!("accounts[{}]", i),
+                account_selector(account, &["alice", "bob", "faucet"]),
+            );
+        }
+        for (i, escrow) in self.escrows.iter().enumerate() {
+            errors.check(&format!("escrows[{}]", i), hex_string(escrow, true));
+        }
+    }
+}
+
+impl Validate for InstallmentPlanInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("property_id", property_id(&self.property_id));
+        errors.check(
+            "buyer_account_id",
+            account_selector(&self.buyer_account_id, &["alice", "bob"]),
+        );
+        errors.check(
+            "seller_account_id",
+            account_selector(&self.seller_account_id, &["alice", "bob"]),
+        );
+        if self
+            .buyer_account_id
+            .eq_ignore_ascii_case(&self.seller_account_id)
+        {
+            errors.add("seller_account_id", "must differ from buyer_account_id");
+        }
+        errors.check("interval_secs", positive(self.interval_secs));
+
+        if self.installments == 0 || self.installments > MAX_INSTALLMENTS {
+            errors.add(
+                "installments",
+                format!("must be between 1 and {}", MAX_INSTALLMENTS),
+            );
+        } else if self.total_price < self.installments as u64 {
+            errors.add("total_price", "must be at least 1 per installment");
+        }
+    }
+}
+
+impl Validate for LienInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("property_id", property_id(&self.property_id));
+        errors.check(
+            "lender_account_id",
+            account_selector(&self.lender_account_id, &["alice", "bob"]),
+        );
+        errors.check("amount", positive(self.amount));
+        errors.check("priority", positive(self.priority as u64));
+    }
+}
+
+impl Validate for BenchInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        let operations = self.mints as u64 + self.consumes as u64 + self.transfers as u64;
+        if operations == 0 {
+            errors.add("mints", "at least one operation is required");
+        } else if operations > MAX_BENCH_OPERATIONS as u64 {
+            errors.add(
+                "mints",
+                format!("at most {} operations per run", MAX_BENCH_OPERATIONS),
+            );
+        }
+        if self.transfers > self.mints {
+            errors.add("transfers", "must not exceed mints");
+        }
+        if let Some(concurrency) = self.concurrency {
+            if concurrency == 0 || concurrency > MAX_BENCH_CONCURRENCY {
+                errors.add(
+                    "concurrency",
+                    format!("must be between 1 and {}", MAX_BENCH_CONCURRENCY),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(feature = "fault-injection")]
+impl Validate for FaultInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if let Some(count) = self.count {
+            errors.check("count", positive(count as u64));
+        }
+        match (self.kind, self.delay_ms) {
+            (FaultKind::NoteDelay, Some(delay)) => errors.check("delay_ms", positive(delay)),
+            (FaultKind::NoteDelay, None) => errors.add("delay_ms", "required for note_delay"),
+            (_, Some(_)) => errors.add("delay_ms", "only applies to note_delay"),
+            (_, None) => {}
+        }
+    }
+}
+
+impl Validate for AllowanceInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "owner_account_id",
+            account_selector(&self.owner_account_id, &["alice", "bob"]),
+        );
+        errors.check(
+            "spender",
+            account_selector(&self.spender, &[SERVICE_SPENDER, "alice", "bob"]),
+        );
+        errors.check("limit", positive(self.limit));
+        if let Some(faucet_id) = &self.faucet_id {
+            errors.check("faucet_id", account_selector(faucet_id, &["faucet"]));
+        }
+        if let Some(secs) = self.expires_in_secs {
+            errors.check("expires_in_secs", positive(secs));
+        }
+        if let Some(label) = &self.label {
+            errors.check("label", non_empty(label));
+        }
+    }
+}
+
+impl Validate for AllowanceTransferInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "to_account_id",
+            account_selector(&self.to_account_id, &["alice", "bob"]),
+        );
+        errors.check("amount", positive(self.amount));
+    }
+}
+
+impl Validate for SignOffInput {
+    // The action is checked when the payload is decoded
+    fn validate(&self, _errors: &mut ValidationErrors) {}
+}
+
+impl Validate for DischargeInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("payoff_tx_id", hex_string(&self.payoff_tx_id, true));
+    }
+}
+
+impl Validate for LeaseInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("property_id", property_id(&self.property_id));
+        errors.check(
+            "owner_account_id",
+            account_selector(&self.owner_account_id, &["alice", "bob"]),
+        );
+        errors.check(
+            "tenant_account_id",
+            account_selector(&self.tenant_account_id, &["alice", "bob"]),
+        );
+        if self
+            .owner_account_id
+            .eq_ignore_ascii_case(&self.tenant_account_id)
+        {
+            errors.add("tenant_account_id", "must differ from owner_account_id");
+        }
+        errors.check("rent_amount", positive(self.rent_amount));
+        errors.check("rent_interval_secs", positive(self.rent_interval_secs));
+        errors.check("deposit_amount", positive(self.deposit_amount));
+        if self.periods == 0 || self.periods > MAX_RENT_PERIODS {
+            errors.add(
+                "periods",
+                format!("must be between 1 and {}", MAX_RENT_PERIODS),
+            );
+        }
+    }
+}
+
+impl Validate for LeaseEndInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if let Some(reason) = &self.reason {
+            errors.check("reason", non_empty(reason));
+        }
+    }
+}
+
+impl Validate for AuctionInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("property_id", property_id(&self.property_id));
+        errors.check(
+            "seller_account_id",
+            account_selector(&self.seller_account_id, &["alice", "bob"]),
+        );
+        errors.check("reserve_price", positive(self.reserve_price));
+        errors.check("min_increment", positive(self.min_increment));
+        if self.duration_secs == 0 || self.duration_secs > MAX_AUCTION_DURATION_SECS {
+            errors.add(
+                "duration_secs",
+                format!("must be between 1 and {}", MAX_AUCTION_DURATION_SECS),
+            );
+        }
+    }
+}
+
+impl Validate for BidInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "bidder_account_id",
+            account_selector(&self.bidder_account_id, &["alice", "bob"]),
+        );
+        errors.check("amount", positive(self.amount));
+    }
+}
+
+fn offer_expiry(value: Option<u64>) -> Result<(), String> {
+    match value {
+        Some(secs) if secs == 0 || secs > MAX_OFFER_EXPIRY_SECS => Err(format!(
+            "must be between 1 and {}",
+            MAX_OFFER_EXPIRY_SECS
+        )),
+        _ => Ok(()),
+    }
+}
+
+impl Validate for OfferInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "buyer_account_id",
+            account_selector(&self.buyer_account_id, &["alice", "bob"]),
+        );
+        errors.check("amount", positive(self.amount));
+        errors.check("expires_in_secs", offer_expiry(self.expires_in_secs));
+        if let Some(message) = &self.message {
+            errors.check("message", non_empty(message));
+        }
+    }
+}
+
+impl Validate for CounterInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("amount", positive(self.amount));
+        errors.check("expires_in_secs", offer_expiry(self.expires_in_secs));
+        if let Some(message) = &self.message {
+            errors.check("message", non_empty(message));
+        }
+    }
+}
+
+impl Validate for OfferResponseInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if let Some(reason) = &self.reason {
+            errors.check("reason", non_empty(reason));
+        }
+    }
+}
+
+impl Validate for MarketInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("property_id", property_id(&self.property_id));
+        errors.check("share_faucet_id", hex_string(&self.share_faucet_id, true));
+    }
+}
+
+impl Validate for OrderInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "account_id",
+            account_selector(&self.account_id, &["alice", "bob"]),
+        );
+        errors.check("quantity", positive(self.quantity));
+        errors.check("price", positive(self.price));
+    }
+}
+
+impl Validate for LotSelectionInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("property_id", property_id(&self.property_id));
+        if self.lot_ids.is_empty() {
+            errors.add("lot_ids", "must name at least one lot");
+        }
+    }
+}
+
+impl Validate for InsurerInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("name", non_empty(&self.name));
+        errors.check("public_key", hex_string(&self.public_key, false));
+    }
+}
+
+impl Validate for BinderInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("document_hash", hex_string(&self.document_hash, false));
+        errors.check("signature", hex_string(&self.signature, false));
+        if self.coverage_end <= self.coverage_start {
+            errors.add("coverage_end", "must be after coverage_start");
+        }
+    }
+}
+
+impl Validate for ChecklistInput {
+    // The document kinds are checked when the payload is decoded, and a kind
+    // listed twice is only required once
+    fn validate(&self, _errors: &mut ValidationErrors) {}
+}
+
+impl Validate for DocumentInput {
+    // A known kind and zone are all it takes; serde rejects the rest
+    fn validate(&self, _errors: &mut ValidationErrors) {}
+}
+
+impl Validate for SignerInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("role", non_empty(&self.role));
+        errors.check("name", non_empty(&self.name));
+        errors.check("email", email(&self.email));
+    }
+}
+
+impl Validate for SignatureInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if self.signers.is_empty() || self.signers.len() > MAX_SIGNERS {
+            errors.add(
+                "signers",
+                format!("must list between 1 and {} signers", MAX_SIGNERS),
+            );
+        }
+        for (i, signer) in self.signers.iter().enumerate() {
+            errors.nested(&format!("signers[{}]", i), signer);
+            let email = signer.email.trim();
+            if self.signers[..i]
+                .iter()
+                .any(|s| s.email.trim().eq_ignore_ascii_case(email))
+            {
+                errors.add(format!("signers[{}].email", i), "is listed twice");
+            }
+        }
+    }
+}
+
+impl Validate for ProfessionalInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "account_id",
+            account_selector(&self.account_id, &["alice", "bob"]),
+        );
+        errors.check("name", non_empty(&self.name));
+        errors.check("license_number", non_empty(&self.license_number));
+        errors.check(
+            "license_jurisdiction",
+            non_empty(&self.license_jurisdiction),
+        );
+        errors.check("public_key", hex_string(&self.public_key, false));
+    }
+}
+
+impl Validate for RevokeProfessionalInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("reason", non_empty(&self.reason));
+    }
+}
+
+impl Validate for ReportInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("document_hash", hex_string(&self.document_hash, false));
+        errors.check("signature", hex_string(&self.signature, false));
+        if let Some(value) = self.appraised_value {
+            errors.check("appraised_value", positive(value));
+        }
+    }
+}
+
+impl Validate for NotarizationInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("document_hash", hex_string(&self.document_hash, false));
+        if let Some(label) = &self.label {
+            errors.check("label", non_empty(label));
+        }
+        if let Some(id) = &self.property_id {
+            errors.check("property_id", property_id(id));
+        }
+    }
+}
+
+impl Validate for ConfidentialListingInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if let Some(threshold) = self.min_accreditation_threshold {
+            errors.check("min_accreditation_threshold", positive(threshold));
+        }
+    }
+}
+
+impl Validate for DisclosureRequestInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "buyer_account_id",
+            account_selector(&self.buyer_account_id, &["alice", "bob"]),
+        );
+        if self.proof.trim().is_empty() {
+            errors.add("proof", "must not be empty");
+        }
+        errors.check("program_hash", non_empty(&self.program_hash));
+        if self.public_inputs.len() != 2 {
+            errors.add("public_inputs", "must be [threshold, rule_id]");
+        }
+    }
+}
+
+impl Validate for ChallengeInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("account_id", hex_string(&self.account_id, true));
+    }
+}
+
+impl Validate for SessionInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        // Challenges are numbered from 1
+        errors.check("challenge_id", positive(self.challenge_id));
+    }
+}
+
+impl Validate for UnsignedPaymentInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "to_account_id",
+            account_selector(&self.to_account_id, &["alice", "bob"]),
+        );
+        if let Some(faucet_id) = &self.faucet_id {
+            errors.check("faucet_id", account_selector(faucet_id, &["faucet"]));
+        }
+        errors.check("amount", positive(self.amount));
+        if let Some(note_type) = &self.note_type {
+            if !matches!(note_type.as_str(), "public" | "private") {
+                errors.add("note_type", "must be public or private");
+            }
+        }
+    }
+}
+
+impl Validate for OtpRequestInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("email", email(&self.email));
+    }
+}
+
+impl Validate for OtpVerifyInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        let code = self.code.trim();
+        if code.len() != 6 || !code.chars().all(|c| c.is_ascii_digit()) {
+            errors.add("code", "must be the six-digit code sent");
+        }
+    }
+}
+
+impl Validate for CustodialPaymentInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "to_account_id",
+            account_selector(&self.to_account_id, &["alice", "bob"]),
+        );
+        errors.check("amount", positive(self.amount));
+    }
+}
+
+impl Validate for SweepInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if let Some(account_ids) = &self.account_ids {
+            if account_ids.is_empty() {
+                errors.add("account_ids", "must not be empty when given");
+            }
+            for (i, account_id) in account_ids.iter().enumerate() {
+                errors.check(&format!("account_ids[{}]", i), hex_string(account_id, true));
+            }
+        }
+    }
+}
+
+impl Validate for OrganizationInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("name", non_empty(&self.name));
+        for (i, account) in self.accounts.iter().enumerate() {
+            errors.check(
+                &format!("accounts[{}]", i),
+                account_selector(account, &["alice", "bob"]),
+            );
+        }
+    }
+}
+
+impl Validate for MemberInput {
+    // Any key ID may be named, the bootstrap key's 0 included; the role is
+    // checked when the payload is decoded
+    fn validate(&self, _errors: &mut ValidationErrors) {}
+}
+
+impl Validate for MemberRoleInput {
+    fn validate(&self, _errors: &mut ValidationErrors) {}
+}
+
+impl Validate for OrgAccountInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "account",
+            account_selector(&self.account, &["alice", "bob"]),
+        );
+    }
+}
+
+impl Validate for HookInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("name", non_empty(&self.name));
+        match &self.target {
+            HookTarget::Webhook { url } => {
+                if !(url.starts_with("https://") || url.starts_with("http://")) {
+                    errors.add("target.url", "must be an http(s) URL");
+                }
+            }
+            // Whether the module exists and compiles is checked on creation
+            HookTarget::Wasm { module } => errors.check("target.module", non_empty(module)),
+        }
+    }
+}
+
+impl Validate for PruneInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        // A zero horizon would keep only the latest block and those with notes
+        if let Some(retention_blocks) = self.retention_blocks {
+            errors.check("retention_blocks", positive(retention_blocks.into()));
+        }
+    }
+}
+
+impl Validate for UpgradeInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if self.components.is_empty() {
+            errors.add("components", "must name at least one component");
+        }
+        for (i, component) in self.components.iter().enumerate() {
+            if self.components[..i].contains(component) {
+                errors.add(format!("components[{}]", i), "is listed twice");
+            }
+        }
+    }
+}
+
+impl Validate for ErasureInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("reason", non_empty(&self.reason));
+    }
+}
+
+impl Validate for WithdrawalInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "recipient_account_id",
+            account_selector(&self.recipient_account_id, &["alice", "bob"]),
+        );
+        errors.check("amount", positive(self.amount));
+        if let Some(faucet_id) = &self.faucet_id {
+            errors.check("faucet_id", hex_string(faucet_id, true));
+        }
+    }
+}
+
+impl Validate for CommissionInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if self.commission_bps > 10_000 {
+            errors.add("commission_bps", "must be at most 10000");
+        }
+        match &self.agent_account_id {
+            Some(agent) => errors.check("agent_account_id", hex_string(agent, true)),
+            None if self.commission_bps > 0 => {
+                errors.add("agent_account_id", "is required with a commission")
+            }
+            None => {}
+        }
+    }
+}
+
+impl Validate for WithholdingRuleInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        // Empty or "*" selects the wildcard rule
+        if let Some(jurisdiction) = self
+            .jurisdiction
+            .as_deref()
+            .map(str::trim)
+            .filter(|j| !j.is_empty() && *j != WILDCARD)
+        {
+            errors.check("jurisdiction", jurisdiction_code(jurisdiction));
+        }
+        if self.rate_bps == 0 || self.rate_bps > 10_000 {
+            errors.add("rate_bps", "must be between 1 and 10000");
+        }
+    }
+}
+
+impl Validate for JurisdictionInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if let Some(jurisdiction) = self
+            .jurisdiction
+            .as_deref()
+            .filter(|j| !j.trim().is_empty())
+        {
+            errors.check("jurisdiction", jurisdiction_code(jurisdiction.trim()));
+        }
+    }
+}
+
+impl Validate for PropertyReissueInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if let Some(owner) = &self.owner_account_id {
+            errors.check(
+                "owner_account_id",
+                account_selector(owner, &["alice", "bob"]),
+            );
+        }
+        errors.check("reason", non_empty(self.reason.trim()));
+    }
+}
+
+impl Validate for LostAccessInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "new_owner_account_id",
+            account_selector(&self.new_owner_account_id, &["alice", "bob"]),
+        );
+        errors.check("reason", non_empty(self.reason.trim()));
+    }
+}
+
+impl Validate for PropertyRetireInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check("reason", non_empty(self.reason.trim()));
+    }
+}
+
+impl Validate for SubscriptionInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if self.event_types.is_empty() {
+            errors.add("event_types", "at least one event type is required");
+        }
+        for (i, account) in self.account_ids.iter().enumerate() {
+            errors.check(
+                &format!("account_ids[{}]", i),
+                account_selector(account, &["alice", "bob", "faucet"]),
+            );
+        }
+        for (i, id) in self.property_ids.iter().enumerate() {
+            errors.check(&format!("property_ids[{}]", i), property_id(id));
+        }
+        if let Some(faucet_id) = &self.faucet_id {
+            errors.check(
+                "faucet_id",
+                account_selector(faucet_id, &[SERVICE_TOKEN_SYMBOL]),
+            );
+        }
+        if let Some(min_amount) = self.min_amount {
+            errors.check("min_amount", positive(min_amount));
+        }
+        if let Delivery::Webhook { url } = &self.delivery {
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                errors.add("delivery.url", "must be an http(s) URL");
+            }
+        }
+    }
+}
+
+impl Validate for ListingDetailsInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if let Some(address) = &self.address {
+            errors.check("address", non_empty(address));
+            if address.len() > MAX_ADDRESS_LEN {
+                errors.add("address", format!("at most {} characters", MAX_ADDRESS_LEN));
+            }
+        }
+        if let Some(description) = &self.description {
+            if description.len() > MAX_DESCRIPTION_LEN {
+                errors.add(
+                    "description",
+                    format!("at most {} characters", MAX_DESCRIPTION_LEN),
+                );
+            }
+        }
+        if let Some(size) = self.size_sqm {
+            if !(size.is_finite() && size > 0.0) {
+                errors.add("size_sqm", "must be greater than zero");
+            }
+        }
+    }
+}
+
+impl Validate for SettlementTokenInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "symbol",
+            parse_symbol(&self.symbol)
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+        );
+        errors.check("faucet_id", hex_string(&self.faucet_id, true));
+        if self.decimals > MAX_DECIMALS {
+            errors.add("decimals", format!("at most {}", MAX_DECIMALS));
+        }
+    }
+}
+
+impl Validate for ListingTokenInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if let Some(symbol) = &self.symbol {
+            errors.check("symbol", non_empty(symbol));
+        }
+    }
+}
+
+impl Validate for SwapQuoteInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.check(
+            "account_id",
+            account_selector(&self.account_id, &["alice", "bob"]),
+        );
+        errors.check("from", non_empty(&self.from));
+        if let Some(escrow) = &self.escrow_account_id {
+            errors.check("escrow_account_id", hex_string(escrow, true));
+            if self.amount_in.is_some() || self.amount_out.is_some() {
+                errors.add(
+                    "escrow_account_id",
+                    "quotes the escrow's amount; give no amount_in or amount_out",
+                );
+            }
+            return;
+        }
+        match &self.to {
+            Some(to) => errors.check("to", non_empty(to)),
+            None => errors.add("to", "is required without escrow_account_id"),
+        }
+        match (self.amount_in, self.amount_out) {
+            (Some(amount), None) => errors.check("amount_in", positive(amount)),
+            (None, Some(amount)) => errors.check("amount_out", positive(amount)),
+            _ => errors.add("amount_in", "give exactly one of amount_in and amount_out"),
+        }
+    }
+}
+
+impl Validate for SearchQuery {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if let Some(value) = self.property_type {
+            errors.check("property_type", property_type(value));
+        }
+        if let (Some(min), Some(max)) = (self.min_price, self.max_price) {
+            if min > max {
+                errors.add("max_price", "must not be below min_price");
+            }
+        }
+        if let Some(currency) = &self.currency {
+            if find_currency(currency).is_none() {
+                errors.add("currency", format!("unknown currency {}", currency));
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_size, self.max_size) {
+            if min > max {
+                errors.add("max_size", "must not be below min_size");
+            }
+        }
+        if let Some(bbox) = &self.bbox {
+            if let Err(e) = bbox.parse::<BoundingBox>() {
+                errors.add("bbox", e.to_string());
+            }
+        }
+        if let Some(limit) = self.limit {
+            if limit == 0 || limit > MAX_SEARCH_LIMIT {
+                errors.add(
+                    "limit",
+                    format!("must be between 1 and {}", MAX_SEARCH_LIMIT),
+                );
+            }
+        }
+    }
+}
+
+/// Latitude and longitude in range, reported under the given field names.
+fn coordinates(errors: &mut ValidationErrors, fields: [&str; 2], latitude: f64, longitude: f64) {
+    if !(-90.0..=90.0).contains(&latitude) {
+        errors.add(fields[0], "must be between -90 and 90");
+    }
+    if !(-180.0..=180.0).contains(&longitude) {
+        errors.add(fields[1], "must be between -180 and 180");
+    }
+}
+
+fn map_limit(errors: &mut ValidationErrors, limit: Option<usize>) {
+    if let Some(limit) = limit {
+        if limit == 0 || limit > MAX_MAP_LIMIT {
+            errors.add("limit", format!("must be between 1 and {}", MAX_MAP_LIMIT));
+        }
+    }
+}
+
+impl Validate for LocationInput {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        coordinates(
+            errors,
+            ["latitude", "longitude"],
+            self.latitude,
+            self.longitude,
+        );
+
+        let Some(parcel) = &self.parcel else {
+            return;
+        };
+        let Some(rings) = parcel.rings() else {
+            errors.add("parcel", "must be a Polygon or MultiPolygon");
+            return;
+        };
+        if rings.is_empty() {
+            errors.add("parcel", "must have at least one ring");
+        }
+        if rings.iter().map(|ring| ring.len()).sum::<usize>() > MAX_PARCEL_POSITIONS {
+            errors.add(
+                "parcel",
+                format!("at most {} positions", MAX_PARCEL_POSITIONS),
+            );
+        }
+        for (i, ring) in rings.iter().enumerate() {
+            if ring.len() < 4 || ring.first() != ring.last() {
+                errors.add(
+                    format!("parcel.rings[{}]", i),
+                    "must be closed, with at least 4 positions",
+                );
+            }
+            if !ring.iter().all(|position| valid_position(*position)) {
+                errors.add(
+                    format!("parcel.rings[{}]", i),
+                    "positions must be [longitude, latitude] in range",
+                );
+            }
+        }
+        if let Geometry::MultiPolygon { coordinates } = parcel {
+            if coordinates.iter().any(|polygon| polygon.is_empty()) {
+                errors.add("parcel", "polygons must have at least one ring");
+            }
+        }
+    }
+}
+
+impl Validate for NearbyQuery {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        coordinates(errors, ["lat", "lon"], self.lat, self.lon);
+        if !(self.radius > 0.0 && self.radius <= MAX_NEARBY_RADIUS_M) {
+            errors.add(
+                "radius",
+                format!("must be between 0 and {} meters", MAX_NEARBY_RADIUS_M),
+            );
+        }
+        if let Some(value) = self.property_type {
+            errors.check("property_type", property_type(value));
+        }
+        map_limit(errors, self.limit);
+    }
+}
+
+impl Validate for WithinQuery {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if let Err(e) = self.bbox.parse::<BoundingBox>() {
+            errors.add("bbox", e.to_string());
+        }
+        if let Some(value) = self.property_type {
+            errors.check("property_type", property_type(value));
+        }
+        map_limit(errors, self.limit);
+    }
+}
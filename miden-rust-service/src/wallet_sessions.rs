@@ -0,0 +1,486 @@
+// src/wallet_sessions.rs
+//
+// Wallet sessions for self-custodial users
+//
+// An end user whose Miden account lives in their own wallet connects it to the
+// service by proving they control it with a challenge note:
+//
+//   POST /wallet/challenges {account_id}
+//     -> the service's challenge account (WALLET_CHALLENGE_ACCOUNT) and a
+//        random aux value
+//   the wallet sends a public P2ID note (assets optional) from the account to
+//   the challenge account with that aux value
+//   POST /wallet/sessions {challenge_id}
+//     -> the service syncs, finds the note (only the account itself can send a
+//        note with it as sender) and issues a session token
+//
+// The token (X-Wallet-Session header) lets later calls act for the account
+// without the service ever holding its keys:
+// - watching: the account's note tag is added to the client, so notes sent to
+//   it are picked up by syncs and listed by GET /wallet/notes
+// - unsigned transaction building: POST /wallet/transactions/p2id returns a
+//   serialized transaction request for the wallet to execute, prove and submit
+//
+// Only token hashes are stored. Challenges expire after WALLET_CHALLENGE_TTL_SECS
+// and are single-use; sessions expire after WALLET_SESSION_TTL_SECS or when
+// the user logs out.
+
+use anyhow::Result;
+use miden_client::{
+    account::AccountId,
+    asset::FungibleAsset,
+    note::{create_p2id_note, NoteTag, NoteType},
+    store::NoteFilter,
+    transaction::{OutputNote, TransactionRequestBuilder},
+    Felt, Serializable,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{account_id_to_hex, escrow::EscrowAuthError, MidenClientWrapper};
+
+const TOKEN_PREFIX: &str = "obw_";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChallengeInput {
+    /// Hex AccountId of the user's wallet account
+    pub account_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SessionInput {
+    pub challenge_id: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UnsignedPaymentInput {
+    pub to_account_id: String,
+    /// Faucet of the asset to send (the service token when absent)
+    #[serde(default)]
+    pub faucet_id: Option<String>,
+    pub amount: u64,
+    /// "public" (default) or "private"
+    #[serde(default)]
+    pub note_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletChallenge {
+    pub challenge_id: u64,
+    pub account_id: String,
+    /// Account the challenge note must be sent to
+    pub recipient_account_id: String,
+    /// Value the challenge note's aux field must carry
+    pub aux: u64,
+    pub issued_at: i64,
+    pub expires_at: i64,
+    pub used: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletSession {
+    pub session_id: u64,
+    pub account_id: String,
+    /// Challenge note that proved control of the account
+    pub challenge_note_id: String,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub ended_at: Option<i64>,
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    token_hash: String,
+}
+
+impl WalletSession {
+    pub fn is_active(&self, now: i64) -> bool {
+        self.ended_at.is_none() && now < self.expires_at
+    }
+
+    /// Copy without the token hash, for API responses.
+    pub fn public(&self) -> WalletSession {
+        WalletSession {
+            token_hash: String::new(),
+            ..self.clone()
+        }
+    }
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WalletSessionStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    challenges: BTreeMap<u64, WalletChallenge>,
+    #[serde(default)]
+    sessions: BTreeMap<u64, WalletSession>,
+    #[serde(default)]
+    next_challenge_id: u64,
+    #[serde(default)]
+    next_session_id: u64,
+}
+
+impl WalletSessionStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<WalletSessionStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            WalletSessionStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn issue_challenge(
+        &mut self,
+        account_hex: &str,
+        recipient_hex: &str,
+        ttl_secs: u64,
+    ) -> Result<WalletChallenge> {
+        let now = chrono::Utc::now().timestamp();
+        // Expired challenges are of no further use
+        self.challenges.retain(|_, c| c.expires_at > now && !c.used);
+
+        self.next_challenge_id += 1;
+        let challenge = WalletChallenge {
+            challenge_id: self.next_challenge_id,
+            account_id: account_hex.to_lowercase(),
+            recipient_account_id: recipient_hex.to_lowercase(),
+            // Below the field modulus, so it fits a Felt unchanged
+            aux: rand::random::<u64>() >> 2,
+            issued_at: now,
+            expires_at: now + ttl_secs as i64,
+            used: false,
+        };
+
+        self.challenges
+            .insert(challenge.challenge_id, challenge.clone());
+        self.save()?;
+        Ok(challenge)
+    }
+
+    /// An unused, unexpired challenge.
+    pub fn open_challenge(&self, challenge_id: u64) -> Result<&WalletChallenge> {
+        let now = chrono::Utc::now().timestamp();
+        self.challenges
+            .get(&challenge_id)
+            .filter(|c| !c.used && c.expires_at > now)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Challenge {} is unknown, expired or already used",
+                    challenge_id
+                )
+            })
+    }
+
+    /// Marks the challenge used and opens a session; returns it with the
+    /// plaintext token.
+    pub fn open_session(
+        &mut self,
+        challenge_id: u64,
+        challenge_note_id: &str,
+        ttl_secs: u64,
+    ) -> Result<(WalletSession, String)> {
+        let challenge = self
+            .challenges
+            .get_mut(&challenge_id)
+            .ok_or_else(|| anyhow::anyhow!("Challenge {} not found", challenge_id))?;
+        challenge.used = true;
+        let account_id = challenge.account_id.clone();
+
+        self.next_session_id += 1;
+        let session_id = self.next_session_id;
+        let secret: [u8; 32] = rand::random();
+        let token = format!("{}{}_{}", TOKEN_PREFIX, session_id, hex::encode(secret));
+
+        let now = chrono::Utc::now().timestamp();
+        let session = WalletSession {
+            session_id,
+            account_id,
+            challenge_note_id: challenge_note_id.to_string(),
+            created_at: now,
+            expires_at: now + ttl_secs as i64,
+            ended_at: None,
+            token_hash: hash_token(&token),
+        };
+
+        self.sessions.insert(session_id, session.clone());
+        self.save()?;
+        Ok((session.public(), token))
+    }
+
+    /// Resolves a presented token to its active session.
+    pub fn authenticate(&self, token: &str) -> Option<&WalletSession> {
+        let session_id = token
+            .strip_prefix(TOKEN_PREFIX)?
+            .split('_')
+            .next()?
+            .parse::<u64>()
+            .ok()?;
+
+        let now = chrono::Utc::now().timestamp();
+        let presented = hash_token(token);
+        self.sessions
+            .get(&session_id)
+            .filter(|s| s.token_hash == presented && s.is_active(now))
+    }
+
+    pub fn end(&mut self, session_id: u64) -> Result<WalletSession> {
+        let session = self
+            .sessions
+            .get_mut(&session_id)
+            .ok_or_else(|| anyhow::anyhow!("Session {} not found", session_id))?;
+        session.ended_at = Some(chrono::Utc::now().timestamp());
+
+        let session = session.public();
+        self.save()?;
+        Ok(session)
+    }
+}
+
+// =============================================================================
+// CLIENT INTEGRATION
+// =============================================================================
+
+crate::operations! {
+    CreateWalletChallenge {
+        input: ChallengeInput,
+    } -> serde_json::Value;
+    |client, op| client.create_wallet_challenge(op.input);
+
+    CreateWalletSession {
+        input: SessionInput,
+    } -> serde_json::Value;
+    |client, op| client.create_wallet_session(op.input).await;
+
+    GetWalletSession {
+        session_token: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.get_wallet_session(op.session_token.as_deref());
+
+    EndWalletSession {
+        session_token: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.end_wallet_session(op.session_token.as_deref());
+
+    ListWalletNotes {
+        session_token: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.list_wallet_notes(op.session_token.as_deref()).await;
+
+    BuildUnsignedPayment {
+        input: UnsignedPaymentInput,
+        session_token: Option<String>,
+    } -> serde_json::Value;
+    |client, op| client.build_unsigned_payment(op.input, op.session_token.as_deref())
+}
+
+impl MidenClientWrapper {
+    /// The session behind a wallet session token.
+    fn wallet_session(&self, token: Option<&str>) -> Result<WalletSession> {
+        let token = token.ok_or_else(|| {
+            EscrowAuthError::Unauthenticated("X-Wallet-Session header is required".into())
+        })?;
+        self.wallet_sessions
+            .authenticate(token)
+            .map(WalletSession::public)
+            .ok_or_else(|| {
+                EscrowAuthError::Unauthenticated("Invalid or expired wallet session".into()).into()
+            })
+    }
+
+    /// Starts connecting an external account: returns the challenge note the
+    /// wallet has to send.
+    pub fn create_wallet_challenge(&mut self, input: ChallengeInput) -> Result<serde_json::Value> {
+        let account_id = AccountId::from_hex(input.account_id.trim())
+            .map_err(|e| anyhow::anyhow!("Invalid account_id: {}", e))?;
+        let recipient = self.named_account(&self.config.wallet_challenge_account)?;
+
+        let challenge = self.wallet_sessions.issue_challenge(
+            &account_id_to_hex(account_id),
+            &account_id_to_hex(recipient),
+            self.config.wallet_challenge_ttl.as_secs(),
+        )?;
+        tracing::info!(
+            "Wallet challenge {} issued for {}",
+            challenge.challenge_id,
+            challenge.account_id
+        );
+
+        Ok(serde_json::json!({
+            "challenge": challenge,
+            "instructions": format!(
+                "Send a public P2ID note from {} to {} with aux = {} before {}",
+                challenge.account_id,
+                challenge.recipient_account_id,
+                challenge.aux,
+                challenge.expires_at
+            ),
+        }))
+    }
+
+    /// Completes a challenge: looks for the challenge note on-chain and, when
+    /// found, opens a session and starts watching the account.
+    pub async fn create_wallet_session(
+        &mut self,
+        input: SessionInput,
+    ) -> Result<serde_json::Value> {
+        let challenge = self
+            .wallet_sessions
+            .open_challenge(input.challenge_id)?
+            .clone();
+        let account_id = AccountId::from_hex(&challenge.account_id)?;
+        let recipient = AccountId::from_hex(&challenge.recipient_account_id)?;
+
+        self.sync_state().await?;
+        let notes = self.client.get_input_notes(NoteFilter::All).await?;
+        let challenge_note = notes.iter().find(|note| {
+            note.metadata()
+                .map(|m| {
+                    m.sender() == account_id
+                        && m.aux().as_int() == challenge.aux
+                        && m.tag() == NoteTag::from_account_id(recipient)
+                })
+                .unwrap_or(false)
+        });
+        let Some(challenge_note) = challenge_note else {
+            return Err(EscrowAuthError::Unauthenticated(format!(
+                "Challenge note from {} with aux {} has not arrived yet",
+                challenge.account_id, challenge.aux
+            ))
+            .into());
+        };
+        let note_id = challenge_note.id().to_string();
+
+        // Watch the account: notes tagged for it are fetched on sync
+        self.client
+            .add_note_tag(NoteTag::from_account_id(account_id))
+            .await?;
+
+        let (session, token) = self.wallet_sessions.open_session(
+            challenge.challenge_id,
+            &note_id,
+            self.config.wallet_session_ttl.as_secs(),
+        )?;
+        tracing::info!(
+            session_id = session.session_id,
+            account = %session.account_id,
+            "Wallet session opened"
+        );
+
+        Ok(serde_json::json!({
+            "session": session,
+            "session_token": token,
+        }))
+    }
+
+    pub fn get_wallet_session(&self, token: Option<&str>) -> Result<serde_json::Value> {
+        Ok(serde_json::json!(self.wallet_session(token)?))
+    }
+
+    pub fn end_wallet_session(&mut self, token: Option<&str>) -> Result<serde_json::Value> {
+        let session = self.wallet_session(token)?;
+        let session = self.wallet_sessions.end(session.session_id)?;
+        tracing::info!("Wallet session {} ended", session.session_id);
+        Ok(serde_json::json!(session))
+    }
+
+    /// Notes the client has seen under the session account's tag.
+    pub async fn list_wallet_notes(&mut self, token: Option<&str>) -> Result<serde_json::Value> {
+        let session = self.wallet_session(token)?;
+        let account_id = AccountId::from_hex(&session.account_id)?;
+        let tag = NoteTag::from_account_id(account_id);
+
+        self.sync_state().await?;
+        let notes: Vec<_> = self
+            .client
+            .get_input_notes(NoteFilter::All)
+            .await?
+            .into_iter()
+            .filter(|note| note.metadata().map(|m| m.tag() == tag).unwrap_or(false))
+            .map(|note| {
+                let assets: Vec<_> = note
+                    .assets()
+                    .iter_fungible()
+                    .map(|asset| {
+                        serde_json::json!({
+                            "faucet_id": account_id_to_hex(asset.faucet_id()),
+                            "amount": asset.amount(),
+                        })
+                    })
+                    .collect();
+                serde_json::json!({
+                    "note_id": note.id().to_string(),
+                    "sender": note.metadata().map(|m| account_id_to_hex(m.sender())),
+                    "assets": assets,
+                    "committed": note.inclusion_proof().is_some(),
+                    "consumed": note.is_consumed(),
+                })
+            })
+            .collect();
+
+        Ok(serde_json::json!({
+            "account_id": session.account_id,
+            "notes": notes,
+        }))
+    }
+
+    /// Builds (but does not execute) a P2ID payment from the session account.
+    ///
+    /// The serialized transaction request is for the user's wallet to execute,
+    /// prove and submit with its own keys.
+    pub fn build_unsigned_payment(
+        &mut self,
+        input: UnsignedPaymentInput,
+        token: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let session = self.wallet_session(token)?;
+        let from = AccountId::from_hex(&session.account_id)?;
+        let to = AccountId::from_hex(&self.account_hex(input.to_account_id.trim())?)?;
+        let faucet = match input.faucet_id.as_deref() {
+            Some(faucet) => AccountId::from_hex(&self.account_hex(faucet.trim())?)?,
+            None => self.named_account("faucet")?,
+        };
+        let note_type = match input.note_type.as_deref().unwrap_or("public") {
+            "public" => NoteType::Public,
+            "private" => NoteType::Private,
+            other => return Err(anyhow::anyhow!("Unknown note type: {}", other)),
+        };
+
+        let asset = FungibleAsset::new(faucet, input.amount)?;
+        let note = create_p2id_note(
+            from,
+            to,
+            vec![asset.into()],
+            note_type,
+            Felt::new(0),
+            &mut self.rng,
+        )?;
+        let note_id = note.id().to_string();
+
+        let transaction_request = TransactionRequestBuilder::new()
+            .own_output_notes(vec![OutputNote::Full(note)])
+            .build()?;
+
+        Ok(serde_json::json!({
+            "account_id": session.account_id,
+            "note_id": note_id,
+            "transaction_request": hex::encode(transaction_request.to_bytes()),
+        }))
+    }
+}
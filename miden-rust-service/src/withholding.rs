@@ -0,0 +1,336 @@
+// src/withholding.rs
+//
+// Tax withholding at escrow settlement
+//
+// Where a property's jurisdiction requires part of the sale price to be withheld
+// (e.g. on sales by non-resident sellers), the service withholds it when the
+// escrow settles and sends it to a tax escrow account (TAX_ESCROW_ACCOUNT), from
+// which it is remitted to the authority outside the service.
+//
+// Rules are kept per jurisdiction: an ISO country code ("DE"), a country and
+// region ("US-CA"), or "*" for any property. A rule withholds `rate_bps` basis
+// points of the seller's share of a settlement when that share is at least
+// `min_amount`. A property is subject to the most specific rule: that of its
+// jurisdiction, then of its country, then "*". Escrows without a property, or
+// for a property whose jurisdiction is not set, only match "*".
+//
+//   GET/POST /admin/withholding-rules, DELETE /admin/withholding-rules/:rule_id
+//   PUT /admin/properties/:property_id/jurisdiction {"jurisdiction": "US-CA"}
+//
+// Withholding is one more payout of the settlement plan (fee_splits.rs): a
+// separate P2ID note to the tax escrow account, in the same transaction as the
+// seller's payout and out of the seller's share, like the platform fee and the
+// agent commission. The escrow record keeps what was withheld under which rule
+// (`withholding`), and the seller's tax report (tax.rs) lists it for the year
+// of settlement, in JSON and as rows of the CSV export.
+//
+// The rule in force when the escrow settles applies; changing or deleting a
+// rule does not touch settled escrows.
+
+use anyhow::Result;
+use miden_client::account::AccountId;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    account_id_to_hex, accreditation_rules::WILDCARD, escrow::EscrowAccount,
+    reconcile::parse_hex_account_id, records::PropertyRecord, MidenClientWrapper,
+};
+
+const BPS_DENOMINATOR: u128 = 10_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WithholdingRule {
+    pub rule_id: u64,
+    /// ISO country code, country-region code (upper case) or "*"
+    pub jurisdiction: String,
+    pub rate_bps: u64,
+    /// Smallest seller's share the rule applies to
+    pub min_amount: u64,
+    pub description: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WithholdingRuleInput {
+    #[serde(default)]
+    pub jurisdiction: Option<String>,
+    pub rate_bps: u64,
+    #[serde(default)]
+    pub min_amount: u64,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JurisdictionInput {
+    /// None clears the property's jurisdiction
+    #[serde(default)]
+    pub jurisdiction: Option<String>,
+}
+
+/// Tax withheld from one escrow settlement.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Withholding {
+    pub escrow_account_id: String,
+    pub seller_account_id: String,
+    pub property_id: Option<String>,
+    pub rule_id: u64,
+    /// Jurisdiction of the rule applied
+    pub jurisdiction: String,
+    pub rate_bps: u64,
+    /// Seller's share the rate was applied to
+    pub base_amount: u64,
+    pub amount: u64,
+    pub faucet_id: String,
+    pub tax_account_id: String,
+    /// Settlement transaction; None until submitted
+    pub tx_id: Option<String>,
+    pub withheld_at: i64,
+}
+
+fn normalize_jurisdiction(value: Option<&str>) -> String {
+    match value.map(str::trim) {
+        None | Some("") => WILDCARD.to_string(),
+        Some(v) => v.to_uppercase(),
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WithholdingRuleStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    rules: BTreeMap<u64, WithholdingRule>,
+    #[serde(default)]
+    next_rule_id: u64,
+}
+
+impl WithholdingRuleStore {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        let mut store = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<WithholdingRuleStore>(&raw)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            WithholdingRuleStore::default()
+        };
+        store.path = path;
+
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<WithholdingRule> {
+        self.rules.values().cloned().collect()
+    }
+
+    pub fn create(&mut self, input: WithholdingRuleInput) -> Result<WithholdingRule> {
+        if input.rate_bps == 0 || input.rate_bps as u128 > BPS_DENOMINATOR {
+            return Err(anyhow::anyhow!(
+                "rate_bps must be between 1 and 10000, got {}",
+                input.rate_bps
+            ));
+        }
+        let jurisdiction = normalize_jurisdiction(input.jurisdiction.as_deref());
+        if let Some(existing) = self.rules.values().find(|r| r.jurisdiction == jurisdiction) {
+            return Err(anyhow::anyhow!(
+                "Conflict: rule {} already covers jurisdiction '{}'",
+                existing.rule_id,
+                jurisdiction
+            ));
+        }
+
+        self.next_rule_id += 1;
+        let rule = WithholdingRule {
+            rule_id: self.next_rule_id,
+            jurisdiction,
+            rate_bps: input.rate_bps,
+            min_amount: input.min_amount,
+            description: input.description,
+            created_at: chrono::Utc::now().timestamp(),
+        };
+        self.rules.insert(rule.rule_id, rule.clone());
+        self.save()?;
+        Ok(rule)
+    }
+
+    pub fn delete(&mut self, rule_id: u64) -> Result<WithholdingRule> {
+        let rule = self
+            .rules
+            .remove(&rule_id)
+            .ok_or_else(|| anyhow::anyhow!("Withholding rule {} not found", rule_id))?;
+        self.save()?;
+        Ok(rule)
+    }
+
+    /// The most specific rule for a jurisdiction: exact, its country, "*".
+    pub fn resolve(&self, jurisdiction: Option<&str>) -> Option<&WithholdingRule> {
+        let jurisdiction = normalize_jurisdiction(jurisdiction);
+        let country = jurisdiction
+            .split_once('-')
+            .map(|(country, _)| country.to_string());
+        [Some(jurisdiction), country, Some(WILDCARD.to_string())]
+            .into_iter()
+            .flatten()
+            .find_map(|j| self.rules.values().find(|r| r.jurisdiction == j))
+    }
+}
+
+// ============================================================================
+// CLIENT INTEGRATION
+// ============================================================================
+
+crate::operations! {
+    /// Withholding rules, by ID.
+    ListWithholdingRules {} -> Vec<WithholdingRule>;
+    |client, _op| Ok(client.withholding_rules.list());
+
+    CreateWithholdingRule {
+        input: WithholdingRuleInput,
+    } -> WithholdingRule;
+    |client, op| client.create_withholding_rule(op.input);
+
+    DeleteWithholdingRule {
+        rule_id: u64,
+    } -> WithholdingRule;
+    |client, op| client.withholding_rules.delete(op.rule_id);
+
+    /// Sets or clears the jurisdiction of a property.
+    SetPropertyJurisdiction {
+        property_id: String,
+        input: JurisdictionInput,
+    } -> PropertyRecord;
+    |client, op| client.set_property_jurisdiction(&op.property_id, op.input)
+}
+
+impl MidenClientWrapper {
+    /// Adds a rule; refused without a tax escrow account to withhold into.
+    pub fn create_withholding_rule(
+        &mut self,
+        input: WithholdingRuleInput,
+    ) -> Result<WithholdingRule> {
+        self.tax_escrow_account_id()?;
+        let rule = self.withholding_rules.create(input)?;
+        tracing::info!(
+            "Withholding rule {}: {} bps in {}",
+            rule.rule_id,
+            rule.rate_bps,
+            rule.jurisdiction
+        );
+        Ok(rule)
+    }
+
+    pub fn set_property_jurisdiction(
+        &mut self,
+        property_id: &str,
+        input: JurisdictionInput,
+    ) -> Result<PropertyRecord> {
+        if !self.records.properties.contains_key(property_id) {
+            return Err(anyhow::anyhow!(
+                "Property {} has not been minted",
+                property_id
+            ));
+        }
+        let jurisdiction = input
+            .jurisdiction
+            .as_deref()
+            .map(str::trim)
+            .filter(|j| !j.is_empty())
+            .map(str::to_uppercase);
+        self.records
+            .set_property_jurisdiction(property_id, jurisdiction);
+        self.records
+            .properties
+            .get(property_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Property {} has not been minted", property_id))
+    }
+
+    fn tax_escrow_account_id(&self) -> Result<AccountId> {
+        let selector = self
+            .config
+            .tax_escrow_account
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("No tax escrow account (TAX_ESCROW_ACCOUNT)"))?;
+        parse_hex_account_id(&self.account_hex(selector)?)
+    }
+
+    /// Tax to withhold from a settlement paying `to_seller` to the seller of
+    /// `escrow`; None when no rule applies.
+    pub(crate) fn plan_withholding(
+        &self,
+        escrow: &EscrowAccount,
+        faucet_id: AccountId,
+        to_seller: u64,
+    ) -> Result<Option<Withholding>> {
+        let escrow_hex = account_id_to_hex(escrow.escrow_account_id);
+        let property_id = self
+            .records
+            .escrows
+            .get(&escrow_hex)
+            .and_then(|record| record.property_id.clone());
+        let jurisdiction = property_id
+            .as_deref()
+            .and_then(|id| self.records.properties.get(id))
+            .and_then(|property| property.jurisdiction.as_deref());
+
+        let Some(rule) = self.withholding_rules.resolve(jurisdiction) else {
+            return Ok(None);
+        };
+        if to_seller < rule.min_amount {
+            return Ok(None);
+        }
+        let amount = (to_seller as u128 * rule.rate_bps as u128 / BPS_DENOMINATOR) as u64;
+        if amount == 0 {
+            return Ok(None);
+        }
+
+        let tax_account = self
+            .tax_escrow_account_id()
+            .map_err(|e| anyhow::anyhow!("Withholding rule {} applies: {}", rule.rule_id, e))?;
+        Ok(Some(Withholding {
+            escrow_account_id: escrow_hex,
+            seller_account_id: account_id_to_hex(escrow.seller_account_id),
+            property_id,
+            rule_id: rule.rule_id,
+            jurisdiction: rule.jurisdiction.clone(),
+            rate_bps: rule.rate_bps,
+            base_amount: to_seller,
+            amount,
+            faucet_id: account_id_to_hex(faucet_id),
+            tax_account_id: account_id_to_hex(tax_account),
+            tx_id: None,
+            withheld_at: chrono::Utc::now().timestamp(),
+        }))
+    }
+
+    /// Records tax withheld in settlement transaction `tx_id` on the escrow
+    /// and in the seller's tax ledger.
+    pub(crate) fn record_withholding(&mut self, withholding: &Withholding, tx_id: &str) {
+        let withholding = Withholding {
+            tx_id: Some(tx_id.to_string()),
+            withheld_at: chrono::Utc::now().timestamp(),
+            ..withholding.clone()
+        };
+        tracing::info!(
+            "🧾 Withheld {} from escrow {} under rule {} ({})",
+            withholding.amount,
+            withholding.escrow_account_id,
+            withholding.rule_id,
+            withholding.jurisdiction
+        );
+        self.records
+            .set_escrow_withholding(&withholding.escrow_account_id, withholding.clone());
+        self.tax.record_withholding(withholding);
+        self.tax.persist();
+    }
+}
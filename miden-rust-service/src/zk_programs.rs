@@ -0,0 +1,156 @@
+// src/zk_programs.rs
+//
+// Versioned ZK program registry
+//
+// Each proof family (accreditation, jurisdiction, ownership, identity) is backed
+// by a MASM program under masm/. Programs are compiled once at startup and
+// identified by their MAST root, which is what proofs carry as `program_hash`.
+//
+// Verification accepts a proof only if its program hash belongs to a registered
+// program of the expected family. Older versions stay registered (marked
+// superseded) so proofs generated before an upgrade can still be verified.
+
+use anyhow::Result;
+use miden_assembly::Assembler;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// Built-in program sources: (family, version, source).
+const BUILTIN_PROGRAMS: &[(&str, u32, &str)] = &[
+    ("accreditation", 1, include_str!("../masm/accreditation_v1.masm")),
+    ("accreditation", 2, include_str!("../masm/accreditation_v2.masm")),
+    ("jurisdiction", 1, include_str!("../masm/jurisdiction_v1.masm")),
+    ("identity", 1, include_str!("../masm/identity_v1.masm")),
+    ("ownership", 1, include_str!("../masm/ownership_v1.masm")),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgramEntry {
+    /// "{family}_v{version}", e.g. "accreditation_v2"
+    pub program_id: String,
+    pub family: String,
+    pub version: u32,
+    /// MAST root of the compiled program (0x-prefixed hex)
+    pub mast_root: String,
+    /// SHA-256 of the MASM source
+    pub source_hash: String,
+    /// False once a newer version of the same family is registered
+    pub active: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct ProgramRegistry {
+    programs: BTreeMap<String, ProgramEntry>,
+}
+
+impl ProgramRegistry {
+    /// Compiles and registers the built-in programs.
+    pub fn builtin() -> Result<Self> {
+        let mut registry = Self::default();
+
+        for (family, version, source) in BUILTIN_PROGRAMS {
+            registry.register(family, *version, source)?;
+        }
+
+        tracing::info!("ZK program registry loaded ({} programs)", registry.programs.len());
+        Ok(registry)
+    }
+
+    /// Compiles a MASM program and registers it as the given family/version.
+    ///
+    /// The highest version of each family is the active one.
+    pub fn register(&mut self, family: &str, version: u32, source: &str) -> Result<&ProgramEntry> {
+        let program_id = format!("{}_v{}", family, version);
+
+        let program = Assembler::default()
+            .assemble_program(source)
+            .map_err(|e| anyhow::anyhow!("Failed to assemble {}: {}", program_id, e))?;
+
+        let mast_root = program.hash().to_hex();
+        let source_hash = {
+            let mut hasher = Sha256::new();
+            hasher.update(source.as_bytes());
+            format!("{:x}", hasher.finalize())
+        };
+
+        tracing::info!("Registered ZK program {} (MAST root {})", program_id, mast_root);
+
+        self.programs.insert(
+            program_id.clone(),
+            ProgramEntry {
+                program_id: program_id.clone(),
+                family: family.to_string(),
+                version,
+                mast_root,
+                source_hash,
+                active: false,
+            },
+        );
+
+        self.refresh_active(family);
+
+        self.programs
+            .get(&program_id)
+            .ok_or_else(|| anyhow::anyhow!("Program {} missing after registration", program_id))
+    }
+
+    fn refresh_active(&mut self, family: &str) {
+        let latest = self
+            .programs
+            .values()
+            .filter(|p| p.family == family)
+            .map(|p| p.version)
+            .max();
+
+        for program in self.programs.values_mut().filter(|p| p.family == family) {
+            program.active = Some(program.version) == latest;
+        }
+    }
+
+    pub fn get(&self, program_id: &str) -> Option<&ProgramEntry> {
+        self.programs.get(program_id)
+    }
+
+    /// Active (latest) program for a family.
+    pub fn active(&self, family: &str) -> Result<&ProgramEntry> {
+        self.programs
+            .values()
+            .find(|p| p.family == family && p.active)
+            .ok_or_else(|| anyhow::anyhow!("No registered program for family '{}'", family))
+    }
+
+    /// Resolves a submitted program hash to a registered program of `family`.
+    pub fn check_hash(&self, family: &str, program_hash: &str) -> Result<&ProgramEntry> {
+        let normalized = program_hash.trim().to_lowercase();
+
+        self.programs
+            .values()
+            .find(|p| p.family == family && p.mast_root.to_lowercase() == normalized)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Program hash {} is not a registered {} program",
+                    program_hash,
+                    family
+                )
+            })
+    }
+
+    pub fn list(&self) -> Vec<&ProgramEntry> {
+        self.programs.values().collect()
+    }
+
+    /// Registry as seen by the shared verifier (same shape as GET /zk/programs).
+    pub fn published(&self) -> Vec<obscura_proof_verifier::PublishedProgram> {
+        self.programs
+            .values()
+            .map(|p| obscura_proof_verifier::PublishedProgram {
+                program_id: p.program_id.clone(),
+                family: p.family.clone(),
+                version: p.version,
+                mast_root: p.mast_root.clone(),
+                active: p.active,
+            })
+            .collect()
+    }
+}